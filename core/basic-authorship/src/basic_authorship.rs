@@ -24,10 +24,10 @@ use log::{info, debug, trace};
 
 use client::{
 	self, error, Client as SubstrateClient, CallExecutor,
-	block_builder::api::BlockBuilder as BlockBuilderApi, runtime_api::Core,
+	block_builder::api::BlockBuilder as BlockBuilderApi, runtime_api::{Core, OverlayedChanges},
 };
 use codec::Decode;
-use consensus_common::{self, evaluation};
+use consensus_common::{self, evaluation, Proposal};
 use primitives::{H256, Blake2Hasher, ExecutionContext};
 use runtime_primitives::traits::{
 	Block as BlockT, Hash as HashT, Header as HeaderT, ProvideRuntimeApi,
@@ -56,13 +56,15 @@ pub trait AuthoringApi: Send + Sync + ProvideRuntimeApi where
 
 	/// Build a block on top of the given block, with inherent extrinsics and
 	/// inherent digests pre-pushed.
+	///
+	/// Returns the block along with the storage changes it makes.
 	fn build_block<F: FnMut(&mut dyn BlockBuilder<Self::Block>) -> ()>(
 		&self,
 		at: &BlockId<Self::Block>,
 		inherent_data: InherentData,
 		inherent_digests: DigestFor<Self::Block>,
 		build_ctx: F,
-	) -> Result<Self::Block, error::Error>;
+	) -> Result<(Self::Block, OverlayedChanges), error::Error>;
 }
 
 impl<'a, B, E, Block, RA> BlockBuilder<Block>
@@ -97,9 +99,9 @@ impl<B, E, Block, RA> AuthoringApi for SubstrateClient<B, E, Block, RA> where
 		inherent_data: InherentData,
 		inherent_digests: DigestFor<Self::Block>,
 		mut build_ctx: F,
-	) -> Result<Self::Block, error::Error> {
+	) -> Result<(Self::Block, OverlayedChanges), error::Error> {
 
-		let mut block_builder = self.new_block_at(at, inherent_digests)?;
+		let mut block_builder = self.new_block_at(at, inherent_digests, false)?;
 
 		let runtime_api = self.runtime_api();
 		// We don't check the API versions any further here since the dispatch compatibility
@@ -109,7 +111,7 @@ impl<B, E, Block, RA> AuthoringApi for SubstrateClient<B, E, Block, RA> where
 
 		build_ctx(&mut block_builder);
 
-		block_builder.bake().map_err(Into::into)
+		block_builder.bake_and_extract_changes().map_err(Into::into)
 	}
 }
 
@@ -171,7 +173,7 @@ impl<Block, C, A> consensus_common::Proposer<<C as AuthoringApi>::Block> for Pro
 	A: txpool::ChainApi<Block=Block>,
 	client::error::Error: From<<C as AuthoringApi>::Error>
 {
-	type Create = Result<<C as AuthoringApi>::Block, error::Error>;
+	type Create = Result<Proposal<<C as AuthoringApi>::Block>, error::Error>;
 	type Error = error::Error;
 
 	fn propose(
@@ -179,7 +181,7 @@ impl<Block, C, A> consensus_common::Proposer<<C as AuthoringApi>::Block> for Pro
 		inherent_data: InherentData,
 		inherent_digests: DigestFor<Block>,
 		max_duration: time::Duration,
-	) -> Result<<C as AuthoringApi>::Block, error::Error>
+	) -> Result<Proposal<<C as AuthoringApi>::Block>, error::Error>
 	{
 		// leave some time for evaluation and block finalization (33%)
 		let deadline = (self.now)() + max_duration - max_duration / 3;
@@ -199,7 +201,7 @@ impl<Block, C, A> Proposer<Block, C, A>	where
 		inherent_data: InherentData,
 		inherent_digests: DigestFor<Block>,
 		deadline: time::Instant,
-	) -> Result<<C as AuthoringApi>::Block, error::Error>
+	) -> Result<Proposal<<C as AuthoringApi>::Block>, error::Error>
 	{
 		use runtime_primitives::traits::BlakeTwo256;
 
@@ -208,7 +210,7 @@ impl<Block, C, A> Proposer<Block, C, A>	where
 		/// It allows us to increase block utilization.
 		const MAX_SKIPPED_TRANSACTIONS: usize = 8;
 
-		let block = self.client.build_block(
+		let (block, storage_changes) = self.client.build_block(
 			&self.parent_id,
 			inherent_data,
 			inherent_digests.clone(),
@@ -231,9 +233,9 @@ impl<Block, C, A> Proposer<Block, C, A>	where
 						Ok(()) => {
 							debug!("[{:?}] Pushed to the block.", pending.hash);
 						}
-						Err(error::Error::ApplyExtrinsicFailed(ApplyError::FullBlock)) => {
+						Err(error::Error::ApplyExtrinsicFailed(ApplyError::ExhaustsResources)) => {
 							if is_first {
-								debug!("[{:?}] Invalid transaction: FullBlock on empty block", pending.hash);
+								debug!("[{:?}] Invalid transaction: ExhaustsResources on empty block", pending.hash);
 								unqueue_invalid.push(pending.hash.clone());
 							} else if skipped < MAX_SKIPPED_TRANSACTIONS {
 								skipped += 1;
@@ -282,7 +284,7 @@ impl<Block, C, A> Proposer<Block, C, A>	where
 			self.parent_number,
 		).is_ok());
 
-		Ok(substrate_block)
+		Ok(Proposal { block: substrate_block, storage_changes: Some(storage_changes) })
 	}
 }
 
@@ -328,11 +330,12 @@ mod tests {
 			cell.replace(new)
 		});
 		let deadline = time::Duration::from_secs(3);
-		let block = proposer.propose(Default::default(), Default::default(), deadline).unwrap();
+		let proposal = proposer.propose(Default::default(), Default::default(), deadline).unwrap();
 
 		// then
 		// block should have some extrinsics although we have some more in the pool.
-		assert_eq!(block.extrinsics().len(), 1);
+		assert_eq!(proposal.block.extrinsics().len(), 1);
+		assert!(proposal.storage_changes.is_some());
 		assert_eq!(txpool.ready().count(), 2);
 	}
 }
@@ -15,18 +15,34 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Substrate chain configurations.
+//!
+//! A chain spec is a JSON document describing a chain: its name and id, the boot nodes, telemetry
+//! endpoints, protocol and fork ids, loosely-typed properties (e.g. the token decimals and symbol) and the
+//! genesis state. The genesis is either given as the human-readable genesis config of the runtime
+//! or, once converted with `to_json(true)`, as the raw storage it builds.
+//!
+//! Nodes can add their own fields to the chain spec, e.g. a list of fork blocks. Such fields are
+//! kept as extensions and can be read back in a typed form with `ChainSpec::extensions`.
+
+#![warn(missing_docs)]
 
 use std::collections::HashMap;
 use std::fs::File;
 use std::path::PathBuf;
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
 use primitives::storage::{StorageKey, StorageData};
 use runtime_primitives::{BuildStorage, StorageOverlay, ChildrenStorageOverlay};
 use serde_json as json;
-use crate::components::RuntimeGenesis;
 use network::Multiaddr;
 use tel::TelemetryEndpoints;
 
+/// A set of traits for the runtime genesis config.
+pub trait RuntimeGenesis: Serialize + DeserializeOwned + BuildStorage {}
+impl<T: Serialize + DeserializeOwned + BuildStorage> RuntimeGenesis for T {}
+
+/// Fields of the chain spec not known to this crate, declared by the node.
+pub type Extensions = json::map::Map<String, json::Value>;
+
 enum GenesisSource<G> {
 	File(PathBuf),
 	Embedded(&'static [u8]),
@@ -93,8 +109,21 @@ struct ChainSpecFile {
 	pub boot_nodes: Vec<String>,
 	pub telemetry_endpoints: Option<TelemetryEndpoints>,
 	pub protocol_id: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub fork_id: Option<String>,
 	pub consensus_engine: Option<String>,
 	pub properties: Option<Properties>,
+	#[serde(flatten)]
+	pub extensions: Extensions,
+}
+
+impl ChainSpecFile {
+	fn parse<R: std::io::Read>(reader: R) -> json::Result<Self> {
+		let mut spec: Self = json::from_reader(reader)?;
+		// the genesis is resolved separately and must not end up among the extensions.
+		spec.extensions.remove("genesis");
+		Ok(spec)
+	}
 }
 
 /// Arbitrary properties defined in chain spec as a JSON object
@@ -141,6 +170,14 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 		self.spec.protocol_id.as_ref().map(String::as_str)
 	}
 
+	/// Identifier of the fork of the chain, if any.
+	///
+	/// Nodes with different fork ids don't connect to each other, even if they share the same
+	/// genesis block.
+	pub fn fork_id(&self) -> Option<&str> {
+		self.spec.fork_id.as_ref().map(String::as_str)
+	}
+
 	/// Name of the consensus engine.
 	pub fn consensus_engine(&self) -> Option<&str> {
 		self.spec.consensus_engine.as_ref().map(String::as_str)
@@ -157,9 +194,32 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 		self.spec.boot_nodes.push(addr.to_string())
 	}
 
+	/// Decodes the node specific fields of the chain spec.
+	///
+	/// Fields of `E` that are missing in the chain spec must have a `#[serde(default)]`.
+	pub fn extensions<E: DeserializeOwned>(&self) -> Result<E, String> {
+		json::from_value(json::Value::Object(self.spec.extensions.clone()))
+			.map_err(|e| format!("Error parsing chain spec extensions: {}", e))
+	}
+
+	/// Sets the node specific fields of the chain spec.
+	///
+	/// `extensions` must serialize to a JSON object whose fields don't collide with the ones of
+	/// the chain spec.
+	pub fn with_extensions<E: Serialize>(mut self, extensions: &E) -> Result<Self, String> {
+		match json::to_value(extensions) {
+			Ok(json::Value::Object(extensions)) => {
+				self.spec.extensions = extensions;
+				Ok(self)
+			},
+			Ok(_) => Err("Chain spec extensions must be a JSON object".into()),
+			Err(e) => Err(format!("Error generating chain spec extensions: {}", e)),
+		}
+	}
+
 	/// Parse json content into a `ChainSpec`
 	pub fn from_embedded(json: &'static [u8]) -> Result<Self, String> {
-		let spec = json::from_slice(json).map_err(|e| format!("Error parsing spec file: {}", e))?;
+		let spec = ChainSpecFile::parse(json).map_err(|e| format!("Error parsing spec file: {}", e))?;
 		Ok(ChainSpec {
 			spec,
 			genesis: GenesisSource::Embedded(json),
@@ -169,7 +229,7 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 	/// Parse json file into a `ChainSpec`
 	pub fn from_json_file(path: PathBuf) -> Result<Self, String> {
 		let file = File::open(&path).map_err(|e| format!("Error opening spec file: {}", e))?;
-		let spec = json::from_reader(file).map_err(|e| format!("Error parsing spec file: {}", e))?;
+		let spec = ChainSpecFile::parse(file).map_err(|e| format!("Error parsing spec file: {}", e))?;
 		Ok(ChainSpec {
 			spec,
 			genesis: GenesisSource::File(path),
@@ -194,8 +254,10 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 			boot_nodes: boot_nodes,
 			telemetry_endpoints,
 			protocol_id: protocol_id.map(str::to_owned),
+			fork_id: None,
 			consensus_engine: consensus_engine.map(str::to_owned),
 			properties,
+			extensions: Default::default(),
 		};
 		ChainSpec {
 			spec,
@@ -210,7 +272,6 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 			#[serde(flatten)]
 			spec: ChainSpecFile,
 			genesis: Genesis<G>,
-
 		};
 		let genesis = match (raw, self.genesis.resolve()?) {
 			(true, Genesis::Runtime(g)) => {
@@ -229,3 +290,79 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 		json::to_string_pretty(&spec).map_err(|e| format!("Error generating spec json: {}", e))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Serialize, Deserialize)]
+	struct Genesis(HashMap<String, String>);
+
+	impl BuildStorage for Genesis {
+		fn assimilate_storage(
+			self,
+			storage: &mut StorageOverlay,
+			_: &mut ChildrenStorageOverlay,
+		) -> Result<(), String> {
+			storage.extend(self.0.into_iter().map(|(k, v)| (k.into_bytes(), v.into_bytes())));
+			Ok(())
+		}
+	}
+
+	fn genesis() -> Genesis {
+		Genesis(vec![("balance".to_owned(), "1000".to_owned())].into_iter().collect())
+	}
+
+	#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+	#[serde(rename_all = "camelCase")]
+	struct NodeExtensions {
+		#[serde(default)]
+		fork_blocks: Vec<(u64, String)>,
+	}
+
+	fn parse(json: String) -> ChainSpec<Genesis> {
+		ChainSpec::from_embedded(Box::leak(json.into_bytes().into_boxed_slice())).unwrap()
+	}
+
+	#[test]
+	fn extensions_are_preserved() {
+		let extensions = NodeExtensions { fork_blocks: vec![(10, "0x01".into())] };
+		let spec = ChainSpec::from_genesis("Test", "test", genesis, vec![], None, None, None, None)
+			.with_extensions(&extensions)
+			.unwrap();
+
+		let parsed = parse(spec.to_json(false).unwrap());
+		assert_eq!(parsed.id(), "test");
+		assert_eq!(parsed.extensions::<NodeExtensions>().unwrap(), extensions);
+		assert!(!parsed.spec.extensions.contains_key("genesis"));
+	}
+
+	#[test]
+	fn missing_extensions_use_defaults() {
+		let spec = ChainSpec::from_genesis("Test", "test", genesis, vec![], None, None, None, None);
+		let parsed = parse(spec.to_json(false).unwrap());
+		assert_eq!(parsed.extensions::<NodeExtensions>().unwrap(), NodeExtensions::default());
+	}
+
+	#[test]
+	fn raw_genesis_builds_the_same_storage() {
+		let spec = ChainSpec::from_genesis("Test", "test", genesis, vec![], None, None, None, None);
+		let expected = genesis().build_storage().unwrap().0;
+
+		let raw = parse(spec.to_json(true).unwrap());
+		assert_eq!((&raw).build_storage().unwrap().0, expected);
+	}
+
+	#[test]
+	fn fork_id_is_optional() {
+		let mut spec = ChainSpec::from_genesis("Test", "test", genesis, vec![], None, None, None, None);
+		let json = spec.clone().to_json(false).unwrap();
+		assert!(!json.contains("forkId"));
+		assert_eq!(parse(json).fork_id(), None);
+
+		spec.spec.fork_id = Some("classic".into());
+		let parsed = parse(spec.to_json(false).unwrap());
+		assert_eq!(parsed.fork_id(), Some("classic"));
+		assert!(!parsed.spec.extensions.contains_key("forkId"));
+	}
+}
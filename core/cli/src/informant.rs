@@ -59,9 +59,15 @@ where C: Components {
 		};
 		last_number = Some(best_number);
 		let finalized_number: u64 = info.chain.finalized_number.saturated_into::<u64>();
+		let usage = client.usage_info().map_or_else(String::new, |usage| format!(
+			", db: {} (state pruning: {}, blocks pruning: {})",
+			usage.database_size.map_or_else(|| "in memory".into(), |size| SizeFormat(size).to_string()),
+			usage.state_pruning,
+			usage.blocks_pruning.map_or_else(|| "archive".into(), |n| format!("{} blocks", n)),
+		));
 		info!(
 			target: "substrate",
-			"{}{} ({} peers), best: #{} ({}), finalized #{} ({}), ⬇ {} ⬆ {}",
+			"{}{} ({} peers), best: #{} ({}), finalized #{} ({}, {} behind), ⬇ {} ⬆ {}{}",
 			Colour::White.bold().paint(&status),
 			target,
 			Colour::White.bold().paint(format!("{}", net_status.num_connected_peers)),
@@ -69,8 +75,10 @@ where C: Components {
 			best_hash,
 			Colour::White.paint(format!("{}", finalized_number)),
 			info.chain.finalized_hash,
+			best_number.saturating_sub(finalized_number),
 			TransferRateFormat(net_status.average_download_per_sec),
 			TransferRateFormat(net_status.average_upload_per_sec),
+			usage,
 		);
 
 		Ok(())
@@ -154,3 +162,24 @@ impl fmt::Display for TransferRateFormat {
 		write!(f, "{:.1}MiB/s", self.0 as f64 / (1024.0 * 1024.0))
 	}
 }
+
+/// Contains a number of bytes. Implements `fmt::Display` and shows this number of bytes in a nice
+/// way.
+struct SizeFormat(u64);
+impl fmt::Display for SizeFormat {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		const UNITS: [&str; 4] = ["kiB", "MiB", "GiB", "TiB"];
+
+		if self.0 < 1024 {
+			return write!(f, "{} B", self.0)
+		}
+
+		let mut size = self.0 as f64 / 1024.0;
+		let mut unit = 0;
+		while size >= 1024.0 && unit < UNITS.len() - 1 {
+			size /= 1024.0;
+			unit += 1;
+		}
+		write!(f, "{:.1} {}", size, UNITS[unit])
+	}
+}
@@ -0,0 +1,197 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Implementation of the `key` subcommands.
+
+use bip39::{Language, Mnemonic, MnemonicType};
+use keystore::BareCryptoStore;
+use log::info;
+use primitives::{
+	ecdsa, ed25519, sr25519, hexdisplay::HexDisplay,
+	crypto::{key_types, set_default_ss58_version, KeyTypeId, Pair, Ss58Codec},
+};
+use service::{ChainSpec, RuntimeGenesis};
+
+use crate::error;
+use crate::params::{CryptoScheme, GenerateKeyCmd, InspectKeyCmd, InsertKeyCmd, KeySubcommand};
+use crate::{VersionInfo, base_path, keystore_password, keystore_path, load_spec};
+
+/// Runs the given `key` subcommand.
+pub(crate) fn run<G, S>(cmd: KeySubcommand, spec_factory: S, version: &VersionInfo) -> error::Result<()>
+where
+	G: RuntimeGenesis,
+	S: FnOnce(&str) -> Result<Option<ChainSpec<G>>, String>,
+{
+	match cmd {
+		KeySubcommand::Generate(cmd) => generate(cmd),
+		KeySubcommand::Inspect(cmd) => inspect(cmd),
+		KeySubcommand::Insert(cmd) => insert(cmd, spec_factory, version),
+	}
+}
+
+fn generate(cmd: GenerateKeyCmd) -> error::Result<()> {
+	let words = MnemonicType::for_word_count(cmd.words)
+		.map_err(|_| error::Error::Input(
+			"Invalid number of words given for phrase: must be 12/15/18/21/24".into()
+		))?;
+	if let Some(network) = cmd.network {
+		set_default_ss58_version(network);
+	}
+	let mnemonic = Mnemonic::new(words, Language::English);
+	println!("{}", describe(cmd.scheme, mnemonic.phrase(), cmd.password.as_ref().map(String::as_str))?);
+	Ok(())
+}
+
+fn inspect(cmd: InspectKeyCmd) -> error::Result<()> {
+	if let Some(network) = cmd.network {
+		set_default_ss58_version(network);
+	}
+	println!("{}", describe(cmd.scheme, &cmd.uri, cmd.password.as_ref().map(String::as_str))?);
+	Ok(())
+}
+
+fn insert<G, S>(cmd: InsertKeyCmd, spec_factory: S, version: &VersionInfo) -> error::Result<()>
+where
+	G: RuntimeGenesis,
+	S: FnOnce(&str) -> Result<Option<ChainSpec<G>>, String>,
+{
+	let key_type = parse_key_type(&cmd.key_type)?;
+	let path = match cmd.keystore_params.keystore_path {
+		Some(ref path) => path.clone(),
+		None => {
+			let spec = load_spec(&cmd.shared_params, spec_factory)?;
+			keystore_path(&base_path(&cmd.shared_params, version), spec.id())
+		},
+	};
+	if let Some(network) = cmd.network {
+		set_default_ss58_version(network);
+	}
+	let store = keystore::Store::open(path.clone(), keystore_password(&cmd.keystore_params)?)
+		.map_err(|e| error::Error::Input(format!("Failed to open keystore: {}", e)))?;
+
+	let public = match key_type {
+		key_types::ED25519 => insert_pair::<ed25519::Pair>(&store, &cmd.suri)?,
+		key_types::SR25519 => insert_pair::<sr25519::Pair>(&store, &cmd.suri)?,
+		key_types::ECDSA => insert_pair::<ecdsa::Pair>(&store, &cmd.suri)?,
+		_ => return Err(error::Error::Input(format!("Unsupported key type {}", key_type))),
+	};
+	info!("Inserted key {} into {}", public, path.display());
+	Ok(())
+}
+
+fn insert_pair<P: Pair>(store: &dyn BareCryptoStore, suri: &str) -> error::Result<String>
+	where P::Public: Ss58Codec,
+{
+	let pair = store.insert::<P>(suri)
+		.map_err(|e| error::Error::Input(format!("Failed to insert key: {}", e)))?;
+	Ok(pair.public().to_ss58check())
+}
+
+/// Parses a key type id given as number or as the name of a cryptography scheme.
+fn parse_key_type(input: &str) -> error::Result<KeyTypeId> {
+	match input.to_lowercase().as_str() {
+		"ed25519" => Ok(key_types::ED25519),
+		"sr25519" => Ok(key_types::SR25519),
+		"ecdsa" => Ok(key_types::ECDSA),
+		other => other.parse().map_err(|_| error::Error::Input(format!("Invalid key type {}", input))),
+	}
+}
+
+/// Describes the key given by a secret phrase, secret URI or public URI.
+fn describe(scheme: CryptoScheme, uri: &str, password: Option<&str>) -> error::Result<String> {
+	match scheme {
+		CryptoScheme::Ed25519 => describe_pair::<ed25519::Pair>(uri, password),
+		CryptoScheme::Sr25519 => describe_pair::<sr25519::Pair>(uri, password),
+		CryptoScheme::Ecdsa => describe_pair::<ecdsa::Pair>(uri, password),
+	}
+}
+
+fn describe_pair<P: Pair>(uri: &str, password: Option<&str>) -> error::Result<String>
+	where P::Public: Ss58Codec + AsRef<[u8]>,
+{
+	if let Ok((pair, seed)) = P::from_phrase(uri, password) {
+		let public = pair.public();
+		Ok(format!(
+			"Secret phrase `{}` is account:\n  Secret seed: 0x{}\n  Public key (hex): 0x{}\n  Address (SS58): {}",
+			uri,
+			HexDisplay::from(&seed.as_ref()),
+			HexDisplay::from(&public.as_ref()),
+			public.to_ss58check(),
+		))
+	} else if let Ok(pair) = P::from_string(uri, password) {
+		let public = pair.public();
+		Ok(format!(
+			"Secret Key URI `{}` is account:\n  Public key (hex): 0x{}\n  Address (SS58): {}",
+			uri,
+			HexDisplay::from(&public.as_ref()),
+			public.to_ss58check(),
+		))
+	} else if let Ok(public) = P::Public::from_string(uri) {
+		Ok(format!(
+			"Public Key URI `{}` is account:\n  Public key (hex): 0x{}\n  Address (SS58): {}",
+			uri,
+			HexDisplay::from(&public.as_ref()),
+			public.to_ss58check(),
+		))
+	} else {
+		Err(error::Error::Input(format!("Invalid phrase or URI `{}`", uri)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::crypto::DEV_PHRASE;
+	use tempdir::TempDir;
+
+	#[test]
+	fn describes_phrases_and_uris() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap().public().to_ss58check();
+
+		let phrase = describe(CryptoScheme::Sr25519, DEV_PHRASE, None).unwrap();
+		assert!(phrase.starts_with("Secret phrase"));
+
+		let secret = describe(CryptoScheme::Sr25519, "//Alice", None).unwrap();
+		assert!(secret.starts_with("Secret Key URI"));
+		assert!(secret.ends_with(&alice));
+
+		let public = describe(CryptoScheme::Sr25519, &alice, None).unwrap();
+		assert!(public.starts_with("Public Key URI"));
+		assert!(public.ends_with(&alice));
+
+		assert!(describe(CryptoScheme::Ed25519, "not a key", None).is_err());
+	}
+
+	#[test]
+	fn parses_key_types() {
+		assert_eq!(parse_key_type("Ed25519").unwrap(), key_types::ED25519);
+		assert_eq!(parse_key_type("20").unwrap(), key_types::SR25519);
+		assert_eq!(parse_key_type("ecdsa").unwrap(), key_types::ECDSA);
+		assert!(parse_key_type("foo").is_err());
+	}
+
+	#[test]
+	fn inserted_key_is_in_keystore() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = keystore::Store::open(temp_dir.path().to_owned(), None).unwrap();
+		let alice = insert_pair::<sr25519::Pair>(&store, "//Alice").unwrap();
+
+		let store = keystore::Store::open(temp_dir.path().to_owned(), None).unwrap();
+		let contents = (&store as &dyn BareCryptoStore).contents::<sr25519::Public>().unwrap();
+		assert_eq!(contents.iter().map(Ss58Codec::to_ss58check).collect::<Vec<_>>(), vec![alice]);
+	}
+}
@@ -24,21 +24,24 @@ mod traits;
 mod params;
 pub mod error;
 pub mod informant;
+mod key;
 
 use client::ExecutionStrategies;
 use service::{
 	ServiceFactory, FactoryFullConfiguration, RuntimeGenesis,
-	FactoryGenesis, PruningMode, ChainSpec,
+	FactoryGenesis, FactoryBlock, PruningMode, ChainSpec, KeystoreConfig,
 };
 use network::{
 	self, multiaddr::Protocol,
 	config::{NetworkConfiguration, TransportConfig, NonReservedPeerMode, NodeKeyConfig, build_multiaddr},
 };
-use primitives::H256;
+use primitives::{H256, blake2_256, crypto::{Protected, Ss58AddressFormat, set_default_ss58_version}};
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, NumberFor};
 
 use std::{
 	io::{Write, Read, stdin, stdout, ErrorKind}, iter, fs::{self, File}, net::{Ipv4Addr, SocketAddr},
-	path::{Path, PathBuf}, str::FromStr,
+	path::{Path, PathBuf}, str::FromStr, convert::TryFrom,
 };
 
 use names::{Generator, Name};
@@ -47,7 +50,9 @@ use structopt::{StructOpt, clap::AppSettings};
 #[doc(hidden)]
 pub use structopt::clap::App;
 use params::{
-	RunCmd, PurgeChainCmd, RevertCmd, ImportBlocksCmd, ExportBlocksCmd, BuildSpecCmd,
+	RunCmd, PurgeChainCmd, RevertCmd, MigrateDbCmd, ImportBlocksCmd, ExportBlocksCmd, CheckBlockCmd,
+	BuildSpecCmd,
+	KeystoreParams,
 	NetworkConfigurationParams, MergeParameters, TransactionPoolParams,
 	NodeKeyParams, NodeKeyType, Cors,
 };
@@ -55,7 +60,6 @@ pub use params::{NoCustom, CoreParams, SharedParams};
 pub use traits::{GetLogFilter, AugmentClap};
 use app_dirs::{AppInfo, AppDataType};
 use log::info;
-use lazy_static::lazy_static;
 
 use futures::Future;
 use substrate_telemetry::TelemetryEndpoints;
@@ -127,9 +131,26 @@ fn load_spec<F, G>(cli: &SharedParams, factory: F) -> error::Result<ChainSpec<G>
 		Some(spec) => spec,
 		None => ChainSpec::from_json_file(PathBuf::from(chain_key))?
 	};
+	if let Some(format) = ss58_address_format(&spec)? {
+		set_default_ss58_version(format);
+	}
 	Ok(spec)
 }
 
+/// Returns the SS58 address format given by the `ss58Format` property of the chain spec, if any.
+fn ss58_address_format<G: RuntimeGenesis>(spec: &ChainSpec<G>) -> error::Result<Option<Ss58AddressFormat>> {
+	let format = match spec.properties().get("ss58Format") {
+		Some(format) => format.clone(),
+		None => return Ok(None),
+	};
+	format.as_u64()
+		.and_then(|prefix| u8::try_from(prefix).ok())
+		.and_then(|prefix| Ss58AddressFormat::try_from(prefix).ok())
+		.or_else(|| format.as_str().and_then(|name| Ss58AddressFormat::try_from(name).ok()))
+		.map(Some)
+		.ok_or_else(|| error::Error::Input(format!("Invalid `ss58Format` in chain spec: {}", format)))
+}
+
 fn base_path(cli: &SharedParams, version: &VersionInfo) -> PathBuf {
 	cli.base_path.clone()
 		.unwrap_or_else(||
@@ -216,7 +237,10 @@ where
 		.get_matches_from(args);
 	let cli_args = CoreParams::<CC, RP>::from_clap(&matches);
 
-	init_logger(cli_args.get_log_filter().as_ref().map(|v| v.as_ref()).unwrap_or(""));
+	let _ = substrate_logging::init(
+		cli_args.get_log_filter().as_ref().map(|v| v.as_ref()).unwrap_or(""),
+		cli_args.get_log_output(),
+	);
 	fdlimit::raise_fd_limit();
 
 	match cli_args {
@@ -229,10 +253,16 @@ where
 			export_blocks::<F, _, _>(params, spec_factory, exit, version).map(|_| None),
 		params::CoreParams::ImportBlocks(params) =>
 			import_blocks::<F, _, _>(params, spec_factory, exit, version).map(|_| None),
+		params::CoreParams::CheckBlock(params) =>
+			check_block::<F, _>(params, spec_factory, version).map(|_| None),
 		params::CoreParams::PurgeChain(params) =>
 			purge_chain::<F, _>(params, spec_factory, version).map(|_| None),
 		params::CoreParams::Revert(params) =>
 			revert_chain::<F, _>(params, spec_factory, version).map(|_| None),
+		params::CoreParams::MigrateDb(params) =>
+			migrate_db::<F, _>(params, spec_factory, version).map(|_| None),
+		params::CoreParams::Key(params) =>
+			key::run::<FactoryGenesis<F>, _>(params, spec_factory, version).map(|_| None),
 		params::CoreParams::Custom(params) => Ok(Some(params)),
 	}
 }
@@ -263,6 +293,14 @@ where
 	}
 }
 
+/// Returns the hex-encoded node key of a keyring test account.
+///
+/// The key is derived from the account name, so that nodes started with e.g. `--alice` always
+/// have the same peer id and can be given to others as a bootnode.
+fn keyring_node_key(account: keyring::AuthorityKeyring) -> String {
+	format!("{:x}", H256::from(blake2_256(format!("//{}", account).as_bytes())))
+}
+
 fn net_config_file<P>(net_config_dir: &Option<P>, name: &str) -> Option<PathBuf>
 where
 	P: AsRef<Path>
@@ -352,9 +390,14 @@ fn fill_network_configuration(
 
 	config.in_peers = cli.in_peers;
 	config.out_peers = cli.out_peers;
+	config.full_peers = cli.full_peers;
+	config.light_peers = cli.light_peers;
+	config.reserved_peers = cli.reserved_peers;
+	config.authority_peers = cli.authority_peers;
 
 	config.transport = TransportConfig::Normal {
 		enable_mdns: !is_dev && !cli.no_mdns,
+		enable_nat_port_mapping: !is_dev && cli.nat_port_mapping,
 		wasm_external_transport: None,
 	};
 
@@ -366,6 +409,20 @@ fn input_keystore_password() -> Result<String, String> {
 		.map_err(|e| format!("{:?}", e))
 }
 
+/// Returns the keystore password given on the command line, in a file or interactively.
+fn keystore_password(params: &KeystoreParams) -> error::Result<Option<Protected<String>>> {
+	let password = if params.password_interactive {
+		Some(input_keystore_password()?)
+	} else if let Some(ref file) = params.password_filename {
+		let password = fs::read_to_string(file)
+			.map_err(|e| format!("Unable to read password file {}: {}", file.display(), e))?;
+		Some(password.trim_end_matches(|c| c == '\n' || c == '\r').to_owned())
+	} else {
+		params.password.clone()
+	};
+	Ok(password.map(Into::into))
+}
+
 fn create_run_node_config<F, S>(
 	cli: RunCmd, spec_factory: S, impl_name: &'static str, version: &VersionInfo
 ) -> error::Result<FactoryFullConfiguration<F>>
@@ -375,8 +432,8 @@ where
 {
 	let spec = load_spec(&cli.shared_params, spec_factory)?;
 	let mut config = service::Configuration::default_with_spec(spec.clone());
-	if cli.interactive_password {
-		config.password = input_keystore_password()?.into()
+	if let Some(password) = keystore_password(&cli.keystore_params)? {
+		config.password = password;
 	}
 
 	config.impl_name = impl_name;
@@ -401,9 +458,16 @@ where
 
 	let base_path = base_path(&cli.shared_params, version);
 
-	config.keystore_path = cli.keystore_path.or_else(|| Some(keystore_path(&base_path, config.chain_spec.id())));
+	config.keystore = match (cli.remote_signer, cli.keystore_params.keystore_path) {
+		(Some(address), _) => KeystoreConfig::Remote(address),
+		(None, Some(path)) => KeystoreConfig::Path(path),
+		(None, None) if cli.shared_params.dev || cli.keyring.account.is_some() => KeystoreConfig::InMemory,
+		(None, None) => KeystoreConfig::Path(keystore_path(&base_path, config.chain_spec.id())),
+	};
 
 	config.database_path = db_path(&base_path, config.chain_spec.id());
+	config.database = cli.shared_params.database.into();
+	config.wasm_method = cli.shared_params.wasm_method.into();
 	config.database_cache_size = cli.database_cache_size;
 	config.state_cache_size = cli.state_cache_size;
 	config.pruning = match cli.pruning {
@@ -413,11 +477,18 @@ where
 			s.parse().map_err(|_| error::Error::Input("Invalid pruning mode specified".to_string()))?
 		),
 	};
+	config.blocks_pruning = match cli.blocks_pruning {
+		Some(ref s) if s == "archive" => None,
+		None => None,
+		Some(s) => Some(
+			s.parse().map_err(|_| error::Error::Input("Invalid blocks pruning mode specified".to_string()))?
+		),
+	};
 
 	let role =
 		if cli.light {
 			service::Roles::LIGHT
-		} else if cli.validator || cli.shared_params.dev {
+		} else if cli.validator || cli.shared_params.dev || cli.keyring.account.is_some() {
 			service::Roles::AUTHORITY
 		} else {
 			service::Roles::FULL
@@ -447,9 +518,17 @@ where
 
 	let is_dev = cli.shared_params.dev;
 
+	let mut network_config = cli.network_config;
+	if let Some(account) = cli.keyring.account {
+		let node_key = &mut network_config.node_key_params;
+		if node_key.node_key.is_none() && node_key.node_key_file.is_none() {
+			node_key.node_key = Some(keyring_node_key(account));
+		}
+	}
+
 	let client_id = config.client_id();
 	fill_network_configuration(
-		cli.network_config,
+		network_config,
 		&base_path,
 		spec.id(),
 		&mut config.network,
@@ -484,6 +563,13 @@ where
 		parse_address(&format!("{}:{}", ws_interface, 9944), cli.ws_port)?
 	);
 	config.rpc_ws_max_connections = cli.ws_max_connections;
+	config.rpc_methods = cli.rpc_methods.into();
+	if let Some(port) = cli.prometheus_port {
+		let prometheus_interface: &str = if cli.prometheus_external { "0.0.0.0" } else { "127.0.0.1" };
+		config.prometheus_port = Some(
+			parse_address(&format!("{}:{}", prometheus_interface, 9615), Some(port))?
+		);
+	}
 	config.rpc_cors = cli.rpc_cors.unwrap_or_else(|| if is_dev {
 		log::warn!("Running in --dev mode, RPC CORS has been disabled.");
 		Cors::All
@@ -596,6 +682,8 @@ where
 
 	let mut config = service::Configuration::default_with_spec(spec.clone());
 	config.database_path = db_path(&base_path, spec.id());
+	config.database = cli.database.into();
+	config.wasm_method = cli.wasm_method.into();
 
 	Ok(config)
 }
@@ -616,7 +704,7 @@ where
 	info!("DB path: {}", config.database_path.display());
 	let from = cli.from.unwrap_or(1);
 	let to = cli.to;
-	let json = cli.json;
+	let binary = cli.binary;
 
 	let file: Box<dyn Write> = match cli.output {
 		Some(filename) => Box::new(File::create(filename)?),
@@ -624,7 +712,7 @@ where
 	};
 
 	service::chain_ops::export_blocks::<F, _, _>(
-		config, exit.into_exit(), file, from.into(), to.map(Into::into), json
+		config, exit.into_exit(), file, from.into(), to.map(Into::into), binary
 	).map_err(Into::into)
 }
 
@@ -639,18 +727,52 @@ where
 	E: IntoExit,
 	S: FnOnce(&str) -> Result<Option<ChainSpec<FactoryGenesis<F>>>, String>,
 {
-	let config = create_config_with_db_path::<F, _>(spec_factory, &cli.shared_params, version)?;
+	let mut config = create_config_with_db_path::<F, _>(spec_factory, &cli.shared_params, version)?;
+	config.default_heap_pages = cli.default_heap_pages.map(Into::into);
 
 	let file: Box<dyn Read> = match cli.input {
 		Some(filename) => Box::new(File::open(filename)?),
 		None => Box::new(stdin()),
 	};
 
-	let fut = service::chain_ops::import_blocks::<F, _, _>(config, exit.into_exit(), file)?;
+	let fut = service::chain_ops::import_blocks::<F, _, _>(config, exit.into_exit(), file, cli.binary)?;
 	tokio::run(fut);
 	Ok(())
 }
 
+fn check_block<F, S>(
+	cli: CheckBlockCmd,
+	spec_factory: S,
+	version: &VersionInfo,
+) -> error::Result<()>
+where
+	F: ServiceFactory,
+	S: FnOnce(&str) -> Result<Option<ChainSpec<FactoryGenesis<F>>>, String>,
+{
+	let mut config = create_config_with_db_path::<F, _>(spec_factory, &cli.shared_params, version)?;
+	config.default_heap_pages = cli.default_heap_pages.map(Into::into);
+
+	let block_id = parse_block_id::<FactoryBlock<F>>(&cli.input)?;
+	Ok(service::chain_ops::check_block::<F>(config, block_id)?)
+}
+
+/// Parses a block hash (with or without `0x` prefix) or a block number.
+fn parse_block_id<B>(input: &str) -> error::Result<BlockId<B>> where
+	B: BlockT<Hash=H256>,
+	NumberFor<B>: From<u32>,
+{
+	let hex = input.trim_start_matches("0x");
+	if hex.len() == 64 {
+		H256::from_str(hex)
+			.map(BlockId::Hash)
+			.map_err(|e| format!("Invalid block hash {}: {:?}", input, e).into())
+	} else {
+		input.parse::<u32>()
+			.map(|number| BlockId::Number(number.into()))
+			.map_err(|_| format!("Invalid block number {}, expected a block hash or number", input).into())
+	}
+}
+
 fn revert_chain<F, S>(
 	cli: RevertCmd,
 	spec_factory: S,
@@ -665,6 +787,19 @@ where
 	Ok(service::chain_ops::revert_chain::<F>(config, blocks.into())?)
 }
 
+fn migrate_db<F, S>(
+	cli: MigrateDbCmd,
+	spec_factory: S,
+	version: &VersionInfo,
+) -> error::Result<()>
+where
+	F: ServiceFactory,
+	S: FnOnce(&str) -> Result<Option<ChainSpec<FactoryGenesis<F>>>, String>,
+{
+	let config = create_config_with_db_path::<F, _>(spec_factory, &cli.shared_params, version)?;
+	Ok(service::chain_ops::migrate_database::<F>(config)?)
+}
+
 fn purge_chain<F, S>(
 	cli: PurgeChainCmd,
 	spec_factory: S,
@@ -745,69 +880,6 @@ fn network_path(base_path: &Path, chain_id: &str) -> PathBuf {
 	path
 }
 
-fn init_logger(pattern: &str) {
-	use ansi_term::Colour;
-
-	let mut builder = env_logger::Builder::new();
-	// Disable info logging by default for some modules:
-	builder.filter(Some("ws"), log::LevelFilter::Off);
-	builder.filter(Some("hyper"), log::LevelFilter::Warn);
-	// Enable info for others.
-	builder.filter(None, log::LevelFilter::Info);
-
-	if let Ok(lvl) = std::env::var("RUST_LOG") {
-		builder.parse_filters(&lvl);
-	}
-
-	builder.parse_filters(pattern);
-	let isatty = atty::is(atty::Stream::Stderr);
-	let enable_color = isatty;
-
-	builder.format(move |buf, record| {
-		let now = time::now();
-		let timestamp =
-			time::strftime("%Y-%m-%d %H:%M:%S", &now)
-				.expect("Error formatting log timestamp");
-
-		let mut output = if log::max_level() <= log::LevelFilter::Info {
-			format!("{} {}", Colour::Black.bold().paint(timestamp), record.args())
-		} else {
-			let name = ::std::thread::current()
-				.name()
-				.map_or_else(Default::default, |x| format!("{}", Colour::Blue.bold().paint(x)));
-			let millis = (now.tm_nsec as f32 / 1000000.0).round() as usize;
-			let timestamp = format!("{}.{:03}", timestamp, millis);
-			format!(
-				"{} {} {} {}  {}",
-				Colour::Black.bold().paint(timestamp),
-				name,
-				record.level(),
-				record.target(),
-				record.args()
-			)
-		};
-
-		if !enable_color {
-			output = kill_color(output.as_ref());
-		}
-
-		if !isatty && record.level() <= log::Level::Info && atty::is(atty::Stream::Stdout) {
-			// duplicate INFO/WARN output to console
-			println!("{}", output);
-		}
-		writeln!(buf, "{}", output)
-	});
-
-	builder.init();
-}
-
-fn kill_color(s: &str) -> String {
-	lazy_static! {
-		static ref RE: Regex = Regex::new("\x1b\\[[^m]+m").expect("Error initializing color regex");
-	}
-	RE.replace_all(s, "").to_string()
-}
-
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -935,4 +1007,28 @@ mod tests {
 		assert!(no_config_dir().is_ok());
 		assert!(some_config_dir("x".to_string()).is_ok());
 	}
+
+	#[test]
+	fn keyring_node_keys_are_valid_and_distinct() {
+		let alice = keyring_node_key(keyring::AuthorityKeyring::Alice);
+		let bob = keyring_node_key(keyring::AuthorityKeyring::Bob);
+
+		assert_eq!(alice, keyring_node_key(keyring::AuthorityKeyring::Alice));
+		assert_ne!(alice, bob);
+		assert!(parse_ed25519_secret(&alice).is_ok());
+		assert!(parse_secp256k1_secret(&alice).is_ok());
+	}
+
+	#[test]
+	fn parses_block_hashes_and_numbers() {
+		use runtime_primitives::testing::{Block, ExtrinsicWrapper};
+		type TestBlock = Block<ExtrinsicWrapper<u64>>;
+
+		let hash: H256 = [0x42; 32].into();
+		let hex = format!("{:x}", hash);
+		assert_eq!(parse_block_id::<TestBlock>(&hex).unwrap(), BlockId::Hash(hash));
+		assert_eq!(parse_block_id::<TestBlock>(&format!("0x{}", hex)).unwrap(), BlockId::Hash(hash));
+		assert_eq!(parse_block_id::<TestBlock>("1337").unwrap(), BlockId::Number(1337));
+		assert!(parse_block_id::<TestBlock>("0x1337").is_err());
+	}
 }
@@ -16,9 +16,11 @@
 
 use crate::traits::{AugmentClap, GetLogFilter};
 
-use std::path::PathBuf;
+use std::{convert::TryFrom, path::PathBuf};
 use structopt::{StructOpt, clap::{arg_enum, _clap_count_exprs, App, AppSettings, SubCommand, Arg}};
 use client;
+use keystore::RemoteAddress;
+use primitives::crypto::Ss58AddressFormat;
 
 /// Auxiliary macro to implement `GetLogFilter` for all types that have the `shared_params` field.
 macro_rules! impl_get_log_filter {
@@ -27,6 +29,10 @@ macro_rules! impl_get_log_filter {
 			fn get_log_filter(&self) -> Option<String> {
 				self.shared_params.get_log_filter()
 			}
+
+			fn get_log_output(&self) -> substrate_logging::Output {
+				self.shared_params.get_log_output()
+			}
 		}
 	}
 }
@@ -53,6 +59,26 @@ impl Into<client::ExecutionStrategy> for ExecutionStrategy {
 	}
 }
 
+arg_enum! {
+	/// Which RPC methods to expose
+	#[derive(Debug, Clone, Copy)]
+	pub enum RpcMethods {
+		Auto,
+		Safe,
+		Unsafe,
+	}
+}
+
+impl Into<service::RpcMethods> for RpcMethods {
+	fn into(self) -> service::RpcMethods {
+		match self {
+			RpcMethods::Auto => service::RpcMethods::Auto,
+			RpcMethods::Safe => service::RpcMethods::Safe,
+			RpcMethods::Unsafe => service::RpcMethods::Unsafe,
+		}
+	}
+}
+
 arg_enum! {
 	/// How to execute blocks
 	#[derive(Debug, Clone)]
@@ -63,6 +89,90 @@ arg_enum! {
 	}
 }
 
+arg_enum! {
+	/// Database backend
+	#[derive(Debug, Clone, Copy)]
+	pub enum Database {
+		RocksDb,
+		ParityDb,
+	}
+}
+
+arg_enum! {
+	/// Format of the log output
+	#[derive(Debug, Clone, Copy)]
+	pub enum LogFormat {
+		Text,
+		Json,
+	}
+}
+
+impl Into<substrate_logging::Format> for LogFormat {
+	fn into(self) -> substrate_logging::Format {
+		match self {
+			LogFormat::Text => substrate_logging::Format::Text,
+			LogFormat::Json => substrate_logging::Format::Json,
+		}
+	}
+}
+
+arg_enum! {
+	/// When to color the log output
+	#[derive(Debug, Clone, Copy)]
+	pub enum LogColor {
+		Auto,
+		Always,
+		Never,
+	}
+}
+
+impl Into<substrate_logging::Color> for LogColor {
+	fn into(self) -> substrate_logging::Color {
+		match self {
+			LogColor::Auto => substrate_logging::Color::Auto,
+			LogColor::Always => substrate_logging::Color::Always,
+			LogColor::Never => substrate_logging::Color::Never,
+		}
+	}
+}
+
+impl Into<service::DatabaseBackend> for Database {
+	fn into(self) -> service::DatabaseBackend {
+		match self {
+			Database::RocksDb => service::DatabaseBackend::RocksDb,
+			Database::ParityDb => service::DatabaseBackend::ParityDb,
+		}
+	}
+}
+
+arg_enum! {
+	/// How to execute Wasm runtimes
+	#[derive(Debug, Clone, Copy)]
+	pub enum WasmExecutionMethod {
+		Interpreted,
+		Compiled,
+	}
+}
+
+impl Into<service::WasmExecutionMethod> for WasmExecutionMethod {
+	fn into(self) -> service::WasmExecutionMethod {
+		match self {
+			WasmExecutionMethod::Interpreted => service::WasmExecutionMethod::Interpreted,
+			WasmExecutionMethod::Compiled => service::WasmExecutionMethod::Compiled,
+		}
+	}
+}
+
+arg_enum! {
+	/// Cryptography scheme of a key
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub enum CryptoScheme {
+		Ed25519,
+		Sr25519,
+		Ecdsa,
+	}
+}
+
 /// Shared parameters used by all `CoreParams`.
 #[derive(Debug, StructOpt, Clone)]
 pub struct SharedParams {
@@ -78,15 +188,71 @@ pub struct SharedParams {
 	#[structopt(long = "base-path", short = "d", value_name = "PATH", parse(from_os_str))]
 	pub base_path: Option<PathBuf>,
 
-	/// Sets a custom logging filter
+	/// Select the database backend to use.
+	#[structopt(
+		long = "database",
+		value_name = "DB",
+		raw(
+			possible_values = "&Database::variants()",
+			case_insensitive = "true",
+			default_value = r#""RocksDb""#
+		)
+	)]
+	pub database: Database,
+
+	/// Method for executing Wasm runtime code. `Compiled` requires the node to be built with the
+	/// `wasmtime` feature of the executor.
+	#[structopt(
+		long = "wasm-execution",
+		value_name = "METHOD",
+		raw(
+			possible_values = "&WasmExecutionMethod::variants()",
+			case_insensitive = "true",
+			default_value = r#""Interpreted""#
+		)
+	)]
+	pub wasm_method: WasmExecutionMethod,
+
+	/// Sets a custom logging filter, e.g. `sync=debug,afg=trace`
 	#[structopt(short = "l", long = "log", value_name = "LOG_PATTERN")]
 	pub log: Option<String>,
+
+	/// Format of the log output
+	#[structopt(
+		long = "log-format",
+		value_name = "FORMAT",
+		raw(
+			possible_values = "&LogFormat::variants()",
+			case_insensitive = "true",
+			default_value = r#""Text""#
+		)
+	)]
+	pub log_format: LogFormat,
+
+	/// When to color the log output; `auto` colors it only if it is written to a terminal
+	#[structopt(
+		long = "log-color",
+		value_name = "WHEN",
+		raw(
+			possible_values = "&LogColor::variants()",
+			case_insensitive = "true",
+			default_value = r#""Auto""#
+		)
+	)]
+	pub log_color: LogColor,
 }
 
 impl GetLogFilter for SharedParams {
 	fn get_log_filter(&self) -> Option<String> {
 		self.log.clone()
 	}
+
+	fn get_log_output(&self) -> substrate_logging::Output {
+		substrate_logging::Output {
+			format: self.log_format.into(),
+			color: self.log_color.into(),
+		}
+	}
 }
 
 /// Parameters used to create the network configuration.
@@ -116,11 +282,35 @@ pub struct NetworkConfigurationParams {
 	#[structopt(long = "in-peers", value_name = "IN_PEERS", default_value = "25")]
 	pub in_peers: u32,
 
+	/// Specify the maximum number of full nodes we're connected to. Unlimited by default, apart
+	/// from --in-peers and --out-peers.
+	#[structopt(long = "full-peers", value_name = "COUNT")]
+	pub full_peers: Option<u32>,
+
+	/// Specify the maximum number of light clients we're connected to. Unlimited by default, apart
+	/// from --in-peers and --out-peers.
+	#[structopt(long = "light-peers", value_name = "COUNT")]
+	pub light_peers: Option<u32>,
+
+	/// Specify the maximum number of reserved nodes we're connected to. Unlimited by default.
+	#[structopt(long = "reserved-peers", value_name = "COUNT")]
+	pub reserved_peers: Option<u32>,
+
+	/// Specify the maximum number of authorities we're connected to. Authorities beyond this
+	/// number are counted as full nodes. Unlimited by default.
+	#[structopt(long = "authority-peers", value_name = "COUNT")]
+	pub authority_peers: Option<u32>,
+
 	/// By default, the network will use mDNS to discover other nodes on the local network. This
 	/// disables it. Automatically implied when using --dev.
 	#[structopt(long = "no-mdns")]
 	pub no_mdns: bool,
 
+	/// Try to map the listening port on the router with UPnP or NAT-PMP, so that the node can be
+	/// reached from the internet without configuring the router manually.
+	#[structopt(long = "nat-port-mapping")]
+	pub nat_port_mapping: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub node_key_params: NodeKeyParams
@@ -302,13 +492,52 @@ pub struct ExecutionStrategies {
 	pub execution: Option<ExecutionStrategy>,
 }
 
-/// The `run` command used to run a node.
+/// Parameters of the keystore.
 #[derive(Debug, StructOpt, Clone)]
-pub struct RunCmd {
+pub struct KeystoreParams {
 	/// Specify custom keystore path
 	#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
 	pub keystore_path: Option<PathBuf>,
 
+	/// Use interactive shell for entering the password used by the keystore.
+	#[structopt(
+		short = "i",
+		long = "password-interactive",
+		raw(conflicts_with_all = "&[ \"password\", \"password_filename\" ]")
+	)]
+	pub password_interactive: bool,
+
+	/// Password used by the keystore.
+	#[structopt(
+		long = "password",
+		value_name = "PASSWORD",
+		raw(conflicts_with_all = "&[ \"password_interactive\", \"password_filename\" ]")
+	)]
+	pub password: Option<String>,
+
+	/// File that contains the password used by the keystore.
+	#[structopt(
+		long = "password-filename",
+		value_name = "PATH",
+		parse(from_os_str),
+		raw(conflicts_with_all = "&[ \"password_interactive\", \"password\" ]")
+	)]
+	pub password_filename: Option<PathBuf>,
+}
+
+/// The `run` command used to run a node.
+#[derive(Debug, StructOpt, Clone)]
+pub struct RunCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+
+	/// Sign with the keys of an external signer, listening on `host:port` or `unix:<path>`
+	///
+	/// The keys are not read from the keystore then.
+	#[structopt(long = "remote-signer", value_name = "ADDR", conflicts_with = "keystore_path")]
+	pub remote_signer: Option<RemoteAddress>,
+
 	/// Specify additional key seed
 	#[structopt(long = "key", value_name = "STRING")]
 	pub key: Option<String>,
@@ -334,7 +563,7 @@ pub struct RunCmd {
 	#[structopt(long = "db-cache", value_name = "MiB")]
 	pub database_cache_size: Option<u32>,
 
-	/// Specify the state cache size
+	/// Specify the state cache size. An additional quarter of this size is used to cache trie nodes.
 	#[structopt(long = "state-cache-size", value_name = "Bytes", default_value = "67108864")]
 	pub state_cache_size: usize,
 
@@ -346,6 +575,19 @@ pub struct RunCmd {
 	#[structopt(long = "ws-external")]
 	pub ws_external: bool,
 
+	/// Which RPC methods to expose. `Auto` exposes unsafe methods, e.g. `author_rotateKeys`, only
+	/// on servers listening on a local interface.
+	#[structopt(
+		long = "rpc-methods",
+		value_name = "METHODS",
+		raw(
+			possible_values = "&RpcMethods::variants()",
+			case_insensitive = "true",
+			default_value = r#""Auto""#
+		)
+	)]
+	pub rpc_methods: RpcMethods,
+
 	/// Specify HTTP RPC server TCP port
 	#[structopt(long = "rpc-port", value_name = "PORT")]
 	pub rpc_port: Option<u16>,
@@ -366,10 +608,23 @@ pub struct RunCmd {
 	#[structopt(long = "rpc-cors", value_name = "ORIGINS", parse(try_from_str = "parse_cors"))]
 	pub rpc_cors: Option<Cors>,
 
+	/// Expose a Prometheus exporter on this TCP port. Metrics are disabled if unspecified.
+	#[structopt(long = "prometheus-port", value_name = "PORT")]
+	pub prometheus_port: Option<u16>,
+
+	/// Listen to all Prometheus exporter interfaces (default is local)
+	#[structopt(long = "prometheus-external")]
+	pub prometheus_external: bool,
+
 	/// Specify the pruning mode, a number of blocks to keep or 'archive'. Default is 256.
 	#[structopt(long = "pruning", value_name = "PRUNING_MODE")]
 	pub pruning: Option<String>,
 
+	/// Specify the blocks pruning mode, a number of finalized blocks whose bodies and
+	/// justifications are kept or 'archive'. Headers are always kept. Default is 'archive'.
+	#[structopt(long = "blocks-pruning", value_name = "PRUNING_MODE")]
+	pub blocks_pruning: Option<String>,
+
 	/// The human-readable name for this node, as reported to the telemetry server, if enabled
 	#[structopt(long = "name", value_name = "NAME")]
 	pub name: Option<String>,
@@ -420,10 +675,6 @@ pub struct RunCmd {
 	/// Enable authoring even when offline.
 	#[structopt(long = "force-authoring")]
 	pub force_authoring: bool,
-
-	/// Interactive password for validator key.
-	#[structopt(short = "i")]
-	pub interactive_password: bool,
 }
 
 /// Stores all required Cli values for a keyring test account.
@@ -438,7 +689,12 @@ lazy_static::lazy_static! {
 	/// The Cli values for all test accounts.
 	static ref TEST_ACCOUNTS_CLI_VALUES: Vec<KeyringTestAccountCliValues> = {
 		keyring::AuthorityKeyring::iter().map(|a| {
-			let help = format!("Shortcut for `--key //{} --name {}`.", a, a);
+			let help = format!(
+				"Shortcut for `--key //{} --name {} --validator`, with an in-memory keystore and a \
+				node key derived from the account name.",
+				a,
+				a,
+			);
 			let conflicts_with = keyring::AuthorityKeyring::iter()
 				.filter(|b| a != *b)
 				.map(|b| b.to_string().to_lowercase())
@@ -532,6 +788,15 @@ impl From<Cors> for Option<Vec<String>> {
 	}
 }
 
+/// Parse an SS58 address format given by name or numeric prefix.
+fn parse_ss58_address_format(s: &str) -> Result<Ss58AddressFormat, String> {
+	Ss58AddressFormat::try_from(s).map_err(|_| format!(
+		"Invalid network `{}`: must be one of {} or a prefix below 64",
+		s,
+		Ss58AddressFormat::all_names().join(", "),
+	))
+}
+
 /// Parse cors origins
 fn parse_cors(s: &str) -> Result<Cors, Box<dyn std::error::Error>> {
 	let mut is_all = false;
@@ -585,9 +850,9 @@ pub struct ExportBlocksCmd {
 	#[structopt(long = "to", value_name = "BLOCK")]
 	pub to: Option<u32>,
 
-	/// Use JSON output rather than binary.
-	#[structopt(long = "json")]
-	pub json: bool,
+	/// Use binary output rather than JSON.
+	#[structopt(long = "binary")]
+	pub binary: bool,
 
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
@@ -603,6 +868,10 @@ pub struct ImportBlocksCmd {
 	#[structopt(parse(from_os_str))]
 	pub input: Option<PathBuf>,
 
+	/// Try importing blocks from binary format rather than JSON.
+	#[structopt(long = "binary")]
+	pub binary: bool,
+
 	/// The default number of 64KB pages to ever allocate for Wasm execution. Don't alter this unless you know what you're doing.
 	#[structopt(long = "default-heap-pages", value_name = "COUNT")]
 	pub default_heap_pages: Option<u32>,
@@ -614,6 +883,24 @@ pub struct ImportBlocksCmd {
 
 impl_get_log_filter!(ImportBlocksCmd);
 
+/// The `check-block` command used to validate blocks.
+#[derive(Debug, StructOpt, Clone)]
+pub struct CheckBlockCmd {
+	/// Block hash or number.
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub input: String,
+
+	/// The default number of 64KB pages to ever allocate for Wasm execution. Don't alter this unless you know what you're doing.
+	#[structopt(long = "default-heap-pages", value_name = "COUNT")]
+	pub default_heap_pages: Option<u32>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(CheckBlockCmd);
+
 /// The `revert` command used revert the chain to a previous state.
 #[derive(Debug, StructOpt, Clone)]
 pub struct RevertCmd {
@@ -642,6 +929,164 @@ pub struct PurgeChainCmd {
 
 impl_get_log_filter!(PurgeChainCmd);
 
+/// The `migrate-db` command used to migrate the RocksDb database of the chain to ParityDb.
+#[derive(Debug, StructOpt, Clone)]
+pub struct MigrateDbCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(MigrateDbCmd);
+
+/// The `key generate` command used to generate a new random key.
+#[derive(Debug, StructOpt, Clone)]
+pub struct GenerateKeyCmd {
+	/// The number of words in the phrase to generate. One of 12, 15, 18, 21 and 24.
+	#[structopt(long = "words", value_name = "WORDS", default_value = "12")]
+	pub words: usize,
+
+	/// The cryptography scheme of the key.
+	#[structopt(
+		long = "scheme",
+		value_name = "SCHEME",
+		raw(
+			possible_values = "&CryptoScheme::variants()",
+			case_insensitive = "true",
+			default_value = r#""Sr25519""#
+		)
+	)]
+	pub scheme: CryptoScheme,
+
+	/// The password the key is protected with.
+	#[structopt(long = "password", value_name = "PASSWORD")]
+	pub password: Option<String>,
+
+	/// The network to encode the SS58 address for, either by name (e.g. `substrate`, `polkadot`,
+	/// `kusama`) or as numeric prefix. Defaults to `substrate`.
+	#[structopt(long = "network", value_name = "NETWORK", parse(try_from_str = "parse_ss58_address_format"))]
+	pub network: Option<Ss58AddressFormat>,
+}
+
+/// The `key inspect` command used to show the public forms of a secret or public key URI.
+#[derive(Debug, StructOpt, Clone)]
+pub struct InspectKeyCmd {
+	/// A secret phrase, secret seed or SS58 address, optionally followed by a derivation path.
+	#[structopt(value_name = "URI")]
+	pub uri: String,
+
+	/// The cryptography scheme of the key.
+	#[structopt(
+		long = "scheme",
+		value_name = "SCHEME",
+		raw(
+			possible_values = "&CryptoScheme::variants()",
+			case_insensitive = "true",
+			default_value = r#""Sr25519""#
+		)
+	)]
+	pub scheme: CryptoScheme,
+
+	/// The password the key is protected with.
+	#[structopt(long = "password", value_name = "PASSWORD")]
+	pub password: Option<String>,
+
+	/// The network to encode the SS58 address for, either by name (e.g. `substrate`, `polkadot`,
+	/// `kusama`) or as numeric prefix. Defaults to `substrate`.
+	#[structopt(long = "network", value_name = "NETWORK", parse(try_from_str = "parse_ss58_address_format"))]
+	pub network: Option<Ss58AddressFormat>,
+}
+
+/// The `key insert` command used to insert a key into the keystore of a node.
+#[derive(Debug, StructOpt, Clone)]
+pub struct InsertKeyCmd {
+	/// The secret URI of the key, e.g. a secret phrase or seed with an optional derivation path.
+	#[structopt(value_name = "SURI")]
+	pub suri: String,
+
+	/// The key type id, either as number or as the name of the scheme (`ed25519`, `sr25519` or
+	/// `ecdsa`).
+	#[structopt(long = "key-type", value_name = "KEY_TYPE")]
+	pub key_type: String,
+
+	/// The network to encode the SS58 address for, either by name (e.g. `substrate`, `polkadot`,
+	/// `kusama`) or as numeric prefix. Defaults to the format of the chain specification.
+	#[structopt(long = "network", value_name = "NETWORK", parse(try_from_str = "parse_ss58_address_format"))]
+	pub network: Option<Ss58AddressFormat>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(InsertKeyCmd);
+
+/// The `key` subcommands used to manage keys.
+#[derive(Debug, Clone)]
+pub enum KeySubcommand {
+	/// Generate a random key.
+	Generate(GenerateKeyCmd),
+
+	/// Show the public forms of a key.
+	Inspect(InspectKeyCmd),
+
+	/// Insert a key into the keystore of a node.
+	Insert(InsertKeyCmd),
+}
+
+impl StructOpt for KeySubcommand {
+	fn clap<'a, 'b>() -> App<'a, 'b> {
+		<Self as AugmentClap>::augment_clap(App::new("key"))
+	}
+
+	fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
+		match matches.subcommand() {
+			("generate", Some(matches)) => KeySubcommand::Generate(GenerateKeyCmd::from_clap(matches)),
+			("inspect", Some(matches)) => KeySubcommand::Inspect(InspectKeyCmd::from_clap(matches)),
+			("insert", Some(matches)) => KeySubcommand::Insert(InsertKeyCmd::from_clap(matches)),
+			_ => unreachable!("a subcommand is required by the `key` command; qed"),
+		}
+	}
+}
+
+impl AugmentClap for KeySubcommand {
+	fn augment_clap<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+		app.setting(AppSettings::SubcommandRequiredElseHelp)
+			.subcommand(
+				GenerateKeyCmd::augment_clap(SubCommand::with_name("generate"))
+					.about("Generate a random key.")
+			)
+			.subcommand(
+				InspectKeyCmd::augment_clap(SubCommand::with_name("inspect"))
+					.about("Show the public forms of a key.")
+			)
+			.subcommand(
+				InsertKeyCmd::augment_clap(SubCommand::with_name("insert"))
+					.about("Insert a key into the keystore of a node.")
+			)
+	}
+}
+
+impl GetLogFilter for KeySubcommand {
+	fn get_log_filter(&self) -> Option<String> {
+		match self {
+			KeySubcommand::Insert(c) => c.get_log_filter(),
+			KeySubcommand::Generate(_) | KeySubcommand::Inspect(_) => None,
+		}
+	}
+
+	fn get_log_output(&self) -> substrate_logging::Output {
+		match self {
+			KeySubcommand::Insert(c) => c.get_log_output(),
+			KeySubcommand::Generate(_) | KeySubcommand::Inspect(_) => Default::default(),
+		}
+	}
+}
+
 /// All core commands that are provided by default.
 ///
 /// The core commands are split into multiple subcommands and `Run` is the default subcommand. From
@@ -661,12 +1106,21 @@ pub enum CoreParams<CC, RP> {
 	/// Import blocks from file.
 	ImportBlocks(ImportBlocksCmd),
 
+	/// Validate a single block.
+	CheckBlock(CheckBlockCmd),
+
 	/// Revert chain to the previous state.
 	Revert(RevertCmd),
 
 	/// Remove the whole chain data.
 	PurgeChain(PurgeChainCmd),
 
+	/// Migrate the RocksDb database to ParityDb.
+	MigrateDb(MigrateDbCmd),
+
+	/// Manage keys.
+	Key(KeySubcommand),
+
 	/// Further custom subcommands.
 	Custom(CC),
 }
@@ -692,6 +1146,10 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 			ImportBlocksCmd::augment_clap(SubCommand::with_name("import-blocks"))
 				.about("Import blocks from file.")
 		)
+		.subcommand(
+			CheckBlockCmd::augment_clap(SubCommand::with_name("check-block"))
+				.about("Validate a single block.")
+		)
 		.subcommand(
 			RevertCmd::augment_clap(SubCommand::with_name("revert"))
 				.about("Revert chain to the previous state.")
@@ -700,6 +1158,14 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 			PurgeChainCmd::augment_clap(SubCommand::with_name("purge-chain"))
 				.about("Remove the whole chain data.")
 		)
+		.subcommand(
+			MigrateDbCmd::augment_clap(SubCommand::with_name("migrate-db"))
+				.about("Migrate the RocksDb database to ParityDb.")
+		)
+		.subcommand(
+			<KeySubcommand as AugmentClap>::augment_clap(SubCommand::with_name("key"))
+				.about("Manage keys.")
+		)
 	}
 
 	fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
@@ -710,9 +1176,14 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 				CoreParams::ExportBlocks(ExportBlocksCmd::from_clap(matches)),
 			("import-blocks", Some(matches)) =>
 				CoreParams::ImportBlocks(ImportBlocksCmd::from_clap(matches)),
+			("check-block", Some(matches)) =>
+				CoreParams::CheckBlock(CheckBlockCmd::from_clap(matches)),
 			("revert", Some(matches)) => CoreParams::Revert(RevertCmd::from_clap(matches)),
 			("purge-chain", Some(matches)) =>
 				CoreParams::PurgeChain(PurgeChainCmd::from_clap(matches)),
+			("migrate-db", Some(matches)) =>
+				CoreParams::MigrateDb(MigrateDbCmd::from_clap(matches)),
+			("key", Some(matches)) => CoreParams::Key(KeySubcommand::from_clap(matches)),
 			(_, None) => CoreParams::Run(MergeParameters::from_clap(matches)),
 			_ => CoreParams::Custom(CC::from_clap(matches)),
 		}
@@ -726,11 +1197,29 @@ impl<CC, RP> GetLogFilter for CoreParams<CC, RP> where CC: GetLogFilter {
 			CoreParams::BuildSpec(c) => c.get_log_filter(),
 			CoreParams::ExportBlocks(c) => c.get_log_filter(),
 			CoreParams::ImportBlocks(c) => c.get_log_filter(),
+			CoreParams::CheckBlock(c) => c.get_log_filter(),
 			CoreParams::PurgeChain(c) => c.get_log_filter(),
+			CoreParams::MigrateDb(c) => c.get_log_filter(),
 			CoreParams::Revert(c) => c.get_log_filter(),
+			CoreParams::Key(c) => c.get_log_filter(),
 			CoreParams::Custom(c) => c.get_log_filter(),
 		}
 	}
+
+	fn get_log_output(&self) -> substrate_logging::Output {
+		match self {
+			CoreParams::Run(c) => c.left.get_log_output(),
+			CoreParams::BuildSpec(c) => c.get_log_output(),
+			CoreParams::ExportBlocks(c) => c.get_log_output(),
+			CoreParams::ImportBlocks(c) => c.get_log_output(),
+			CoreParams::CheckBlock(c) => c.get_log_output(),
+			CoreParams::PurgeChain(c) => c.get_log_output(),
+			CoreParams::MigrateDb(c) => c.get_log_output(),
+			CoreParams::Revert(c) => c.get_log_output(),
+			CoreParams::Key(c) => c.get_log_output(),
+			CoreParams::Custom(c) => c.get_log_output(),
+		}
+	}
 }
 
 /// A special commandline parameter that expands to nothing.
@@ -41,4 +41,9 @@ macro_rules! impl_augment_clap {
 pub trait GetLogFilter {
 	/// Returns the set log filter.
 	fn get_log_filter(&self) -> Option<String>;
+
+	/// Returns how log records should be written.
+	fn get_log_output(&self) -> substrate_logging::Output {
+		Default::default()
+	}
 }
@@ -0,0 +1,77 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compares the write and read throughput of the supported database backends.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use kvdb::{KeyValueDB, DBTransaction};
+use std::path::PathBuf;
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
+
+const COLUMN: Option<u32> = Some(1);
+const ENTRIES: u32 = 1000;
+
+fn temp_path(name: &str) -> PathBuf {
+	let path = std::env::temp_dir().join(format!("substrate-db-bench-{}-{}", name, std::process::id()));
+	let _ = std::fs::remove_dir_all(&path);
+	path
+}
+
+fn key(i: u32) -> [u8; 32] {
+	let mut key = [0u8; 32];
+	key[..4].copy_from_slice(&i.to_le_bytes());
+	key
+}
+
+fn write_batch(db: &dyn KeyValueDB) {
+	let mut transaction = DBTransaction::new();
+	for i in 0..ENTRIES {
+		transaction.put(COLUMN, &key(i), &[i as u8; 128]);
+	}
+	db.write(transaction).unwrap();
+}
+
+fn read_all(db: &dyn KeyValueDB) {
+	for i in 0..ENTRIES {
+		assert!(db.get(COLUMN, &key(i)).unwrap().is_some());
+	}
+}
+
+fn bench_backend(c: &mut Criterion, name: &'static str, db: Box<dyn KeyValueDB>) {
+	let db = std::sync::Arc::new(db);
+	let write_db = db.clone();
+	c.bench_function(&format!("{}-write", name), move |b| b.iter(|| write_batch(&**write_db)));
+	c.bench_function(&format!("{}-read", name), move |b| b.iter(|| read_all(&**db)));
+}
+
+fn benchmark(c: &mut Criterion) {
+	let num_columns = 10;
+
+	let rocksdb_path = temp_path("rocksdb");
+	let config = kvdb_rocksdb::DatabaseConfig::with_columns(Some(num_columns));
+	let rocksdb = kvdb_rocksdb::Database::open(&config, rocksdb_path.to_str().unwrap()).unwrap();
+	bench_backend(c, "rocksdb", Box::new(rocksdb));
+
+	let paritydb_path = temp_path("paritydb");
+	let paritydb = substrate_client_db::parity_db::DbAdapter::open(&paritydb_path, num_columns, &[COLUMN])
+		.unwrap();
+	bench_backend(c, "paritydb", Box::new(paritydb));
+
+	let _ = std::fs::remove_dir_all(&rocksdb_path);
+	let _ = std::fs::remove_dir_all(&paritydb_path);
+}
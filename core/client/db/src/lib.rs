@@ -32,9 +32,12 @@ pub mod offchain;
 mod cache;
 mod storage_cache;
 mod utils;
+#[cfg(feature = "parity-db")]
+pub mod parity_db;
 
 use std::sync::Arc;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use std::io;
 use std::collections::HashMap;
 
@@ -47,7 +50,7 @@ use hash_db::Hasher;
 use kvdb::{KeyValueDB, DBTransaction};
 use trie::{MemoryDB, PrefixedMemoryDB, prefixed_key};
 use parking_lot::{Mutex, RwLock};
-use primitives::{H256, Blake2Hasher, ChangesTrieConfiguration, convert_hash};
+use primitives::{H256, Blake2Hasher, ChangesTrieConfiguration, convert_hash, traits::KeyStorePtr};
 use primitives::storage::well_known_keys;
 use runtime_primitives::{
 	generic::{BlockId, DigestItem}, Justification, StorageOverlay, ChildrenStorageOverlay,
@@ -64,7 +67,7 @@ use client::leaves::{LeafSet, FinalizationDisplaced};
 use client::children;
 use state_db::StateDb;
 use consensus_common::well_known_cache_keys;
-use crate::storage_cache::{CachingState, SharedCache, new_shared_cache};
+use crate::storage_cache::{CachingState, SharedCache, NodeCache, new_shared_cache};
 use log::{trace, debug, warn};
 pub use state_db::PruningMode;
 
@@ -76,6 +79,10 @@ const MIN_BLOCKS_TO_KEEP_CHANGES_TRIES_FOR: u32 = 32768;
 
 /// Default value for storage cache child ratio.
 const DEFAULT_CHILD_RATIO: (usize, usize) = (1, 10);
+/// The trie node cache is sized to this fraction of the state cache size.
+const NODE_CACHE_RATIO: usize = 4;
+/// How long a measured database size is reported before the database is measured again.
+const DATABASE_SIZE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
 
 /// DB-backed patricia trie state, transaction type is an overlay of changes to commit.
 pub type DbState = state_machine::TrieBackend<Arc<dyn state_machine::Storage<Blake2Hasher>>, Blake2Hasher>;
@@ -136,6 +143,10 @@ impl<B: BlockT> StateBackend<Blake2Hasher> for RefTrackingState<B> {
 		self.state.exists_child_storage(storage_key, key)
 	}
 
+	fn next_storage_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.state.next_storage_key(key)
+	}
+
 	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], f: F) {
 		self.state.for_keys_with_prefix(prefix, f)
 	}
@@ -175,6 +186,21 @@ impl<B: BlockT> StateBackend<Blake2Hasher> for RefTrackingState<B> {
 	}
 }
 
+/// Key-value database implementation storing the client data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseBackend {
+	/// RocksDb.
+	RocksDb,
+	/// ParityDb. Requires the `parity-db` feature.
+	ParityDb,
+}
+
+impl Default for DatabaseBackend {
+	fn default() -> Self {
+		DatabaseBackend::RocksDb
+	}
+}
+
 /// Database settings.
 pub struct DatabaseSettings {
 	/// Cache size in bytes. If `None` default is used.
@@ -185,8 +211,13 @@ pub struct DatabaseSettings {
 	pub state_cache_child_ratio: Option<(usize, usize)>,
 	/// Path to the database.
 	pub path: PathBuf,
+	/// Database implementation.
+	pub backend: DatabaseBackend,
 	/// Pruning mode.
 	pub pruning: PruningMode,
+	/// Number of finalized blocks for which bodies and justifications are kept. `None` keeps
+	/// all of them.
+	pub blocks_pruning: Option<u32>,
 }
 
 /// Create an instance of db-backed client.
@@ -195,6 +226,7 @@ pub fn new_client<E, S, Block, RA>(
 	executor: E,
 	genesis_storage: S,
 	execution_strategies: ExecutionStrategies,
+	keystore: Option<KeyStorePtr>,
 ) -> Result<
 	client::Client<Backend<Block>,
 	client::LocalCallExecutor<Backend<Block>, E>, Block, RA>, client::error::Error
@@ -205,7 +237,7 @@ pub fn new_client<E, S, Block, RA>(
 		S: BuildStorage,
 {
 	let backend = Arc::new(Backend::new(settings, CANONICALIZATION_DELAY)?);
-	let executor = client::LocalCallExecutor::new(backend.clone(), executor);
+	let executor = client::LocalCallExecutor::new(backend.clone(), executor, keystore);
 	Ok(client::Client::new(backend, executor, genesis_storage, execution_strategies)?)
 }
 
@@ -504,13 +536,21 @@ where Block: BlockT<Hash=H256>,
 struct StorageDb<Block: BlockT> {
 	pub db: Arc<dyn KeyValueDB>,
 	pub state_db: StateDb<Block::Hash, Vec<u8>>,
+	pub node_cache: NodeCache,
 }
 
 impl<Block: BlockT> state_machine::Storage<Blake2Hasher> for StorageDb<Block> {
 	fn get(&self, key: &H256, prefix: &[u8]) -> Result<Option<DBValue>, String> {
 		let key = prefixed_key::<Blake2Hasher>(key, prefix);
-		self.state_db.get(&key, self).map(|r| r.map(|v| DBValue::from_slice(&v)))
-			.map_err(|e| format!("Database backend error: {:?}", e))
+		if let Some(node) = self.node_cache.get(&key) {
+			return Ok(Some(DBValue::from_slice(&node)));
+		}
+		let node = self.state_db.get(&key, self)
+			.map_err(|e| format!("Database backend error: {:?}", e))?;
+		if let Some(ref node) = node {
+			self.node_cache.insert(key, node.clone());
+		}
+		Ok(node.map(|v| DBValue::from_slice(&v)))
 	}
 }
 
@@ -692,6 +732,12 @@ pub struct Backend<Block: BlockT> {
 	changes_trie_config: Mutex<Option<Option<ChangesTrieConfiguration>>>,
 	blockchain: BlockchainDb<Block>,
 	canonicalization_delay: u64,
+	blocks_pruning: Option<u32>,
+	state_pruning: String,
+	/// Path to the database, `None` if it isn't stored on disk.
+	database_path: Option<PathBuf>,
+	/// The database size and when it was last measured.
+	database_size: Mutex<Option<(Instant, Option<u64>)>>,
 	shared_cache: SharedCache<Block, Blake2Hasher>,
 	import_lock: Mutex<()>,
 }
@@ -705,11 +751,11 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 	}
 
 	fn new_inner(config: DatabaseSettings, canonicalization_delay: u64) -> Result<Self, client::error::Error> {
-		#[cfg(feature = "kvdb-rocksdb")]
-		let db = crate::utils::open_database(&config, columns::META, "full")?;
-		#[cfg(not(feature = "kvdb-rocksdb"))]
+		#[cfg(any(feature = "kvdb-rocksdb", feature = "parity-db"))]
+		let db = crate::utils::open_database(&config, columns::META, "full", &[columns::STATE])?;
+		#[cfg(not(any(feature = "kvdb-rocksdb", feature = "parity-db")))]
 		let db = {
-			log::warn!("Running without the RocksDB feature. The database will NOT be saved.");
+			log::warn!("Running without a database feature. The database will NOT be saved.");
 			Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))
 		};
 		Self::from_kvdb(db as Arc<_>, canonicalization_delay, &config)
@@ -731,7 +777,9 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
 			path: Default::default(),
+			backend: Default::default(),
 			pruning: PruningMode::keep_blocks(keep_blocks),
+			blocks_pruning: None,
 		};
 		Self::from_kvdb(
 			db,
@@ -749,10 +797,15 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 		let blockchain = BlockchainDb::new(db.clone())?;
 		let meta = blockchain.meta.clone();
 		let map_e = |e: state_db::Error<io::Error>| ::client::error::Error::from(format!("State database error: {:?}", e));
-		let state_db: StateDb<_, _> = StateDb::new(config.pruning.clone(), &StateMetaDb(&*db)).map_err(map_e)?;
+		let (state_db_init_commit, state_db): (_, StateDb<_, _>) =
+			StateDb::new(config.pruning.clone(), &StateMetaDb(&*db)).map_err(map_e)?;
+		let mut transaction = DBTransaction::new();
+		apply_state_commit(&mut transaction, state_db_init_commit);
+		db.write(transaction).map_err(db_err)?;
 		let storage_db = StorageDb {
 			db: db.clone(),
 			state_db,
+			node_cache: NodeCache::new(config.state_cache_size / NODE_CACHE_RATIO),
 		};
 		let offchain_storage = offchain::LocalStorage::new(db.clone());
 		let changes_tries_storage = DbChangesTrieStorage {
@@ -769,6 +822,16 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			changes_trie_config: Mutex::new(None),
 			blockchain,
 			canonicalization_delay,
+			blocks_pruning: config.blocks_pruning,
+			state_pruning: describe_pruning(&config.pruning),
+			database_path: if cfg!(any(feature = "kvdb-rocksdb", feature = "parity-db"))
+				&& !config.path.as_os_str().is_empty()
+			{
+				Some(config.path.clone())
+			} else {
+				None
+			},
+			database_size: Mutex::new(None),
 			shared_cache: new_shared_cache(
 				config.state_cache_size,
 				config.state_cache_child_ratio.unwrap_or(DEFAULT_CHILD_RATIO),
@@ -999,9 +1062,11 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 		let mut last_finalized_hash = self.blockchain.meta.read().finalized_hash;
 
 		if !operation.finalized_blocks.is_empty() {
+			let mut last_finalized_number = Zero::zero();
 			for (block, justification) in operation.finalized_blocks {
 				let block_hash = self.blockchain.expect_block_hash_from_id(&block)?;
 				let block_header = self.blockchain.expect_header(BlockId::Hash(block_hash))?;
+				last_finalized_number = *block_header.number();
 
 				meta_updates.push(self.finalize_block_with_transaction(
 					&mut transaction,
@@ -1013,6 +1078,12 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 				)?);
 				last_finalized_hash = block_hash;
 			}
+
+			self.displace_stale_leaves(
+				last_finalized_hash,
+				last_finalized_number,
+				&mut finalization_displaced_leaves,
+			)?;
 		}
 
 		let imported = if let Some(pending_block) = operation.pending_block {
@@ -1082,6 +1153,7 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 					hash,
 					&mut finalization_displaced_leaves,
 				)?;
+				self.displace_stale_leaves(hash, number, &mut finalization_displaced_leaves)?;
 			} else {
 				// canonicalize blocks which are old enough, regardless of finality.
 				self.force_delayed_canonicalize(&mut transaction, hash, *header.number())?
@@ -1190,6 +1262,10 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			}
 		}
 
+		if let Some(keep) = self.blocks_pruning {
+			self.prune_block_data(transaction, f_num, keep)?;
+		}
+
 		let new_displaced = self.blockchain.leaves.write().finalize_height(f_num);
 		match displaced {
 			x @ &mut None => *x = Some(new_displaced),
@@ -1198,6 +1274,78 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 
 		Ok(())
 	}
+
+	// displace the leaves which do not descend from the last block finalized by an
+	// operation, as they can never become canonical. each leaf is walked back until it
+	// reaches the finalized height or a block seen from another leaf, so every block
+	// above the finalized one is visited at most once.
+	fn displace_stale_leaves(
+		&self,
+		f_hash: Block::Hash,
+		f_num: NumberFor<Block>,
+		displaced: &mut Option<FinalizationDisplaced<Block::Hash, NumberFor<Block>>>
+	) -> Result<(), client::error::Error> {
+		let mut descends = HashMap::new();
+		let new_displaced = self.blockchain.leaves.write().finalize_block(f_num, |leaf_hash, leaf_number| {
+			let mut branch = Vec::new();
+			let mut hash = *leaf_hash;
+			let mut number = *leaf_number;
+			let is_descendent = loop {
+				if number == f_num {
+					break hash == f_hash;
+				}
+				if let Some(is_descendent) = descends.get(&hash) {
+					break *is_descendent;
+				}
+				branch.push(hash);
+				hash = *self.blockchain.expect_header(BlockId::Hash(hash))?.parent_hash();
+				number -= One::one();
+			};
+			descends.extend(branch.into_iter().map(|hash| (hash, is_descendent)));
+			Ok(is_descendent)
+		})?;
+		match displaced {
+			x @ &mut None => *x = Some(new_displaced),
+			&mut Some(ref mut displaced) => displaced.merge(new_displaced),
+		}
+
+		Ok(())
+	}
+}
+
+impl<Block: BlockT<Hash=H256>> Backend<Block> {
+	// discards the body and the justification of the finalized block that has just left the
+	// blocks pruning window. headers are always kept. justifications of blocks carrying a
+	// consensus digest are kept since they are needed to prove authority set hand-offs.
+	fn prune_block_data(
+		&self,
+		transaction: &mut DBTransaction,
+		finalized: NumberFor<Block>,
+		keep: u32,
+	) -> Result<(), client::error::Error> {
+		let finalized = finalized.saturated_into::<u64>();
+		if finalized <= u64::from(keep) {
+			return Ok(())
+		}
+
+		let number: NumberFor<Block> = (finalized - u64::from(keep)).saturated_into();
+		let id = BlockId::<Block>::Number(number);
+		let lookup_key = match block_id_to_lookup_key::<Block>(&*self.storage.db, columns::KEY_LOOKUP, id)? {
+			Some(lookup_key) => lookup_key,
+			None => return Ok(()),
+		};
+
+		trace!(target: "db", "Pruning body of block #{}", number);
+		transaction.delete(columns::BODY, &lookup_key);
+
+		let signals_change = self.blockchain.header(id)?
+			.map_or(false, |header| header.digest().logs().iter().any(|log| log.as_consensus().is_some()));
+		if !signals_change {
+			transaction.delete(columns::JUSTIFICATION, &lookup_key);
+		}
+
+		Ok(())
+	}
 }
 
 fn apply_state_commit(transaction: &mut DBTransaction, commit: state_db::CommitSet<Vec<u8>>) {
@@ -1215,6 +1363,21 @@ fn apply_state_commit(transaction: &mut DBTransaction, commit: state_db::CommitS
 	}
 }
 
+/// Human readable description of the state pruning mode.
+fn describe_pruning(mode: &PruningMode) -> String {
+	match mode {
+		PruningMode::Constrained(constraints) => match (constraints.max_blocks, constraints.max_mem) {
+			(Some(blocks), None) => format!("{} blocks", blocks),
+			(Some(blocks), Some(mem)) => format!("up to {} blocks and {} bytes", blocks, mem),
+			(None, Some(mem)) => format!("up to {} bytes", mem),
+			// no window: only the last canonicalized state is kept.
+			(None, None) => "latest canonical only".into(),
+		},
+		PruningMode::ArchiveAll => "archive".into(),
+		PruningMode::ArchiveCanonical => "archive-canonical".into(),
+	}
+}
+
 impl<Block> client::backend::AuxStore for Backend<Block> where Block: BlockT<Hash=H256> {
 	fn insert_aux<
 		'a,
@@ -1297,6 +1460,7 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 				justification,
 				displaced,
 			)?;
+			self.displace_stale_leaves(hash, number, displaced)?;
 			self.storage.db.write(transaction).map_err(db_err)?;
 			self.blockchain.update_meta(hash, number, is_best, is_finalized);
 			Ok(())
@@ -1346,8 +1510,26 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 							format!("Error reverting to {}. Block hash not found.", best)))?;
 					let key = utils::number_and_hash_to_lookup_key(best.clone(), &hash)?;
 					transaction.put(columns::META, meta_keys::BEST_BLOCK, &key);
-					transaction.delete(columns::KEY_LOOKUP, removed.hash().as_ref());
-					children::remove_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, hash);
+					utils::remove_key_mappings(
+						&mut transaction,
+						columns::KEY_LOOKUP,
+						removed.number().clone(),
+						removed.hash(),
+					)?;
+
+					// the parent may have other children on competing forks.
+					let mut siblings: Vec<Block::Hash> = children::read_children(
+						&*self.storage.db,
+						columns::META,
+						meta_keys::CHILDREN_PREFIX,
+						hash,
+					)?;
+					siblings.retain(|child| *child != removed.hash());
+					if siblings.is_empty() {
+						children::remove_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, hash);
+					} else {
+						children::write_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, hash, siblings);
+					}
 					self.storage.db.write(transaction).map_err(db_err)?;
 					self.blockchain.update_meta(hash, best, true, false);
 					self.blockchain.leaves.write().revert(removed.hash().clone(), removed.number().clone(), removed.parent_hash().clone());
@@ -1364,7 +1546,29 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 
 	fn used_state_cache_size(&self) -> Option<usize> {
 		let used = (*&self.shared_cache).lock().used_storage_cache_size();
-		Some(used)
+		Some(used + self.storage.node_cache.used_size())
+	}
+
+	fn usage_info(&self) -> Option<client::backend::UsageInfo> {
+		let database_size = self.database_path.as_ref().and_then(|path| {
+			let mut cached = self.database_size.lock();
+			match *cached {
+				Some((measured, size)) if measured.elapsed() < DATABASE_SIZE_REFRESH_INTERVAL => size,
+				_ => {
+					// walking the database directory is too expensive to do on every call.
+					let size = crate::utils::directory_size(path).ok();
+					*cached = Some((Instant::now(), size));
+					size
+				}
+			}
+		});
+
+		Some(client::backend::UsageInfo {
+			state_cache_size: self.used_state_cache_size().unwrap_or(0),
+			database_size,
+			state_pruning: self.state_pruning.clone(),
+			blocks_pruning: self.blocks_pruning,
+		})
 	}
 
 	fn state_at(&self, block: BlockId<Block>) -> Result<Self::State, client::error::Error> {
@@ -2106,8 +2310,41 @@ mod tests {
 		backend.finalize_block(BlockId::hash(block1_a), None).unwrap();
 		backend.finalize_block(BlockId::hash(block2_a), None).unwrap();
 
-		// leaves at same height stay. Leaves at lower heights pruned.
-		assert_eq!(backend.blockchain().leaves().unwrap(), vec![block2_a, block2_b, block2_c]);
+		// leaves not descending from the finalized block are pruned.
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![block2_a]);
+	}
+
+	#[test]
+	fn test_stale_leaves_pruned_when_finalizing_multiple_blocks() {
+		let backend: Backend<Block> = Backend::new_test(10, 10);
+		let block0 = insert_header(&backend, 0, Default::default(), Default::default(), Default::default());
+
+		let block1_a = insert_header(&backend, 1, block0, Default::default(), Default::default());
+		let block1_b = insert_header(&backend, 1, block0, Default::default(), [1; 32].into());
+
+		let block2_a = insert_header(&backend, 2, block1_a, Default::default(), Default::default());
+		let block2_b = insert_header(&backend, 2, block1_b, Default::default(), Default::default());
+		let block2_c = insert_header(&backend, 2, block1_b, Default::default(), [1; 32].into());
+
+		let block3_a = insert_header(&backend, 3, block2_a, Default::default(), Default::default());
+		let block3_b = insert_header(&backend, 3, block2_a, Default::default(), [1; 32].into());
+
+		assert_eq!(
+			backend.blockchain().leaves().unwrap(),
+			vec![block3_a, block3_b, block2_b, block2_c],
+		);
+
+		{
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(block0)).unwrap();
+			op.mark_finalized(BlockId::Hash(block1_a), None).unwrap();
+			op.mark_finalized(BlockId::Hash(block2_a), None).unwrap();
+			backend.commit_operation(op).unwrap();
+		}
+
+		// both leaves forking off below the finalized blocks are pruned, the
+		// descendants of the last finalized block stay.
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![block3_a, block3_b]);
 	}
 
 	#[test]
@@ -2120,6 +2357,28 @@ mod tests {
 		assert!(backend.get_aux(b"test").unwrap().is_none());
 	}
 
+	#[test]
+	fn revert_keeps_siblings_of_reverted_blocks() {
+		use client::blockchain::{Backend as BlockChainBackend, HeaderBackend};
+
+		let backend = Backend::<Block>::new_test(10, 10);
+
+		let block0 = insert_header(&backend, 0, Default::default(), Default::default(), Default::default());
+		let block1a = insert_header(&backend, 1, block0, Default::default(), Default::default());
+		let block1b = insert_header(&backend, 1, block0, Default::default(), [1; 32].into());
+		let block2 = insert_header(&backend, 2, block1b, Default::default(), Default::default());
+
+		assert_eq!(backend.revert(2).unwrap(), 2);
+
+		let blockchain = backend.blockchain();
+		assert_eq!(blockchain.info().best_hash, block0);
+		assert_eq!(blockchain.hash(1).unwrap(), None);
+		assert_eq!(blockchain.header(BlockId::Hash(block2)).unwrap(), None);
+		assert!(blockchain.header(BlockId::Hash(block1a)).unwrap().is_some());
+		assert_eq!(blockchain.children(block0).unwrap(), vec![block1a]);
+		assert!(blockchain.children(block1b).unwrap().is_empty());
+	}
+
 	#[test]
 	fn test_finalize_block_with_justification() {
 		use client::blockchain::{Backend as BlockChainBackend};
@@ -2154,6 +2413,60 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn prunes_block_bodies_and_justifications() {
+		use client::blockchain::Backend as BlockChainBackend;
+
+		let backend = {
+			let db = Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+			let settings = DatabaseSettings {
+				cache_size: None,
+				state_cache_size: 16777216,
+				state_cache_child_ratio: Some((50, 100)),
+				path: Default::default(),
+				backend: Default::default(),
+				pruning: PruningMode::keep_blocks(10),
+				blocks_pruning: Some(2),
+			};
+			Backend::<Block>::from_kvdb(db as Arc<_>, 10, &settings).unwrap()
+		};
+
+		let mut parent_hash = Default::default();
+		for number in 0..6u64 {
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: Default::default(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			let block_id = if number == 0 {
+				BlockId::Hash(Default::default())
+			} else {
+				BlockId::Number(number - 1)
+			};
+			parent_hash = header.hash();
+
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, block_id).unwrap();
+			op.set_block_data(header, Some(vec![number.into()]), None, NewBlockState::Best).unwrap();
+			backend.commit_operation(op).unwrap();
+			if number != 0 {
+				backend.finalize_block(BlockId::Hash(parent_hash), Some(vec![number as u8])).unwrap();
+			}
+		}
+
+		let blockchain = backend.blockchain();
+		for number in 0..6u64 {
+			let id = BlockId::Number(number);
+			assert!(blockchain.header(id).unwrap().is_some());
+			// genesis is finalized at import without any justification.
+			let expected_kept = number >= 4 || number == 0;
+			assert_eq!(blockchain.body(id).unwrap().is_some(), expected_kept);
+			assert_eq!(blockchain.justification(id).unwrap().is_some(), number >= 4);
+		}
+	}
+
 	#[test]
 	fn test_finalize_non_sequential() {
 		let backend = Backend::<Block>::new_test(10, 10);
@@ -2168,4 +2481,30 @@ mod tests {
 			backend.commit_operation(op).unwrap_err();
 		}
 	}
+
+	#[test]
+	fn usage_info_reports_pruning_modes() {
+		let backend = Backend::<Block>::new_test(256, 10);
+		let usage = backend.usage_info().unwrap();
+		assert_eq!(usage.state_pruning, "256 blocks");
+		assert_eq!(usage.blocks_pruning, None);
+		// the test database is kept in memory.
+		assert_eq!(usage.database_size, None);
+	}
+
+	#[test]
+	fn pruning_description_covers_unbounded_constraints() {
+		use state_db::Constraints;
+
+		assert_eq!(describe_pruning(&PruningMode::keep_blocks(256)), "256 blocks");
+		assert_eq!(
+			describe_pruning(&PruningMode::Constrained(Constraints { max_blocks: None, max_mem: None })),
+			"latest canonical only",
+		);
+		assert_eq!(
+			describe_pruning(&PruningMode::Constrained(Constraints { max_blocks: None, max_mem: Some(1024) })),
+			"up to 1024 bytes",
+		);
+		assert_eq!(describe_pruning(&PruningMode::ArchiveAll), "archive");
+	}
 }
@@ -26,6 +26,7 @@ use client::backend::{AuxStore, NewBlockState};
 use client::blockchain::{BlockStatus, Cache as BlockchainCache,
 	HeaderBackend as BlockchainHeaderBackend, Info as BlockchainInfo};
 use client::cht;
+use client::children;
 use client::leaves::{LeafSet, FinalizationDisplaced};
 use client::error::{Error as ClientError, Result as ClientResult};
 use client::light::blockchain::Storage as LightBlockchainStorage;
@@ -71,15 +72,15 @@ impl<Block> LightStorage<Block>
 		Self::new_inner(config)
 	}
 
-	#[cfg(feature = "kvdb-rocksdb")]
+	#[cfg(any(feature = "kvdb-rocksdb", feature = "parity-db"))]
 	fn new_inner(config: DatabaseSettings) -> ClientResult<Self> {
-		let db = crate::utils::open_database(&config, columns::META, "light")?;
+		let db = crate::utils::open_database(&config, columns::META, "light", &[])?;
 		Self::from_kvdb(db as Arc<_>)
 	}
 
-	#[cfg(not(feature = "kvdb-rocksdb"))]
+	#[cfg(not(any(feature = "kvdb-rocksdb", feature = "parity-db")))]
 	fn new_inner(_config: DatabaseSettings) -> ClientResult<Self> {
-		log::warn!("Running without the RocksDB feature. The database will NOT be saved.");
+		log::warn!("Running without a database feature. The database will NOT be saved.");
 		let db = Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
 		Self::from_kvdb(db as Arc<_>)
 	}
@@ -449,6 +450,12 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 			)?;
 		}
 
+		if !is_genesis {
+			let mut children = children::read_children(&*self.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)?;
+			children.push(hash);
+			children::write_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash, children);
+		}
+
 		{
 			let mut leaves = self.leaves.write();
 			let displaced_leaf = leaves.import(hash, number, parent_hash);
@@ -559,6 +566,14 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 	fn cache(&self) -> Option<Arc<dyn BlockchainCache<Block>>> {
 		Some(self.cache.clone())
 	}
+
+	fn leaves(&self) -> ClientResult<Vec<Block::Hash>> {
+		Ok(self.leaves.read().hashes())
+	}
+
+	fn children(&self, parent_hash: Block::Hash) -> ClientResult<Vec<Block::Hash>> {
+		children::read_children(&*self.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)
+	}
 }
 
 /// Build the key for inserting header-CHT at given block.
@@ -1089,6 +1104,20 @@ pub(crate) mod tests {
 		assert_eq!(db.leaves.read().hashes(), vec![block2_a, block2_b, block2_c]);
 	}
 
+	#[test]
+	fn leaves_and_children_are_reported() {
+		let db = LightStorage::<Block>::new_test();
+		let block0 = insert_block(&db, HashMap::new(), || default_header(&Default::default(), 0));
+		let block1_a = insert_block(&db, HashMap::new(), || default_header(&block0, 1));
+		let block1_b = insert_block(&db, HashMap::new(), || header_with_extrinsics_root(&block0, 1, [1; 32].into()));
+		let block2_a = insert_block(&db, HashMap::new(), || default_header(&block1_a, 2));
+
+		assert_eq!(LightBlockchainStorage::leaves(&db).unwrap(), vec![block2_a, block1_b]);
+		assert_eq!(db.children(block0).unwrap(), vec![block1_a, block1_b]);
+		assert_eq!(db.children(block1_a).unwrap(), vec![block2_a]);
+		assert!(db.children(block2_a).unwrap().is_empty());
+	}
+
 	#[test]
 	fn cache_can_be_initialized_after_genesis_inserted() {
 		let db = LightStorage::<Block>::new_test();
@@ -0,0 +1,287 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! ParityDb-based implementation of the `KeyValueDB` interface used by the client database.
+//!
+//! ParityDb has no notion of a default column, so the `None` column of `kvdb` maps to column 0
+//! and `Some(n)` maps to column `n`. Columns that need to be iterated are opened with an ordered
+//! index; all the other columns use the faster hash index and can only be queried by key.
+//!
+//! An existing RocksDb database can be converted with [`migrate_rocksdb`], which the CLI exposes
+//! as the `migrate-db` subcommand.
+
+use std::{io, path::Path};
+
+use kvdb::{KeyValueDB, DBTransaction, DBOp, DBValue};
+use log::warn;
+use parity_db::{Db, Options};
+
+fn col_index(col: Option<u32>) -> u8 {
+	col.map(|c| c as u8).unwrap_or(0)
+}
+
+fn io_err(err: parity_db::Error) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, format!("{:?}", err))
+}
+
+/// A `KeyValueDB` backed by ParityDb.
+pub struct DbAdapter {
+	db: Db,
+	ordered: Vec<u8>,
+}
+
+impl DbAdapter {
+	/// Opens or creates a database at `path` with `num_columns` columns.
+	///
+	/// All columns except the ones listed in `hashed` are ordered and support iteration.
+	pub fn open(path: &Path, num_columns: u32, hashed: &[Option<u32>]) -> io::Result<Self> {
+		let mut options = Options::with_columns(path, num_columns as u8);
+		let hashed = hashed.iter().map(|c| col_index(*c)).collect::<Vec<_>>();
+		let mut ordered = Vec::new();
+		for (index, column) in options.columns.iter_mut().enumerate() {
+			let index = index as u8;
+			if !hashed.contains(&index) {
+				column.btree_index = true;
+				ordered.push(index);
+			}
+		}
+		let db = Db::open_or_create(&options).map_err(io_err)?;
+		Ok(DbAdapter { db, ordered })
+	}
+
+	fn ordered_iter<'a>(
+		&'a self,
+		col: Option<u32>,
+		prefix: &'a [u8],
+	) -> Box<dyn Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		let col = col_index(col);
+		if !self.ordered.contains(&col) {
+			warn!(target: "db", "Attempted to iterate over hashed ParityDb column {}", col);
+			return Box::new(std::iter::empty());
+		}
+
+		let mut iter = match self.db.iter(col) {
+			Ok(iter) => iter,
+			Err(e) => {
+				warn!(target: "db", "Error opening ParityDb iterator: {:?}", e);
+				return Box::new(std::iter::empty());
+			},
+		};
+		if let Err(e) = iter.seek(prefix) {
+			warn!(target: "db", "Error seeking ParityDb iterator: {:?}", e);
+			return Box::new(std::iter::empty());
+		}
+
+		Box::new(std::iter::from_fn(move || match iter.next() {
+			Ok(next) => next,
+			Err(e) => {
+				warn!(target: "db", "Error iterating ParityDb column: {:?}", e);
+				None
+			},
+		})
+			.take_while(move |(key, _)| key.starts_with(prefix))
+			.map(|(key, value)| (key.into_boxed_slice(), value.into_boxed_slice())))
+	}
+}
+
+impl KeyValueDB for DbAdapter {
+	fn get(&self, col: Option<u32>, key: &[u8]) -> io::Result<Option<DBValue>> {
+		self.db.get(col_index(col), key)
+			.map(|value| value.map(|v| DBValue::from_slice(&v)))
+			.map_err(io_err)
+	}
+
+	fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
+		self.ordered_iter(col, prefix).next().map(|(_, value)| value)
+	}
+
+	fn write_buffered(&self, transaction: DBTransaction) {
+		if let Err(e) = self.write(transaction) {
+			warn!(target: "db", "Error writing ParityDb transaction: {:?}", e);
+		}
+	}
+
+	fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+		self.db.commit(transaction.ops.into_iter().map(|op| match op {
+			DBOp::Insert { col, key, value } => (col_index(col), key.to_vec(), Some(value.into_vec())),
+			DBOp::Delete { col, key } => (col_index(col), key.to_vec(), None),
+		})).map_err(io_err)
+	}
+
+	fn flush(&self) -> io::Result<()> {
+		// every `write` is committed to the log straight away.
+		Ok(())
+	}
+
+	fn iter<'a>(&'a self, col: Option<u32>) -> Box<dyn Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.ordered_iter(col, &[])
+	}
+
+	fn iter_from_prefix<'a>(
+		&'a self,
+		col: Option<u32>,
+		prefix: &'a [u8],
+	) -> Box<dyn Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.ordered_iter(col, prefix)
+	}
+
+	fn restore(&self, _new_db: &str) -> io::Result<()> {
+		Err(io::Error::new(io::ErrorKind::Other, "Restore is not supported by ParityDb"))
+	}
+}
+
+/// Copies every entry of the `num_columns` columns of `from` into `to`.
+///
+/// Used to migrate an existing RocksDb database to ParityDb. Columns which can't be iterated in
+/// the source database are skipped. Returns the number of copied entries.
+pub fn migrate(from: &dyn KeyValueDB, to: &dyn KeyValueDB, num_columns: u32) -> io::Result<u64> {
+	const BATCH_SIZE: usize = 4096;
+
+	let mut copied = 0u64;
+	for col in 0..num_columns {
+		let col = Some(col);
+		let mut transaction = DBTransaction::new();
+		for (key, value) in from.iter(col) {
+			transaction.put_vec(col, &key, value.into_vec());
+			copied += 1;
+			if transaction.ops.len() >= BATCH_SIZE {
+				to.write(std::mem::replace(&mut transaction, DBTransaction::new()))?;
+			}
+		}
+		to.write(transaction)?;
+	}
+	Ok(copied)
+}
+
+/// Migrates the RocksDb database at `path` to ParityDb.
+///
+/// The entries are copied into a new ParityDb database next to `path` first, so that an
+/// interrupted migration leaves the original database untouched. Once the copy is complete the
+/// RocksDb database is moved to `<path>.rocksdb` and the ParityDb database takes its place.
+/// Returns the number of copied entries.
+#[cfg(feature = "kvdb-rocksdb")]
+pub fn migrate_rocksdb(path: &Path) -> io::Result<u64> {
+	use kvdb_rocksdb::{Database, DatabaseConfig};
+	use crate::utils::{NUM_COLUMNS, COLUMN_META, meta_keys};
+
+	let path_str = path.to_str()
+		.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid database path"))?;
+	let target = path.with_extension("paritydb");
+	let backup = path.with_extension("rocksdb");
+	if target.exists() || backup.exists() {
+		return Err(io::Error::new(
+			io::ErrorKind::AlreadyExists,
+			format!("{:?} or {:?} already exists", target, backup),
+		));
+	}
+
+	let copied = {
+		let source = Database::open(&DatabaseConfig::with_columns(Some(NUM_COLUMNS)), path_str)?;
+		// only the state column of full databases is opened with a hash index.
+		let hashed = match source.get(COLUMN_META, meta_keys::TYPE)? {
+			Some(ref db_type) if &db_type[..] == b"full" => vec![crate::columns::STATE],
+			_ => Vec::new(),
+		};
+		let destination = DbAdapter::open(&target, NUM_COLUMNS, &hashed)?;
+		migrate(&source, &destination, NUM_COLUMNS)?
+	};
+
+	std::fs::rename(path, &backup)?;
+	std::fs::rename(&target, path)?;
+	Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn temp_path(name: &str) -> std::path::PathBuf {
+		let path = std::env::temp_dir().join(format!("substrate-parity-db-{}-{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		path
+	}
+
+	#[test]
+	fn reads_writes_and_iterates_ordered_columns() {
+		let path = temp_path("basic");
+		let db = DbAdapter::open(&path, 3, &[Some(1)]).unwrap();
+
+		let mut transaction = DBTransaction::new();
+		transaction.put(Some(0), b"leaf1", b"a");
+		transaction.put(Some(0), b"leaf2", b"b");
+		transaction.put(Some(0), b"other", b"c");
+		transaction.put(Some(1), b"hashed", b"d");
+		db.write(transaction).unwrap();
+
+		assert_eq!(db.get(Some(1), b"hashed").unwrap().unwrap().into_vec(), b"d".to_vec());
+		assert_eq!(db.iter_from_prefix(Some(0), b"leaf").count(), 2);
+		assert_eq!(db.iter(Some(0)).count(), 3);
+		assert_eq!(db.iter(Some(1)).count(), 0);
+
+		let mut transaction = DBTransaction::new();
+		transaction.delete(Some(0), b"leaf1");
+		db.write(transaction).unwrap();
+		assert!(db.get(Some(0), b"leaf1").unwrap().is_none());
+		assert_eq!(db.iter_from_prefix(Some(0), b"leaf").count(), 1);
+
+		let _ = std::fs::remove_dir_all(&path);
+	}
+
+	#[test]
+	fn migrates_from_another_database() {
+		let path = temp_path("migrate");
+		let source = kvdb_memorydb::create(2);
+		let mut transaction = DBTransaction::new();
+		transaction.put(Some(0), b"meta", b"1");
+		transaction.put(Some(1), b"header", b"2");
+		source.write(transaction).unwrap();
+
+		let target = DbAdapter::open(&path, 2, &[]).unwrap();
+		assert_eq!(migrate(&source, &target, 2).unwrap(), 2);
+		assert_eq!(target.get(Some(1), b"header").unwrap().unwrap().into_vec(), b"2".to_vec());
+
+		let _ = std::fs::remove_dir_all(&path);
+	}
+
+	#[cfg(feature = "kvdb-rocksdb")]
+	#[test]
+	fn replaces_rocksdb_database_with_migrated_one() {
+		use crate::utils::{NUM_COLUMNS, COLUMN_META, meta_keys};
+
+		let path = temp_path("rocksdb");
+		let backup = path.with_extension("rocksdb");
+		let _ = std::fs::remove_dir_all(&backup);
+		{
+			let config = kvdb_rocksdb::DatabaseConfig::with_columns(Some(NUM_COLUMNS));
+			let source = kvdb_rocksdb::Database::open(&config, path.to_str().unwrap()).unwrap();
+			let mut transaction = DBTransaction::new();
+			transaction.put(COLUMN_META, meta_keys::TYPE, b"full");
+			transaction.put(crate::columns::STATE, b"node", b"1");
+			source.write(transaction).unwrap();
+		}
+
+		assert_eq!(migrate_rocksdb(&path).unwrap(), 2);
+		assert!(backup.exists());
+		assert!(migrate_rocksdb(&path).is_err());
+
+		let db = DbAdapter::open(&path, NUM_COLUMNS, &[crate::columns::STATE]).unwrap();
+		assert_eq!(db.get(COLUMN_META, meta_keys::TYPE).unwrap().unwrap().into_vec(), b"full".to_vec());
+		assert_eq!(db.get(crate::columns::STATE, b"node").unwrap().unwrap().into_vec(), b"1".to_vec());
+
+		let _ = std::fs::remove_dir_all(&path);
+		let _ = std::fs::remove_dir_all(&backup);
+	}
+}
@@ -174,6 +174,34 @@ pub fn new_shared_cache<B: BlockT, H: Hasher>(
 	}))
 }
 
+/// Size-bounded cache of encoded trie nodes read from the state database.
+///
+/// Nodes are addressed by their hash, so unlike the key-value cache entries never become
+/// stale on reorgs and are shared by all forks.
+pub struct NodeCache(Mutex<LRUMap<Vec<u8>, Vec<u8>>>);
+
+impl NodeCache {
+	/// Create a new node cache with given max memory usage.
+	pub fn new(size: usize) -> Self {
+		NodeCache(Mutex::new(LRUMap(LinkedHashMap::new(), 0, size)))
+	}
+
+	/// Get a cached node.
+	pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.0.lock().get(key).cloned()
+	}
+
+	/// Cache a node.
+	pub fn insert(&self, key: Vec<u8>, node: Vec<u8>) {
+		self.0.lock().add(key, node)
+	}
+
+	/// Returns the used memory size of the cache in bytes.
+	pub fn used_size(&self) -> usize {
+		self.0.lock().used_size()
+	}
+}
+
 #[derive(Debug)]
 /// Accumulates a list of storage changed in a block.
 struct BlockChanges<B: Header> {
@@ -519,6 +547,10 @@ impl<H: Hasher, S: StateBackend<H>, B: BlockT> StateBackend<H> for CachingState<
 		self.state.exists_child_storage(storage_key, key)
 	}
 
+	fn next_storage_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.state.next_storage_key(key)
+	}
+
 	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], f: F) {
 		self.state.for_keys_with_prefix(prefix, f)
 	}
@@ -568,6 +600,23 @@ mod tests {
 	use primitives::Blake2Hasher;
 
 	type Block = RawBlock<ExtrinsicWrapper<u32>>;
+
+	#[test]
+	fn node_cache_evicts_least_recently_used() {
+		let cache = NodeCache::new(10);
+		cache.insert(vec![1], vec![1; 4]);
+		cache.insert(vec![2], vec![2; 4]);
+		assert_eq!(cache.used_size(), 10);
+
+		// refresh the first node, so that the second one is evicted
+		assert_eq!(cache.get(&[1]), Some(vec![1; 4]));
+		cache.insert(vec![3], vec![3; 4]);
+		assert_eq!(cache.get(&[2]), None);
+		assert_eq!(cache.get(&[1]), Some(vec![1; 4]));
+		assert_eq!(cache.get(&[3]), Some(vec![3; 4]));
+		assert_eq!(cache.used_size(), 10);
+	}
+
 	#[test]
 	fn smoke() {
 		//init_log();
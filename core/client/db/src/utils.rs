@@ -17,9 +17,9 @@
 //! Db-based backend utility structures and functions, used by both
 //! full and light storages.
 
-#[cfg(feature = "kvdb-rocksdb")]
+#[cfg(any(feature = "kvdb-rocksdb", feature = "parity-db"))]
 use std::sync::Arc;
-use std::{io, convert::TryInto};
+use std::{io, convert::TryInto, path::Path};
 
 use kvdb::{KeyValueDB, DBTransaction};
 #[cfg(feature = "kvdb-rocksdb")]
@@ -34,8 +34,8 @@ use runtime_primitives::traits::{
 	Block as BlockT, Header as HeaderT, Zero,
 	UniqueSaturatedFrom, UniqueSaturatedInto,
 };
-#[cfg(feature = "kvdb-rocksdb")]
-use crate::DatabaseSettings;
+#[cfg(any(feature = "kvdb-rocksdb", feature = "parity-db"))]
+use crate::{DatabaseSettings, DatabaseBackend};
 
 /// Number of columns in the db. Must be the same for both full && light dbs.
 /// Otherwise RocksDb will fail to open database && check its type.
@@ -205,17 +205,51 @@ pub fn db_err(err: io::Error) -> client::error::Error {
 	client::error::Error::Backend(format!("{}", err))
 }
 
-/// Open RocksDB database.
-#[cfg(feature = "kvdb-rocksdb")]
+/// Total size of the files in the directory at `path` and its subdirectories.
+pub fn directory_size(path: &Path) -> io::Result<u64> {
+	let mut size = 0;
+	for entry in std::fs::read_dir(path)? {
+		let entry = entry?;
+		let metadata = entry.metadata()?;
+		size += if metadata.is_dir() {
+			directory_size(&entry.path())?
+		} else {
+			metadata.len()
+		};
+	}
+	Ok(size)
+}
+
+/// Open the database configured in `config`.
+///
+/// `hashed_columns` lists columns which are never iterated over. Backends that distinguish
+/// between ordered and hashed storage may use a faster index for them.
+#[cfg(any(feature = "kvdb-rocksdb", feature = "parity-db"))]
 pub fn open_database(
 	config: &DatabaseSettings,
 	col_meta: Option<u32>,
-	db_type: &str
+	db_type: &str,
+	hashed_columns: &[Option<u32>],
 ) -> client::error::Result<Arc<dyn KeyValueDB>> {
-	let mut db_config = DatabaseConfig::with_columns(Some(NUM_COLUMNS));
-	db_config.memory_budget = config.cache_size;
-	let path = config.path.to_str().ok_or_else(|| client::error::Error::Backend("Invalid database path".into()))?;
-	let db = Database::open(&db_config, &path).map_err(db_err)?;
+	let db: Arc<dyn KeyValueDB> = match config.backend {
+		#[cfg(feature = "kvdb-rocksdb")]
+		DatabaseBackend::RocksDb => {
+			let _ = hashed_columns;
+			let mut db_config = DatabaseConfig::with_columns(Some(NUM_COLUMNS));
+			db_config.memory_budget = config.cache_size;
+			let path = config.path.to_str()
+				.ok_or_else(|| client::error::Error::Backend("Invalid database path".into()))?;
+			Arc::new(Database::open(&db_config, &path).map_err(db_err)?)
+		},
+		#[cfg(feature = "parity-db")]
+		DatabaseBackend::ParityDb => Arc::new(
+			crate::parity_db::DbAdapter::open(&config.path, NUM_COLUMNS, hashed_columns).map_err(db_err)?
+		),
+		#[allow(unreachable_patterns)]
+		backend => return Err(client::error::Error::Backend(
+			format!("Database backend {:?} is not supported by this build", backend)
+		)),
+	};
 
 	// check database type
 	match db.get(col_meta, meta_keys::TYPE).map_err(db_err)? {
@@ -232,7 +266,7 @@ pub fn open_database(
 		},
 	}
 
-	Ok(Arc::new(db))
+	Ok(db)
 }
 
 /// Read database column entry for the given block.
@@ -0,0 +1,181 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Pruning of auxiliary data on finality.
+//!
+//! Consensus engines keep per-block data in the aux store (epoch changes, authority sets,
+//! round states...). Most of it is only needed until the block is finalized or until its branch
+//! can no longer be finalized. Consumers register an `AuxDataPruner` with the client, which is
+//! asked for the aux changes to apply whenever blocks are finalized. The changes are written in
+//! the same operation as the finalization, so pruning is deterministic and survives restarts.
+
+use std::collections::{HashMap, HashSet};
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, NumberFor};
+use crate::blockchain::{self, BlockStatus, HeaderBackend, Info, RouteEntry};
+use crate::error;
+
+/// Blocks affected by a finalization.
+pub struct FinalizedBlocks<Block: BlockT> {
+	/// The block that was finalized before.
+	pub previous: RouteEntry<Block>,
+	/// The blocks that were finalized, in ascending order. The last one is the new finalized
+	/// block.
+	pub finalized: Vec<RouteEntry<Block>>,
+	/// Blocks on branches that forked off at or after the previously finalized block and are not
+	/// ancestors of the new finalized block. These can never be finalized.
+	pub displaced: Vec<RouteEntry<Block>>,
+	/// The leaves which do not descend from the new finalized block.
+	pub stale_heads: Vec<Block::Hash>,
+}
+
+/// A consumer of the aux store that cleans up after itself when blocks are finalized.
+pub trait AuxDataPruner<Block: BlockT>: Send + Sync {
+	/// Returns the aux changes to apply for the given finalization. Values of `None` delete the
+	/// key.
+	///
+	/// `headers` and `get_aux` read the chain and the aux store as they were before the
+	/// finalization.
+	fn prune(
+		&self,
+		blocks: &FinalizedBlocks<Block>,
+		headers: &dyn HeaderBackend<Block>,
+		get_aux: &dyn Fn(&[u8]) -> error::Result<Option<Vec<u8>>>,
+	) -> error::Result<Vec<(Vec<u8>, Option<Vec<u8>>)>>;
+}
+
+// where the branch of a block forks off the finalized chain.
+#[derive(Clone, Copy, PartialEq)]
+enum Fork {
+	// the block descends from the new finalized block.
+	Descendant,
+	// at or after the previously finalized block, so the branch is displaced now.
+	Displaced,
+	// below the previously finalized block, so the branch was displaced before.
+	Earlier,
+}
+
+/// Collect the blocks affected by finalizing `finalized`, given the previously finalized block.
+///
+/// Each leaf is walked back until it reaches the finalized chain or a block seen from another
+/// leaf, so every unfinalized block is visited at most once.
+pub(crate) fn finalized_blocks<Block: BlockT, B: blockchain::Backend<Block>>(
+	blockchain: &B,
+	previous: RouteEntry<Block>,
+	finalized: Vec<RouteEntry<Block>>,
+) -> error::Result<FinalizedBlocks<Block>> {
+	let new_finalized = match finalized.last() {
+		Some(entry) => entry.hash,
+		None => return Ok(FinalizedBlocks {
+			previous,
+			finalized,
+			displaced: Vec::new(),
+			stale_heads: Vec::new(),
+		}),
+	};
+
+	let finalized_hashes = finalized.iter().map(|entry| entry.hash).collect::<HashSet<_>>();
+	let mut forks = HashMap::new();
+	let mut displaced = Vec::new();
+	let mut stale_heads = Vec::new();
+	for leaf in blockchain.leaves()? {
+		let mut branch = Vec::new();
+		let mut hash = leaf;
+		let fork = loop {
+			if hash == new_finalized {
+				break Fork::Descendant;
+			}
+			if hash == previous.hash || finalized_hashes.contains(&hash) {
+				break Fork::Displaced;
+			}
+			if let Some(fork) = forks.get(&hash) {
+				break *fork;
+			}
+
+			let header = blockchain.expect_header(BlockId::Hash(hash))?;
+			if *header.number() <= previous.number {
+				break Fork::Earlier;
+			}
+			branch.push(RouteEntry { hash, number: *header.number() });
+			hash = *header.parent_hash();
+		};
+
+		if fork != Fork::Descendant {
+			stale_heads.push(leaf);
+		}
+		for entry in branch {
+			forks.insert(entry.hash, fork);
+			if fork == Fork::Displaced {
+				displaced.push(entry);
+			}
+		}
+	}
+
+	Ok(FinalizedBlocks { previous, finalized, displaced, stale_heads })
+}
+
+/// Headers of the chain, plus the header of a block that is being imported.
+///
+/// Used to run the pruners for a block imported as finalized, whose header is not in the
+/// backend yet.
+pub(crate) struct WithPendingHeader<'a, Block: BlockT> {
+	pub(crate) headers: &'a dyn HeaderBackend<Block>,
+	pub(crate) pending: &'a Block::Header,
+}
+
+impl<'a, Block: BlockT> WithPendingHeader<'a, Block> {
+	fn is_pending(&self, id: &BlockId<Block>) -> bool {
+		match *id {
+			BlockId::Hash(hash) => hash == self.pending.hash(),
+			BlockId::Number(number) => number == *self.pending.number(),
+		}
+	}
+}
+
+impl<'a, Block: BlockT> HeaderBackend<Block> for WithPendingHeader<'a, Block> {
+	fn header(&self, id: BlockId<Block>) -> error::Result<Option<Block::Header>> {
+		if self.is_pending(&id) {
+			return Ok(Some(self.pending.clone()));
+		}
+		self.headers.header(id)
+	}
+
+	fn info(&self) -> Info<Block> {
+		self.headers.info()
+	}
+
+	fn status(&self, id: BlockId<Block>) -> error::Result<BlockStatus> {
+		if self.is_pending(&id) {
+			return Ok(BlockStatus::InChain);
+		}
+		self.headers.status(id)
+	}
+
+	fn number(&self, hash: Block::Hash) -> error::Result<Option<NumberFor<Block>>> {
+		if hash == self.pending.hash() {
+			return Ok(Some(*self.pending.number()));
+		}
+		self.headers.number(hash)
+	}
+
+	fn hash(&self, number: NumberFor<Block>) -> error::Result<Option<Block::Hash>> {
+		if number == *self.pending.number() {
+			return Ok(Some(self.pending.hash()));
+		}
+		self.headers.hash(number)
+	}
+}
@@ -17,6 +17,7 @@
 //! Substrate Client data backend
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use crate::error;
 use primitives::ChangesTrieConfiguration;
 use runtime_primitives::{generic::BlockId, Justification, StorageOverlay, ChildrenStorageOverlay};
@@ -34,6 +35,19 @@ pub type StorageCollection = Vec<(Vec<u8>, Option<Vec<u8>>)>;
 /// In memory arrays of storage values for multiple child tries.
 pub type ChildStorageCollection = Vec<(Vec<u8>, StorageCollection)>;
 
+/// Resource usage of a backend.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsageInfo {
+	/// Memory used by the state cache, in bytes.
+	pub state_cache_size: usize,
+	/// Size of the database on disk, in bytes. `None` if it isn't stored on disk.
+	pub database_size: Option<u64>,
+	/// Description of the state pruning mode, e.g. `256 blocks` or `archive`.
+	pub state_pruning: String,
+	/// Number of finalized blocks for which bodies are kept. `None` keeps all of them.
+	pub blocks_pruning: Option<u32>,
+}
+
 /// State of a new block.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NewBlockState {
@@ -106,6 +120,11 @@ pub trait BlockImportOperation<Block, H> where
 }
 
 /// Provides access to an auxiliary database.
+///
+/// This is a simple global database not aware of forks. It is used by consensus
+/// engines to persist their metadata (e.g. authority sets, slot claims) next to
+/// the chain data. Writes which must land atomically with a block import should
+/// go through `BlockImportParams::auxiliary` rather than `insert_aux`.
 pub trait AuxStore {
 	/// Insert auxiliary data into key-value store. Deletions occur after insertions.
 	fn insert_aux<
@@ -119,6 +138,38 @@ pub trait AuxStore {
 	fn get_aux(&self, key: &[u8]) -> error::Result<Option<Vec<u8>>>;
 }
 
+impl<'x, T: AuxStore + ?Sized> AuxStore for &'x T {
+	fn insert_aux<
+		'a,
+		'b: 'a,
+		'c: 'a,
+		I: IntoIterator<Item=&'a(&'c [u8], &'c [u8])>,
+		D: IntoIterator<Item=&'a &'b [u8]>,
+	>(&self, insert: I, delete: D) -> error::Result<()> {
+		(**self).insert_aux(insert, delete)
+	}
+
+	fn get_aux(&self, key: &[u8]) -> error::Result<Option<Vec<u8>>> {
+		(**self).get_aux(key)
+	}
+}
+
+impl<T: AuxStore + ?Sized> AuxStore for Arc<T> {
+	fn insert_aux<
+		'a,
+		'b: 'a,
+		'c: 'a,
+		I: IntoIterator<Item=&'a(&'c [u8], &'c [u8])>,
+		D: IntoIterator<Item=&'a &'b [u8]>,
+	>(&self, insert: I, delete: D) -> error::Result<()> {
+		(**self).insert_aux(insert, delete)
+	}
+
+	fn get_aux(&self, key: &[u8]) -> error::Result<Option<Vec<u8>>> {
+		(**self).get_aux(key)
+	}
+}
+
 /// Client backend. Manages the data layer.
 ///
 /// Note on state pruning: while an object from `state_at` is alive, the state
@@ -156,6 +207,8 @@ pub trait Backend<Block, H>: AuxStore + Send + Sync where
 	fn blockchain(&self) -> &Self::Blockchain;
 	/// Returns the used state cache, if existent.
 	fn used_state_cache_size(&self) -> Option<usize>;
+	/// Returns the resource usage of the backend, if it is tracked.
+	fn usage_info(&self) -> Option<UsageInfo>;
 	/// Returns reference to changes trie storage.
 	fn changes_trie_storage(&self) -> Option<&Self::ChangesTrieStorage>;
 	/// Returns a handle to offchain storage.
@@ -21,18 +21,67 @@ use rstd::vec::Vec;
 use sr_api_macros::decl_runtime_apis;
 pub use inherents::{InherentData, CheckInherentsResult};
 
+/// Definitions for supporting the older version of the API: v3.
+pub mod compatibility_v3 {
+	use runtime_primitives::ApplyError;
+	use parity_codec::{Encode, Decode, Input, Output};
+
+	/// Outcome of a valid extrinsic application, as returned by `apply_extrinsic` up to v3.
+	#[derive(Eq, PartialEq, Clone, Copy)]
+	#[cfg_attr(feature = "std", derive(Debug))]
+	#[repr(u8)]
+	pub enum ApplyOutcome {
+		/// Successful application (extrinsic reported no issue).
+		Success = 0,
+		/// Failed application (extrinsic was probably a no-op other than fees).
+		Fail = 1,
+	}
+
+	impl Encode for ApplyOutcome {
+		fn encode_to<T: Output>(&self, dest: &mut T) {
+			dest.push_byte(*self as u8);
+		}
+	}
+
+	impl Decode for ApplyOutcome {
+		fn decode<I: Input>(input: &mut I) -> Option<Self> {
+			match input.read_byte()? {
+				0 => Some(ApplyOutcome::Success),
+				1 => Some(ApplyOutcome::Fail),
+				_ => None,
+			}
+		}
+	}
+
+	/// Result from attempt to apply an extrinsic, as returned by `apply_extrinsic` up to v3.
+	pub type ApplyResult = Result<ApplyOutcome, ApplyError>;
+}
+
 decl_runtime_apis! {
 	/// The `BlockBuilder` api trait that provides required functions for building a block for a runtime.
-	#[api_version(3)]
+	#[api_version(4)]
 	pub trait BlockBuilder {
-		/// Apply the given extrinsics.
+		/// Apply the given extrinsic.
+		///
+		/// Returns `Ok` if the extrinsic was included in the block, with the dispatch error
+		/// attached if the dispatch itself failed, or the reason why the extrinsic could
+		/// not be included at all.
 		fn apply_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> ApplyResult;
+		/// Apply the given extrinsic.
+		#[changed_in(4)]
+		fn apply_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> self::compatibility_v3::ApplyResult;
 		/// Finish the current block.
+		///
+		/// Returns the header of the block, with all roots computed.
 		#[renamed("finalise_block", 3)]
 		fn finalize_block() -> <Block as BlockT>::Header;
-		/// Generate inherent extrinsics. The inherent data will vary from chain to chain.
+		/// Generate inherent extrinsics for the block being built.
+		///
+		/// The inherent data will vary from chain to chain.
 		fn inherent_extrinsics(inherent: InherentData) -> Vec<<Block as BlockT>::Extrinsic>;
-		/// Check that the inherents are valid. The inherent data will vary from chain to chain.
+		/// Check that the inherents of the given block are valid against the inherent data.
+		///
+		/// The inherent data will vary from chain to chain.
 		fn check_inherents(block: Block, data: InherentData) -> CheckInherentsResult;
 		/// Generate a random seed.
 		fn random_seed() -> <Block as BlockT>::Hash;
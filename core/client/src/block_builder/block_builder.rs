@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use super::api::BlockBuilder as BlockBuilderApi;
+use super::api::{BlockBuilder as BlockBuilderApi, compatibility_v3};
 use std::vec::Vec;
 use parity_codec::Encode;
 use runtime_primitives::ApplyOutcome;
@@ -24,7 +24,7 @@ use runtime_primitives::traits::{
 };
 use primitives::{H256, ExecutionContext};
 use crate::blockchain::HeaderBackend;
-use crate::runtime_api::{Core, ApiExt};
+use crate::runtime_api::{Core, ApiExt, OverlayedChanges, StorageProof};
 use crate::error;
 
 /// Utility for building new (valid) blocks from a stream of extrinsics.
@@ -99,12 +99,28 @@ where
 		let extrinsics = &mut self.extrinsics;
 
 		self.api.map_api_result(|api| {
-			match api.apply_extrinsic_with_context(
-				block_id,
-				ExecutionContext::BlockConstruction,
-				xt.clone()
-			)? {
-				Ok(ApplyOutcome::Success) | Ok(ApplyOutcome::Fail) => {
+			let api_version = api.api_version::<dyn BlockBuilderApi<Block>>(block_id)?;
+			let apply_result = match api_version {
+				Some(version) if version < 4 => {
+					#[allow(deprecated)]
+					api.apply_extrinsic_before_version_4_with_context(
+						block_id,
+						ExecutionContext::BlockConstruction,
+						xt.clone(),
+					)?.map(|outcome| match outcome {
+						compatibility_v3::ApplyOutcome::Success => ApplyOutcome::Success,
+						compatibility_v3::ApplyOutcome::Fail => ApplyOutcome::Fail(Default::default()),
+					})
+				}
+				_ => api.apply_extrinsic_with_context(
+					block_id,
+					ExecutionContext::BlockConstruction,
+					xt.clone(),
+				)?,
+			};
+
+			match apply_result {
+				Ok(ApplyOutcome::Success) | Ok(ApplyOutcome::Fail(_)) => {
 					extrinsics.push(xt);
 					Ok(())
 				}
@@ -141,10 +157,22 @@ where
 	///
 	/// The proof will be `Some(_)`, if proof recording was enabled while creating
 	/// the block builder.
-	pub fn bake_and_extract_proof(mut self) -> error::Result<(Block, Option<Vec<Vec<u8>>>)> {
+	pub fn bake_and_extract_proof(mut self) -> error::Result<(Block, Option<StorageProof>)> {
 		self.bake_impl()?;
 
 		let proof = self.api.extract_proof();
 		Ok((<Block as BlockT>::new(self.header, self.extrinsics), proof))
 	}
+
+	/// Consume the builder to return a valid `Block` containing all pushed extrinsics
+	/// and the storage changes it makes.
+	///
+	/// The changes can be passed to the import through `BlockImportParams::storage_changes`,
+	/// so that the block doesn't need to be executed again.
+	pub fn bake_and_extract_changes(mut self) -> error::Result<(Block, OverlayedChanges)> {
+		self.bake_impl()?;
+
+		let changes = self.api.extract_changes();
+		Ok((<Block as BlockT>::new(self.header, self.extrinsics), changes))
+	}
 }
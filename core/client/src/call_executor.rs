@@ -21,12 +21,12 @@ use runtime_primitives::{
 };
 use state_machine::{
 	self, OverlayedChanges, Ext, CodeExecutor, ExecutionManager,
-	ExecutionStrategy, NeverOffchainExt, backend::Backend as _,
+	ExecutionStrategy, NeverOffchainExt, StorageProof, backend::Backend as _,
 };
 use executor::{RuntimeVersion, RuntimeInfo, NativeVersion};
 use hash_db::Hasher;
 use trie::MemoryDB;
-use primitives::{offchain, H256, Blake2Hasher, NativeOrEncoded, NeverNativeValue};
+use primitives::{offchain, H256, Blake2Hasher, NativeOrEncoded, NeverNativeValue, traits::KeyStorePtr};
 
 use crate::runtime_api::{ProofRecorder, InitializeBlock};
 use crate::backend;
@@ -121,7 +121,7 @@ where
 		overlay: &mut OverlayedChanges,
 		method: &str,
 		call_data: &[u8]
-	) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error> {
+	) -> Result<(Vec<u8>, StorageProof), error::Error> {
 		let trie_state = state.as_trie_backend()
 			.ok_or_else(||
 				Box::new(state_machine::ExecutionError::UnableToGenerateProof)
@@ -139,7 +139,7 @@ where
 		overlay: &mut OverlayedChanges,
 		method: &str,
 		call_data: &[u8]
-	) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error>;
+	) -> Result<(Vec<u8>, StorageProof), error::Error>;
 
 	/// Get runtime version if supported.
 	fn native_runtime_version(&self) -> Option<&NativeVersion>;
@@ -150,14 +150,18 @@ where
 pub struct LocalCallExecutor<B, E> {
 	backend: Arc<B>,
 	executor: E,
+	keystore: Option<KeyStorePtr>,
 }
 
 impl<B, E> LocalCallExecutor<B, E> {
 	/// Creates new instance of local call executor.
-	pub fn new(backend: Arc<B>, executor: E) -> Self {
+	///
+	/// The `keystore` is made available to the runtime.
+	pub fn new(backend: Arc<B>, executor: E, keystore: Option<KeyStorePtr>) -> Self {
 		LocalCallExecutor {
 			backend,
 			executor,
+			keystore,
 		}
 	}
 }
@@ -167,6 +171,7 @@ impl<B, E> Clone for LocalCallExecutor<B, E> where E: Clone {
 		LocalCallExecutor {
 			backend: self.backend.clone(),
 			executor: self.executor.clone(),
+			keystore: self.keystore.clone(),
 		}
 	}
 }
@@ -197,7 +202,9 @@ where
 			&self.executor,
 			method,
 			call_data,
-		).execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
+		)
+		.with_keystore(self.keystore.clone())
+		.execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
 			strategy.get_manager(),
 			false,
 			None,
@@ -263,6 +270,7 @@ where
 					method,
 					call_data,
 				)
+				.with_keystore(self.keystore.clone())
 				.execute_using_consensus_failure_handler(
 					execution_manager,
 					false,
@@ -280,6 +288,7 @@ where
 				method,
 				call_data,
 			)
+			.with_keystore(self.keystore.clone())
 			.execute_using_consensus_failure_handler(
 				execution_manager,
 				false,
@@ -327,7 +336,9 @@ where
 			&self.executor,
 			method,
 			call_data,
-		).execute_using_consensus_failure_handler(
+		)
+		.with_keystore(self.keystore.clone())
+		.execute_using_consensus_failure_handler(
 			manager,
 			true,
 			native_call,
@@ -346,7 +357,7 @@ where
 		overlay: &mut OverlayedChanges,
 		method: &str,
 		call_data: &[u8]
-	) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error> {
+	) -> Result<(Vec<u8>, StorageProof), error::Error> {
 		state_machine::prove_execution_on_trie_backend(
 			trie_state,
 			overlay,
@@ -17,7 +17,7 @@
 //! Substrate Client
 
 use std::{
-	marker::PhantomData, collections::{HashSet, BTreeMap, HashMap}, sync::Arc,
+	marker::PhantomData, collections::{BTreeMap, HashMap}, sync::Arc,
 	panic::UnwindSafe, result, cell::RefCell, rc::Rc,
 };
 use crate::error::Error;
@@ -56,7 +56,7 @@ use state_machine::{
 	DBValue, Backend as StateBackend, CodeExecutor, ChangesTrieAnchorBlockId,
 	ExecutionStrategy, ExecutionManager, prove_read, prove_child_read,
 	ChangesTrieRootsStorage, ChangesTrieStorage,
-	key_changes, key_changes_proof, OverlayedChanges, NeverOffchainExt,
+	key_changes, key_changes_proof, OverlayedChanges, NeverOffchainExt, Ext, StorageProof,
 };
 use hash_db::Hasher;
 
@@ -66,8 +66,9 @@ use crate::backend::{
 };
 use crate::blockchain::{
 	self, Info as ChainInfo, Backend as ChainBackend,
-	HeaderBackend as ChainHeaderBackend, ProvideCache, Cache,
+	HeaderBackend as ChainHeaderBackend, ProvideCache, Cache, RouteEntry,
 };
+use crate::aux_pruning::AuxDataPruner;
 use crate::call_executor::{CallExecutor, LocalCallExecutor};
 use executor::{RuntimeVersion, RuntimeInfo};
 use crate::notifications::{StorageNotifications, StorageEventStream};
@@ -79,7 +80,7 @@ use crate::block_builder::{self, api::BlockBuilder as BlockBuilderAPI};
 use crate::genesis;
 use substrate_telemetry::{telemetry, SUBSTRATE_INFO};
 
-use log::{info, trace, warn};
+use log::{info, debug, trace, warn};
 
 
 /// Type that implements `futures::Stream` of block import events.
@@ -129,6 +130,7 @@ pub struct Client<B, E, Block, RA> where Block: BlockT {
 	storage_notifications: Mutex<StorageNotifications<Block>>,
 	import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<BlockImportNotification<Block>>>>,
 	finality_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<FinalityNotification<Block>>>>,
+	aux_pruners: RwLock<Vec<Box<dyn AuxDataPruner<Block>>>>,
 	// holds the block hash currently being imported. TODO: replace this with block queue
 	importing_block: RwLock<Option<Block::Hash>>,
 	execution_strategies: ExecutionStrategies,
@@ -138,16 +140,21 @@ pub struct Client<B, E, Block, RA> where Block: BlockT {
 /// Client import operation, a wrapper for the backend.
 pub struct ClientImportOperation<Block: BlockT, H: Hasher<Out=Block::Hash>, B: backend::Backend<Block, H>> {
 	op: B::BlockImportOperation,
-	notify_imported: Option<(
-		Block::Hash,
-		BlockOrigin,
-		Block::Header,
-		bool,
-		Option<(
-			StorageCollection,
-			ChildStorageCollection,
-		)>)>,
+	notify_imported: Option<ImportSummary<Block>>,
 	notify_finalized: Vec<Block::Hash>,
+	notify_stale_heads: Vec<Block::Hash>,
+}
+
+/// Summary of an imported block, used to emit the import notification once the import
+/// operation is committed.
+struct ImportSummary<Block: BlockT> {
+	hash: Block::Hash,
+	origin: BlockOrigin,
+	header: Block::Header,
+	is_new_best: bool,
+	retracted: Vec<Block::Hash>,
+	enacted: Vec<Block::Hash>,
+	storage_changes: Option<(StorageCollection, ChildStorageCollection)>,
 }
 
 /// A source of blockchain events.
@@ -170,6 +177,43 @@ pub trait BlockchainEvents<Block: BlockT> {
 	) -> error::Result<StorageEventStream<Block::Hash>>;
 }
 
+/// Finalize blocks.
+pub trait Finalizer<Block: BlockT, H: Hasher<Out=Block::Hash>, B: backend::Backend<Block, H>> {
+	/// Mark all blocks up to given as finalized in operation. If a
+	/// justification is provided it is stored with the given finalized
+	/// block (any other finalized blocks are left unjustified).
+	///
+	/// If the block being finalized is on a different fork from the current
+	/// best block the finalized block is set as best, this might be slightly
+	/// innacurate (i.e. outdated), usages that require determining an accurate
+	/// best block should use `SelectChain` instead of the client.
+	fn apply_finality(
+		&self,
+		operation: &mut ClientImportOperation<Block, H, B>,
+		id: BlockId<Block>,
+		justification: Option<Justification>,
+		notify: bool,
+	) -> error::Result<()>;
+
+	/// Finalize a block. This will implicitly finalize all blocks up to it and
+	/// fire finality notifications.
+	///
+	/// If the block being finalized is on a different fork from the current
+	/// best block the finalized block is set as best, this might be slightly
+	/// innacurate (i.e. outdated), usages that require determining an accurate
+	/// best block should use `SelectChain` instead of the client.
+	///
+	/// Pass a flag to indicate whether finality notifications should be propagated.
+	/// This is usually tied to some synchronization state, where we don't send notifications
+	/// while performing major synchronization work.
+	fn finalize_block(
+		&self,
+		id: BlockId<Block>,
+		justification: Option<Justification>,
+		notify: bool,
+	) -> error::Result<()>;
+}
+
 /// Fetch block body by ID.
 pub trait BlockBody<Block: BlockT> {
 	/// Get block body by ID. Returns `None` if the body is not stored.
@@ -211,6 +255,16 @@ pub struct BlockImportNotification<Block: BlockT> {
 	pub header: Block::Header,
 	/// Is this the new best block.
 	pub is_new_best: bool,
+	/// Blocks that left the best chain because of this import, from the previous best block
+	/// down to the common ancestor with the new best chain (excluded).
+	///
+	/// Empty unless the import caused a reorganization.
+	pub retracted: Vec<Block::Hash>,
+	/// Blocks other than the imported one that joined the best chain because of this import,
+	/// from the common ancestor (excluded) up to the parent of the imported block.
+	///
+	/// Empty unless the import caused a reorganization.
+	pub enacted: Vec<Block::Hash>,
 }
 
 /// Summary of a finalized block.
@@ -220,6 +274,11 @@ pub struct FinalityNotification<Block: BlockT> {
 	pub hash: Block::Hash,
 	/// Imported block header.
 	pub header: Block::Header,
+	/// Heads of the branches which do not descend from the finalized block and
+	/// are displaced by its finalization.
+	///
+	/// Only set on the notification of the last block finalized by an operation.
+	pub stale_heads: Vec<Block::Hash>,
 }
 
 // used in importing a block, where additional changes are made after the runtime
@@ -287,7 +346,7 @@ pub fn new_with_backend<B, E, Block, S, RA>(
 		Block: BlockT<Hash=H256>,
 		B: backend::LocalBackend<Block, Blake2Hasher>
 {
-	let call_executor = LocalCallExecutor::new(backend.clone(), executor);
+	let call_executor = LocalCallExecutor::new(backend.clone(), executor, None);
 	Client::new(backend, call_executor, build_genesis_storage, Default::default())
 }
 
@@ -328,12 +387,18 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			storage_notifications: Default::default(),
 			import_notification_sinks: Default::default(),
 			finality_notification_sinks: Default::default(),
+			aux_pruners: Default::default(),
 			importing_block: Default::default(),
 			execution_strategies,
 			_phantom: Default::default(),
 		})
 	}
 
+	/// Register a consumer of the aux store whose data is pruned when blocks are finalized.
+	pub fn register_aux_pruner(&self, pruner: Box<dyn AuxDataPruner<Block>>) {
+		self.aux_pruners.write().push(pruner);
+	}
+
 	/// Get a reference to the execution strategies.
 	pub fn execution_strategies(&self) -> &ExecutionStrategies {
 		&self.execution_strategies
@@ -431,7 +496,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 	}
 
 	/// Reads storage value at a given block + key, returning read proof.
-	pub fn read_proof(&self, id: &BlockId<Block>, key: &[u8]) -> error::Result<Vec<Vec<u8>>> {
+	pub fn read_proof(&self, id: &BlockId<Block>, key: &[u8]) -> error::Result<StorageProof> {
 		self.state_at(id)
 			.and_then(|state| prove_read(state, key)
 				.map(|(_, proof)| proof)
@@ -445,7 +510,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		id: &BlockId<Block>,
 		storage_key: &[u8],
 		key: &[u8]
-	) -> error::Result<Vec<Vec<u8>>> {
+	) -> error::Result<StorageProof> {
 		self.state_at(id)
 			.and_then(|state| prove_child_read(state, storage_key, key)
 				.map(|(_, proof)| proof)
@@ -460,14 +525,14 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		id: &BlockId<Block>,
 		method: &str,
 		call_data: &[u8]
-	) -> error::Result<(Vec<u8>, Vec<Vec<u8>>)> {
+	) -> error::Result<(Vec<u8>, StorageProof)> {
 		let state = self.state_at(id)?;
 		let header = self.prepare_environment_block(id)?;
 		prove_execution(state, header, &self.executor, method, call_data)
 	}
 
 	/// Reads given header and generates CHT-based header proof.
-	pub fn header_proof(&self, id: &BlockId<Block>) -> error::Result<(Block::Header, Vec<Vec<u8>>)> {
+	pub fn header_proof(&self, id: &BlockId<Block>) -> error::Result<(Block::Header, StorageProof)> {
 		self.header_proof_with_cht_size(id, cht::size())
 	}
 
@@ -483,7 +548,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		&self,
 		id: &BlockId<Block>,
 		cht_size: NumberFor<Block>,
-	) -> error::Result<(Block::Header, Vec<Vec<u8>>)> {
+	) -> error::Result<(Block::Header, StorageProof)> {
 		let proof_error = || error::Error::Backend(format!("Failed to generate header proof for {:?}", id));
 		let header = self.backend.blockchain().expect_header(*id)?;
 		let block_num = *header.number();
@@ -551,6 +616,48 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		.map_err(|err| error::Error::ChangesTrieAccessFailed(err))
 	}
 
+	/// Get blocks at which any of the given keys has been changed at given blocks range, along with
+	/// the keys changed at each block.
+	/// Works only for runtimes that are supporting changes tries.
+	///
+	/// The changes trie configuration and anchor block are resolved once for all keys, which makes
+	/// this cheaper than calling `key_changes` for every key.
+	pub fn keys_changes(
+		&self,
+		first: NumberFor<Block>,
+		last: BlockId<Block>,
+		keys: &[StorageKey],
+	) -> error::Result<BTreeMap<NumberFor<Block>, Vec<StorageKey>>> {
+		let (config, storage) = self.require_changes_trie()?;
+		let last_number = self.backend.blockchain().expect_block_number_from_id(&last)?;
+		let last_hash = self.backend.blockchain().expect_block_hash_from_id(&last)?;
+		let anchor = ChangesTrieAnchorBlockId {
+			hash: convert_hash(&last_hash),
+			number: last_number,
+		};
+		let best_number = self.backend.blockchain().info().best_number;
+
+		let mut changes: BTreeMap<NumberFor<Block>, Vec<StorageKey>> = BTreeMap::new();
+		for key in keys {
+			let key_changes = key_changes::<_, Blake2Hasher, _>(
+				&config,
+				&*storage,
+				first,
+				&anchor,
+				best_number,
+				&key.0,
+			).map_err(|err| error::Error::ChangesTrieAccessFailed(err))?;
+			for change in key_changes {
+				let (block, _) = change.map_err(|err| error::Error::ChangesTrieAccessFailed(err))?;
+				let block_keys = changes.entry(block).or_default();
+				if block_keys.last() != Some(key) {
+					block_keys.push(key.clone());
+				}
+			}
+		}
+		Ok(changes)
+	}
+
 	/// Get proof for computation of (block, extrinsic) pairs where key has been changed at given blocks range.
 	/// `min` is the hash of the first block, which changes trie root is known to the requester - when we're using
 	/// changes tries from ascendants of this block, we should provide proofs for changes tries roots
@@ -670,18 +777,18 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		&self,
 		cht_size: NumberFor<Block>,
 		blocks: I
-	) -> error::Result<Vec<Vec<u8>>> {
+	) -> error::Result<StorageProof> {
 		// most probably we have touched several changes tries that are parts of the single CHT
 		// => GroupBy changes tries by CHT number and then gather proof for the whole group at once
-		let mut proof = HashSet::new();
+		let mut proofs = Vec::new();
 
 		cht::for_each_cht_group::<Block::Header, _, _, _>(cht_size, blocks, |_, cht_num, cht_blocks| {
 			let cht_proof = self.changes_trie_roots_proof_at_cht(cht_size, cht_num, cht_blocks)?;
-			proof.extend(cht_proof);
+			proofs.push(cht_proof);
 			Ok(())
 		}, ())?;
 
-		Ok(proof.into_iter().collect())
+		Ok(StorageProof::merge(proofs))
 	}
 
 	/// Generates CHT-based proof for roots of changes tries at given blocks (that are part of single CHT).
@@ -690,7 +797,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		cht_size: NumberFor<Block>,
 		cht_num: NumberFor<Block>,
 		blocks: Vec<NumberFor<Block>>
-	) -> error::Result<Vec<Vec<u8>>> {
+	) -> error::Result<StorageProof> {
 		let cht_start = cht::start_number(cht_size, cht_num);
 		let mut current_num = cht_start;
 		let cht_range = ::std::iter::from_fn(|| {
@@ -729,35 +836,22 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 	}
 
 	/// Create a new block, built on top of `parent`.
-	pub fn new_block_at(
-		&self,
-		parent: &BlockId<Block>,
-		inherent_digests: DigestFor<Block>,
-	) -> error::Result<block_builder::BlockBuilder<Block, Self>> where
-		E: Clone + Send + Sync,
-		RA: Send + Sync,
-		Self: ProvideRuntimeApi,
-		<Self as ProvideRuntimeApi>::Api: BlockBuilderAPI<Block>
-	{
-		block_builder::BlockBuilder::at_block(parent, &self, false, inherent_digests)
-	}
-
-	/// Create a new block, built on top of `parent` with proof recording enabled.
 	///
-	/// While proof recording is enabled, all accessed trie nodes are saved.
+	/// When proof recording is enabled, all accessed trie nodes are saved.
 	/// These recorded trie nodes can be used by a third party to proof the
 	/// output of this block builder without having access to the full storage.
-	pub fn new_block_at_with_proof_recording(
+	pub fn new_block_at(
 		&self,
 		parent: &BlockId<Block>,
 		inherent_digests: DigestFor<Block>,
+		record_proof: bool,
 	) -> error::Result<block_builder::BlockBuilder<Block, Self>> where
 		E: Clone + Send + Sync,
 		RA: Send + Sync,
 		Self: ProvideRuntimeApi,
 		<Self as ProvideRuntimeApi>::Api: BlockBuilderAPI<Block>
 	{
-		block_builder::BlockBuilder::at_block(parent, &self, true, inherent_digests)
+		block_builder::BlockBuilder::at_block(parent, &self, record_proof, inherent_digests)
 	}
 
 	/// Lock the import lock, and run operations inside.
@@ -772,13 +866,14 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				op: self.backend.begin_operation()?,
 				notify_imported: None,
 				notify_finalized: Vec::new(),
+				notify_stale_heads: Vec::new(),
 			};
 
 			let r = f(&mut op)?;
 
-			let ClientImportOperation { op, notify_imported, notify_finalized } = op;
+			let ClientImportOperation { op, notify_imported, notify_finalized, notify_stale_heads } = op;
 			self.backend.commit_operation(op)?;
-			self.notify_finalized(notify_finalized)?;
+			self.notify_finalized(notify_finalized, notify_stale_heads)?;
 
 			if let Some(notify_imported) = notify_imported {
 				self.notify_imported(notify_imported)?;
@@ -831,6 +926,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			finalized,
 			auxiliary,
 			fork_choice,
+			storage_changes,
 		} = import_block;
 
 		assert!(justification.is_some() && finalized || justification.is_none());
@@ -868,6 +964,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			finalized,
 			auxiliary,
 			fork_choice,
+			storage_changes,
 		);
 
 		telemetry!(SUBSTRATE_INFO; "block.import";
@@ -891,6 +988,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		finalized: bool,
 		aux: Vec<(Vec<u8>, Option<Vec<u8>>)>,
 		fork_choice: ForkChoiceStrategy,
+		recorded_changes: Option<OverlayedChanges>,
 	) -> error::Result<ImportResult> where
 		E: CallExecutor<Block, Blake2Hasher> + Send + Sync + Clone,
 	{
@@ -922,7 +1020,14 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		}
 
 		// FIXME #1232: correct path logic for when to execute this function
-		let (storage_update,changes_update,storage_changes) = self.block_execution(&operation.op, &import_headers, origin, hash, body.clone())?;
+		let (storage_update, changes_update, storage_changes) = self.block_execution(
+			&operation.op,
+			&import_headers,
+			origin,
+			hash,
+			body.clone(),
+			recorded_changes,
+		)?;
 
 		let is_new_best = finalized || match fork_choice {
 			ForkChoiceStrategy::LongestChain => import_headers.post().number() > &last_best_number,
@@ -958,12 +1063,64 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 
 		operation.op.insert_aux(aux)?;
 
+		// the parent's finalization above ran the pruners up to the parent only.
+		if finalized {
+			let aux_pruners = self.aux_pruners.read();
+			if !aux_pruners.is_empty() {
+				let header = import_headers.post();
+				let parent_number = self.backend.blockchain()
+					.expect_block_number_from_id(&BlockId::Hash(parent_hash))?;
+				let finalized_blocks = crate::aux_pruning::finalized_blocks(
+					self.backend.blockchain(),
+					RouteEntry { hash: parent_hash, number: parent_number },
+					vec![RouteEntry { hash, number: *header.number() }],
+				)?;
+				let headers = crate::aux_pruning::WithPendingHeader {
+					headers: self.backend.blockchain(),
+					pending: header,
+				};
+				let get_aux = |key: &[u8]| backend::AuxStore::get_aux(&*self.backend, key);
+				for pruner in aux_pruners.iter() {
+					operation.op.insert_aux(pruner.prune(&finalized_blocks, &headers, &get_aux)?)?;
+				}
+			}
+		}
+
 		if make_notifications {
 			if finalized {
+				// the imported block has no children yet, so every current leaf but
+				// its parent is on a branch displaced by its finalization.
+				operation.notify_stale_heads = self.backend.blockchain().leaves()?
+					.into_iter()
+					.filter(|leaf| *leaf != parent_hash)
+					.collect();
 				operation.notify_finalized.push(hash);
 			}
 
-			operation.notify_imported = Some((hash, origin, import_headers.into_post(), is_new_best, storage_changes));
+			// the new best block doesn't extend the previous one: compute the reorganization.
+			let (retracted, enacted) = if is_new_best && parent_hash != last_best {
+				let route_from_best = crate::blockchain::tree_route(
+					self.backend.blockchain(),
+					BlockId::Hash(last_best),
+					BlockId::Hash(parent_hash),
+				)?;
+				(
+					route_from_best.retracted().iter().map(|e| e.hash).collect(),
+					route_from_best.enacted().iter().map(|e| e.hash).collect(),
+				)
+			} else {
+				(Vec::new(), Vec::new())
+			};
+
+			operation.notify_imported = Some(ImportSummary {
+				hash,
+				origin,
+				header: import_headers.into_post(),
+				is_new_best,
+				retracted,
+				enacted,
+				storage_changes,
+			});
 		}
 
 		Ok(ImportResult::imported())
@@ -976,6 +1133,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		origin: BlockOrigin,
 		hash: Block::Hash,
 		body: Option<Vec<Block::Extrinsic>>,
+		recorded_changes: Option<OverlayedChanges>,
 	) -> error::Result<(
 		Option<StorageUpdate<B, Block>>,
 		Option<Option<ChangesUpdate>>,
@@ -989,6 +1147,13 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 	{
 		match transaction.state()? {
 			Some(transaction_state) => {
+				if let Some(overlay) = recorded_changes {
+					if let Some(result) = self.apply_recorded_changes(transaction_state, import_headers, overlay)? {
+						return Ok(result);
+					}
+					debug!("Recorded changes of block {} don't match its header, executing it", hash);
+				}
+
 				let mut overlay = Default::default();
 				let get_execution_manager = |execution_strategy: ExecutionStrategy| {
 					match execution_strategy {
@@ -1037,6 +1202,50 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		}
 	}
 
+	/// Computes the storage and changes trie updates of changes recorded while authoring the block.
+	///
+	/// Returns `None` if the roots they produce don't match the ones in the header.
+	fn apply_recorded_changes<S: state_machine::Backend<Blake2Hasher>>(
+		&self,
+		state: &S,
+		import_headers: &PrePostHeader<Block::Header>,
+		mut overlay: OverlayedChanges,
+	) -> error::Result<Option<(
+		Option<S::Transaction>,
+		Option<Option<ChangesUpdate>>,
+		Option<(
+			Vec<(Vec<u8>, Option<Vec<u8>>)>,
+			Vec<(Vec<u8>, Vec<(Vec<u8>, Option<Vec<u8>>)>)>
+		)>
+	)>> {
+		overlay.commit_prospective();
+		let parent_hash = convert_hash(import_headers.pre().parent_hash());
+		let (storage_update, changes_update) = Ext::<_, NumberFor<Block>, _, _, NeverOffchainExt>::new(
+			&mut overlay,
+			state,
+			self.backend.changes_trie_storage(),
+			None,
+		).storage_changes(parent_hash).map_err(|_| error::Error::ChangesTrieAccessFailed(
+			"Failed to build changes trie of recorded changes".into()
+		))?;
+
+		if import_headers.post().state_root() != &storage_update.1 {
+			return Ok(None);
+		}
+		let changes_trie_root = import_headers.pre().digest().log(DigestItem::as_changes_trie_root).cloned();
+		if changes_trie_root != changes_update.as_ref().map(|(_, root)| convert_hash(root)) {
+			return Ok(None);
+		}
+
+		let (top, children) = overlay.into_committed();
+		let children = children.map(|(sk, it)| (sk, it.collect())).collect();
+		Ok(Some((
+			Some(storage_update.0),
+			Some(changes_update.map(|(tx, _)| tx)),
+			Some((top.collect(), children)),
+		)))
+	}
+
 	fn apply_finality_with_block_hash(
 		&self,
 		operation: &mut ClientImportOperation<Block, Blake2Hasher, B>,
@@ -1093,15 +1302,33 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		assert_eq!(enacted.last().map(|e| e.hash), Some(block));
 		operation.op.mark_finalized(BlockId::Hash(block), justification)?;
 
+		let aux_pruners = self.aux_pruners.read();
+		if aux_pruners.is_empty() && !notify {
+			return Ok(());
+		}
+
+		let finalized = crate::aux_pruning::finalized_blocks(
+			self.backend.blockchain(),
+			{
+				let previous = route_from_finalized.common_block();
+				RouteEntry { hash: previous.hash, number: previous.number }
+			},
+			enacted.iter().map(|e| RouteEntry { hash: e.hash, number: e.number }).collect(),
+		)?;
+		let get_aux = |key: &[u8]| backend::AuxStore::get_aux(&*self.backend, key);
+		for pruner in aux_pruners.iter() {
+			operation.op.insert_aux(pruner.prune(&finalized, self.backend.blockchain(), &get_aux)?)?;
+		}
+
 		if notify {
 			// sometimes when syncing, tons of blocks can be finalized at once.
 			// we'll send notifications spuriously in that case.
 			const MAX_TO_NOTIFY: usize = 256;
-			let enacted = route_from_finalized.enacted();
 			let start = enacted.len() - ::std::cmp::min(enacted.len(), MAX_TO_NOTIFY);
 			for finalized in &enacted[start..] {
 				operation.notify_finalized.push(finalized.hash);
 			}
+			operation.notify_stale_heads = finalized.stale_heads;
 		}
 
 		Ok(())
@@ -1110,10 +1337,12 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 	fn notify_finalized(
 		&self,
 		notify_finalized: Vec<Block::Hash>,
+		mut stale_heads: Vec<Block::Hash>,
 	) -> error::Result<()> {
 		let mut sinks = self.finality_notification_sinks.lock();
 
-		for finalized_hash in notify_finalized {
+		let last = notify_finalized.len().saturating_sub(1);
+		for (i, finalized_hash) in notify_finalized.into_iter().enumerate() {
 			let header = self.header(&BlockId::Hash(finalized_hash))?
 				.expect("header already known to exist in DB because it is indicated in the tree route; qed");
 
@@ -1125,6 +1354,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			let notification = FinalityNotification {
 				header,
 				hash: finalized_hash,
+				stale_heads: if i == last { ::std::mem::replace(&mut stale_heads, Vec::new()) } else { Vec::new() },
 			};
 
 			sinks.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
@@ -1135,17 +1365,17 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 
 	fn notify_imported(
 		&self,
-		notify_import: (
-			Block::Hash, BlockOrigin,
-			Block::Header,
-			bool,
-			Option<(
-				Vec<(Vec<u8>, Option<Vec<u8>>)>,
-				Vec<(Vec<u8>, Vec<(Vec<u8>, Option<Vec<u8>>)>)>,
-				)
-			>),
+		notify_import: ImportSummary<Block>,
 	) -> error::Result<()> {
-		let (hash, origin, header, is_new_best, storage_changes) = notify_import;
+		let ImportSummary {
+			hash,
+			origin,
+			header,
+			is_new_best,
+			retracted,
+			enacted,
+			storage_changes,
+		} = notify_import;
 
 		if let Some(storage_changes) = storage_changes {
 			// TODO [ToDr] How to handle re-orgs? Should we re-emit all storage changes?
@@ -1162,6 +1392,8 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			origin,
 			header,
 			is_new_best,
+			retracted,
+			enacted,
 		};
 
 		self.import_notification_sinks.lock()
@@ -1190,45 +1422,6 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		)
 	}
 
-	/// Mark all blocks up to given as finalized in operation. If a
-	/// justification is provided it is stored with the given finalized
-	/// block (any other finalized blocks are left unjustified).
-	///
-	/// If the block being finalized is on a different fork from the current
-	/// best block the finalized block is set as best, this might be slightly
-	/// innacurate (i.e. outdated), usages that require determining an accurate
-	/// best block should use `SelectChain` instead of the client.
-	pub fn apply_finality(
-		&self,
-		operation: &mut ClientImportOperation<Block, Blake2Hasher, B>,
-		id: BlockId<Block>,
-		justification: Option<Justification>,
-		notify: bool,
-	) -> error::Result<()> {
-		let last_best = self.backend.blockchain().info().best_hash;
-		let to_finalize_hash = self.backend.blockchain().expect_block_hash_from_id(&id)?;
-		self.apply_finality_with_block_hash(operation, to_finalize_hash, justification, last_best, notify)
-	}
-
-	/// Finalize a block. This will implicitly finalize all blocks up to it and
-	/// fire finality notifications.
-	///
-	/// If the block being finalized is on a different fork from the current
-	/// best block the finalized block is set as best, this might be slightly
-	/// innacurate (i.e. outdated), usages that require determining an accurate
-	/// best block should use `SelectChain` instead of the client.
-	///
-	/// Pass a flag to indicate whether finality notifications should be propagated.
-	/// This is usually tied to some synchronization state, where we don't send notifications
-	/// while performing major synchronization work.
-	pub fn finalize_block(&self, id: BlockId<Block>, justification: Option<Justification>, notify: bool) -> error::Result<()> {
-		self.lock_import_and_run(|operation| {
-			let last_best = self.backend.blockchain().info().best_hash;
-			let to_finalize_hash = self.backend.blockchain().expect_block_hash_from_id(&id)?;
-			self.apply_finality_with_block_hash(operation, to_finalize_hash, justification, last_best, notify)
-		})
-	}
-
 	/// Attempts to revert the chain by `n` blocks. Returns the number of blocks that were
 	/// successfully reverted.
 	pub fn revert(&self, n: NumberFor<Block>) -> error::Result<NumberFor<Block>> {
@@ -1243,6 +1436,14 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		}
 	}
 
+	/// Get the resource usage of the backend, if it is tracked.
+	///
+	/// This may measure the database on disk, so unlike `info` it isn't meant to be called on
+	/// every block.
+	pub fn usage_info(&self) -> Option<backend::UsageInfo> {
+		self.backend.usage_info()
+	}
+
 	/// Get block status.
 	pub fn block_status(&self, id: &BlockId<Block>) -> error::Result<BlockStatus> {
 		// this can probably be implemented more efficiently
@@ -1324,6 +1525,25 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Ok(uncles)
 	}
 
+	/// Gets the headers of the uncles of the block with `target_hash` going back
+	/// `max_generation` ancestors.
+	///
+	/// Uncles whose headers are not known locally are skipped.
+	pub fn uncle_headers(
+		&self,
+		target_hash: Block::Hash,
+		max_generation: NumberFor<Block>,
+	) -> error::Result<Vec<Block::Header>> {
+		let mut headers = Vec::new();
+		for hash in self.uncles(target_hash, max_generation)? {
+			if let Some(header) = self.backend.blockchain().header(BlockId::Hash(hash))? {
+				headers.push(header);
+			}
+		}
+
+		Ok(headers)
+	}
+
 	fn changes_trie_config(&self) -> Result<Option<ChangesTrieConfiguration>, Error> {
 		Ok(self.backend.state_at(BlockId::Number(self.backend.blockchain().info().best_number))?
 			.storage(well_known_keys::CHANGES_TRIE_CONFIG)
@@ -1545,6 +1765,35 @@ impl<B, E, Block, RA> BlockNumberToHash for Client<B, E, Block, RA> where
 }
 
 
+impl<B, E, Block, RA> Finalizer<Block, Blake2Hasher, B> for Client<B, E, Block, RA> where
+	B: backend::Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+	Block: BlockT<Hash=H256>,
+{
+	fn apply_finality(
+		&self,
+		operation: &mut ClientImportOperation<Block, Blake2Hasher, B>,
+		id: BlockId<Block>,
+		justification: Option<Justification>,
+		notify: bool,
+	) -> error::Result<()> {
+		let last_best = self.backend.blockchain().info().best_hash;
+		let to_finalize_hash = self.backend.blockchain().expect_block_hash_from_id(&id)?;
+		self.apply_finality_with_block_hash(operation, to_finalize_hash, justification, last_best, notify)
+	}
+
+	fn finalize_block(
+		&self,
+		id: BlockId<Block>,
+		justification: Option<Justification>,
+		notify: bool,
+	) -> error::Result<()> {
+		self.lock_import_and_run(|operation| {
+			self.apply_finality(operation, id, justification, notify)
+		})
+	}
+}
+
 impl<B, E, Block, RA> BlockchainEvents<Block> for Client<B, E, Block, RA>
 where
 	E: CallExecutor<Block, Blake2Hasher>,
@@ -1952,6 +2201,78 @@ pub(crate) mod tests {
 		);
 	}
 
+	#[test]
+	fn block_builder_changes_are_imported_without_execution() {
+		use consensus::BlockImport;
+
+		let client = test_client::new();
+
+		let mut builder = client.new_block(Default::default()).unwrap();
+
+		builder.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		}).unwrap();
+
+		let (block, changes) = builder.bake_and_extract_changes().unwrap();
+		let (header, extrinsics) = block.deconstruct();
+		let import = BlockImportParams {
+			origin: BlockOrigin::Own,
+			header,
+			justification: None,
+			post_digests: vec![],
+			body: Some(extrinsics),
+			finalized: false,
+			auxiliary: Vec::new(),
+			fork_choice: ForkChoiceStrategy::LongestChain,
+			storage_changes: Some(changes),
+		};
+		(&client).import_block(import, HashMap::new()).unwrap();
+
+		assert_eq!(client.info().chain.best_number, 1);
+		assert_eq!(
+			client.runtime_api().balance_of(
+				&BlockId::Number(1),
+				AccountKeyring::Ferdie.into()
+			).unwrap(),
+			42
+		);
+	}
+
+	#[test]
+	fn import_block_writes_auxiliary_data() {
+		use consensus::BlockImport;
+		use crate::backend::AuxStore;
+
+		let client = test_client::new();
+
+		let import = |block: Block, auxiliary| {
+			let (header, extrinsics) = block.deconstruct();
+			let import = BlockImportParams {
+				origin: BlockOrigin::Own,
+				header,
+				justification: None,
+				post_digests: vec![],
+				body: Some(extrinsics),
+				finalized: false,
+				auxiliary,
+				fork_choice: ForkChoiceStrategy::LongestChain,
+				storage_changes: None,
+			};
+			(&client).import_block(import, HashMap::new()).unwrap();
+		};
+
+		let a1 = client.new_block(Default::default()).unwrap().bake().unwrap();
+		import(a1.clone(), vec![(b"key".to_vec(), Some(b"value".to_vec()))]);
+		assert_eq!(client.get_aux(b"key").unwrap(), Some(b"value".to_vec()));
+
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap().bake().unwrap();
+		import(a2, vec![(b"key".to_vec(), None)]);
+		assert_eq!(client.get_aux(b"key").unwrap(), None);
+	}
+
 	#[test]
 	fn block_builder_does_not_include_invalid() {
 		let client = test_client::new();
@@ -2043,23 +2364,23 @@ pub(crate) mod tests {
 		client.import(BlockOrigin::Own, a1.clone()).unwrap();
 
 		// A1 -> A2
-		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap().bake().unwrap();
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a2.clone()).unwrap();
 
 		// A2 -> A3
-		let a3 = client.new_block_at(&BlockId::Hash(a2.hash()), Default::default()).unwrap().bake().unwrap();
+		let a3 = client.new_block_at(&BlockId::Hash(a2.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a3.clone()).unwrap();
 
 		// A3 -> A4
-		let a4 = client.new_block_at(&BlockId::Hash(a3.hash()), Default::default()).unwrap().bake().unwrap();
+		let a4 = client.new_block_at(&BlockId::Hash(a3.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a4.clone()).unwrap();
 
 		// A4 -> A5
-		let a5 = client.new_block_at(&BlockId::Hash(a4.hash()), Default::default()).unwrap().bake().unwrap();
+		let a5 = client.new_block_at(&BlockId::Hash(a4.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a5.clone()).unwrap();
 
 		// A1 -> B2
-		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap();
+		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap();
 		// this push is required as otherwise B2 has the same hash as A2 and won't get imported
 		builder.push_transfer(Transfer {
 			from: AccountKeyring::Alice.into(),
@@ -2071,15 +2392,15 @@ pub(crate) mod tests {
 		client.import(BlockOrigin::Own, b2.clone()).unwrap();
 
 		// B2 -> B3
-		let b3 = client.new_block_at(&BlockId::Hash(b2.hash()), Default::default()).unwrap().bake().unwrap();
+		let b3 = client.new_block_at(&BlockId::Hash(b2.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, b3.clone()).unwrap();
 
 		// B3 -> B4
-		let b4 = client.new_block_at(&BlockId::Hash(b3.hash()), Default::default()).unwrap().bake().unwrap();
+		let b4 = client.new_block_at(&BlockId::Hash(b3.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, b4.clone()).unwrap();
 
 		// // B2 -> C3
-		let mut builder = client.new_block_at(&BlockId::Hash(b2.hash()), Default::default()).unwrap();
+		let mut builder = client.new_block_at(&BlockId::Hash(b2.hash()), Default::default(), false).unwrap();
 		// this push is required as otherwise C3 has the same hash as B3 and won't get imported
 		builder.push_transfer(Transfer {
 			from: AccountKeyring::Alice.into(),
@@ -2091,7 +2412,7 @@ pub(crate) mod tests {
 		client.import(BlockOrigin::Own, c3.clone()).unwrap();
 
 		// A1 -> D2
-		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap();
+		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap();
 		// this push is required as otherwise D2 has the same hash as B2 and won't get imported
 		builder.push_transfer(Transfer {
 			from: AccountKeyring::Alice.into(),
@@ -2159,23 +2480,23 @@ pub(crate) mod tests {
 		client.import(BlockOrigin::Own, a1.clone()).unwrap();
 
 		// A1 -> A2
-		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap().bake().unwrap();
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a2.clone()).unwrap();
 
 		// A2 -> A3
-		let a3 = client.new_block_at(&BlockId::Hash(a2.hash()), Default::default()).unwrap().bake().unwrap();
+		let a3 = client.new_block_at(&BlockId::Hash(a2.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a3.clone()).unwrap();
 
 		// A3 -> A4
-		let a4 = client.new_block_at(&BlockId::Hash(a3.hash()), Default::default()).unwrap().bake().unwrap();
+		let a4 = client.new_block_at(&BlockId::Hash(a3.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a4.clone()).unwrap();
 
 		// A4 -> A5
-		let a5 = client.new_block_at(&BlockId::Hash(a4.hash()), Default::default()).unwrap().bake().unwrap();
+		let a5 = client.new_block_at(&BlockId::Hash(a4.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a5.clone()).unwrap();
 
 		// A1 -> B2
-		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap();
+		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap();
 		// this push is required as otherwise B2 has the same hash as A2 and won't get imported
 		builder.push_transfer(Transfer {
 			from: AccountKeyring::Alice.into(),
@@ -2187,15 +2508,15 @@ pub(crate) mod tests {
 		client.import(BlockOrigin::Own, b2.clone()).unwrap();
 
 		// B2 -> B3
-		let b3 = client.new_block_at(&BlockId::Hash(b2.hash()), Default::default()).unwrap().bake().unwrap();
+		let b3 = client.new_block_at(&BlockId::Hash(b2.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, b3.clone()).unwrap();
 
 		// B3 -> B4
-		let b4 = client.new_block_at(&BlockId::Hash(b3.hash()), Default::default()).unwrap().bake().unwrap();
+		let b4 = client.new_block_at(&BlockId::Hash(b3.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, b4.clone()).unwrap();
 
 		// // B2 -> C3
-		let mut builder = client.new_block_at(&BlockId::Hash(b2.hash()), Default::default()).unwrap();
+		let mut builder = client.new_block_at(&BlockId::Hash(b2.hash()), Default::default(), false).unwrap();
 		// this push is required as otherwise C3 has the same hash as B3 and won't get imported
 		builder.push_transfer(Transfer {
 			from: AccountKeyring::Alice.into(),
@@ -2207,7 +2528,7 @@ pub(crate) mod tests {
 		client.import(BlockOrigin::Own, c3.clone()).unwrap();
 
 		// A1 -> D2
-		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap();
+		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap();
 		// this push is required as otherwise D2 has the same hash as B2 and won't get imported
 		builder.push_transfer(Transfer {
 			from: AccountKeyring::Alice.into(),
@@ -2466,6 +2787,26 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn keys_changes_works() {
+		let (client, _, test_cases) = prepare_client_with_key_changes();
+
+		for (index, (begin, end, key, expected_result)) in test_cases.into_iter().enumerate() {
+			let end = client.block_hash(end).unwrap().unwrap();
+			let key = StorageKey(key);
+			let actual_result = client.keys_changes(begin, BlockId::Hash(end), &[key.clone(), key.clone()]).unwrap();
+			let mut expected_blocks = expected_result.into_iter().map(|(block, _)| block).collect::<Vec<_>>();
+			expected_blocks.sort();
+			expected_blocks.dedup();
+			assert_eq!(
+				actual_result.keys().cloned().collect::<Vec<_>>(),
+				expected_blocks,
+				"Failed test {}", index,
+			);
+			assert!(actual_result.values().all(|keys| keys == &vec![key.clone()]));
+		}
+	}
+
 	#[test]
 	fn import_with_justification() {
 		use test_client::blockchain::Backend;
@@ -2477,12 +2818,12 @@ pub(crate) mod tests {
 		client.import(BlockOrigin::Own, a1.clone()).unwrap();
 
 		// A1 -> A2
-		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap().bake().unwrap();
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a2.clone()).unwrap();
 
 		// A2 -> A3
 		let justification = vec![1, 2, 3];
-		let a3 = client.new_block_at(&BlockId::Hash(a2.hash()), Default::default()).unwrap().bake().unwrap();
+		let a3 = client.new_block_at(&BlockId::Hash(a2.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import_justified(BlockOrigin::Own, a3.clone(), justification.clone()).unwrap();
 
 		#[allow(deprecated)]
@@ -2518,13 +2859,13 @@ pub(crate) mod tests {
 		// G -> A1 -> A2
 		//   \
 		//    -> B1
-		let a1 = client.new_block_at(&BlockId::Number(0), Default::default()).unwrap().bake().unwrap();
+		let a1 = client.new_block_at(&BlockId::Number(0), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a1.clone()).unwrap();
 
-		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap().bake().unwrap();
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a2.clone()).unwrap();
 
-		let mut b1 = client.new_block_at(&BlockId::Number(0), Default::default()).unwrap();
+		let mut b1 = client.new_block_at(&BlockId::Number(0), Default::default(), false).unwrap();
 		// needed to make sure B1 gets a different hash from A1
 		b1.push_transfer(Transfer {
 			from: AccountKeyring::Alice.into(),
@@ -2568,13 +2909,13 @@ pub(crate) mod tests {
 		// G -> A1 -> A2
 		//   \
 		//    -> B1 -> B2
-		let a1 = client.new_block_at(&BlockId::Number(0), Default::default()).unwrap().bake().unwrap();
+		let a1 = client.new_block_at(&BlockId::Number(0), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a1.clone()).unwrap();
 
-		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap().bake().unwrap();
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, a2.clone()).unwrap();
 
-		let mut b1 = client.new_block_at(&BlockId::Number(0), Default::default()).unwrap();
+		let mut b1 = client.new_block_at(&BlockId::Number(0), Default::default(), false).unwrap();
 		// needed to make sure B1 gets a different hash from A1
 		b1.push_transfer(Transfer {
 			from: AccountKeyring::Alice.into(),
@@ -2585,7 +2926,7 @@ pub(crate) mod tests {
 		let b1 = b1.bake().unwrap();
 		client.import(BlockOrigin::Own, b1.clone()).unwrap();
 
-		let b2 = client.new_block_at(&BlockId::Hash(b1.hash()), Default::default()).unwrap().bake().unwrap();
+		let b2 = client.new_block_at(&BlockId::Hash(b1.hash()), Default::default(), false).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, b2.clone()).unwrap();
 
 		#[allow(deprecated)]
@@ -2599,7 +2940,7 @@ pub(crate) mod tests {
 
 		// we finalize block B1 which is on a different branch from current best
 		// which should trigger a re-org.
-		client.finalize_block(BlockId::Hash(b1.hash()), None, false).unwrap();
+		Finalizer::finalize_block(&client, BlockId::Hash(b1.hash()), None, false).unwrap();
 
 		// B1 should now be the latest finalized
 		assert_eq!(
@@ -2625,6 +2966,7 @@ pub(crate) mod tests {
 		let b3 = client.new_block_at(
 			&BlockId::Hash(b2.hash()),
 			Default::default(),
+			false,
 		).unwrap().bake().unwrap();
 		client.import(BlockOrigin::Own, b3.clone()).unwrap();
 
@@ -2634,6 +2976,105 @@ pub(crate) mod tests {
 		);
 	}
 
+	#[test]
+	fn import_notifications_report_reorganizations() {
+		// block tree:
+		// G -> A1 -> A2
+		//      A1 -> B2 -> B3
+		let client = test_client::new();
+		let mut notifications = client.import_notification_stream();
+
+		let a1 = client.new_block(Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, a2.clone()).unwrap();
+
+		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap();
+		// needed to make sure B2 has a different hash than A2
+		builder.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 1,
+			nonce: 0,
+		}).unwrap();
+		let b2 = builder.bake().unwrap();
+		client.import(BlockOrigin::NetworkBroadcast, b2.clone()).unwrap();
+
+		let b3 = client.new_block_at(&BlockId::Hash(b2.hash()), Default::default(), false).unwrap().bake().unwrap();
+		client.import(BlockOrigin::NetworkBroadcast, b3.clone()).unwrap();
+
+		let mut next = || notifications.try_next().unwrap().unwrap();
+
+		// extending the best chain isn't a reorganization.
+		let notification = next();
+		assert_eq!(notification.hash, a1.hash());
+		assert!(notification.is_new_best);
+		assert!(notification.retracted.is_empty() && notification.enacted.is_empty());
+		assert_eq!(next().hash, a2.hash());
+
+		// B2 doesn't become the best block.
+		let notification = next();
+		assert_eq!(notification.hash, b2.hash());
+		assert_eq!(notification.origin, BlockOrigin::NetworkBroadcast);
+		assert!(!notification.is_new_best);
+		assert!(notification.retracted.is_empty() && notification.enacted.is_empty());
+
+		// B3 switches the best chain from A2 to B3.
+		let notification = next();
+		assert_eq!(notification.hash, b3.hash());
+		assert!(notification.is_new_best);
+		assert_eq!(notification.retracted, vec![a2.hash()]);
+		assert_eq!(notification.enacted, vec![b2.hash()]);
+	}
+
+	#[test]
+	fn finality_notifications_report_stale_heads() {
+		// block tree:
+		// G -> A1 -> A2 -> A3
+		//      A1 -> B2
+		let client = test_client::new();
+		let mut notifications = client.finality_notification_stream();
+
+		let a1 = client.new_block(Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, a2.clone()).unwrap();
+
+		let a3 = client.new_block_at(&BlockId::Hash(a2.hash()), Default::default(), false).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, a3.clone()).unwrap();
+
+		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false).unwrap();
+		// needed to make sure B2 has a different hash than A2
+		builder.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 1,
+			nonce: 0,
+		}).unwrap();
+		let b2 = builder.bake().unwrap();
+		client.import(BlockOrigin::Own, b2.clone()).unwrap();
+
+		Finalizer::finalize_block(&client, BlockId::Hash(a2.hash()), None, true).unwrap();
+
+		let mut next = || notifications.try_next().unwrap().unwrap();
+
+		// only the last finalized block carries the stale heads.
+		let notification = next();
+		assert_eq!(notification.hash, a1.hash());
+		assert!(notification.stale_heads.is_empty());
+
+		let notification = next();
+		assert_eq!(notification.hash, a2.hash());
+		assert_eq!(notification.stale_heads, vec![b2.hash()]);
+
+		// the stale branch is displaced from the leaves.
+		#[allow(deprecated)]
+		let leaves = client.backend().blockchain().leaves().unwrap();
+		assert_eq!(leaves, vec![a3.hash()]);
+	}
+
 	#[test]
 	fn get_header_by_block_number_doesnt_panic() {
 		let client = test_client::new();
@@ -2643,4 +3084,145 @@ pub(crate) mod tests {
 		let id = BlockId::<Block>::Number(72340207214430721);
 		client.header(&id).expect_err("invalid block number overflows u32");
 	}
+
+	#[test]
+	fn aux_data_is_pruned_on_finality() {
+		use crate::aux_pruning::{AuxDataPruner, FinalizedBlocks};
+		use crate::blockchain::HeaderBackend;
+		use crate::backend::AuxStore;
+
+		fn aux_key(hash: &H256) -> Vec<u8> {
+			let mut key = b"test_block_data".to_vec();
+			key.extend_from_slice(hash.as_ref());
+			key
+		}
+
+		// keeps data only for the last finalized block and the blocks that may still be finalized.
+		struct TestPruner;
+
+		impl AuxDataPruner<Block> for TestPruner {
+			fn prune(
+				&self,
+				blocks: &FinalizedBlocks<Block>,
+				_headers: &dyn HeaderBackend<Block>,
+				_get_aux: &dyn Fn(&[u8]) -> error::Result<Option<Vec<u8>>>,
+			) -> error::Result<Vec<(Vec<u8>, Option<Vec<u8>>)>> {
+				let finalized = &blocks.finalized[..blocks.finalized.len() - 1];
+				Ok(std::iter::once(&blocks.previous)
+					.chain(finalized.iter())
+					.chain(blocks.displaced.iter())
+					.map(|entry| (aux_key(&entry.hash), None))
+					.collect())
+			}
+		}
+
+		let client = test_client::new();
+		client.register_aux_pruner(Box::new(TestPruner));
+
+		// G -> A1 -> A2 -> ... -> A30
+		//  \     \     \
+		//   B1    B2    B3 ...
+		let mut main_chain = vec![client.info().chain.genesis_hash];
+		let mut forks = Vec::new();
+		for _ in 0..30 {
+			let parent = *main_chain.last().unwrap();
+
+			let a = client.new_block_at(&BlockId::Hash(parent), Default::default(), false).unwrap().bake().unwrap();
+			client.import(BlockOrigin::Own, a.clone()).unwrap();
+
+			let mut b = client.new_block_at(&BlockId::Hash(parent), Default::default(), false).unwrap();
+			b.push_transfer(Transfer {
+				from: AccountKeyring::Alice.into(),
+				to: AccountKeyring::Ferdie.into(),
+				amount: 1,
+				nonce: 0,
+			}).unwrap();
+			let b = b.bake().unwrap();
+			client.import(BlockOrigin::Own, b.clone()).unwrap();
+
+			for hash in &[a.hash(), b.hash()] {
+				client.insert_aux(&[(&aux_key(hash)[..], &b"data"[..])], &[]).unwrap();
+			}
+			main_chain.push(a.hash());
+			forks.push(b.hash());
+
+			let number = main_chain.len() - 1;
+			if number % 7 == 0 {
+				Finalizer::finalize_block(&client, BlockId::Hash(a.hash()), None, false).unwrap();
+			}
+		}
+
+		// A28 is the last finalized block; A29 and A30 are still pending.
+		let has_aux = |hash: &H256| client.get_aux(&aux_key(hash)).unwrap().is_some();
+		for (number, hash) in main_chain.iter().enumerate().skip(1) {
+			assert_eq!(has_aux(hash), number >= 28, "block A{}", number);
+		}
+		for (i, hash) in forks.iter().enumerate() {
+			// B29 and B30 fork off at or after A28 and may still be finalized.
+			assert_eq!(has_aux(hash), i + 1 >= 29, "block B{}", i + 1);
+		}
+	}
+
+	#[test]
+	fn aux_data_is_pruned_for_blocks_imported_as_final() {
+		use crate::aux_pruning::{AuxDataPruner, FinalizedBlocks};
+		use crate::blockchain::HeaderBackend;
+		use crate::backend::AuxStore;
+
+		fn aux_key(hash: &H256) -> Vec<u8> {
+			let mut key = b"test_block_data".to_vec();
+			key.extend_from_slice(hash.as_ref());
+			key
+		}
+
+		// drops the data of the previously finalized block and of the displaced blocks.
+		struct TestPruner;
+
+		impl AuxDataPruner<Block> for TestPruner {
+			fn prune(
+				&self,
+				blocks: &FinalizedBlocks<Block>,
+				headers: &dyn HeaderBackend<Block>,
+				_get_aux: &dyn Fn(&[u8]) -> error::Result<Option<Vec<u8>>>,
+			) -> error::Result<Vec<(Vec<u8>, Option<Vec<u8>>)>> {
+				let finalized = blocks.finalized.last().expect("blocks are finalized; qed");
+				headers.expect_header(BlockId::Hash(finalized.hash))?;
+				Ok(std::iter::once(&blocks.previous)
+					.chain(blocks.displaced.iter())
+					.map(|entry| (aux_key(&entry.hash), None))
+					.collect())
+			}
+		}
+
+		let client = test_client::new();
+		client.register_aux_pruner(Box::new(TestPruner));
+
+		// G -> A1 -> A2
+		//  		//   B1
+		let a1 = client.new_block(Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+
+		let genesis = BlockId::Hash(client.info().chain.genesis_hash);
+		let mut b1 = client.new_block_at(&genesis, Default::default(), false).unwrap();
+		b1.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 1,
+			nonce: 0,
+		}).unwrap();
+		let b1 = b1.bake().unwrap();
+		client.import(BlockOrigin::Own, b1.clone()).unwrap();
+
+		for hash in &[a1.hash(), b1.hash()] {
+			client.insert_aux(&[(&aux_key(hash)[..], &b"data"[..])], &[]).unwrap();
+		}
+
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default(), false)
+			.unwrap().bake().unwrap();
+		client.import_justified(BlockOrigin::Own, a2.clone(), Vec::new()).unwrap();
+
+		assert_eq!(client.info().chain.finalized_hash, a2.hash());
+		assert!(client.get_aux(&aux_key(&a1.hash())).unwrap().is_none());
+		assert!(client.get_aux(&aux_key(&b1.hash())).unwrap().is_none());
+	}
 }
@@ -67,6 +67,9 @@ pub enum Error {
 	/// Invalid remote CHT-based proof.
 	#[display(fmt = "Remote node has responded with invalid header proof")]
 	InvalidCHTProof,
+	/// Invalid remote compact proof.
+	#[display(fmt = "Remote node has responded with invalid compact proof")]
+	InvalidCompactProof,
 	/// Remote fetch has been cancelled.
 	#[display(fmt = "Remote data fetch has been cancelled")]
 	RemoteFetchCancelled,
@@ -348,8 +348,11 @@ impl<Block: BlockT> blockchain::Backend<Block> for Blockchain<Block> {
 		Ok(self.storage.read().leaves.hashes())
 	}
 
-	fn children(&self, _parent_hash: Block::Hash) -> error::Result<Vec<Block::Hash>> {
-		unimplemented!()
+	fn children(&self, parent_hash: Block::Hash) -> error::Result<Vec<Block::Hash>> {
+		Ok(self.storage.read().blocks.iter()
+			.filter(|(_, block)| block.header().parent_hash() == &parent_hash)
+			.map(|(hash, _)| hash.clone())
+			.collect())
 	}
 }
 
@@ -433,6 +436,14 @@ impl<Block: BlockT> light::blockchain::Storage<Block> for Blockchain<Block>
 	fn cache(&self) -> Option<Arc<dyn blockchain::Cache<Block>>> {
 		None
 	}
+
+	fn leaves(&self) -> error::Result<Vec<Block::Hash>> {
+		blockchain::Backend::leaves(self)
+	}
+
+	fn children(&self, parent_hash: Block::Hash) -> error::Result<Vec<Block::Hash>> {
+		blockchain::Backend::children(self, parent_hash)
+	}
 }
 
 /// In-memory operation.
@@ -666,6 +677,10 @@ where
 		None
 	}
 
+	fn usage_info(&self) -> Option<backend::UsageInfo> {
+		None
+	}
+
 	fn changes_trie_storage(&self) -> Option<&Self::ChangesTrieStorage> {
 		Some(&self.changes_trie_storage)
 	}
@@ -45,11 +45,12 @@ pub struct FinalizationDisplaced<H, N> {
 impl<H, N: Ord> FinalizationDisplaced<H, N> {
 	/// Merge with another. This should only be used for displaced items that
 	/// are produced within one transaction of each other.
-	pub fn merge(&mut self, mut other: Self) {
-		// this will ignore keys that are in duplicate, however
-		// if these are actually produced correctly via the leaf-set within
-		// one transaction, then there will be no overlap in the keys.
-		self.leaves.append(&mut other.leaves);
+	pub fn merge(&mut self, other: Self) {
+		// leaves displaced for being stale may share a height with leaves
+		// displaced by an earlier finalization, so hashes are merged per height.
+		for (number, hashes) in other.leaves {
+			self.leaves.entry(number).or_insert_with(Vec::new).extend(hashes);
+		}
 	}
 }
 
@@ -148,6 +149,37 @@ impl<H, N> LeafSet<H, N> where
 		}
 	}
 
+	/// Note a block finalized, displacing all leaves which are not its descendants.
+	///
+	/// In addition to the leaves below the finalized height (see `finalize_height`), this
+	/// displaces the leaves at or above it for which `is_descendent_of(leaf_hash, leaf_number)`
+	/// returns `false`. The leaf set is left untouched if the predicate fails.
+	pub fn finalize_block<F>(
+		&mut self,
+		number: N,
+		mut is_descendent_of: F,
+	) -> error::Result<FinalizationDisplaced<H, N>> where
+		F: FnMut(&H, &N) -> error::Result<bool>,
+	{
+		let mut stale = Vec::new();
+		for (leaf_number, hashes) in self.storage.range(..=Reverse(number.clone())) {
+			for hash in hashes {
+				if !is_descendent_of(hash, &leaf_number.0)? {
+					stale.push((leaf_number.clone(), hash.clone()));
+				}
+			}
+		}
+
+		let mut displaced = self.finalize_height(number);
+		for (leaf_number, hash) in stale {
+			self.remove_leaf(&leaf_number, &hash);
+			self.pending_removed.push(hash.clone());
+			displaced.leaves.entry(leaf_number).or_insert_with(Vec::new).push(hash);
+		}
+
+		Ok(displaced)
+	}
+
 	/// Undo all pending operations.
 	///
 	/// This returns an `Undo` struct, where any
@@ -238,8 +270,12 @@ impl<'a, H: 'a, N: 'a> Undo<'a, H, N> where
 	}
 
 	/// Undo a finalization operation by providing the displaced leaves.
-	pub fn undo_finalization(&mut self, mut displaced: FinalizationDisplaced<H, N>) {
-		self.inner.storage.append(&mut displaced.leaves);
+	pub fn undo_finalization(&mut self, displaced: FinalizationDisplaced<H, N>) {
+		for (number, hashes) in displaced.leaves {
+			for hash in hashes {
+				self.inner.insert_leaf(number.clone(), hash);
+			}
+		}
 	}
 }
 
@@ -353,4 +389,38 @@ mod tests {
 		set.undo().undo_finalization(displaced);
 		assert!(set.contains(10, 10_1));
 	}
+
+	#[test]
+	fn finalize_block_displaces_stale_leaves() {
+		let mut set = LeafSet::new();
+		set.import(10_1u32, 10u32, 0u32);
+		set.import(11_1, 11, 10_2);
+		set.import(11_2, 11, 10_3);
+		set.import(12_1, 12, 11_3);
+
+		// 11_1 is finalized, so 11_2 and 12_1 are on stale branches.
+		let displaced = set.finalize_block(11, |hash, _| Ok(*hash == 11_1)).unwrap();
+		assert!(!set.contains(10, 10_1));
+		assert!(!set.contains(11, 11_2));
+		assert!(!set.contains(12, 12_1));
+		assert!(set.contains(11, 11_1));
+
+		set.undo().undo_finalization(displaced);
+		assert!(set.contains(10, 10_1));
+		assert!(set.contains(11, 11_1));
+		assert!(set.contains(11, 11_2));
+		assert!(set.contains(12, 12_1));
+	}
+
+	#[test]
+	fn finalize_block_leaves_set_untouched_on_error() {
+		let mut set = LeafSet::new();
+		set.import(10_1u32, 10u32, 0u32);
+		set.import(11_1, 11, 10_2);
+
+		let result = set.finalize_block(11, |_, _| Err(error::Error::Backend("missing header".into())));
+		assert!(result.is_err());
+		assert!(set.contains(10, 10_1));
+		assert!(set.contains(11, 11_1));
+	}
 }
@@ -47,6 +47,8 @@ mod call_executor;
 mod client;
 #[cfg(feature = "std")]
 mod notifications;
+#[cfg(feature = "std")]
+pub mod aux_pruning;
 
 
 #[cfg(feature = "std")]
@@ -59,12 +61,12 @@ pub use crate::client::{
 	new_in_mem,
 	BlockBody, BlockStatus, ImportNotifications, FinalityNotifications, BlockchainEvents,
 	BlockImportNotification, Client, ClientInfo, ExecutionStrategies, FinalityNotification,
-	LongestChain,
+	Finalizer, LongestChain,
 };
 #[cfg(feature = "std")]
 pub use crate::notifications::{StorageEventStream, StorageChangeSet};
 #[cfg(feature = "std")]
-pub use state_machine::{ExecutionStrategy, NeverOffchainExt};
+pub use state_machine::{ExecutionStrategy, NeverOffchainExt, StorageProof, CompactProof};
 #[cfg(feature = "std")]
 pub use crate::leaves::LeafSet;
 
@@ -70,6 +70,12 @@ pub trait Storage<Block: BlockT>: AuxStore + BlockchainHeaderBackend<Block> {
 
 	/// Get storage cache.
 	fn cache(&self) -> Option<Arc<dyn BlockchainCache<Block>>>;
+
+	/// Get hashes of all blocks that are leaves of the block tree.
+	fn leaves(&self) -> ClientResult<Vec<Block::Hash>>;
+
+	/// Get hashes of the known children of the given block.
+	fn children(&self, parent_hash: Block::Hash) -> ClientResult<Vec<Block::Hash>>;
 }
 
 /// Light client blockchain.
@@ -180,11 +186,11 @@ impl<S, F, Block> BlockchainBackend<Block> for Blockchain<S, F> where Block: Blo
 	}
 
 	fn leaves(&self) -> ClientResult<Vec<Block::Hash>> {
-		unimplemented!()
+		self.storage.leaves()
 	}
 
-	fn children(&self, _parent_hash: Block::Hash) -> ClientResult<Vec<Block::Hash>> {
-		unimplemented!()
+	fn children(&self, parent_hash: Block::Hash) -> ClientResult<Vec<Block::Hash>> {
+		self.storage.children(parent_hash)
 	}
 }
 
@@ -306,5 +312,13 @@ pub mod tests {
 		fn cache(&self) -> Option<Arc<dyn BlockchainCache<Block>>> {
 			None
 		}
+
+		fn leaves(&self) -> ClientResult<Vec<Hash>> {
+			Err(ClientError::Backend("Test error".into()))
+		}
+
+		fn children(&self, _parent_hash: Hash) -> ClientResult<Vec<Hash>> {
+			Err(ClientError::Backend("Test error".into()))
+		}
 	}
 }
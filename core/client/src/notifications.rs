@@ -31,8 +31,8 @@ use runtime_primitives::traits::Block as BlockT;
 pub struct StorageChangeSet {
 	changes: Arc<Vec<(StorageKey, Option<StorageData>)>>,
 	child_changes: Arc<Vec<(StorageKey, Vec<(StorageKey, Option<StorageData>)>)>>,
-	filter: Option<HashSet<StorageKey>>,
-	child_filters: Option<HashMap<StorageKey, Option<HashSet<StorageKey>>>>,
+	filter: Keys,
+	child_filters: ChildKeys,
 }
 
 impl StorageChangeSet {
@@ -69,6 +69,11 @@ impl StorageChangeSet {
 /// Type that implements `futures::Stream` of storage change events.
 pub type StorageEventStream<H> = mpsc::UnboundedReceiver<(H, StorageChangeSet)>;
 
+// filters are shared between the subscriber and every change set sent to it,
+// so notifying a subscriber doesn't copy its keys.
+type Keys = Option<Arc<HashSet<StorageKey>>>;
+type ChildKeys = Option<Arc<HashMap<StorageKey, Option<HashSet<StorageKey>>>>>;
+
 type SubscriberId = u64;
 
 /// Manages storage listeners.
@@ -83,8 +88,8 @@ pub struct StorageNotifications<Block: BlockT> {
 	)>,
 	sinks: FnvHashMap<SubscriberId, (
 		mpsc::UnboundedSender<(Block::Hash, StorageChangeSet)>,
-		Option<HashSet<StorageKey>>,
-		Option<HashMap<StorageKey, Option<HashSet<StorageKey>>>>,
+		Keys,
+		ChildKeys,
 	)>,
 }
 
@@ -120,7 +125,7 @@ impl<Block: BlockT> StorageNotifications<Block> {
 			return;
 		}
 
-		let mut subscribers = self.wildcard_listeners.clone();
+		let mut subscribers = FnvHashSet::default();
 		let mut changes = Vec::new();
 		let mut child_changes = Vec::new();
 
@@ -166,6 +171,11 @@ impl<Block: BlockT> StorageNotifications<Block> {
 			return;
 		}
 
+		// wildcard listeners only care about the top-level changes
+		if has_wildcard && !changes.is_empty() {
+			subscribers.extend(self.wildcard_listeners.iter());
+		}
+
 		let changes = Arc::new(changes);
 		let child_changes = Arc::new(child_changes);
 		// Trigger the events
@@ -189,7 +199,7 @@ impl<Block: BlockT> StorageNotifications<Block> {
 
 	fn remove_subscriber_from(
 		subscriber: &SubscriberId,
-		filters: &Option<HashSet<StorageKey>>,
+		filters: Option<&HashSet<StorageKey>>,
 		listeners: &mut HashMap<StorageKey, FnvHashSet<SubscriberId>>,
 		wildcards: &mut FnvHashSet<SubscriberId>,
 	){
@@ -220,17 +230,17 @@ impl<Block: BlockT> StorageNotifications<Block> {
 		if let Some((_, filters, child_filters)) = self.sinks.remove(&subscriber) {
 			Self::remove_subscriber_from(
 				&subscriber,
-				&filters,
+				filters.as_ref().map(|filters| &**filters),
 				&mut self.listeners,
 				&mut self.wildcard_listeners,
 			);
 			if let Some(child_filters) = child_filters.as_ref() {
-				for (c_key, filters) in child_filters {
+				for (c_key, filters) in child_filters.iter() {
 
 					if let Some((listeners, wildcards)) = self.child_listeners.get_mut(&c_key) {
 						Self::remove_subscriber_from(
 							&subscriber,
-							&filters,
+							filters.as_ref(),
 							&mut *listeners,
 							&mut *wildcards,
 						);
@@ -300,7 +310,7 @@ impl<Block: BlockT> StorageNotifications<Block> {
 
 		// insert sink
 		let (tx, rx) = mpsc::unbounded();
-		self.sinks.insert(current_id, (tx, keys, child_keys));
+		self.sinks.insert(current_id, (tx, keys.map(Arc::new), child_keys.map(Arc::new)));
 		rx
 	}
 }
@@ -320,10 +330,10 @@ mod tests {
 	impl From<TestChangeSet> for StorageChangeSet {
 		fn from(changes: TestChangeSet) -> Self {
 			// warning hardcoded child trie wildcard to test upon
-			let child_filters = Some([
+			let child_filters = Some(Arc::new([
 				(StorageKey(vec![4]), None),
 				(StorageKey(vec![5]), None),
-			].into_iter().cloned().collect());
+			].into_iter().cloned().collect()));
 			StorageChangeSet {
 				changes: Arc::new(changes.0),
 				child_changes: Arc::new(changes.1),
@@ -423,6 +433,35 @@ mod tests {
 
 	}
 
+	#[test]
+	fn should_not_notify_wildcard_listeners_about_unwatched_child_changes() {
+		// given
+		let mut notifications = StorageNotifications::<Block>::default();
+		let child_filter = [(StorageKey(vec![4]), None)];
+		let mut recv1 = futures::executor::block_on_stream(
+			notifications.listen(None, None)
+		);
+		let mut recv2 = futures::executor::block_on_stream(
+			notifications.listen(Some(&[]), Some(&child_filter))
+		);
+
+		// when
+		let c_changeset = vec![(vec![4], vec![(vec![5], Some(vec![4]))])];
+		notifications.trigger(
+			&Hash::from_low_u64_be(1),
+			empty(),
+			c_changeset.into_iter().map(|(a,b)| (a, b.into_iter())),
+		);
+		drop(notifications);
+
+		// then
+		assert_eq!(recv1.next(), None);
+		assert_eq!(recv2.next().unwrap(), (Hash::from_low_u64_be(1), (vec![],
+		vec![
+			(StorageKey(vec![4]), vec![(StorageKey(vec![5]), Some(StorageData(vec![4])))]),
+		]).into()));
+	}
+
 	#[test]
 	fn should_cleanup_subscribers_if_dropped() {
 		// given
@@ -18,7 +18,7 @@
 
 #[doc(hidden)]
 #[cfg(feature = "std")]
-pub use state_machine::OverlayedChanges;
+pub use state_machine::{OverlayedChanges, StorageProof, extract_recorded_proof};
 #[doc(hidden)]
 #[cfg(feature = "std")]
 pub use primitives::NativeOrEncoded;
@@ -95,6 +95,17 @@ pub trait ApiExt<Block: BlockT> {
 		self.runtime_version_at(at).map(|v| v.has_api_with::<A, _>(pred))
 	}
 
+	/// Returns the version of the given api implemented at the given block, if any.
+	///
+	/// Call sites can use this to pick between the current and a `#[changed_in(N)]`
+	/// variant of a method.
+	fn api_version<A: RuntimeApiInfo + ?Sized>(
+		&self,
+		at: &BlockId<Block>,
+	) -> error::Result<Option<u32>> where Self: Sized {
+		self.runtime_version_at(at).map(|v| v.api_version(&A::ID))
+	}
+
 	/// Returns the runtime version at the given block id.
 	fn runtime_version_at(&self, at: &BlockId<Block>) -> error::Result<RuntimeVersion>;
 
@@ -103,7 +114,11 @@ pub trait ApiExt<Block: BlockT> {
 
 	/// Extract the recorded proof.
 	/// This stops the proof recording.
-	fn extract_proof(&mut self) -> Option<Vec<Vec<u8>>>;
+	fn extract_proof(&mut self) -> Option<StorageProof>;
+
+	/// Extract the storage changes committed by the api calls made so far.
+	/// The internal changes are reset.
+	fn extract_changes(&mut self) -> OverlayedChanges;
 }
 
 /// Before calling any runtime api function, the runtime need to be initialized
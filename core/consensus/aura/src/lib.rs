@@ -28,7 +28,7 @@
 //!
 //! NOTE: Aura itself is designed to be generic over the crypto used.
 #![forbid(missing_docs, unsafe_code)]
-use std::{sync::Arc, time::Duration, thread, marker::PhantomData, hash::Hash, fmt::Debug};
+use std::{sync::Arc, time::Duration, marker::PhantomData, hash::Hash, fmt::Debug};
 
 use parity_codec::{Encode, Decode, Codec};
 use consensus_common::{self, BlockImport, Environment, Proposer,
@@ -37,6 +37,7 @@ use consensus_common::{self, BlockImport, Environment, Proposer,
 };
 use consensus_common::import_queue::{
 	Verifier, BasicQueue, BoxBlockImport, BoxJustificationImport, BoxFinalityProofImport,
+	VerificationError,
 };
 use client::{
 	block_builder::api::BlockBuilder as BlockBuilderApi,
@@ -44,13 +45,15 @@ use client::{
 	runtime_api::ApiExt,
 	error::Result as CResult,
 	backend::AuxStore,
+	aux_pruning::AuxDataPruner,
 };
 
 use runtime_primitives::{generic::{self, BlockId, OpaqueDigestItemId}, Justification};
 use runtime_primitives::traits::{Block as BlockT, Header, DigestItemFor, ProvideRuntimeApi, Zero, Member};
 
 use primitives::Pair;
-use inherents::{InherentDataProviders, InherentData};
+use keystore::Signer;
+use inherents::{InherentDataProviders, InherentData, RetryHint};
 
 use futures::{Future, IntoFuture, future};
 use parking_lot::Mutex;
@@ -59,12 +62,12 @@ use log::{error, warn, debug, info, trace};
 
 use srml_aura::{
 	InherentType as AuraInherent, AuraInherentData,
-	timestamp::{TimestampInherentData, InherentType as TimestampInherent, InherentError as TIError}
+	timestamp::{TimestampInherentData, InherentType as TimestampInherent}
 };
 use substrate_telemetry::{telemetry, CONSENSUS_TRACE, CONSENSUS_DEBUG, CONSENSUS_WARN, CONSENSUS_INFO};
 
 use slots::{CheckedHeader, SlotData, SlotWorker, SlotInfo, SlotCompatible};
-use slots::{SignedDuration, check_equivocation};
+use slots::{SignedDuration, EquivocationProof, EquivocationPruner, check_equivocation};
 
 pub use aura_primitives::*;
 pub use consensus_common::SyncOracle;
@@ -131,7 +134,7 @@ impl SlotCompatible for AuraSlotCompatible {
 /// Start the aura worker. The returned future should be run in a tokio runtime.
 pub fn start_aura<B, C, SC, E, I, P, SO, Error, H>(
 	slot_duration: SlotDuration,
-	local_key: Arc<P>,
+	local_key: Signer<P>,
 	client: Arc<C>,
 	select_chain: SC,
 	block_import: I,
@@ -148,7 +151,7 @@ pub fn start_aura<B, C, SC, E, I, P, SO, Error, H>(
 	<<E::Proposer as Proposer<B>>::Create as IntoFuture>::Future: Send + 'static,
 	P: Pair + Send + Sync + 'static,
 	P::Public: Hash + Member + Encode + Decode,
-	P::Signature: Hash + Member + Encode + Decode,
+	P::Signature: Hash + Member + Encode + Decode + Default + AsMut<[u8]>,
 	H: Header<Hash=B::Hash>,
 	E: Environment<B, Error=Error>,
 	I: BlockImport<B> + Send + Sync + 'static,
@@ -181,7 +184,7 @@ struct AuraWorker<C, E, I, P, SO> {
 	client: Arc<C>,
 	block_import: Arc<Mutex<I>>,
 	env: Arc<E>,
-	local_key: Arc<P>,
+	local_key: Signer<P>,
 	sync_oracle: SO,
 	force_authoring: bool,
 }
@@ -197,7 +200,7 @@ impl<H, B, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I, P, SO> w
 	I: BlockImport<B> + Send + Sync + 'static,
 	P: Pair + Send + Sync + 'static,
 	P::Public: Member + Encode + Decode + Hash,
-	P::Signature: Member + Encode + Decode + Hash + Debug,
+	P::Signature: Member + Encode + Decode + Hash + Debug + Default + AsMut<[u8]>,
 	SO: SyncOracle + Send + Clone,
 	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
 {
@@ -208,7 +211,7 @@ impl<H, B, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I, P, SO> w
 		chain_head: B::Header,
 		slot_info: SlotInfo,
 	) -> Self::OnSlot {
-		let pair = self.local_key.clone();
+		let signer = self.local_key.clone();
 		let public_key = self.local_key.public();
 		let client = self.client.clone();
 		let block_import = self.block_import.clone();
@@ -284,69 +287,86 @@ impl<H, B, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I, P, SO> w
 			}
 		};
 
-		Box::new(proposal_work.map(move |b| {
-			// minor hack since we don't have access to the timestamp
-			// that is actually set by the proposer.
-			let slot_after_building = SignedDuration::default().slot_now(slot_duration);
-			if slot_after_building != slot_num {
-				info!(
-					"Discarding proposal for slot {}; block production took too long",
-					slot_num
-				);
-				telemetry!(CONSENSUS_INFO; "aura.discarding_proposal_took_too_long";
-					"slot" => slot_num
-				);
-				return
-			}
+		Box::new(proposal_work
+			.map_err(|e| consensus_common::Error::ClientImport(format!("{:?}", e)))
+			.and_then(move |proposal| -> Box<dyn Future<Item=(), Error=consensus_common::Error> + Send> {
+				// minor hack since we don't have access to the timestamp
+				// that is actually set by the proposer.
+				let slot_after_building = SignedDuration::default().slot_now(slot_duration);
+				if slot_after_building != slot_num {
+					info!(
+						"Discarding proposal for slot {}; block production took too long",
+						slot_num
+					);
+					telemetry!(CONSENSUS_INFO; "aura.discarding_proposal_took_too_long";
+						"slot" => slot_num
+					);
+					return Box::new(future::ok(()))
+				}
 
-			let (header, body) = b.deconstruct();
-			let pre_digest: Result<u64, String> = find_pre_digest::<B, P>(&header);
-			if let Err(e) = pre_digest {
-				error!(target: "aura", "FATAL ERROR: Invalid pre-digest: {}!", e);
-				return
-			} else {
-				trace!(target: "aura", "Got correct number of seals.  Good!")
-			};
-
-			let header_num = header.number().clone();
-			let parent_hash = header.parent_hash().clone();
-
-			// sign the pre-sealed hash of the block and then
-			// add it to a digest item.
-			let header_hash = header.hash();
-			let signature = pair.sign(header_hash.as_ref());
-			let signature_digest_item = <DigestItemFor<B> as CompatibleDigestItem<P>>::aura_seal(signature);
-
-			let import_block: BlockImportParams<B> = BlockImportParams {
-				origin: BlockOrigin::Own,
-				header,
-				justification: None,
-				post_digests: vec![signature_digest_item],
-				body: Some(body),
-				finalized: false,
-				auxiliary: Vec::new(),
-				fork_choice: ForkChoiceStrategy::LongestChain,
-			};
-
-			info!("Pre-sealed block for proposal at {}. Hash now {:?}, previously {:?}.",
-					header_num,
-					import_block.post_header().hash(),
-					header_hash
-			);
-			telemetry!(CONSENSUS_INFO; "aura.pre_sealed_block";
-				"header_num" => ?header_num,
-				"hash_now" => ?import_block.post_header().hash(),
-				"hash_previously" => ?header_hash
-			);
+				let (header, body) = proposal.block.deconstruct();
+				let pre_digest: Result<u64, String> = find_pre_digest::<B, P>(&header);
+				if let Err(e) = pre_digest {
+					error!(target: "aura", "FATAL ERROR: Invalid pre-digest: {}!", e);
+					return Box::new(future::ok(()))
+				} else {
+					trace!(target: "aura", "Got correct number of seals.  Good!")
+				};
 
-			if let Err(e) = block_import.lock().import_block(import_block, Default::default()) {
-				warn!(target: "aura", "Error with block built on {:?}: {:?}",
-						parent_hash, e);
-				telemetry!(CONSENSUS_WARN; "aura.err_with_block_built_on";
-					"hash" => ?parent_hash, "err" => ?e
-				);
-			}
-		}).map_err(|e| consensus_common::Error::ClientImport(format!("{:?}", e)).into()))
+				// sign the pre-sealed hash of the block and then
+				// add it to a digest item.
+				let header_hash = header.hash();
+				let storage_changes = proposal.storage_changes;
+				Box::new(signer.sign(header_hash.as_ref()).then(move |signature| {
+					let header_num = header.number().clone();
+					let parent_hash = header.parent_hash().clone();
+
+					let signature = match signature {
+						Ok(signature) => signature,
+						Err(e) => {
+							warn!(target: "aura", "Unable to sign block {:?} in slot {}: {}", header_hash, slot_num, e);
+							telemetry!(CONSENSUS_WARN; "aura.unable_signing_block";
+								"slot" => slot_num, "err" => ?e
+							);
+							return Ok(())
+						}
+					};
+					let signature_digest_item = <DigestItemFor<B> as CompatibleDigestItem<P>>::aura_seal(signature);
+
+					let import_block: BlockImportParams<B> = BlockImportParams {
+						origin: BlockOrigin::Own,
+						header,
+						justification: None,
+						post_digests: vec![signature_digest_item],
+						body: Some(body),
+						finalized: false,
+						auxiliary: Vec::new(),
+						fork_choice: ForkChoiceStrategy::LongestChain,
+						storage_changes,
+					};
+
+					info!("Pre-sealed block for proposal at {}. Hash now {:?}, previously {:?}.",
+							header_num,
+							import_block.post_header().hash(),
+							header_hash
+					);
+					telemetry!(CONSENSUS_INFO; "aura.pre_sealed_block";
+						"header_num" => ?header_num,
+						"hash_now" => ?import_block.post_header().hash(),
+						"hash_previously" => ?header_hash
+					);
+
+					if let Err(e) = block_import.lock().import_block(import_block, Default::default()) {
+						warn!(target: "aura", "Error with block built on {:?}: {:?}",
+								parent_hash, e);
+						telemetry!(CONSENSUS_WARN; "aura.err_with_block_built_on";
+							"hash" => ?parent_hash, "err" => ?e
+						);
+					}
+
+					Ok(())
+				}))
+			}))
 	}
 }
 
@@ -376,23 +396,48 @@ fn find_pre_digest<B: BlockT, P: Pair>(header: &B::Header) -> Result<u64, String
 }
 
 
+/// Prunes the headers kept to detect equivocations as blocks are finalized.
+///
+/// Register it with the client of nodes which import blocks through the AuRa import queue.
+pub fn equivocation_pruner<B: BlockT, P: Pair>() -> impl AuxDataPruner<B>
+	where DigestItemFor<B>: CompatibleDigestItem<P>,
+		P::Signature: Decode,
+		P::Public: Encode + Decode + PartialEq + Clone,
+{
+	EquivocationPruner::new(|header: &B::Header| find_pre_digest::<B, P>(header).ok())
+}
+
+/// A hook called when the verifier detects that a slot author signed two different headers in the
+/// same slot, e.g. to submit a report to the runtime so that the author can be punished.
+pub trait SendEquivocationReport<H, A>: Send + Sync {
+	/// Report that `author` equivocated, with the two headers it signed in `proof`.
+	fn send_equivocation_report(&self, author: &A, proof: EquivocationProof<H>);
+}
+
+impl<H, A> SendEquivocationReport<H, A> for () {
+	fn send_equivocation_report(&self, _author: &A, _proof: EquivocationProof<H>) {}
+}
+
 /// check a header has been signed by the right key. If the slot is too far in the future, an error will be returned.
 /// if it's successful, returns the pre-header and the digest item containing the seal.
 ///
 /// This digest item will always return `Some` when used with `as_aura_seal`.
-//
-// FIXME #1018 needs misbehavior types
-fn check_header<C, B: BlockT, P: Pair>(
+///
+/// Headers are also checked against the ones previously seen in the same slot, and equivocations
+/// are handed to the `equivocation_reporter`.
+fn check_header<C, B: BlockT, P: Pair, R>(
 	client: &C,
 	slot_now: u64,
 	mut header: B::Header,
 	hash: B::Hash,
 	authorities: &[AuthorityId<P>],
+	equivocation_reporter: &R,
 ) -> Result<CheckedHeader<B::Header, (u64, DigestItemFor<B>)>, String> where
 	DigestItemFor<B>: CompatibleDigestItem<P>,
 	P::Signature: Decode,
 	C: client::backend::AuxStore,
 	P::Public: AsRef<P::Public> + Encode + Decode + PartialEq + Clone,
+	R: SendEquivocationReport<B::Header, AuthorityId<P>>,
 {
 	let seal = match header.digest_mut().pop() {
 		Some(x) => x,
@@ -432,6 +477,7 @@ fn check_header<C, B: BlockT, P: Pair>(
 					equivocation_proof.fst_header().hash(),
 					equivocation_proof.snd_header().hash(),
 				);
+				equivocation_reporter.send_equivocation_report(expected_author, equivocation_proof);
 			}
 
 			Ok(CheckedHeader::Checked(header, (slot_num, seal)))
@@ -442,57 +488,66 @@ fn check_header<C, B: BlockT, P: Pair>(
 }
 
 /// A verifier for Aura blocks.
-pub struct AuraVerifier<C, P> {
+pub struct AuraVerifier<C, P, R = ()> {
 	client: Arc<C>,
 	phantom: PhantomData<P>,
 	inherent_data_providers: inherents::InherentDataProviders,
+	slot_duration: u64,
+	equivocation_reporter: R,
 }
 
-impl<C, P> AuraVerifier<C, P>
+impl<C, P, R> AuraVerifier<C, P, R>
 	where P: Send + Sync + 'static
 {
 	fn check_inherents<B: BlockT>(
 		&self,
 		block: B,
 		block_id: BlockId<B>,
-		inherent_data: InherentData,
+		mut inherent_data: InherentData,
+		extra_inherent_data: InherentData,
 		timestamp_now: u64,
-	) -> Result<(), String>
+	) -> Result<(), VerificationError>
 		where C: ProvideRuntimeApi, C::Api: BlockBuilderApi<B>
 	{
-		const MAX_TIMESTAMP_DRIFT_SECS: u64 = 60;
-
+		inherent_data.merge(extra_inherent_data);
 		let inherent_res = self.client.runtime_api().check_inherents(
 			&block_id,
 			block,
 			inherent_data,
-		).map_err(|e| format!("{:?}", e))?;
+		).map_err(|e| VerificationError::from(format!("{:?}", e)))?;
 
 		if !inherent_res.ok() {
-			inherent_res
-				.into_errors()
-				.try_for_each(|(i, e)| match TIError::try_from(&i, &e) {
-					Some(TIError::ValidAtTimestamp(timestamp)) => {
-						// halt import until timestamp is valid.
+			self.inherent_data_providers
+				.check_inherents_errors(inherent_res)
+				.into_iter()
+				.try_for_each(|e| match e.retry_hint {
+					Some(RetryHint::AtTimestamp(timestamp)) => {
+						// defer import until timestamp is valid.
 						// reject when too far ahead.
-						if timestamp > timestamp_now + MAX_TIMESTAMP_DRIFT_SECS {
+						let diff = Duration::from_secs(timestamp.saturating_sub(timestamp_now));
+						if diff > slots::MAX_SLOT_DEFERRAL {
 							return Err("Rejecting block too far in future".into());
 						}
 
-						let diff = timestamp.saturating_sub(timestamp_now);
 						info!(
 							target: "aura",
-							"halting for block {} seconds in the future",
+							"deferring block {:?} in the future",
 							diff
 						);
-						telemetry!(CONSENSUS_INFO; "aura.halting_for_future_block";
+						telemetry!(CONSENSUS_INFO; "aura.deferring_future_block";
 							"diff" => ?diff
 						);
-						thread::sleep(Duration::from_secs(diff));
-						Ok(())
+						Err(VerificationError::TooFarInFuture(diff))
+					},
+					Some(RetryHint::After(duration)) if duration <= slots::MAX_SLOT_DEFERRAL => {
+						info!(
+							target: "aura",
+							"deferring block until its inherents are valid in {:?}",
+							duration
+						);
+						Err(VerificationError::TooFarInFuture(duration))
 					},
-					Some(TIError::Other(e)) => Err(e.into()),
-					None => Err(self.inherent_data_providers.error_to_string(&i, &e)),
+					_ => Err(e.message.into()),
 				})
 		} else {
 			Ok(())
@@ -501,13 +556,14 @@ impl<C, P> AuraVerifier<C, P>
 }
 
 #[forbid(deprecated)]
-impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
+impl<B: BlockT, C, P, R> Verifier<B> for AuraVerifier<C, P, R> where
 	C: ProvideRuntimeApi + Send + Sync + client::backend::AuxStore + ProvideCache<B>,
 	C::Api: BlockBuilderApi<B> + AuraApi<B, AuthorityId<P>>,
 	DigestItemFor<B>: CompatibleDigestItem<P>,
 	P: Pair + Send + Sync + 'static,
 	P::Public: Send + Sync + Hash + Eq + Clone + Decode + Encode + Debug + AsRef<P::Public> + 'static,
 	P::Signature: Encode + Decode,
+	R: SendEquivocationReport<B::Header, AuthorityId<P>>,
 {
 	fn verify(
 		&self,
@@ -515,8 +571,8 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 		header: B::Header,
 		justification: Option<Justification>,
 		mut body: Option<Vec<B::Extrinsic>>,
-	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
-		let mut inherent_data = self.inherent_data_providers.create_inherent_data().map_err(String::from)?;
+	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), VerificationError> {
+		let inherent_data = self.inherent_data_providers.create_inherent_data().map_err(String::from)?;
 		let (timestamp_now, slot_now, _) = AuraSlotCompatible.extract_timestamp_and_slot(&inherent_data)
 			.map_err(|e| format!("Could not extract timestamp and slot: {:?}", e))?;
 		let hash = header.hash();
@@ -527,12 +583,13 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 		// we add one to allow for some small drift.
 		// FIXME #1019 in the future, alter this queue to allow deferring of
 		// headers
-		let checked_header = check_header::<C, B, P>(
+		let checked_header = check_header::<C, B, P, R>(
 			&self.client,
 			slot_now + 1,
 			header,
 			hash,
 			&authorities[..],
+			&self.equivocation_reporter,
 		)?;
 		match checked_header {
 			CheckedHeader::Checked(pre_header, (slot_num, seal)) => {
@@ -540,7 +597,8 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 				// to check that the internally-set timestamp in the inherents
 				// actually matches the slot set in the seal.
 				if let Some(inner_body) = body.take() {
-					inherent_data.aura_replace_inherent_data(slot_num);
+					let mut extra_inherent_data = InherentData::new();
+					extra_inherent_data.aura_replace_inherent_data(slot_num);
 					let block = B::new(pre_header.clone(), inner_body);
 
 					// skip the inherents verification if the runtime API is old.
@@ -553,6 +611,7 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 							block.clone(),
 							BlockId::Hash(parent_hash),
 							inherent_data,
+							extra_inherent_data,
 							timestamp_now,
 						)?;
 					}
@@ -587,6 +646,7 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 					justification,
 					auxiliary: Vec::new(),
 					fork_choice: ForkChoiceStrategy::LongestChain,
+					storage_changes: None,
 				};
 
 				Ok((import_block, maybe_keys))
@@ -596,7 +656,10 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 				telemetry!(CONSENSUS_DEBUG; "aura.header_too_far_in_future";
 					"hash" => ?hash, "a" => ?a, "b" => ?b
 				);
-				Err(format!("Header {:?} rejected: too far in the future", hash))
+				match slots::time_until_slot(timestamp_now, b, self.slot_duration, slots::MAX_SLOT_DEFERRAL) {
+					Some(wait) => Err(VerificationError::TooFarInFuture(wait)),
+					None => Err(format!("Header {:?} rejected: too far in the future", hash).into()),
+				}
 			}
 		}
 	}
@@ -671,13 +734,17 @@ fn register_aura_inherent_data_provider(
 }
 
 /// Start an import queue for the Aura consensus algorithm.
-pub fn import_queue<B, C, P>(
+///
+/// Equivocations detected while verifying blocks are handed to `equivocation_reporter`; pass `()`
+/// to only log them.
+pub fn import_queue<B, C, P, R>(
 	slot_duration: SlotDuration,
 	block_import: BoxBlockImport<B>,
 	justification_import: Option<BoxJustificationImport<B>>,
 	finality_proof_import: Option<BoxFinalityProofImport<B>>,
 	client: Arc<C>,
 	inherent_data_providers: InherentDataProviders,
+	equivocation_reporter: R,
 ) -> Result<AuraImportQueue<B>, consensus_common::Error> where
 	B: BlockT,
 	C: 'static + ProvideRuntimeApi + ProvideCache<B> + Send + Sync + AuxStore,
@@ -686,6 +753,7 @@ pub fn import_queue<B, C, P>(
 	P: Pair + Send + Sync + 'static,
 	P::Public: Clone + Eq + Send + Sync + Hash + Debug + Encode + Decode + AsRef<P::Public>,
 	P::Signature: Encode + Decode,
+	R: SendEquivocationReport<B::Header, AuthorityId<P>> + 'static,
 {
 	register_aura_inherent_data_provider(&inherent_data_providers, slot_duration.get())?;
 	initialize_authorities_cache(&*client)?;
@@ -695,6 +763,8 @@ pub fn import_queue<B, C, P>(
 			client: client.clone(),
 			inherent_data_providers,
 			phantom: PhantomData,
+			slot_duration: slot_duration.get(),
+			equivocation_reporter,
 		}
 	);
 	Ok(BasicQueue::new(
@@ -710,7 +780,7 @@ mod tests {
 	use super::*;
 	use futures::{Async, stream::Stream as _};
 	use futures03::{StreamExt as _, TryStreamExt as _};
-	use consensus_common::NoNetwork as DummyOracle;
+	use consensus_common::{NoNetwork as DummyOracle, Proposal};
 	use network::test::*;
 	use network::test::{Block as TestBlock, PeersClient, PeersFullClient};
 	use runtime_primitives::traits::{Block as BlockT, DigestFor};
@@ -747,15 +817,15 @@ mod tests {
 
 	impl Proposer<TestBlock> for DummyProposer {
 		type Error = Error;
-		type Create = Result<TestBlock, Error>;
+		type Create = Result<Proposal<TestBlock>, Error>;
 
 		fn propose(
 			&self,
 			_: InherentData,
 			digests: DigestFor<TestBlock>,
 			_: Duration,
-		) -> Result<TestBlock, Error> {
-			self.1.new_block(digests).unwrap().bake().map_err(|e| e.into())
+		) -> Result<Proposal<TestBlock>, Error> {
+			self.1.new_block(digests).unwrap().bake().map(Into::into).map_err(|e| e.into())
 		}
 	}
 
@@ -795,6 +865,8 @@ mod tests {
 						client,
 						inherent_data_providers,
 						phantom: Default::default(),
+						slot_duration: slot_duration.get(),
+						equivocation_reporter: (),
 					})
 				},
 				PeersClient::Light(_) => unreachable!("No (yet) tests for light client + Aura"),
@@ -854,7 +926,7 @@ mod tests {
 
 			let aura = start_aura::<_, _, _, _, _, sr25519::Pair, _, _, _>(
 				slot_duration,
-				Arc::new(key.clone().into()),
+				Signer::from_pair(key.clone().into()),
 				client.clone(),
 				select_chain,
 				client,
@@ -887,4 +959,67 @@ mod tests {
 			Keyring::Charlie.into()
 		]);
 	}
+
+	#[derive(Default)]
+	struct ReportedEquivocations(Mutex<Vec<(sr25519::Public, u64)>>);
+
+	impl SendEquivocationReport<<TestBlock as BlockT>::Header, sr25519::Public> for ReportedEquivocations {
+		fn send_equivocation_report(
+			&self,
+			author: &sr25519::Public,
+			proof: EquivocationProof<<TestBlock as BlockT>::Header>,
+		) {
+			self.0.lock().push((author.clone(), proof.slot()));
+		}
+	}
+
+	fn sealed_header(
+		keyring: Keyring,
+		slot_num: u64,
+		state_root: u8,
+	) -> <TestBlock as BlockT>::Header {
+		let mut header = test_client::runtime::Header {
+			parent_hash: Default::default(),
+			number: 1,
+			state_root: primitives::H256::repeat_byte(state_root),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		};
+		header.digest_mut().push(
+			<DigestItemFor<TestBlock> as CompatibleDigestItem<sr25519::Pair>>::aura_pre_digest(slot_num)
+		);
+		let signature = keyring.pair().sign(header.hash().as_ref());
+		header.digest_mut().push(
+			<DigestItemFor<TestBlock> as CompatibleDigestItem<sr25519::Pair>>::aura_seal(signature)
+		);
+		header
+	}
+
+	#[test]
+	fn check_header_reports_equivocations() {
+		let client = test_client::new();
+		let authorities: Vec<sr25519::Public> = vec![Keyring::Alice.into()];
+		let reporter = ReportedEquivocations::default();
+		let check = |header: <TestBlock as BlockT>::Header| {
+			let hash = header.hash();
+			check_header::<_, TestBlock, sr25519::Pair, _>(&client, 2, header, hash, &authorities, &reporter)
+				.is_ok()
+		};
+
+		assert!(check(sealed_header(Keyring::Alice, 1, 1)));
+		// importing the same header again isn't an equivocation.
+		assert!(check(sealed_header(Keyring::Alice, 1, 1)));
+		assert!(reporter.0.lock().is_empty());
+
+		// a header signed by someone else than the slot author is rejected.
+		assert!(!check(sealed_header(Keyring::Bob, 1, 2)));
+		assert!(reporter.0.lock().is_empty());
+
+		assert!(check(sealed_header(Keyring::Alice, 1, 2)));
+		assert_eq!(*reporter.0.lock(), vec![(Keyring::Alice.into(), 1)]);
+
+		// a different header in another slot is fine.
+		assert!(check(sealed_header(Keyring::Alice, 2, 3)));
+		assert_eq!(reporter.0.lock().len(), 1);
+	}
 }
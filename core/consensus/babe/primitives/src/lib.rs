@@ -94,6 +94,23 @@ pub struct BabeConfiguration {
 	pub median_required_blocks: u64,
 }
 
+/// A BABE epoch, as seen from the state of a block.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Epoch {
+	/// The index of the epoch.
+	pub epoch_index: u64,
+	/// The slot at which the epoch started.
+	pub start_slot: SlotNumber,
+	/// The number of slots the epoch lasts.
+	pub duration: SlotNumber,
+	/// The authorities of the epoch and their weights. An authority's chance of winning a slot
+	/// is proportional to its share of the total weight.
+	pub authorities: Vec<(AuthorityId, Weight)>,
+	/// The randomness committed to the VRF transcript of every slot of the epoch.
+	pub randomness: [u8; VRF_OUTPUT_LENGTH],
+}
+
 #[cfg(feature = "std")]
 impl slots::SlotData for BabeConfiguration {
 	/// Return the slot duration in milliseconds for BABE. Currently, only
@@ -118,5 +135,9 @@ decl_runtime_apis! {
 
 		/// Get the current authorites for Babe.
 		fn authorities() -> Vec<AuthorityId>;
+
+		/// Get the current epoch, which determines the VRF transcript and the
+		/// threshold of each authority.
+		fn epoch() -> Epoch;
 	}
 }
@@ -32,7 +32,7 @@ pub use digest::{BabePreDigest, BABE_VRF_PREFIX};
 pub use babe_primitives::*;
 pub use consensus_common::SyncOracle;
 use consensus_common::import_queue::{
-	BoxBlockImport, BoxJustificationImport, BoxFinalityProofImport,
+	BoxBlockImport, BoxJustificationImport, BoxFinalityProofImport, VerificationError,
 };
 use consensus_common::well_known_cache_keys::Id as CacheKeyId;
 use runtime_primitives::{generic, generic::{BlockId, OpaqueDigestItemId}, Justification};
@@ -54,12 +54,8 @@ use substrate_telemetry::{
 	CONSENSUS_WARN,
 	CONSENSUS_INFO,
 };
-use schnorrkel::{
-	keys::Keypair,
-	vrf::{
-		VRFProof, VRFProofBatchable, VRFInOut,
-	},
-};
+use schnorrkel::vrf::{VRFProof, VRFInOut};
+use keystore::{Signer, SignFuture, VRFTranscriptData};
 use consensus_common::{
 	self, BlockImport, Environment, Proposer,
 	ForkChoiceStrategy, BlockImportParams, BlockOrigin, Error as ConsensusError,
@@ -72,7 +68,7 @@ use consensus_common::{SelectChain, well_known_cache_keys};
 use consensus_common::import_queue::{Verifier, BasicQueue};
 use client::{
 	block_builder::api::BlockBuilder as BlockBuilderApi,
-	blockchain::ProvideCache,
+	blockchain::{ProvideCache, HeaderBackend},
 	runtime_api::ApiExt,
 	error::Result as CResult,
 	backend::AuxStore,
@@ -142,7 +138,7 @@ pub struct BabeParams<C, E, I, SO, SC> {
 	pub config: Config,
 
 	/// The key of the node we are running on.
-	pub local_key: Arc<sr25519::Pair>,
+	pub local_key: Signer<sr25519::Pair>,
 
 	/// The client to use
 	pub client: Arc<C>,
@@ -186,13 +182,13 @@ pub fn start_babe<B, C, SC, E, I, SO, Error, H>(BabeParams {
 	consensus_common::Error,
 > where
 	B: BlockT<Header=H>,
-	C: ProvideRuntimeApi + ProvideCache<B>,
+	C: ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B>,
 	C::Api: BabeApi<B>,
 	SC: SelectChain<B>,
 	E::Proposer: Proposer<B, Error=Error>,
 	<<E::Proposer as Proposer<B>>::Create as IntoFuture>::Future: Send + 'static,
 	H: Header<Hash=B::Hash>,
-	E: Environment<B, Error=Error>,
+	E: Environment<B, Error=Error> + Send + Sync + 'static,
 	I: BlockImport<B> + Send + Sync + 'static,
 	Error: std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
 	SO: SyncOracle + Send + Sync + Clone,
@@ -221,7 +217,7 @@ struct BabeWorker<C, E, I, SO> {
 	client: Arc<C>,
 	block_import: Arc<Mutex<I>>,
 	env: Arc<E>,
-	local_key: Arc<sr25519::Pair>,
+	local_key: Signer<sr25519::Pair>,
 	sync_oracle: SO,
 	force_authoring: bool,
 	threshold: u64,
@@ -229,9 +225,9 @@ struct BabeWorker<C, E, I, SO> {
 
 impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<C, E, I, SO> where
 	B: BlockT<Header=H, Hash=Hash>,
-	C: ProvideRuntimeApi + ProvideCache<B>,
+	C: ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B>,
 	C::Api: BabeApi<B>,
-	E: Environment<B, Error=Error>,
+	E: Environment<B, Error=Error> + Send + Sync + 'static,
 	E::Proposer: Proposer<B, Error=Error>,
 	<<E::Proposer as Proposer<B>>::Create as IntoFuture>::Future: Send + 'static,
 	Hash: Debug + Eq + Copy + SimpleBitOps + Encode + Decode + Serialize +
@@ -249,50 +245,53 @@ impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<C, E, I, SO> w
 		chain_head: B::Header,
 		slot_info: SlotInfo,
 	) -> Self::OnSlot {
-		let pair = self.local_key.clone();
+		let signer = self.local_key.clone();
 		let ref client = self.client;
 		let block_import = self.block_import.clone();
-		let ref env = self.env;
+		let env = self.env.clone();
 
 		let (timestamp, slot_num, slot_duration) =
 			(slot_info.timestamp, slot_info.number, slot_info.duration);
 
-		let authorities = match authorities(client.as_ref(), &BlockId::Hash(chain_head.hash())) {
-			Ok(authorities) => authorities,
+		let epoch = match epoch(client.as_ref(), &BlockId::Hash(chain_head.hash())) {
+			Ok(epoch) => epoch,
 			Err(e) => {
 				error!(
 					target: "babe",
-					"Unable to fetch authorities at block {:?}: {:?}",
+					"Unable to fetch epoch at block {:?}: {:?}",
 					chain_head.hash(),
 					e
 				);
-				telemetry!(CONSENSUS_WARN; "babe.unable_fetching_authorities";
+				telemetry!(CONSENSUS_WARN; "babe.unable_fetching_epoch";
 					"slot" => ?chain_head.hash(), "err" => ?e
 				);
 				return Box::new(future::ok(()));
 			}
 		};
 
-		if !self.force_authoring && self.sync_oracle.is_offline() && authorities.len() > 1 {
+		if !self.force_authoring && self.sync_oracle.is_offline() && epoch.authorities.len() > 1 {
 			debug!(target: "babe", "Skipping proposal slot. Waiting for the network.");
 			telemetry!(CONSENSUS_DEBUG; "babe.skipping_proposal_slot";
-				"authorities_len" => authorities.len()
+				"authorities_len" => epoch.authorities.len()
 			);
 			return Box::new(future::ok(()));
 		}
 
-		// FIXME replace the dummy empty slices with real data
-		// https://github.com/paritytech/substrate/issues/2435
-		// https://github.com/paritytech/substrate/issues/2436
-		let proposal_work = if let Some(((inout, proof, _batchable_proof), index)) = claim_slot(
-			&[0u8; 0],
-			slot_info.number,
-			&[0u8; 0],
-			0,
-			&authorities,
-			&pair,
-			self.threshold,
-		) {
+		let genesis_hash = client.info().genesis_hash;
+		let claim = claim_slot(slot_num, genesis_hash.as_ref(), &epoch, &signer, self.threshold);
+		Box::new(claim.then(move |claim| -> Box<dyn Future<Item=(), Error=consensus_common::Error> + Send> {
+			let ((inout, proof), index) = match claim {
+				Ok(Some(claim)) => claim,
+				Ok(None) => return Box::new(future::ok(())),
+				Err(e) => {
+					warn!(target: "babe", "Unable to claim slot {}: {}", slot_num, e);
+					telemetry!(CONSENSUS_WARN; "babe.unable_claiming_slot";
+						"slot" => slot_num, "err" => ?e
+					);
+					return Box::new(future::ok(()))
+				}
+			};
+
 			debug!(
 				target: "babe", "Starting authorship at slot {}; timestamp = {}",
 				slot_num,
@@ -323,7 +322,7 @@ impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<C, E, I, SO> w
 
 			// deadline our production to approx. the end of the slot
 			let remaining_duration = slot_info.remaining_duration();
-			Timeout::new(
+			let proposal_work = Timeout::new(
 				proposer.propose(
 					slot_info.inherent_data,
 					generic::Digest {
@@ -334,78 +333,91 @@ impl<Hash, H, B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<C, E, I, SO> w
 					remaining_duration,
 				).into_future(),
 				remaining_duration,
-			)
-		} else {
-			return Box::new(future::ok(()));
-		};
-
-		Box::new(proposal_work.map(move |b| {
-			// minor hack since we don't have access to the timestamp
-			// that is actually set by the proposer.
-			let slot_after_building = SignedDuration::default().slot_now(slot_duration);
-			if slot_after_building != slot_num {
-				info!(
-					target: "babe",
-					"Discarding proposal for slot {}; block production took too long",
-					slot_num
-				);
-				telemetry!(CONSENSUS_INFO; "babe.discarding_proposal_took_too_long";
-					"slot" => slot_num
-				);
-				return
-			}
+			);
 
-			let (header, body) = b.deconstruct();
-			let pre_digest: Result<BabePreDigest, String> = find_pre_digest::<B>(&header);
-			if let Err(e) = pre_digest {
-				error!(target: "babe", "FATAL ERROR: Invalid pre-digest: {}!", e);
-				return
-			} else {
-				trace!(target: "babe", "Got correct number of seals.  Good!")
-			};
+			Box::new(proposal_work.map_err(|e| {
+				warn!("Client import failed: {:?}", e);
+				consensus_common::Error::ClientImport(format!("{:?}", e))
+			}).and_then(move |proposal| -> Box<dyn Future<Item=(), Error=consensus_common::Error> + Send> {
+				// minor hack since we don't have access to the timestamp
+				// that is actually set by the proposer.
+				let slot_after_building = SignedDuration::default().slot_now(slot_duration);
+				if slot_after_building != slot_num {
+					info!(
+						target: "babe",
+						"Discarding proposal for slot {}; block production took too long",
+						slot_num
+					);
+					telemetry!(CONSENSUS_INFO; "babe.discarding_proposal_took_too_long";
+						"slot" => slot_num
+					);
+					return Box::new(future::ok(()))
+				}
 
-			let header_num = header.number().clone();
-			let parent_hash = header.parent_hash().clone();
-
-			// sign the pre-sealed hash of the block and then
-			// add it to a digest item.
-			let header_hash = header.hash();
-			let signature = pair.sign(header_hash.as_ref());
-			let signature_digest_item = DigestItemFor::<B>::babe_seal(signature);
-
-			let import_block: BlockImportParams<B> = BlockImportParams {
-				origin: BlockOrigin::Own,
-				header,
-				justification: None,
-				post_digests: vec![signature_digest_item],
-				body: Some(body),
-				finalized: false,
-				auxiliary: Vec::new(),
-				fork_choice: ForkChoiceStrategy::LongestChain,
-			};
+				let (header, body) = proposal.block.deconstruct();
+				let pre_digest: Result<BabePreDigest, String> = find_pre_digest::<B>(&header);
+				if let Err(e) = pre_digest {
+					error!(target: "babe", "FATAL ERROR: Invalid pre-digest: {}!", e);
+					return Box::new(future::ok(()))
+				} else {
+					trace!(target: "babe", "Got correct number of seals.  Good!")
+				};
 
-			info!(target: "babe",
-					"Pre-sealed block for proposal at {}. Hash now {:?}, previously {:?}.",
-					header_num,
-					import_block.post_header().hash(),
-					header_hash,
-			);
-			telemetry!(CONSENSUS_INFO; "babe.pre_sealed_block";
-				"header_num" => ?header_num,
-				"hash_now" => ?import_block.post_header().hash(),
-				"hash_previously" => ?header_hash,
-			);
+				// sign the pre-sealed hash of the block and then
+				// add it to a digest item.
+				let header_hash = header.hash();
+				let storage_changes = proposal.storage_changes;
+				Box::new(signer.sign(header_hash.as_ref()).then(move |signature| {
+					let header_num = header.number().clone();
+					let parent_hash = header.parent_hash().clone();
+
+					let signature = match signature {
+						Ok(signature) => signature,
+						Err(e) => {
+							warn!(target: "babe", "Unable to sign block {:?} in slot {}: {}", header_hash, slot_num, e);
+							telemetry!(CONSENSUS_WARN; "babe.unable_signing_block";
+								"slot" => slot_num, "err" => ?e
+							);
+							return Ok(())
+						}
+					};
+					let signature_digest_item = DigestItemFor::<B>::babe_seal(signature);
+
+					let import_block: BlockImportParams<B> = BlockImportParams {
+						origin: BlockOrigin::Own,
+						header,
+						justification: None,
+						post_digests: vec![signature_digest_item],
+						body: Some(body),
+						finalized: false,
+						auxiliary: Vec::new(),
+						fork_choice: ForkChoiceStrategy::LongestChain,
+						storage_changes,
+					};
+
+					info!(target: "babe",
+							"Pre-sealed block for proposal at {}. Hash now {:?}, previously {:?}.",
+							header_num,
+							import_block.post_header().hash(),
+							header_hash,
+					);
+					telemetry!(CONSENSUS_INFO; "babe.pre_sealed_block";
+						"header_num" => ?header_num,
+						"hash_now" => ?import_block.post_header().hash(),
+						"hash_previously" => ?header_hash,
+					);
 
-			if let Err(e) = block_import.lock().import_block(import_block, Default::default()) {
-				warn!(target: "babe", "Error with block built on {:?}: {:?}",
-						parent_hash, e);
-				telemetry!(CONSENSUS_WARN; "babe.err_with_block_built_on";
-					"hash" => ?parent_hash, "err" => ?e
-				);
-			}
-		}).map_err(|e| {
-			warn!("Client import failed: {:?}", e);
-			consensus_common::Error::ClientImport(format!("{:?}", e)).into()
+					if let Err(e) = block_import.lock().import_block(import_block, Default::default()) {
+						warn!(target: "babe", "Error with block built on {:?}: {:?}",
+								parent_hash, e);
+						telemetry!(CONSENSUS_WARN; "babe.err_with_block_built_on";
+							"hash" => ?parent_hash, "err" => ?e
+						);
+					}
+
+					Ok(())
+				}))
+			}))
 		}))
 	}
 }
@@ -440,6 +452,10 @@ fn find_pre_digest<B: BlockT>(header: &B::Header) -> Result<BabePreDigest, Strin
 /// The seal must be the last digest.  Otherwise, the whole header is considered
 /// unsigned.  This is required for security and must not be changed.
 ///
+/// The VRF output must be proven under the author's key for the slot, the
+/// genesis hash and the given epoch, and must be below the author's share of
+/// the `threshold`. The slot must not be before the start of the epoch.
+///
 /// This digest item will always return `Some` when used with `as_babe_pre_digest`.
 //
 // FIXME #1018 needs misbehavior types
@@ -448,7 +464,8 @@ fn check_header<B: BlockT + Sized, C: AuxStore>(
 	slot_now: u64,
 	mut header: B::Header,
 	hash: B::Hash,
-	authorities: &[AuthorityId],
+	genesis_hash: &[u8],
+	epoch: &Epoch,
 	threshold: u64,
 ) -> Result<CheckedHeader<B::Header, (DigestItemFor<B>, DigestItemFor<B>)>, String>
 	where DigestItemFor<B>: CompatibleDigestItem,
@@ -469,18 +486,20 @@ fn check_header<B: BlockT + Sized, C: AuxStore>(
 	if slot_num > slot_now {
 		header.digest_mut().push(seal);
 		Ok(CheckedHeader::Deferred(header, slot_num))
-	} else if index > authorities.len() as u64 {
+	} else if slot_num < epoch.start_slot {
+		Err(babe_err!("Slot {} is before the start of epoch {}", slot_num, epoch.epoch_index))
+	} else if index >= epoch.authorities.len() as u64 {
 		Err(babe_err!("Slot author not found"))
 	} else {
-		let (pre_hash, author): (_, &sr25519::Public) = (header.hash(), &authorities[index as usize]);
+		let (pre_hash, author): (_, &sr25519::Public) = (header.hash(), &epoch.authorities[index as usize].0);
 
 		if sr25519::Pair::verify(&sig, pre_hash, author.clone()) {
 			let (inout, _batchable_proof) = {
 				let transcript = make_transcript(
-					Default::default(),
+					&epoch.randomness,
 					slot_num,
-					Default::default(),
-					0,
+					genesis_hash,
+					epoch.epoch_index,
 				);
 				schnorrkel::PublicKey::from_bytes(author.as_slice()).and_then(|p| {
 					p.vrf_verify(transcript, vrf_output, proof)
@@ -489,6 +508,7 @@ fn check_header<B: BlockT + Sized, C: AuxStore>(
 				})?
 			};
 
+			let threshold = calculate_threshold(threshold, &epoch.authorities, index as usize);
 			if !check(&inout, threshold) {
 				return Err(babe_err!("VRF verification of block by author {:?} failed: \
 									  threshold {} exceeded", author, threshold));
@@ -535,10 +555,12 @@ impl<C> BabeVerifier<C> {
 		&self,
 		block: B,
 		block_id: BlockId<B>,
-		inherent_data: InherentData,
+		mut inherent_data: InherentData,
+		extra_inherent_data: InherentData,
 	) -> Result<(), String>
 		where C: ProvideRuntimeApi, C::Api: BlockBuilderApi<B>
 	{
+		inherent_data.merge(extra_inherent_data);
 		let inherent_res = self.client.runtime_api().check_inherents(
 			&block_id,
 			block,
@@ -546,9 +568,10 @@ impl<C> BabeVerifier<C> {
 		).map_err(|e| format!("{:?}", e))?;
 
 		if !inherent_res.ok() {
-			inherent_res
-				.into_errors()
-				.try_for_each(|(i, e)| Err(self.inherent_data_providers.error_to_string(&i, &e)))
+			self.inherent_data_providers
+				.check_inherents_errors(inherent_res)
+				.into_iter()
+				.try_for_each(|e| Err(e.message))
 		} else {
 			Ok(())
 		}
@@ -588,7 +611,7 @@ fn median_algorithm(
 }
 
 impl<B: BlockT, C> Verifier<B> for BabeVerifier<C> where
-	C: ProvideRuntimeApi + Send + Sync + AuxStore + ProvideCache<B>,
+	C: ProvideRuntimeApi + Send + Sync + AuxStore + ProvideCache<B> + HeaderBackend<B>,
 	C::Api: BlockBuilderApi<B> + BabeApi<B>,
 	DigestItemFor<B>: CompatibleDigestItem,
 {
@@ -598,7 +621,7 @@ impl<B: BlockT, C> Verifier<B> for BabeVerifier<C> where
 		header: B::Header,
 		justification: Option<Justification>,
 		mut body: Option<Vec<B::Extrinsic>>,
-	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), VerificationError> {
 		trace!(
 			target: "babe",
 			"Verifying origin: {:?} header: {:?} justification: {:?} body: {:?}",
@@ -609,26 +632,28 @@ impl<B: BlockT, C> Verifier<B> for BabeVerifier<C> where
 		);
 
 		debug!(target: "babe", "We have {:?} logs in this header", header.digest().logs().len());
-		let mut inherent_data = self
+		let inherent_data = self
 			.inherent_data_providers
 			.create_inherent_data()
 			.map_err(String::from)?;
-		let (_, slot_now, _) = self.time_source.extract_timestamp_and_slot(&inherent_data)
+		let (timestamp_now, slot_now, _) = self.time_source.extract_timestamp_and_slot(&inherent_data)
 			.map_err(|e| format!("Could not extract timestamp and slot: {:?}", e))?;
 		let hash = header.hash();
 		let parent_hash = *header.parent_hash();
-		let authorities = authorities(self.client.as_ref(), &BlockId::Hash(parent_hash))
-			.map_err(|e| format!("Could not fetch authorities at {:?}: {:?}", parent_hash, e))?;
+		let epoch = epoch(self.client.as_ref(), &BlockId::Hash(parent_hash))
+			.map_err(|e| format!("Could not fetch epoch at {:?}: {:?}", parent_hash, e))?;
 
 		// we add one to allow for some small drift.
 		// FIXME #1019 in the future, alter this queue to allow deferring of
 		// headers
+		let genesis_hash = self.client.info().genesis_hash;
 		let checked_header = check_header::<B, C>(
 			&self.client,
 			slot_now + 1,
 			header,
 			hash,
-			&authorities[..],
+			genesis_hash.as_ref(),
+			&epoch,
 			self.config.threshold(),
 		)?;
 		match checked_header {
@@ -640,13 +665,15 @@ impl<B: BlockT, C> Verifier<B> for BabeVerifier<C> where
 				// to check that the internally-set timestamp in the inherents
 				// actually matches the slot set in the seal.
 				if let Some(inner_body) = body.take() {
-					inherent_data.babe_replace_inherent_data(slot_num);
+					let mut extra_inherent_data = InherentData::new();
+					extra_inherent_data.babe_replace_inherent_data(slot_num);
 					let block = B::new(pre_header.clone(), inner_body);
 
 					self.check_inherents(
 						block.clone(),
 						BlockId::Hash(parent_hash),
 						inherent_data,
+						extra_inherent_data,
 					)?;
 
 					let (_, inner_body) = block.deconstruct();
@@ -675,6 +702,7 @@ impl<B: BlockT, C> Verifier<B> for BabeVerifier<C> where
 					justification,
 					auxiliary: Vec::new(),
 					fork_choice: ForkChoiceStrategy::LongestChain,
+					storage_changes: None,
 				};
 				median_algorithm(
 					self.config.0.median_required_blocks,
@@ -691,7 +719,10 @@ impl<B: BlockT, C> Verifier<B> for BabeVerifier<C> where
 				telemetry!(CONSENSUS_DEBUG; "babe.header_too_far_in_future";
 					"hash" => ?hash, "a" => ?a, "b" => ?b
 				);
-				Err(format!("Header {:?} rejected: too far in the future", hash))
+				match slots::time_until_slot(timestamp_now, b, self.config.get(), slots::MAX_SLOT_DEFERRAL) {
+					Some(wait) => Err(VerificationError::TooFarInFuture(wait)),
+					None => Err(format!("Header {:?} rejected: too far in the future", hash).into()),
+				}
 			}
 		}
 	}
@@ -719,6 +750,19 @@ fn authorities<B, C>(client: &C, at: &BlockId<B>) -> Result<
 		}).ok_or(consensus_common::Error::InvalidAuthoritiesSet)
 }
 
+/// Fetch the epoch at the given block. Blocks are authored and verified
+/// against the epoch of their parent.
+fn epoch<B, C>(client: &C, at: &BlockId<B>) -> Result<Epoch, ConsensusError> where
+	B: BlockT,
+	C: ProvideRuntimeApi,
+	C::Api: BabeApi<B>,
+{
+	client
+		.runtime_api()
+		.epoch(at)
+		.map_err(|e| ConsensusError::ChainLookup(format!("{:?}", e)))
+}
+
 /// The BABE import queue type.
 pub type BabeImportQueue<B> = BasicQueue<B>;
 
@@ -738,61 +782,102 @@ fn register_babe_inherent_data_provider(
 	}
 }
 
-fn get_keypair(q: &sr25519::Pair) -> &Keypair {
-	q.as_ref()
+/// The messages of the VRF transcript of a slot, so that the VRF can be
+/// evaluated by a remote signer.
+fn make_transcript_data(
+	randomness: &[u8],
+	slot_number: u64,
+	genesis_hash: &[u8],
+	epoch: u64,
+) -> VRFTranscriptData {
+	VRFTranscriptData {
+		label: &BABE_ENGINE_ID,
+		items: vec![
+			(&b"slot number"[..], slot_number.to_le_bytes().to_vec()),
+			(&b"genesis block hash"[..], genesis_hash.to_vec()),
+			(&b"current epoch"[..], epoch.to_le_bytes().to_vec()),
+			(&b"chain randomness"[..], randomness.to_vec()),
+		],
+	}
 }
 
-#[allow(deprecated)]
 fn make_transcript(
 	randomness: &[u8],
 	slot_number: u64,
 	genesis_hash: &[u8],
 	epoch: u64,
 ) -> Transcript {
-	let mut transcript = Transcript::new(&BABE_ENGINE_ID);
-	transcript.commit_bytes(b"slot number", &slot_number.to_le_bytes());
-	transcript.commit_bytes(b"genesis block hash", genesis_hash);
-	transcript.commit_bytes(b"current epoch", &epoch.to_le_bytes());
-	transcript.commit_bytes(b"chain randomness", randomness);
-	transcript
+	make_transcript_data(randomness, slot_number, genesis_hash, epoch).to_transcript()
 }
 
 fn check(inout: &VRFInOut, threshold: u64) -> bool {
 	u64::from_le_bytes(inout.make_bytes::<[u8; 8]>(BABE_VRF_PREFIX)) < threshold
 }
 
-/// Claim a slot if it is our turn.  Returns `None` if it is not our turn.
+/// Compute the threshold of the authority at `authority_index`, which is its
+/// share of the total weight of `authorities` applied to `threshold`.
+fn calculate_threshold(
+	threshold: u64,
+	authorities: &[(AuthorityId, Weight)],
+	authority_index: usize,
+) -> u64 {
+	let total_weight: u128 = authorities.iter().map(|&(_, weight)| u128::from(weight)).sum();
+	if total_weight == 0 {
+		return 0;
+	}
+
+	let weight = u128::from(authorities[authority_index].1);
+	// the weight is at most the total weight, so this fits into a `u64`.
+	(u128::from(threshold) * weight / total_weight) as u64
+}
+
+/// Claim a slot if it is our turn.  Resolves to `None` if it is not our turn.
+///
+/// This hashes the slot number, genesis hash, epoch index and epoch randomness
+/// into the VRF, which is evaluated by `signer`.  If the VRF produces a value
+/// less than our share of `threshold`, it is our turn, so it resolves to
+/// `Some(_)`.  Otherwise, it resolves to `None`.
 ///
-/// This hashes the slot number, epoch, genesis hash, and chain randomness into
-/// the VRF.  If the VRF produces a value less than `threshold`, it is our turn,
-/// so it returns `Some(_)`.  Otherwise, it returns `None`.
+/// Slots before the start of the epoch can't be claimed. Slots after its end
+/// are still claimed with it: blocks are authored against the epoch of their
+/// parent, and the first block after the end of an epoch starts the next one.
 fn claim_slot(
-	randomness: &[u8],
 	slot_number: u64,
 	genesis_hash: &[u8],
-	epoch: u64,
-	authorities: &[AuthorityId],
-	key: &sr25519::Pair,
+	epoch: &Epoch,
+	signer: &Signer<sr25519::Pair>,
 	threshold: u64,
-) -> Option<((VRFInOut, VRFProof, VRFProofBatchable), usize)> {
-	let public = &key.public();
-	let index = authorities.iter().position(|s| s == public)?;
-	let transcript = make_transcript(
-		randomness,
+) -> SignFuture<Option<((VRFInOut, VRFProof), usize)>> {
+	let public = signer.public();
+	let index = match epoch.authorities.iter().position(|(s, _)| s == &public) {
+		Some(index) => index,
+		None => return Box::new(future::ok(None)),
+	};
+	if slot_number < epoch.start_slot {
+		return Box::new(future::ok(None));
+	}
+	let transcript = make_transcript_data(
+		&epoch.randomness,
 		slot_number,
 		genesis_hash,
-		epoch,
+		epoch.epoch_index,
 	);
 
-	// Compute the threshold we will use.
-	//
-	// We already checked that authorities contains `key.public()`, so it can’t
-	// be empty.  Therefore, this division is safe.
-	let threshold = threshold / authorities.len() as u64;
+	let threshold = calculate_threshold(threshold, &epoch.authorities, index);
+
+	Box::new(signer.vrf_sign(transcript.clone()).and_then(move |signature| {
+		// the output is checked against our key, since it might come from a
+		// remote signer.
+		let (inout, _batchable_proof) = schnorrkel::PublicKey::from_bytes(public.as_slice())
+			.and_then(|p| p.vrf_verify(transcript.to_transcript(), &signature.output, &signature.proof))
+			.map_err(|_| keystore::Error::InvalidSignature)?;
 
-	get_keypair(key)
-		.vrf_sign_n_check(transcript, |inout| check(inout, threshold))
-		.map(|s|(s, index))
+		Ok(if check(&inout, threshold) {
+			Some(((inout, signature.proof), index))
+		} else {
+			None
+		})
+	}))
 }
 
 fn initialize_authorities_cache<B, C>(client: &C) -> Result<(), ConsensusError> where
@@ -835,7 +920,7 @@ pub fn import_queue<B, C, E>(
 	inherent_data_providers: InherentDataProviders,
 ) -> Result<(BabeImportQueue<B>, BabeLink), consensus_common::Error> where
 	B: BlockT,
-	C: 'static + ProvideRuntimeApi + ProvideCache<B> + Send + Sync + AuxStore,
+	C: 'static + ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B> + Send + Sync + AuxStore,
 	C::Api: BlockBuilderApi<B> + BabeApi<B>,
 	DigestItemFor<B>: CompatibleDigestItem,
 	E: 'static,
@@ -867,7 +952,7 @@ mod tests {
 	use super::*;
 
 	use client::LongestChain;
-	use consensus_common::NoNetwork as DummyOracle;
+	use consensus_common::{NoNetwork as DummyOracle, Proposal};
 	use network::test::*;
 	use network::test::{Block as TestBlock, PeersClient};
 	use runtime_primitives::traits::{Block as BlockT, DigestFor};
@@ -909,10 +994,10 @@ mod tests {
 
 	impl Proposer<TestBlock> for DummyProposer {
 		type Error = Error;
-		type Create = Result<TestBlock, Error>;
+		type Create = Result<Proposal<TestBlock>, Error>;
 
-		fn propose(&self, _: InherentData, digests: DigestFor<TestBlock>, _: Duration) -> Result<TestBlock, Error> {
-			self.1.new_block(digests).unwrap().bake().map_err(|e| e.into())
+		fn propose(&self, _: InherentData, digests: DigestFor<TestBlock>, _: Duration) -> Result<Proposal<TestBlock>, Error> {
+			self.1.new_block(digests).unwrap().bake().map(Into::into).map_err(|e| e.into())
 		}
 	}
 
@@ -1025,7 +1110,7 @@ mod tests {
 
 			runtime.spawn(start_babe(BabeParams {
 				config,
-				local_key: Arc::new(key.clone().into()),
+				local_key: Signer::from_pair(key.clone().into()),
 				block_import: client.clone(),
 				select_chain,
 				client,
@@ -1075,11 +1160,15 @@ mod tests {
 	#[test]
 	fn can_author_block() {
 		drop(env_logger::try_init());
-		let randomness = &[];
 		let (pair, _) = sr25519::Pair::generate();
+		let epoch = Epoch {
+			authorities: vec![(pair.public(), 1)],
+			..Default::default()
+		};
+		let signer = Signer::from_pair(pair);
 		let mut i = 0;
 		loop {
-			match claim_slot(randomness, i, &[], 0, &[pair.public()], &pair, u64::MAX / 10) {
+			match claim_slot(i, &[], &epoch, &signer, u64::MAX / 10).wait().unwrap() {
 				None => i += 1,
 				Some(s) => {
 					debug!(target: "babe", "Authored block {:?}", s);
@@ -1089,6 +1178,96 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn check_header_verifies_vrf_against_epoch() {
+		drop(env_logger::try_init());
+		let client = test_client::new();
+		let (pair, _) = sr25519::Pair::generate();
+		let (other, _) = sr25519::Pair::generate();
+		let epoch = Epoch {
+			epoch_index: 3,
+			start_slot: 0,
+			duration: 100,
+			authorities: vec![(pair.public(), 1), (other.public(), 3)],
+			randomness: [42; VRF_OUTPUT_LENGTH],
+		};
+		let threshold = u64::MAX / 2;
+		let genesis_hash = [7u8; 32];
+
+		let signer = Signer::from_pair(pair.clone());
+		let (slot_num, ((inout, proof), index)) = (1..)
+			.find_map(|slot_num| {
+				claim_slot(slot_num, &genesis_hash, &epoch, &signer, threshold)
+					.wait()
+					.unwrap()
+					.map(|c| (slot_num, c))
+			})
+			.expect("a slot is eventually claimed; qed");
+		// slots before the start of the epoch are never claimed.
+		let later_epoch = Epoch { start_slot: slot_num + 1, ..epoch.clone() };
+		assert!(
+			claim_slot(slot_num, &genesis_hash, &later_epoch, &signer, u64::MAX).wait().unwrap().is_none()
+		);
+		let mut header = test_client::runtime::Header {
+			parent_hash: Default::default(),
+			number: 1,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		};
+		header.digest_mut().push(CompatibleDigestItem::babe_pre_digest(BabePreDigest {
+			vrf_output: inout.to_output(),
+			proof,
+			index: index as u64,
+			slot_num,
+		}));
+		let signature = pair.sign(header.hash().as_ref());
+		header.digest_mut().push(CompatibleDigestItem::babe_seal(signature));
+
+		let check_with_genesis = |genesis_hash: &[u8], epoch: &Epoch| check_header::<TestBlock, _>(
+			&client,
+			slot_num,
+			header.clone(),
+			header.hash(),
+			genesis_hash,
+			epoch,
+			threshold,
+		).is_ok();
+		let check = |epoch: &Epoch| check_with_genesis(&genesis_hash, epoch);
+
+		assert!(check(&epoch));
+		// the VRF output is bound to the genesis hash.
+		assert!(!check_with_genesis(&[8u8; 32], &epoch));
+		// the slot must not be before the start of the epoch.
+		assert!(!check(&later_epoch));
+		// the VRF output is bound to the epoch index and randomness.
+		assert!(!check(&Epoch { epoch_index: 4, ..epoch.clone() }));
+		assert!(!check(&Epoch { randomness: [0; VRF_OUTPUT_LENGTH], ..epoch.clone() }));
+		// the claimed index must be the author's.
+		assert!(!check(&Epoch {
+			authorities: vec![(other.public(), 1), (pair.public(), 3)],
+			..epoch.clone()
+		}));
+		// an authority without any weight never wins a slot.
+		assert!(!check(&Epoch {
+			authorities: vec![(pair.public(), 0), (other.public(), 3)],
+			..epoch.clone()
+		}));
+	}
+
+	#[test]
+	fn threshold_is_proportional_to_weight() {
+		let authorities: Vec<(AuthorityId, Weight)> = vec![
+			(Keyring::Alice.into(), 1),
+			(Keyring::Bob.into(), 3),
+			(Keyring::Charlie.into(), 0),
+		];
+		assert_eq!(calculate_threshold(1000, &authorities, 0), 250);
+		assert_eq!(calculate_threshold(1000, &authorities, 1), 750);
+		assert_eq!(calculate_threshold(1000, &authorities, 2), 0);
+		assert_eq!(calculate_threshold(u64::MAX, &authorities[1..2], 0), u64::MAX);
+	}
+
 	#[test]
 	fn authorities_call_works() {
 		drop(env_logger::try_init());
@@ -18,6 +18,7 @@
 
 use runtime_primitives::traits::{Block as BlockT, DigestItemFor, Header as HeaderT, NumberFor};
 use runtime_primitives::Justification;
+use state_machine::OverlayedChanges;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -128,6 +129,11 @@ pub struct BlockImportParams<Block: BlockT> {
 	pub auxiliary: Vec<(Vec<u8>, Option<Vec<u8>>)>,
 	/// Fork choice strategy of this import.
 	pub fork_choice: ForkChoiceStrategy,
+	/// Storage changes recorded while authoring the block.
+	///
+	/// If present, the changes are applied without executing the block again, provided
+	/// the roots they produce match the ones in the header.
+	pub storage_changes: Option<OverlayedChanges>,
 }
 
 impl<Block: BlockT> BlockImportParams<Block> {
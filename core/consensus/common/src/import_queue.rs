@@ -25,7 +25,7 @@
 //! instantiated. The `BasicQueue` and `BasicVerifier` traits allow serial
 //! queues to be instantiated simply.
 
-use std::{sync::Arc, collections::HashMap};
+use std::{sync::Arc, collections::HashMap, fmt, time::Duration};
 use runtime_primitives::{Justification, traits::{Block as BlockT, Header as _, NumberFor}};
 use crate::{error::Error as ConsensusError, well_known_cache_keys::Id as CacheKeyId};
 use crate::block_import::{
@@ -65,10 +65,42 @@ pub struct IncomingBlock<B: BlockT> {
 	pub origin: Option<Origin>,
 }
 
+/// Error returned by a `Verifier`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum VerificationError {
+	/// The block is invalid. The message is presented to the user in the logs.
+	Invalid(String),
+	/// The block was produced for a slot that has not started yet. It should be verified again
+	/// once the given duration has elapsed.
+	TooFarInFuture(Duration),
+}
+
+impl From<String> for VerificationError {
+	fn from(msg: String) -> Self {
+		VerificationError::Invalid(msg)
+	}
+}
+
+impl<'a> From<&'a str> for VerificationError {
+	fn from(msg: &'a str) -> Self {
+		VerificationError::Invalid(msg.into())
+	}
+}
+
+impl fmt::Display for VerificationError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			VerificationError::Invalid(msg) => write!(f, "{}", msg),
+			VerificationError::TooFarInFuture(wait) =>
+				write!(f, "Block is too far in the future, can be verified in {:?}", wait),
+		}
+	}
+}
+
 /// Verify a justification of a block
 pub trait Verifier<B: BlockT>: Send + Sync {
 	/// Verify the given data and return the BlockImportParams and an optional
-	/// new set of validators to import. If not, err with an Error-Message
+	/// new set of validators to import. If not, err with a `VerificationError`, whose message is
 	/// presented to the User in the logs.
 	fn verify(
 		&self,
@@ -76,9 +108,24 @@ pub trait Verifier<B: BlockT>: Send + Sync {
 		header: B::Header,
 		justification: Option<Justification>,
 		body: Option<Vec<B::Extrinsic>>,
-	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String>;
+	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), VerificationError>;
+}
+
+/// Check the signatures of block bodies before the blocks are imported.
+///
+/// The import queue calls this from a thread pool for all extrinsics of the blocks it is about
+/// to import, before taking the import lock. Implementations should verify signatures through
+/// `substrate_primitives::batch_verifier::pre_verify`, so that the runtime does not verify them a
+/// second time while executing the block.
+pub trait ExtrinsicPreVerifier<B: BlockT>: Send + Sync {
+	/// Returns `false` if the extrinsic carries an invalid signature. Extrinsics that are unsigned
+	/// or that can not be checked outside of the runtime should return `true`.
+	fn pre_verify(&self, extrinsic: &B::Extrinsic) -> bool;
 }
 
+/// Shared extrinsic pre-verifier used by the queue.
+pub type SharedExtrinsicPreVerifier<B> = Arc<dyn ExtrinsicPreVerifier<B>>;
+
 /// Blocks import queue API.
 ///
 /// The `import_*` methods can be called in order to send elements for the import queue to verify.
@@ -159,6 +206,9 @@ pub enum BlockImportError {
 	BadBlock(Option<Origin>),
 	/// Block has an unknown parent
 	UnknownParent,
+	/// Block was produced for a slot that has not started yet and can be imported after the
+	/// given duration.
+	TooFarInFuture(Option<Origin>, Duration),
 	/// Block import has been cancelled. This can happen if the parent block fails to be imported.
 	Cancelled,
 	/// Other error.
@@ -220,13 +270,19 @@ pub fn import_single_block<B: BlockT, V: Verifier<B>>(
 	}
 
 	let (import_block, maybe_keys) = verifier.verify(block_origin, header, justification, block.body)
-		.map_err(|msg| {
-			if let Some(ref peer) = peer {
-				trace!(target: "sync", "Verifying {}({}) from {} failed: {}", number, hash, peer, msg);
-			} else {
-				trace!(target: "sync", "Verifying {}({}) failed: {}", number, hash, msg);
-			}
-			BlockImportError::VerificationFailed(peer.clone(), msg)
+		.map_err(|e| match e {
+			VerificationError::TooFarInFuture(wait) => {
+				trace!(target: "sync", "Verifying {}({}) deferred by {:?}", number, hash, wait);
+				BlockImportError::TooFarInFuture(peer.clone(), wait)
+			},
+			VerificationError::Invalid(msg) => {
+				if let Some(ref peer) = peer {
+					trace!(target: "sync", "Verifying {}({}) from {} failed: {}", number, hash, peer, msg);
+				} else {
+					trace!(target: "sync", "Verifying {}({}) failed: {}", number, hash, msg);
+				}
+				BlockImportError::VerificationFailed(peer.clone(), msg)
+			},
 		})?;
 
 	let mut cache = HashMap::new();
@@ -14,15 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
-use futures::{prelude::*, future::Executor, sync::mpsc};
+use std::{iter, sync::Arc, time::{Duration, Instant}};
+use futures::{prelude::*, future::Executor, stream::FuturesUnordered, sync::mpsc};
+use rayon::prelude::*;
+use tokio_timer::Delay;
 use runtime_primitives::{Justification, traits::{Block as BlockT, Header as HeaderT, NumberFor}};
 
 use crate::error::Error as ConsensusError;
 use crate::block_import::{BlockImport, BlockOrigin};
 use crate::import_queue::{
-	BlockImportResult, BlockImportError, Verifier, BoxBlockImport, BoxFinalityProofImport,
-	BoxJustificationImport, ImportQueue, Link, Origin,
+	BlockImportResult, BlockImportError, Verifier, ExtrinsicPreVerifier, BoxBlockImport, BoxFinalityProofImport,
+	BoxJustificationImport, ImportQueue, Link, Origin, SharedExtrinsicPreVerifier,
 	IncomingBlock, import_single_block,
 	buffered_link::{self, BufferedLinkSender, BufferedLinkReceiver}
 };
@@ -55,6 +57,20 @@ impl<B: BlockT> BasicQueue<B> {
 		block_import: BoxBlockImport<B>,
 		justification_import: Option<BoxJustificationImport<B>>,
 		finality_proof_import: Option<BoxFinalityProofImport<B>>,
+	) -> Self {
+		Self::with_pre_verifier(verifier, block_import, justification_import, finality_proof_import, None)
+	}
+
+	/// Instantiate a new basic queue, with given verifier and extrinsic pre-verifier.
+	///
+	/// The signatures of the bodies of queued blocks are checked in parallel by the pre-verifier
+	/// before the blocks are imported one by one.
+	pub fn with_pre_verifier<V: 'static + Verifier<B>>(
+		verifier: Arc<V>,
+		block_import: BoxBlockImport<B>,
+		justification_import: Option<BoxJustificationImport<B>>,
+		finality_proof_import: Option<BoxFinalityProofImport<B>>,
+		pre_verifier: Option<SharedExtrinsicPreVerifier<B>>,
 	) -> Self {
 		let (result_sender, result_port) = buffered_link::buffered_link();
 		let (future, worker_sender) = BlockImportWorker::new(
@@ -63,6 +79,7 @@ impl<B: BlockT> BasicQueue<B> {
 			block_import,
 			justification_import,
 			finality_proof_import,
+			pre_verifier,
 		);
 
 		Self {
@@ -135,8 +152,15 @@ struct BlockImportWorker<B: BlockT, V: Verifier<B>> {
 	justification_import: Option<BoxJustificationImport<B>>,
 	finality_proof_import: Option<BoxFinalityProofImport<B>>,
 	verifier: Arc<V>,
+	pre_verifier: Option<SharedExtrinsicPreVerifier<B>>,
+	/// Blocks that were too far in the future to be imported. Each future resolves once it is
+	/// time to import them again.
+	deferred: FuturesUnordered<DeferredBlocks<B>>,
 }
 
+/// Future that yields a batch of deferred blocks once their delay has elapsed.
+type DeferredBlocks<B> = Box<dyn Future<Item = (BlockOrigin, Vec<IncomingBlock<B>>), Error = ()> + Send>;
+
 impl<B: BlockT, V: 'static + Verifier<B>> BlockImportWorker<B, V> {
 	fn new(
 		result_sender: BufferedLinkSender<B>,
@@ -144,6 +168,7 @@ impl<B: BlockT, V: 'static + Verifier<B>> BlockImportWorker<B, V> {
 		block_import: BoxBlockImport<B>,
 		justification_import: Option<BoxJustificationImport<B>>,
 		finality_proof_import: Option<BoxFinalityProofImport<B>>,
+		pre_verifier: Option<SharedExtrinsicPreVerifier<B>>,
 	) -> (impl Future<Item = (), Error = ()> + Send, mpsc::UnboundedSender<ToWorkerMsg<B>>) {
 		let (sender, mut port) = mpsc::unbounded();
 
@@ -153,6 +178,8 @@ impl<B: BlockT, V: 'static + Verifier<B>> BlockImportWorker<B, V> {
 			justification_import,
 			block_import,
 			finality_proof_import,
+			pre_verifier,
+			deferred: FuturesUnordered::new(),
 		};
 
 		if let Some(justification_import) = worker.justification_import.as_mut() {
@@ -172,7 +199,7 @@ impl<B: BlockT, V: 'static + Verifier<B>> BlockImportWorker<B, V> {
 				let msg = match port.poll() {
 					Ok(Async::Ready(Some(msg))) => msg,
 					Err(_) | Ok(Async::Ready(None)) => return Ok(Async::Ready(())),
-					Ok(Async::NotReady) => return Ok(Async::NotReady),
+					Ok(Async::NotReady) => break,
 				};
 
 				match msg {
@@ -187,6 +214,11 @@ impl<B: BlockT, V: 'static + Verifier<B>> BlockImportWorker<B, V> {
 					}
 				}
 			}
+
+			// Polled after the messages, so that the timers of blocks deferred above are
+			// registered with the task.
+			worker.import_deferred_blocks();
+			Ok(Async::NotReady)
 		});
 
 		(future, sender)
@@ -194,17 +226,37 @@ impl<B: BlockT, V: 'static + Verifier<B>> BlockImportWorker<B, V> {
 
 	fn import_a_batch_of_blocks(&mut self, origin: BlockOrigin, blocks: Vec<IncomingBlock<B>>) {
 		let result_sender = &self.result_sender;
-		let (imported, count, results) = import_many_blocks(
+		let (imported, count, results, deferred) = import_many_blocks(
 			&mut *self.block_import,
-			origin,
+			origin.clone(),
 			blocks,
 			self.verifier.clone(),
+			self.pre_verifier.as_ref().map(|p| &**p),
 			|| !result_sender.is_closed(),
 		);
 
+		if let Some((wait, blocks)) = deferred {
+			let deferred = Delay::new(Instant::now() + wait).then(move |result| {
+				if let Err(e) = result {
+					warn!(target: "sync", "Timer for deferred blocks failed, importing now: {:?}", e);
+				}
+				Ok((origin, blocks))
+			});
+			self.deferred.push(Box::new(deferred));
+		}
+
 		self.result_sender.blocks_processed(imported, count, results);
 	}
 
+	/// Import the deferred blocks whose delay has elapsed.
+	///
+	/// Blocks are deferred again if they are still too far in the future.
+	fn import_deferred_blocks(&mut self) {
+		while let Ok(Async::Ready(Some((origin, blocks)))) = self.deferred.poll() {
+			self.import_a_batch_of_blocks(origin, blocks);
+		}
+	}
+
 	fn import_finality_proof(&mut self, who: Origin, hash: B::Hash, number: NumberFor<B>, finality_proof: Vec<u8>) {
 		let verifier = &*self.verifier;
 		let result = self.finality_proof_import.as_mut().map(|finality_proof_import| {
@@ -250,8 +302,29 @@ impl<B: BlockT, V: 'static + Verifier<B>> BlockImportWorker<B, V> {
 	}
 }
 
+/// Check the bodies of all given blocks with the pre-verifier on a thread pool.
+///
+/// Returns for each block whether all of its extrinsics passed.
+fn pre_verify_blocks<B: BlockT>(
+	blocks: &[IncomingBlock<B>],
+	pre_verifier: &dyn ExtrinsicPreVerifier<B>,
+) -> Vec<bool> {
+	blocks.par_iter()
+		.map(|block| block.body.as_ref().map_or(true, |body| {
+			body.par_iter().all(|extrinsic| pre_verifier.pre_verify(extrinsic))
+		}))
+		.collect()
+}
+
 /// Import several blocks at once, returning import result for each block.
 ///
+/// If a `pre_verifier` is given, the signatures of all block bodies are checked in parallel
+/// first and blocks with a bad signature fail verification without being imported.
+///
+/// If a block is too far in the future, the import stops without a result for that block and
+/// the following ones. These blocks are returned with the duration after which they should be
+/// imported again.
+///
 /// The `keep_going` closure will be called regularly. If it returns false, then the function will
 /// end prematurely.
 fn import_many_blocks<B: BlockT, V: Verifier<B>>(
@@ -259,11 +332,12 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>>(
 	blocks_origin: BlockOrigin,
 	blocks: Vec<IncomingBlock<B>>,
 	verifier: Arc<V>,
+	pre_verifier: Option<&dyn ExtrinsicPreVerifier<B>>,
 	keep_going: impl Fn() -> bool,
 ) -> (usize, usize, Vec<(
 	Result<BlockImportResult<NumberFor<B>>, BlockImportError>,
 	B::Hash,
-)>) {
+)>, Option<(Duration, Vec<IncomingBlock<B>>)>) {
 	let count = blocks.len();
 	let mut imported = 0;
 
@@ -278,12 +352,19 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>>(
 
 	trace!(target: "sync", "Starting import of {} blocks {}", count, blocks_range);
 
+	let pre_verified = match pre_verifier {
+		Some(pre_verifier) => pre_verify_blocks(&blocks, pre_verifier),
+		None => vec![true; count],
+	};
+
 	let mut results = vec![];
+	let mut deferred = None;
 
 	let mut has_error = false;
 
 	// Blocks in the response/drain should be in ascending order.
-	for block in blocks {
+	let mut blocks = blocks.into_iter().zip(pre_verified);
+	while let Some((block, pre_verified)) = blocks.next() {
 		if !keep_going() {
 			// Setting `has_error` to true cancels the rest of the import.
 			has_error = true;
@@ -293,13 +374,36 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>>(
 		let block_hash = block.hash;
 		let import_result = if has_error {
 			Err(BlockImportError::Cancelled)
+		} else if !pre_verified {
+			debug!(target: "sync", "Block {:?} ({}) has an extrinsic with a bad signature", block_number, block_hash);
+			Err(BlockImportError::VerificationFailed(
+				block.origin,
+				"Block contains an extrinsic with a bad signature".into(),
+			))
 		} else {
-			import_single_block(
+			// The block is cloned to be able to requeue it if it is too far in the future.
+			match import_single_block(
 				import_handle,
 				blocks_origin.clone(),
-				block,
+				block.clone(),
 				verifier.clone(),
-			)
+			) {
+				Err(BlockImportError::TooFarInFuture(_, wait)) => {
+					debug!(
+						target: "sync",
+						"Block {:?} ({}) is too far in the future, deferring import by {:?}",
+						block_number,
+						block_hash,
+						wait,
+					);
+					let requeued = iter::once(block)
+						.chain(blocks.by_ref().map(|(block, _)| block))
+						.collect();
+					deferred = Some((wait, requeued));
+					break;
+				},
+				import_result => import_result,
+			}
 		};
 		let was_ok = import_result.is_ok();
 		if was_ok {
@@ -311,5 +415,264 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>>(
 		results.push((import_result, block_hash));
 	}
 
-	(imported, count, results)
+	// Deferred blocks are not accounted for in this batch.
+	(imported, results.len(), results, deferred)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::block_import::BlockImportParams;
+	use crate::import_queue::VerificationError;
+	use crate::well_known_cache_keys::Id as CacheKeyId;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use parity_codec::Encode;
+	use primitives::{batch_verifier, crypto::key_types};
+	use test_client::AccountKeyring;
+	use test_client::runtime::{Block, Extrinsic, Hash, Header, Transfer};
+
+	struct RejectingVerifier;
+
+	impl Verifier<Block> for RejectingVerifier {
+		fn verify(
+			&self,
+			_origin: BlockOrigin,
+			_header: Header,
+			_justification: Option<Justification>,
+			_body: Option<Vec<Extrinsic>>,
+		) -> Result<(BlockImportParams<Block>, Option<Vec<(CacheKeyId, Vec<u8>)>>), VerificationError> {
+			Err("not verified".into())
+		}
+	}
+
+	struct DeferringVerifier;
+
+	impl Verifier<Block> for DeferringVerifier {
+		fn verify(
+			&self,
+			_origin: BlockOrigin,
+			_header: Header,
+			_justification: Option<Justification>,
+			_body: Option<Vec<Extrinsic>>,
+		) -> Result<(BlockImportParams<Block>, Option<Vec<(CacheKeyId, Vec<u8>)>>), VerificationError> {
+			Err(VerificationError::TooFarInFuture(Duration::from_secs(3)))
+		}
+	}
+
+	/// Defers the first block it is given, then rejects every block.
+	#[derive(Default)]
+	struct DeferOnce(AtomicBool);
+
+	impl Verifier<Block> for DeferOnce {
+		fn verify(
+			&self,
+			_origin: BlockOrigin,
+			_header: Header,
+			_justification: Option<Justification>,
+			_body: Option<Vec<Extrinsic>>,
+		) -> Result<(BlockImportParams<Block>, Option<Vec<(CacheKeyId, Vec<u8>)>>), VerificationError> {
+			if self.0.swap(true, Ordering::SeqCst) {
+				Err("not verified".into())
+			} else {
+				Err(VerificationError::TooFarInFuture(Duration::from_millis(50)))
+			}
+		}
+	}
+
+	#[derive(Default)]
+	struct RecordingLink(Vec<(Result<BlockImportResult<u64>, BlockImportError>, Hash)>);
+
+	impl Link<Block> for RecordingLink {
+		fn blocks_processed(
+			&mut self,
+			_imported: usize,
+			_count: usize,
+			results: Vec<(Result<BlockImportResult<u64>, BlockImportError>, Hash)>
+		) {
+			self.0.extend(results);
+		}
+	}
+
+	/// Checks the sr25519 signatures of transfers, like the runtime does when executing them.
+	struct TransferPreVerifier;
+
+	impl ExtrinsicPreVerifier<Block> for TransferPreVerifier {
+		fn pre_verify(&self, extrinsic: &Extrinsic) -> bool {
+			match extrinsic {
+				Extrinsic::Transfer(transfer, signature) |
+				Extrinsic::TransferWithOptions(transfer, signature, _) => batch_verifier::pre_verify(
+					key_types::SR25519,
+					&signature.0,
+					&transfer.encode(),
+					&transfer.from.0,
+				),
+				_ => true,
+			}
+		}
+	}
+
+	fn transfer(nonce: u64) -> Transfer {
+		Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 1,
+			nonce,
+		}
+	}
+
+	fn badly_signed(nonce: u64) -> Extrinsic {
+		let signature = AccountKeyring::Alice.sign(&transfer(nonce + 1).encode()).into();
+		Extrinsic::Transfer(transfer(nonce), signature)
+	}
+
+	fn incoming_block(parent_hash: Hash, body: Vec<Extrinsic>) -> IncomingBlock<Block> {
+		let header = Header {
+			number: 1,
+			parent_hash,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		};
+		IncomingBlock {
+			hash: header.hash(),
+			header: Some(header),
+			body: Some(body),
+			justification: None,
+			origin: None,
+		}
+	}
+
+	#[test]
+	fn blocks_with_bad_signatures_fail_pre_verification() {
+		let mut client = test_client::new();
+		let genesis_hash = client.info().chain.genesis_hash;
+		let blocks = vec![
+			incoming_block(genesis_hash, vec![transfer(0).into_signed_tx(), transfer(1).into_signed_tx()]),
+			incoming_block(genesis_hash, vec![transfer(2).into_signed_tx(), badly_signed(3)]),
+			incoming_block(genesis_hash, vec![Extrinsic::IncludeData(b"unsigned".to_vec())]),
+		];
+
+		assert_eq!(pre_verify_blocks(&blocks, &TransferPreVerifier), vec![true, false, true]);
+
+		// The first block passes pre-verification and is rejected by the verifier, which
+		// cancels the rest of the import.
+		let (imported, count, results, _) = import_many_blocks(
+			&mut client,
+			BlockOrigin::NetworkBroadcast,
+			blocks,
+			Arc::new(RejectingVerifier),
+			Some(&TransferPreVerifier),
+			|| true,
+		);
+		assert_eq!((imported, count), (0, 3));
+		match results[0].0 {
+			Err(BlockImportError::VerificationFailed(_, ref msg)) => assert_eq!(msg, "not verified"),
+			ref r => panic!("Unexpected result {:?}", r),
+		}
+		match results[1].0 {
+			Err(BlockImportError::Cancelled) => {},
+			ref r => panic!("Unexpected result {:?}", r),
+		}
+
+		let bad_block = incoming_block(genesis_hash, vec![badly_signed(4)]);
+		let (imported, _, results, _) = import_many_blocks(
+			&mut client,
+			BlockOrigin::NetworkBroadcast,
+			vec![bad_block],
+			Arc::new(RejectingVerifier),
+			Some(&TransferPreVerifier),
+			|| true,
+		);
+		assert_eq!(imported, 0);
+		match results[0].0 {
+			Err(BlockImportError::VerificationFailed(_, ref msg)) =>
+				assert_eq!(msg, "Block contains an extrinsic with a bad signature"),
+			ref r => panic!("Unexpected result {:?}", r),
+		}
+	}
+
+	#[test]
+	fn pre_verified_signatures_are_not_verified_again_on_execution() {
+		let client = test_client::new();
+		let genesis_hash = client.info().chain.genesis_hash;
+		let transfer = transfer(5);
+		let signature = AccountKeyring::Alice.sign(&transfer.encode());
+		let block = incoming_block(
+			genesis_hash,
+			vec![Extrinsic::Transfer(transfer.clone(), signature.clone().into())],
+		);
+
+		assert_eq!(pre_verify_blocks(&[block], &TransferPreVerifier), vec![true]);
+
+		// This is what the `sig_batch_verify` host function does while the block is executed.
+		let mut batch = batch_verifier::BatchVerifier::new();
+		assert!(batch.push(key_types::SR25519, &signature.0, &transfer.encode(), &transfer.from.0));
+		assert!(batch.is_empty());
+	}
+
+	#[test]
+	fn blocks_too_far_in_future_are_deferred() {
+		let mut client = test_client::new();
+		let genesis_hash = client.info().chain.genesis_hash;
+		let blocks = vec![
+			incoming_block(genesis_hash, vec![Extrinsic::IncludeData(b"first".to_vec())]),
+			incoming_block(genesis_hash, vec![Extrinsic::IncludeData(b"second".to_vec())]),
+		];
+		let hashes: Vec<_> = blocks.iter().map(|b| b.hash).collect();
+
+		let (imported, count, results, deferred) = import_many_blocks(
+			&mut client,
+			BlockOrigin::NetworkBroadcast,
+			blocks,
+			Arc::new(DeferringVerifier),
+			None,
+			|| true,
+		);
+
+		assert_eq!((imported, count), (0, 0));
+		assert!(results.is_empty());
+		let (wait, deferred) = deferred.expect("blocks are deferred");
+		assert_eq!(wait, Duration::from_secs(3));
+		assert_eq!(deferred.iter().map(|b| b.hash).collect::<Vec<_>>(), hashes);
+	}
+
+	#[test]
+	fn deferred_blocks_are_imported_once_their_delay_elapses() {
+		let client = test_client::new();
+		let genesis_hash = client.info().chain.genesis_hash;
+		let block = incoming_block(genesis_hash, vec![Extrinsic::IncludeData(b"deferred".to_vec())]);
+		let hash = block.hash;
+
+		let (result_sender, mut result_port) = buffered_link::buffered_link();
+		let (worker, sender) = BlockImportWorker::new(
+			result_sender,
+			Arc::new(DeferOnce::default()),
+			Box::new(client),
+			None,
+			None,
+			None,
+		);
+
+		let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
+		runtime.spawn(worker);
+		sender.unbounded_send(ToWorkerMsg::ImportBlocks(BlockOrigin::NetworkBroadcast, vec![block])).unwrap();
+
+		// Nothing else wakes the worker up, so the block is only retried if its timer is polled.
+		let mut link = RecordingLink::default();
+		let results = runtime.block_on(futures::future::poll_fn(|| -> Result<_, ()> {
+			result_port.poll_actions(&mut link);
+			if link.0.is_empty() {
+				Ok(Async::NotReady)
+			} else {
+				Ok(Async::Ready(std::mem::replace(&mut link.0, Vec::new())))
+			}
+		})).unwrap();
+
+		assert_eq!(results.len(), 1);
+		assert_eq!(results[0].1, hash);
+		match results[0].0 {
+			Err(BlockImportError::VerificationFailed(_, ref msg)) => assert_eq!(msg, "not verified"),
+			ref r => panic!("Unexpected result {:?}", r),
+		}
+	}
 }
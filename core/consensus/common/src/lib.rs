@@ -33,6 +33,7 @@ use std::time::Duration;
 
 use runtime_primitives::traits::{Block as BlockT, DigestFor};
 use futures::prelude::*;
+use state_machine::OverlayedChanges;
 pub use inherents::InherentData;
 
 pub mod offline_tracker;
@@ -65,6 +66,22 @@ pub trait Environment<B: BlockT> {
 		-> Result<Self::Proposer, Self::Error>;
 }
 
+/// A block proposed by a `Proposer`.
+pub struct Proposal<B: BlockT> {
+	/// The proposed block.
+	pub block: B,
+	/// The storage changes made while building the block, if they were recorded.
+	///
+	/// They can be passed to the import in `BlockImportParams::storage_changes`.
+	pub storage_changes: Option<OverlayedChanges>,
+}
+
+impl<B: BlockT> From<B> for Proposal<B> {
+	fn from(block: B) -> Self {
+		Proposal { block, storage_changes: None }
+	}
+}
+
 /// Logic for a proposer.
 ///
 /// This will encapsulate creation and evaluation of proposals at a specific
@@ -75,7 +92,7 @@ pub trait Proposer<B: BlockT> {
 	/// Error type which can occur when proposing or evaluating.
 	type Error: From<Error> + ::std::fmt::Debug + 'static;
 	/// Future that resolves to a committed proposal.
-	type Create: IntoFuture<Item=B, Error=Self::Error>;
+	type Create: IntoFuture<Item=Proposal<B>, Error=Self::Error>;
 	/// Create a proposal.
 	fn propose(
 		&self,
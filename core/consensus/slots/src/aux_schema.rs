@@ -17,9 +17,12 @@
 //! Schema for slots in the aux-db.
 
 use codec::{Encode, Decode};
+use client::aux_pruning::{AuxDataPruner, FinalizedBlocks};
 use client::backend::AuxStore;
+use client::blockchain::HeaderBackend;
 use client::error::{Result as ClientResult, Error as ClientError};
-use runtime_primitives::traits::Header;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, Header};
 
 const SLOT_HEADER_MAP_KEY: &[u8] = b"slot_header_map";
 const SLOT_HEADER_START: &[u8] = b"slot_header_start";
@@ -38,7 +41,7 @@ fn load_decode<C, T>(backend: &C, key: &[u8]) -> ClientResult<Option<T>>
 		None => Ok(None),
 		Some(t) => T::decode(&mut &t[..])
 			.ok_or_else(
-				|| ClientError::Backend(format!("Slots DB is corrupted.")).into(),
+				|| ClientError::Backend("Slots DB is corrupted.".into()).into(),
 			)
 			.map(Some)
 	}
@@ -149,6 +152,63 @@ pub fn check_equivocation<C, H, P>(
 	Ok(None)
 }
 
+/// Prunes the headers kept to check equivocations once their slot is more than
+/// `MAX_SLOT_CAPACITY` behind the slot of the last finalized block.
+///
+/// The pruning in `check_equivocation` depends on the local time, while this one only depends on
+/// finality and so removes the same headers on every node.
+pub struct EquivocationPruner<F> {
+	slot_of: F,
+}
+
+impl<F> EquivocationPruner<F> {
+	/// Create a pruner reading the slot of a header with `slot_of`.
+	pub fn new(slot_of: F) -> Self {
+		EquivocationPruner { slot_of }
+	}
+}
+
+impl<B, F> AuxDataPruner<B> for EquivocationPruner<F> where
+	B: BlockT,
+	F: Fn(&B::Header) -> Option<u64> + Send + Sync,
+{
+	fn prune(
+		&self,
+		blocks: &FinalizedBlocks<B>,
+		headers: &dyn HeaderBackend<B>,
+		get_aux: &dyn Fn(&[u8]) -> ClientResult<Option<Vec<u8>>>,
+	) -> ClientResult<Vec<(Vec<u8>, Option<Vec<u8>>)>> {
+		let finalized = match blocks.finalized.last() {
+			Some(finalized) => headers.expect_header(BlockId::Hash(finalized.hash))?,
+			None => return Ok(Vec::new()),
+		};
+		let finalized_slot = match (self.slot_of)(&finalized) {
+			Some(slot) => slot,
+			None => return Ok(Vec::new()),
+		};
+		let first_saved_slot = match get_aux(SLOT_HEADER_START)? {
+			Some(slot) => u64::decode(&mut &slot[..])
+				.ok_or_else(|| ClientError::Backend("Slots DB is corrupted.".into()))?,
+			None => return Ok(Vec::new()),
+		};
+
+		let new_first_saved_slot = finalized_slot.saturating_sub(MAX_SLOT_CAPACITY);
+		if new_first_saved_slot <= first_saved_slot {
+			return Ok(Vec::new());
+		}
+
+		let mut changes = Vec::new();
+		for s in first_saved_slot..new_first_saved_slot {
+			let mut key = SLOT_HEADER_MAP_KEY.to_vec();
+			s.using_encoded(|s| key.extend(s));
+			changes.push((key, None));
+		}
+		changes.push((SLOT_HEADER_START.to_vec(), Some(new_first_saved_slot.encode())));
+
+		Ok(changes)
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use primitives::{sr25519, Pair};
@@ -156,7 +216,7 @@ mod test {
 	use runtime_primitives::testing::{Header as HeaderTest, Digest as DigestTest};
 	use test_client;
 
-	use super::{MAX_SLOT_CAPACITY, PRUNING_BOUND, check_equivocation};
+	use super::{MAX_SLOT_CAPACITY, PRUNING_BOUND, EquivocationPruner, check_equivocation};
 
 	fn create_header(number: u64) -> HeaderTest {
 		// so that different headers for the same number get different hashes
@@ -262,4 +322,50 @@ mod test {
 			).unwrap().is_none(),
 		);
 	}
+
+	#[test]
+	fn equivocation_pruner_prunes_behind_finalized_slot() {
+		use client::aux_pruning::{AuxDataPruner, FinalizedBlocks};
+		use client::backend::AuxStore;
+		use client::blockchain::RouteEntry;
+		use test_client::runtime::{Block, Header};
+
+		let client = test_client::new();
+		let (pair, _seed) = sr25519::Pair::generate();
+		let public = pair.public();
+
+		let header1 = create_header(1); // @ slot 2
+		let header2 = create_header(2); // @ slot 2
+		let header3 = create_header(2); // @ slot 4
+		let header4 = create_header(3); // @ slot 4
+
+		assert!(check_equivocation(&client, 2, 2, &header1, &public).unwrap().is_none());
+		assert!(check_equivocation(&client, 4, 4, &header3, &public).unwrap().is_none());
+
+		// the finalized block is at a slot which leaves slot 2 out of the capacity.
+		let genesis = client.info().chain.genesis_hash;
+		let blocks = FinalizedBlocks::<Block> {
+			previous: RouteEntry { hash: genesis, number: 0 },
+			finalized: vec![RouteEntry { hash: genesis, number: 0 }],
+			displaced: Vec::new(),
+			stale_heads: Vec::new(),
+		};
+		let pruner = EquivocationPruner::new(|_: &Header| Some(MAX_SLOT_CAPACITY + 3));
+		let changes = pruner.prune(&blocks, &client, &|key: &[u8]| client.get_aux(key)).unwrap();
+		let insert = changes.iter()
+			.filter_map(|(key, value)| value.as_ref().map(|value| (&key[..], &value[..])))
+			.collect::<Vec<_>>();
+		let delete = changes.iter()
+			.filter(|(_, value)| value.is_none())
+			.map(|(key, _)| &key[..])
+			.collect::<Vec<_>>();
+		assert_eq!(delete.len(), 1);
+		client.insert_aux(&insert, &delete).unwrap();
+
+		// header 1 was pruned, so this is not detected anymore.
+		assert!(check_equivocation(&client, 5, 2, &header2, &public).unwrap().is_none());
+
+		// but header 3 is still known.
+		assert!(check_equivocation(&client, 5, 4, &header4, &public).unwrap().is_some());
+	}
 }
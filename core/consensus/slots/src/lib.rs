@@ -25,8 +25,10 @@
 mod slots;
 mod aux_schema;
 
-pub use slots::{SignedDuration, SlotInfo, Slots};
-pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
+pub use slots::{SignedDuration, SlotInfo, Slots, time_until_slot};
+pub use aux_schema::{
+	check_equivocation, EquivocationProof, EquivocationPruner, MAX_SLOT_CAPACITY, PRUNING_BOUND,
+};
 
 use codec::{Decode, Encode};
 use consensus_common::{SyncOracle, SelectChain};
@@ -135,6 +137,10 @@ where
 	)
 }
 
+/// The maximum time a block produced for a future slot is held back in the import queue before
+/// it is verified again. Blocks for slots further in the future are rejected.
+pub const MAX_SLOT_DEFERRAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 /// A header which has been checked
 pub enum CheckedHeader<H, S> {
 	/// A header which has slot in the future. this is the full header (not stripped)
@@ -69,6 +69,25 @@ pub fn time_until_next(now: Duration, slot_duration: u64) -> Duration {
 	Duration::new(remaining_full_secs, remaining_nanos)
 }
 
+/// Returns the duration until the given slot starts, based on the current timestamp in seconds.
+///
+/// Returns `None` if the slot starts more than `max_wait` from now.
+pub fn time_until_slot(
+	timestamp_now: u64,
+	slot: u64,
+	slot_duration: u64,
+	max_wait: Duration,
+) -> Option<Duration> {
+	let wait = Duration::from_secs(
+		slot.saturating_mul(slot_duration).saturating_sub(timestamp_now)
+	);
+	if wait > max_wait {
+		None
+	} else {
+		Some(wait)
+	}
+}
+
 /// Information about a slot.
 pub struct SlotInfo {
 	/// The slot number.
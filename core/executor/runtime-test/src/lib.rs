@@ -9,7 +9,8 @@ use rstd::{vec::Vec, slice, vec};
 
 use runtime_io::{
 	set_storage, storage, clear_prefix, print, blake2_128, blake2_256,
-	twox_128, twox_256, ed25519_verify, sr25519_verify, enumerated_trie_root
+	twox_128, twox_256, keccak_256, sha2_256, ripemd_160, ed25519_verify, sr25519_verify,
+	enumerated_trie_root
 };
 
 macro_rules! impl_stubs {
@@ -72,6 +73,9 @@ impl_stubs!(
 	test_blake2_128 => |input| blake2_128(input).to_vec(),
 	test_twox_256 => |input| twox_256(input).to_vec(),
 	test_twox_128 => |input| twox_128(input).to_vec(),
+	test_keccak_256 => |input| keccak_256(input).to_vec(),
+	test_sha2_256 => |input| sha2_256(input).to_vec(),
+	test_ripemd_160 => |input| ripemd_160(input).to_vec(),
 	test_ed25519_verify => |input: &[u8]| {
 		let mut pubkey = [0; 32];
 		let mut sig = [0; 64];
@@ -172,6 +176,14 @@ impl_stubs!(
 
 		[if run().is_some() { 0 } else { 1 }].to_vec()
 	},
+	test_static_counter => |_| {
+		static mut COUNTER: u8 = 0;
+		let value = unsafe {
+			COUNTER += 1;
+			COUNTER
+		};
+		[value].to_vec()
+	},
 );
 
 fn execute_sandboxed(code: &[u8], args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
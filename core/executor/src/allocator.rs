@@ -17,10 +17,10 @@
 //! This module implements a freeing-bump allocator.
 //! See more details at https://github.com/paritytech/substrate/issues/1615.
 
+use std::rc::Rc;
 use crate::error::{Error, Result};
+use crate::wasm_runtime::Memory;
 use log::trace;
-use wasmi::MemoryRef;
-use wasmi::memory_units::Bytes;
 
 // The pointers need to be aligned to 8 bytes.
 const ALIGNMENT: u32 = 8;
@@ -36,7 +36,7 @@ const MAX_POSSIBLE_ALLOCATION: u32 = 16777216; // 2^24 bytes
 pub struct FreeingBumpHeapAllocator {
 	bumper: u32,
 	heads: [u32; N],
-	heap: MemoryRef,
+	heap: Rc<dyn Memory>,
 	max_heap_size: u32,
 	ptr_offset: u32,
 	total_size: u32,
@@ -60,13 +60,12 @@ impl FreeingBumpHeapAllocator {
 	/// * `heap_size` - The size available to this heap instance (in bytes) for
 	///   allocating memory.
 	///
-	/// * `heap` - A `MemoryRef` to the available `MemoryInstance` which is
-	///   used as the heap.
+	/// * `heap` - The linear memory of the runtime instance which is used as
+	///   the heap.
 	///
-	pub fn new(mem: MemoryRef) -> Self {
-		let current_size: Bytes = mem.current_size().into();
-		let current_size = current_size.0 as u32;
-		let used_size = mem.used_size().0 as u32;
+	pub fn new(mem: Rc<dyn Memory>) -> Self {
+		let current_size = mem.current_size();
+		let used_size = mem.used_size();
 		let heap_size = current_size - used_size;
 
 		let mut ptr_offset = used_size;
@@ -203,7 +202,7 @@ mod tests {
 	fn should_allocate_properly() {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		// when
 		let ptr = heap.allocate(1).unwrap();
@@ -217,7 +216,7 @@ mod tests {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
 		set_offset(mem.clone(), 13);
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		// when
 		let ptr = heap.allocate(1).unwrap();
@@ -232,7 +231,7 @@ mod tests {
 	fn should_increment_pointers_properly() {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		// when
 		let ptr1 = heap.allocate(1).unwrap();
@@ -255,7 +254,7 @@ mod tests {
 	fn should_free_properly() {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 		let ptr1 = heap.allocate(1).unwrap();
 		// the prefix of 8 bytes is prepended to the pointer
 		assert_eq!(ptr1, 8);
@@ -279,7 +278,7 @@ mod tests {
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
 		set_offset(mem.clone(), 13);
 		let padded_offset = 16;
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		let ptr1 = heap.allocate(1).unwrap();
 		// the prefix of 8 bytes is prepended to the pointer
@@ -305,7 +304,7 @@ mod tests {
 	fn should_build_linked_list_of_free_areas_properly() {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		let ptr1 = heap.allocate(8).unwrap();
 		let ptr2 = heap.allocate(8).unwrap();
@@ -333,7 +332,7 @@ mod tests {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), Some(Pages(1))).unwrap();
 		set_offset(mem.clone(), 13);
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		// when
 		let ptr = heap.allocate(PAGE_SIZE - 13);
@@ -352,7 +351,7 @@ mod tests {
 	fn should_not_allocate_if_full() {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), Some(Pages(1))).unwrap();
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 		let ptr1 = heap.allocate((PAGE_SIZE / 2) - 8).unwrap();
 		assert_eq!(ptr1, 8);
 
@@ -375,7 +374,7 @@ mod tests {
 		// given
 		let pages_needed = (MAX_POSSIBLE_ALLOCATION as usize / PAGE_SIZE as usize) + 1;
 		let mem = MemoryInstance::alloc(Pages(pages_needed), Some(Pages(pages_needed))).unwrap();
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		// when
 		let ptr = heap.allocate(MAX_POSSIBLE_ALLOCATION).unwrap();
@@ -388,7 +387,7 @@ mod tests {
 	fn should_not_allocate_if_requested_size_too_large() {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		// when
 		let ptr = heap.allocate(MAX_POSSIBLE_ALLOCATION + 1);
@@ -408,7 +407,7 @@ mod tests {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
 		set_offset(mem.clone(), 1);
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		// when
 		// an item size of 16 must be used then
@@ -423,7 +422,7 @@ mod tests {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
 		set_offset(mem.clone(), 13);
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		// when
 		let ptr = heap.allocate(42).unwrap();
@@ -439,7 +438,7 @@ mod tests {
 		// given
 		let mem = MemoryInstance::alloc(Pages(1), None).unwrap();
 		set_offset(mem.clone(), 19);
-		let mut heap = FreeingBumpHeapAllocator::new(mem);
+		let mut heap = FreeingBumpHeapAllocator::new(Rc::new(mem));
 
 		// when
 		for _ in 1..10 {
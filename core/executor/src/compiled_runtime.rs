@@ -0,0 +1,468 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtimes compiled to native code with wasmtime.
+//!
+//! A compiled runtime calls the same host functions as an interpreted one: every import is
+//! forwarded to `wasmi::Externals::invoke_index` of the `FunctionExecutor` of the current call.
+//! The module is compiled once and instantiated anew for every call, so calls can't observe
+//! each other.
+
+use std::{cell::Cell, ops::Range, ptr::NonNull, rc::Rc};
+use crate::error::{Error, Result};
+use crate::sandbox::{SandboxCapabilities, SupervisorTable};
+use crate::wasm_executor::{self, FunctionExecutor};
+use crate::wasm_runtime::{Memory, WasmRuntime};
+use parity_codec::Decode;
+use parity_wasm::elements::{External, Instruction, Module as RawModule};
+use primitives::Blake2Hasher;
+use runtime_version::RuntimeVersion;
+use state_machine::Externalities;
+use wasmi::{Externals, RuntimeArgs, RuntimeValue, nan_preserving_float::{F32, F64}};
+use wasmtime::{Extern, Func, FuncType, Instance, Module, Store, Table, Trap, Val, ValType};
+use log::trace;
+
+thread_local! {
+	/// Host functions of the runtime call that is executing on this thread.
+	static EXTERNALS: Cell<Option<NonNull<dyn Externals>>> = Cell::new(None);
+}
+
+/// Makes `externals` available to the host functions called by compiled code while `f` runs.
+fn with_externals<R>(externals: &mut dyn Externals, f: impl FnOnce() -> R) -> R {
+	struct Restore(Option<NonNull<dyn Externals>>);
+
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			EXTERNALS.with(|current| current.set(self.0));
+		}
+	}
+
+	// The pointer is only dereferenced by host functions called from `f`, while `externals` is
+	// still borrowed by this function.
+	let externals: NonNull<dyn Externals + '_> = NonNull::from(externals);
+	let externals: NonNull<dyn Externals> = unsafe { std::mem::transmute(externals) };
+	let _restore = Restore(EXTERNALS.with(|current| current.replace(Some(externals))));
+	f()
+}
+
+/// Calls the host function with the given index for the runtime call executing on this thread.
+fn call_host_function(index: usize, params: &[Val], results: &mut [Val]) -> std::result::Result<(), Trap> {
+	let args = params.iter().map(into_runtime_value).collect::<std::result::Result<Vec<_>, _>>()?;
+	let externals = EXTERNALS.with(|current| current.get())
+		.ok_or_else(|| Trap::new("Host function called outside of a runtime call"))?;
+	// `with_externals` keeps the pointer valid while compiled code is running.
+	let result = unsafe { (*externals.as_ptr()).invoke_index(index, RuntimeArgs::from(&args[..])) }
+		.map_err(|trap| Trap::new(trap.to_string()))?;
+
+	match (result, results.first_mut()) {
+		(Some(value), Some(result)) => *result = from_runtime_value(value),
+		(None, None) => {},
+		_ => return Err(Trap::new("Host function returned an unexpected result")),
+	}
+	Ok(())
+}
+
+fn into_runtime_value(value: &Val) -> std::result::Result<RuntimeValue, Trap> {
+	match value {
+		Val::I32(v) => Ok(RuntimeValue::I32(*v)),
+		Val::I64(v) => Ok(RuntimeValue::I64(*v)),
+		Val::F32(v) => Ok(RuntimeValue::F32(F32::from_bits(*v))),
+		Val::F64(v) => Ok(RuntimeValue::F64(F64::from_bits(*v))),
+		_ => Err(Trap::new("Unsupported value passed to the host")),
+	}
+}
+
+fn from_runtime_value(value: RuntimeValue) -> Val {
+	match value {
+		RuntimeValue::I32(v) => Val::I32(v),
+		RuntimeValue::I64(v) => Val::I64(v),
+		RuntimeValue::F32(v) => Val::F32(v.to_bits()),
+		RuntimeValue::F64(v) => Val::F64(v.to_bits()),
+	}
+}
+
+fn value_type(value_type: wasmi::ValueType) -> ValType {
+	match value_type {
+		wasmi::ValueType::I32 => ValType::I32,
+		wasmi::ValueType::I64 => ValType::I64,
+		wasmi::ValueType::F32 => ValType::F32,
+		wasmi::ValueType::F64 => ValType::F64,
+	}
+}
+
+/// Invokes a function of a compiled runtime from within one of its host functions.
+pub(crate) fn invoke(
+	externals: &mut dyn Externals,
+	func: &Func,
+	args: &[RuntimeValue],
+) -> Result<Option<RuntimeValue>> {
+	let args = args.iter().cloned().map(from_runtime_value).collect::<Vec<_>>();
+	let results = with_externals(externals, || func.call(&args))?;
+	match results.first() {
+		Some(value) => into_runtime_value(value).map(Some).map_err(Into::into),
+		None => Ok(None),
+	}
+}
+
+/// Returns the function at `index` of the function table of a compiled runtime.
+pub(crate) fn table_get(table: &Table, index: u32) -> Result<Func> {
+	match table.get(index) {
+		Some(Val::FuncRef(func)) => Ok(func),
+		Some(_) => Err("dispatch_thunk_idx points on an empty table entry".into()),
+		None => Err("dispatch_thunk_idx is out of the table bounds".into()),
+	}
+}
+
+/// Linear memory of a compiled runtime instance.
+struct CompiledMemory {
+	memory: wasmtime::Memory,
+	/// End of the data segments of the runtime.
+	data_end: u32,
+}
+
+impl CompiledMemory {
+	/// Returns the byte range of the access, if it is in bounds.
+	fn range(&self, offset: u32, size: usize) -> Result<Range<usize>> {
+		let start = offset as usize;
+		match start.checked_add(size) {
+			Some(end) if end <= self.memory.data_size() => Ok(start..end),
+			_ => Err(Error::Other("Memory access is out of bounds")),
+		}
+	}
+}
+
+// The contents of the memory are only borrowed for the duration of a single access, and compiled
+// code doesn't run during an access.
+impl Memory for CompiledMemory {
+	fn get(&self, offset: u32, size: usize) -> Result<Vec<u8>> {
+		let range = self.range(offset, size)?;
+		Ok(unsafe { self.memory.data_unchecked()[range].to_vec() })
+	}
+
+	fn get_into(&self, offset: u32, target: &mut [u8]) -> Result<()> {
+		let range = self.range(offset, target.len())?;
+		target.copy_from_slice(unsafe { &self.memory.data_unchecked()[range] });
+		Ok(())
+	}
+
+	fn set(&self, offset: u32, value: &[u8]) -> Result<()> {
+		let range = self.range(offset, value.len())?;
+		unsafe { self.memory.data_unchecked_mut()[range].copy_from_slice(value) };
+		Ok(())
+	}
+
+	fn current_size(&self) -> u32 {
+		self.memory.data_size() as u32
+	}
+
+	fn used_size(&self) -> u32 {
+		// This is where the interpreter starts the heap as well.
+		self.data_end
+	}
+}
+
+/// A runtime compiled to native code.
+pub(crate) struct CompiledRuntime {
+	/// The compiled module, instantiated for every call.
+	module: Module,
+	/// The host functions imported by the module, in import order.
+	imports: Vec<Extern>,
+	/// Number of heap pages every instance is created with.
+	heap_pages: u32,
+	/// End of the data segments, where the heap starts.
+	data_end: u32,
+	/// Version of the runtime, if it could be retrieved.
+	version: Option<RuntimeVersion>,
+}
+
+impl WasmRuntime for CompiledRuntime {
+	fn version(&self) -> Option<RuntimeVersion> {
+		self.version.clone()
+	}
+
+	fn call(
+		&mut self,
+		ext: &mut dyn Externalities<Blake2Hasher>,
+		method: &str,
+		data: &[u8],
+	) -> Result<Vec<u8>> {
+		// The runtime can't call host functions from a `start` function, as there is no memory
+		// to create them with yet. Runtimes built with the Substrate toolchain have none.
+		let instance = Instance::new(&self.module, &self.imports).map_err(|e| {
+			trace!(target: "executor", "Failed to instantiate the compiled runtime: {}", e);
+			Error::Other("Failed to instantiate the compiled runtime")
+		})?;
+		let memory = instance.get_export("memory")
+			.and_then(Extern::into_memory)
+			.ok_or(Error::InvalidMemoryReference)?;
+		memory.grow(self.heap_pages).map_err(|_| Error::Runtime)?;
+		let table = instance.get_export("__indirect_function_table")
+			.and_then(Extern::into_table)
+			.map(SupervisorTable::Compiled);
+		let func = instance.get_export(method)
+			.and_then(Extern::into_func)
+			.ok_or_else(|| Error::MethodNotFound(method.to_owned()))?;
+
+		let memory = Rc::new(CompiledMemory { memory, data_end: self.data_end });
+		let mut fec = FunctionExecutor::new(memory.clone(), table, ext)?;
+		let offset = fec.allocate(data.len() as u32)?;
+		fec.write_memory(offset, data)?;
+
+		let result = with_externals(&mut fec, || {
+			func.call(&[Val::I32(offset as i32), Val::I32(data.len() as i32)])
+		});
+		match result {
+			Ok(results) => match results.first() {
+				Some(Val::I64(r)) => {
+					let offset = *r as u32;
+					let length = (*r as u64 >> 32) as usize;
+					memory.get(offset, length).map_err(|_| Error::Runtime)
+				},
+				_ => Err(Error::InvalidReturn),
+			},
+			Err(trap) => {
+				trace!(target: "wasm-executor", "Failed to execute compiled code: {}", trap);
+				Err(trap.into())
+			},
+		}
+	}
+}
+
+/// Compiles `code` and retrieves the version of the runtime.
+///
+/// The host functions the module imports must have been checked already.
+pub(crate) fn create_runtime(
+	ext: &mut dyn Externalities<Blake2Hasher>,
+	code: &[u8],
+	raw_module: &RawModule,
+	heap_pages: u64,
+) -> Result<CompiledRuntime> {
+	let data_end = raw_module.data_section()
+		.map(|section| section.entries())
+		.unwrap_or(&[])
+		.iter()
+		.map(|segment| match segment.offset().code() {
+			[Instruction::I32Const(offset), Instruction::End] =>
+				Ok(*offset as u32 + segment.value().len() as u32),
+			_ => Err(Error::Other("Data segments of compiled runtimes must have a constant offset")),
+		})
+		.try_fold(0, |data_end, segment_end| segment_end.map(|end| data_end.max(end)))?;
+
+	let store = Store::default();
+	let module = Module::new(&store, code).map_err(|e| {
+		trace!(target: "executor", "Failed to compile the runtime: {}", e);
+		Error::InvalidCode(vec![])
+	})?;
+
+	let host_functions = wasm_executor::host_functions();
+	let imports = raw_module.import_section()
+		.map(|section| section.entries())
+		.unwrap_or(&[])
+		.iter()
+		.map(|import| {
+			let index = match import.external() {
+				External::Function(_) if import.module() == "env" => host_functions.iter()
+					.position(|(name, _)| *name == import.field())
+					.ok_or(Error::Other("Runtime imports an unknown host function"))?,
+				_ => return Err(Error::Other("Runtime imports something else than host functions")),
+			};
+			let signature = &host_functions[index].1;
+			let ty = FuncType::new(
+				signature.params().iter().cloned().map(value_type).collect(),
+				signature.return_type().map(value_type).into_iter().collect(),
+			);
+			let func = Func::new(&store, ty, move |_caller, params, results| {
+				call_host_function(index, params, results)
+			});
+			Ok(Extern::from(func))
+		})
+		.collect::<Result<Vec<_>>>()?;
+
+	let mut runtime = CompiledRuntime {
+		module,
+		imports,
+		heap_pages: heap_pages as u32,
+		data_end,
+		version: None,
+	};
+	runtime.version = runtime.call(ext, "Core_version", &[])
+		.ok()
+		.and_then(|v| RuntimeVersion::decode(&mut v.as_slice()));
+	Ok(runtime)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+	use crate::wasm_executor::WasmExecutor;
+	use crate::wasm_runtime::WasmExecutionMethod;
+	use crate::wasm_runtimes_cache::RuntimesCache;
+	use parity_codec::Encode;
+	use primitives::{blake2_256, ed25519, map, sr25519, Pair};
+	use runtime_test::WASM_BINARY;
+	use state_machine::TestExternalities as CoreTestExternalities;
+
+	type TestExternalities = CoreTestExternalities<Blake2Hasher, u64>;
+
+	/// Calls `method` of the test runtime with both the interpreter and the compiler, asserts
+	/// that both return the same result and leave the same storage behind, and returns the result.
+	fn call_both(storage: HashMap<Vec<u8>, Vec<u8>>, method: &str, data: &[u8]) -> Option<Vec<u8>> {
+		let call = |wasm_method| {
+			let mut ext = TestExternalities::new_with_code(WASM_BINARY, storage.clone());
+			let result = RuntimesCache::default()
+				.fetch_runtime(&WasmExecutor::new(), wasm_method, &mut ext, Some(8))
+				.and_then(|runtime| runtime.call(&mut ext, method, data))
+				.ok();
+			(result, ext)
+		};
+
+		let (interpreted, interpreted_ext) = call(WasmExecutionMethod::Interpreted);
+		let (compiled, compiled_ext) = call(WasmExecutionMethod::Compiled);
+		assert_eq!(interpreted, compiled, "{} returned different results", method);
+		assert_eq!(interpreted_ext, compiled_ext, "{} left different storage", method);
+		compiled
+	}
+
+	fn sandboxed_module(wat: &str) -> Vec<u8> {
+		wabt::wat2wasm(wat).unwrap()
+	}
+
+	#[test]
+	fn storage_functions_have_parity() {
+		let storage = map![b"foo".to_vec() => b"bar".to_vec()];
+		assert_eq!(call_both(storage, "test_data_in", b"Hello world"), Some(b"all ok!".to_vec()));
+
+		let storage = map![
+			b"aaa".to_vec() => b"1".to_vec(),
+			b"aba".to_vec() => b"2".to_vec(),
+			b"abb".to_vec() => b"3".to_vec()
+		];
+		assert_eq!(call_both(storage, "test_clear_prefix", b"ab"), Some(b"all ok!".to_vec()));
+	}
+
+	#[test]
+	fn returns_and_panics_have_parity() {
+		assert_eq!(call_both(HashMap::new(), "test_empty_return", &[]), Some(vec![]));
+		assert_eq!(call_both(HashMap::new(), "test_conditional_panic", &[]), Some(vec![]));
+		assert_eq!(call_both(HashMap::new(), "test_conditional_panic", &[2]), None);
+		assert_eq!(call_both(HashMap::new(), "test_panic", &[]), None);
+		assert_eq!(call_both(HashMap::new(), "test_exhaust_heap", &[]), None);
+		assert_eq!(call_both(HashMap::new(), "test_static_counter", &[]), Some(vec![1]));
+		assert_eq!(call_both(HashMap::new(), "unknown_method", &[]), None);
+	}
+
+	#[test]
+	fn hashing_functions_have_parity() {
+		let input = b"Hello world!";
+		assert_eq!(call_both(HashMap::new(), "test_blake2_256", input), Some(blake2_256(input).to_vec()));
+		for method in &[
+			"test_blake2_128",
+			"test_twox_256",
+			"test_twox_128",
+			"test_keccak_256",
+			"test_sha2_256",
+			"test_ripemd_160",
+			"test_enumerated_trie_root",
+		] {
+			assert!(call_both(HashMap::new(), method, input).is_some());
+		}
+	}
+
+	#[test]
+	fn signature_verification_has_parity() {
+		let ed25519_key = ed25519::Pair::from_seed(&blake2_256(b"test"));
+		let sr25519_key = sr25519::Pair::from_seed(&blake2_256(b"test"));
+		for (message, expected) in &[(&b"all ok!"[..], 1), (&b"all is not ok!"[..], 0)] {
+			let mut calldata = ed25519_key.public().as_ref().to_vec();
+			calldata.extend_from_slice(ed25519_key.sign(message).as_ref());
+			assert_eq!(call_both(HashMap::new(), "test_ed25519_verify", &calldata), Some(vec![*expected]));
+
+			let mut calldata = sr25519_key.public().as_ref().to_vec();
+			calldata.extend_from_slice(sr25519_key.sign(message).as_ref());
+			assert_eq!(call_both(HashMap::new(), "test_sr25519_verify", &calldata), Some(vec![*expected]));
+		}
+	}
+
+	#[test]
+	fn sandbox_has_parity() {
+		let code = sandboxed_module(r#"
+		(module
+			(import "env" "assert" (func $assert (param i32)))
+			(import "env" "inc_counter" (func $inc_counter (param i32) (result i32)))
+			(func (export "call")
+				(drop
+					(call $inc_counter (i32.const 5))
+				)
+
+				(call $inc_counter (i32.const 3))
+				;; current counter value is on the stack
+
+				;; check whether current == 8
+				i32.const 8
+				i32.eq
+
+				call $assert
+			)
+		)
+		"#);
+		assert_eq!(call_both(HashMap::new(), "test_sandbox", &code), Some(vec![1]));
+
+		let code = sandboxed_module(r#"
+		(module
+			(import "env" "assert" (func $assert (param i32)))
+			(func (export "call")
+				i32.const 0
+				call $assert
+			)
+		)
+		"#);
+		assert_eq!(call_both(HashMap::new(), "test_sandbox", &code), Some(vec![0]));
+
+		let code = sandboxed_module(r#"
+		(module
+			(func (export "call") (param $x i32) (result i32)
+				(i32.add
+					(get_local $x)
+					(i32.const 1)
+				)
+			)
+		)
+		"#);
+		assert_eq!(
+			call_both(HashMap::new(), "test_sandbox_return_val", &code),
+			Some(vec![1]),
+		);
+
+		let code = sandboxed_module(r#"
+		(module
+			(import "env" "non-existent" (func))
+			(func (export "call"))
+		)
+		"#);
+		assert_eq!(call_both(HashMap::new(), "test_sandbox_instantiate", &code), Some(vec![1]));
+	}
+
+	#[test]
+	fn heap_pages_have_parity() {
+		// The runtime reads its heap pages from storage, so a larger heap makes the allocation of
+		// `test_exhaust_heap` succeed with both methods.
+		let storage = map![
+			primitives::storage::well_known_keys::HEAP_PAGES.to_vec() => 1024u64.encode()
+		];
+		assert_eq!(call_both(storage, "test_exhaust_heap", &[]), Some(vec![]));
+	}
+}
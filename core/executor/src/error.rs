@@ -32,6 +32,10 @@ pub enum Error {
 	Trap(wasmi::Trap),
 	/// Wasmi loading/instantiating error
 	Wasmi(wasmi::Error),
+	/// Trap occured during execution of a compiled runtime
+	#[cfg(feature = "wasmtime")]
+	#[display(fmt="Compiled runtime trapped: {}", _0)]
+	CompiledTrap(wasmtime::Trap),
 	/// Error in the API. Parameter is an error message.
 	ApiError(String),
 	/// Method is not found
@@ -58,6 +62,9 @@ pub enum Error {
 	/// Invalid memory reference.
 	#[display(fmt="Invalid memory reference")]
 	InvalidMemoryReference,
+	/// The runtime imports host functions that are not provided or have a different signature.
+	#[display(fmt="Runtime requires unsupported host functions: {}", _0)]
+	UnsupportedHostFunctions(String),
 	/// Some other error occurred
 	Other(&'static str),
 	/// Some error occurred in the allocator
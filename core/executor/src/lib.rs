@@ -31,6 +31,10 @@
 #[macro_use]
 mod wasm_utils;
 mod wasm_executor;
+mod wasm_runtime;
+#[cfg(feature = "wasmtime")]
+mod compiled_runtime;
+mod wasm_runtimes_cache;
 #[macro_use]
 mod native_executor;
 mod sandbox;
@@ -39,6 +43,7 @@ mod allocator;
 pub mod error;
 pub use wasmi;
 pub use wasm_executor::WasmExecutor;
+pub use wasm_runtime::{WasmRuntime, WasmExecutionMethod};
 pub use native_executor::{with_native_environment, NativeExecutor, NativeExecutionDispatch};
 pub use state_machine::Externalities;
 pub use runtime_version::{RuntimeVersion, NativeVersion};
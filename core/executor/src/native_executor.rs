@@ -14,86 +14,22 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{borrow::BorrowMut, result, cell::{RefMut, RefCell}};
+use std::{result, cell::RefCell, panic::UnwindSafe};
 use crate::error::{Error, Result};
 use state_machine::{CodeExecutor, Externalities};
 use crate::wasm_executor::WasmExecutor;
-use wasmi::{Module as WasmModule, ModuleRef as WasmModuleInstanceRef};
+use crate::wasm_runtime::WasmExecutionMethod;
+use crate::wasm_runtimes_cache::RuntimesCache;
 use runtime_version::{NativeVersion, RuntimeVersion};
-use std::{collections::HashMap, panic::UnwindSafe};
 use parity_codec::{Decode, Encode};
 use crate::RuntimeInfo;
 use primitives::{Blake2Hasher, NativeOrEncoded};
-use primitives::storage::well_known_keys;
-use log::trace;
-
-/// Default num of pages for the heap
-const DEFAULT_HEAP_PAGES: u64 = 1024;
-
-// For the internal Runtime Cache:
-// Is it compatible enough to run this natively or do we need to fall back on the WasmModule
-
-enum RuntimePreproc {
-	InvalidCode,
-	ValidCode(WasmModuleInstanceRef, Option<RuntimeVersion>),
-}
-
-type CacheType = HashMap<[u8; 32], RuntimePreproc>;
+use log::{trace, warn};
+use parking_lot::Mutex;
+use std::sync::Arc;
 
 thread_local! {
-	static RUNTIMES_CACHE: RefCell<CacheType> = RefCell::new(HashMap::new());
-}
-
-/// fetch a runtime version from the cache or if there is no cached version yet, create
-/// the runtime version entry for `code`, determines whether `Compatibility::IsCompatible`
-/// can be used by comparing returned RuntimeVersion to `ref_version`
-fn fetch_cached_runtime_version<'a, E: Externalities<Blake2Hasher>>(
-	wasm_executor: &WasmExecutor,
-	cache: &'a mut RefMut<CacheType>,
-	ext: &mut E,
-	default_heap_pages: Option<u64>,
-) -> Result<(&'a WasmModuleInstanceRef, &'a Option<RuntimeVersion>)> {
-	let code_hash = match ext.original_storage_hash(well_known_keys::CODE) {
-		Some(code_hash) => code_hash,
-		None => return Err(Error::InvalidCode(vec![])),
-	};
-
-	let maybe_runtime_preproc = cache.borrow_mut().entry(code_hash.into())
-		.or_insert_with(|| {
-			let code = match ext.original_storage(well_known_keys::CODE) {
-				Some(code) => code,
-				None => return RuntimePreproc::InvalidCode,
-			};
-			let heap_pages = ext.storage(well_known_keys::HEAP_PAGES)
-				.and_then(|pages| u64::decode(&mut &pages[..]))
-				.or(default_heap_pages)
-				.unwrap_or(DEFAULT_HEAP_PAGES);
-			match WasmModule::from_buffer(code)
-				.map_err(|_| Error::InvalidCode(vec![]))
-				.and_then(|module| wasm_executor.prepare_module(ext, heap_pages as usize, &module))
-			{
-				Ok(module) => {
-					let version = wasm_executor.call_in_wasm_module(ext, &module, "Core_version", &[])
-						.ok()
-						.and_then(|v| RuntimeVersion::decode(&mut v.as_slice()));
-					RuntimePreproc::ValidCode(module, version)
-				}
-				Err(e) => {
-					trace!(target: "executor", "Invalid code presented to executor ({:?})", e);
-					RuntimePreproc::InvalidCode
-				}
-			}
-		});
-
-	match maybe_runtime_preproc {
-		RuntimePreproc::InvalidCode => {
-			let code = ext.original_storage(well_known_keys::CODE).unwrap_or(vec![]);
-			Err(Error::InvalidCode(code))
-		},
-		RuntimePreproc::ValidCode(m, v) => {
-			Ok((m, v))
-		}
-	}
+	static RUNTIMES_CACHE: RefCell<RuntimesCache> = RefCell::new(RuntimesCache::default());
 }
 
 fn safe_call<F, U>(f: F) -> Result<U>
@@ -110,7 +46,12 @@ fn safe_call<F, U>(f: F) -> Result<U>
 pub fn with_native_environment<F, U>(ext: &mut dyn Externalities<Blake2Hasher>, f: F) -> Result<U>
 	where F: UnwindSafe + FnOnce() -> U
 {
-	::runtime_io::with_externalities(ext, move || safe_call(f))
+	::runtime_io::with_externalities(ext, move ||
+		::runtime_io::with_runtime_version_provider(
+			crate::wasm_executor::runtime_version_of_code,
+			move || safe_call(f),
+		)
+	)
 }
 
 /// Delegate for dispatching a CodeExecutor call to native code.
@@ -138,20 +79,57 @@ pub struct NativeExecutor<D> {
 	_dummy: ::std::marker::PhantomData<D>,
 	/// The fallback executor in case native isn't available.
 	fallback: WasmExecutor,
+	/// Engine the fallback executes Wasm runtimes with.
+	wasm_method: WasmExecutionMethod,
 	/// Native runtime version info.
 	native_version: NativeVersion,
 	/// The default number of 64KB pages to allocate for Wasm execution.
 	default_heap_pages: Option<u64>,
+	/// Last on-chain runtime version the native runtime was found incompatible with.
+	last_mismatch: Arc<Mutex<Option<RuntimeVersion>>>,
 }
 
 impl<D: NativeExecutionDispatch> NativeExecutor<D> {
-	/// Create new instance.
+	/// Create new instance that interprets Wasm runtimes.
 	pub fn new(default_heap_pages: Option<u64>) -> Self {
+		Self::with_wasm_method(WasmExecutionMethod::Interpreted, default_heap_pages)
+	}
+
+	/// Create new instance that executes Wasm runtimes with the given method.
+	pub fn with_wasm_method(wasm_method: WasmExecutionMethod, default_heap_pages: Option<u64>) -> Self {
 		NativeExecutor {
 			_dummy: Default::default(),
 			fallback: WasmExecutor::new(),
+			wasm_method,
 			native_version: D::native_version(),
 			default_heap_pages,
+			last_mismatch: Default::default(),
+		}
+	}
+
+	/// Reports that native execution was requested but the on-chain runtime differs from the
+	/// native one.
+	///
+	/// A warning is logged the first time a given on-chain version is encountered, further
+	/// calls are only traced.
+	fn report_mismatch(&self, onchain_version: Option<&RuntimeVersion>) {
+		let onchain = onchain_version.map_or_else(|| "<None>".into(), |v| format!("{}", v));
+		let mut last_mismatch = self.last_mismatch.lock();
+		if onchain_version.is_some() && last_mismatch.as_ref() != onchain_version {
+			warn!(
+				target: "executor",
+				"Native runtime {} can't be used for on-chain runtime {}, falling back to WASM",
+				self.native_version.runtime_version,
+				onchain,
+			);
+			*last_mismatch = onchain_version.cloned();
+		} else {
+			trace!(
+				target: "executor",
+				"Request for native execution failed (native: {}, chain: {})",
+				self.native_version.runtime_version,
+				onchain,
+			);
 		}
 	}
 }
@@ -161,8 +139,10 @@ impl<D: NativeExecutionDispatch> Clone for NativeExecutor<D> {
 		NativeExecutor {
 			_dummy: Default::default(),
 			fallback: self.fallback.clone(),
+			wasm_method: self.wasm_method,
 			native_version: D::native_version(),
 			default_heap_pages: self.default_heap_pages,
+			last_mismatch: self.last_mismatch.clone(),
 		}
 	}
 }
@@ -177,8 +157,8 @@ impl<D: NativeExecutionDispatch> RuntimeInfo for NativeExecutor<D> {
 		ext: &mut E,
 	) -> Option<RuntimeVersion> {
 		RUNTIMES_CACHE.with(|c|
-			fetch_cached_runtime_version(&self.fallback, &mut c.borrow_mut(), ext, self.default_heap_pages)
-				.ok()?.1.clone()
+			c.borrow_mut().fetch_runtime(&self.fallback, self.wasm_method, ext, self.default_heap_pages)
+				.ok()?.version()
 		)
 	}
 }
@@ -201,11 +181,11 @@ impl<D: NativeExecutionDispatch> CodeExecutor<Blake2Hasher> for NativeExecutor<D
 	) -> (Result<NativeOrEncoded<R>>, bool) {
 		RUNTIMES_CACHE.with(|c| {
 			let mut c = c.borrow_mut();
-			let (module, onchain_version) = match fetch_cached_runtime_version(
-				&self.fallback, &mut c, ext, self.default_heap_pages) {
-					Ok((module, onchain_version)) => (module, onchain_version),
-					Err(e) => return (Err(e), false),
+			let runtime = match c.fetch_runtime(&self.fallback, self.wasm_method, ext, self.default_heap_pages) {
+				Ok(runtime) => runtime,
+				Err(e) => return (Err(e), false),
 			};
+			let onchain_version = runtime.version();
 			match (
 				use_native,
 				onchain_version
@@ -213,27 +193,16 @@ impl<D: NativeExecutionDispatch> CodeExecutor<Blake2Hasher> for NativeExecutor<D
 					.map_or(false, |v| v.can_call_with(&self.native_version.runtime_version)),
 				native_call,
 			) {
-				(_, false, _) => {
-					trace!(
-						target: "executor",
-						"Request for native execution failed (native: {}, chain: {})",
-						self.native_version.runtime_version,
-						onchain_version
-							.as_ref()
-							.map_or_else(||"<None>".into(), |v| format!("{}", v))
-					);
+				(true, false, _) => {
+					self.report_mismatch(onchain_version.as_ref());
 					(
-						self.fallback
-							.call_in_wasm_module(ext, module, method, data)
-							.map(NativeOrEncoded::Encoded),
+						runtime.call(ext, method, data).map(NativeOrEncoded::Encoded),
 						false
 					)
 				}
 				(false, _, _) => {
 					(
-						self.fallback
-							.call_in_wasm_module(ext, module, method, data)
-							.map(NativeOrEncoded::Encoded),
+						runtime.call(ext, method, data).map(NativeOrEncoded::Encoded),
 						false
 					)
 				}
@@ -24,9 +24,47 @@ use parity_codec::{Decode, Encode};
 use primitives::sandbox as sandbox_primitives;
 use wasmi::{
 	Externals, FuncRef, ImportResolver, MemoryInstance, MemoryRef, Module, ModuleInstance,
-	ModuleRef, RuntimeArgs, RuntimeValue, Trap, TrapKind, memory_units::Pages,
+	ModuleRef, RuntimeArgs, RuntimeValue, TableRef, Trap, TrapKind, memory_units::Pages,
 };
 
+/// A function of the supervisor, i.e. of the runtime that uses the sandbox.
+#[derive(Clone)]
+pub enum SupervisorFuncRef {
+	/// A function of a runtime executed by the interpreter.
+	Interpreted(FuncRef),
+	/// A function of a compiled runtime.
+	#[cfg(feature = "wasmtime")]
+	Compiled(wasmtime::Func),
+}
+
+/// The function table of the supervisor, from which dispatch thunks are taken.
+pub enum SupervisorTable {
+	/// The table of a runtime executed by the interpreter.
+	Interpreted(TableRef),
+	/// The table of a compiled runtime.
+	#[cfg(feature = "wasmtime")]
+	Compiled(wasmtime::Table),
+}
+
+impl SupervisorTable {
+	/// Returns the function at `index` of the table.
+	///
+	/// # Errors
+	///
+	/// Returns `Err` if `index` is out of the table bounds or points to an empty entry.
+	pub fn get(&self, index: u32) -> Result<SupervisorFuncRef> {
+		match self {
+			SupervisorTable::Interpreted(table) => table.get(index)
+				.map_err(|_| "dispatch_thunk_idx is out of the table bounds")?
+				.map(SupervisorFuncRef::Interpreted)
+				.ok_or_else(|| "dispatch_thunk_idx points on an empty table entry".into()),
+			#[cfg(feature = "wasmtime")]
+			SupervisorTable::Compiled(table) =>
+				crate::compiled_runtime::table_get(table, index).map(SupervisorFuncRef::Compiled),
+		}
+	}
+}
+
 /// Index of a function inside the supervisor.
 ///
 /// This is a typically an index in the default table of the supervisor, however
@@ -174,6 +212,13 @@ pub trait SandboxCapabilities {
 	///
 	/// Returns `Err` if `ptr + len` is out of bounds.
 	fn read_memory(&self, ptr: u32, len: u32) -> Result<Vec<u8>>;
+
+	/// Invoke a function of the supervisor, e.g. a dispatch thunk.
+	///
+	/// # Errors
+	///
+	/// Returns `Err` if the function traps.
+	fn invoke(&mut self, func: &SupervisorFuncRef, args: &[RuntimeValue]) -> Result<Option<RuntimeValue>>;
 }
 
 /// Implementation of [`Externals`] that allows execution of guest module with
@@ -240,7 +285,7 @@ impl<'a, FE: SandboxCapabilities + Externals + 'a> Externals for GuestExternals<
 			.allocate(invoke_args_data.len() as u32)?;
 		self.supervisor_externals
 			.write_memory(invoke_args_ptr, &invoke_args_data)?;
-		let result = ::wasmi::FuncInstance::invoke(
+		let result = self.supervisor_externals.invoke(
 			&dispatch_thunk,
 			&[
 				RuntimeValue::I32(invoke_args_ptr as i32),
@@ -248,7 +293,6 @@ impl<'a, FE: SandboxCapabilities + Externals + 'a> Externals for GuestExternals<
 				RuntimeValue::I32(state as i32),
 				RuntimeValue::I32(func_idx.0 as i32),
 			],
-			self.supervisor_externals,
 		);
 		self.supervisor_externals.deallocate(invoke_args_ptr)?;
 
@@ -310,7 +354,7 @@ where
 /// [`invoke`]: #method.invoke
 pub struct SandboxInstance {
 	instance: ModuleRef,
-	dispatch_thunk: FuncRef,
+	dispatch_thunk: SupervisorFuncRef,
 	guest_to_supervisor_mapping: GuestToSupervisorFunctionMapping,
 }
 
@@ -413,7 +457,7 @@ fn decode_environment_definition(
 /// [`EnvironmentDefinition`]: ../../sandbox/struct.EnvironmentDefinition.html
 pub fn instantiate<FE: SandboxCapabilities + Externals>(
 	supervisor_externals: &mut FE,
-	dispatch_thunk: FuncRef,
+	dispatch_thunk: SupervisorFuncRef,
 	wasm: &[u8],
 	raw_env_def: &[u8],
 	state: u32,
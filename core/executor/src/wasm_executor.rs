@@ -16,25 +16,30 @@
 
 //! Rust implementation of Substrate contracts.
 
-use std::{collections::HashMap, convert::TryFrom, str};
+use std::{collections::HashMap, convert::TryFrom, rc::Rc, str};
 use tiny_keccak;
 use secp256k1;
 
 use wasmi::{
-	Module, ModuleInstance, MemoryInstance, MemoryRef, TableRef, ImportsBuilder, ModuleRef,
+	Module, ModuleInstance, MemoryRef, TableRef, ImportsBuilder, ModuleRef,
 	memory_units::Pages, RuntimeValue::{I32, I64, self},
 };
-use state_machine::{Externalities, ChildStorageKey};
+use state_machine::{BasicExternalities, Externalities, ChildStorageKey};
 use crate::error::{Error, Result};
-use parity_codec::Encode;
-use primitives::{blake2_128, blake2_256, twox_64, twox_128, twox_256, ed25519, sr25519, Pair};
+use parity_codec::{Encode, Decode};
+use primitives::{
+	blake2_128, blake2_256, twox_64, twox_128, twox_256, sha2_256, ripemd_160, bls, ecdsa, ed25519, sr25519, Pair,
+};
 use primitives::offchain;
+use primitives::batch_verifier::{self, BatchVerifier};
 use primitives::hexdisplay::HexDisplay;
 use primitives::sandbox as sandbox_primitives;
 use primitives::{H256, Blake2Hasher};
 use trie::ordered_trie_root;
-use crate::sandbox;
+use parity_wasm::elements::{External, Module as RawModule, Type, ValueType as RawValueType};
+use crate::sandbox::{self, SupervisorFuncRef, SupervisorTable};
 use crate::allocator;
+use crate::wasm_runtime;
 use log::trace;
 
 #[cfg(feature="wasm-extern-trace")]
@@ -46,17 +51,21 @@ macro_rules! debug_trace {
 	( $( $x:tt )* ) => ()
 }
 
-struct FunctionExecutor<'e, E: Externalities<Blake2Hasher> + 'e> {
+/// Host functions of a runtime call, shared by the interpreted and the compiled runtimes.
+pub(crate) struct FunctionExecutor<'e, E: Externalities<Blake2Hasher> + ?Sized + 'e> {
 	sandbox_store: sandbox::Store,
 	heap: allocator::FreeingBumpHeapAllocator,
-	memory: MemoryRef,
-	table: Option<TableRef>,
+	memory: Rc<dyn wasm_runtime::Memory>,
+	table: Option<SupervisorTable>,
 	ext: &'e mut E,
 	hash_lookup: HashMap<Vec<u8>, Vec<u8>>,
+	batch_verifier: Option<BatchVerifier>,
 }
 
-impl<'e, E: Externalities<Blake2Hasher>> FunctionExecutor<'e, E> {
-	fn new(m: MemoryRef, t: Option<TableRef>, e: &'e mut E) -> Result<Self> {
+impl<'e, E: Externalities<Blake2Hasher> + ?Sized> FunctionExecutor<'e, E> {
+	/// Creates the host functions for a call into the runtime instance with memory `m` and
+	/// function table `t`.
+	pub(crate) fn new(m: Rc<dyn wasm_runtime::Memory>, t: Option<SupervisorTable>, e: &'e mut E) -> Result<Self> {
 		Ok(FunctionExecutor {
 			sandbox_store: sandbox::Store::new(),
 			heap: allocator::FreeingBumpHeapAllocator::new(m.clone()),
@@ -64,11 +73,12 @@ impl<'e, E: Externalities<Blake2Hasher>> FunctionExecutor<'e, E> {
 			table: t,
 			ext: e,
 			hash_lookup: HashMap::new(),
+			batch_verifier: None,
 		})
 	}
 }
 
-impl<'e, E: Externalities<Blake2Hasher>> sandbox::SandboxCapabilities for FunctionExecutor<'e, E> {
+impl<'e, E: Externalities<Blake2Hasher> + ?Sized> sandbox::SandboxCapabilities for FunctionExecutor<'e, E> {
 	fn store(&self) -> &sandbox::Store {
 		&self.sandbox_store
 	}
@@ -87,13 +97,20 @@ impl<'e, E: Externalities<Blake2Hasher>> sandbox::SandboxCapabilities for Functi
 	fn read_memory(&self, ptr: u32, len: u32) -> Result<Vec<u8>> {
 		self.memory.get(ptr, len as usize).map_err(Into::into)
 	}
+	fn invoke(&mut self, func: &SupervisorFuncRef, args: &[RuntimeValue]) -> Result<Option<RuntimeValue>> {
+		match func {
+			SupervisorFuncRef::Interpreted(func) => wasmi::FuncInstance::invoke(func, args, self).map_err(Into::into),
+			#[cfg(feature = "wasmtime")]
+			SupervisorFuncRef::Compiled(func) => crate::compiled_runtime::invoke(self, func, args),
+		}
+	}
 }
 
 trait WritePrimitive<T: Sized> {
 	fn write_primitive(&self, offset: u32, t: T) -> Result<()>;
 }
 
-impl WritePrimitive<u32> for MemoryInstance {
+impl WritePrimitive<u32> for dyn wasm_runtime::Memory {
 	fn write_primitive(&self, offset: u32, t: u32) -> Result<()> {
 		use byteorder::{LittleEndian, ByteOrder};
 		let mut r = [0u8; 4];
@@ -106,7 +123,7 @@ trait ReadPrimitive<T: Sized> {
 	fn read_primitive(&self, offset: u32) -> Result<T>;
 }
 
-impl ReadPrimitive<u32> for MemoryInstance {
+impl ReadPrimitive<u32> for dyn wasm_runtime::Memory {
 	fn read_primitive(&self, offset: u32) -> Result<u32> {
 		use byteorder::{LittleEndian, ByteOrder};
 		let result = self.get(offset, 4)?;
@@ -141,6 +158,36 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 		println!("{}", number);
 		Ok(())
 	},
+	ext_log(
+		level: u32,
+		target_data: *const u8,
+		target_len: u32,
+		message_data: *const u8,
+		message_len: u32
+	) => {
+		let target = this.memory.get(target_data, target_len as usize)
+			.map_err(|_| "Invalid attempt to determine target in ext_log")?;
+		let message = this.memory.get(message_data, message_len as usize)
+			.map_err(|_| "Invalid attempt to determine message in ext_log")?;
+		runtime_io::log(level.into(), &target, &message);
+		Ok(())
+	},
+	ext_wasm_tracing_enter_span(
+		target_data: *const u8,
+		target_len: u32,
+		name_data: *const u8,
+		name_len: u32
+	) -> u64 => {
+		let target = this.memory.get(target_data, target_len as usize)
+			.map_err(|_| "Invalid attempt to determine target in ext_wasm_tracing_enter_span")?;
+		let name = this.memory.get(name_data, name_len as usize)
+			.map_err(|_| "Invalid attempt to determine name in ext_wasm_tracing_enter_span")?;
+		Ok(runtime_io::wasm_tracing_enter_span(&target, &name))
+	},
+	ext_wasm_tracing_exit_span(id: u64) => {
+		runtime_io::wasm_tracing_exit_span(id);
+		Ok(())
+	},
 	ext_malloc(size: usize) -> *mut u8 => {
 		let r = this.heap.allocate(size)?;
 		debug_trace!(target: "sr-io", "malloc {} bytes at {}", size, r);
@@ -282,6 +329,23 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 		this.ext.clear_prefix(&prefix);
 		Ok(())
 	},
+	ext_storage_start_transaction() => {
+		this.ext.storage_start_transaction();
+		Ok(())
+	},
+	ext_storage_rollback_transaction() => {
+		this.ext.storage_rollback_transaction()
+			.map_err(|_| "No open storage transaction in ext_storage_rollback_transaction")?;
+		Ok(())
+	},
+	ext_storage_commit_transaction() => {
+		this.ext.storage_commit_transaction()
+			.map_err(|_| "No open storage transaction in ext_storage_commit_transaction")?;
+		Ok(())
+	},
+	ext_extrinsic_index() -> u32 => {
+		Ok(this.ext.extrinsic_index().unwrap_or(u32::max_value()))
+	},
 	ext_kill_child_storage(storage_key_data: *const u8, storage_key_len: u32) => {
 		let storage_key = this.memory.get(
 			storage_key_data,
@@ -328,6 +392,25 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 			Ok(0)
 		}
 	},
+	// return 0 and place u32::max_value() into written_out if there is no key after the given one.
+	ext_next_key(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8 => {
+		let key = this.memory.get(key_data, key_len as usize)
+			.map_err(|_| "Invalid attempt to determine key in ext_next_key")?;
+		let maybe_next_key = this.ext.next_storage_key(&key);
+
+		if let Some(next_key) = maybe_next_key {
+			let offset = this.heap.allocate(next_key.len() as u32)? as u32;
+			this.memory.set(offset, &next_key)
+				.map_err(|_| "Invalid attempt to set memory in ext_next_key")?;
+			this.memory.write_primitive(written_out, next_key.len() as u32)
+				.map_err(|_| "Invalid attempt to write written_out in ext_next_key")?;
+			Ok(offset)
+		} else {
+			this.memory.write_primitive(written_out, u32::max_value())
+				.map_err(|_| "Invalid attempt to write failed written_out in ext_next_key")?;
+			Ok(0)
+		}
+	},
 	// return 0 and place u32::max_value() into written_out if no value exists for the key.
 	ext_get_allocated_child_storage(
 		storage_key_data: *const u8,
@@ -536,6 +619,34 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 	ext_chain_id() -> u64 => {
 		Ok(this.ext.chain_id())
 	},
+	ext_is_offchain_worker() -> u32 => {
+		Ok(if this.ext.is_offchain_worker() { 1 } else { 0 })
+	},
+	ext_benchmark_time_nanos() -> u64 => {
+		if !cfg!(feature = "runtime-benchmarks") {
+			return Err("ext_benchmark_time_nanos requires the `runtime-benchmarks` feature".into());
+		}
+		Ok(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+			.map(|time| time.as_secs() * 1_000_000_000 + u64::from(time.subsec_nanos()))
+			.unwrap_or(0))
+	},
+	ext_runtime_version(code_data: *const u8, code_len: u32, written_out: *mut u32) -> *mut u8 => {
+		let code = this.memory.get(code_data, code_len as usize)
+			.map_err(|_| "Invalid attempt to get code in ext_runtime_version")?;
+
+		if let Some(version) = runtime_version_of_code(&code) {
+			let offset = this.heap.allocate(version.len() as u32)? as u32;
+			this.memory.set(offset, &version)
+				.map_err(|_| "Invalid attempt to set memory in ext_runtime_version")?;
+			this.memory.write_primitive(written_out, version.len() as u32)
+				.map_err(|_| "Invalid attempt to write written_out in ext_runtime_version")?;
+			Ok(offset)
+		} else {
+			this.memory.write_primitive(written_out, u32::max_value())
+				.map_err(|_| "Invalid attempt to write failed written_out in ext_runtime_version")?;
+			Ok(0)
+		}
+	},
 	ext_twox_64(data: *const u8, len: u32, out: *mut u8) => {
 		let result: [u8; 8] = if len == 0 {
 			let hashed = twox_64(&[0u8; 0]);
@@ -641,6 +752,28 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 		this.memory.set(out, &result).map_err(|_| "Invalid attempt to set result in ext_keccak_256")?;
 		Ok(())
 	},
+	ext_sha2_256(data: *const u8, len: u32, out: *mut u8) => {
+		let result: [u8; 32] = if len == 0 {
+			sha2_256(&[0u8; 0])
+		} else {
+			let mem = this.memory.get(data, len as usize)
+				.map_err(|_| "Invalid attempt to get data in ext_sha2_256")?;
+			sha2_256(&mem)
+		};
+		this.memory.set(out, &result).map_err(|_| "Invalid attempt to set result in ext_sha2_256")?;
+		Ok(())
+	},
+	ext_ripemd_160(data: *const u8, len: u32, out: *mut u8) => {
+		let result: [u8; 20] = if len == 0 {
+			ripemd_160(&[0u8; 0])
+		} else {
+			let mem = this.memory.get(data, len as usize)
+				.map_err(|_| "Invalid attempt to get data in ext_ripemd_160")?;
+			ripemd_160(&mem)
+		};
+		this.memory.set(out, &result).map_err(|_| "Invalid attempt to set result in ext_ripemd_160")?;
+		Ok(())
+	},
 	ext_ed25519_verify(msg_data: *const u8, msg_len: u32, sig_data: *const u8, pubkey_data: *const u8) -> u32 => {
 		let mut sig = [0u8; 64];
 		this.memory.get_into(sig_data, &mut sig[..])
@@ -701,6 +834,208 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 
 		Ok(0)
 	},
+	ext_ed25519_generate(id: u32, seed: *const u8, seed_len: u32, out: *mut u8) => {
+		let seed = if seed_len == u32::max_value() {
+			None
+		} else {
+			let seed = this.memory.get(seed, seed_len as usize)
+				.map_err(|_| "Invalid attempt to get seed in ext_ed25519_generate")?;
+			Some(String::from_utf8(seed).map_err(|_| "Seed is not valid UTF-8 in ext_ed25519_generate")?)
+		};
+
+		let public = this.ext.keystore()
+			.ok_or("No `keystore` associated for the current context!")?
+			.ed25519_generate_new(id, seed.as_ref().map(String::as_str))
+			.map_err(|_| "`ed25519_generate` failed")?;
+
+		this.memory.set(out, public.as_ref())
+			.map_err(|_| "Invalid attempt to set out in ext_ed25519_generate")?;
+		Ok(())
+	},
+	ext_sr25519_generate(id: u32, seed: *const u8, seed_len: u32, out: *mut u8) => {
+		let seed = if seed_len == u32::max_value() {
+			None
+		} else {
+			let seed = this.memory.get(seed, seed_len as usize)
+				.map_err(|_| "Invalid attempt to get seed in ext_sr25519_generate")?;
+			Some(String::from_utf8(seed).map_err(|_| "Seed is not valid UTF-8 in ext_sr25519_generate")?)
+		};
+
+		let public = this.ext.keystore()
+			.ok_or("No `keystore` associated for the current context!")?
+			.sr25519_generate_new(id, seed.as_ref().map(String::as_str))
+			.map_err(|_| "`sr25519_generate` failed")?;
+
+		this.memory.set(out, public.as_ref())
+			.map_err(|_| "Invalid attempt to set out in ext_sr25519_generate")?;
+		Ok(())
+	},
+	ext_secp256k1_ecdsa_recover_compressed(msg_data: *const u8, sig_data: *const u8, pubkey_data: *mut u8) -> u32 => {
+		let mut sig = [0u8; 65];
+		this.memory.get_into(sig_data, &mut sig[..])
+			.map_err(|_| "Invalid attempt to get signature in ext_secp256k1_ecdsa_recover_compressed")?;
+		let rs = match secp256k1::Signature::parse_slice(&sig[0..64]) {
+			Ok(rs) => rs,
+			_ => return Ok(1),
+		};
+		let v = match secp256k1::RecoveryId::parse(if sig[64] > 26 { sig[64] - 27 } else { sig[64] } as u8) {
+			Ok(v) => v,
+			_ => return Ok(2),
+		};
+
+		let mut msg = [0u8; 32];
+		this.memory.get_into(msg_data, &mut msg[..])
+			.map_err(|_| "Invalid attempt to get message in ext_secp256k1_ecdsa_recover_compressed")?;
+
+		let pubkey = match secp256k1::recover(&secp256k1::Message::parse(&msg), &rs, &v) {
+			Ok(pk) => pk,
+			_ => return Ok(3),
+		};
+
+		this.memory.set(pubkey_data, &pubkey.serialize_compressed()[..])
+			.map_err(|_| "Invalid attempt to set pubkey in ext_secp256k1_ecdsa_recover_compressed")?;
+
+		Ok(0)
+	},
+	ext_ecdsa_generate(id: u32, seed: *const u8, seed_len: u32, out: *mut u8) => {
+		let seed = if seed_len == u32::max_value() {
+			None
+		} else {
+			let seed = this.memory.get(seed, seed_len as usize)
+				.map_err(|_| "Invalid attempt to get seed in ext_ecdsa_generate")?;
+			Some(String::from_utf8(seed).map_err(|_| "Seed is not valid UTF-8 in ext_ecdsa_generate")?)
+		};
+
+		let public = this.ext.keystore()
+			.ok_or("No `keystore` associated for the current context!")?
+			.ecdsa_generate_new(id, seed.as_ref().map(String::as_str))
+			.map_err(|_| "`ecdsa_generate` failed")?;
+
+		this.memory.set(out, public.as_ref())
+			.map_err(|_| "Invalid attempt to set out in ext_ecdsa_generate")?;
+		Ok(())
+	},
+	ext_ecdsa_sign(pubkey_data: *const u8, msg_data: *const u8, msg_len: u32, out: *mut u8) -> u32 => {
+		let mut pubkey = [0u8; 33];
+		this.memory.get_into(pubkey_data, &mut pubkey[..])
+			.map_err(|_| "Invalid attempt to get pubkey in ext_ecdsa_sign")?;
+		let msg = this.memory.get(msg_data, msg_len as usize)
+			.map_err(|_| "Invalid attempt to get message in ext_ecdsa_sign")?;
+
+		let signature = this.ext.keystore()
+			.ok_or("No `keystore` associated for the current context!")?
+			.ecdsa_sign(&ecdsa::Public::from_raw(pubkey), &msg);
+
+		match signature {
+			Ok(signature) => {
+				this.memory.set(out, &signature.0[..])
+					.map_err(|_| "Invalid attempt to set out in ext_ecdsa_sign")?;
+				Ok(0)
+			},
+			Err(_) => Ok(1),
+		}
+	},
+	ext_ecdsa_verify(msg_data: *const u8, msg_len: u32, sig_data: *const u8, pubkey_data: *const u8) -> u32 => {
+		let mut sig = [0u8; 65];
+		this.memory.get_into(sig_data, &mut sig[..])
+			.map_err(|_| "Invalid attempt to get signature in ext_ecdsa_verify")?;
+		let mut pubkey = [0u8; 33];
+		this.memory.get_into(pubkey_data, &mut pubkey[..])
+			.map_err(|_| "Invalid attempt to get pubkey in ext_ecdsa_verify")?;
+		let msg = this.memory.get(msg_data, msg_len as usize)
+			.map_err(|_| "Invalid attempt to get message in ext_ecdsa_verify")?;
+
+		Ok(if ecdsa::Pair::verify_weak(&sig[..], &msg, &pubkey[..]) {
+			0
+		} else {
+			5
+		})
+	},
+	ext_bls_verify(msg_data: *const u8, msg_len: u32, sig_data: *const u8, pubkey_data: *const u8) -> u32 => {
+		let mut sig = [0u8; 96];
+		this.memory.get_into(sig_data, &mut sig[..])
+			.map_err(|_| "Invalid attempt to get signature in ext_bls_verify")?;
+		let mut pubkey = [0u8; 48];
+		this.memory.get_into(pubkey_data, &mut pubkey[..])
+			.map_err(|_| "Invalid attempt to get pubkey in ext_bls_verify")?;
+		let msg = this.memory.get(msg_data, msg_len as usize)
+			.map_err(|_| "Invalid attempt to get message in ext_bls_verify")?;
+
+		Ok(if bls::Pair::verify_weak(&sig[..], &msg, &pubkey[..]) {
+			0
+		} else {
+			5
+		})
+	},
+	ext_bls_aggregate_verify(
+		messages_data: *const u8,
+		messages_len: u32,
+		sig_data: *const u8,
+		pubkeys_data: *const u8,
+		pubkeys_len: u32
+	) -> u32 => {
+		let mut sig = [0u8; 96];
+		this.memory.get_into(sig_data, &mut sig[..])
+			.map_err(|_| "Invalid attempt to get signature in ext_bls_aggregate_verify")?;
+		let pubkeys = this.memory.get(pubkeys_data, pubkeys_len as usize * bls::PUBLIC_KEY_LENGTH)
+			.map_err(|_| "Invalid attempt to get pubkeys in ext_bls_aggregate_verify")?;
+		let messages = this.memory.get(messages_data, messages_len as usize)
+			.map_err(|_| "Invalid attempt to get messages in ext_bls_aggregate_verify")?;
+		let messages: Vec<Vec<u8>> = Decode::decode(&mut &messages[..])
+			.ok_or_else(|| "Invalid messages encoding in ext_bls_aggregate_verify")?;
+
+		if messages.len() != pubkeys_len as usize {
+			return Ok(5);
+		}
+		let signed = messages.iter()
+			.zip(pubkeys.chunks(bls::PUBLIC_KEY_LENGTH))
+			.map(|(message, pubkey)| {
+				let mut public = bls::Public::default();
+				public.0.copy_from_slice(pubkey);
+				(message, public)
+			})
+			.collect::<Vec<_>>();
+
+		Ok(if bls::Signature(sig).verify_aggregate(&signed) {
+			0
+		} else {
+			5
+		})
+	},
+	ext_start_batch_verify() => {
+		if this.batch_verifier.is_some() {
+			return Err("`ext_start_batch_verify` called while a batch is already started".into());
+		}
+		this.batch_verifier = Some(BatchVerifier::new());
+		Ok(())
+	},
+	ext_sig_batch_verify(
+		id: u32,
+		msg_data: *const u8,
+		msg_len: u32,
+		sig_data: *const u8,
+		pubkey_data: *const u8
+	) -> u32 => {
+		let mut sig = [0u8; 64];
+		this.memory.get_into(sig_data, &mut sig[..])
+			.map_err(|_| "Invalid attempt to get signature in ext_sig_batch_verify")?;
+		let mut pubkey = [0u8; 32];
+		this.memory.get_into(pubkey_data, &mut pubkey[..])
+			.map_err(|_| "Invalid attempt to get pubkey in ext_sig_batch_verify")?;
+		let msg = this.memory.get(msg_data, msg_len as usize)
+			.map_err(|_| "Invalid attempt to get message in ext_sig_batch_verify")?;
+
+		let valid = match this.batch_verifier {
+			Some(ref mut batch) => batch.push(id, &sig, &msg, &pubkey),
+			None => batch_verifier::verify(id, &sig, &msg, &pubkey),
+		};
+		Ok(if valid { 0 } else { 5 })
+	},
+	ext_finish_batch_verify() -> u32 => {
+		let mut batch = this.batch_verifier.take()
+			.ok_or_else(|| "`ext_finish_batch_verify` called without `ext_start_batch_verify`")?;
+		Ok(if batch.verify_and_clear() { 0 } else { 5 })
+	},
 	ext_submit_transaction(msg_data: *const u8, len: u32) -> u32 => {
 		let extrinsic = this.memory.get(msg_data, len as usize)
 			.map_err(|_| "OOB while ext_submit_transaction: wasm")?;
@@ -1133,14 +1468,9 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 			.map_err(|_| "OOB while ext_sandbox_instantiate: imports")?;
 
 		// Extract a dispatch thunk from instance's table by the specified index.
-		let dispatch_thunk = {
-			let table = this.table.as_ref()
-				.ok_or_else(|| "Runtime doesn't have a table; sandbox is unavailable")?;
-			table.get(dispatch_thunk_idx)
-				.map_err(|_| "dispatch_thunk_idx is out of the table bounds")?
-				.ok_or_else(|| "dispatch_thunk_idx points on an empty table entry")?
-				.clone()
-		};
+		let dispatch_thunk = this.table.as_ref()
+			.ok_or_else(|| "Runtime doesn't have a table; sandbox is unavailable")?
+			.get(dispatch_thunk_idx)?;
 
 		let instance_idx_or_err_code =
 			match sandbox::instantiate(this, dispatch_thunk, &wasm, &raw_env_def, state) {
@@ -1211,13 +1541,11 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 	ext_sandbox_memory_get(memory_idx: u32, offset: u32, buf_ptr: *mut u8, buf_len: u32) -> u32 => {
 		let sandboxed_memory = this.sandbox_store.memory(memory_idx)?;
 
-		match MemoryInstance::transfer(
-			&sandboxed_memory,
-			offset as usize,
-			&this.memory,
-			buf_ptr as usize,
-			buf_len as usize,
-		) {
+		let buffer = match sandboxed_memory.get(offset, buf_len as usize) {
+			Ok(buffer) => buffer,
+			Err(_) => return Ok(sandbox_primitives::ERR_OUT_OF_BOUNDS),
+		};
+		match this.memory.set(buf_ptr, &buffer) {
 			Ok(()) => Ok(sandbox_primitives::ERR_OK),
 			Err(_) => Ok(sandbox_primitives::ERR_OUT_OF_BOUNDS),
 		}
@@ -1225,13 +1553,11 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 	ext_sandbox_memory_set(memory_idx: u32, offset: u32, val_ptr: *const u8, val_len: u32) -> u32 => {
 		let sandboxed_memory = this.sandbox_store.memory(memory_idx)?;
 
-		match MemoryInstance::transfer(
-			&this.memory,
-			val_ptr as usize,
-			&sandboxed_memory,
-			offset as usize,
-			val_len as usize,
-		) {
+		let buffer = match this.memory.get(val_ptr, val_len as usize) {
+			Ok(buffer) => buffer,
+			Err(_) => return Ok(sandbox_primitives::ERR_OUT_OF_BOUNDS),
+		};
+		match sandboxed_memory.set(offset, &buffer) {
 			Ok(()) => Ok(sandbox_primitives::ERR_OK),
 			Err(_) => Ok(sandbox_primitives::ERR_OUT_OF_BOUNDS),
 		}
@@ -1240,9 +1566,54 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 		this.sandbox_store.memory_teardown(memory_idx)?;
 		Ok(())
 	},
-	=> <'e, E: Externalities<Blake2Hasher> + 'e>
+	=> <'e, E: Externalities<Blake2Hasher> + ?Sized + 'e>
 );
 
+/// Returns the name and signature of every host function, ordered by the index that
+/// `wasmi::Externals::invoke_index` dispatches on.
+pub(crate) fn host_functions() -> Vec<(&'static str, wasmi::Signature)> {
+	FunctionExecutor::<dyn Externalities<Blake2Hasher>>::host_functions()
+}
+
+/// Returns the encoded `RuntimeVersion` of `code` by calling its `Core_version` function.
+///
+/// The call runs against empty, throwaway externalities, so the given code can neither read
+/// nor modify the state of the caller.
+pub(crate) fn runtime_version_of_code(code: &[u8]) -> Option<Vec<u8>> {
+	let mut ext = BasicExternalities::default();
+	WasmExecutor::new().call(&mut ext, crate::wasm_runtimes_cache::DEFAULT_HEAP_PAGES as usize, code, "Core_version", &[])
+		.map_err(|e| trace!(target: "executor", "Failed to read the version of the given code: {:?}", e))
+		.ok()
+}
+
+/// Strips the `_version_N` suffix of a versioned host function name.
+fn unversioned_name(name: &str) -> &str {
+	match name.rfind("_version_") {
+		Some(index) if name[index + "_version_".len()..].parse::<u32>().is_ok() => &name[..index],
+		_ => name,
+	}
+}
+
+fn value_type_from_raw(value_type: &RawValueType) -> Option<wasmi::ValueType> {
+	match value_type {
+		RawValueType::I32 => Some(wasmi::ValueType::I32),
+		RawValueType::I64 => Some(wasmi::ValueType::I64),
+		RawValueType::F32 => Some(wasmi::ValueType::F32),
+		RawValueType::F64 => Some(wasmi::ValueType::F64),
+		#[allow(unreachable_patterns)]
+		_ => None,
+	}
+}
+
+fn signature_from_raw(params: &[RawValueType], return_type: Option<RawValueType>) -> Option<wasmi::Signature> {
+	let params = params.iter().map(value_type_from_raw).collect::<Option<Vec<_>>>()?;
+	let return_type = match return_type {
+		Some(return_type) => Some(value_type_from_raw(&return_type)?),
+		None => None,
+	};
+	Some(wasmi::Signature::new(params, return_type))
+}
+
 /// Wasm rust executor for contracts.
 ///
 /// Executes the provided code in a sandboxed wasm runtime.
@@ -1261,7 +1632,7 @@ impl WasmExecutor {
 	/// Signature of this method needs to be `(I32, I32) -> I64`.
 	///
 	/// This should be used for tests only.
-	pub fn call<E: Externalities<Blake2Hasher>>(
+	pub fn call<E: Externalities<Blake2Hasher> + ?Sized>(
 		&self,
 		ext: &mut E,
 		heap_pages: usize,
@@ -1278,7 +1649,7 @@ impl WasmExecutor {
 	///
 	/// This should be used for tests only.
 	pub fn call_with_custom_signature<
-		E: Externalities<Blake2Hasher>,
+		E: Externalities<Blake2Hasher> + ?Sized,
 		F: FnOnce(&mut dyn FnMut(&[u8]) -> Result<u32>) -> Result<Vec<RuntimeValue>>,
 		FR: FnOnce(Option<RuntimeValue>, &MemoryRef) -> Result<Option<R>>,
 		R,
@@ -1312,7 +1683,7 @@ impl WasmExecutor {
 	}
 
 	/// Call a given method in the given wasm-module runtime.
-	pub fn call_in_wasm_module<E: Externalities<Blake2Hasher>>(
+	pub fn call_in_wasm_module<E: Externalities<Blake2Hasher> + ?Sized>(
 		&self,
 		ext: &mut E,
 		module_instance: &ModuleRef,
@@ -1341,7 +1712,7 @@ impl WasmExecutor {
 
 	/// Call a given method in the given wasm-module runtime.
 	fn call_in_wasm_module_with_custom_signature<
-		E: Externalities<Blake2Hasher>,
+		E: Externalities<Blake2Hasher> + ?Sized,
 		F: FnOnce(&mut dyn FnMut(&[u8]) -> Result<u32>) -> Result<Vec<RuntimeValue>>,
 		FR: FnOnce(Option<RuntimeValue>, &MemoryRef) -> Result<Option<R>>,
 		R,
@@ -1362,7 +1733,11 @@ impl WasmExecutor {
 
 		let low = memory.lowest_used();
 		let used_mem = memory.used_size();
-		let mut fec = FunctionExecutor::new(memory.clone(), table, ext)?;
+		let mut fec = FunctionExecutor::new(
+			Rc::new(memory.clone()),
+			table.map(SupervisorTable::Interpreted),
+			ext,
+		)?;
 		let parameters = create_parameters(&mut |data: &[u8]| {
 			let offset = fec.heap.allocate(data.len() as u32)?;
 			memory.set(offset, &data)?;
@@ -1395,8 +1770,56 @@ impl WasmExecutor {
 		result
 	}
 
+	/// Checks that every function `module` imports from the `env` namespace is provided with the
+	/// same signature.
+	///
+	/// Unlike instantiation, which stops at the first unresolved import, this reports all the
+	/// missing and mismatched host functions at once. For a versioned import such as
+	/// `ext_foo_version_2`, the provided versions of `ext_foo` are listed as well.
+	pub fn check_host_functions(&self, module: &RawModule) -> Result<()> {
+		let provided = host_functions();
+		let types = module.type_section().map(|section| section.types()).unwrap_or(&[]);
+		let imports = module.import_section().map(|section| section.entries()).unwrap_or(&[]);
+
+		let mut problems = Vec::new();
+		for import in imports.iter().filter(|import| import.module() == "env") {
+			let type_index = match import.external() {
+				External::Function(type_index) => *type_index,
+				_ => continue,
+			};
+			let name = import.field();
+			let expected = types.get(type_index as usize).and_then(|ty| match ty {
+				Type::Function(ty) => signature_from_raw(ty.params(), ty.return_type()),
+			});
+
+			match provided.iter().find(|(provided_name, _)| *provided_name == name) {
+				Some((_, signature)) if Some(signature) == expected.as_ref() => {},
+				Some((_, signature)) => problems.push(
+					format!("{} (expected {:?}, provided {:?})", name, expected, signature)
+				),
+				None => {
+					let versions = provided.iter()
+						.map(|(provided_name, _)| *provided_name)
+						.filter(|provided_name| unversioned_name(provided_name) == unversioned_name(name))
+						.collect::<Vec<_>>();
+					if versions.is_empty() {
+						problems.push(format!("{} (not provided)", name));
+					} else {
+						problems.push(format!("{} (provided: {})", name, versions.join(", ")));
+					}
+				},
+			}
+		}
+
+		if problems.is_empty() {
+			Ok(())
+		} else {
+			Err(Error::UnsupportedHostFunctions(problems.join("; ")))
+		}
+	}
+
 	/// Prepare module instance
-	pub fn prepare_module<E: Externalities<Blake2Hasher>>(
+	pub fn prepare_module<E: Externalities<Blake2Hasher> + ?Sized>(
 		&self,
 		ext: &mut E,
 		heap_pages: usize,
@@ -1418,7 +1841,11 @@ impl WasmExecutor {
 			.not_started_instance()
 			.export_by_name("__indirect_function_table")
 			.and_then(|e| e.as_table().cloned());
-		let mut fec = FunctionExecutor::new(memory.clone(), table, ext)?;
+		let mut fec = FunctionExecutor::new(
+			Rc::new(memory.clone()),
+			table.map(SupervisorTable::Interpreted),
+			ext,
+		)?;
 
 		// finish instantiation by running 'start' function (if any).
 		Ok(intermediate_instance.run_start(&mut fec)?)
@@ -1449,6 +1876,34 @@ mod tests {
 		assert_eq!(output, vec![0u8; 0]);
 	}
 
+	#[test]
+	fn runtime_test_host_functions_are_provided() {
+		let module = parity_wasm::deserialize_buffer(WASM_BINARY).unwrap();
+		WasmExecutor::new().check_host_functions(&module).unwrap();
+	}
+
+	#[test]
+	fn unsupported_host_functions_are_reported() {
+		let code = wabt::wat2wasm(r#"
+		(module
+			(import "env" "ext_print_num" (func (param i32)))
+			(import "env" "ext_print_num_version_2" (func (param i64)))
+			(import "env" "ext_unknown" (func))
+			(memory (export "memory") 1)
+		)
+		"#).unwrap();
+		let module = parity_wasm::deserialize_buffer(&code).unwrap();
+
+		match WasmExecutor::new().check_host_functions(&module) {
+			Err(Error::UnsupportedHostFunctions(problems)) => {
+				assert!(problems.contains("ext_print_num (expected"));
+				assert!(problems.contains("ext_print_num_version_2 (provided: ext_print_num)"));
+				assert!(problems.contains("ext_unknown (not provided)"));
+			},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
 	#[test]
 	fn panicking_should_work() {
 		let mut ext = TestExternalities::default();
@@ -1561,6 +2016,48 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn keccak_256_should_work() {
+		let mut ext = TestExternalities::default();
+		let test_code = WASM_BINARY;
+		assert_eq!(
+			WasmExecutor::new().call(&mut ext, 8, &test_code[..], "test_keccak_256", &[]).unwrap(),
+			hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+		);
+		assert_eq!(
+			WasmExecutor::new().call(&mut ext, 8, &test_code[..], "test_keccak_256", b"abc").unwrap(),
+			hex!("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+		);
+	}
+
+	#[test]
+	fn sha2_256_should_work() {
+		let mut ext = TestExternalities::default();
+		let test_code = WASM_BINARY;
+		assert_eq!(
+			WasmExecutor::new().call(&mut ext, 8, &test_code[..], "test_sha2_256", &[]).unwrap(),
+			hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+		);
+		assert_eq!(
+			WasmExecutor::new().call(&mut ext, 8, &test_code[..], "test_sha2_256", b"abc").unwrap(),
+			hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+		);
+	}
+
+	#[test]
+	fn ripemd_160_should_work() {
+		let mut ext = TestExternalities::default();
+		let test_code = WASM_BINARY;
+		assert_eq!(
+			WasmExecutor::new().call(&mut ext, 8, &test_code[..], "test_ripemd_160", &[]).unwrap(),
+			hex!("9c1185a5c5e9fc54612808977ee8f548b2258d31")
+		);
+		assert_eq!(
+			WasmExecutor::new().call(&mut ext, 8, &test_code[..], "test_ripemd_160", b"abc").unwrap(),
+			hex!("8eb208f7e05d987a9b044a8e98c6b087f15a0bfc")
+		);
+	}
+
 	#[test]
 	fn ed25519_verify_should_work() {
 		let mut ext = TestExternalities::<Blake2Hasher>::default();
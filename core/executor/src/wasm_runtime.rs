@@ -0,0 +1,99 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Interface shared by the Wasm execution backends.
+
+use crate::error::{Error, Result};
+use primitives::Blake2Hasher;
+use runtime_version::RuntimeVersion;
+use state_machine::Externalities;
+use wasmi::{MemoryInstance, MemoryRef, memory_units::Bytes};
+
+/// Engine used to execute Wasm runtimes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WasmExecutionMethod {
+	/// Execute the runtime with the wasmi interpreter.
+	Interpreted,
+	/// Compile the runtime to native code with wasmtime before executing it.
+	///
+	/// Only available if the executor is built with the `wasmtime` feature.
+	Compiled,
+}
+
+impl Default for WasmExecutionMethod {
+	fn default() -> WasmExecutionMethod {
+		WasmExecutionMethod::Interpreted
+	}
+}
+
+/// An instantiated Wasm runtime that can be called repeatedly.
+///
+/// Every call must observe the runtime in the state it had right after instantiation, so that
+/// results do not depend on the backend or on previous calls.
+pub trait WasmRuntime {
+	/// Returns the version reported by the runtime's `Core_version`, if any.
+	fn version(&self) -> Option<RuntimeVersion>;
+
+	/// Calls `method` of the runtime with the encoded `data` and returns the encoded result.
+	fn call(
+		&mut self,
+		ext: &mut dyn Externalities<Blake2Hasher>,
+		method: &str,
+		data: &[u8],
+	) -> Result<Vec<u8>>;
+}
+
+/// Linear memory of a runtime instance, as accessed by the host functions and the allocator.
+pub(crate) trait Memory {
+	/// Reads `size` bytes starting at `offset`.
+	fn get(&self, offset: u32, size: usize) -> Result<Vec<u8>>;
+
+	/// Fills `target` with the bytes starting at `offset`.
+	fn get_into(&self, offset: u32, target: &mut [u8]) -> Result<()>;
+
+	/// Writes `value` starting at `offset`.
+	fn set(&self, offset: u32, value: &[u8]) -> Result<()>;
+
+	/// Returns the size of the memory in bytes.
+	fn current_size(&self) -> u32;
+
+	/// Returns the number of bytes at the start of the memory that are occupied by the data
+	/// segments of the runtime. The heap starts right after them.
+	fn used_size(&self) -> u32;
+}
+
+impl Memory for MemoryRef {
+	fn get(&self, offset: u32, size: usize) -> Result<Vec<u8>> {
+		MemoryInstance::get(self, offset, size).map_err(Error::Wasmi)
+	}
+
+	fn get_into(&self, offset: u32, target: &mut [u8]) -> Result<()> {
+		MemoryInstance::get_into(self, offset, target).map_err(Error::Wasmi)
+	}
+
+	fn set(&self, offset: u32, value: &[u8]) -> Result<()> {
+		MemoryInstance::set(self, offset, value).map_err(Error::Wasmi)
+	}
+
+	fn current_size(&self) -> u32 {
+		let current_size: Bytes = MemoryInstance::current_size(self).into();
+		current_size.0 as u32
+	}
+
+	fn used_size(&self) -> u32 {
+		MemoryInstance::used_size(self).0 as u32
+	}
+}
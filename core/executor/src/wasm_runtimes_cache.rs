@@ -0,0 +1,307 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cache of instantiated Wasm runtimes.
+//!
+//! Instantiating a runtime module is expensive, so every runtime is instantiated only once per
+//! code hash. Right after instantiation the data segments and the values of the mutable globals
+//! are recorded in a `StateSnapshot`, which is applied again before the instance is reused. If
+//! the snapshot can't be taken or applied, a fresh instance is created instead.
+
+use std::{collections::HashMap, mem};
+use crate::error::{Error, Result};
+use crate::wasm_executor::WasmExecutor;
+use crate::wasm_runtime::{WasmExecutionMethod, WasmRuntime};
+use parity_codec::Decode;
+use parity_wasm::elements::{deserialize_buffer, DataSegment, Instruction, Module as RawModule};
+use primitives::{Blake2Hasher, storage::well_known_keys};
+use runtime_version::RuntimeVersion;
+use state_machine::Externalities;
+use wasmi::{Module as WasmModule, ModuleRef as WasmModuleInstanceRef, RuntimeValue};
+use log::{error, trace, warn};
+
+/// Default num of pages for the heap
+pub(crate) const DEFAULT_HEAP_PAGES: u64 = 1024;
+
+/// State of a freshly instantiated runtime that is restored before every reuse.
+struct StateSnapshot {
+	/// Offsets and contents of the active data segments.
+	data_segments: Vec<(u32, Vec<u8>)>,
+	/// Values of all the mutable globals, in declaration order.
+	global_mut_values: Vec<RuntimeValue>,
+}
+
+impl StateSnapshot {
+	/// Records the state of a freshly instantiated module.
+	///
+	/// Returns `None` if the offset of a data segment is not a constant expression.
+	fn take(instance: &WasmModuleInstanceRef, data_segments: Vec<DataSegment>) -> Option<Self> {
+		let data_segments = data_segments
+			.into_iter()
+			.map(|mut segment| {
+				let contents = mem::replace(segment.value_mut(), Vec::new());
+				let offset = match segment.offset().code() {
+					[Instruction::I32Const(offset), Instruction::End] => *offset as u32,
+					[Instruction::GetGlobal(index), Instruction::End] => {
+						match instance.globals().get(*index as usize)?.get() {
+							RuntimeValue::I32(offset) => offset as u32,
+							_ => return None,
+						}
+					},
+					_ => return None,
+				};
+				Some((offset, contents))
+			})
+			.collect::<Option<Vec<_>>>()?;
+
+		let global_mut_values = instance.globals()
+			.iter()
+			.filter(|global| global.is_mutable())
+			.map(|global| global.get())
+			.collect();
+
+		Some(StateSnapshot { data_segments, global_mut_values })
+	}
+
+	/// Resets the given instance to the recorded state.
+	fn apply(&self, instance: &WasmModuleInstanceRef) -> Result<()> {
+		let memory = instance.export_by_name("memory")
+			.and_then(|e| e.as_memory().cloned())
+			.ok_or(Error::InvalidMemoryReference)?;
+
+		memory.erase()?;
+		for (offset, contents) in &self.data_segments {
+			memory.set(*offset, contents)?;
+		}
+
+		let globals = instance.globals();
+		let mutable = globals.iter().filter(|global| global.is_mutable());
+		for (global, value) in mutable.zip(self.global_mut_values.iter()) {
+			global.set(*value)?;
+		}
+		Ok(())
+	}
+}
+
+/// A runtime executed by the wasmi interpreter.
+struct InterpretedRuntime {
+	/// The executor the calls are performed with.
+	executor: WasmExecutor,
+	/// The module the instance was created from, kept for re-instantiation.
+	module: WasmModule,
+	/// Number of heap pages the instance was created with.
+	heap_pages: u64,
+	/// The instance used for calls.
+	instance: WasmModuleInstanceRef,
+	/// Initial state of the instance, if it could be recorded.
+	snapshot: Option<StateSnapshot>,
+	/// Whether the instance was used since it was last reset.
+	dirty: bool,
+	/// Version reported by the runtime.
+	version: Option<RuntimeVersion>,
+}
+
+impl InterpretedRuntime {
+	/// Returns an instance that is in the state right after instantiation, ready for a call.
+	fn instance(&mut self, ext: &mut dyn Externalities<Blake2Hasher>) -> Result<WasmModuleInstanceRef> {
+		if self.dirty {
+			let restored = match self.snapshot {
+				Some(ref snapshot) => match snapshot.apply(&self.instance) {
+					Ok(()) => true,
+					Err(e) => {
+						warn!(target: "executor", "Failed to restore runtime instance: {:?}", e);
+						false
+					},
+				},
+				None => false,
+			};
+
+			if !restored {
+				self.instance = self.executor.prepare_module(ext, self.heap_pages as usize, &self.module)?;
+			}
+		}
+
+		self.dirty = true;
+		Ok(self.instance.clone())
+	}
+}
+
+impl WasmRuntime for InterpretedRuntime {
+	fn version(&self) -> Option<RuntimeVersion> {
+		self.version.clone()
+	}
+
+	fn call(
+		&mut self,
+		ext: &mut dyn Externalities<Blake2Hasher>,
+		method: &str,
+		data: &[u8],
+	) -> Result<Vec<u8>> {
+		let instance = self.instance(ext)?;
+		self.executor.call_in_wasm_module(ext, &instance, method, data)
+	}
+}
+
+enum RuntimePreproc {
+	InvalidCode,
+	ValidCode(Box<dyn WasmRuntime>),
+}
+
+/// Cache of runtimes, keyed by the execution method and the hash of their code.
+#[derive(Default)]
+pub(crate) struct RuntimesCache {
+	runtimes: HashMap<(WasmExecutionMethod, [u8; 32]), RuntimePreproc>,
+}
+
+impl RuntimesCache {
+	/// Fetches the runtime for the `:code` of `ext`, instantiating it if it is not cached yet.
+	pub(crate) fn fetch_runtime<E: Externalities<Blake2Hasher>>(
+		&mut self,
+		wasm_executor: &WasmExecutor,
+		wasm_method: WasmExecutionMethod,
+		ext: &mut E,
+		default_heap_pages: Option<u64>,
+	) -> Result<&mut (dyn WasmRuntime + 'static)> {
+		let code_hash = match ext.original_storage_hash(well_known_keys::CODE) {
+			Some(code_hash) => code_hash,
+			None => return Err(Error::InvalidCode(vec![])),
+		};
+
+		let preproc = self.runtimes.entry((wasm_method, code_hash.into()))
+			.or_insert_with(|| {
+				let code = match ext.original_storage(well_known_keys::CODE) {
+					Some(code) => code,
+					None => return RuntimePreproc::InvalidCode,
+				};
+				let heap_pages = ext.storage(well_known_keys::HEAP_PAGES)
+					.and_then(|pages| u64::decode(&mut &pages[..]))
+					.or(default_heap_pages)
+					.unwrap_or(DEFAULT_HEAP_PAGES);
+				match create_runtime(wasm_executor, wasm_method, ext, &code, heap_pages) {
+					Ok(runtime) => RuntimePreproc::ValidCode(runtime),
+					Err(e) => {
+						trace!(target: "executor", "Invalid code presented to executor ({:?})", e);
+						RuntimePreproc::InvalidCode
+					}
+				}
+			});
+
+		match preproc {
+			RuntimePreproc::InvalidCode => {
+				let code = ext.original_storage(well_known_keys::CODE).unwrap_or(vec![]);
+				Err(Error::InvalidCode(code))
+			},
+			RuntimePreproc::ValidCode(runtime) => Ok(runtime.as_mut()),
+		}
+	}
+}
+
+fn create_runtime(
+	wasm_executor: &WasmExecutor,
+	wasm_method: WasmExecutionMethod,
+	ext: &mut dyn Externalities<Blake2Hasher>,
+	code: &[u8],
+	heap_pages: u64,
+) -> Result<Box<dyn WasmRuntime>> {
+	let raw_module: RawModule = deserialize_buffer(code).map_err(|_| Error::InvalidCode(vec![]))?;
+	if let Err(e) = wasm_executor.check_host_functions(&raw_module) {
+		error!(target: "executor", "Runtime can't be instantiated: {}", e);
+		return Err(e);
+	}
+
+	match wasm_method {
+		WasmExecutionMethod::Interpreted => create_interpreted_runtime(wasm_executor, ext, raw_module, heap_pages)
+			.map(|runtime| Box::new(runtime) as Box<dyn WasmRuntime>),
+		#[cfg(feature = "wasmtime")]
+		WasmExecutionMethod::Compiled => crate::compiled_runtime::create_runtime(ext, code, &raw_module, heap_pages)
+			.map(|runtime| Box::new(runtime) as Box<dyn WasmRuntime>),
+		#[cfg(not(feature = "wasmtime"))]
+		WasmExecutionMethod::Compiled => {
+			error!(target: "executor", "Compiled Wasm execution requires the `wasmtime` feature");
+			Err(Error::Other("Compiled Wasm execution is not supported"))
+		},
+	}
+}
+
+fn create_interpreted_runtime(
+	wasm_executor: &WasmExecutor,
+	ext: &mut dyn Externalities<Blake2Hasher>,
+	raw_module: RawModule,
+	heap_pages: u64,
+) -> Result<InterpretedRuntime> {
+	let data_segments = raw_module.data_section()
+		.map(|section| section.entries().to_vec())
+		.unwrap_or_default();
+	let module = WasmModule::from_parity_wasm_module(raw_module)?;
+	let instance = wasm_executor.prepare_module(ext, heap_pages as usize, &module)?;
+	let snapshot = StateSnapshot::take(&instance, data_segments);
+	if snapshot.is_none() {
+		warn!(target: "executor", "Runtime instance state can't be recorded, it will be recreated for every call");
+	}
+
+	let mut runtime = InterpretedRuntime {
+		executor: wasm_executor.clone(),
+		module,
+		heap_pages,
+		instance,
+		snapshot,
+		dirty: false,
+		version: None,
+	};
+	runtime.version = runtime.call(ext, "Core_version", &[])
+		.ok()
+		.and_then(|v| RuntimeVersion::decode(&mut v.as_slice()));
+	Ok(runtime)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use state_machine::TestExternalities as CoreTestExternalities;
+	use runtime_test::WASM_BINARY;
+
+	type TestExternalities<H> = CoreTestExternalities<H, u64>;
+
+	fn assert_instance_is_reset_between_calls(wasm_method: WasmExecutionMethod) {
+		let mut ext = TestExternalities::new_with_code(WASM_BINARY, Default::default());
+		let executor = WasmExecutor::new();
+		let mut cache = RuntimesCache::default();
+
+		for _ in 0..3 {
+			let runtime = cache.fetch_runtime(&executor, wasm_method, &mut ext, None).unwrap();
+			assert_eq!(runtime.call(&mut ext, "test_static_counter", &[]).unwrap(), vec![1]);
+		}
+	}
+
+	#[test]
+	fn cached_instance_is_reset_between_calls() {
+		assert_instance_is_reset_between_calls(WasmExecutionMethod::Interpreted);
+	}
+
+	#[cfg(feature = "wasmtime")]
+	#[test]
+	fn cached_compiled_runtime_is_reset_between_calls() {
+		assert_instance_is_reset_between_calls(WasmExecutionMethod::Compiled);
+	}
+
+	#[test]
+	fn state_of_runtime_test_can_be_recorded() {
+		let mut ext = TestExternalities::new_with_code(WASM_BINARY, Default::default());
+		let raw_module = deserialize_buffer(WASM_BINARY).unwrap();
+		let runtime = create_interpreted_runtime(&WasmExecutor::new(), &mut ext, raw_module, 8).unwrap();
+		assert!(runtime.snapshot.is_some());
+	}
+}
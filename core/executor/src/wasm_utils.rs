@@ -165,6 +165,18 @@ where
 	f
 }
 
+/// Enter a span for a call of the host function with the given name, if the execution is traced.
+///
+/// The tracing host functions themselves are left out, since the spans they enter and exit must
+/// not be nested in a host function span.
+#[inline(always)]
+pub fn host_function_span(name: &'static str) -> Option<substrate_tracing::SpanGuard> {
+	if !substrate_tracing::is_tracing() || name.starts_with("ext_wasm_tracing_") {
+		return None;
+	}
+	Some(substrate_tracing::span(substrate_tracing::HOST_FUNCTION_TARGET, name))
+}
+
 /// Pass the list of parameters by converting them to respective WASM types.
 #[macro_export]
 macro_rules! marshall {
@@ -204,6 +216,7 @@ macro_rules! dispatch_fn {
 		$name:ident ( $( $names:ident : $params:ty ),* ) $( -> $returns:ty )* => $body:tt $($tail:tt)*
 	) => (
 		if $index_ident == $index {
+			let _span = $crate::wasm_utils::host_function_span(stringify!($name));
 			{ marshall!($args_iter, $objectname, ( $( $names : $params ),* ) $( -> $returns )* => $body) }
 		}
 		dispatch_fn!( @iter $index + 1, $index_ident, $objectname, $args_iter $($tail)*)
@@ -239,6 +252,14 @@ macro_rules! impl_function_executor {
 				}
 				&Resolver
 			}
+
+			/// Returns the name and signature of every provided host function.
+			#[allow(unused)]
+			fn host_functions() -> Vec<(&'static str, $crate::wasmi::Signature)> {
+				vec![
+					$( (stringify!($name), gen_signature!( ( $( $params ),* ) $( -> $returns )* )), )*
+				]
+			}
 		}
 
 		impl $( $pre ) + $crate::wasmi::Externals for $structname {
@@ -83,8 +83,17 @@ pub(crate) struct Status<H, N> {
 	pub(crate) new_set_block: Option<(H, N)>,
 }
 
+/// Whether the authorities of a set are voting.
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq)]
+pub(crate) enum SetState {
+	/// The authorities are voting.
+	Live,
+	/// The authorities aren't voting.
+	Paused,
+}
+
 /// A set of authorities.
-#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+#[derive(Debug, Clone, Encode, PartialEq)]
 pub(crate) struct AuthoritySet<H, N> {
 	pub(crate) current_authorities: Vec<(AuthorityId, u64)>,
 	pub(crate) set_id: u64,
@@ -96,6 +105,34 @@ pub(crate) struct AuthoritySet<H, N> {
 	// Forced changes are enacted on block depth (not finality), for this reason
 	// only one forced change should exist per fork.
 	pub(crate) pending_forced_changes: Vec<PendingChange<H, N>>,
+	// Tree of pending pause and resume signals across forks. A pause is enacted
+	// on finality and a resume on import of its effective block, once every
+	// signal before it on the same branch was enacted.
+	pub(crate) pending_signals: ForkTree<H, N, PendingSignal<H, N>>,
+	// Whether the set is voting, changed by enacting pause and resume signals.
+	pub(crate) state: SetState,
+}
+
+impl<H: Decode, N: Decode> Decode for AuthoritySet<H, N> {
+	fn decode<I: parity_codec::Input>(value: &mut I) -> Option<Self> {
+		let current_authorities = Decode::decode(value)?;
+		let set_id = Decode::decode(value)?;
+		let pending_standard_changes = Decode::decode(value)?;
+		let pending_forced_changes = Decode::decode(value)?;
+
+		// sets stored before pause and resume signals were supported are live.
+		let pending_signals = Decode::decode(value).unwrap_or_else(ForkTree::new);
+		let state = SetState::decode(value).unwrap_or(SetState::Live);
+
+		Some(AuthoritySet {
+			current_authorities,
+			set_id,
+			pending_standard_changes,
+			pending_forced_changes,
+			pending_signals,
+			state,
+		})
+	}
 }
 
 impl<H, N> AuthoritySet<H, N>
@@ -109,6 +146,8 @@ where H: PartialEq,
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			pending_signals: ForkTree::new(),
+			state: SetState::Live,
 		}
 	}
 
@@ -214,6 +253,16 @@ where
 		}
 	}
 
+	/// Removes the pending changes and signals announced in blocks for which `is_known` returns `false`,
+	/// e.g. because they were reverted. Returns `true` if any change was removed.
+	pub(crate) fn retain_known_changes<F: Fn(&H) -> bool>(&mut self, is_known: F) -> bool {
+		let standard = self.pending_standard_changes.retain(|hash, _, _| is_known(hash));
+		let signals = self.pending_signals.retain(|hash, _, _| is_known(hash));
+		let forced = self.pending_forced_changes.len();
+		self.pending_forced_changes.retain(|change| is_known(&change.canon_hash));
+		standard || signals || forced != self.pending_forced_changes.len()
+	}
+
 	/// Inspect pending changes. Standard pending changes are iterated first,
 	/// and the changes in the tree are traversed in pre-order, afterwards all
 	/// forced changes are iterated.
@@ -226,10 +275,101 @@ where
 	/// different forks, this method will return the earliest effective number (across the
 	/// different branches). Only standard changes are taken into account for the current
 	/// limit, since any existing forced change should preclude the voter from voting.
+	/// Pending pause and resume signals are taken into account as well, so that they are
+	/// finalized in-order.
 	pub(crate) fn current_limit(&self) -> Option<N> {
 		self.pending_standard_changes.roots()
-			.min_by_key(|&(_, _, c)| c.effective_number())
 			.map(|(_, _, c)| c.effective_number())
+			.chain(self.pending_signals.roots().map(|(_, _, s)| s.effective_number()))
+			.min()
+	}
+
+	/// Note a pause or resume signal. Signals on the same branch are enacted in-order,
+	/// e.g. a resumption imported before its pause is finalized waits for the pause to be
+	/// enacted. The given function `is_descendent_of` should return `true` if the second
+	/// hash (target) is a descendent of the first hash (base).
+	pub(crate) fn add_pending_signal<F, E>(
+		&mut self,
+		signal: PendingSignal<H, N>,
+		is_descendent_of: &F,
+	) -> Result<(), fork_tree::Error<E>> where
+		F: Fn(&H, &H) -> Result<bool, E>,
+		E: std::error::Error,
+	{
+		debug!(target: "afg", "Inserting potential {:?} signaled at block {:?} (delayed by {:?} blocks).",
+			signal.kind, (&signal.canon_height, &signal.canon_hash), signal.delay);
+
+		self.pending_signals.import(
+			signal.canon_hash.clone(),
+			signal.canon_height.clone(),
+			signal,
+			is_descendent_of,
+		)?;
+
+		Ok(())
+	}
+
+	/// Enact a pending pause on finalization of the given block. Returns `true` if the set was
+	/// paused.
+	///
+	/// Signals on forks other than the finalized chain are pruned. A resumption which was
+	/// already enacted on import is dropped once its effective block is finalized.
+	pub(crate) fn apply_pause<F, E>(
+		&mut self,
+		finalized_hash: H,
+		finalized_number: N,
+		is_descendent_of: &F,
+	) -> Result<bool, fork_tree::Error<E>> where
+		F: Fn(&H, &H) -> Result<bool, E>,
+		E: std::error::Error,
+	{
+		let signal = match self.pending_signals.finalize_with_descendent_if(
+			&finalized_hash,
+			finalized_number.clone(),
+			is_descendent_of,
+			|signal| signal.effective_number() <= finalized_number,
+		)? {
+			fork_tree::FinalizationResult::Changed(signal) => signal,
+			fork_tree::FinalizationResult::Unchanged => None,
+		};
+
+		match signal {
+			Some(PendingSignal { kind: SignalKind::Pause, .. }) => {
+				info!(target: "finality", "Pausing authority set at block #{:?}", finalized_number);
+				telemetry!(CONSENSUS_INFO; "afg.pausing_authority_set";
+					"block" => ?finalized_number
+				);
+				self.state = SetState::Paused;
+				Ok(true)
+			},
+			_ => Ok(false),
+		}
+	}
+
+	/// Check whether the given block enacts a pending resumption of the paused set, i.e.
+	/// whether it is at or past the effective number on top of the announcing block. Only
+	/// resumptions whose pause was enacted are taken into account.
+	pub(crate) fn enacts_resume<F, E>(
+		&self,
+		hash: H,
+		number: N,
+		is_descendent_of: &F,
+	) -> Result<bool, E> where
+		F: Fn(&H, &H) -> Result<bool, E>,
+	{
+		if self.state != SetState::Paused {
+			return Ok(false);
+		}
+
+		for (_, _, signal) in self.pending_signals.roots() {
+			if signal.kind == SignalKind::Resume && signal.effective_number() <= number &&
+				(signal.canon_hash == hash || is_descendent_of(&signal.canon_hash, &hash)?)
+			{
+				return Ok(true);
+			}
+		}
+
+		Ok(false)
 	}
 
 	/// Apply or prune any pending transitions based on a best-block trigger.
@@ -274,6 +414,8 @@ where
 					set_id: self.set_id + 1,
 					pending_standard_changes: ForkTree::new(), // new set, new changes.
 					pending_forced_changes: Vec::new(),
+					pending_signals: ForkTree::new(),
+					state: SetState::Live,
 				}));
 
 				break;
@@ -428,6 +570,39 @@ impl<H, N: Add<Output=N> + Clone> PendingChange<H, N> {
 	}
 }
 
+/// Kinds of signals altering whether the authorities are voting.
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq)]
+pub(crate) enum SignalKind {
+	/// Stop voting once the effective block is finalized.
+	Pause,
+	/// Start voting again once the effective block is imported.
+	Resume,
+}
+
+/// A pending pause or resume signal.
+///
+/// This will be enacted when the announcing block is at some depth within
+/// the finalized (pause) or imported (resume) chain.
+#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+pub(crate) struct PendingSignal<H, N> {
+	/// Whether the set pauses or resumes.
+	pub(crate) kind: SignalKind,
+	/// How deep in the chain the announcing block must be
+	/// before the signal is enacted.
+	pub(crate) delay: N,
+	/// The announcing block's height.
+	pub(crate) canon_height: N,
+	/// The announcing block's hash.
+	pub(crate) canon_hash: H,
+}
+
+impl<H, N: Add<Output=N> + Clone> PendingSignal<H, N> {
+	/// Returns the effective number this signal will be enacted at.
+	pub fn effective_number(&self) -> N {
+		self.canon_height.clone() + self.delay.clone()
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -451,6 +626,8 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			pending_signals: ForkTree::new(),
+			state: SetState::Live,
 		};
 
 		let change_a = PendingChange {
@@ -511,6 +688,41 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn changes_of_unknown_blocks_are_removed() {
+		let mut authorities = AuthoritySet {
+			current_authorities: Vec::new(),
+			set_id: 0,
+			pending_standard_changes: ForkTree::new(),
+			pending_forced_changes: Vec::new(),
+			pending_signals: ForkTree::new(),
+			state: SetState::Live,
+		};
+
+		let change = |canon_height, canon_hash, delay_kind| PendingChange {
+			next_authorities: Vec::new(),
+			delay: 0,
+			canon_height,
+			canon_hash,
+			delay_kind,
+		};
+		let change_a = change(5, "hash_a", DelayKind::Finalized);
+		let change_b = change(10, "hash_b", DelayKind::Finalized);
+		let change_c = change(7, "hash_c", DelayKind::Best { median_last_finalized: 0 });
+
+		authorities.add_pending_change(change_a.clone(), &static_is_descendent_of(false)).unwrap();
+		authorities.add_pending_change(change_b.clone(), &static_is_descendent_of(true)).unwrap();
+		authorities.add_pending_change(change_c.clone(), &static_is_descendent_of(false)).unwrap();
+
+		assert!(!authorities.retain_known_changes(|_| true));
+		assert!(authorities.retain_known_changes(|hash| *hash == "hash_a" || *hash == "hash_b"));
+		assert_eq!(authorities.pending_changes().collect::<Vec<_>>(), vec![&change_a, &change_b]);
+
+		// descendents of removed changes are removed as well.
+		assert!(authorities.retain_known_changes(|hash| *hash == "hash_b"));
+		assert_eq!(authorities.pending_changes().count(), 0);
+	}
+
 	#[test]
 	fn apply_change() {
 		let mut authorities = AuthoritySet {
@@ -518,6 +730,8 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			pending_signals: ForkTree::new(),
+			state: SetState::Live,
 		};
 
 		let set_a = vec![(AuthorityId::from_raw([1; 32]), 5)];
@@ -583,6 +797,8 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			pending_signals: ForkTree::new(),
+			state: SetState::Live,
 		};
 
 		let set_a = vec![(AuthorityId::from_raw([1; 32]), 5)];
@@ -649,6 +865,8 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			pending_signals: ForkTree::new(),
+			state: SetState::Live,
 		};
 
 		let set_a = vec![(AuthorityId::from_raw([1; 32]), 5)];
@@ -715,6 +933,8 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			pending_signals: ForkTree::new(),
+			state: SetState::Live,
 		};
 
 		let set_a = vec![(AuthorityId::from_raw([1; 32]), 5)];
@@ -780,7 +1000,116 @@ mod tests {
 				set_id: 1,
 				pending_standard_changes: ForkTree::new(),
 				pending_forced_changes: Vec::new(),
+				pending_signals: ForkTree::new(),
+				state: SetState::Live,
 			})
 		);
 	}
+
+	fn signal(kind: SignalKind, canon_hash: &'static str, canon_height: u64, delay: u64)
+		-> PendingSignal<&'static str, u64>
+	{
+		PendingSignal { kind, delay, canon_height, canon_hash }
+	}
+
+	#[test]
+	fn pause_and_resume() {
+		let mut authorities = AuthoritySet::<&str, u64>::genesis(Vec::new());
+
+		authorities.add_pending_signal(
+			signal(SignalKind::Pause, "hash_a5", 5, 2),
+			&static_is_descendent_of(true),
+		).unwrap();
+
+		// votes are limited to the block enacting the pause.
+		assert_eq!(authorities.current_limit(), Some(7));
+
+		// finalizing a block before the effective number doesn't pause the set.
+		assert!(!authorities.apply_pause("hash_a6", 6, &static_is_descendent_of(true)).unwrap());
+		assert!(authorities.apply_pause("hash_a7", 7, &static_is_descendent_of(true)).unwrap());
+		assert_eq!(authorities.state, SetState::Paused);
+		assert_eq!(authorities.current_limit(), None);
+
+		authorities.add_pending_signal(
+			signal(SignalKind::Resume, "hash_a10", 10, 0),
+			&static_is_descendent_of(true),
+		).unwrap();
+
+		// the signaling block itself enacts a resumption without delay.
+		assert!(!authorities.enacts_resume("hash_b10", 10, &static_is_descendent_of(false)).unwrap());
+		assert!(authorities.enacts_resume("hash_a10", 10, &static_is_descendent_of(false)).unwrap());
+
+		// the enacted resumption is dropped once finalized.
+		authorities.state = SetState::Live;
+		assert!(!authorities.apply_pause("hash_a10", 10, &static_is_descendent_of(true)).unwrap());
+		assert_eq!(authorities.pending_signals.iter().count(), 0);
+	}
+
+	#[test]
+	fn resume_before_pause_is_finalized() {
+		let mut authorities = AuthoritySet::<&str, u64>::genesis(Vec::new());
+
+		authorities.add_pending_signal(
+			signal(SignalKind::Pause, "hash_a5", 5, 2),
+			&static_is_descendent_of(true),
+		).unwrap();
+
+		// the resumption is queued behind the pause on the same branch.
+		authorities.add_pending_signal(
+			signal(SignalKind::Resume, "hash_a9", 9, 1),
+			&static_is_descendent_of(true),
+		).unwrap();
+		assert_eq!(authorities.current_limit(), Some(7));
+
+		// importing the resumption's effective block doesn't resume a live set.
+		assert!(!authorities.enacts_resume("hash_a10", 10, &static_is_descendent_of(true)).unwrap());
+
+		assert!(authorities.apply_pause("hash_a7", 7, &static_is_descendent_of(true)).unwrap());
+		assert_eq!(authorities.state, SetState::Paused);
+		assert_eq!(authorities.current_limit(), Some(10));
+
+		// the already imported effective block enacts the resumption once the pause is enacted.
+		assert!(authorities.enacts_resume("hash_a10", 10, &static_is_descendent_of(true)).unwrap());
+		assert!(authorities.enacts_resume("hash_a12", 12, &static_is_descendent_of(true)).unwrap());
+		assert!(!authorities.enacts_resume("hash_a8", 8, &static_is_descendent_of(true)).unwrap());
+	}
+
+	#[test]
+	fn pause_signaled_on_retracted_fork_is_dropped() {
+		let mut authorities = AuthoritySet::<&str, u64>::genesis(Vec::new());
+
+		let is_descendent_of_fork = is_descendent_of(|base: &&str, hash: &&str| {
+			base[..6] == hash[..6]
+		});
+
+		authorities.add_pending_signal(
+			signal(SignalKind::Pause, "hash_b5", 5, 2),
+			&is_descendent_of_fork,
+		).unwrap();
+		authorities.add_pending_signal(
+			signal(SignalKind::Pause, "hash_a6", 6, 2),
+			&is_descendent_of_fork,
+		).unwrap();
+		assert_eq!(authorities.current_limit(), Some(7));
+
+		// finalizing the other fork drops the pause signaled on it.
+		assert!(!authorities.apply_pause("hash_a7", 7, &is_descendent_of_fork).unwrap());
+		assert_eq!(authorities.state, SetState::Live);
+		assert_eq!(authorities.current_limit(), Some(8));
+
+		assert!(authorities.apply_pause("hash_a8", 8, &is_descendent_of_fork).unwrap());
+		assert_eq!(authorities.state, SetState::Paused);
+	}
+
+	#[test]
+	fn authority_set_without_state_decodes_as_live() {
+		let authorities = AuthoritySet::<u64, u64>::genesis(vec![(AuthorityId::default(), 1)]);
+		// sets stored before pause and resume signals lack the trailing fields.
+		let mut encoded = authorities.current_authorities.encode();
+		encoded.extend(authorities.set_id.encode());
+		encoded.extend(authorities.pending_standard_changes.encode());
+		encoded.extend(authorities.pending_forced_changes.encode());
+
+		assert_eq!(AuthoritySet::decode(&mut &encoded[..]), Some(authorities));
+	}
 }
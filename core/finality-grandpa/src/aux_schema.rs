@@ -28,7 +28,7 @@ use log::{info, warn};
 use substrate_telemetry::{telemetry, CONSENSUS_INFO};
 use fg_primitives::AuthorityId;
 
-use crate::authorities::{AuthoritySet, SharedAuthoritySet, PendingChange, DelayKind};
+use crate::authorities::{AuthoritySet, SharedAuthoritySet, PendingChange, DelayKind, SetState};
 use crate::consensus_changes::{SharedConsensusChanges, ConsensusChanges};
 use crate::environment::{CompletedRound, CompletedRounds, HasVoted, SharedVoterSetState, VoterSetState};
 use crate::NewAuthoritySet;
@@ -99,6 +99,8 @@ where H: Clone + Debug + PartialEq,
 			current_authorities: self.current_authorities,
 			set_id: self.set_id,
 			pending_forced_changes: Vec::new(),
+			pending_signals: ForkTree::new(),
+			state: SetState::Live,
 			pending_standard_changes
 		}
 	}
@@ -366,6 +368,43 @@ pub(crate) fn load_persistent<Block: BlockT, B, G>(
 	})
 }
 
+/// Removes the pending authority set and consensus changes signaled in blocks for which
+/// `is_known` returns `false` and writes the result back to disk.
+///
+/// Blocks that were reverted are no longer known, so this unwinds the GRANDPA data of reverted
+/// blocks.
+pub(crate) fn prune_unknown_changes<Block: BlockT, B, F>(
+	backend: &B,
+	data: &PersistentData<Block>,
+	is_known: F,
+) -> ClientResult<()> where
+	B: AuxStore,
+	F: Fn(&Block::Hash) -> bool,
+{
+	let mut authority_set = data.authority_set.inner().write();
+	let mut consensus_changes = data.consensus_changes.lock();
+
+	let authority_set = if authority_set.retain_known_changes(&is_known) {
+		Some(authority_set.encode())
+	} else {
+		None
+	};
+	let consensus_changes = if consensus_changes.retain_known(&is_known) {
+		Some(consensus_changes.encode())
+	} else {
+		None
+	};
+
+	let write = authority_set.as_ref().map(|set| (AUTHORITY_SET_KEY, set.as_slice())).into_iter()
+		.chain(consensus_changes.as_ref().map(|changes| (CONSENSUS_CHANGES_KEY, changes.as_slice())))
+		.collect::<Vec<_>>();
+	if !write.is_empty() {
+		info!(target: "afg", "Removing GRANDPA changes signaled in blocks that are no longer known.");
+		backend.insert_aux(&write, &[])?;
+	}
+	Ok(())
+}
+
 /// Update the authority set on disk after a change.
 ///
 /// If there has just been a handoff, pass a `new_set` parameter that describes the
@@ -523,6 +562,8 @@ mod test {
 				current_authorities: authorities.clone(),
 				pending_standard_changes: ForkTree::new(),
 				pending_forced_changes: Vec::new(),
+				pending_signals: ForkTree::new(),
+				state: SetState::Live,
 				set_id,
 			},
 		);
@@ -564,6 +605,8 @@ mod test {
 				current_authorities: authorities.clone(),
 				pending_standard_changes: ForkTree::new(),
 				pending_forced_changes: Vec::new(),
+				pending_signals: ForkTree::new(),
+				state: SetState::Live,
 				set_id,
 			};
 
@@ -610,6 +653,8 @@ mod test {
 				current_authorities: authorities.clone(),
 				pending_standard_changes: ForkTree::new(),
 				pending_forced_changes: Vec::new(),
+				pending_signals: ForkTree::new(),
+				state: SetState::Live,
 				set_id,
 			},
 		);
@@ -27,16 +27,18 @@
 //! In the future, there will be a fallback for allowing sending the same message
 //! under certain conditions that are used to un-stick the protocol.
 
+use std::collections::VecDeque;
 use std::sync::Arc;
 
 use grandpa::{voter, voter_set::VoterSet};
 use grandpa::Message::{Prevote, Precommit, PrimaryPropose};
 use futures::prelude::*;
 use futures::sync::{oneshot, mpsc};
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use tokio_executor::Executor;
 use parity_codec::{Encode, Decode};
 use substrate_primitives::{ed25519, Pair};
+use keystore::{Signer, SignFuture};
 use substrate_telemetry::{telemetry, CONSENSUS_DEBUG, CONSENSUS_INFO};
 use runtime_primitives::traits::{Block as BlockT, Hash as HashT, Header as HeaderT};
 use network::{consensus_gossip as network_gossip, NetworkService};
@@ -60,6 +62,11 @@ mod tests;
 
 pub use fg_primitives::GRANDPA_ENGINE_ID;
 
+/// Name of the notifications protocol GRANDPA messages are gossiped on.
+pub const GRANDPA_PROTOCOL_NAME: &[u8] = b"/paritytech/grandpa/1";
+/// Maximum size of a GRANDPA message, in bytes.
+const MAX_GRANDPA_NOTIFICATION_SIZE: usize = 1024 * 1024;
+
 // cost scalars for reporting peers.
 mod cost {
 	pub(super) const PAST_REJECTION: i32 = -50;
@@ -155,6 +162,16 @@ impl<B, S, H> Network<B> for Arc<NetworkService<B, S, H>> where
 	}
 
 	fn register_validator(&self, validator: Arc<dyn network_gossip::Validator<B>>) {
+		// messages received on the protocol still reach us through the consensus gossip, so the
+		// event stream isn't needed.
+		if let Err(e) = self.register_notifications_protocol(
+			GRANDPA_ENGINE_ID,
+			GRANDPA_PROTOCOL_NAME,
+			Vec::new(),
+			MAX_GRANDPA_NOTIFICATION_SIZE,
+		) {
+			warn!(target: "afg", "Failed to register the GRANDPA notifications protocol: {}", e);
+		}
 		self.with_gossip(
 			move |gossip, context| gossip.register_validator(context, GRANDPA_ENGINE_ID, validator)
 		)
@@ -341,7 +358,7 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 		round: Round,
 		set_id: SetId,
 		voters: Arc<VoterSet<AuthorityId>>,
-		local_key: Option<Arc<ed25519::Pair>>,
+		local_key: Option<Signer<ed25519::Pair>>,
 		has_voted: HasVoted<B>,
 	) -> (
 		impl Stream<Item=SignedMessage<B>,Error=Error>,
@@ -422,6 +439,7 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 			set_id: set_id.0,
 			network: self.service.clone(),
 			locals,
+			pending: VecDeque::new(),
 			sender: tx,
 			has_voted,
 		};
@@ -650,10 +668,14 @@ pub(crate) fn check_message_sig<Block: BlockT>(
 /// use the same raw message and key to sign. This is currently true for
 /// `ed25519` and `BLS` signatures (which we might use in the future), care must
 /// be taken when switching to different key types.
+///
+/// The signatures are created asynchronously, and the signed messages are sent
+/// in order once their signatures are ready.
 struct OutgoingMessages<Block: BlockT, N: Network<Block>> {
 	round: u64,
 	set_id: u64,
-	locals: Option<(Arc<ed25519::Pair>, AuthorityId)>,
+	locals: Option<(Signer<ed25519::Pair>, AuthorityId)>,
+	pending: VecDeque<(Message<Block>, SignFuture<AuthoritySignature>)>,
 	sender: mpsc::UnboundedSender<SignedMessage<Block>>,
 	network: N,
 	has_voted: HasVoted<Block>,
@@ -682,58 +704,93 @@ impl<Block: BlockT, N: Network<Block>> Sink for OutgoingMessages<Block, N>
 		}
 
 		// when locals exist, sign messages on import
-		if let Some((ref pair, ref local_id)) = self.locals {
+		if let Some((ref signer, _)) = self.locals {
 			let encoded = localized_payload(self.round, self.set_id, &msg);
-			let signature = pair.sign(&encoded[..]);
+			let signature = signer.sign(&encoded[..]);
+			self.pending.push_back((msg, signature));
+		}
+
+		Ok(AsyncSink::Ready)
+	}
 
-			let target_hash = msg.target().0.clone();
-			let signed = SignedMessage::<Block> {
-				message: msg,
-				signature,
-				id: local_id.clone(),
+	fn poll_complete(&mut self) -> Poll<(), Error> {
+		while let Some((_, signature)) = self.pending.front_mut() {
+			let signature = match signature.poll() {
+				Ok(Async::Ready(signature)) => Some(signature),
+				Ok(Async::NotReady) => return Ok(Async::NotReady),
+				Err(e) => {
+					warn!(target: "afg", "Failed to sign vote in round {}: {}", self.round, e);
+					None
+				}
 			};
 
-			let message = GossipMessage::VoteOrPrecommit(VoteOrPrecommitMessage::<Block> {
-				message: signed.clone(),
-				round: Round(self.round),
-				set_id: SetId(self.set_id),
-			});
-
-			debug!(
-				target: "afg",
-				"Announcing block {} to peers which we voted on in round {} in set {}",
-				target_hash,
-				self.round,
-				self.set_id,
-			);
-
-			telemetry!(
-				CONSENSUS_DEBUG; "afg.announcing_blocks_to_voted_peers";
-				"block" => ?target_hash, "round" => ?self.round, "set_id" => ?self.set_id,
-			);
-
-			// announce our block hash to peers and propagate the
-			// message.
-			self.network.announce(target_hash);
-
-			let topic = round_topic::<Block>(self.round, self.set_id);
-			self.network.gossip_message(topic, message.encode(), false);
-
-			// forward the message to the inner sender.
-			let _ = self.sender.unbounded_send(signed);
+			let (msg, _) = self.pending.pop_front()
+				.expect("the front of the queue was matched by the loop condition; qed");
+			if let Some(signature) = signature {
+				self.send_signed(msg, signature);
+			}
 		}
 
-		Ok(AsyncSink::Ready)
+		Ok(Async::Ready(()))
 	}
 
-	fn poll_complete(&mut self) -> Poll<(), Error> { Ok(Async::Ready(())) }
-
 	fn close(&mut self) -> Poll<(), Error> {
+		if let Async::NotReady = self.poll_complete()? {
+			return Ok(Async::NotReady);
+		}
+
 		// ignore errors since we allow this inner sender to be closed already.
 		self.sender.close().or_else(|_| Ok(Async::Ready(())))
 	}
 }
 
+impl<Block: BlockT, N: Network<Block>> OutgoingMessages<Block, N> {
+	/// Gossip our signed vote, announce the block we voted on and forward the
+	/// vote to the inner sender.
+	fn send_signed(&mut self, msg: Message<Block>, signature: AuthoritySignature) {
+		let local_id = match self.locals {
+			Some((_, ref local_id)) => local_id.clone(),
+			None => return,
+		};
+
+		let target_hash = msg.target().0.clone();
+		let signed = SignedMessage::<Block> {
+			message: msg,
+			signature,
+			id: local_id,
+		};
+
+		let message = GossipMessage::VoteOrPrecommit(VoteOrPrecommitMessage::<Block> {
+			message: signed.clone(),
+			round: Round(self.round),
+			set_id: SetId(self.set_id),
+		});
+
+		debug!(
+			target: "afg",
+			"Announcing block {} to peers which we voted on in round {} in set {}",
+			target_hash,
+			self.round,
+			self.set_id,
+		);
+
+		telemetry!(
+			CONSENSUS_DEBUG; "afg.announcing_blocks_to_voted_peers";
+			"block" => ?target_hash, "round" => ?self.round, "set_id" => ?self.set_id,
+		);
+
+		// announce our block hash to peers and propagate the
+		// message.
+		self.network.announce(target_hash);
+
+		let topic = round_topic::<Block>(self.round, self.set_id);
+		self.network.gossip_message(topic, message.encode(), false);
+
+		// forward the message to the inner sender.
+		let _ = self.sender.unbounded_send(signed);
+	}
+}
+
 // checks a compact commit. returns the cost associated with processing it if
 // the commit was bad.
 fn check_compact_commit<Block: BlockT>(
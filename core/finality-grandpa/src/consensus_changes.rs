@@ -45,6 +45,14 @@ impl<H: Copy + PartialEq, N: Copy + Ord> ConsensusChanges<H, N> {
 		self.pending_changes.insert(idx, at);
 	}
 
+	/// Removes the pending changes of blocks for which `is_known` returns `false`.
+	/// Returns true if any change was removed.
+	pub(crate) fn retain_known<F: Fn(&H) -> bool>(&mut self, is_known: F) -> bool {
+		let len = self.pending_changes.len();
+		self.pending_changes.retain(|(_, hash)| is_known(hash));
+		len != self.pending_changes.len()
+	}
+
 	/// Finalize all pending consensus changes that are finalized by given block.
 	/// Returns true if there any changes were finalized.
 	pub(crate) fn finalize<F: Fn(N) -> ::client::error::Result<Option<H>>>(
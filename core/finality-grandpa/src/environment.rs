@@ -26,7 +26,7 @@ use tokio_timer::Delay;
 use parking_lot::RwLock;
 
 use client::{
-	backend::Backend, BlockchainEvents, CallExecutor, Client, error::Error as ClientError
+	backend::Backend, BlockchainEvents, CallExecutor, Client, Finalizer, error::Error as ClientError
 };
 use grandpa::{
 	BlockNumberOps, Equivocation, Error as GrandpaError, round::State as RoundState,
@@ -34,9 +34,9 @@ use grandpa::{
 };
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::{
-	Block as BlockT, Header as HeaderT, NumberFor, One, Zero, BlockNumberToHash,
+	Block as BlockT, Header as HeaderT, NumberFor, Zero, BlockNumberToHash,
 };
-use substrate_primitives::{Blake2Hasher, ed25519, H256, Pair};
+use substrate_primitives::{Blake2Hasher, ed25519, H256};
 use substrate_telemetry::{telemetry, CONSENSUS_INFO};
 
 use crate::{
@@ -46,10 +46,11 @@ use crate::{
 
 use consensus_common::SelectChain;
 
-use crate::authorities::{AuthoritySet, SharedAuthoritySet};
+use crate::authorities::{AuthoritySet, SetState, SharedAuthoritySet};
 use crate::consensus_changes::SharedConsensusChanges;
 use crate::justification::GrandpaJustification;
 use crate::until_imported::UntilVoteTargetImported;
+use crate::voting_rule::VotingRule;
 use fg_primitives::{AuthorityId, AuthoritySignature};
 
 type HistoricalVotes<Block> = grandpa::HistoricalVotes<
@@ -304,9 +305,10 @@ impl<Block: BlockT> SharedVoterSetState<Block> {
 }
 
 /// The environment we run GRANDPA in.
-pub(crate) struct Environment<B, E, Block: BlockT, N: Network<Block>, RA, SC> {
+pub(crate) struct Environment<B, E, Block: BlockT, N: Network<Block>, RA, SC, VR> {
 	pub(crate) inner: Arc<Client<B, E, Block, RA>>,
 	pub(crate) select_chain: SC,
+	pub(crate) voting_rule: VR,
 	pub(crate) voters: Arc<VoterSet<AuthorityId>>,
 	pub(crate) config: Config,
 	pub(crate) authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
@@ -316,7 +318,7 @@ pub(crate) struct Environment<B, E, Block: BlockT, N: Network<Block>, RA, SC> {
 	pub(crate) voter_set_state: SharedVoterSetState<Block>,
 }
 
-impl<B, E, Block: BlockT, N: Network<Block>, RA, SC> Environment<B, E, Block, N, RA, SC> {
+impl<B, E, Block: BlockT, N: Network<Block>, RA, SC, VR> Environment<B, E, Block, N, RA, SC, VR> {
 	/// Updates the voter set state using the given closure. The write lock is
 	/// held during evaluation of the closure and the environment's voter set
 	/// state is set to its result if successful.
@@ -332,16 +334,18 @@ impl<B, E, Block: BlockT, N: Network<Block>, RA, SC> Environment<B, E, Block, N,
 	}
 }
 
-impl<Block: BlockT<Hash=H256>, B, E, N, RA, SC>
+impl<Block: BlockT<Hash=H256>, B, E, N, RA, SC, VR>
 	grandpa::Chain<Block::Hash, NumberFor<Block>>
-for Environment<B, E, Block, N, RA, SC>
+for Environment<B, E, Block, N, RA, SC, VR>
 where
 	Block: 'static,
 	B: Backend<Block, Blake2Hasher> + 'static,
-	E: CallExecutor<Block, Blake2Hasher> + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
 	N: Network<Block> + 'static,
 	N::In: 'static,
+	RA: Send + Sync,
 	SC: SelectChain<Block> + 'static,
+	VR: VotingRule<Block, Client<B, E, Block, RA>>,
 	NumberFor<Block>: BlockNumberOps,
 {
 	fn ancestry(&self, base: Block::Hash, block: Block::Hash) -> Result<Vec<Block::Hash>, GrandpaError> {
@@ -365,7 +369,7 @@ where
 		debug!(target: "afg", "Finding best chain containing block {:?} with number limit {:?}", block, limit);
 
 		match self.select_chain.finality_target(block, None) {
-			Ok(Some(mut best_hash)) => {
+			Ok(Some(best_hash)) => {
 				let base_header = self.inner.header(&BlockId::Hash(block)).ok()?
 					.expect("Header known to exist after `best_containing` call; qed");
 
@@ -381,35 +385,45 @@ where
 					}
 				}
 
-				let mut best_header = self.inner.header(&BlockId::Hash(best_hash)).ok()?
+				let best_header = self.inner.header(&BlockId::Hash(best_hash)).ok()?
 					.expect("Header known to exist after `best_containing` call; qed");
 
-				// we target a vote towards 3/4 of the unfinalized chain (rounding up)
-				let target = {
-					let two = NumberFor::<Block>::one() + One::one();
-					let three = two + One::one();
-					let four = three + One::one();
-
-					let diff = *best_header.number() - *base_header.number();
-					let diff = ((diff * three) + two) / four;
+				// check if our vote is currently being limited due to a pending change
+				let limit = limit.filter(|limit| limit < best_header.number());
+				let target;
 
-					*base_header.number() + diff
-				};
+				let target_header = if let Some(target_number) = limit {
+					let mut target_header = best_header.clone();
 
-				// unless our vote is currently being limited due to a pending change
-				let target = limit.map(|limit| limit.min(target)).unwrap_or(target);
+					// walk backwards until we find the target block
+					loop {
+						if *target_header.number() < target_number { unreachable!(); }
+						if *target_header.number() == target_number { break; }
 
-				// walk backwards until we find the target block
-				loop {
-					if *best_header.number() < target { unreachable!(); }
-					if *best_header.number() == target {
-						return Some((best_hash, *best_header.number()));
+						target_header = self.inner.header(&BlockId::Hash(*target_header.parent_hash())).ok()?
+							.expect("Header known to exist after `best_containing` call; qed");
 					}
 
-					best_hash = *best_header.parent_hash();
-					best_header = self.inner.header(&BlockId::Hash(best_hash)).ok()?
-						.expect("Header known to exist after `best_containing` call; qed");
-				}
+					target = target_header;
+					&target
+				} else {
+					// otherwise just use the given best as the target
+					&best_header
+				};
+
+				// restrict the vote according to the voting rule. the limit of a pending change
+				// is applied first, as an implicit voting rule, but the rule still sees the
+				// original best block.
+				//
+				// a restricted vote is only taken if it is between the base and the current
+				// target, otherwise the current target is used instead.
+				self.voting_rule
+					.restrict_vote(&*self.inner, &base_header, &best_header, target_header)
+					.filter(|(_, restricted_number)| {
+						restricted_number >= base_header.number() &&
+							restricted_number < target_header.number()
+					})
+					.or(Some((target_header.hash(), *target_header.number())))
 			},
 			Ok(None) => {
 				debug!(target: "afg", "Encountered error finding best chain containing {:?}: couldn't find target block", block);
@@ -460,9 +474,9 @@ pub(crate) fn ancestry<B, Block: BlockT<Hash=H256>, E, RA>(
 	Ok(tree_route.retracted().iter().skip(1).map(|e| e.hash).collect())
 }
 
-impl<B, E, Block: BlockT<Hash=H256>, N, RA, SC>
+impl<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR>
 	voter::Environment<Block::Hash, NumberFor<Block>>
-for Environment<B, E, Block, N, RA, SC>
+for Environment<B, E, Block, N, RA, SC, VR>
 where
 	Block: 'static,
 	B: Backend<Block, Blake2Hasher> + 'static,
@@ -471,6 +485,7 @@ where
 	N::In: 'static + Send,
 	RA: 'static + Send + Sync,
 	SC: SelectChain<Block> + 'static,
+	VR: VotingRule<Block, Client<B, E, Block, RA>>,
 	NumberFor<Block>: BlockNumberOps,
 {
 	type Timer = Box<dyn Future<Item = (), Error = Self::Error> + Send>;
@@ -811,6 +826,25 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 			&is_descendent_of(client, None),
 		).map_err(|e| Error::Safety(e.to_string()))?;
 
+		// the authorities stop voting once the block enacting a pause is finalized.
+		let mut paused = authority_set.apply_pause(
+			hash,
+			number,
+			&is_descendent_of(client, None),
+		).map_err(|e| Error::Safety(e.to_string()))?;
+
+		// a resumption queued behind the pause may have been imported already.
+		if paused {
+			let chain = client.info().chain;
+			if authority_set.enacts_resume(chain.best_hash, chain.best_number, &is_descendent_of(client, None))
+				.map_err(|e| Error::Safety(e.to_string()))?
+			{
+				info!(target: "afg", "Resuming paused authority set at block #{}", chain.best_number);
+				authority_set.state = SetState::Live;
+				paused = false;
+			}
+		}
+
 		// check if this is this is the first finalization of some consensus changes
 		let (alters_consensus_changes, finalizes_consensus_changes) = consensus_changes
 			.finalize((number, hash), &canon_at_height)?;
@@ -909,7 +943,10 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 			None
 		};
 
-		if status.changed {
+		let signals_changed = authority_set.state != old_authority_set.state ||
+			authority_set.pending_signals != old_authority_set.pending_signals;
+
+		if status.changed || signals_changed {
 			let write_result = crate::aux_schema::update_authority_set::<Block, _, _>(
 				&authority_set,
 				new_authorities.as_ref(),
@@ -924,7 +961,11 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 			}
 		}
 
-		Ok(new_authorities.map(VoterCommand::ChangeAuthorities))
+		Ok(new_authorities.map(VoterCommand::ChangeAuthorities).or_else(|| if paused {
+			Some(VoterCommand::Pause(format!("Authority set paused at block #{}", number)))
+		} else {
+			None
+		}))
 	});
 
 	match update_res {
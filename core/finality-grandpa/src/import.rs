@@ -30,9 +30,9 @@ use consensus_common::{
 	BlockImportParams, ImportResult, JustificationImport, well_known_cache_keys,
 	SelectChain,
 };
-use fg_primitives::GrandpaApi;
+use fg_primitives::{ConsensusLog, GrandpaApi, GRANDPA_ENGINE_ID};
 use runtime_primitives::Justification;
-use runtime_primitives::generic::BlockId;
+use runtime_primitives::generic::{BlockId, OpaqueDigestItemId};
 use runtime_primitives::traits::{
 	Block as BlockT, DigestFor,
 	Header as HeaderT, NumberFor, ProvideRuntimeApi,
@@ -40,7 +40,9 @@ use runtime_primitives::traits::{
 use substrate_primitives::{H256, Blake2Hasher};
 
 use crate::{Error, CommandOrError, NewAuthoritySet, VoterCommand};
-use crate::authorities::{AuthoritySet, SharedAuthoritySet, DelayKind, PendingChange};
+use crate::authorities::{
+	AuthoritySet, SharedAuthoritySet, DelayKind, PendingChange, PendingSignal, SetState, SignalKind,
+};
 use crate::consensus_changes::SharedConsensusChanges;
 use crate::environment::{finalize_block, is_descendent_of};
 use crate::justification::GrandpaJustification;
@@ -152,16 +154,17 @@ struct PendingSetChanges<'a, Block: 'a + BlockT> {
 	)>,
 	applied_changes: AppliedChanges<Block::Hash, NumberFor<Block>>,
 	do_pause: bool,
+	do_resume: bool,
 }
 
 impl<'a, Block: 'a + BlockT> PendingSetChanges<'a, Block> {
 	// revert the pending set change explicitly.
 	fn revert(self) { }
 
-	fn defuse(mut self) -> (AppliedChanges<Block::Hash, NumberFor<Block>>, bool) {
+	fn defuse(mut self) -> (AppliedChanges<Block::Hash, NumberFor<Block>>, bool, bool) {
 		self.just_in_case = None;
 		let applied_changes = ::std::mem::replace(&mut self.applied_changes, AppliedChanges::None);
-		(applied_changes, self.do_pause)
+		(applied_changes, self.do_pause, self.do_resume)
 	}
 }
 
@@ -244,6 +247,27 @@ where
 		}
 	}
 
+	// check for pause and resume signals.
+	fn check_pause_or_resume(&self, header: &Block::Header, hash: Block::Hash)
+		-> Vec<PendingSignal<Block::Hash, NumberFor<Block>>>
+	{
+		let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+		header.digest().logs().iter()
+			.filter_map(|log| log.try_to::<ConsensusLog<NumberFor<Block>>>(id))
+			.filter_map(|log| match log {
+				ConsensusLog::Pause(delay) => Some((SignalKind::Pause, delay)),
+				ConsensusLog::Resume(delay) => Some((SignalKind::Resume, delay)),
+				_ => None,
+			})
+			.map(|(kind, delay)| PendingSignal {
+				kind,
+				delay,
+				canon_height: *header.number(),
+				canon_hash: hash,
+			})
+			.collect()
+	}
+
 	fn make_authorities_changes<'a>(&'a self, block: &mut BlockImportParams<Block>, hash: Block::Hash)
 		-> Result<PendingSetChanges<'a, Block>, ConsensusError>
 	{
@@ -300,6 +324,27 @@ where
 			old: None,
 		};
 
+		// pause and resume signals only alter whether the current set is voting.
+		for signal in self.check_pause_or_resume(&block.header, hash) {
+			let old = guard.as_mut().clone();
+			guard.set_old(old);
+
+			guard.as_mut().add_pending_signal(
+				signal,
+				&is_descendent_of,
+			).map_err(|e| ConsensusError::from(ConsensusError::ClientImport(e.to_string())))?;
+		}
+
+		// whether to resume the paused authority set -- happens after import
+		// of the block at the resume signal's delay.
+		let do_resume = guard.as_mut().enacts_resume(hash, number, &is_descendent_of)
+			.map_err(|e| ConsensusError::ClientImport(e.to_string()))?;
+		if do_resume {
+			let old = guard.as_mut().clone();
+			guard.set_old(old);
+			guard.as_mut().state = SetState::Live;
+		}
+
 		// whether to pause the old authority set -- happens after import
 		// of a forced change block.
 		let mut do_pause = false;
@@ -389,7 +434,7 @@ where
 			);
 		}
 
-		Ok(PendingSetChanges { just_in_case, applied_changes, do_pause })
+		Ok(PendingSetChanges { just_in_case, applied_changes, do_pause, do_resume })
 	}
 }
 
@@ -443,7 +488,7 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, PRA, SC> BlockImport<Block>
 			}
 		};
 
-		let (applied_changes, do_pause) = pending_changes.defuse();
+		let (applied_changes, do_pause, do_resume) = pending_changes.defuse();
 
 		// Send the pause signal after import but BEFORE sending a `ChangeAuthorities` message.
 		if do_pause {
@@ -452,6 +497,11 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, PRA, SC> BlockImport<Block>
 			);
 		}
 
+		if do_resume {
+			info!(target: "afg", "Resuming paused authority set at block #{}", number);
+			let _ = self.send_voter_commands.unbounded_send(VoterCommand::Resume);
+		}
+
 		let needs_justification = applied_changes.needs_justification();
 
 		match applied_changes {
@@ -65,7 +65,8 @@ use fg_primitives::GrandpaApi;
 use inherents::InherentDataProviders;
 use runtime_primitives::generic::BlockId;
 use consensus_common::SelectChain;
-use substrate_primitives::{ed25519, H256, Pair, Blake2Hasher};
+use substrate_primitives::{ed25519, H256, Blake2Hasher};
+use keystore::Signer;
 use substrate_telemetry::{telemetry, CONSENSUS_INFO, CONSENSUS_DEBUG, CONSENSUS_WARN};
 use serde_json;
 
@@ -89,6 +90,7 @@ mod justification;
 mod light_import;
 mod observer;
 mod until_imported;
+mod voting_rule;
 
 #[cfg(feature="service-integration")]
 mod service_integration;
@@ -98,7 +100,11 @@ pub use communication::Network;
 pub use finality_proof::FinalityProofProvider;
 pub use light_import::light_block_import;
 pub use observer::run_grandpa_observer;
+pub use voting_rule::{
+	BeforeBestBlockBy, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRulesBuilder
+};
 
+use authorities::SetState;
 use aux_schema::PersistentData;
 use environment::{CompletedRound, CompletedRounds, Environment, HasVoted, SharedVoterSetState, VoterSetState};
 use import::GrandpaBlockImport;
@@ -199,7 +205,7 @@ pub struct Config {
 	/// justification generation.
 	pub justification_period: u32,
 	/// The local signing key.
-	pub local_key: Option<Arc<ed25519::Pair>>,
+	pub local_key: Option<Signer<ed25519::Pair>>,
 	/// Some local identifier of the voter.
 	pub name: Option<String>,
 }
@@ -273,6 +279,8 @@ pub(crate) struct NewAuthoritySet<H, N> {
 pub(crate) enum VoterCommand<H, N> {
 	/// Pause the voter for given reason.
 	Pause(String),
+	/// Resume the paused voter.
+	Resume,
 	/// New authorities.
 	ChangeAuthorities(NewAuthoritySet<H, N>)
 }
@@ -281,6 +289,7 @@ impl<H, N> fmt::Display for VoterCommand<H, N> {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match *self {
 			VoterCommand::Pause(ref reason) => write!(f, "Pausing voter: {}", reason),
+			VoterCommand::Resume => write!(f, "Resuming voter"),
 			VoterCommand::ChangeAuthorities(_) => write!(f, "Changing authorities"),
 		}
 	}
@@ -375,6 +384,13 @@ where
 		}
 	)?;
 
+	aux_schema::prune_unknown_changes(
+		#[allow(deprecated)]
+		&**client.backend(),
+		&persistent_data,
+		|hash| client.header(&BlockId::Hash(*hash)).ok().and_then(|header| header).is_some(),
+	)?;
+
 	let (voter_commands_tx, voter_commands_rx) = mpsc::unbounded();
 
 	Ok((
@@ -396,7 +412,7 @@ where
 }
 
 fn global_communication<Block: BlockT<Hash=H256>, B, E, N, RA>(
-	local_key: Option<&Arc<ed25519::Pair>>,
+	local_key: Option<&Signer<ed25519::Pair>>,
 	set_id: u64,
 	voters: &Arc<VoterSet<AuthorityId>>,
 	client: &Arc<Client<B, E, Block, RA>>,
@@ -472,7 +488,7 @@ fn register_finality_tracker_inherent_data_provider<B, E, Block: BlockT<Hash=H25
 }
 
 /// Parameters used to run Grandpa.
-pub struct GrandpaParams<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X> {
+pub struct GrandpaParams<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X> {
 	/// Configuration for the GRANDPA service.
 	pub config: Config,
 	/// A link to the block import worker.
@@ -485,12 +501,14 @@ pub struct GrandpaParams<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X> {
 	pub on_exit: X,
 	/// If supplied, can be used to hook on telemetry connection established events.
 	pub telemetry_on_connect: Option<TelemetryOnConnect>,
+	/// A voting rule used to potentially restrict target votes.
+	pub voting_rule: VR,
 }
 
 /// Run a GRANDPA voter as a task. Provide configuration and a link to a
 /// block import worker that has already been instantiated with `block_import`.
-pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
-	grandpa_params: GrandpaParams<B, E, Block, N, RA, SC, X>,
+pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
+	grandpa_params: GrandpaParams<B, E, Block, N, RA, SC, VR, X>,
 ) -> ::client::error::Result<impl Future<Item=(),Error=()> + Send + 'static> where
 	Block::Hash: Ord,
 	B: Backend<Block, Blake2Hasher> + 'static,
@@ -498,6 +516,7 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 	N: Network<Block> + Send + Sync + 'static,
 	N::In: Send + 'static,
 	SC: SelectChain<Block> + 'static,
+	VR: VotingRule<Block, Client<B, E, Block, RA>> + Clone + 'static,
 	NumberFor<Block>: BlockNumberOps,
 	DigestFor<Block>: Encode,
 	RA: Send + Sync + 'static,
@@ -510,6 +529,7 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 		inherent_data_providers,
 		on_exit,
 		telemetry_on_connect,
+		voting_rule,
 	} = grandpa_params;
 
 	use futures::future::{self, Loop as FutureLoop};
@@ -560,6 +580,7 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 		inner: client.clone(),
 		config: config.clone(),
 		select_chain: select_chain.clone(),
+		voting_rule: voting_rule.clone(),
 		voters: Arc::new(voters),
 		network: network.clone(),
 		set_id: authority_set.set_id(),
@@ -569,6 +590,13 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 	});
 
 	initial_environment.update_voter_set_state(|voter_set_state| {
+		// the authority set may have been paused while the voter was shutting down.
+		if authority_set.inner().read().state == SetState::Paused {
+			if let VoterSetState::Live { completed_rounds, .. } = voter_set_state {
+				return Ok(Some(VoterSetState::Paused { completed_rounds: completed_rounds.clone() }));
+			}
+		}
+
 		match voter_set_state {
 			VoterSetState::Live { current_round: HasVoted::Yes(id, _), completed_rounds } => {
 				let local_id = config.local_key.clone().map(|pair| pair.public());
@@ -644,6 +672,7 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 		let config = config.clone();
 		let network = network.clone();
 		let select_chain = select_chain.clone();
+		let voting_rule = voting_rule.clone();
 		let authority_set = authority_set.clone();
 		let consensus_changes = consensus_changes.clone();
 
@@ -687,6 +716,7 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 					let env = Arc::new(Environment {
 						inner: client,
 						select_chain,
+						voting_rule,
 						config,
 						voters: Arc::new(new.authorities.into_iter().collect()),
 						set_id: new.set_id,
@@ -711,6 +741,25 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 						Ok(Some(set_state))
 					})?;
 
+					Ok(FutureLoop::Continue((env, voter_commands_rx)))
+				},
+				VoterCommand::Resume => {
+					info!(target: "afg", "Resuming paused validator set");
+
+					env.update_voter_set_state(|voter_set_state| {
+						let set_state = match voter_set_state {
+							VoterSetState::Paused { completed_rounds } => VoterSetState::Live {
+								completed_rounds: completed_rounds.clone(),
+								current_round: HasVoted::No,
+							},
+							VoterSetState::Live { .. } => return Ok(None),
+						};
+
+						#[allow(deprecated)]
+						aux_schema::write_voter_set_state(&**client.backend(), &set_state)?;
+						Ok(Some(set_state))
+					})?;
+
 					Ok(FutureLoop::Continue((env, voter_commands_rx)))
 				},
 			}
@@ -761,8 +810,8 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 }
 
 #[deprecated(since = "1.1", note = "Please switch to run_grandpa_voter.")]
-pub fn run_grandpa<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
-	grandpa_params: GrandpaParams<B, E, Block, N, RA, SC, X>,
+pub fn run_grandpa<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
+	grandpa_params: GrandpaParams<B, E, Block, N, RA, SC, VR, X>,
 ) -> ::client::error::Result<impl Future<Item=(),Error=()> + Send + 'static> where
 	Block::Hash: Ord,
 	B: Backend<Block, Blake2Hasher> + 'static,
@@ -770,6 +819,7 @@ pub fn run_grandpa<B, E, Block: BlockT<Hash=H256>, N, RA, SC, X>(
 	N: Network<Block> + Send + Sync + 'static,
 	N::In: Send + 'static,
 	SC: SelectChain<Block> + 'static,
+	VR: VotingRule<Block, Client<B, E, Block, RA>> + Clone + 'static,
 	NumberFor<Block>: BlockNumberOps,
 	DigestFor<Block>: Encode,
 	RA: Send + Sync + 'static,
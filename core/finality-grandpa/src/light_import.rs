@@ -20,7 +20,7 @@ use log::{info, trace, warn};
 use parking_lot::RwLock;
 
 use client::{
-	CallExecutor, Client,
+	CallExecutor, Client, Finalizer,
 	backend::{AuxStore, Backend},
 	blockchain::HeaderBackend,
 	error::Error as ClientError,
@@ -315,7 +315,7 @@ fn do_import_finality_proof<B, E, Block: BlockT<Hash=H256>, RA, J>(
 	let block_origin = BlockOrigin::NetworkBroadcast;
 	for header_to_import in finality_effects.headers_to_import {
 		let (block_to_import, new_authorities) = verifier.verify(block_origin, header_to_import, None, None)
-			.map_err(|e| ConsensusError::ClientImport(e))?;
+			.map_err(|e| ConsensusError::ClientImport(e.to_string()))?;
 		assert!(block_to_import.justification.is_none(), "We have passed None as justification to verifier.verify");
 
 		let mut cache = HashMap::new();
@@ -655,6 +655,7 @@ pub mod tests {
 			finalized: false,
 			auxiliary: Vec::new(),
 			fork_choice: ForkChoiceStrategy::LongestChain,
+			storage_changes: None,
 		};
 		do_import_block::<_, _, _, _, TestJustification>(
 			&client,
@@ -235,6 +235,20 @@ pub fn run_grandpa_observer<B, E, Block: BlockT<Hash=H256>, N, RA, SC>(
 
 					set_state
 				},
+				VoterCommand::Resume => {
+					info!(target: "afg", "Resuming paused validator set");
+
+					let completed_rounds = set_state.read().completed_rounds();
+					let set_state = VoterSetState::Live::<Block> {
+						completed_rounds,
+						current_round: HasVoted::No,
+					};
+
+					#[allow(deprecated)]
+					crate::aux_schema::write_voter_set_state(&**client.backend(), &set_state)?;
+
+					set_state
+				},
 				VoterCommand::ChangeAuthorities(new) => {
 					// start the new authority set using the block where the
 					// set changed (not where the signal happened!) as the base.
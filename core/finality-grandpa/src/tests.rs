@@ -26,7 +26,7 @@ use tokio::runtime::current_thread;
 use keyring::ed25519::{Keyring as AuthorityKeyring};
 use client::{
 	error::Result,
-	runtime_api::{Core, RuntimeVersion, ApiExt},
+	runtime_api::{Core, RuntimeVersion, ApiExt, OverlayedChanges, StorageProof},
 	LongestChain,
 };
 use test_client::{self, runtime::BlockNumber};
@@ -261,7 +261,11 @@ impl ApiExt<Block> for RuntimeApi {
 		unimplemented!("Not required for testing!")
 	}
 
-	fn extract_proof(&mut self) -> Option<Vec<Vec<u8>>> {
+	fn extract_proof(&mut self) -> Option<StorageProof> {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn extract_changes(&mut self) -> OverlayedChanges {
 		unimplemented!("Not required for testing!")
 	}
 }
@@ -323,8 +327,8 @@ impl AuthoritySetForFinalityProver<Block> for TestApi {
 			})
 	}
 
-	fn prove_authorities(&self, block: &BlockId<Block>) -> Result<Vec<Vec<u8>>> {
-		self.authorities(block).map(|auth| vec![auth.encode()])
+	fn prove_authorities(&self, block: &BlockId<Block>) -> Result<StorageProof> {
+		self.authorities(block).map(|auth| StorageProof::new(vec![auth.encode()]))
 	}
 }
 
@@ -333,9 +337,9 @@ impl AuthoritySetForFinalityChecker<Block> for TestApi {
 		&self,
 		_hash: <Block as BlockT>::Hash,
 		_header: <Block as BlockT>::Header,
-		proof: Vec<Vec<u8>>,
+		proof: StorageProof,
 	) -> Result<Vec<(AuthorityId, u64)>> {
-		Decode::decode(&mut &proof[0][..])
+		Decode::decode(&mut &proof.nodes()[0][..])
 			.ok_or_else(|| unreachable!("incorrect value is passed as GRANDPA authorities proof"))
 	}
 }
@@ -405,7 +409,7 @@ fn run_to_completion_with<F>(
 			config: Config {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_period: 32,
-				local_key: Some(Arc::new(key.clone().into())),
+				local_key: Some(Signer::from_pair(key.clone().into())),
 				name: Some(format!("peer#{}", peer_id)),
 			},
 			link: link,
@@ -413,6 +417,7 @@ fn run_to_completion_with<F>(
 			inherent_data_providers: InherentDataProviders::new(),
 			on_exit: Exit,
 			telemetry_on_connect: None,
+			voting_rule: ThreeQuartersOfTheUnfinalizedChain,
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -482,7 +487,7 @@ fn finalize_3_voters_1_full_observer() {
 
 	let all_peers = peers.iter()
 		.cloned()
-		.map(|key| Some(Arc::new(key.into())))
+		.map(|key| Some(Signer::from_pair(key.into())))
 		.chain(::std::iter::once(None));
 
 	for (peer_id, local_key) in all_peers.enumerate() {
@@ -514,6 +519,7 @@ fn finalize_3_voters_1_full_observer() {
 			inherent_data_providers: InherentDataProviders::new(),
 			on_exit: Exit,
 			telemetry_on_connect: None,
+			voting_rule: ThreeQuartersOfTheUnfinalizedChain,
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -640,7 +646,7 @@ fn transition_3_voters_twice_1_full_observer() {
 		.cloned()
 		.collect::<HashSet<_>>() // deduplicate
 		.into_iter()
-		.map(|key| Some(Arc::new(key.into())))
+		.map(|key| Some(Signer::from_pair(key.into())))
 		.enumerate();
 
 	for (peer_id, local_key) in all_peers {
@@ -682,6 +688,7 @@ fn transition_3_voters_twice_1_full_observer() {
 			inherent_data_providers: InherentDataProviders::new(),
 			on_exit: Exit,
 			telemetry_on_connect: None,
+			voting_rule: ThreeQuartersOfTheUnfinalizedChain,
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -960,7 +967,7 @@ fn allows_reimporting_change_blocks() {
 	let (mut block_import, ..) = net.make_block_import(client.clone());
 
 	let full_client = client.as_full().unwrap();
-	let builder = full_client.new_block_at(&BlockId::Number(0), Default::default()).unwrap();
+	let builder = full_client.new_block_at(&BlockId::Number(0), Default::default(), false).unwrap();
 	let block = builder.bake().unwrap();
 	api.scheduled_changes.lock().insert(*block.header.parent_hash(), ScheduledChange {
 		next_authorities: make_ids(peers_b),
@@ -978,6 +985,7 @@ fn allows_reimporting_change_blocks() {
 			finalized: false,
 			auxiliary: Vec::new(),
 			fork_choice: ForkChoiceStrategy::LongestChain,
+			storage_changes: None,
 		}
 	};
 
@@ -1009,7 +1017,7 @@ fn test_bad_justification() {
 	let (mut block_import, ..) = net.make_block_import(client.clone());
 
 	let full_client = client.as_full().expect("only full clients are used in test");
-	let builder = full_client.new_block_at(&BlockId::Number(0), Default::default()).unwrap();
+	let builder = full_client.new_block_at(&BlockId::Number(0), Default::default(), false).unwrap();
 	let block = builder.bake().unwrap();
 	api.scheduled_changes.lock().insert(*block.header.parent_hash(), ScheduledChange {
 		next_authorities: make_ids(peers_b),
@@ -1027,6 +1035,7 @@ fn test_bad_justification() {
 			finalized: false,
 			auxiliary: Vec::new(),
 			fork_choice: ForkChoiceStrategy::LongestChain,
+			storage_changes: None,
 		}
 	};
 
@@ -1089,7 +1098,7 @@ fn voter_persists_its_votes() {
 				config: Config {
 					gossip_duration: TEST_GOSSIP_DURATION,
 					justification_period: 32,
-					local_key: Some(Arc::new(peers[0].clone().into())),
+					local_key: Some(Signer::from_pair(peers[0].clone().into())),
 					name: Some(format!("peer#{}", 0)),
 				},
 				link: link,
@@ -1097,6 +1106,7 @@ fn voter_persists_its_votes() {
 				inherent_data_providers: InherentDataProviders::new(),
 				on_exit: Exit,
 				telemetry_on_connect: None,
+				voting_rule: ThreeQuartersOfTheUnfinalizedChain,
 			};
 
 			let voter = run_grandpa_voter(grandpa_params)
@@ -1141,7 +1151,7 @@ fn voter_persists_its_votes() {
 		let config = Config {
 			gossip_duration: TEST_GOSSIP_DURATION,
 			justification_period: 32,
-			local_key: Some(Arc::new(peers[1].clone().into())),
+			local_key: Some(Signer::from_pair(peers[1].clone().into())),
 			name: Some(format!("peer#{}", 1)),
 		};
 
@@ -1424,6 +1434,7 @@ fn voter_catches_up_to_latest_round_when_behind() {
 			inherent_data_providers: InherentDataProviders::new(),
 			on_exit: Exit,
 			telemetry_on_connect: None,
+			voting_rule: ThreeQuartersOfTheUnfinalizedChain,
 		};
 
 		Box::new(run_grandpa_voter(grandpa_params).expect("all in order with client and network"))
@@ -1447,7 +1458,7 @@ fn voter_catches_up_to_latest_round_when_behind() {
 				.for_each(move |_| Ok(()))
 		);
 
-		let voter = voter(Some(Arc::new((*key).into())), peer_id, link, net.clone());
+		let voter = voter(Some(Signer::from_pair((*key).into())), peer_id, link, net.clone());
 
 		runtime.spawn(voter);
 	}
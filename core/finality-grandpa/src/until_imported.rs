@@ -472,6 +472,8 @@ mod tests {
 				origin: BlockOrigin::File,
 				header,
 				is_new_best: false,
+				retracted: Vec::new(),
+				enacted: Vec::new(),
 			}).unwrap();
 		}
 	}
@@ -0,0 +1,355 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Handling custom voting rules for GRANDPA.
+//!
+//! This exposes the `VotingRule` trait used to implement arbitrary voting
+//! restrictions that are taken into account by the GRANDPA environment when
+//! selecting a finality target to vote on.
+
+use std::sync::Arc;
+
+use client::blockchain::HeaderBackend;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, Header, NumberFor, One, Zero};
+
+/// A trait for custom voting rules in GRANDPA.
+pub trait VotingRule<Block, B>: Send + Sync where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	/// Restrict the given `current_target` vote, returning the block hash and
+	/// number of the block to vote on, and `None` in case the vote should not
+	/// be restricted. `base` is the block that we're basing our votes on in
+	/// order to pick our target (e.g. last round estimate), and `best_target`
+	/// is the initial best vote target before any vote rules were applied. When
+	/// applying multiple `VotingRule`s both `base` and `best_target` should
+	/// remain unchanged.
+	///
+	/// The contract of this interface requires that when restricting a vote, the
+	/// returned value **must** be an ancestor of the given `current_target`,
+	/// this also means that a variant must be maintained throughout the
+	/// execution of voting rules wherein `current_target <= best_target`.
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)>;
+}
+
+impl<Block, B> VotingRule<Block, B> for () where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	fn restrict_vote(
+		&self,
+		_backend: &B,
+		_base: &Block::Header,
+		_best_target: &Block::Header,
+		_current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		None
+	}
+}
+
+/// A custom voting rule that guarantees that our vote is always behind the best
+/// block by at least N blocks. In the best case our vote is exactly N blocks
+/// behind the best block.
+#[derive(Clone)]
+pub struct BeforeBestBlockBy<N>(pub N);
+impl<Block, B> VotingRule<Block, B> for BeforeBestBlockBy<NumberFor<Block>> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		_base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		use runtime_primitives::traits::Saturating;
+
+		if current_target.number().is_zero() {
+			return None;
+		}
+
+		// find the target number restricted by this rule
+		let target_number = best_target.number().saturating_sub(self.0);
+
+		// our current target is already lower than this rule would restrict
+		if target_number >= *current_target.number() {
+			return None;
+		}
+
+		// find the block at the given target height
+		find_target(backend, target_number, current_target)
+	}
+}
+
+/// A custom voting rule that limits votes towards 3/4 of the unfinalized chain,
+/// using the given `base` and `best_target` to figure where the 3/4 target
+/// should fall.
+#[derive(Clone)]
+pub struct ThreeQuartersOfTheUnfinalizedChain;
+
+impl<Block, B> VotingRule<Block, B> for ThreeQuartersOfTheUnfinalizedChain where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		// target a vote towards 3/4 of the unfinalized chain (rounding up)
+		let target_number = {
+			let two = NumberFor::<Block>::one() + One::one();
+			let three = two + One::one();
+			let four = three + One::one();
+
+			let diff = *best_target.number() - *base.number();
+			let diff = ((diff * three) + two) / four;
+
+			*base.number() + diff
+		};
+
+		// our current target is already lower than this rule would restrict
+		if target_number >= *current_target.number() {
+			return None;
+		}
+
+		// find the block at the given target height
+		find_target(backend, target_number, current_target)
+	}
+}
+
+// walk backwards until we find the target block
+fn find_target<Block, B>(
+	backend: &B,
+	target_number: NumberFor<Block>,
+	current_header: &Block::Header,
+) -> Option<(Block::Hash, NumberFor<Block>)> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	let mut target_hash = current_header.hash();
+	let mut target_header = current_header.clone();
+
+	loop {
+		if *target_header.number() < target_number {
+			unreachable!(
+				"we are traversing backwards from a known block; \
+				 blocks are stored contiguously; \
+				 qed"
+			);
+		}
+
+		if *target_header.number() == target_number {
+			return Some((target_hash, target_number));
+		}
+
+		target_hash = *target_header.parent_hash();
+		target_header = backend.header(BlockId::Hash(target_hash)).ok()?
+			.expect("Header known to exist due to the existence of one of its descendents; qed");
+	}
+}
+
+struct VotingRules<Block, B> {
+	rules: Arc<Vec<Box<dyn VotingRule<Block, B>>>>,
+}
+
+impl<Block, B> Clone for VotingRules<Block, B> {
+	fn clone(&self) -> Self {
+		VotingRules {
+			rules: self.rules.clone(),
+		}
+	}
+}
+
+impl<Block, B> VotingRule<Block, B> for VotingRules<Block, B> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		let restricted_target = self.rules.iter().fold(
+			current_target.clone(),
+			|current_target, rule| {
+				rule
+					.restrict_vote(backend, base, best_target, &current_target)
+					.and_then(|(hash, _)| backend.header(BlockId::Hash(hash)).ok())
+					.and_then(std::convert::identity)
+					.unwrap_or(current_target)
+			},
+		);
+
+		let restricted_hash = restricted_target.hash();
+
+		if restricted_hash != current_target.hash() {
+			Some((restricted_hash, *restricted_target.number()))
+		} else {
+			None
+		}
+	}
+}
+
+/// A builder of a composite voting rule that applies a set of rules to
+/// progressively restrict the vote.
+pub struct VotingRulesBuilder<Block, B> {
+	rules: Vec<Box<dyn VotingRule<Block, B>>>,
+}
+
+impl<Block, B> Default for VotingRulesBuilder<Block, B> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	fn default() -> Self {
+		VotingRulesBuilder::new()
+			.add(BeforeBestBlockBy(2u32.into()))
+			.add(ThreeQuartersOfTheUnfinalizedChain)
+	}
+}
+
+impl<Block, B> VotingRulesBuilder<Block, B> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	/// Return a new voting rule builder without any rules.
+	pub fn new() -> Self {
+		VotingRulesBuilder {
+			rules: Vec::new(),
+		}
+	}
+
+	/// Add a new voting rule to the builder.
+	pub fn add<R>(mut self, rule: R) -> Self where
+		R: VotingRule<Block, B> + 'static
+	{
+		self.rules.push(Box::new(rule));
+		self
+	}
+
+	/// Add all given voting rules to the builder.
+	pub fn add_all<I>(mut self, rules: I) -> Self where
+		I: IntoIterator<Item=Box<dyn VotingRule<Block, B>>>,
+	{
+		self.rules.extend(rules);
+		self
+	}
+
+	/// Return a new `VotingRule` that applies all of the previously added
+	/// voting rules in-order.
+	pub fn build(self) -> impl VotingRule<Block, B> + Clone {
+		VotingRules {
+			rules: Arc::new(self.rules),
+		}
+	}
+}
+
+impl<Block, B> VotingRule<Block, B> for Box<dyn VotingRule<Block, B>> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		(**self).restrict_vote(backend, base, best_target, current_target)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use consensus_common::BlockOrigin;
+	use substrate_primitives::H256;
+	use test_client::{prelude::*, runtime::Block};
+
+	fn client_with_blocks(n: u64) -> TestClient {
+		let client = test_client::new();
+		for _ in 0..n {
+			let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+			client.import(BlockOrigin::Own, block).unwrap();
+		}
+		client
+	}
+
+	fn hash(client: &TestClient, number: u64) -> H256 {
+		client.hash(number).unwrap().unwrap()
+	}
+
+	// restrict a vote on `current`, with the round based on `base` and the best block `best`.
+	fn restrict_vote<R: VotingRule<Block, TestClient>>(
+		rule: &R,
+		client: &TestClient,
+		base: u64,
+		best: u64,
+		current: u64,
+	) -> Option<(H256, u64)> {
+		let header = |number| client.header(BlockId::Number(number)).unwrap().unwrap();
+		rule.restrict_vote(client, &header(base), &header(best), &header(current))
+	}
+
+	#[test]
+	fn before_best_block_by_restricts_vote() {
+		let client = client_with_blocks(10);
+		let rule = BeforeBestBlockBy(2u64);
+
+		assert_eq!(restrict_vote(&rule, &client, 0, 10, 10), Some((hash(&client, 8), 8)));
+		// a target that is already far enough behind the best block isn't restricted.
+		assert_eq!(restrict_vote(&rule, &client, 0, 10, 5), None);
+	}
+
+	#[test]
+	fn three_quarters_of_the_unfinalized_chain_restricts_vote() {
+		let client = client_with_blocks(10);
+		let rule = ThreeQuartersOfTheUnfinalizedChain;
+
+		assert_eq!(restrict_vote(&rule, &client, 2, 10, 10), Some((hash(&client, 8), 8)));
+		assert_eq!(restrict_vote(&rule, &client, 2, 10, 7), None);
+	}
+
+	#[test]
+	fn voting_rules_are_applied_in_order() {
+		let client = client_with_blocks(12);
+		let rules = VotingRulesBuilder::new()
+			.add(BeforeBestBlockBy(4u64))
+			.add(ThreeQuartersOfTheUnfinalizedChain)
+			.build();
+
+		// 12 - 4 = 8, while 3/4 of the chain between the base at 0 and the best block at 12
+		// is 9, so the second rule doesn't restrict the vote any further.
+		assert_eq!(restrict_vote(&rules, &client, 0, 12, 12), Some((hash(&client, 8), 8)));
+		assert_eq!(restrict_vote(&rules, &client, 0, 12, 6), None);
+
+		// without any rules the vote isn't restricted.
+		let rules = VotingRulesBuilder::<Block, TestClient>::new().build();
+		assert_eq!(restrict_vote(&rules, &client, 0, 12, 12), None);
+	}
+}
@@ -22,7 +22,9 @@
 //! the runtime.
 //!
 //! Types that provide data for inherents, should implement `InherentDataProvider` and need to be
-//! registered at `InherentDataProviders`.
+//! registered at `InherentDataProviders`. Instead of registering the providers once for the whole
+//! lifetime of the node, they can also be created for each block by implementing
+//! `CreateInherentDataProviders`.
 //!
 //! In the runtime, modules need to implement `ProvideInherent` when they can create and/or check
 //! inherents. By implementing `ProvideInherent`, a module is not enforced to create an inherent.
@@ -42,7 +44,10 @@ use rstd::{collections::btree_map::{BTreeMap, IntoIter, Entry}, vec::Vec};
 use parking_lot::RwLock;
 
 #[cfg(feature = "std")]
-use std::{sync::Arc, format};
+use std::{sync::Arc, format, pin::Pin, future::Future};
+
+#[cfg(feature = "std")]
+use runtime_primitives::traits::Block as BlockT;
 
 pub use runtime_primitives::RuntimeString;
 
@@ -97,6 +102,14 @@ impl InherentData {
 		self.data.insert(identifier, inherent.encode());
 	}
 
+	/// Merge the given `InherentData` into this one.
+	///
+	/// The data of `other` replaces any data stored for the same identifier. This is used to
+	/// pass extra inherent data, e.g. the slot found in a block header, into `check_inherents`.
+	pub fn merge(&mut self, other: InherentData) {
+		self.data.extend(other.data);
+	}
+
 	/// Returns the data for the requested inherent.
 	///
 	/// # Return
@@ -276,6 +289,31 @@ impl InherentDataProviders {
 		Ok(data)
 	}
 
+	/// Converts the errors of the given `CheckInherentsResult` into `InherentCheckError`s.
+	///
+	/// Each error is converted to a string and given a retry hint by the provider registered for
+	/// its identifier. If the result contains a fatal error, it is the only returned error.
+	pub fn check_inherents_errors(&self, result: CheckInherentsResult) -> Vec<InherentCheckError> {
+		let fatal = result.fatal_error();
+		let providers = self.providers.read();
+
+		result.into_errors().map(|(identifier, error)| {
+			let provider = providers.iter().find(|p| p.inherent_identifier() == &identifier);
+			let message = match provider {
+				Some(p) => p.error_to_string(&error)
+					.unwrap_or_else(|| error_to_string_fallback(&identifier)),
+				None => unknown_inherent_error(&identifier),
+			};
+			let retry_hint = if fatal {
+				None
+			} else {
+				provider.and_then(|p| p.retry_hint(&error))
+			};
+
+			InherentCheckError { identifier, message, fatal, retry_hint }
+		}).collect()
+	}
+
 	/// Converts a given encoded error into a `String`.
 	///
 	/// Useful if the implementation encouters an error for an identifier it does not know.
@@ -291,16 +329,81 @@ impl InherentDataProviders {
 			}
 		).next();
 
-		match res {
-			Some(res) => res,
-			None => format!(
-				"Error while checking inherent of type \"{}\", but this inherent type is unknown.",
-				String::from_utf8_lossy(identifier)
-			)
-		}
+		res.unwrap_or_else(|| unknown_inherent_error(identifier))
+	}
+}
+
+#[cfg(feature = "std")]
+impl<Block: BlockT, ExtraArgs> CreateInherentDataProviders<Block, ExtraArgs> for InherentDataProviders {
+	fn create_inherent_data_providers(
+		&self,
+		_: Block::Hash,
+		_: ExtraArgs,
+	) -> Pin<Box<dyn Future<Output = Result<InherentDataProviders, RuntimeString>> + Send>> {
+		Box::pin(futures03::future::ready(Ok(self.clone())))
 	}
 }
 
+/// Something that creates the `InherentDataProviders` to use for a block.
+///
+/// The providers are created once per block, on top of the given parent, which allows them to
+/// depend on the state of the chain. `ExtraArgs` is passed by the caller, e.g. the slot of the
+/// block for the consensus engines that need it.
+///
+/// `InherentDataProviders` implements this trait by returning itself for every block, and it is
+/// implemented for closures returning a future of `InherentDataProviders`.
+#[cfg(feature = "std")]
+pub trait CreateInherentDataProviders<Block: BlockT, ExtraArgs>: Send + Sync {
+	/// Create the inherent data providers for a block built on top of `parent`.
+	fn create_inherent_data_providers(
+		&self,
+		parent: Block::Hash,
+		extra_args: ExtraArgs,
+	) -> Pin<Box<dyn Future<Output = Result<InherentDataProviders, RuntimeString>> + Send>>;
+}
+
+#[cfg(feature = "std")]
+impl<F, Fut, Block, ExtraArgs> CreateInherentDataProviders<Block, ExtraArgs> for F where
+	Block: BlockT,
+	F: Fn(Block::Hash, ExtraArgs) -> Fut + Send + Sync,
+	Fut: Future<Output = Result<InherentDataProviders, RuntimeString>> + Send + 'static,
+{
+	fn create_inherent_data_providers(
+		&self,
+		parent: Block::Hash,
+		extra_args: ExtraArgs,
+	) -> Pin<Box<dyn Future<Output = Result<InherentDataProviders, RuntimeString>> + Send>> {
+		Box::pin((*self)(parent, extra_args))
+	}
+}
+
+/// A hint when the check of an inherent that failed with a non-fatal error should be retried.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryHint {
+	/// The inherent will be valid at the given timestamp, in the unit of the timestamp inherent.
+	AtTimestamp(u64),
+	/// The inherent check should be retried after the given duration.
+	After(std::time::Duration),
+}
+
+/// An error of an inherent check, as surfaced to the node.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InherentCheckError {
+	/// The identifier of the inherent that failed the check.
+	pub identifier: InherentIdentifier,
+	/// The error converted to a string by the provider of the inherent.
+	pub message: String,
+	/// Is this a fatal error?
+	///
+	/// A block with a fatal inherent error is invalid. Otherwise the block may become valid
+	/// later, as indicated by `retry_hint`.
+	pub fatal: bool,
+	/// When the check should be retried, if known. Always `None` for fatal errors.
+	pub retry_hint: Option<RetryHint>,
+}
+
 /// Something that provides inherent data.
 #[cfg(feature = "std")]
 pub trait ProvideInherentData {
@@ -322,6 +425,23 @@ pub trait ProvideInherentData {
 	///
 	/// If the given error could not be decoded, `None` should be returned.
 	fn error_to_string(&self, error: &[u8]) -> Option<String>;
+
+	/// Returns when the check of the inherent should be retried after the given encoded
+	/// non-fatal error.
+	///
+	/// If the given error could not be decoded or carries no hint, `None` should be returned.
+	fn retry_hint(&self, _error: &[u8]) -> Option<RetryHint> {
+		None
+	}
+}
+
+/// The error message for an inherent without a registered provider.
+#[cfg(feature = "std")]
+fn unknown_inherent_error(identifier: &InherentIdentifier) -> String {
+	format!(
+		"Error while checking inherent of type \"{}\", but this inherent type is unknown.",
+		String::from_utf8_lossy(identifier)
+	)
 }
 
 /// A fallback function, if the decoding of an error fails.
@@ -462,6 +582,10 @@ mod tests {
 		fn error_to_string(&self, _: &[u8]) -> Option<String> {
 			Some(ERROR_TO_STRING.into())
 		}
+
+		fn retry_hint(&self, error: &[u8]) -> Option<RetryHint> {
+			u32::decode(&mut &error[..]).map(|t| RetryHint::AtTimestamp(t as u64))
+		}
 	}
 
 	#[test]
@@ -512,6 +636,87 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn merging_inherent_data_replaces_existing_data() {
+		let mut data = InherentData::new();
+		data.put_data(TEST_INHERENT_0, &1u32).unwrap();
+
+		let mut extra = InherentData::new();
+		extra.put_data(TEST_INHERENT_0, &2u32).unwrap();
+		extra.put_data(TEST_INHERENT_1, &3u32).unwrap();
+
+		data.merge(extra);
+
+		assert_eq!(data.get_data::<u32>(&TEST_INHERENT_0).unwrap(), Some(2));
+		assert_eq!(data.get_data::<u32>(&TEST_INHERENT_1).unwrap(), Some(3));
+	}
+
+	#[test]
+	fn create_inherent_data_providers_per_block() {
+		use runtime_primitives::testing::{Block, ExtrinsicWrapper, H256};
+		type TestBlock = Block<ExtrinsicWrapper<u64>>;
+
+		let registered = InherentDataProviders::new();
+		registered.register_provider(TestInherentDataProvider::new()).unwrap();
+
+		let providers = futures03::executor::block_on(
+			CreateInherentDataProviders::<TestBlock, ()>::create_inherent_data_providers(
+				&registered,
+				H256::default(),
+				(),
+			)
+		).unwrap();
+		assert!(providers.has_provider(&TEST_INHERENT_0));
+
+		let create = |_: H256, slot: u64| {
+			assert_eq!(slot, 5);
+			let providers = InherentDataProviders::new();
+			let res = providers.register_provider(TestInherentDataProvider::new());
+			futures03::future::ready(res.map(|_| providers))
+		};
+
+		let providers = futures03::executor::block_on(
+			CreateInherentDataProviders::<TestBlock, u64>::create_inherent_data_providers(
+				&create,
+				H256::default(),
+				5,
+			)
+		).unwrap();
+		assert!(providers.has_provider(&TEST_INHERENT_0));
+	}
+
+	#[test]
+	fn check_inherents_errors_are_structured() {
+		let providers = InherentDataProviders::new();
+		providers.register_provider(TestInherentDataProvider::new()).unwrap();
+
+		let mut result = CheckInherentsResult::new();
+		result.put_error(TEST_INHERENT_0, &NoFatalError(10u32)).unwrap();
+		result.put_error(TEST_INHERENT_1, &NoFatalError(11u32)).unwrap();
+
+		let errors = providers.check_inherents_errors(result);
+		assert_eq!(errors.len(), 2);
+		assert_eq!(
+			errors[0],
+			InherentCheckError {
+				identifier: TEST_INHERENT_0,
+				message: ERROR_TO_STRING.into(),
+				fatal: false,
+				retry_hint: Some(RetryHint::AtTimestamp(10)),
+			},
+		);
+		assert!(errors[1].message.contains("inherent type is unknown"));
+		assert_eq!(errors[1].retry_hint, None);
+
+		let mut result = CheckInherentsResult::new();
+		result.put_error(TEST_INHERENT_0, &MakeFatalError(10u32)).unwrap();
+
+		let errors = providers.check_inherents_errors(result);
+		assert_eq!(errors.len(), 1);
+		assert!(errors[0].fatal);
+		assert_eq!(errors[0].retry_hint, None);
+	}
+
 	#[test]
 	fn check_inherents_result_encodes_and_decodes() {
 		let mut result = CheckInherentsResult::new();
@@ -15,15 +15,43 @@
 // along with Substrate. If not, see <http://www.gnu.org/licenses/>.
 
 //! Keystore (and session key management) for ed25519 based chains like Polkadot.
+//!
+//! Keys are kept behind the object safe `BareCryptoStore` trait, which is implemented by the
+//! on-disk `Store` and by the ephemeral `MemoryStore`. If the on-disk store is opened with a
+//! password, the key files are encrypted with ChaCha20-Poly1305, using a key derived from the
+//! password with PBKDF2.
+//!
+//! Signing is done through the `CryptoStore` trait, so that the secret keys of a validator do not
+//! need to be known to the node. Besides the local stores, it is implemented by `RemoteSigner`,
+//! which delegates signing to an external signer process. Signatures are returned as futures, so
+//! that waiting for a remote signer never blocks the caller.
 
 #![warn(missing_docs)]
 
+use std::any::Any;
 use std::collections::HashMap;
+use std::num::NonZeroU32;
 use std::path::PathBuf;
 use std::fs::{self, File};
 use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::sync::Arc;
 
-use substrate_primitives::crypto::{KeyTypeId, Pair, Public};
+use futures::{Future, future};
+use merlin::Transcript;
+use parking_lot::RwLock;
+use ring::{aead, digest, pbkdf2, rand::{SecureRandom, SystemRandom}};
+use schnorrkel::vrf::{VRFOutput, VRFProof};
+use serde::{Serialize, Deserialize};
+use substrate_primitives::{bls, ecdsa, ed25519, sr25519, traits::KeyStore};
+use substrate_primitives::crypto::{KeyTypeId, Pair, Public, Protected, key_types};
+
+mod remote;
+
+pub use remote::{RemoteSigner, RemoteAddress};
+
+/// Number of PBKDF2 iterations used to derive the encryption key from the password.
+const PBKDF2_ITERATIONS: u32 = 10_240;
 
 /// Keystore error.
 #[derive(Debug, derive_more::Display, derive_more::From)]
@@ -41,6 +69,21 @@ pub enum Error {
 	/// Invalid seed
 	#[display(fmt="Invalid seed")]
 	InvalidSeed,
+	/// Key file could not be encrypted.
+	#[display(fmt="Key encryption failed")]
+	Encryption,
+	/// No key with the given public key is stored.
+	#[display(fmt="Key not found")]
+	KeyNotFound,
+	/// Keys of the given type can not be used for signing.
+	#[display(fmt="Unsupported key type")]
+	UnsupportedKeyType,
+	/// The signature has an unexpected length.
+	#[display(fmt="Invalid signature")]
+	InvalidSignature,
+	/// The remote signer returned an error.
+	#[display(fmt="Remote signer error: {}", _0)]
+	Remote(String),
 }
 
 /// Keystore Result
@@ -56,62 +99,64 @@ impl std::error::Error for Error {
 	}
 }
 
-/// Key store.
-pub struct Store {
-	path: PathBuf,
-	additional: HashMap<(KeyTypeId, Vec<u8>), Vec<u8>>,
-}
+/// Storage of the secret URIs of keys, indexed by key type and raw public key.
+pub trait BareCryptoStore: Send + Sync {
+	/// Returns the raw public keys of all stored keys of the given type.
+	fn public_keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>>;
 
-impl Store {
-	/// Create a new store at the given path.
-	pub fn open(path: PathBuf) -> Result<Self> {
-		fs::create_dir_all(&path)?;
-		Ok(Store { path, additional: HashMap::new() })
-	}
+	/// Returns the secret URI of the key with the given type and raw public key.
+	fn secret_uri(&self, key_type: KeyTypeId, public: &[u8]) -> Result<String>;
 
-	fn get_pair<TPair: Pair>(&self, public: &TPair::Public) -> Result<Option<TPair>> {
-		let key = (TPair::KEY_TYPE, public.to_raw_vec());
-		if let Some(bytes) = self.additional.get(&key) {
-			let pair = TPair::from_seed_slice(bytes)
-				.map_err(|_| Error::InvalidSeed)?;
-			return Ok(Some(pair));
-		}
-		Ok(None)
-	}
+	/// Stores the secret URI of a key.
+	fn insert_secret_uri(&self, key_type: KeyTypeId, public: &[u8], suri: &str) -> Result<()>;
 
-	fn insert_pair<TPair: Pair>(&mut self, pair: &TPair) {
-		let key = (TPair::KEY_TYPE, pair.public().to_raw_vec());
-		self.additional.insert(key, pair.to_raw_vec());
-	}
+	/// Stores the secret URI of a key for the lifetime of the store only.
+	fn insert_ephemeral(&self, key_type: KeyTypeId, public: &[u8], suri: &str) -> Result<()>;
 
+	/// Returns the password of the store.
+	fn password(&self) -> Option<&str>;
+}
+
+impl<'a> dyn BareCryptoStore + 'a {
 	/// Generate a new key, placing it into the store.
-	pub fn generate<TPair: Pair>(&self, password: &str) -> Result<TPair> {
-		let (pair, phrase, _) = TPair::generate_with_phrase(Some(password));
-		let mut file = File::create(self.key_file_path::<TPair>(&pair.public()))?;
-		::serde_json::to_writer(&file, &phrase)?;
-		file.flush()?;
+	pub fn generate<TPair: Pair>(&self) -> Result<TPair> {
+		let (pair, phrase, _) = TPair::generate_with_phrase(None);
+		self.insert_secret_uri(TPair::KEY_TYPE, pair.public().as_slice(), &phrase)?;
+		Ok(pair)
+	}
+
+	/// Insert the key given by the secret URI `suri` into the store.
+	///
+	/// The URI is stored as is, so that derivation paths and dev seeds like `//Alice` can be used.
+	pub fn insert<TPair: Pair>(&self, suri: &str) -> Result<TPair> {
+		let pair = TPair::from_string(suri, None)
+			.ok().ok_or(Error::InvalidSeed)?;
+		self.insert_secret_uri(TPair::KEY_TYPE, pair.public().as_slice(), suri)?;
 		Ok(pair)
 	}
 
-	/// Create a new key from seed. Do not place it into the store.
-	pub fn generate_from_seed<TPair: Pair>(&mut self, seed: &str) -> Result<TPair> {
+	/// Create a new key from seed. It is kept in memory only and is not written to disk.
+	pub fn generate_from_seed<TPair: Pair>(&self, seed: &str) -> Result<TPair> {
 		let pair = TPair::from_string(seed, None)
 			.ok().ok_or(Error::InvalidSeed)?;
-		self.insert_pair(&pair);
+		self.insert_ephemeral(TPair::KEY_TYPE, pair.public().as_slice(), seed)?;
 		Ok(pair)
 	}
 
-	/// Load a key file with given public key.
-	pub fn load<TPair: Pair>(&self, public: &TPair::Public, password: &str) -> Result<TPair> {
-		if let Some(pair) = self.get_pair(public)? {
+	/// Load the key with given public key.
+	pub fn load<TPair: Pair>(&self, public: &TPair::Public) -> Result<TPair> {
+		let suri = self.secret_uri(TPair::KEY_TYPE, public.as_slice())?;
+		let pair = TPair::from_phrase(&suri, None)
+			.map(|(pair, _)| pair)
+			.or_else(|_| TPair::from_string(&suri, None))
+			.ok().ok_or(Error::InvalidPhrase)?;
+		if &pair.public() == public {
 			return Ok(pair)
 		}
 
-		let path = self.key_file_path::<TPair>(public);
-		let file = File::open(path)?;
-
-		let phrase: String = ::serde_json::from_reader(&file)?;
-		let (pair, _) = TPair::from_phrase(&phrase, Some(password))
+		// keys generated by earlier versions used the password of the store as phrase password.
+		let pair = TPair::from_phrase(&suri, self.password())
+			.map(|(pair, _)| pair)
 			.ok().ok_or(Error::InvalidPhrase)?;
 		if &pair.public() != public {
 			return Err(Error::InvalidPassword);
@@ -121,16 +166,397 @@ impl Store {
 
 	/// Get public keys of all stored keys.
 	pub fn contents<TPublic: Public>(&self) -> Result<Vec<TPublic>> {
-		let mut public_keys: Vec<TPublic> = self.additional.keys()
-			.filter_map(|(ty, public)| {
-				if *ty != TPublic::KEY_TYPE {
-					return None
-				}
-				Some(TPublic::from_slice(public))
-			})
+		Ok(self.public_keys(TPublic::KEY_TYPE)?
+			.iter()
+			.map(|public| TPublic::from_slice(public))
+			.collect())
+	}
+}
+
+/// A signature, or another result of a secret key, that is being computed by a `CryptoStore`.
+pub type SignFuture<T> = Box<dyn Future<Item=T, Error=Error> + Send>;
+
+/// The messages of a VRF transcript.
+///
+/// A `merlin::Transcript` can't be sent to a remote signer, so the messages committed to it are
+/// passed instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VRFTranscriptData {
+	/// The label the transcript is created with.
+	pub label: &'static [u8],
+	/// The labelled messages committed to the transcript, in order.
+	pub items: Vec<(&'static [u8], Vec<u8>)>,
+}
+
+impl VRFTranscriptData {
+	/// Create the transcript.
+	#[allow(deprecated)]
+	pub fn to_transcript(&self) -> Transcript {
+		let mut transcript = Transcript::new(self.label);
+		for (label, message) in &self.items {
+			transcript.commit_bytes(*label, message);
+		}
+		transcript
+	}
+}
+
+/// The output of a VRF evaluation, together with the proof that it was computed correctly.
+#[derive(Debug, Clone)]
+pub struct VRFSignature {
+	/// The VRF output.
+	pub output: VRFOutput,
+	/// The VRF proof.
+	pub proof: VRFProof,
+}
+
+/// Signing with stored keys, indexed by key type and raw public key.
+///
+/// Unlike `BareCryptoStore`, this never exposes the secret key, so that it can be implemented by
+/// signers that keep the keys outside of the node.
+pub trait CryptoStore: Send + Sync {
+	/// Returns the raw public keys of all keys of the given type.
+	fn public_keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>>;
+
+	/// Sign `message` with the key of the given type and raw public key.
+	///
+	/// Resolves to the raw signature.
+	fn sign(&self, key_type: KeyTypeId, public: &[u8], message: &[u8]) -> SignFuture<Vec<u8>>;
+
+	/// Evaluate the VRF of the sr25519 key `public` on the given transcript.
+	fn sr25519_vrf_sign(
+		&self,
+		public: &sr25519::Public,
+		transcript: VRFTranscriptData,
+	) -> SignFuture<VRFSignature>;
+}
+
+/// Evaluate the VRF of `pair` on the given transcript.
+fn vrf_sign_with_pair(pair: &sr25519::Pair, transcript: &VRFTranscriptData) -> VRFSignature {
+	let keypair: &schnorrkel::Keypair = pair.as_ref();
+	let (inout, proof, _) = keypair.vrf_sign(transcript.to_transcript());
+	VRFSignature { output: inout.to_output(), proof }
+}
+
+/// Sign `message` with a key loaded from `store`.
+fn sign_with_store(
+	store: &dyn BareCryptoStore,
+	key_type: KeyTypeId,
+	public: &[u8],
+	message: &[u8],
+) -> Result<Vec<u8>> {
+	match key_type {
+		key_types::ED25519 => {
+			let pair = store.load::<ed25519::Pair>(&ed25519::Public::from_slice(public))?;
+			Ok(pair.sign(message).as_ref().to_vec())
+		}
+		key_types::SR25519 => {
+			let pair = store.load::<sr25519::Pair>(&sr25519::Public::from_slice(public))?;
+			Ok(pair.sign(message).as_ref().to_vec())
+		}
+		key_types::ECDSA => {
+			let pair = store.load::<ecdsa::Pair>(&ecdsa::Public::from_slice(public))?;
+			Ok(pair.sign(message).as_ref().to_vec())
+		}
+		key_types::BLS12_381 => {
+			let pair = store.load::<bls::Pair>(&bls::Public::from_slice(public))?;
+			Ok(pair.sign(message).as_ref().to_vec())
+		}
+		_ => Err(Error::UnsupportedKeyType),
+	}
+}
+
+impl CryptoStore for Arc<dyn BareCryptoStore> {
+	fn public_keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>> {
+		BareCryptoStore::public_keys(&**self, key_type)
+	}
+
+	fn sign(&self, key_type: KeyTypeId, public: &[u8], message: &[u8]) -> SignFuture<Vec<u8>> {
+		Box::new(future::result(sign_with_store(&**self, key_type, public, message)))
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		public: &sr25519::Public,
+		transcript: VRFTranscriptData,
+	) -> SignFuture<VRFSignature> {
+		let signature = self.load::<sr25519::Pair>(public)
+			.map(|pair| vrf_sign_with_pair(&pair, &transcript));
+		Box::new(future::result(signature))
+	}
+}
+
+/// Generate a new key of type `id`, derived from `seed` if given.
+fn generate_new<TPair: Pair>(
+	store: &dyn BareCryptoStore,
+	id: KeyTypeId,
+	seed: Option<&str>,
+) -> std::result::Result<TPair::Public, String> {
+	let pair = match seed {
+		Some(seed) => {
+			let pair = TPair::from_string(seed, None).map_err(|_| Error::InvalidSeed.to_string())?;
+			store.insert_ephemeral(id, pair.public().as_slice(), seed).map_err(|e| e.to_string())?;
+			pair
+		}
+		None => {
+			let (pair, phrase, _) = TPair::generate_with_phrase(None);
+			store.insert_secret_uri(id, pair.public().as_slice(), &phrase).map_err(|e| e.to_string())?;
+			pair
+		}
+	};
+	Ok(pair.public())
+}
+
+impl KeyStore for Arc<dyn BareCryptoStore> {
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, String> {
+		generate_new::<ed25519::Pair>(&**self, id, seed)
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, String> {
+		generate_new::<sr25519::Pair>(&**self, id, seed)
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, String> {
+		generate_new::<ecdsa::Pair>(&**self, id, seed)
+	}
+
+	fn ecdsa_sign(
+		&self,
+		public: &ecdsa::Public,
+		message: &[u8],
+	) -> std::result::Result<ecdsa::Signature, String> {
+		self.load::<ecdsa::Pair>(public)
+			.map(|pair| pair.sign(message))
+			.map_err(|e| e.to_string())
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		public_keys.iter().all(|(public, id)| self.secret_uri(*id, public).is_ok())
+	}
+}
+
+/// A `CryptoStore` holding a single key pair.
+pub struct PairStore<P: Pair>(P);
+
+impl<P: Pair> PairStore<P> {
+	/// Create a store holding `pair`.
+	pub fn new(pair: P) -> Self {
+		PairStore(pair)
+	}
+}
+
+impl<P: Pair> CryptoStore for PairStore<P> {
+	fn public_keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>> {
+		if key_type == P::KEY_TYPE {
+			Ok(vec![self.0.public().as_slice().to_vec()])
+		} else {
+			Ok(Vec::new())
+		}
+	}
+
+	fn sign(&self, key_type: KeyTypeId, public: &[u8], message: &[u8]) -> SignFuture<Vec<u8>> {
+		if key_type != P::KEY_TYPE || self.0.public().as_slice() != public {
+			return Box::new(future::err(Error::KeyNotFound));
+		}
+		Box::new(future::ok(self.0.sign(message).as_ref().to_vec()))
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		public: &sr25519::Public,
+		transcript: VRFTranscriptData,
+	) -> SignFuture<VRFSignature> {
+		match (&self.0 as &dyn Any).downcast_ref::<sr25519::Pair>() {
+			Some(pair) if &pair.public() == public =>
+				Box::new(future::ok(vrf_sign_with_pair(pair, &transcript))),
+			_ => Box::new(future::err(Error::KeyNotFound)),
+		}
+	}
+}
+
+/// A key of type `P`, whose signatures are created by a `CryptoStore`.
+pub struct Signer<P: Pair> {
+	store: Arc<dyn CryptoStore>,
+	public: P::Public,
+	_marker: PhantomData<P>,
+}
+
+impl<P: Pair> Clone for Signer<P> {
+	fn clone(&self) -> Self {
+		Signer {
+			store: self.store.clone(),
+			public: self.public.clone(),
+			_marker: PhantomData,
+		}
+	}
+}
+
+impl<P: Pair> Signer<P> {
+	/// Create a signer for the key with the given public key, kept in `store`.
+	pub fn new(store: Arc<dyn CryptoStore>, public: P::Public) -> Self {
+		Signer { store, public, _marker: PhantomData }
+	}
+
+	/// Create a signer that signs with the given key pair.
+	pub fn from_pair(pair: P) -> Self {
+		let public = pair.public();
+		Self::new(Arc::new(PairStore::new(pair)), public)
+	}
+
+	/// The public key of the signer.
+	pub fn public(&self) -> P::Public {
+		self.public.clone()
+	}
+
+	/// Sign the given message, resolving to the raw signature.
+	pub fn sign_raw(&self, message: &[u8]) -> SignFuture<Vec<u8>> {
+		self.store.sign(P::KEY_TYPE, self.public.as_slice(), message)
+	}
+}
+
+impl<P: Pair> Signer<P> where P::Signature: Default + AsMut<[u8]> + Send {
+	/// Sign the given message.
+	pub fn sign(&self, message: &[u8]) -> SignFuture<P::Signature> {
+		Box::new(self.sign_raw(message).and_then(|raw| {
+			let mut signature = P::Signature::default();
+			if raw.len() != signature.as_mut().len() {
+				return Err(Error::InvalidSignature);
+			}
+			signature.as_mut().copy_from_slice(&raw);
+			Ok(signature)
+		}))
+	}
+}
+
+impl Signer<sr25519::Pair> {
+	/// Evaluate the VRF of the key on the given transcript.
+	pub fn vrf_sign(&self, transcript: VRFTranscriptData) -> SignFuture<VRFSignature> {
+		self.store.sr25519_vrf_sign(&self.public, transcript)
+	}
+}
+
+/// Contents of an encrypted key file.
+#[derive(Serialize, Deserialize)]
+struct EncryptedKey {
+	/// Hex encoded PBKDF2 salt.
+	salt: String,
+	/// Hex encoded nonce.
+	nonce: String,
+	/// Hex encoded secret URI, followed by the authentication tag.
+	ciphertext: String,
+}
+
+/// Contents of a key file, either a plain secret URI or an encrypted one.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum KeyFile {
+	Plain(String),
+	Encrypted(EncryptedKey),
+}
+
+fn encryption_key(password: &str, salt: &[u8]) -> [u8; 32] {
+	let mut key = [0u8; 32];
+	let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).expect("iterations is a non zero constant; qed");
+	pbkdf2::derive(&digest::SHA256, iterations, salt, password.as_bytes(), &mut key);
+	key
+}
+
+impl EncryptedKey {
+	fn encrypt(suri: &str, password: &str) -> Result<Self> {
+		let rng = SystemRandom::new();
+		let mut salt = [0u8; 16];
+		let mut nonce = [0u8; 12];
+		rng.fill(&mut salt).map_err(|_| Error::Encryption)?;
+		rng.fill(&mut nonce).map_err(|_| Error::Encryption)?;
+
+		let algorithm = &aead::CHACHA20_POLY1305;
+		let key = aead::SealingKey::new(algorithm, &encryption_key(password, &salt))
+			.map_err(|_| Error::Encryption)?;
+		let mut in_out = suri.as_bytes().to_vec();
+		in_out.resize(in_out.len() + algorithm.tag_len(), 0);
+		let len = aead::seal_in_place(
+			&key,
+			aead::Nonce::assume_unique_for_key(nonce),
+			aead::Aad::empty(),
+			&mut in_out,
+			algorithm.tag_len(),
+		).map_err(|_| Error::Encryption)?;
+		in_out.truncate(len);
+
+		Ok(EncryptedKey {
+			salt: hex::encode(salt),
+			nonce: hex::encode(nonce),
+			ciphertext: hex::encode(in_out),
+		})
+	}
+
+	fn decrypt(&self, password: &str) -> Result<String> {
+		let salt = hex::decode(&self.salt).map_err(|_| Error::InvalidSeed)?;
+		let nonce = hex::decode(&self.nonce).map_err(|_| Error::InvalidSeed)?;
+		let mut in_out = hex::decode(&self.ciphertext).map_err(|_| Error::InvalidSeed)?;
+		if nonce.len() != 12 {
+			return Err(Error::InvalidSeed);
+		}
+		let mut nonce_bytes = [0u8; 12];
+		nonce_bytes.copy_from_slice(&nonce);
+
+		let key = aead::OpeningKey::new(&aead::CHACHA20_POLY1305, &encryption_key(password, &salt))
+			.map_err(|_| Error::InvalidPassword)?;
+		let plain = aead::open_in_place(
+			&key,
+			aead::Nonce::assume_unique_for_key(nonce_bytes),
+			aead::Aad::empty(),
+			0,
+			&mut in_out,
+		).map_err(|_| Error::InvalidPassword)?;
+		String::from_utf8(plain.to_vec()).map_err(|_| Error::InvalidSeed)
+	}
+}
+
+/// Key store, keeping every key in its own file.
+pub struct Store {
+	path: PathBuf,
+	password: Option<Protected<String>>,
+	additional: RwLock<HashMap<(KeyTypeId, Vec<u8>), String>>,
+}
+
+impl Store {
+	/// Open the store at the given path, creating it if necessary.
+	///
+	/// Key files are encrypted with `password`, if given.
+	pub fn open(path: PathBuf, password: Option<Protected<String>>) -> Result<Self> {
+		fs::create_dir_all(&path)?;
+		Ok(Store { path, password, additional: RwLock::new(HashMap::new()) })
+	}
+
+	fn key_file_path(&self, key_type: KeyTypeId, public: &[u8]) -> PathBuf {
+		let mut buf = self.path.clone();
+		let key_type = hex::encode(key_type.to_le_bytes());
+		let key = hex::encode(public);
+		buf.push(key_type + key.as_str());
+		buf
+	}
+}
+
+impl BareCryptoStore for Store {
+	fn public_keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>> {
+		let mut public_keys: Vec<Vec<u8>> = self.additional.read().keys()
+			.filter(|(ty, _)| *ty == key_type)
+			.map(|(_, public)| public.clone())
 			.collect();
 
-		let key_type: [u8; 4] = TPublic::KEY_TYPE.to_le_bytes();
+		let key_type: [u8; 4] = key_type.to_le_bytes();
 		for entry in fs::read_dir(&self.path)? {
 			let entry = entry?;
 			let path = entry.path();
@@ -138,10 +564,12 @@ impl Store {
 			// skip directories and non-unicode file names (hex is unicode)
 			if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
 				match hex::decode(name) {
-					Ok(ref hex) => {
+					Ok(ref hex) if hex.len() > 4 => {
 						if hex[0..4] != key_type { continue	}
-						let public = TPublic::from_slice(&hex[4..]);
-						public_keys.push(public);
+						let public = hex[4..].to_vec();
+						if !public_keys.contains(&public) {
+							public_keys.push(public);
+						}
 					}
 					_ => continue,
 				}
@@ -151,13 +579,85 @@ impl Store {
 		Ok(public_keys)
 	}
 
-	fn key_file_path<TPair: Pair>(&self, public: &TPair::Public) -> PathBuf {
-		let mut buf = self.path.clone();
-		let bytes: [u8; 4] = TPair::KEY_TYPE.to_le_bytes();
-		let key_type = hex::encode(bytes);
-		let key = hex::encode(public.as_slice());
-		buf.push(key_type + key.as_str());
-		buf
+	fn secret_uri(&self, key_type: KeyTypeId, public: &[u8]) -> Result<String> {
+		if let Some(suri) = self.additional.read().get(&(key_type, public.to_vec())) {
+			return Ok(suri.clone());
+		}
+
+		let path = self.key_file_path(key_type, public);
+		let file = match File::open(path) {
+			Ok(file) => file,
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Err(Error::KeyNotFound),
+			Err(e) => return Err(e.into()),
+		};
+		match serde_json::from_reader(&file)? {
+			KeyFile::Plain(suri) => Ok(suri),
+			KeyFile::Encrypted(encrypted) => match self.password() {
+				Some(password) => encrypted.decrypt(password),
+				None => Err(Error::InvalidPassword),
+			},
+		}
+	}
+
+	fn insert_secret_uri(&self, key_type: KeyTypeId, public: &[u8], suri: &str) -> Result<()> {
+		let contents = match self.password() {
+			Some(password) => KeyFile::Encrypted(EncryptedKey::encrypt(suri, password)?),
+			None => KeyFile::Plain(suri.to_owned()),
+		};
+		let mut file = File::create(self.key_file_path(key_type, public))?;
+		serde_json::to_writer(&file, &contents)?;
+		file.flush()?;
+		Ok(())
+	}
+
+	fn insert_ephemeral(&self, key_type: KeyTypeId, public: &[u8], suri: &str) -> Result<()> {
+		self.additional.write().insert((key_type, public.to_vec()), suri.to_owned());
+		Ok(())
+	}
+
+	fn password(&self) -> Option<&str> {
+		self.password.as_ref()
+			.map(|password| password.as_ref().as_str())
+			.filter(|password| !password.is_empty())
+	}
+}
+
+/// Key store that keeps all keys in memory only.
+#[derive(Default)]
+pub struct MemoryStore {
+	keys: RwLock<HashMap<(KeyTypeId, Vec<u8>), String>>,
+}
+
+impl MemoryStore {
+	/// Create a new, empty store.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+impl BareCryptoStore for MemoryStore {
+	fn public_keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>> {
+		Ok(self.keys.read().keys()
+			.filter(|(ty, _)| *ty == key_type)
+			.map(|(_, public)| public.clone())
+			.collect())
+	}
+
+	fn secret_uri(&self, key_type: KeyTypeId, public: &[u8]) -> Result<String> {
+		self.keys.read().get(&(key_type, public.to_vec())).cloned().ok_or(Error::KeyNotFound)
+	}
+
+	fn insert_secret_uri(&self, key_type: KeyTypeId, public: &[u8], suri: &str) -> Result<()> {
+		self.keys.write().insert((key_type, public.to_vec()), suri.to_owned());
+		Ok(())
+	}
+
+	fn insert_ephemeral(&self, key_type: KeyTypeId, public: &[u8], suri: &str) -> Result<()> {
+		self.insert_secret_uri(key_type, public, suri)
+	}
+
+	fn password(&self) -> Option<&str> {
+		None
 	}
 }
 
@@ -165,20 +665,19 @@ impl Store {
 mod tests {
 	use super::*;
 	use tempdir::TempDir;
-	use substrate_primitives::ed25519;
-	use substrate_primitives::crypto::Ss58Codec;
+	use substrate_primitives::{ed25519, sr25519};
+	use substrate_primitives::crypto::{Ss58Codec, TypedKey};
 
 	#[test]
 	fn basic_store() {
 		let temp_dir = TempDir::new("keystore").unwrap();
-		let store = Store::open(temp_dir.path().to_owned()).unwrap();
+		let store = Store::open(temp_dir.path().to_owned(), None).unwrap();
+		let store = &store as &dyn BareCryptoStore;
 
 		assert!(store.contents::<ed25519::Public>().unwrap().is_empty());
 
-		let key: ed25519::Pair = store.generate("thepassword").unwrap();
-		let key2: ed25519::Pair = store.load(&key.public(), "thepassword").unwrap();
-
-		assert!(store.load::<ed25519::Pair>(&key.public(), "notthepassword").is_err());
+		let key: ed25519::Pair = store.generate().unwrap();
+		let key2: ed25519::Pair = store.load(&key.public()).unwrap();
 
 		assert_eq!(key.public(), key2.public());
 
@@ -188,11 +687,146 @@ mod tests {
 	#[test]
 	fn test_generate_from_seed() {
 		let temp_dir = TempDir::new("keystore").unwrap();
-		let mut store = Store::open(temp_dir.path().to_owned()).unwrap();
+		let store = Store::open(temp_dir.path().to_owned(), None).unwrap();
+		let store = &store as &dyn BareCryptoStore;
 
 		let pair: ed25519::Pair = store
 			.generate_from_seed("0x3d97c819d68f9bafa7d6e79cb991eebcd77d966c5334c0b94d9e1fa7ad0869dc")
 			.unwrap();
 		assert_eq!("5DKUrgFqCPV8iAXx9sjy1nyBygQCeiUYRFWurZGhnrn3HJCA", pair.public().to_ss58check());
+
+		// ephemeral keys are not written to disk.
+		let reopened = Store::open(temp_dir.path().to_owned(), None).unwrap();
+		assert!((&reopened as &dyn BareCryptoStore).contents::<ed25519::Public>().unwrap().is_empty());
+	}
+
+	#[test]
+	fn inserted_keys_can_be_loaded() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path().to_owned(), None).unwrap();
+		let store = &store as &dyn BareCryptoStore;
+
+		let pair: sr25519::Pair = store.insert("//Alice").unwrap();
+		assert_eq!(store.contents::<sr25519::Public>().unwrap(), vec![pair.public()]);
+		assert!(store.contents::<ed25519::Public>().unwrap().is_empty());
+
+		let loaded: sr25519::Pair = store.load(&pair.public()).unwrap();
+		assert_eq!(loaded.public(), pair.public());
+	}
+
+	#[test]
+	fn key_files_are_encrypted_with_password() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let open = |password: Option<&str>| Store::open(
+			temp_dir.path().to_owned(),
+			password.map(|password| password.to_owned().into()),
+		).unwrap();
+
+		let store = open(Some("thepassword"));
+		let key: sr25519::Pair = (&store as &dyn BareCryptoStore).generate().unwrap();
+
+		let path = store.key_file_path(sr25519::Public::KEY_TYPE, key.public().as_slice());
+		let contents = fs::read_to_string(path).unwrap();
+		assert!(contents.contains("ciphertext"));
+
+		let store = open(Some("thepassword"));
+		let loaded: sr25519::Pair = (&store as &dyn BareCryptoStore).load(&key.public()).unwrap();
+		assert_eq!(loaded.public(), key.public());
+
+		for password in vec![None, Some("notthepassword")] {
+			let store = open(password);
+			let result = (&store as &dyn BareCryptoStore).load::<sr25519::Pair>(&key.public());
+			assert!(match result { Err(Error::InvalidPassword) => true, _ => false });
+		}
+	}
+
+	#[test]
+	fn stores_sign_with_loaded_keys() {
+		let store: Arc<dyn BareCryptoStore> = Arc::new(MemoryStore::new());
+		let alice: sr25519::Pair = store.insert("//Alice").unwrap();
+		let signer = Signer::<sr25519::Pair>::new(Arc::new(store.clone()), alice.public());
+
+		let signature = signer.sign(b"hello").wait().unwrap();
+		assert!(sr25519::Pair::verify(&signature, b"hello", &alice.public()));
+
+		let unknown = Signer::<ed25519::Pair>::new(Arc::new(store), Default::default());
+		assert!(unknown.sign(b"hello").wait().is_err());
+	}
+
+	#[test]
+	fn signer_from_pair_signs() {
+		let pair = ed25519::Pair::from_string("//Bob", None).unwrap();
+		let signer = Signer::from_pair(pair.clone());
+
+		assert_eq!(signer.public(), pair.public());
+		assert_eq!(signer.sign(b"hello").wait().unwrap(), pair.sign(b"hello"));
+	}
+
+	#[test]
+	fn vrf_signatures_verify() {
+		let transcript = VRFTranscriptData {
+			label: b"test",
+			items: vec![(&b"slot"[..], vec![1, 2, 3])],
+		};
+		let store: Arc<dyn BareCryptoStore> = Arc::new(MemoryStore::new());
+		let alice: sr25519::Pair = store.insert("//Alice").unwrap();
+		let bob = sr25519::Pair::from_string("//Bob", None).unwrap();
+
+		let signers = vec![
+			Signer::<sr25519::Pair>::new(Arc::new(store.clone()), alice.public()),
+			Signer::from_pair(bob.clone()),
+		];
+		for signer in signers {
+			let signature = signer.vrf_sign(transcript.clone()).wait().unwrap();
+			let public = schnorrkel::PublicKey::from_bytes(signer.public().as_slice()).unwrap();
+			assert!(public.vrf_verify(transcript.to_transcript(), &signature.output, &signature.proof).is_ok());
+		}
+
+		let unknown = Signer::<sr25519::Pair>::new(Arc::new(store), bob.public());
+		assert!(unknown.vrf_sign(transcript).wait().is_err());
+	}
+
+	#[test]
+	fn generated_keys_are_reported() {
+		let store: Arc<dyn BareCryptoStore> = Arc::new(MemoryStore::new());
+
+		let generated = store.sr25519_generate_new(key_types::SR25519, None).unwrap();
+		let seeded = store.ed25519_generate_new(key_types::ED25519, Some("//Alice")).unwrap();
+		assert_eq!(seeded, ed25519::Pair::from_string("//Alice", None).unwrap().public());
+
+		assert!(store.has_keys(&[
+			(generated.as_slice().to_vec(), key_types::SR25519),
+			(seeded.as_slice().to_vec(), key_types::ED25519),
+		]));
+		assert!(!store.has_keys(&[(seeded.as_slice().to_vec(), key_types::SR25519)]));
+	}
+
+	#[test]
+	fn ecdsa_keys_sign() {
+		let store: Arc<dyn BareCryptoStore> = Arc::new(MemoryStore::new());
+
+		let public = store.ecdsa_generate_new(key_types::ECDSA, None).unwrap();
+		assert!(store.has_keys(&[(public.as_slice().to_vec(), key_types::ECDSA)]));
+
+		let signature = store.ecdsa_sign(&public, b"hello").unwrap();
+		assert!(ecdsa::Pair::verify(&signature, b"hello", &public));
+		assert!(store.ecdsa_sign(&Default::default(), b"hello").is_err());
+
+		let signer = Signer::<ecdsa::Pair>::new(Arc::new(store), public.clone());
+		assert!(ecdsa::Pair::verify(&signer.sign(b"hello").wait().unwrap(), b"hello", &public));
+	}
+
+	#[test]
+	fn memory_store_keeps_keys() {
+		let store = MemoryStore::new();
+		let store = &store as &dyn BareCryptoStore;
+
+		let alice: sr25519::Pair = store.insert("//Alice").unwrap();
+		let generated: ed25519::Pair = store.generate().unwrap();
+
+		assert_eq!(store.contents::<sr25519::Public>().unwrap(), vec![alice.public()]);
+		assert_eq!(store.contents::<ed25519::Public>().unwrap(), vec![generated.public()]);
+		assert_eq!(store.load::<ed25519::Pair>(&generated.public()).unwrap().public(), generated.public());
+		assert!(store.load::<ed25519::Pair>(&Default::default()).is_err());
 	}
 }
@@ -0,0 +1,353 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Signing through an external signer process.
+//!
+//! The signer is reached over a local TCP or Unix socket. Every request is a single line of
+//! JSON-RPC 2.0, answered by a single line:
+//!
+//! - `signer_publicKeys(keyType)` returns the hex encoded public keys of the given type.
+//! - `signer_sign(keyType, public, message)` returns the hex encoded signature of the hex
+//!   encoded `message`, made with the key `public`.
+//! - `signer_sr25519VrfSign(public, label, items)` evaluates the VRF of the sr25519 key `public`
+//!   on the transcript with the given `label` and `[label, message]` items, and returns an object
+//!   with the hex encoded `output` and `proof`.
+//!
+//! The requests are made from a dedicated thread, so that waiting for the signer never blocks
+//! the caller.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use futures::{Future, future, sync::oneshot};
+use parking_lot::Mutex;
+use schnorrkel::vrf::{VRFOutput, VRFProof};
+use serde_json::{json, Value};
+use substrate_primitives::{sr25519, crypto::{KeyTypeId, Public}};
+
+use crate::{CryptoStore, Error, Result, SignFuture, VRFSignature, VRFTranscriptData};
+
+/// Time to wait for the signer to answer a request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Address of a remote signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteAddress {
+	/// A TCP socket.
+	Tcp(SocketAddr),
+	/// A Unix domain socket.
+	#[cfg(unix)]
+	Unix(PathBuf),
+}
+
+impl FromStr for RemoteAddress {
+	type Err = String;
+
+	/// Parses either `host:port` or `unix:<path>`.
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		if s.starts_with("unix:") {
+			#[cfg(unix)]
+			return Ok(RemoteAddress::Unix(PathBuf::from(&s["unix:".len()..])));
+			#[cfg(not(unix))]
+			return Err("Unix sockets are not supported on this platform".into());
+		}
+
+		s.parse()
+			.map(RemoteAddress::Tcp)
+			.map_err(|_| format!("Invalid remote signer address: {}", s))
+	}
+}
+
+trait Connection: io::Read + io::Write {}
+
+impl<T: io::Read + io::Write> Connection for T {}
+
+/// A request to the signer, whose result is sent back through `response`.
+struct Request {
+	method: &'static str,
+	params: Value,
+	response: oneshot::Sender<Result<Value>>,
+}
+
+/// A `CryptoStore` that delegates signing to an external signer.
+pub struct RemoteSigner {
+	requests: Mutex<mpsc::Sender<Request>>,
+}
+
+impl RemoteSigner {
+	/// Create a signer talking to the signer listening on `address`.
+	///
+	/// This spawns the thread making the requests, which stops when the signer is dropped.
+	pub fn new(address: RemoteAddress) -> io::Result<Self> {
+		let (sender, receiver) = mpsc::channel::<Request>();
+		let mut client = Client { address, next_id: 1 };
+		thread::Builder::new()
+			.name("remote-signer".into())
+			.spawn(move || for request in receiver {
+				let _ = request.response.send(client.request(request.method, request.params));
+			})?;
+
+		Ok(RemoteSigner { requests: Mutex::new(sender) })
+	}
+
+	/// Send a request to the signer, resolving to the `result` of its response.
+	fn request(&self, method: &'static str, params: Value) -> SignFuture<Value> {
+		let (response, receiver) = oneshot::channel();
+		if self.requests.lock().send(Request { method, params, response }).is_err() {
+			return Box::new(future::err(Error::Remote("The signer thread has stopped".into())));
+		}
+		Box::new(receiver.then(|result| match result {
+			Ok(result) => result,
+			Err(_) => Err(Error::Remote("The signer thread has stopped".into())),
+		}))
+	}
+}
+
+/// The connection details of the signer, used from the thread making the requests.
+struct Client {
+	address: RemoteAddress,
+	next_id: u64,
+}
+
+impl Client {
+	fn connect(&self) -> io::Result<Box<dyn Connection>> {
+		match self.address {
+			RemoteAddress::Tcp(ref address) => {
+				let stream = TcpStream::connect_timeout(address, REQUEST_TIMEOUT)?;
+				stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+				stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+				Ok(Box::new(stream))
+			}
+			#[cfg(unix)]
+			RemoteAddress::Unix(ref path) => {
+				let stream = std::os::unix::net::UnixStream::connect(path)?;
+				stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+				stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+				Ok(Box::new(stream))
+			}
+		}
+	}
+
+	/// Send a request to the signer and return the `result` of its response.
+	fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+		let id = self.next_id;
+		self.next_id += 1;
+		let request = json!({
+			"jsonrpc": "2.0",
+			"id": id,
+			"method": method,
+			"params": params,
+		});
+
+		let mut connection = self.connect()?;
+		let mut line = serde_json::to_string(&request)?;
+		line.push('\n');
+		connection.write_all(line.as_bytes())?;
+		connection.flush()?;
+
+		let mut response = String::new();
+		BufReader::new(connection).read_line(&mut response)?;
+		let mut response: Value = serde_json::from_str(&response)?;
+
+		if response["id"] != json!(id) {
+			return Err(Error::Remote("Response does not match the request".into()));
+		}
+		if let Some(error) = response.get("error") {
+			let message = error["message"].as_str().unwrap_or("Unknown error");
+			return Err(Error::Remote(message.into()));
+		}
+		match response.get_mut("result") {
+			Some(result) => Ok(result.take()),
+			None => Err(Error::Remote("Response without result".into())),
+		}
+	}
+}
+
+fn decode_hex(value: &Value) -> Result<Vec<u8>> {
+	let hex = value.as_str().ok_or_else(|| Error::Remote("Expected a hex string".into()))?;
+	let hex = if hex.starts_with("0x") { &hex[2..] } else { hex };
+	hex::decode(hex).map_err(|_| Error::Remote("Invalid hex string".into()))
+}
+
+fn encode_hex(data: &[u8]) -> String {
+	format!("0x{}", hex::encode(data))
+}
+
+impl CryptoStore for RemoteSigner {
+	fn public_keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>> {
+		match self.request("signer_publicKeys", json!([key_type])).wait()? {
+			Value::Array(keys) => keys.iter().map(decode_hex).collect(),
+			_ => Err(Error::Remote("Expected a list of public keys".into())),
+		}
+	}
+
+	fn sign(&self, key_type: KeyTypeId, public: &[u8], message: &[u8]) -> SignFuture<Vec<u8>> {
+		let signature = self.request(
+			"signer_sign",
+			json!([key_type, encode_hex(public), encode_hex(message)]),
+		);
+		Box::new(signature.and_then(|signature| decode_hex(&signature)))
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		public: &sr25519::Public,
+		transcript: VRFTranscriptData,
+	) -> SignFuture<VRFSignature> {
+		let items = transcript.items.iter()
+			.map(|(label, message)| json!([encode_hex(label), encode_hex(message)]))
+			.collect::<Vec<_>>();
+		let signature = self.request(
+			"signer_sr25519VrfSign",
+			json!([encode_hex(public.as_slice()), encode_hex(transcript.label), items]),
+		);
+		Box::new(signature.and_then(|signature| {
+			let output = VRFOutput::from_bytes(&decode_hex(&signature["output"])?)
+				.map_err(|_| Error::Remote("Invalid VRF output".into()))?;
+			let proof = VRFProof::from_bytes(&decode_hex(&signature["proof"])?)
+				.map_err(|_| Error::Remote("Invalid VRF proof".into()))?;
+			Ok(VRFSignature { output, proof })
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::TcpListener;
+	use std::sync::Arc;
+	use substrate_primitives::{ed25519, crypto::{Pair, TypedKey}};
+	use crate::{BareCryptoStore, MemoryStore, Signer};
+
+	/// Serve `requests` signer requests with the keys of `store`.
+	fn serve(store: Arc<dyn BareCryptoStore>, requests: usize) -> SocketAddr {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap();
+
+		thread::spawn(move || {
+			for stream in listener.incoming().take(requests) {
+				let mut stream = stream.unwrap();
+				let mut line = String::new();
+				BufReader::new(&mut stream).read_line(&mut line).unwrap();
+				let request: Value = serde_json::from_str(&line).unwrap();
+				let params = &request["params"];
+
+				let result = match request["method"].as_str().unwrap() {
+					"signer_publicKeys" => {
+						let key_type = params[0].as_u64().unwrap() as KeyTypeId;
+						CryptoStore::public_keys(&store, key_type)
+							.map(|keys| json!(keys.iter().map(|key| encode_hex(key)).collect::<Vec<_>>()))
+					}
+					"signer_sr25519VrfSign" => {
+						// Only transcripts with static labels can be served here.
+						assert_eq!(decode_hex(&params[1]).unwrap(), b"test".to_vec());
+						let transcript = VRFTranscriptData {
+							label: b"test",
+							items: vec![(&b"slot"[..], decode_hex(&params[2][0][1]).unwrap())],
+						};
+						let public = sr25519::Public::from_slice(&decode_hex(&params[0]).unwrap());
+						store.sr25519_vrf_sign(&public, transcript).wait().map(|signature| json!({
+							"output": encode_hex(&signature.output.to_bytes()),
+							"proof": encode_hex(&signature.proof.to_bytes()),
+						}))
+					}
+					_ => {
+						let key_type = params[0].as_u64().unwrap() as KeyTypeId;
+						let public = decode_hex(&params[1]).unwrap();
+						let message = decode_hex(&params[2]).unwrap();
+						store.sign(key_type, &public, &message).wait().map(|signature| json!(encode_hex(&signature)))
+					}
+				};
+				let response = match result {
+					Ok(result) => json!({
+						"jsonrpc": "2.0",
+						"id": request["id"],
+						"result": result,
+					}),
+					Err(e) => json!({
+						"jsonrpc": "2.0",
+						"id": request["id"],
+						"error": { "code": 1, "message": e.to_string() },
+					}),
+				};
+				writeln!(stream, "{}", response).unwrap();
+			}
+		});
+
+		address
+	}
+
+	#[test]
+	fn parses_addresses() {
+		assert_eq!(
+			"127.0.0.1:9955".parse::<RemoteAddress>(),
+			Ok(RemoteAddress::Tcp("127.0.0.1:9955".parse().unwrap())),
+		);
+		#[cfg(unix)]
+		assert_eq!(
+			"unix:/tmp/signer.sock".parse::<RemoteAddress>(),
+			Ok(RemoteAddress::Unix("/tmp/signer.sock".into())),
+		);
+		assert!("signer".parse::<RemoteAddress>().is_err());
+	}
+
+	#[test]
+	fn signs_with_remote_keys() {
+		let store: Arc<dyn BareCryptoStore> = Arc::new(MemoryStore::new());
+		let pair: ed25519::Pair = store.insert("//Alice").unwrap();
+		let remote = Arc::new(RemoteSigner::new(RemoteAddress::Tcp(serve(store, 3))).unwrap());
+
+		assert_eq!(remote.public_keys(ed25519::Public::KEY_TYPE).unwrap(), vec![pair.public().as_slice().to_vec()]);
+
+		let signer = Signer::<ed25519::Pair>::new(remote.clone(), pair.public());
+		assert_eq!(signer.sign(b"hello").wait().unwrap(), pair.sign(b"hello"));
+
+		let unknown = Signer::<ed25519::Pair>::new(remote, Default::default());
+		assert!(match unknown.sign(b"hello").wait() { Err(Error::Remote(_)) => true, _ => false });
+	}
+
+	#[test]
+	fn evaluates_remote_vrf() {
+		let store: Arc<dyn BareCryptoStore> = Arc::new(MemoryStore::new());
+		let pair: sr25519::Pair = store.insert("//Alice").unwrap();
+		let remote = Arc::new(RemoteSigner::new(RemoteAddress::Tcp(serve(store, 1))).unwrap());
+
+		let transcript = VRFTranscriptData { label: b"test", items: vec![(&b"slot"[..], vec![1, 2, 3])] };
+		let signer = Signer::<sr25519::Pair>::new(remote, pair.public());
+		let signature = signer.vrf_sign(transcript.clone()).wait().unwrap();
+
+		let public = schnorrkel::PublicKey::from_bytes(pair.public().as_slice()).unwrap();
+		assert!(public.vrf_verify(transcript.to_transcript(), &signature.output, &signature.proof).is_ok());
+	}
+
+	#[test]
+	fn fails_when_the_signer_is_unreachable() {
+		let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+		let public = ed25519::Pair::from_string("//Alice", None).unwrap().public();
+		let remote = Signer::<ed25519::Pair>::new(
+			Arc::new(RemoteSigner::new(RemoteAddress::Tcp(address)).unwrap()),
+			public,
+		);
+
+		assert!(remote.sign(b"hello").wait().is_err());
+	}
+}
@@ -0,0 +1,365 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Logger of the Substrate node.
+//!
+//! Log records are filtered by directives of the form `target=level`, `level` or `target`,
+//! separated by commas. A record is logged if the directive with the longest target that is a
+//! prefix of the record's target (or the directive without a target) allows its level; a
+//! directive without a level allows everything. This is the syntax of `RUST_LOG` and `--log`.
+//!
+//! Unlike `env_logger`, directives can be added while the node is running with
+//! [`add_directives`](fn.add_directives.html) and dropped again with
+//! [`reset_directives`](fn.reset_directives.html), e.g. through the `system_addLogFilter` and
+//! `system_resetLogFilter` RPCs.
+//!
+//! Records are written to stderr, either as human-readable text or as one JSON object per line.
+
+use std::io::Write;
+
+use ansi_term::Colour;
+use lazy_static::lazy_static;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use parking_lot::RwLock;
+
+/// Directives that apply before `RUST_LOG` and the user-provided ones.
+const DEFAULT_DIRECTIVES: &str = "ws=off,hyper=warn,info";
+
+/// How log records are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// Human-readable text.
+	Text,
+	/// One JSON object per record, with the `timestamp`, `level`, `target`, `thread` and
+	/// `message` fields.
+	Json,
+}
+
+/// Whether text records are colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+	/// Color if stderr is a terminal.
+	Auto,
+	/// Always color, e.g. when the output is piped into a pager that supports colors.
+	Always,
+	/// Never color.
+	Never,
+}
+
+/// How log records are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Output {
+	/// The format of the records.
+	pub format: Format,
+	/// Whether text records are colored.
+	pub color: Color,
+}
+
+impl Default for Output {
+	fn default() -> Self {
+		Output { format: Format::Text, color: Color::Auto }
+	}
+}
+
+/// A single filtering directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Directive {
+	target: Option<String>,
+	level: LevelFilter,
+}
+
+/// Parse a comma-separated list of directives.
+fn parse_directives(spec: &str) -> Result<Vec<Directive>, String> {
+	spec.split(',')
+		.map(str::trim)
+		.filter(|directive| !directive.is_empty())
+		.map(|directive| {
+			let mut parts = directive.splitn(2, '=');
+			let first = parts.next().unwrap_or_default().trim();
+			match parts.next().map(str::trim) {
+				Some(level) => {
+					let level = level.parse()
+						.map_err(|_| format!("Invalid log level `{}` in `{}`", level, directive))?;
+					if first.is_empty() {
+						return Err(format!("Missing log target in `{}`", directive));
+					}
+					Ok(Directive { target: Some(first.into()), level })
+				}
+				None => Ok(match first.parse() {
+					Ok(level) => Directive { target: None, level },
+					Err(_) => Directive { target: Some(first.into()), level: LevelFilter::max() },
+				}),
+			}
+		})
+		.collect()
+}
+
+/// The directives in effect.
+#[derive(Debug, Default)]
+struct Filters {
+	/// The directives given at startup.
+	initial: Vec<Directive>,
+	/// The directives added since, which take precedence over the initial ones.
+	added: Vec<Directive>,
+}
+
+impl Filters {
+	/// The level allowed for `target`.
+	fn level(&self, target: &str) -> LevelFilter {
+		let mut best: Option<&Directive> = None;
+		for directive in self.initial.iter().chain(self.added.iter()) {
+			let len = match directive.target {
+				Some(ref t) if target.starts_with(&t[..]) => t.len(),
+				Some(_) => continue,
+				None => 0,
+			};
+			let best_len = best.and_then(|d| d.target.as_ref()).map_or(0, |t| t.len());
+			// later directives override earlier ones with the same target.
+			if best.is_none() || len >= best_len {
+				best = Some(directive);
+			}
+		}
+		best.map_or(LevelFilter::Off, |directive| directive.level)
+	}
+
+	/// Add directives which take precedence over the current ones, replacing the previously
+	/// added ones with the same target.
+	fn add(&mut self, directives: Vec<Directive>) {
+		for directive in directives {
+			self.added.retain(|added| added.target != directive.target);
+			self.added.push(directive);
+		}
+	}
+
+	/// The most verbose level allowed for any target.
+	fn max_level(&self) -> LevelFilter {
+		self.initial.iter().chain(self.added.iter())
+			.map(|directive| directive.level)
+			.max()
+			.unwrap_or(LevelFilter::Off)
+	}
+}
+
+lazy_static! {
+	static ref FILTERS: RwLock<Filters> = RwLock::new(Filters::default());
+}
+
+/// Add the given comma-separated directives, which take precedence over the current ones.
+///
+/// A directive replaces any previously added one for the same target.
+pub fn add_directives(directives: &str) -> Result<(), String> {
+	let directives = parse_directives(directives)?;
+	let mut filters = FILTERS.write();
+	filters.add(directives);
+	log::set_max_level(filters.max_level());
+	Ok(())
+}
+
+/// Drop the directives added since startup.
+pub fn reset_directives() {
+	let mut filters = FILTERS.write();
+	filters.added.clear();
+	log::set_max_level(filters.max_level());
+}
+
+/// Install the logger, filtering records with the default directives, `RUST_LOG` and then the
+/// given directives.
+///
+/// Invalid directives are reported on stderr and ignored.
+pub fn init(directives: &str, output: Output) -> Result<(), log::SetLoggerError> {
+	let mut initial = Vec::new();
+	let rust_log = std::env::var("RUST_LOG").unwrap_or_default();
+	for spec in &[DEFAULT_DIRECTIVES, &rust_log[..], directives] {
+		for directive in spec.split(',') {
+			match parse_directives(directive) {
+				Ok(parsed) => initial.extend(parsed),
+				Err(e) => eprintln!("Ignoring log directive: {}", e),
+			}
+		}
+	}
+
+	let max_level = {
+		let mut filters = FILTERS.write();
+		*filters = Filters { initial, added: Vec::new() };
+		filters.max_level()
+	};
+
+	let stderr_is_tty = atty::is(atty::Stream::Stderr);
+	let color = match output.color {
+		Color::Auto => stderr_is_tty,
+		Color::Always => true,
+		Color::Never => false,
+	};
+	log::set_boxed_logger(Box::new(Logger {
+		format: output.format,
+		color,
+		// duplicate INFO/WARN output to the console if stderr is redirected.
+		duplicate_to_stdout: !stderr_is_tty && atty::is(atty::Stream::Stdout),
+	}))?;
+	log::set_max_level(max_level);
+	Ok(())
+}
+
+struct Logger {
+	format: Format,
+	color: bool,
+	duplicate_to_stdout: bool,
+}
+
+impl Logger {
+	fn format_text(&self, record: &Record, now: &time::Tm) -> String {
+		let paint = |colour: Colour, text: String| if self.color {
+			colour.bold().paint(text).to_string()
+		} else {
+			text
+		};
+
+		let timestamp = time::strftime("%Y-%m-%d %H:%M:%S", now)
+			.expect("Error formatting log timestamp");
+		if log::max_level() <= LevelFilter::Info {
+			format!("{} {}", paint(Colour::Black, timestamp), record.args())
+		} else {
+			let name = std::thread::current()
+				.name()
+				.map_or_else(Default::default, |x| paint(Colour::Blue, x.into()));
+			let millis = (now.tm_nsec as f32 / 1000000.0).round() as usize;
+			let timestamp = format!("{}.{:03}", timestamp, millis);
+			format!(
+				"{} {} {} {}  {}",
+				paint(Colour::Black, timestamp),
+				name,
+				record.level(),
+				record.target(),
+				record.args()
+			)
+		}
+	}
+
+	fn format_json(&self, record: &Record, now: &time::Tm) -> String {
+		let timestamp = time::strftime("%Y-%m-%dT%H:%M:%S", &now.to_utc())
+			.expect("Error formatting log timestamp");
+		let millis = now.tm_nsec / 1_000_000;
+		serde_json::json!({
+			"timestamp": format!("{}.{:03}Z", timestamp, millis),
+			"level": record.level().to_string(),
+			"target": record.target(),
+			"thread": std::thread::current().name(),
+			"message": record.args().to_string(),
+		}).to_string()
+	}
+}
+
+impl Log for Logger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= FILTERS.read().level(metadata.target())
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+
+		let now = time::now();
+		let output = match self.format {
+			Format::Text => self.format_text(record, &now),
+			Format::Json => self.format_json(record, &now),
+		};
+
+		if self.duplicate_to_stdout && record.level() <= Level::Info {
+			println!("{}", output);
+		}
+		let _ = writeln!(std::io::stderr(), "{}", output);
+	}
+
+	fn flush(&self) {
+		let _ = std::io::stderr().flush();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn new_filters(initial: &str, added: &str) -> Filters {
+		Filters {
+			initial: parse_directives(initial).unwrap(),
+			added: parse_directives(added).unwrap(),
+		}
+	}
+
+	#[test]
+	fn parse_directives_works() {
+		assert_eq!(parse_directives(" info, sync=trace ,,afg").unwrap(), vec![
+			Directive { target: None, level: LevelFilter::Info },
+			Directive { target: Some("sync".into()), level: LevelFilter::Trace },
+			Directive { target: Some("afg".into()), level: LevelFilter::Trace },
+		]);
+		assert_eq!(parse_directives("").unwrap(), vec![]);
+		assert!(parse_directives("sync=loud").is_err());
+		assert!(parse_directives("=debug").is_err());
+	}
+
+	#[test]
+	fn longest_matching_target_wins() {
+		let filters = new_filters("ws=off,hyper=warn,info,sub-libp2p=debug,sub-libp2p::foo=error", "");
+		assert_eq!(filters.level("ws"), LevelFilter::Off);
+		assert_eq!(filters.level("hyper::server"), LevelFilter::Warn);
+		assert_eq!(filters.level("sync"), LevelFilter::Info);
+		assert_eq!(filters.level("sub-libp2p"), LevelFilter::Debug);
+		assert_eq!(filters.level("sub-libp2p::foo::bar"), LevelFilter::Error);
+		assert_eq!(filters.max_level(), LevelFilter::Debug);
+
+		assert_eq!(Filters::default().level("sync"), LevelFilter::Off);
+		assert_eq!(new_filters("sync=debug", "").level("afg"), LevelFilter::Off);
+	}
+
+	#[test]
+	fn added_directives_take_precedence() {
+		let filters = new_filters("info,sync=debug", "sync=warn,afg=trace,error");
+		assert_eq!(filters.level("sync"), LevelFilter::Warn);
+		assert_eq!(filters.level("afg"), LevelFilter::Trace);
+		assert_eq!(filters.level("txpool"), LevelFilter::Error);
+		assert_eq!(filters.max_level(), LevelFilter::Trace);
+	}
+
+	#[test]
+	fn add_and_reset_directives_work() {
+		*FILTERS.write() = new_filters("info", "");
+		assert!(add_directives("sync=foo").is_err());
+		assert!(FILTERS.read().added.is_empty());
+
+		add_directives("sync=trace").unwrap();
+		assert_eq!(FILTERS.read().level("sync"), LevelFilter::Trace);
+		assert_eq!(log::max_level(), LevelFilter::Trace);
+
+		reset_directives();
+		assert_eq!(FILTERS.read().level("sync"), LevelFilter::Info);
+		assert_eq!(log::max_level(), LevelFilter::Info);
+	}
+
+	#[test]
+	fn added_directives_replace_the_ones_with_the_same_target() {
+		let mut filters = new_filters("info", "");
+		filters.add(parse_directives("sync=trace,afg=debug").unwrap());
+		filters.add(parse_directives("sync=warn,sync=error").unwrap());
+		filters.add(parse_directives("debug").unwrap());
+		filters.add(parse_directives("warn").unwrap());
+		assert_eq!(filters.added, parse_directives("afg=debug,sync=error,warn").unwrap());
+		assert_eq!(filters.level("sync"), LevelFilter::Error);
+		assert_eq!(filters.level("txpool"), LevelFilter::Warn);
+		assert_eq!(filters.max_level(), LevelFilter::Debug);
+	}
+}
@@ -73,6 +73,10 @@ pub struct Params<B: BlockT, S, H: ExHashT> {
 	/// Name of the protocol to use on the wire. Should be different for each chain.
 	pub protocol_id: ProtocolId,
 
+	/// Identifier of the fork of the chain we follow, if any. It is part of the protocol name and
+	/// of the handshake, so that nodes on different forks of the same chain don't connect.
+	pub fork_id: Option<String>,
+
 	/// Import queue to use.
 	///
 	/// The import queue is the component that verifies that blocks received from other nodes are
@@ -242,6 +246,16 @@ pub struct NetworkConfiguration {
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub non_reserved_mode: NonReservedPeerMode,
+	/// Maximum number of connected full nodes. `None` means only `in_peers` and `out_peers` apply.
+	pub full_peers: Option<u32>,
+	/// Maximum number of connected light clients. `None` means only `in_peers` and `out_peers`
+	/// apply.
+	pub light_peers: Option<u32>,
+	/// Maximum number of connected reserved nodes. `None` means no limit.
+	pub reserved_peers: Option<u32>,
+	/// Maximum number of connected peers that announced themselves as authorities, on top of
+	/// the full nodes. `None` means no limit.
+	pub authority_peers: Option<u32>,
 	/// Client identifier. Sent over the wire for debugging purposes.
 	pub client_version: String,
 	/// Name of the node. Sent over the wire for debugging purposes.
@@ -263,10 +277,15 @@ impl Default for NetworkConfiguration {
 			out_peers: 75,
 			reserved_nodes: Vec::new(),
 			non_reserved_mode: NonReservedPeerMode::Accept,
+			full_peers: None,
+			light_peers: None,
+			reserved_peers: None,
+			authority_peers: None,
 			client_version: "unknown".into(),
 			node_name: "unknown".into(),
 			transport: TransportConfig::Normal {
 				enable_mdns: false,
+				enable_nat_port_mapping: false,
 				wasm_external_transport: None,
 			},
 		}
@@ -311,6 +330,10 @@ pub enum TransportConfig {
 		/// and connect to them if they support the same chain.
 		enable_mdns: bool,
 
+		/// If true, the node tries to map the TCP ports it listens on with UPnP or NAT-PMP, and
+		/// advertises the resulting public addresses.
+		enable_nat_port_mapping: bool,
+
 		/// Optional external implementation of a libp2p transport. Used in WASM contexts where we
 		/// need some binding between the networking provided by the operating system or environment
 		/// and libp2p.
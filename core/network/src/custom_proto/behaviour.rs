@@ -16,7 +16,7 @@
 
 use crate::{DiscoveryNetBehaviour, config::ProtocolId};
 use crate::custom_proto::handler::{CustomProtoHandlerProto, CustomProtoHandlerOut, CustomProtoHandlerIn};
-use crate::custom_proto::upgrade::{CustomMessage, RegisteredProtocol};
+use crate::custom_proto::upgrade::{CustomMessage, ProtocolBandwidth, RegisteredProtocol};
 use fnv::FnvHashMap;
 use futures::prelude::*;
 use futures03::{compat::Compat, TryFutureExt as _, StreamExt as _, TryStreamExt as _};
@@ -227,10 +227,12 @@ impl<TMessage, TSubstream> CustomProto<TMessage, TSubstream> {
 	/// Creates a `CustomProtos`.
 	pub fn new(
 		protocol: impl Into<ProtocolId>,
+		genesis_hash: &[u8],
+		fork_id: Option<&str>,
 		versions: &[u8],
 		peerset: peerset::Peerset,
 	) -> Self {
-		let protocol = RegisteredProtocol::new(protocol, versions);
+		let protocol = RegisteredProtocol::new(protocol, genesis_hash, fork_id, versions);
 
 		CustomProto {
 			protocol,
@@ -367,6 +369,22 @@ impl<TMessage, TSubstream> CustomProto<TMessage, TSubstream> {
 		self.peerset.debug_info()
 	}
 
+	/// Indicates to the peerset manager which set a connected peer belongs to. Returns the set
+	/// the peer has been admitted into, or `None` if the peer should be disconnected.
+	pub fn set_peer_set(&mut self, peer_id: &PeerId, set: peerset::PeerSet) -> Option<peerset::PeerSet> {
+		self.peerset.set_peer_set(peer_id.clone(), set)
+	}
+
+	/// Returns the bytes and messages sent and received so far, by protocol name.
+	pub fn protocols_bandwidth(&self) -> FnvHashMap<Vec<u8>, ProtocolBandwidth> {
+		self.protocol.bandwidth().snapshot()
+	}
+
+	/// Accounts the bandwidth of the given notifications protocol under its own name.
+	pub fn register_notifications_protocol(&self, protocol: &[u8]) {
+		self.protocol.bandwidth().register(protocol)
+	}
+
 	/// Function that is called when the peerset wants us to connect to a node.
 	fn peerset_report_connect(&mut self, peer_id: PeerId) {
 		let mut occ_entry = match self.peers.entry(peer_id) {
@@ -15,7 +15,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 pub use self::behaviour::{CustomProto, CustomProtoOut};
-pub use self::upgrade::CustomMessage;
+pub use self::upgrade::{CustomMessage, ProtocolBandwidth};
 
 mod behaviour;
 mod handler;
@@ -69,10 +69,11 @@ fn build_nodes<T: CustomMessage + Send + 'static>()
 			},
 			reserved_only: false,
 			reserved_nodes: Vec::new(),
+			slots: Default::default(),
 		});
 
 		let behaviour = CustomProtoWithAddr {
-			inner: CustomProto::new(&b"test"[..], &[1], peerset),
+			inner: CustomProto::new(&b"test"[..], &[0], None, &[1], peerset),
 			addrs: addrs
 				.iter()
 				.enumerate()
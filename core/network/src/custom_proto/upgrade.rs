@@ -16,10 +16,13 @@
 
 use crate::config::ProtocolId;
 use bytes::Bytes;
+use fnv::FnvHashMap;
 use libp2p::core::{Negotiated, Endpoint, UpgradeInfo, InboundUpgrade, OutboundUpgrade, upgrade::ProtocolName};
 use libp2p::tokio_codec::Framed;
 use log::warn;
-use std::{collections::VecDeque, io, marker::PhantomData, vec::IntoIter as VecIntoIter};
+use parking_lot::Mutex;
+use rustc_hex::ToHex;
+use std::{collections::VecDeque, io, marker::PhantomData, sync::Arc, vec::IntoIter as VecIntoIter};
 use futures::{prelude::*, future, stream};
 use tokio_io::{AsyncRead, AsyncWrite};
 use unsigned_varint::codec::UviBytes;
@@ -31,45 +34,134 @@ use unsigned_varint::codec::UviBytes;
 pub struct RegisteredProtocol<TMessage> {
 	/// Id of the protocol for API purposes.
 	id: ProtocolId,
-	/// Base name of the protocol as advertised on the network.
-	/// Ends with `/` so that we can append a version number behind.
-	base_name: Bytes,
+	/// Base names of the protocol as advertised on the network, the preferred one first.
+	/// Each ends with `/` so that we can append a version number behind.
+	base_names: Vec<Bytes>,
 	/// List of protocol versions that we support.
 	/// Ordered in descending order so that the best comes first.
 	supported_versions: Vec<u8>,
+	/// Bandwidth counters shared by all the substreams of the protocol.
+	bandwidth: ProtocolsBandwidth,
 	/// Marker to pin the generic.
 	marker: PhantomData<TMessage>,
 }
 
+/// Bytes and messages sent and received on a protocol.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProtocolBandwidth {
+	/// Bytes received from the remotes.
+	pub inbound_bytes: u64,
+	/// Bytes sent to the remotes.
+	pub outbound_bytes: u64,
+	/// Messages received from the remotes.
+	pub inbound_messages: u64,
+	/// Messages sent to the remotes.
+	pub outbound_messages: u64,
+}
+
+/// Name the bandwidth of notifications on unregistered protocols is accounted to.
+pub const UNKNOWN_PROTOCOL_NAME: &[u8] = b"unknown";
+
+/// Bandwidth counters of the protocols multiplexed on a `RegisteredProtocol`, by name.
+///
+/// Notifications are accounted to the protocol they are sent on if it was registered with
+/// `register`, and to `UNKNOWN_PROTOCOL_NAME` otherwise, so that remotes can't grow the counters
+/// at will. All other messages are accounted to `/substrate/<protocol>`.
+#[derive(Debug, Clone)]
+pub struct ProtocolsBandwidth {
+	/// Name messages that aren't notifications are accounted to.
+	general_name: Bytes,
+	counters: Arc<Mutex<FnvHashMap<Vec<u8>, ProtocolBandwidth>>>,
+}
+
+impl ProtocolsBandwidth {
+	fn new(general_name: Bytes) -> Self {
+		let mut counters = FnvHashMap::default();
+		counters.insert(general_name.to_vec(), Default::default());
+		counters.insert(UNKNOWN_PROTOCOL_NAME.to_vec(), Default::default());
+		ProtocolsBandwidth { general_name, counters: Arc::new(Mutex::new(counters)) }
+	}
+
+	/// Starts accounting the notifications of the given protocol under its own name.
+	pub fn register(&self, notification_protocol: &[u8]) {
+		self.counters.lock().entry(notification_protocol.to_vec()).or_insert_with(Default::default);
+	}
+
+	fn record(&self, notification_protocol: Option<&[u8]>, len: usize, inbound: bool) {
+		let name = notification_protocol.unwrap_or(&self.general_name[..]);
+		let mut counters = self.counters.lock();
+		let name = if counters.contains_key(name) { name } else { UNKNOWN_PROTOCOL_NAME };
+		let counter = counters.get_mut(name).expect("Unknown protocol is inserted on creation; qed");
+		if inbound {
+			counter.inbound_bytes += len as u64;
+			counter.inbound_messages += 1;
+		} else {
+			counter.outbound_bytes += len as u64;
+			counter.outbound_messages += 1;
+		}
+	}
+
+	/// Returns the current value of the counters, by protocol name.
+	pub fn snapshot(&self) -> FnvHashMap<Vec<u8>, ProtocolBandwidth> {
+		self.counters.lock().clone()
+	}
+}
+
 impl<TMessage> RegisteredProtocol<TMessage> {
 	/// Creates a new `RegisteredProtocol`. The `custom_data` parameter will be
 	/// passed inside the `RegisteredProtocolOutput`.
-	pub fn new(protocol: impl Into<ProtocolId>, versions: &[u8])
-		-> Self {
+	///
+	/// The protocol is advertised as `/substrate/<protocol>/<genesis hash>/<version>`, or
+	/// `/substrate/<protocol>/<genesis hash>/<fork id>/<version>` if a fork id is given, so that
+	/// nodes of different chains don't even open a substream with each other. The chain-agnostic
+	/// `/substrate/<protocol>/<version>` is still supported, with a lower priority, so that nodes
+	/// which don't know about the chain-specific names can still connect.
+	pub fn new(
+		protocol: impl Into<ProtocolId>,
+		genesis_hash: &[u8],
+		fork_id: Option<&str>,
+		versions: &[u8],
+	) -> Self {
 		let protocol = protocol.into();
-		let mut base_name = Bytes::from_static(b"/substrate/");
-		base_name.extend_from_slice(protocol.as_bytes());
+		let mut legacy_base_name = Bytes::from_static(b"/substrate/");
+		legacy_base_name.extend_from_slice(protocol.as_bytes());
+		legacy_base_name.extend_from_slice(b"/");
+
+		let mut base_name = legacy_base_name.clone();
+		base_name.extend_from_slice(genesis_hash.to_hex::<String>().as_bytes());
 		base_name.extend_from_slice(b"/");
+		if let Some(fork_id) = fork_id {
+			base_name.extend_from_slice(fork_id.as_bytes());
+			base_name.extend_from_slice(b"/");
+		}
 
+		let general_name = legacy_base_name.slice_to(legacy_base_name.len() - 1);
 		RegisteredProtocol {
-			base_name,
+			base_names: vec![base_name, legacy_base_name],
 			id: protocol,
 			supported_versions: {
 				let mut tmp = versions.to_vec();
 				tmp.sort_unstable_by(|a, b| b.cmp(&a));
 				tmp
 			},
+			bandwidth: ProtocolsBandwidth::new(general_name),
 			marker: PhantomData,
 		}
 	}
+
+	/// Returns the bandwidth counters of the protocol.
+	pub fn bandwidth(&self) -> &ProtocolsBandwidth {
+		&self.bandwidth
+	}
 }
 
 impl<TMessage> Clone for RegisteredProtocol<TMessage> {
 	fn clone(&self) -> Self {
 		RegisteredProtocol {
 			id: self.id.clone(),
-			base_name: self.base_name.clone(),
+			base_names: self.base_names.clone(),
 			supported_versions: self.supported_versions.clone(),
+			bandwidth: self.bandwidth.clone(),
 			marker: PhantomData,
 		}
 	}
@@ -93,6 +185,8 @@ pub struct RegisteredProtocolSubstream<TMessage, TSubstream> {
 	/// If true, we have sent a "remote is clogged" event recently and shouldn't send another one
 	/// unless the buffer empties then fills itself again.
 	clogged_fuse: bool,
+	/// Bandwidth counters of the protocol.
+	bandwidth: ProtocolsBandwidth,
 	/// Marker to pin the generic.
 	marker: PhantomData<TMessage>,
 }
@@ -127,7 +221,11 @@ impl<TMessage, TSubstream> RegisteredProtocolSubstream<TMessage, TSubstream> {
 			return
 		}
 
-		self.send_queue.push_back(data.into_bytes());
+		// the message is consumed by the encoding, so account for the encoded length afterwards.
+		let name = data.notification_protocol().map(|name| name.to_vec());
+		let bytes = data.into_bytes();
+		self.bandwidth.record(name.as_ref().map(|name| &name[..]), bytes.len(), false);
+		self.send_queue.push_back(bytes);
 	}
 }
 
@@ -139,6 +237,12 @@ pub trait CustomMessage {
 	/// Tries to parse `bytes` received from the network into a message.
 	fn from_bytes(bytes: &[u8]) -> Result<Self, ()>
 		where Self: Sized;
+
+	/// Returns the name of the notifications protocol the message is sent on, if any. Used to
+	/// account the bandwidth per protocol.
+	fn notification_protocol(&self) -> Option<&[u8]> {
+		None
+	}
 }
 
 /// Event produced by the `RegisteredProtocolSubstream`.
@@ -211,6 +315,7 @@ where TSubstream: AsyncRead + AsyncWrite, TMessage: CustomMessage {
 						warn!(target: "sub-libp2p", "Couldn't decode packet sent by the remote: {:?}", data);
 						io::ErrorKind::InvalidData
 					})?;
+				self.bandwidth.record(message.notification_protocol(), data.len(), true);
 				Ok(Async::Ready(Some(RegisteredProtocolEvent::Message(message))))
 			}
 			Async::Ready(None) =>
@@ -230,16 +335,18 @@ impl<TMessage> UpgradeInfo for RegisteredProtocol<TMessage> {
 
 	#[inline]
 	fn protocol_info(&self) -> Self::InfoIter {
-		// Report each version as an individual protocol.
-		self.supported_versions.iter().map(|&version| {
-			let num = version.to_string();
-
-			let mut name = self.base_name.clone();
-			name.extend_from_slice(num.as_bytes());
-			RegisteredProtocolName {
-				name,
-				version,
-			}
+		// Report each version under each name as an individual protocol.
+		self.base_names.iter().flat_map(|base_name| {
+			self.supported_versions.iter().map(move |&version| {
+				let num = version.to_string();
+
+				let mut name = base_name.clone();
+				name.extend_from_slice(num.as_bytes());
+				RegisteredProtocolName {
+					name,
+					version,
+				}
+			})
 		}).collect::<Vec<_>>().into_iter()
 	}
 }
@@ -285,6 +392,7 @@ where TSubstream: AsyncRead + AsyncWrite,
 			inner: framed.fuse(),
 			protocol_version: info.version,
 			clogged_fuse: false,
+			bandwidth: self.bandwidth,
 			marker: PhantomData,
 		})
 	}
@@ -312,7 +420,69 @@ where TSubstream: AsyncRead + AsyncWrite,
 			inner: framed.fuse(),
 			protocol_version: info.version,
 			clogged_fuse: false,
+			bandwidth: self.bandwidth,
 			marker: PhantomData,
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{ProtocolBandwidth, RegisteredProtocol, UNKNOWN_PROTOCOL_NAME};
+	use libp2p::core::{UpgradeInfo, upgrade::ProtocolName};
+
+	fn protocol_names(protocol: &RegisteredProtocol<()>) -> Vec<String> {
+		protocol.protocol_info()
+			.map(|info| String::from_utf8(info.protocol_name().to_vec()).unwrap())
+			.collect()
+	}
+
+	#[test]
+	fn chain_specific_names_come_first() {
+		let protocol = RegisteredProtocol::<()>::new(&b"dot"[..], &[0xab, 0xcd], None, &[2, 3]);
+		assert_eq!(protocol_names(&protocol), vec![
+			"/substrate/dot/abcd/3",
+			"/substrate/dot/abcd/2",
+			"/substrate/dot/3",
+			"/substrate/dot/2",
+		]);
+
+		let protocol = RegisteredProtocol::<()>::new(&b"dot"[..], &[0xab, 0xcd], Some("classic"), &[3]);
+		assert_eq!(protocol_names(&protocol), vec![
+			"/substrate/dot/abcd/classic/3",
+			"/substrate/dot/3",
+		]);
+	}
+
+	#[test]
+	fn bandwidth_is_accounted_per_protocol() {
+		let protocol = RegisteredProtocol::<()>::new(&b"dot"[..], &[0xab, 0xcd], None, &[3]);
+		let bandwidth = protocol.bandwidth();
+		bandwidth.record(None, 10, true);
+		bandwidth.record(None, 5, false);
+		bandwidth.register(&b"/paritytech/grandpa/1"[..]);
+		bandwidth.record(Some(&b"/paritytech/grandpa/1"[..]), 3, true);
+		bandwidth.record(Some(&b"/unregistered/1"[..]), 7, true);
+
+		let snapshot = bandwidth.snapshot();
+		assert_eq!(snapshot.len(), 3);
+		assert_eq!(snapshot[&b"/substrate/dot"[..].to_vec()], ProtocolBandwidth {
+			inbound_bytes: 10,
+			outbound_bytes: 5,
+			inbound_messages: 1,
+			outbound_messages: 1,
+		});
+		assert_eq!(snapshot[&b"/paritytech/grandpa/1"[..].to_vec()], ProtocolBandwidth {
+			inbound_bytes: 3,
+			outbound_bytes: 0,
+			inbound_messages: 1,
+			outbound_messages: 0,
+		});
+		assert_eq!(snapshot[&UNKNOWN_PROTOCOL_NAME.to_vec()], ProtocolBandwidth {
+			inbound_bytes: 7,
+			outbound_bytes: 0,
+			inbound_messages: 1,
+			outbound_messages: 0,
+		});
+	}
+}
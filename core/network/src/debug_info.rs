@@ -14,10 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use futures::prelude::*;
 use futures03::{StreamExt as _, TryStreamExt as _};
 use libp2p::Multiaddr;
+use libp2p::multiaddr::Protocol;
 use libp2p::core::{either::EitherOutput, PeerId, PublicKey};
 use libp2p::core::protocols_handler::{IntoProtocolsHandler, IntoProtocolsHandlerSelect, ProtocolsHandler};
 use libp2p::core::nodes::ConnectedPoint;
@@ -25,7 +26,9 @@ use libp2p::core::swarm::{NetworkBehaviour, NetworkBehaviourAction, PollParamete
 use libp2p::identify::{Identify, IdentifyEvent, protocol::IdentifyInfo};
 use libp2p::ping::{Ping, PingConfig, PingEvent, PingSuccess};
 use log::{debug, trace, error};
-use std::collections::hash_map::Entry;
+use std::collections::{VecDeque, hash_map::Entry};
+use std::iter;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::time::{Duration, Instant};
 use tokio_io::{AsyncRead, AsyncWrite};
 use futures_timer::Interval;
@@ -34,6 +37,9 @@ use futures_timer::Interval;
 const CACHE_EXPIRE: Duration = Duration::from_secs(10 * 60);
 /// Interval at which we perform garbage collection on the node info.
 const GARBAGE_COLLECT_INTERVAL: Duration = Duration::from_secs(2 * 60);
+/// Number of nodes from distinct subnets that must observe the same address of ours before we
+/// report it as one of our external addresses.
+const MIN_ADDRESS_OBSERVATIONS: usize = 2;
 
 /// Implementation of `NetworkBehaviour` that holds information about nodes in cache for diagnostic
 /// purposes.
@@ -44,6 +50,10 @@ pub struct DebugInfoBehaviour<TSubstream> {
 	identify: Identify<TSubstream>,
 	/// Information that we know about all nodes.
 	nodes_info: FnvHashMap<PeerId, NodeInfo>,
+	/// Addresses of ours observed by the nodes we're connected to.
+	observed_addrs: ObservedAddrs,
+	/// Observed addresses that have just been confirmed and must be reported to the swarm.
+	confirmed_addrs: VecDeque<Multiaddr>,
 	/// Interval at which we perform garbage collection in `nodes_info`.
 	garbage_collect: Box<dyn Stream<Item = (), Error = ()> + Send>,
 }
@@ -77,6 +87,8 @@ impl<TSubstream> DebugInfoBehaviour<TSubstream> {
 			ping: Ping::new(PingConfig::new()),
 			identify,
 			nodes_info: FnvHashMap::default(),
+			observed_addrs: ObservedAddrs::default(),
+			confirmed_addrs: VecDeque::new(),
 			garbage_collect: Box::new(Interval::new(GARBAGE_COLLECT_INTERVAL).map(|()| Ok(())).compat()),
 		}
 	}
@@ -113,6 +125,153 @@ impl<TSubstream> DebugInfoBehaviour<TSubstream> {
 				"Received pong from node we're not connected to {:?}", peer_id);
 		}
 	}
+
+	/// Records an address of ours observed by a node, which is reported to the swarm once
+	/// confirmed. Has no effect if we don't have any entry for that node, which shouldn't happen.
+	fn handle_observed_addr(
+		&mut self,
+		peer_id: &PeerId,
+		observed: &Multiaddr,
+		listened: impl Iterator<Item = Multiaddr>,
+	) {
+		let observer = match self.nodes_info.get(peer_id) {
+			Some(entry) => match &entry.endpoint {
+				ConnectedPoint::Dialer { address } => address,
+				ConnectedPoint::Listener { send_back_addr, .. } => send_back_addr,
+			},
+			None => {
+				error!(target: "sub-libp2p",
+					"Received identify info from node we're not connected to {:?}", peer_id);
+				return
+			}
+		};
+		let subnet = match subnet(observer) {
+			Some(subnet) => subnet,
+			None => {
+				trace!(target: "sub-libp2p", "Ignoring address observed by {:?} from {}", peer_id, observer);
+				return
+			}
+		};
+
+		let confirmed = self.observed_addrs.observe(subnet, observed, listened);
+		for addr in &confirmed {
+			debug!(target: "sub-libp2p", "Address {} confirmed by {} subnets", addr, MIN_ADDRESS_OBSERVATIONS);
+		}
+		self.confirmed_addrs.extend(confirmed);
+	}
+}
+
+/// Addresses of ours observed by other nodes, translated to the ports we're listening on, with
+/// the subnets of the nodes that observed them.
+///
+/// Addresses are confirmed once observed from `MIN_ADDRESS_OBSERVATIONS` distinct subnets, so
+/// that a single host can't make us advertise arbitrary addresses by connecting with several
+/// identities.
+#[derive(Default)]
+struct ObservedAddrs {
+	observers: FnvHashMap<Multiaddr, FnvHashSet<IpAddr>>,
+}
+
+impl ObservedAddrs {
+	/// Records an address observed from the given subnet, returning the addresses that are
+	/// confirmed by this observation. Addresses that aren't reachable from the internet are
+	/// ignored.
+	fn observe(
+		&mut self,
+		subnet: IpAddr,
+		observed: &Multiaddr,
+		listened: impl Iterator<Item = Multiaddr>,
+	) -> Vec<Multiaddr> {
+		let mut translated = Vec::new();
+		for listened in listened {
+			if let Some(addr) = translate_observed_addr(observed, &listened) {
+				if !translated.contains(&addr) {
+					translated.push(addr);
+				}
+			}
+		}
+
+		translated.into_iter().filter(|addr| {
+			let observers = self.observers.entry(addr.clone()).or_insert_with(Default::default);
+			observers.insert(subnet) && observers.len() == MIN_ADDRESS_OBSERVATIONS
+		}).collect()
+	}
+
+	/// Forgets the addresses that weren't confirmed, so that the list doesn't grow forever.
+	fn collect_garbage(&mut self) {
+		self.observers.retain(|_, observers| observers.len() >= MIN_ADDRESS_OBSERVATIONS);
+	}
+}
+
+/// Replaces the port of an address observed by a remote with the port we're listening on, as
+/// the remote sees the port of the outgoing connection if we dialed it.
+///
+/// Returns `None` if the observed address isn't reachable from the internet.
+fn translate_observed_addr(observed: &Multiaddr, listened: &Multiaddr) -> Option<Multiaddr> {
+	let mut observed = observed.iter();
+	let mut listened = listened.iter();
+	let ip = match (observed.next()?, listened.next()?) {
+		(Protocol::Ip4(ip), Protocol::Ip4(_)) if is_global(&IpAddr::V4(ip)) => Protocol::Ip4(ip),
+		(Protocol::Ip6(ip), Protocol::Ip6(_)) if is_global(&IpAddr::V6(ip)) => Protocol::Ip6(ip),
+		_ => return None,
+	};
+	match (observed.next()?, listened.next()?) {
+		(Protocol::Tcp(_), Protocol::Tcp(port)) =>
+			Some(iter::once(ip).chain(iter::once(Protocol::Tcp(port))).collect()),
+		_ => None,
+	}
+}
+
+/// Returns the subnet of the IP address of the given address: the first 24 bits of IPv4
+/// addresses, and the first 64 bits of IPv6 addresses.
+fn subnet(address: &Multiaddr) -> Option<IpAddr> {
+	match address.iter().next()? {
+		Protocol::Ip4(ip) => {
+			let [a, b, c, _] = ip.octets();
+			Some(IpAddr::V4(Ipv4Addr::new(a, b, c, 0)))
+		},
+		Protocol::Ip6(ip) => {
+			let mut segments = ip.segments();
+			for segment in &mut segments[4..] {
+				*segment = 0;
+			}
+			Some(IpAddr::V6(segments.into()))
+		},
+		_ => None,
+	}
+}
+
+/// Returns true if the address is reachable from the internet.
+pub(crate) fn is_global(ip: &IpAddr) -> bool {
+	match ip {
+		IpAddr::V4(ip) => {
+			let octets = ip.octets();
+			// 100.64.0.0/10 is used by carrier-grade NATs.
+			let is_shared = octets[0] == 100 && (octets[1] & 0b1100_0000) == 0b0100_0000;
+			!(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() ||
+				ip.is_broadcast() || ip.is_documentation() || is_shared)
+		},
+		IpAddr::V6(ip) => {
+			if let Some(ip) = ipv4_mapped(ip) {
+				return is_global(&IpAddr::V4(ip));
+			}
+			let segments = ip.segments();
+			let is_unique_local = (segments[0] & 0xfe00) == 0xfc00;
+			let is_link_local = (segments[0] & 0xffc0) == 0xfe80;
+			let is_documentation = segments[0] == 0x2001 && segments[1] == 0x0db8;
+			!(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || is_unique_local ||
+				is_link_local || is_documentation)
+		},
+	}
+}
+
+/// Returns the IPv4 address of an IPv4-mapped IPv6 address (`::ffff:a.b.c.d`).
+fn ipv4_mapped(ip: &Ipv6Addr) -> Option<Ipv4Addr> {
+	match ip.segments() {
+		[0, 0, 0, 0, 0, 0xffff, high, low] =>
+			Some(Ipv4Addr::new((high >> 8) as u8, high as u8, (low >> 8) as u8, low as u8)),
+		_ => None,
+	}
 }
 
 /// Gives access to the information about a node.
@@ -261,6 +420,10 @@ where TSubstream: AsyncRead + AsyncWrite {
 			Self::OutEvent
 		>
 	> {
+		if let Some(address) = self.confirmed_addrs.pop_front() {
+			return Async::Ready(NetworkBehaviourAction::ReportObservedAddr { address });
+		}
+
 		loop {
 			match self.ping.poll(params) {
 				Async::NotReady => break,
@@ -288,8 +451,9 @@ where TSubstream: AsyncRead + AsyncWrite {
 				Async::NotReady => break,
 				Async::Ready(NetworkBehaviourAction::GenerateEvent(event)) => {
 					match event {
-						IdentifyEvent::Identified { peer_id, info, .. } => {
+						IdentifyEvent::Identified { peer_id, info, observed_addr } => {
 							self.handle_identify_report(&peer_id, &info);
+							self.handle_observed_addr(&peer_id, &observed_addr, params.listened_addresses());
 							let event = DebugInfoEvent::Identified { peer_id, info };
 							return Async::Ready(NetworkBehaviourAction::GenerateEvent(event));
 						}
@@ -309,8 +473,8 @@ where TSubstream: AsyncRead + AsyncWrite {
 						peer_id,
 						event: EitherOutput::Second(event)
 					}),
-				Async::Ready(NetworkBehaviourAction::ReportObservedAddr { address }) =>
-					return Async::Ready(NetworkBehaviourAction::ReportObservedAddr { address }),
+				// observed addresses are only reported once confirmed, see `handle_observed_addr`.
+				Async::Ready(NetworkBehaviourAction::ReportObservedAddr { .. }) => {}
 			}
 		}
 
@@ -318,8 +482,76 @@ where TSubstream: AsyncRead + AsyncWrite {
 			self.nodes_info.retain(|_, node| {
 				node.info_expire.as_ref().map(|exp| *exp >= Instant::now()).unwrap_or(true)
 			});
+			self.observed_addrs.collect_garbage();
 		}
 
 		Async::NotReady
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn addr(addr: &str) -> Multiaddr {
+		addr.parse().unwrap()
+	}
+
+	#[test]
+	fn translates_observed_addresses_to_listened_ports() {
+		assert_eq!(
+			translate_observed_addr(&addr("/ip4/1.2.3.4/tcp/52000"), &addr("/ip4/0.0.0.0/tcp/30333")),
+			Some(addr("/ip4/1.2.3.4/tcp/30333")),
+		);
+		assert_eq!(
+			translate_observed_addr(&addr("/ip6/2a00::1/tcp/52000"), &addr("/ip6/::/tcp/30333")),
+			Some(addr("/ip6/2a00::1/tcp/30333")),
+		);
+		// the protocols must match.
+		assert_eq!(translate_observed_addr(&addr("/ip4/1.2.3.4/tcp/52000"), &addr("/ip6/::/tcp/30333")), None);
+		assert_eq!(translate_observed_addr(&addr("/ip4/1.2.3.4/udp/52000"), &addr("/ip4/0.0.0.0/tcp/30333")), None);
+		// addresses that aren't reachable from the internet are ignored.
+		assert_eq!(translate_observed_addr(&addr("/ip4/192.168.1.2/tcp/52000"), &addr("/ip4/0.0.0.0/tcp/30333")), None);
+		assert_eq!(translate_observed_addr(&addr("/ip6/fe80::1/tcp/52000"), &addr("/ip6/::/tcp/30333")), None);
+	}
+
+	#[test]
+	fn observed_addresses_are_confirmed_by_distinct_subnets() {
+		let mut observed_addrs = ObservedAddrs::default();
+		let observed = addr("/ip4/1.2.3.4/tcp/52000");
+		let listened = || vec![addr("/ip4/0.0.0.0/tcp/30333"), addr("/ip4/127.0.0.1/tcp/30333")].into_iter();
+		let subnet1 = subnet(&addr("/ip4/5.6.7.8/tcp/30333")).unwrap();
+		let subnet1_other_host = subnet(&addr("/ip4/5.6.7.9/tcp/30333")).unwrap();
+		let subnet2 = subnet(&addr("/ip4/5.6.8.8/tcp/30333")).unwrap();
+		assert_eq!(subnet1, subnet1_other_host);
+
+		assert!(observed_addrs.observe(subnet1, &observed, listened()).is_empty());
+		assert!(observed_addrs.observe(subnet1_other_host, &observed, listened()).is_empty());
+		assert_eq!(
+			observed_addrs.observe(subnet2, &observed, listened()),
+			vec![addr("/ip4/1.2.3.4/tcp/30333")],
+		);
+		// confirmed addresses are only reported once.
+		let subnet3 = subnet(&addr("/ip4/9.9.9.9/tcp/30333")).unwrap();
+		assert!(observed_addrs.observe(subnet3, &observed, listened()).is_empty());
+
+		// unconfirmed addresses are forgotten.
+		let other = addr("/ip4/4.3.2.1/tcp/52000");
+		assert!(observed_addrs.observe(subnet1, &other, listened()).is_empty());
+		observed_addrs.collect_garbage();
+		assert_eq!(observed_addrs.observers.len(), 1);
+	}
+
+	#[test]
+	fn only_public_addresses_are_global() {
+		assert!(is_global(&"8.8.8.8".parse().unwrap()));
+		assert!(!is_global(&"192.168.1.2".parse().unwrap()));
+		assert!(!is_global(&"10.0.0.1".parse().unwrap()));
+		assert!(!is_global(&"100.64.0.1".parse().unwrap()));
+		assert!(is_global(&"100.128.0.1".parse().unwrap()));
+		assert!(is_global(&"2a00:1450::1".parse().unwrap()));
+		assert!(!is_global(&"::1".parse().unwrap()));
+		assert!(!is_global(&"fd00::1".parse().unwrap()));
+		assert!(!is_global(&"::ffff:192.168.1.2".parse().unwrap()));
+	}
+}
@@ -59,6 +59,13 @@
 //! order for nodes to propagate to us their view of the network. More information about Kademlia
 //! can be found [on Wikipedia](https://en.wikipedia.org/wiki/Kademlia).
 //!
+//! Other nodes can only reach us if they know one of our public addresses. Besides the ones passed
+//! in the network configuration, an address is considered public once nodes from at least two
+//! distinct subnets report having observed it through the `identify` protocol (see below). Nodes
+//! behind a NAT can also map the port they listen on with UPnP or NAT-PMP, which is disabled by
+//! default. The mappings are removed from the router when the network shuts down. The public
+//! addresses are advertised in the DHT and reported in the network state sent to the telemetry.
+//!
 //! ## Connection establishment
 //!
 //! When node Alice knows node Bob's identity and address, it can establish a connection with Bob.
@@ -147,6 +154,19 @@
 //! remote. This is meant to be used by the chain that is being built on top of Substrate
 //! (eg. Polkadot).
 //!
+//! ## Light clients
+//!
+//! Nodes advertise [`Roles::LIGHT`](config::Roles::LIGHT) in their status message when they run a
+//! light client. A light client only syncs headers and justifications (or finality proofs) from full
+//! nodes. Block bodies, storage and call results are fetched on demand, with a proof that is checked
+//! against the local headers. Once a range of finalized headers is covered by a CHT (canonical hash
+//! trie), the light client prunes them and only keeps the CHT root. Pruned headers are fetched
+//! again with a proof against that root when they are needed.
+//!
+//! Full nodes never request blocks from light peers. A light client disconnects from other light
+//! peers and from peers that are too far behind it, since none of them can serve its requests. It
+//! also disconnects peers that send it requests for blocks or proofs.
+//!
 //! It is intended that in the future each of these components gets more isolated, so that they
 //! are free to open and close their own substreams, and so that syncing and light client requests
 //! are able to communicate with nodes outside of the range of the PSM.
@@ -175,6 +195,8 @@ mod chain;
 mod custom_proto;
 mod debug_info;
 mod discovery;
+#[cfg(not(target_os = "unknown"))]
+mod nat;
 mod on_demand_layer;
 mod protocol;
 mod service;
@@ -191,7 +213,8 @@ pub use service::{
 	NetworkService, NetworkWorker, TransactionPool, ExHashT, ReportHandle,
 	NetworkStateInfo,
 };
-pub use protocol::{PeerInfo, Context, consensus_gossip, message, specialization};
+pub use protocol::{PeerInfo, Context, consensus_gossip, message, specialization, TRANSACTIONS_PROTOCOL};
+pub use protocol::event::NotificationEvent;
 pub use protocol::sync::SyncState;
 pub use libp2p::{Multiaddr, PeerId};
 #[doc(inline)]
@@ -244,6 +267,25 @@ pub struct NetworkState {
 	pub average_upload_per_sec: u64,
 	/// State of the peerset manager.
 	pub peerset: serde_json::Value,
+	/// Bandwidth used by each protocol, by name.
+	#[serde(default)]
+	pub protocols: HashMap<String, NetworkStateProtocol>,
+}
+
+/// Part of the `NetworkState` struct. Unstable.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStateProtocol {
+	/// Bytes received since the node started.
+	pub inbound_bytes: u64,
+	/// Bytes sent since the node started.
+	pub outbound_bytes: u64,
+	/// Messages received since the node started.
+	pub inbound_messages: u64,
+	/// Messages sent since the node started.
+	pub outbound_messages: u64,
+	/// Maximum size of a notification, if this is a registered notifications protocol.
+	pub max_notification_size: Option<u64>,
 }
 
 /// Part of the `NetworkState` struct. Unstable.
@@ -0,0 +1,528 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Port mapping on the local router, so that nodes behind a NAT can be dialed.
+//!
+//! The TCP ports we listen on are mapped with UPnP IGD if the router supports it, and with
+//! NAT-PMP otherwise. This happens on a background thread, which refreshes the mappings before
+//! they expire and reports the resulting public addresses through a channel. The mappings are
+//! removed from the router when the `PortMapping` handle is dropped.
+
+use crate::debug_info::is_global;
+use futures::sync::mpsc;
+use libp2p::Multiaddr;
+use libp2p::multiaddr::Protocol;
+use log::{debug, info, warn};
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpStream, UdpSocket};
+use std::sync::mpsc as std_mpsc;
+use std::{fs, iter, thread, time::{Duration, Instant}};
+
+/// Lifetime of the mappings we request.
+const LEASE_DURATION: Duration = Duration::from_secs(60 * 60);
+/// Interval at which the mappings are renewed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// Timeout of each request to the router.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+/// Maximum size of the HTTP responses of the router we read.
+const MAX_RESPONSE_SIZE: u64 = 64 * 1024;
+/// Multicast address of the SSDP discovery.
+const SSDP_ADDRESS: &str = "239.255.255.250:1900";
+/// Device type searched for with SSDP.
+const SSDP_SEARCH_TARGET: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+/// Port of the NAT-PMP service of the router.
+const NAT_PMP_PORT: u16 = 5351;
+/// UPnP services that can map ports, by order of preference.
+const WAN_SERVICES: &[&str] = &[
+	"urn:schemas-upnp-org:service:WANIPConnection:2",
+	"urn:schemas-upnp-org:service:WANIPConnection:1",
+	"urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+
+/// Handle to the port mapping thread.
+///
+/// Dropping it stops the thread, which removes the mappings from the router. The drop blocks
+/// until the router answered, or the requests timed out.
+pub struct PortMapping {
+	/// Public addresses the node can be reached at. Each address is reported again every time its
+	/// mapping is renewed.
+	pub addresses: mpsc::UnboundedReceiver<Multiaddr>,
+	/// Dropped to signal the thread to stop.
+	stop: Option<std_mpsc::Sender<()>>,
+	thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for PortMapping {
+	fn drop(&mut self) {
+		self.stop.take();
+		if let Some(thread) = self.thread.take() {
+			if thread.join().is_err() {
+				warn!(target: "sub-libp2p", "The port mapping thread panicked");
+			}
+		}
+	}
+}
+
+/// Starts mapping the TCP ports of the given listen addresses on the router.
+///
+/// The stream of addresses ends immediately if there is no port to map.
+pub fn start(listen_addresses: &[Multiaddr]) -> PortMapping {
+	let (tx, rx) = mpsc::unbounded();
+	let (stop_tx, stop_rx) = std_mpsc::channel();
+	let mut ports = listen_addresses.iter()
+		.filter_map(tcp_port)
+		.filter(|port| *port != 0)
+		.collect::<Vec<_>>();
+	ports.sort();
+	ports.dedup();
+	if ports.is_empty() {
+		return PortMapping { addresses: rx, stop: None, thread: None };
+	}
+
+	let spawned = thread::Builder::new()
+		.name("nat-port-mapping".into())
+		.spawn(move || run(ports, tx, stop_rx));
+
+	let thread = match spawned {
+		Ok(thread) => Some(thread),
+		Err(err) => {
+			warn!(target: "sub-libp2p", "Failed to spawn the port mapping thread: {}", err);
+			None
+		}
+	};
+	PortMapping { addresses: rx, stop: Some(stop_tx), thread }
+}
+
+/// Body of the port mapping thread. Maps and renews the mappings of the given ports until `stop`
+/// is disconnected, then removes them.
+fn run(ports: Vec<u16>, tx: mpsc::UnboundedSender<Multiaddr>, stop: std_mpsc::Receiver<()>) {
+	let mut mappings = Vec::new();
+	let stopped = || match stop.try_recv() {
+		Err(std_mpsc::TryRecvError::Empty) => false,
+		_ => true,
+	};
+
+	'refresh: loop {
+		mappings.clear();
+		for &port in &ports {
+			if stopped() {
+				break 'refresh;
+			}
+
+			let (external_ip, mapping) = match map_port(port) {
+				Ok(mapped) => mapped,
+				Err(err) => {
+					debug!(target: "sub-libp2p", "Couldn't map port {} on the router: {}", port, err);
+					continue;
+				}
+			};
+			mappings.push(mapping);
+
+			if !is_global(&external_ip.into()) {
+				debug!(target: "sub-libp2p", "Router reported non-public address {}, probably behind \
+					another NAT", external_ip);
+				continue;
+			}
+
+			let address = iter::once(Protocol::Ip4(external_ip))
+				.chain(iter::once(Protocol::Tcp(port)))
+				.collect();
+			if tx.unbounded_send(address).is_err() {
+				// the network has been shut down.
+				break 'refresh;
+			}
+		}
+
+		// wake up as soon as the handle is dropped rather than at the next refresh.
+		match stop.recv_timeout(REFRESH_INTERVAL) {
+			Err(std_mpsc::RecvTimeoutError::Timeout) => {},
+			_ => break,
+		}
+	}
+
+	for mapping in mappings {
+		match mapping.remove() {
+			Ok(()) => debug!(target: "sub-libp2p", "Removed the mapping of port {}", mapping.port),
+			Err(err) => debug!(target: "sub-libp2p", "Couldn't remove the mapping of port {}: {}",
+				mapping.port, err),
+		}
+	}
+}
+
+/// A port mapped on the router.
+struct Mapping {
+	port: u16,
+	method: MappingMethod,
+}
+
+/// How a port has been mapped, with what is needed to remove the mapping.
+enum MappingMethod {
+	Upnp { router: SocketAddrV4, control_path: String, service: &'static str },
+	NatPmp { gateway: Ipv4Addr },
+}
+
+impl Mapping {
+	/// Removes the mapping from the router.
+	fn remove(&self) -> io::Result<()> {
+		match &self.method {
+			MappingMethod::Upnp { router, control_path, service } => {
+				let arguments = format!(
+					"<NewRemoteHost></NewRemoteHost>\
+					<NewExternalPort>{port}</NewExternalPort>\
+					<NewProtocol>TCP</NewProtocol>",
+					port = self.port,
+				);
+				soap_request(*router, control_path, service, "DeletePortMapping", &arguments)?;
+				Ok(())
+			},
+			// a mapping request with a lifetime of zero removes the mapping.
+			MappingMethod::NatPmp { gateway } =>
+				nat_pmp_request(*gateway, self.port, Duration::from_secs(0)).map(|_| ()),
+		}
+	}
+}
+
+/// Maps the given TCP port on the router, returning our public IP address.
+fn map_port(port: u16) -> io::Result<(Ipv4Addr, Mapping)> {
+	match upnp_map_port(port) {
+		Ok((ip, method)) => {
+			info!(target: "sub-libp2p", "Mapped port {} to {} with UPnP", port, ip);
+			Ok((ip, Mapping { port, method }))
+		},
+		Err(err) => {
+			debug!(target: "sub-libp2p", "UPnP port mapping failed: {}", err);
+			let (ip, method) = nat_pmp_map_port(port)?;
+			info!(target: "sub-libp2p", "Mapped port {} to {} with NAT-PMP", port, ip);
+			Ok((ip, Mapping { port, method }))
+		},
+	}
+}
+
+/// Returns the TCP port of the given address, if any.
+fn tcp_port(address: &Multiaddr) -> Option<u16> {
+	address.iter().filter_map(|protocol| match protocol {
+		Protocol::Tcp(port) => Some(port),
+		_ => None,
+	}).next()
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Maps the port with UPnP IGD.
+fn upnp_map_port(port: u16) -> io::Result<(Ipv4Addr, MappingMethod)> {
+	let (router, description_path) = discover_router()?;
+	let description = http_request(router, "GET", &description_path, &[], "")?;
+	let (service, control_path) = find_wan_service(&description)
+		.ok_or_else(|| invalid_data("the router doesn't support port mapping"))?;
+	let local_ip = local_ip_towards(router)?;
+
+	let arguments = format!(
+		"<NewRemoteHost></NewRemoteHost>\
+		<NewExternalPort>{port}</NewExternalPort>\
+		<NewProtocol>TCP</NewProtocol>\
+		<NewInternalPort>{port}</NewInternalPort>\
+		<NewInternalClient>{client}</NewInternalClient>\
+		<NewEnabled>1</NewEnabled>\
+		<NewPortMappingDescription>substrate</NewPortMappingDescription>\
+		<NewLeaseDuration>{lease}</NewLeaseDuration>",
+		port = port,
+		client = local_ip,
+		lease = LEASE_DURATION.as_secs(),
+	);
+	soap_request(router, &control_path, service, "AddPortMapping", &arguments)?;
+
+	let response = soap_request(router, &control_path, service, "GetExternalIPAddress", "")?;
+	let ip = xml_element(&response, "NewExternalIPAddress")
+		.and_then(|ip| ip.trim().parse().ok())
+		.ok_or_else(|| invalid_data("the router didn't report its external address"))?;
+	Ok((ip, MappingMethod::Upnp { router, control_path, service }))
+}
+
+/// Looks for an internet gateway with SSDP, returning its address and the path of its
+/// description.
+fn discover_router() -> io::Result<(SocketAddrV4, String)> {
+	let socket = UdpSocket::bind("0.0.0.0:0")?;
+	socket.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+	let request = format!(
+		"M-SEARCH * HTTP/1.1\r\n\
+		HOST: {}\r\n\
+		MAN: \"ssdp:discover\"\r\n\
+		MX: 2\r\n\
+		ST: {}\r\n\r\n",
+		SSDP_ADDRESS,
+		SSDP_SEARCH_TARGET,
+	);
+	socket.send_to(request.as_bytes(), SSDP_ADDRESS)?;
+
+	// other devices may answer too, so responses are read until one comes from a gateway.
+	let deadline = Instant::now() + REQUEST_TIMEOUT;
+	let mut buffer = [0; 2048];
+	loop {
+		let now = Instant::now();
+		if now >= deadline {
+			return Err(io::Error::new(io::ErrorKind::TimedOut, "no internet gateway answered"));
+		}
+		socket.set_read_timeout(Some(deadline - now))?;
+		let (len, _) = socket.recv_from(&mut buffer)?;
+		match parse_ssdp_response(&String::from_utf8_lossy(&buffer[..len])) {
+			Some(router) => return Ok(router),
+			None => debug!(target: "sub-libp2p", "Ignoring SSDP response from another device"),
+		}
+	}
+}
+
+/// Parses the response to an SSDP search, returning the address and description path of the
+/// gateway. Returns `None` if the response isn't from an internet gateway.
+fn parse_ssdp_response(response: &str) -> Option<(SocketAddrV4, String)> {
+	let header = |name: &str| response.lines().skip(1)
+		.filter_map(|line| {
+			let mut parts = line.splitn(2, ':');
+			let header_name = parts.next()?.trim();
+			let value = parts.next()?.trim();
+			if header_name.eq_ignore_ascii_case(name) { Some(value) } else { None }
+		})
+		.next();
+
+	if response.split_whitespace().nth(1) != Some("200") || header("st")? != SSDP_SEARCH_TARGET {
+		return None;
+	}
+	parse_url(header("location")?)
+}
+
+/// Splits an `http://` URL into the address of the host and the path.
+fn parse_url(url: &str) -> Option<(SocketAddrV4, String)> {
+	let url = url.trim();
+	if url.len() < 7 || !url[..7].eq_ignore_ascii_case("http://") {
+		return None;
+	}
+	let url = &url[7..];
+	let (host, path) = match url.find('/') {
+		Some(pos) => (&url[..pos], &url[pos..]),
+		None => (url, "/"),
+	};
+	let host = if host.contains(':') {
+		host.parse().ok()?
+	} else {
+		SocketAddrV4::new(host.parse().ok()?, 80)
+	};
+	Some((host, path.into()))
+}
+
+/// Finds a service able to map ports in a device description, returning its type and the path
+/// of its control URL.
+fn find_wan_service(description: &str) -> Option<(&'static str, String)> {
+	WAN_SERVICES.iter().filter_map(|service| {
+		let start = description.find(&format!("<serviceType>{}</serviceType>", service)[..])?;
+		let rest = &description[start..];
+		let rest = &rest[..rest.find("</service>").unwrap_or(rest.len())];
+		let control_url = xml_element(rest, "controlURL")?.trim();
+		let control_path = match parse_url(control_url) {
+			Some((_, path)) => path,
+			None if control_url.starts_with('/') => control_url.into(),
+			None => format!("/{}", control_url),
+		};
+		Some((*service, control_path))
+	}).next()
+}
+
+/// Returns the content of the first element with the given name.
+fn xml_element<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+	let start = text.find(&format!("<{}>", name)[..])? + name.len() + 2;
+	let len = text[start..].find(&format!("</{}>", name)[..])?;
+	Some(&text[start..start + len])
+}
+
+/// Returns the local address used to reach the router.
+fn local_ip_towards(router: SocketAddrV4) -> io::Result<Ipv4Addr> {
+	let socket = UdpSocket::bind("0.0.0.0:0")?;
+	socket.connect(router)?;
+	match socket.local_addr()? {
+		SocketAddr::V4(address) => Ok(*address.ip()),
+		SocketAddr::V6(_) => Err(invalid_data("no IPv4 route to the router")),
+	}
+}
+
+/// Calls a UPnP action, returning the body of the response.
+fn soap_request(
+	router: SocketAddrV4,
+	control_path: &str,
+	service: &str,
+	action: &str,
+	arguments: &str,
+) -> io::Result<String> {
+	let body = format!(
+		"<?xml version=\"1.0\"?>\
+		<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+		s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+		<s:Body><u:{action} xmlns:u=\"{service}\">{arguments}</u:{action}></s:Body>\
+		</s:Envelope>",
+		action = action,
+		service = service,
+		arguments = arguments,
+	);
+	let soap_action = format!("\"{}#{}\"", service, action);
+	http_request(router, "POST", control_path, &[
+		("Content-Type", "text/xml; charset=\"utf-8\""),
+		("SOAPAction", &soap_action),
+	], &body)
+}
+
+/// Sends an HTTP/1.0 request, returning the body of the response if it's successful.
+fn http_request(
+	host: SocketAddrV4,
+	method: &str,
+	path: &str,
+	headers: &[(&str, &str)],
+	body: &str,
+) -> io::Result<String> {
+	let mut stream = TcpStream::connect_timeout(&SocketAddr::V4(host), REQUEST_TIMEOUT)?;
+	stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+	stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+	let mut request = format!(
+		"{} {} HTTP/1.0\r\nHost: {}\r\nContent-Length: {}\r\n",
+		method, path, host, body.len(),
+	);
+	for (name, value) in headers {
+		request.push_str(&format!("{}: {}\r\n", name, value));
+	}
+	request.push_str("\r\n");
+	request.push_str(body);
+	stream.write_all(request.as_bytes())?;
+
+	let mut response = String::new();
+	stream.take(MAX_RESPONSE_SIZE).read_to_string(&mut response)?;
+	let status = response.split_whitespace().nth(1).unwrap_or_default();
+	if status != "200" {
+		return Err(io::Error::new(io::ErrorKind::Other, format!("HTTP status {:?}", status)));
+	}
+	Ok(response.find("\r\n\r\n").map(|pos| response[pos + 4..].to_owned()).unwrap_or_default())
+}
+
+/// Maps the port with NAT-PMP.
+fn nat_pmp_map_port(port: u16) -> io::Result<(Ipv4Addr, MappingMethod)> {
+	let route_table = fs::read_to_string("/proc/net/route")?;
+	let gateway = default_gateway(&route_table)
+		.ok_or_else(|| invalid_data("no default gateway"))?;
+	let external_ip = nat_pmp_request(gateway, port, LEASE_DURATION)?;
+	Ok((external_ip, MappingMethod::NatPmp { gateway }))
+}
+
+/// Requests a NAT-PMP mapping of the TCP port with the given lifetime, returning our public IP
+/// address.
+fn nat_pmp_request(gateway: Ipv4Addr, port: u16, lifetime: Duration) -> io::Result<Ipv4Addr> {
+	let socket = UdpSocket::bind("0.0.0.0:0")?;
+	socket.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+	socket.connect(SocketAddrV4::new(gateway, NAT_PMP_PORT))?;
+	let mut response = [0; 16];
+
+	// external address request: version 0, opcode 0.
+	socket.send(&[0, 0])?;
+	let len = socket.recv(&mut response)?;
+	if len < 12 || response[1] != 128 || response[2..4] != [0, 0] {
+		return Err(invalid_data("invalid NAT-PMP external address response"));
+	}
+	let external_ip = Ipv4Addr::new(response[8], response[9], response[10], response[11]);
+
+	// TCP mapping request: version 0, opcode 2, reserved, internal port, external port, lifetime.
+	let mut request = [0; 12];
+	request[1] = 2;
+	request[4..6].copy_from_slice(&port.to_be_bytes());
+	request[6..8].copy_from_slice(&port.to_be_bytes());
+	request[8..12].copy_from_slice(&(lifetime.as_secs() as u32).to_be_bytes());
+	socket.send(&request)?;
+	let len = socket.recv(&mut response)?;
+	if len < 16 || response[1] != 130 || response[2..4] != [0, 0] {
+		return Err(invalid_data("the router refused the NAT-PMP port mapping"));
+	}
+
+	Ok(external_ip)
+}
+
+/// Returns the default gateway from the content of `/proc/net/route`.
+fn default_gateway(route_table: &str) -> Option<Ipv4Addr> {
+	route_table.lines().skip(1).filter_map(|line| {
+		let mut fields = line.split_whitespace().skip(1);
+		let destination = fields.next()?;
+		let gateway = fields.next()?;
+		if destination != "00000000" {
+			return None;
+		}
+		// addresses are written in the host byte order, i.e. little endian.
+		let gateway = u32::from_str_radix(gateway, 16).ok()?;
+		Some(Ipv4Addr::from(gateway.to_le_bytes()))
+	}).next()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_router_urls() {
+		assert_eq!(
+			parse_url("http://192.168.1.1:5000/rootDesc.xml"),
+			Some(("192.168.1.1:5000".parse().unwrap(), "/rootDesc.xml".into())),
+		);
+		assert_eq!(
+			parse_url("HTTP://192.168.1.1"),
+			Some(("192.168.1.1:80".parse().unwrap(), "/".into())),
+		);
+		assert_eq!(parse_url("https://192.168.1.1/rootDesc.xml"), None);
+		assert_eq!(parse_url("http://router.local/rootDesc.xml"), None);
+	}
+
+	#[test]
+	fn finds_wan_service_in_description() {
+		let description = "<root><device><serviceList>\
+			<service><serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>\
+			<controlURL>/ctl/L3F</controlURL></service>\
+			<service><serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>\
+			<controlURL>ctl/IPConn</controlURL></service>\
+			</serviceList></device></root>";
+		assert_eq!(
+			find_wan_service(description),
+			Some(("urn:schemas-upnp-org:service:WANIPConnection:1", "/ctl/IPConn".into())),
+		);
+		assert_eq!(find_wan_service("<root></root>"), None);
+	}
+
+	#[test]
+	fn reads_default_gateway() {
+		let route_table = "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\n\
+			eth0\t0001A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\n\
+			eth0\t00000000\t0101A8C0\t0003\t0\t0\t0\t00000000\n";
+		assert_eq!(default_gateway(route_table), Some(Ipv4Addr::new(192, 168, 1, 1)));
+		assert_eq!(default_gateway("Iface\tDestination\tGateway\n"), None);
+	}
+
+	#[test]
+	fn only_gateways_are_accepted_in_ssdp_responses() {
+		let response = |st: &str| format!(
+			"HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=120\r\nST: {}\r\n\
+			LOCATION: http://192.168.1.1:5000/rootDesc.xml\r\n\r\n",
+			st,
+		);
+		assert_eq!(
+			parse_ssdp_response(&response(SSDP_SEARCH_TARGET)),
+			Some(("192.168.1.1:5000".parse().unwrap(), "/rootDesc.xml".into())),
+		);
+		assert_eq!(parse_ssdp_response(&response("urn:schemas-upnp-org:device:MediaRenderer:1")), None);
+		assert_eq!(parse_ssdp_response("HTTP/1.1 404 Not Found\r\n\r\n"), None);
+	}
+}
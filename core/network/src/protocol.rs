@@ -16,13 +16,13 @@
 
 use crate::{DiscoveryNetBehaviour, config::ProtocolId};
 use crate::custom_proto::{CustomProto, CustomProtoOut};
-use futures::prelude::*;
+use futures::{prelude::*, sync::mpsc};
 use futures03::{StreamExt as _, TryStreamExt as _};
 use libp2p::{Multiaddr, PeerId};
 use libp2p::core::swarm::{ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction, PollParameters};
 use libp2p::core::{nodes::Substream, muxing::StreamMuxerBox};
 use libp2p::core::protocols_handler::{ProtocolsHandler, IntoProtocolsHandler};
-use primitives::storage::StorageKey;
+use primitives::{Blake2Hasher, storage::StorageKey};
 use consensus::{import_queue::IncomingBlock, import_queue::Origin, BlockOrigin};
 use runtime_primitives::{generic::BlockId, ConsensusEngineId, Justification};
 use runtime_primitives::traits::{
@@ -32,7 +32,7 @@ use runtime_primitives::traits::{
 use consensus::import_queue::{BlockImportResult, BlockImportError};
 use message::{BlockAttributes, Direction, FromBlock, Message, RequestId};
 use message::generic::{Message as GenericMessage, ConsensusMessage};
-use event::Event;
+use event::{Event, NotificationEvent};
 use consensus_gossip::{ConsensusGossip, MessageRecipient as GossipMessageRecipient};
 use on_demand::{OnDemandCore, OnDemandNetwork, RequestData};
 use specialization::NetworkSpecialization;
@@ -40,12 +40,13 @@ use sync::{ChainSync, SyncState};
 use crate::service::{TransactionPool, ExHashT};
 use crate::config::{BoxFinalityProofRequestBuilder, Roles};
 use rustc_hex::ToHex;
+use parity_codec::{Encode, Decode};
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use std::{cmp, num::NonZeroUsize, time};
 use log::{trace, debug, warn, error};
 use crate::chain::{Client, FinalityProofProvider};
-use client::light::fetcher::{FetchChecker, ChangesProof};
+use client::{StorageProof, CompactProof, light::fetcher::{FetchChecker, ChangesProof}};
 use crate::error;
 use util::LruHashSet;
 
@@ -64,9 +65,22 @@ const TICK_TIMEOUT: time::Duration = time::Duration::from_millis(1100);
 const PROPAGATE_TIMEOUT: time::Duration = time::Duration::from_millis(2900);
 
 /// Current protocol version.
-pub(crate) const CURRENT_VERSION: u32 = 3;
+pub(crate) const CURRENT_VERSION: u32 = 6;
 /// Lowest version we support
 pub(crate) const MIN_VERSION: u32 = 2;
+/// First version whose status message contains the fork id.
+const FORK_ID_VERSION: u32 = 4;
+/// First version that supports notifications protocols.
+const NOTIFICATIONS_VERSION: u32 = 5;
+/// First version whose light client responses carry compact proofs.
+const COMPACT_PROOF_VERSION: u32 = 6;
+
+/// Name of the notifications protocol transactions are propagated on.
+pub const TRANSACTIONS_PROTOCOL: &[u8] = b"/substrate/transactions/1";
+/// Maximum size of the handshake sent when opening a notifications protocol.
+pub const MAX_HANDSHAKE_SIZE: usize = 1024;
+/// Maximum size of a notification on the transactions protocol.
+const MAX_TRANSACTIONS_NOTIFICATION_SIZE: usize = 16 * 1024 * 1024;
 
 // Maximum allowed entries in `BlockResponse`
 const MAX_BLOCK_DATA_RESPONSE: u32 = 128;
@@ -84,6 +98,10 @@ const TIMEOUT_REPUTATION_CHANGE: i32 = -(1 << 10);
 const UNEXPECTED_STATUS_REPUTATION_CHANGE: i32 = -(1 << 20);
 /// Reputation change when we are a light client and a peer is behind us.
 const PEER_BEHIND_US_LIGHT_REPUTATION_CHANGE: i32 = -(1 << 8);
+/// Reputation change when a peer sends us a notification larger than the protocol allows.
+const OVERSIZED_NOTIFICATION_REPUTATION_CHANGE: i32 = i32::min_value();
+/// Reputation change when a peer opens a notifications protocol with an oversized handshake.
+const OVERSIZED_HANDSHAKE_REPUTATION_CHANGE: i32 = i32::min_value();
 /// Reputation change when a peer sends us an extrinsic that we didn't know about.
 const NEW_EXTRINSIC_REPUTATION_CHANGE: i32 = 1 << 7;
 /// We sent an RPC query to the given node, but it failed.
@@ -99,9 +117,13 @@ pub struct Protocol<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> {
 	/// Handler for on-demand requests.
 	on_demand_core: OnDemandCore<B>,
 	genesis_hash: B::Hash,
+	/// Identifier of the fork of the chain we follow, if any.
+	fork_id: Option<String>,
 	sync: ChainSync<B>,
 	specialization: S,
 	consensus_gossip: ConsensusGossip<B>,
+	/// Notifications protocols registered by services, by name.
+	notifications_protocols: HashMap<Vec<u8>, NotificationsProtocol>,
 	context_data: ContextData<B, H>,
 	// Connected peers pending Status message.
 	handshaking_peers: HashMap<PeerId, HandshakingPeer>,
@@ -120,6 +142,18 @@ struct HandshakingPeer {
 	timestamp: time::Instant,
 }
 
+/// A notifications protocol registered through `register_notifications_protocol`.
+struct NotificationsProtocol {
+	/// Consensus engine whose messages are sent on this protocol, if any.
+	engine_id: Option<ConsensusEngineId>,
+	/// Handshake sent to peers when opening the protocol.
+	handshake: Vec<u8>,
+	/// Maximum size of a notification, in bytes. Peers sending larger ones are disconnected.
+	max_notification_size: usize,
+	/// Senders of the events reported to the registering services.
+	event_senders: Vec<mpsc::UnboundedSender<NotificationEvent>>,
+}
+
 /// Peer information
 #[derive(Debug, Clone)]
 struct Peer<B: BlockT, H: ExHashT> {
@@ -134,6 +168,10 @@ struct Peer<B: BlockT, H: ExHashT> {
 	known_blocks: LruHashSet<B::Hash>,
 	/// Request counter,
 	next_request_id: message::RequestId,
+	/// Notifications protocols opened by the peer, with their handshake.
+	notifications_protocols: HashMap<Vec<u8>, Vec<u8>>,
+	/// Set of the peerset manager the peer has been admitted into.
+	set: peerset::PeerSet,
 }
 
 /// Info about a peer's known state.
@@ -147,6 +185,9 @@ pub struct PeerInfo<B: BlockT> {
 	pub best_hash: B::Hash,
 	/// Peer best block number
 	pub best_number: <B::Header as HeaderT>::Number,
+	/// Whether the peer has been admitted into the reserved nodes or authorities set. Block
+	/// requests are handed to preferred peers first.
+	pub preferred: bool,
 }
 
 struct OnDemandIn<'a, B: BlockT> {
@@ -306,11 +347,34 @@ impl<'a, B: BlockT + 'a, H: ExHashT + 'a> Context<B> for ProtocolContext<'a, B,
 	}
 
 	fn send_consensus(&mut self, who: PeerId, consensus: ConsensusMessage) {
+		// prefer the notifications protocol of the engine if the peer opened it.
+		let protocol = {
+			let peers = &self.context_data.peers;
+			self.context_data.notifications_engines.get(&consensus.engine_id)
+				.filter(|(protocol, _)| peers.get(&who)
+					.map_or(false, |peer| peer.notifications_protocols.contains_key(protocol)))
+				.cloned()
+		};
+		let message = match protocol {
+			Some((protocol, max_size)) => {
+				// the remote disconnects us if we exceed the size limit of the protocol.
+				if consensus.data.len() > max_size {
+					warn!(target: "sync", "Dropping consensus message of {} bytes on {:?}, which exceeds the \
+						maximum size", consensus.data.len(), String::from_utf8_lossy(&protocol));
+					return;
+				}
+				GenericMessage::Notification(message::Notification {
+					protocol,
+					message: consensus.data,
+				})
+			},
+			None => GenericMessage::Consensus(consensus),
+		};
 		send_message(
 			self.behaviour,
 			&mut self.context_data.peers,
 			who,
-			GenericMessage::Consensus(consensus)
+			message,
 		)
 	}
 
@@ -328,6 +392,9 @@ impl<'a, B: BlockT + 'a, H: ExHashT + 'a> Context<B> for ProtocolContext<'a, B,
 struct ContextData<B: BlockT, H: ExHashT> {
 	// All connected peers
 	peers: HashMap<PeerId, Peer<B, H>>,
+	/// Notifications protocols of the consensus engines that registered one, with the maximum
+	/// size of their notifications.
+	notifications_engines: HashMap<ConsensusEngineId, (Vec<u8>, usize)>,
 	pub chain: Arc<dyn Client<B>>,
 }
 
@@ -357,13 +424,29 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		finality_proof_provider: Option<Arc<dyn FinalityProofProvider<B>>>,
 		finality_proof_request_builder: Option<BoxFinalityProofRequestBuilder<B>>,
 		protocol_id: ProtocolId,
+		fork_id: Option<String>,
 		peerset_config: peerset::PeersetConfig,
 	) -> error::Result<(Protocol<B, S, H>, peerset::PeersetHandle)> {
 		let info = chain.info();
 		let sync = ChainSync::new(config.roles, chain.clone(), &info, finality_proof_request_builder);
 		let (peerset, peerset_handle) = peerset::Peerset::from_config(peerset_config);
 		let versions = &((MIN_VERSION as u8)..=(CURRENT_VERSION as u8)).collect::<Vec<u8>>();
-		let behaviour = CustomProto::new(protocol_id, versions, peerset);
+		let behaviour = CustomProto::new(
+			protocol_id,
+			info.chain.genesis_hash.as_ref(),
+			fork_id.as_ref().map(String::as_str),
+			versions,
+			peerset,
+		);
+
+		let mut notifications_protocols = HashMap::new();
+		notifications_protocols.insert(TRANSACTIONS_PROTOCOL.to_vec(), NotificationsProtocol {
+			engine_id: None,
+			handshake: Vec::new(),
+			max_notification_size: MAX_TRANSACTIONS_NOTIFICATION_SIZE,
+			event_senders: Vec::new(),
+		});
+		behaviour.register_notifications_protocol(TRANSACTIONS_PROTOCOL);
 
 		let protocol = Protocol {
 			tick_timeout: Box::new(futures_timer::Interval::new(TICK_TIMEOUT).map(|v| Ok::<_, ()>(v)).compat()),
@@ -371,13 +454,16 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			config: config,
 			context_data: ContextData {
 				peers: HashMap::new(),
+				notifications_engines: HashMap::new(),
 				chain,
 			},
 			on_demand_core: OnDemandCore::new(checker),
 			genesis_hash: info.chain.genesis_hash,
+			fork_id,
 			sync,
 			specialization: specialization,
 			consensus_gossip: ConsensusGossip::new(),
+			notifications_protocols,
 			handshaking_peers: HashMap::new(),
 			transaction_pool,
 			finality_proof_provider,
@@ -501,6 +587,15 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		who: PeerId,
 		message: Message<B>,
 	) -> CustomMessageOutcome<B> {
+		// sending requests for blocks or proofs to the node that is unable to serve them is
+		// considered a bad behavior
+		if !self.config.roles.is_full() && requires_full_node(&message) {
+			trace!(target: "sync", "Peer {} is sending full node requests to the light node", who);
+			self.behaviour.disconnect_peer(&who);
+			self.peerset_handle.report_peer(who, i32::min_value());
+			return CustomMessageOutcome::None;
+		}
+
 		match message {
 			GenericMessage::Status(s) => self.on_status_message(who, s),
 			GenericMessage::BlockRequest(r) => self.on_block_request(who, r),
@@ -543,6 +638,8 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			GenericMessage::FinalityProofResponse(response) =>
 				return self.on_finality_proof_response(who, response),
 			GenericMessage::RemoteReadChildRequest(_) => {}
+			GenericMessage::NotificationsOpen(open) => self.on_notifications_open(who, open),
+			GenericMessage::Notification(notification) => self.on_notification(who, notification),
 			GenericMessage::Consensus(msg) => {
 				if self.context_data.peers.get(&who).map_or(false, |peer| peer.info.protocol_version > 2) {
 					self.consensus_gossip.on_incoming(
@@ -562,6 +659,13 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		CustomMessageOutcome::None
 	}
 
+	/// Converts `proof` to the compact form, unless peer `who` doesn't support it.
+	fn compact_proof(&self, who: &PeerId, proof: StorageProof) -> CompactProof {
+		let protocol_version = self.context_data.peers.get(who)
+			.map_or(MIN_VERSION, |peer| peer.info.protocol_version);
+		compact_proof(proof, protocol_version)
+	}
+
 	fn send_message(&mut self, who: PeerId, message: Message<B>) {
 		send_message::<B, H>(
 			&mut self.behaviour,
@@ -603,8 +707,218 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			GossipMessageRecipient::BroadcastNew =>
 				self.consensus_gossip.multicast(&mut context, topic, message, false),
 			GossipMessageRecipient::Peer(who) =>
-				self.send_message(who, GenericMessage::Consensus(message)),
+				context.send_consensus(who, message),
+		}
+	}
+
+	/// Registers a notifications protocol with the given name and handshake, reporting the
+	/// events of the protocol to `event_sender`. Peers sending notifications larger than
+	/// `max_notification_size` bytes are disconnected.
+	///
+	/// If `engine_id` is set, the consensus messages of this engine are sent on the protocol to
+	/// the peers that opened it, and the notifications received on it are passed to the
+	/// consensus gossip.
+	///
+	/// Notifications protocols aren't separate libp2p substreams: their messages are framed
+	/// with the protocol name and multiplexed over the substream of this protocol.
+	pub fn register_notifications_protocol(
+		&mut self,
+		engine_id: Option<ConsensusEngineId>,
+		protocol: Vec<u8>,
+		handshake: Vec<u8>,
+		max_notification_size: usize,
+		event_sender: mpsc::UnboundedSender<NotificationEvent>,
+	) {
+		if handshake.len() > MAX_HANDSHAKE_SIZE {
+			warn!(target: "sync", "Not registering notifications protocol {:?}: handshake of {} bytes \
+				exceeds the maximum of {} bytes", String::from_utf8_lossy(&protocol), handshake.len(),
+				MAX_HANDSHAKE_SIZE);
+			return;
+		}
+
+		if let Some(engine_id) = engine_id {
+			self.context_data.notifications_engines.insert(engine_id, (protocol.clone(), max_notification_size));
+		}
+
+		// report the peers that already opened the protocol.
+		for (who, peer) in self.context_data.peers.iter() {
+			if let Some(remote_handshake) = peer.notifications_protocols.get(&protocol) {
+				let _ = event_sender.unbounded_send(NotificationEvent::StreamOpened {
+					remote: who.clone(),
+					roles: peer.info.roles,
+					handshake: remote_handshake.clone(),
+				});
+			}
+		}
+
+		if let Some(registered) = self.notifications_protocols.get_mut(&protocol) {
+			debug!(target: "sync", "Notifications protocol {:?} is already registered",
+				String::from_utf8_lossy(&protocol));
+			registered.event_senders.push(event_sender);
+			return;
+		}
+
+		let open = message::NotificationsOpen { protocol: protocol.clone(), handshake: handshake.clone() };
+		for (who, peer) in self.context_data.peers.iter() {
+			if peer.info.protocol_version >= NOTIFICATIONS_VERSION {
+				self.behaviour.send_packet(who, GenericMessage::NotificationsOpen(open.clone()));
+			}
+		}
+
+		self.behaviour.register_notifications_protocol(&protocol);
+		self.notifications_protocols.insert(protocol, NotificationsProtocol {
+			engine_id,
+			handshake,
+			max_notification_size,
+			event_senders: vec![event_sender],
+		});
+	}
+
+	/// Sends a notification to a peer on a notifications protocol it opened.
+	pub fn write_notification(&mut self, who: PeerId, protocol: Vec<u8>, message: Vec<u8>) {
+		let opened = self.context_data.peers.get(&who)
+			.map_or(false, |peer| peer.notifications_protocols.contains_key(&protocol));
+		if !opened {
+			trace!(target: "sync", "Dropping notification to {}, which didn't open {:?}",
+				who, String::from_utf8_lossy(&protocol));
+			return;
+		}
+
+		let max_size = self.notifications_protocols.get(&protocol).map(|p| p.max_notification_size);
+		if max_size.map_or(false, |max_size| message.len() > max_size) {
+			warn!(target: "sync", "Dropping notification of {} bytes on {:?}, which exceeds the maximum size",
+				message.len(), String::from_utf8_lossy(&protocol));
+			return;
+		}
+
+		self.send_message(who, GenericMessage::Notification(message::Notification { protocol, message }));
+	}
+
+	/// Returns the bandwidth used by each protocol, and the notifications size limits.
+	pub fn protocols_state(&self) -> HashMap<String, crate::NetworkStateProtocol> {
+		let mut protocols = self.behaviour.protocols_bandwidth()
+			.into_iter()
+			.map(|(name, bandwidth)| (String::from_utf8_lossy(&name).into_owned(), crate::NetworkStateProtocol {
+				inbound_bytes: bandwidth.inbound_bytes,
+				outbound_bytes: bandwidth.outbound_bytes,
+				inbound_messages: bandwidth.inbound_messages,
+				outbound_messages: bandwidth.outbound_messages,
+				max_notification_size: None,
+			}))
+			.collect::<HashMap<_, _>>();
+		for (name, registered) in self.notifications_protocols.iter() {
+			protocols.entry(String::from_utf8_lossy(name).into_owned())
+				.or_insert_with(Default::default)
+				.max_notification_size = Some(registered.max_notification_size as u64);
+		}
+		protocols
+	}
+
+	/// Reports an event to the services that registered the given notifications protocol.
+	fn report_notification_event(&mut self, protocol: &[u8], event: NotificationEvent) {
+		if let Some(registered) = self.notifications_protocols.get_mut(protocol) {
+			// services that dropped their receiver are removed.
+			registered.event_senders.retain(|sender| sender.unbounded_send(event.clone()).is_ok());
+		}
+	}
+
+	fn on_notifications_open(&mut self, who: PeerId, open: message::NotificationsOpen) {
+		if open.handshake.len() > MAX_HANDSHAKE_SIZE {
+			debug!(target: "sync", "{} opened notifications protocol {:?} with a handshake of {} bytes, \
+				which exceeds the maximum size", who, String::from_utf8_lossy(&open.protocol), open.handshake.len());
+			self.peerset_handle.report_peer(who.clone(), OVERSIZED_HANDSHAKE_REPUTATION_CHANGE);
+			self.behaviour.disconnect_peer(&who);
+			return;
+		}
+
+		let handshake = match self.notifications_protocols.get(&open.protocol) {
+			Some(registered) => registered.handshake.clone(),
+			// the remote may run a protocol we don't know or haven't registered yet. if we register
+			// it later, we open it ourselves and the remote opens it again in reply.
+			None => {
+				trace!(target: "sync", "Ignoring unknown notifications protocol {:?} opened by {}",
+					String::from_utf8_lossy(&open.protocol), who);
+				return;
+			},
+		};
+
+		let roles = match self.context_data.peers.get_mut(&who) {
+			Some(peer) => {
+				let first_open = peer.notifications_protocols
+					.insert(open.protocol.clone(), open.handshake.clone())
+					.is_none();
+				if !first_open {
+					trace!(target: "sync", "{} opened notifications protocol {:?} again",
+						who, String::from_utf8_lossy(&open.protocol));
+					return;
+				}
+				peer.info.roles
+			},
+			None => {
+				trace!(target: "sync", "Ignoring notifications protocol opened by unconnected {}", who);
+				return;
+			},
+		};
+
+		trace!(target: "sync", "{} opened notifications protocol {:?}",
+			who, String::from_utf8_lossy(&open.protocol));
+
+		// the remote may have rejected our own opening if it registered the protocol after we
+		// sent it, so we open the protocol again in reply. the remote ignores it otherwise.
+		self.behaviour.send_packet(&who, GenericMessage::NotificationsOpen(message::NotificationsOpen {
+			protocol: open.protocol.clone(),
+			handshake,
+		}));
+
+		self.report_notification_event(&open.protocol, NotificationEvent::StreamOpened {
+			remote: who,
+			roles,
+			handshake: open.handshake,
+		});
+	}
+
+	fn on_notification(&mut self, who: PeerId, notification: message::Notification) {
+		let opened = self.context_data.peers.get(&who)
+			.map_or(false, |peer| peer.notifications_protocols.contains_key(&notification.protocol));
+		if !opened {
+			trace!(target: "sync", "Ignoring notification from {} on unopened protocol {:?}",
+				who, String::from_utf8_lossy(&notification.protocol));
+			return;
+		}
+
+		let max_size = self.notifications_protocols.get(&notification.protocol)
+			.map(|registered| registered.max_notification_size);
+		if max_size.map_or(false, |max_size| notification.message.len() > max_size) {
+			debug!(target: "sync", "{} sent a notification of {} bytes on {:?}, which exceeds the maximum size",
+				who, notification.message.len(), String::from_utf8_lossy(&notification.protocol));
+			self.peerset_handle.report_peer(who.clone(), OVERSIZED_NOTIFICATION_REPUTATION_CHANGE);
+			self.behaviour.disconnect_peer(&who);
+			return;
 		}
+
+		if &notification.protocol[..] == TRANSACTIONS_PROTOCOL {
+			match Decode::decode(&mut &notification.message[..]) {
+				Some(extrinsics) => self.on_extrinsics(who.clone(), extrinsics),
+				None => {
+					debug!(target: "sync", "Couldn't decode transactions notification from {}", who);
+					self.peerset_handle.report_peer(who, i32::min_value());
+					return;
+				},
+			}
+		} else if let Some(engine_id) = self.notifications_protocols.get(&notification.protocol)
+			.and_then(|registered| registered.engine_id)
+		{
+			self.consensus_gossip.on_incoming(
+				&mut ProtocolContext::new(&mut self.context_data, &mut self.behaviour, &self.peerset_handle),
+				who.clone(),
+				ConsensusMessage { engine_id, data: notification.message.clone() },
+			);
+		}
+
+		self.report_notification_event(&notification.protocol, NotificationEvent::Received {
+			remote: who,
+			message: notification.message,
+		});
 	}
 
 	/// Called when a new peer is connected
@@ -632,7 +946,10 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			self.on_demand_core.on_disconnect(OnDemandIn {
 				behaviour: &mut self.behaviour,
 				peerset: self.peerset_handle.clone(),
-			}, peer);
+			}, peer.clone());
+			for protocol in peer_data.notifications_protocols.keys() {
+				self.report_notification_event(protocol, NotificationEvent::StreamClosed { remote: peer.clone() });
+			}
 		}
 	}
 
@@ -664,14 +981,6 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			request.to,
 			request.max);
 
-		// sending block requests to the node that is unable to serve it is considered a bad behavior
-		if !self.config.roles.is_full() {
-			trace!(target: "sync", "Peer {} is trying to sync from the light node", peer);
-			self.behaviour.disconnect_peer(&peer);
-			self.peerset_handle.report_peer(peer, i32::min_value());
-			return;
-		}
-
 		let mut blocks = Vec::new();
 		let mut id = match request.from {
 			message::FromBlock::Hash(h) => BlockId::Hash(h),
@@ -832,24 +1141,40 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 	/// Called by peer to report status
 	fn on_status_message(&mut self, who: PeerId, status: message::Status<B>) {
 		trace!(target: "sync", "New peer {} {:?}", who, status);
-		let protocol_version = {
+		let (protocol_version, set) = {
 			if self.context_data.peers.contains_key(&who) {
 				debug!("Unexpected status packet from {}", who);
 				self.peerset_handle.report_peer(who, UNEXPECTED_STATUS_REPUTATION_CHANGE);
 				return;
 			}
 			if status.genesis_hash != self.genesis_hash {
-				trace!(
+				debug!(
 					target: "protocol",
-					"Peer is on different chain (our genesis: {} theirs: {})",
-					self.genesis_hash, status.genesis_hash
+					"Disconnecting peer {} which is on a different chain (our genesis: {} theirs: {})",
+					who, self.genesis_hash, status.genesis_hash
 				);
 				self.peerset_handle.report_peer(who.clone(), i32::min_value());
 				self.behaviour.disconnect_peer(&who);
 				return;
 			}
-			if status.version < MIN_VERSION && CURRENT_VERSION < status.min_supported_version {
-				trace!(target: "protocol", "Peer {:?} using unsupported protocol version {}", who, status.version);
+			// peers using an older protocol version don't tell us their fork.
+			if status.version >= FORK_ID_VERSION && status.fork_id != self.fork_id {
+				debug!(
+					target: "protocol",
+					"Disconnecting peer {} which is on a different fork (our fork: {:?} theirs: {:?})",
+					who, self.fork_id, status.fork_id
+				);
+				self.peerset_handle.report_peer(who.clone(), i32::min_value());
+				self.behaviour.disconnect_peer(&who);
+				return;
+			}
+			if status.version < MIN_VERSION || CURRENT_VERSION < status.min_supported_version {
+				debug!(
+					target: "protocol",
+					"Disconnecting peer {} using unsupported protocol version {} (minimum supported: {}, \
+					ours: {} with minimum supported: {})",
+					who, status.version, status.min_supported_version, CURRENT_VERSION, MIN_VERSION
+				);
 				self.peerset_handle.report_peer(who.clone(), i32::min_value());
 				self.behaviour.disconnect_peer(&who);
 				return;
@@ -882,6 +1207,23 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				}
 			}
 
+			// the peer must fit in the set corresponding to its roles
+			let requested_set = if status.roles.is_light() {
+				peerset::PeerSet::Light
+			} else if status.roles.contains(Roles::AUTHORITY) {
+				peerset::PeerSet::Authorities
+			} else {
+				peerset::PeerSet::Full
+			};
+			let set = match self.behaviour.set_peer_set(&who, requested_set) {
+				Some(set) => set,
+				None => {
+					debug!(target: "sync", "Peer {} doesn't fit in the {:?} peer set", who, requested_set);
+					self.behaviour.disconnect_peer(&who);
+					return;
+				},
+			};
+
 			let cache_limit = NonZeroUsize::new(1_000_000).expect("1_000_000 > 0; qed");
 
 			let info = match self.handshaking_peers.remove(&who) {
@@ -890,7 +1232,8 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 						protocol_version: status.version,
 						roles: status.roles,
 						best_hash: status.best_hash,
-						best_number: status.best_number
+						best_number: status.best_number,
+						preferred: set == peerset::PeerSet::Reserved || set == peerset::PeerSet::Authorities,
 					}
 				},
 				None => {
@@ -906,11 +1249,13 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				known_blocks: LruHashSet::new(cache_limit),
 				next_request_id: 0,
 				obsolete_requests: HashMap::new(),
+				notifications_protocols: HashMap::new(),
+				set,
 			};
 			self.context_data.peers.insert(who.clone(), peer);
 
-			debug!(target: "sync", "Connected {}", who);
-			status.version
+			debug!(target: "sync", "Connected {} ({:?} set)", who, set);
+			(status.version, set)
 		};
 
 		let info = self.context_data.peers.get(&who).expect("We just inserted above; QED").info.clone();
@@ -918,16 +1263,28 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			behaviour: &mut self.behaviour,
 			peerset: self.peerset_handle.clone(),
 		}, who.clone(), status.roles, status.best_number);
-		match self.sync.new_peer(who.clone(), info) {
-			Ok(None) => (),
-			Ok(Some(req)) => self.send_message(who.clone(), GenericMessage::BlockRequest(req)),
-			Err(sync::BadPeer(id, repu)) => {
-				self.behaviour.disconnect_peer(&id);
-				self.peerset_handle.report_peer(id, repu)
+		// peers in the light set are never used for sync requests or consensus gossip
+		if set != peerset::PeerSet::Light {
+			match self.sync.new_peer(who.clone(), info) {
+				Ok(None) => (),
+				Ok(Some(req)) => self.send_message(who.clone(), GenericMessage::BlockRequest(req)),
+				Err(sync::BadPeer(id, repu)) => {
+					self.behaviour.disconnect_peer(&id);
+					self.peerset_handle.report_peer(id, repu)
+				}
+			}
+		}
+		if protocol_version >= NOTIFICATIONS_VERSION {
+			for (protocol, registered) in self.notifications_protocols.iter() {
+				let open = message::NotificationsOpen {
+					protocol: protocol.clone(),
+					handshake: registered.handshake.clone(),
+				};
+				self.behaviour.send_packet(&who, GenericMessage::NotificationsOpen(open));
 			}
 		}
 		let mut context = ProtocolContext::new(&mut self.context_data, &mut self.behaviour, &self.peerset_handle);
-		if protocol_version > 2 {
+		if protocol_version > 2 && set != peerset::PeerSet::Light {
 			self.consensus_gossip.new_peer(&mut context, who.clone(), status.roles);
 		}
 		self.specialization.on_connect(&mut context, who, status);
@@ -971,6 +1328,11 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		let extrinsics = self.transaction_pool.transactions();
 		let mut propagated_to = HashMap::new();
 		for (who, peer) in self.context_data.peers.iter_mut() {
+			// light clients don't keep a transaction pool
+			if peer.set == peerset::PeerSet::Light {
+				continue;
+			}
+
 			let (hashes, to_send): (Vec<_>, Vec<_>) = extrinsics
 				.iter()
 				.filter(|&(ref hash, _)| peer.known_extrinsics.insert(hash.clone()))
@@ -985,7 +1347,16 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 						.push(who.to_base58());
 				}
 				trace!(target: "sync", "Sending {} transactions to {}", to_send.len(), who);
-				self.behaviour.send_packet(who, GenericMessage::Transactions(to_send))
+				if peer.notifications_protocols.contains_key(TRANSACTIONS_PROTOCOL) {
+					for message in transactions_notifications(to_send, MAX_TRANSACTIONS_NOTIFICATION_SIZE) {
+						self.behaviour.send_packet(who, GenericMessage::Notification(message::Notification {
+							protocol: TRANSACTIONS_PROTOCOL.to_vec(),
+							message,
+						}));
+					}
+				} else {
+					self.behaviour.send_packet(who, GenericMessage::Transactions(to_send))
+				}
 			}
 		}
 
@@ -1036,6 +1407,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			best_number: info.chain.best_number,
 			best_hash: info.chain.best_hash,
 			chain_status: self.specialization.status(),
+			fork_id: self.fork_id.clone(),
 		};
 
 		self.send_message(who, GenericMessage::Status(status))
@@ -1175,10 +1547,11 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 					error
 				);
 				self.peerset_handle.report_peer(who.clone(), RPC_FAILED_REPUTATION_CHANGE);
-				Default::default()
+				StorageProof::empty()
 			}
 		};
 
+		let proof = self.compact_proof(&who, proof);
 		self.send_message(
 			who,
 			GenericMessage::RemoteCallResponse(message::RemoteCallResponse {
@@ -1276,9 +1649,10 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 					request.block,
 					error
 				);
-				Default::default()
+				StorageProof::empty()
 			}
 		};
+		let proof = self.compact_proof(&who, proof);
 		self.send_message(
 			who,
 			GenericMessage::RemoteReadResponse(message::RemoteReadResponse {
@@ -1316,9 +1690,10 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 					request.block,
 					error
 				);
-				(Default::default(), Default::default())
+				(Default::default(), StorageProof::empty())
 			}
 		};
+		let proof = self.compact_proof(&who, proof);
 		self.send_message(
 			who,
 			GenericMessage::RemoteHeaderResponse(message::RemoteHeaderResponse {
@@ -1375,10 +1750,11 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 					max_block: Zero::zero(),
 					proof: vec![],
 					roots: BTreeMap::new(),
-					roots_proof: vec![],
+					roots_proof: StorageProof::empty(),
 				}
 			}
 		};
+		let roots_proof = self.compact_proof(&who, proof.roots_proof);
 		self.send_message(
 			who,
 			GenericMessage::RemoteChangesResponse(message::RemoteChangesResponse {
@@ -1386,7 +1762,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				max: proof.max_block,
 				proof: proof.proof,
 				roots: proof.roots.into_iter().collect(),
-				roots_proof: proof.roots_proof,
+				roots_proof,
 			}),
 		);
 	}
@@ -1478,6 +1854,39 @@ pub enum CustomMessageOutcome<B: BlockT> {
 	None,
 }
 
+/// Returns whether `message` is a request that only full nodes are able to serve.
+///
+/// Light nodes don't keep block bodies or state, and answering these requests would make them
+/// fetch the data from their own peers.
+fn requires_full_node<B: BlockT>(message: &Message<B>) -> bool {
+	match message {
+		GenericMessage::BlockRequest(_) |
+		GenericMessage::RemoteCallRequest(_) |
+		GenericMessage::RemoteReadRequest(_) |
+		GenericMessage::RemoteReadChildRequest(_) |
+		GenericMessage::RemoteHeaderRequest(_) |
+		GenericMessage::RemoteChangesRequest(_) => true,
+		_ => false,
+	}
+}
+
+/// Converts `proof` to the compact form if `protocol_version` supports it.
+///
+/// Older peers receive the proof with all its child hashes, encoded exactly like a `StorageProof`.
+fn compact_proof(proof: StorageProof, protocol_version: u32) -> CompactProof {
+	if protocol_version < COMPACT_PROOF_VERSION {
+		return proof.into();
+	}
+
+	match proof.clone().into_compact::<Blake2Hasher>() {
+		Ok(compact) => compact,
+		Err(error) => {
+			warn!(target: "sync", "Failed to compact proof: {}", error);
+			proof.into()
+		}
+	}
+}
+
 fn send_message<B: BlockT, H: ExHashT>(
 	behaviour: &mut CustomProto<Message<B>, Substream<StreamMuxerBox>>,
 	peers: &mut HashMap<PeerId, Peer<B, H>>,
@@ -1498,6 +1907,37 @@ fn send_message<B: BlockT, H: ExHashT>(
 	behaviour.send_packet(&who, message);
 }
 
+/// Encodes `extrinsics` into transactions notifications of at most `max_size` bytes each.
+/// Extrinsics that don't fit in a notification on their own are dropped.
+fn transactions_notifications<E: Encode>(extrinsics: Vec<E>, max_size: usize) -> Vec<Vec<u8>> {
+	// the length prefix of an encoded batch takes at most 5 bytes.
+	const LENGTH_PREFIX_SIZE: usize = 5;
+
+	let mut notifications = Vec::new();
+	let mut batch = Vec::new();
+	let mut batch_size = LENGTH_PREFIX_SIZE;
+	for extrinsic in extrinsics {
+		let size = extrinsic.using_encoded(|e| e.len());
+		if LENGTH_PREFIX_SIZE + size > max_size {
+			warn!(target: "sync", "Not propagating transaction of {} bytes, which exceeds the maximum \
+				notification size", size);
+			continue;
+		}
+
+		if batch_size + size > max_size {
+			notifications.push(std::mem::replace(&mut batch, Vec::new()).encode());
+			batch_size = LENGTH_PREFIX_SIZE;
+		}
+		batch_size += size;
+		batch.push(extrinsic);
+	}
+
+	if !batch.is_empty() {
+		notifications.push(batch.encode());
+	}
+	notifications
+}
+
 impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> NetworkBehaviour for
 Protocol<B, S, H> {
 	type ProtocolsHandler = <CustomProto<Message<B>, Substream<StreamMuxerBox>> as NetworkBehaviour>::ProtocolsHandler;
@@ -1634,3 +2074,78 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> DiscoveryNetBehaviour f
 		self.behaviour.add_discovered_nodes(peer_ids)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use parity_codec::{Encode, Decode};
+	use primitives::{Blake2Hasher, storage::well_known_keys};
+	use runtime_primitives::generic::BlockId;
+	use test_client::runtime::Block;
+	use super::{
+		message, transactions_notifications, compact_proof, requires_full_node, COMPACT_PROOF_VERSION,
+		GenericMessage, Message,
+	};
+
+	#[test]
+	fn transactions_notifications_respect_the_maximum_size() {
+		let extrinsics = vec![vec![1u8; 10], vec![2u8; 10], vec![3u8; 100]];
+
+		// the oversized extrinsic is dropped.
+		let notifications = transactions_notifications(extrinsics.clone(), 30);
+		assert_eq!(notifications.len(), 1);
+		assert_eq!(Vec::<Vec<u8>>::decode(&mut &notifications[0][..]), Some(extrinsics[..2].to_vec()));
+
+		let notifications = transactions_notifications(extrinsics.clone(), 20);
+		assert_eq!(notifications.len(), 2);
+		for (notification, extrinsic) in notifications.iter().zip(extrinsics) {
+			assert!(notification.len() <= 20);
+			assert_eq!(Vec::<Vec<u8>>::decode(&mut &notification[..]), Some(vec![extrinsic]));
+		}
+	}
+
+	#[test]
+	fn read_proofs_are_compacted_for_supporting_peers() {
+		let client = test_client::new();
+		let proof = client.read_proof(&BlockId::Number(0), well_known_keys::CODE).unwrap();
+
+		// older peers receive the proof unchanged.
+		let full = message::RemoteReadResponse {
+			id: 0,
+			proof: compact_proof(proof.clone(), COMPACT_PROOF_VERSION - 1),
+		};
+		assert_eq!(full.proof.encode(), proof.encode());
+
+		let compact = message::RemoteReadResponse {
+			id: 0,
+			proof: compact_proof(proof.clone(), COMPACT_PROOF_VERSION),
+		};
+		let encoded = compact.encode();
+		assert!(encoded.len() < full.encode().len());
+
+		let decoded = message::RemoteReadResponse::decode(&mut &encoded[..]).unwrap();
+		assert_eq!(decoded.proof.to_storage_proof::<Blake2Hasher>().unwrap(), proof);
+	}
+
+	#[test]
+	fn light_nodes_do_not_serve_requests_for_blocks_or_proofs() {
+		let read: Message<Block> = GenericMessage::RemoteReadRequest(message::RemoteReadRequest {
+			id: 0,
+			block: Default::default(),
+			key: vec![1],
+		});
+		let header: Message<Block> = GenericMessage::RemoteHeaderRequest(message::RemoteHeaderRequest {
+			id: 0,
+			block: 1,
+		});
+		assert!(requires_full_node(&read));
+		assert!(requires_full_node(&header));
+
+		let finality_proof: Message<Block> = GenericMessage::FinalityProofRequest(message::FinalityProofRequest {
+			id: 0,
+			block: Default::default(),
+			request: vec![],
+		});
+		assert!(!requires_full_node(&finality_proof));
+		assert!(!requires_full_node::<Block>(&GenericMessage::Transactions(vec![])));
+	}
+}
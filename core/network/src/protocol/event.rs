@@ -17,7 +17,8 @@
 //! Network event types. These are are not the part of the protocol, but rather
 //! events that happen on the network like DHT get/put results received.
 
-use libp2p::multihash::Multihash;
+use crate::config::Roles;
+use libp2p::{PeerId, multihash::Multihash};
 
 /// Events generated by DHT as a response to get_value and put_value requests.
 pub enum DhtEvent {
@@ -39,3 +40,31 @@ pub enum Event {
 	/// Event generated by a DHT.
 	Dht(DhtEvent),
 }
+
+/// Events reported to the service that registered a notifications protocol.
+#[derive(Debug, Clone)]
+pub enum NotificationEvent {
+	/// The remote opened the protocol, sending its handshake.
+	StreamOpened {
+		/// Node we are connected to.
+		remote: PeerId,
+		/// Roles of the remote.
+		roles: Roles,
+		/// Handshake payload sent by the remote.
+		handshake: Vec<u8>,
+	},
+
+	/// The connection to the remote has been closed.
+	StreamClosed {
+		/// Node we were connected to.
+		remote: PeerId,
+	},
+
+	/// The remote sent a notification on the protocol.
+	Received {
+		/// Node that sent the notification.
+		remote: PeerId,
+		/// Message payload.
+		message: Vec<u8>,
+	},
+}
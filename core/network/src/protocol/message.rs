@@ -18,13 +18,14 @@
 
 use bitflags::bitflags;
 use runtime_primitives::{ConsensusEngineId, traits::{Block as BlockT, Header as HeaderT}};
+use client::CompactProof;
 use parity_codec::{Encode, Decode, Input, Output};
 pub use self::generic::{
 	BlockAnnounce, RemoteCallRequest, RemoteReadRequest,
 	RemoteHeaderRequest, RemoteHeaderResponse,
 	RemoteChangesRequest, RemoteChangesResponse,
 	FinalityProofRequest, FinalityProofResponse,
-	FromBlock, RemoteReadChildRequest,
+	FromBlock, RemoteReadChildRequest, NotificationsOpen, Notification,
 };
 
 /// A unique ID of a request.
@@ -110,8 +111,8 @@ pub enum Direction {
 pub struct RemoteCallResponse {
 	/// Id of a request this response was made for.
 	pub id: RequestId,
-	/// Execution proof.
-	pub proof: Vec<Vec<u8>>,
+	/// Execution proof, in its compact form.
+	pub proof: CompactProof,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
@@ -119,19 +120,19 @@ pub struct RemoteCallResponse {
 pub struct RemoteReadResponse {
 	/// Id of a request this response was made for.
 	pub id: RequestId,
-	/// Read proof.
-	pub proof: Vec<Vec<u8>>,
+	/// Read proof, in its compact form.
+	pub proof: CompactProof,
 }
 
 /// Generic types.
 pub mod generic {
 	use crate::custom_proto::CustomMessage;
-	use parity_codec::{Encode, Decode};
+	use parity_codec::{Encode, Decode, Input};
 	use runtime_primitives::Justification;
 	use crate::config::Roles;
 	use super::{
 		RemoteReadResponse, Transactions, Direction,
-		RequestId, BlockAttributes, RemoteCallResponse, ConsensusEngineId,
+		RequestId, BlockAttributes, RemoteCallResponse, ConsensusEngineId, CompactProof,
 	};
 	/// Consensus is mostly opaque to us
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
@@ -142,6 +143,24 @@ pub mod generic {
 		pub data: Vec<u8>,
 	}
 
+	/// Opens a notifications protocol towards the remote.
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	pub struct NotificationsOpen {
+		/// Name of the protocol, e.g. `/paritytech/grandpa/1`.
+		pub protocol: Vec<u8>,
+		/// Handshake payload of the registering service.
+		pub handshake: Vec<u8>,
+	}
+
+	/// Notification sent on a protocol opened with `NotificationsOpen`.
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	pub struct Notification {
+		/// Name of the protocol.
+		pub protocol: Vec<u8>,
+		/// Message payload.
+		pub message: Vec<u8>,
+	}
+
 	/// Block data sent in the response.
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 	pub struct BlockData<Header, Hash, Extrinsic> {
@@ -205,6 +224,10 @@ pub mod generic {
 		FinalityProofRequest(FinalityProofRequest<Hash>),
 		/// Finality proof reponse.
 		FinalityProofResponse(FinalityProofResponse<Hash>),
+		/// Notifications protocol opening.
+		NotificationsOpen(NotificationsOpen),
+		/// Notification on an opened notifications protocol.
+		Notification(Notification),
 		/// Chain-specific message.
 		#[codec(index = "255")]
 		ChainSpecific(Vec<u8>),
@@ -220,10 +243,17 @@ pub mod generic {
 		fn from_bytes(bytes: &[u8]) -> Result<Self, ()> {
 			Decode::decode(&mut &bytes[..]).ok_or(())
 		}
+
+		fn notification_protocol(&self) -> Option<&[u8]> {
+			match self {
+				Message::Notification(notification) => Some(&notification.protocol),
+				_ => None,
+			}
+		}
 	}
 
 	/// Status sent on connection.
-	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	#[derive(Debug, PartialEq, Eq, Clone, Encode)]
 	pub struct Status<Hash, Number> {
 		/// Protocol version.
 		pub version: u32,
@@ -239,6 +269,26 @@ pub mod generic {
 		pub genesis_hash: Hash,
 		/// Chain-specific status.
 		pub chain_status: Vec<u8>,
+		/// Identifier of the fork of the chain the peer follows, if any.
+		///
+		/// Only sent since protocol version 4.
+		pub fork_id: Option<String>,
+	}
+
+	impl<Hash: Decode, Number: Decode> Decode for Status<Hash, Number> {
+		fn decode<I: Input>(input: &mut I) -> Option<Self> {
+			Some(Status {
+				version: Decode::decode(input)?,
+				min_supported_version: Decode::decode(input)?,
+				roles: Decode::decode(input)?,
+				best_number: Decode::decode(input)?,
+				best_hash: Decode::decode(input)?,
+				genesis_hash: Decode::decode(input)?,
+				chain_status: Decode::decode(input)?,
+				// the status is the last field of the message, so older peers simply end it here.
+				fork_id: Decode::decode(input).unwrap_or(None),
+			})
+		}
 	}
 
 	/// Request block data from a peer.
@@ -327,8 +377,8 @@ pub mod generic {
 		pub id: RequestId,
 		/// Header. None if proof generation has failed (e.g. header is unknown).
 		pub header: Option<Header>,
-		/// Header proof.
-		pub proof: Vec<Vec<u8>>,
+		/// Header proof, in its compact form.
+		pub proof: CompactProof,
 	}
 
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
@@ -361,8 +411,8 @@ pub mod generic {
 		pub proof: Vec<Vec<u8>>,
 		/// Changes tries roots missing on the requester' node.
 		pub roots: Vec<(N, H)>,
-		/// Missing changes tries roots proof.
-		pub roots_proof: Vec<Vec<u8>>,
+		/// Missing changes tries roots proof, in its compact form.
+		pub roots_proof: CompactProof,
 	}
 
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
@@ -387,3 +437,56 @@ pub mod generic {
 		pub proof: Option<Vec<u8>>,
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::generic::{Message, Notification, NotificationsOpen, Status};
+	use crate::config::Roles;
+	use parity_codec::{Encode, Decode};
+
+	#[test]
+	fn status_without_fork_id_is_decoded() {
+		let status = Status::<u64, u64> {
+			version: 3,
+			min_supported_version: 2,
+			roles: Roles::FULL,
+			best_number: 10,
+			best_hash: 10,
+			genesis_hash: 1,
+			chain_status: vec![1, 2, 3],
+			fork_id: None,
+		};
+		let legacy_encoding = (
+			status.version,
+			status.min_supported_version,
+			status.roles,
+			status.best_number,
+			status.best_hash,
+			status.genesis_hash,
+			status.chain_status.clone(),
+		).encode();
+		assert_eq!(Status::decode(&mut &legacy_encoding[..]), Some(status.clone()));
+
+		let status = Status { fork_id: Some("fork".into()), ..status };
+		assert_eq!(Status::decode(&mut &status.encode()[..]), Some(status));
+	}
+
+	#[test]
+	fn notifications_messages_keep_chain_specific_index() {
+		type TestMessage = Message<u64, u64, u64, u64>;
+
+		let open = TestMessage::NotificationsOpen(NotificationsOpen {
+			protocol: b"/paritytech/grandpa/1".to_vec(),
+			handshake: vec![1],
+		});
+		assert_eq!(TestMessage::decode(&mut &open.encode()[..]), Some(open));
+
+		let notification = TestMessage::Notification(Notification {
+			protocol: b"/substrate/transactions/1".to_vec(),
+			message: vec![2, 3],
+		});
+		assert_eq!(TestMessage::decode(&mut &notification.encode()[..]), Some(notification));
+
+		assert_eq!(TestMessage::ChainSpecific(vec![]).encode()[0], 255);
+	}
+}
@@ -22,12 +22,13 @@ use std::time::{Instant, Duration};
 use log::{trace, info};
 use futures::sync::oneshot::{Sender as OneShotSender};
 use linked_hash_map::{Entry, LinkedHashMap};
-use client::error::Error as ClientError;
+use client::{StorageProof, CompactProof, error::Error as ClientError};
 use client::light::fetcher::{FetchChecker, RemoteHeaderRequest,
 	RemoteCallRequest, RemoteReadRequest, RemoteChangesRequest, ChangesProof,
 	RemoteReadChildRequest, RemoteBodyRequest};
 use crate::message::{self, BlockAttributes, Direction, FromBlock, RequestId};
 use libp2p::PeerId;
+use primitives::Blake2Hasher;
 use crate::config::Roles;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 
@@ -138,6 +139,11 @@ enum Accept<Block: BlockT> {
 	Unexpected(RequestData<Block>),
 }
 
+/// Restores the proof of a response from its compact form.
+fn expand_proof(proof: &CompactProof) -> Result<StorageProof, ClientError> {
+	proof.to_storage_proof::<Blake2Hasher>().map_err(|_| ClientError::InvalidCompactProof)
+}
+
 /// Dummy implementation of `FetchChecker` that always assumes that responses are bad.
 ///
 /// Considering that it is the responsibility of the client to build the fetcher, it can use this
@@ -150,7 +156,7 @@ impl<Block: BlockT> FetchChecker<Block> for AlwaysBadChecker {
 		&self,
 		_request: &RemoteHeaderRequest<Block::Header>,
 		_remote_header: Option<Block::Header>,
-		_remote_proof: Vec<Vec<u8>>
+		_remote_proof: StorageProof
 	) -> Result<Block::Header, ClientError> {
 		Err(ClientError::Msg("AlwaysBadChecker".into()))
 	}
@@ -158,7 +164,7 @@ impl<Block: BlockT> FetchChecker<Block> for AlwaysBadChecker {
 	fn check_read_proof(
 		&self,
 		_request: &RemoteReadRequest<Block::Header>,
-		_remote_proof: Vec<Vec<u8>>
+		_remote_proof: StorageProof
 	) -> Result<Option<Vec<u8>>, ClientError> {
 		Err(ClientError::Msg("AlwaysBadChecker".into()))
 	}
@@ -166,7 +172,7 @@ impl<Block: BlockT> FetchChecker<Block> for AlwaysBadChecker {
 	fn check_read_child_proof(
 		&self,
 		_request: &RemoteReadChildRequest<Block::Header>,
-		_remote_proof: Vec<Vec<u8>>
+		_remote_proof: StorageProof
 	) -> Result<Option<Vec<u8>>, ClientError> {
 		Err(ClientError::Msg("AlwaysBadChecker".into()))
 	}
@@ -174,7 +180,7 @@ impl<Block: BlockT> FetchChecker<Block> for AlwaysBadChecker {
 	fn check_execution_proof(
 		&self,
 		_request: &RemoteCallRequest<Block::Header>,
-		_remote_proof: Vec<Vec<u8>>
+		_remote_proof: StorageProof
 	) -> Result<Vec<u8>, ClientError> {
 		Err(ClientError::Msg("AlwaysBadChecker".into()))
 	}
@@ -336,10 +342,8 @@ impl<B: BlockT> OnDemandCore<B> where
 		response: message::RemoteHeaderResponse<B::Header>
 	) {
 		self.accept_response("header", network, peer, response.id, |request, checker| match request.data {
-			RequestData::RemoteHeader(request, sender) => match checker.check_header_proof(
-				&request,
-				response.header,
-				response.proof
+			RequestData::RemoteHeader(request, sender) => match expand_proof(&response.proof).and_then(|proof|
+				checker.check_header_proof(&request, response.header, proof)
 			) {
 				Ok(response) => {
 					// we do not bother if receiver has been dropped already
@@ -360,7 +364,7 @@ impl<B: BlockT> OnDemandCore<B> where
 	) {
 		self.accept_response("read", network, peer, response.id, |request, checker| match request.data {
 			RequestData::RemoteRead(request, sender) => {
-				match checker.check_read_proof(&request, response.proof) {
+				match expand_proof(&response.proof).and_then(|proof| checker.check_read_proof(&request, proof)) {
 					Ok(response) => {
 						// we do not bother if receiver has been dropped already
 						let _ = sender.send(Ok(response));
@@ -372,7 +376,7 @@ impl<B: BlockT> OnDemandCore<B> where
 					),
 			}},
 			RequestData::RemoteReadChild(request, sender) => {
-				match checker.check_read_child_proof(&request, response.proof) {
+				match expand_proof(&response.proof).and_then(|proof| checker.check_read_child_proof(&request, proof)) {
 					Ok(response) => {
 						// we do not bother if receiver has been dropped already
 						let _ = sender.send(Ok(response));
@@ -394,7 +398,9 @@ impl<B: BlockT> OnDemandCore<B> where
 		response: message::RemoteCallResponse
 	) {
 		self.accept_response("call", network, peer, response.id, |request, checker| match request.data {
-			RequestData::RemoteCall(request, sender) => match checker.check_execution_proof(&request, response.proof) {
+			RequestData::RemoteCall(request, sender) => match expand_proof(&response.proof)
+				.and_then(|proof| checker.check_execution_proof(&request, proof))
+			{
 				Ok(response) => {
 					// we do not bother if receiver has been dropped already
 					let _ = sender.send(Ok(response));
@@ -413,13 +419,14 @@ impl<B: BlockT> OnDemandCore<B> where
 		response: message::RemoteChangesResponse<NumberFor<B>, B::Hash>
 	) {
 		self.accept_response("changes", network, peer, response.id, |request, checker| match request.data {
-			RequestData::RemoteChanges(request, sender) => match checker.check_changes_proof(
-				&request, ChangesProof {
+			RequestData::RemoteChanges(request, sender) => match expand_proof(&response.roots_proof).and_then(|roots_proof|
+				checker.check_changes_proof(&request, ChangesProof {
 					max_block: response.max,
 					proof: response.proof,
 					roots: response.roots.into_iter().collect(),
-					roots_proof: response.roots_proof,
-			}) {
+					roots_proof,
+				})
+			) {
 				Ok(response) => {
 					// we do not bother if receiver has been dropped already
 					let _ = sender.send(Ok(response));
@@ -638,7 +645,7 @@ pub mod tests {
 	use std::time::Instant;
 	use futures::{Future, sync::oneshot};
 	use runtime_primitives::traits::{Block as BlockT, NumberFor, Header as HeaderT};
-	use client::{error::{Error as ClientError, Result as ClientResult}};
+	use client::{StorageProof, error::{Error as ClientError, Result as ClientResult}};
 	use client::light::fetcher::{FetchChecker, RemoteHeaderRequest,
 		ChangesProof,	RemoteCallRequest, RemoteReadRequest,
 		RemoteReadChildRequest, RemoteChangesRequest, RemoteBodyRequest};
@@ -655,7 +662,7 @@ pub mod tests {
 			&self,
 			_request: &RemoteHeaderRequest<Header>,
 			header: Option<Header>,
-			_remote_proof: Vec<Vec<u8>>
+			_remote_proof: StorageProof
 		) -> ClientResult<Header> {
 			match self.ok {
 				true if header.is_some() => Ok(header.unwrap()),
@@ -663,7 +670,7 @@ pub mod tests {
 			}
 		}
 
-		fn check_read_proof(&self, _: &RemoteReadRequest<Header>, _: Vec<Vec<u8>>) -> ClientResult<Option<Vec<u8>>> {
+		fn check_read_proof(&self, _: &RemoteReadRequest<Header>, _: StorageProof) -> ClientResult<Option<Vec<u8>>> {
 			match self.ok {
 				true => Ok(Some(vec![42])),
 				false => Err(ClientError::Backend("Test error".into())),
@@ -673,7 +680,7 @@ pub mod tests {
 		fn check_read_child_proof(
 			&self,
 			_: &RemoteReadChildRequest<Header>,
-			_: Vec<Vec<u8>>
+			_: StorageProof
 		) -> ClientResult<Option<Vec<u8>>> {
 			match self.ok {
 				true => Ok(Some(vec![42])),
@@ -681,7 +688,7 @@ pub mod tests {
 			}
 		}
 
-		fn check_execution_proof(&self, _: &RemoteCallRequest<Header>, _: Vec<Vec<u8>>) -> ClientResult<Vec<u8>> {
+		fn check_execution_proof(&self, _: &RemoteCallRequest<Header>, _: StorageProof) -> ClientResult<Vec<u8>> {
 			match self.ok {
 				true => Ok(vec![42]),
 				false => Err(ClientError::Backend("Test error".into())),
@@ -727,7 +734,7 @@ pub mod tests {
 	) {
 		on_demand.on_remote_call_response(network_interface, peer, message::RemoteCallResponse {
 			id: id,
-			proof: vec![vec![2]],
+			proof: StorageProof::new(vec![vec![2]]).into(),
 		});
 	}
 
@@ -890,7 +897,7 @@ pub mod tests {
 
 		on_demand.on_remote_read_response(&mut network_interface, peer0.clone(), message::RemoteReadResponse {
 			id: 0,
-			proof: vec![vec![2]],
+			proof: StorageProof::new(vec![vec![2]]).into(),
 		});
 		assert_disconnected_peer(&network_interface);
 		assert_eq!(on_demand.pending_requests.len(), 1);
@@ -985,7 +992,7 @@ pub mod tests {
 
 		on_demand.on_remote_read_response(&mut network_interface, peer0.clone(), message::RemoteReadResponse {
 			id: 0,
-			proof: vec![vec![2]],
+			proof: StorageProof::new(vec![vec![2]]).into(),
 		});
 		thread.join().unwrap();
 	}
@@ -1013,7 +1020,7 @@ pub mod tests {
 		on_demand.on_remote_read_response(&mut network_interface,
 			peer0.clone(), message::RemoteReadResponse {
 				id: 0,
-				proof: vec![vec![2]],
+				proof: StorageProof::new(vec![vec![2]]).into(),
 		});
 		thread.join().unwrap();
 	}
@@ -1049,7 +1056,7 @@ pub mod tests {
 				extrinsics_root: Default::default(),
 				digest: Default::default(),
 			}),
-			proof: vec![vec![2]],
+			proof: StorageProof::new(vec![vec![2]]).into(),
 		});
 		thread.join().unwrap();
 	}
@@ -1081,7 +1088,7 @@ pub mod tests {
 			max: 1000,
 			proof: vec![vec![2]],
 			roots: vec![],
-			roots_proof: vec![],
+			roots_proof: StorageProof::empty().into(),
 		});
 		thread.join().unwrap();
 	}
@@ -1129,7 +1136,7 @@ pub mod tests {
 		on_demand.on_remote_header_response(&mut network_interface, peer1.clone(), message::RemoteHeaderResponse {
 			id: 0,
 			header: Some(dummy_header()),
-			proof: vec![],
+			proof: StorageProof::empty().into(),
 		});
 
 		assert!(!on_demand.idle_peers.iter().any(|_| true));
@@ -137,7 +137,9 @@ pub struct PeerSync<B: BlockT> {
 	pub state: PeerSyncState<B>,
 	/// A queue of blocks that this peer has announced to us, should only
 	/// contain `ANNOUNCE_HISTORY_SIZE` entries.
-	pub recently_announced: VecDeque<B::Hash>
+	pub recently_announced: VecDeque<B::Hash>,
+	/// Whether block requests are handed to this peer before the others.
+	pub preferred: bool,
 }
 
 /// The sync status of a peer we are trying to sync with
@@ -359,7 +361,8 @@ impl<B: BlockT> ChainSync<B> {
 						best_hash: info.best_hash,
 						best_number: info.best_number,
 						state: PeerSyncState::Available,
-						recently_announced: Default::default()
+						recently_announced: Default::default(),
+						preferred: info.preferred,
 					});
 					return Ok(None)
 				}
@@ -373,6 +376,7 @@ impl<B: BlockT> ChainSync<B> {
 						best_number: info.best_number,
 						state: PeerSyncState::Available,
 						recently_announced: Default::default(),
+						preferred: info.preferred,
 					});
 					return Ok(self.select_new_blocks(who).map(|(_, req)| req))
 				}
@@ -393,7 +397,8 @@ impl<B: BlockT> ChainSync<B> {
 						common_best,
 						AncestorSearchState::ExponentialBackoff(One::one())
 					),
-					recently_announced: Default::default()
+					recently_announced: Default::default(),
+					preferred: info.preferred,
 				});
 
 				Ok(Some(ancestry_request::<B>(common_best)))
@@ -406,6 +411,7 @@ impl<B: BlockT> ChainSync<B> {
 					best_number: info.best_number,
 					state: PeerSyncState::Available,
 					recently_announced: Default::default(),
+					preferred: info.preferred,
 				});
 				Ok(None)
 			}
@@ -490,7 +496,10 @@ impl<B: BlockT> ChainSync<B> {
 		}
 		let blocks = &mut self.blocks;
 		let attrs = &self.required_block_attributes;
-		let iter = self.peers.iter_mut().filter_map(move |(id, peer)| {
+		// preferred peers are handed the first ranges to download.
+		let mut peers = self.peers.iter_mut().collect::<Vec<_>>();
+		peers.sort_by_key(|(_, peer)| !peer.preferred);
+		let iter = peers.into_iter().filter_map(move |(id, peer)| {
 			if !peer.state.is_available() {
 				trace!(target: "sync", "Peer {} is busy", id);
 				return None
@@ -774,6 +783,9 @@ impl<B: BlockT> ChainSync<B> {
 						output.extend(self.restart(&mut peer_info));
 					}
 				},
+				Err(BlockImportError::TooFarInFuture(_, wait)) => {
+					trace!(target: "sync", "Block {:?} is too far in the future, retry in {:?}", hash, wait);
+				},
 				Err(BlockImportError::UnknownParent) |
 				Err(BlockImportError::Cancelled) |
 				Err(BlockImportError::Other(_)) => {
@@ -441,7 +441,8 @@ mod tests {
 				best_hash: Hash::random(),
 				best_number: g.gen(),
 				state: ArbitraryPeerSyncState::arbitrary(g).0,
-				recently_announced: Default::default()
+				recently_announced: Default::default(),
+				preferred: g.gen(),
 			};
 			ArbitraryPeerSync(ps)
 		}
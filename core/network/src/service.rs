@@ -45,9 +45,11 @@ use crate::config::{Params, TransportConfig};
 use crate::error::Error;
 use crate::protocol::{self, Protocol, Context, CustomMessageOutcome, PeerInfo};
 use crate::protocol::consensus_gossip::{ConsensusGossip, MessageRecipient as GossipMessageRecipient};
-use crate::protocol::{event::Event, on_demand::{AlwaysBadChecker, RequestData}};
+use crate::protocol::{event::{Event, NotificationEvent}, on_demand::{AlwaysBadChecker, RequestData}};
 use crate::protocol::specialization::NetworkSpecialization;
 use crate::protocol::sync::SyncState;
+#[cfg(not(target_os = "unknown"))]
+use crate::nat::PortMapping;
 
 /// Minimum Requirements for a Hash within Networking
 pub trait ExHashT:
@@ -152,6 +154,12 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 			bootnodes,
 			reserved_only: params.network_config.non_reserved_mode == NonReservedPeerMode::Deny,
 			reserved_nodes,
+			slots: peerset::SetSlots {
+				full: params.network_config.full_peers.unwrap_or(u32::max_value()),
+				light: params.network_config.light_peers.unwrap_or(u32::max_value()),
+				reserved: params.network_config.reserved_peers.unwrap_or(u32::max_value()),
+				authorities: params.network_config.authority_peers.unwrap_or(u32::max_value()),
+			},
 		};
 
 		// Private and public keys configuration.
@@ -175,9 +183,17 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 			params.finality_proof_provider,
 			params.finality_proof_request_builder,
 			params.protocol_id,
+			params.fork_id,
 			peerset_config,
 		)?;
 
+		// Map the ports we listen on with the router, if enabled.
+		let port_mapping = match params.network_config.transport {
+			TransportConfig::Normal { enable_nat_port_mapping: true, .. } =>
+				start_nat_port_mapping(&params.network_config.listen_addresses),
+			_ => None,
+		};
+
 		// Build the swarm.
 		let (mut swarm, bandwidth) = {
 			let user_agent = format!(
@@ -240,6 +256,7 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 			import_queue: params.import_queue,
 			from_worker,
 			on_demand_in: params.on_demand.and_then(|od| od.extract_receiver()),
+			port_mapping,
 		})
 	}
 
@@ -357,6 +374,7 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 			connected_peers,
 			not_connected_peers,
 			peerset: swarm.user_protocol_mut().peerset_debug_info(),
+			protocols: swarm.user_protocol().protocols_state(),
 		}
 	}
 
@@ -406,6 +424,47 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkServic
 			));
 	}
 
+	/// Registers a notifications protocol, e.g. `/paritytech/grandpa/1`, on which the messages of
+	/// the given consensus engine are sent to the peers that opened it.
+	///
+	/// The handshake is sent to every peer when opening the protocol, and peers sending
+	/// notifications larger than `max_notification_size` bytes are disconnected. Returns a stream
+	/// of the events of the protocol; dropping it doesn't unregister the protocol.
+	///
+	/// Fails if the handshake is larger than `MAX_HANDSHAKE_SIZE` or if the network is shut down.
+	pub fn register_notifications_protocol(
+		&self,
+		engine_id: ConsensusEngineId,
+		protocol_name: impl Into<Vec<u8>>,
+		handshake: Vec<u8>,
+		max_notification_size: usize,
+	) -> Result<mpsc::UnboundedReceiver<NotificationEvent>, String> {
+		if handshake.len() > protocol::MAX_HANDSHAKE_SIZE {
+			return Err(format!(
+				"Handshake of {} bytes exceeds the maximum of {} bytes",
+				handshake.len(),
+				protocol::MAX_HANDSHAKE_SIZE,
+			));
+		}
+
+		let (tx, rx) = mpsc::unbounded();
+		self.to_worker
+			.unbounded_send(ServerToWorkerMsg::RegisterNotificationsProtocol(
+				engine_id, protocol_name.into(), handshake, max_notification_size, tx,
+			))
+			.map_err(|_| "Network is shut down".to_string())?;
+		Ok(rx)
+	}
+
+	/// Sends a notification to the given peer on a notifications protocol.
+	///
+	/// The notification is dropped if the peer didn't open the protocol.
+	pub fn write_notification(&self, target: PeerId, protocol_name: impl Into<Vec<u8>>, message: Vec<u8>) {
+		let _ = self
+			.to_worker
+			.unbounded_send(ServerToWorkerMsg::WriteNotification(target, protocol_name.into(), message));
+	}
+
 	/// Report a given peer as either beneficial (+) or costly (-) according to the
 	/// given scalar.
 	pub fn report_peer(&self, who: PeerId, cost_benefit: i32) {
@@ -548,6 +607,8 @@ enum ServerToWorkerMsg<B: BlockT, S: NetworkSpecialization<B>> {
 	ExecuteWithSpec(Box<dyn FnOnce(&mut S, &mut dyn Context<B>) + Send>),
 	ExecuteWithGossip(Box<dyn FnOnce(&mut ConsensusGossip<B>, &mut dyn Context<B>) + Send>),
 	GossipConsensusMessage(B::Hash, ConsensusEngineId, Vec<u8>, GossipMessageRecipient),
+	RegisterNotificationsProtocol(ConsensusEngineId, Vec<u8>, Vec<u8>, usize, mpsc::UnboundedSender<NotificationEvent>),
+	WriteNotification(PeerId, Vec<u8>, Vec<u8>),
 	GetValue(Multihash),
 	PutValue(Multihash, Vec<u8>),
 	AddKnownAddress(PeerId, Multiaddr),
@@ -574,6 +635,9 @@ pub struct NetworkWorker<B: BlockT + 'static, S: NetworkSpecialization<B>, H: Ex
 	from_worker: mpsc::UnboundedReceiver<ServerToWorkerMsg<B, S>>,
 	/// Receiver for queries from the on-demand that must be processed.
 	on_demand_in: Option<mpsc::UnboundedReceiver<RequestData<B>>>,
+	/// Mapping of the ports we listen on with the router, if enabled. Removed from the router
+	/// when the worker is dropped.
+	port_mapping: Option<PortMapping>,
 }
 
 impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> Future for NetworkWorker<B, S, H> {
@@ -593,6 +657,13 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> Future for Ne
 			}
 		}
 
+		// Advertise the addresses mapped on the router.
+		if let Some(port_mapping) = self.port_mapping.as_mut() {
+			while let Ok(Async::Ready(Some(addr))) = port_mapping.addresses.poll() {
+				Swarm::<B, S, H>::add_external_address(&mut self.network_service, addr);
+			}
+		}
+
 		loop {
 			// Process the next message coming from the `NetworkService`.
 			let msg = match self.from_worker.poll() {
@@ -614,6 +685,11 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> Future for Ne
 				}
 				ServerToWorkerMsg::GossipConsensusMessage(topic, engine_id, message, recipient) =>
 					self.network_service.user_protocol_mut().gossip_consensus_message(topic, engine_id, message, recipient),
+				ServerToWorkerMsg::RegisterNotificationsProtocol(engine_id, protocol, handshake, max_size, sender) =>
+					self.network_service.user_protocol_mut()
+						.register_notifications_protocol(Some(engine_id), protocol, handshake, max_size, sender),
+				ServerToWorkerMsg::WriteNotification(target, protocol, message) =>
+					self.network_service.user_protocol_mut().write_notification(target, protocol, message),
 				ServerToWorkerMsg::AnnounceBlock(hash) =>
 					self.network_service.user_protocol_mut().announce_block(hash),
 				ServerToWorkerMsg::RequestJustification(hash, number) =>
@@ -674,6 +750,25 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> Future for Ne
 	}
 }
 
+/// Starts mapping the given listen addresses on the router.
+#[cfg(not(target_os = "unknown"))]
+fn start_nat_port_mapping(listen_addresses: &[Multiaddr]) -> Option<PortMapping> {
+	Some(crate::nat::start(listen_addresses))
+}
+
+/// Port mapping isn't available in the browser, so this is never built.
+#[cfg(target_os = "unknown")]
+struct PortMapping {
+	addresses: mpsc::UnboundedReceiver<Multiaddr>,
+}
+
+/// Port mapping isn't available in the browser.
+#[cfg(target_os = "unknown")]
+fn start_nat_port_mapping(_: &[Multiaddr]) -> Option<PortMapping> {
+	warn!(target: "sub-libp2p", "Port mapping isn't supported on this platform");
+	None
+}
+
 /// The libp2p swarm, customized for our needs.
 type Swarm<B, S, H> = libp2p::core::Swarm<
 	Boxed<(PeerId, StreamMuxerBox), io::Error>,
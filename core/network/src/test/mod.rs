@@ -19,6 +19,8 @@
 #[cfg(test)]
 mod block_import;
 #[cfg(test)]
+mod notifications;
+#[cfg(test)]
 mod sync;
 
 use std::collections::HashMap;
@@ -27,14 +29,17 @@ use std::sync::Arc;
 use crate::config::build_multiaddr;
 use log::trace;
 use crate::chain::FinalityProofProvider;
-use client::{self, ClientInfo, BlockchainEvents, BlockImportNotification, FinalityNotifications, FinalityNotification};
+use client::{
+	self, ClientInfo, BlockchainEvents, BlockImportNotification, FinalityNotifications, FinalityNotification,
+	Finalizer,
+};
 use client::{in_mem::Backend as InMemoryBackend, error::Result as ClientResult};
 use client::block_builder::BlockBuilder;
 use client::backend::AuxStore;
 use crate::config::Roles;
 use consensus::import_queue::BasicQueue;
 use consensus::import_queue::{
-	BoxBlockImport, BoxJustificationImport, Verifier, BoxFinalityProofImport,
+	BoxBlockImport, BoxJustificationImport, Verifier, BoxFinalityProofImport, VerificationError,
 };
 use consensus::block_import::{BlockImport, ImportResult};
 use consensus::{Error as ConsensusError, well_known_cache_keys::{self, Id as CacheKeyId}};
@@ -43,7 +48,7 @@ use futures::prelude::*;
 use futures03::{StreamExt as _, TryStreamExt as _};
 use crate::{NetworkWorker, NetworkService, config::ProtocolId};
 use crate::config::{NetworkConfiguration, TransportConfig, BoxFinalityProofRequestBuilder};
-use libp2p::PeerId;
+use libp2p::{Multiaddr, PeerId};
 use parking_lot::Mutex;
 use primitives::{H256, Blake2Hasher};
 use crate::protocol::{Context, ProtocolConfig};
@@ -73,7 +78,7 @@ impl<B: BlockT> Verifier<B> for PassThroughVerifier {
 		header: B::Header,
 		justification: Option<Justification>,
 		body: Option<Vec<B::Extrinsic>>
-	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), VerificationError> {
 		let maybe_keys = header.digest()
 			.log(|l| l.try_as_raw(OpaqueDigestItemId::Consensus(b"aura"))
 				.or_else(|| l.try_as_raw(OpaqueDigestItemId::Consensus(b"babe")))
@@ -89,6 +94,7 @@ impl<B: BlockT> Verifier<B> for PassThroughVerifier {
 			post_digests: vec![],
 			auxiliary: Vec::new(),
 			fork_choice: ForkChoiceStrategy::LongestChain,
+			storage_changes: None,
 		}, maybe_keys))
 	}
 }
@@ -217,6 +223,8 @@ pub struct Peer<D, S: NetworkSpecialization<Block>> {
 	/// instead of going through the import queue.
 	block_import: Box<dyn BlockImport<Block, Error = ConsensusError>>,
 	network: NetworkWorker<Block, S, <Block as BlockT>::Hash>,
+	/// The address the peer is listening on.
+	listen_addr: Multiaddr,
 	imported_blocks_stream: Box<dyn Stream<Item = BlockImportNotification<Block>, Error = ()> + Send>,
 	finality_notification_stream: Box<dyn Stream<Item = FinalityNotification<Block>, Error = ()> + Send>,
 }
@@ -237,6 +245,35 @@ impl<D, S: NetworkSpecialization<Block>> Peer<D, S> {
 		self.num_peers() == 0
 	}
 
+	/// Returns the address of the peer, including its `PeerId`.
+	pub fn address(&self) -> String {
+		format!("{}/p2p/{}", self.listen_addr, self.network.service().local_peer_id().to_base58())
+	}
+
+	/// Returns the best block of the peer.
+	pub fn best_hash(&self) -> <Block as BlockT>::Hash {
+		self.client.info().chain.best_hash
+	}
+
+	/// Processes the pending network actions and import notifications of this peer.
+	fn poll(&mut self) {
+		self.network.poll().unwrap();
+
+		// We poll `imported_blocks_stream`.
+		while let Ok(Async::Ready(Some(notification))) = self.imported_blocks_stream.poll() {
+			self.network.on_block_imported(notification.hash, notification.header);
+		}
+
+		// We poll `finality_notification_stream`, but we only take the last event.
+		let mut last = None;
+		while let Ok(Async::Ready(Some(item))) = self.finality_notification_stream.poll() {
+			last = Some(item);
+		}
+		if let Some(notification) = last {
+			self.network.on_block_finalized(notification.hash, notification.header);
+		}
+	}
+
 	/// Request a justification for the given block.
 	pub fn request_justification(&self, hash: &<Block as BlockT>::Hash, number: NumberFor<Block>) {
 		self.network.service().request_justification(hash, number);
@@ -267,7 +304,7 @@ impl<D, S: NetworkSpecialization<Block>> Peer<D, S> {
 		let full_client = self.client.as_full().expect("blocks could only be generated by full clients");
 		let mut at = full_client.header(&at).unwrap().unwrap().hash();
 		for _  in 0..count {
-			let builder = full_client.new_block_at(&BlockId::Hash(at), Default::default()
+			let builder = full_client.new_block_at(&BlockId::Hash(at), Default::default(), false
 			).unwrap();
 			let block = edit_block(builder);
 			let hash = block.header.hash();
@@ -474,6 +511,7 @@ pub trait TestNetFactory: Sized {
 			on_demand: None,
 			transaction_pool: Arc::new(EmptyTransactionPool),
 			protocol_id: ProtocolId::from(&b"test-protocol-name"[..]),
+			fork_id: None,
 			import_queue,
 			specialization: self::SpecializationFactory::create(),
 		}).unwrap();
@@ -496,6 +534,7 @@ pub trait TestNetFactory: Sized {
 				block_import: Box::new(block_import),
 				verifier,
 				network,
+				listen_addr,
 			});
 		});
 	}
@@ -533,6 +572,7 @@ pub trait TestNetFactory: Sized {
 			on_demand: None,
 			transaction_pool: Arc::new(EmptyTransactionPool),
 			protocol_id: ProtocolId::from(&b"test-protocol-name"[..]),
+			fork_id: None,
 			import_queue,
 			specialization: self::SpecializationFactory::create(),
 		}).unwrap();
@@ -555,6 +595,7 @@ pub trait TestNetFactory: Sized {
 				imported_blocks_stream,
 				finality_notification_stream,
 				network,
+				listen_addr,
 			});
 		});
 	}
@@ -584,25 +625,90 @@ pub trait TestNetFactory: Sized {
 		runtime.block_on(futures::future::poll_fn::<(), (), _>(|| Ok(self.poll_until_sync()))).unwrap();
 	}
 
+	/// Returns true if all peers have the same best block.
+	fn is_converged(&self) -> bool {
+		let mut best_hashes = self.peers().iter().map(|peer| peer.best_hash());
+		match best_hashes.next() {
+			Some(first) => best_hashes.all(|hash| hash == first),
+			None => true,
+		}
+	}
+
+	/// Panics if the peers don't agree on the best block.
+	fn assert_converged(&self) {
+		let best_hashes = self.peers().iter().map(|peer| peer.best_hash()).collect::<Vec<_>>();
+		assert!(self.is_converged(), "Peers have not converged, best blocks: {:?}", best_hashes);
+	}
+
+	/// Polls the testnet until all peers have the same best block.
+	///
+	/// Unlike `poll_until_sync`, this also requires peers on forks of equal length to agree.
+	/// Must be executed in a task context.
+	fn poll_until_converged(&mut self) -> Async<()> {
+		self.poll();
+
+		if self.is_converged() {
+			Async::Ready(())
+		} else {
+			Async::NotReady
+		}
+	}
+
+	/// Blocks the current thread until all peers have the same best block.
+	fn block_until_converged(&mut self, runtime: &mut tokio::runtime::current_thread::Runtime) {
+		runtime.block_on(futures::future::poll_fn::<(), (), _>(|| Ok(self.poll_until_converged()))).unwrap();
+	}
+
+	/// Splits the network into the given groups of peer indices.
+	///
+	/// Peers of a group disconnect from all peers outside their group and only reconnect after
+	/// `heal_partition` is called. Peers that are not part of any group are not restricted, but
+	/// can't connect to the peers of a group either.
+	fn partition(&mut self, groups: &[&[usize]]) {
+		let addresses = self.peers().iter().map(|peer| peer.address()).collect::<Vec<_>>();
+		for group in groups {
+			for &i in group.iter() {
+				let service = self.peers()[i].network.service();
+				for &j in group.iter().filter(|&&j| j != i) {
+					service.add_reserved_peer(addresses[j].clone())
+						.expect("addresses of test peers are valid; qed");
+				}
+				service.deny_unreserved_peers();
+			}
+		}
+	}
+
+	/// Lifts all partitions created by `partition`.
+	fn heal_partition(&mut self) {
+		let peer_ids = self.peers().iter()
+			.map(|peer| peer.network.service().local_peer_id())
+			.collect::<Vec<_>>();
+		for peer in self.peers() {
+			let service = peer.network.service();
+			for peer_id in peer_ids.iter().filter(|&id| *id != service.local_peer_id()) {
+				service.remove_reserved_peer(peer_id.clone());
+			}
+			service.accept_unreserved_peers();
+		}
+	}
+
 	/// Polls the testnet. Processes all the pending actions and returns `NotReady`.
 	fn poll(&mut self) {
 		self.mut_peers(|peers| {
 			for peer in peers {
-				peer.network.poll().unwrap();
-
-				// We poll `imported_blocks_stream`.
-				while let Ok(Async::Ready(Some(notification))) = peer.imported_blocks_stream.poll() {
-					peer.network.on_block_imported(notification.hash, notification.header);
-				}
+				peer.poll();
+			}
+		});
+	}
 
-				// We poll `finality_notification_stream`, but we only take the last event.
-				let mut last = None;
-				while let Ok(Async::Ready(Some(item))) = peer.finality_notification_stream.poll() {
-					last = Some(item);
-				}
-				if let Some(notification) = last {
-					peer.network.on_block_finalized(notification.hash, notification.header);
-				}
+	/// Polls only the given peers, in the given order.
+	///
+	/// Messages that are sent to other peers stay queued until these are polled, which allows
+	/// tests to control the order in which peers process their messages.
+	fn poll_peers(&mut self, order: &[usize]) {
+		self.mut_peers(|peers| {
+			for &i in order {
+				peers[i].poll();
 			}
 		});
 	}
@@ -0,0 +1,126 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use futures::sync::mpsc;
+use futures03::TryFutureExt as _;
+use runtime_primitives::ConsensusEngineId;
+use std::time::Duration;
+use tokio::runtime::current_thread;
+use crate::NotificationEvent;
+use crate::protocol::MAX_HANDSHAKE_SIZE;
+use super::*;
+
+const PROTOCOL: &[u8] = b"/test/notifications/1";
+const ENGINE_ID: ConsensusEngineId = *b"test";
+
+fn register(net: &mut TestNet, peer: usize, handshake: Vec<u8>) -> mpsc::UnboundedReceiver<NotificationEvent> {
+	net.peer(peer).network_service()
+		.register_notifications_protocol(ENGINE_ID, PROTOCOL, handshake, 1024)
+		.unwrap()
+}
+
+fn connected_net(runtime: &mut current_thread::Runtime) -> TestNet {
+	let mut net = TestNet::new(2);
+	runtime.block_on(futures::future::poll_fn::<(), (), _>(|| -> Result<_, ()> {
+		net.poll();
+		if net.peer(0).num_peers() == 1 && net.peer(1).num_peers() == 1 {
+			Ok(Async::Ready(()))
+		} else {
+			Ok(Async::NotReady)
+		}
+	})).unwrap();
+	net
+}
+
+// polls the network until `events` yields an event for which `f` returns `Some`.
+fn next_event<T>(
+	runtime: &mut current_thread::Runtime,
+	net: &mut TestNet,
+	events: &mut mpsc::UnboundedReceiver<NotificationEvent>,
+	f: impl Fn(NotificationEvent) -> Option<T>,
+) -> T {
+	runtime.block_on(futures::future::poll_fn::<T, (), _>(|| -> Result<_, ()> {
+		net.poll();
+		while let Async::Ready(Some(event)) = events.poll()? {
+			if let Some(item) = f(event) {
+				return Ok(Async::Ready(item));
+			}
+		}
+		Ok(Async::NotReady)
+	})).unwrap()
+}
+
+fn opened(event: NotificationEvent) -> Option<(PeerId, Vec<u8>)> {
+	match event {
+		NotificationEvent::StreamOpened { remote, handshake, .. } => Some((remote, handshake)),
+		_ => None,
+	}
+}
+
+#[test]
+fn notifications_protocol_exchanges_handshakes_and_messages() {
+	let _ = ::env_logger::try_init();
+	let mut runtime = current_thread::Runtime::new().unwrap();
+	let mut net = connected_net(&mut runtime);
+	let peer0 = net.peer(0).network_service().local_peer_id();
+	let peer1 = net.peer(1).network_service().local_peer_id();
+
+	let mut events0 = register(&mut net, 0, vec![0]);
+	let mut events1 = register(&mut net, 1, vec![1]);
+
+	assert_eq!(next_event(&mut runtime, &mut net, &mut events0, opened), (peer1.clone(), vec![1]));
+	assert_eq!(next_event(&mut runtime, &mut net, &mut events1, opened), (peer0.clone(), vec![0]));
+
+	net.peer(0).network_service().write_notification(peer1, PROTOCOL, vec![42]);
+	let received = next_event(&mut runtime, &mut net, &mut events1, |event| match event {
+		NotificationEvent::Received { remote, message } => Some((remote, message)),
+		_ => None,
+	});
+	assert_eq!(received, (peer0, vec![42]));
+}
+
+#[test]
+fn protocol_registered_late_is_opened_by_both_peers() {
+	let _ = ::env_logger::try_init();
+	let mut runtime = current_thread::Runtime::new().unwrap();
+	let mut net = connected_net(&mut runtime);
+	let peer0 = net.peer(0).network_service().local_peer_id();
+	let peer1 = net.peer(1).network_service().local_peer_id();
+
+	// peer 1 ignores the opening, since it didn't register the protocol yet.
+	let mut events0 = register(&mut net, 0, vec![0]);
+	let mut delay = futures_timer::Delay::new(Duration::from_secs(1)).compat();
+	runtime.block_on(futures::future::poll_fn::<(), (), _>(|| {
+		net.poll();
+		delay.poll().map_err(|_| ())
+	})).unwrap();
+
+	let mut events1 = register(&mut net, 1, vec![1]);
+	assert_eq!(next_event(&mut runtime, &mut net, &mut events0, opened), (peer1, vec![1]));
+	assert_eq!(next_event(&mut runtime, &mut net, &mut events1, opened), (peer0, vec![0]));
+}
+
+#[test]
+fn oversized_handshake_is_not_registered() {
+	let _ = ::env_logger::try_init();
+	let mut runtime = current_thread::Runtime::new().unwrap();
+	let mut net = connected_net(&mut runtime);
+
+	let handshake = vec![0; MAX_HANDSHAKE_SIZE + 1];
+	assert!(net.peer(0).network_service()
+		.register_notifications_protocol(ENGINE_ID, PROTOCOL, handshake, 1024)
+		.is_err());
+}
@@ -527,3 +527,31 @@ fn light_peer_imports_header_from_announce() {
 	let known_stale_hash = net.peer(0).push_blocks_at(BlockId::Number(0), 1, true);
 	import_with_announce(&mut net, &mut runtime, known_stale_hash);
 }
+
+#[test]
+fn partitioned_peers_converge_after_healing() {
+	let _ = ::env_logger::try_init();
+	let mut runtime = current_thread::Runtime::new().unwrap();
+	let mut net = TestNet::new(4);
+	net.partition(&[&[0, 1], &[2, 3]]);
+
+	// each side of the partition builds its own chain.
+	net.peer(0).push_blocks(10, false);
+	net.peer(2).push_blocks(5, true);
+
+	runtime.block_on(futures::future::poll_fn::<(), (), _>(|| -> Result<_, ()> {
+		net.poll();
+		if net.peer(1).client().info().chain.best_number != 10
+			|| net.peer(3).client().info().chain.best_number != 5
+		{
+			return Ok(Async::NotReady)
+		}
+		Ok(Async::Ready(()))
+	})).unwrap();
+	assert!(!net.is_converged());
+
+	net.heal_partition();
+	net.block_until_converged(&mut runtime);
+	net.assert_converged();
+	assert_eq!(net.peer(3).client().info().chain.best_number, 10);
+}
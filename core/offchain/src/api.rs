@@ -22,6 +22,7 @@ use std::{
 };
 use client::backend::OffchainStorage;
 use crate::AuthorityKeyProvider;
+use keystore::Signer;
 use futures::{Stream, Future, sync::mpsc};
 use log::{info, debug, warn, error};
 use parity_codec::{Encode, Decode};
@@ -129,8 +130,8 @@ impl LocalKey {
 /// A key.
 enum Key<ConsensusPair, FinalityPair> {
 	LocalKey(LocalKey),
-	AuthorityKey(ConsensusPair),
-	FgAuthorityKey(FinalityPair),
+	AuthorityKey(Signer<ConsensusPair>),
+	FgAuthorityKey(Signer<FinalityPair>),
 }
 
 impl<ConsensusPair: Pair, FinalityPair: Pair> Key<ConsensusPair, FinalityPair> {
@@ -139,11 +140,11 @@ impl<ConsensusPair: Pair, FinalityPair: Pair> Key<ConsensusPair, FinalityPair> {
 			Key::LocalKey(local) => {
 				local.public()
 			}
-			Key::AuthorityKey(pair) => {
-				Ok(pair.public().to_raw_vec())
+			Key::AuthorityKey(signer) => {
+				Ok(signer.public().to_raw_vec())
 			}
-			Key::FgAuthorityKey(pair) => {
-				Ok(pair.public().to_raw_vec())
+			Key::FgAuthorityKey(signer) => {
+				Ok(signer.public().to_raw_vec())
 			}
 		}
 	}
@@ -153,11 +154,15 @@ impl<ConsensusPair: Pair, FinalityPair: Pair> Key<ConsensusPair, FinalityPair> {
 			Key::LocalKey(local) => {
 				local.sign(data)
 			}
-			Key::AuthorityKey(pair) => {
-				Ok(pair.sign(data).as_ref().to_vec())
+			Key::AuthorityKey(signer) => {
+				signer.sign_raw(data)
+					.wait()
+					.map_err(|e| warn!("Failed to sign with the authority key: {}", e))
 			}
-			Key::FgAuthorityKey(pair) => {
-				Ok(pair.sign(data).as_ref().to_vec())
+			Key::FgAuthorityKey(signer) => {
+				signer.sign_raw(data)
+					.wait()
+					.map_err(|e| warn!("Failed to sign with the finality key: {}", e))
 			}
 		}
 	}
@@ -167,11 +172,11 @@ impl<ConsensusPair: Pair, FinalityPair: Pair> Key<ConsensusPair, FinalityPair> {
 			Key::LocalKey(local) => {
 				local.verify(msg, signature)
 			}
-			Key::AuthorityKey(pair) => {
-				Ok(ConsensusPair::verify_weak(signature, msg, pair.public()))
+			Key::AuthorityKey(signer) => {
+				Ok(ConsensusPair::verify_weak(signature, msg, signer.public()))
 			}
-			Key::FgAuthorityKey(pair) => {
-				Ok(FinalityPair::verify_weak(signature, msg, pair.public()))
+			Key::FgAuthorityKey(signer) => {
+				Ok(FinalityPair::verify_weak(signature, msg, signer.public()))
 			}
 		}
 	}
@@ -40,6 +40,7 @@ use std::{
 };
 
 use client::runtime_api::ApiExt;
+use keystore::Signer;
 use log::{debug, warn};
 use primitives::{
 	ExecutionContext,
@@ -60,6 +61,8 @@ pub mod testing;
 pub use offchain_primitives::OffchainWorkerApi;
 
 /// Provides currently configured authority key.
+///
+/// The keys are only exposed as `Signer`s, so that they may be kept by an external signer.
 pub trait AuthorityKeyProvider<Block: traits::Block>: Clone + 'static {
 	/// The crypto used by the block authoring algorithm.
 	type ConsensusPair: crypto::Pair;
@@ -67,10 +70,10 @@ pub trait AuthorityKeyProvider<Block: traits::Block>: Clone + 'static {
 	type FinalityPair: crypto::Pair;
 
 	/// Returns currently configured authority key.
-	fn authority_key(&self, block_id: &BlockId<Block>) -> Option<Self::ConsensusPair>;
+	fn authority_key(&self, block_id: &BlockId<Block>) -> Option<Signer<Self::ConsensusPair>>;
 
 	/// Returns currently configured finality gadget authority key.
-	fn fg_authority_key(&self, block_id: &BlockId<Block>) -> Option<Self::FinalityPair>;
+	fn fg_authority_key(&self, block_id: &BlockId<Block>) -> Option<Signer<Self::FinalityPair>>;
 }
 
 /// An offchain workers manager.
@@ -207,12 +210,12 @@ mod tests {
 		type ConsensusPair = ed25519::Pair;
 		type FinalityPair = sr25519::Pair;
 
-		fn authority_key(&self, _: &BlockId<Block>) -> Option<Self::ConsensusPair> {
-			self.ed_key.clone()
+		fn authority_key(&self, _: &BlockId<Block>) -> Option<Signer<Self::ConsensusPair>> {
+			self.ed_key.clone().map(Signer::from_pair)
 		}
 
-		fn fg_authority_key(&self, _: &BlockId<Block>) -> Option<Self::FinalityPair> {
-			self.sr_key.clone()
+		fn fg_authority_key(&self, _: &BlockId<Block>) -> Option<Signer<Self::FinalityPair>> {
+			self.sr_key.clone().map(Signer::from_pair)
 		}
 	}
 
@@ -22,6 +22,8 @@ use std::{
 };
 use client::backend::OffchainStorage;
 use parking_lot::RwLock;
+use primitives::{ed25519, sr25519};
+use primitives::crypto::{Pair, Public};
 use primitives::offchain::{
 	self,
 	HttpError,
@@ -69,6 +71,12 @@ pub struct State {
 	pub persistent_storage: client::in_mem::OffchainStorage,
 	/// Local storage
 	pub local_storage: client::in_mem::OffchainStorage,
+	/// Transactions submitted to the pool, encoded.
+	pub transactions: Vec<Vec<u8>>,
+	/// Keys created with `new_crypto_key`: their kind and seed, indexed by key id.
+	pub local_keys: Vec<(CryptoKind, [u8; 32])>,
+	/// The current time.
+	pub timestamp: Timestamp,
 }
 
 impl State {
@@ -103,6 +111,23 @@ impl State {
 		}
 	}
 
+	/// Returns the public key of the local key `key`, if it exists.
+	pub fn public_key(&self, key: CryptoKey) -> Option<Vec<u8>> {
+		match self.local_key(key)? {
+			(CryptoKind::Ed25519, seed) => Some(ed25519::Pair::from_seed(&seed).public().to_raw_vec()),
+			(CryptoKind::Sr25519, seed) => Some(sr25519::Pair::from_seed(&seed).public().to_raw_vec()),
+		}
+	}
+
+	fn local_key(&self, key: CryptoKey) -> Option<(CryptoKind, [u8; 32])> {
+		match key {
+			CryptoKey::LocalKey { id, kind } => self.local_keys.get(id as usize)
+				.cloned()
+				.filter(|(local_kind, _)| *local_kind == kind),
+			_ => None,
+		}
+	}
+
 	/// Add expected HTTP request.
 	///
 	/// This method can be used to initialize expected HTTP requests and their responses
@@ -136,20 +161,27 @@ impl TestOffchainExt {
 }
 
 impl offchain::Externalities for TestOffchainExt {
-	fn submit_transaction(&mut self, _ex: Vec<u8>) -> Result<(), ()> {
-		unimplemented!("not needed in tests so far")
+	fn submit_transaction(&mut self, ex: Vec<u8>) -> Result<(), ()> {
+		self.0.write().transactions.push(ex);
+		Ok(())
 	}
 
 	fn network_state(&self) -> Result<OpaqueNetworkState, ()> {
 		unimplemented!("not needed in tests so far")
 	}
 
-	fn pubkey(&self, _key: CryptoKey) -> Result<Vec<u8>, ()> {
-		unimplemented!("not needed in tests so far")
+	fn pubkey(&self, key: CryptoKey) -> Result<Vec<u8>, ()> {
+		self.0.read().public_key(key).ok_or(())
 	}
 
-	fn new_crypto_key(&mut self, _crypto: CryptoKind) -> Result<CryptoKey, ()> {
-		unimplemented!("not needed in tests so far")
+	fn new_crypto_key(&mut self, crypto: CryptoKind) -> Result<CryptoKey, ()> {
+		let mut state = self.0.write();
+		let id = state.local_keys.len() as u16;
+		// keys are derived from their id, so that tests are deterministic.
+		let mut seed = [0u8; 32];
+		seed[..2].copy_from_slice(&id.to_le_bytes());
+		state.local_keys.push((crypto, seed));
+		Ok(CryptoKey::LocalKey { id, kind: crypto })
 	}
 
 	fn encrypt(
@@ -170,23 +202,39 @@ impl offchain::Externalities for TestOffchainExt {
 
 	fn sign(
 		&mut self,
-		_key: CryptoKey,
-		_data: &[u8],
+		key: CryptoKey,
+		data: &[u8],
 	) -> Result<Vec<u8>, ()> {
-		unimplemented!("not needed in tests so far")
+		match self.0.read().local_key(key).ok_or(())? {
+			(CryptoKind::Ed25519, seed) => {
+				let sig = ed25519::Pair::from_seed(&seed).sign(data);
+				let bytes: &[u8] = sig.as_ref();
+				Ok(bytes.to_vec())
+			}
+			(CryptoKind::Sr25519, seed) => {
+				let sig = sr25519::Pair::from_seed(&seed).sign(data);
+				let bytes: &[u8] = sig.as_ref();
+				Ok(bytes.to_vec())
+			}
+		}
 	}
 
 	fn verify(
 		&mut self,
-		_key: CryptoKey,
-		_msg: &[u8],
-		_signature: &[u8],
+		key: CryptoKey,
+		msg: &[u8],
+		signature: &[u8],
 	) -> Result<bool, ()> {
-		unimplemented!("not needed in tests so far")
+		match self.0.read().local_key(key).ok_or(())? {
+			(CryptoKind::Ed25519, seed) =>
+				Ok(ed25519::Pair::verify_weak(signature, msg, ed25519::Pair::from_seed(&seed).public())),
+			(CryptoKind::Sr25519, seed) =>
+				Ok(sr25519::Pair::verify_weak(signature, msg, sr25519::Pair::from_seed(&seed).public())),
+		}
 	}
 
 	fn timestamp(&mut self) -> Timestamp {
-		unimplemented!("not needed in tests so far")
+		self.0.read().timestamp
 	}
 
 	fn sleep_until(&mut self, _deadline: Timestamp) {
@@ -30,6 +30,9 @@ use std::{pin::Pin, task::Context, task::Poll};
 const BANNED_THRESHOLD: i32 = 82 * (i32::min_value() / 100);
 /// Reputation change for a node when we get disconnected from it.
 const DISCONNECT_REPUTATION_CHANGE: i32 = -10;
+/// Reputation change for a node that doesn't fit in its set, so that we don't try it again while
+/// the other nodes are available.
+const SET_FULL_REPUTATION_CHANGE: i32 = -(1 << 10);
 /// Reserved peers group ID
 const RESERVED_NODES: &'static str = "reserved";
 
@@ -122,6 +125,62 @@ impl From<u64> for IncomingIndex {
 	}
 }
 
+/// Set a connected peer belongs to. Each set has its own number of slots.
+///
+/// The network hands block requests to reserved nodes and authorities before the other peers,
+/// and doesn't use light clients for sync or consensus gossip. Consensus gossip is otherwise sent
+/// to the peers of every set alike.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PeerSet {
+	/// Full nodes.
+	Full,
+	/// Light clients.
+	Light,
+	/// Nodes of the reserved list.
+	Reserved,
+	/// Nodes that announced themselves as authorities.
+	Authorities,
+}
+
+/// Maximum number of connected peers in each `PeerSet`.
+///
+/// Full nodes and light clients also occupy one of the `in_peers` or `out_peers` slots, while
+/// reserved nodes don't.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SetSlots {
+	/// Maximum number of full nodes.
+	pub full: u32,
+	/// Maximum number of light clients.
+	pub light: u32,
+	/// Maximum number of reserved nodes.
+	pub reserved: u32,
+	/// Maximum number of authorities. Authorities that don't fit are treated as full nodes.
+	pub authorities: u32,
+}
+
+impl SetSlots {
+	/// Returns the number of slots of the given set.
+	pub fn get(&self, set: PeerSet) -> u32 {
+		match set {
+			PeerSet::Full => self.full,
+			PeerSet::Light => self.light,
+			PeerSet::Reserved => self.reserved,
+			PeerSet::Authorities => self.authorities,
+		}
+	}
+}
+
+impl Default for SetSlots {
+	fn default() -> Self {
+		SetSlots {
+			full: u32::max_value(),
+			light: u32::max_value(),
+			reserved: u32::max_value(),
+			authorities: u32::max_value(),
+		}
+	}
+}
+
 /// Configuration to pass when creating the peer set manager.
 #[derive(Debug)]
 pub struct PeersetConfig {
@@ -145,6 +204,9 @@ pub struct PeersetConfig {
 	/// > **Note**: Keep in mind that the networking has to know an address for these nodes,
 	/// >			otherwise it will not be able to connect to them.
 	pub reserved_nodes: Vec<PeerId>,
+
+	/// Number of slots of each set of peers.
+	pub slots: SetSlots,
 }
 
 /// Side of the peer set manager owned by the network. In other words, the "receiving" side.
@@ -156,6 +218,10 @@ pub struct Peerset {
 	data: peersstate::PeersState,
 	/// If true, we only accept reserved nodes.
 	reserved_only: bool,
+	/// Number of slots of each set of peers.
+	slots: SetSlots,
+	/// Set that each peer has been admitted into. Only meaningful for connected peers.
+	sets: HashMap<PeerId, PeerSet>,
 	rx: Fuse<mpsc::UnboundedReceiver<Action>>,
 	message_queue: VecDeque<Message>,
 	/// When the `Peerset` was created.
@@ -177,6 +243,8 @@ impl Peerset {
 			data: peersstate::PeersState::new(config.in_peers, config.out_peers),
 			rx: rx.fuse(),
 			reserved_only: config.reserved_only,
+			slots: config.slots,
+			sets: HashMap::new(),
 			message_queue: VecDeque::new(),
 			created: Instant::now(),
 			latest_time_update: Instant::now(),
@@ -313,13 +381,18 @@ impl Peerset {
 	fn alloc_slots(&mut self) {
 		self.update_time();
 
+		// Reserved nodes beyond the number of reserved slots are left alone, and so are the other
+		// nodes if there is no slot left in the sets they can be admitted into.
+		let reserved_full = self.num_connected_reserved() >= self.slots.reserved;
+		let others_full = self.reserved_only ||
+			(self.is_set_full(PeerSet::Full) && self.is_set_full(PeerSet::Authorities));
+
 		// Try to grab the next node to attempt to connect to.
-		while let Some(next) = {
-			if self.reserved_only {
-				self.data.priority_not_connected_peer_from_group(RESERVED_NODES)
-			} else {
-				self.data.priority_not_connected_peer()
-			}
+		while let Some(next) = match (reserved_full, others_full) {
+			(true, true) => None,
+			(false, true) => self.data.priority_not_connected_peer_from_group(RESERVED_NODES),
+			(true, false) => self.data.priority_not_connected_peer_outside_group(RESERVED_NODES),
+			(false, false) => self.data.priority_not_connected_peer(),
 		} {
 			match next.try_outgoing() {
 				Ok(conn) => self.message_queue.push_back(Message::Connect(conn.into_peer_id())),
@@ -328,12 +401,17 @@ impl Peerset {
 		}
 
 		loop {
-			if self.reserved_only {
+			if others_full {
 				break
 			}
 
 			// Try to grab the next node to attempt to connect to.
-			let next = match self.data.highest_not_connected_peer() {
+			let next = if reserved_full {
+				self.data.highest_not_connected_peer_outside_group(RESERVED_NODES)
+			} else {
+				self.data.highest_not_connected_peer()
+			};
+			let next = match next {
 				Some(p) => p,
 				None => break,	// No known node to add.
 			};
@@ -350,6 +428,58 @@ impl Peerset {
 		}
 	}
 
+	/// Returns the number of reserved nodes we are connected to.
+	fn num_connected_reserved(&self) -> u32 {
+		let reserved = self.data.get_priority_group(RESERVED_NODES).unwrap_or_default();
+		self.data.connected_peers().filter(|peer_id| reserved.contains(*peer_id)).count() as u32
+	}
+
+	/// Returns the number of connected peers other than `except` that were admitted into `set`.
+	fn num_in_set(&self, set: PeerSet, except: Option<&PeerId>) -> u32 {
+		self.data.connected_peers()
+			.filter(|peer_id| Some(*peer_id) != except && self.sets.get(*peer_id) == Some(&set))
+			.count() as u32
+	}
+
+	/// Returns true if there is no slot left in `set`.
+	fn is_set_full(&self, set: PeerSet) -> bool {
+		self.num_in_set(set, None) >= self.slots.get(set)
+	}
+
+	/// Indicate which set a connected peer belongs to, once the network knows its role.
+	///
+	/// Reserved nodes are put in `PeerSet::Reserved` regardless of `set` as long as there is a
+	/// free reserved slot, and authorities that don't fit in their set are treated as full nodes.
+	///
+	/// Returns the set the peer has been admitted into, or `None` if there is no free slot for it,
+	/// in which case the connection should be dropped. The reputation of a peer that isn't
+	/// admitted is lowered, so that the nodes that fit are tried first.
+	pub fn set_peer_set(&mut self, peer_id: PeerId, set: PeerSet) -> Option<PeerSet> {
+		let is_reserved = self.data.get_priority_group(RESERVED_NODES)
+			.map_or(false, |reserved| reserved.contains(&peer_id));
+		let mut candidates = Vec::with_capacity(3);
+		if is_reserved {
+			candidates.push(PeerSet::Reserved);
+		}
+		match set {
+			PeerSet::Full | PeerSet::Reserved => candidates.push(PeerSet::Full),
+			PeerSet::Light => candidates.push(PeerSet::Light),
+			PeerSet::Authorities => candidates.extend(&[PeerSet::Authorities, PeerSet::Full]),
+		}
+
+		let admitted = candidates.into_iter()
+			.find(|set| self.num_in_set(*set, Some(&peer_id)) < self.slots.get(*set));
+		trace!(target: "peerset", "Admitting {:?} into {:?} set: {:?}", peer_id, set, admitted);
+		match admitted {
+			Some(set) => { self.sets.insert(peer_id, set); },
+			None => {
+				self.sets.remove(&peer_id);
+				self.on_report_peer(peer_id, SET_FULL_REPUTATION_CHANGE);
+			},
+		}
+		admitted
+	}
+
 	/// Indicate that we received an incoming connection. Must be answered either with
 	/// a corresponding `Accept` or `Reject`, except if we were already connected to this peer.
 	///
@@ -388,6 +518,7 @@ impl Peerset {
 	/// `PeerId`, or accepted an incoming connection with this `PeerId`.
 	pub fn dropped(&mut self, peer_id: PeerId) {
 		trace!(target: "peerset", "Dropping {:?}", peer_id);
+		self.sets.remove(&peer_id);
 
 		// We want reputations to be up-to-date before adjusting them.
 		self.update_time();
@@ -445,6 +576,9 @@ impl Peerset {
 
 				(peer_id.to_base58(), state)
 			}).collect::<HashMap<_, _>>(),
+			"sets": self.sets.iter()
+				.map(|(peer_id, set)| (peer_id.to_base58(), format!("{:?}", set)))
+				.collect::<HashMap<_, _>>(),
 			"reserved_only": self.reserved_only,
 			"message_queue": self.message_queue.len(),
 		})
@@ -525,6 +659,7 @@ mod tests {
 			bootnodes: vec![bootnode],
 			reserved_only: true,
 			reserved_nodes: Vec::new(),
+			slots: Default::default(),
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -553,6 +688,7 @@ mod tests {
 			bootnodes: vec![bootnode.clone()],
 			reserved_only: false,
 			reserved_nodes: Vec::new(),
+			slots: Default::default(),
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -580,6 +716,7 @@ mod tests {
 			bootnodes: vec![bootnode.clone()],
 			reserved_only: false,
 			reserved_nodes: vec![],
+			slots: Default::default(),
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -601,6 +738,7 @@ mod tests {
 			bootnodes: vec![],
 			reserved_only: false,
 			reserved_nodes: vec![],
+			slots: Default::default(),
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -633,5 +771,76 @@ mod tests {
 
 		futures::executor::block_on(fut);
 	}
-}
 
+	#[test]
+	fn test_peerset_reserved_slots() {
+		let reserved_peer = PeerId::random();
+		let reserved_peer2 = PeerId::random();
+		let (peerset, _handle) = Peerset::from_config(PeersetConfig {
+			in_peers: 0,
+			out_peers: 0,
+			bootnodes: vec![],
+			reserved_only: true,
+			reserved_nodes: vec![reserved_peer.clone(), reserved_peer2.clone()],
+			slots: SetSlots { reserved: 1, ..Default::default() },
+		});
+
+		let (message, peerset) = next_message(peerset).expect("expected message");
+		assert!(
+			message == Message::Connect(reserved_peer) ||
+			message == Message::Connect(reserved_peer2)
+		);
+		assert!(peerset.message_queue.is_empty());
+	}
+
+	#[test]
+	fn test_peerset_set_slots() {
+		let bootnode = PeerId::random();
+		let bootnode2 = PeerId::random();
+		let bootnode3 = PeerId::random();
+		let (mut peerset, _handle) = Peerset::from_config(PeersetConfig {
+			in_peers: 0,
+			out_peers: 3,
+			bootnodes: vec![bootnode.clone(), bootnode2.clone(), bootnode3.clone()],
+			reserved_only: false,
+			reserved_nodes: vec![],
+			slots: SetSlots { full: 1, light: 1, reserved: 0, authorities: 0 },
+		});
+
+		assert_eq!(peerset.set_peer_set(bootnode.clone(), PeerSet::Light), Some(PeerSet::Light));
+		assert_eq!(peerset.set_peer_set(bootnode2.clone(), PeerSet::Light), None);
+		// Authorities without a free slot are treated as full nodes.
+		assert_eq!(peerset.set_peer_set(bootnode2.clone(), PeerSet::Authorities), Some(PeerSet::Full));
+		assert_eq!(peerset.set_peer_set(bootnode3.clone(), PeerSet::Full), None);
+
+		// Dropping a peer frees its slot.
+		peerset.dropped(bootnode2);
+		assert_eq!(peerset.set_peer_set(bootnode3, PeerSet::Full), Some(PeerSet::Full));
+	}
+
+	#[test]
+	fn test_peerset_stops_dialing_when_sets_are_full() {
+		let bootnode = PeerId::random();
+		let bootnode2 = PeerId::random();
+		let (mut peerset, _handle) = Peerset::from_config(PeersetConfig {
+			in_peers: 0,
+			out_peers: 2,
+			bootnodes: vec![bootnode.clone(), bootnode2.clone()],
+			reserved_only: false,
+			reserved_nodes: vec![],
+			slots: SetSlots { full: 1, authorities: 0, ..Default::default() },
+		});
+
+		assert_eq!(peerset.set_peer_set(bootnode.clone(), PeerSet::Full), Some(PeerSet::Full));
+		assert_eq!(peerset.set_peer_set(bootnode2.clone(), PeerSet::Authorities), None);
+		assert!(peerset.data.peer(&bootnode2).into_connected().unwrap().reputation() < 0);
+
+		// The rejected node isn't dialed again while the full nodes set is full.
+		peerset.message_queue.clear();
+		peerset.dropped(bootnode2.clone());
+		assert!(peerset.message_queue.is_empty());
+
+		peerset.dropped(bootnode);
+		assert_eq!(peerset.message_queue.len(), 2);
+	}
+}
@@ -174,13 +174,48 @@ impl PeersState {
 		})
 	}
 
+	/// Returns the first priority peer that we are not connected to and that isn't part of the
+	/// given group.
+	///
+	/// If multiple nodes are prioritized, which one is returned is unspecified.
+	pub fn priority_not_connected_peer_outside_group(&mut self, group_id: &str) -> Option<NotConnectedPeer> {
+		let excluded = self.priority_nodes.get(group_id);
+		let id = self.priority_nodes.iter()
+			.filter(|(g, _)| *g != group_id)
+			.flat_map(|(_, group)| group.iter())
+			.filter(|id| excluded.map_or(true, |excluded| !excluded.contains(*id)))
+			.find(|id| self.nodes.get(id).map_or(false, |node| !node.connection_state.is_connected()))
+			.cloned();
+		id.map(move |id| NotConnectedPeer {
+			state: self,
+			peer_id: Cow::Owned(id),
+		})
+	}
+
 	/// Returns the peer with the highest reputation and that we are not connected to.
 	///
 	/// If multiple nodes have the same reputation, which one is returned is unspecified.
 	pub fn highest_not_connected_peer(&mut self) -> Option<NotConnectedPeer> {
+		self.highest_not_connected_peer_matching(|_| true)
+	}
+
+	/// Returns the peer with the highest reputation, that we are not connected to and that isn't
+	/// part of the given priority group.
+	///
+	/// If multiple nodes have the same reputation, which one is returned is unspecified.
+	pub fn highest_not_connected_peer_outside_group(&mut self, group_id: &str) -> Option<NotConnectedPeer> {
+		let excluded = self.priority_nodes.get(group_id).cloned().unwrap_or_default();
+		self.highest_not_connected_peer_matching(move |peer_id| !excluded.contains(peer_id))
+	}
+
+	fn highest_not_connected_peer_matching(
+		&mut self,
+		matches: impl Fn(&PeerId) -> bool,
+	) -> Option<NotConnectedPeer> {
 		let outcome = self.nodes
 			.iter_mut()
 			.filter(|(_, Node { connection_state, .. })| !connection_state.is_connected())
+			.filter(|(peer_id, _)| matches(*peer_id))
 			.fold(None::<(&PeerId, &mut Node)>, |mut cur_node, to_try| {
 				if let Some(cur_node) = cur_node.take() {
 					if cur_node.1.reputation >= to_try.1.reputation {
@@ -608,6 +643,39 @@ mod tests {
 		assert_eq!(peers_state.highest_not_connected_peer().map(|p| p.into_peer_id()), Some(id2.clone()));
 	}
 
+	#[test]
+	fn highest_not_connected_peer_outside_group() {
+		let mut peers_state = PeersState::new(25, 25);
+		let id1 = PeerId::random();
+		let id2 = PeerId::random();
+
+		peers_state.peer(&id1).into_unknown().unwrap().discover().set_reputation(50);
+		peers_state.peer(&id2).into_unknown().unwrap().discover().set_reputation(25);
+		peers_state.set_priority_group("reserved", vec![id1.clone()].into_iter().collect());
+		assert_eq!(
+			peers_state.highest_not_connected_peer_outside_group("reserved").map(|p| p.into_peer_id()),
+			Some(id2.clone())
+		);
+		peers_state.peer(&id2).into_not_connected().unwrap().try_outgoing().unwrap();
+		assert!(peers_state.highest_not_connected_peer_outside_group("reserved").is_none());
+	}
+
+	#[test]
+	fn priority_not_connected_peer_outside_group() {
+		let mut peers_state = PeersState::new(25, 25);
+		let id1 = PeerId::random();
+		let id2 = PeerId::random();
+
+		peers_state.set_priority_group("reserved", vec![id1.clone()].into_iter().collect());
+		peers_state.set_priority_group("other", vec![id1.clone(), id2.clone()].into_iter().collect());
+		assert_eq!(
+			peers_state.priority_not_connected_peer_outside_group("reserved").map(|p| p.into_peer_id()),
+			Some(id2.clone())
+		);
+		peers_state.peer(&id2).into_not_connected().unwrap().try_outgoing().unwrap();
+		assert!(peers_state.priority_not_connected_peer_outside_group("reserved").is_none());
+	}
+
 	#[test]
 	fn disconnect_priority_doesnt_panic() {
 		let mut peers_state = PeersState::new(1, 1);
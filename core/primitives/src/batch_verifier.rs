@@ -0,0 +1,184 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Batch verification of signatures.
+//!
+//! Signatures are queued while a block is executed and checked in parallel once the whole
+//! batch is known.
+//!
+//! Signatures that were already checked with `pre_verify`, e.g. by the import queue before the
+//! block is executed, are remembered and not verified a second time.
+
+use std::collections::HashSet;
+use parking_lot::Mutex;
+use parity_codec::Encode;
+use rayon::prelude::*;
+use crate::crypto::{key_types, KeyTypeId, Pair as _};
+use crate::{blake2_256, ed25519, sr25519};
+
+/// Maximum number of pre-verified signatures to remember.
+const MAX_PRE_VERIFIED: usize = 65536;
+
+lazy_static::lazy_static! {
+	static ref PRE_VERIFIED: Mutex<HashSet<[u8; 32]>> = Mutex::new(HashSet::new());
+}
+
+fn signature_key(id: KeyTypeId, sig: &[u8; 64], msg: &[u8], pubkey: &[u8; 32]) -> [u8; 32] {
+	(id, &sig[..], msg, &pubkey[..]).using_encoded(blake2_256)
+}
+
+/// Whether the signature was pre-verified. It is forgotten once it has been asked for.
+fn take_pre_verified(id: KeyTypeId, sig: &[u8; 64], msg: &[u8], pubkey: &[u8; 32]) -> bool {
+	let mut pre_verified = PRE_VERIFIED.lock();
+	!pre_verified.is_empty() && pre_verified.remove(&signature_key(id, sig, msg, pubkey))
+}
+
+/// Verify a signature ahead of block execution and remember it if it is valid, so that
+/// verifying it again while executing the block is free.
+pub fn pre_verify(id: KeyTypeId, sig: &[u8; 64], msg: &[u8], pubkey: &[u8; 32]) -> bool {
+	if !verify_now(id, sig, msg, pubkey) {
+		return false;
+	}
+
+	let mut pre_verified = PRE_VERIFIED.lock();
+	if pre_verified.len() >= MAX_PRE_VERIFIED {
+		pre_verified.clear();
+	}
+	pre_verified.insert(signature_key(id, sig, msg, pubkey));
+	true
+}
+
+/// A signature check waiting to be verified.
+enum PendingSignature {
+	Ed25519([u8; 64], Vec<u8>, [u8; 32]),
+	Sr25519([u8; 64], Vec<u8>, [u8; 32]),
+}
+
+impl PendingSignature {
+	fn verify(&self) -> bool {
+		match self {
+			PendingSignature::Ed25519(sig, msg, pubkey) =>
+				ed25519::Pair::verify_weak(&sig[..], msg, &pubkey[..]),
+			PendingSignature::Sr25519(sig, msg, pubkey) =>
+				sr25519::Pair::verify_weak(&sig[..], msg, &pubkey[..]),
+		}
+	}
+}
+
+/// Queue of signatures to verify together.
+#[derive(Default)]
+pub struct BatchVerifier {
+	pending: Vec<PendingSignature>,
+}
+
+impl BatchVerifier {
+	/// Create an empty batch.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Queue a signature of the given key type for verification.
+	///
+	/// Returns `false` if the key type can not be batch verified; such a signature is not queued.
+	pub fn push(&mut self, id: KeyTypeId, sig: &[u8; 64], msg: &[u8], pubkey: &[u8; 32]) -> bool {
+		if take_pre_verified(id, sig, msg, pubkey) {
+			return true;
+		}
+		let pending = match id {
+			key_types::ED25519 => PendingSignature::Ed25519(*sig, msg.to_vec(), *pubkey),
+			key_types::SR25519 => PendingSignature::Sr25519(*sig, msg.to_vec(), *pubkey),
+			_ => return false,
+		};
+		self.pending.push(pending);
+		true
+	}
+
+	/// Number of signatures waiting to be verified.
+	pub fn len(&self) -> usize {
+		self.pending.len()
+	}
+
+	/// Whether no signatures are waiting to be verified.
+	pub fn is_empty(&self) -> bool {
+		self.pending.is_empty()
+	}
+
+	/// Verify all queued signatures in parallel and clear the batch.
+	///
+	/// Returns `true` if all of them are valid.
+	pub fn verify_and_clear(&mut self) -> bool {
+		let pending = std::mem::replace(&mut self.pending, Vec::new());
+		pending.par_iter().all(PendingSignature::verify)
+	}
+}
+
+/// Verify a single signature of the given key type right away, unless it was pre-verified.
+///
+/// Returns `false` for key types that can not be batch verified.
+pub fn verify(id: KeyTypeId, sig: &[u8; 64], msg: &[u8], pubkey: &[u8; 32]) -> bool {
+	take_pre_verified(id, sig, msg, pubkey) || verify_now(id, sig, msg, pubkey)
+}
+
+fn verify_now(id: KeyTypeId, sig: &[u8; 64], msg: &[u8], pubkey: &[u8; 32]) -> bool {
+	match id {
+		key_types::ED25519 => ed25519::Pair::verify_weak(&sig[..], msg, &pubkey[..]),
+		key_types::SR25519 => sr25519::Pair::verify_weak(&sig[..], msg, &pubkey[..]),
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn batch_should_verify_all_signatures() {
+		let ed = ed25519::Pair::from_string("//Alice", None).unwrap();
+		let sr = sr25519::Pair::from_string("//Bob", None).unwrap();
+
+		let mut batch = BatchVerifier::new();
+		assert!(batch.push(key_types::ED25519, &ed.sign(b"one").0, b"one", &ed.public().0));
+		assert!(batch.push(key_types::SR25519, &sr.sign(b"two").0, b"two", &sr.public().0));
+		assert!(!batch.push(key_types::ECDSA, &[0u8; 64], b"three", &[0u8; 32]));
+		assert_eq!(batch.len(), 2);
+		assert!(batch.verify_and_clear());
+		assert!(batch.is_empty());
+
+		assert!(batch.push(key_types::ED25519, &ed.sign(b"one").0, b"one", &ed.public().0));
+		assert!(batch.push(key_types::SR25519, &sr.sign(b"two").0, b"three", &sr.public().0));
+		assert!(!batch.verify_and_clear());
+		assert!(batch.is_empty());
+	}
+
+	#[test]
+	fn pre_verified_signatures_are_not_queued() {
+		let pair = ed25519::Pair::from_string("//Charlie", None).unwrap();
+		let sig = pair.sign(b"pre-verified").0;
+
+		assert!(!pre_verify(key_types::ED25519, &sig, b"tampered", &pair.public().0));
+		assert!(pre_verify(key_types::ED25519, &sig, b"pre-verified", &pair.public().0));
+
+		let mut batch = BatchVerifier::new();
+		assert!(batch.push(key_types::ED25519, &sig, b"pre-verified", &pair.public().0));
+		assert!(batch.is_empty());
+
+		// Pre-verification is only used once.
+		assert!(batch.push(key_types::ED25519, &sig, b"pre-verified", &pair.public().0));
+		assert_eq!(batch.len(), 1);
+		assert!(batch.verify_and_clear());
+	}
+}
@@ -0,0 +1,511 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+// tag::description[]
+//! Simple BLS (BLS12-381) API with signature aggregation.
+// end::description[]
+//!
+//! Aggregated signatures must be over distinct messages. Checking several signatures of the same
+//! message would mean aggregating the public keys, which is only safe if every signer proved
+//! possession of its secret key; otherwise a rogue key can forge the aggregate. Signatures
+//! aggregating a repeated message are therefore always rejected.
+
+use rstd::cmp::Ordering;
+use parity_codec::{Encode, Decode, Input, Output};
+
+#[cfg(feature = "std")]
+use bls_signatures::Serialize as _;
+#[cfg(feature = "std")]
+use substrate_bip39::seed_from_entropy;
+#[cfg(feature = "std")]
+use bip39::{Mnemonic, Language, MnemonicType};
+#[cfg(feature = "std")]
+use crate::{hashing::blake2_256, crypto::{Pair as TraitPair, DeriveJunction, SecretStringError, Derive, Ss58Codec}};
+#[cfg(feature = "std")]
+use serde::{de, Serializer, Serialize, Deserializer, Deserialize};
+use crate::crypto::{key_types, KeyTypeId, Public as TraitPublic, TypedKey, UncheckedFrom};
+
+/// The length of a compressed public key (a G1 point).
+pub const PUBLIC_KEY_LENGTH: usize = 48;
+
+/// The length of a compressed signature (a G2 point).
+pub const SIGNATURE_LENGTH: usize = 96;
+
+/// A secret seed from which the secret key is derived deterministically.
+#[cfg(feature = "std")]
+type Seed = [u8; 32];
+
+/// A compressed public key.
+pub struct Public(pub [u8; PUBLIC_KEY_LENGTH]);
+
+/// A compressed signature, which may be an aggregate of several signatures.
+pub struct Signature(pub [u8; SIGNATURE_LENGTH]);
+
+macro_rules! impl_byte_array_traits {
+	($name:ident, $len:expr) => {
+		impl Clone for $name {
+			fn clone(&self) -> Self {
+				$name(self.0)
+			}
+		}
+
+		impl Default for $name {
+			fn default() -> Self {
+				$name([0u8; $len])
+			}
+		}
+
+		impl PartialEq for $name {
+			fn eq(&self, b: &Self) -> bool {
+				&self.0[..] == &b.0[..]
+			}
+		}
+
+		impl Eq for $name {}
+
+		impl Encode for $name {
+			fn encode_to<T: Output>(&self, dest: &mut T) {
+				dest.write(&self.0[..])
+			}
+		}
+
+		impl Decode for $name {
+			fn decode<I: Input>(input: &mut I) -> Option<Self> {
+				let mut r = [0u8; $len];
+				if input.read(&mut r[..]) != $len {
+					return None;
+				}
+				Some($name(r))
+			}
+		}
+
+		impl AsRef<[u8]> for $name {
+			fn as_ref(&self) -> &[u8] {
+				&self.0[..]
+			}
+		}
+
+		impl AsMut<[u8]> for $name {
+			fn as_mut(&mut self) -> &mut [u8] {
+				&mut self.0[..]
+			}
+		}
+
+		impl From<$name> for [u8; $len] {
+			fn from(x: $name) -> Self {
+				x.0
+			}
+		}
+
+		#[cfg(feature = "std")]
+		impl ::std::hash::Hash for $name {
+			fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+				self.0[..].hash(state);
+			}
+		}
+	}
+}
+
+impl_byte_array_traits!(Public, PUBLIC_KEY_LENGTH);
+impl_byte_array_traits!(Signature, SIGNATURE_LENGTH);
+
+impl PartialOrd for Public {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Public {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.0[..].cmp(&other.0[..])
+	}
+}
+
+impl AsRef<Public> for Public {
+	fn as_ref(&self) -> &Public {
+		&self
+	}
+}
+
+impl UncheckedFrom<[u8; PUBLIC_KEY_LENGTH]> for Public {
+	fn unchecked_from(x: [u8; PUBLIC_KEY_LENGTH]) -> Self {
+		Public(x)
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::fmt::Display for Public {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		write!(f, "{}", self.to_ss58check())
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::fmt::Debug for Public {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		let s = self.to_ss58check();
+		write!(f, "{} ({}...)", crate::hexdisplay::HexDisplay::from(&&self.0[..]), &s[0..8])
+	}
+}
+
+#[cfg(feature = "std")]
+impl Serialize for Public {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		serializer.serialize_str(&self.to_ss58check())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Public {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		Public::from_ss58check(&String::deserialize(deserializer)?)
+			.map_err(|e| de::Error::custom(format!("{:?}", e)))
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::fmt::Debug for Signature {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		write!(f, "{}", crate::hexdisplay::HexDisplay::from(&&self.0[..]))
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<Pair> for Public {
+	fn from(x: Pair) -> Self {
+		x.public()
+	}
+}
+
+impl TraitPublic for Public {
+	/// A new instance from the given slice that should be 48 bytes long.
+	///
+	/// NOTE: No checking goes on to ensure this is a real public key. Only use it if
+	/// you are certain that the array actually is a pubkey. GIGO!
+	fn from_slice(data: &[u8]) -> Self {
+		let mut r = [0u8; PUBLIC_KEY_LENGTH];
+		r.copy_from_slice(data);
+		Public(r)
+	}
+
+	/// Return a `Vec<u8>` filled with raw data.
+	#[cfg(feature = "std")]
+	fn to_raw_vec(&self) -> Vec<u8> {
+		self.0.to_vec()
+	}
+
+	/// Return a slice filled with raw data.
+	fn as_slice(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+#[cfg(feature = "std")]
+impl Derive for Public {}
+
+#[cfg(feature = "std")]
+impl Public {
+	fn to_bls(&self) -> Option<bls_signatures::PublicKey> {
+		bls_signatures::PublicKey::from_bytes(&self.0[..]).ok()
+	}
+}
+
+impl Signature {
+	/// A new instance from the given slice that should be 96 bytes long.
+	///
+	/// NOTE: No checking goes on to ensure this is a real signature. Only use it if
+	/// you are certain that the array actually is a signature. GIGO!
+	pub fn from_slice(data: &[u8]) -> Self {
+		let mut r = [0u8; SIGNATURE_LENGTH];
+		r.copy_from_slice(data);
+		Signature(r)
+	}
+
+	#[cfg(feature = "std")]
+	fn to_bls(&self) -> Option<bls_signatures::Signature> {
+		bls_signatures::Signature::from_bytes(&self.0[..]).ok()
+	}
+
+	#[cfg(feature = "std")]
+	fn from_bls(signature: &bls_signatures::Signature) -> Self {
+		Signature::from_slice(&signature.as_bytes())
+	}
+
+	/// Aggregate the given signatures into a single signature.
+	///
+	/// Returns `None` if no signatures are given or any of them is not a valid point.
+	#[cfg(feature = "std")]
+	pub fn aggregate<'a, I: IntoIterator<Item=&'a Signature>>(signatures: I) -> Option<Signature> {
+		let signatures = signatures.into_iter()
+			.map(Signature::to_bls)
+			.collect::<Option<Vec<_>>>()?;
+		if signatures.is_empty() {
+			return None;
+		}
+		bls_signatures::aggregate(&signatures).ok().map(|s| Signature::from_bls(&s))
+	}
+
+	/// Verify an aggregated signature over the given messages and their signers.
+	///
+	/// All messages must be distinct, otherwise the signature is rejected even if every signer
+	/// signed it. See the module documentation for why.
+	#[cfg(feature = "std")]
+	pub fn verify_aggregate<M: AsRef<[u8]>>(&self, signed: &[(M, Public)]) -> bool {
+		if signed.is_empty() {
+			return false;
+		}
+		let mut seen = ::std::collections::HashSet::new();
+		if !signed.iter().all(|(message, _)| seen.insert(message.as_ref())) {
+			return false;
+		}
+		let signature = match self.to_bls() {
+			Some(signature) => signature,
+			None => return false,
+		};
+		let public_keys = match signed.iter().map(|(_, public)| public.to_bls()).collect::<Option<Vec<_>>>() {
+			Some(public_keys) => public_keys,
+			None => return false,
+		};
+		let messages = signed.iter().map(|(message, _)| message.as_ref()).collect::<Vec<_>>();
+		bls_signatures::verify_messages(&signature, &messages, &public_keys)
+	}
+}
+
+/// A key pair.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct Pair {
+	secret: bls_signatures::PrivateKey,
+	seed: Seed,
+}
+
+#[cfg(feature = "std")]
+impl AsRef<Pair> for Pair {
+	fn as_ref(&self) -> &Pair {
+		&self
+	}
+}
+
+/// Derive a single hard junction.
+#[cfg(feature = "std")]
+fn derive_hard_junction(secret_seed: &Seed, cc: &[u8; 32]) -> Seed {
+	("BLS12381HDKD", secret_seed, cc).using_encoded(|data| blake2_256(data))
+}
+
+/// An error when deriving a key.
+#[cfg(feature = "std")]
+pub enum DeriveError {
+	/// A soft key was found in the path (and is unsupported).
+	SoftKeyInPath,
+}
+
+#[cfg(feature = "std")]
+impl TraitPair for Pair {
+	type Public = Public;
+	type Seed = Seed;
+	type Signature = Signature;
+	type DeriveError = DeriveError;
+
+	/// Generate new secure (random) key pair and provide the recovery phrase.
+	///
+	/// You can recover the same key later with `from_phrase`.
+	fn generate_with_phrase(password: Option<&str>) -> (Pair, String, Seed) {
+		let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
+		let phrase = mnemonic.phrase();
+		let (pair, seed) = Self::from_phrase(phrase, password)
+			.expect("All phrases generated by Mnemonic are valid; qed");
+		(
+			pair,
+			phrase.to_owned(),
+			seed,
+		)
+	}
+
+	/// Generate key pair from given recovery phrase and password.
+	fn from_phrase(phrase: &str, password: Option<&str>) -> Result<(Pair, Seed), SecretStringError> {
+		let big_seed = seed_from_entropy(
+			Mnemonic::from_phrase(phrase, Language::English)
+				.map_err(|_| SecretStringError::InvalidPhrase)?.entropy(),
+			password.unwrap_or(""),
+		).map_err(|_| SecretStringError::InvalidSeed)?;
+		let mut seed = Seed::default();
+		seed.copy_from_slice(&big_seed[0..32]);
+		Self::from_seed_slice(&big_seed[0..32]).map(|x| (x, seed))
+	}
+
+	/// Make a new key pair from secret seed material.
+	///
+	/// You should never need to use this; generate(), generate_with_phrase
+	fn from_seed(seed: &Seed) -> Pair {
+		Pair {
+			secret: bls_signatures::PrivateKey::new(&seed[..]),
+			seed: *seed,
+		}
+	}
+
+	/// Make a new key pair from secret seed material. The slice must be 32 bytes long or it
+	/// will return an error.
+	///
+	/// You should never need to use this; generate(), generate_with_phrase
+	fn from_seed_slice(seed_slice: &[u8]) -> Result<Pair, SecretStringError> {
+		if seed_slice.len() != 32 {
+			return Err(SecretStringError::InvalidSeedLength);
+		}
+		let mut seed = Seed::default();
+		seed.copy_from_slice(seed_slice);
+		Ok(Self::from_seed(&seed))
+	}
+
+	/// Derive a child key from a series of given junctions.
+	fn derive<Iter: Iterator<Item=DeriveJunction>>(&self, path: Iter) -> Result<Pair, DeriveError> {
+		let mut acc = self.seed;
+		for j in path {
+			match j {
+				DeriveJunction::Soft(_cc) => return Err(DeriveError::SoftKeyInPath),
+				DeriveJunction::Hard(cc) => acc = derive_hard_junction(&acc, &cc),
+			}
+		}
+		Ok(Self::from_seed(&acc))
+	}
+
+	/// Generate a key from the phrase, password and derivation path.
+	fn from_standard_components<I: Iterator<Item=DeriveJunction>>(
+		phrase: &str,
+		password: Option<&str>,
+		path: I
+	) -> Result<Pair, SecretStringError> {
+		Self::from_phrase(phrase, password)?.0
+			.derive(path)
+			.map_err(|_| SecretStringError::InvalidPath)
+	}
+
+	/// Get the public key.
+	fn public(&self) -> Public {
+		Public::from_slice(&self.secret.public_key().as_bytes())
+	}
+
+	/// Sign a message.
+	fn sign(&self, message: &[u8]) -> Signature {
+		Signature::from_bls(&self.secret.sign(message))
+	}
+
+	/// Verify a signature on a message. Returns true if the signature is good.
+	fn verify<P: AsRef<Self::Public>, M: AsRef<[u8]>>(sig: &Self::Signature, message: M, pubkey: P) -> bool {
+		sig.verify_aggregate(&[(message.as_ref(), pubkey.as_ref().clone())])
+	}
+
+	/// Verify a signature on a message. Returns true if the signature is good.
+	///
+	/// This doesn't use the type system to ensure that `sig` and `pubkey` are the correct
+	/// size. Use it only if you're coming from byte buffers and need the speed.
+	fn verify_weak<P: AsRef<[u8]>, M: AsRef<[u8]>>(sig: &[u8], message: M, pubkey: P) -> bool {
+		if sig.len() != SIGNATURE_LENGTH || pubkey.as_ref().len() != PUBLIC_KEY_LENGTH {
+			return false;
+		}
+		Self::verify(&Signature::from_slice(sig), message, Public::from_slice(pubkey.as_ref()))
+	}
+
+	/// Return a vec filled with raw data.
+	fn to_raw_vec(&self) -> Vec<u8> {
+		self.seed.to_vec()
+	}
+}
+
+#[cfg(feature = "std")]
+impl Pair {
+	/// Get the seed for this key.
+	pub fn seed(&self) -> &Seed {
+		&self.seed
+	}
+}
+
+impl TypedKey for Public {
+	const KEY_TYPE: KeyTypeId = key_types::BLS12_381;
+}
+
+impl TypedKey for Signature {
+	const KEY_TYPE: KeyTypeId = key_types::BLS12_381;
+}
+
+#[cfg(feature = "std")]
+impl TypedKey for Pair {
+	const KEY_TYPE: KeyTypeId = key_types::BLS12_381;
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::crypto::DEV_PHRASE;
+
+	#[test]
+	fn default_phrase_should_be_used() {
+		assert_eq!(
+			Pair::from_string("//Alice///password", None).unwrap().public(),
+			Pair::from_string(&format!("{}//Alice", DEV_PHRASE), Some("password")).unwrap().public(),
+		);
+	}
+
+	#[test]
+	fn seeded_pair_is_deterministic() {
+		let seed = [7u8; 32];
+		assert_eq!(Pair::from_seed(&seed).public(), Pair::from_seed(&seed).public());
+		assert!(Pair::from_seed(&seed).derive(vec![DeriveJunction::soft(1)].into_iter()).is_err());
+	}
+
+	#[test]
+	fn generated_pair_should_work() {
+		let (pair, _) = Pair::generate();
+		let public = pair.public();
+		let message = b"Something important";
+		let signature = pair.sign(&message[..]);
+		assert!(Pair::verify(&signature, &message[..], &public));
+		assert!(Pair::verify_weak(&signature.0[..], &message[..], &public.0[..]));
+		assert!(!Pair::verify(&signature, b"Something else", &public));
+	}
+
+	#[test]
+	fn aggregated_signature_should_work() {
+		let alice = Pair::from_string("//Alice", None).unwrap();
+		let bob = Pair::from_string("//Bob", None).unwrap();
+
+		let signatures = [alice.sign(b"alice"), bob.sign(b"bob")];
+		let aggregate = Signature::aggregate(signatures.iter()).unwrap();
+
+		assert!(aggregate.verify_aggregate(&[(&b"alice"[..], alice.public()), (&b"bob"[..], bob.public())]));
+		assert!(!aggregate.verify_aggregate(&[(&b"alice"[..], alice.public()), (&b"bob"[..], alice.public())]));
+		assert!(Signature::aggregate(&[]).is_none());
+
+	}
+
+	#[test]
+	fn aggregated_signature_over_a_repeated_message_is_rejected() {
+		let alice = Pair::from_string("//Alice", None).unwrap();
+		let bob = Pair::from_string("//Bob", None).unwrap();
+
+		// both signatures are valid on their own.
+		let signatures = [alice.sign(b"same"), bob.sign(b"same")];
+		assert!(Pair::verify(&signatures[0], b"same", &alice.public()));
+		assert!(Pair::verify(&signatures[1], b"same", &bob.public()));
+
+		let repeated = Signature::aggregate(signatures.iter()).unwrap();
+		assert!(!repeated.verify_aggregate(&[(&b"same"[..], alice.public()), (&b"same"[..], bob.public())]));
+
+		// the same message signed twice by the same signer is rejected as well.
+		let doubled = Signature::aggregate(&[alice.sign(b"same"), alice.sign(b"same")]).unwrap();
+		assert!(!doubled.verify_aggregate(&[(&b"same"[..], alice.public()), (&b"same"[..], alice.public())]));
+	}
+}
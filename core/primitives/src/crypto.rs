@@ -24,7 +24,6 @@ use std::convert::{TryFrom, TryInto};
 use parking_lot::Mutex;
 #[cfg(feature = "std")]
 use rand::{RngCore, rngs::OsRng};
-#[cfg(feature = "std")]
 use parity_codec::{Encode, Decode};
 #[cfg(feature = "std")]
 use regex::Regex;
@@ -307,83 +306,111 @@ lazy_static::lazy_static! {
 		= Mutex::new(Ss58AddressFormat::SubstrateAccountDirect);
 }
 
-/// A known address (sub)format/network ID for SS58.
 #[cfg(feature = "std")]
-#[derive(Copy, Clone, PartialEq, Eq)]
-pub enum Ss58AddressFormat {
-	/// Any Substrate network, direct checksum, standard account (*25519).
-	SubstrateAccountDirect,
-	/// Polkadot Relay-chain, direct checksum, standard account (*25519).
-	PolkadotAccountDirect,
-	/// Kusama Relay-chain, direct checksum, standard account (*25519).
-	KusamaAccountDirect,
-	/// Use a manually provided numeric value.
-	Custom(u8),
-}
+macro_rules! ss58_address_format {
+	( $( $identifier:ident => ($number:literal, $name:literal, $desc:literal) )* ) => (
+		/// A known address (sub)format/network ID for SS58.
+		#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+		pub enum Ss58AddressFormat {
+			$(#[doc = $desc] $identifier),*,
+			/// Use a manually provided numeric value.
+			Custom(u8),
+		}
 
-#[cfg(feature = "std")]
-impl From<Ss58AddressFormat> for u8 {
-	fn from(x: Ss58AddressFormat) -> u8 {
-		match x {
-			Ss58AddressFormat::SubstrateAccountDirect => 42,
-			Ss58AddressFormat::PolkadotAccountDirect => 0,
-			Ss58AddressFormat::KusamaAccountDirect => 2,
-			Ss58AddressFormat::Custom(n) => n,
+		impl Ss58AddressFormat {
+			/// All known address formats.
+			pub fn all() -> &'static [Ss58AddressFormat] {
+				&[$(Ss58AddressFormat::$identifier),*]
+			}
+
+			/// The names of all known address formats.
+			pub fn all_names() -> &'static [&'static str] {
+				&[$($name),*]
+			}
 		}
-	}
-}
 
-#[cfg(feature = "std")]
-impl TryFrom<u8> for Ss58AddressFormat {
-	type Error = ();
-	fn try_from(x: u8) -> Result<Ss58AddressFormat, ()> {
-		match x {
-			42 => Ok(Ss58AddressFormat::SubstrateAccountDirect),
-			0 => Ok(Ss58AddressFormat::PolkadotAccountDirect),
-			2 => Ok(Ss58AddressFormat::KusamaAccountDirect),
-			_ => Err(()),
+		impl From<Ss58AddressFormat> for u8 {
+			fn from(x: Ss58AddressFormat) -> u8 {
+				match x {
+					$(Ss58AddressFormat::$identifier => $number),*,
+					Ss58AddressFormat::Custom(n) => n,
+				}
+			}
 		}
-	}
-}
 
-#[cfg(feature = "std")]
-impl<'a> TryFrom<&'a str> for Ss58AddressFormat {
-	type Error = ();
-	fn try_from(x: &'a str) -> Result<Ss58AddressFormat, ()> {
-		match x {
-			"substrate" => Ok(Ss58AddressFormat::SubstrateAccountDirect),
-			"polkadot" => Ok(Ss58AddressFormat::PolkadotAccountDirect),
-			"kusama" => Ok(Ss58AddressFormat::KusamaAccountDirect),
-			a => a.parse::<u8>().map(Ss58AddressFormat::Custom).map_err(|_| ()),
+		impl TryFrom<u8> for Ss58AddressFormat {
+			type Error = ();
+
+			fn try_from(x: u8) -> Result<Ss58AddressFormat, ()> {
+				match x {
+					$($number => Ok(Ss58AddressFormat::$identifier)),*,
+					n if n < MAX_SS58_PREFIX => Ok(Ss58AddressFormat::Custom(n)),
+					_ => Err(()),
+				}
+			}
 		}
-	}
-}
 
-#[cfg(feature = "std")]
-impl From<Ss58AddressFormat> for String {
-	fn from(x: Ss58AddressFormat) -> String {
-		match x {
-			Ss58AddressFormat::SubstrateAccountDirect => "substrate".into(),
-			Ss58AddressFormat::PolkadotAccountDirect => "polkadot".into(),
-			Ss58AddressFormat::KusamaAccountDirect => "kusama".into(),
-			Ss58AddressFormat::Custom(x) => x.to_string(),
+		impl<'a> TryFrom<&'a str> for Ss58AddressFormat {
+			type Error = ();
+
+			fn try_from(x: &'a str) -> Result<Ss58AddressFormat, ()> {
+				match x {
+					$($name => Ok(Ss58AddressFormat::$identifier)),*,
+					a => a.parse::<u8>().map_err(|_| ()).and_then(TryFrom::try_from),
+				}
+			}
 		}
-	}
+
+		impl From<Ss58AddressFormat> for String {
+			fn from(x: Ss58AddressFormat) -> String {
+				match x {
+					$(Ss58AddressFormat::$identifier => $name.into()),*,
+					Ss58AddressFormat::Custom(x) => x.to_string(),
+				}
+			}
+		}
+	)
 }
 
+/// Prefixes from this value on are reserved for multi-byte prefixes, which are not supported.
+#[cfg(feature = "std")]
+const MAX_SS58_PREFIX: u8 = 64;
+
+#[cfg(feature = "std")]
+ss58_address_format!(
+	SubstrateAccountDirect =>
+		(42, "substrate", "Any Substrate network, direct checksum, standard account (*25519).")
+	PolkadotAccountDirect =>
+		(0, "polkadot", "Polkadot Relay-chain, direct checksum, standard account (*25519).")
+	KusamaAccountDirect =>
+		(2, "kusama", "Kusama Relay-chain, direct checksum, standard account (*25519).")
+	EdgewareAccountDirect =>
+		(7, "edgeware", "Edgeware mainnet, direct checksum, standard account (*25519).")
+	KulupuAccountDirect =>
+		(16, "kulupu", "Kulupu mainnet, direct checksum, standard account (*25519).")
+	DothereumAccountDirect =>
+		(20, "dothereum", "Dothereum Para-chain, direct checksum, standard account (*25519).")
+	CentrifugeAccountDirect =>
+		(36, "centrifuge", "Centrifuge Chain mainnet, direct checksum, standard account (*25519).")
+);
+
 /// Set the default "version" (actually, this is a bit of a misnomer and the version byte is
 /// typically used not just to encode format/version but also network identity) that is used for
 /// encoding and decoding SS58 addresses. If an unknown version is provided then it fails.
 ///
-/// Current known "versions" are:
-/// - 0 direct (payload) checksum for 32-byte *25519 Polkadot addresses.
-/// - 2 direct (payload) checksum for 32-byte *25519 Polkadot Milestone 'K' addresses.
-/// - 42 direct (payload) checksum for 32-byte *25519 addresses on any Substrate-based network.
+/// The known "versions" are listed in `Ss58AddressFormat`; any other single-byte prefix (below
+/// 64) may be given as `Ss58AddressFormat::Custom`.
 #[cfg(feature = "std")]
 pub fn set_default_ss58_version(version: Ss58AddressFormat) {
 	*DEFAULT_VERSION.lock() = version
 }
 
+/// Returns the default "version" that is used for encoding and decoding SS58 addresses.
+#[cfg(feature = "std")]
+pub fn default_ss58_version() -> Ss58AddressFormat {
+	*DEFAULT_VERSION.lock()
+}
+
 #[cfg(feature = "std")]
 impl<T: AsMut<[u8]> + AsRef<[u8]> + Default + Derive> Ss58Codec for T {
 	fn from_ss58check_with_version(s: &str) -> Result<(Self, Ss58AddressFormat), PublicError> {
@@ -617,6 +644,12 @@ pub mod key_types {
 
 	/// SR25519 public key.
 	pub const SR25519: KeyTypeId = 20;
+
+	/// ECDSA (secp256k1) compressed public key.
+	pub const ECDSA: KeyTypeId = 30;
+
+	/// BLS12-381 public key.
+	pub const BLS12_381: KeyTypeId = 40;
 }
 
 /// A trait for something that has a key type ID.
@@ -625,6 +658,117 @@ pub trait TypedKey {
 	const KEY_TYPE: KeyTypeId;
 }
 
+/// An opaque 32-byte cryptographic identifier.
+///
+/// This is used as account id by runtimes that accept several signature schemes, see
+/// `MultiSigner` in `sr-primitives`.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Default, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Hash))]
+pub struct AccountId32([u8; 32]);
+
+impl UncheckedFrom<crate::hash::H256> for AccountId32 {
+	fn unchecked_from(h: crate::hash::H256) -> Self {
+		AccountId32(h.into())
+	}
+}
+
+impl AsRef<[u8]> for AccountId32 {
+	fn as_ref(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+impl AsMut<[u8]> for AccountId32 {
+	fn as_mut(&mut self) -> &mut [u8] {
+		&mut self.0[..]
+	}
+}
+
+impl AsRef<[u8; 32]> for AccountId32 {
+	fn as_ref(&self) -> &[u8; 32] {
+		&self.0
+	}
+}
+
+impl From<[u8; 32]> for AccountId32 {
+	fn from(x: [u8; 32]) -> AccountId32 {
+		AccountId32(x)
+	}
+}
+
+impl From<AccountId32> for [u8; 32] {
+	fn from(x: AccountId32) -> [u8; 32] {
+		x.0
+	}
+}
+
+impl From<crate::sr25519::Public> for AccountId32 {
+	fn from(k: crate::sr25519::Public) -> Self {
+		k.0.into()
+	}
+}
+
+impl From<crate::ed25519::Public> for AccountId32 {
+	fn from(k: crate::ed25519::Public) -> Self {
+		k.0.into()
+	}
+}
+
+#[cfg(feature = "std")]
+impl Derive for AccountId32 {}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for AccountId32 {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.to_ss58check())
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Debug for AccountId32 {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let s = self.to_ss58check();
+		write!(f, "{} ({}...)", crate::hexdisplay::HexDisplay::from(&self.0), &s[0..8])
+	}
+}
+
+#[cfg(feature = "std")]
+impl serde::Serialize for AccountId32 {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: serde::Serializer {
+		serializer.serialize_str(&self.to_ss58check())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> serde::Deserialize<'de> for AccountId32 {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: serde::Deserializer<'de> {
+		AccountId32::from_ss58check(&<String as serde::Deserialize>::deserialize(deserializer)?)
+			.map_err(|e| serde::de::Error::custom(format!("{:?}", e)))
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::str::FromStr for AccountId32 {
+	type Err = &'static str;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let hex_or_ss58_without_prefix = s.trim_start_matches("0x");
+		if hex_or_ss58_without_prefix.len() == 64 {
+			let mut bytes = [0u8; 32];
+			hex::decode(hex_or_ss58_without_prefix)
+				.ok()
+				.filter(|decoded| decoded.len() == 32)
+				.map(|decoded| {
+					bytes.copy_from_slice(&decoded);
+					AccountId32(bytes)
+				})
+				.ok_or("invalid hex address.")
+		} else {
+			Self::from_ss58check(s).map_err(|_| "invalid ss58 address.")
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::DeriveJunction;
@@ -788,4 +932,27 @@ mod tests {
 			Ok(TestPair::Standard{phrase: "hello world".to_owned(), password: Some("password".to_owned()), path: vec![DeriveJunction::soft(1), DeriveJunction::hard("DOT")]})
 		);
 	}
+
+	#[test]
+	fn account_id_32_parses_from_ss58_and_hex() {
+		use std::str::FromStr;
+
+		let account = AccountId32::from([1u8; 32]);
+		assert_eq!(AccountId32::from_str(&account.to_ss58check()), Ok(account.clone()));
+		assert_eq!(AccountId32::from_str(&format!("0x{}", hex::encode([1u8; 32]))), Ok(account));
+		assert!(AccountId32::from_str("0x01").is_err());
+	}
+
+	#[test]
+	fn ss58_address_format_conversions_work() {
+		for format in Ss58AddressFormat::all() {
+			assert_eq!(Ss58AddressFormat::try_from(u8::from(*format)), Ok(*format));
+			assert_eq!(Ss58AddressFormat::try_from(String::from(*format).as_str()), Ok(*format));
+		}
+		assert_eq!(Ss58AddressFormat::try_from("42"), Ok(Ss58AddressFormat::SubstrateAccountDirect));
+		assert_eq!(Ss58AddressFormat::try_from(5u8), Ok(Ss58AddressFormat::Custom(5)));
+		assert_eq!(Ss58AddressFormat::try_from("5"), Ok(Ss58AddressFormat::Custom(5)));
+		assert_eq!(Ss58AddressFormat::try_from(64u8), Err(()));
+		assert_eq!(Ss58AddressFormat::try_from("unknown"), Err(()));
+	}
 }
@@ -0,0 +1,569 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+// tag::description[]
+//! Simple ECDSA API (secp256k1), using compressed public keys.
+// end::description[]
+
+use rstd::cmp::Ordering;
+use parity_codec::{Encode, Decode, Input, Output};
+
+#[cfg(feature = "std")]
+use substrate_bip39::seed_from_entropy;
+#[cfg(feature = "std")]
+use bip39::{Mnemonic, Language, MnemonicType};
+#[cfg(feature = "std")]
+use crate::{hashing::blake2_256, crypto::{Pair as TraitPair, DeriveJunction, SecretStringError, Derive, Ss58Codec}};
+#[cfg(feature = "std")]
+use serde::{de, Serializer, Serialize, Deserializer, Deserialize};
+use crate::crypto::{key_types, KeyTypeId, Public as TraitPublic, TypedKey, UncheckedFrom};
+
+/// The length of a compressed public key.
+pub const PUBLIC_KEY_LENGTH: usize = 33;
+
+/// The length of a recoverable signature.
+pub const SIGNATURE_LENGTH: usize = 65;
+
+/// A secret seed, which is the raw secret key.
+#[cfg(feature = "std")]
+type Seed = [u8; 32];
+
+/// A compressed public key.
+pub struct Public(pub [u8; PUBLIC_KEY_LENGTH]);
+
+impl Clone for Public {
+	fn clone(&self) -> Self {
+		Public(self.0)
+	}
+}
+
+impl Default for Public {
+	fn default() -> Self {
+		Public([0u8; PUBLIC_KEY_LENGTH])
+	}
+}
+
+impl PartialEq for Public {
+	fn eq(&self, b: &Self) -> bool {
+		&self.0[..] == &b.0[..]
+	}
+}
+
+impl Eq for Public {}
+
+impl PartialOrd for Public {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Public {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.0[..].cmp(&other.0[..])
+	}
+}
+
+impl Encode for Public {
+	fn encode_to<T: Output>(&self, dest: &mut T) {
+		dest.write(&self.0[..])
+	}
+}
+
+impl Decode for Public {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		let mut r = [0u8; PUBLIC_KEY_LENGTH];
+		if input.read(&mut r[..]) != PUBLIC_KEY_LENGTH {
+			return None;
+		}
+		Some(Public(r))
+	}
+}
+
+/// A key pair.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct Pair {
+	public: secp256k1::PublicKey,
+	secret: secp256k1::SecretKey,
+}
+
+impl AsRef<[u8]> for Public {
+	fn as_ref(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+impl AsMut<[u8]> for Public {
+	fn as_mut(&mut self) -> &mut [u8] {
+		&mut self.0[..]
+	}
+}
+
+impl From<Public> for [u8; PUBLIC_KEY_LENGTH] {
+	fn from(x: Public) -> Self {
+		x.0
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<Pair> for Public {
+	fn from(x: Pair) -> Self {
+		x.public()
+	}
+}
+
+impl AsRef<Public> for Public {
+	fn as_ref(&self) -> &Public {
+		&self
+	}
+}
+
+impl UncheckedFrom<[u8; PUBLIC_KEY_LENGTH]> for Public {
+	fn unchecked_from(x: [u8; PUBLIC_KEY_LENGTH]) -> Self {
+		Public::from_raw(x)
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::fmt::Display for Public {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		write!(f, "{}", self.to_ss58check())
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::fmt::Debug for Public {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		let s = self.to_ss58check();
+		write!(f, "{} ({}...)", crate::hexdisplay::HexDisplay::from(&&self.0[..]), &s[0..8])
+	}
+}
+
+#[cfg(feature = "std")]
+impl Serialize for Public {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		serializer.serialize_str(&self.to_ss58check())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Public {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		Public::from_ss58check(&String::deserialize(deserializer)?)
+			.map_err(|e| de::Error::custom(format!("{:?}", e)))
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::hash::Hash for Public {
+	fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+		self.0[..].hash(state);
+	}
+}
+
+/// A recoverable signature: the 64-byte `r` and `s` values followed by the recovery id.
+pub struct Signature(pub [u8; SIGNATURE_LENGTH]);
+
+impl Clone for Signature {
+	fn clone(&self) -> Self {
+		Signature(self.0)
+	}
+}
+
+impl Default for Signature {
+	fn default() -> Self {
+		Signature([0u8; SIGNATURE_LENGTH])
+	}
+}
+
+impl PartialEq for Signature {
+	fn eq(&self, b: &Self) -> bool {
+		&self.0[..] == &b.0[..]
+	}
+}
+
+impl Eq for Signature {}
+
+impl Encode for Signature {
+	fn encode_to<T: Output>(&self, dest: &mut T) {
+		dest.write(&self.0[..])
+	}
+}
+
+impl Decode for Signature {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		let mut r = [0u8; SIGNATURE_LENGTH];
+		if input.read(&mut r[..]) != SIGNATURE_LENGTH {
+			return None;
+		}
+		Some(Signature(r))
+	}
+}
+
+impl From<Signature> for [u8; SIGNATURE_LENGTH] {
+	fn from(v: Signature) -> [u8; SIGNATURE_LENGTH] {
+		v.0
+	}
+}
+
+impl AsRef<[u8; SIGNATURE_LENGTH]> for Signature {
+	fn as_ref(&self) -> &[u8; SIGNATURE_LENGTH] {
+		&self.0
+	}
+}
+
+impl AsRef<[u8]> for Signature {
+	fn as_ref(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+impl AsMut<[u8]> for Signature {
+	fn as_mut(&mut self) -> &mut [u8] {
+		&mut self.0[..]
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::fmt::Debug for Signature {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		write!(f, "{}", crate::hexdisplay::HexDisplay::from(&&self.0[..]))
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::hash::Hash for Signature {
+	fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+		::std::hash::Hash::hash(&self.0[..], state);
+	}
+}
+
+impl Signature {
+	/// A new instance from the given 65-byte `data`.
+	///
+	/// NOTE: No checking goes on to ensure this is a real signature. Only use it if
+	/// you are certain that the array actually is a signature. GIGO!
+	pub fn from_raw(data: [u8; SIGNATURE_LENGTH]) -> Signature {
+		Signature(data)
+	}
+
+	/// A new instance from the given slice that should be 65 bytes long.
+	///
+	/// NOTE: No checking goes on to ensure this is a real signature. Only use it if
+	/// you are certain that the array actually is a signature. GIGO!
+	pub fn from_slice(data: &[u8]) -> Self {
+		let mut r = [0u8; SIGNATURE_LENGTH];
+		r.copy_from_slice(data);
+		Signature(r)
+	}
+
+	/// Recover the compressed public key that created this signature over the given message.
+	///
+	/// The message is hashed with `blake2_256` before recovery, just as in `Pair::sign`.
+	#[cfg(feature = "std")]
+	pub fn recover<M: AsRef<[u8]>>(&self, message: M) -> Option<Public> {
+		let message = secp256k1::Message::parse(&blake2_256(message.as_ref()));
+		let rs = secp256k1::Signature::parse_slice(&self.0[0..64]).ok()?;
+		// Ethereum style recovery ids are offset by 27.
+		let v = secp256k1::RecoveryId::parse(if self.0[64] > 26 { self.0[64] - 27 } else { self.0[64] }).ok()?;
+		secp256k1::recover(&message, &rs, &v)
+			.ok()
+			.map(|public| Public(public.serialize_compressed()))
+	}
+}
+
+impl Public {
+	/// A new instance from the given 33-byte `data`.
+	///
+	/// NOTE: No checking goes on to ensure this is a real public key. Only use it if
+	/// you are certain that the array actually is a pubkey. GIGO!
+	pub fn from_raw(data: [u8; PUBLIC_KEY_LENGTH]) -> Self {
+		Public(data)
+	}
+
+	/// A new instance from a full (uncompressed) public key, either 64 bytes or 65 bytes with a
+	/// leading `0x04` tag, as e.g. returned from `secp256k1_ecdsa_recover`.
+	#[cfg(feature = "std")]
+	pub fn from_full(full: &[u8]) -> Result<Self, ()> {
+		let mut tagged = [4u8; 65];
+		let raw = match full.len() {
+			64 => &full[..],
+			65 if full[0] == 4 => &full[1..],
+			_ => return Err(()),
+		};
+		tagged[1..].copy_from_slice(raw);
+		secp256k1::PublicKey::parse(&tagged)
+			.map(|public| Public(public.serialize_compressed()))
+			.map_err(|_| ())
+	}
+}
+
+impl TraitPublic for Public {
+	/// A new instance from the given slice that should be 33 bytes long.
+	///
+	/// NOTE: No checking goes on to ensure this is a real public key. Only use it if
+	/// you are certain that the array actually is a pubkey. GIGO!
+	fn from_slice(data: &[u8]) -> Self {
+		let mut r = [0u8; PUBLIC_KEY_LENGTH];
+		r.copy_from_slice(data);
+		Public(r)
+	}
+
+	/// Return a `Vec<u8>` filled with raw data.
+	#[cfg(feature = "std")]
+	fn to_raw_vec(&self) -> Vec<u8> {
+		self.0.to_vec()
+	}
+
+	/// Return a slice filled with raw data.
+	fn as_slice(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+#[cfg(feature = "std")]
+impl Derive for Public {}
+
+#[cfg(feature = "std")]
+impl AsRef<Pair> for Pair {
+	fn as_ref(&self) -> &Pair {
+		&self
+	}
+}
+
+/// Derive a single hard junction.
+#[cfg(feature = "std")]
+fn derive_hard_junction(secret_seed: &Seed, cc: &[u8; 32]) -> Seed {
+	("Secp256k1HDKD", secret_seed, cc).using_encoded(|data| blake2_256(data))
+}
+
+/// An error when deriving a key.
+#[cfg(feature = "std")]
+pub enum DeriveError {
+	/// A soft key was found in the path (and is unsupported).
+	SoftKeyInPath,
+}
+
+#[cfg(feature = "std")]
+impl TraitPair for Pair {
+	type Public = Public;
+	type Seed = Seed;
+	type Signature = Signature;
+	type DeriveError = DeriveError;
+
+	/// Generate new secure (random) key pair and provide the recovery phrase.
+	///
+	/// You can recover the same key later with `from_phrase`.
+	fn generate_with_phrase(password: Option<&str>) -> (Pair, String, Seed) {
+		let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
+		let phrase = mnemonic.phrase();
+		let (pair, seed) = Self::from_phrase(phrase, password)
+			.expect("All phrases generated by Mnemonic are valid; qed");
+		(
+			pair,
+			phrase.to_owned(),
+			seed,
+		)
+	}
+
+	/// Generate key pair from given recovery phrase and password.
+	fn from_phrase(phrase: &str, password: Option<&str>) -> Result<(Pair, Seed), SecretStringError> {
+		let big_seed = seed_from_entropy(
+			Mnemonic::from_phrase(phrase, Language::English)
+				.map_err(|_| SecretStringError::InvalidPhrase)?.entropy(),
+			password.unwrap_or(""),
+		).map_err(|_| SecretStringError::InvalidSeed)?;
+		let mut seed = Seed::default();
+		seed.copy_from_slice(&big_seed[0..32]);
+		Self::from_seed_slice(&big_seed[0..32]).map(|x| (x, seed))
+	}
+
+	/// Make a new key pair from secret seed material.
+	///
+	/// You should never need to use this; generate(), generate_with_phrase
+	fn from_seed(seed: &Seed) -> Pair {
+		Self::from_seed_slice(&seed[..]).expect("seed has valid length; qed")
+	}
+
+	/// Make a new key pair from secret seed material. The slice must be 32 bytes long and a
+	/// valid secp256k1 secret key or it will return an error.
+	///
+	/// You should never need to use this; generate(), generate_with_phrase
+	fn from_seed_slice(seed_slice: &[u8]) -> Result<Pair, SecretStringError> {
+		if seed_slice.len() != 32 {
+			return Err(SecretStringError::InvalidSeedLength);
+		}
+		let secret = secp256k1::SecretKey::parse_slice(seed_slice)
+			.map_err(|_| SecretStringError::InvalidSeed)?;
+		let public = secp256k1::PublicKey::from_secret_key(&secret);
+		Ok(Pair { public, secret })
+	}
+
+	/// Derive a child key from a series of given junctions.
+	fn derive<Iter: Iterator<Item=DeriveJunction>>(&self, path: Iter) -> Result<Pair, DeriveError> {
+		let mut acc = self.seed();
+		for j in path {
+			match j {
+				DeriveJunction::Soft(_cc) => return Err(DeriveError::SoftKeyInPath),
+				DeriveJunction::Hard(cc) => acc = derive_hard_junction(&acc, &cc),
+			}
+		}
+		Ok(Self::from_seed(&acc))
+	}
+
+	/// Generate a key from the phrase, password and derivation path.
+	fn from_standard_components<I: Iterator<Item=DeriveJunction>>(
+		phrase: &str,
+		password: Option<&str>,
+		path: I
+	) -> Result<Pair, SecretStringError> {
+		Self::from_phrase(phrase, password)?.0
+			.derive(path)
+			.map_err(|_| SecretStringError::InvalidPath)
+	}
+
+	/// Get the public key.
+	fn public(&self) -> Public {
+		Public(self.public.serialize_compressed())
+	}
+
+	/// Sign a message.
+	///
+	/// The message is hashed with `blake2_256` before signing.
+	fn sign(&self, message: &[u8]) -> Signature {
+		let message = secp256k1::Message::parse(&blake2_256(message));
+		let (sig, recovery_id) = secp256k1::sign(&message, &self.secret)
+			.expect("signing a 32-byte message with a valid secret key cannot fail; qed");
+		let mut r = [0u8; SIGNATURE_LENGTH];
+		r[0..64].copy_from_slice(&sig.serialize()[..]);
+		r[64] = recovery_id.serialize();
+		Signature(r)
+	}
+
+	/// Verify a signature on a message. Returns true if the signature is good.
+	fn verify<P: AsRef<Self::Public>, M: AsRef<[u8]>>(sig: &Self::Signature, message: M, pubkey: P) -> bool {
+		match sig.recover(message) {
+			Some(actual) => &actual == pubkey.as_ref(),
+			None => false,
+		}
+	}
+
+	/// Verify a signature on a message. Returns true if the signature is good.
+	///
+	/// This doesn't use the type system to ensure that `sig` and `pubkey` are the correct
+	/// size. Use it only if you're coming from byte buffers and need the speed.
+	fn verify_weak<P: AsRef<[u8]>, M: AsRef<[u8]>>(sig: &[u8], message: M, pubkey: P) -> bool {
+		if sig.len() != SIGNATURE_LENGTH || pubkey.as_ref().len() != PUBLIC_KEY_LENGTH {
+			return false;
+		}
+		match Signature::from_slice(sig).recover(message) {
+			Some(actual) => actual.as_ref() == pubkey.as_ref(),
+			None => false,
+		}
+	}
+
+	/// Return a vec filled with raw data.
+	fn to_raw_vec(&self) -> Vec<u8> {
+		self.seed().to_vec()
+	}
+}
+
+#[cfg(feature = "std")]
+impl Pair {
+	/// Get the seed for this key.
+	pub fn seed(&self) -> Seed {
+		self.secret.serialize()
+	}
+}
+
+impl TypedKey for Public {
+	const KEY_TYPE: KeyTypeId = key_types::ECDSA;
+}
+
+impl TypedKey for Signature {
+	const KEY_TYPE: KeyTypeId = key_types::ECDSA;
+}
+
+#[cfg(feature = "std")]
+impl TypedKey for Pair {
+	const KEY_TYPE: KeyTypeId = key_types::ECDSA;
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use hex_literal::hex;
+	use crate::crypto::DEV_PHRASE;
+
+	#[test]
+	fn default_phrase_should_be_used() {
+		assert_eq!(
+			Pair::from_string("//Alice///password", None).unwrap().public(),
+			Pair::from_string(&format!("{}//Alice", DEV_PHRASE), Some("password")).unwrap().public(),
+		);
+	}
+
+	#[test]
+	fn seed_and_derive_should_work() {
+		let seed = hex!("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60");
+		let pair = Pair::from_seed(&seed);
+		assert_eq!(pair.seed(), seed);
+		let path = vec![DeriveJunction::Hard([0u8; 32])];
+		let derived = pair.derive(path.into_iter()).ok().unwrap();
+		assert_ne!(derived.seed(), seed);
+		assert!(pair.derive(vec![DeriveJunction::soft(1)].into_iter()).is_err());
+	}
+
+	#[test]
+	fn public_key_is_compressed() {
+		let pair = Pair::from_seed(
+			&hex!("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+		);
+		let public = pair.public();
+		assert!(public.0[0] == 2 || public.0[0] == 3);
+		assert_eq!(Public::decode(&mut &public.encode()[..]), Some(public));
+	}
+
+	#[test]
+	fn generated_pair_should_work() {
+		let (pair, _) = Pair::generate();
+		let public = pair.public();
+		let message = b"Something important";
+		let signature = pair.sign(&message[..]);
+		assert!(Pair::verify(&signature, &message[..], &public));
+		assert!(Pair::verify_weak(&signature.0[..], &message[..], &public));
+		assert!(!Pair::verify(&signature, b"Something else", &public));
+		assert_eq!(signature.recover(&message[..]), Some(public));
+	}
+
+	#[test]
+	fn full_public_key_converts_to_compressed() {
+		let (pair, _) = Pair::generate();
+		let full = pair.public.serialize();
+		assert_eq!(Public::from_full(&full[..]), Ok(pair.public()));
+		assert_eq!(Public::from_full(&full[1..]), Ok(pair.public()));
+		assert_eq!(Public::from_full(&full[2..]), Err(()));
+	}
+
+	#[test]
+	fn ss58check_roundtrip_works() {
+		let (pair, _) = Pair::generate();
+		let public = pair.public();
+		let s = public.to_ss58check();
+		assert_eq!(Public::from_ss58check(&s), Ok(public));
+	}
+}
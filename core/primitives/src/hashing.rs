@@ -18,6 +18,7 @@
 
 use blake2_rfc;
 use twox_hash;
+use sha2::Digest;
 
 /// Do a Blake2 512-bit hash and place result in `dest`.
 pub fn blake2_512_into(data: &[u8], dest: &mut [u8; 64]) {
@@ -121,3 +122,17 @@ pub fn twox_256(data: &[u8]) -> [u8; 32] {
 	twox_256_into(data, &mut r);
 	r
 }
+
+/// Do a Sha2 256-bit hash and return result.
+pub fn sha2_256(data: &[u8]) -> [u8; 32] {
+	let mut r = [0; 32];
+	r.copy_from_slice(&sha2::Sha256::digest(data));
+	r
+}
+
+/// Do a Ripemd 160-bit hash and return result.
+pub fn ripemd_160(data: &[u8]) -> [u8; 20] {
+	let mut r = [0; 20];
+	r.copy_from_slice(&ripemd160::Ripemd160::digest(data));
+	r
+}
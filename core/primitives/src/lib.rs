@@ -45,7 +45,7 @@ pub use impl_serde::serialize as bytes;
 #[cfg(feature = "std")]
 pub mod hashing;
 #[cfg(feature = "std")]
-pub use hashing::{blake2_128, blake2_256, twox_64, twox_128, twox_256};
+pub use hashing::{blake2_128, blake2_256, twox_64, twox_128, twox_256, sha2_256, ripemd_160};
 #[cfg(feature = "std")]
 pub mod hexdisplay;
 pub mod crypto;
@@ -54,6 +54,10 @@ pub mod u32_trait;
 
 pub mod ed25519;
 pub mod sr25519;
+pub mod ecdsa;
+pub mod bls;
+#[cfg(feature = "std")]
+pub mod batch_verifier;
 pub mod hash;
 mod hasher;
 pub mod offchain;
@@ -61,6 +65,8 @@ pub mod sandbox;
 pub mod storage;
 pub mod uint;
 mod changes_trie;
+#[cfg(feature = "std")]
+pub mod traits;
 
 #[cfg(test)]
 mod tests;
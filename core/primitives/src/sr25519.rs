@@ -539,7 +539,7 @@ impl TypedKey for Pair {
 #[cfg(test)]
 mod test {
 	use super::*;
-	use crate::crypto::{Ss58Codec, DEV_PHRASE, DEV_ADDRESS};
+	use crate::crypto::{Ss58Codec, Ss58AddressFormat, PublicError, DEV_PHRASE, DEV_ADDRESS};
 	use hex_literal::hex;
 
 	#[test]
@@ -670,6 +670,20 @@ mod test {
 		assert_eq!(cmp, public);
 	}
 
+	#[test]
+	fn ss58check_custom_format_works() {
+		let (pair, _) = Pair::generate();
+		let public = pair.public();
+		let format = Ss58AddressFormat::Custom(5);
+		let s = public.to_ss58check_with_version(format);
+		assert_eq!(Public::from_ss58check_with_version(&s), Ok((public.clone(), format)));
+		// Not the default format, so it is not accepted without the version.
+		assert_eq!(Public::from_ss58check(&s), Err(PublicError::UnknownVersion));
+
+		let s = public.to_ss58check_with_version(Ss58AddressFormat::Custom(64));
+		assert_eq!(Public::from_ss58check_with_version(&s), Err(PublicError::UnknownVersion));
+	}
+
 	#[test]
 	fn verify_from_wasm_works() {
 		// The values in this test case are compared to the output of `node-test.js` in schnorrkel-js.
@@ -0,0 +1,52 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Shareable Substrate traits.
+
+use std::sync::Arc;
+
+use crate::{crypto::KeyTypeId, ecdsa, ed25519, sr25519};
+
+/// Something that generates and stores keys on behalf of the runtime.
+pub trait KeyStore: Send + Sync {
+	/// Generate a new ed25519 key of type `id` and store it.
+	///
+	/// If `seed` is given, the key is derived from it and only kept for the lifetime of the
+	/// store. Returns the public key.
+	fn ed25519_generate_new(&self, id: KeyTypeId, seed: Option<&str>) -> Result<ed25519::Public, String>;
+
+	/// Generate a new sr25519 key of type `id` and store it.
+	///
+	/// If `seed` is given, the key is derived from it and only kept for the lifetime of the
+	/// store. Returns the public key.
+	fn sr25519_generate_new(&self, id: KeyTypeId, seed: Option<&str>) -> Result<sr25519::Public, String>;
+
+	/// Generate a new ecdsa key of type `id` and store it.
+	///
+	/// If `seed` is given, the key is derived from it and only kept for the lifetime of the
+	/// store. Returns the public key.
+	fn ecdsa_generate_new(&self, id: KeyTypeId, seed: Option<&str>) -> Result<ecdsa::Public, String>;
+
+	/// Sign `message` with the stored ecdsa key of the given public key.
+	fn ecdsa_sign(&self, public: &ecdsa::Public, message: &[u8]) -> Result<ecdsa::Signature, String>;
+
+	/// Returns `true` if the secret keys of all given raw public keys and key types are stored.
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool;
+}
+
+/// A shared pointer to a `KeyStore`.
+pub type KeyStorePtr = Arc<dyn KeyStore>;
@@ -38,6 +38,15 @@ pub enum Error {
 	/// Incorrect extrinsic format.
 	#[display(fmt="Invalid extrinsic format")]
 	BadFormat,
+	/// Invalid session keys encoding.
+	#[display(fmt="Session keys are not encoded correctly")]
+	InvalidSessionKeys,
+	/// The node has no keystore to store or look up keys in.
+	#[display(fmt="The keystore is unavailable")]
+	KeystoreUnavailable,
+	/// An unsafe method was called on an interface that denies them.
+	#[display(fmt="RPC call is unsafe to be called externally")]
+	UnsafeRpcCalled,
 }
 
 impl std::error::Error for Error {
@@ -57,6 +66,12 @@ const BASE_ERROR: i64 = 1000;
 const BAD_FORMAT: i64 = BASE_ERROR + 1;
 /// Error during transaction verification in runtime.
 const VERIFICATION_ERROR: i64 = BASE_ERROR + 2;
+/// The given session keys could not be decoded.
+const INVALID_SESSION_KEYS: i64 = BASE_ERROR + 3;
+/// The keystore is not available.
+const KEYSTORE_UNAVAILABLE: i64 = BASE_ERROR + 4;
+/// An unsafe method was called externally.
+const UNSAFE_RPC_CALLED: i64 = BASE_ERROR + 5;
 
 /// Pool rejected the transaction as invalid
 const POOL_INVALID_TX: i64 = BASE_ERROR + 10;
@@ -88,6 +103,21 @@ impl From<Error> for rpc::Error {
 				message: format!("Verification Error: {}", e).into(),
 				data: Some(format!("{:?}", e).into()),
 			},
+			Error::InvalidSessionKeys => rpc::Error {
+				code: rpc::ErrorCode::ServerError(INVALID_SESSION_KEYS),
+				message: "Invalid session keys encoding.".into(),
+				data: None,
+			},
+			Error::KeystoreUnavailable => rpc::Error {
+				code: rpc::ErrorCode::ServerError(KEYSTORE_UNAVAILABLE),
+				message: "The keystore is unavailable on this node.".into(),
+				data: None,
+			},
+			Error::UnsafeRpcCalled => rpc::Error {
+				code: rpc::ErrorCode::ServerError(UNSAFE_RPC_CALLED),
+				message: "RPC call is unsafe to be called externally.".into(),
+				data: None,
+			},
 			Error::Pool(PoolError::InvalidTransaction(code)) => rpc::Error {
 				code: rpc::ErrorCode::ServerError(POOL_INVALID_TX),
 				message: "Invalid Transaction".into(),
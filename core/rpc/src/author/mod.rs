@@ -27,13 +27,15 @@ use std::sync::Arc;
 use client::{self, Client};
 use crate::rpc::futures::{Sink, Stream, Future};
 use crate::subscriptions::Subscriptions;
+use crate::DenyUnsafe;
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use log::warn;
 use parity_codec::{Encode, Decode};
-use primitives::{Bytes, Blake2Hasher, H256};
-use runtime_primitives::{generic, traits};
+use primitives::{Bytes, Blake2Hasher, H256, traits::KeyStorePtr};
+use runtime_primitives::{generic, traits::{self, ProvideRuntimeApi}};
 use self::error::Result;
+use session::SessionKeys;
 use transaction_pool::{
 	txpool::{
 		ChainApi as PoolChainApi,
@@ -57,6 +59,23 @@ pub trait AuthorApi<Hash, BlockHash> {
 	#[rpc(name = "author_submitExtrinsic")]
 	fn submit_extrinsic(&self, extrinsic: Bytes) -> Result<Hash>;
 
+	/// Generate new session keys and returns the corresponding public keys.
+	///
+	/// The keys are stored in the keystore of the node. The returned bytes are the SCALE
+	/// encoded session keys, ready to be submitted to `session::set_keys`.
+	///
+	/// This method is unsafe: it is denied on interfaces exposed publicly.
+	#[rpc(name = "author_rotateKeys")]
+	fn rotate_keys(&self) -> Result<Bytes>;
+
+	/// Checks if the keystore has private keys for the given session public keys.
+	///
+	/// `session_keys` is the SCALE encoded session keys object from the runtime.
+	///
+	/// Returns `true` iff all private keys could be found.
+	#[rpc(name = "author_hasSessionKeys")]
+	fn has_session_keys(&self, session_keys: Bytes) -> Result<bool>;
+
 	/// Returns all pending extrinsics, potentially grouped by sender.
 	#[rpc(name = "author_pendingExtrinsics")]
 	fn pending_extrinsics(&self) -> Result<Vec<Bytes>>;
@@ -82,6 +101,10 @@ pub struct Author<B, E, P, RA> where P: PoolChainApi + Sync + Send + 'static {
 	pool: Arc<Pool<P>>,
 	/// Subscriptions manager
 	subscriptions: Subscriptions,
+	/// The key store.
+	keystore: Option<KeyStorePtr>,
+	/// Whether unsafe methods are denied.
+	deny_unsafe: DenyUnsafe,
 }
 
 impl<B, E, P, RA> Author<B, E, P, RA> where P: PoolChainApi + Sync + Send + 'static {
@@ -90,11 +113,15 @@ impl<B, E, P, RA> Author<B, E, P, RA> where P: PoolChainApi + Sync + Send + 'sta
 		client: Arc<Client<B, E, <P as PoolChainApi>::Block, RA>>,
 		pool: Arc<Pool<P>>,
 		subscriptions: Subscriptions,
+		keystore: Option<KeyStorePtr>,
+		deny_unsafe: DenyUnsafe,
 	) -> Self {
 		Author {
 			client,
 			pool,
 			subscriptions,
+			keystore,
+			deny_unsafe,
 		}
 	}
 }
@@ -105,7 +132,10 @@ impl<B, E, P, RA> AuthorApi<ExHash<P>, BlockHash<P>> for Author<B, E, P, RA> whe
 	P: PoolChainApi + Sync + Send + 'static,
 	P::Block: traits::Block<Hash=H256>,
 	P::Error: 'static,
-	RA: Send + Sync + 'static
+	RA: Send + Sync + 'static,
+	Client<B, E, <P as PoolChainApi>::Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, <P as PoolChainApi>::Block, RA> as ProvideRuntimeApi>::Api:
+		SessionKeys<<P as PoolChainApi>::Block>,
 {
 	type Metadata = crate::metadata::Metadata;
 
@@ -120,6 +150,33 @@ impl<B, E, P, RA> AuthorApi<ExHash<P>, BlockHash<P>> for Author<B, E, P, RA> whe
 			)
 	}
 
+	fn rotate_keys(&self) -> Result<Bytes> {
+		if self.deny_unsafe == DenyUnsafe::Yes {
+			return Err(error::Error::UnsafeRpcCalled);
+		}
+		if self.keystore.is_none() {
+			return Err(error::Error::KeystoreUnavailable);
+		}
+
+		let best_block_hash = self.client.info().chain.best_hash;
+		self.client.runtime_api().generate_session_keys(
+			&generic::BlockId::Hash(best_block_hash),
+			None,
+		).map(Into::into).map_err(Into::into)
+	}
+
+	fn has_session_keys(&self, session_keys: Bytes) -> Result<bool> {
+		let keystore = self.keystore.as_ref().ok_or(error::Error::KeystoreUnavailable)?;
+
+		let best_block_hash = self.client.info().chain.best_hash;
+		let keys = self.client.runtime_api().decode_session_keys(
+			&generic::BlockId::Hash(best_block_hash),
+			session_keys.to_vec(),
+		)?.ok_or(error::Error::InvalidSessionKeys)?;
+
+		Ok(keystore.has_keys(&keys))
+	}
+
 	fn pending_extrinsics(&self) -> Result<Vec<Bytes>> {
 		Ok(self.pool.ready().map(|tx| tx.data.encode().into()).collect())
 	}
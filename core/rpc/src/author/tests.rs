@@ -23,8 +23,12 @@ use transaction_pool::{
 	txpool::Pool,
 	ChainApi,
 };
-use primitives::{H256, blake2_256, hexdisplay::HexDisplay};
-use test_client::{self, AccountKeyring, runtime::{Extrinsic, Transfer}};
+use primitives::{H256, blake2_256, ed25519, hexdisplay::HexDisplay};
+use keystore::{BareCryptoStore, MemoryStore};
+use test_client::{
+	self, AccountKeyring, DefaultTestClientBuilderExt, TestClientBuilderExt,
+	runtime::{Extrinsic, SessionKeys, Transfer},
+};
 use tokio::runtime;
 
 fn uxt(sender: AccountKeyring, nonce: u64) -> Extrinsic {
@@ -45,6 +49,8 @@ fn submit_transaction_should_not_cause_error() {
 		client: client.clone(),
 		pool: Arc::new(Pool::new(Default::default(), ChainApi::new(client))),
 		subscriptions: Subscriptions::new(Arc::new(runtime.executor())),
+		keystore: None,
+		deny_unsafe: DenyUnsafe::No,
 	};
 	let xt = uxt(AccountKeyring::Alice, 1).encode();
 	let h: H256 = blake2_256(&xt).into();
@@ -66,6 +72,8 @@ fn submit_rich_transaction_should_not_cause_error() {
 		client: client.clone(),
 		pool: Arc::new(Pool::new(Default::default(), ChainApi::new(client.clone()))),
 		subscriptions: Subscriptions::new(Arc::new(runtime.executor())),
+		keystore: None,
+		deny_unsafe: DenyUnsafe::No,
 	};
 	let xt = uxt(AccountKeyring::Alice, 0).encode();
 	let h: H256 = blake2_256(&xt).into();
@@ -89,6 +97,8 @@ fn should_watch_extrinsic() {
 		client,
 		pool: pool.clone(),
 		subscriptions: Subscriptions::new(Arc::new(runtime.executor())),
+		keystore: None,
+		deny_unsafe: DenyUnsafe::No,
 	};
 	let (subscriber, id_rx, data) = ::jsonrpc_pubsub::typed::Subscriber::new_test("test");
 
@@ -129,6 +139,8 @@ fn should_return_pending_extrinsics() {
 		client,
 		pool: pool.clone(),
 		subscriptions: Subscriptions::new(Arc::new(runtime.executor())),
+		keystore: None,
+		deny_unsafe: DenyUnsafe::No,
 	};
 	let ex = uxt(AccountKeyring::Alice, 0);
 	AuthorApi::submit_extrinsic(&p, ex.encode().into()).unwrap();
@@ -147,6 +159,8 @@ fn should_remove_extrinsics() {
 		client,
 		pool: pool.clone(),
 		subscriptions: Subscriptions::new(Arc::new(runtime.executor())),
+		keystore: None,
+		deny_unsafe: DenyUnsafe::No,
 	};
 	let ex1 = uxt(AccountKeyring::Alice, 0);
 	p.submit_extrinsic(ex1.encode().into()).unwrap();
@@ -165,3 +179,49 @@ fn should_remove_extrinsics() {
 
  	assert_eq!(removed.len(), 3);
 }
+
+#[test]
+fn should_rotate_keys() {
+	let runtime = runtime::Runtime::new().unwrap();
+	let store: Arc<dyn BareCryptoStore> = Arc::new(MemoryStore::new());
+	let keystore: KeyStorePtr = Arc::new(store.clone());
+	let client = Arc::new(test_client::TestClientBuilder::new().set_keystore(keystore.clone()).build());
+	let p = Author {
+		client: client.clone(),
+		pool: Arc::new(Pool::new(Default::default(), ChainApi::new(client))),
+		subscriptions: Subscriptions::new(Arc::new(runtime.executor())),
+		keystore: Some(keystore),
+		deny_unsafe: DenyUnsafe::No,
+	};
+
+	let new_public_keys = p.rotate_keys().expect("Rotates the keys");
+
+	let session_keys = SessionKeys::decode(&mut &new_public_keys[..])
+		.expect("SessionKeys decode successfully");
+
+	let ed25519_keys = store.contents::<ed25519::Public>().unwrap();
+	assert_eq!(ed25519_keys.len(), 1);
+	assert_eq!(ed25519_keys[0], session_keys.ed25519);
+
+	assert_matches!(p.has_session_keys(new_public_keys), Ok(true));
+	assert_matches!(p.has_session_keys(SessionKeys::default().encode().into()), Ok(false));
+	assert_matches!(p.has_session_keys(vec![1, 2, 3].into()), Err(error::Error::InvalidSessionKeys));
+}
+
+#[test]
+fn rotate_keys_is_denied_when_unsafe_methods_are() {
+	let runtime = runtime::Runtime::new().unwrap();
+	let store: Arc<dyn BareCryptoStore> = Arc::new(MemoryStore::new());
+	let keystore: KeyStorePtr = Arc::new(store.clone());
+	let client = Arc::new(test_client::TestClientBuilder::new().set_keystore(keystore.clone()).build());
+	let p = Author {
+		client: client.clone(),
+		pool: Arc::new(Pool::new(Default::default(), ChainApi::new(client))),
+		subscriptions: Subscriptions::new(Arc::new(runtime.executor())),
+		keystore: Some(keystore),
+		deny_unsafe: DenyUnsafe::Yes,
+	};
+
+	assert_matches!(p.rotate_keys(), Err(error::Error::UnsafeRpcCalled));
+	assert!(store.contents::<ed25519::Public>().unwrap().is_empty());
+}
@@ -24,6 +24,16 @@ mod subscriptions;
 
 pub use subscriptions::Subscriptions;
 
+/// Whether methods that are unsafe to expose publicly, e.g. because they change the keys of the
+/// node, are denied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DenyUnsafe {
+	/// Unsafe methods are denied.
+	Yes,
+	/// Unsafe methods are allowed.
+	No,
+}
+
 pub mod author;
 pub mod chain;
 pub mod metadata;
@@ -38,6 +38,8 @@ pub enum Error {
 		/// Details of the error message.
 		details: String,
 	},
+	/// A storage key prefix of a tracing filter isn't hex-encoded.
+	InvalidStorageKey(substrate_tracing::InvalidStorageKey),
 }
 
 impl std::error::Error for Error {
@@ -60,6 +62,11 @@ impl From<Error> for rpc::Error {
 				message: format!("{}", e),
 				data: None,
 			},
+			Error::InvalidStorageKey(_) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: format!("{}", e),
+				data: None,
+			},
 			e => errors::internal(e),
 		}
 	}
@@ -35,6 +35,7 @@ use crate::subscriptions::Subscriptions;
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use log::{warn, trace};
+use parity_codec::Encode;
 use primitives::hexdisplay::HexDisplay;
 use primitives::storage::{self, StorageKey, StorageData, StorageChangeSet};
 use primitives::{H256, Blake2Hasher, Bytes};
@@ -46,6 +47,7 @@ use runtime_primitives::traits::{
 use runtime_version::RuntimeVersion;
 use self::error::Result;
 use state_machine::{self, ExecutionStrategy};
+use substrate_tracing::BlockTrace;
 
 pub use self::gen_client::Client as StateClient;
 
@@ -131,6 +133,20 @@ pub trait StateApi<Hash> {
 		hash: Option<Hash>
 	) -> Result<Vec<StorageChangeSet<Hash>>>;
 
+	/// Re-execute a block on top of its parent with tracing enabled, and return the spans entered
+	/// and the storage accesses made.
+	///
+	/// `targets` is a comma-separated list of prefixes of the targets to record, e.g.
+	/// `executive,state`, and `storage_keys` a comma-separated list of hex-encoded prefixes of the
+	/// storage keys whose accesses are recorded. Both record everything when left empty.
+	#[rpc(name = "state_traceBlock")]
+	fn trace_block(
+		&self,
+		block: Hash,
+		targets: Option<String>,
+		storage_keys: Option<String>
+	) -> Result<BlockTrace<Hash>>;
+
 	/// New runtime version subscription
 	#[pubsub(
 		subscription = "state_runtimeVersion",
@@ -302,26 +318,21 @@ impl<B, E, Block: BlockT, RA> State<B, E, Block, RA> where
 			None => return Ok(()),
 		};
 		let mut changes_map: BTreeMap<NumberFor<Block>, StorageChangeSet<Block::Hash>> = BTreeMap::new();
-		for key in keys {
-			let mut last_block = None;
-			let mut last_value = last_values.get(key).cloned().unwrap_or_default();
-			for (block, _) in self.client.key_changes(begin, end, key)?.into_iter().rev() {
-				if last_block == Some(block) {
-					continue;
-				}
-
-				let block_hash = range.hashes[(block - range.first_number).saturated_into::<usize>()].clone();
-				let id = BlockId::Hash(block_hash);
-				let value_at_block = self.client.storage(&id, key)?;
-				if last_value == value_at_block {
+		let mut last_values = last_values.clone();
+		for (block, changed_keys) in self.client.keys_changes(begin, end, keys)? {
+			let block_hash = range.hashes[(block - range.first_number).saturated_into::<usize>()].clone();
+			let id = BlockId::Hash(block_hash);
+			for key in changed_keys {
+				let value_at_block = self.client.storage(&id, &key)?;
+				let last_value = last_values.entry(key.clone()).or_default();
+				if *last_value == value_at_block {
 					continue;
 				}
 
 				changes_map.entry(block)
 					.or_insert_with(|| StorageChangeSet { block: block_hash, changes: Vec::new() })
-					.changes.push((key.clone(), value_at_block.clone()));
-				last_block = Some(block);
-				last_value = value_at_block;
+					.changes.push((key, value_at_block.clone()));
+				*last_value = value_at_block;
 			}
 		}
 		if let Some(additional_capacity) = changes_map.len().checked_sub(changes.len()) {
@@ -451,6 +462,44 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA> where
 		Ok(changes)
 	}
 
+	fn trace_block(
+		&self,
+		block: Block::Hash,
+		targets: Option<String>,
+		storage_keys: Option<String>,
+	) -> Result<BlockTrace<Block::Hash>> {
+		let id = BlockId::Hash(block);
+		let block = self.client.block(&id)?
+			.ok_or_else(|| client::error::Error::UnknownBlock(format!("{:?}", id)))?
+			.block;
+		let block_hash = block.header().hash();
+		let parent_hash = *block.header().parent_hash();
+		let tracing_targets = targets.unwrap_or_default();
+		let storage_keys = storage_keys.unwrap_or_default();
+		trace!(target: "rpc", "Tracing block {:?} (targets: {:?}, storage keys: {:?})", block_hash, tracing_targets, storage_keys);
+
+		// always execute the wasm runtime, so that host function calls are traced as well.
+		let (result, traces) = substrate_tracing::trace(&tracing_targets, &storage_keys, || {
+			self.client.executor().call(
+				&BlockId::Hash(parent_hash),
+				"Core_execute_block",
+				&block.encode(),
+				ExecutionStrategy::AlwaysWasm,
+				state_machine::NeverOffchainExt::new(),
+			)
+		})?;
+		result?;
+
+		Ok(BlockTrace {
+			block_hash,
+			parent_hash,
+			tracing_targets,
+			storage_keys,
+			spans: traces.spans,
+			events: traces.events,
+		})
+	}
+
 	fn subscribe_storage(
 		&self,
 		_meta: Self::Metadata,
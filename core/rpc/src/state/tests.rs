@@ -239,6 +239,44 @@ fn should_query_storage() {
 	run_tests(Arc::new(TestClientBuilder::new().set_support_changes_trie(true).build()));
 }
 
+#[test]
+fn should_trace_block() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(vec![1], Some(vec![42])).unwrap();
+	builder.push_storage_change(vec![2], Some(vec![43])).unwrap();
+	let block = builder.bake().unwrap();
+	let block_hash = block.header.hash();
+	client.import(BlockOrigin::Own, block).unwrap();
+	let genesis_hash = client.genesis_hash();
+	let api = State::new(client, Subscriptions::new(Arc::new(core.executor())));
+
+	let trace = api.trace_block(block_hash, None, Some("0x01".into())).unwrap();
+	assert_eq!(trace.block_hash, block_hash);
+	assert_eq!(trace.parent_hash, genesis_hash);
+	assert!(trace.spans.iter().any(|span| span.name == "ext_set_storage"));
+	let puts: Vec<_> = trace.events.iter().filter(|event| event.name == "put").collect();
+	assert_eq!(puts.len(), 1);
+	assert_eq!(puts[0].values["key"], "0x01");
+	assert_eq!(puts[0].values["value"], "0x2a");
+	let parent = trace.spans.iter().find(|span| Some(span.id) == puts[0].parent_id).unwrap();
+	assert_eq!(parent.name, "ext_set_storage");
+
+	let trace = api.trace_block(block_hash, Some("state".into()), None).unwrap();
+	assert!(trace.spans.is_empty());
+	assert!(trace.events.iter().any(|event| event.values["key"] == "0x02"));
+
+	assert_matches!(
+		api.trace_block(H256::repeat_byte(1), None, None),
+		Err(Error::Client(client::error::Error::UnknownBlock(_)))
+	);
+	assert_matches!(
+		api.trace_block(block_hash, None, Some("0x01,zz".into())),
+		Err(Error::InvalidStorageKey(_))
+	);
+}
+
 #[test]
 fn should_split_ranges() {
 	assert_eq!(split_range(1, None), (0..1, None));
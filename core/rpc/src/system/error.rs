@@ -28,6 +28,9 @@ pub enum Error {
 	/// Provided block range couldn't be resolved to a list of blocks.
 	#[display(fmt = "Node is not fully functional: {}", _0)]
 	NotHealthy(Health),
+	/// The given log filter couldn't be parsed.
+	#[display(fmt = "Invalid log filter: {}", _0)]
+	InvalidLogFilter(String),
 }
 
 impl std::error::Error for Error {}
@@ -43,6 +46,11 @@ impl From<Error> for rpc::Error {
 				message: format!("{}", e),
 				data: serde_json::to_value(h).ok(),
 			},
+			Error::InvalidLogFilter(_) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: format!("{}", e),
+				data: None,
+			},
 		}
 	}
 }
@@ -70,6 +70,15 @@ pub trait SystemApi<Hash, Number> {
 	// TODO: make this stable and move structs https://github.com/paritytech/substrate/issues/1890
 	#[rpc(name = "system_networkState", returns = "network::NetworkState")]
 	fn system_network_state(&self) -> Receiver<network::NetworkState>;
+
+	/// Adds the given comma-separated log filter directives, e.g. `sync=debug,afg=trace`, which
+	/// take precedence over the ones the node was started with.
+	#[rpc(name = "system_addLogFilter")]
+	fn system_add_log_filter(&self, directives: String) -> Result<()>;
+
+	/// Drops the log filter directives added since the node was started.
+	#[rpc(name = "system_resetLogFilter")]
+	fn system_reset_log_filter(&self) -> Result<()>;
 }
 
 /// System API implementation
@@ -138,4 +147,13 @@ impl<B: traits::Block> SystemApi<B::Hash, <B::Header as HeaderT>::Number> for Sy
 		let _ = self.send_back.unbounded_send(Request::NetworkState(tx));
 		Receiver(rx)
 	}
+
+	fn system_add_log_filter(&self, directives: String) -> Result<()> {
+		substrate_logging::add_directives(&directives).map_err(error::Error::InvalidLogFilter)
+	}
+
+	fn system_reset_log_filter(&self) -> Result<()> {
+		substrate_logging::reset_directives();
+		Ok(())
+	}
 }
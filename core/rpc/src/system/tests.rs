@@ -78,6 +78,7 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 						average_download_per_sec: 0,
 						average_upload_per_sec: 0,
 						peerset: serde_json::Value::Null,
+						protocols: Default::default(),
 					});
 				}
 			};
@@ -217,6 +218,18 @@ fn system_network_state() {
 			average_download_per_sec: 0,
 			average_upload_per_sec: 0,
 			peerset: serde_json::Value::Null,
+			protocols: Default::default(),
 		}
 	);
 }
+
+#[test]
+fn system_add_and_reset_log_filter() {
+	let api = api(None);
+	assert_matches!(
+		api.system_add_log_filter("sync=loud".into()),
+		Err(error::Error::InvalidLogFilter(_))
+	);
+	assert_matches!(api.system_add_log_filter("sync=debug,afg".into()), Ok(()));
+	assert_matches!(api.system_reset_log_filter(), Ok(()));
+}
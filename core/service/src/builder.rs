@@ -0,0 +1,222 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Step-by-step construction of a `Service`.
+//!
+//! By default every part of the service is built by the `Components` and their
+//! `ServiceFactory`. The `ServiceBuilder` allows replacing each of these parts individually:
+//!
+//! ```ignore
+//! let service = ServiceBuilder::<FullComponents<Factory>>::new(config)
+//! 	.with_select_chain(|_config, client| {
+//! 		#[allow(deprecated)]
+//! 		Ok(Some(LongestChain::new(client.backend().clone())))
+//! 	})
+//! 	.with_rpc_extensions(|io, deps| {
+//! 		io.extend_with(MyRpc::new(deps.client, deps.keystore).to_delegate());
+//! 	})
+//! 	.build()?;
+//!
+//! // The handles of the service can be used to drive custom background tasks.
+//! let client = service.client();
+//! service.spawn_task("my-task", my_task(client, service.transaction_pool(), service.network()));
+//! ```
+//!
+//! The `ServiceFactory` itself, declared with `construct_service_factory!`, still provides the
+//! types of the service and the default parts. Its `FullService` and `LightService` initializers
+//! are where the builder is used, through `FullComponents::from_builder` and
+//! `LightComponents::from_builder`.
+
+use std::sync::Arc;
+use primitives::traits::KeyStorePtr;
+use network::{FinalityProofProvider, config::BoxFinalityProofRequestBuilder};
+use transaction_pool::txpool::{Options as TransactionPoolOptions, Pool as TransactionPool};
+use crate::components::{
+	Components, ComponentBlock, ComponentClient, FactoryFullConfiguration, ServiceFactory,
+};
+use crate::{error, Service, SpawnTaskHandle};
+
+/// Builds the fork choice rule of the service.
+pub type SelectChainBuilder<C> = Box<dyn FnOnce(
+	&mut FactoryFullConfiguration<<C as Components>::Factory>,
+	Arc<ComponentClient<C>>,
+) -> Result<Option<<C as Components>::SelectChain>, error::Error>>;
+
+/// Builds the import queue of the service, and optionally a way for the network to build
+/// requests for proofs of finality.
+pub type ImportQueueBuilder<C> = Box<dyn FnOnce(
+	&mut FactoryFullConfiguration<<C as Components>::Factory>,
+	Arc<ComponentClient<C>>,
+	Option<<C as Components>::SelectChain>,
+) -> Result<
+	(<C as Components>::ImportQueue, Option<BoxFinalityProofRequestBuilder<ComponentBlock<C>>>),
+	error::Error,
+>>;
+
+/// Builds the provider of finality proofs for network requests.
+pub type FinalityProofProviderBuilder<C> = Box<dyn FnOnce(
+	Arc<ComponentClient<C>>,
+) -> Result<Option<Arc<dyn FinalityProofProvider<ComponentBlock<C>>>>, error::Error>>;
+
+/// Builds the transaction pool of the service.
+pub type TransactionPoolBuilder<C> = Box<dyn FnOnce(
+	TransactionPoolOptions,
+	Arc<ComponentClient<C>>,
+) -> Result<TransactionPool<<C as Components>::TransactionPoolApi>, error::Error>>;
+
+/// Builds the network protocol specialization of the service.
+pub type NetworkProtocolBuilder<C> = Box<dyn FnOnce(
+	&FactoryFullConfiguration<<C as Components>::Factory>,
+) -> Result<<<C as Components>::Factory as ServiceFactory>::NetworkProtocol, error::Error>>;
+
+/// Handles to the components of the service, given to the RPC extensions.
+pub struct RpcDeps<C: Components> {
+	/// The client of the service.
+	pub client: Arc<ComponentClient<C>>,
+	/// The transaction pool of the service.
+	pub pool: Arc<TransactionPool<C::TransactionPoolApi>>,
+	/// The keystore of the node, if it has one.
+	pub keystore: Option<KeyStorePtr>,
+	/// Handle for spawning the tasks that drive subscriptions.
+	pub spawn_handle: SpawnTaskHandle,
+}
+
+/// Adds custom methods to the RPC handler of the service.
+///
+/// Called once for every RPC handler that is created.
+pub type RpcExtension<C> = Box<dyn Fn(&mut rpc::RpcHandler, RpcDeps<C>)>;
+
+/// Builder for a `Service`.
+///
+/// Each part of the service that isn't explicitly provided is built by `C`.
+pub struct ServiceBuilder<C: Components> {
+	pub(crate) config: FactoryFullConfiguration<C::Factory>,
+	pub(crate) select_chain: SelectChainBuilder<C>,
+	pub(crate) import_queue: ImportQueueBuilder<C>,
+	pub(crate) finality_proof_provider: FinalityProofProviderBuilder<C>,
+	pub(crate) transaction_pool: TransactionPoolBuilder<C>,
+	pub(crate) network_protocol: NetworkProtocolBuilder<C>,
+	pub(crate) rpc_extensions: Vec<RpcExtension<C>>,
+}
+
+impl<C: Components> ServiceBuilder<C> {
+	/// Start building a service with the given configuration.
+	pub fn new(config: FactoryFullConfiguration<C::Factory>) -> Self {
+		ServiceBuilder {
+			config,
+			select_chain: Box::new(C::build_select_chain),
+			import_queue: Box::new(C::build_import_queue),
+			finality_proof_provider: Box::new(C::build_finality_proof_provider),
+			transaction_pool: Box::new(C::build_transaction_pool),
+			network_protocol: Box::new(<C::Factory as ServiceFactory>::build_network_protocol),
+			rpc_extensions: Vec::new(),
+		}
+	}
+
+	/// Use the given function to build the fork choice rule.
+	pub fn with_select_chain(
+		mut self,
+		builder: impl FnOnce(
+			&mut FactoryFullConfiguration<C::Factory>,
+			Arc<ComponentClient<C>>,
+		) -> Result<Option<C::SelectChain>, error::Error> + 'static,
+	) -> Self {
+		self.select_chain = Box::new(builder);
+		self
+	}
+
+	/// Use the given function to build the import queue.
+	pub fn with_import_queue(
+		mut self,
+		builder: impl FnOnce(
+			&mut FactoryFullConfiguration<C::Factory>,
+			Arc<ComponentClient<C>>,
+			Option<C::SelectChain>,
+		) -> Result<
+			(C::ImportQueue, Option<BoxFinalityProofRequestBuilder<ComponentBlock<C>>>),
+			error::Error,
+		> + 'static,
+	) -> Self {
+		self.import_queue = Box::new(builder);
+		self
+	}
+
+	/// Use the given function to build the finality proof provider.
+	pub fn with_finality_proof_provider(
+		mut self,
+		builder: impl FnOnce(
+			Arc<ComponentClient<C>>,
+		) -> Result<Option<Arc<dyn FinalityProofProvider<ComponentBlock<C>>>>, error::Error> + 'static,
+	) -> Self {
+		self.finality_proof_provider = Box::new(builder);
+		self
+	}
+
+	/// Use the given function to build the transaction pool.
+	pub fn with_transaction_pool(
+		mut self,
+		builder: impl FnOnce(
+			TransactionPoolOptions,
+			Arc<ComponentClient<C>>,
+		) -> Result<TransactionPool<C::TransactionPoolApi>, error::Error> + 'static,
+	) -> Self {
+		self.transaction_pool = Box::new(builder);
+		self
+	}
+
+	/// Use the given function to build the network protocol specialization.
+	pub fn with_network_protocol(
+		mut self,
+		builder: impl FnOnce(
+			&FactoryFullConfiguration<C::Factory>,
+		) -> Result<<C::Factory as ServiceFactory>::NetworkProtocol, error::Error> + 'static,
+	) -> Self {
+		self.network_protocol = Box::new(builder);
+		self
+	}
+
+	/// Add custom methods to the RPC handler.
+	///
+	/// This is the place for node-specific RPC modules, which don't belong in `core/rpc`. Can be
+	/// called multiple times, the extensions are applied in order. Methods registered by an
+	/// extension replace any method of the same name registered before.
+	pub fn with_rpc_extensions(
+		mut self,
+		extension: impl Fn(&mut rpc::RpcHandler, RpcDeps<C>) + 'static,
+	) -> Self {
+		self.rpc_extensions.push(Box::new(extension));
+		self
+	}
+
+	/// Returns the configuration of the service.
+	pub fn config(&self) -> &FactoryFullConfiguration<C::Factory> {
+		&self.config
+	}
+
+	/// Returns the configuration of the service mutably.
+	pub fn config_mut(&mut self) -> &mut FactoryFullConfiguration<C::Factory> {
+		&mut self.config
+	}
+
+	/// Build the service, starting the network, RPC servers and background tasks.
+	///
+	/// The returned service gives access to the client, transaction pool, network and a
+	/// handle for spawning custom background tasks.
+	pub fn build(self) -> Result<Service<C>, error::Error> {
+		Service::from_builder(self)
+	}
+}
@@ -19,7 +19,11 @@
 use std::{self, io::{Read, Write}};
 use futures::prelude::*;
 use log::{info, warn};
+use serde::Deserialize;
 
+use client::{CallExecutor, ExecutionStrategy, NeverOffchainExt};
+use primitives::{Bytes, U256};
+use runtime_primitives::Justification;
 use runtime_primitives::generic::{SignedBlock, BlockId};
 use runtime_primitives::traits::{SaturatedConversion, Zero, One, Block, Header, NumberFor};
 use consensus_common::import_queue::{ImportQueue, IncomingBlock, Link, BlockImportError, BlockImportResult};
@@ -28,18 +32,18 @@ use network::message;
 use consensus_common::BlockOrigin;
 use crate::components::{self, Components, ServiceFactory, FactoryFullConfiguration, FactoryBlockNumber, RuntimeGenesis};
 use crate::new_client;
-use parity_codec::{Decode, Encode};
+use parity_codec::{Compact, Decode, Encode};
 use crate::error;
-use crate::chain_spec::ChainSpec;
+use chain_spec::ChainSpec;
 
-/// Export a range of blocks to a binary stream.
+/// Export a range of blocks to a binary or JSON stream.
 pub fn export_blocks<F, E, W>(
 	config: FactoryFullConfiguration<F>,
 	exit: E,
 	mut output: W,
 	from: FactoryBlockNumber<F>,
 	to: Option<FactoryBlockNumber<F>>,
-	json: bool
+	binary: bool
 ) -> error::Result<()>
 	where
 	F: ServiceFactory,
@@ -65,7 +69,7 @@ pub fn export_blocks<F, E, W>(
 		let _ = exit_send.send(());
 	});
 	info!("Exporting blocks from #{} to #{}", block, last);
-	if !json {
+	if binary {
 		let last_: u64 = last.saturated_into::<u64>();
 		let block_: u64 = block.saturated_into::<u64>();
 		let len: u64 = last_ - block_ + 1;
@@ -78,11 +82,11 @@ pub fn export_blocks<F, E, W>(
 		}
 		match client.block(&BlockId::number(block))? {
 			Some(block) => {
-				if json {
+				if binary {
+					output.write(&block.encode())?;
+				} else {
 					serde_json::to_writer(&mut output, &block)
 						.map_err(|e| format!("Error writing JSON: {}", e))?;
-				} else {
-					output.write(&block.encode())?;
 				}
 			},
 			None => break,
@@ -98,6 +102,80 @@ pub fn export_blocks<F, E, W>(
 	Ok(())
 }
 
+/// Bytes of a JSON exported block, either as a hex string or as an array of numbers.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonBytes {
+	Hex(Bytes),
+	Raw(Vec<u8>),
+}
+
+impl JsonBytes {
+	fn into_vec(self) -> Vec<u8> {
+		match self {
+			JsonBytes::Hex(bytes) => bytes.0,
+			JsonBytes::Raw(bytes) => bytes,
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct JsonDigest {
+	logs: Vec<JsonBytes>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonHeader {
+	parent_hash: JsonBytes,
+	number: U256,
+	state_root: JsonBytes,
+	extrinsics_root: JsonBytes,
+	digest: JsonDigest,
+}
+
+#[derive(Deserialize)]
+struct JsonBlock {
+	header: JsonHeader,
+	extrinsics: Vec<JsonBytes>,
+}
+
+/// A block as written by `export_blocks` in JSON mode.
+///
+/// The generic block types can't be deserialized, so the block is read through this
+/// mirror and re-encoded in the binary format instead.
+#[derive(Deserialize)]
+struct JsonSignedBlock {
+	block: JsonBlock,
+	justification: Option<Justification>,
+}
+
+impl JsonSignedBlock {
+	fn encode(self) -> Result<Vec<u8>, String> {
+		fn encode_list(items: Vec<JsonBytes>, out: &mut Vec<u8>) {
+			Compact(items.len() as u32).encode_to(out);
+			for item in items {
+				out.extend(item.into_vec());
+			}
+		}
+
+		let header = self.block.header;
+		if header.number > U256::from(u64::max_value()) {
+			return Err(format!("Invalid block number {}", header.number));
+		}
+
+		let mut out = Vec::new();
+		out.extend(header.parent_hash.into_vec());
+		Compact(header.number.low_u64()).encode_to(&mut out);
+		out.extend(header.state_root.into_vec());
+		out.extend(header.extrinsics_root.into_vec());
+		encode_list(header.digest.logs, &mut out);
+		encode_list(self.block.extrinsics, &mut out);
+		self.justification.encode_to(&mut out);
+		Ok(out)
+	}
+}
+
 struct WaitLink {
 	imported_blocks: u64,
 }
@@ -124,11 +202,12 @@ impl<B: Block> Link<B> for WaitLink {
 	}
 }
 
-/// Returns a future that import blocks from a binary stream.
+/// Returns a future that import blocks from a binary or JSON stream.
 pub fn import_blocks<F, E, R>(
 	mut config: FactoryFullConfiguration<F>,
 	exit: E,
-	mut input: R
+	mut input: R,
+	binary: bool,
 ) -> error::Result<impl Future<Item = (), Error = ()>>
 	where F: ServiceFactory, E: Future<Item=(),Error=()> + Send + 'static, R: Read,
 {
@@ -147,42 +226,64 @@ pub fn import_blocks<F, E, R>(
 		let _ = exit_send.send(());
 	});
 
-	let count: u64 = Decode::decode(&mut input).ok_or("Error reading file")?;
-	info!("Importing {} blocks", count);
-	let mut block_count = 0;
-	for b in 0 .. count {
+	let blocks: Box<dyn Iterator<Item = Result<SignedBlock<F::Block>, String>> + '_> = if binary {
+		let count: u64 = Decode::decode(&mut input).ok_or("Error reading file")?;
+		info!("Importing {} blocks", count);
+		Box::new((0 .. count).map(move |b| SignedBlock::<F::Block>::decode(&mut input)
+			.ok_or_else(|| format!("Error reading block data at {}.", b))
+		))
+	} else {
+		info!("Importing blocks from JSON");
+		Box::new(serde_json::Deserializer::from_reader(input)
+			.into_iter::<JsonSignedBlock>()
+			.enumerate()
+			.map(|(b, block)| block
+				.map_err(|e| e.to_string())
+				.and_then(JsonSignedBlock::encode)
+				.and_then(|encoded| SignedBlock::<F::Block>::decode(&mut &encoded[..])
+					.ok_or_else(|| "Invalid block encoding".into())
+				)
+				.map_err(|e| format!("Error reading block data at {}: {}", b, e))
+			))
+	};
+
+	let mut count = 0u64;
+	for signed in blocks {
 		if exit_recv.try_recv().is_ok() {
 			break;
 		}
-		if let Some(signed) = SignedBlock::<F::Block>::decode(&mut input) {
-			let (header, extrinsics) = signed.block.deconstruct();
-			let hash = header.hash();
-			let block  = message::BlockData::<F::Block> {
-				hash,
-				justification: signed.justification,
-				header: Some(header),
-				body: Some(extrinsics),
-				receipt: None,
-				message_queue: None
-			};
-			// import queue handles verification and importing it into the client
-			queue.import_blocks(BlockOrigin::File, vec![
-				IncomingBlock::<F::Block>{
-					hash: block.hash,
-					header: block.header,
-					body: block.body,
-					justification: block.justification,
-					origin: None,
-				}
-			]);
-		} else {
-			warn!("Error reading block data at {}.", b);
-			break;
-		}
+		let signed = match signed {
+			Ok(signed) => signed,
+			Err(e) => {
+				warn!("{}", e);
+				break;
+			}
+		};
+
+		let (header, extrinsics) = signed.block.deconstruct();
+		let hash = header.hash();
+		let block  = message::BlockData::<F::Block> {
+			hash,
+			justification: signed.justification,
+			header: Some(header),
+			body: Some(extrinsics),
+			receipt: None,
+			message_queue: None
+		};
+		// import queue handles verification and importing it into the client
+		queue.import_blocks(BlockOrigin::File, vec![
+			IncomingBlock::<F::Block>{
+				hash: block.hash,
+				header: block.header,
+				body: block.body,
+				justification: block.justification,
+				origin: None,
+			}
+		]);
 
-		block_count = b;
-		if b % 1000 == 0 && b != 0 {
-			info!("#{} blocks were added to the queue", b);
+		count += 1;
+		if count % 1000 == 0 {
+			info!("#{} blocks were added to the queue", count);
 		}
 	}
 
@@ -202,7 +303,7 @@ pub fn import_blocks<F, E, R>(
 			);
 		}
 		if link.imported_blocks >= count {
-			info!("Imported {} blocks. Best: #{}", block_count, client.info().chain.best_number);
+			info!("Imported {} blocks. Best: #{}", count, client.info().chain.best_number);
 			Ok(Async::Ready(()))
 		} else {
 			Ok(Async::NotReady)
@@ -210,6 +311,36 @@ pub fn import_blocks<F, E, R>(
 	}))
 }
 
+/// Re-executes an existing block on top of the state of its parent.
+pub fn check_block<F>(
+	config: FactoryFullConfiguration<F>,
+	block_id: BlockId<F::Block>
+) -> error::Result<()>
+	where F: ServiceFactory,
+{
+	let client = new_client::<F>(&config)?;
+	let block = client.block(&block_id)?.ok_or("Unknown block")?.block;
+	let header = block.header().clone();
+	info!("Checking block #{} ({})", header.number(), header.hash());
+
+	let start = std::time::Instant::now();
+	client.executor().call::<NeverOffchainExt>(
+		&BlockId::Hash(*header.parent_hash()),
+		"Core_execute_block",
+		&block.encode(),
+		ExecutionStrategy::NativeElseWasm,
+		None,
+	)?;
+	let elapsed = start.elapsed();
+	info!(
+		"Block #{} is valid. Executed in {}.{:03}s",
+		header.number(),
+		elapsed.as_secs(),
+		elapsed.subsec_millis(),
+	);
+	Ok(())
+}
+
 /// Revert the chain.
 pub fn revert_chain<F>(
 	config: FactoryFullConfiguration<F>,
@@ -229,9 +360,49 @@ pub fn revert_chain<F>(
 	Ok(())
 }
 
+/// Migrate the RocksDb database of the chain to ParityDb.
+pub fn migrate_database<F>(config: FactoryFullConfiguration<F>) -> error::Result<()>
+	where F: ServiceFactory,
+{
+	info!("Migrating database at {:?} to ParityDb", config.database_path);
+	let copied = client_db::parity_db::migrate_rocksdb(&config.database_path)?;
+	info!(
+		"Migrated {} entries. Run the node with `--database ParityDb` to use the new database.",
+		copied,
+	);
+	Ok(())
+}
+
 /// Build a chain spec json
 pub fn build_spec<G>(spec: ChainSpec<G>, raw: bool) -> error::Result<String>
 	where G: RuntimeGenesis,
 {
 	Ok(spec.to_json(raw)?)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_primitives::generic::DigestItem;
+	use substrate_test_runtime_client::runtime::{Block as TestBlock, Extrinsic, Header};
+
+	#[test]
+	fn json_blocks_are_re_encoded() {
+		let mut header = Header::new(
+			42,
+			[1; 32].into(),
+			[2; 32].into(),
+			[3; 32].into(),
+			Default::default(),
+		);
+		header.digest_mut().push(DigestItem::PreRuntime(*b"test", vec![4, 5]));
+		let signed = SignedBlock {
+			block: TestBlock::new(header, vec![Extrinsic::IncludeData(vec![6, 7, 8])]),
+			justification: Some(vec![9]),
+		};
+
+		let json = serde_json::to_string(&signed).unwrap();
+		let decoded: JsonSignedBlock = serde_json::from_str(&json).unwrap();
+		assert_eq!(decoded.encode().unwrap(), signed.encode());
+	}
+}
@@ -17,20 +17,19 @@
 //! Substrate service components.
 
 use std::{sync::Arc, ops::Deref, ops::DerefMut};
-use serde::{Serialize, de::DeserializeOwned};
-use crate::chain_spec::ChainSpec;
+use chain_spec::ChainSpec;
 use client_db;
 use client::{self, Client, runtime_api};
-use crate::{error, Service, AuthorityKeyProvider};
+use crate::{error, Service, ServiceBuilder, AuthorityKeyProvider};
 use consensus_common::{import_queue::ImportQueue, SelectChain};
 use network::{self, OnDemand, FinalityProofProvider, NetworkStateInfo, config::BoxFinalityProofRequestBuilder};
 use substrate_executor::{NativeExecutor, NativeExecutionDispatch};
 use transaction_pool::txpool::{self, Options as TransactionPoolOptions, Pool as TransactionPool};
 use runtime_primitives::{
-	BuildStorage, traits::{Block as BlockT, Header as HeaderT, ProvideRuntimeApi}, generic::BlockId
+	traits::{Block as BlockT, Header as HeaderT, ProvideRuntimeApi}, generic::BlockId
 };
 use crate::config::Configuration;
-use primitives::{Blake2Hasher, H256, Pair};
+use primitives::{Blake2Hasher, H256, Pair, traits::KeyStorePtr};
 use rpc::{self, apis::system::SystemInfo};
 use futures::{prelude::*, future::Executor, sync::mpsc};
 
@@ -147,9 +146,7 @@ pub type ComponentExtrinsic<C> = <ComponentBlock<C> as BlockT>::Extrinsic;
 /// Extrinsic pool API type for `Components`.
 pub type PoolApi<C> = <C as Components>::TransactionPoolApi;
 
-/// A set of traits for the runtime genesis config.
-pub trait RuntimeGenesis: Serialize + DeserializeOwned + BuildStorage {}
-impl<T: Serialize + DeserializeOwned + BuildStorage> RuntimeGenesis for T {}
+pub use chain_spec::RuntimeGenesis;
 
 /// Something that can start the RPC service.
 pub trait StartRPC<C: Components> {
@@ -159,12 +156,15 @@ pub trait StartRPC<C: Components> {
 		system_info: SystemInfo,
 		task_executor: TaskExecutor,
 		transaction_pool: Arc<TransactionPool<C::TransactionPoolApi>>,
+		keystore: Option<KeyStorePtr>,
+		deny_unsafe: rpc::apis::DenyUnsafe,
 	) -> rpc::RpcHandler;
 }
 
 impl<C: Components> StartRPC<Self> for C where
 	ComponentClient<C>: ProvideRuntimeApi,
-	<ComponentClient<C> as ProvideRuntimeApi>::Api: runtime_api::Metadata<ComponentBlock<C>>,
+	<ComponentClient<C> as ProvideRuntimeApi>::Api:
+		runtime_api::Metadata<ComponentBlock<C>> + session::SessionKeys<ComponentBlock<C>>,
 {
 	fn start_rpc(
 		client: Arc<ComponentClient<C>>,
@@ -172,11 +172,19 @@ impl<C: Components> StartRPC<Self> for C where
 		rpc_system_info: SystemInfo,
 		task_executor: TaskExecutor,
 		transaction_pool: Arc<TransactionPool<C::TransactionPoolApi>>,
+		keystore: Option<KeyStorePtr>,
+		deny_unsafe: rpc::apis::DenyUnsafe,
 	) -> rpc::RpcHandler {
 		let subscriptions = rpc::apis::Subscriptions::new(task_executor.clone());
 		let chain = rpc::apis::chain::Chain::new(client.clone(), subscriptions.clone());
 		let state = rpc::apis::state::State::new(client.clone(), subscriptions.clone());
-		let author = rpc::apis::author::Author::new(client, transaction_pool, subscriptions);
+		let author = rpc::apis::author::Author::new(
+			client,
+			transaction_pool,
+			subscriptions,
+			keystore,
+			deny_unsafe,
+		);
 		let system = rpc::apis::system::System::new(rpc_system_info, system_send_back);
 		rpc::rpc_handler::<ComponentBlock<C>, ComponentExHash<C>, _, _, _, _>(
 			state,
@@ -189,8 +197,12 @@ impl<C: Components> StartRPC<Self> for C where
 
 /// Something that can maintain transaction pool on every imported block.
 pub trait MaintainTransactionPool<C: Components> {
+	/// Updates the pool after the import of the block `id`.
+	///
+	/// `retracted` are the blocks that left the best chain because of the import.
 	fn maintain_transaction_pool(
 		id: &BlockId<ComponentBlock<C>>,
+		retracted: &[<ComponentBlock<C> as BlockT>::Hash],
 		client: &ComponentClient<C>,
 		transaction_pool: &TransactionPool<C::TransactionPoolApi>,
 	) -> error::Result<()>;
@@ -198,6 +210,7 @@ pub trait MaintainTransactionPool<C: Components> {
 
 fn maintain_transaction_pool<Api, Backend, Block, Executor, PoolApi>(
 	id: &BlockId<Block>,
+	retracted: &[Block::Hash],
 	client: &Client<Backend, Executor, Block, Api>,
 	transaction_pool: &TransactionPool<PoolApi>,
 ) -> error::Result<()> where
@@ -209,10 +222,18 @@ fn maintain_transaction_pool<Api, Backend, Block, Executor, PoolApi>(
 	PoolApi: txpool::ChainApi<Hash = Block::Hash, Block = Block>,
 {
 	// Avoid calling into runtime if there is nothing to prune from the pool anyway.
-	if transaction_pool.status().is_empty() {
+	if transaction_pool.status().is_empty() && retracted.is_empty() {
 		return Ok(())
 	}
 
+	// Put back the transactions of the blocks that left the best chain. Those that are
+	// invalid on the new best chain, e.g. because they are included in it too, are rejected.
+	for hash in retracted {
+		if let Some(block) = client.block(&BlockId::hash(*hash))? {
+			let _ = transaction_pool.submit_at(id, block.block.extrinsics().iter().cloned());
+		}
+	}
+
 	if let Some(block) = client.block(id)? {
 		let parent_id = BlockId::hash(*block.block.header().parent_hash());
 		let extrinsics = block.block.extrinsics();
@@ -228,10 +249,11 @@ impl<C: Components> MaintainTransactionPool<Self> for C where
 {
 	fn maintain_transaction_pool(
 		id: &BlockId<ComponentBlock<C>>,
+		retracted: &[<ComponentBlock<C> as BlockT>::Hash],
 		client: &ComponentClient<C>,
 		transaction_pool: &TransactionPool<C::TransactionPoolApi>,
 	) -> error::Result<()> {
-		maintain_transaction_pool(id, client, transaction_pool)
+		maintain_transaction_pool(id, retracted, client, transaction_pool)
 	}
 }
 
@@ -408,9 +430,12 @@ pub trait Components: Sized + 'static {
 	type SelectChain: SelectChain<FactoryBlock<Self::Factory>>;
 
 	/// Create client.
+	///
+	/// The `keystore` is made available to the runtime.
 	fn build_client(
 		config: &FactoryFullConfiguration<Self::Factory>,
 		executor: CodeExecutor<Self::Factory>,
+		keystore: Option<KeyStorePtr>,
 	) -> Result<
 		(
 			Arc<ComponentClient<Self>>,
@@ -459,6 +484,15 @@ impl<Factory: ServiceFactory> FullComponents<Factory> {
 			}
 		)
 	}
+
+	/// Create new `FullComponents` from a `ServiceBuilder`.
+	pub fn from_builder(builder: ServiceBuilder<Self>) -> Result<Self, error::Error> {
+		Ok(
+			Self {
+				service: builder.build()?,
+			}
+		)
+	}
 }
 
 impl<Factory: ServiceFactory> Deref for FullComponents<Factory> {
@@ -497,6 +531,7 @@ impl<Factory: ServiceFactory> Components for FullComponents<Factory> {
 	fn build_client(
 		config: &FactoryFullConfiguration<Factory>,
 		executor: CodeExecutor<Self::Factory>,
+		keystore: Option<KeyStorePtr>,
 	)
 		-> Result<(
 			Arc<ComponentClient<Self>>,
@@ -509,13 +544,16 @@ impl<Factory: ServiceFactory> Components for FullComponents<Factory> {
 			state_cache_child_ratio:
 				config.state_cache_child_ratio.map(|v| (v, 100)),
 			path: config.database_path.clone(),
+			backend: config.database,
 			pruning: config.pruning.clone(),
+			blocks_pruning: config.blocks_pruning,
 		};
 		Ok((Arc::new(client_db::new_client(
 			db_settings,
 			executor,
 			&config.chain_spec,
 			config.execution_strategies.clone(),
+			keystore,
 		)?), None))
 	}
 
@@ -567,6 +605,15 @@ impl<Factory: ServiceFactory> LightComponents<Factory> {
 			}
 		)
 	}
+
+	/// Create new `LightComponents` from a `ServiceBuilder`.
+	pub fn from_builder(builder: ServiceBuilder<Self>) -> Result<Self, error::Error> {
+		Ok(
+			Self {
+				service: builder.build()?,
+			}
+		)
+	}
 }
 
 impl<Factory: ServiceFactory> Deref for LightComponents<Factory> {
@@ -599,6 +646,7 @@ impl<Factory: ServiceFactory> Components for LightComponents<Factory> {
 	fn build_client(
 		config: &FactoryFullConfiguration<Factory>,
 		executor: CodeExecutor<Self::Factory>,
+		_keystore: Option<KeyStorePtr>,
 	)
 		-> Result<
 			(
@@ -612,7 +660,9 @@ impl<Factory: ServiceFactory> Components for LightComponents<Factory> {
 			state_cache_child_ratio:
 				config.state_cache_child_ratio.map(|v| (v, 100)),
 			path: config.database_path.clone(),
+			backend: config.database,
 			pruning: config.pruning.clone(),
+			blocks_pruning: None,
 		};
 		let db_storage = client_db::light::LightStorage::new(db_settings)?;
 		let light_blockchain = client::light::new_light_blockchain(db_storage);
@@ -655,7 +705,7 @@ impl<Factory: ServiceFactory> Components for LightComponents<Factory> {
 mod tests {
 	use super::*;
 	use consensus_common::BlockOrigin;
-	use substrate_test_runtime_client::{prelude::*, runtime::Transfer};
+	use substrate_test_runtime_client::{prelude::*, runtime::{Extrinsic, Transfer}};
 
 	#[test]
 	fn should_remove_transactions_from_the_pool() {
@@ -684,6 +734,7 @@ mod tests {
 		assert_eq!(pool.status().ready, 1);
 		maintain_transaction_pool(
 			&id,
+			&[],
 			&client,
 			&pool,
 		).unwrap();
@@ -692,4 +743,43 @@ mod tests {
 		assert_eq!(pool.status().ready, 0);
 		assert_eq!(pool.status().future, 0);
 	}
+
+	#[test]
+	fn should_resubmit_transactions_of_retracted_blocks() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let pool = TransactionPool::new(Default::default(), ::transaction_pool::ChainApi::new(client.clone()));
+		let transaction = Transfer {
+			amount: 5,
+			nonce: 0,
+			from: AccountKeyring::Alice.into(),
+			to: Default::default(),
+		}.into_signed_tx();
+
+		// G -> A1, with A1 including the transaction
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push(transaction.clone()).unwrap();
+		let a1 = builder.bake().unwrap();
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+
+		// G -> B1 -> B2, which becomes the best chain
+		let mut builder = client.new_block_at(&BlockId::number(0), Default::default(), false).unwrap();
+		builder.push(Extrinsic::IncludeData(vec![1])).unwrap();
+		let b1 = builder.bake().unwrap();
+		client.import(BlockOrigin::Own, b1.clone()).unwrap();
+		let b2 = client.new_block_at(&BlockId::hash(b1.hash()), Default::default(), false).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, b2.clone()).unwrap();
+
+		// when
+		assert_eq!(pool.status().ready, 0);
+		maintain_transaction_pool(
+			&BlockId::hash(b2.hash()),
+			&[a1.hash()],
+			&client,
+			&pool,
+		).unwrap();
+
+		// then
+		assert_eq!(pool.status().ready, 1);
+		assert_eq!(pool.ready().next().map(|t| t.data.clone()), Some(transaction));
+	}
 }
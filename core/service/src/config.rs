@@ -17,18 +17,50 @@
 //! Service configuration.
 
 pub use client::ExecutionStrategies;
-pub use client_db::PruningMode;
+pub use client_db::{PruningMode, DatabaseBackend};
 pub use network::config::{ExtTransport, NetworkConfiguration, Roles};
+pub use substrate_executor::WasmExecutionMethod;
 
 use std::{path::PathBuf, net::SocketAddr};
 use transaction_pool;
-use crate::chain_spec::ChainSpec;
+use chain_spec::ChainSpec;
+use keystore::RemoteAddress;
 use primitives::crypto::Protected;
 use runtime_primitives::BuildStorage;
 use serde::{Serialize, de::DeserializeOwned};
 use target_info::Target;
 use tel::TelemetryEndpoints;
 
+/// Which RPC methods are exposed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcMethods {
+	/// Unsafe methods are only exposed on servers listening on a loopback interface.
+	Auto,
+	/// Only safe methods are exposed.
+	Safe,
+	/// All methods are exposed, on every interface.
+	Unsafe,
+}
+
+impl Default for RpcMethods {
+	fn default() -> Self {
+		RpcMethods::Auto
+	}
+}
+
+/// Where the keys of the node are stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeystoreConfig {
+	/// No keystore.
+	None,
+	/// Key files at the given path, encrypted with the node keystore's password if set.
+	Path(PathBuf),
+	/// Keys that are kept in memory only and are lost when the node stops.
+	InMemory,
+	/// Keys that are kept by an external signer, listening on the given address.
+	Remote(RemoteAddress),
+}
+
 /// Service configuration.
 #[derive(Clone)]
 pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
@@ -44,18 +76,22 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub transaction_pool: transaction_pool::txpool::Options,
 	/// Network configuration.
 	pub network: NetworkConfiguration,
-	/// Path to key files.
-	pub keystore_path: Option<PathBuf>,
+	/// Keystore configuration.
+	pub keystore: KeystoreConfig,
 	/// Path to the database.
 	pub database_path: PathBuf,
 	/// Cache Size for internal database in MiB
 	pub database_cache_size: Option<u32>,
+	/// Database implementation.
+	pub database: DatabaseBackend,
 	/// Size of internal state cache in Bytes
 	pub state_cache_size: usize,
 	/// Size in percent of cache size dedicated to child tries
 	pub state_cache_child_ratio: Option<usize>,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Number of finalized blocks whose bodies and justifications are kept. `None` keeps all.
+	pub blocks_pruning: Option<u32>,
 	/// Additional key seeds.
 	pub keys: Vec<String>,
 	/// Chain configuration.
@@ -74,6 +110,10 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub rpc_ws_max_connections: Option<usize>,
 	/// CORS settings for HTTP & WS servers. `None` if all origins are allowed.
 	pub rpc_cors: Option<Vec<String>>,
+	/// Which RPC methods the HTTP & WS servers expose.
+	pub rpc_methods: RpcMethods,
+	/// Prometheus metrics endpoint binding address. `None` if disabled.
+	pub prometheus_port: Option<SocketAddr>,
 	/// Telemetry service URL. `None` if disabled.
 	pub telemetry_endpoints: Option<TelemetryEndpoints>,
 	/// External WASM transport for the telemetry. If `Some`, when connection to a telemetry
@@ -81,6 +121,8 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub telemetry_external_transport: Option<ExtTransport>,
 	/// The default number of 64KB pages to allocate for Wasm execution
 	pub default_heap_pages: Option<u64>,
+	/// Engine used to execute Wasm runtimes.
+	pub wasm_method: WasmExecutionMethod,
 	/// Should offchain workers be executed.
 	pub offchain_worker: bool,
 	/// Enable authoring even when offline.
@@ -106,22 +148,27 @@ impl<C: Default, G: Serialize + DeserializeOwned + BuildStorage> Configuration<C
 			roles: Roles::FULL,
 			transaction_pool: Default::default(),
 			network: Default::default(),
-			keystore_path: Default::default(),
+			keystore: KeystoreConfig::None,
 			database_path: Default::default(),
 			database_cache_size: Default::default(),
+			database: Default::default(),
 			state_cache_size: Default::default(),
 			state_cache_child_ratio: Default::default(),
 			keys: Default::default(),
 			custom: Default::default(),
 			pruning: PruningMode::default(),
+			blocks_pruning: None,
 			execution_strategies: Default::default(),
 			rpc_http: None,
 			rpc_ws: None,
 			rpc_ws_max_connections: None,
 			rpc_cors: Some(vec![]),
+			rpc_methods: Default::default(),
+			prometheus_port: None,
 			telemetry_endpoints: None,
 			telemetry_external_transport: None,
 			default_heap_pages: None,
+			wasm_method: Default::default(),
 			offchain_worker: Default::default(),
 			force_authoring: false,
 			disable_grandpa: false,
@@ -20,10 +20,12 @@
 #![warn(missing_docs)]
 
 mod components;
-mod chain_spec;
+pub mod builder;
 pub mod config;
 pub mod chain_ops;
 pub mod error;
+pub mod metrics;
+pub mod task_manager;
 
 use std::io;
 use std::marker::PhantomData;
@@ -33,15 +35,14 @@ use std::time::{Duration, Instant};
 use futures::sync::mpsc;
 use parking_lot::Mutex;
 
-use client::{BlockchainEvents, backend::Backend, runtime_api::BlockT};
-use exit_future::Signal;
+use client::{BlockchainEvents, CallExecutor, backend::Backend, runtime_api::{BlockT, RuntimeVersion}};
 use futures::prelude::*;
 use futures03::stream::{StreamExt as _, TryStreamExt as _};
-use keystore::Store as Keystore;
+use keystore::{BareCryptoStore, CryptoStore, MemoryStore, RemoteSigner, Signer, Store as Keystore};
 use network::{NetworkState, NetworkStateInfo};
 use log::{log, info, warn, debug, error, Level};
 use parity_codec::{Encode, Decode};
-use primitives::{Pair, ed25519, crypto};
+use primitives::{Pair, ed25519, crypto::{Public, TypedKey}, traits::KeyStorePtr};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::{Header, NumberFor, SaturatedConversion, Zero};
 use substrate_executor::NativeExecutor;
@@ -49,8 +50,10 @@ use sysinfo::{get_current_pid, ProcessExt, System, SystemExt};
 use tel::{telemetry, SUBSTRATE_INFO};
 
 pub use self::error::Error;
-pub use config::{Configuration, Roles, PruningMode};
-pub use chain_spec::{ChainSpec, Properties};
+pub use builder::{ServiceBuilder, RpcDeps};
+pub use task_manager::{SpawnTaskHandle, TaskManager};
+pub use config::{Configuration, Roles, PruningMode, DatabaseBackend, KeystoreConfig, RpcMethods, WasmExecutionMethod};
+pub use chain_spec::{ChainSpec, Properties, Extensions as ChainSpecExtensions};
 pub use transaction_pool::txpool::{
 	self, Pool as TransactionPool, Options as TransactionPoolOptions, ChainApi, IntoPoolError
 };
@@ -86,22 +89,15 @@ pub struct Service<Components: components::Components> {
 	)>>>>,
 	transaction_pool: Arc<TransactionPool<Components::TransactionPoolApi>>,
 	keystore: ComponentAuthorityKeyProvider<Components>,
-	exit: ::exit_future::Exit,
-	signal: Option<Signal>,
-	/// Sender for futures that must be spawned as background tasks.
-	to_spawn_tx: mpsc::UnboundedSender<Box<dyn Future<Item = (), Error = ()> + Send>>,
-	/// Receiver for futures that must be spawned as background tasks.
-	to_spawn_rx: mpsc::UnboundedReceiver<Box<dyn Future<Item = (), Error = ()> + Send>>,
-	/// List of futures to poll from `poll`.
-	/// If spawning a background task is not possible, we instead push the task into this `Vec`.
-	/// The elements must then be polled manually.
-	to_poll: Vec<Box<dyn Future<Item = (), Error = ()> + Send>>,
+	/// Background tasks of the service.
+	task_manager: TaskManager,
 	/// Configuration of this Service
 	pub config: FactoryFullConfiguration<Components::Factory>,
 	rpc_handlers: rpc::RpcHandler,
 	_rpc: Box<dyn std::any::Any + Send + Sync>,
 	_telemetry: Option<tel::Telemetry>,
 	_telemetry_on_connect_sinks: Arc<Mutex<Vec<mpsc::UnboundedSender<()>>>>,
+	metrics: metrics::Registry,
 	_offchain_workers: Option<Arc<offchain::OffchainWorkers<
 		ComponentClient<Components>,
 		ComponentOffchainStorage<Components>,
@@ -114,34 +110,15 @@ pub struct Service<Components: components::Components> {
 pub fn new_client<Factory: components::ServiceFactory>(config: &FactoryFullConfiguration<Factory>)
 	-> Result<Arc<ComponentClient<components::FullComponents<Factory>>>, error::Error>
 {
-	let executor = NativeExecutor::new(config.default_heap_pages);
+	let executor = NativeExecutor::with_wasm_method(config.wasm_method, config.default_heap_pages);
 	let (client, _) = components::FullComponents::<Factory>::build_client(
 		config,
 		executor,
+		None,
 	)?;
 	Ok(client)
 }
 
-/// An handle for spawning tasks in the service.
-#[derive(Clone)]
-pub struct SpawnTaskHandle {
-	sender: mpsc::UnboundedSender<Box<dyn Future<Item = (), Error = ()> + Send>>,
-}
-
-impl Executor<Box<dyn Future<Item = (), Error = ()> + Send>> for SpawnTaskHandle {
-	fn execute(
-		&self,
-		future: Box<dyn Future<Item = (), Error = ()> + Send>
-	) -> Result<(), futures::future::ExecuteError<Box<dyn Future<Item = (), Error = ()> + Send>>> {
-		if let Err(err) = self.sender.unbounded_send(future) {
-			let kind = futures::future::ExecuteErrorKind::Shutdown;
-			Err(futures::future::ExecuteError::new(kind, err.into_inner()))
-		} else {
-			Ok(())
-		}
-	}
-}
-
 /// Stream of events for connection established to a telemetry server.
 pub type TelemetryOnConnectNotifications = mpsc::UnboundedReceiver<()>;
 
@@ -160,28 +137,50 @@ impl<Components: components::Components> Service<Components> {
 	}
 
 	/// Creates a new service.
+	///
+	/// All parts of the service are built by `Components`, use `ServiceBuilder` to replace some
+	/// of them.
 	pub fn new(
-		mut config: FactoryFullConfiguration<Components::Factory>,
+		config: FactoryFullConfiguration<Components::Factory>,
 	) -> Result<Self, error::Error> {
-		let (signal, exit) = ::exit_future::signal();
+		ServiceBuilder::new(config).build()
+	}
 
-		// List of asynchronous tasks to spawn. We collect them, then spawn them all at once.
-		let (to_spawn_tx, to_spawn_rx) =
-			mpsc::unbounded::<Box<dyn Future<Item = (), Error = ()> + Send>>();
+	/// Creates a new service from the parts provided by `builder`.
+	fn from_builder(builder: ServiceBuilder<Components>) -> Result<Self, error::Error> {
+		let ServiceBuilder {
+			mut config,
+			select_chain: build_select_chain,
+			import_queue: build_import_queue,
+			finality_proof_provider: build_finality_proof_provider,
+			transaction_pool: build_transaction_pool,
+			network_protocol: build_network_protocol,
+			rpc_extensions,
+		} = builder;
+
+		// Asynchronous tasks are collected by the task manager, then spawned all at once.
+		let task_manager = TaskManager::new();
+		let spawn_handle = task_manager.spawn_handle();
 
 		// Create client
-		let executor = NativeExecutor::new(config.default_heap_pages);
+		let executor = NativeExecutor::with_wasm_method(config.wasm_method, config.default_heap_pages);
 
-		let mut keystore = if let Some(keystore_path) = config.keystore_path.as_ref() {
-			match Keystore::open(keystore_path.clone()) {
-				Ok(ks) => Some(ks),
+		let keystore: Option<Arc<dyn BareCryptoStore>> = match config.keystore {
+			KeystoreConfig::Path(ref path) => match Keystore::open(path.clone(), Some(config.password.clone())) {
+				Ok(ks) => Some(Arc::new(ks)),
 				Err(err) => {
 					error!("Failed to initialize keystore: {}", err);
 					None
 				}
-			}
-		} else {
-			None
+			},
+			KeystoreConfig::InMemory => Some(Arc::new(MemoryStore::new())),
+			KeystoreConfig::Remote(_) | KeystoreConfig::None => None,
+		};
+
+		// All signing with authority keys goes through the crypto store.
+		let crypto_store: Option<Arc<dyn CryptoStore>> = match config.keystore {
+			KeystoreConfig::Remote(ref address) => Some(Arc::new(RemoteSigner::new(address.clone())?)),
+			_ => keystore.clone().map(|keystore| Arc::new(keystore) as Arc<dyn CryptoStore>),
 		};
 
 		// Keep the public key for telemetry
@@ -189,7 +188,7 @@ impl<Components: components::Components> Service<Components> {
 
 		// This is meant to be for testing only
 		// FIXME #1063 remove this
-		if let Some(keystore) = keystore.as_mut() {
+		if let Some(keystore) = keystore.as_ref() {
 			for seed in &config.keys {
 				keystore.generate_from_seed::<ed25519::Pair>(seed)?;
 			}
@@ -197,25 +196,35 @@ impl<Components: components::Components> Service<Components> {
 			public_key = match keystore.contents::<ed25519::Public>()?.get(0) {
 				Some(public_key) => public_key.to_string(),
 				None => {
-					let key: ed25519::Pair = keystore.generate(&config.password.as_ref())?;
+					let key: ed25519::Pair = keystore.generate()?;
 					let public_key = key.public();
 					info!("Generated a new keypair: {:?}", public_key);
 					public_key.to_string()
 				}
 			}
+		} else if let Some(crypto_store) = crypto_store.as_ref() {
+			public_key = match crypto_store.public_keys(ed25519::Public::KEY_TYPE) {
+				Ok(ref keys) if !keys.is_empty() => ed25519::Public::from_slice(&keys[0]).to_string(),
+				Ok(_) => format!("<remote-signer>"),
+				Err(err) => {
+					warn!("Failed to query the remote signer: {}", err);
+					format!("<remote-signer>")
+				}
+			}
 		} else {
 			public_key = format!("<disabled-keystore>");
 		}
 
-		let (client, on_demand) = Components::build_client(&config, executor)?;
-		let select_chain = Components::build_select_chain(&mut config, client.clone())?;
-		let (import_queue, finality_proof_request_builder) = Components::build_import_queue(
+		let runtime_keystore = keystore.clone().map(|keystore| Arc::new(keystore) as KeyStorePtr);
+		let (client, on_demand) = Components::build_client(&config, executor, runtime_keystore.clone())?;
+		let select_chain = build_select_chain(&mut config, client.clone())?;
+		let (import_queue, finality_proof_request_builder) = build_import_queue(
 			&mut config,
 			client.clone(),
 			select_chain.clone(),
 		)?;
 		let import_queue = Box::new(import_queue);
-		let finality_proof_provider = Components::build_finality_proof_provider(client.clone())?;
+		let finality_proof_provider = build_finality_proof_provider(client.clone())?;
 		let chain_info = client.info().chain;
 
 		let version = config.full_version();
@@ -225,9 +234,9 @@ impl<Components: components::Components> Service<Components> {
 			"best" => ?chain_info.best_hash
 		);
 
-		let network_protocol = <Components::Factory>::build_network_protocol(&config)?;
+		let network_protocol = build_network_protocol(&config)?;
 		let transaction_pool = Arc::new(
-			Components::build_transaction_pool(config.transaction_pool.clone(), client.clone())?
+			build_transaction_pool(config.transaction_pool.clone(), client.clone())?
 		);
 		let transaction_pool_adapter = Arc::new(TransactionPoolAdapter::<Components> {
 			imports_external_transactions: !config.roles.is_light(),
@@ -258,6 +267,7 @@ impl<Components: components::Components> Service<Components> {
 			transaction_pool: transaction_pool_adapter.clone() as _,
 			import_queue,
 			protocol_id,
+			fork_id: config.chain_spec.fork_id().map(str::to_owned),
 			specialization: network_protocol,
 		};
 
@@ -269,8 +279,7 @@ impl<Components: components::Components> Service<Components> {
 		let keystore_authority_key = AuthorityKeyProvider {
 			_marker: PhantomData,
 			roles: config.roles,
-			password: config.password.clone(),
-			keystore: keystore.map(Arc::new),
+			crypto_store,
 		};
 
 		#[allow(deprecated)]
@@ -291,25 +300,66 @@ impl<Components: components::Components> Service<Components> {
 			_ => None,
 		};
 
+		let metrics = metrics::Registry::with_prefix("substrate");
+		task_manager.register_metrics(&metrics);
+		start_metrics_server(&config.prometheus_port, &metrics, &spawn_handle)?;
+
 		{
 			// block notifications
 			let txpool = Arc::downgrade(&transaction_pool);
 			let wclient = Arc::downgrade(&client);
 			let offchain = offchain_workers.as_ref().map(Arc::downgrade);
-			let to_spawn_tx_ = to_spawn_tx.clone();
+			let spawn_handle_ = spawn_handle.clone();
 			let network_state_info: Arc<dyn NetworkStateInfo + Send + Sync> = network.clone();
+			let imported_blocks = metrics.register_counter(
+				"block_import_notifications_total",
+				"Number of blocks imported by the client",
+			);
+			let maintain_time = metrics.register_timer(
+				"transaction_pool_maintain_seconds",
+				"Time spent updating the transaction pool after a block import",
+			);
+
+			// Compare the native runtime with the on-chain one at startup, then whenever the
+			// runtime of the best block changes.
+			let best_block = BlockId::hash(client.info().chain.best_hash);
+			let mut onchain_version = client.runtime_version_at(&best_block).ok();
+			match onchain_version {
+				Some(ref onchain) => report_runtime_version(
+					client.executor().native_runtime_version().map(|v| &v.runtime_version),
+					onchain,
+				),
+				None => warn!("Unable to read the runtime version of the best block"),
+			}
 
 			let events = client.import_notification_stream()
 				.map(|v| Ok::<_, ()>(v)).compat()
 				.for_each(move |notification| {
 					let number = *notification.header.number();
+					imported_blocks.inc();
+
+					if let (true, Some(client)) = (notification.is_new_best, wclient.upgrade()) {
+						let onchain = client.runtime_version_at(&BlockId::hash(notification.hash)).ok();
+						if onchain.is_some() && onchain != onchain_version {
+							if let Some(ref onchain) = onchain {
+								report_runtime_version(
+									client.executor().native_runtime_version().map(|v| &v.runtime_version),
+									onchain,
+								);
+							}
+							onchain_version = onchain;
+						}
+					}
 
 					if let (Some(txpool), Some(client)) = (txpool.upgrade(), wclient.upgrade()) {
+						let started = Instant::now();
 						Components::RuntimeServices::maintain_transaction_pool(
 							&BlockId::hash(notification.hash),
+							&notification.retracted,
 							&*client,
 							&*txpool,
 						).map_err(|e| warn!("Pool error processing new block: {:?}", e))?;
+						maintain_time.observe(started.elapsed());
 					}
 
 					if let (Some(txpool), Some(offchain)) = (txpool.upgrade(), offchain.as_ref().and_then(|o| o.upgrade())) {
@@ -319,14 +369,12 @@ impl<Components: components::Components> Service<Components> {
 							&txpool,
 							&network_state_info,
 						).map_err(|e| warn!("Offchain workers error processing new block: {:?}", e))?;
-						let _ = to_spawn_tx_.unbounded_send(future);
+						spawn_handle_.spawn("offchain-worker", future);
 					}
 
 					Ok(())
-				})
-				.select(exit.clone())
-				.then(|_| Ok(()));
-			let _ = to_spawn_tx.unbounded_send(Box::new(events));
+				});
+			spawn_handle.spawn("block-import-notifications", events);
 		}
 
 		{
@@ -344,11 +392,9 @@ impl<Components: components::Components> Service<Components> {
 						"future" => status.future
 					);
 					Ok(())
-				})
-				.select(exit.clone())
-				.then(|_| Ok(()));
+				});
 
-			let _ = to_spawn_tx.unbounded_send(Box::new(events));
+			spawn_handle.spawn("transaction-import-notifications", events);
 		}
 
 		// Periodically notify the telemetry.
@@ -358,6 +404,7 @@ impl<Components: components::Components> Service<Components> {
 		let self_pid = get_current_pid().ok();
 		let (netstat_tx, netstat_rx) = mpsc::unbounded::<(NetworkStatus<ComponentBlock<Components>>, NetworkState)>();
 		network_status_sinks.lock().push(netstat_tx);
+		let node_metrics = NodeMetrics::register(&metrics);
 		let tel_task = netstat_rx.for_each(move |(net_status, network_state)| {
 			let info = client_.info();
 			let best_number = info.chain.best_number.saturated_into::<u64>();
@@ -374,6 +421,8 @@ impl<Components: components::Components> Service<Components> {
 				Some(size) => size,
 				None => 0,
 			};
+			let database_size = client_.usage_info().and_then(|usage| usage.database_size);
+			*node_metrics.protocols.lock() = network_state.protocols.clone();
 
 			// get cpu usage and memory usage of this process
 			let (cpu_usage, memory) = if let Some(self_pid) = self_pid {
@@ -399,42 +448,70 @@ impl<Components: components::Components> Service<Components> {
 				"bandwidth_download" => bandwidth_download,
 				"bandwidth_upload" => bandwidth_upload,
 				"used_state_cache_size" => used_state_cache_size,
+				"database_size" => ?database_size,
 			);
 
+			node_metrics.peers.set(num_peers as f64);
+			node_metrics.sync_peers.set(f64::from(net_status.num_sync_peers));
+			node_metrics.best_block.set(best_number as f64);
+			node_metrics.finalized_block.set(finalized_number as f64);
+			node_metrics.finality_lag.set(best_number.saturating_sub(finalized_number) as f64);
+			node_metrics.sync_target.set(net_status.best_seen_block
+				.map(|n| n.saturated_into::<u64>())
+				.unwrap_or(best_number) as f64
+			);
+			node_metrics.bandwidth_download.set(bandwidth_download as f64);
+			node_metrics.bandwidth_upload.set(bandwidth_upload as f64);
+			node_metrics.txpool_ready.set(txpool_status.ready as f64);
+			node_metrics.txpool_future.set(txpool_status.future as f64);
+			node_metrics.state_cache_size.set(used_state_cache_size as f64);
+			if let Some(database_size) = database_size {
+				node_metrics.database_size.set(database_size as f64);
+			}
+
 			Ok(())
-		}).select(exit.clone()).then(|_| Ok(()));
-		let _ = to_spawn_tx.unbounded_send(Box::new(tel_task));
+		});
+		spawn_handle.spawn("telemetry-periodic-send", tel_task);
 
 		// RPC
 		let (system_rpc_tx, system_rpc_rx) = mpsc::unbounded();
-		let gen_handler = || {
+		let gen_handler = |deny_unsafe: rpc::apis::DenyUnsafe| {
 			let system_info = rpc::apis::system::SystemInfo {
 				chain_name: config.chain_spec.name().into(),
 				impl_name: config.impl_name.into(),
 				impl_version: config.impl_version.into(),
 				properties: config.chain_spec.properties(),
 			};
-			Components::RuntimeServices::start_rpc(
+			let mut handler = Components::RuntimeServices::start_rpc(
 				client.clone(),
 				system_rpc_tx.clone(),
 				system_info.clone(),
-				Arc::new(SpawnTaskHandle { sender: to_spawn_tx.clone() }),
+				Arc::new(spawn_handle.clone()),
 				transaction_pool.clone(),
-			)
+				runtime_keystore.clone(),
+				deny_unsafe,
+			);
+			for extension in &rpc_extensions {
+				extension(&mut handler, builder::RpcDeps {
+					client: client.clone(),
+					pool: transaction_pool.clone(),
+					keystore: runtime_keystore.clone(),
+					spawn_handle: spawn_handle.clone(),
+				});
+			}
+			handler
 		};
-		let rpc_handlers = gen_handler();
+		// in-process queries come from the embedder of the node, so they can use every method.
+		let rpc_handlers = gen_handler(rpc::apis::DenyUnsafe::No);
 		let rpc = start_rpc_servers::<Components::Factory, _>(&config, gen_handler)?;
 
-		let _ = to_spawn_tx.unbounded_send(Box::new(build_network_future::<Components, _, _>(
+		spawn_handle.spawn_essential("network-worker", build_network_future::<Components, _, _>(
 			network_mut,
 			client.clone(),
 			network_status_sinks.clone(),
 			system_rpc_rx,
 			has_bootnodes
-		)
-			.map_err(|_| ())
-			.select(exit.clone())
-			.then(|_| Ok(()))));
+		).map_err(|_| ()));
 
 		let telemetry_connection_sinks: Arc<Mutex<Vec<mpsc::UnboundedSender<()>>>> = Default::default();
 
@@ -474,9 +551,7 @@ impl<Components: components::Components> Service<Components> {
 					});
 					Ok(())
 				});
-			let _ = to_spawn_tx.unbounded_send(Box::new(future
-				.select(exit.clone())
-				.then(|_| Ok(()))));
+			spawn_handle.spawn("telemetry-worker", future);
 			telemetry
 		});
 
@@ -486,30 +561,27 @@ impl<Components: components::Components> Service<Components> {
 			network_status_sinks,
 			select_chain,
 			transaction_pool,
-			signal: Some(signal),
-			to_spawn_tx,
-			to_spawn_rx,
-			to_poll: Vec::new(),
+			task_manager,
 			keystore: keystore_authority_key,
 			config,
-			exit,
 			rpc_handlers,
 			_rpc: rpc,
 			_telemetry: telemetry,
 			_offchain_workers: offchain_workers,
 			_telemetry_on_connect_sinks: telemetry_connection_sinks.clone(),
+			metrics,
 		})
 	}
 
 	/// give the authority key, if we are an authority and have a key
-	pub fn authority_key(&self) -> Option<ComponentConsensusPair<Components>> {
+	pub fn authority_key(&self) -> Option<Signer<ComponentConsensusPair<Components>>> {
 		use offchain::AuthorityKeyProvider;
 
 		self.keystore.authority_key(&BlockId::Number(Zero::zero()))
 	}
 
-	/// give the authority key, if we are an authority and have a key
-	pub fn fg_authority_key(&self) -> Option<ComponentFinalityPair<Components>> {
+	/// give the finality gadget authority key, if we are an authority and have a key
+	pub fn fg_authority_key(&self) -> Option<Signer<ComponentFinalityPair<Components>>> {
 		use offchain::AuthorityKeyProvider;
 
 		self.keystore.fg_authority_key(&BlockId::Number(Zero::zero()))
@@ -520,16 +592,47 @@ impl<Components: components::Components> Service<Components> {
 		self._telemetry.as_ref().map(|t| t.clone())
 	}
 
+	/// Returns the registry of the metrics exported by the node.
+	///
+	/// Custom subsystems can register their own collectors into it.
+	pub fn metrics(&self) -> metrics::Registry {
+		self.metrics.clone()
+	}
+
 	/// Spawns a task in the background that runs the future passed as parameter.
-	pub fn spawn_task(&self, task: impl Future<Item = (), Error = ()> + Send + 'static) {
-		let _ = self.to_spawn_tx.unbounded_send(Box::new(task));
+	///
+	/// The name is used for logging and metrics. The task is dropped when the service shuts down.
+	pub fn spawn_task(&self, name: &'static str, task: impl Future<Item = (), Error = ()> + Send + 'static) {
+		self.task_manager.spawn_handle().spawn(name, task)
+	}
+
+	/// Spawns a task in the background whose termination brings the service down.
+	///
+	/// See `SpawnTaskHandle::spawn_essential`.
+	pub fn spawn_essential_task(
+		&self,
+		name: &'static str,
+		task: impl Future<Item = (), Error = ()> + Send + 'static,
+	) {
+		self.task_manager.spawn_handle().spawn_essential(name, task)
+	}
+
+	/// Runs a closure that may block on the threads dedicated to blocking tasks.
+	pub fn spawn_blocking_task(&self, name: &'static str, task: impl FnOnce() + Send + 'static) {
+		self.task_manager.spawn_handle().spawn_blocking(name, task)
 	}
 
 	/// Returns a handle for spawning tasks.
 	pub fn spawn_task_handle(&self) -> SpawnTaskHandle {
-		SpawnTaskHandle {
-			sender: self.to_spawn_tx.clone(),
-		}
+		self.task_manager.spawn_handle()
+	}
+
+	/// Returns a future that waits, for at most `timeout`, for the background tasks to end.
+	///
+	/// The tasks are only told to stop when the service is dropped, so the future is meant to be
+	/// obtained beforehand and run afterwards. It resolves to an error on timeout.
+	pub fn drain_tasks(&self, timeout: Duration) -> impl Future<Item = (), Error = ()> + Send + 'static {
+		self.task_manager.drain(timeout)
 	}
 
 	/// Starts an RPC query.
@@ -576,7 +679,7 @@ impl<Components: components::Components> Service<Components> {
 
 	/// Get a handle to a future that will resolve on exit.
 	pub fn on_exit(&self) -> ::exit_future::Exit {
-		self.exit.clone()
+		self.task_manager.on_exit()
 	}
 }
 
@@ -585,25 +688,8 @@ impl<Components> Future for Service<Components> where Components: components::Co
 	type Error = ();
 
 	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-		while let Ok(Async::Ready(Some(task_to_spawn))) = self.to_spawn_rx.poll() {
-			let executor = tokio_executor::DefaultExecutor::current();
-			if let Err(err) = executor.execute(task_to_spawn) {
-				debug!(
-					target: "service",
-					"Failed to spawn background task: {:?}; falling back to manual polling",
-					err
-				);
-				self.to_poll.push(err.into_future());
-			}
-		}
-
-		// Polling all the `to_poll` futures.
-		while let Some(pos) = self.to_poll.iter_mut().position(|t| t.poll().map(|t| t.is_ready()).unwrap_or(true)) {
-			self.to_poll.remove(pos);
-		}
-
-		// The service future never ends.
-		Ok(Async::NotReady)
+		// The service future only ends, with an error, when an essential task has ended.
+		self.task_manager.poll()
 	}
 }
 
@@ -614,13 +700,31 @@ impl<Components> Executor<Box<dyn Future<Item = (), Error = ()> + Send>>
 		&self,
 		future: Box<dyn Future<Item = (), Error = ()> + Send>
 	) -> Result<(), futures::future::ExecuteError<Box<dyn Future<Item = (), Error = ()> + Send>>> {
-		if let Err(err) = self.to_spawn_tx.unbounded_send(future) {
-			let kind = futures::future::ExecuteErrorKind::Shutdown;
-			Err(futures::future::ExecuteError::new(kind, err.into_inner()))
-		} else {
-			Ok(())
-		}
+		self.task_manager.spawn_handle().execute(future)
+	}
+}
+
+/// Compares the native runtime with the on-chain one, logging the outcome and reporting both
+/// versions to telemetry.
+///
+/// When they diverge, the executor runs the on-chain WASM runtime instead of the native one.
+fn report_runtime_version(native: Option<&RuntimeVersion>, onchain: &RuntimeVersion) {
+	match native {
+		Some(native) if native.can_call_with(onchain) =>
+			info!("Native runtime {} matches the on-chain runtime", native),
+		Some(native) => warn!(
+			"Native runtime {} differs from the on-chain runtime {}, blocks will be executed with WASM",
+			native,
+			onchain,
+		),
+		None => info!("No native runtime, on-chain runtime is {}", onchain),
 	}
+
+	telemetry!(SUBSTRATE_INFO; "system.runtime_version";
+		"native" => native.map(|v| v.to_string()),
+		"onchain" => onchain.to_string(),
+		"native_compatible" => native.map_or(false, |v| v.can_call_with(onchain))
+	);
 }
 
 /// Builds a never-ending future that continuously polls the network.
@@ -745,21 +849,106 @@ pub struct NetworkStatus<B: BlockT> {
 	pub average_upload_per_sec: u64,
 }
 
+/// Metrics periodically updated from the network status.
+struct NodeMetrics {
+	peers: metrics::Gauge,
+	sync_peers: metrics::Gauge,
+	best_block: metrics::Gauge,
+	finalized_block: metrics::Gauge,
+	finality_lag: metrics::Gauge,
+	sync_target: metrics::Gauge,
+	bandwidth_download: metrics::Gauge,
+	bandwidth_upload: metrics::Gauge,
+	txpool_ready: metrics::Gauge,
+	txpool_future: metrics::Gauge,
+	state_cache_size: metrics::Gauge,
+	database_size: metrics::Gauge,
+	/// Bandwidth used by each network protocol, as of the last network status.
+	protocols: Arc<Mutex<HashMap<String, network::NetworkStateProtocol>>>,
+}
+
+impl NodeMetrics {
+	fn register(registry: &metrics::Registry) -> Self {
+		NodeMetrics {
+			peers: registry.register_gauge("sub_libp2p_peers_count", "Number of connected peers"),
+			sync_peers: registry.register_gauge("sync_peers", "Number of peers we sync with"),
+			best_block: registry.register_gauge("block_height_best", "Best block number"),
+			finalized_block: registry.register_gauge("block_height_finalized", "Finalized block number"),
+			finality_lag: registry.register_gauge(
+				"finality_lag",
+				"Number of blocks between the best and the finalized block",
+			),
+			sync_target: registry.register_gauge(
+				"sync_target_number",
+				"Best block number announced by our peers",
+			),
+			bandwidth_download: registry.register_gauge(
+				"network_download_bytes_per_second",
+				"Downloaded bytes per second averaged over the past few seconds",
+			),
+			bandwidth_upload: registry.register_gauge(
+				"network_upload_bytes_per_second",
+				"Uploaded bytes per second averaged over the past few seconds",
+			),
+			txpool_ready: registry.register_gauge("ready_transactions_number", "Number of ready transactions"),
+			txpool_future: registry.register_gauge("future_transactions_number", "Number of future transactions"),
+			state_cache_size: registry.register_gauge("state_cache_bytes", "Used size of the state cache"),
+			database_size: registry.register_gauge("database_size_bytes", "Size of the database on disk"),
+			protocols: Self::register_protocols(registry),
+		}
+	}
+
+	/// Registers the per-protocol bandwidth counters, labelled with the protocol name.
+	fn register_protocols(
+		registry: &metrics::Registry,
+	) -> Arc<Mutex<HashMap<String, network::NetworkStateProtocol>>> {
+		let protocols = Arc::new(Mutex::new(HashMap::<String, network::NetworkStateProtocol>::new()));
+		let protocols_ = protocols.clone();
+		registry.register(move || {
+			let protocols = protocols_.lock();
+			let family = |name: &str, help: &str, value: fn(&network::NetworkStateProtocol) -> u64| {
+				metrics::MetricFamily {
+					name: format!("substrate_sub_libp2p_protocol_{}", name),
+					help: help.into(),
+					kind: metrics::MetricKind::Counter,
+					samples: protocols.iter().map(|(protocol, state)| metrics::Sample {
+						suffix: "",
+						labels: vec![("protocol".into(), protocol.clone())],
+						value: value(state) as f64,
+					}).collect(),
+				}
+			};
+			vec![
+				family("inbound_bytes_total", "Bytes received on the protocol", |p| p.inbound_bytes),
+				family("outbound_bytes_total", "Bytes sent on the protocol", |p| p.outbound_bytes),
+				family("inbound_messages_total", "Messages received on the protocol", |p| p.inbound_messages),
+				family("outbound_messages_total", "Messages sent on the protocol", |p| p.outbound_messages),
+			]
+		});
+		protocols
+	}
+}
+
 impl<Components> Drop for Service<Components> where Components: components::Components {
 	fn drop(&mut self) {
+		// Dropping the task manager fires the exit signal.
 		debug!(target: "service", "Substrate service shutdown");
-		if let Some(signal) = self.signal.take() {
-			signal.fire();
-		}
 	}
 }
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
 #[cfg(not(target_os = "unknown"))]
-fn start_rpc_servers<F: ServiceFactory, H: FnMut() -> rpc::RpcHandler>(
+fn start_rpc_servers<F: ServiceFactory, H: FnMut(rpc::apis::DenyUnsafe) -> rpc::RpcHandler>(
 	config: &FactoryFullConfiguration<F>,
 	mut gen_handler: H
 ) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> {
+	let deny_unsafe = |address: &SocketAddr| match config.rpc_methods {
+		RpcMethods::Safe => rpc::apis::DenyUnsafe::Yes,
+		RpcMethods::Unsafe => rpc::apis::DenyUnsafe::No,
+		RpcMethods::Auto if address.ip().is_loopback() => rpc::apis::DenyUnsafe::No,
+		RpcMethods::Auto => rpc::apis::DenyUnsafe::Yes,
+	};
+
 	fn maybe_start_server<T, F>(address: Option<SocketAddr>, mut start: F) -> Result<Option<T>, io::Error>
 		where F: FnMut(&SocketAddr) -> Result<T, io::Error>,
 	{
@@ -781,7 +970,11 @@ fn start_rpc_servers<F: ServiceFactory, H: FnMut() -> rpc::RpcHandler>(
 	Ok(Box::new((
 		maybe_start_server(
 			config.rpc_http,
-			|address| rpc::start_http(address, config.rpc_cors.as_ref(), gen_handler()),
+			|address| rpc::start_http(
+				address,
+				config.rpc_cors.as_ref(),
+				gen_handler(deny_unsafe(address)),
+			),
 		)?,
 		maybe_start_server(
 			config.rpc_ws,
@@ -789,7 +982,7 @@ fn start_rpc_servers<F: ServiceFactory, H: FnMut() -> rpc::RpcHandler>(
 				address,
 				config.rpc_ws_max_connections,
 				config.rpc_cors.as_ref(),
-				gen_handler(),
+				gen_handler(deny_unsafe(address)),
 			),
 		)?.map(Mutex::new),
 	)))
@@ -797,13 +990,39 @@ fn start_rpc_servers<F: ServiceFactory, H: FnMut() -> rpc::RpcHandler>(
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
 #[cfg(target_os = "unknown")]
-fn start_rpc_servers<F: ServiceFactory, H: FnMut() -> rpc::RpcHandler>(
+fn start_rpc_servers<F: ServiceFactory, H: FnMut(rpc::apis::DenyUnsafe) -> rpc::RpcHandler>(
 	_: &FactoryFullConfiguration<F>,
 	_: H
 ) -> Result<Box<std::any::Any + Send + Sync>, error::Error> {
 	Ok(Box::new(()))
 }
 
+/// Starts the Prometheus exporter if an address is configured, spawning it as a background task.
+#[cfg(not(target_os = "unknown"))]
+fn start_metrics_server(
+	address: &Option<SocketAddr>,
+	registry: &metrics::Registry,
+	spawn_handle: &SpawnTaskHandle,
+) -> Result<(), error::Error> {
+	if let Some(address) = address {
+		let server = metrics::serve(address, registry.clone())
+			.map_err(|e| error::Error::Other(format!("Failed to start metrics server: {}", e)))?;
+		info!("Prometheus metrics server started at {}", address);
+		spawn_handle.spawn("metrics-server", server);
+	}
+	Ok(())
+}
+
+/// Starts the Prometheus exporter if an address is configured, spawning it as a background task.
+#[cfg(target_os = "unknown")]
+fn start_metrics_server(
+	_: &Option<SocketAddr>,
+	_: &metrics::Registry,
+	_: &SpawnTaskHandle,
+) -> Result<(), error::Error> {
+	Ok(())
+}
+
 /// An RPC session. Used to perform in-memory RPC queries (ie. RPC queries that don't go through
 /// the HTTP or WebSockets server).
 pub struct RpcSession {
@@ -907,65 +1126,53 @@ impl<C: Components> network::TransactionPool<ComponentExHash<C>, ComponentBlock<
 pub struct AuthorityKeyProvider<Block, ConsensusPair, FinalityPair> {
 	_marker: PhantomData<(Block, ConsensusPair, FinalityPair)>,
 	roles: Roles,
-	keystore: Option<Arc<Keystore>>,
-	password: crypto::Protected<String>,
+	crypto_store: Option<Arc<dyn CryptoStore>>,
 }
 
-impl<Block, ConsensusPair, FinalityPair>
-	offchain::AuthorityKeyProvider<Block>
-	for AuthorityKeyProvider<Block, ConsensusPair, FinalityPair>
+impl<Block, ConsensusPair, FinalityPair> AuthorityKeyProvider<Block, ConsensusPair, FinalityPair>
 where
-	Block: runtime_primitives::traits::Block,
 	ConsensusPair: Pair,
 	FinalityPair: Pair,
 {
-	type ConsensusPair = ConsensusPair;
-	type FinalityPair = FinalityPair;
-
-	fn authority_key(&self, _at: &BlockId<Block>) -> Option<Self::ConsensusPair> {
+	/// Returns a signer for the first key of type `P` in the crypto store.
+	fn signer<P: Pair>(&self) -> Option<Signer<P>> {
 		if self.roles != Roles::AUTHORITY {
 			return None
 		}
 
-		let keystore = match self.keystore {
-			Some(ref keystore) => keystore,
+		let crypto_store = match self.crypto_store {
+			Some(ref crypto_store) => crypto_store,
 			None => return None
 		};
 
-		let loaded_key = keystore
-			.contents()
-			.map(|keys| keys.get(0)
-				 .map(|k| keystore.load(k, self.password.as_ref()))
-			);
-
-		if let Ok(Some(Ok(key))) = loaded_key {
-			Some(key)
-		} else {
-			None
+		match crypto_store.public_keys(P::KEY_TYPE) {
+			Ok(keys) => keys.get(0)
+				.map(|public| Signer::new(crypto_store.clone(), P::Public::from_slice(public))),
+			Err(err) => {
+				warn!("Failed to read the authority keys: {}", err);
+				None
+			}
 		}
 	}
+}
 
-	fn fg_authority_key(&self, _at: &BlockId<Block>) -> Option<Self::FinalityPair> {
-		if self.roles != Roles::AUTHORITY {
-			return None
-		}
-
-		let keystore = match self.keystore {
-			Some(ref keystore) => keystore,
-			None => return None
-		};
+impl<Block, ConsensusPair, FinalityPair>
+	offchain::AuthorityKeyProvider<Block>
+	for AuthorityKeyProvider<Block, ConsensusPair, FinalityPair>
+where
+	Block: runtime_primitives::traits::Block,
+	ConsensusPair: Pair,
+	FinalityPair: Pair,
+{
+	type ConsensusPair = ConsensusPair;
+	type FinalityPair = FinalityPair;
 
-		let loaded_key = keystore
-			.contents()
-			.map(|keys| keys.get(0)
-				 .map(|k| keystore.load(k, self.password.as_ref()))
-			);
+	fn authority_key(&self, _at: &BlockId<Block>) -> Option<Signer<Self::ConsensusPair>> {
+		self.signer()
+	}
 
-		if let Ok(Some(Ok(key))) = loaded_key {
-			Some(key)
-		} else {
-			None
-		}
+	fn fg_authority_key(&self, _at: &BlockId<Block>) -> Option<Signer<Self::FinalityPair>> {
+		self.signer()
 	}
 }
 
@@ -975,17 +1182,21 @@ where
 /// In these block it is required to write a closure that takes the same number of arguments,
 /// the corresponding function in the `ServiceFactory` trait provides.
 ///
+/// The factory declares the types of the service and how its parts are built by default. The
+/// `FullService` and `LightService` initializers should build the service with a
+/// `ServiceBuilder`, which is where parts are replaced and RPC extensions are added.
+///
 /// # Example
 ///
 /// ```
 /// # use substrate_service::{
 /// # 	construct_service_factory, Service, FullBackend, FullExecutor, LightBackend, LightExecutor,
-/// # 	FullComponents, LightComponents, FactoryFullConfiguration, FullClient
+/// # 	FullComponents, LightComponents, FactoryFullConfiguration, FullClient, ServiceBuilder
 /// # };
 /// # use transaction_pool::{self, txpool::{Pool as TransactionPool}};
 /// # use network::{config::DummyFinalityProofRequestBuilder, construct_simple_protocol};
 /// # use client::{self, LongestChain};
-/// # use consensus_common::import_queue::{BasicQueue, Verifier};
+/// # use consensus_common::import_queue::{BasicQueue, Verifier, VerificationError};
 /// # use consensus_common::{BlockOrigin, BlockImportParams, well_known_cache_keys::Id as CacheKeyId};
 /// # use node_runtime::{GenesisConfig, RuntimeApi};
 /// # use std::sync::Arc;
@@ -1004,7 +1215,7 @@ where
 /// # 		header: B::Header,
 /// # 		justification: Option<Justification>,
 /// # 		body: Option<Vec<B::Extrinsic>>,
-/// # 	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+/// # 	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), VerificationError> {
 /// # 		unimplemented!();
 /// # 	}
 /// # }
@@ -1030,14 +1241,14 @@ where
 /// 		Genesis = GenesisConfig,
 /// 		Configuration = (),
 /// 		FullService = FullComponents<Self>
-/// 			{ |config| <FullComponents<Factory>>::new(config) },
+/// 			{ |config| <FullComponents<Factory>>::from_builder(ServiceBuilder::new(config)) },
 /// 		// Setup as Consensus Authority (if the role and key are given)
 /// 		AuthoritySetup = {
 /// 			|service: Self::FullService| {
 /// 				Ok(service)
 /// 			}},
 /// 		LightService = LightComponents<Self>
-/// 			{ |config| <LightComponents<Factory>>::new(config) },
+/// 			{ |config| <LightComponents<Factory>>::from_builder(ServiceBuilder::new(config)) },
 /// 		FullImportQueue = BasicQueue<Block>
 /// 			{ |_, client, _| Ok(BasicQueue::new(Arc::new(MyVerifier), Box::new(client), None, None)) },
 /// 		LightImportQueue = BasicQueue<Block>
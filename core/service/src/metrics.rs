@@ -0,0 +1,402 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prometheus metrics exporter.
+//!
+//! The service owns a `Registry` into which subsystems register their metrics. The registry is
+//! rendered in the Prometheus text exposition format by an HTTP server listening on the address
+//! given by the `prometheus_port` configuration field.
+//!
+//! The service itself exports the peer counts, the best, finalized and sync target block
+//! numbers, the finality lag, the average and per-protocol bandwidth, the sizes of the
+//! transaction pool, the state cache and the database, the number of imported blocks and the
+//! time spent maintaining the transaction pool after each import.
+//!
+//! Block import and block proposal timings are not exported by the service, as the import queue
+//! and the proposer are owned by the consensus engine that the node sets up. Nodes that want
+//! them can register a `Timer` into the registry returned by `Service::metrics` and observe
+//! these operations where they are performed.
+
+use std::fmt::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+#[cfg(not(target_os = "unknown"))]
+use futures::prelude::*;
+#[cfg(not(target_os = "unknown"))]
+use hyper::{Body, Request, Response, Server, StatusCode, service::service_fn_ok};
+use parking_lot::RwLock;
+
+/// Kind of a metric, as reported in the `# TYPE` line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+	/// A value that can go up and down.
+	Gauge,
+	/// A monotonically increasing value.
+	Counter,
+	/// A count and a sum of observations.
+	Summary,
+}
+
+impl MetricKind {
+	fn as_str(&self) -> &'static str {
+		match self {
+			MetricKind::Gauge => "gauge",
+			MetricKind::Counter => "counter",
+			MetricKind::Summary => "summary",
+		}
+	}
+}
+
+/// A single sample of a metric family.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+	/// Suffix appended to the family name (e.g. `_sum`), usually empty.
+	pub suffix: &'static str,
+	/// Labels attached to the sample.
+	pub labels: Vec<(String, String)>,
+	/// Value of the sample.
+	pub value: f64,
+}
+
+/// A named group of samples sharing the same kind and help text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricFamily {
+	/// Name of the family. Must be a valid Prometheus metric name.
+	pub name: String,
+	/// Human-readable description.
+	pub help: String,
+	/// Kind of the metric.
+	pub kind: MetricKind,
+	/// Current samples.
+	pub samples: Vec<Sample>,
+}
+
+/// Something that can produce metric families when the registry is scraped.
+pub trait Collector: Send + Sync {
+	/// Returns the current state of the metrics of this collector.
+	fn collect(&self) -> Vec<MetricFamily>;
+}
+
+impl<F: Fn() -> Vec<MetricFamily> + Send + Sync> Collector for F {
+	fn collect(&self) -> Vec<MetricFamily> {
+		(self)()
+	}
+}
+
+/// Shared floating point value.
+#[derive(Debug, Clone, Default)]
+struct AtomicF64(Arc<AtomicU64>);
+
+impl AtomicF64 {
+	fn get(&self) -> f64 {
+		f64::from_bits(self.0.load(Ordering::Relaxed))
+	}
+
+	fn set(&self, value: f64) {
+		self.0.store(value.to_bits(), Ordering::Relaxed)
+	}
+
+	fn add(&self, value: f64) {
+		let mut current = self.0.load(Ordering::Relaxed);
+		loop {
+			let new = (f64::from_bits(current) + value).to_bits();
+			match self.0.compare_exchange_weak(current, new, Ordering::Relaxed, Ordering::Relaxed) {
+				Ok(_) => break,
+				Err(actual) => current = actual,
+			}
+		}
+	}
+}
+
+/// A value that can be set to arbitrary values.
+#[derive(Debug, Clone)]
+pub struct Gauge {
+	name: String,
+	help: String,
+	value: AtomicF64,
+}
+
+impl Gauge {
+	/// Sets the value of the gauge.
+	pub fn set(&self, value: f64) {
+		self.value.set(value)
+	}
+
+	/// Returns the current value of the gauge.
+	pub fn get(&self) -> f64 {
+		self.value.get()
+	}
+}
+
+impl Collector for Gauge {
+	fn collect(&self) -> Vec<MetricFamily> {
+		vec![MetricFamily {
+			name: self.name.clone(),
+			help: self.help.clone(),
+			kind: MetricKind::Gauge,
+			samples: vec![Sample { suffix: "", labels: Vec::new(), value: self.get() }],
+		}]
+	}
+}
+
+/// A value that only ever increases.
+#[derive(Debug, Clone)]
+pub struct Counter {
+	name: String,
+	help: String,
+	value: AtomicF64,
+}
+
+impl Counter {
+	/// Increments the counter by one.
+	pub fn inc(&self) {
+		self.inc_by(1.0)
+	}
+
+	/// Increments the counter by the given amount. Negative amounts are ignored.
+	pub fn inc_by(&self, value: f64) {
+		if value > 0.0 {
+			self.value.add(value)
+		}
+	}
+
+	/// Returns the current value of the counter.
+	pub fn get(&self) -> f64 {
+		self.value.get()
+	}
+}
+
+impl Collector for Counter {
+	fn collect(&self) -> Vec<MetricFamily> {
+		vec![MetricFamily {
+			name: self.name.clone(),
+			help: self.help.clone(),
+			kind: MetricKind::Counter,
+			samples: vec![Sample { suffix: "", labels: Vec::new(), value: self.get() }],
+		}]
+	}
+}
+
+/// Tracks the number and total duration of timed operations, in seconds.
+#[derive(Debug, Clone)]
+pub struct Timer {
+	name: String,
+	help: String,
+	count: AtomicF64,
+	sum: AtomicF64,
+}
+
+impl Timer {
+	/// Records one observation.
+	pub fn observe(&self, duration: Duration) {
+		let secs = duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000.0;
+		self.count.add(1.0);
+		self.sum.add(secs);
+	}
+}
+
+impl Collector for Timer {
+	fn collect(&self) -> Vec<MetricFamily> {
+		vec![MetricFamily {
+			name: self.name.clone(),
+			help: self.help.clone(),
+			kind: MetricKind::Summary,
+			samples: vec![
+				Sample { suffix: "_sum", labels: Vec::new(), value: self.sum.get() },
+				Sample { suffix: "_count", labels: Vec::new(), value: self.count.get() },
+			],
+		}]
+	}
+}
+
+/// Collection of metrics exposed by the node.
+///
+/// Cloning the registry is cheap and all the clones share the same set of collectors.
+#[derive(Clone, Default)]
+pub struct Registry {
+	prefix: Option<String>,
+	collectors: Arc<RwLock<Vec<Box<dyn Collector>>>>,
+}
+
+impl Registry {
+	/// Creates a new registry whose metric names are prefixed with `prefix_`.
+	pub fn with_prefix(prefix: impl Into<String>) -> Self {
+		Registry {
+			prefix: Some(prefix.into()),
+			collectors: Default::default(),
+		}
+	}
+
+	fn full_name(&self, name: &str) -> String {
+		match self.prefix {
+			Some(ref prefix) => format!("{}_{}", prefix, name),
+			None => name.to_owned(),
+		}
+	}
+
+	/// Registers a custom collector.
+	///
+	/// Metric names returned by the collector are not prefixed.
+	pub fn register(&self, collector: impl Collector + 'static) {
+		self.collectors.write().push(Box::new(collector));
+	}
+
+	/// Creates and registers a new gauge.
+	pub fn register_gauge(&self, name: &str, help: &str) -> Gauge {
+		let gauge = Gauge { name: self.full_name(name), help: help.into(), value: Default::default() };
+		self.register(gauge.clone());
+		gauge
+	}
+
+	/// Creates and registers a new counter.
+	pub fn register_counter(&self, name: &str, help: &str) -> Counter {
+		let counter = Counter { name: self.full_name(name), help: help.into(), value: Default::default() };
+		self.register(counter.clone());
+		counter
+	}
+
+	/// Creates and registers a new timer.
+	pub fn register_timer(&self, name: &str, help: &str) -> Timer {
+		let timer = Timer {
+			name: self.full_name(name),
+			help: help.into(),
+			count: Default::default(),
+			sum: Default::default(),
+		};
+		self.register(timer.clone());
+		timer
+	}
+
+	/// Collects all the metrics of the registry.
+	pub fn gather(&self) -> Vec<MetricFamily> {
+		self.collectors.read().iter().flat_map(|c| c.collect()).collect()
+	}
+
+	/// Renders all the metrics in the Prometheus text exposition format.
+	pub fn render(&self) -> String {
+		let mut out = String::new();
+		for family in self.gather() {
+			let _ = writeln!(out, "# HELP {} {}", family.name, escape(&family.help, false));
+			let _ = writeln!(out, "# TYPE {} {}", family.name, family.kind.as_str());
+			for sample in family.samples {
+				let _ = write!(out, "{}{}", family.name, sample.suffix);
+				if !sample.labels.is_empty() {
+					let labels = sample.labels.iter()
+						.map(|(k, v)| format!("{}=\"{}\"", k, escape(v, true)))
+						.collect::<Vec<_>>()
+						.join(",");
+					let _ = write!(out, "{{{}}}", labels);
+				}
+				let _ = writeln!(out, " {}", sample.value);
+			}
+		}
+		out
+	}
+}
+
+fn escape(s: &str, quotes: bool) -> String {
+	let mut out = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'"' if quotes => out.push_str("\\\""),
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+/// Builds a future serving the metrics of `registry` at `/metrics` on the given address.
+///
+/// **Note**: Only available if `not(target_os = "unknown")`.
+#[cfg(not(target_os = "unknown"))]
+pub fn serve(
+	address: &std::net::SocketAddr,
+	registry: Registry,
+) -> Result<impl Future<Item = (), Error = ()>, hyper::Error> {
+	let make_service = move || {
+		let registry = registry.clone();
+		service_fn_ok(move |req: Request<Body>| {
+			if req.uri().path() == "/metrics" {
+				Response::builder()
+					.status(StatusCode::OK)
+					.header("Content-Type", "text/plain; version=0.0.4")
+					.body(Body::from(registry.render()))
+					.expect("Sending a static status and header; qed")
+			} else {
+				Response::builder()
+					.status(StatusCode::NOT_FOUND)
+					.body(Body::from("Not found."))
+					.expect("Sending a static status; qed")
+			}
+		})
+	};
+
+	Ok(Server::try_bind(address)?
+		.serve(make_service)
+		.map_err(|e| log::error!(target: "prometheus", "Metrics server error: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn renders_registered_metrics() {
+		let registry = Registry::with_prefix("substrate");
+		let peers = registry.register_gauge("sub_libp2p_peers_count", "Number of network gossip peers");
+		let blocks = registry.register_counter("import_queue_blocks_submitted", "Number of blocks imported");
+		peers.set(3.0);
+		blocks.inc();
+		blocks.inc_by(2.0);
+		blocks.inc_by(-1.0);
+
+		assert_eq!(
+			registry.render(),
+			"# HELP substrate_sub_libp2p_peers_count Number of network gossip peers\n\
+			# TYPE substrate_sub_libp2p_peers_count gauge\n\
+			substrate_sub_libp2p_peers_count 3\n\
+			# HELP substrate_import_queue_blocks_submitted Number of blocks imported\n\
+			# TYPE substrate_import_queue_blocks_submitted counter\n\
+			substrate_import_queue_blocks_submitted 3\n"
+		);
+	}
+
+	#[test]
+	fn renders_custom_collectors_with_labels() {
+		let registry = Registry::default();
+		registry.register(|| vec![MetricFamily {
+			name: "bandwidth".into(),
+			help: "Bandwidth \"per\" protocol".into(),
+			kind: MetricKind::Gauge,
+			samples: vec![Sample {
+				suffix: "",
+				labels: vec![("protocol".into(), "sup\"".into())],
+				value: 1.5,
+			}],
+		}]);
+
+		assert_eq!(
+			registry.render(),
+			"# HELP bandwidth Bandwidth \"per\" protocol\n\
+			# TYPE bandwidth gauge\n\
+			bandwidth{protocol=\"sup\\\"\"} 1.5\n"
+		);
+	}
+}
@@ -0,0 +1,424 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Spawning and supervision of the background tasks of the service.
+//!
+//! Every task is spawned under a name, which is used for logging and for the per-task metrics.
+//! Tasks come in three flavours:
+//!
+//! - regular tasks, which may end at any time without further consequences;
+//! - essential tasks, whose termination brings the whole service down;
+//! - blocking tasks, which are closures run on a dedicated pool of threads rather than on the
+//!   executor.
+//!
+//! Regular and essential tasks are dropped when the exit signal of the service fires. Once the
+//! service is gone, `TaskManager::drain` can be used to wait, for a bounded amount of time, for
+//! the remaining tasks to finish.
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, mpsc as std_mpsc};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use exit_future::{Exit, Signal};
+use futures::prelude::*;
+use futures::future::{Executor, ExecuteError, ExecuteErrorKind};
+use futures::sync::mpsc;
+use log::{debug, error, warn};
+use parking_lot::Mutex;
+
+use crate::metrics::{Collector, MetricFamily, MetricKind, Sample};
+
+/// Type-erased future spawned as a background task.
+pub type BoxedTask = Box<dyn Future<Item = (), Error = ()> + Send>;
+
+/// Closure run on the blocking pool.
+type BlockingJob = Box<dyn FnOnce() + Send>;
+
+/// Name given to the tasks spawned through the `Executor` implementation.
+const UNNAMED_TASK: &str = "unnamed";
+
+/// Number of threads dedicated to blocking tasks.
+const BLOCKING_THREADS: usize = 4;
+
+/// Interval at which the number of alive tasks is checked while draining.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Default amount of time given to the tasks to finish once the service shuts down.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of tasks spawned and ended, per task name.
+#[derive(Clone, Default)]
+struct TaskMetrics(Arc<Mutex<HashMap<&'static str, (u64, u64)>>>);
+
+impl TaskMetrics {
+	fn spawned(&self, name: &'static str) {
+		self.0.lock().entry(name).or_default().0 += 1;
+	}
+
+	fn ended(&self, name: &'static str) {
+		self.0.lock().entry(name).or_default().1 += 1;
+	}
+}
+
+impl Collector for TaskMetrics {
+	fn collect(&self) -> Vec<MetricFamily> {
+		let counts = self.0.lock();
+		let family = |name: &str, help: &str, value: fn(&(u64, u64)) -> u64| MetricFamily {
+			name: name.into(),
+			help: help.into(),
+			kind: MetricKind::Counter,
+			samples: counts.iter().map(|(task, count)| Sample {
+				suffix: "",
+				labels: vec![("task".into(), task.to_string())],
+				value: value(count) as f64,
+			}).collect(),
+		};
+
+		vec![
+			family("substrate_tasks_spawned_total", "Number of tasks spawned", |c| c.0),
+			family("substrate_tasks_ended_total", "Number of tasks that have ended", |c| c.1),
+		]
+	}
+}
+
+/// Keeps a task accounted for as alive until dropped.
+struct AliveGuard {
+	name: &'static str,
+	alive: Arc<AtomicUsize>,
+	metrics: TaskMetrics,
+}
+
+impl AliveGuard {
+	fn new(name: &'static str, alive: &Arc<AtomicUsize>, metrics: &TaskMetrics) -> Self {
+		alive.fetch_add(1, Ordering::AcqRel);
+		metrics.spawned(name);
+		AliveGuard { name, alive: alive.clone(), metrics: metrics.clone() }
+	}
+}
+
+impl Drop for AliveGuard {
+	fn drop(&mut self) {
+		self.alive.fetch_sub(1, Ordering::AcqRel);
+		self.metrics.ended(self.name);
+	}
+}
+
+/// An handle for spawning tasks in the service.
+#[derive(Clone)]
+pub struct SpawnTaskHandle {
+	sender: mpsc::UnboundedSender<BoxedTask>,
+	blocking: Arc<Mutex<std_mpsc::Sender<BlockingJob>>>,
+	essential_failed: mpsc::UnboundedSender<&'static str>,
+	exit: Exit,
+	alive: Arc<AtomicUsize>,
+	metrics: TaskMetrics,
+}
+
+impl SpawnTaskHandle {
+	/// Spawns a task in the background under the given name.
+	///
+	/// The task is dropped when the service shuts down.
+	pub fn spawn(&self, name: &'static str, task: impl Future<Item = (), Error = ()> + Send + 'static) {
+		self.spawn_inner(name, task, false)
+	}
+
+	/// Spawns a task whose termination brings the service down.
+	///
+	/// If the task ends on its own, successfully or not, the service future resolves with an
+	/// error. Being dropped because the service shuts down is not considered a failure.
+	pub fn spawn_essential(
+		&self,
+		name: &'static str,
+		task: impl Future<Item = (), Error = ()> + Send + 'static,
+	) {
+		self.spawn_inner(name, task, true)
+	}
+
+	/// Runs a closure that may block on the pool of threads dedicated to blocking tasks.
+	///
+	/// Blocking tasks can't be interrupted by the exit signal, but they are taken into account
+	/// when draining the tasks at shutdown.
+	pub fn spawn_blocking(&self, name: &'static str, task: impl FnOnce() + Send + 'static) {
+		let guard = AliveGuard::new(name, &self.alive, &self.metrics);
+		let job: BlockingJob = Box::new(move || {
+			debug!(target: "service", "Blocking task `{}` started", name);
+			if panic::catch_unwind(AssertUnwindSafe(task)).is_err() {
+				error!(target: "service", "Blocking task `{}` panicked", name);
+			}
+			drop(guard);
+		});
+
+		if self.blocking.lock().send(job).is_err() {
+			warn!(target: "service", "Failed to spawn blocking task `{}`: the pool is shut down", name);
+		}
+	}
+
+	fn spawn_inner(
+		&self,
+		name: &'static str,
+		task: impl Future<Item = (), Error = ()> + Send + 'static,
+		essential: bool,
+	) {
+		if self.sender.unbounded_send(self.wrap(name, task, essential)).is_err() {
+			debug!(target: "service", "Failed to spawn task `{}`: the service is shut down", name);
+		}
+	}
+
+	/// Wraps a task so that it stops on exit, is accounted for, and reports its termination.
+	fn wrap(
+		&self,
+		name: &'static str,
+		task: impl Future<Item = (), Error = ()> + Send + 'static,
+		essential: bool,
+	) -> BoxedTask {
+		let guard = AliveGuard::new(name, &self.alive, &self.metrics);
+		let essential_failed = self.essential_failed.clone();
+
+		Box::new(task
+			.then(|_| Ok::<_, ()>(true))
+			.select(self.exit.clone().then(|_| Ok(false)))
+			.then(move |result| {
+				let _guard = guard;
+				let ended_on_its_own = result.map(|(ended, _)| ended).unwrap_or(true);
+				if ended_on_its_own {
+					debug!(target: "service", "Task `{}` ended", name);
+					if essential {
+						let _ = essential_failed.unbounded_send(name);
+					}
+				}
+				Ok(())
+			}))
+	}
+}
+
+impl Executor<BoxedTask> for SpawnTaskHandle {
+	fn execute(&self, future: BoxedTask) -> Result<(), ExecuteError<BoxedTask>> {
+		self.sender.unbounded_send(self.wrap(UNNAMED_TASK, future, false))
+			.map_err(|err| ExecuteError::new(ExecuteErrorKind::Shutdown, err.into_inner()))
+	}
+}
+
+/// Owns the background tasks of the service.
+///
+/// Tasks are sent through `SpawnTaskHandle`s and spawned on the default executor when the
+/// manager is polled. Dropping the manager fires the exit signal.
+pub struct TaskManager {
+	signal: Option<Signal>,
+	exit: Exit,
+	/// Sender for futures that must be spawned as background tasks.
+	to_spawn_tx: mpsc::UnboundedSender<BoxedTask>,
+	/// Receiver for futures that must be spawned as background tasks.
+	to_spawn_rx: mpsc::UnboundedReceiver<BoxedTask>,
+	/// List of futures to poll from `poll`.
+	/// If spawning a background task is not possible, we instead push the task into this `Vec`.
+	/// The elements must then be polled manually.
+	to_poll: Vec<BoxedTask>,
+	/// Receives the name of the essential tasks that have ended.
+	essential_failed_tx: mpsc::UnboundedSender<&'static str>,
+	essential_failed_rx: mpsc::UnboundedReceiver<&'static str>,
+	blocking: Arc<Mutex<std_mpsc::Sender<BlockingJob>>>,
+	alive: Arc<AtomicUsize>,
+	metrics: TaskMetrics,
+}
+
+impl TaskManager {
+	/// Creates a new task manager, starting the threads of the blocking pool.
+	pub fn new() -> Self {
+		let (signal, exit) = exit_future::signal();
+		let (to_spawn_tx, to_spawn_rx) = mpsc::unbounded();
+		let (essential_failed_tx, essential_failed_rx) = mpsc::unbounded();
+		let (blocking_tx, blocking_rx) = std_mpsc::channel::<BlockingJob>();
+
+		let blocking_rx = Arc::new(Mutex::new(blocking_rx));
+		for n in 0..BLOCKING_THREADS {
+			let blocking_rx = blocking_rx.clone();
+			let spawned = thread::Builder::new()
+				.name(format!("blocking-{}", n))
+				.spawn(move || loop {
+					// Only hold the lock while waiting for a job, not while running it.
+					let job = blocking_rx.lock().recv();
+					match job {
+						Ok(job) => job(),
+						Err(_) => break,
+					}
+				});
+			if let Err(err) = spawned {
+				warn!(target: "service", "Failed to start blocking task thread: {}", err);
+			}
+		}
+
+		TaskManager {
+			signal: Some(signal),
+			exit,
+			to_spawn_tx,
+			to_spawn_rx,
+			to_poll: Vec::new(),
+			essential_failed_tx,
+			essential_failed_rx,
+			blocking: Arc::new(Mutex::new(blocking_tx)),
+			alive: Arc::new(AtomicUsize::new(0)),
+			metrics: Default::default(),
+		}
+	}
+
+	/// Returns a handle for spawning tasks.
+	pub fn spawn_handle(&self) -> SpawnTaskHandle {
+		SpawnTaskHandle {
+			sender: self.to_spawn_tx.clone(),
+			blocking: self.blocking.clone(),
+			essential_failed: self.essential_failed_tx.clone(),
+			exit: self.exit.clone(),
+			alive: self.alive.clone(),
+			metrics: self.metrics.clone(),
+		}
+	}
+
+	/// Returns a future that resolves when the exit signal fires.
+	pub fn on_exit(&self) -> Exit {
+		self.exit.clone()
+	}
+
+	/// Number of tasks that have been spawned and haven't ended yet.
+	pub fn alive_tasks(&self) -> usize {
+		self.alive.load(Ordering::Acquire)
+	}
+
+	/// Registers the per-task metrics into the given registry.
+	pub fn register_metrics(&self, registry: &crate::metrics::Registry) {
+		registry.register(self.metrics.clone());
+	}
+
+	/// Returns a future that waits for all the tasks to end, for at most `timeout`.
+	///
+	/// Meant to be run once the manager has been dropped, and hence the exit signal fired. The
+	/// future resolves to an error if some tasks are still alive when the timeout elapses.
+	pub fn drain(&self, timeout: Duration) -> impl Future<Item = (), Error = ()> + Send + 'static {
+		let alive = self.alive.clone();
+		let remaining = self.alive.clone();
+
+		// The timeout starts when the future is first polled, not when it is created.
+		futures::future::lazy(move || {
+			tokio_timer::Interval::new(Instant::now(), DRAIN_POLL_INTERVAL)
+				.map_err(|_| ())
+				.take_while(move |_| Ok(alive.load(Ordering::Acquire) != 0))
+				.for_each(|_| Ok(()))
+				.select(tokio_timer::Delay::new(Instant::now() + timeout).map_err(|_| ()))
+				.then(move |_| match remaining.load(Ordering::Acquire) {
+					0 => Ok(()),
+					n => {
+						warn!(target: "service", "{} tasks still running after {:?}", n, timeout);
+						Err(())
+					},
+				})
+		})
+	}
+
+	/// Spawns the pending tasks and polls the ones that couldn't be spawned.
+	///
+	/// Resolves to an error as soon as an essential task has ended. Never resolves otherwise.
+	pub fn poll(&mut self) -> Poll<(), ()> {
+		while let Ok(Async::Ready(Some(task_to_spawn))) = self.to_spawn_rx.poll() {
+			let executor = tokio_executor::DefaultExecutor::current();
+			if let Err(err) = executor.execute(task_to_spawn) {
+				debug!(
+					target: "service",
+					"Failed to spawn background task: {:?}; falling back to manual polling",
+					err
+				);
+				self.to_poll.push(err.into_future());
+			}
+		}
+
+		// Polling all the `to_poll` futures.
+		while let Some(pos) = self.to_poll.iter_mut().position(|t| t.poll().map(|t| t.is_ready()).unwrap_or(true)) {
+			self.to_poll.remove(pos);
+		}
+
+		if let Ok(Async::Ready(Some(name))) = self.essential_failed_rx.poll() {
+			error!(target: "service", "Essential task `{}` ended; shutting down the service", name);
+			return Err(());
+		}
+
+		Ok(Async::NotReady)
+	}
+}
+
+impl Drop for TaskManager {
+	fn drop(&mut self) {
+		if let Some(signal) = self.signal.take() {
+			signal.fire();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::future;
+	use std::sync::mpsc::channel;
+
+	// Without a tokio runtime the tasks can't be spawned and are polled by the manager itself.
+	fn poll_once(manager: &mut TaskManager) -> Poll<(), ()> {
+		future::lazy(|| Ok::<_, ()>(manager.poll())).wait().unwrap()
+	}
+
+	#[test]
+	fn regular_task_ending_does_not_fail_the_manager() {
+		let mut manager = TaskManager::new();
+		manager.spawn_handle().spawn("regular", future::ok(()));
+		manager.spawn_handle().spawn_essential("essential", future::empty());
+
+		assert_eq!(poll_once(&mut manager), Ok(Async::NotReady));
+		assert_eq!(manager.alive_tasks(), 1);
+	}
+
+	#[test]
+	fn essential_task_ending_fails_the_manager() {
+		let mut manager = TaskManager::new();
+		manager.spawn_handle().spawn_essential("essential", future::err(()));
+
+		assert_eq!(poll_once(&mut manager), Err(()));
+	}
+
+	#[test]
+	fn tasks_stop_on_exit() {
+		let mut manager = TaskManager::new();
+		let handle = manager.spawn_handle();
+		handle.spawn_essential("essential", future::empty());
+		assert_eq!(poll_once(&mut manager), Ok(Async::NotReady));
+		assert_eq!(manager.alive_tasks(), 1);
+
+		// Dropping the manager fires the exit signal and drops the manually polled tasks.
+		let alive = manager.alive.clone();
+		drop(manager);
+		assert_eq!(alive.load(Ordering::Acquire), 0);
+	}
+
+	#[test]
+	fn blocking_tasks_run_on_the_pool() {
+		let manager = TaskManager::new();
+		let (tx, rx) = channel();
+		manager.spawn_handle().spawn_blocking("blocking", move || {
+			tx.send(thread::current().name().map(String::from)).unwrap();
+		});
+
+		let thread_name = rx.recv().unwrap().unwrap();
+		assert!(thread_name.starts_with("blocking-"));
+	}
+}
@@ -32,6 +32,7 @@ use service::{
 	FactoryFullConfiguration,
 	FactoryChainSpec,
 	Roles,
+	KeystoreConfig,
 	FactoryExtrinsic,
 };
 use network::{multiaddr, Multiaddr};
@@ -158,10 +159,15 @@ fn node_config<F: ServiceFactory> (
 		out_peers: 450,
 		reserved_nodes: vec![],
 		non_reserved_mode: NonReservedPeerMode::Accept,
+		full_peers: None,
+		light_peers: None,
+		reserved_peers: None,
+		authority_peers: None,
 		client_version: "network/test/0.1".to_owned(),
 		node_name: "unknown".to_owned(),
 		transport: TransportConfig::Normal {
 			enable_mdns: false,
+			enable_nat_port_mapping: false,
 			wasm_external_transport: None,
 		},
 	};
@@ -173,12 +179,14 @@ fn node_config<F: ServiceFactory> (
 		roles: role,
 		transaction_pool: Default::default(),
 		network: network_config,
-		keystore_path: Some(root.join("key")),
+		keystore: KeystoreConfig::Path(root.join("key")),
 		database_path: root.join("db"),
 		database_cache_size: None,
+		database: Default::default(),
 		state_cache_size: 16777216,
 		state_cache_child_ratio: None,
 		pruning: Default::default(),
+		blocks_pruning: None,
 		keys: keys,
 		chain_spec: (*spec).clone(),
 		custom: Default::default(),
@@ -188,9 +196,12 @@ fn node_config<F: ServiceFactory> (
 		rpc_ws: None,
 		rpc_ws_max_connections: None,
 		rpc_cors: None,
+		rpc_methods: Default::default(),
+		prometheus_port: None,
 		telemetry_endpoints: None,
 		telemetry_external_transport: None,
 		default_heap_pages: None,
+		wasm_method: Default::default(),
 		offchain_worker: false,
 		force_authoring: false,
 		disable_grandpa: false,
@@ -306,16 +306,14 @@ fn generate_runtime_api_base_structures(impls: &[ItemImpl]) -> Result<TokenStrea
 				self.recorder = Some(Default::default());
 			}
 
-			fn extract_proof(&mut self) -> Option<Vec<Vec<u8>>> {
+			fn extract_proof(&mut self) -> Option<#crate_::runtime_api::StorageProof> {
 				self.recorder
 					.take()
-					.map(|r| {
-						r.borrow_mut()
-							.drain()
-							.into_iter()
-							.map(|n| n.data.to_vec())
-							.collect()
-					})
+					.map(|r| #crate_::runtime_api::extract_recorded_proof(&mut *r.borrow_mut()))
+			}
+
+			fn extract_changes(&mut self) -> #crate_::runtime_api::OverlayedChanges {
+				self.changes.replace(Default::default())
 			}
 		}
 
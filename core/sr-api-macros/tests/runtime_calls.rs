@@ -16,8 +16,9 @@
 
 use test_client::{
 	prelude::*,
-	runtime::{TestAPI, DecodeFails, Transfer, Header},
+	runtime::{TestAPI, DecodeFails, Transfer, Block, Header},
 };
+use client::runtime_api::{ApiExt, Core};
 use runtime_primitives::{
 	generic::BlockId,
 	traits::{ProvideRuntimeApi, Header as HeaderT, Hash as HashT},
@@ -86,6 +87,27 @@ fn calling_wasm_runtime_signature_changed_old_function() {
 	assert_eq!(&res, &[1, 2]);
 }
 
+#[test]
+fn calling_function_by_advertised_api_version() {
+	let client = TestClientBuilder::new().set_execution_strategy(ExecutionStrategy::NativeWhenPossible).build();
+	let runtime_api = client.runtime_api();
+	let block_id = BlockId::Number(client.info().chain.best_number);
+
+	assert_eq!(runtime_api.api_version::<dyn Core<Block>>(&block_id).unwrap(), Some(2));
+	let version = runtime_api.api_version::<dyn TestAPI<Block>>(&block_id).unwrap();
+	assert_eq!(version, Some(2));
+
+	let res = match version {
+		Some(v) if v >= 2 => runtime_api.function_signature_changed(&block_id).unwrap(),
+		_ => {
+			#[allow(deprecated)]
+			let old = runtime_api.function_signature_changed_before_version_2(&block_id).unwrap();
+			old.len() as u64
+		},
+	};
+	assert_eq!(res, 1);
+}
+
 #[test]
 fn calling_with_both_strategy_and_fail_on_wasm_should_return_error() {
 	let client = TestClientBuilder::new().set_execution_strategy(ExecutionStrategy::Both).build();
@@ -170,7 +192,7 @@ fn record_proof_works() {
 
 	// Build the block and record proof
 	let mut builder = client
-		.new_block_at_with_proof_recording(&block_id, Default::default())
+		.new_block_at(&block_id, Default::default(), true)
 		.expect("Creates block builder");
 	builder.push(transaction.clone()).unwrap();
 	let (block, proof) = builder.bake_and_extract_proof().expect("Bake block");
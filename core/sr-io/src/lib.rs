@@ -33,6 +33,7 @@ use rstd::vec::Vec;
 pub use codec;
 
 pub use primitives::Blake2Hasher;
+use primitives::crypto::KeyTypeId;
 use primitives::offchain::{
 	Timestamp,
 	HttpRequestId, HttpRequestStatus, HttpError,
@@ -59,6 +60,47 @@ pub trait Printable {
 	fn print(self);
 }
 
+/// The level of a runtime log message.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum LogLevel {
+	/// Very serious errors.
+	Error = 1,
+	/// Hazardous situations.
+	Warn = 2,
+	/// Useful information.
+	Info = 3,
+	/// Lower priority information.
+	Debug = 4,
+	/// Very low priority, often extremely verbose, information.
+	Trace = 5,
+}
+
+impl From<u32> for LogLevel {
+	fn from(level: u32) -> Self {
+		match level {
+			1 => LogLevel::Error,
+			2 => LogLevel::Warn,
+			3 => LogLevel::Info,
+			4 => LogLevel::Debug,
+			_ => LogLevel::Trace,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<LogLevel> for log::Level {
+	fn from(level: LogLevel) -> Self {
+		match level {
+			LogLevel::Error => log::Level::Error,
+			LogLevel::Warn => log::Level::Warn,
+			LogLevel::Info => log::Level::Info,
+			LogLevel::Debug => log::Level::Debug,
+			LogLevel::Trace => log::Level::Trace,
+		}
+	}
+}
+
 /// Converts a public trait definition into a private trait and set of public functions
 /// that assume the trait is implemented for `()` for ease of calling.
 macro_rules! export_api {
@@ -134,12 +176,34 @@ export_api! {
 		/// Check whether a given `key` exists in storage.
 		fn exists_storage(key: &[u8]) -> bool;
 
+		/// Get the next key in storage after the given one in lexicographic order.
+		fn next_key(key: &[u8]) -> Option<Vec<u8>>;
+
 		/// Check whether a given `key` exists in storage.
 		fn exists_child_storage(storage_key: &[u8], key: &[u8]) -> bool;
 
 		/// Clear the storage entries with a key that starts with the given prefix.
 		fn clear_prefix(prefix: &[u8]);
 
+		/// Start a new nested storage transaction.
+		///
+		/// All storage changes made from now on are reverted by a matching
+		/// `storage_rollback_transaction` or kept by a matching `storage_commit_transaction`.
+		fn storage_start_transaction();
+
+		/// Revert all storage changes made since the innermost transaction was started and close it.
+		///
+		/// Panics if there is no open transaction.
+		fn storage_rollback_transaction();
+
+		/// Close the innermost storage transaction, keeping its changes.
+		///
+		/// Panics if there is no open transaction.
+		fn storage_commit_transaction();
+
+		/// The index of the extrinsic that is currently being applied, if any.
+		fn extrinsic_index() -> Option<u32>;
+
 		/// "Commit" all existing operations and compute the resultant storage root.
 		fn storage_root() -> [u8; 32];
 
@@ -187,12 +251,43 @@ export_api! {
 		/// The current relay chain identifier.
 		fn chain_id() -> u64;
 
+		/// Returns `true` if the code is executed by an offchain worker, rather than to build,
+		/// import or query a block.
+		fn is_offchain_worker() -> bool;
+
 		/// Print a printable value.
 		fn print<T>(value: T)
 		where
 			T: Printable,
 			T: Sized
 		;
+
+		/// Send a message to the node's logger, with the given `level` and `target`.
+		///
+		/// The message is logged under the target `runtime::<target>`, so runtime logs can be
+		/// filtered with e.g. `-l runtime=debug`. Invalid UTF-8 is replaced.
+		fn log(level: LogLevel, target: &[u8], message: &[u8]);
+
+		/// Enter a tracing span with the given `target` and `name`, e.g. a module and a function.
+		///
+		/// Returns the id of the span, to be passed to `wasm_tracing_exit_span`, or 0 if the
+		/// execution isn't being traced (see the `state_traceBlock` RPC) or the target is
+		/// filtered out.
+		fn wasm_tracing_enter_span(target: &[u8], name: &[u8]) -> u64;
+
+		/// Exit the tracing span with the given id.
+		fn wasm_tracing_exit_span(id: u64);
+
+		/// Returns the encoded `RuntimeVersion` reported by the `Core_version` function of the
+		/// given wasm blob, or `None` if it can not be determined.
+		fn runtime_version(code: &[u8]) -> Option<Vec<u8>>;
+
+		/// Returns the current time of the host in nanoseconds since the UNIX epoch.
+		///
+		/// Only meant for benchmarking; the result is not deterministic and must never
+		/// influence the state.
+		#[cfg(feature = "runtime-benchmarks")]
+		fn benchmark_time_nanos() -> u64;
 	}
 }
 
@@ -204,10 +299,72 @@ export_api! {
 		/// Verify an sr25519 signature.
 		fn sr25519_verify<P: AsRef<[u8]>>(sig: &[u8; 64], msg: &[u8], pubkey: P) -> bool;
 
+		/// Generate an ed25519 key of type `id` in the keystore and return its public key.
+		///
+		/// If `seed` is given, it is used as the secret URI of the key and the key is not
+		/// persisted.
+		fn ed25519_generate(id: KeyTypeId, seed: Option<&str>) -> [u8; 32];
+
+		/// Generate an sr25519 key of type `id` in the keystore and return its public key.
+		///
+		/// If `seed` is given, it is used as the secret URI of the key and the key is not
+		/// persisted.
+		fn sr25519_generate(id: KeyTypeId, seed: Option<&str>) -> [u8; 32];
+
 		/// Verify and recover a SECP256k1 ECDSA signature.
 		/// - `sig` is passed in RSV format. V should be either 0/1 or 27/28.
 		/// - returns `Err` if the signature is bad, otherwise the 64-byte pubkey (doesn't include the 0x04 prefix).
 		fn secp256k1_ecdsa_recover(sig: &[u8; 65], msg: &[u8; 32]) -> Result<[u8; 64], EcdsaVerifyError>;
+
+		/// Verify and recover a SECP256k1 ECDSA signature.
+		/// - `sig` is passed in RSV format. V should be either 0/1 or 27/28.
+		/// - returns `Err` if the signature is bad, otherwise the 33-byte compressed pubkey.
+		fn secp256k1_ecdsa_recover_compressed(sig: &[u8; 65], msg: &[u8; 32]) -> Result<[u8; 33], EcdsaVerifyError>;
+
+		/// Generate an ecdsa key of type `id` in the keystore and return its compressed public key.
+		///
+		/// If `seed` is given, it is used as the secret URI of the key and the key is not
+		/// persisted.
+		fn ecdsa_generate(id: KeyTypeId, seed: Option<&str>) -> [u8; 33];
+
+		/// Sign `msg` with the ecdsa key of the given compressed public key from the keystore.
+		///
+		/// The message is hashed with `blake2_256` before signing. Returns `None` if the key is
+		/// not in the keystore.
+		fn ecdsa_sign(pubkey: &[u8; 33], msg: &[u8]) -> Option<[u8; 65]>;
+
+		/// Verify an ecdsa signature of the `blake2_256` hash of `msg` against the compressed
+		/// public key.
+		fn ecdsa_verify(sig: &[u8; 65], msg: &[u8], pubkey: &[u8; 33]) -> bool;
+
+		/// Verify a BLS12-381 signature of `msg` against the compressed public key.
+		fn bls_verify(sig: &[u8; 96], msg: &[u8], pubkey: &[u8; 48]) -> bool;
+
+		/// Verify an aggregated BLS12-381 signature, where `messages[i]` was signed by
+		/// `pubkeys[i]`.
+		///
+		/// Returns `false` if the lengths differ, no messages are given or a message is repeated.
+		fn bls_aggregate_verify(sig: &[u8; 96], messages: &[&[u8]], pubkeys: &[[u8; 48]]) -> bool;
+
+		/// Start a signature verification batch.
+		///
+		/// Until `finish_batch_verify` is called, `sig_batch_verify` queues the signatures it is
+		/// given instead of verifying them. The batch ends with the current runtime call, even if
+		/// the call panics.
+		///
+		/// Panics if a batch is already started.
+		fn start_batch_verify();
+
+		/// Verify an ed25519 or sr25519 signature, identified by the key type `id`.
+		///
+		/// If a batch is started the signature is only queued and `true` is returned, unless the
+		/// key type is not supported. The result of the batch is given by `finish_batch_verify`.
+		fn sig_batch_verify(id: KeyTypeId, sig: &[u8; 64], msg: &[u8], pubkey: &[u8; 32]) -> bool;
+
+		/// Verify all signatures queued since `start_batch_verify` and end the batch.
+		///
+		/// Returns `true` if all of them are valid. Panics if no batch was started.
+		fn finish_batch_verify() -> bool;
 	}
 }
 
@@ -216,6 +373,12 @@ export_api! {
 		/// Conduct a 256-bit Keccak hash.
 		fn keccak_256(data: &[u8]) -> [u8; 32] ;
 
+		/// Conduct a 256-bit Sha2 hash.
+		fn sha2_256(data: &[u8]) -> [u8; 32];
+
+		/// Conduct a 160-bit Ripemd hash.
+		fn ripemd_160(data: &[u8]) -> [u8; 20];
+
 		/// Conduct a 128-bit Blake2 hash.
 		fn blake2_128(data: &[u8]) -> [u8; 16];
 
@@ -398,7 +561,7 @@ mod imp {
 #[cfg(feature = "std")]
 pub use self::imp::{
 	StorageOverlay, ChildrenStorageOverlay, with_storage, with_storage_and_children,
-	with_externalities
+	with_externalities, with_runtime_version_provider, RuntimeVersionProvider, TestExternalitiesExt,
 };
 #[cfg(not(feature = "std"))]
 pub use self::imp::ext::*;
@@ -15,7 +15,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use primitives::{
-	blake2_128, blake2_256, twox_128, twox_256, twox_64, ed25519, Blake2Hasher,
+	blake2_128, blake2_256, twox_128, twox_256, twox_64, sha2_256, ripemd_160, bls, ecdsa, ed25519, Blake2Hasher,
 	sr25519, Pair
 };
 // Switch to this after PoC-3
@@ -29,12 +29,25 @@ pub use substrate_state_machine::{
 
 use environmental::environmental;
 use primitives::{offchain, hexdisplay::HexDisplay, H256};
+use primitives::batch_verifier::{self, BatchVerifier};
 
 #[cfg(feature = "std")]
 use std::collections::HashMap;
 
 environmental!(ext: trait Externalities<Blake2Hasher>);
 
+/// Function returning the encoded `RuntimeVersion` of a wasm blob.
+///
+/// The blob is executed without access to the current externalities, so reading the version
+/// can't change the storage.
+pub type RuntimeVersionProvider = fn(&[u8]) -> Option<Vec<u8>>;
+
+environmental!(runtime_version_provider: RuntimeVersionProvider);
+
+// The signature verification batch of the current call, if any. It only lives as long as the
+// `with_externalities` call it belongs to, so a panicking runtime can't leave it open.
+environmental!(batch_verification: Option<BatchVerifier>);
+
 /// Additional bounds for `Hasher` trait for with_std.
 pub trait HasherBounds {}
 impl<T: Hasher> HasherBounds for T {}
@@ -132,6 +145,11 @@ impl StorageApi for () {
 		).unwrap_or(false)
 	}
 
+	fn next_key(key: &[u8]) -> Option<Vec<u8>> {
+		ext::with(|ext| ext.next_storage_key(key))
+			.expect("next_key cannot be called outside of an Externalities-provided environment.")
+	}
+
 	fn exists_child_storage(storage_key: &[u8], key: &[u8]) -> bool {
 		ext::with(|ext| {
 			let storage_key = child_storage_key_or_panic(storage_key);
@@ -145,6 +163,27 @@ impl StorageApi for () {
 		);
 	}
 
+	fn storage_start_transaction() {
+		ext::with(|ext| ext.storage_start_transaction())
+			.expect("storage_start_transaction cannot be called outside of an Externalities-provided environment.")
+	}
+
+	fn storage_rollback_transaction() {
+		ext::with(|ext| ext.storage_rollback_transaction())
+			.expect("storage_rollback_transaction cannot be called outside of an Externalities-provided environment.")
+			.expect("No open storage transaction to roll back.")
+	}
+
+	fn storage_commit_transaction() {
+		ext::with(|ext| ext.storage_commit_transaction())
+			.expect("storage_commit_transaction cannot be called outside of an Externalities-provided environment.")
+			.expect("No open storage transaction to commit.")
+	}
+
+	fn extrinsic_index() -> Option<u32> {
+		ext::with(|ext| ext.extrinsic_index()).unwrap_or(None)
+	}
+
 	fn storage_root() -> [u8; 32] {
 		ext::with(|ext|
 			ext.storage_root()
@@ -201,9 +240,45 @@ impl OtherApi for () {
 		).unwrap_or(0)
 	}
 
+	fn is_offchain_worker() -> bool {
+		ext::with(|ext| ext.is_offchain_worker()).unwrap_or(false)
+	}
+
 	fn print<T: Printable + Sized>(value: T) {
 		value.print()
 	}
+
+	fn log(level: LogLevel, target: &[u8], message: &[u8]) {
+		let target = String::from_utf8_lossy(target);
+		log::log!(
+			target: &format!("runtime::{}", target),
+			log::Level::from(level),
+			"{}",
+			String::from_utf8_lossy(message)
+		)
+	}
+
+	fn wasm_tracing_enter_span(target: &[u8], name: &[u8]) -> u64 {
+		if !substrate_tracing::is_tracing() {
+			return 0;
+		}
+		substrate_tracing::enter_span(&String::from_utf8_lossy(target), &String::from_utf8_lossy(name))
+	}
+
+	fn wasm_tracing_exit_span(id: u64) {
+		substrate_tracing::exit_span(id)
+	}
+
+	fn runtime_version(code: &[u8]) -> Option<Vec<u8>> {
+		runtime_version_provider::with(|provider| provider(code)).and_then(|version| version)
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn benchmark_time_nanos() -> u64 {
+		std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+			.map(|time| time.as_secs() * 1_000_000_000 + u64::from(time.subsec_nanos()))
+			.unwrap_or(0)
+	}
 }
 
 impl CryptoApi for () {
@@ -215,6 +290,26 @@ impl CryptoApi for () {
 		sr25519::Pair::verify_weak(sig, msg, pubkey)
 	}
 
+	fn ed25519_generate(id: KeyTypeId, seed: Option<&str>) -> [u8; 32] {
+		ext::with(|ext| {
+			ext.keystore()
+				.expect("No `keystore` associated for the current context!")
+				.ed25519_generate_new(id, seed)
+				.expect("`ed25519_generate` failed")
+				.0
+		}).expect("`ed25519_generate` cannot be called outside of an Externalities-provided environment.")
+	}
+
+	fn sr25519_generate(id: KeyTypeId, seed: Option<&str>) -> [u8; 32] {
+		ext::with(|ext| {
+			ext.keystore()
+				.expect("No `keystore` associated for the current context!")
+				.sr25519_generate_new(id, seed)
+				.expect("`sr25519_generate` failed")
+				.0
+		}).expect("`sr25519_generate` cannot be called outside of an Externalities-provided environment.")
+	}
+
 	fn secp256k1_ecdsa_recover(sig: &[u8; 65], msg: &[u8; 32]) -> Result<[u8; 64], EcdsaVerifyError> {
 		let rs = secp256k1::Signature::parse_slice(&sig[0..64])
 			.map_err(|_| EcdsaVerifyError::BadRS)?;
@@ -226,6 +321,76 @@ impl CryptoApi for () {
 		res.copy_from_slice(&pubkey.serialize()[1..65]);
 		Ok(res)
 	}
+
+	fn secp256k1_ecdsa_recover_compressed(sig: &[u8; 65], msg: &[u8; 32]) -> Result<[u8; 33], EcdsaVerifyError> {
+		let rs = secp256k1::Signature::parse_slice(&sig[0..64])
+			.map_err(|_| EcdsaVerifyError::BadRS)?;
+		let v = secp256k1::RecoveryId::parse(if sig[64] > 26 { sig[64] - 27 } else { sig[64] } as u8)
+			.map_err(|_| EcdsaVerifyError::BadV)?;
+		let pubkey = secp256k1::recover(&secp256k1::Message::parse(msg), &rs, &v)
+			.map_err(|_| EcdsaVerifyError::BadSignature)?;
+		Ok(pubkey.serialize_compressed())
+	}
+
+	fn ecdsa_generate(id: KeyTypeId, seed: Option<&str>) -> [u8; 33] {
+		ext::with(|ext| {
+			ext.keystore()
+				.expect("No `keystore` associated for the current context!")
+				.ecdsa_generate_new(id, seed)
+				.expect("`ecdsa_generate` failed")
+				.0
+		}).expect("`ecdsa_generate` cannot be called outside of an Externalities-provided environment.")
+	}
+
+	fn ecdsa_sign(pubkey: &[u8; 33], msg: &[u8]) -> Option<[u8; 65]> {
+		ext::with(|ext| {
+			ext.keystore()
+				.expect("No `keystore` associated for the current context!")
+				.ecdsa_sign(&ecdsa::Public::from_raw(*pubkey), msg)
+				.ok()
+				.map(|sig| sig.0)
+		}).expect("`ecdsa_sign` cannot be called outside of an Externalities-provided environment.")
+	}
+
+	fn ecdsa_verify(sig: &[u8; 65], msg: &[u8], pubkey: &[u8; 33]) -> bool {
+		ecdsa::Pair::verify_weak(&sig[..], msg, &pubkey[..])
+	}
+
+	fn bls_verify(sig: &[u8; 96], msg: &[u8], pubkey: &[u8; 48]) -> bool {
+		bls::Pair::verify_weak(&sig[..], msg, &pubkey[..])
+	}
+
+	fn bls_aggregate_verify(sig: &[u8; 96], messages: &[&[u8]], pubkeys: &[[u8; 48]]) -> bool {
+		if messages.len() != pubkeys.len() {
+			return false;
+		}
+		let signed = messages.iter()
+			.zip(pubkeys.iter())
+			.map(|(message, pubkey)| (*message, bls::Public(*pubkey)))
+			.collect::<Vec<_>>();
+		bls::Signature(*sig).verify_aggregate(&signed)
+	}
+
+	fn start_batch_verify() {
+		batch_verification::with(|batch| {
+			assert!(batch.is_none(), "`start_batch_verify` called while a batch is already started");
+			*batch = Some(BatchVerifier::new());
+		}).expect("`start_batch_verify` cannot be called outside of an Externalities-provided environment.")
+	}
+
+	fn sig_batch_verify(id: KeyTypeId, sig: &[u8; 64], msg: &[u8], pubkey: &[u8; 32]) -> bool {
+		batch_verification::with(|batch| match *batch {
+			Some(ref mut batch) => batch.push(id, sig, msg, pubkey),
+			None => batch_verifier::verify(id, sig, msg, pubkey),
+		}).unwrap_or_else(|| batch_verifier::verify(id, sig, msg, pubkey))
+	}
+
+	fn finish_batch_verify() -> bool {
+		batch_verification::with(|batch| batch.take())
+			.and_then(|batch| batch)
+			.expect("`finish_batch_verify` called without `start_batch_verify`")
+			.verify_and_clear()
+	}
 }
 
 impl HashingApi for () {
@@ -233,6 +398,14 @@ impl HashingApi for () {
 		tiny_keccak::keccak256(data)
 	}
 
+	fn sha2_256(data: &[u8]) -> [u8; 32] {
+		sha2_256(data)
+	}
+
+	fn ripemd_160(data: &[u8]) -> [u8; 20] {
+		ripemd_160(data)
+	}
+
 	fn blake2_128(data: &[u8]) -> [u8; 16] {
 		blake2_128(data)
 	}
@@ -429,7 +602,30 @@ impl Api for () {}
 /// externalities `ext`. Forwards the value that the closure returns.
 // NOTE: need a concrete hasher here due to limitations of the `environmental!` macro, otherwise a type param would have been fine I think.
 pub fn with_externalities<R, F: FnOnce() -> R>(ext: &mut dyn Externalities<Blake2Hasher>, f: F) -> R {
-	ext::using(ext, f)
+	let mut batch = None;
+	batch_verification::using(&mut batch, || ext::using(ext, f))
+}
+
+/// Extension trait for `TestExternalities` to execute closures against it.
+pub trait TestExternalitiesExt {
+	/// Execute the given closure while `self` is set as externalities. Forwards the value that
+	/// the closure returns.
+	fn execute_with<R>(&mut self, f: impl FnOnce() -> R) -> R;
+}
+
+impl TestExternalitiesExt for TestExternalities<Blake2Hasher, u64> {
+	fn execute_with<R>(&mut self, f: impl FnOnce() -> R) -> R {
+		with_externalities(self, f)
+	}
+}
+
+/// Execute the given closure with `runtime_version` routed into `provider`. Forwards the value
+/// that the closure returns.
+///
+/// Without a provider, `runtime_version` always returns `None`.
+pub fn with_runtime_version_provider<R, F: FnOnce() -> R>(provider: RuntimeVersionProvider, f: F) -> R {
+	let mut provider = provider;
+	runtime_version_provider::using(&mut provider, f)
 }
 
 /// A set of key value pairs for storage.
@@ -444,7 +640,7 @@ pub fn with_storage<R, F: FnOnce() -> R>(storage: &mut StorageOverlay, f: F) ->
 	let mut alt_storage = Default::default();
 	rstd::mem::swap(&mut alt_storage, storage);
 	let mut ext = BasicExternalities::new(alt_storage);
-	let r = ext::using(&mut ext, f);
+	let r = with_externalities(&mut ext, f);
 	*storage = ext.into_storages().0;
 	r
 }
@@ -463,7 +659,7 @@ pub fn with_storage_and_children<R, F: FnOnce() -> R>(
 	rstd::mem::swap(&mut alt_children_storage, children_storage);
 
 	let mut ext = BasicExternalities::new_with_children(alt_storage, alt_children_storage);
-	let r = ext::using(&mut ext, f);
+	let r = with_externalities(&mut ext, f);
 
 	let storage_tuple = ext.into_storages();
 	*storage = storage_tuple.0;
@@ -516,6 +712,16 @@ mod std_tests {
 		}));
 	}
 
+	#[test]
+	fn execute_with_forwards_return_value() {
+		let mut t = TestExternalities::<Blake2Hasher, u64>::default();
+		t.execute_with(|| set_storage(b"hello", b"world"));
+		t.commit_all();
+
+		let value = t.execute_with(|| storage(b"hello"));
+		assert_eq!(value, Some(b"world".to_vec()));
+	}
+
 	#[test]
 	fn read_storage_works() {
 		let mut t = BasicExternalities::new(map![
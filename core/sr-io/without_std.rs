@@ -198,6 +198,23 @@ pub mod ext {
 		fn ext_print_hex(data: *const u8, len: u32);
 		/// Print a number
 		fn ext_print_num(value: u64);
+		/// Log a message with the given level (see `LogLevel`) and target.
+		fn ext_log(
+			level: u32,
+			target_data: *const u8,
+			target_len: u32,
+			message_data: *const u8,
+			message_len: u32
+		);
+		/// Enter a tracing span with the given target and name, returning its id.
+		fn ext_wasm_tracing_enter_span(
+			target_data: *const u8,
+			target_len: u32,
+			name_data: *const u8,
+			name_len: u32
+		) -> u64;
+		/// Exit the tracing span with the given id.
+		fn ext_wasm_tracing_exit_span(id: u64);
 
 		/// Set value for key in storage.
 		fn ext_set_storage(key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32);
@@ -212,6 +229,23 @@ pub mod ext {
 		fn ext_exists_storage(key_data: *const u8, key_len: u32) -> u32;
 		/// Remove storage entries which key starts with given prefix.
 		fn ext_clear_prefix(prefix_data: *const u8, prefix_len: u32);
+		/// Start a new nested storage transaction.
+		fn ext_storage_start_transaction();
+		/// Revert all storage changes made since the innermost transaction was started.
+		///
+		/// Traps if there is no open transaction.
+		fn ext_storage_rollback_transaction();
+		/// Close the innermost storage transaction, keeping its changes.
+		///
+		/// Traps if there is no open transaction.
+		fn ext_storage_commit_transaction();
+		/// The index of the extrinsic that is currently being applied.
+		///
+		/// # Returns
+		///
+		/// - `u32::max_value()` if no extrinsic is being applied
+		/// - Otherwise, the index of the extrinsic
+		fn ext_extrinsic_index() -> u32;
 		/// Gets the value of the given key from storage.
 		///
 		/// The host allocates the memory for storing the value.
@@ -221,6 +255,15 @@ pub mod ext {
 		/// - `0` if no value exists to the given key. `written_out` is set to `u32::max_value()`.
 		/// - Otherwise, pointer to the value in memory. `written_out` contains the length of the value.
 		fn ext_get_allocated_storage(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8;
+		/// Gets the next key in storage after the given one in lexicographic order.
+		///
+		/// The host allocates the memory for storing the key.
+		///
+		/// # Returns
+		///
+		/// - `0` if there is no key after the given one. `written_out` is set to `u32::max_value()`.
+		/// - Otherwise, pointer to the key in memory. `written_out` contains the length of the key.
+		fn ext_next_key(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8;
 		/// Gets the value of the given key from storage.
 		///
 		/// The value is written into `value` starting at `value_offset`.
@@ -333,6 +376,22 @@ pub mod ext {
 		/// The current relay chain identifier.
 		fn ext_chain_id() -> u64;
 
+		/// Returns `1` if the code is executed by an offchain worker and `0` otherwise.
+		fn ext_is_offchain_worker() -> u32;
+
+		/// Get the encoded `RuntimeVersion` of the given wasm blob.
+		///
+		/// # Returns
+		///
+		/// - `0` if the version can not be determined. `written_out` is set to `u32::max_value()`.
+		///
+		/// - Otherwise, the pointer to the encoded version and `written_out` contains its length.
+		fn ext_runtime_version(code_data: *const u8, code_len: u32, written_out: *mut u32) -> *mut u8;
+
+		/// Current time of the host in nanoseconds since the UNIX epoch, for benchmarking.
+		#[cfg(feature = "runtime-benchmarks")]
+		fn ext_benchmark_time_nanos() -> u64;
+
 		/// Calculate a blake2_256 merkle trie root.
 		fn ext_blake2_256_enumerated_trie_root(
 			values_data: *const u8,
@@ -352,6 +411,10 @@ pub mod ext {
 		fn ext_twox_256(data: *const u8, len: u32, out: *mut u8);
 		/// Keccak256 hash
 		fn ext_keccak_256(data: *const u8, len: u32, out: *mut u8);
+		/// Sha2 256 hash
+		fn ext_sha2_256(data: *const u8, len: u32, out: *mut u8);
+		/// Ripemd160 hash
+		fn ext_ripemd_160(data: *const u8, len: u32, out: *mut u8);
 		/// Note: ext_ed25519_verify returns 0 if the signature is correct, nonzero otherwise.
 		fn ext_ed25519_verify(
 			msg_data: *const u8,
@@ -372,6 +435,74 @@ pub mod ext {
 			sig_data: *const u8,
 			pubkey_data: *mut u8
 		) -> u32;
+		/// Generate an ed25519 key of type `id` in the keystore, writing the public key to `out`.
+		///
+		/// `seed_len` is `u32::max_value()` if no seed is given.
+		fn ext_ed25519_generate(id: u32, seed: *const u8, seed_len: u32, out: *mut u8);
+		/// Generate an sr25519 key of type `id` in the keystore, writing the public key to `out`.
+		///
+		/// `seed_len` is `u32::max_value()` if no seed is given.
+		fn ext_sr25519_generate(id: u32, seed: *const u8, seed_len: u32, out: *mut u8);
+		/// Note: ext_secp256k1_ecdsa_recover_compressed returns 0 if the signature is correct,
+		/// nonzero otherwise.
+		fn ext_secp256k1_ecdsa_recover_compressed(
+			msg_data: *const u8,
+			sig_data: *const u8,
+			pubkey_data: *mut u8
+		) -> u32;
+		/// Generate an ecdsa key of type `id` in the keystore, writing the compressed public key
+		/// to `out`.
+		///
+		/// `seed_len` is `u32::max_value()` if no seed is given.
+		fn ext_ecdsa_generate(id: u32, seed: *const u8, seed_len: u32, out: *mut u8);
+		/// Sign the message with the ecdsa key of the given compressed public key, writing the
+		/// signature to `out`.
+		///
+		/// Returns 0 on success, nonzero if the key is not in the keystore.
+		fn ext_ecdsa_sign(pubkey_data: *const u8, msg_data: *const u8, msg_len: u32, out: *mut u8) -> u32;
+		/// Note: ext_ecdsa_verify returns 0 if the signature is correct, nonzero otherwise.
+		fn ext_ecdsa_verify(
+			msg_data: *const u8,
+			msg_len: u32,
+			sig_data: *const u8,
+			pubkey_data: *const u8
+		) -> u32;
+		/// Note: ext_bls_verify returns 0 if the signature is correct, nonzero otherwise.
+		fn ext_bls_verify(
+			msg_data: *const u8,
+			msg_len: u32,
+			sig_data: *const u8,
+			pubkey_data: *const u8
+		) -> u32;
+		/// Verify an aggregated signature.
+		///
+		/// `messages_data` is the parity-codec encoded list of messages and `pubkeys_data` holds
+		/// `pubkeys_len` compressed public keys of 48 bytes each.
+		///
+		/// Note: ext_bls_aggregate_verify returns 0 if the signature is correct, nonzero otherwise.
+		fn ext_bls_aggregate_verify(
+			messages_data: *const u8,
+			messages_len: u32,
+			sig_data: *const u8,
+			pubkeys_data: *const u8,
+			pubkeys_len: u32
+		) -> u32;
+		/// Start queueing the signatures given to `ext_sig_batch_verify`.
+		fn ext_start_batch_verify();
+		/// Verify or, while a batch is started, queue an ed25519 or sr25519 signature.
+		///
+		/// Note: ext_sig_batch_verify returns 0 if the signature is correct or queued,
+		/// nonzero otherwise.
+		fn ext_sig_batch_verify(
+			id: u32,
+			msg_data: *const u8,
+			msg_len: u32,
+			sig_data: *const u8,
+			pubkey_data: *const u8
+		) -> u32;
+		/// Note: ext_finish_batch_verify returns 0 if all queued signatures are correct,
+		/// nonzero otherwise.
+		fn ext_finish_batch_verify() -> u32;
 
 		//================================
 		// Offchain-worker Context
@@ -725,6 +856,14 @@ impl StorageApi for () {
 		}
 	}
 
+	fn next_key(key: &[u8]) -> Option<Vec<u8>> {
+		let mut length: u32 = 0;
+		unsafe {
+			let ptr = ext_next_key.get()(key.as_ptr(), key.len() as u32, &mut length);
+			from_raw_parts(ptr, length)
+		}
+	}
+
 	fn exists_child_storage(storage_key: &[u8], key: &[u8]) -> bool {
 		unsafe {
 			ext_exists_child_storage.get()(
@@ -743,6 +882,33 @@ impl StorageApi for () {
 		}
 	}
 
+	fn storage_start_transaction() {
+		unsafe {
+			ext_storage_start_transaction.get()();
+		}
+	}
+
+	fn storage_rollback_transaction() {
+		unsafe {
+			ext_storage_rollback_transaction.get()();
+		}
+	}
+
+	fn storage_commit_transaction() {
+		unsafe {
+			ext_storage_commit_transaction.get()();
+		}
+	}
+
+	fn extrinsic_index() -> Option<u32> {
+		let index = unsafe { ext_extrinsic_index.get()() };
+		if index == u32::max_value() {
+			None
+		} else {
+			Some(index)
+		}
+	}
+
 	fn kill_child_storage(storage_key: &[u8]) {
 		unsafe {
 			ext_kill_child_storage.get()(
@@ -814,10 +980,57 @@ impl OtherApi for () {
 		}
 	}
 
+	fn is_offchain_worker() -> bool {
+		unsafe { ext_is_offchain_worker.get()() == 1 }
+	}
+
 	fn print<T: Printable + Sized>(value: T) {
 		value.print()
 	}
 
+	fn log(level: LogLevel, target: &[u8], message: &[u8]) {
+		unsafe {
+			ext_log.get()(
+				level as u32,
+				target.as_ptr(),
+				target.len() as u32,
+				message.as_ptr(),
+				message.len() as u32,
+			)
+		}
+	}
+
+	fn wasm_tracing_enter_span(target: &[u8], name: &[u8]) -> u64 {
+		unsafe {
+			ext_wasm_tracing_enter_span.get()(
+				target.as_ptr(),
+				target.len() as u32,
+				name.as_ptr(),
+				name.len() as u32,
+			)
+		}
+	}
+
+	fn wasm_tracing_exit_span(id: u64) {
+		unsafe {
+			ext_wasm_tracing_exit_span.get()(id)
+		}
+	}
+
+	fn runtime_version(code: &[u8]) -> Option<Vec<u8>> {
+		let mut length: u32 = 0;
+		unsafe {
+			let ptr = ext_runtime_version.get()(code.as_ptr(), code.len() as u32, &mut length);
+			from_raw_parts(ptr, length)
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn benchmark_time_nanos() -> u64 {
+		unsafe {
+			ext_benchmark_time_nanos.get()()
+		}
+	}
 }
 
 impl HashingApi for () {
@@ -829,6 +1042,22 @@ impl HashingApi for () {
 		result
 	}
 
+	fn sha2_256(data: &[u8]) -> [u8; 32] {
+		let mut result: [u8; 32] = Default::default();
+		unsafe {
+			ext_sha2_256.get()(data.as_ptr(), data.len() as u32, result.as_mut_ptr());
+		}
+		result
+	}
+
+	fn ripemd_160(data: &[u8]) -> [u8; 20] {
+		let mut result: [u8; 20] = Default::default();
+		unsafe {
+			ext_ripemd_160.get()(data.as_ptr(), data.len() as u32, result.as_mut_ptr());
+		}
+		result
+	}
+
 	fn blake2_128(data: &[u8]) -> [u8; 16] {
 		let mut result: [u8; 16] = Default::default();
 		unsafe {
@@ -883,6 +1112,26 @@ impl CryptoApi for () {
 		}
 	}
 
+	fn ed25519_generate(id: KeyTypeId, seed: Option<&str>) -> [u8; 32] {
+		let mut public = [0u8; 32];
+		let (seed_data, seed_len) = seed.map(|s| (s.as_ptr(), s.len() as u32))
+			.unwrap_or((rstd::ptr::null(), u32::max_value()));
+		unsafe {
+			ext_ed25519_generate.get()(id, seed_data, seed_len, public.as_mut_ptr());
+		}
+		public
+	}
+
+	fn sr25519_generate(id: KeyTypeId, seed: Option<&str>) -> [u8; 32] {
+		let mut public = [0u8; 32];
+		let (seed_data, seed_len) = seed.map(|s| (s.as_ptr(), s.len() as u32))
+			.unwrap_or((rstd::ptr::null(), u32::max_value()));
+		unsafe {
+			ext_sr25519_generate.get()(id, seed_data, seed_len, public.as_mut_ptr());
+		}
+		public
+	}
+
 	fn secp256k1_ecdsa_recover(sig: &[u8; 65], msg: &[u8; 32]) -> Result<[u8; 64], EcdsaVerifyError> {
 		let mut pubkey = [0u8; 64];
 		match unsafe {
@@ -895,6 +1144,84 @@ impl CryptoApi for () {
 			_ => unreachable!("`ext_secp256k1_ecdsa_recover` only returns 0, 1, 2 or 3; qed"),
 		}
 	}
+
+	fn secp256k1_ecdsa_recover_compressed(sig: &[u8; 65], msg: &[u8; 32]) -> Result<[u8; 33], EcdsaVerifyError> {
+		let mut pubkey = [0u8; 33];
+		match unsafe {
+			ext_secp256k1_ecdsa_recover_compressed.get()(msg.as_ptr(), sig.as_ptr(), pubkey.as_mut_ptr())
+		} {
+			0 => Ok(pubkey),
+			1 => Err(EcdsaVerifyError::BadRS),
+			2 => Err(EcdsaVerifyError::BadV),
+			3 => Err(EcdsaVerifyError::BadSignature),
+			_ => unreachable!("`ext_secp256k1_ecdsa_recover_compressed` only returns 0, 1, 2 or 3; qed"),
+		}
+	}
+
+	fn ecdsa_generate(id: KeyTypeId, seed: Option<&str>) -> [u8; 33] {
+		let mut public = [0u8; 33];
+		let (seed_data, seed_len) = seed.map(|s| (s.as_ptr(), s.len() as u32))
+			.unwrap_or((rstd::ptr::null(), u32::max_value()));
+		unsafe {
+			ext_ecdsa_generate.get()(id, seed_data, seed_len, public.as_mut_ptr());
+		}
+		public
+	}
+
+	fn ecdsa_sign(pubkey: &[u8; 33], msg: &[u8]) -> Option<[u8; 65]> {
+		let mut sig = [0u8; 65];
+		let result = unsafe {
+			ext_ecdsa_sign.get()(pubkey.as_ptr(), msg.as_ptr(), msg.len() as u32, sig.as_mut_ptr())
+		};
+		if result == 0 {
+			Some(sig)
+		} else {
+			None
+		}
+	}
+
+	fn ecdsa_verify(sig: &[u8; 65], msg: &[u8], pubkey: &[u8; 33]) -> bool {
+		unsafe {
+			ext_ecdsa_verify.get()(msg.as_ptr(), msg.len() as u32, sig.as_ptr(), pubkey.as_ptr()) == 0
+		}
+	}
+
+	fn bls_verify(sig: &[u8; 96], msg: &[u8], pubkey: &[u8; 48]) -> bool {
+		unsafe {
+			ext_bls_verify.get()(msg.as_ptr(), msg.len() as u32, sig.as_ptr(), pubkey.as_ptr()) == 0
+		}
+	}
+
+	fn bls_aggregate_verify(sig: &[u8; 96], messages: &[&[u8]], pubkeys: &[[u8; 48]]) -> bool {
+		let messages = codec::Encode::encode(messages);
+		unsafe {
+			ext_bls_aggregate_verify.get()(
+				messages.as_ptr(),
+				messages.len() as u32,
+				sig.as_ptr(),
+				pubkeys.as_ptr() as *const u8,
+				pubkeys.len() as u32,
+			) == 0
+		}
+	}
+
+	fn start_batch_verify() {
+		unsafe {
+			ext_start_batch_verify.get()();
+		}
+	}
+
+	fn sig_batch_verify(id: KeyTypeId, sig: &[u8; 64], msg: &[u8], pubkey: &[u8; 32]) -> bool {
+		unsafe {
+			ext_sig_batch_verify.get()(id, msg.as_ptr(), msg.len() as u32, sig.as_ptr(), pubkey.as_ptr()) == 0
+		}
+	}
+
+	fn finish_batch_verify() -> bool {
+		unsafe {
+			ext_finish_batch_verify.get()() == 0
+		}
+	}
 }
 
 impl OffchainApi for () {
@@ -56,6 +56,10 @@ where
 		self.signed.as_ref().map(|x| &x.0)
 	}
 
+	fn call(&self) -> &Self::Call {
+		&self.function
+	}
+
 	fn validate<U: ValidateUnsigned<Call=Self::Call>>(&self,
 		info: DispatchInfo,
 		len: usize,
@@ -74,9 +78,10 @@ where
 		}
 	}
 
-	fn dispatch(self,
+	fn dispatch<F: FnOnce(&Self::Call) -> bool>(self,
 		info: DispatchInfo,
 		len: usize,
+		filter: F,
 	) -> Result<DispatchResult, DispatchError> {
 		let maybe_who = if let Some((id, extra)) = self.signed {
 			Extra::pre_dispatch(extra, &id, info, len)?;
@@ -85,6 +90,9 @@ where
 			Extra::pre_dispatch_unsigned(info, len)?;
 			None
 		};
+		if !filter(&self.function) {
+			return Ok(Err(crate::CALL_FILTERED));
+		}
 		Ok(self.function.dispatch(Origin::from(maybe_who)))
 	}
 }
@@ -23,6 +23,7 @@ use rstd::prelude::*;
 use runtime_io::blake2_256;
 use crate::codec::{Decode, Encode, Input};
 use crate::traits::{self, Member, MaybeDisplay, SignedExtension, Checkable, Extrinsic};
+use crate::weights::{GetDispatchInfo, DispatchInfo};
 use super::CheckedExtrinsic;
 
 const TRANSACTION_VERSION: u8 = 2;
@@ -123,6 +124,17 @@ where
 	}
 }
 
+impl<Address, Call, Signature, Extra> GetDispatchInfo
+	for UncheckedExtrinsic<Address, Call, Signature, Extra>
+where
+	Call: GetDispatchInfo,
+	Extra: SignedExtension,
+{
+	fn get_dispatch_info(&self) -> DispatchInfo {
+		self.function.get_dispatch_info()
+	}
+}
+
 impl<Address, Call, Signature, Extra> Decode
 	for UncheckedExtrinsic<Address, Call, Signature, Extra>
 where
@@ -316,6 +328,34 @@ mod tests {
 		);
 	}
 
+	// Resolves the "index" `1` to `TEST_ACCOUNT`, like an indices-aware lookup would.
+	struct IndexContext;
+	impl Lookup for IndexContext {
+		type Source = u64;
+		type Target = u64;
+		fn lookup(&self, s: u64) -> Result<u64, &'static str> {
+			if s == 1 { Ok(TEST_ACCOUNT) } else { Err("invalid account index") }
+		}
+	}
+
+	#[test]
+	fn signed_check_should_resolve_address() {
+		let signed = |address| Ex::new_signed(
+			vec![0u8; 0],
+			address,
+			TestSig(TEST_ACCOUNT, (vec![0u8; 0], TestExtra).encode()),
+			TestExtra
+		);
+		assert_eq!(
+			<Ex as Checkable<IndexContext>>::check(signed(1), &IndexContext),
+			Ok(CEx { signed: Some((TEST_ACCOUNT, TestExtra)), function: vec![0u8; 0] })
+		);
+		assert_eq!(
+			<Ex as Checkable<IndexContext>>::check(signed(2), &IndexContext),
+			Err("invalid account index")
+		);
+	}
+
 	#[test]
 	fn encoding_matches_vec() {
 		let ex = Ex::new_unsigned(vec![0u8; 0]);
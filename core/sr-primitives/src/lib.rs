@@ -35,7 +35,7 @@ pub use paste;
 pub use runtime_io::{StorageOverlay, ChildrenStorageOverlay};
 
 use rstd::{prelude::*, ops, convert::TryInto};
-use substrate_primitives::{crypto, ed25519, sr25519, hash::{H256, H512}};
+use substrate_primitives::{crypto, ecdsa, ed25519, sr25519, hash::{H256, H512}, U256};
 use codec::{Encode, Decode};
 
 #[cfg(feature = "std")]
@@ -43,7 +43,9 @@ pub mod testing;
 
 pub mod weights;
 pub mod traits;
-use traits::{SaturatedConversion, UniqueSaturatedInto, Saturating, Bounded, CheckedSub, CheckedAdd};
+use traits::{
+	SaturatedConversion, UniqueSaturatedFrom, UniqueSaturatedInto, Saturating, Bounded, CheckedSub, CheckedAdd,
+};
 
 pub mod generic;
 pub mod transaction_validity;
@@ -52,7 +54,7 @@ pub mod transaction_validity;
 pub use generic::{DigestItem, Digest};
 
 /// Re-export this since it's part of the API of this crate.
-pub use substrate_primitives::crypto::{key_types, KeyTypeId};
+pub use substrate_primitives::crypto::{key_types, AccountId32, KeyTypeId};
 
 /// A message indicating an invalid signature in extrinsic.
 pub const BAD_SIGNATURE: &str = "bad signature in extrinsic";
@@ -67,6 +69,12 @@ pub const BAD_SIGNATURE: &str = "bad signature in extrinsic";
 /// Example: block gas limit is reached (the transaction can be retried in the next block though).
 pub const BLOCK_FULL: &str = "block size limit is reached";
 
+/// Filtered call error message.
+///
+/// The call of an extrinsic was rejected by the runtime's call filter. The fees of the
+/// extrinsic are paid nonetheless.
+pub const CALL_FILTERED: &str = "call is filtered";
+
 /// Justification type.
 pub type Justification = Vec<u8>;
 
@@ -167,217 +175,205 @@ impl BuildStorage for (StorageOverlay, ChildrenStorageOverlay) {
 /// Consensus engine unique ID.
 pub type ConsensusEngineId = [u8; 4];
 
-/// Permill is parts-per-million (i.e. after multiplying by this, divide by 1000000).
-#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug, Ord, PartialOrd))]
-#[derive(Encode, Decode, Default, Copy, Clone, PartialEq, Eq)]
-pub struct Permill(u32);
-
-impl Permill {
-	/// Nothing.
-	pub fn zero() -> Self { Self(0) }
-
-	/// `true` if this is nothing.
-	pub fn is_zero(&self) -> bool { self.0 == 0 }
-
-	/// Everything.
-	pub fn one() -> Self { Self(1_000_000) }
-
-	/// From an explicitly defined number of parts per maximum of the type.
-	pub fn from_parts(x: u32) -> Self { Self(x.min(1_000_000)) }
-
-	/// Converts from a percent. Equal to `x / 100`.
-	pub fn from_percent(x: u32) -> Self { Self(x.min(100) * 10_000) }
-
-	/// Converts a fraction into `Permill`.
-	#[cfg(feature = "std")]
-	pub fn from_fraction(x: f64) -> Self { Self((x * 1_000_000.0) as u32) }
-
-	/// Approximate the fraction `p/q` into a per million fraction
-	pub fn from_rational_approximation<N>(p: N, q: N) -> Self
-		where N: traits::SimpleArithmetic + Clone
-	{
-		let p = p.min(q.clone());
-		let factor = (q.clone() / 1_000_000u32.into()).max(1u32.into());
-
-		// Conversion can't overflow as p < q so ( p / (q/million)) < million
-		let p_reduce: u32 = (p / factor.clone()).try_into().unwrap_or_else(|_| panic!());
-		let q_reduce: u32 = (q / factor.clone()).try_into().unwrap_or_else(|_| panic!());
-		let part = p_reduce as u64 * 1_000_000u64 / q_reduce as u64;
-
-		Permill(part as u32)
-	}
-}
-
-impl<N> ops::Mul<N> for Permill
-where
-	N: Clone + From<u32> + UniqueSaturatedInto<u32> + ops::Rem<N, Output=N>
-		+ ops::Div<N, Output=N> + ops::Mul<N, Output=N> + ops::Add<N, Output=N>,
-{
-	type Output = N;
-	fn mul(self, b: N) -> Self::Output {
-		let million: N = 1_000_000.into();
-		let part: N = self.0.into();
+macro_rules! implement_per_thing {
+	($name:ident, $max:tt, $type:ty, $upper_type:ty, $title:expr $(,)?) => {
+		/// A fixed point representation of a number in the range [0, 1].
+		///
+		#[doc = $title]
+		#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+		#[derive(Encode, Decode, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+		pub struct $name($type);
+
+		impl $name {
+			/// Nothing.
+			pub fn zero() -> Self { Self(0) }
+
+			/// `true` if this is nothing.
+			pub fn is_zero(&self) -> bool { self.0 == 0 }
+
+			/// Everything.
+			pub fn one() -> Self { Self($max) }
+
+			/// From an explicitly defined number of parts per maximum of the type.
+			pub fn from_parts(parts: $type) -> Self { Self(parts.min($max)) }
+
+			/// Consume self and return the number of parts per thing.
+			pub fn deconstruct(self) -> $type { self.0 }
+
+			/// Converts from a percent. Equal to `x / 100`.
+			pub fn from_percent(x: $type) -> Self { Self(x.min(100) * ($max / 100)) }
+
+			/// Converts a fraction into `Self`, clamping it to the range [0, 1].
+			#[cfg(feature = "std")]
+			pub fn from_fraction(x: f64) -> Self { Self((x.max(0.0).min(1.0) * ($max as $type) as f64) as $type) }
+
+			/// Approximate the fraction `p/q` into a per-thing fraction.
+			///
+			/// The fraction is clamped to `one()` if `p > q`, and `q` is treated as `1` if it is
+			/// zero.
+			pub fn from_rational_approximation<N>(p: N, q: N) -> Self
+				where N: traits::SimpleArithmetic + Clone
+			{
+				let q = q.max(N::one());
+				let p = p.min(q.clone());
+				let factor = (q.clone() / N::unique_saturated_from($max as u128)).max(N::one());
+
+				// `p / factor` and `q / factor` are less than `2 * $max`, thus they fit into
+				// `$type`, and their product with `$max` fits into `$upper_type`.
+				let p_reduce: $type = (p / factor.clone()).saturated_into();
+				let q_reduce: $type = (q / factor).saturated_into();
+				let part = p_reduce as $upper_type * $max as $upper_type / q_reduce as $upper_type;
+
+				$name(part as $type)
+			}
 
-		let rem_multiplied_divided = {
-			let rem = b.clone().rem(million.clone());
+			/// Multiply by `b`, rounding the result down.
+			///
+			/// This is the same as `self * b`.
+			pub fn mul_floor<N>(self, b: N) -> N
+			where
+				N: Clone + From<$type> + UniqueSaturatedInto<$type> + ops::Rem<N, Output=N>
+					+ ops::Div<N, Output=N> + ops::Mul<N, Output=N> + ops::Add<N, Output=N>,
+			{
+				self.mul_rounded(b, false)
+			}
 
-			// `rem` is inferior to one million, thus it fits into u32
-			let rem_u32 = rem.saturated_into::<u32>();
+			/// Multiply by `b`, rounding the result up.
+			pub fn mul_ceil<N>(self, b: N) -> N
+			where
+				N: Clone + From<$type> + UniqueSaturatedInto<$type> + ops::Rem<N, Output=N>
+					+ ops::Div<N, Output=N> + ops::Mul<N, Output=N> + ops::Add<N, Output=N>,
+			{
+				self.mul_rounded(b, true)
+			}
 
-			// `self` and `rem` are inferior to one million, thus the product is less than 10^12
-			// and fits into u64
-			let rem_multiplied_u64 = rem_u32 as u64 * self.0 as u64;
+			fn mul_rounded<N>(self, b: N, round_up: bool) -> N
+			where
+				N: Clone + From<$type> + UniqueSaturatedInto<$type> + ops::Rem<N, Output=N>
+					+ ops::Div<N, Output=N> + ops::Mul<N, Output=N> + ops::Add<N, Output=N>,
+			{
+				let maximum: N = ($max as $type).into();
+				let part: N = self.0.into();
+
+				let rem_multiplied_divided = {
+					let rem = b.clone().rem(maximum.clone());
+
+					// `rem` is inferior to `$max`, thus it fits into `$type`.
+					let rem_sized = rem.saturated_into::<$type>();
+
+					// `self` and `rem` are inferior to `$max`, thus the product fits into
+					// `$upper_type`.
+					let rem_multiplied_upper = rem_sized as $upper_type * self.0 as $upper_type;
+
+					// `rem_multiplied_upper` is less than `$max^2`, therefore divided by `$max` it
+					// fits into `$type`. Rounding up can't overflow either, as the result is then
+					// still at most `rem`.
+					let mut rem_multiplied_divided_sized =
+						(rem_multiplied_upper / ($max as $upper_type)) as $type;
+					if round_up && rem_multiplied_upper % ($max as $upper_type) != 0 {
+						rem_multiplied_divided_sized += 1;
+					}
+
+					// `rem_multiplied_divided` is inferior to `b`, thus it can be converted back to
+					// `N`.
+					rem_multiplied_divided_sized.into()
+				};
+
+				(b / maximum) * part + rem_multiplied_divided
+			}
+		}
 
-			// `rem_multiplied_u64` is less than 10^12 therefore divided by a million it fits into
-			// u32
-			let rem_multiplied_divided_u32 = (rem_multiplied_u64 / 1_000_000) as u32;
+		impl Saturating for $name {
+			fn saturating_add(self, rhs: Self) -> Self {
+				// `$max` is less than half of the maximum of `$type`, so this can't overflow.
+				Self::from_parts(self.0 + rhs.0)
+			}
+			fn saturating_sub(self, rhs: Self) -> Self {
+				Self(self.0.saturating_sub(rhs.0))
+			}
+			fn saturating_mul(self, rhs: Self) -> Self {
+				let product = self.0 as $upper_type * rhs.0 as $upper_type / $max as $upper_type;
+				Self(product as $type)
+			}
+		}
 
-			// `rem_multiplied_divided` is inferior to b, thus it can be converted back to N type
-			rem_multiplied_divided_u32.into()
-		};
+		impl<N> ops::Mul<N> for $name
+		where
+			N: Clone + From<$type> + UniqueSaturatedInto<$type> + ops::Rem<N, Output=N>
+				+ ops::Div<N, Output=N> + ops::Mul<N, Output=N> + ops::Add<N, Output=N>,
+		{
+			type Output = N;
+			fn mul(self, b: N) -> Self::Output {
+				self.mul_floor(b)
+			}
+		}
 
-		(b / million) * part + rem_multiplied_divided
-	}
-}
+		#[cfg(feature = "std")]
+		impl From<f64> for $name {
+			fn from(x: f64) -> $name {
+				$name::from_fraction(x)
+			}
+		}
 
-#[cfg(feature = "std")]
-impl From<f64> for Permill {
-	fn from(x: f64) -> Permill {
-		Permill::from_fraction(x)
-	}
-}
+		#[cfg(feature = "std")]
+		impl From<f32> for $name {
+			fn from(x: f32) -> $name {
+				$name::from_fraction(x as f64)
+			}
+		}
 
-#[cfg(feature = "std")]
-impl From<f32> for Permill {
-	fn from(x: f32) -> Permill {
-		Permill::from_fraction(x as f64)
-	}
-}
+		impl codec::CompactAs for $name {
+			type As = $type;
+			fn encode_as(&self) -> &$type {
+				&self.0
+			}
+			fn decode_from(x: $type) -> $name {
+				$name(x)
+			}
+		}
 
-impl codec::CompactAs for Permill {
-	type As = u32;
-	fn encode_as(&self) -> &u32 {
-		&self.0
-	}
-	fn decode_from(x: u32) -> Permill {
-		Permill(x)
+		impl From<codec::Compact<$name>> for $name {
+			fn from(x: codec::Compact<$name>) -> $name {
+				x.0
+			}
+		}
 	}
 }
 
-impl From<codec::Compact<Permill>> for Permill {
-	fn from(x: codec::Compact<Permill>) -> Permill {
-		x.0
-	}
-}
-
-/// Perbill is parts-per-billion. It stores a value between 0 and 1 in fixed point and
-/// provides a means to multiply some other value by that.
-#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
-#[derive(Encode, Decode, Default, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
-pub struct Perbill(u32);
+implement_per_thing!(
+	Percent,
+	100,
+	u8,
+	u16,
+	"_Percent_, parts-per-hundred. After multiplying by this, divide by `100`.",
+);
+implement_per_thing!(
+	Permill,
+	1_000_000,
+	u32,
+	u64,
+	"_Parts per Million_. After multiplying by this, divide by `1_000_000`.",
+);
+implement_per_thing!(
+	Perbill,
+	1_000_000_000,
+	u32,
+	u64,
+	"_Parts per Billion_. After multiplying by this, divide by `1_000_000_000`.",
+);
+implement_per_thing!(
+	Perquintill,
+	1_000_000_000_000_000_000,
+	u64,
+	u128,
+	"_Parts per Quintillion_. After multiplying by this, divide by `1_000_000_000_000_000_000`.",
+);
 
 impl Perbill {
-	/// Nothing.
-	pub fn zero() -> Self { Self(0) }
-
-	/// `true` if this is nothing.
-	pub fn is_zero(&self) -> bool { self.0 == 0 }
-
-	/// Everything.
-	pub fn one() -> Self { Self(1_000_000_000) }
-
-	/// From an explicitly defined number of parts per maximum of the type.
-	pub fn from_parts(x: u32) -> Self { Self(x.min(1_000_000_000)) }
-
-	/// Converts from a percent. Equal to `x / 100`.
-	pub fn from_percent(x: u32) -> Self { Self(x.min(100) * 10_000_000) }
-
 	/// Construct new instance where `x` is in millionths. Value equivalent to `x / 1,000,000`.
 	pub fn from_millionths(x: u32) -> Self { Self(x.min(1_000_000) * 1000) }
-
-	#[cfg(feature = "std")]
-	/// Construct new instance whose value is equal to `x` (between 0 and 1).
-	pub fn from_fraction(x: f64) -> Self { Self((x.max(0.0).min(1.0) * 1_000_000_000.0) as u32) }
-
-	/// Approximate the fraction `p/q` into a per billion fraction
-	pub fn from_rational_approximation<N>(p: N, q: N) -> Self
-		where N: traits::SimpleArithmetic + Clone
-	{
-		let p = p.min(q.clone());
-		let factor = (q.clone() / 1_000_000_000u32.into()).max(1u32.into());
-
-		// Conversion can't overflow as p < q so ( p / (q/billion)) < billion
-		let p_reduce: u32 = (p / factor.clone()).try_into().unwrap_or_else(|_| panic!());
-		let q_reduce: u32 = (q / factor.clone()).try_into().unwrap_or_else(|_| panic!());
-		let part = p_reduce as u64 * 1_000_000_000u64 / q_reduce as u64;
-
-		Perbill(part as u32)
-	}
-}
-
-impl<N> ops::Mul<N> for Perbill
-where
-	N: Clone + From<u32> + UniqueSaturatedInto<u32> + ops::Rem<N, Output=N>
-	+ ops::Div<N, Output=N> + ops::Mul<N, Output=N> + ops::Add<N, Output=N>,
-{
-	type Output = N;
-	fn mul(self, b: N) -> Self::Output {
-		let billion: N = 1_000_000_000.into();
-		let part: N = self.0.into();
-
-		let rem_multiplied_divided = {
-			let rem = b.clone().rem(billion.clone());
-
-			// `rem` is inferior to one billion, thus it fits into u32
-			let rem_u32 = rem.saturated_into::<u32>();
-
-			// `self` and `rem` are inferior to one billion, thus the product is less than 10^18
-			// and fits into u64
-			let rem_multiplied_u64 = rem_u32 as u64 * self.0 as u64;
-
-			// `rem_multiplied_u64` is less than 10^18 therefore divided by a billion it fits into
-			// u32
-			let rem_multiplied_divided_u32 = (rem_multiplied_u64 / 1_000_000_000) as u32;
-
-			// `rem_multiplied_divided` is inferior to b, thus it can be converted back to N type
-			rem_multiplied_divided_u32.into()
-		};
-
-		(b / billion) * part + rem_multiplied_divided
-	}
-}
-
-#[cfg(feature = "std")]
-impl From<f64> for Perbill {
-	fn from(x: f64) -> Perbill {
-		Perbill::from_fraction(x)
-	}
-}
-
-#[cfg(feature = "std")]
-impl From<f32> for Perbill {
-	fn from(x: f32) -> Perbill {
-		Perbill::from_fraction(x as f64)
-	}
 }
 
-impl codec::CompactAs for Perbill {
-	type As = u32;
-	fn encode_as(&self) -> &u32 {
-		&self.0
-	}
-	fn decode_from(x: u32) -> Perbill {
-		Perbill(x)
-	}
-}
-
-impl From<codec::Compact<Perbill>> for Perbill {
-	fn from(x: codec::Compact<Perbill>) -> Perbill {
-		x.0
-	}
-}
-
-
 /// A fixed point number by the scale of 1 billion.
 ///
 /// cannot hold a value larger than +-`9223372036854775807 / 1_000_000_000` (~9 billion).
@@ -385,7 +381,7 @@ impl From<codec::Compact<Perbill>> for Perbill {
 #[derive(Encode, Decode, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Fixed64(i64);
 
-/// The maximum value of the `Fixed64` type
+/// The number of parts of a `Fixed64` that make up the value `1`.
 const DIV: i64 = 1_000_000_000;
 
 impl Fixed64 {
@@ -499,6 +495,105 @@ impl CheckedAdd for Fixed64 {
 	}
 }
 
+/// An unsigned fixed point number by the scale of 1 quintillion (`10^18`).
+///
+/// cannot hold a value larger than `340282366920938463463374607431768211455 / 10^18` (~340 quintillion).
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FixedU128(u128);
+
+/// The number of parts of a `FixedU128` that make up the value `1`.
+const DIV_U128: u128 = 1_000_000_000_000_000_000;
+
+impl FixedU128 {
+	/// creates self from a natural number.
+	///
+	/// Note that this might be lossy.
+	pub fn from_natural(int: u128) -> Self {
+		Self(int.saturating_mul(DIV_U128))
+	}
+
+	/// Return the accuracy of the type. Given that this function returns the value `X`, it means
+	/// that an instance composed of `X` parts (`FixedU128::from_parts(X)`) is equal to `1`.
+	pub fn accuracy() -> u128 {
+		DIV_U128
+	}
+
+	/// creates self from a rational number. Equal to `n/d`.
+	///
+	/// Note that this might be lossy.
+	pub fn from_rational(n: u128, d: u128) -> Self {
+		Self(saturated_u128(U256::from(n) * U256::from(DIV_U128) / U256::from(d.max(1))))
+	}
+
+	/// Raw constructor. Equal to `parts / 10^18`.
+	pub fn from_parts(parts: u128) -> Self {
+		Self(parts)
+	}
+
+	/// Consume self and return the inner raw value.
+	pub fn deconstruct(self) -> u128 {
+		self.0
+	}
+
+	/// Performs a saturated multiplication with a natural number, rounding the result down.
+	pub fn saturated_multiply(&self, int: u128) -> u128 {
+		saturated_u128(U256::from(self.0) * U256::from(int) / U256::from(DIV_U128))
+	}
+}
+
+fn saturated_u128(x: U256) -> u128 {
+	if x > U256::from(u128::max_value()) {
+		u128::max_value()
+	} else {
+		x.as_u128()
+	}
+}
+
+impl Saturating for FixedU128 {
+	fn saturating_add(self, rhs: Self) -> Self {
+		Self(self.0.saturating_add(rhs.0))
+	}
+	fn saturating_mul(self, rhs: Self) -> Self {
+		Self(self.saturated_multiply(rhs.0))
+	}
+	fn saturating_sub(self, rhs: Self) -> Self {
+		Self(self.0.saturating_sub(rhs.0))
+	}
+}
+
+/// Note that this is a standard, _potentially-panicking_, implementation. Use `Saturating` trait for
+/// safe addition.
+impl ops::Add for FixedU128 {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self::Output {
+		Self(self.0 + rhs.0)
+	}
+}
+
+/// Note that this is a standard, _potentially-panicking_, implementation. Use `Saturating` trait for
+/// safe subtraction.
+impl ops::Sub for FixedU128 {
+	type Output = Self;
+
+	fn sub(self, rhs: Self) -> Self::Output {
+		Self(self.0 - rhs.0)
+	}
+}
+
+impl CheckedSub for FixedU128 {
+	fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+		self.0.checked_sub(rhs.0).map(Self)
+	}
+}
+
+impl CheckedAdd for FixedU128 {
+	fn checked_add(&self, rhs: &Self) -> Option<Self> {
+		self.0.checked_add(rhs.0).map(Self)
+	}
+}
+
 /// PerU128 is parts-per-u128-max-value. It stores a value between 0 and 1 in fixed point.
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 #[derive(Encode, Decode, Default, Copy, Clone, PartialEq, Eq)]
@@ -555,6 +650,8 @@ pub enum MultiSignature {
 	Ed25519(ed25519::Signature),
 	/// An Sr25519 signature.
 	Sr25519(sr25519::Signature),
+	/// An ECDSA/SECP256k1 signature.
+	Ecdsa(ecdsa::Signature),
 }
 
 impl From<ed25519::Signature> for MultiSignature {
@@ -569,6 +666,12 @@ impl From<sr25519::Signature> for MultiSignature {
 	}
 }
 
+impl From<ecdsa::Signature> for MultiSignature {
+	fn from(x: ecdsa::Signature) -> Self {
+		MultiSignature::Ecdsa(x)
+	}
+}
+
 impl Default for MultiSignature {
 	fn default() -> Self {
 		MultiSignature::Ed25519(Default::default())
@@ -583,6 +686,8 @@ pub enum MultiSigner {
 	Ed25519(ed25519::Public),
 	/// An Sr25519 identity.
 	Sr25519(sr25519::Public),
+	/// An ECDSA/SECP256k1 identity (actually, the compressed public key).
+	Ecdsa(ecdsa::Public),
 }
 
 impl Default for MultiSigner {
@@ -604,6 +709,18 @@ impl AsRef<[u8]> for MultiSigner {
 		match *self {
 			MultiSigner::Ed25519(ref who) => who.as_ref(),
 			MultiSigner::Sr25519(ref who) => who.as_ref(),
+			MultiSigner::Ecdsa(ref who) => who.as_ref(),
+		}
+	}
+}
+
+impl traits::IdentifyAccount for MultiSigner {
+	type AccountId = AccountId32;
+	fn into_account(self) -> AccountId32 {
+		match self {
+			MultiSigner::Ed25519(who) => who.0.into(),
+			MultiSigner::Sr25519(who) => who.0.into(),
+			MultiSigner::Ecdsa(who) => runtime_io::blake2_256(&who.0[..]).into(),
 		}
 	}
 }
@@ -620,23 +737,39 @@ impl From<sr25519::Public> for MultiSigner {
 	}
 }
 
+impl From<ecdsa::Public> for MultiSigner {
+	fn from(x: ecdsa::Public) -> Self {
+		MultiSigner::Ecdsa(x)
+	}
+}
+
  #[cfg(feature = "std")]
 impl std::fmt::Display for MultiSigner {
 	fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
 		match *self {
 			MultiSigner::Ed25519(ref who) => write!(fmt, "ed25519: {}", who),
 			MultiSigner::Sr25519(ref who) => write!(fmt, "sr25519: {}", who),
+			MultiSigner::Ecdsa(ref who) => write!(fmt, "ecdsa: {}", who),
 		}
 	}
 }
 
 impl Verify for MultiSignature {
-	type Signer = MultiSigner;
-	fn verify<L: Lazy<[u8]>>(&self, msg: L, signer: &Self::Signer) -> bool {
-		match (self, signer) {
-			(MultiSignature::Ed25519(ref sig), &MultiSigner::Ed25519(ref who)) => sig.verify(msg, who),
-			(MultiSignature::Sr25519(ref sig), &MultiSigner::Sr25519(ref who)) => sig.verify(msg, who),
-			_ => false,
+	type Signer = AccountId32;
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &AccountId32) -> bool {
+		let who: [u8; 32] = signer.clone().into();
+		match self {
+			MultiSignature::Ed25519(ref sig) => sig.verify(msg, &ed25519::Public(who)),
+			MultiSignature::Sr25519(ref sig) => sig.verify(msg, &sr25519::Public(who)),
+			MultiSignature::Ecdsa(ref sig) => {
+				match runtime_io::secp256k1_ecdsa_recover_compressed(
+					sig.as_ref(),
+					&runtime_io::blake2_256(msg.get()),
+				) {
+					Ok(public) => runtime_io::blake2_256(&public[..]) == who,
+					Err(_) => false,
+				}
+			}
 		}
 	}
 }
@@ -666,23 +799,38 @@ impl From<ed25519::Signature> for AnySignature {
 	}
 }
 
-#[derive(Eq, PartialEq, Clone, Copy, Decode)]
+/// Reason why the dispatch of an applied extrinsic failed.
+#[derive(Eq, PartialEq, Clone, Default, Encode, Decode)]
 #[cfg_attr(feature = "std", derive(Debug, Serialize))]
-#[repr(u8)]
-/// Outcome of a valid extrinsic application. Capable of being sliced.
-pub enum ApplyOutcome {
-	/// Successful application (extrinsic reported no issue).
-	Success = 0,
-	/// Failed application (extrinsic was probably a no-op other than fees).
-	Fail = 1,
+pub struct DispatchFailure {
+	/// The error message returned by the dispatched call.
+	pub message: Vec<u8>,
 }
 
-impl codec::Encode for ApplyOutcome {
-	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
-		f(&[*self as u8])
+impl From<&'static str> for DispatchFailure {
+	fn from(message: &'static str) -> Self {
+		DispatchFailure { message: message.as_bytes().to_vec() }
 	}
 }
 
+#[cfg(feature = "std")]
+impl std::fmt::Display for DispatchFailure {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", String::from_utf8_lossy(&self.message))
+	}
+}
+
+#[derive(Eq, PartialEq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize))]
+/// Outcome of a valid extrinsic application.
+pub enum ApplyOutcome {
+	/// Successful application (extrinsic reported no issue).
+	Success,
+	/// Failed application: the extrinsic was included and paid its fees, but its dispatch
+	/// returned an error.
+	Fail(DispatchFailure),
+}
+
 #[derive(Eq, PartialEq, Clone, Copy, Decode)]
 #[cfg_attr(feature = "std", derive(Debug, Serialize))]
 #[repr(u8)]
@@ -696,8 +844,10 @@ pub enum ApplyError {
 	Future = 2,
 	/// Sending account had too low a balance.
 	CantPay = 3,
-	/// Block is full, no more extrinsics can be applied.
-	FullBlock = 255,
+	/// The block resources (weight or length) are exhausted, no more extrinsics can be applied.
+	///
+	/// The extrinsic may still be valid in a later block.
+	ExhaustsResources = 255,
 }
 
 impl codec::Encode for ApplyError {
@@ -881,7 +1031,34 @@ impl traits::Extrinsic for OpaqueExtrinsic {
 #[cfg(test)]
 mod tests {
 	use crate::codec::{Encode, Decode};
-	use super::{Perbill, Permill};
+	use crate::traits::Saturating;
+	use super::{Perbill, Permill, Percent, Perquintill, FixedU128};
+
+	#[test]
+	fn multi_signature_verifies_against_account_id() {
+		use substrate_primitives::{ecdsa, ed25519, sr25519, Pair};
+		use crate::traits::{IdentifyAccount, Verify};
+		use super::{MultiSignature, MultiSigner};
+
+		let msg = &b"test-message"[..];
+
+		let pair = ed25519::Pair::from_string("//Alice", None).unwrap();
+		let account = MultiSigner::from(pair.public()).into_account();
+		assert!(MultiSignature::from(pair.sign(msg)).verify(msg, &account));
+
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let account = MultiSigner::from(pair.public()).into_account();
+		assert!(MultiSignature::from(pair.sign(msg)).verify(msg, &account));
+		assert!(!MultiSignature::from(pair.sign(msg)).verify(&b"other"[..], &account));
+
+		let pair = ecdsa::Pair::from_string("//Alice", None).unwrap();
+		let account = MultiSigner::from(pair.public()).into_account();
+		assert!(MultiSignature::from(pair.sign(msg)).verify(msg, &account));
+		assert!(!MultiSignature::from(pair.sign(msg)).verify(
+			msg,
+			&MultiSigner::from(ecdsa::Pair::from_string("//Bob", None).unwrap().public()).into_account(),
+		));
+	}
 
 	macro_rules! per_thing_upper_test {
 		($num_type:tt, $per:tt) => {
@@ -962,7 +1139,6 @@ mod tests {
 
 	#[test]
 	fn per_things_should_work() {
-		use super::{Perbill, Permill};
 		use primitive_types::U256;
 
 		per_thing_upper_test!(u32, Perbill);
@@ -973,6 +1149,51 @@ mod tests {
 		per_thing_upper_test!(u64, Permill);
 		per_thing_upper_test!(u128, Permill);
 
+		per_thing_upper_test!(u32, Percent);
+		per_thing_upper_test!(u64, Percent);
+		per_thing_upper_test!(u128, Percent);
+
+		per_thing_upper_test!(u64, Perquintill);
+		per_thing_upper_test!(u128, Perquintill);
+	}
+
+	#[test]
+	fn per_things_mul_ceil_rounds_up() {
+		assert_eq!(Percent::from_percent(50).mul_floor(5u32), 2);
+		assert_eq!(Percent::from_percent(50).mul_ceil(5u32), 3);
+		assert_eq!(Percent::from_percent(50).mul_ceil(4u32), 2);
+		assert_eq!(Perbill::from_parts(1).mul_ceil(1u64), 1);
+		assert_eq!(Perbill::one().mul_ceil(u64::max_value()), u64::max_value());
+		assert_eq!(Perquintill::zero().mul_ceil(u128::max_value()), 0);
+	}
+
+	#[test]
+	fn per_things_saturate() {
+		assert_eq!(Percent::from_percent(60).saturating_add(Percent::from_percent(60)), Percent::one());
+		assert_eq!(Permill::from_percent(10).saturating_sub(Permill::from_percent(20)), Permill::zero());
+		assert_eq!(
+			Perbill::from_percent(50).saturating_mul(Perbill::from_percent(50)),
+			Perbill::from_percent(25),
+		);
+		assert_eq!(Percent::from_rational_approximation(1u32, 0), Percent::one());
+		assert_eq!(Perbill::from_fraction(1.5), Perbill::one());
+	}
+
+	#[test]
+	fn fixed_u128_works() {
+		assert_eq!(FixedU128::from_natural(5).saturated_multiply(3), 15);
+		assert_eq!(FixedU128::from_rational(1, 3).saturated_multiply(9), 2);
+		assert_eq!(FixedU128::from_rational(3, 2).saturated_multiply(u128::max_value()), u128::max_value());
+		assert_eq!(FixedU128::from_rational(5, 0), FixedU128::from_natural(5));
+		assert_eq!(
+			FixedU128::from_rational(1, 2).saturating_mul(FixedU128::from_natural(3)),
+			FixedU128::from_rational(3, 2),
+		);
+		assert_eq!(
+			FixedU128::from_natural(1).saturating_sub(FixedU128::from_natural(2)),
+			FixedU128::from_natural(0),
+		);
+		assert_eq!(FixedU128::from_natural(u128::max_value()).deconstruct(), u128::max_value());
 	}
 
 	#[test]
@@ -253,6 +253,8 @@ impl<Origin, Call, Extra> Applyable for TestXt<Call, Extra> where
 
 	fn sender(&self) -> Option<&u64> { self.0.as_ref().map(|x| &x.0) }
 
+	fn call(&self) -> &Call { &self.1 }
+
 	/// Checks to see if this is a valid *transaction*. It returns information on it if so.
 	fn validate<U: ValidateUnsigned<Call=Self::Call>>(&self,
 		_info: DispatchInfo,
@@ -263,9 +265,10 @@ impl<Origin, Call, Extra> Applyable for TestXt<Call, Extra> where
 
 	/// Executes all necessary logic needed prior to dispatch and deconstructs into function call,
 	/// index and sender.
-	fn dispatch(self,
+	fn dispatch<F: FnOnce(&Self::Call) -> bool>(self,
 		info: DispatchInfo,
 		len: usize,
+		filter: F,
 	) -> Result<DispatchResult, DispatchError> {
 		let maybe_who = if let Some((who, extra)) = self.0 {
 			Extra::pre_dispatch(extra, &who, info, len)?;
@@ -274,6 +277,9 @@ impl<Origin, Call, Extra> Applyable for TestXt<Call, Extra> where
 			Extra::pre_dispatch_unsigned(info, len)?;
 			None
 		};
+		if !filter(&self.1) {
+			return Ok(Err(crate::CALL_FILTERED));
+		}
 		Ok(self.1.dispatch(maybe_who.into()))
 	}
 }
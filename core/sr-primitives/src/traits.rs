@@ -21,11 +21,11 @@ use rstd::{self, result, marker::PhantomData, convert::{TryFrom, TryInto}};
 use runtime_io;
 #[cfg(feature = "std")] use std::fmt::{Debug, Display};
 #[cfg(feature = "std")] use serde::{Serialize, Deserialize, de::DeserializeOwned};
-use substrate_primitives::{self, Hasher, Blake2Hasher};
+use substrate_primitives::{self, Hasher, Blake2Hasher, crypto::key_types};
 use crate::codec::{Codec, Encode, Decode, HasCompact};
 use crate::transaction_validity::{ValidTransaction, TransactionValidity};
 use crate::generic::{Digest, DigestItem};
-use crate::weights::DispatchInfo;
+use crate::weights::{DispatchInfo, Weight};
 pub use substrate_primitives::crypto::TypedKey;
 pub use integer_sqrt::IntegerSquareRoot;
 pub use num_traits::{
@@ -60,17 +60,85 @@ pub trait Verify {
 impl Verify for substrate_primitives::ed25519::Signature {
 	type Signer = substrate_primitives::ed25519::Public;
 	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &Self::Signer) -> bool {
-		runtime_io::ed25519_verify(self.as_ref(), msg.get(), signer)
+		runtime_io::sig_batch_verify(key_types::ED25519, &self.0, msg.get(), &signer.0)
 	}
 }
 
 impl Verify for substrate_primitives::sr25519::Signature {
 	type Signer = substrate_primitives::sr25519::Public;
 	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &Self::Signer) -> bool {
-		runtime_io::sr25519_verify(self.as_ref(), msg.get(), signer)
+		runtime_io::sig_batch_verify(key_types::SR25519, &self.0, msg.get(), &signer.0)
 	}
 }
 
+impl Verify for substrate_primitives::ecdsa::Signature {
+	type Signer = substrate_primitives::ecdsa::Public;
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &Self::Signer) -> bool {
+		match runtime_io::secp256k1_ecdsa_recover_compressed(
+			self.as_ref(),
+			&runtime_io::blake2_256(msg.get()),
+		) {
+			Ok(public) => &public[..] == &signer.0[..],
+			Err(_) => false,
+		}
+	}
+}
+
+impl Verify for substrate_primitives::bls::Signature {
+	type Signer = substrate_primitives::bls::Public;
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &Self::Signer) -> bool {
+		runtime_io::bls_verify(&self.0, msg.get(), &signer.0)
+	}
+}
+
+/// Means of verifying a single signature that aggregates the signatures of several signers,
+/// each over their own message.
+///
+/// The messages must be distinct: an aggregate over a message signed more than once is
+/// rejected. Signers of a common statement should each sign it with something that tells them
+/// apart, e.g. their index or public key.
+pub trait VerifyAggregate {
+	/// Type of the signers.
+	type Signer;
+	/// Verify an aggregated signature. Return `true` if it is valid for all of the given
+	/// messages and their signers, which must all be distinct messages.
+	fn verify_aggregate(&self, signed: &[(&[u8], Self::Signer)]) -> bool;
+}
+
+impl VerifyAggregate for substrate_primitives::bls::Signature {
+	type Signer = substrate_primitives::bls::Public;
+	fn verify_aggregate(&self, signed: &[(&[u8], Self::Signer)]) -> bool {
+		let messages = signed.iter().map(|(message, _)| *message).collect::<Vec<_>>();
+		let pubkeys = signed.iter().map(|(_, signer)| signer.0).collect::<Vec<_>>();
+		runtime_io::bls_aggregate_verify(&self.0, &messages, &pubkeys)
+	}
+}
+
+/// Some type that is able to be collapsed into an account ID. It is not possible to recreate the
+/// original value from the account ID.
+pub trait IdentifyAccount {
+	/// The account ID that this can be transformed into.
+	type AccountId;
+	/// Transform into an account.
+	fn into_account(self) -> Self::AccountId;
+}
+
+impl IdentifyAccount for substrate_primitives::ed25519::Public {
+	type AccountId = Self;
+	fn into_account(self) -> Self { self }
+}
+
+impl IdentifyAccount for substrate_primitives::sr25519::Public {
+	type AccountId = Self;
+	fn into_account(self) -> Self { self }
+}
+
+/// ECDSA accounts are identified by their compressed public key.
+impl IdentifyAccount for substrate_primitives::ecdsa::Public {
+	type AccountId = Self;
+	fn into_account(self) -> Self { self }
+}
+
 /// Some sort of check on the origin is performed by this object.
 pub trait EnsureOrigin<OuterOrigin> {
 	/// A return type.
@@ -350,11 +418,35 @@ impl<N> OnFinalize<N> for () {}
 /// for your module when the block is beginning (right before the first extrinsic is executed).
 pub trait OnInitialize<BlockNumber> {
 	/// The block is being initialized. Implement to have something happen.
-	fn on_initialize(_n: BlockNumber) {}
+	///
+	/// Return the weight consumed, which is accounted in the block weight.
+	fn on_initialize(_n: BlockNumber) -> Weight { 0 }
 }
 
 impl<N> OnInitialize<N> for () {}
 
+/// The block idle trait. Implementing this lets you use the weight left over at the end of a
+/// block (after all extrinsics have been applied and before `on_finalize`).
+pub trait OnIdle<BlockNumber> {
+	/// The block is being finalized and `remaining_weight` is still available.
+	///
+	/// Return the weight consumed, which must not exceed `remaining_weight`.
+	fn on_idle(_n: BlockNumber, _remaining_weight: Weight) -> Weight { 0 }
+}
+
+impl<N> OnIdle<N> for () {}
+
+/// The runtime upgrade trait. Implementing this lets you migrate the storage of your module when
+/// a new runtime is enacted, before the first block it executes is initialized.
+pub trait OnRuntimeUpgrade {
+	/// A new runtime was enacted. Implement to have something happen.
+	///
+	/// Return the weight consumed, which is accounted in the block weight.
+	fn on_runtime_upgrade() -> Weight { 0 }
+}
+
+impl OnRuntimeUpgrade for () {}
+
 /// Off-chain computation trait.
 ///
 /// Implementing this trait on a module allows you to perform long-running tasks
@@ -390,8 +482,32 @@ macro_rules! tuple_impl {
 			Number: Copy,
 			$($direct: OnInitialize<Number>),+
 		> OnInitialize<Number> for ($($direct),+,) {
-			fn on_initialize(n: Number) {
-				$($direct::on_initialize(n);)+
+			fn on_initialize(n: Number) -> Weight {
+				let mut weight: Weight = 0;
+				$(weight = weight.saturating_add($direct::on_initialize(n));)+
+				weight
+			}
+		}
+		impl<
+			Number: Copy,
+			$($direct: OnIdle<Number>),+
+		> OnIdle<Number> for ($($direct),+,) {
+			fn on_idle(n: Number, remaining_weight: Weight) -> Weight {
+				let mut weight: Weight = 0;
+				$(
+					let left = remaining_weight.saturating_sub(weight);
+					weight = weight.saturating_add($direct::on_idle(n, left));
+				)+
+				weight
+			}
+		}
+		impl<
+			$($direct: OnRuntimeUpgrade),+
+		> OnRuntimeUpgrade for ($($direct),+,) {
+			fn on_runtime_upgrade() -> Weight {
+				let mut weight: Weight = 0;
+				$(weight = weight.saturating_add($direct::on_runtime_upgrade());)+
+				weight
 			}
 		}
 		impl<
@@ -786,6 +902,9 @@ pub enum DispatchError {
 	/// General error to do with the transaction's proofs (e.g. signature).
 	BadProof,
 
+	/// General error to do with the exhaustion of block resources (e.g. weight or length).
+	ExhaustsResources,
+
 /*	/// General error to do with actually executing the dispatched logic.
 	User(&'static str),*/
 }
@@ -799,6 +918,7 @@ impl From<DispatchError> for i8 {
 			DispatchError::Stale => -67,
 			DispatchError::Future => -68,
 			DispatchError::BadProof => -69,
+			DispatchError::ExhaustsResources => -70,
 		}
 	}
 }
@@ -957,6 +1077,9 @@ pub trait Applyable: Sized + Send + Sync {
 	/// Returns a reference to the sender if any.
 	fn sender(&self) -> Option<&Self::AccountId>;
 
+	/// Returns a reference to the call that is dispatched by this extrinsic.
+	fn call(&self) -> &Self::Call;
+
 	/// Checks to see if this is a valid *transaction*. It returns information on it if so.
 	fn validate<V: ValidateUnsigned<Call=Self::Call>>(&self,
 		info: DispatchInfo,
@@ -964,10 +1087,12 @@ pub trait Applyable: Sized + Send + Sync {
 	) -> TransactionValidity;
 
 	/// Executes all necessary logic needed prior to dispatch and deconstructs into function call,
-	/// index and sender.
-	fn dispatch(self,
+	/// index and sender. The call is only dispatched if `filter` allows it, otherwise it fails
+	/// with `CALL_FILTERED` once the logic prior to dispatch, e.g. paying fees, was executed.
+	fn dispatch<F: FnOnce(&Self::Call) -> bool>(self,
 		info: DispatchInfo,
 		len: usize,
+		filter: F,
 	) -> Result<DispatchResult, DispatchError>;
 }
 
@@ -1057,6 +1182,19 @@ pub trait OpaqueKeys: Clone {
 	fn ownership_proof_is_valid(&self, _proof: &[u8]) -> bool { true }
 }
 
+/// Generate a new key of the given key type in the keystore of the current externalities,
+/// returning the raw public key.
+///
+/// The crypto of the key is derived from the key type. An optional `seed` may be given, in which
+/// case the key is derived from it deterministically.
+pub fn generate_key(id: super::KeyTypeId, seed: Option<&str>) -> Vec<u8> {
+	match id {
+		super::key_types::ED25519 => runtime_io::ed25519_generate(id, seed).to_vec(),
+		super::key_types::SR25519 => runtime_io::sr25519_generate(id, seed).to_vec(),
+		_ => panic!("Key generation is not supported for the given key type"),
+	}
+}
+
 struct TrailingZeroInput<'a>(&'a [u8]);
 impl<'a> codec::Input for TrailingZeroInput<'a> {
 	fn read(&mut self, into: &mut [u8]) -> usize {
@@ -1145,6 +1283,94 @@ mod tests {
 
 	type AccountId = u64;
 
+	#[test]
+	fn ecdsa_signature_verifies() {
+		use substrate_primitives::{ecdsa, Pair};
+		use super::{IdentifyAccount, Verify};
+
+		let pair = ecdsa::Pair::from_string("//Alice", None).unwrap();
+		let signature = pair.sign(b"hello");
+		assert!(signature.verify(&b"hello"[..], &pair.public()));
+		assert!(!signature.verify(&b"hell"[..], &pair.public()));
+		assert!(!signature.verify(&b"hello"[..], &ecdsa::Pair::from_string("//Bob", None).unwrap().public()));
+		assert_eq!(pair.public().into_account(), pair.public());
+	}
+
+	#[test]
+	fn signatures_are_queued_while_batch_verifying() {
+		use substrate_primitives::{ed25519, sr25519, Pair};
+		use super::Verify;
+
+		let ed = ed25519::Pair::from_string("//Alice", None).unwrap();
+		let sr = sr25519::Pair::from_string("//Alice", None).unwrap();
+		assert!(!ed.sign(b"hello").verify(&b"hell"[..], &ed.public()));
+
+		let mut ext = runtime_io::TestExternalities::<super::Blake2Hasher>::default();
+		runtime_io::with_externalities(&mut ext, || {
+			runtime_io::start_batch_verify();
+			assert!(ed.sign(b"hello").verify(&b"hello"[..], &ed.public()));
+			assert!(sr.sign(b"hello").verify(&b"hello"[..], &sr.public()));
+			assert!(runtime_io::finish_batch_verify());
+
+			runtime_io::start_batch_verify();
+			assert!(sr.sign(b"hello").verify(&b"hell"[..], &sr.public()));
+			assert!(!runtime_io::finish_batch_verify());
+		});
+	}
+
+	#[test]
+	fn batch_does_not_outlive_a_panicking_call() {
+		use std::panic::{catch_unwind, AssertUnwindSafe};
+		use substrate_primitives::{sr25519, Pair};
+		use super::Verify;
+
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let mut ext = runtime_io::TestExternalities::<super::Blake2Hasher>::default();
+
+		let result = catch_unwind(AssertUnwindSafe(|| runtime_io::with_externalities(&mut ext, || {
+			runtime_io::start_batch_verify();
+			panic!("runtime panicked");
+		})));
+		assert!(result.is_err());
+
+		// No batch is left open: forged signatures are rejected right away.
+		runtime_io::with_externalities(&mut ext, || {
+			assert!(!pair.sign(b"hello").verify(&b"hell"[..], &pair.public()));
+		});
+
+		// Starting a batch twice is an error.
+		let result = catch_unwind(AssertUnwindSafe(|| runtime_io::with_externalities(&mut ext, || {
+			runtime_io::start_batch_verify();
+			runtime_io::start_batch_verify();
+		})));
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn bls_aggregate_signature_verifies() {
+		use substrate_primitives::{bls, Pair};
+		use super::{Verify, VerifyAggregate};
+
+		let alice = bls::Pair::from_string("//Alice", None).unwrap();
+		let bob = bls::Pair::from_string("//Bob", None).unwrap();
+		assert!(alice.sign(b"hello").verify(&b"hello"[..], &alice.public()));
+		assert!(!alice.sign(b"hello").verify(&b"hello"[..], &bob.public()));
+
+		let aggregate = bls::Signature::aggregate(&[alice.sign(b"alice"), bob.sign(b"bob")]).unwrap();
+		assert!(aggregate.verify_aggregate(&[(&b"alice"[..], alice.public()), (&b"bob"[..], bob.public())]));
+		assert!(!aggregate.verify_aggregate(&[(&b"alice"[..], alice.public())]));
+
+		// signatures of a repeated message are not accepted, even when every signer signed it.
+		let repeated = bls::Signature::aggregate(&[alice.sign(b"vote"), bob.sign(b"vote")]).unwrap();
+		assert!(!repeated.verify_aggregate(&[(&b"vote"[..], alice.public()), (&b"vote"[..], bob.public())]));
+
+		// the same statement signed together with the signer is accepted.
+		let statement = |signer: &bls::Public| (&b"vote"[..], signer).encode();
+		let (alice_vote, bob_vote) = (statement(&alice.public()), statement(&bob.public()));
+		let distinct = bls::Signature::aggregate(&[alice.sign(&alice_vote), bob.sign(&bob_vote)]).unwrap();
+		assert!(distinct.verify_aggregate(&[(&alice_vote[..], alice.public()), (&bob_vote[..], bob.public())]));
+	}
+
 	#[test]
 	fn into_account_should_work() {
 		let r: AccountId = U32Value::into_account(&U32Value(0xdeadbeef));
@@ -1255,5 +1481,37 @@ macro_rules! impl_opaque_keys {
 				}
 			}
 		}
+
+		impl $name {
+			/// Generate a set of keys, optionally using the given seed.
+			///
+			/// The generated keys are stored in the keystore of the current externalities.
+			///
+			/// Returns the concatenated SCALE encoded public keys.
+			pub fn generate(seed: Option<&str>) -> $crate::rstd::vec::Vec<u8> {
+				let keys = Self {
+					$(
+						$field: $crate::codec::Decode::decode(
+							&mut &$crate::traits::generate_key($key_id, seed)[..]
+						).expect("Generated keys are encoded in the form of the key type; qed"),
+					)*
+				};
+				$crate::codec::Encode::encode(&keys)
+			}
+
+			/// Decode the given encoded session keys into the raw public keys and their key types.
+			///
+			/// Returns `None` if the keys could not be decoded.
+			pub fn decode_into_raw_public_keys(
+				encoded: &[u8],
+			) -> Option<$crate::rstd::vec::Vec<($crate::rstd::vec::Vec<u8>, $crate::KeyTypeId)>> {
+				let keys: Self = $crate::codec::Decode::decode(&mut &encoded[..])?;
+				let mut raw = $crate::rstd::vec::Vec::new();
+				$(
+					raw.push((keys.$field.as_ref().to_vec(), $key_id));
+				)*
+				Some(raw)
+			}
+		}
 	};
 }
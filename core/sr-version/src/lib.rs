@@ -138,6 +138,11 @@ impl RuntimeVersion {
 			s == &A::ID && pred(*v)
 		})
 	}
+
+	/// Returns the version of the given api, if it is implemented.
+	pub fn api_version(&self, id: &ApiId) -> Option<u32> {
+		self.apis.iter().find_map(|(s, v)| if s == id { Some(*v) } else { None })
+	}
 }
 
 #[cfg(feature = "std")]
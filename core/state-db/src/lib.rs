@@ -28,6 +28,10 @@
 //! # Pruning.
 //! See `RefWindow` for pruning algorithm details. `StateDb` prunes on each canonicalization until pruning
 //! constraints are satisfied.
+//!
+//! The pruning mode is recorded in the database when it is first opened. Opening an existing
+//! database with an incompatible mode (e.g. an archive database in pruning mode) is an error,
+//! since the history that the requested mode expects may already have been discarded.
 
 mod noncanonical;
 mod pruning;
@@ -42,6 +46,11 @@ use noncanonical::NonCanonicalOverlay;
 use pruning::RefWindow;
 use log::trace;
 
+const PRUNING_MODE: &[u8] = b"mode";
+const PRUNING_MODE_ARCHIVE: &[u8] = b"archive";
+const PRUNING_MODE_ARCHIVE_CANON: &[u8] = b"archive_canonical";
+const PRUNING_MODE_CONSTRAINED: &[u8] = b"constrained";
+
 /// Database value type.
 pub type DBValue = Vec<u8>;
 
@@ -80,6 +89,8 @@ pub enum Error<E: fmt::Debug> {
 	InvalidParent,
 	/// Canonicalization would discard pinned state.
 	DiscardingPinned,
+	/// The database was created with a pruning mode incompatible with the requested one.
+	InvalidPruningMode(String),
 }
 
 impl<E: fmt::Debug> fmt::Debug for Error<E> {
@@ -91,6 +102,7 @@ impl<E: fmt::Debug> fmt::Debug for Error<E> {
 			Error::InvalidBlockNumber => write!(f, "Trying to insert block with invalid number"),
 			Error::InvalidParent => write!(f, "Trying to insert block with unknown parent"),
 			Error::DiscardingPinned => write!(f, "Trying to discard pinned state"),
+			Error::InvalidPruningMode(e) => write!(f, "Expected pruning mode: {}", e),
 		}
 	}
 }
@@ -151,6 +163,15 @@ impl PruningMode {
 		}
 	}
 
+	/// Returns the identifier of the pruning mode as stored in the database.
+	pub fn id(&self) -> &'static [u8] {
+		match self {
+			PruningMode::ArchiveAll => PRUNING_MODE_ARCHIVE,
+			PruningMode::ArchiveCanonical => PRUNING_MODE_ARCHIVE_CANON,
+			PruningMode::Constrained(_) => PRUNING_MODE_CONSTRAINED,
+		}
+	}
+
 }
 
 impl Default for PruningMode {
@@ -176,6 +197,11 @@ struct StateDbSync<BlockHash: Hash, Key: Hash> {
 impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 	pub fn new<D: MetaDb>(mode: PruningMode, db: &D) -> Result<StateDbSync<BlockHash, Key>, Error<D::Error>> {
 		trace!(target: "state-db", "StateDb settings: {:?}", mode);
+		if let Some(stored) = db.get_meta(PRUNING_MODE).map_err(|e| Error::Db(e))? {
+			if stored.as_slice() != mode.id() {
+				return Err(Error::InvalidPruningMode(String::from_utf8_lossy(&stored).into_owned()));
+			}
+		}
 		let non_canonical: NonCanonicalOverlay<BlockHash, Key> = NonCanonicalOverlay::new(db)?;
 		let pruning: Option<RefWindow<BlockHash, Key>> = match mode {
 			PruningMode::Constrained(Constraints {
@@ -349,11 +375,20 @@ pub struct StateDb<BlockHash: Hash, Key: Hash> {
 }
 
 impl<BlockHash: Hash, Key: Hash> StateDb<BlockHash, Key> {
-	/// Creates a new instance. Does not expect any metadata in the database.
-	pub fn new<D: MetaDb>(mode: PruningMode, db: &D) -> Result<StateDb<BlockHash, Key>, Error<D::Error>> {
-		Ok(StateDb {
+	/// Creates a new instance.
+	///
+	/// Fails if the database was previously opened with an incompatible pruning mode. The returned
+	/// commit records the pruning mode and must be written to the database.
+	pub fn new<D: MetaDb>(
+		mode: PruningMode,
+		db: &D,
+	) -> Result<(CommitSet<Key>, StateDb<BlockHash, Key>), Error<D::Error>> {
+		let mut commit = CommitSet::default();
+		commit.meta.inserted.push((PRUNING_MODE.to_vec(), mode.id().to_vec()));
+		let state_db = StateDb {
 			db: RwLock::new(StateDbSync::new(mode, db)?)
-		})
+		};
+		Ok((commit, state_db))
 	}
 
 	/// Add a new non-canonical block.
@@ -420,7 +455,8 @@ mod tests {
 
 	fn make_test_db(settings: PruningMode) -> (TestDb, StateDb<H256, H256>) {
 		let mut db = make_db(&[91, 921, 922, 93, 94]);
-		let state_db = StateDb::new(settings, &db).unwrap();
+		let (commit, state_db) = StateDb::new(settings, &db).unwrap();
+		db.commit(&commit);
 
 		db.commit(
 			&state_db
@@ -531,4 +567,25 @@ mod tests {
 		assert!(sdb.is_pruned(&H256::from_low_u64_be(22), 2));
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
+
+	#[test]
+	fn detects_incompatible_mode() {
+		let mut db = make_db(&[]);
+		let (commit, _) = StateDb::<H256, H256>::new(PruningMode::ArchiveAll, &db).unwrap();
+		db.commit(&commit);
+
+		assert!(StateDb::<H256, H256>::new(PruningMode::ArchiveAll, &db).is_ok());
+		assert!(StateDb::<H256, H256>::new(PruningMode::ArchiveCanonical, &db).is_err());
+		assert!(StateDb::<H256, H256>::new(PruningMode::keep_blocks(10), &db).is_err());
+	}
+
+	#[test]
+	fn constrained_windows_are_compatible() {
+		let mut db = make_db(&[]);
+		let (commit, _) = StateDb::<H256, H256>::new(PruningMode::keep_blocks(256), &db).unwrap();
+		db.commit(&commit);
+
+		assert!(StateDb::<H256, H256>::new(PruningMode::keep_blocks(10), &db).is_ok());
+		assert!(StateDb::<H256, H256>::new(PruningMode::ArchiveAll, &db).is_err());
+	}
 }
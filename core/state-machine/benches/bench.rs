@@ -0,0 +1,57 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks of bulk trie imports, such as genesis or state sync.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use primitives::{Blake2Hasher, blake2_256};
+use substrate_state_machine::{Backend, TrieBackend, TrieDBMut, TrieMut, MemoryDB};
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
+
+/// Random-looking key-value pairs, in the order they would come out of a hash map.
+fn pairs(count: u32) -> Vec<(Vec<u8>, Vec<u8>)> {
+	(0..count).map(|i| (blake2_256(&i.to_le_bytes()).to_vec(), vec![i as u8; 32])).collect()
+}
+
+fn empty_backend() -> TrieBackend<MemoryDB<Blake2Hasher>, Blake2Hasher> {
+	let mut mdb = MemoryDB::default();
+	let mut root = Default::default();
+	TrieDBMut::<Blake2Hasher>::new(&mut mdb, &mut root);
+	TrieBackend::new(mdb, root)
+}
+
+fn benchmark(c: &mut Criterion) {
+	for &count in &[1_000u32, 10_000] {
+		let input = pairs(count);
+		c.bench_function(&format!("unsorted trie insertion of {} keys", count), move |b| b.iter(|| {
+			let mut mdb = MemoryDB::<Blake2Hasher>::default();
+			let mut root = Default::default();
+			let mut trie = TrieDBMut::<Blake2Hasher>::new(&mut mdb, &mut root);
+			for (key, value) in &input {
+				trie.insert(key, value).unwrap();
+			}
+			*trie.root()
+		}));
+
+		let input = pairs(count);
+		let backend = empty_backend();
+		c.bench_function(&format!("storage root of {} keys", count), move |b| b.iter(|| {
+			backend.storage_root(input.iter().map(|(k, v)| (k.clone(), Some(v.clone()))))
+		}));
+	}
+}
@@ -21,7 +21,7 @@ use std::iter::FromIterator;
 use crate::backend::{Backend, InMemory};
 use hash_db::Hasher;
 use trie::trie_root;
-use primitives::offchain;
+use primitives::{offchain, traits::KeyStorePtr};
 use primitives::storage::well_known_keys::{HEAP_PAGES, is_child_storage_key};
 use parity_codec::Encode;
 use super::{ChildStorageKey, Externalities};
@@ -32,6 +32,8 @@ use log::warn;
 pub struct BasicExternalities {
 	top: HashMap<Vec<u8>, Vec<u8>>,
 	children: HashMap<Vec<u8>, HashMap<Vec<u8>, Vec<u8>>>,
+	/// Snapshots of the storage taken at the start of each open transaction.
+	transactions: Vec<(HashMap<Vec<u8>, Vec<u8>>, HashMap<Vec<u8>, HashMap<Vec<u8>, Vec<u8>>>)>,
 }
 
 impl BasicExternalities {
@@ -49,6 +51,7 @@ impl BasicExternalities {
 		BasicExternalities {
 			top,
 			children,
+			transactions: Vec::new(),
 		}
 	}
 
@@ -89,6 +92,7 @@ impl From<HashMap<Vec<u8>, Vec<u8>>> for BasicExternalities {
 		BasicExternalities {
 			top: hashmap,
 			children: Default::default(),
+			transactions: Vec::new(),
 		}
 	}
 }
@@ -98,6 +102,10 @@ impl<H: Hasher> Externalities<H> for BasicExternalities where H::Out: Ord {
 		self.top.get(key).cloned()
 	}
 
+	fn next_storage_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.top.keys().filter(|k| &k[..] > key).min().cloned()
+	}
+
 	fn original_storage(&self, key: &[u8]) -> Option<Vec<u8>> {
 		Externalities::<H>::storage(self, key)
 	}
@@ -148,6 +156,21 @@ impl<H: Hasher> Externalities<H> for BasicExternalities where H::Out: Ord {
 		self.top.retain(|key, _| !key.starts_with(prefix));
 	}
 
+	fn storage_start_transaction(&mut self) {
+		self.transactions.push((self.top.clone(), self.children.clone()));
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		let (top, children) = self.transactions.pop().ok_or(())?;
+		self.top = top;
+		self.children = children;
+		Ok(())
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.transactions.pop().map(|_| ()).ok_or(())
+	}
+
 	fn chain_id(&self) -> u64 { 42 }
 
 	fn storage_root(&mut self) -> H::Out {
@@ -172,6 +195,11 @@ impl<H: Hasher> Externalities<H> for BasicExternalities where H::Out: Ord {
 		warn!("Call to non-existent out offchain externalities set.");
 		None
 	}
+
+	fn keystore(&self) -> Option<KeyStorePtr> {
+		warn!("Call to non-existent keystore.");
+		None
+	}
 }
 
 #[cfg(test)]
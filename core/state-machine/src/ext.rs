@@ -22,7 +22,7 @@ use crate::backend::Backend;
 use crate::changes_trie::{Storage as ChangesTrieStorage, build_changes_trie};
 use crate::{Externalities, OverlayedChanges, ChildStorageKey};
 use hash_db::Hasher;
-use primitives::offchain;
+use primitives::{offchain, traits::KeyStorePtr};
 use primitives::storage::well_known_keys::is_child_storage_key;
 use trie::{MemoryDB, default_child_trie_root};
 
@@ -83,6 +83,8 @@ where
 	///
 	/// If None, some methods from the trait might not be supported.
 	offchain_externalities: Option<&'a mut O>,
+	/// The keystore available to the runtime.
+	keystore: Option<KeyStorePtr>,
 	/// Dummy usage of N arg.
 	_phantom: ::std::marker::PhantomData<N>,
 }
@@ -110,10 +112,17 @@ where
 			changes_trie_storage,
 			changes_trie_transaction: None,
 			offchain_externalities,
+			keystore: None,
 			_phantom: Default::default(),
 		}
 	}
 
+	/// Make the given keystore available to the runtime.
+	pub fn with_keystore(mut self, keystore: Option<KeyStorePtr>) -> Self {
+		self.keystore = keystore;
+		self
+	}
+
 	/// Get the transaction necessary to update the backend.
 	pub fn transaction(mut self) -> ((B::Transaction, H::Out), Option<MemoryDB<H>>) {
 		let _ = self.storage_root();
@@ -131,6 +140,23 @@ where
 		)
 	}
 
+	/// Get the transactions necessary to apply changes that have already been recorded in the
+	/// overlay, e.g. while authoring a block, without executing anything.
+	///
+	/// Returns the storage transaction and root, along with the changes trie transaction and
+	/// root if changes tries are enabled.
+	pub fn storage_changes(
+		mut self,
+		parent_hash: H::Out,
+	) -> Result<((B::Transaction, H::Out), Option<(MemoryDB<H>, H::Out)>), ()> {
+		let changes_trie_root = self.storage_changes_root(parent_hash)?;
+		let (storage_transaction, changes_trie_transaction) = self.transaction();
+		Ok((
+			storage_transaction,
+			changes_trie_transaction.and_then(|tx| changes_trie_root.map(|root| (tx, root))),
+		))
+	}
+
 	/// Invalidates the currently cached storage root and the db transaction.
 	///
 	/// Called when there are changes that likely will invalidate the storage root.
@@ -174,14 +200,38 @@ where
 {
 	fn storage(&self, key: &[u8]) -> Option<Vec<u8>> {
 		let _guard = panic_handler::AbortGuard::new(true);
-		self.overlay.storage(key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
-			self.backend.storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL))
+		let result = self.overlay.storage(key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
+			self.backend.storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL));
+		substrate_tracing::storage_event("get", None, key, result.as_ref().map(|value| &value[..]));
+		result
 	}
 
 	fn storage_hash(&self, key: &[u8]) -> Option<H::Out> {
 		let _guard = panic_handler::AbortGuard::new(true);
-		self.overlay.storage(key).map(|x| x.map(|x| H::hash(x))).unwrap_or_else(||
-			self.backend.storage_hash(key).expect(EXT_NOT_ALLOWED_TO_FAIL))
+		let result = self.overlay.storage(key).map(|x| x.map(|x| H::hash(x))).unwrap_or_else(||
+			self.backend.storage_hash(key).expect(EXT_NOT_ALLOWED_TO_FAIL));
+		substrate_tracing::storage_event("hash", None, key, result.as_ref().map(|hash| hash.as_ref()));
+		result
+	}
+
+	fn next_storage_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		let _guard = panic_handler::AbortGuard::new(true);
+		let mut key = key.to_vec();
+		loop {
+			let next_backend_key = self.backend.next_storage_key(&key).expect(EXT_NOT_ALLOWED_TO_FAIL);
+			let next_overlay_key_change = self.overlay.next_storage_key_change(&key);
+
+			match (next_backend_key, next_overlay_key_change) {
+				(Some(backend_key), Some(overlay_key)) if &backend_key[..] < overlay_key.0 => return Some(backend_key),
+				(backend_key, None) => return backend_key,
+				(_, Some(overlay_key)) => if overlay_key.1.value.is_some() {
+					return Some(overlay_key.0.to_vec());
+				} else {
+					// The key has been deleted in the overlay, continue the search after it.
+					key = overlay_key.0.to_vec();
+				},
+			}
+		}
 	}
 
 	fn original_storage(&self, key: &[u8]) -> Option<Vec<u8>> {
@@ -196,12 +246,20 @@ where
 
 	fn child_storage(&self, storage_key: ChildStorageKey<H>, key: &[u8]) -> Option<Vec<u8>> {
 		let _guard = panic_handler::AbortGuard::new(true);
-		self.overlay.child_storage(storage_key.as_ref(), key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
-			self.backend.child_storage(storage_key.as_ref(), key).expect(EXT_NOT_ALLOWED_TO_FAIL))
+		let result = self.overlay.child_storage(storage_key.as_ref(), key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
+			self.backend.child_storage(storage_key.as_ref(), key).expect(EXT_NOT_ALLOWED_TO_FAIL));
+		substrate_tracing::storage_event(
+			"child_get",
+			Some(storage_key.as_ref()),
+			key,
+			result.as_ref().map(|value| &value[..]),
+		);
+		result
 	}
 
 	fn exists_storage(&self, key: &[u8]) -> bool {
 		let _guard = panic_handler::AbortGuard::new(true);
+		substrate_tracing::storage_event("exists", None, key, None);
 		match self.overlay.storage(key) {
 			Some(x) => x.is_some(),
 			_ => self.backend.exists_storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL),
@@ -224,6 +282,8 @@ where
 			return;
 		}
 
+		let name = if value.is_some() { "put" } else { "clear" };
+		substrate_tracing::storage_event(name, None, &key, value.as_ref().map(|value| &value[..]));
 		self.mark_dirty();
 		self.overlay.set_storage(key, value);
 	}
@@ -231,6 +291,8 @@ where
 	fn place_child_storage(&mut self, storage_key: ChildStorageKey<H>, key: Vec<u8>, value: Option<Vec<u8>>) {
 		let _guard = panic_handler::AbortGuard::new(true);
 
+		let name = if value.is_some() { "child_put" } else { "child_clear" };
+		substrate_tracing::storage_event(name, Some(storage_key.as_ref()), &key, value.as_ref().map(|value| &value[..]));
 		self.mark_dirty();
 		self.overlay.set_child_storage(storage_key.into_owned(), key, value);
 	}
@@ -252,6 +314,7 @@ where
 			return;
 		}
 
+		substrate_tracing::storage_event("clear_prefix", None, prefix, None);
 		self.mark_dirty();
 		self.overlay.clear_prefix(prefix);
 		self.backend.for_keys_with_prefix(prefix, |key| {
@@ -259,6 +322,22 @@ where
 		});
 	}
 
+	fn storage_start_transaction(&mut self) {
+		let _guard = panic_handler::AbortGuard::new(true);
+		self.overlay.start_transaction();
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		let _guard = panic_handler::AbortGuard::new(true);
+		self.mark_dirty();
+		self.overlay.rollback_transaction()
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		let _guard = panic_handler::AbortGuard::new(true);
+		self.overlay.commit_transaction()
+	}
+
 	fn chain_id(&self) -> u64 {
 		42
 	}
@@ -332,6 +411,14 @@ where
 	fn offchain(&mut self) -> Option<&mut dyn offchain::Externalities> {
 		self.offchain_externalities.as_mut().map(|x| &mut **x as _)
 	}
+
+	fn is_offchain_worker(&self) -> bool {
+		self.offchain_externalities.is_some()
+	}
+
+	fn keystore(&self) -> Option<KeyStorePtr> {
+		self.keystore.clone()
+	}
 }
 
 #[cfg(test)]
@@ -367,6 +454,7 @@ mod tests {
 				digest_interval: 0,
 				digest_levels: 0,
 			}),
+			transactions: Default::default(),
 		}
 	}
 
@@ -408,4 +496,52 @@ mod tests {
 		assert_eq!(ext.storage_changes_root(Default::default()).unwrap(),
 			Some(hex!("bcf494e41e29a15c9ae5caa053fe3cb8b446ee3e02a254efbdec7a19235b76e4").into()));
 	}
+
+	#[test]
+	fn next_storage_key_works() {
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_storage(vec![20], None);
+		overlay.set_storage(vec![30], Some(vec![31]));
+		let backend: TestBackend = vec![
+			(None, vec![10], Some(vec![10])),
+			(None, vec![20], Some(vec![20])),
+			(None, vec![40], Some(vec![40])),
+		].into();
+
+		let ext = TestExt::new(&mut overlay, &backend, None, None);
+
+		// next_backend < next_overlay
+		assert_eq!(ext.next_storage_key(&[5]), Some(vec![10]));
+
+		// next_backend == next_overlay but next_overlay is a delete
+		assert_eq!(ext.next_storage_key(&[10]), Some(vec![30]));
+
+		// next_overlay < next_backend
+		assert_eq!(ext.next_storage_key(&[20]), Some(vec![30]));
+
+		// next_backend exist but next_overlay doesn't exist
+		assert_eq!(ext.next_storage_key(&[30]), Some(vec![40]));
+
+		drop(ext);
+		overlay.set_storage(vec![50], Some(vec![50]));
+		let ext = TestExt::new(&mut overlay, &backend, None, None);
+
+		// next_overlay exist but next_backend doesn't exist
+		assert_eq!(ext.next_storage_key(&[40]), Some(vec![50]));
+
+		// nothing after
+		assert_eq!(ext.next_storage_key(&[50]), None);
+	}
+
+	#[test]
+	fn next_storage_key_skips_many_deleted_keys() {
+		let mut overlay = OverlayedChanges::default();
+		for i in 0u32..100_000 {
+			overlay.set_storage(i.to_be_bytes().to_vec(), None);
+		}
+		let backend: TestBackend = vec![(None, vec![0xff; 5], Some(vec![1]))].into();
+
+		let ext = TestExt::new(&mut overlay, &backend, None, None);
+		assert_eq!(ext.next_storage_key(&[]), Some(vec![0xff; 5]));
+	}
 }
@@ -25,6 +25,7 @@ use hash_db::Hasher;
 use parity_codec::{Decode, Encode};
 use primitives::{
 	storage::well_known_keys, NativeOrEncoded, NeverNativeValue, offchain,
+	traits::KeyStorePtr,
 };
 
 pub mod backend;
@@ -38,7 +39,7 @@ mod trie_backend;
 mod trie_backend_essence;
 
 use overlayed_changes::OverlayedChangeSet;
-pub use trie::{TrieMut, TrieDBMut, DBValue, MemoryDB};
+pub use trie::{TrieMut, TrieDBMut, DBValue, MemoryDB, StorageProof, CompactProof};
 pub use testing::TestExternalities;
 pub use basic::BasicExternalities;
 pub use ext::Ext;
@@ -55,7 +56,7 @@ pub use changes_trie::{
 pub use overlayed_changes::OverlayedChanges;
 pub use proving_backend::{
 	create_proof_check_backend, create_proof_check_backend_storage,
-	Recorder as ProofRecorder, ProvingBackend,
+	Recorder as ProofRecorder, ProvingBackend, extract_recorded_proof,
 };
 pub use trie_backend_essence::{TrieBackendStorage, Storage};
 pub use trie_backend::TrieBackend;
@@ -147,6 +148,9 @@ pub trait Externalities<H: Hasher> {
 	/// Read runtime storage.
 	fn storage(&self, key: &[u8]) -> Option<Vec<u8>>;
 
+	/// Return the next key in storage in lexicographic order or `None` if there is no value.
+	fn next_storage_key(&self, key: &[u8]) -> Option<Vec<u8>>;
+
 	/// Get storage value hash. This may be optimized for large values.
 	fn storage_hash(&self, key: &[u8]) -> Option<H::Out> {
 		self.storage(key).map(|v| H::hash(&v))
@@ -206,6 +210,37 @@ pub trait Externalities<H: Hasher> {
 	/// Set or clear a child storage entry. Return whether the operation succeeds.
 	fn place_child_storage(&mut self, storage_key: ChildStorageKey<H>, key: Vec<u8>, value: Option<Vec<u8>>);
 
+	/// Start a new nested storage transaction.
+	///
+	/// All changes made from now on are reverted by a matching
+	/// [`storage_rollback_transaction`](#tymethod.storage_rollback_transaction) or kept by a
+	/// matching [`storage_commit_transaction`](#tymethod.storage_commit_transaction).
+	fn storage_start_transaction(&mut self);
+
+	/// Revert all changes made since the innermost storage transaction was started and close it.
+	///
+	/// Returns `Err` if there is no open transaction.
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()>;
+
+	/// Close the innermost storage transaction, keeping its changes.
+	///
+	/// Returns `Err` if there is no open transaction.
+	fn storage_commit_transaction(&mut self) -> Result<(), ()>;
+
+	/// Get the index of the extrinsic that is currently being applied, if any.
+	///
+	/// The index is read from the `:extrinsic_index` storage entry, which is maintained by the
+	/// runtime.
+	fn extrinsic_index(&self) -> Option<u32> {
+		self.storage(well_known_keys::EXTRINSIC_INDEX).and_then(|index| Decode::decode(&mut &index[..]))
+	}
+
+	/// Returns `true` if the code is executed by an offchain worker, rather than to build, import
+	/// or query a block.
+	fn is_offchain_worker(&self) -> bool {
+		false
+	}
+
 	/// Get the identity of the chain.
 	fn chain_id(&self) -> u64;
 
@@ -223,6 +258,9 @@ pub trait Externalities<H: Hasher> {
 
 	/// Returns offchain externalities extension if present.
 	fn offchain(&mut self) -> Option<&mut dyn offchain::Externalities>;
+
+	/// Returns the keystore, if present.
+	fn keystore(&self) -> Option<KeyStorePtr>;
 }
 
 /// An implementation of offchain extensions that should never be triggered.
@@ -489,6 +527,7 @@ pub fn new<'a, H, N, B, T, O, Exec>(
 		exec,
 		method,
 		call_data,
+		keystore: None,
 		_hasher: PhantomData,
 	}
 }
@@ -502,6 +541,7 @@ pub struct StateMachine<'a, H, N, B, T, O, Exec> {
 	exec: &'a Exec,
 	method: &'a str,
 	call_data: &'a [u8],
+	keystore: Option<KeyStorePtr>,
 	_hasher: PhantomData<(H, N)>,
 }
 
@@ -514,6 +554,12 @@ impl<'a, H, N, B, T, O, Exec> StateMachine<'a, H, N, B, T, O, Exec> where
 	H::Out: Ord + 'static,
 	N: crate::changes_trie::BlockNumber,
 {
+	/// Make the given keystore available to the runtime.
+	pub fn with_keystore(mut self, keystore: Option<KeyStorePtr>) -> Self {
+		self.keystore = keystore;
+		self
+	}
+
 	/// Execute a call using the given state backend, overlayed changes, and call executor.
 	/// Produces a state-backend-specific "transaction" which can be used to apply the changes
 	/// to the backing store, such as the disk.
@@ -559,7 +605,7 @@ impl<'a, H, N, B, T, O, Exec> StateMachine<'a, H, N, B, T, O, Exec> where
 			self.backend,
 			self.changes_trie_storage,
 			self.offchain_ext.as_mut().map(|x| &mut **x),
-		);
+		).with_keystore(self.keystore.clone());
 		let (result, was_native) = self.exec.call(
 			&mut externalities,
 			self.method,
@@ -706,7 +752,7 @@ pub fn prove_execution<B, H, Exec>(
 	exec: &Exec,
 	method: &str,
 	call_data: &[u8],
-) -> Result<(Vec<u8>, Vec<Vec<u8>>), Box<dyn Error>>
+) -> Result<(Vec<u8>, StorageProof), Box<dyn Error>>
 where
 	B: Backend<H>,
 	H: Hasher,
@@ -733,7 +779,7 @@ pub fn prove_execution_on_trie_backend<S, H, Exec>(
 	exec: &Exec,
 	method: &str,
 	call_data: &[u8],
-) -> Result<(Vec<u8>, Vec<Vec<u8>>), Box<dyn Error>>
+) -> Result<(Vec<u8>, StorageProof), Box<dyn Error>>
 where
 	S: trie_backend_essence::TrieBackendStorage<H>,
 	H: Hasher,
@@ -749,6 +795,7 @@ where
 		exec,
 		method,
 		call_data,
+		keystore: None,
 		_hasher: PhantomData,
 	};
 	let (result, _, _) = sm.execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
@@ -763,7 +810,7 @@ where
 /// Check execution proof, generated by `prove_execution` call.
 pub fn execution_proof_check<H, Exec>(
 	root: H::Out,
-	proof: Vec<Vec<u8>>,
+	proof: StorageProof,
 	overlay: &mut OverlayedChanges,
 	exec: &Exec,
 	method: &str,
@@ -799,6 +846,7 @@ where
 		exec,
 		method,
 		call_data,
+		keystore: None,
 		_hasher: PhantomData,
 	};
 	sm.execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
@@ -812,7 +860,7 @@ where
 pub fn prove_read<B, H>(
 	mut backend: B,
 	key: &[u8]
-) -> Result<(Option<Vec<u8>>, Vec<Vec<u8>>), Box<dyn Error>>
+) -> Result<(Option<Vec<u8>>, StorageProof), Box<dyn Error>>
 where
 	B: Backend<H>,
 	H: Hasher,
@@ -830,7 +878,7 @@ pub fn prove_child_read<B, H>(
 	mut backend: B,
 	storage_key: &[u8],
 	key: &[u8],
-) -> Result<(Option<Vec<u8>>, Vec<Vec<u8>>), Box<dyn Error>>
+) -> Result<(Option<Vec<u8>>, StorageProof), Box<dyn Error>>
 where
 	B: Backend<H>,
 	H: Hasher,
@@ -846,7 +894,7 @@ where
 pub fn prove_read_on_trie_backend<S, H>(
 	trie_backend: &TrieBackend<S, H>,
 	key: &[u8]
-) -> Result<(Option<Vec<u8>>, Vec<Vec<u8>>), Box<dyn Error>>
+) -> Result<(Option<Vec<u8>>, StorageProof), Box<dyn Error>>
 where
 	S: trie_backend_essence::TrieBackendStorage<H>,
 	H: Hasher,
@@ -862,7 +910,7 @@ pub fn prove_child_read_on_trie_backend<S, H>(
 	trie_backend: &TrieBackend<S, H>,
 	storage_key: &[u8],
 	key: &[u8]
-) -> Result<(Option<Vec<u8>>, Vec<Vec<u8>>), Box<dyn Error>>
+) -> Result<(Option<Vec<u8>>, StorageProof), Box<dyn Error>>
 where
 	S: trie_backend_essence::TrieBackendStorage<H>,
 	H: Hasher,
@@ -876,7 +924,7 @@ where
 /// Check storage read proof, generated by `prove_read` call.
 pub fn read_proof_check<H>(
 	root: H::Out,
-	proof: Vec<Vec<u8>>,
+	proof: StorageProof,
 	key: &[u8],
 ) -> Result<Option<Vec<u8>>, Box<dyn Error>>
 where
@@ -890,7 +938,7 @@ where
 /// Check child storage read proof, generated by `prove_child_read` call.
 pub fn read_child_proof_check<H>(
 	root: H::Out,
-	proof: Vec<Vec<u8>>,
+	proof: StorageProof,
 	storage_key: &[u8],
 	key: &[u8],
 ) -> Result<Option<Vec<u8>>, Box<dyn Error>>
@@ -35,6 +35,8 @@ pub struct OverlayedChanges {
 	/// Changes trie configuration. None by default, but could be installed by the
 	/// runtime if it supports change tries.
 	pub(crate) changes_trie_config: Option<ChangesTrieConfig>,
+	/// Stack of open storage transactions, innermost last.
+	pub(crate) transactions: Vec<OverlayedTransaction>,
 }
 
 /// The storage value, used inside OverlayedChanges.
@@ -58,6 +60,16 @@ pub struct OverlayedChangeSet {
 	pub children: HashMap<Vec<u8>, (Option<BTreeSet<u32>>, HashMap<Vec<u8>, Option<Vec<u8>>>)>,
 }
 
+/// The prospective entries a storage transaction has overwritten, as they were before the
+/// transaction first touched them. `None` means the entry was not present.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct OverlayedTransaction {
+	/// Original top level entries.
+	top: HashMap<Vec<u8>, Option<OverlayedValue>>,
+	/// Original child storage entries.
+	children: HashMap<Vec<u8>, Option<(Option<BTreeSet<u32>>, HashMap<Vec<u8>, Option<Vec<u8>>>)>>,
+}
+
 #[cfg(test)]
 impl FromIterator<(Vec<u8>, OverlayedValue)> for OverlayedChangeSet {
 	fn from_iter<T: IntoIterator<Item = (Vec<u8>, OverlayedValue)>>(iter: T) -> Self {
@@ -112,6 +124,27 @@ impl OverlayedChanges {
 			.map(|x| x.value.as_ref().map(AsRef::as_ref))
 	}
 
+	/// Returns the next (in lexicographic order) storage key in the overlayed changes, alongside
+	/// its value. The value is `None` if the key has been deleted.
+	///
+	/// Prospective changes take precedence over committed ones.
+	pub fn next_storage_key_change(&self, key: &[u8]) -> Option<(&[u8], &OverlayedValue)> {
+		let next_after = |set: &'_ OverlayedChangeSet| set.top.iter()
+			.filter(|(k, _)| &k[..] > key)
+			.min_by(|a, b| a.0.cmp(b.0))
+			.map(|(k, v)| (&k[..], v));
+
+		match (next_after(&self.prospective), next_after(&self.committed)) {
+			(Some(prospective), Some(committed)) => if prospective.0 <= committed.0 {
+				Some(prospective)
+			} else {
+				Some(committed)
+			},
+			(prospective, None) => prospective,
+			(None, committed) => committed,
+		}
+	}
+
 	/// Returns a double-Option: None if the key is unknown (i.e. and the query should be refered
 	/// to the backend); Some(None) if the key has been deleted. Some(Some(...)) for a key whose
 	/// value has been set.
@@ -136,6 +169,7 @@ impl OverlayedChanges {
 	/// `None` can be used to delete a value specified by the given key.
 	pub(crate) fn set_storage(&mut self, key: Vec<u8>, val: Option<Vec<u8>>) {
 		let extrinsic_index = self.extrinsic_index();
+		self.note_top_change(&key);
 		let entry = self.prospective.top.entry(key).or_default();
 		entry.value = val;
 
@@ -150,6 +184,7 @@ impl OverlayedChanges {
 	/// `None` can be used to delete a value specified by the given key.
 	pub(crate) fn set_child_storage(&mut self, storage_key: Vec<u8>, key: Vec<u8>, val: Option<Vec<u8>>) {
 		let extrinsic_index = self.extrinsic_index();
+		self.note_child_change(&storage_key);
 		let map_entry = self.prospective.children.entry(storage_key).or_default();
 		map_entry.1.insert(key, val);
 
@@ -167,6 +202,7 @@ impl OverlayedChanges {
 	/// [`discard_prospective`]: #method.discard_prospective
 	pub(crate) fn clear_child_storage(&mut self, storage_key: &[u8]) {
 		let extrinsic_index = self.extrinsic_index();
+		self.note_child_change(storage_key);
 		let map_entry = self.prospective.children.entry(storage_key.to_vec()).or_default();
 
 		if let Some(extrinsic) = extrinsic_index {
@@ -192,6 +228,15 @@ impl OverlayedChanges {
 	pub(crate) fn clear_prefix(&mut self, prefix: &[u8]) {
 		let extrinsic_index = self.extrinsic_index();
 
+		if !self.transactions.is_empty() {
+			let keys = self.prospective.top.keys()
+				.chain(self.committed.top.keys())
+				.filter(|key| key.starts_with(prefix))
+				.cloned()
+				.collect::<Vec<_>>();
+			keys.iter().for_each(|key| self.note_top_change(key));
+		}
+
 		// Iterate over all prospective and mark all keys that share
 		// the given prefix as removed (None).
 		for (key, entry) in self.prospective.top.iter_mut() {
@@ -220,13 +265,100 @@ impl OverlayedChanges {
 		}
 	}
 
+	/// Records the prospective value of `key` in the innermost open transaction, unless it
+	/// has been recorded already.
+	fn note_top_change(&mut self, key: &[u8]) {
+		if let Some(transaction) = self.transactions.last_mut() {
+			if !transaction.top.contains_key(key) {
+				transaction.top.insert(key.to_vec(), self.prospective.top.get(key).cloned());
+			}
+		}
+	}
+
+	/// Records the prospective child change set of `storage_key` in the innermost open
+	/// transaction, unless it has been recorded already.
+	fn note_child_change(&mut self, storage_key: &[u8]) {
+		if let Some(transaction) = self.transactions.last_mut() {
+			if !transaction.children.contains_key(storage_key) {
+				transaction.children.insert(
+					storage_key.to_vec(),
+					self.prospective.children.get(storage_key).cloned(),
+				);
+			}
+		}
+	}
+
+	/// Start a new storage transaction.
+	///
+	/// All changes made to the prospective change set from now on can be reverted with
+	/// [`rollback_transaction`] or kept with [`commit_transaction`]. Transactions can be nested.
+	///
+	/// [`rollback_transaction`]: #method.rollback_transaction
+	/// [`commit_transaction`]: #method.commit_transaction
+	pub fn start_transaction(&mut self) {
+		self.transactions.push(Default::default());
+	}
+
+	/// Revert all prospective changes made since the innermost transaction was started and
+	/// close it.
+	///
+	/// Returns `Err` if there is no open transaction.
+	pub fn rollback_transaction(&mut self) -> Result<(), ()> {
+		let transaction = self.transactions.pop().ok_or(())?;
+
+		for (key, value) in transaction.top {
+			match value {
+				Some(value) => { self.prospective.top.insert(key, value); },
+				None => { self.prospective.top.remove(&key); },
+			}
+		}
+		for (storage_key, map) in transaction.children {
+			match map {
+				Some(map) => { self.prospective.children.insert(storage_key, map); },
+				None => { self.prospective.children.remove(&storage_key); },
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Close the innermost transaction, keeping its changes. They become part of the
+	/// enclosing transaction, if any.
+	///
+	/// Returns `Err` if there is no open transaction.
+	pub fn commit_transaction(&mut self) -> Result<(), ()> {
+		let transaction = self.transactions.pop().ok_or(())?;
+
+		if let Some(parent) = self.transactions.last_mut() {
+			for (key, value) in transaction.top {
+				parent.top.entry(key).or_insert(value);
+			}
+			for (storage_key, map) in transaction.children {
+				parent.children.entry(storage_key).or_insert(map);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Returns the number of currently open transactions.
+	pub fn transaction_depth(&self) -> usize {
+		self.transactions.len()
+	}
+
 	/// Discard prospective changes to state.
+	///
+	/// This also closes all open transactions.
 	pub fn discard_prospective(&mut self) {
 		self.prospective.clear();
+		self.transactions.clear();
 	}
 
 	/// Commit prospective changes to state.
+	///
+	/// This also closes all open transactions, keeping their changes.
 	pub fn commit_prospective(&mut self) {
+		self.transactions.clear();
 		if self.committed.is_empty() {
 			::std::mem::swap(&mut self.prospective, &mut self.committed);
 		} else {
@@ -475,4 +607,44 @@ mod tests {
 		assert_eq!(overlay.prospective,
 			Default::default());
 	}
+
+	#[test]
+	fn nested_transactions_work() {
+		let mut overlayed = OverlayedChanges::default();
+		let child = b":child_storage:default:test".to_vec();
+
+		overlayed.set_storage(b"a".to_vec(), Some(vec![1]));
+		overlayed.set_storage(b"c".to_vec(), Some(vec![1]));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(b"a".to_vec(), Some(vec![2]));
+		overlayed.set_storage(b"b".to_vec(), Some(vec![2]));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(b"a".to_vec(), Some(vec![3]));
+		overlayed.clear_prefix(b"c");
+		overlayed.set_child_storage(child.clone(), b"k".to_vec(), Some(vec![3]));
+		assert_eq!(overlayed.transaction_depth(), 2);
+
+		// Rolling back the inner transaction restores the state of the outer one.
+		assert_eq!(overlayed.rollback_transaction(), Ok(()));
+		assert_eq!(overlayed.storage(b"a").unwrap(), Some(&[2][..]));
+		assert_eq!(overlayed.storage(b"b").unwrap(), Some(&[2][..]));
+		assert_eq!(overlayed.storage(b"c").unwrap(), Some(&[1][..]));
+		assert!(overlayed.child_storage(&child, b"k").is_none());
+
+		overlayed.start_transaction();
+		overlayed.set_storage(b"b".to_vec(), Some(vec![4]));
+		assert_eq!(overlayed.commit_transaction(), Ok(()));
+		assert_eq!(overlayed.storage(b"b").unwrap(), Some(&[4][..]));
+
+		// Changes of a committed inner transaction are reverted with the outer one.
+		assert_eq!(overlayed.rollback_transaction(), Ok(()));
+		assert_eq!(overlayed.storage(b"a").unwrap(), Some(&[1][..]));
+		assert!(overlayed.storage(b"b").is_none());
+		assert_eq!(overlayed.transaction_depth(), 0);
+
+		assert_eq!(overlayed.rollback_transaction(), Err(()));
+		assert_eq!(overlayed.commit_transaction(), Err(()));
+	}
 }
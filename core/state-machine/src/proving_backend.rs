@@ -21,7 +21,7 @@ use log::debug;
 use hash_db::Hasher;
 use hash_db::HashDB;
 use trie::{
-	MemoryDB, PrefixedMemoryDB, TrieError, default_child_trie_root,
+	MemoryDB, PrefixedMemoryDB, StorageProof, TrieError, default_child_trie_root,
 	read_trie_value_with, read_child_trie_value_with, record_all_keys
 };
 pub use trie::Recorder;
@@ -112,18 +112,19 @@ impl<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> ProvingBackend<'a, S, H>
 		}
 	}
 
-	/// Consume the backend, extracting the gathered proof in lexicographical order
-	/// by value.
-	pub fn extract_proof(self) -> Vec<Vec<u8>> {
-		self.proof_recorder
-			.borrow_mut()
-			.drain()
-			.into_iter()
-			.map(|n| n.data.to_vec())
-			.collect()
+	/// Consume the backend, extracting the gathered proof.
+	pub fn extract_proof(self) -> StorageProof {
+		extract_recorded_proof(&mut *self.proof_recorder.borrow_mut())
 	}
 }
 
+/// Drain the trie nodes gathered by the recorder into a proof.
+///
+/// The recorder keeps a node every time it is accessed, the proof deduplicates them.
+pub fn extract_recorded_proof<H>(recorder: &mut Recorder<H>) -> StorageProof {
+	StorageProof::new(recorder.drain().into_iter().map(|n| n.data.to_vec()).collect())
+}
+
 impl<'a, S, H> Backend<H> for ProvingBackend<'a, S, H>
 	where
 		S: 'a + TrieBackendStorage<H>,
@@ -154,6 +155,10 @@ impl<'a, S, H> Backend<H> for ProvingBackend<'a, S, H>
 		self.backend.for_keys_in_child_storage(storage_key, f)
 	}
 
+	fn next_storage_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.backend.next_storage_key(key)
+	}
+
 	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], f: F) {
 		self.backend.for_keys_with_prefix(prefix, f)
 	}
@@ -192,7 +197,7 @@ impl<'a, S, H> Backend<H> for ProvingBackend<'a, S, H>
 /// Create proof check backend.
 pub fn create_proof_check_backend<H>(
 	root: H::Out,
-	proof: Vec<Vec<u8>>
+	proof: StorageProof,
 ) -> Result<TrieBackend<MemoryDB<H>, H>, Box<dyn Error>>
 where
 	H: Hasher,
@@ -208,16 +213,12 @@ where
 
 /// Create in-memory storage of proof check backend.
 pub fn create_proof_check_backend_storage<H>(
-	proof: Vec<Vec<u8>>
+	proof: StorageProof,
 ) -> MemoryDB<H>
 where
 	H: Hasher,
 {
-	let mut db = MemoryDB::default();
-	for item in proof {
-		db.insert(&[], &item);
-	}
-	db
+	proof.into_memory_db()
 }
 
 #[cfg(test)]
@@ -251,7 +252,7 @@ mod tests {
 	#[test]
 	fn proof_is_invalid_when_does_not_contains_root() {
 		use primitives::H256;
-		assert!(create_proof_check_backend::<Blake2Hasher>(H256::from_low_u64_be(1), vec![]).is_err());
+		assert!(create_proof_check_backend::<Blake2Hasher>(H256::from_low_u64_be(1), StorageProof::empty()).is_err());
 	}
 
 	#[test]
@@ -289,6 +290,21 @@ mod tests {
 		assert_eq!(proof_check.storage(&[42]).unwrap().unwrap(), vec![42]);
 	}
 
+	#[test]
+	fn proof_does_not_contain_duplicate_nodes() {
+		let trie_backend = test_trie();
+		let backend = test_proving(&trie_backend);
+		assert_eq!(backend.storage(b"key").unwrap(), Some(b"value".to_vec()));
+		assert_eq!(backend.storage(b"key").unwrap(), Some(b"value".to_vec()));
+		assert_eq!(backend.storage(b"value1").unwrap(), Some(vec![42]));
+
+		let proof = backend.extract_proof().into_nodes();
+		let mut deduplicated = proof.clone();
+		deduplicated.sort();
+		deduplicated.dedup();
+		assert_eq!(proof, deduplicated);
+	}
+
 	#[test]
 	fn proof_recorded_and_checked_with_child() {
 		let subtrie1 = ChildStorageKey::<Blake2Hasher>::from_slice(
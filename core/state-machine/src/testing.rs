@@ -25,7 +25,7 @@ use crate::changes_trie::{
 	build_changes_trie, InMemoryStorage as ChangesTrieInMemoryStorage,
 	BlockNumber as ChangesTrieBlockNumber,
 };
-use primitives::offchain;
+use primitives::{offchain, traits::KeyStorePtr};
 use primitives::storage::well_known_keys::{CHANGES_TRIE_CONFIG, CODE, HEAP_PAGES};
 use parity_codec::Encode;
 use super::{ChildStorageKey, Externalities, OverlayedChanges};
@@ -40,6 +40,7 @@ pub struct TestExternalities<H: Hasher, N: ChangesTrieBlockNumber> {
 	backend: InMemory<H>,
 	changes_trie_storage: ChangesTrieInMemoryStorage<H, N>,
 	offchain: Option<Box<dyn offchain::Externalities>>,
+	keystore: Option<KeyStorePtr>,
 }
 
 impl<H: Hasher, N: ChangesTrieBlockNumber> TestExternalities<H, N> {
@@ -60,6 +61,17 @@ impl<H: Hasher, N: ChangesTrieBlockNumber> TestExternalities<H, N> {
 
 	/// Create a new instance of `TestExternalities` with code, storage and children.
 	pub fn new_with_code_with_children(code: &[u8], mut storage: StorageTuple) -> Self {
+		storage.0.insert(HEAP_PAGES.to_vec(), 8u64.encode());
+		storage.0.insert(CODE.to_vec(), code.to_vec());
+
+		Self::from_raw_snapshot(storage)
+	}
+
+	/// Create a new instance of `TestExternalities` directly from a raw storage snapshot.
+	///
+	/// Unlike the other constructors this doesn't insert any code or heap pages, the backend
+	/// contains exactly the given key/value pairs.
+	pub fn from_raw_snapshot(storage: StorageTuple) -> Self {
 		let mut overlay = OverlayedChanges::default();
 
 		assert!(storage.0.keys().all(|key| !is_child_storage_key(key)));
@@ -71,9 +83,6 @@ impl<H: Hasher, N: ChangesTrieBlockNumber> TestExternalities<H, N> {
 			false,
 		).expect("changes trie configuration is correct in test env; qed");
 
-		storage.0.insert(HEAP_PAGES.to_vec(), 8u64.encode());
-		storage.0.insert(CODE.to_vec(), code.to_vec());
-
 		let backend: HashMap<_, _> = storage.1.into_iter()
 			.map(|(keyspace, map)| (Some(keyspace), map))
 			.chain(Some((None, storage.0)).into_iter())
@@ -84,6 +93,7 @@ impl<H: Hasher, N: ChangesTrieBlockNumber> TestExternalities<H, N> {
 			changes_trie_storage: ChangesTrieInMemoryStorage::new(),
 			backend: backend.into(),
 			offchain: None,
+			keystore: None,
 		}
 	}
 
@@ -97,13 +107,34 @@ impl<H: Hasher, N: ChangesTrieBlockNumber> TestExternalities<H, N> {
 		self.offchain = Some(Box::new(offchain));
 	}
 
+	/// Set the keystore.
+	pub fn set_keystore(&mut self, keystore: KeyStorePtr) {
+		self.keystore = Some(keystore);
+	}
+
 	/// Get mutable reference to changes trie storage.
 	pub fn changes_trie_storage(&mut self) -> &mut ChangesTrieInMemoryStorage<H, N> {
 		&mut self.changes_trie_storage
 	}
 
+	/// Commit all pending changes of the overlay into the backend.
+	///
+	/// This can be used to simulate the end of a block: subsequent changes are recorded
+	/// against the new backend state.
+	pub fn commit_all(&mut self) {
+		self.backend = self.as_backend();
+		self.overlay.committed = Default::default();
+		self.overlay.prospective = Default::default();
+		self.overlay.transactions.clear();
+	}
+
+	/// Return the storage root of the backend, ignoring any pending changes.
+	pub fn backend_storage_root(&self) -> H::Out {
+		self.backend.storage_root(std::iter::empty::<(Vec<u8>, Option<Vec<u8>>)>()).0
+	}
+
 	/// Return a new backend with all pending value.
-	pub fn commit_all(&self) -> InMemory<H> {
+	pub fn as_backend(&self) -> InMemory<H> {
 		let top = self.overlay.committed.top.clone().into_iter()
 			.chain(self.overlay.prospective.top.clone().into_iter())
 			.map(|(k, v)| (None, k, v.value));
@@ -130,7 +161,7 @@ impl<H: Hasher, N: ChangesTrieBlockNumber> PartialEq for TestExternalities<H, N>
 	/// This doesn't test if they are in the same state, only if they contains the
 	/// same data at this state
 	fn eq(&self, other: &TestExternalities<H, N>) -> bool {
-		self.commit_all().eq(&other.commit_all())
+		self.as_backend().eq(&other.as_backend())
 	}
 }
 
@@ -167,6 +198,25 @@ impl<H, N> Externalities<H> for TestExternalities<H, N>
 			self.backend.storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL))
 	}
 
+	fn next_storage_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		let mut key = key.to_vec();
+		loop {
+			let next_backend_key = self.backend.next_storage_key(&key).expect(EXT_NOT_ALLOWED_TO_FAIL);
+			let next_overlay_key_change = self.overlay.next_storage_key_change(&key);
+
+			match (next_backend_key, next_overlay_key_change) {
+				(Some(backend_key), Some(overlay_key)) if &backend_key[..] < overlay_key.0 => return Some(backend_key),
+				(backend_key, None) => return backend_key,
+				(_, Some(overlay_key)) => if overlay_key.1.value.is_some() {
+					return Some(overlay_key.0.to_vec());
+				} else {
+					// The key has been deleted in the overlay, continue the search after it.
+					key = overlay_key.0.to_vec();
+				},
+			}
+		}
+	}
+
 	fn original_storage(&self, key: &[u8]) -> Option<Vec<u8>> {
 		self.backend.storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL)
 	}
@@ -222,6 +272,18 @@ impl<H, N> Externalities<H> for TestExternalities<H, N>
 		});
 	}
 
+	fn storage_start_transaction(&mut self) {
+		self.overlay.start_transaction();
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		self.overlay.rollback_transaction()
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.overlay.commit_transaction()
+	}
+
 	fn chain_id(&self) -> u64 { 42 }
 
 	fn storage_root(&mut self) -> H::Out {
@@ -263,12 +325,21 @@ impl<H, N> Externalities<H> for TestExternalities<H, N>
 			.as_mut()
 			.map(|x| &mut **x as _)
 	}
+
+	fn is_offchain_worker(&self) -> bool {
+		self.offchain.is_some()
+	}
+
+	fn keystore(&self) -> Option<KeyStorePtr> {
+		self.keystore.clone()
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use primitives::{Blake2Hasher, H256};
+	use primitives::storage::well_known_keys::EXTRINSIC_INDEX;
 	use hex_literal::hex;
 
 	#[test]
@@ -290,4 +361,45 @@ mod tests {
 
 		assert_eq!(&ext.storage(CODE).unwrap(), &code);
 	}
+
+	#[test]
+	fn commit_all_moves_changes_into_backend() {
+		let mut ext = TestExternalities::<Blake2Hasher, u64>::default();
+		ext.set_storage(b"doe".to_vec(), b"reindeer".to_vec());
+		ext.set_storage(b"dog".to_vec(), b"puppy".to_vec());
+		let root = ext.storage_root();
+		assert!(ext.backend_storage_root() != root);
+
+		ext.commit_all();
+		assert_eq!(ext.backend_storage_root(), root);
+		assert_eq!(ext.storage(b"dog"), Some(b"puppy".to_vec()));
+
+		ext.clear_storage(b"dog");
+		ext.commit_all();
+		assert_eq!(ext.storage(b"dog"), None);
+		assert_eq!(ext.storage(b"doe"), Some(b"reindeer".to_vec()));
+	}
+
+	#[test]
+	fn from_raw_snapshot_keeps_storage_as_is() {
+		let storage = (
+			vec![(b"doe".to_vec(), b"reindeer".to_vec())].into_iter().collect(),
+			Default::default(),
+		);
+		let ext = TestExternalities::<Blake2Hasher, u64>::from_raw_snapshot(storage);
+
+		assert_eq!(ext.storage(b"doe"), Some(b"reindeer".to_vec()));
+		assert_eq!(ext.storage(CODE), None);
+		assert_eq!(ext.storage(HEAP_PAGES), None);
+	}
+
+	#[test]
+	fn extrinsic_index_is_read_from_storage() {
+		let mut ext = TestExternalities::<Blake2Hasher, u64>::default();
+		assert_eq!(ext.extrinsic_index(), None);
+		assert!(!ext.is_offchain_worker());
+
+		ext.set_storage(EXTRINSIC_INDEX.to_vec(), 5u32.encode());
+		assert_eq!(ext.extrinsic_index(), Some(5));
+	}
 }
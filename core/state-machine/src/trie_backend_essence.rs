@@ -63,6 +63,35 @@ impl<S: TrieBackendStorage<H>, H: Hasher> TrieBackendEssence<S, H> {
 		self.storage
 	}
 
+	/// Return the next key in the trie i.e. the minimum key that is strictly superior to `key` in
+	/// lexicographic order.
+	pub fn next_storage_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+		let mut read_overlay = S::Overlay::default();
+		let eph = Ephemeral {
+			storage: &self.storage,
+			overlay: &mut read_overlay,
+		};
+
+		let next = || -> Result<Option<Vec<u8>>, Box<TrieError<H::Out>>> {
+			let trie = TrieDB::<H>::new(&eph, &self.root)?;
+			let mut iter = trie.iter()?;
+
+			// `seek` positions the iterator on the first key that is greater or equal to `key`.
+			iter.seek(key)?;
+
+			for x in iter {
+				let (next_key, _) = x?;
+				if &next_key[..] != key {
+					return Ok(Some(next_key));
+				}
+			}
+
+			Ok(None)
+		};
+
+		next().map_err(|e| format!("Trie lookup error: {}", e))
+	}
+
 	/// Get the value of storage at given key.
 	pub fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
 		let mut read_overlay = S::Overlay::default();
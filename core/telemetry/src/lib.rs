@@ -57,6 +57,14 @@
 //! )
 //! ```
 //!
+//! Subsystems that want to emit records that don't fit the `slog` key-value model can instead
+//! obtain a [`TelemetryHandle`] with [`Telemetry::handle`] and send arbitrary JSON objects.
+//!
+//! Each endpoint has its own verbosity level, and messages are only sent to the endpoints whose
+//! level is greater or equal than the verbosity of the message. Messages emitted while an endpoint
+//! is unreachable are buffered and sent as a batch once the connection is re-established, and
+//! reconnection attempts are spaced out with an exponential backoff.
+//!
 
 use futures::{prelude::*, task::AtomicWaker};
 use libp2p::{Multiaddr, wasm_ext};
@@ -99,6 +107,11 @@ impl TelemetryEndpoints {
 	pub fn new(endpoints: Vec<(String, u8)>) -> Self {
 		TelemetryEndpoints(endpoints)
 	}
+
+	/// Returns the list of endpoints and their maximum verbosity level.
+	pub fn endpoints(&self) -> &[(String, u8)] {
+		&self.0
+	}
 }
 
 /// Log levels.
@@ -170,6 +183,57 @@ pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
 	}
 }
 
+impl Telemetry {
+	/// Returns a handle that can be used to send custom records to the telemetry.
+	///
+	/// The handle doesn't keep the telemetry alive.
+	pub fn handle(&self) -> TelemetryHandle {
+		TelemetryHandle { inner: Arc::downgrade(&self.inner) }
+	}
+}
+
+/// Handle that allows subsystems to send custom JSON records to the telemetry endpoints.
+#[derive(Clone)]
+pub struct TelemetryHandle {
+	inner: Weak<TelemetryInner>,
+}
+
+impl TelemetryHandle {
+	/// Sends a record with the given verbosity level to all the interested endpoints.
+	///
+	/// The `msg` field identifies the kind of record, and the `level` field and a `ts` field with
+	/// the current time are added, as for the records emitted through the `telemetry!` macro.
+	/// Does nothing if the telemetry has been dropped.
+	pub fn send(
+		&self,
+		verbosity: u8,
+		level: slog::Level,
+		msg: &str,
+		mut payload: serde_json::Map<String, serde_json::Value>,
+	) {
+		let inner = match self.inner.upgrade() {
+			Some(inner) => inner,
+			None => return,
+		};
+
+		let mut worker = inner.worker.lock();
+		if !worker.is_interested(verbosity) {
+			return
+		}
+
+		payload.insert("msg".into(), msg.into());
+		payload.insert("level".into(), level.as_short_str().into());
+		payload.insert("ts".into(), chrono::Local::now().to_rfc3339().into());
+		match serde_json::to_vec(&payload) {
+			Ok(serialized) => {
+				worker.send_serialized(verbosity, serialized);
+				inner.polling_waker.wake();
+			},
+			Err(err) => warn!(target: "telemetry", "Failed to serialize telemetry record: {}", err),
+		}
+	}
+}
+
 /// Event generated when polling the worker.
 #[derive(Debug)]
 pub enum TelemetryEvent {
@@ -150,7 +150,7 @@ impl TelemetryWorker {
 		};
 
 		// None of the nodes want that verbosity, so just return without doing any serialization.
-		if self.nodes.iter().all(|(_, node_max_verbosity)| msg_verbosity > *node_max_verbosity) {
+		if !self.is_interested(msg_verbosity) {
 			trace!(
 				target: "telemetry",
 				"Skipping log entry because verbosity {:?} is too high for all endpoints",
@@ -166,6 +166,19 @@ impl TelemetryWorker {
 			out
 		};
 
+		self.send_serialized(msg_verbosity, serialized);
+		Ok(())
+	}
+
+	/// Returns true if at least one of the endpoints accepts messages of the given verbosity.
+	pub fn is_interested(&self, msg_verbosity: u8) -> bool {
+		self.nodes.iter().any(|(_, node_max_verbosity)| msg_verbosity <= *node_max_verbosity)
+	}
+
+	/// Sends an already serialized JSON message to all the endpoints that accept its verbosity.
+	///
+	/// Keep in mind that you should call `TelemetryWorker::poll` in order to process the messages.
+	pub fn send_serialized(&mut self, msg_verbosity: u8, serialized: Vec<u8>) {
 		for (node, node_max_verbosity) in &mut self.nodes {
 			if msg_verbosity > *node_max_verbosity {
 				trace!(target: "telemetry", "Skipping {:?} for log entry with verbosity {:?}",
@@ -173,11 +186,9 @@ impl TelemetryWorker {
 				continue;
 			}
 
-			// `send_message` returns an error if we're not connected, which we silently ignore.
+			// `send_message` returns an error if the queue is full, which we silently ignore.
 			let _ = node.send_message(serialized.clone());
 		}
-
-		Ok(())
 	}
 }
 
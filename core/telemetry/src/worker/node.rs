@@ -28,6 +28,16 @@ use std::{collections::VecDeque, fmt, mem, pin::Pin, task::Context, task::Poll,
 /// Maximum number of pending telemetry messages.
 const MAX_PENDING: usize = 10;
 
+/// Maximum number of messages buffered while we are not connected to the node. They are sent as
+/// a batch as soon as the connection is established.
+const MAX_BUFFERED_WHILE_DISCONNECTED: usize = 64;
+
+/// Delay before the first reconnection attempt. Doubles after each failed attempt.
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Upper bound of the delay between two reconnection attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(300);
+
 /// Handler for a single telemetry node.
 pub struct Node<TTrans: Transport> {
 	/// Address of the node.
@@ -36,6 +46,10 @@ pub struct Node<TTrans: Transport> {
 	socket: NodeSocket<TTrans>,
 	/// Transport used to establish new connections.
 	transport: TTrans,
+	/// Messages received while not connected, sent as soon as we connect.
+	buffered: VecDeque<BytesMut>,
+	/// Number of consecutive failed connection attempts. Used to compute the reconnection delay.
+	failed_attempts: u32,
 }
 
 enum NodeSocket<TTrans: Transport> {
@@ -76,6 +90,8 @@ impl<TTrans: Transport> Node<TTrans> {
 			addr,
 			socket: NodeSocket::ReconnectNow,
 			transport,
+			buffered: VecDeque::new(),
+			failed_attempts: 0,
 		}
 	}
 
@@ -88,7 +104,11 @@ impl<TTrans: Transport> Node<TTrans> {
 impl<TTrans: Transport, TSinkErr> Node<TTrans>
 where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 	TTrans::Output: Sink<BytesMut, Error = TSinkErr> + Unpin, TSinkErr: fmt::Debug {
-	/// Sends a WebSocket frame to the node. Returns an error if we are not connected to the node.
+	/// Sends a WebSocket frame to the node.
+	///
+	/// If we are not connected, the message is buffered and sent together with the other buffered
+	/// messages once the connection is established. Returns an error if the message had to be
+	/// dropped because the queue is full.
 	///
 	/// After calling this method, you should call `poll` in order for it to be properly processed.
 	pub fn send_message(&mut self, payload: Vec<u8>) -> Result<(), ()> {
@@ -102,11 +122,25 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 					self.addr);
 				Err(())
 			}
+		} else if self.buffered.len() < MAX_BUFFERED_WHILE_DISCONNECTED {
+			trace!(target: "telemetry", "Buffering log entry until connected to {:?}", self.addr);
+			self.buffered.push_back(payload.into());
+			Ok(())
 		} else {
+			// Drop the oldest entry in favour of the most recent one.
+			self.buffered.pop_front();
+			self.buffered.push_back(payload.into());
 			Err(())
 		}
 	}
 
+	/// Builds the delay to wait before the next reconnection attempt, and records the failure.
+	fn next_reconnect_delay(&mut self) -> Delay {
+		let delay = reconnect_delay(self.failed_attempts);
+		self.failed_attempts = self.failed_attempts.saturating_add(1);
+		Delay::new(delay)
+	}
+
 	/// Polls the node for updates. Must be performed regularly.
 	pub fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<NodeEvent<TSinkErr>> {
 		let mut socket = mem::replace(&mut self.socket, NodeSocket::Poisoned);
@@ -118,7 +152,7 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 						Poll::Pending => break NodeSocket::Connected(conn),
 						Poll::Ready(Err(err)) => {
 							debug!(target: "telemetry", "Disconnected from {}: {:?}", self.addr, err);
-							let timeout = gen_rand_reconnect_delay();
+							let timeout = self.next_reconnect_delay();
 							self.socket = NodeSocket::WaitingReconnect(timeout);
 							return Poll::Ready(NodeEvent::Disconnected(err))
 						}
@@ -126,14 +160,16 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 				NodeSocket::Dialing(mut s) => match Future::poll(Pin::new(&mut s), cx) {
 					Poll::Ready(Ok(sink)) => {
 						debug!(target: "telemetry", "Connected to {}", self.addr);
-						let conn = NodeSocketConnected { sink, pending: VecDeque::new(), need_flush: false };
+						self.failed_attempts = 0;
+						let pending = mem::replace(&mut self.buffered, VecDeque::new());
+						let conn = NodeSocketConnected { sink, pending, need_flush: false };
 						self.socket = NodeSocket::Connected(conn);
 						return Poll::Ready(NodeEvent::Connected)
 					},
 					Poll::Pending => break NodeSocket::Dialing(s),
 					Poll::Ready(Err(err)) => {
 						debug!(target: "telemetry", "Error while dialing {}: {:?}", self.addr, err);
-						let timeout = gen_rand_reconnect_delay();
+						let timeout = self.next_reconnect_delay();
 						socket = NodeSocket::WaitingReconnect(timeout);
 					}
 				}
@@ -144,7 +180,7 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 					}
 					Err(err) => {
 						debug!(target: "telemetry", "Error while dialing {}: {:?}", self.addr, err);
-						let timeout = gen_rand_reconnect_delay();
+						let timeout = self.next_reconnect_delay();
 						socket = NodeSocket::WaitingReconnect(timeout);
 					}
 				}
@@ -165,13 +201,18 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 	}
 }
 
-/// Generates a `Delay` object with a random timeout.
+/// Computes the delay before reconnecting after `failed_attempts` consecutive failures.
 ///
-/// If there are general connection issues, not all endpoints should be synchronized in their
-/// re-connection time.
-fn gen_rand_reconnect_delay() -> Delay {
-	let random_delay = rand::thread_rng().gen_range(5, 10);
-	Delay::new(Duration::from_secs(random_delay))
+/// The delay doubles with each failure up to `MAX_RECONNECT_DELAY`, and a random jitter of up to
+/// half the delay is added. If there are general connection issues, not all endpoints should be
+/// synchronized in their re-connection time.
+fn reconnect_delay(failed_attempts: u32) -> Duration {
+	let base = INITIAL_RECONNECT_DELAY.as_secs()
+		.checked_shl(failed_attempts.min(16))
+		.unwrap_or(u64::max_value())
+		.min(MAX_RECONNECT_DELAY.as_secs());
+	let jitter = rand::thread_rng().gen_range(0, base / 2 + 1);
+	Duration::from_secs(base + jitter)
 }
 
 impl<TTrans: Transport, TSinkErr> NodeSocketConnected<TTrans>
@@ -232,3 +273,18 @@ impl<TTrans: Transport> fmt::Debug for Node<TTrans> {
 			.finish()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reconnect_delay_grows_exponentially_and_is_bounded() {
+		for attempts in 0..20 {
+			let expected = (5u64 << attempts.min(16)).min(300);
+			let delay = reconnect_delay(attempts).as_secs();
+			assert!(delay >= expected, "attempt {}: {} < {}", attempts, delay, expected);
+			assert!(delay <= expected + expected / 2, "attempt {}: {} too large", attempts, delay);
+		}
+	}
+}
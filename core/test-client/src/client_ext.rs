@@ -73,6 +73,7 @@ impl<B, E, RA, Block> ClientExt<Block> for Client<B, E, Block, RA>
 			finalized: false,
 			auxiliary: Vec::new(),
 			fork_choice: ForkChoiceStrategy::LongestChain,
+			storage_changes: None,
 		};
 
 		BlockImport::import_block(&mut (&*self), import, HashMap::new()).map(|_| ())
@@ -94,6 +95,7 @@ impl<B, E, RA, Block> ClientExt<Block> for Client<B, E, Block, RA>
 			finalized: true,
 			auxiliary: Vec::new(),
 			fork_choice: ForkChoiceStrategy::LongestChain,
+			storage_changes: None,
 		};
 
 		BlockImport::import_block(&mut (&*self), import, HashMap::new()).map(|_| ())
@@ -104,7 +106,7 @@ impl<B, E, RA, Block> ClientExt<Block> for Client<B, E, Block, RA>
 		id: BlockId<Block>,
 		justification: Option<Justification>,
 	) -> client::error::Result<()> {
-		self.finalize_block(id, justification, true)
+		client::Finalizer::finalize_block(self, id, justification, true)
 	}
 
 	fn genesis_hash(&self) -> <Block as BlockT>::Hash {
@@ -34,7 +34,7 @@ use std::sync::Arc;
 use std::collections::HashMap;
 use futures::future::Ready;
 use hash_db::Hasher;
-use primitives::storage::well_known_keys;
+use primitives::{storage::well_known_keys, traits::KeyStorePtr};
 use runtime_primitives::traits::{
 	Block as BlockT, NumberFor
 };
@@ -68,6 +68,7 @@ pub struct TestClientBuilder<Executor, Backend, G: GenesisInit = ()> {
 	genesis_init: G,
 	child_storage_extension: HashMap<Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>>,
 	backend: Arc<Backend>,
+	keystore: Option<KeyStorePtr>,
 	_executor: std::marker::PhantomData<Executor>,
 }
 
@@ -108,6 +109,7 @@ impl<Executor, Backend, G: GenesisInit> TestClientBuilder<
 			execution_strategies: ExecutionStrategies::default(),
 			child_storage_extension: Default::default(),
 			genesis_init: Default::default(),
+			keystore: None,
 			_executor: Default::default(),
 		}
 	}
@@ -144,6 +146,12 @@ impl<Executor, Backend, G: GenesisInit> TestClientBuilder<
 		self
 	}
 
+	/// Set the keystore that is available to the runtime.
+	pub fn set_keystore(mut self, keystore: KeyStorePtr) -> Self {
+		self.keystore = Some(keystore);
+		self
+	}
+
 	/// Build the test client with the given native executor.
 	pub fn build_with_executor<Block, RuntimeApi>(
 		self,
@@ -213,7 +221,7 @@ impl<E, Backend, G: GenesisInit> TestClientBuilder<
 		Block: BlockT<Hash=<Blake2Hasher as Hasher>::Out>,
 	{
 		let executor = executor.into().unwrap_or_else(|| executor::NativeExecutor::new(None));
-		let executor = LocalCallExecutor::new(self.backend.clone(), executor);
+		let executor = LocalCallExecutor::new(self.backend.clone(), executor, self.keystore.clone());
 
 		self.build_with_executor(executor)
 	}
@@ -27,6 +27,8 @@ pub trait BlockBuilderExt {
 	fn push_transfer(&mut self, transfer: runtime::Transfer) -> Result<(), client::error::Error>;
 	/// Add storage change extrinsic to the block.
 	fn push_storage_change(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) -> Result<(), client::error::Error>;
+	/// Add GRANDPA authority set change extrinsic to the block.
+	fn push_grandpa_change(&mut self, change: runtime::GrandpaChange) -> Result<(), client::error::Error>;
 }
 
 impl<'a, A> BlockBuilderExt for client::block_builder::BlockBuilder<'a, runtime::Block, A> where
@@ -40,4 +42,8 @@ impl<'a, A> BlockBuilderExt for client::block_builder::BlockBuilder<'a, runtime:
 	fn push_storage_change(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) -> Result<(), client::error::Error> {
 		self.push(runtime::Extrinsic::StorageChange(key, value))
 	}
+
+	fn push_grandpa_change(&mut self, change: runtime::GrandpaChange) -> Result<(), client::error::Error> {
+		self.push(runtime::Extrinsic::GrandpaAuthoritiesChange(change))
+	}
 }
@@ -95,11 +95,16 @@ pub type LightExecutor = client::light::call_executor::RemoteOrLocalCallExecutor
 #[derive(Default)]
 pub struct GenesisParameters {
 	support_changes_trie: bool,
+	grandpa_authorities: Vec<(runtime::GrandpaId, u64)>,
+	epoch_duration: runtime::BlockNumber,
 }
 
 impl generic_test_client::GenesisInit for GenesisParameters {
 	fn genesis_storage(&self) -> (StorageOverlay, ChildrenStorageOverlay) {
-		let mut storage = genesis_config(self.support_changes_trie).genesis_map();
+		let mut config = genesis_config(self.support_changes_trie);
+		config.grandpa_authorities = self.grandpa_authorities.clone();
+		config.epoch_duration = self.epoch_duration;
+		let mut storage = config.genesis_map();
 
 		let state_root = <<<runtime::Block as BlockT>::Header as HeaderT>::Hashing as HashT>::trie_root(
 			storage.clone().into_iter()
@@ -145,6 +150,12 @@ pub trait TestClientBuilderExt<B>: Sized {
 	/// Enable or disable support for changes trie in genesis.
 	fn set_support_changes_trie(self, support_changes_trie: bool) -> Self;
 
+	/// Set the GRANDPA authorities in genesis.
+	fn set_grandpa_authorities(self, authorities: Vec<(runtime::GrandpaId, u64)>) -> Self;
+
+	/// Set the length of a BABE epoch in blocks. Zero, the default, disables epochs.
+	fn set_epoch_duration(self, epoch_duration: runtime::BlockNumber) -> Self;
+
 	/// Build the test client.
 	fn build(self) -> Client<B> {
 		self.build_with_longest_chain().0
@@ -165,6 +176,16 @@ impl<B> TestClientBuilderExt<B> for TestClientBuilder<
 		self
 	}
 
+	fn set_grandpa_authorities(mut self, authorities: Vec<(runtime::GrandpaId, u64)>) -> Self {
+		self.genesis_init_mut().grandpa_authorities = authorities;
+		self
+	}
+
+	fn set_epoch_duration(mut self, epoch_duration: runtime::BlockNumber) -> Self {
+		self.genesis_init_mut().epoch_duration = epoch_duration;
+		self
+	}
+
 	fn build_with_longest_chain(self) -> (Client<B>, client::LongestChain<B, runtime::Block>) {
 		self.build_with_native_executor(None)
 	}
@@ -199,7 +220,7 @@ pub fn new_light() -> client::Client<LightBackend, LightExecutor, runtime::Block
 	let executor = NativeExecutor::new(None);
 	let fetcher = Arc::new(LightFetcher);
 	let remote_call_executor = client::light::call_executor::RemoteCallExecutor::new(blockchain.clone(), fetcher);
-	let local_call_executor = client::LocalCallExecutor::new(backend.clone(), executor);
+	let local_call_executor = client::LocalCallExecutor::new(backend.clone(), executor, None);
 	let call_executor = LightExecutor::new(backend.clone(), remote_call_executor, local_call_executor);
 
 	TestClientBuilder::with_backend(backend)
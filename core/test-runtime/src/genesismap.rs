@@ -18,7 +18,7 @@
 
 use std::collections::HashMap;
 use runtime_io::{blake2_256, twox_128};
-use super::{AuthorityId, AccountId, WASM_BINARY};
+use super::{AuthorityId, AccountId, GrandpaId, BlockNumber, WASM_BINARY};
 use parity_codec::{Encode, KeyedVec, Joiner};
 use primitives::{ChangesTrieConfiguration, map, storage::well_known_keys};
 use runtime_primitives::traits::Block;
@@ -28,6 +28,9 @@ pub struct GenesisConfig {
 	pub changes_trie_config: Option<ChangesTrieConfiguration>,
 	pub authorities: Vec<AuthorityId>,
 	pub balances: Vec<(AccountId, u64)>,
+	pub grandpa_authorities: Vec<(GrandpaId, u64)>,
+	/// The length of a BABE epoch in blocks, zero disables epochs.
+	pub epoch_duration: BlockNumber,
 }
 
 impl GenesisConfig {
@@ -44,6 +47,8 @@ impl GenesisConfig {
 			},
 			authorities: authorities.clone(),
 			balances: endowed_accounts.into_iter().map(|a| (a, balance)).collect(),
+			grandpa_authorities: Vec::new(),
+			epoch_duration: 0,
 		}
 	}
 
@@ -61,6 +66,12 @@ impl GenesisConfig {
 			map.insert(well_known_keys::CHANGES_TRIE_CONFIG.to_vec(), changes_trie_config.encode());
 		}
 		map.insert(twox_128(&b"sys:auth"[..])[..].to_vec(), self.authorities.encode());
+		if !self.grandpa_authorities.is_empty() {
+			map.insert(twox_128(&b"sys:grandpa_auth"[..])[..].to_vec(), self.grandpa_authorities.encode());
+		}
+		if self.epoch_duration > 0 {
+			map.insert(twox_128(&b"sys:epoch_duration"[..])[..].to_vec(), self.epoch_duration.encode());
+		}
 		map
 	}
 }
@@ -36,6 +36,10 @@ use substrate_client::{
 use runtime_primitives::{
 	ApplyResult,
 	create_runtime_str,
+	impl_opaque_keys,
+	key_types,
+	KeyTypeId,
+	generic::OpaqueDigestItemId,
 	transaction_validity::{TransactionValidity, ValidTransaction},
 	traits::{
 		BlindCheckable, BlakeTwo256, Block as BlockT, Extrinsic as ExtrinsicT,
@@ -44,17 +48,27 @@ use runtime_primitives::{
 };
 use runtime_version::RuntimeVersion;
 pub use primitives::hash::H256;
-use primitives::{sr25519, OpaqueMetadata};
+use primitives::{ed25519, sr25519, OpaqueMetadata};
 #[cfg(any(feature = "std", test))]
 use runtime_version::NativeVersion;
 use inherents::{CheckInherentsResult, InherentData};
 use cfg_if::cfg_if;
+use fg_primitives::ScheduledChange;
 pub use consensus_babe::AuthorityId;
 
 // Ensure Babe and Aura use the same crypto to simplify things a bit.
 pub type AuraId = AuthorityId;
 // Ensure Babe and Aura use the same crypto to simplify things a bit.
 pub type BabeId = AuthorityId;
+/// The identity of a GRANDPA authority.
+pub type GrandpaId = fg_primitives::AuthorityId;
+
+impl_opaque_keys! {
+	pub struct SessionKeys {
+		#[id(key_types::ED25519)]
+		pub ed25519: ed25519::Public,
+	}
+}
 
 // Inlucde the WASM binary
 #[cfg(feature = "std")]
@@ -101,6 +115,45 @@ impl Transfer {
 			.expect("Creates keyring from public key.").sign(&self.encode()).into();
 		Extrinsic::Transfer(self, signature)
 	}
+
+	/// Convert into a signed extrinsic that is executed with the given `options`.
+	#[cfg(feature = "std")]
+	pub fn into_signed_tx_with_options(self, options: TransferOptions) -> Extrinsic {
+		let signature = keyring::AccountKeyring::from_public(&self.from)
+			.expect("Creates keyring from public key.").sign(&self.encode()).into();
+		Extrinsic::TransferWithOptions(self, signature, options)
+	}
+}
+
+/// Options that change how a transfer is validated and executed.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TransferOptions {
+	/// Fail with `ExhaustsResources` if the transfer is not the first extrinsic of the block.
+	pub exhaust_resources_when_not_first: bool,
+	/// Whether the transfer should be propagated to other peers.
+	pub propagate: bool,
+}
+
+impl Default for TransferOptions {
+	fn default() -> Self {
+		TransferOptions {
+			exhaust_resources_when_not_first: false,
+			propagate: true,
+		}
+	}
+}
+
+/// A GRANDPA authority set change.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct GrandpaChange {
+	/// The new authorities, along with their weights.
+	pub next_authorities: Vec<(GrandpaId, u64)>,
+	/// The number of blocks to delay the change.
+	pub delay: BlockNumber,
+	/// The median last finalized block number if the change is forced, `None` otherwise.
+	pub forced: Option<BlockNumber>,
 }
 
 /// Extrinsic for test-runtime.
@@ -111,6 +164,8 @@ pub enum Extrinsic {
 	Transfer(Transfer, AccountSignature),
 	IncludeData(Vec<u8>),
 	StorageChange(Vec<u8>, Option<Vec<u8>>),
+	TransferWithOptions(Transfer, AccountSignature, TransferOptions),
+	GrandpaAuthoritiesChange(GrandpaChange),
 }
 
 #[cfg(feature = "std")]
@@ -135,6 +190,14 @@ impl BlindCheckable for Extrinsic {
 			},
 			Extrinsic::IncludeData(_) => Err(runtime_primitives::BAD_SIGNATURE),
 			Extrinsic::StorageChange(key, value) => Ok(Extrinsic::StorageChange(key, value)),
+			Extrinsic::TransferWithOptions(transfer, signature, options) => {
+				if runtime_primitives::verify_encoded_lazy(&signature, &transfer, &transfer.from) {
+					Ok(Extrinsic::TransferWithOptions(transfer, signature, options))
+				} else {
+					Err(runtime_primitives::BAD_SIGNATURE)
+				}
+			},
+			Extrinsic::GrandpaAuthoritiesChange(change) => Ok(Extrinsic::GrandpaAuthoritiesChange(change)),
 		}
 	}
 }
@@ -159,6 +222,7 @@ impl Extrinsic {
 	pub fn transfer(&self) -> &Transfer {
 		match self {
 			Extrinsic::Transfer(ref transfer, _) => transfer,
+			Extrinsic::TransferWithOptions(ref transfer, _, _) => transfer,
 			_ => panic!("cannot convert to transfer ref"),
 		}
 	}
@@ -321,6 +385,12 @@ fn benchmark_add_one(i: u64) -> u64 {
 #[cfg(not(feature = "std"))]
 static BENCHMARK_ADD_ONE: runtime_io::ExchangeableFunction<fn(u64) -> u64> = runtime_io::ExchangeableFunction::new(benchmark_add_one);
 
+/// Returns the first GRANDPA log of the given digest.
+fn grandpa_log(digest: &Digest) -> Option<fg_primitives::ConsensusLog<BlockNumber>> {
+	let id = OpaqueDigestItemId::Consensus(&fg_primitives::GRANDPA_ENGINE_ID);
+	digest.convert_first(|l| l.try_to::<fg_primitives::ConsensusLog<BlockNumber>>(id))
+}
+
 fn code_using_trie() -> u64 {
 	let pairs = [
 		(b"0103000000000000000464".to_vec(), b"0400000000".to_vec()),
@@ -484,6 +554,23 @@ cfg_if! {
 					}
 				}
 				fn authorities() -> Vec<BabeId> { system::authorities() }
+				fn epoch() -> consensus_babe::Epoch { system::babe_epoch() }
+			}
+
+			impl fg_primitives::GrandpaApi<Block> for Runtime {
+				fn grandpa_pending_change(digest: &Digest) -> Option<ScheduledChange<BlockNumber>> {
+					grandpa_log(digest).and_then(|log| log.try_into_change())
+				}
+
+				fn grandpa_forced_change(digest: &Digest)
+					-> Option<(BlockNumber, ScheduledChange<BlockNumber>)>
+				{
+					grandpa_log(digest).and_then(|log| log.try_into_forced_change())
+				}
+
+				fn grandpa_authorities() -> Vec<(GrandpaId, u64)> {
+					system::grandpa_authorities()
+				}
 			}
 
 			impl offchain_primitives::OffchainWorkerApi<Block> for Runtime {
@@ -492,6 +579,23 @@ cfg_if! {
 					runtime_io::submit_transaction(&ex).unwrap();
 				}
 			}
+
+			impl session::SessionKeys<Block> for Runtime {
+				fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
+					let seed = seed.as_ref().map(|s| rstd::str::from_utf8(s).expect("Seed is an utf8 string"));
+					SessionKeys::generate(seed)
+				}
+
+				fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+					SessionKeys::decode_into_raw_public_keys(&encoded)
+				}
+			}
+
+			impl system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
+				fn account_nonce(account: AccountId) -> Index {
+					system::nonce_of(account)
+				}
+			}
 		}
 	} else {
 		impl_runtime_apis! {
@@ -629,6 +733,23 @@ cfg_if! {
 					}
 				}
 				fn authorities() -> Vec<BabeId> { system::authorities() }
+				fn epoch() -> consensus_babe::Epoch { system::babe_epoch() }
+			}
+
+			impl fg_primitives::GrandpaApi<Block> for Runtime {
+				fn grandpa_pending_change(digest: &Digest) -> Option<ScheduledChange<BlockNumber>> {
+					grandpa_log(digest).and_then(|log| log.try_into_change())
+				}
+
+				fn grandpa_forced_change(digest: &Digest)
+					-> Option<(BlockNumber, ScheduledChange<BlockNumber>)>
+				{
+					grandpa_log(digest).and_then(|log| log.try_into_forced_change())
+				}
+
+				fn grandpa_authorities() -> Vec<(GrandpaId, u64)> {
+					system::grandpa_authorities()
+				}
 			}
 
 			impl offchain_primitives::OffchainWorkerApi<Block> for Runtime {
@@ -637,6 +758,141 @@ cfg_if! {
 					runtime_io::submit_transaction(&ex).unwrap()
 				}
 			}
+
+			impl session::SessionKeys<Block> for Runtime {
+				fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
+					let seed = seed.as_ref().map(|s| rstd::str::from_utf8(s).expect("Seed is an utf8 string"));
+					SessionKeys::generate(seed)
+				}
+
+				fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+					SessionKeys::decode_into_raw_public_keys(&encoded)
+				}
+			}
+
+			impl system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
+				fn account_nonce(account: AccountId) -> Index {
+					system::nonce_of(account)
+				}
+			}
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use substrate_test_runtime_client::{
+		prelude::*,
+		consensus::BlockOrigin,
+		runtime::{GrandpaChange, Header, Transfer, TransferOptions},
+	};
+	use runtime_primitives::{ApplyError, generic::{BlockId, OpaqueDigestItemId}, traits::ProvideRuntimeApi};
+	use consensus_babe::{BABE_ENGINE_ID, BabeApi, ConsensusLog as BabeConsensusLog};
+	use fg_primitives::{GrandpaApi, ScheduledChange};
+	use keyring::ed25519::Keyring as GrandpaKeyring;
+
+	#[test]
+	fn forced_grandpa_change_is_enacted_through_the_client() {
+		let genesis_authorities = vec![(GrandpaKeyring::Alice.into(), 1)];
+		let next_authorities = vec![(GrandpaKeyring::Bob.into(), 1)];
+		let client = TestClientBuilder::new()
+			.set_grandpa_authorities(genesis_authorities.clone())
+			.build();
+		let authorities_at = |number| client.runtime_api().grandpa_authorities(&BlockId::Number(number)).unwrap();
+		assert_eq!(authorities_at(0), genesis_authorities);
+
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_grandpa_change(GrandpaChange {
+			next_authorities: next_authorities.clone(),
+			delay: 1,
+			forced: Some(0),
+		}).unwrap();
+		let block = builder.bake().unwrap();
+
+		let api = client.runtime_api();
+		let at = BlockId::Number(0);
+		assert_eq!(api.grandpa_pending_change(&at, &block.header.digest).unwrap(), None);
+		assert_eq!(
+			api.grandpa_forced_change(&at, &block.header.digest).unwrap(),
+			Some((0, ScheduledChange { next_authorities: next_authorities.clone(), delay: 1 })),
+		);
+
+		client.import(BlockOrigin::Own, block).unwrap();
+		assert_eq!(authorities_at(1), genesis_authorities);
+
+		let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, block).unwrap();
+		assert_eq!(authorities_at(2), next_authorities);
+	}
+
+	#[test]
+	fn next_epoch_is_announced_through_the_client() {
+		let client = TestClientBuilder::new().set_epoch_duration(2).build();
+		let babe_log = |header: &Header| header.digest.logs().iter()
+			.find_map(|l| l.try_to::<BabeConsensusLog>(OpaqueDigestItemId::Consensus(&BABE_ENGINE_ID)));
+
+		let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+		assert!(babe_log(&block.header).is_none());
+		client.import(BlockOrigin::Own, block).unwrap();
+
+		let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+		match babe_log(&block.header) {
+			Some(BabeConsensusLog::NextEpochData(start_slot, _, _)) => assert_eq!(start_slot, 6),
+			_ => panic!("next epoch data is announced at the end of an epoch"),
+		}
+		client.import(BlockOrigin::Own, block).unwrap();
+	}
+
+	#[test]
+	fn epoch_advances_with_the_slot() {
+		let client = TestClientBuilder::new().set_epoch_duration(2).build();
+		let epoch_at = |number| {
+			let epoch = client.runtime_api().epoch(&BlockId::Number(number)).unwrap();
+			(epoch.epoch_index, epoch.start_slot, epoch.duration)
+		};
+		assert_eq!(epoch_at(0), (0, 0, 2));
+
+		// without a BABE pre-digest, every block takes one slot.
+		for _ in 0..4 {
+			let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+			client.import(BlockOrigin::Own, block).unwrap();
+		}
+		assert_eq!(epoch_at(1), (0, 0, 2));
+		assert_eq!(epoch_at(2), (1, 2, 2));
+		assert_eq!(epoch_at(3), (1, 2, 2));
+		assert_eq!(epoch_at(4), (2, 4, 2));
+	}
+
+	#[test]
+	fn epoch_never_ends_when_epochs_are_disabled() {
+		let client = TestClientBuilder::new().build();
+		let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, block).unwrap();
+
+		let epoch = client.runtime_api().epoch(&BlockId::Number(1)).unwrap();
+		assert_eq!((epoch.epoch_index, epoch.start_slot, epoch.duration), (0, 0, u64::max_value()));
+	}
+
+	#[test]
+	fn transfer_exhausting_resources_is_rejected_by_the_block_builder() {
+		let client = TestClientBuilder::new().build();
+		let transfer = |from: AccountKeyring| Transfer {
+			from: from.into(),
+			to: AccountKeyring::Charlie.into(),
+			amount: 1,
+			nonce: 0,
+		};
+		let options = TransferOptions { exhaust_resources_when_not_first: true, propagate: true };
+
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push(transfer(AccountKeyring::Alice).into_signed_tx()).unwrap();
+		match builder.push(transfer(AccountKeyring::Bob).into_signed_tx_with_options(options.clone())) {
+			Err(substrate_client::error::Error::ApplyExtrinsicFailed(ApplyError::ExhaustsResources)) => {},
+			_ => panic!("only the first extrinsic of a block can exhaust the resources"),
+		}
+
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push(transfer(AccountKeyring::Bob).into_signed_tx_with_options(options)).unwrap();
+		client.import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+	}
+}
@@ -22,14 +22,19 @@ use runtime_io::{storage_root, enumerated_trie_root, storage_changes_root, twox_
 use runtime_support::storage::{self, StorageValue, StorageMap};
 use runtime_support::storage_items;
 use runtime_primitives::traits::{Hash as HashT, BlakeTwo256, Header as _};
-use runtime_primitives::generic;
+use runtime_primitives::generic::{self, OpaqueDigestItemId};
 use runtime_primitives::{ApplyError, ApplyOutcome, ApplyResult};
 use runtime_primitives::transaction_validity::{TransactionValidity, ValidTransaction};
 use parity_codec::{KeyedVec, Encode};
 use super::{
-	AccountId, BlockNumber, Extrinsic, Transfer, H256 as Hash, Block, Header, Digest, AuthorityId
+	AccountId, BlockNumber, Extrinsic, Transfer, TransferOptions, H256 as Hash, Block, Header,
+	Digest, DigestItem, AuthorityId, GrandpaId, GrandpaChange,
 };
 use primitives::{Blake2Hasher, storage::well_known_keys};
+use consensus_babe::{
+	BABE_ENGINE_ID, ConsensusLog as BabeConsensusLog, Epoch, SlotNumber, VRF_OUTPUT_LENGTH, VRF_PROOF_LENGTH,
+};
+use fg_primitives::{GRANDPA_ENGINE_ID, ConsensusLog as GrandpaConsensusLog, ScheduledChange};
 
 const NONCE_OF: &[u8] = b"nonce:";
 const BALANCE_OF: &[u8] = b"balance:";
@@ -42,6 +47,14 @@ storage_items! {
 	NewAuthorities: b"sys:new_auth" => Vec<AuthorityId>;
 	StorageDigest: b"sys:digest" => Digest;
 	Authorities get(authorities): b"sys:auth" => default Vec<AuthorityId>;
+	NewGrandpaChange: b"sys:new_grandpa_change" => GrandpaChange;
+	// The block number a GRANDPA change was signaled at and the change itself.
+	PendingGrandpaChange: b"sys:grandpa_pending" => (BlockNumber, GrandpaChange);
+	GrandpaAuthorities get(grandpa_authorities): b"sys:grandpa_auth" => default Vec<(GrandpaId, u64)>;
+	// The length of a BABE epoch in slots. Zero disables epochs.
+	EpochDuration get(epoch_duration): b"sys:epoch_duration" => default BlockNumber;
+	// The slot of the last block, taken from its BABE pre-digest or, without one, its number.
+	CurrentSlot get(current_slot): b"sys:slot" => default SlotNumber;
 }
 
 pub fn balance_of_key(who: AccountId) -> Vec<u8> {
@@ -64,6 +77,26 @@ pub fn initialize_block(header: &Header) {
 	storage::unhashed::put(well_known_keys::EXTRINSIC_INDEX, &0u32);
 }
 
+/// The BABE epoch that children of the last block are authored in.
+pub fn babe_epoch() -> Epoch {
+	let (epoch_index, start_slot, duration) = match epoch_duration() {
+		// a single epoch that never ends.
+		0 => (0, 0, SlotNumber::max_value()),
+		duration => {
+			let epoch_index = current_slot() / duration;
+			(epoch_index, epoch_index * duration, duration)
+		},
+	};
+
+	Epoch {
+		epoch_index,
+		start_slot,
+		duration,
+		authorities: authorities().into_iter().map(|a| (a, 1)).collect(),
+		randomness: [0; VRF_OUTPUT_LENGTH],
+	}
+}
+
 pub fn get_block_number() -> Option<BlockNumber> {
 	Number::get()
 }
@@ -111,7 +144,7 @@ fn execute_block_with_state_root_handler(
 		storage::unhashed::kill(well_known_keys::EXTRINSIC_INDEX);
 	});
 
-	let o_new_authorities = <NewAuthorities>::take();
+	let consensus_logs = finalize_consensus(header.number, header.parent_hash, &header.digest);
 
 	if let Mode::Overwrite = mode {
 		header.state_root = storage_root().into();
@@ -127,10 +160,7 @@ fn execute_block_with_state_root_handler(
 	if let Some(storage_changes_root) = storage_changes_root(header.parent_hash.into()) {
 		digest.push(generic::DigestItem::ChangesTrieRoot(storage_changes_root.into()));
 	}
-	if let Some(new_authorities) = o_new_authorities {
-		digest.push(generic::DigestItem::Consensus(*b"aura", new_authorities.encode()));
-		digest.push(generic::DigestItem::Consensus(*b"babe", new_authorities.encode()));
-	}
+	consensus_logs.into_iter().for_each(|log| digest.push(log));
 }
 
 /// The block executor.
@@ -176,12 +206,17 @@ pub fn validate_transaction(utx: Extrinsic) -> TransactionValidity {
 		p
 	};
 
+	let propagate = match utx {
+		Extrinsic::TransferWithOptions(_, _, ref options) => options.propagate,
+		_ => true,
+	};
+
 	TransactionValidity::Valid(ValidTransaction {
 		priority: tx.amount,
 		requires,
 		provides,
 		longevity: 64,
-		propagate: true,
+		propagate,
 	})
 }
 
@@ -206,7 +241,7 @@ pub fn finalize_block() -> Header {
 	let parent_hash = <ParentHash>::take();
 	let mut digest = <StorageDigest>::take().expect("StorageDigest is set by `initialize_block`");
 
-	let o_new_authorities = <NewAuthorities>::take();
+	let consensus_logs = finalize_consensus(number, parent_hash, &digest);
 	// This MUST come after all changes to storage are done.  Otherwise we will fail the
 	// “Storage root does not match that calculated” assertion.
 	let storage_root = BlakeTwo256::storage_root();
@@ -215,11 +250,7 @@ pub fn finalize_block() -> Header {
 	if let Some(storage_changes_root) = storage_changes_root {
 		digest.push(generic::DigestItem::ChangesTrieRoot(storage_changes_root));
 	}
-
-	if let Some(new_authorities) = o_new_authorities {
-		digest.push(generic::DigestItem::Consensus(*b"aura", new_authorities.encode()));
-		digest.push(generic::DigestItem::Consensus(*b"babe", new_authorities.encode()));
-	}
+	consensus_logs.into_iter().for_each(|log| digest.push(log));
 
 	Header {
 		number,
@@ -230,6 +261,64 @@ pub fn finalize_block() -> Header {
 	}
 }
 
+/// Returns the slot of the BABE pre-digest of a block, if it has one.
+fn babe_slot(digest: &Digest) -> Option<SlotNumber> {
+	let id = OpaqueDigestItemId::PreRuntime(&BABE_ENGINE_ID);
+	digest.convert_first(|l| l.try_to::<([u8; VRF_OUTPUT_LENGTH], [u8; VRF_PROOF_LENGTH], u64, SlotNumber)>(id))
+		.map(|(_, _, _, slot)| slot)
+}
+
+/// Apply the consensus related changes of block `number` and return the digest items
+/// announcing them.
+///
+/// This MUST be called before the storage root is calculated.
+fn finalize_consensus(number: BlockNumber, parent_hash: Hash, digest: &Digest) -> Vec<DigestItem> {
+	let mut logs = Vec::new();
+
+	if let Some(new_authorities) = <NewAuthorities>::take() {
+		logs.push(generic::DigestItem::Consensus(*b"aura", new_authorities.encode()));
+		logs.push(generic::DigestItem::Consensus(*b"babe", new_authorities.encode()));
+	}
+
+	if let Some(change) = <NewGrandpaChange>::take() {
+		let scheduled = ScheduledChange {
+			next_authorities: change.next_authorities.clone(),
+			delay: change.delay,
+		};
+		let log = match change.forced {
+			Some(median) => GrandpaConsensusLog::ForcedChange(median, scheduled),
+			None => GrandpaConsensusLog::ScheduledChange(scheduled),
+		};
+		logs.push(generic::DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode()));
+		<PendingGrandpaChange>::put((number, change));
+	}
+
+	if let Some((scheduled_at, change)) = <PendingGrandpaChange>::get() {
+		if number >= scheduled_at + change.delay {
+			<GrandpaAuthorities>::put(change.next_authorities);
+			<PendingGrandpaChange>::kill();
+		}
+	}
+
+	// without a BABE pre-digest, assume one slot per block.
+	let slot = babe_slot(digest).unwrap_or(number);
+	let parent_slot = <CurrentSlot>::get();
+	<CurrentSlot>::put(slot);
+
+	let epoch_duration = epoch_duration();
+	if epoch_duration > 0 && slot / epoch_duration > parent_slot / epoch_duration {
+		// the first block of an epoch announces the epoch after next.
+		let epoch_index = slot / epoch_duration;
+		let start_slot = (epoch_index + 2) * epoch_duration;
+		let authorities = authorities().into_iter().map(|a| (a, 1)).collect();
+		let randomness = blake2_256(&(epoch_index, parent_hash).encode());
+		let log = BabeConsensusLog::NextEpochData(start_slot, authorities, randomness);
+		logs.push(generic::DigestItem::Consensus(BABE_ENGINE_ID, log.encode()));
+	}
+
+	logs
+}
+
 #[inline(always)]
 fn check_signature(utx: &Extrinsic) -> Result<(), ApplyError> {
 	use runtime_primitives::traits::BlindCheckable;
@@ -244,6 +333,9 @@ fn execute_transaction_backend(utx: &Extrinsic) -> ApplyResult {
 		Extrinsic::AuthoritiesChange(ref new_auth) => execute_new_authorities_backend(new_auth),
 		Extrinsic::IncludeData(_) => Ok(ApplyOutcome::Success),
 		Extrinsic::StorageChange(key, value) => execute_storage_change(key, value.as_ref().map(|v| &**v)),
+		Extrinsic::TransferWithOptions(ref transfer, _, ref options) =>
+			execute_transfer_with_options_backend(transfer, options),
+		Extrinsic::GrandpaAuthoritiesChange(ref change) => execute_grandpa_change_backend(change),
 	}
 }
 
@@ -273,12 +365,26 @@ fn execute_transfer_backend(tx: &Transfer) -> ApplyResult {
 	Ok(ApplyOutcome::Success)
 }
 
+fn execute_transfer_with_options_backend(tx: &Transfer, options: &TransferOptions) -> ApplyResult {
+	let extrinsic_index: u32 = storage::unhashed::get(well_known_keys::EXTRINSIC_INDEX).unwrap_or_default();
+	if options.exhaust_resources_when_not_first && extrinsic_index != 0 {
+		return Err(ApplyError::ExhaustsResources)
+	}
+
+	execute_transfer_backend(tx)
+}
+
 fn execute_new_authorities_backend(new_authorities: &[AuthorityId]) -> ApplyResult {
 	let new_authorities: Vec<AuthorityId> = new_authorities.iter().cloned().collect();
 	<NewAuthorities>::put(new_authorities);
 	Ok(ApplyOutcome::Success)
 }
 
+fn execute_grandpa_change_backend(change: &GrandpaChange) -> ApplyResult {
+	<NewGrandpaChange>::put(change);
+	Ok(ApplyOutcome::Success)
+}
+
 fn execute_storage_change(key: &[u8], value: Option<&[u8]>) -> ApplyResult {
 	match value {
 		Some(value) => storage::unhashed::put_raw(key, value),
@@ -454,4 +560,93 @@ mod tests {
 			WasmExecutor::new().call(ext, 8, &WASM_BINARY, "Core_execute_block", &b.encode()).unwrap();
 		})
 	}
+
+	fn new_block(number: BlockNumber, extrinsics: Vec<Extrinsic>) -> Block {
+		Block {
+			header: Header {
+				parent_hash: [69u8; 32].into(),
+				number,
+				state_root: Default::default(),
+				extrinsics_root: Default::default(),
+				digest: Default::default(),
+			},
+			extrinsics,
+		}
+	}
+
+	#[test]
+	fn grandpa_change_is_signaled_and_enacted() {
+		let next_authorities = vec![(keyring::ed25519::Keyring::Dave.into(), 1)];
+		let change = GrandpaChange { next_authorities: next_authorities.clone(), delay: 1, forced: None };
+		let mut b = new_block(1, vec![Extrinsic::GrandpaAuthoritiesChange(change)]);
+
+		with_externalities(&mut new_test_ext(), || {
+			polish_block(&mut b);
+			assert!(grandpa_authorities().is_empty());
+			assert!(<PendingGrandpaChange>::get().is_some());
+
+			let scheduled = ScheduledChange { next_authorities: next_authorities.clone(), delay: 1 };
+			assert_eq!(
+				crate::grandpa_log(&b.header.digest),
+				Some(GrandpaConsensusLog::ScheduledChange(scheduled)),
+			);
+
+			let mut b2 = new_block(2, vec![]);
+			b2.header.parent_hash = b.header.hash();
+			polish_block(&mut b2);
+			assert_eq!(grandpa_authorities(), next_authorities);
+			assert!(<PendingGrandpaChange>::get().is_none());
+		});
+	}
+
+	#[test]
+	fn next_epoch_is_announced() {
+		let mut t = new_test_ext();
+		t.insert(twox_128(b"sys:epoch_duration").to_vec(), 2u64.encode());
+
+		with_externalities(&mut t, || {
+			let babe_log = |block: &Block| block.header.digest.logs().iter()
+				.find_map(|l| l.try_to::<BabeConsensusLog>(
+					runtime_primitives::generic::OpaqueDigestItemId::Consensus(&BABE_ENGINE_ID)
+				));
+
+			let mut b1 = new_block(1, vec![]);
+			polish_block(&mut b1);
+			assert!(babe_log(&b1).is_none());
+
+			let mut b2 = new_block(2, vec![]);
+			polish_block(&mut b2);
+			match babe_log(&b2) {
+				Some(BabeConsensusLog::NextEpochData(start_slot, authorities, _)) => {
+					assert_eq!(start_slot, 6);
+					assert_eq!(authorities.len(), 3);
+				},
+				_ => panic!("next epoch data is announced at the end of an epoch"),
+			}
+		});
+	}
+
+	#[test]
+	fn transfer_can_exhaust_resources_when_not_first() {
+		let options = TransferOptions { exhaust_resources_when_not_first: true, propagate: false };
+		let tx = Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 1,
+			nonce: 0,
+		}.into_signed_tx_with_options(options);
+
+		with_externalities(&mut new_test_ext(), || {
+			match validate_transaction(tx.clone()) {
+				TransactionValidity::Valid(v) => assert!(!v.propagate),
+				_ => panic!("transfer is valid"),
+			}
+
+			storage::unhashed::put(well_known_keys::EXTRINSIC_INDEX, &1u32);
+			assert_eq!(execute_transaction(tx.clone()), Err(ApplyError::ExhaustsResources));
+
+			storage::unhashed::put(well_known_keys::EXTRINSIC_INDEX, &0u32);
+			assert_eq!(execute_transaction(tx), Ok(ApplyOutcome::Success));
+		});
+	}
 }
@@ -0,0 +1,427 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tracing of block execution.
+//!
+//! While a closure runs in [`trace`](fn.trace.html), the spans entered on the current thread
+//! (host function calls, spans entered by the runtime through `runtime_io`) and its storage
+//! accesses are recorded. Outside of `trace` entering spans and reporting storage accesses is a
+//! no-op, so the hooks can stay in place during normal block import.
+//!
+//! Spans and events are filtered by target: a comma-separated list of target prefixes, where an
+//! empty list matches every target. Storage accesses are reported under the `state` target and
+//! can be further filtered by a comma-separated list of hex-encoded key prefixes.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Instant;
+
+use rustc_hex::{FromHex, ToHex};
+use serde::{Serialize, Deserialize};
+
+/// The target of storage access events.
+pub const STORAGE_TARGET: &str = "state";
+
+/// The target of host function spans.
+pub const HOST_FUNCTION_TARGET: &str = "wasm_host";
+
+/// A span that was entered during tracing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+	/// The id of the span, unique within a trace.
+	pub id: u64,
+	/// The id of the innermost recorded span this one was entered in.
+	pub parent_id: Option<u64>,
+	/// The target of the span, e.g. the name of the module it was entered in.
+	pub target: String,
+	/// The name of the span.
+	pub name: String,
+	/// How long the span was entered, in nanoseconds.
+	pub exec_time_nanos: u64,
+}
+
+/// An event that happened during tracing, e.g. a storage access.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+	/// The id of the innermost recorded span the event happened in.
+	pub parent_id: Option<u64>,
+	/// The target of the event.
+	pub target: String,
+	/// The name of the event.
+	pub name: String,
+	/// The values attached to the event; storage keys and values are hex-encoded.
+	pub values: BTreeMap<String, String>,
+}
+
+/// The spans and events recorded by [`trace`](fn.trace.html), in the order they were entered or
+/// happened.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Traces {
+	/// The recorded spans.
+	pub spans: Vec<Span>,
+	/// The recorded events.
+	pub events: Vec<Event>,
+}
+
+/// The trace of the execution of a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace<Hash> {
+	/// The hash of the traced block.
+	pub block_hash: Hash,
+	/// The hash of its parent, on top of which it was executed.
+	pub parent_hash: Hash,
+	/// The targets the trace was filtered by.
+	pub tracing_targets: String,
+	/// The storage key prefixes the storage accesses were filtered by.
+	pub storage_keys: String,
+	/// The recorded spans.
+	pub spans: Vec<Span>,
+	/// The recorded events.
+	pub events: Vec<Event>,
+}
+
+/// A storage key prefix of the filter that isn't hex-encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidStorageKey(pub String);
+
+impl fmt::Display for InvalidStorageKey {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Invalid hex-encoded storage key prefix: {:?}", self.0)
+	}
+}
+
+impl std::error::Error for InvalidStorageKey {}
+
+struct Collector {
+	targets: Vec<String>,
+	storage_keys: Vec<Vec<u8>>,
+	next_id: u64,
+	/// The open spans: their id, index in `traces.spans` and when they were entered.
+	stack: Vec<(u64, usize, Instant)>,
+	traces: Traces,
+}
+
+impl Collector {
+	fn new(targets: &str, storage_keys: &str) -> Result<Self, InvalidStorageKey> {
+		Ok(Collector {
+			targets: split_list(targets).map(Into::into).collect(),
+			storage_keys: split_list(storage_keys)
+				.map(|key| {
+					key.trim_start_matches("0x").from_hex().map_err(|_| InvalidStorageKey(key.into()))
+				})
+				.collect::<Result<_, _>>()?,
+			next_id: 1,
+			stack: Vec::new(),
+			traces: Traces::default(),
+		})
+	}
+
+	fn matches_target(&self, target: &str) -> bool {
+		self.targets.is_empty() || self.targets.iter().any(|t| target.starts_with(&t[..]))
+	}
+
+	fn matches_key(&self, key: &[u8]) -> bool {
+		self.storage_keys.is_empty() || self.storage_keys.iter().any(|k| key.starts_with(k))
+	}
+
+	fn parent_id(&self) -> Option<u64> {
+		self.stack.last().map(|(id, _, _)| *id)
+	}
+
+	fn enter(&mut self, target: &str, name: &str) -> u64 {
+		if !self.matches_target(target) {
+			return 0;
+		}
+		let id = self.next_id;
+		self.next_id += 1;
+		self.traces.spans.push(Span {
+			id,
+			parent_id: self.parent_id(),
+			target: target.into(),
+			name: name.into(),
+			exec_time_nanos: 0,
+		});
+		self.stack.push((id, self.traces.spans.len() - 1, Instant::now()));
+		id
+	}
+
+	/// Exit the span with the given id, and any spans that were entered in it and are still open.
+	fn exit(&mut self, id: u64) {
+		if let Some(position) = self.stack.iter().rposition(|(open, _, _)| *open == id) {
+			for (_, index, entered) in self.stack.drain(position..) {
+				self.traces.spans[index].exec_time_nanos = elapsed_nanos(entered);
+			}
+		}
+	}
+
+	fn exit_all(&mut self) {
+		for (_, index, entered) in self.stack.drain(..) {
+			self.traces.spans[index].exec_time_nanos = elapsed_nanos(entered);
+		}
+	}
+}
+
+fn split_list(list: &str) -> impl Iterator<Item=&str> {
+	list.split(',').map(str::trim).filter(|item| !item.is_empty())
+}
+
+fn elapsed_nanos(since: Instant) -> u64 {
+	let elapsed = since.elapsed();
+	elapsed.as_secs().saturating_mul(1_000_000_000).saturating_add(elapsed.subsec_nanos() as u64)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+	format!("0x{}", bytes.to_hex::<String>())
+}
+
+thread_local! {
+	static COLLECTOR: RefCell<Option<Collector>> = RefCell::new(None);
+}
+
+/// Run `f`, recording the spans entered and the storage accesses made on the current thread.
+///
+/// Only spans and events with a target starting with one of the comma-separated `targets` are
+/// recorded, and only storage accesses to keys starting with one of the comma-separated, hex
+/// encoded `storage_keys`. An empty list doesn't filter anything.
+///
+/// Fails without running `f` if one of the `storage_keys` isn't hex-encoded.
+pub fn trace<R, F: FnOnce() -> R>(
+	targets: &str,
+	storage_keys: &str,
+	f: F,
+) -> Result<(R, Traces), InvalidStorageKey> {
+	/// Restores the previous collector, even if `f` panics.
+	struct Restore(Option<Collector>);
+
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			let previous = self.0.take();
+			COLLECTOR.with(|collector| *collector.borrow_mut() = previous);
+		}
+	}
+
+	let new = Collector::new(targets, storage_keys)?;
+	let previous = COLLECTOR.with(|collector| collector.replace(Some(new)));
+	let restore = Restore(previous);
+	let result = f();
+	let collector = COLLECTOR.with(|collector| collector.borrow_mut().take());
+	drop(restore);
+
+	let traces = collector.map(|mut collector| {
+		collector.exit_all();
+		collector.traces
+	}).unwrap_or_default();
+	Ok((result, traces))
+}
+
+/// Whether the current thread is being traced.
+pub fn is_tracing() -> bool {
+	COLLECTOR.with(|collector| collector.borrow().is_some())
+}
+
+/// Enter a span with the given target and name.
+///
+/// Returns the id of the span, to be passed to [`exit_span`](fn.exit_span.html), or 0 if the
+/// current thread isn't being traced or the target is filtered out.
+pub fn enter_span(target: &str, name: &str) -> u64 {
+	COLLECTOR.with(|collector| {
+		collector.borrow_mut().as_mut().map_or(0, |collector| collector.enter(target, name))
+	})
+}
+
+/// Exit the span with the given id. Does nothing for 0.
+pub fn exit_span(id: u64) {
+	if id == 0 {
+		return;
+	}
+	COLLECTOR.with(|collector| {
+		if let Some(collector) = collector.borrow_mut().as_mut() {
+			collector.exit(id);
+		}
+	})
+}
+
+/// A span that is exited when dropped.
+pub struct SpanGuard(u64);
+
+impl Drop for SpanGuard {
+	fn drop(&mut self) {
+		exit_span(self.0)
+	}
+}
+
+/// Enter a span with the given target and name, which is exited when the returned guard is
+/// dropped.
+pub fn span(target: &str, name: &str) -> SpanGuard {
+	SpanGuard(enter_span(target, name))
+}
+
+/// Report an access to the storage entry under `key`, or under `key` in the given child storage.
+///
+/// The event is named `name` and has the `state` target. `value` is the value that was read or
+/// written, if any.
+pub fn storage_event(name: &str, child_storage_key: Option<&[u8]>, key: &[u8], value: Option<&[u8]>) {
+	COLLECTOR.with(|collector| {
+		let mut collector = collector.borrow_mut();
+		let collector = match collector.as_mut() {
+			Some(collector) => collector,
+			None => return,
+		};
+		if !collector.matches_target(STORAGE_TARGET) || !collector.matches_key(key) {
+			return;
+		}
+
+		let mut values = BTreeMap::new();
+		if let Some(child_storage_key) = child_storage_key {
+			values.insert("childStorageKey".into(), to_hex(child_storage_key));
+		}
+		values.insert("key".into(), to_hex(key));
+		if let Some(value) = value {
+			values.insert("value".into(), to_hex(value));
+		}
+		let parent_id = collector.parent_id();
+		collector.traces.events.push(Event {
+			parent_id,
+			target: STORAGE_TARGET.into(),
+			name: name.into(),
+			values,
+		});
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn nothing_is_recorded_outside_of_trace() {
+		assert!(!is_tracing());
+		assert_eq!(enter_span("runtime", "foo"), 0);
+		storage_event("get", None, b"key", None);
+		exit_span(1);
+
+		let ((), traces) = trace("", "", || assert!(is_tracing())).unwrap();
+		assert_eq!(traces, Traces::default());
+		assert!(!is_tracing());
+	}
+
+	#[test]
+	fn spans_and_events_are_nested() {
+		let ((), traces) = trace("", "", || {
+			let outer = enter_span("executive", "apply_extrinsic");
+			{
+				let _inner = span(HOST_FUNCTION_TARGET, "ext_get_storage_into");
+				storage_event("get", None, b"key", Some(b"value"));
+			}
+			storage_event("put", Some(b"child"), b"other", None);
+			exit_span(outer);
+			storage_event("clear", None, b"key", None);
+		}).unwrap();
+
+		let spans: Vec<_> = traces.spans.iter()
+			.map(|span| (span.id, span.parent_id, &span.target[..], &span.name[..]))
+			.collect();
+		assert_eq!(spans, vec![
+			(1, None, "executive", "apply_extrinsic"),
+			(2, Some(1), HOST_FUNCTION_TARGET, "ext_get_storage_into"),
+		]);
+
+		let events: Vec<_> = traces.events.iter()
+			.map(|event| (event.parent_id, &event.name[..], event.values.clone()))
+			.collect();
+		let values = |pairs: &[(&str, &str)]| pairs.iter()
+			.map(|(k, v)| (k.to_string(), v.to_string()))
+			.collect::<BTreeMap<_, _>>();
+		assert_eq!(events, vec![
+			(Some(2), "get", values(&[("key", "0x6b6579"), ("value", "0x76616c7565")])),
+			(Some(1), "put", values(&[("childStorageKey", "0x6368696c64"), ("key", "0x6f74686572")])),
+			(None, "clear", values(&[("key", "0x6b6579")])),
+		]);
+	}
+
+	#[test]
+	fn targets_and_storage_keys_are_filtered() {
+		let ((), traces) = trace("executive, state", "0x6b65", || {
+			let host = enter_span(HOST_FUNCTION_TARGET, "ext_twox_128");
+			assert_eq!(host, 0);
+			let outer = enter_span("executive", "initialize_block");
+			storage_event("get", None, b"key", None);
+			storage_event("get", None, b"other", None);
+			// exiting the outer span also exits the ones left open in it.
+			enter_span("executive", "inner");
+			exit_span(outer);
+			exit_span(host);
+		}).unwrap();
+
+		assert_eq!(traces.spans.len(), 2);
+		assert_eq!(traces.spans[1].parent_id, Some(1));
+		assert_eq!(traces.events.len(), 1);
+		assert_eq!(traces.events[0].values["key"], "0x6b6579");
+
+		let ((), traces) = trace("executive", "", || storage_event("get", None, b"key", None)).unwrap();
+		assert!(traces.events.is_empty());
+	}
+
+	#[test]
+	fn invalid_storage_keys_are_rejected() {
+		let mut called = false;
+		assert_eq!(
+			trace("", "0x6b65, 0xkey", || called = true),
+			Err(InvalidStorageKey("0xkey".into())),
+		);
+		assert!(!called);
+		assert!(!is_tracing());
+	}
+
+	#[test]
+	fn nested_trace_restores_outer_collector() {
+		let (inner, outer) = trace("", "", || {
+			enter_span("outer", "a");
+			let ((), inner) = trace("", "", || { enter_span("inner", "b"); }).unwrap();
+			enter_span("outer", "c");
+			inner
+		}).unwrap();
+		assert_eq!(inner.spans.iter().map(|s| &s.name[..]).collect::<Vec<_>>(), vec!["b"]);
+		assert_eq!(outer.spans.iter().map(|s| &s.name[..]).collect::<Vec<_>>(), vec!["a", "c"]);
+	}
+
+	#[test]
+	fn block_trace_serializes_to_camel_case() {
+		let trace = BlockTrace {
+			block_hash: 1u64,
+			parent_hash: 0u64,
+			tracing_targets: "state".into(),
+			storage_keys: String::new(),
+			spans: vec![Span {
+				id: 1,
+				parent_id: None,
+				target: "executive".into(),
+				name: "apply_extrinsic".into(),
+				exec_time_nanos: 5,
+			}],
+			events: vec![],
+		};
+		assert_eq!(
+			serde_json::to_string(&trace).unwrap(),
+			r#"{"blockHash":1,"parentHash":0,"tracingTargets":"state","storageKeys":"","spans":[{"id":1,"parentId":null,"target":"executive","name":"apply_extrinsic","execTimeNanos":5}],"events":[]}"#,
+		);
+	}
+}
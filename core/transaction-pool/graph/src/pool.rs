@@ -412,6 +412,12 @@ impl<B: ChainApi> Pool<B> {
 		self.pool.read().ready()
 	}
 
+	/// Get the transactions which are waiting for their requirements to be satisfied, in no
+	/// particular order.
+	pub fn futures(&self) -> Vec<TransactionFor<B>> {
+		self.pool.read().futures().cloned().map(Arc::new).collect()
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> base::Status {
 		self.pool.read().status()
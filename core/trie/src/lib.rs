@@ -22,6 +22,7 @@ mod error;
 mod node_header;
 mod node_codec;
 mod trie_stream;
+mod storage_proof;
 
 use rstd::boxed::Box;
 use rstd::vec::Vec;
@@ -32,6 +33,8 @@ pub use error::Error;
 pub use trie_stream::TrieStream;
 /// The Substrate format implementation of `NodeCodec`.
 pub use node_codec::NodeCodec;
+/// Storage proofs and their compact form.
+pub use storage_proof::{StorageProof, CompactProof, verify_storage_proof};
 /// Various re-exports from the `trie-db` crate.
 pub use trie_db::{Trie, TrieMut, DBValue, Recorder, Query};
 /// Various re-exports from the `memory-db` crate.
@@ -85,7 +88,7 @@ pub fn delta_trie_root<H: Hasher, I, A, B, DB>(
 	{
 		let mut trie = TrieDBMut::<H>::from_existing(&mut *db, &mut root)?;
 
-		for (key, change) in delta {
+		for (key, change) in sorted_delta(delta) {
 			match change {
 				Some(val) => trie.insert(key.as_ref(), val.as_ref())?,
 				None => trie.remove(key.as_ref())?,
@@ -96,6 +99,17 @@ pub fn delta_trie_root<H: Hasher, I, A, B, DB>(
 	Ok(root)
 }
 
+/// Sort the changes by key, so that they are applied to the trie in order and every branch is
+/// only loaded and encoded once. The sort is stable, so that the last change of a key still wins.
+fn sorted_delta<I, A, B>(delta: I) -> Vec<(A, Option<B>)> where
+	I: IntoIterator<Item = (A, Option<B>)>,
+	A: AsRef<[u8]> + Ord,
+{
+	let mut delta: Vec<_> = delta.into_iter().collect();
+	delta.sort_by(|a, b| a.0.cmp(&b.0));
+	delta
+}
+
 /// Read a value from the trie.
 pub fn read_trie_value<H: Hasher, DB: hash_db::HashDBRef<H, trie_db::DBValue>>(
 	db: &DB,
@@ -190,7 +204,7 @@ pub fn child_delta_trie_root<H: Hasher, I, A, B, DB>(
 	{
 		let mut trie = TrieDBMut::<H>::from_existing(&mut *db, &mut root)?;
 
-		for (key, change) in delta {
+		for (key, change) in sorted_delta(delta) {
 			match change {
 				Some(val) => trie.insert(key.as_ref(), val.as_ref())?,
 				None => trie.remove(key.as_ref())?,
@@ -618,4 +632,26 @@ mod tests {
 
 		assert_eq!(pairs, iter_pairs);
 	}
+
+	#[test]
+	fn delta_trie_root_applies_unsorted_changes_in_order() {
+		let delta = vec![
+			(b"dog".to_vec(), Some(b"puppy".to_vec())),
+			(b"doe".to_vec(), Some(b"reindeer".to_vec())),
+			(b"dogglesworth".to_vec(), Some(b"cat".to_vec())),
+			(b"dog".to_vec(), Some(b"hound".to_vec())),
+			(b"doe".to_vec(), None),
+		];
+
+		let mut mdb = MemoryDB::<Blake2Hasher>::default();
+		let mut root = Default::default();
+		TrieDBMut::<Blake2Hasher>::new(&mut mdb, &mut root);
+		let root = delta_trie_root::<Blake2Hasher, _, _, _, _>(&mut mdb, root, delta).unwrap();
+
+		let expected = trie_root::<Blake2Hasher, _, _, _>(vec![
+			(b"dog".to_vec(), b"hound".to_vec()),
+			(b"dogglesworth".to_vec(), b"cat".to_vec()),
+		]);
+		assert_eq!(root, expected);
+	}
 }
@@ -0,0 +1,372 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Storage proofs and their compact encoding.
+
+use rstd::boxed::Box;
+use rstd::vec::Vec;
+use rstd::collections::{btree_map::BTreeMap, btree_set::BTreeSet};
+use codec::{Encode, Decode, Compact, Input, Output};
+use hash_db::{Hasher, HashDB};
+use crate::{MemoryDB, TrieError, read_trie_value, take, error::Error, node_header::NodeHeader};
+
+/// Maximum nesting of the nodes of a `CompactProof`.
+///
+/// Every nested node consumes at least one nibble of the key, so this covers keys of up to 256
+/// bytes while bounding the recursion when expanding untrusted proofs.
+const MAX_NESTING: usize = 512;
+
+/// A set of trie nodes proving the values of some storage keys.
+///
+/// Nodes are kept deduplicated and ordered, so two proofs of the same nodes are equal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageProof {
+	trie_nodes: Vec<Vec<u8>>,
+}
+
+impl StorageProof {
+	/// Creates a proof from the given trie nodes.
+	pub fn new(trie_nodes: Vec<Vec<u8>>) -> Self {
+		StorageProof {
+			trie_nodes: trie_nodes.into_iter().collect::<BTreeSet<_>>().into_iter().collect(),
+		}
+	}
+
+	/// Creates a proof without any node.
+	pub fn empty() -> Self {
+		StorageProof { trie_nodes: Vec::new() }
+	}
+
+	/// Returns whether the proof contains any node.
+	pub fn is_empty(&self) -> bool {
+		self.trie_nodes.is_empty()
+	}
+
+	/// Returns the nodes of the proof.
+	pub fn nodes(&self) -> &[Vec<u8>] {
+		&self.trie_nodes
+	}
+
+	/// Consumes the proof, returning its nodes.
+	pub fn into_nodes(self) -> Vec<Vec<u8>> {
+		self.trie_nodes
+	}
+
+	/// Merges several proofs into one containing the nodes of all of them.
+	pub fn merge<I: IntoIterator<Item=StorageProof>>(proofs: I) -> Self {
+		Self::new(proofs.into_iter().flat_map(|proof| proof.trie_nodes).collect())
+	}
+
+	/// Builds a database containing the nodes of the proof.
+	pub fn into_memory_db<H: Hasher>(self) -> MemoryDB<H> {
+		let mut db = MemoryDB::default();
+		for node in self.trie_nodes {
+			db.insert(&[], &node);
+		}
+		db
+	}
+
+	/// Converts the proof to its compact form.
+	///
+	/// Nodes are ordered depth-first from the nodes that no other node of the proof refers to,
+	/// and references to a child which follows in the proof are replaced by an empty reference,
+	/// so that the hashes of the nodes are not transmitted.
+	pub fn into_compact<H: Hasher>(self) -> Result<CompactProof, Error> {
+		let nodes: BTreeMap<H::Out, Vec<u8>> = self.trie_nodes.into_iter()
+			.map(|node| (H::hash(&node), node))
+			.collect();
+
+		let mut referenced = BTreeSet::new();
+		for node in nodes.values() {
+			map_children(node, |child| {
+				if let Some(hash) = decode_hash::<H>(child) {
+					referenced.insert(hash);
+				}
+				Ok(child.to_vec())
+			})?;
+		}
+
+		let mut emitted = BTreeSet::new();
+		let mut encoded_nodes = Vec::with_capacity(nodes.len());
+		for hash in nodes.keys().filter(|hash| !referenced.contains(*hash)) {
+			compact_node::<H>(hash, &nodes, &mut emitted, &mut encoded_nodes)?;
+		}
+
+		Ok(CompactProof { encoded_nodes })
+	}
+}
+
+impl Encode for StorageProof {
+	fn encode_to<T: Output>(&self, dest: &mut T) {
+		self.trie_nodes.encode_to(dest)
+	}
+}
+
+impl Decode for StorageProof {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Vec::<Vec<u8>>::decode(input).map(StorageProof::new)
+	}
+}
+
+/// Compact encoding of a `StorageProof`, see `StorageProof::into_compact`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactProof {
+	encoded_nodes: Vec<Vec<u8>>,
+}
+
+impl CompactProof {
+	/// Returns the encoded nodes of the proof.
+	pub fn encoded_nodes(&self) -> &[Vec<u8>] {
+		&self.encoded_nodes
+	}
+
+	/// Restores the original proof, recomputing the omitted child hashes.
+	pub fn to_storage_proof<H: Hasher>(&self) -> Result<StorageProof, Error> {
+		let mut trie_nodes = Vec::with_capacity(self.encoded_nodes.len());
+		let mut nodes = self.encoded_nodes.iter();
+		while let Some(node) = nodes.next() {
+			expand_node::<H, _>(node, &mut nodes, &mut trie_nodes, 0)?;
+		}
+		Ok(StorageProof::new(trie_nodes))
+	}
+}
+
+impl From<StorageProof> for CompactProof {
+	/// Wraps the nodes of `proof` without omitting any child hash.
+	///
+	/// This is a valid compact proof which is encoded exactly like the original `StorageProof`.
+	fn from(proof: StorageProof) -> Self {
+		CompactProof { encoded_nodes: proof.trie_nodes }
+	}
+}
+
+impl Encode for CompactProof {
+	fn encode_to<T: Output>(&self, dest: &mut T) {
+		self.encoded_nodes.encode_to(dest)
+	}
+}
+
+impl Decode for CompactProof {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Vec::<Vec<u8>>::decode(input).map(|encoded_nodes| CompactProof { encoded_nodes })
+	}
+}
+
+/// Checks that `proof` proves the given values under `root`. A `None` value proves the absence
+/// of the key.
+///
+/// Returns `Ok(false)` if a value differs and an error if the proof lacks some nodes.
+pub fn verify_storage_proof<H, K, V>(
+	root: &H::Out,
+	proof: StorageProof,
+	items: &[(K, Option<V>)],
+) -> Result<bool, Box<TrieError<H::Out>>> where
+	H: Hasher,
+	K: AsRef<[u8]>,
+	V: AsRef<[u8]>,
+{
+	let db = proof.into_memory_db::<H>();
+	for (key, value) in items {
+		let actual = read_trie_value::<H, _>(&db, root, key.as_ref())?;
+		if actual.as_ref().map(|v| &v[..]) != value.as_ref().map(|v| v.as_ref()) {
+			return Ok(false);
+		}
+	}
+	Ok(true)
+}
+
+fn decode_hash<H: Hasher>(data: &[u8]) -> Option<H::Out> {
+	if data.len() == H::LENGTH {
+		let mut hash = H::Out::default();
+		hash.as_mut().copy_from_slice(data);
+		Some(hash)
+	} else {
+		None
+	}
+}
+
+/// Re-encodes `node`, replacing every child reference with the output of `f`.
+fn map_children<F>(node: &[u8], mut f: F) -> Result<Vec<u8>, Error> where
+	F: FnMut(&[u8]) -> Result<Vec<u8>, Error>,
+{
+	let input = &mut &*node;
+	let children = match NodeHeader::decode(input).ok_or(Error::BadFormat)? {
+		NodeHeader::Null | NodeHeader::Leaf(_) => return Ok(node.to_vec()),
+		NodeHeader::Branch(has_value) => {
+			let bitmap = u16::decode(input).ok_or(Error::BadFormat)?;
+			if has_value {
+				let count = <Compact<u32>>::decode(input).ok_or(Error::BadFormat)?.0 as usize;
+				take(input, count).ok_or(Error::BadFormat)?;
+			}
+			bitmap.count_ones()
+		},
+		NodeHeader::Extension(nibble_count) => {
+			take(input, (nibble_count + 1) / 2).ok_or(Error::BadFormat)?;
+			1
+		},
+	};
+
+	let mut output = node[..node.len() - input.len()].to_vec();
+	for _ in 0..children {
+		let count = <Compact<u32>>::decode(input).ok_or(Error::BadFormat)?.0 as usize;
+		let child = f(take(input, count).ok_or(Error::BadFormat)?)?;
+		(&child[..]).encode_to(&mut output);
+	}
+	if !input.is_empty() {
+		return Err(Error::BadFormat);
+	}
+	Ok(output)
+}
+
+fn compact_node<H: Hasher>(
+	hash: &H::Out,
+	nodes: &BTreeMap<H::Out, Vec<u8>>,
+	emitted: &mut BTreeSet<H::Out>,
+	output: &mut Vec<Vec<u8>>,
+) -> Result<(), Error> {
+	emitted.insert(hash.clone());
+	let index = output.len();
+	output.push(Vec::new());
+
+	let mut children = Vec::new();
+	let node = map_children(&nodes[hash], |child| match decode_hash::<H>(child) {
+		Some(child_hash) if nodes.contains_key(&child_hash)
+			&& !emitted.contains(&child_hash)
+			&& !children.contains(&child_hash) =>
+		{
+			children.push(child_hash);
+			Ok(Vec::new())
+		},
+		_ => Ok(child.to_vec()),
+	})?;
+	output[index] = node;
+
+	for child in children {
+		compact_node::<H>(&child, nodes, emitted, output)?;
+	}
+	Ok(())
+}
+
+fn expand_node<'a, H: Hasher, I: Iterator<Item=&'a Vec<u8>>>(
+	node: &[u8],
+	nodes: &mut I,
+	output: &mut Vec<Vec<u8>>,
+	depth: usize,
+) -> Result<H::Out, Error> {
+	if depth >= MAX_NESTING {
+		return Err(Error::BadFormat);
+	}
+
+	let node = map_children(node, |child| if child.is_empty() {
+		let next = nodes.next().ok_or(Error::BadFormat)?;
+		expand_node::<H, _>(next, nodes, output, depth + 1).map(|hash| hash.as_ref().to_vec())
+	} else {
+		Ok(child.to_vec())
+	})?;
+	let hash = H::hash(&node);
+	output.push(node);
+	Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_primitives::Blake2Hasher;
+	use trie_db::{Recorder, TrieMut};
+	use crate::{TrieDBMut, read_trie_value_with};
+
+	fn test_trie() -> (MemoryDB<Blake2Hasher>, <Blake2Hasher as Hasher>::Out) {
+		let mut db = MemoryDB::default();
+		let mut root = Default::default();
+		{
+			let mut trie = TrieDBMut::<Blake2Hasher>::new(&mut db, &mut root);
+			for i in 0u32..256 {
+				trie.insert(&i.to_le_bytes(), &[i as u8; 40]).unwrap();
+			}
+		}
+		(db, root)
+	}
+
+	fn prove(db: &MemoryDB<Blake2Hasher>, root: &<Blake2Hasher as Hasher>::Out, keys: &[[u8; 4]]) -> StorageProof {
+		let mut recorder = Recorder::new();
+		for key in keys {
+			read_trie_value_with::<Blake2Hasher, _, _>(db, root, key, &mut recorder).unwrap();
+		}
+		StorageProof::new(recorder.drain().into_iter().map(|r| r.data.to_vec()).collect())
+	}
+
+	#[test]
+	fn compact_proof_round_trips() {
+		let (db, root) = test_trie();
+		let keys = [1u32.to_le_bytes(), 200u32.to_le_bytes(), 1000u32.to_le_bytes()];
+		let proof = prove(&db, &root, &keys);
+
+		let compact = proof.clone().into_compact::<Blake2Hasher>().unwrap();
+		assert!(compact.encode().len() < proof.encode().len());
+		let decoded = CompactProof::decode(&mut &compact.encode()[..]).unwrap();
+		assert_eq!(decoded.to_storage_proof::<Blake2Hasher>().unwrap(), proof);
+
+		let uncompacted = CompactProof::from(proof.clone());
+		assert_eq!(uncompacted.encode(), proof.encode());
+		assert_eq!(uncompacted.to_storage_proof::<Blake2Hasher>().unwrap(), proof);
+	}
+
+	#[test]
+	fn storage_proof_is_verified() {
+		let (db, root) = test_trie();
+		let proof = prove(&db, &root, &[1u32.to_le_bytes(), 1000u32.to_le_bytes()]);
+
+		assert!(verify_storage_proof::<Blake2Hasher, _, _>(
+			&root,
+			proof.clone(),
+			&[(1u32.to_le_bytes(), Some(vec![1u8; 40])), (1000u32.to_le_bytes(), None)],
+		).unwrap());
+		assert!(!verify_storage_proof::<Blake2Hasher, _, _>(
+			&root,
+			proof.clone(),
+			&[(1u32.to_le_bytes(), Some(vec![2u8; 40]))],
+		).unwrap());
+		assert!(verify_storage_proof::<Blake2Hasher, _, Vec<u8>>(
+			&root,
+			proof,
+			&[(200u32.to_le_bytes(), None)],
+		).is_err());
+	}
+
+	#[test]
+	fn deeply_nested_compact_proof_is_rejected() {
+		// extension nodes with a single nibble, each followed by its child.
+		let nested = |depth: usize| {
+			let mut encoded_nodes = vec![vec![129, 0x01, 0x00]; depth];
+			encoded_nodes.push(vec![2, 0x01, 0x04, 0xaa]);
+			CompactProof { encoded_nodes }
+		};
+
+		assert_eq!(nested(3).to_storage_proof::<Blake2Hasher>().unwrap().nodes().len(), 4);
+		assert!(nested(MAX_NESTING).to_storage_proof::<Blake2Hasher>().is_err());
+	}
+
+	#[test]
+	fn merged_proofs_are_deduplicated() {
+		let (db, root) = test_trie();
+		let first = prove(&db, &root, &[1u32.to_le_bytes()]);
+		let second = prove(&db, &root, &[2u32.to_le_bytes()]);
+		let merged = StorageProof::merge(vec![first.clone(), second.clone()]);
+		assert!(merged.nodes().len() < first.nodes().len() + second.nodes().len());
+		assert_eq!(merged, StorageProof::merge(vec![second, first]));
+	}
+}
@@ -152,6 +152,109 @@ impl<H, N, V> ForkTree<H, N, V> where
 		self.node_iter().map(|node| (&node.hash, &node.number, &node.data))
 	}
 
+	/// Find the deepest node in the tree that is the given block or one of its ancestors, and
+	/// whose data passes the given `predicate`. The given function `is_descendent_of` should
+	/// return `true` if the second hash (target) is a descendent of the first hash (base).
+	pub fn find_node_where<F, P, E>(
+		&self,
+		hash: &H,
+		number: &N,
+		is_descendent_of: &F,
+		predicate: &P,
+	) -> Result<Option<(&H, &N, &V)>, Error<E>>
+		where E: std::error::Error,
+			  F: Fn(&H, &H) -> Result<bool, E>,
+			  P: Fn(&V) -> bool,
+	{
+		let path = match self.find_node_path_where(hash, number, is_descendent_of, predicate)? {
+			Some(path) => path,
+			None => return Ok(None),
+		};
+
+		let mut path = path.into_iter().rev();
+		let mut node = &self.roots[path.next().expect("paths are never empty; qed")];
+		for index in path {
+			node = &node.children[index];
+		}
+
+		Ok(Some((&node.hash, &node.number, &node.data)))
+	}
+
+	/// Prune all branches that are not part of the canonical chain ending at the given block.
+	///
+	/// The deepest node that is the given block or one of its ancestors, and that passes the
+	/// given `predicate`, becomes the only root of the tree. Its children that are not
+	/// descendents of the given block are removed as well. If no such node exists the tree is
+	/// left unchanged. Returns `true` if any node was removed. The given function
+	/// `is_descendent_of` should return `true` if the second hash (target) is a descendent of
+	/// the first hash (base).
+	pub fn prune<F, P, E>(
+		&mut self,
+		hash: &H,
+		number: &N,
+		is_descendent_of: &F,
+		predicate: &P,
+	) -> Result<bool, Error<E>>
+		where E: std::error::Error,
+			  F: Fn(&H, &H) -> Result<bool, E>,
+			  P: Fn(&V) -> bool,
+	{
+		let path = match self.find_node_path_where(hash, number, is_descendent_of, predicate)? {
+			Some(path) => path,
+			None => return Ok(false),
+		};
+
+		let previous_count = self.node_iter().count();
+
+		let mut path = path.into_iter().rev();
+		let mut node = self.roots.swap_remove(path.next().expect("paths are never empty; qed"));
+		for index in path {
+			node = node.children.swap_remove(index);
+		}
+
+		let mut children = Vec::new();
+		for child in node.children.drain(..) {
+			if child.number > *number && is_descendent_of(hash, &child.hash)? {
+				children.push(child);
+			}
+		}
+		node.children = children;
+		self.roots = vec![node];
+
+		Ok(self.node_iter().count() != previous_count)
+	}
+
+	/// Returns the child indices leading from a root to the node found by `find_node_where`,
+	/// starting with the deepest one.
+	fn find_node_path_where<F, P, E>(
+		&self,
+		hash: &H,
+		number: &N,
+		is_descendent_of: &F,
+		predicate: &P,
+	) -> Result<Option<Vec<usize>>, Error<E>>
+		where E: std::error::Error,
+			  F: Fn(&H, &H) -> Result<bool, E>,
+			  P: Fn(&V) -> bool,
+	{
+		for (index, root) in self.roots.iter().enumerate() {
+			if let Some(mut path) = root.find_node_path_where(hash, number, is_descendent_of, predicate)? {
+				path.push(index);
+				return Ok(Some(path));
+			}
+		}
+
+		Ok(None)
+	}
+
+	/// Removes all the nodes for which `predicate` returns `false`, together with all their
+	/// descendents. Returns `true` if any node was removed.
+	pub fn retain<F>(&mut self, mut predicate: F) -> bool where
+		F: FnMut(&H, &N, &V) -> bool,
+	{
+		Node::retain(&mut self.roots, &mut predicate)
+	}
+
 	/// Finalize a root in the tree and return it, return `None` in case no root
 	/// with the given hash exists. All other roots are pruned, and the children
 	/// of the finalized node become the new roots.
@@ -400,6 +503,54 @@ mod node_implementation {
 				Ok(Some((hash, number, data)))
 			}
 		}
+
+		/// Returns the child indices leading from this node to the deepest node that is the given
+		/// block or one of its ancestors and passes the predicate, starting with the deepest one.
+		/// An empty path means this node itself.
+		pub fn find_node_path_where<F, P, E>(
+			&self,
+			hash: &H,
+			number: &N,
+			is_descendent_of: &F,
+			predicate: &P,
+		) -> Result<Option<Vec<usize>>, Error<E>>
+			where E: std::error::Error,
+				  F: Fn(&H, &H) -> Result<bool, E>,
+				  P: Fn(&V) -> bool,
+		{
+			if *number < self.number {
+				return Ok(None);
+			}
+
+			if *number == self.number {
+				return Ok(if self.hash == *hash && predicate(&self.data) { Some(Vec::new()) } else { None });
+			}
+
+			for (index, child) in self.children.iter().enumerate() {
+				if let Some(mut path) = child.find_node_path_where(hash, number, is_descendent_of, predicate)? {
+					path.push(index);
+					return Ok(Some(path));
+				}
+			}
+
+			if predicate(&self.data) && is_descendent_of(&self.hash, hash)? {
+				Ok(Some(Vec::new()))
+			} else {
+				Ok(None)
+			}
+		}
+
+		pub fn retain<F>(nodes: &mut Vec<Self>, predicate: &mut F) -> bool where
+			F: FnMut(&H, &N, &V) -> bool,
+		{
+			let len = nodes.len();
+			nodes.retain(|node| predicate(&node.hash, &node.number, &node.data));
+			let mut changed = nodes.len() != len;
+			for node in nodes.iter_mut() {
+				changed |= Node::retain(&mut node.children, predicate);
+			}
+			changed
+		}
 	}
 }
 
@@ -799,6 +950,90 @@ mod test {
 		);
 	}
 
+	#[test]
+	fn retain_removes_nodes_with_their_descendents() {
+		let (mut tree, ..) = test_fork_tree();
+
+		assert!(tree.retain(|hash, _, _| *hash != "F" && *hash != "K"));
+		assert_eq!(
+			tree.iter().map(|(h, _, _)| h.clone()).collect::<Vec<_>>(),
+			vec!["A", "J", "B", "C", "D", "E"],
+		);
+		assert!(!tree.retain(|_, number, _| *number < 10));
+	}
+
+	#[test]
+	fn find_node_where_finds_deepest_ancestor() {
+		let (_, is_descendent_of) = test_fork_tree();
+
+		let mut tree = ForkTree::new();
+		let nodes = vec![
+			("A", 1), ("B", 2), ("C", 3), ("D", 4), ("E", 5), ("F", 2),
+			("G", 3), ("H", 3), ("I", 4), ("J", 2), ("K", 3),
+		];
+		for (hash, number) in nodes {
+			tree.import(hash, number, hash, &is_descendent_of).unwrap();
+		}
+
+		assert_eq!(
+			tree.find_node_where(&"I", &4, &is_descendent_of, &|_| true),
+			Ok(Some((&"I", &4, &"I"))),
+		);
+
+		assert_eq!(
+			tree.find_node_where(&"I", &4, &is_descendent_of, &|data| *data != "I"),
+			Ok(Some((&"H", &3, &"H"))),
+		);
+
+		assert_eq!(
+			tree.find_node_where(&"I", &4, &is_descendent_of, &|data| *data == "A"),
+			Ok(Some((&"A", &1, &"A"))),
+		);
+
+		assert_eq!(
+			tree.find_node_where(&"I", &4, &is_descendent_of, &|data| *data == "B"),
+			Ok(None),
+		);
+	}
+
+	#[test]
+	fn prune_removes_stale_branches() {
+		let (mut tree, is_descendent_of) = test_fork_tree();
+
+		// nothing in the tree is an ancestor of an unknown block
+		assert_eq!(
+			tree.prune(&"L", &3, &is_descendent_of, &|_| true),
+			Ok(false),
+		);
+
+		// pruning at "F" removes the branches of "B" and "J"
+		assert_eq!(
+			tree.prune(&"F", &2, &is_descendent_of, &|_| true),
+			Ok(true),
+		);
+
+		assert_eq!(
+			tree.iter().map(|(h, n, _)| (h.clone(), n.clone())).collect::<Vec<_>>(),
+			vec![("F", 2), ("H", 3), ("I", 4), ("G", 3)],
+		);
+
+		// pruning at "I" makes it the only root, removing "G"
+		assert_eq!(
+			tree.prune(&"I", &4, &is_descendent_of, &|_| true),
+			Ok(true),
+		);
+
+		assert_eq!(
+			tree.iter().map(|(h, n, _)| (h.clone(), n.clone())).collect::<Vec<_>>(),
+			vec![("I", 4)],
+		);
+
+		assert_eq!(
+			tree.prune(&"I", &4, &is_descendent_of, &|_| true),
+			Ok(false),
+		);
+	}
+
 	#[test]
 	fn minimizes_calls_to_is_descendent_of() {
 		use std::sync::atomic::{AtomicUsize, Ordering};
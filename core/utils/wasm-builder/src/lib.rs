@@ -43,6 +43,7 @@
 //! ```
 //!
 //! The `no-std` feature will be enabled by WASM builder while compiling your project to WASM.
+//! The `runtime-benchmarks` feature is enabled as well if it is enabled for the native build.
 //!
 //! As the final step, you need to add the following to your project:
 //!
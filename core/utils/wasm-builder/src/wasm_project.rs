@@ -191,6 +191,23 @@ fn create_wasm_workspace_project(wasm_workspace: &Path) {
 	).expect("WASM workspace `Cargo.toml` writing can not fail; qed");
 }
 
+/// Features of the project that are also enabled for the WASM binary when enabled for the native
+/// build.
+const FORWARDED_FEATURES: &[&str] = &["runtime-benchmarks"];
+
+/// Returns the features to enable when compiling the project to WASM, formatted as a TOML array.
+fn wasm_features() -> String {
+	let forwarded = FORWARDED_FEATURES.iter().filter(|feature| {
+		env::var(format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"))).is_ok()
+	});
+
+	std::iter::once(&"no_std")
+		.chain(forwarded)
+		.map(|feature| format!("\"{}\"", feature))
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
 /// Create the project used to build the wasm binary.
 ///
 /// # Returns
@@ -217,9 +234,10 @@ fn create_project(cargo_manifest: &Path, wasm_workspace: &Path) -> PathBuf {
 				crate-type = ["cdylib"]
 
 				[dependencies]
-				wasm_project = {{ package = "{crate_name}", path = "{crate_path}", default-features = false, features = [ "no_std" ] }}
+				wasm_project = {{ package = "{crate_name}", path = "{crate_path}", default-features = false, features = [ {features} ] }}
 			"#,
 			crate_name = crate_name,
+			features = wasm_features(),
 			crate_path = crate_path.display(),
 			wasm_binary = wasm_binary,
 		)
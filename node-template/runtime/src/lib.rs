@@ -17,6 +17,7 @@ use primitives::bytes;
 use primitives::{ed25519, sr25519, OpaqueMetadata};
 use sr_primitives::{
 	ApplyResult, transaction_validity::TransactionValidity, generic, create_runtime_str,
+	impl_opaque_keys, key_types, KeyTypeId,
 	traits::{self, NumberFor, BlakeTwo256, Block as BlockT, StaticLookup, Verify}, weights::Weight,
 };
 use client::{
@@ -79,6 +80,13 @@ pub mod opaque {
 	pub type BlockId = generic::BlockId<Block>;
 	/// Opaque session key type.
 	pub type SessionKey = AuraId;
+
+	impl_opaque_keys! {
+		pub struct SessionKeys {
+			#[id(key_types::ED25519)]
+			pub aura: AuraId,
+		}
+	}
 }
 
 /// This runtime version.
@@ -86,8 +94,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-template"),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 3,
-	spec_version: 4,
-	impl_version: 4,
+	spec_version: 6,
+	impl_version: 6,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -127,6 +135,10 @@ impl system::Trait for Runtime {
 	type WeightMultiplierUpdate = ();
 	/// The ubiquitous origin type.
 	type Origin = Origin;
+	/// The aggregated dispatch type that is available for extrinsics.
+	type Call = Call;
+	/// The filter that all dispatched calls must pass; `()` allows all calls.
+	type BaseCallFilter = ();
 	/// Maximum number of block number to block hash mappings to keep (oldest pruned first).
 	type BlockHashCount = BlockHashCount;
 	/// Maximum weight of each block. With a default weight system of 1byte == 1weight, 4mb is ok.
@@ -140,12 +152,18 @@ impl aura::Trait for Runtime {
 	type AuthorityId = AuraId;
 }
 
+parameter_types! {
+	pub const IndexDeposit: u128 = 500;
+}
+
 impl indices::Trait for Runtime {
 	/// The type for recording indexing into the account enumeration. If this ever overflows, there
 	/// will be problems!
 	type AccountIndex = u32;
-	/// Use the standard means of resolving an index hint from an id.
-	type ResolveHint = indices::SimpleResolveHint<Self::AccountId, Self::AccountIndex>;
+	/// The currency in which index deposits are held.
+	type Currency = Balances;
+	/// The deposit needed for claiming an index.
+	type Deposit = IndexDeposit;
 	/// Determine whether an account is dead.
 	type IsDeadAccount = Balances;
 	/// The ubiquitous event type.
@@ -154,12 +172,14 @@ impl indices::Trait for Runtime {
 
 parameter_types! {
 	pub const MinimumPeriod: u64 = 5;
+	pub const MaxDrift: u64 = 60;
 }
 impl timestamp::Trait for Runtime {
 	/// A timestamp: seconds since the unix epoch.
 	type Moment = u64;
 	type OnTimestampSet = Aura;
 	type MinimumPeriod = MinimumPeriod;
+	type MaxDrift = MaxDrift;
 }
 
 parameter_types! {
@@ -176,11 +196,12 @@ impl balances::Trait for Runtime {
 	/// What to do if an account's free balance gets zeroed.
 	type OnFreeBalanceZero = ();
 	/// What to do if a new account is created.
-	type OnNewAccount = Indices;
+	type OnNewAccount = ();
 	/// The ubiquitous event type.
 	type Event = Event;
 
 	type TransactionPayment = ();
+	type TipPayment = ();
 	type DustRemoval = ();
 	type TransferPayment = ();
 	type ExistentialDeposit = ExistentialDeposit;
@@ -301,4 +322,15 @@ impl_runtime_apis! {
 			Executive::offchain_worker(n)
 		}
 	}
+
+	impl session::SessionKeys<Block> for Runtime {
+		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
+			let seed = seed.as_ref().map(|s| rstd::str::from_utf8(s).expect("Seed is an utf8 string"));
+			opaque::SessionKeys::generate(seed)
+		}
+
+		fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+			opaque::SessionKeys::decode_into_raw_public_keys(&encoded)
+		}
+	}
 }
@@ -91,6 +91,8 @@ mod tests {
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
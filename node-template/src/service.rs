@@ -7,7 +7,7 @@ use log::info;
 use transaction_pool::{self, txpool::{Pool as TransactionPool}};
 use node_template_runtime::{self, GenesisConfig, opaque::Block, RuntimeApi, WASM_BINARY};
 use substrate_service::{
-	FactoryFullConfiguration, LightComponents, FullComponents, FullBackend,
+	FactoryFullConfiguration, LightComponents, FullComponents, FullBackend, ServiceBuilder,
 	FullClient, LightClient, LightBackend, FullExecutor, LightExecutor,
 	error::{Error as ServiceError},
 };
@@ -64,7 +64,8 @@ construct_service_factory! {
 		Configuration = NodeConfig,
 		FullService = FullComponents<Self>
 			{ |config: FactoryFullConfiguration<Self>|
-				FullComponents::<Factory>::new(config)
+				// Parts of the service can be customized here, e.g. with `with_rpc_extensions`.
+				FullComponents::<Factory>::from_builder(ServiceBuilder::new(config))
 			},
 		AuthoritySetup = {
 			|service: Self::FullService| {
@@ -79,7 +80,7 @@ construct_service_factory! {
 						.ok_or_else(|| ServiceError::SelectChainRequired)?;
 					let aura = start_aura(
 						SlotDuration::get_or_compute(&*client)?,
-						Arc::new(key),
+						key,
 						client.clone(),
 						select_chain,
 						client,
@@ -88,25 +89,26 @@ construct_service_factory! {
 						service.config.custom.inherent_data_providers.clone(),
 						service.config.force_authoring,
 					)?;
-					service.spawn_task(Box::new(aura.select(service.on_exit()).then(|_| Ok(()))));
+					service.spawn_essential_task("aura", aura.select(service.on_exit()).then(|_| Ok(())));
 				}
 
 				Ok(service)
 			}
 		},
 		LightService = LightComponents<Self>
-			{ |config| <LightComponents<Factory>>::new(config) },
+			{ |config| <LightComponents<Factory>>::from_builder(ServiceBuilder::new(config)) },
 		FullImportQueue = AuraImportQueue<
 			Self::Block,
 		>
 			{ |config: &mut FactoryFullConfiguration<Self> , client: Arc<FullClient<Self>>, _select_chain: Self::SelectChain| {
-					import_queue::<_, _, Pair>(
+					import_queue::<_, _, Pair, _>(
 						SlotDuration::get_or_compute(&*client)?,
 						Box::new(client.clone()),
 						None,
 						None,
 						client,
 						config.custom.inherent_data_providers.clone(),
+						(),
 					).map_err(Into::into)
 				}
 			},
@@ -115,13 +117,14 @@ construct_service_factory! {
 		>
 			{ |config: &mut FactoryFullConfiguration<Self>, client: Arc<LightClient<Self>>| {
 					let fprb = Box::new(DummyFinalityProofRequestBuilder::default()) as Box<_>;
-					import_queue::<_, _, Pair>(
+					import_queue::<_, _, Pair, _>(
 						SlotDuration::get_or_compute(&*client)?,
 						Box::new(client.clone()),
 						None,
 						None,
 						client,
 						config.custom.inherent_data_providers.clone(),
+						(),
 					).map(|q| (q, fprb)).map_err(Into::into)
 				}
 			},
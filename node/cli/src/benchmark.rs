@@ -0,0 +1,110 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The `benchmark` subcommand, available when the node is built with the `runtime-benchmarks`
+//! feature.
+
+use std::{fs, path::PathBuf};
+use cli::{AugmentClap, VersionInfo};
+use client::{ExecutionStrategy, NeverOffchainExt, CallExecutor};
+use parity_codec::{Encode, Decode};
+use sr_primitives::generic::BlockId;
+use structopt::{StructOpt, clap::App};
+use benchmarking::{Analysis, BenchmarkResult};
+use crate::{SharedParams, error, load_spec, service};
+
+/// The `benchmark` command used to measure the weights of dispatchable functions.
+#[derive(Debug, StructOpt, Clone)]
+pub struct BenchmarkCmd {
+	/// Name of the module, e.g. `balances`.
+	#[structopt(long="module")]
+	pub module: String,
+
+	/// Name of the benchmark, e.g. `transfer`.
+	#[structopt(long="benchmark")]
+	pub benchmark: String,
+
+	/// Number of values every component takes over its range.
+	#[structopt(long="steps", default_value = "10")]
+	pub steps: u32,
+
+	/// How often every setting of the components is measured.
+	#[structopt(long="repeat", default_value = "1")]
+	pub repeat: u32,
+
+	/// Write the resulting weight function to the given file.
+	#[structopt(long="output", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl AugmentClap for BenchmarkCmd {
+	fn augment_clap<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+		BenchmarkCmd::augment_clap(app)
+	}
+}
+
+impl BenchmarkCmd {
+	/// Runs the benchmark in the Wasm runtime of the best block and prints the results.
+	pub fn run(&self, version: &VersionInfo) -> error::Result<()> {
+		let config = cli::create_config_with_db_path::<service::Factory, _>(
+			load_spec,
+			&self.shared_params,
+			version,
+		)?;
+		let client = substrate_service::new_client::<service::Factory>(&config)?;
+		let at = BlockId::hash(client.info().chain.best_hash);
+
+		let call_data = (
+			self.module.as_bytes(),
+			self.benchmark.as_bytes(),
+			self.steps,
+			self.repeat,
+		).encode();
+		let result = client.executor().call::<NeverOffchainExt>(
+			&at,
+			"Benchmark_dispatch_benchmark",
+			&call_data,
+			ExecutionStrategy::AlwaysWasm,
+			None,
+		).map_err(|e| format!("Error executing the benchmark: {}", e))?;
+
+		let results = Option::<Vec<BenchmarkResult>>::decode(&mut &result[..])
+			.ok_or_else(|| String::from("Failed to decode the benchmark results"))?
+			.ok_or_else(|| format!("Benchmark {}::{} failed or doesn't exist", self.module, self.benchmark))?;
+
+		for result in &results {
+			let components = result.components.iter()
+				.map(|(name, value)| format!("{}={}", String::from_utf8_lossy(name), value))
+				.collect::<Vec<_>>()
+				.join(" ");
+			println!("{} {}ns", components, result.time);
+		}
+
+		let analysis = Analysis::from_results(&results).ok_or_else(|| String::from("The benchmark has no results"))?;
+		let weight_fn = analysis.weight_fn(&self.benchmark);
+		println!("\n{}", weight_fn);
+
+		if let Some(ref output) = self.output {
+			fs::write(output, weight_fn)
+				.map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+		}
+		Ok(())
+	}
+}
@@ -17,7 +17,7 @@
 //! Substrate chain configurations.
 
 use primitives::{ed25519, sr25519, Pair, crypto::UncheckedInto};
-use node_primitives::{AccountId, AuraId, Balance};
+use node_primitives::{AccountId, AuraId, Balance, BlockNumber, Hash};
 use node_runtime::{
 	GrandpaConfig, BalancesConfig, ContractsConfig, ElectionsConfig, DemocracyConfig,
 	CouncilConfig, AuraConfig,  ImOnlineConfig, IndicesConfig, SessionConfig, StakingConfig,
@@ -25,8 +25,9 @@ use node_runtime::{
 	StakerStatus, DAYS, DOLLARS, MILLICENTS,
 };
 pub use node_runtime::GenesisConfig;
-use substrate_service;
+use substrate_service::{self, Properties};
 use hex_literal::hex;
+use serde::{Serialize, Deserialize};
 use substrate_telemetry::TelemetryEndpoints;
 use grandpa::AuthorityId as GrandpaId;
 
@@ -35,6 +36,19 @@ const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 /// Specialized `ChainSpec`.
 pub type ChainSpec = substrate_service::ChainSpec<GenesisConfig>;
 
+/// Node specific fields of the chain spec, read with `ChainSpec::extensions`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Extensions {
+	/// Blocks that must be part of the chain, as `(number, hash)`, to follow the right side of
+	/// a fork.
+	#[serde(default)]
+	pub fork_blocks: Vec<(BlockNumber, Hash)>,
+	/// Hashes of blocks that are known to be bad.
+	#[serde(default)]
+	pub bad_blocks: Vec<Hash>,
+}
+
 /// Flaming Fir testnet generator
 pub fn flaming_fir_config() -> Result<ChainSpec, String> {
 	ChainSpec::from_embedded(include_bytes!("../res/flaming-fir.json"))
@@ -316,9 +330,26 @@ fn development_config_genesis() -> GenesisConfig {
 	)
 }
 
+/// Properties of the development chains.
+fn development_properties() -> Properties {
+	let mut properties = Properties::new();
+	properties.insert("tokenDecimals".into(), 15.into());
+	properties.insert("tokenSymbol".into(), "DEV".into());
+	properties
+}
+
 /// Development config (single validator Alice)
 pub fn development_config() -> ChainSpec {
-	ChainSpec::from_genesis("Development", "dev", development_config_genesis, vec![], None, None, None, None)
+	ChainSpec::from_genesis(
+		"Development",
+		"dev",
+		development_config_genesis,
+		vec![],
+		None,
+		None,
+		None,
+		Some(development_properties()),
+	)
 }
 
 fn local_testnet_genesis() -> GenesisConfig {
@@ -335,7 +366,16 @@ fn local_testnet_genesis() -> GenesisConfig {
 
 /// Local testnet config (multivalidator Alice + Bob)
 pub fn local_testnet_config() -> ChainSpec {
-	ChainSpec::from_genesis("Local Testnet", "local_testnet", local_testnet_genesis, vec![], None, None, None, None)
+	ChainSpec::from_genesis(
+		"Local Testnet",
+		"local_testnet",
+		local_testnet_genesis,
+		vec![],
+		None,
+		None,
+		None,
+		Some(development_properties()),
+	)
 }
 
 #[cfg(test)]
@@ -379,4 +419,10 @@ pub(crate) mod tests {
 	fn test_connectivity() {
 		service_test::connectivity::<Factory>(integration_test_config_with_two_authorities());
 	}
+
+	#[test]
+	fn extensions_default_to_empty() {
+		assert_eq!(development_config().extensions::<Extensions>().unwrap(), Extensions::default());
+		assert_eq!(development_config().properties()["tokenSymbol"], "DEV");
+	}
 }
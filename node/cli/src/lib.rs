@@ -23,6 +23,9 @@ pub use cli::error;
 pub mod chain_spec;
 mod service;
 mod factory_impl;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmark;
+mod try_runtime;
 
 use tokio::prelude::Future;
 use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
@@ -33,6 +36,9 @@ use log::info;
 use structopt::{StructOpt, clap::App};
 use cli::{AugmentClap, GetLogFilter};
 use crate::factory_impl::FactoryState;
+#[cfg(feature = "runtime-benchmarks")]
+pub use crate::benchmark::BenchmarkCmd;
+pub use crate::try_runtime::{TryRuntimeCmd, Mode as TryRuntimeMode};
 use transaction_factory::RuntimeAdapter;
 
 /// The chain specification option.
@@ -58,6 +64,21 @@ pub enum CustomSubcommands {
 		Only supported for development or local testnet."
 	)]
 	Factory(FactoryCmd),
+
+	/// The custom benchmark subcommand for measuring the weights of dispatchable functions.
+	#[cfg(feature = "runtime-benchmarks")]
+	#[structopt(
+		name = "benchmark",
+		about = "Runs a benchmark of a runtime module and derives a weight function from the results."
+	)]
+	Benchmark(BenchmarkCmd),
+
+	/// The custom try-runtime subcommand for testing the local runtime against live state.
+	#[structopt(
+		name = "try-runtime",
+		about = "Downloads the state of a live chain and executes the local runtime on it."
+	)]
+	TryRuntime(TryRuntimeCmd),
 }
 
 impl GetLogFilter for CustomSubcommands {
@@ -196,6 +217,9 @@ pub fn run<I, T, E>(args: I, exit: E, version: cli::VersionInfo) -> error::Resul
 
 			Ok(())
 		},
+		#[cfg(feature = "runtime-benchmarks")]
+		Ok(Some(CustomSubcommands::Benchmark(cli_args))) => cli_args.run(&version),
+		Ok(Some(CustomSubcommands::TryRuntime(cli_args))) => cli_args.run(),
 		_ => ret.map_err(Into::into).map(|_| ())
 	}
 }
@@ -218,12 +242,18 @@ fn run_until_exit<T, C, E>(
 	// we eagerly drop the service so that the internal exit future is fired,
 	// but we need to keep holding a reference to the global telemetry guard
 	let _telemetry = service.telemetry();
+	let drain = service.drain_tasks(substrate_service::task_manager::DEFAULT_DRAIN_TIMEOUT);
 
 	let _ = runtime.block_on(service.select(e.into_exit()));
+	let drained = runtime.block_on(drain).is_ok();
 	exit_send.fire();
 
-	// TODO [andre]: timeout this future #1318
-	let _ = runtime.shutdown_on_idle().wait();
+	// Tasks that didn't end within the drain timeout are abandoned.
+	if drained {
+		let _ = runtime.shutdown_on_idle().wait();
+	} else {
+		let _ = runtime.shutdown_now().wait();
+	}
 
 	Ok(())
 }
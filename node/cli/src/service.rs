@@ -21,7 +21,7 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use aura::{import_queue, start_aura, AuraImportQueue, SlotDuration};
+use aura::{equivocation_pruner, import_queue, start_aura, AuraImportQueue, SlotDuration};
 use client::{self, LongestChain};
 use grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider};
 use node_executor;
@@ -29,10 +29,10 @@ use primitives::Pair;
 use grandpa_primitives::AuthorityPair as GrandpaPair;
 use futures::prelude::*;
 use node_primitives::{AuraPair, Block};
-use node_runtime::{GenesisConfig, RuntimeApi};
+use node_runtime::{GenesisConfig, RuntimeApi, UNCLE_GENERATIONS};
 use substrate_service::{
 	FactoryFullConfiguration, LightComponents, FullComponents, FullBackend,
-	FullClient, LightClient, LightBackend, FullExecutor, LightExecutor,
+	FullClient, LightClient, LightBackend, FullExecutor, LightExecutor, ServiceBuilder,
 	error::{Error as ServiceError},
 };
 use transaction_pool::{self, txpool::{Pool as TransactionPool}};
@@ -79,8 +79,17 @@ construct_service_factory! {
 		Genesis = GenesisConfig,
 		Configuration = NodeConfig<Self>,
 		FullService = FullComponents<Self>
-			{ |config: FactoryFullConfiguration<Self>|
-				FullComponents::<Factory>::new(config) },
+			{ |config: FactoryFullConfiguration<Self>| {
+				let builder = ServiceBuilder::<FullComponents<Factory>>::new(config)
+					.with_rpc_extensions(|io, deps| {
+						use srml_system_rpc::{System, SystemApi};
+						use srml_balances_rpc::{TransactionPayment, TransactionPaymentApi};
+
+						io.extend_with(SystemApi::to_delegate(System::new(deps.client.clone(), deps.pool)));
+						io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(deps.client)));
+					});
+				FullComponents::<Factory>::from_builder(builder)
+			} },
 		AuthoritySetup = {
 			|mut service: Self::FullService| {
 				let (block_import, link_half) = service.config.custom.grandpa_import_setup.take()
@@ -89,6 +98,14 @@ construct_service_factory! {
 				if let Some(aura_key) = service.authority_key() {
 					info!("Using aura key {}", aura_key.public());
 
+					let uncles_client = service.client();
+					service.config.custom.inherent_data_providers
+						.register_provider(authorship::InherentDataProvider::new(move || {
+							let best_hash = uncles_client.info().chain.best_hash;
+							uncles_client.uncle_headers(best_hash, UNCLE_GENERATIONS).unwrap_or_default()
+						}))
+						.map_err(|e| ServiceError::Other(e.into()))?;
+
 					let proposer = Arc::new(substrate_basic_authorship::ProposerFactory {
 						client: service.client(),
 						transaction_pool: service.transaction_pool(),
@@ -100,7 +117,7 @@ construct_service_factory! {
 
 					let aura = start_aura(
 						SlotDuration::get_or_compute(&*client)?,
-						Arc::new(aura_key),
+						aura_key,
 						client,
 						select_chain,
 						block_import,
@@ -110,7 +127,7 @@ construct_service_factory! {
 						service.config.force_authoring,
 					)?;
 					let select = aura.select(service.on_exit()).then(|_| Ok(()));
-					service.spawn_task(Box::new(select));
+					service.spawn_essential_task("aura", select);
 				}
 
 				let grandpa_key = if service.config.disable_grandpa {
@@ -120,7 +137,7 @@ construct_service_factory! {
 				};
 
 				let config = grandpa::Config {
-					local_key: grandpa_key.map(Arc::new),
+					local_key: grandpa_key,
 					// FIXME #1578 make this available through chainspec
 					gossip_duration: Duration::from_millis(333),
 					justification_period: 4096,
@@ -129,12 +146,12 @@ construct_service_factory! {
 
 				match config.local_key {
 					None if !service.config.grandpa_voter => {
-						service.spawn_task(Box::new(grandpa::run_grandpa_observer(
+						service.spawn_task("grandpa-observer", grandpa::run_grandpa_observer(
 							config,
 							link_half,
 							service.network(),
 							service.on_exit(),
-						)?));
+						)?);
 					},
 					// Either config.local_key is set, or user forced voter service via `--grandpa-voter` flag.
 					_ => {
@@ -148,8 +165,9 @@ construct_service_factory! {
 							inherent_data_providers: service.config.custom.inherent_data_providers.clone(),
 							on_exit: service.on_exit(),
 							telemetry_on_connect: Some(telemetry_on_connect),
+							voting_rule: grandpa::VotingRulesBuilder::default().build(),
 						};
-						service.spawn_task(Box::new(grandpa::run_grandpa_voter(grandpa_config)?));
+						service.spawn_essential_task("grandpa-voter", grandpa::run_grandpa_voter(grandpa_config)?);
 					},
 				}
 
@@ -157,7 +175,7 @@ construct_service_factory! {
 			}
 		},
 		LightService = LightComponents<Self>
-			{ |config| <LightComponents<Factory>>::new(config) },
+			{ |config| <LightComponents<Factory>>::from_builder(ServiceBuilder::new(config)) },
 		FullImportQueue = AuraImportQueue<Self::Block>
 			{ |config: &mut FactoryFullConfiguration<Self> , client: Arc<FullClient<Self>>, select_chain: Self::SelectChain| {
 				let slot_duration = SlotDuration::get_or_compute(&*client)?;
@@ -169,13 +187,15 @@ construct_service_factory! {
 
 				config.custom.grandpa_import_setup = Some((block_import.clone(), link_half));
 
-				import_queue::<_, _, AuraPair>(
+				client.register_aux_pruner(Box::new(equivocation_pruner::<Block, AuraPair>()));
+				import_queue::<_, _, AuraPair, _>(
 					slot_duration,
 					Box::new(block_import),
 					Some(Box::new(justification_import)),
 					None,
 					client,
 					config.custom.inherent_data_providers.clone(),
+					(),
 				).map_err(Into::into)
 			}},
 		LightImportQueue = AuraImportQueue<Self::Block>
@@ -191,13 +211,15 @@ construct_service_factory! {
 				let finality_proof_import = block_import.clone();
 				let finality_proof_request_builder = finality_proof_import.create_finality_proof_request_builder();
 
-				import_queue::<_, _, AuraPair>(
+				client.register_aux_pruner(Box::new(equivocation_pruner::<Block, AuraPair>()));
+				import_queue::<_, _, AuraPair, _>(
 					SlotDuration::get_or_compute(&*client)?,
 					Box::new(block_import),
 					None,
 					Some(Box::new(finality_proof_import)),
 					client,
 					config.custom.inherent_data_providers.clone(),
+					(),
 				).map(|q| (q, finality_proof_request_builder)).map_err(Into::into)
 			}},
 		SelectChain = LongestChain<FullBackend<Self>, Self::Block>
@@ -317,13 +339,13 @@ mod tests {
 			let mut digest = Digest::<H256>::default();
 			digest.push(<DigestItem as CompatibleDigestItem<Pair>>::aura_pre_digest(slot_num));
 			let proposer = proposer_factory.init(&parent_header).unwrap();
-			let new_block = proposer.propose(
+			let proposal = proposer.propose(
 				inherent_data,
 				digest,
 				std::time::Duration::from_secs(1),
 			).expect("Error making test block");
 
-			let (new_header, new_body) = new_block.deconstruct();
+			let (new_header, new_body) = proposal.block.deconstruct();
 			let pre_hash = new_header.hash();
 			// sign the pre-sealed hash of the block and then
 			// add it to a digest item.
@@ -343,6 +365,7 @@ mod tests {
 				finalized: true,
 				auxiliary: Vec::new(),
 				fork_choice: ForkChoiceStrategy::LongestChain,
+				storage_changes: proposal.storage_changes,
 			}
 		};
 
@@ -0,0 +1,292 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The `try-runtime` subcommand.
+//!
+//! Downloads the state of a live chain over RPC and executes the locally built runtime on it, to
+//! test storage migrations against real data before an upgrade is enacted.
+
+use std::{collections::HashMap, str::FromStr};
+use cli::AugmentClap;
+use futures::{Future, Stream, stream};
+use jsonrpc_core_client::transports::http;
+use node_primitives::{Block, BlockNumber, Hash, Header};
+use parity_codec::{Encode, Decode};
+use primitives::{Blake2Hasher, Bytes, U256, storage::{StorageKey, well_known_keys}};
+use serde::{Serialize, Deserialize};
+use sr_io::{TestExternalities, with_externalities};
+use node_runtime::StorageValue;
+use sr_primitives::{OpaqueExtrinsic, generic::{Digest, DigestItem}};
+use structopt::{StructOpt, clap::App};
+use substrate_executor::{Externalities, WasmExecutor};
+use substrate_rpc::{chain::ChainClient, state::StateClient};
+use tokio::runtime::Runtime;
+use crate::error;
+
+/// Number of storage values that are requested concurrently.
+const CONCURRENT_REQUESTS: usize = 64;
+
+/// Heap pages the runtime is executed with.
+const HEAP_PAGES: usize = 1024;
+
+/// What is executed on the downloaded state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+	/// Enact the local runtime on the downloaded state and initialize the following block, which
+	/// runs the `on_runtime_upgrade` hooks that perform the migrations.
+	Upgrade,
+	/// Execute the most recent blocks with the local runtime on the unmodified state of their
+	/// parent.
+	Replay,
+}
+
+impl FromStr for Mode {
+	type Err = String;
+	fn from_str(mode: &str) -> Result<Self, Self::Err> {
+		match mode {
+			"upgrade" => Ok(Mode::Upgrade),
+			"replay" => Ok(Mode::Replay),
+			_ => Err(format!("Invalid mode: {}", mode)),
+		}
+	}
+}
+
+/// The `try-runtime` command used to test the local runtime against live state.
+#[derive(Debug, StructOpt, Clone)]
+pub struct TryRuntimeCmd {
+	/// HTTP RPC endpoint of the node the state is downloaded from.
+	#[structopt(long="url", default_value = "http://localhost:9933")]
+	pub url: String,
+
+	/// Hash of the block whose state is used. Defaults to the best block of the node.
+	#[structopt(long="at")]
+	pub at: Option<String>,
+
+	/// Hex encoded prefix of the storage keys to download. May be given multiple times.
+	///
+	/// The whole state is downloaded if no prefix is given. Replaying blocks requires the whole
+	/// state, since the state root is checked.
+	#[structopt(long="prefix")]
+	pub prefixes: Vec<String>,
+
+	/// `upgrade` runs the runtime upgrade hooks of the local runtime and initializes the next
+	/// block with it, `replay` re-executes the most recent blocks with it.
+	#[structopt(long="mode", default_value = "upgrade")]
+	pub mode: Mode,
+
+	/// Number of blocks to replay, ending with the block given by `--at`.
+	#[structopt(long="blocks", default_value = "1")]
+	pub blocks: u32,
+}
+
+impl AugmentClap for TryRuntimeCmd {
+	fn augment_clap<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+		TryRuntimeCmd::augment_clap(app)
+	}
+}
+
+/// Header as returned over RPC, with the digest items left encoded.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcHeader {
+	parent_hash: Hash,
+	number: U256,
+	state_root: Hash,
+	extrinsics_root: Hash,
+	digest: RpcDigest,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RpcDigest {
+	logs: Vec<Bytes>,
+}
+
+impl RpcHeader {
+	fn into_header(self) -> Option<Header> {
+		let logs = self.digest.logs.into_iter()
+			.map(|log| DigestItem::decode(&mut &log.0[..]))
+			.collect::<Option<Vec<_>>>()?;
+		Some(Header {
+			parent_hash: self.parent_hash,
+			number: self.number.low_u64(),
+			state_root: self.state_root,
+			extrinsics_root: self.extrinsics_root,
+			digest: Digest { logs },
+		})
+	}
+}
+
+/// Block as returned over RPC, with the extrinsics left encoded.
+#[derive(Serialize, Deserialize)]
+struct RpcSignedBlock {
+	block: RpcBlock,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RpcBlock {
+	header: RpcHeader,
+	extrinsics: Vec<Bytes>,
+}
+
+impl RpcBlock {
+	fn into_block(self) -> Option<Block> {
+		let extrinsics = self.extrinsics.into_iter()
+			.map(|xt| OpaqueExtrinsic::decode(&mut &xt.0[..]))
+			.collect::<Option<Vec<_>>>()?;
+		Some(Block { header: self.header.into_header()?, extrinsics })
+	}
+}
+
+type Chain = ChainClient<BlockNumber, Hash, RpcHeader, RpcSignedBlock>;
+
+fn rpc_err(e: jsonrpc_core_client::RpcError) -> String {
+	format!("RPC request failed: {:?}", e)
+}
+
+impl TryRuntimeCmd {
+	/// Downloads the state and executes the local runtime on it.
+	pub fn run(&self) -> error::Result<()> {
+		let mut runtime = Runtime::new()?;
+		let state = runtime.block_on(http::connect::<StateClient<Hash>>(&self.url)).map_err(rpc_err)?;
+		let chain = runtime.block_on(http::connect::<Chain>(&self.url)).map_err(rpc_err)?;
+
+		let at = match self.at {
+			Some(ref at) => Hash::from_str(at.trim_start_matches("0x"))
+				.map_err(|_| format!("Invalid block hash: {}", at))?,
+			None => runtime.block_on(chain.block_hash(None))
+				.map_err(rpc_err)?
+				.ok_or_else(|| String::from("The node has no best block"))?,
+		};
+
+		match self.mode {
+			Mode::Upgrade => {
+				let header = self.header(&mut runtime, &chain, at)?;
+				let mut ext = self.download_state(&mut runtime, &state, at)?;
+
+				// Enact the local runtime as if it was set by `set_code` in the downloaded block, so
+				// that its `on_runtime_upgrade` hooks run when the next block is initialized.
+				ext.insert(well_known_keys::CODE.to_vec(), node_runtime::WASM_BINARY.to_vec());
+				with_externalities(&mut ext, || system::RuntimeUpgraded::put(true));
+
+				let next = Header {
+					parent_hash: at,
+					number: header.number + 1,
+					state_root: Default::default(),
+					extrinsics_root: Default::default(),
+					digest: Default::default(),
+				};
+				let root = ext.storage_root();
+				execute(&mut ext, "Core_initialize_block", &next.encode())?;
+				println!("Upgraded the runtime and initialized block #{} with it.", next.number);
+				println!("State root changed from {:?} to {:?}.", root, ext.storage_root());
+			},
+			Mode::Replay => {
+				if !self.prefixes.is_empty() {
+					return Err("Replaying blocks requires the whole state, no prefix can be given".into());
+				}
+
+				let mut hashes = Vec::new();
+				let mut parent = at;
+				for _ in 0..self.blocks.max(1) {
+					hashes.push(parent);
+					parent = self.header(&mut runtime, &chain, parent)?.parent_hash;
+				}
+
+				// The blocks are executed on the state they were produced on, including the on-chain
+				// runtime code, so that the state roots in their headers can be checked.
+				let mut ext = self.download_state(&mut runtime, &state, parent)?;
+				for hash in hashes.into_iter().rev() {
+					let block = runtime.block_on(chain.block(Some(hash)))
+						.map_err(rpc_err)?
+						.and_then(|signed| signed.block.into_block())
+						.ok_or_else(|| format!("Block {} is not available", hash))?;
+					execute(&mut ext, "Core_execute_block", &block.encode())?;
+					println!("Replayed block #{} ({}).", block.header.number, hash);
+				}
+			},
+		}
+		Ok(())
+	}
+
+	fn header(&self, runtime: &mut Runtime, chain: &Chain, hash: Hash) -> error::Result<Header> {
+		Ok(runtime.block_on(chain.header(Some(hash)))
+			.map_err(rpc_err)?
+			.and_then(RpcHeader::into_header)
+			.ok_or_else(|| format!("Header of block {} is not available", hash))?)
+	}
+
+	/// Downloads the storage under the given prefixes.
+	fn download_state(
+		&self,
+		runtime: &mut Runtime,
+		state: &StateClient<Hash>,
+		at: Hash,
+	) -> error::Result<TestExternalities<Blake2Hasher>> {
+		let prefixes = if self.prefixes.is_empty() {
+			vec![Vec::new()]
+		} else {
+			self.prefixes.iter()
+				.map(|prefix| hex::decode(prefix.trim_start_matches("0x"))
+					.map_err(|_| format!("Invalid storage prefix: {}", prefix)))
+				.collect::<Result<Vec<_>, _>>()?
+		};
+
+		let mut storage = HashMap::new();
+		for prefix in prefixes {
+			let keys = runtime.block_on(state.storage_keys(StorageKey(prefix), Some(at))).map_err(rpc_err)?;
+			let client = state.clone();
+			let values = runtime.block_on(
+				stream::iter_ok(keys.clone())
+					.map(move |key| client.storage(key, Some(at)))
+					.buffered(CONCURRENT_REQUESTS)
+					.collect()
+			).map_err(rpc_err)?;
+
+			storage.extend(keys.into_iter()
+				.zip(values)
+				.filter_map(|(key, value)| value.map(|value| (key.0, value.0))));
+		}
+		println!("Downloaded {} storage entries at {}.", storage.len(), at);
+
+		Ok(TestExternalities::new(storage))
+	}
+}
+
+fn execute(ext: &mut TestExternalities<Blake2Hasher>, method: &str, data: &[u8]) -> error::Result<()> {
+	WasmExecutor::new()
+		.call(ext, HEAP_PAGES, node_runtime::WASM_BINARY, method, data)
+		.map(|_| ())
+		.map_err(|e| format!("{} failed: {}", method, e).into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rpc_header_is_converted() {
+		let header = Header {
+			parent_hash: [1u8; 32].into(),
+			number: 42,
+			state_root: [2u8; 32].into(),
+			extrinsics_root: [3u8; 32].into(),
+			digest: Digest { logs: vec![DigestItem::Other(vec![4, 5])] },
+		};
+		let json = serde_json::to_string(&header).unwrap();
+		let rpc: RpcHeader = serde_json::from_str(&json).unwrap();
+		assert_eq!(rpc.into_header(), Some(header));
+	}
+}
@@ -16,12 +16,16 @@
 
 //! Some configurable implementations as associated type for the substrate runtime.
 
-use node_primitives::Balance;
+use parity_codec::Decode;
+use node_primitives::{AccountId, Balance};
+use runtime_primitives::generic::DigestItem;
 use runtime_primitives::weights::{Weight, WeightMultiplier};
-use runtime_primitives::traits::{Convert, Saturating};
+use runtime_primitives::traits::{Convert, Saturating, Header as HeaderT, Verify};
 use runtime_primitives::Fixed64;
-use support::traits::Get;
-use crate::{Balances, MaximumBlockWeight};
+use substrate_primitives::ed25519;
+use support::traits::{Get, FindAuthor, VerifySeal};
+use consensus_aura::AURA_ENGINE_ID;
+use crate::{Aura, Balances, Header, MaximumBlockWeight, Runtime};
 
 /// Struct that handles the conversion of Balance -> `u64`. This is used for staking's election
 /// calculation.
@@ -36,7 +40,7 @@ impl Convert<Balance, u64> for CurrencyToVoteHandler {
 }
 
 impl Convert<u128, Balance> for CurrencyToVoteHandler {
-	fn convert(x: u128) -> Balance { x * Self::factor() }
+	fn convert(x: u128) -> Balance { x.saturating_mul(Self::factor()) }
 }
 
 /// A struct that updates the weight multiplier based on the saturation level of the previous block.
@@ -85,7 +89,7 @@ impl Convert<(Weight, WeightMultiplier), WeightMultiplier> for WeightMultiplierU
 			multiplier.saturating_add(WeightMultiplier::from_fixed(excess))
 		} else {
 			// first_term > second_term
-			let negative = first_term - second_term;
+			let negative = first_term.saturating_sub(second_term);
 			multiplier.saturating_sub(WeightMultiplier::from_fixed(negative))
 				// despite the fact that apply_to saturates weight (final fee cannot go below 0)
 				// it is crucially important to stop here and don't further reduce the weight fee
@@ -97,6 +101,33 @@ impl Convert<(Weight, WeightMultiplier), WeightMultiplier> for WeightMultiplierU
 	}
 }
 
+/// Verifies the Aura seal of uncles, so that only uncles signed by the authority of their slot
+/// earn reward points.
+///
+/// The authority of the slot is taken from the current set of authorities, and the author is
+/// the account of the validator with the same index in the session.
+pub struct AuraSealVerifier;
+
+impl VerifySeal<Header, AccountId> for AuraSealVerifier {
+	fn verify_seal(header: &Header) -> Result<Option<AccountId>, &'static str> {
+		let mut header = header.clone();
+		let signature = match header.digest.pop() {
+			Some(DigestItem::Seal(id, ref seal)) if id == AURA_ENGINE_ID =>
+				ed25519::Signature::decode(&mut &seal[..]).ok_or("Invalid Aura seal")?,
+			_ => return Err("Header is not sealed by Aura"),
+		};
+
+		let pre_runtime_digests = || header.digest().logs().iter().filter_map(|d| d.as_pre_runtime());
+		let index = Aura::find_author(pre_runtime_digests()).ok_or("Header has no Aura slot")?;
+		let authority = Aura::authorities().get(index as usize).cloned().ok_or("Unknown Aura authority")?;
+		if !signature.verify(header.hash().as_ref(), &authority) {
+			return Err("Invalid Aura seal signature");
+		}
+
+		Ok(session::FindAccountFromAuthorIndex::<Runtime, Aura>::find_author(pre_runtime_digests()))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -225,4 +256,19 @@ mod tests {
 				assert_eq!(fm, max_fm);
 			});
 	}
+
+	#[test]
+	fn uncles_must_be_sealed_by_aura() {
+		let header = |digest: Vec<DigestItem<_>>| Header {
+			parent_hash: Default::default(),
+			number: 1,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: runtime_primitives::generic::Digest { logs: digest },
+		};
+
+		assert!(AuraSealVerifier::verify_seal(&header(vec![])).is_err());
+		assert!(AuraSealVerifier::verify_seal(&header(vec![DigestItem::Seal(*b"babe", vec![0; 64])])).is_err());
+		assert!(AuraSealVerifier::verify_seal(&header(vec![DigestItem::Seal(AURA_ENGINE_ID, vec![0; 3])])).is_err());
+	}
 }
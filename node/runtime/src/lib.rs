@@ -34,7 +34,7 @@ use client::{
 	block_builder::api::{self as block_builder_api, InherentData, CheckInherentsResult},
 	runtime_api as client_api, impl_runtime_apis
 };
-use runtime_primitives::{ApplyResult, impl_opaque_keys, generic, create_runtime_str, key_types};
+use runtime_primitives::{ApplyResult, impl_opaque_keys, generic, create_runtime_str, key_types, KeyTypeId};
 use runtime_primitives::transaction_validity::TransactionValidity;
 use runtime_primitives::weights::Weight;
 use runtime_primitives::traits::{
@@ -47,6 +47,7 @@ use version::NativeVersion;
 use substrate_primitives::OpaqueMetadata;
 use grandpa::{AuthorityId as GrandpaId, AuthorityWeight as GrandpaWeight};
 use finality_tracker::{DEFAULT_REPORT_LATENCY, DEFAULT_WINDOW_SIZE};
+use balances_rpc_runtime_api::{RuntimeDispatchInfo, FeeDetails};
 
 #[cfg(any(feature = "std", test))]
 pub use runtime_primitives::BuildStorage;
@@ -60,7 +61,7 @@ pub use staking::StakerStatus;
 /// Implementations for `Convert` and other helper structs passed into runtime modules as associated
 /// types.
 pub mod impls;
-use impls::{CurrencyToVoteHandler, WeightMultiplierUpdateHandler};
+use impls::{AuraSealVerifier, CurrencyToVoteHandler, WeightMultiplierUpdateHandler};
 
 // Make the WASM binary available.
 #[cfg(feature = "std")]
@@ -75,8 +76,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to equal spec_version. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 117,
-	impl_version: 117,
+	spec_version: 125,
+	impl_version: 125,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -123,6 +124,8 @@ parameter_types! {
 
 impl system::Trait for Runtime {
 	type Origin = Origin;
+	type Call = Call;
+	type BaseCallFilter = ();
 	type Index = Index;
 	type BlockNumber = BlockNumber;
 	type Hash = Hash;
@@ -142,10 +145,15 @@ impl aura::Trait for Runtime {
 	type AuthorityId = AuraId;
 }
 
+parameter_types! {
+	pub const IndexDeposit: Balance = 1 * DOLLARS;
+}
+
 impl indices::Trait for Runtime {
 	type AccountIndex = AccountIndex;
+	type Currency = Balances;
+	type Deposit = IndexDeposit;
 	type IsDeadAccount = Balances;
-	type ResolveHint = indices::SimpleResolveHint<Self::AccountId, Self::AccountIndex>;
 	type Event = Event;
 }
 
@@ -160,9 +168,10 @@ parameter_types! {
 impl balances::Trait for Runtime {
 	type Balance = Balance;
 	type OnFreeBalanceZero = ((Staking, Contracts), Session);
-	type OnNewAccount = Indices;
+	type OnNewAccount = ();
 	type Event = Event;
 	type TransactionPayment = DealWithFees;
+	type TipPayment = Author;
 	type DustRemoval = ();
 	type TransferPayment = ();
 	type ExistentialDeposit = ExistentialDeposit;
@@ -174,23 +183,27 @@ impl balances::Trait for Runtime {
 
 parameter_types! {
 	pub const MinimumPeriod: u64 = SECS_PER_BLOCK / 2;
+	pub const MaxDrift: u64 = 60;
 }
 impl timestamp::Trait for Runtime {
 	type Moment = Moment;
 	type OnTimestampSet = Aura;
 	type MinimumPeriod = MinimumPeriod;
+	type MaxDrift = MaxDrift;
 }
 
+/// The number of ancestors of a block whose non-canonical siblings can be included as uncles.
+pub const UNCLE_GENERATIONS: BlockNumber = 5;
+
 parameter_types! {
-	pub const UncleGenerations: u64 = 0;
+	pub const UncleGenerations: BlockNumber = UNCLE_GENERATIONS;
 }
 
-// TODO: #2986 implement this properly
 impl authorship::Trait for Runtime {
-	type FindAuthor = ();
+	type FindAuthor = session::FindAccountFromAuthorIndex<Self, Aura>;
 	type UncleGenerations = UncleGenerations;
-	type FilterUncle = ();
-	type EventHandler = ();
+	type FilterUncle = authorship::OnePerAuthorPerHeight<AuraSealVerifier, BlockNumber>;
+	type EventHandler = Staking;
 }
 
 parameter_types! {
@@ -239,7 +252,7 @@ impl staking::Trait for Runtime {
 	type CurrencyToVote = CurrencyToVoteHandler;
 	type OnRewardMinted = Treasury;
 	type Event = Event;
-	type Slash = ();
+	type Slash = Treasury;
 	type Reward = ();
 	type SessionsPerEra = SessionsPerEra;
 	type BondingDuration = BondingDuration;
@@ -335,6 +348,8 @@ impl treasury::Trait for Runtime {
 	type ProposalBondMinimum = ProposalBondMinimum;
 	type SpendPeriod = SpendPeriod;
 	type Burn = Burn;
+	type BurnDestination = ();
+	type SpendFunds = ();
 }
 
 parameter_types! {
@@ -409,7 +424,7 @@ construct_runtime!(
 		System: system::{Module, Call, Storage, Config, Event},
 		Aura: aura::{Module, Call, Storage, Config<T>, Inherent(Timestamp)},
 		Timestamp: timestamp::{Module, Call, Storage, Inherent},
-		Authorship: authorship::{Module, Call, Storage},
+		Authorship: authorship::{Module, Call, Storage, Inherent},
 		Indices: indices,
 		Balances: balances,
 		Staking: staking::{default, OfflineWorker},
@@ -532,4 +547,56 @@ impl_runtime_apis! {
 			Aura::authorities()
 		}
 	}
+
+	impl session_primitives::SessionKeys<Block> for Runtime {
+		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
+			let seed = seed.as_ref().map(|s| rstd::str::from_utf8(s).expect("Seed is an utf8 string"));
+			SessionKeys::generate(seed)
+		}
+
+		fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+			SessionKeys::decode_into_raw_public_keys(&encoded)
+		}
+	}
+
+	impl system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
+		fn account_nonce(account: AccountId) -> Index {
+			System::account_nonce(account)
+		}
+	}
+
+	impl balances_rpc_runtime_api::TransactionPaymentApi<Block, Balance, UncheckedExtrinsic> for Runtime {
+		fn query_info(uxt: UncheckedExtrinsic, len: u32) -> RuntimeDispatchInfo<Balance> {
+			Balances::query_info(&uxt, len)
+		}
+
+		fn query_fee_details(uxt: UncheckedExtrinsic, len: u32) -> FeeDetails<Balance> {
+			Balances::query_fee_details(&uxt, len)
+		}
+	}
+
+	impl benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			module: Vec<u8>,
+			benchmark: Vec<u8>,
+			steps: u32,
+			repeat: u32,
+		) -> Option<Vec<benchmarking::BenchmarkResult>> {
+			#[cfg(feature = "runtime-benchmarks")]
+			{
+				use benchmarking::Benchmarking;
+
+				match module.as_slice() {
+					b"balances" => Balances::run_benchmark(&benchmark, steps, repeat).ok(),
+					_ => None,
+				}
+			}
+
+			#[cfg(not(feature = "runtime-benchmarks"))]
+			{
+				let _ = (module, benchmark, steps, repeat);
+				None
+			}
+		}
+	}
 }
@@ -0,0 +1,506 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Atomic Swap Module
+//!
+//! A module for atomically sending funds, using hashed time-locks.
+//!
+//! - [`atomic_swap::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//! - [`Module`](./struct.Module.html)
+//!
+//! ## Overview
+//!
+//! A swap is an action, e.g. a transfer of balance, from a source to a target which is locked
+//! under the hash of a secret proof. The source creates the swap, which reserves whatever the
+//! action needs. The target executes the action by revealing the proof before the swap expires;
+//! after that, the source may cancel it to get the reserved resources back.
+//!
+//! Since revealing the proof on one chain allows using it on another, this enables swaps
+//! between chains:
+//!
+//! 1. A creates a swap to B on chain X, locked under the hash of a proof only A knows.
+//! 2. B creates a swap to A on chain Y, locked under the same hash, which expires before A's.
+//! 3. A claims the swap on chain Y, which reveals the proof.
+//! 4. B uses the proof to claim the swap on chain X.
+//!
+//! Actions are defined by the runtime through the [`SwapAction`](./trait.SwapAction.html) trait.
+//! [`BalanceSwapAction`](./struct.BalanceSwapAction.html) swaps balances of a
+//! `ReservableCurrency`.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `create_swap` - Create a swap to a target, reserving the resources of the action.
+//! - `claim_swap` - Claim a swap to the sender by revealing its proof.
+//! - `cancel_swap` - Cancel an expired swap created by the sender.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use rstd::{marker::PhantomData, result};
+use parity_codec::{Encode, Decode, Input, Output};
+use primitives::traits::{Zero, Saturating};
+use srml_support::{
+	StorageDoubleMap, Parameter, decl_module, decl_storage, decl_event, ensure,
+	traits::{Currency, ReservableCurrency, Get},
+};
+use system::ensure_signed;
+
+/// The hash of the proof a swap is locked under.
+pub type HashedProof = [u8; 32];
+
+/// A pending swap.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PendingSwap<AccountId, BlockNumber, Action> {
+	/// The source of the swap.
+	pub source: AccountId,
+	/// The action of the swap.
+	pub action: Action,
+	/// The block from which the source can cancel the swap.
+	pub end_block: BlockNumber,
+}
+
+/// An action executed by a swap.
+pub trait SwapAction<AccountId> {
+	/// Reserve the resources needed for the swap from `source`.
+	fn reserve(&self, source: &AccountId) -> result::Result<(), &'static str>;
+
+	/// Execute the swap from `source` to `target`, using the resources reserved before.
+	///
+	/// Returns whether the action succeeded. A failed action must not move any resources, since
+	/// the swap is kept and may be claimed again.
+	fn claim(&self, source: &AccountId, target: &AccountId) -> bool;
+
+	/// Give the resources reserved for the swap back to `source`.
+	fn cancel(&self, source: &AccountId);
+}
+
+/// A swap action which transfers `value` of the currency `C`.
+pub struct BalanceSwapAction<AccountId, C: ReservableCurrency<AccountId>> {
+	value: <C as Currency<AccountId>>::Balance,
+	_marker: PhantomData<C>,
+}
+
+impl<AccountId, C: ReservableCurrency<AccountId>> BalanceSwapAction<AccountId, C> {
+	/// Create a new action transferring `value`.
+	pub fn new(value: <C as Currency<AccountId>>::Balance) -> Self {
+		Self { value, _marker: PhantomData }
+	}
+
+	/// The value transferred by the action.
+	pub fn value(&self) -> <C as Currency<AccountId>>::Balance {
+		self.value
+	}
+}
+
+// The trait implementations are written by hand, since deriving them would require them for `C`
+// as well, which is usually a module.
+
+impl<AccountId, C: ReservableCurrency<AccountId>> Clone for BalanceSwapAction<AccountId, C> {
+	fn clone(&self) -> Self {
+		Self::new(self.value)
+	}
+}
+
+impl<AccountId, C: ReservableCurrency<AccountId>> PartialEq for BalanceSwapAction<AccountId, C> {
+	fn eq(&self, other: &Self) -> bool {
+		self.value == other.value
+	}
+}
+
+impl<AccountId, C: ReservableCurrency<AccountId>> Eq for BalanceSwapAction<AccountId, C> {}
+
+#[cfg(feature = "std")]
+impl<AccountId, C: ReservableCurrency<AccountId>> std::fmt::Debug for BalanceSwapAction<AccountId, C> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "BalanceSwapAction({:?})", self.value)
+	}
+}
+
+impl<AccountId, C: ReservableCurrency<AccountId>> Encode for BalanceSwapAction<AccountId, C> {
+	fn encode_to<W: Output>(&self, dest: &mut W) {
+		self.value.encode_to(dest)
+	}
+}
+
+impl<AccountId, C: ReservableCurrency<AccountId>> Decode for BalanceSwapAction<AccountId, C> {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Decode::decode(input).map(Self::new)
+	}
+}
+
+impl<AccountId, C: ReservableCurrency<AccountId>> SwapAction<AccountId> for BalanceSwapAction<AccountId, C> {
+	fn reserve(&self, source: &AccountId) -> result::Result<(), &'static str> {
+		C::reserve(source, self.value)
+	}
+
+	fn claim(&self, source: &AccountId, target: &AccountId) -> bool {
+		// the reserved balance may have been slashed since the swap was created, in which case
+		// only part of the value would be repatriated.
+		if C::reserved_balance(source) < self.value {
+			return false;
+		}
+
+		C::repatriate_reserved(source, target, self.value).map_or(false, |left| left.is_zero())
+	}
+
+	fn cancel(&self, source: &AccountId) {
+		C::unreserve(source, self.value);
+	}
+}
+
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The action executed by swaps.
+	type SwapAction: SwapAction<Self::AccountId> + Parameter;
+
+	/// The maximum length of a proof, in bytes. Proofs can be revealed on other chains, so this
+	/// should be the smallest limit of all chains swaps are made with.
+	type ProofLimit: Get<u32>;
+}
+
+type PendingSwapOf<T> = PendingSwap<
+	<T as system::Trait>::AccountId,
+	<T as system::Trait>::BlockNumber,
+	<T as Trait>::SwapAction,
+>;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as AtomicSwap {
+		/// The pending swaps, by target and hashed proof.
+		pub PendingSwaps get(pending_swaps): double_map T::AccountId, blake2_256(HashedProof)
+			=> Option<PendingSwapOf<T>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		PendingSwap = PendingSwapOf<T>
+	{
+		/// A swap was created to the given target, under the given hashed proof.
+		NewSwap(AccountId, HashedProof, PendingSwap),
+		/// A swap was claimed, and its action succeeded or not.
+		SwapClaimed(AccountId, HashedProof, bool),
+		/// A swap was cancelled.
+		SwapCancelled(AccountId, HashedProof),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The maximum length of a proof, in bytes.
+		const ProofLimit: u32 = T::ProofLimit::get();
+
+		fn deposit_event<T>() = default;
+
+		/// Create a swap of `action` from the sender to `target`, locked under `hashed_proof`,
+		/// the blake2-256 hash of the proof. The source may cancel it from `duration` blocks on.
+		///
+		/// The resources needed by `action` are reserved from the sender.
+		///
+		/// Emits `NewSwap`.
+		fn create_swap(
+			origin,
+			target: T::AccountId,
+			hashed_proof: HashedProof,
+			action: T::SwapAction,
+			duration: T::BlockNumber
+		) {
+			let source = ensure_signed(origin)?;
+			ensure!(!<PendingSwaps<T>>::exists(&target, &hashed_proof), "swap already exists");
+
+			action.reserve(&source)?;
+
+			let swap = PendingSwap {
+				source,
+				action,
+				end_block: <system::Module<T>>::block_number().saturating_add(duration),
+			};
+			<PendingSwaps<T>>::insert(&target, &hashed_proof, swap.clone());
+
+			Self::deposit_event(RawEvent::NewSwap(target, hashed_proof, swap));
+		}
+
+		/// Claim the swap to the sender locked under the hash of `proof`, executing its action.
+		///
+		/// `action` must be the action of the swap, so that the sender knows what it claims. The
+		/// swap is only removed if the action succeeds.
+		///
+		/// Emits `SwapClaimed`.
+		fn claim_swap(origin, proof: Vec<u8>, action: T::SwapAction) {
+			ensure!(proof.len() <= T::ProofLimit::get() as usize, "proof too large");
+
+			let target = ensure_signed(origin)?;
+			let hashed_proof = runtime_io::blake2_256(&proof);
+
+			let swap = Self::pending_swaps(&target, &hashed_proof).ok_or("swap does not exist")?;
+			ensure!(swap.action == action, "claimed action mismatch");
+
+			let succeeded = swap.action.claim(&swap.source, &target);
+			// a failed claim keeps the swap, so that it can be claimed again or cancelled.
+			if succeeded {
+				<PendingSwaps<T>>::remove(&target, &hashed_proof);
+			}
+
+			Self::deposit_event(RawEvent::SwapClaimed(target, hashed_proof, succeeded));
+		}
+
+		/// Cancel the swap created by the sender to `target` under `hashed_proof`, once it
+		/// expired, giving back the reserved resources.
+		///
+		/// Emits `SwapCancelled`.
+		fn cancel_swap(origin, target: T::AccountId, hashed_proof: HashedProof) {
+			let source = ensure_signed(origin)?;
+
+			let swap = Self::pending_swaps(&target, &hashed_proof).ok_or("swap does not exist")?;
+			ensure!(swap.source == source, "not the source of the swap");
+			ensure!(
+				<system::Module<T>>::block_number() >= swap.end_block,
+				"swap has not expired yet"
+			);
+
+			swap.action.cancel(&swap.source);
+			<PendingSwaps<T>>::remove(&target, &hashed_proof);
+
+			Self::deposit_event(RawEvent::SwapCancelled(target, hashed_proof));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use srml_support::{assert_noop, assert_ok, impl_outer_origin, parameter_types};
+	use substrate_primitives::{H256, Blake2Hasher};
+	use primitives::{traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+		pub const TransactionBaseFee: u64 = 0;
+		pub const TransactionByteFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnNewAccount = ();
+		type OnFreeBalanceZero = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TipPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+		type TransactionBaseFee = TransactionBaseFee;
+		type TransactionByteFee = TransactionByteFee;
+	}
+	parameter_types! {
+		pub const ProofLimit: u32 = 1024;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type SwapAction = BalanceSwapAction<u64, Balances>;
+		type ProofLimit = ProofLimit;
+	}
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type AtomicSwap = Module<Test>;
+
+	const A: u64 = 1;
+	const B: u64 = 2;
+
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+		t.extend(balances::GenesisConfig::<Test> {
+			balances: vec![(A, 100), (B, 200)],
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	fn action(value: u64) -> BalanceSwapAction<u64, Balances> {
+		BalanceSwapAction::new(value)
+	}
+
+	#[test]
+	fn two_party_successful_swap() {
+		// A and B swap 50 of one chain's balance for 75 of the other's. Both chains are
+		// simulated by the same externalities, so only the movements of each side are checked.
+		with_externalities(&mut new_test_ext(), || {
+			let proof = [1u8; 2].to_vec();
+			let hashed_proof = runtime_io::blake2_256(&proof);
+
+			// A locks 50 for B, and B locks 75 for A, under the same hash.
+			assert_ok!(AtomicSwap::create_swap(Origin::signed(A), B, hashed_proof, action(50), 1000));
+			assert_ok!(AtomicSwap::create_swap(Origin::signed(B), A, hashed_proof, action(75), 500));
+			assert_eq!(Balances::free_balance(&A), 50);
+			assert_eq!(Balances::reserved_balance(&A), 50);
+			assert_eq!(Balances::free_balance(&B), 125);
+
+			// A reveals the proof, which B then uses.
+			assert_ok!(AtomicSwap::claim_swap(Origin::signed(A), proof.clone(), action(75)));
+			assert_ok!(AtomicSwap::claim_swap(Origin::signed(B), proof, action(50)));
+
+			assert_eq!(Balances::free_balance(&A), 50 + 75);
+			assert_eq!(Balances::free_balance(&B), 125 + 50);
+			assert_eq!(Balances::reserved_balance(&A), 0);
+			assert_eq!(Balances::reserved_balance(&B), 0);
+			assert_eq!(AtomicSwap::pending_swaps(&A, &hashed_proof), None);
+			assert_eq!(AtomicSwap::pending_swaps(&B, &hashed_proof), None);
+		});
+	}
+
+	#[test]
+	fn cancel_after_expiry_works() {
+		with_externalities(&mut new_test_ext(), || {
+			let hashed_proof = runtime_io::blake2_256(b"secret");
+			assert_ok!(AtomicSwap::create_swap(Origin::signed(A), B, hashed_proof, action(50), 10));
+
+			assert_noop!(AtomicSwap::cancel_swap(Origin::signed(A), B, hashed_proof), "swap has not expired yet");
+			assert_noop!(AtomicSwap::cancel_swap(Origin::signed(B), B, hashed_proof), "not the source of the swap");
+
+			System::set_block_number(10);
+			assert_ok!(AtomicSwap::cancel_swap(Origin::signed(A), B, hashed_proof));
+			assert_eq!(Balances::free_balance(&A), 100);
+			assert_eq!(AtomicSwap::pending_swaps(&B, &hashed_proof), None);
+			assert_noop!(
+				AtomicSwap::claim_swap(Origin::signed(B), b"secret".to_vec(), action(50)),
+				"swap does not exist"
+			);
+		});
+	}
+
+	#[test]
+	fn failed_claim_keeps_the_swap() {
+		with_externalities(&mut new_test_ext(), || {
+			// C has no account, so the reserved balance can't be repatriated to it.
+			const C: u64 = 3;
+			let hashed_proof = runtime_io::blake2_256(b"secret");
+			assert_ok!(AtomicSwap::create_swap(Origin::signed(A), C, hashed_proof, action(50), 10));
+
+			assert_ok!(AtomicSwap::claim_swap(Origin::signed(C), b"secret".to_vec(), action(50)));
+			assert_eq!(Balances::reserved_balance(&A), 50);
+			assert!(AtomicSwap::pending_swaps(&C, &hashed_proof).is_some());
+
+			System::set_block_number(10);
+			assert_ok!(AtomicSwap::cancel_swap(Origin::signed(A), C, hashed_proof));
+			assert_eq!(Balances::free_balance(&A), 100);
+			assert_eq!(AtomicSwap::pending_swaps(&C, &hashed_proof), None);
+		});
+	}
+
+	#[test]
+	fn claim_of_partially_slashed_swap_moves_nothing() {
+		with_externalities(&mut new_test_ext(), || {
+			let hashed_proof = runtime_io::blake2_256(b"secret");
+			assert_ok!(AtomicSwap::create_swap(Origin::signed(A), B, hashed_proof, action(50), 10));
+			let _ = Balances::slash_reserved(&A, 20);
+
+			assert_ok!(AtomicSwap::claim_swap(Origin::signed(B), b"secret".to_vec(), action(50)));
+			assert_ok!(AtomicSwap::claim_swap(Origin::signed(B), b"secret".to_vec(), action(50)));
+			assert_eq!(Balances::free_balance(&B), 200);
+			assert_eq!(Balances::reserved_balance(&A), 30);
+			assert!(AtomicSwap::pending_swaps(&B, &hashed_proof).is_some());
+
+			System::set_block_number(10);
+			assert_ok!(AtomicSwap::cancel_swap(Origin::signed(A), B, hashed_proof));
+			assert_eq!(Balances::free_balance(&A), 80);
+			assert_eq!(Balances::reserved_balance(&A), 0);
+		});
+	}
+
+	#[test]
+	fn expiry_saturates() {
+		with_externalities(&mut new_test_ext(), || {
+			let hashed_proof = runtime_io::blake2_256(b"secret");
+			System::set_block_number(10);
+			assert_ok!(AtomicSwap::create_swap(Origin::signed(A), B, hashed_proof, action(50), u64::max_value()));
+			assert_eq!(AtomicSwap::pending_swaps(&B, &hashed_proof).unwrap().end_block, u64::max_value());
+		});
+	}
+
+	#[test]
+	fn error_catching_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			let hashed_proof = runtime_io::blake2_256(b"secret");
+			assert_noop!(
+				AtomicSwap::create_swap(Origin::signed(A), B, hashed_proof, action(150), 10),
+				"not enough free funds"
+			);
+
+			assert_ok!(AtomicSwap::create_swap(Origin::signed(A), B, hashed_proof, action(50), 10));
+			assert_noop!(
+				AtomicSwap::create_swap(Origin::signed(A), B, hashed_proof, action(10), 10),
+				"swap already exists"
+			);
+
+			assert_noop!(
+				AtomicSwap::claim_swap(Origin::signed(B), b"wrong".to_vec(), action(50)),
+				"swap does not exist"
+			);
+			assert_noop!(
+				AtomicSwap::claim_swap(Origin::signed(A), b"secret".to_vec(), action(50)),
+				"swap does not exist"
+			);
+			assert_noop!(
+				AtomicSwap::claim_swap(Origin::signed(B), b"secret".to_vec(), action(60)),
+				"claimed action mismatch"
+			);
+			assert_noop!(
+				AtomicSwap::claim_swap(Origin::signed(B), vec![0; 1025], action(50)),
+				"proof too large"
+			);
+		});
+	}
+}
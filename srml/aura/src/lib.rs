@@ -51,11 +51,12 @@
 pub use timestamp;
 
 use rstd::{result, prelude::*};
-use parity_codec::Encode;
-use srml_support::{decl_storage, decl_module, Parameter, storage::StorageValue, traits::Get};
+use parity_codec::{Encode, Decode};
+use srml_support::{decl_storage, decl_module, Parameter, storage::StorageValue, traits::{Get, FindAuthor}};
 use primitives::{
 	traits::{SaturatedConversion, Saturating, Zero, One, Member, IsMember, TypedKey},
 	generic::DigestItem,
+	ConsensusEngineId,
 };
 use timestamp::OnTimestampSet;
 #[cfg(feature = "std")]
@@ -64,8 +65,6 @@ use inherents::{RuntimeString, InherentIdentifier, InherentData, ProvideInherent
 #[cfg(feature = "std")]
 use inherents::{InherentDataProviders, ProvideInherentData};
 use substrate_consensus_aura_primitives::{AURA_ENGINE_ID, ConsensusLog};
-#[cfg(feature = "std")]
-use parity_codec::Decode;
 
 mod mock;
 mod tests;
@@ -210,6 +209,27 @@ impl<T: Trait> session::OneSessionHandler<T::AccountId> for Module<T> {
 	}
 }
 
+impl<T: Trait> FindAuthor<u32> for Module<T> {
+	/// The author of a block is the authority at the index of its slot, as
+	/// given by the Aura pre-runtime digest.
+	fn find_author<'a, I>(digests: I) -> Option<u32> where
+		I: 'a + IntoIterator<Item=(ConsensusEngineId, &'a [u8])>
+	{
+		for (id, mut data) in digests.into_iter() {
+			if id == AURA_ENGINE_ID {
+				let slot_num: u64 = Decode::decode(&mut data)?;
+				let authorities = Self::authorities().len() as u64;
+				if authorities == 0 {
+					return None
+				}
+				return Some((slot_num % authorities) as u32)
+			}
+		}
+
+		None
+	}
+}
+
 impl<T: Trait> IsMember<T::AuthorityId> for Module<T> {
 	fn is_member(authority_id: &T::AuthorityId) -> bool {
 		Self::authorities()
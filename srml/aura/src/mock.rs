@@ -40,10 +40,13 @@ parameter_types! {
 	pub const MaximumBlockWeight: u32 = 1024;
 	pub const MaximumBlockLength: u32 = 2 * 1024;
 	pub const MinimumPeriod: u64 = 1;
+	pub const MaxDrift: u64 = 60;
 }
 
 impl system::Trait for Test {
 	type Origin = Origin;
+	type Call = ();
+	type BaseCallFilter = ();
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -62,6 +65,7 @@ impl timestamp::Trait for Test {
 	type Moment = u64;
 	type OnTimestampSet = Aura;
 	type MinimumPeriod = MinimumPeriod;
+	type MaxDrift = MaxDrift;
 }
 
 impl Trait for Test {
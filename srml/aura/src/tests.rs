@@ -23,6 +23,9 @@ use crate::mock::{System, Aura, new_test_ext};
 use primitives::traits::Header;
 use runtime_io::with_externalities;
 use parking_lot::Mutex;
+use parity_codec::Encode;
+use srml_support::traits::FindAuthor;
+use substrate_consensus_aura_primitives::AURA_ENGINE_ID;
 use crate::{AuraReport, HandleReport};
 
 #[test]
@@ -90,3 +93,13 @@ fn aura_reports_offline() {
 		assert_eq!(SLASH_COUNTS.lock().as_slice(), &[0, 0, 1, 1]);
 	});
 }
+
+#[test]
+fn finds_the_author_of_the_slot() {
+	with_externalities(&mut new_test_ext(vec![0, 1, 2]), || {
+		let slot = 7u64.encode();
+		assert_eq!(Aura::find_author(vec![(AURA_ENGINE_ID, &slot[..])]), Some(1));
+		assert_eq!(Aura::find_author(vec![(*b"BABE", &slot[..])]), None);
+		assert_eq!(Aura::find_author(vec![(AURA_ENGINE_ID, &[1u8][..])]), None);
+	});
+}
@@ -17,6 +17,10 @@
 //! Authorship tracking for SRML runtimes.
 //!
 //! This tracks the current author of the block and recent uncles.
+//!
+//! Uncles are included through the `set_uncles` inherent. The node provides recent
+//! non-canonical headers with the `InherentDataProvider` and the block author includes up to
+//! `MAX_UNCLES` of them which are recent enough and pass the `FilterUncle` checks.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -28,6 +32,94 @@ use srml_support::dispatch::Result as DispatchResult;
 use parity_codec::{Encode, Decode};
 use system::ensure_none;
 use primitives::traits::{SimpleArithmetic, Header as HeaderT, One, Zero};
+use inherents::{RuntimeString, InherentIdentifier, ProvideInherent, IsFatalError, InherentData};
+#[cfg(feature = "std")]
+use inherents::ProvideInherentData;
+
+/// The identifier for the `uncles` inherent.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"uncles00";
+
+/// The maximum number of uncles that can be included in a block.
+pub const MAX_UNCLES: usize = 10;
+
+/// Errors that can occur while checking the uncles inherent.
+#[derive(Encode)]
+#[cfg_attr(feature = "std", derive(Debug, Decode))]
+pub enum InherentError {
+	/// The uncles are invalid.
+	Uncles(RuntimeString),
+}
+
+impl IsFatalError for InherentError {
+	fn is_fatal_error(&self) -> bool {
+		match self {
+			InherentError::Uncles(_) => true,
+		}
+	}
+}
+
+impl InherentError {
+	/// Try to create an instance ouf of the given identifier and data.
+	#[cfg(feature = "std")]
+	pub fn try_from(id: &InherentIdentifier, data: &[u8]) -> Option<Self> {
+		if id == &INHERENT_IDENTIFIER {
+			<InherentError as parity_codec::Decode>::decode(&mut &data[..])
+		} else {
+			None
+		}
+	}
+}
+
+/// Auxiliary trait to extract uncles inherent data.
+pub trait UnclesInherentData<H: Decode> {
+	/// Get uncles.
+	fn uncles(&self) -> Result<Vec<H>, RuntimeString>;
+}
+
+impl<H: Decode> UnclesInherentData<H> for InherentData {
+	fn uncles(&self) -> Result<Vec<H>, RuntimeString> {
+		Ok(self.get_data(&INHERENT_IDENTIFIER)?.unwrap_or_default())
+	}
+}
+
+/// Provider for the uncles inherent data.
+///
+/// `uncles_oracle` returns the recent non-canonical headers known to the node, e.g. through
+/// `Client::uncle_headers`.
+#[cfg(feature = "std")]
+pub struct InherentDataProvider<F, H> {
+	inner: F,
+	_marker: std::marker::PhantomData<H>,
+}
+
+#[cfg(feature = "std")]
+impl<F, H> InherentDataProvider<F, H> {
+	pub fn new(uncles_oracle: F) -> Self {
+		InherentDataProvider { inner: uncles_oracle, _marker: Default::default() }
+	}
+}
+
+#[cfg(feature = "std")]
+impl<F, H: Encode> ProvideInherentData for InherentDataProvider<F, H>
+	where F: Fn() -> Vec<H>
+{
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), RuntimeString> {
+		let uncles = (self.inner)();
+		if !uncles.is_empty() {
+			inherent_data.put_data(INHERENT_IDENTIFIER, &uncles)
+		} else {
+			Ok(())
+		}
+	}
+
+	fn error_to_string(&self, error: &[u8]) -> Option<String> {
+		InherentError::try_from(&INHERENT_IDENTIFIER, error).map(|e| format!("{:?}", e))
+	}
+}
 
 pub trait Trait: system::Trait {
 	/// Find the author of a block.
@@ -100,16 +192,18 @@ pub trait FilterUncle<Header, Author> {
 
 	/// Do additional filtering on a seal-checked uncle block, with the accumulated
 	/// filter.
-	fn filter_uncle(header: &Header, acc: Self::Accumulator)
-		-> Result<(Option<Author>, Self::Accumulator), &'static str>;
+	///
+	/// The accumulator must only be updated if the uncle is accepted.
+	fn filter_uncle(header: &Header, acc: &mut Self::Accumulator)
+		-> Result<Option<Author>, &'static str>;
 }
 
 impl<H, A> FilterUncle<H, A> for () {
 	type Accumulator = ();
-	fn filter_uncle(_: &H, acc: Self::Accumulator)
-		-> Result<(Option<A>, Self::Accumulator), &'static str>
+	fn filter_uncle(_: &H, _acc: &mut Self::Accumulator)
+		-> Result<Option<A>, &'static str>
 	{
-		Ok((None, acc))
+		Ok(None)
 	}
 }
 
@@ -123,10 +217,10 @@ impl<Header, Author, T: VerifySeal<Header, Author>> FilterUncle<Header, Author>
 {
 	type Accumulator = ();
 
-	fn filter_uncle(header: &Header, _acc: ())
-		-> Result<(Option<Author>, ()), &'static str>
+	fn filter_uncle(header: &Header, _acc: &mut ())
+		-> Result<Option<Author>, &'static str>
 	{
-		T::verify_seal(header).map(|author| (author, ()))
+		T::verify_seal(header)
 	}
 }
 
@@ -146,8 +240,8 @@ where
 {
 	type Accumulator = BTreeSet<(Header::Number, Author)>;
 
-	fn filter_uncle(header: &Header, mut acc: Self::Accumulator)
-		-> Result<(Option<Author>, Self::Accumulator), &'static str>
+	fn filter_uncle(header: &Header, acc: &mut Self::Accumulator)
+		-> Result<Option<Author>, &'static str>
 	{
 		let author = T::verify_seal(header)?;
 		let number = header.number();
@@ -158,7 +252,7 @@ where
 			}
 		}
 
-		Ok((author, acc))
+		Ok(author)
 	}
 }
 
@@ -217,9 +311,15 @@ decl_module! {
 		}
 
 		/// Provide a set of uncles.
+		///
+		/// At most `MAX_UNCLES` uncles can be provided per block.
 		fn set_uncles(origin, new_uncles: Vec<T::Header>) -> DispatchResult {
 			ensure_none(origin)?;
 
+			if new_uncles.len() > MAX_UNCLES {
+				return Err("Too many uncles");
+			}
+
 			if <Self as Store>::DidSetUncles::get() {
 				return Err("Uncles already set in block.");
 			}
@@ -254,6 +354,39 @@ impl<T: Trait> Module<T> {
 	fn verify_and_import_uncles(new_uncles: Vec<T::Header>) -> DispatchResult {
 		let now = <system::Module<T>>::block_number();
 
+		let mut uncles = <Self as Store>::Uncles::get();
+		uncles.push(UncleEntryItem::InclusionHeight(now));
+
+		let mut acc: <T::FilterUncle as FilterUncle<_, _>>::Accumulator = Default::default();
+
+		for uncle in new_uncles {
+			let prev_uncles = uncles.iter().filter_map(|entry| match entry {
+				UncleEntryItem::InclusionHeight(_) => None,
+				UncleEntryItem::Uncle(h, _) => Some(h),
+			});
+			let author = Self::verify_uncle(&uncle, prev_uncles, &mut acc)?;
+			let hash = uncle.hash();
+
+			T::EventHandler::note_uncle(
+				author.clone().unwrap_or_default(),
+				now - uncle.number().clone(),
+			);
+			uncles.push(UncleEntryItem::Uncle(hash, author));
+		}
+
+		<Self as Store>::Uncles::put(&uncles);
+		Ok(())
+	}
+
+	/// Check that the given uncle can be included in the current block, given the hashes of
+	/// the uncles that were already included. Returns the author of the uncle.
+	fn verify_uncle<'a, I: IntoIterator<Item=&'a T::Hash>>(
+		uncle: &T::Header,
+		existing_uncles: I,
+		accumulator: &mut <T::FilterUncle as FilterUncle<T::Header, T::AccountId>>::Accumulator,
+	) -> Result<Option<T::AccountId>, &'static str> {
+		let now = <system::Module<T>>::block_number();
+
 		let (minimum_height, maximum_height) = {
 			let uncle_generations = T::UncleGenerations::get();
 			let min = if now >= uncle_generations {
@@ -265,56 +398,86 @@ impl<T: Trait> Module<T> {
 			(min, now)
 		};
 
-		let mut uncles = <Self as Store>::Uncles::get();
-		uncles.push(UncleEntryItem::InclusionHeight(now));
+		let hash = uncle.hash();
 
-		let mut acc: <T::FilterUncle as FilterUncle<_, _>>::Accumulator = Default::default();
+		if uncle.number() < &One::one() {
+			return Err("uncle is genesis");
+		}
 
-		for uncle in new_uncles {
-			let hash = uncle.hash();
+		if uncle.number() > &maximum_height {
+			return Err("uncles too high in chain");
+		}
 
-			if uncle.number() < &One::one() {
-				return Err("uncle is genesis");
+		{
+			let parent_number = uncle.number().clone() - One::one();
+			let parent_hash = <system::Module<T>>::block_hash(&parent_number);
+			if &parent_hash != uncle.parent_hash() {
+				return Err("uncle parent not in chain");
 			}
+		}
 
-			if uncle.number() > &maximum_height {
-				return Err("uncles too high in chain");
-			}
+		if uncle.number() < &minimum_height {
+			return Err("uncle not recent enough to be included");
+		}
 
-			{
-				let parent_number = uncle.number().clone() - One::one();
-				let parent_hash = <system::Module<T>>::block_hash(&parent_number);
-				if &parent_hash != uncle.parent_hash() {
-					return Err("uncle parent not in chain");
-				}
-			}
+		let duplicate = existing_uncles.into_iter().find(|h| **h == hash).is_some();
+		let in_chain = <system::Module<T>>::block_hash(uncle.number()) == hash;
 
-			if uncle.number() < &minimum_height {
-				return Err("uncle not recent enough to be included");
-			}
+		if duplicate || in_chain {
+			return Err("uncle already included")
+		}
 
-			let duplicate = uncles.iter().find(|entry| match entry {
-				UncleEntryItem::InclusionHeight(_) => false,
-				UncleEntryItem::Uncle(h, _) => h == &hash,
-			}).is_some();
+		// check uncle validity.
+		T::FilterUncle::filter_uncle(&uncle, accumulator)
+	}
+}
 
-			let in_chain = <system::Module<T>>::block_hash(uncle.number()) == hash;
+impl<T: Trait> ProvideInherent for Module<T> {
+	type Call = Call<T>;
+	type Error = InherentError;
+	const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+	fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+		let uncles = data.uncles().unwrap_or_default();
+		let mut set_uncles = Vec::new();
+
+		if !uncles.is_empty() {
+			let prev_uncles = <Self as Store>::Uncles::get();
+			let mut existing_hashes: Vec<_> = prev_uncles.into_iter().filter_map(|entry|
+				match entry {
+					UncleEntryItem::InclusionHeight(_) => None,
+					UncleEntryItem::Uncle(h, _) => Some(h),
+				}
+			).collect();
 
-			if duplicate || in_chain { return Err("uncle already included") }
+			let mut acc: <T::FilterUncle as FilterUncle<_, _>>::Accumulator = Default::default();
 
-			// check uncle validity.
-			let (author, temp_acc) = T::FilterUncle::filter_uncle(&uncle, acc)?;
-			acc = temp_acc;
+			for uncle in uncles {
+				// invalid uncles are just skipped.
+				if Self::verify_uncle(&uncle, &existing_hashes, &mut acc).is_ok() {
+					existing_hashes.push(uncle.hash());
+					set_uncles.push(uncle);
 
-			T::EventHandler::note_uncle(
-				author.clone().unwrap_or_default(),
-				now - uncle.number().clone(),
-			);
-			uncles.push(UncleEntryItem::Uncle(hash, author));
+					if set_uncles.len() == MAX_UNCLES {
+						break
+					}
+				}
+			}
 		}
 
-		<Self as Store>::Uncles::put(&uncles);
-		Ok(())
+		if set_uncles.is_empty() {
+			None
+		} else {
+			Some(Call::set_uncles(set_uncles))
+		}
+	}
+
+	fn check_inherent(call: &Self::Call, _data: &InherentData) -> Result<(), Self::Error> {
+		match call {
+			Call::set_uncles(ref uncles) if uncles.len() > MAX_UNCLES =>
+				Err(InherentError::Uncles("Too many uncles".into())),
+			_ => Ok(()),
+		}
 	}
 }
 
@@ -343,6 +506,8 @@ mod tests {
 
 	impl system::Trait for Test {
 		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -595,7 +760,7 @@ mod tests {
 		let author_a = 42;
 		let author_b = 43;
 
-		let mut acc: Option<<Filter as FilterUncle<Header, u64>>::Accumulator> = Some(Default::default());
+		let mut acc: <Filter as FilterUncle<Header, u64>>::Accumulator = Default::default();
 		let header_a1 = seal_header(
 			create_header(1, Default::default(), [1; 32].into()),
 			author_a,
@@ -614,15 +779,7 @@ mod tests {
 			author_a,
 		);
 
-		let mut check_filter = move |uncle| {
-			match Filter::filter_uncle(uncle, acc.take().unwrap()) {
-				Ok((author, a)) => {
-					acc = Some(a);
-					Ok(author)
-				}
-				Err(e) => Err(e),
-			}
-		};
+		let mut check_filter = move |uncle| Filter::filter_uncle(uncle, &mut acc);
 
 		// same height, different author is OK.
 		assert_eq!(check_filter(&header_a1), Ok(Some(author_a)));
@@ -634,4 +791,49 @@ mod tests {
 		// same author, same height (author a, height 2)
 		assert!(check_filter(&header_a2_2).is_err());
 	}
+
+	#[test]
+	fn create_inherent_skips_invalid_uncles() {
+		with_externalities(&mut new_test_ext(), || {
+			let author_a = 69;
+
+			let mut canon_chain = vec![
+				seal_header(create_header(0, Default::default(), Default::default()), 999),
+			];
+
+			for number in 1..8 {
+				System::initialize(&number, &canon_chain.last().unwrap().hash(), &Default::default(), &Default::default());
+				let header = seal_header(System::finalize(), author_a);
+				canon_chain.push(header);
+			}
+
+			System::initialize(&8, &canon_chain.last().unwrap().hash(), &Default::default(), &Default::default());
+
+			let valid = seal_header(
+				create_header(3, canon_chain[2].hash(), [1; 32].into()),
+				author_a,
+			);
+			let unsealed = create_header(3, canon_chain[2].hash(), [2; 32].into());
+			let too_old = seal_header(
+				create_header(2, canon_chain[1].hash(), [3; 32].into()),
+				author_a,
+			);
+
+			let mut data = InherentData::new();
+			data.put_data(
+				INHERENT_IDENTIFIER,
+				&vec![valid.clone(), unsealed, valid.clone(), too_old, canon_chain[5].clone()],
+			).unwrap();
+
+			let call = Authorship::create_inherent(&data).unwrap();
+			match call {
+				Call::set_uncles(ref uncles) => assert_eq!(uncles, &vec![valid]),
+				_ => panic!("unexpected call"),
+			}
+			assert!(Authorship::check_inherent(&call, &data).is_ok());
+
+			// nothing to include.
+			assert!(Authorship::create_inherent(&InherentData::new()).is_none());
+		});
+	}
 }
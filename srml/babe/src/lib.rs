@@ -22,7 +22,7 @@ pub use timestamp;
 
 use rstd::{result, prelude::*};
 use srml_support::{decl_storage, decl_module, StorageValue, traits::FindAuthor, traits::Get};
-use timestamp::{OnTimestampSet, Trait};
+use timestamp::OnTimestampSet;
 use primitives::{
 	generic::DigestItem,
 	traits::{IsMember, SaturatedConversion, Saturating, RandomnessBeacon}
@@ -34,9 +34,15 @@ use parity_codec::{Encode, Decode};
 use inherents::{RuntimeString, InherentIdentifier, InherentData, ProvideInherent, MakeFatalError};
 #[cfg(feature = "std")]
 use inherents::{InherentDataProviders, ProvideInherentData};
-use babe_primitives::{BABE_ENGINE_ID, ConsensusLog};
+use babe_primitives::{BABE_ENGINE_ID, ConsensusLog, Epoch, SlotNumber};
 pub use babe_primitives::{AuthorityId, VRF_OUTPUT_LENGTH, VRF_PROOF_LENGTH, PUBLIC_KEY_LENGTH};
 
+/// The BABE module's configuration trait.
+pub trait Trait: timestamp::Trait {
+	/// The amount of time, in slots, that each epoch should last.
+	type EpochDuration: Get<SlotNumber>;
+}
+
 /// The BABE inherent identifier.
 pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"babeslot";
 
@@ -113,6 +119,14 @@ impl ProvideInherentData for InherentDataProvider {
 /// The length of the BABE randomness
 pub const RANDOMNESS_LENGTH: usize = 32;
 
+/// The raw BABE pre-runtime digest: VRF output, VRF proof, authority index and slot number.
+type RawBabePreDigest = (
+	[u8; VRF_OUTPUT_LENGTH],
+	[u8; VRF_PROOF_LENGTH],
+	u64,
+	SlotNumber,
+);
+
 decl_storage! {
 	trait Store for Module<T: Trait> as Babe {
 		/// The last timestamp.
@@ -141,24 +155,41 @@ decl_storage! {
 
 		/// The current epoch
 		EpochIndex get(epoch_index): u64;
+
+		/// The slot of the first block of the chain, `0` until it has been imported.
+		GenesisSlot get(genesis_slot): SlotNumber;
+
+		/// The slot of the current block.
+		CurrentSlot get(current_slot): SlotNumber;
+
+		/// The slot at which the current epoch started.
+		EpochStartSlot get(epoch_start_slot): SlotNumber;
 	}
 }
 
 decl_module! {
 	/// The BABE SRML module
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The number of slots that each epoch lasts.
+		const EpochDuration: SlotNumber = T::EpochDuration::get();
+
 		/// Initialization
 		fn on_initialize() {
-			for i in Self::get_inherent_digests()
+			for (vrf_output, _, _, slot) in Self::get_inherent_digests()
 				.logs
 				.iter()
 				.filter_map(|s| s.as_pre_runtime())
 				.filter_map(|(id, mut data)| if id == BABE_ENGINE_ID {
-					<[u8; VRF_OUTPUT_LENGTH]>::decode(&mut data)
+					RawBabePreDigest::decode(&mut data)
 				} else {
 					None
 				}) {
-				Self::deposit_vrf_output(&i);
+				if GenesisSlot::get() == 0 {
+					GenesisSlot::put(slot);
+					EpochStartSlot::put(slot);
+				}
+				CurrentSlot::put(slot);
+				Self::deposit_vrf_output(&vrf_output);
 			}
 		}
 	}
@@ -179,11 +210,7 @@ impl<T: Trait> FindAuthor<u64> for Module<T> {
 	{
 		for (id, mut data) in digests.into_iter() {
 			if id == BABE_ENGINE_ID {
-				let (_, _, i): (
-					[u8; VRF_OUTPUT_LENGTH],
-					[u8; VRF_PROOF_LENGTH],
-					u64,
-				) = Decode::decode(&mut data)?;
+				let (_, _, i, _): RawBabePreDigest = Decode::decode(&mut data)?;
 				return Some(i)
 			}
 		}
@@ -207,6 +234,17 @@ impl<T: Trait> Module<T> {
 		<T as timestamp::Trait>::MinimumPeriod::get().saturating_mul(2.into())
 	}
 
+	/// The current epoch, in which all authorities have the same weight.
+	pub fn epoch() -> Epoch {
+		Epoch {
+			epoch_index: Self::epoch_index(),
+			start_slot: Self::epoch_start_slot(),
+			duration: T::EpochDuration::get(),
+			authorities: Self::authorities().into_iter().map(|authority| (authority, 1)).collect(),
+			randomness: Self::epoch_randomness(),
+		}
+	}
+
 	fn change_authorities(new: Vec<AuthorityId>) {
 		Authorities::put(&new);
 
@@ -223,6 +261,17 @@ impl<T: Trait> Module<T> {
 	}
 }
 
+/// Ends the session (i.e. the BABE epoch) once `EpochDuration` slots have passed since the start
+/// of the current epoch.
+///
+/// NOTE: the current slot is read from the pre-runtime digest in `on_initialize`, so this module
+/// must be initialized before the session module.
+impl<T: Trait> session::ShouldEndSession<T::BlockNumber> for Module<T> {
+	fn should_end_session(_now: T::BlockNumber) -> bool {
+		Self::current_slot().saturating_sub(Self::epoch_start_slot()) >= T::EpochDuration::get()
+	}
+}
+
 impl<T: Trait> OnTimestampSet<T::Moment> for Module<T> {
 	fn on_timestamp_set(_moment: T::Moment) { }
 }
@@ -242,6 +291,8 @@ impl<T: Trait> session::OneSessionHandler<T::AccountId> for Module<T> {
 			}
 		}
 
+		EpochStartSlot::put(CurrentSlot::get());
+
 		let rho = UnderConstruction::get();
 		UnderConstruction::put([0; 32]);
 		let last_epoch_randomness = EpochRandomness::get();
@@ -0,0 +1,65 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition for querying the transaction fees charged by the balances module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+use client::decl_runtime_apis;
+use parity_codec::{Codec, Encode, Decode};
+use sr_primitives::weights::{Weight, DispatchClass};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+
+/// The fees of an extrinsic, excluding any tip.
+#[derive(Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct FeeDetails<Balance> {
+	/// The fee for the length of the extrinsic: the base fee plus the fee per byte. Zero for
+	/// dispatches which don't pay it.
+	pub len_fee: Balance,
+	/// The fee for the weight of the extrinsic, adjusted by the current weight multiplier.
+	pub weight_fee: Balance,
+}
+
+/// Information about the dispatch of an extrinsic and the fee it pays.
+#[derive(Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RuntimeDispatchInfo<Balance> {
+	/// Weight of this dispatch.
+	pub weight: Weight,
+	/// Class of this dispatch.
+	pub class: DispatchClass,
+	/// The fee this dispatch pays, excluding any tip: the sum of its `FeeDetails`.
+	pub partial_fee: Balance,
+}
+
+decl_runtime_apis! {
+	/// The API to query the fees of extrinsics.
+	pub trait TransactionPaymentApi<Balance, Extrinsic> where
+		Balance: Codec,
+		Extrinsic: Codec,
+	{
+		/// The dispatch information and fee of `uxt`, whose encoding is `len` bytes long.
+		fn query_info(uxt: Extrinsic, len: u32) -> RuntimeDispatchInfo<Balance>;
+
+		/// The breakdown of the fee of `uxt`, whose encoding is `len` bytes long.
+		fn query_fee_details(uxt: Extrinsic, len: u32) -> FeeDetails<Balance>;
+	}
+}
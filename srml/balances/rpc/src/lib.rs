@@ -0,0 +1,119 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC methods for querying the transaction fees charged by the balances module.
+
+use std::sync::Arc;
+
+use client::blockchain::HeaderBackend;
+use jsonrpc_core::{Result, Error, ErrorCode};
+use jsonrpc_derive::rpc;
+use parity_codec::{Codec, Decode};
+use primitives::Bytes;
+use sr_primitives::{generic::BlockId, traits::{self, ProvideRuntimeApi}};
+
+use srml_balances_rpc_runtime_api::TransactionPaymentApi as TransactionPaymentRuntimeApi;
+
+pub use srml_balances_rpc_runtime_api::{RuntimeDispatchInfo, FeeDetails};
+pub use self::gen_client::Client as TransactionPaymentClient;
+
+/// Transaction fee RPC methods.
+#[rpc]
+pub trait TransactionPaymentApi<BlockHash, Balance> {
+	/// The dispatch information and fee of the given encoded extrinsic, at the given block or
+	/// the best block.
+	#[rpc(name = "payment_queryInfo")]
+	fn query_info(&self, encoded_xt: Bytes, at: Option<BlockHash>) -> Result<RuntimeDispatchInfo<Balance>>;
+
+	/// The breakdown of the fee of the given encoded extrinsic, at the given block or the best
+	/// block.
+	#[rpc(name = "payment_queryFeeDetails")]
+	fn query_fee_details(&self, encoded_xt: Bytes, at: Option<BlockHash>) -> Result<FeeDetails<Balance>>;
+}
+
+const RUNTIME_ERROR: i64 = 1;
+const DECODE_ERROR: i64 = 2;
+
+/// An implementation of the transaction fee RPC methods.
+pub struct TransactionPayment<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> TransactionPayment<C, B> {
+	/// Create new `TransactionPayment` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		TransactionPayment { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block> TransactionPayment<C, Block> where
+	C: HeaderBackend<Block>,
+	Block: traits::Block,
+{
+	/// Decode `encoded_xt` and return it with its length and the block to query at.
+	fn prepare(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<Block::Hash>,
+	) -> Result<(Block::Extrinsic, u32, BlockId<Block>)> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let len = encoded_xt.len() as u32;
+		let uxt: Block::Extrinsic = Decode::decode(&mut &*encoded_xt).ok_or_else(|| Error {
+			code: ErrorCode::ServerError(DECODE_ERROR),
+			message: "Unable to decode extrinsic.".into(),
+			data: None,
+		})?;
+		Ok((uxt, len, at))
+	}
+}
+
+fn runtime_error<E: std::fmt::Debug>(message: &str, e: E) -> Error {
+	Error {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, Balance> TransactionPaymentApi<<Block as traits::Block>::Hash, Balance>
+	for TransactionPayment<C, Block>
+where
+	Block: traits::Block,
+	C: Send + Sync + 'static + ProvideRuntimeApi + HeaderBackend<Block>,
+	C::Api: TransactionPaymentRuntimeApi<Block, Balance, Block::Extrinsic>,
+	Balance: Codec + Send + Sync + 'static,
+{
+	fn query_info(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<<Block as traits::Block>::Hash>,
+	) -> Result<RuntimeDispatchInfo<Balance>> {
+		let (uxt, len, at) = self.prepare(encoded_xt, at)?;
+		self.client.runtime_api().query_info(&at, uxt, len)
+			.map_err(|e| runtime_error("Unable to query dispatch info.", e))
+	}
+
+	fn query_fee_details(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<<Block as traits::Block>::Hash>,
+	) -> Result<FeeDetails<Balance>> {
+		let (uxt, len, at) = self.prepare(encoded_xt, at)?;
+		self.client.runtime_api().query_fee_details(&at, uxt, len)
+			.map_err(|e| runtime_error("Unable to query fee details.", e))
+	}
+}
@@ -0,0 +1,49 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks of the balances module.
+
+use super::*;
+use srml_benchmarking::{benchmarks, account};
+use system::RawOrigin;
+
+/// Returns the smallest amount that can be transferred to a new account.
+fn unit<T: Trait>() -> T::Balance {
+	T::ExistentialDeposit::get().max(1.into())
+}
+
+benchmarks! {
+	impl<T: Trait> for Module<T> {
+		// Transfer `e` times the existential deposit to a new account.
+		transfer(e in 1 .. 1000) {
+			let caller: T::AccountId = account("caller", e);
+			let balance = unit::<T>().saturating_mul((e + 1).into());
+			let _ = <Module<T> as Currency<_>>::make_free_balance_be(&caller, balance);
+			let recipient = T::Lookup::unlookup(account("recipient", e));
+			let amount = unit::<T>().saturating_mul(e.into());
+			Ok((RawOrigin::Signed(caller).into(), Call::<T>::transfer(recipient, amount)))
+		}
+
+		// Set the balance of an existing account to `e` times the existential deposit.
+		set_balance(e in 1 .. 1000) {
+			let who: T::AccountId = account("who", e);
+			let _ = <Module<T> as Currency<_>>::make_free_balance_be(&who, unit::<T>());
+			let who = T::Lookup::unlookup(who);
+			let balance = unit::<T>().saturating_mul(e.into());
+			Ok((RawOrigin::Root.into(), Call::<T>::set_balance(who, balance, Zero::zero())))
+		}
+	}
+}
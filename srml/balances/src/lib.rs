@@ -156,7 +156,7 @@ use srml_support::{StorageValue, StorageMap, Parameter, decl_event, decl_storage
 use srml_support::traits::{
 	UpdateBalanceOutcome, Currency, OnFreeBalanceZero, OnUnbalanced,
 	WithdrawReason, WithdrawReasons, LockIdentifier, LockableCurrency, ExistenceRequirement,
-	Imbalance, SignedImbalance, ReservableCurrency, Get,
+	Imbalance, SignedImbalance, ReservableCurrency, VestingCurrency, Get,
 };
 use srml_support::dispatch::Result;
 use primitives::traits::{
@@ -164,11 +164,14 @@ use primitives::traits::{
 	Saturating, Bounded, SignedExtension, SaturatedConversion, DispatchError
 };
 use primitives::transaction_validity::{TransactionPriority, ValidTransaction};
-use primitives::weights::DispatchInfo;
+use primitives::weights::{DispatchInfo, GetDispatchInfo};
 use system::{IsDeadAccount, OnNewAccount, ensure_signed, ensure_root};
+use srml_balances_rpc_runtime_api::{FeeDetails, RuntimeDispatchInfo};
 
 mod mock;
 mod tests;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
 pub use self::imbalances::{PositiveImbalance, NegativeImbalance};
 
@@ -222,9 +225,12 @@ pub trait Trait<I: Instance = DefaultInstance>: system::Trait {
 	/// Handler for when a new account is created.
 	type OnNewAccount: OnNewAccount<Self::AccountId>;
 
-	/// Handler for the unbalanced reduction when taking transaction fees.
+	/// Handler for the unbalanced reduction when taking transaction fees, excluding tips.
 	type TransactionPayment: OnUnbalanced<NegativeImbalance<Self, I>>;
 
+	/// Handler for the unbalanced reduction when taking the tip of a transaction.
+	type TipPayment: OnUnbalanced<NegativeImbalance<Self, I>>;
+
 	/// Handler for the unbalanced reduction when taking fees associated with balance
 	/// transfer (which may also include account creation).
 	type TransferPayment: OnUnbalanced<NegativeImbalance<Self, I>>;
@@ -493,6 +499,30 @@ impl<T: Trait<I>, I: Instance> Module<T, I> {
 		}
 	}
 
+	/// Query the dispatch information of `unchecked_extrinsic`, whose encoding is `len` bytes
+	/// long, and the fee it pays, excluding any tip.
+	pub fn query_info<Extrinsic: GetDispatchInfo>(
+		unchecked_extrinsic: &Extrinsic,
+		len: u32,
+	) -> RuntimeDispatchInfo<T::Balance> {
+		let info = unchecked_extrinsic.get_dispatch_info();
+		let details = TakeFees::<T, I>::compute_fee_details(len as usize, info);
+		RuntimeDispatchInfo {
+			weight: info.weight,
+			class: info.class,
+			partial_fee: details.len_fee.saturating_add(details.weight_fee),
+		}
+	}
+
+	/// Query the breakdown of the fee paid by `unchecked_extrinsic`, whose encoding is `len`
+	/// bytes long, excluding any tip.
+	pub fn query_fee_details<Extrinsic: GetDispatchInfo>(
+		unchecked_extrinsic: &Extrinsic,
+		len: u32,
+	) -> FeeDetails<T::Balance> {
+		TakeFees::<T, I>::compute_fee_details(len as usize, unchecked_extrinsic.get_dispatch_info())
+	}
+
 	// PRIVATE MUTABLES
 
 	/// Set the reserved balance of an account to some new value. Will enforce `ExistentialDeposit`
@@ -742,7 +772,7 @@ mod imbalances {
 // This works as long as `increase_total_issuance_by` doesn't use the Imbalance
 // types (basically for charging fees).
 // This should eventually be refactored so that the three type items that do
-// depend on the Imbalance type (TransactionPayment, TransferPayment, DustRemoval)
+// depend on the Imbalance type (TransactionPayment, TipPayment, TransferPayment, DustRemoval)
 // are placed in their own SRML module.
 struct ElevatedTrait<T: Subtrait<I>, I: Instance>(T, I);
 impl<T: Subtrait<I>, I: Instance> Clone for ElevatedTrait<T, I> {
@@ -754,6 +784,8 @@ impl<T: Subtrait<I>, I: Instance> PartialEq for ElevatedTrait<T, I> {
 impl<T: Subtrait<I>, I: Instance> Eq for ElevatedTrait<T, I> {}
 impl<T: Subtrait<I>, I: Instance> system::Trait for ElevatedTrait<T, I> {
 	type Origin = T::Origin;
+	type Call = ();
+	type BaseCallFilter = ();
 	type Index = T::Index;
 	type BlockNumber = T::BlockNumber;
 	type Hash = T::Hash;
@@ -773,6 +805,7 @@ impl<T: Subtrait<I>, I: Instance> Trait<I> for ElevatedTrait<T, I> {
 	type OnNewAccount = T::OnNewAccount;
 	type Event = ();
 	type TransactionPayment = ();
+	type TipPayment = ();
 	type TransferPayment = ();
 	type DustRemoval = ();
 	type ExistentialDeposit = T::ExistentialDeposit;
@@ -1149,6 +1182,38 @@ where
 	}
 }
 
+impl<T: Trait<I>, I: Instance> VestingCurrency<T::AccountId> for Module<T, I>
+where
+	T::Balance: MaybeSerializeDebug
+{
+	type Moment = T::BlockNumber;
+
+	fn vesting_balance(who: &T::AccountId) -> T::Balance {
+		Self::vesting_balance(who)
+	}
+
+	fn has_vesting_schedule(who: &T::AccountId) -> bool {
+		<Vesting<T, I>>::exists(who)
+	}
+
+	fn add_vesting_schedule(
+		who: &T::AccountId,
+		locked: T::Balance,
+		per_block: T::Balance,
+		starting_block: T::BlockNumber,
+	) -> Result {
+		if <Vesting<T, I>>::exists(who) {
+			return Err("a vesting schedule already exists for this account");
+		}
+		<Vesting<T, I>>::insert(who, VestingSchedule { locked, per_block, starting_block });
+		Ok(())
+	}
+
+	fn remove_vesting_schedule(who: &T::AccountId) {
+		<Vesting<T, I>>::remove(who);
+	}
+}
+
 /// Require the transactor pay for themselves and maybe include a tip to gain additional priority
 /// in the queue.
 #[derive(Encode, Decode, Clone, Eq, PartialEq)]
@@ -1171,6 +1236,12 @@ impl<T: Trait<I>, I: Instance> TakeFees<T, I> {
 	///   - (optional) _tip_: if included in the transaction, it will be added on top. Only signed
 	///      transactions can have a tip.
 	fn compute_fee(len: usize, info: DispatchInfo, tip: T::Balance) -> T::Balance {
+		let details = Self::compute_fee_details(len, info);
+		details.len_fee.saturating_add(details.weight_fee).saturating_add(tip)
+	}
+
+	/// Compute the _length-fee_ and _weight-fee_ of a particular transaction.
+	fn compute_fee_details(len: usize, info: DispatchInfo) -> FeeDetails<T::Balance> {
 		// length fee
 		let len_fee = if info.pay_length_fee() {
 			let len = T::Balance::from(len as u32);
@@ -1185,7 +1256,7 @@ impl<T: Trait<I>, I: Instance> TakeFees<T, I> {
 		let weight = info.weight;
 		let weight_fee: T::Balance = <system::Module<T>>::next_weight_multiplier().apply_to(weight).into();
 
-		len_fee.saturating_add(weight_fee).saturating_add(tip)
+		FeeDetails { len_fee, weight_fee }
 	}
 }
 
@@ -1215,7 +1286,9 @@ impl<T: Trait<I>, I: Instance + Clone + Eq> SignedExtension for TakeFees<T, I> {
 			WithdrawReason::TransactionPayment,
 			ExistenceRequirement::KeepAlive,
 		).map_err(|_| DispatchError::Payment)?;
-		T::TransactionPayment::on_unbalanced(imbalance);
+		let (tip, fee) = imbalance.split(self.0);
+		T::TransactionPayment::on_unbalanced(fee);
+		T::TipPayment::on_unbalanced(tip);
 
 		let mut r = ValidTransaction::default();
 		// NOTE: we probably want to maximize the _fee (of any type) per weight unit_ here, which
@@ -22,9 +22,9 @@ use primitives::{traits::IdentityLookup, testing::Header, weights::{DispatchInfo
 use substrate_primitives::{H256, Blake2Hasher};
 use runtime_io;
 use srml_support::{impl_outer_origin, parameter_types};
-use srml_support::traits::Get;
+use srml_support::traits::{Get, Currency, OnUnbalanced};
 use std::cell::RefCell;
-use crate::{GenesisConfig, Module, Trait};
+use crate::{GenesisConfig, Module, Trait, NegativeImbalance};
 
 impl_outer_origin!{
 	pub enum Origin for Runtime {}
@@ -63,6 +63,16 @@ impl Get<u64> for TransactionByteFee {
 	fn get() -> u64 { TRANSACTION_BYTE_FEE.with(|v| *v.borrow()) }
 }
 
+/// The account receiving transaction tips.
+pub const TIP_RECEIVER: u64 = 99;
+
+pub struct TipsToReceiver;
+impl OnUnbalanced<NegativeImbalance<Runtime>> for TipsToReceiver {
+	fn on_unbalanced(amount: NegativeImbalance<Runtime>) {
+		Balances::resolve_creating(&TIP_RECEIVER, amount);
+	}
+}
+
 // Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Runtime;
@@ -73,6 +83,8 @@ parameter_types! {
 }
 impl system::Trait for Runtime {
 	type Origin = Origin;
+	type Call = ();
+	type BaseCallFilter = ();
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -92,6 +104,7 @@ impl Trait for Runtime {
 	type OnNewAccount = ();
 	type Event = ();
 	type TransactionPayment = ();
+	type TipPayment = TipsToReceiver;
 	type DustRemoval = ();
 	type TransferPayment = ();
 	type ExistentialDeposit = ExistentialDeposit;
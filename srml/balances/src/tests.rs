@@ -19,12 +19,13 @@
 #![cfg(test)]
 
 use super::*;
-use mock::{Balances, ExtBuilder, Runtime, System, info_from_weight};
+use mock::{Balances, ExtBuilder, Runtime, System, info_from_weight, TIP_RECEIVER};
 use runtime_io::with_externalities;
+use primitives::weights::DispatchClass;
 use srml_support::{
 	assert_noop, assert_ok, assert_err,
 	traits::{LockableCurrency, LockIdentifier, WithdrawReason, WithdrawReasons,
-	Currency, ReservableCurrency}
+	Currency, ReservableCurrency, VestingCurrency}
 };
 
 const ID_1: LockIdentifier = *b"1       ";
@@ -668,6 +669,40 @@ fn unvested_balance_should_not_transfer() {
 	);
 }
 
+#[test]
+fn add_vesting_schedule_should_work() {
+	with_externalities(
+		&mut ExtBuilder::default()
+			.existential_deposit(10)
+			.monied(true)
+			.vesting(true)
+			.build(),
+		|| {
+			assert_eq!(System::block_number(), 1);
+			assert_eq!(Balances::vesting(&3), None);
+			assert_ok!(<Balances as VestingCurrency<_>>::add_vesting_schedule(&3, 100, 10, 1));
+			assert_eq!(
+				Balances::vesting(&3),
+				Some(VestingSchedule { locked: 100, per_block: 10, starting_block: 1 })
+			);
+			assert_eq!(<Balances as VestingCurrency<_>>::vesting_balance(&3), 100);
+			let user3_free_balance = Balances::free_balance(&3);
+			assert_noop!(
+				Balances::transfer(Some(3).into(), 4, user3_free_balance - 99),
+				"vesting balance too high to send value"
+			);
+
+			// Account 1 already has a vesting schedule
+			assert_err!(
+				<Balances as VestingCurrency<_>>::add_vesting_schedule(&1, 100, 10, 1),
+				"a vesting schedule already exists for this account"
+			);
+			<Balances as VestingCurrency<_>>::remove_vesting_schedule(&1);
+			assert_eq!(Balances::vesting_balance(&1), 0);
+		}
+	);
+}
+
 #[test]
 fn vested_balance_should_transfer() {
 	with_externalities(
@@ -764,3 +799,68 @@ fn signed_extension_take_fees_work() {
 		}
 	);
 }
+
+#[test]
+fn signed_extension_take_fees_routes_tips() {
+	with_externalities(
+		&mut ExtBuilder::default()
+			.existential_deposit(10)
+			.transaction_fees(10, 1)
+			.monied(true)
+			.build(),
+		|| {
+			let len = 10;
+			let issuance = Balances::total_issuance();
+			assert!(TakeFees::<Runtime>::from(10 /* tipped */).pre_dispatch(&1, info_from_weight(0), len).is_ok());
+			assert_eq!(Balances::free_balance(&1), 100 - 20 - 10);
+			// the tip goes to the tip handler, the rest of the fee is burned.
+			assert_eq!(Balances::free_balance(&TIP_RECEIVER), 10);
+			assert_eq!(Balances::total_issuance(), issuance - 20);
+		}
+	);
+}
+
+#[test]
+fn query_info_works() {
+	struct WeighedXt(DispatchInfo);
+	impl GetDispatchInfo for WeighedXt {
+		fn get_dispatch_info(&self) -> DispatchInfo {
+			self.0
+		}
+	}
+
+	with_externalities(
+		&mut ExtBuilder::default()
+			.existential_deposit(10)
+			.transaction_fees(10, 1)
+			.build(),
+		|| {
+			let xt = WeighedXt(info_from_weight(5));
+			assert_eq!(Balances::query_fee_details(&xt, 10), FeeDetails { len_fee: 20, weight_fee: 5 });
+			assert_eq!(
+				Balances::query_info(&xt, 10),
+				RuntimeDispatchInfo { weight: 5, class: DispatchClass::Normal, partial_fee: 25 }
+			);
+
+			// operational dispatches don't pay the length fee.
+			let xt = WeighedXt(DispatchInfo { weight: 5, class: DispatchClass::Operational });
+			assert_eq!(
+				Balances::query_info(&xt, 10),
+				RuntimeDispatchInfo { weight: 5, class: DispatchClass::Operational, partial_fee: 5 }
+			);
+		}
+	);
+}
+
+#[test]
+#[cfg(feature = "runtime-benchmarks")]
+fn benchmarks_can_be_run() {
+	use srml_benchmarking::Benchmarking;
+
+	with_externalities(&mut ExtBuilder::default().existential_deposit(10).build(), || {
+		for benchmark in <Balances as Benchmarking>::benchmarks() {
+			let results = <Balances as Benchmarking>::run_benchmark(benchmark, 3, 1).unwrap();
+			assert_eq!(results.len(), 3);
+		}
+	});
+}
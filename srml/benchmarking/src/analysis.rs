@@ -0,0 +1,134 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Analysis of benchmark results.
+
+use std::fmt::Write;
+use crate::BenchmarkResult;
+
+/// Linear model of the time a call takes, as a function of its components.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+	/// Time when all the components are zero, in nanoseconds.
+	pub base: u64,
+	/// Names of the components with the time every unit of them adds, in nanoseconds.
+	pub slopes: Vec<(String, u64)>,
+}
+
+impl Analysis {
+	/// Fits a line to the measurements of every component.
+	///
+	/// Only the measurements where all the other components are at their highest value are
+	/// used for a component, which is how `run` produces them. Returns `None` if there are no
+	/// results.
+	pub fn from_results(results: &[BenchmarkResult]) -> Option<Self> {
+		let names = results.first()?.components.iter()
+			.map(|(name, _)| String::from_utf8_lossy(name).into_owned())
+			.collect::<Vec<_>>();
+		let values = results.iter()
+			.map(|r| r.components.iter().map(|(_, value)| *value).collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+		let highest = (0..names.len())
+			.map(|i| values.iter().filter_map(|v| v.get(i).cloned()).max().unwrap_or(0))
+			.collect::<Vec<_>>();
+
+		let slopes = (0..names.len()).map(|i| {
+			let points = results.iter()
+				.zip(values.iter())
+				.filter(|(_, v)| v.len() == highest.len() && (0..v.len()).all(|j| j == i || v[j] == highest[j]))
+				.map(|(r, v)| (f64::from(v[i]), r.time as f64))
+				.collect::<Vec<_>>();
+			slope(&points).max(0.0)
+		}).collect::<Vec<_>>();
+
+		let mut bases = results.iter()
+			.zip(values.iter())
+			.map(|(r, v)| {
+				let explained = v.iter().zip(slopes.iter()).map(|(v, s)| f64::from(*v) * s).sum::<f64>();
+				(r.time as f64 - explained).max(0.0)
+			})
+			.collect::<Vec<_>>();
+		bases.sort_by(|a, b| a.partial_cmp(b).expect("times are finite; qed"));
+
+		Some(Analysis {
+			base: bases[bases.len() / 2].round() as u64,
+			slopes: names.into_iter().zip(slopes.into_iter().map(|s| s.round() as u64)).collect(),
+		})
+	}
+
+	/// Renders the model as a Rust function `name` computing the weight from the components.
+	pub fn weight_fn(&self, name: &str) -> String {
+		let mut out = String::new();
+		let args = self.slopes.iter()
+			.map(|(component, _)| format!("{}: u32", component))
+			.collect::<Vec<_>>()
+			.join(", ");
+		let _ = writeln!(out, "pub fn {}({}) -> Weight {{", name, args);
+		let _ = write!(out, "\t({} as Weight)", self.base);
+		for (component, slope) in &self.slopes {
+			let _ = write!(out, "\n\t\t.saturating_add(({} as Weight).saturating_mul({} as Weight))", slope, component);
+		}
+		let _ = writeln!(out, "\n}}");
+		out
+	}
+}
+
+/// Least squares slope of the given points, or zero if the points don't vary.
+fn slope(points: &[(f64, f64)]) -> f64 {
+	let n = points.len() as f64;
+	let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+	let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+	let covariance = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>();
+	let variance = points.iter().map(|(x, _)| (x - mean_x) * (x - mean_x)).sum::<f64>();
+	if variance == 0.0 { 0.0 } else { covariance / variance }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::component_values;
+
+	fn result(a: u32, b: u32) -> BenchmarkResult {
+		BenchmarkResult {
+			components: vec![(b"a".to_vec(), a), (b"b".to_vec(), b)],
+			time: 1_000 + 20 * u64::from(a) + 3 * u64::from(b),
+		}
+	}
+
+	#[test]
+	fn linear_times_are_fitted() {
+		let mut results = component_values(0, 100, 5).into_iter().map(|a| result(a, 50)).collect::<Vec<_>>();
+		results.extend(component_values(10, 50, 5).into_iter().map(|b| result(100, b)));
+
+		assert_eq!(
+			Analysis::from_results(&results),
+			Some(Analysis { base: 1_000, slopes: vec![("a".into(), 20), ("b".into(), 3)] }),
+		);
+		assert_eq!(Analysis::from_results(&[]), None);
+	}
+
+	#[test]
+	fn weight_fn_is_rendered() {
+		let analysis = Analysis { base: 1_000, slopes: vec![("a".into(), 20)] };
+		assert_eq!(
+			analysis.weight_fn("transfer"),
+			"pub fn transfer(a: u32) -> Weight {\n\
+			\t(1000 as Weight)\n\
+			\t\t.saturating_add((20 as Weight).saturating_mul(a as Weight))\n\
+			}\n",
+		);
+	}
+}
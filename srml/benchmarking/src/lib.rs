@@ -0,0 +1,300 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Benchmarking Module
+//!
+//! Tools to measure the execution time of dispatchable functions over a range of inputs, in order
+//! to derive their weights.
+//!
+//! ## Overview
+//!
+//! A benchmark sets up the state for a call, parametrized by a number of *components* (e.g. the
+//! number of entries a call has to iterate over), and measures the time it takes to dispatch the
+//! call. Every component is varied over its range while the other components are kept at their
+//! highest value, so that the influence of each component can be estimated separately.
+//!
+//! Modules declare their benchmarks with the [`benchmarks!`](./macro.benchmarks.html) macro and
+//! the runtime exposes them through the [`Benchmark`](./trait.Benchmark.html) runtime api. The
+//! node runs them in Wasm with the `benchmark` subcommand, which turns the measurements into weight
+//! functions with [`Analysis`](./struct.Analysis.html).
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! srml_benchmarking::benchmarks! {
+//! 	impl<T: Trait> for Module<T> {
+//! 		transfer(u in 1 .. 1000) {
+//! 			let caller = srml_benchmarking::account("caller", u);
+//! 			// ... prepare the state ...
+//! 			Ok((system::RawOrigin::Signed(caller).into(), Call::<T>::transfer(/* ... */)))
+//! 		}
+//! 	}
+//! }
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+#[cfg(feature = "std")]
+mod analysis;
+
+#[cfg(feature = "std")]
+pub use analysis::Analysis;
+
+use rstd::prelude::*;
+use client::decl_runtime_apis;
+use parity_codec::{Encode, Decode};
+use runtime_primitives::traits::Dispatchable;
+
+#[doc(hidden)]
+pub use rstd::vec::Vec as BenchmarkVec;
+
+/// Measurement of a single dispatch of a benchmarked call.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct BenchmarkResult {
+	/// Names and values of the components the call was set up with.
+	pub components: Vec<(Vec<u8>, u32)>,
+	/// Time spent dispatching the call, in nanoseconds.
+	pub time: u64,
+}
+
+decl_runtime_apis! {
+	/// The api to run the benchmarks of the runtime modules.
+	pub trait Benchmark {
+		/// Runs `benchmark` of `module` and returns the measurements.
+		///
+		/// Returns `None` if the runtime has no such benchmark.
+		fn dispatch_benchmark(
+			module: Vec<u8>,
+			benchmark: Vec<u8>,
+			steps: u32,
+			repeat: u32,
+		) -> Option<Vec<BenchmarkResult>>;
+	}
+}
+
+/// The benchmarks of a module, usually implemented with `benchmarks!`.
+pub trait Benchmarking {
+	/// Names of all the benchmarks.
+	fn benchmarks() -> Vec<&'static [u8]>;
+
+	/// Runs the benchmark called `name`.
+	///
+	/// Every component takes `steps` values over its range and every setting is measured
+	/// `repeat` times.
+	fn run_benchmark(name: &[u8], steps: u32, repeat: u32) -> Result<Vec<BenchmarkResult>, &'static str>;
+}
+
+/// Returns `steps` evenly spaced values of the range `low ..= high`, always including `high`.
+pub fn component_values(low: u32, high: u32, steps: u32) -> Vec<u32> {
+	if high <= low || steps <= 1 {
+		return vec![high];
+	}
+
+	let step_size = ((high - low) / (steps - 1)).max(1);
+	let mut values = (0..steps)
+		.map(|i| low.saturating_add(i.saturating_mul(step_size)))
+		.take_while(|value| *value < high)
+		.collect::<Vec<_>>();
+	values.push(high);
+	values
+}
+
+/// Runs a benchmark whose components are given as `(name, low, high)`.
+///
+/// `setup` is called with one value per component, in the given order, and returns the origin and
+/// the call whose dispatch is timed. Used by `benchmarks!`.
+pub fn run<C, S>(
+	components: &[(&'static str, u32, u32)],
+	steps: u32,
+	repeat: u32,
+	setup: S,
+) -> Result<Vec<BenchmarkResult>, &'static str> where
+	C: Dispatchable,
+	S: Fn(&[u32]) -> Result<(C::Origin, C), &'static str>,
+{
+	let highest = components.iter().map(|(_, _, high)| *high).collect::<Vec<_>>();
+	let mut settings = Vec::new();
+	if components.is_empty() {
+		settings.push(Vec::new());
+	}
+	for (index, (_, low, high)) in components.iter().enumerate() {
+		for value in component_values(*low, *high, steps) {
+			let mut setting = highest.clone();
+			setting[index] = value;
+			settings.push(setting);
+		}
+	}
+
+	let mut results = Vec::with_capacity(settings.len() * repeat.max(1) as usize);
+	for setting in settings {
+		for _ in 0..repeat.max(1) {
+			let (origin, call) = setup(&setting)?;
+			let start = runtime_io::benchmark_time_nanos();
+			call.dispatch(origin)?;
+			let end = runtime_io::benchmark_time_nanos();
+
+			results.push(BenchmarkResult {
+				components: components.iter()
+					.zip(setting.iter())
+					.map(|((name, _, _), value)| (name.as_bytes().to_vec(), *value))
+					.collect(),
+				time: end.saturating_sub(start),
+			});
+		}
+	}
+	Ok(results)
+}
+
+/// Deterministically derives an account id from a name and an index.
+pub fn account<AccountId: Decode + Default>(name: &'static str, index: u32) -> AccountId {
+	let entropy = (name, index).using_encoded(runtime_io::blake2_256);
+	AccountId::decode(&mut &entropy[..]).unwrap_or_default()
+}
+
+/// Implements `Benchmarking` for a module.
+///
+/// Every benchmark is declared with its components and their ranges, followed by a block that
+/// prepares the state and returns `Ok((origin, call))`. The components are available in the block
+/// as `u32` variables.
+///
+/// ```ignore
+/// benchmarks! {
+/// 	impl<T: Trait> for Module<T> {
+/// 		do_something(a in 0 .. 100, b in 1 .. 10) {
+/// 			let caller = account("caller", 0);
+/// 			Ok((RawOrigin::Signed(caller).into(), Call::<T>::do_something(a, b)))
+/// 		}
+/// 	}
+/// }
+/// ```
+#[macro_export]
+macro_rules! benchmarks {
+	(
+		impl<$t:ident: $bound:path> for $module:ty {
+			$(
+				$name:ident ( $( $component:ident in $low:tt .. $high:tt ),* ) $setup:block
+			)*
+		}
+	) => {
+		impl<$t: $bound> $crate::Benchmarking for $module {
+			fn benchmarks() -> $crate::BenchmarkVec<&'static [u8]> {
+				let names: &[&'static [u8]] = &[ $( stringify!($name).as_bytes(), )* ];
+				names.to_vec()
+			}
+
+			fn run_benchmark(
+				name: &[u8],
+				steps: u32,
+				repeat: u32,
+			) -> Result<$crate::BenchmarkVec<$crate::BenchmarkResult>, &'static str> {
+				$(
+					if name == stringify!($name).as_bytes() {
+						return $crate::run(
+							&[ $( (stringify!($component), $low, $high), )* ],
+							steps,
+							repeat,
+							|values: &[u32]| {
+								let mut _values = values.iter().cloned();
+								$(
+									let $component: u32 = _values.next()
+										.expect("`run` passes one value per component; qed");
+								)*
+								$setup
+							},
+						);
+					}
+				)*
+				Err("No such benchmark")
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_primitives::traits::DispatchResult;
+
+	struct Call(u32);
+
+	impl Dispatchable for Call {
+		type Origin = ();
+		type Trait = ();
+		fn dispatch(self, _origin: ()) -> DispatchResult {
+			if self.0 > 100 { Err("Too big") } else { Ok(()) }
+		}
+	}
+
+	pub trait Trait {}
+	impl Trait for () {}
+
+	pub struct Generic<T>(T);
+
+	benchmarks! {
+		impl<T: Trait> for Generic<T> {
+			single(n in 1 .. 10) {
+				Ok(((), Call(n)))
+			}
+			double(a in 0 .. 4, b in 10 .. 20) {
+				Ok(((), Call(a + b)))
+			}
+			failing(n in 0 .. 200) {
+				Ok(((), Call(n)))
+			}
+		}
+	}
+
+	fn components(results: &[BenchmarkResult]) -> Vec<Vec<u32>> {
+		results.iter().map(|r| r.components.iter().map(|(_, v)| *v).collect()).collect()
+	}
+
+	#[test]
+	fn component_values_cover_the_range() {
+		assert_eq!(component_values(0, 10, 3), vec![0, 5, 10]);
+		assert_eq!(component_values(1, 3, 10), vec![1, 2, 3]);
+		assert_eq!(component_values(5, 5, 10), vec![5]);
+		assert_eq!(component_values(0, 10, 1), vec![10]);
+	}
+
+	#[test]
+	fn benchmarks_are_listed() {
+		assert_eq!(
+			<Generic<()> as Benchmarking>::benchmarks(),
+			vec![&b"single"[..], &b"double"[..], &b"failing"[..]],
+		);
+	}
+
+	#[test]
+	fn components_are_varied_one_at_a_time() {
+		let results = <Generic<()> as Benchmarking>::run_benchmark(b"double", 3, 2).unwrap();
+		assert_eq!(
+			components(&results),
+			vec![
+				vec![0, 20], vec![0, 20], vec![2, 20], vec![2, 20], vec![4, 20], vec![4, 20],
+				vec![4, 10], vec![4, 10], vec![4, 15], vec![4, 15], vec![4, 20], vec![4, 20],
+			],
+		);
+		assert_eq!(results[0].components[1].0, b"b".to_vec());
+	}
+
+	#[test]
+	fn errors_are_reported() {
+		assert_eq!(<Generic<()> as Benchmarking>::run_benchmark(b"failing", 2, 1), Err("Too big"));
+		assert_eq!(<Generic<()> as Benchmarking>::run_benchmark(b"unknown", 2, 1), Err("No such benchmark"));
+	}
+}
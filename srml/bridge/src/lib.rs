@@ -0,0 +1,827 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Bridge Module
+//!
+//! An on-chain light client of another chain finalized by GRANDPA.
+//!
+//! - [`bridge::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//! - [`Module`](./struct.Module.html)
+//!
+//! ## Overview
+//!
+//! The module tracks the headers of the bridged chain, starting from a trusted header and
+//! authority set given at initialization. Relayers, as allowed by `RelayerOrigin`, import headers
+//! of the bridged chain, which are only accepted on top of already imported ones, and anyone can
+//! prove their finality with GRANDPA justifications. A justification is checked against the
+//! authority set currently tracked for the bridged chain: its precommits must be signed by
+//! authorities holding more than two thirds of the weight of the set.
+//!
+//! Authority set changes scheduled in the digests of the bridged headers are followed: once the
+//! header signaling a change is finalized, the new set takes over from the header enacting it,
+//! and justifications of the old set can't finalize past that header. Forced changes are
+//! followed in the same way, as the digest of a header can only be trusted once it is finalized.
+//! If the current set can't finalize the header signaling a forced change anymore, the bridge
+//! stalls and must be set up again.
+//!
+//! Only the best finalized header is kept: once a header is finalized, the headers at its height
+//! and below are removed, while the hashes of the finalized headers remain available.
+//!
+//! Other modules can rely on [`finalized_header`](./struct.Module.html#method.finalized_header)
+//! and [`is_finalized`](./struct.Module.html#method.is_finalized) to trustlessly act on the
+//! state of the bridged chain.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `initialize` - Set the trusted header and authority set of the bridged chain. Root only.
+//! - `import_header` - Import a header of the bridged chain on top of an imported one. Relayers
+//!   only.
+//! - `finalize_header` - Finalize an imported header with a GRANDPA justification.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use parity_codec::{Encode, Decode};
+use primitives::{
+	generic::OpaqueDigestItemId,
+	traits::{EnsureOrigin, Header as HeaderT, One, Verify},
+};
+use srml_support::{StorageValue, StorageMap, decl_module, decl_storage, decl_event, ensure};
+use system::{ensure_root, ensure_signed};
+use substrate_finality_grandpa_primitives::{ConsensusLog, ScheduledChange, GRANDPA_ENGINE_ID};
+pub use substrate_finality_grandpa_primitives::{AuthorityId, AuthoritySignature, AuthorityWeight};
+
+/// The hash type of the bridged chain.
+pub type BridgedHashOf<T> = <<T as Trait>::BridgedHeader as HeaderT>::Hash;
+/// The block number type of the bridged chain.
+pub type BridgedNumberOf<T> = <<T as Trait>::BridgedHeader as HeaderT>::Number;
+
+/// A GRANDPA vote to finalize the given block and its ancestors.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Precommit<Hash, Number> {
+	/// The hash of the voted block.
+	pub target_hash: Hash,
+	/// The number of the voted block.
+	pub target_number: Number,
+}
+
+/// A precommit signed by a GRANDPA authority.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SignedPrecommit<Hash, Number> {
+	/// The precommit.
+	pub precommit: Precommit<Hash, Number>,
+	/// The signature of the authority over the precommit.
+	pub signature: AuthoritySignature,
+	/// The authority which signed the precommit.
+	pub id: AuthorityId,
+}
+
+/// A GRANDPA commit: the precommits of a round finalizing its target.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Commit<Hash, Number> {
+	/// The hash of the finalized block.
+	pub target_hash: Hash,
+	/// The number of the finalized block.
+	pub target_number: Number,
+	/// The precommits justifying the finality of the target.
+	pub precommits: Vec<SignedPrecommit<Hash, Number>>,
+}
+
+/// A GRANDPA justification, as produced by the nodes of the bridged chain: a commit along with
+/// the headers routing the targets of its precommits to the commit target.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct GrandpaJustification<Header: HeaderT> {
+	/// The round of the commit.
+	pub round: u64,
+	/// The commit.
+	pub commit: Commit<Header::Hash, Header::Number>,
+	/// The ancestry of the precommit targets, down to the commit target.
+	pub votes_ancestries: Vec<Header>,
+}
+
+/// An authority set change of the bridged chain, signaled in a finalized header.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PendingChange<Number> {
+	/// The number of the header from which the new set takes over.
+	pub enact_at: Number,
+	/// The next authority set.
+	pub next_authorities: Vec<(AuthorityId, AuthorityWeight)>,
+}
+
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The header type of the bridged chain.
+	type BridgedHeader: HeaderT;
+
+	/// The origin allowed to import headers of the bridged chain.
+	type RelayerOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Bridge {
+		/// The imported headers of the bridged chain, by hash.
+		pub ImportedHeaders get(imported_header): map BridgedHashOf<T> => Option<T::BridgedHeader>;
+
+		/// The hashes of the imported headers of the bridged chain which are not finalized yet,
+		/// by number.
+		pub ImportedHashes get(imported_hashes): map BridgedNumberOf<T> => Vec<BridgedHashOf<T>>;
+
+		/// The hash of the best finalized header of the bridged chain.
+		pub BestFinalized get(best_finalized): Option<BridgedHashOf<T>>;
+
+		/// The hashes of the finalized headers of the bridged chain, by number.
+		pub FinalizedHashes get(finalized_hash): map BridgedNumberOf<T> => Option<BridgedHashOf<T>>;
+
+		/// The current authority set of the bridged chain.
+		pub Authorities get(authorities): Vec<(AuthorityId, AuthorityWeight)>;
+
+		/// The id of the current authority set of the bridged chain.
+		pub SetId get(set_id): u64;
+
+		/// The authority set change of the bridged chain waiting to be enacted, if any.
+		pub NextChange get(next_change): Option<PendingChange<BridgedNumberOf<T>>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where Hash = BridgedHashOf<T>, Number = BridgedNumberOf<T> {
+		/// A header of the bridged chain was imported.
+		HeaderImported(Hash, Number),
+		/// A header of the bridged chain was finalized.
+		HeaderFinalized(Hash, Number),
+		/// An authority set change of the bridged chain was scheduled from the given header.
+		AuthoritySetChangeScheduled(Number),
+		/// The authority set of the bridged chain changed to the given set id.
+		AuthoritySetChanged(u64),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event<T>() = default;
+
+		/// Set the trusted header and authority set the bridged chain is tracked from.
+		///
+		/// The dispatch origin for this call must be _Root_, and the bridge must not be
+		/// initialized yet.
+		fn initialize(
+			origin,
+			header: T::BridgedHeader,
+			authorities: Vec<(AuthorityId, AuthorityWeight)>,
+			set_id: u64
+		) {
+			ensure_root(origin)?;
+			ensure!(Self::best_finalized().is_none(), "Bridge already initialized");
+			ensure!(!authorities.is_empty(), "Empty authority set");
+
+			let hash = header.hash();
+			let number = *header.number();
+			<FinalizedHashes<T>>::insert(number, hash);
+			<BestFinalized<T>>::put(hash);
+			<ImportedHeaders<T>>::insert(hash, header);
+			<Authorities<T>>::put(authorities);
+			<SetId<T>>::put(set_id);
+
+			Self::deposit_event(RawEvent::HeaderFinalized(hash, number));
+		}
+
+		/// Import a header of the bridged chain. Its parent must be imported already.
+		///
+		/// The dispatch origin for this call must be `RelayerOrigin`.
+		fn import_header(origin, header: T::BridgedHeader) {
+			T::RelayerOrigin::ensure_origin(origin)?;
+			let best_finalized = Self::finalized_header().ok_or("Bridge not initialized")?;
+
+			let hash = header.hash();
+			let number = *header.number();
+			ensure!(!<ImportedHeaders<T>>::exists(&hash), "Header already imported");
+			ensure!(number > *best_finalized.number(), "Header is older than the best finalized header");
+			let parent = Self::imported_header(header.parent_hash()).ok_or("Parent header not imported")?;
+			ensure!(number == *parent.number() + One::one(), "Header number does not follow its parent");
+
+			<ImportedHeaders<T>>::insert(hash, header);
+			<ImportedHashes<T>>::mutate(number, |hashes| hashes.push(hash));
+
+			Self::deposit_event(RawEvent::HeaderImported(hash, number));
+		}
+
+		/// Finalize an imported header of the bridged chain with a GRANDPA justification of the
+		/// current authority set. The header must descend from the best finalized header.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		fn finalize_header(origin, hash: BridgedHashOf<T>, justification: GrandpaJustification<T::BridgedHeader>) {
+			ensure_signed(origin)?;
+			let best_finalized = Self::finalized_header().ok_or("Bridge not initialized")?;
+			let header = Self::imported_header(&hash).ok_or("Header not imported")?;
+			let number = *header.number();
+			ensure!(number > *best_finalized.number(), "Header already finalized");
+
+			// the headers finalized along with `header`, in ascending order.
+			let route = Self::route_from_best_finalized(header, &best_finalized)?;
+
+			let mut next_change = Self::next_change();
+			let mut signaled_at = None;
+			if next_change.is_none() {
+				next_change = route.iter()
+					.filter_map(|header| Self::scheduled_change(header).map(|change| (header, change)))
+					.next()
+					.map(|(header, change)| {
+						signaled_at = Some(*header.number());
+						PendingChange {
+							enact_at: *header.number() + change.delay,
+							next_authorities: change.next_authorities,
+						}
+					});
+			}
+			if let Some(ref change) = next_change {
+				ensure!(
+					number <= change.enact_at,
+					"Header is past a scheduled authority set change"
+				);
+			}
+
+			verify_justification::<T::BridgedHeader>(
+				&hash,
+				number,
+				&justification,
+				&Self::authorities(),
+				Self::set_id(),
+			)?;
+
+			// the other headers at the finalized heights can never be finalized, and only the
+			// best finalized header is needed to import its descendants.
+			<ImportedHeaders<T>>::remove(best_finalized.hash());
+			for header in &route {
+				for imported in <ImportedHashes<T>>::take(header.number()) {
+					if imported != hash {
+						<ImportedHeaders<T>>::remove(imported);
+					}
+				}
+				<FinalizedHashes<T>>::insert(*header.number(), header.hash());
+			}
+			<BestFinalized<T>>::put(hash);
+			Self::deposit_event(RawEvent::HeaderFinalized(hash, number));
+
+			if let Some(signaled_at) = signaled_at {
+				Self::deposit_event(RawEvent::AuthoritySetChangeScheduled(signaled_at));
+			}
+			match next_change {
+				Some(change) if change.enact_at == number => {
+					let set_id = Self::set_id() + 1;
+					<Authorities<T>>::put(change.next_authorities);
+					<SetId<T>>::put(set_id);
+					<NextChange<T>>::kill();
+					Self::deposit_event(RawEvent::AuthoritySetChanged(set_id));
+				}
+				Some(change) => <NextChange<T>>::put(change),
+				None => {}
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The best finalized header of the bridged chain, if the bridge is initialized.
+	pub fn finalized_header() -> Option<T::BridgedHeader> {
+		Self::best_finalized().and_then(Self::imported_header)
+	}
+
+	/// Whether the given header of the bridged chain is finalized.
+	pub fn is_finalized(header: &T::BridgedHeader) -> bool {
+		Self::finalized_hash(header.number()) == Some(header.hash())
+	}
+
+	/// The authority set change, scheduled or forced, signaled in the digest of the given header,
+	/// if any.
+	fn scheduled_change(header: &T::BridgedHeader) -> Option<ScheduledChange<BridgedNumberOf<T>>> {
+		let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+		header.digest().logs().iter()
+			.filter_map(|l| l.try_to::<ConsensusLog<BridgedNumberOf<T>>>(id))
+			.filter_map(|log| match log {
+				ConsensusLog::ScheduledChange(change) => Some(change),
+				ConsensusLog::ForcedChange(_, change) => Some(change),
+				_ => None,
+			})
+			.next()
+	}
+
+	/// The imported headers from the child of `best_finalized` up to `header`, in ascending order.
+	///
+	/// Fails if `header` doesn't descend from `best_finalized`.
+	fn route_from_best_finalized(
+		header: T::BridgedHeader,
+		best_finalized: &T::BridgedHeader,
+	) -> rstd::result::Result<Vec<T::BridgedHeader>, &'static str> {
+		let best_hash = best_finalized.hash();
+		let mut route = vec![header];
+		loop {
+			let parent_hash = *route[route.len() - 1].parent_hash();
+			if parent_hash == best_hash {
+				break;
+			}
+			let parent = Self::imported_header(&parent_hash)
+				.filter(|parent| parent.number() > best_finalized.number())
+				.ok_or("Header is not a descendant of the best finalized header")?;
+			route.push(parent);
+		}
+		route.reverse();
+		Ok(route)
+	}
+}
+
+/// The message a GRANDPA authority signs for `precommit` in the given round and set, mirroring
+/// the encoding of `(Message::Precommit(precommit), round, set_id)` by the GRANDPA client.
+pub fn precommit_payload<Hash: Encode, Number: Encode>(
+	precommit: &Precommit<Hash, Number>,
+	round: u64,
+	set_id: u64,
+) -> Vec<u8> {
+	// `1` is the index of the `Precommit` variant of the GRANDPA `Message` enum.
+	(1u8, precommit, round, set_id).encode()
+}
+
+/// Check that `justification` proves the finality of the header with the given hash and number
+/// by the authority set `authorities`, with id `set_id`.
+pub fn verify_justification<Header: HeaderT>(
+	hash: &Header::Hash,
+	number: Header::Number,
+	justification: &GrandpaJustification<Header>,
+	authorities: &[(AuthorityId, AuthorityWeight)],
+	set_id: u64,
+) -> rstd::result::Result<(), &'static str> {
+	let commit = &justification.commit;
+	ensure!(
+		commit.target_hash == *hash && commit.target_number == number,
+		"Justification is for another header"
+	);
+
+	let ancestries: Vec<(Header::Hash, &Header)> = justification.votes_ancestries.iter()
+		.map(|header| (header.hash(), header))
+		.collect();
+	let mut visited_ancestries = Vec::new();
+	let mut signers = Vec::new();
+	let mut weight: AuthorityWeight = 0;
+
+	for signed in &commit.precommits {
+		ensure!(!signers.contains(&signed.id), "Duplicate precommit in justification");
+		let authority_weight = authorities.iter()
+			.find(|(id, _)| *id == signed.id)
+			.map(|(_, weight)| *weight)
+			.ok_or("Precommit from an unknown authority")?;
+		let payload = precommit_payload(&signed.precommit, justification.round, set_id);
+		ensure!(signed.signature.verify(&payload[..], &signed.id), "Invalid precommit signature");
+
+		// the precommit target must be the commit target or one of its descendants.
+		let mut current = signed.precommit.target_hash;
+		while current != commit.target_hash {
+			let (_, header) = ancestries.iter()
+				.find(|(hash, _)| *hash == current)
+				.ok_or("Precommit target is not a descendant of the commit target")?;
+			ensure!(
+				*header.number() > commit.target_number,
+				"Precommit target is not a descendant of the commit target"
+			);
+			if !visited_ancestries.contains(&current) {
+				visited_ancestries.push(current);
+			}
+			current = *header.parent_hash();
+		}
+
+		signers.push(signed.id.clone());
+		weight = weight.saturating_add(authority_weight);
+	}
+
+	ensure!(
+		visited_ancestries.len() == ancestries.len(),
+		"Justification contains unused ancestry headers"
+	);
+
+	let total_weight = authorities.iter()
+		.fold(0 as AuthorityWeight, |total, (_, weight)| total.saturating_add(*weight));
+	let threshold = total_weight - total_weight.saturating_sub(1) / 3;
+	ensure!(weight >= threshold, "Not enough precommit weight in justification");
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use srml_support::{assert_noop, assert_ok, impl_outer_origin, parameter_types};
+	use substrate_primitives::{H256, Blake2Hasher, ed25519, crypto::Pair};
+	use primitives::{
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header},
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const Relayer: u64 = 1;
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type BridgedHeader = Header;
+		type RelayerOrigin = system::EnsureSignedBy<Relayer, u64>;
+	}
+	type Bridge = Module<Test>;
+
+	fn authority(i: u8) -> ed25519::Pair {
+		ed25519::Pair::from_seed(&[i; 32])
+	}
+
+	fn authority_set(authorities: &[u8]) -> Vec<(AuthorityId, AuthorityWeight)> {
+		authorities.iter().map(|i| (authority(*i).public(), 1)).collect()
+	}
+
+	fn header(number: u64, parent: &Header) -> Header {
+		Header {
+			parent_hash: parent.hash(),
+			number,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		}
+	}
+
+	fn with_logs(mut header: Header, logs: Vec<ConsensusLog<u64>>) -> Header {
+		header.digest = Digest {
+			logs: logs.into_iter()
+				.map(|log| DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode()))
+				.collect(),
+		};
+		header
+	}
+
+	fn genesis() -> Header {
+		Header {
+			parent_hash: Default::default(),
+			number: 0,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		}
+	}
+
+	/// Import a chain of `count` headers on top of `parent`, returning them.
+	fn import_chain(parent: &Header, count: u64) -> Vec<Header> {
+		let mut headers: Vec<Header> = Vec::new();
+		for _ in 0..count {
+			let next = {
+				let parent = headers.last().unwrap_or(parent);
+				header(parent.number + 1, parent)
+			};
+			assert_ok!(Bridge::import_header(Origin::signed(1), next.clone()));
+			headers.push(next);
+		}
+		headers
+	}
+
+	fn justification(target: &Header, signers: &[u8], set_id: u64) -> GrandpaJustification<Header> {
+		let precommit = Precommit { target_hash: target.hash(), target_number: target.number };
+		let precommits = signers.iter().map(|i| {
+			let pair = authority(*i);
+			SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: pair.sign(&precommit_payload(&precommit, 1, set_id)),
+				id: pair.public(),
+			}
+		}).collect();
+		GrandpaJustification {
+			round: 1,
+			commit: Commit { target_hash: target.hash(), target_number: target.number, precommits },
+			votes_ancestries: Vec::new(),
+		}
+	}
+
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+		let mut ext: runtime_io::TestExternalities<Blake2Hasher> = t.into();
+		with_externalities(&mut ext, || {
+			assert_ok!(Bridge::initialize(Origin::ROOT, genesis(), authority_set(&[1, 2, 3, 4]), 0));
+		});
+		ext
+	}
+
+	#[test]
+	fn initialize_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(Bridge::finalized_header(), Some(genesis()));
+			assert!(Bridge::is_finalized(&genesis()));
+			assert_eq!(Bridge::authorities(), authority_set(&[1, 2, 3, 4]));
+			assert_noop!(
+				Bridge::initialize(Origin::ROOT, genesis(), authority_set(&[1]), 0),
+				"Bridge already initialized"
+			);
+		});
+	}
+
+	#[test]
+	fn import_header_checks_ancestry() {
+		with_externalities(&mut new_test_ext(), || {
+			let headers = import_chain(&genesis(), 2);
+			assert_noop!(
+				Bridge::import_header(Origin::signed(1), headers[1].clone()),
+				"Header already imported"
+			);
+
+			let mut orphan = header(3, &headers[1]);
+			orphan.parent_hash = H256::repeat_byte(1);
+			assert_noop!(Bridge::import_header(Origin::signed(1), orphan), "Parent header not imported");
+			assert_noop!(
+				Bridge::import_header(Origin::signed(1), header(4, &headers[1])),
+				"Header number does not follow its parent"
+			);
+			assert_eq!(Bridge::imported_header(headers[1].hash()), Some(headers[1].clone()));
+		});
+	}
+
+	#[test]
+	fn import_header_requires_relayer() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(Bridge::import_header(Origin::signed(2), header(1, &genesis())), "Invalid origin");
+			assert_noop!(Bridge::import_header(Origin::NONE, header(1, &genesis())), "Invalid origin");
+		});
+	}
+
+	#[test]
+	fn finalize_header_works() {
+		with_externalities(&mut new_test_ext(), || {
+			let headers = import_chain(&genesis(), 3);
+			assert_ok!(Bridge::finalize_header(
+				Origin::signed(1),
+				headers[2].hash(),
+				justification(&headers[2], &[1, 2, 3], 0),
+			));
+
+			assert_eq!(Bridge::finalized_header(), Some(headers[2].clone()));
+			assert!(headers.iter().all(Bridge::is_finalized));
+
+			// headers below the best finalized one can no longer be imported.
+			assert_noop!(
+				Bridge::import_header(Origin::signed(1), header(2, &headers[0])),
+				"Header is older than the best finalized header"
+			);
+		});
+	}
+
+	#[test]
+	fn finalize_header_requires_supermajority() {
+		with_externalities(&mut new_test_ext(), || {
+			let headers = import_chain(&genesis(), 1);
+			assert_noop!(
+				Bridge::finalize_header(Origin::signed(1), headers[0].hash(), justification(&headers[0], &[1, 2], 0)),
+				"Not enough precommit weight in justification"
+			);
+			assert_noop!(
+				Bridge::finalize_header(
+					Origin::signed(1),
+					headers[0].hash(),
+					justification(&headers[0], &[1, 2, 2], 0),
+				),
+				"Duplicate precommit in justification"
+			);
+			assert_noop!(
+				Bridge::finalize_header(
+					Origin::signed(1),
+					headers[0].hash(),
+					justification(&headers[0], &[1, 2, 5], 0),
+				),
+				"Precommit from an unknown authority"
+			);
+		});
+	}
+
+	#[test]
+	fn finalize_header_checks_signatures() {
+		with_externalities(&mut new_test_ext(), || {
+			let headers = import_chain(&genesis(), 1);
+			// signed for another set.
+			assert_noop!(
+				Bridge::finalize_header(
+					Origin::signed(1),
+					headers[0].hash(),
+					justification(&headers[0], &[1, 2, 3], 1),
+				),
+				"Invalid precommit signature"
+			);
+			assert_noop!(
+				Bridge::finalize_header(Origin::signed(1), headers[0].hash(), justification(&genesis(), &[1, 2, 3], 0)),
+				"Justification is for another header"
+			);
+		});
+	}
+
+	#[test]
+	fn justification_precommits_may_target_descendants() {
+		with_externalities(&mut new_test_ext(), || {
+			let headers = import_chain(&genesis(), 3);
+			let mut justification = justification(&headers[0], &[1, 2, 3], 0);
+			let precommit = Precommit { target_hash: headers[2].hash(), target_number: 3 };
+			justification.commit.precommits[0] = SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: authority(1).sign(&precommit_payload(&precommit, 1, 0)),
+				id: authority(1).public(),
+			};
+
+			// the route to the commit target is missing.
+			assert_noop!(
+				Bridge::finalize_header(Origin::signed(1), headers[0].hash(), justification.clone()),
+				"Precommit target is not a descendant of the commit target"
+			);
+
+			justification.votes_ancestries = vec![headers[2].clone(), headers[1].clone()];
+			assert_ok!(Bridge::finalize_header(Origin::signed(1), headers[0].hash(), justification));
+			assert_eq!(Bridge::finalized_header(), Some(headers[0].clone()));
+		});
+	}
+
+	#[test]
+	fn finalize_header_rejects_forks_of_finalized_chain() {
+		with_externalities(&mut new_test_ext(), || {
+			let headers = import_chain(&genesis(), 2);
+			let mut fork = header(1, &genesis());
+			fork.state_root = H256::repeat_byte(1);
+			assert_ok!(Bridge::import_header(Origin::signed(1), fork.clone()));
+			let fork = import_chain(&fork, 2);
+
+			assert_ok!(Bridge::finalize_header(
+				Origin::signed(1),
+				headers[0].hash(),
+				justification(&headers[0], &[1, 2, 3], 0),
+			));
+			assert_noop!(
+				Bridge::finalize_header(Origin::signed(1), fork[1].hash(), justification(&fork[1], &[1, 2, 3], 0)),
+				"Header is not a descendant of the best finalized header"
+			);
+		});
+	}
+
+	#[test]
+	fn finalize_header_prunes_finalized_and_fork_headers() {
+		with_externalities(&mut new_test_ext(), || {
+			let headers = import_chain(&genesis(), 3);
+			let mut fork = header(1, &genesis());
+			fork.state_root = H256::repeat_byte(1);
+			assert_ok!(Bridge::import_header(Origin::signed(1), fork.clone()));
+			let fork = import_chain(&fork, 3);
+
+			assert_ok!(Bridge::finalize_header(
+				Origin::signed(1),
+				headers[1].hash(),
+				justification(&headers[1], &[1, 2, 3], 0),
+			));
+
+			// only the best finalized header and the unfinalized heights are kept.
+			assert_eq!(Bridge::imported_header(genesis().hash()), None);
+			assert_eq!(Bridge::imported_header(headers[0].hash()), None);
+			assert_eq!(Bridge::imported_header(headers[1].hash()), Some(headers[1].clone()));
+			assert_eq!(Bridge::imported_header(fork[0].hash()), None);
+			assert_eq!(Bridge::imported_header(fork[1].hash()), None);
+			assert!(Bridge::imported_hashes(1).is_empty());
+			assert!(Bridge::imported_hashes(2).is_empty());
+			assert_eq!(Bridge::imported_hashes(3), vec![headers[2].hash(), fork[2].hash()]);
+
+			// pruned headers are still known to be finalized.
+			assert!(Bridge::is_finalized(&genesis()));
+			assert!(Bridge::is_finalized(&headers[0]));
+			assert!(!Bridge::is_finalized(&fork[0]));
+
+			assert_ok!(Bridge::finalize_header(
+				Origin::signed(1),
+				headers[2].hash(),
+				justification(&headers[2], &[1, 2, 3], 0),
+			));
+			assert_eq!(Bridge::imported_header(headers[1].hash()), None);
+			assert_eq!(Bridge::imported_header(fork[2].hash()), None);
+			assert!(Bridge::imported_hashes(3).is_empty());
+		});
+	}
+
+	#[test]
+	fn scheduled_authority_set_changes_are_followed() {
+		with_externalities(&mut new_test_ext(), || {
+			let change = ScheduledChange { next_authorities: authority_set(&[5, 6, 7]), delay: 2 };
+			let signal = with_logs(header(1, &genesis()), vec![ConsensusLog::ScheduledChange(change)]);
+			assert_ok!(Bridge::import_header(Origin::signed(1), signal.clone()));
+			let headers = import_chain(&signal, 3);
+
+			// the old set can't finalize past the header enacting the change.
+			assert_noop!(
+				Bridge::finalize_header(Origin::signed(1), headers[2].hash(), justification(&headers[2], &[1, 2, 3], 0)),
+				"Header is past a scheduled authority set change"
+			);
+
+			assert_ok!(Bridge::finalize_header(
+				Origin::signed(1),
+				signal.hash(),
+				justification(&signal, &[1, 2, 3], 0),
+			));
+			assert_eq!(
+				Bridge::next_change(),
+				Some(PendingChange { enact_at: 3, next_authorities: authority_set(&[5, 6, 7]) }),
+			);
+			assert_eq!(Bridge::set_id(), 0);
+
+			assert_ok!(Bridge::finalize_header(
+				Origin::signed(1),
+				headers[1].hash(),
+				justification(&headers[1], &[1, 2, 3], 0),
+			));
+			assert_eq!(Bridge::next_change(), None);
+			assert_eq!(Bridge::set_id(), 1);
+			assert_eq!(Bridge::authorities(), authority_set(&[5, 6, 7]));
+
+			// the new set finalizes the following headers.
+			assert_noop!(
+				Bridge::finalize_header(Origin::signed(1), headers[2].hash(), justification(&headers[2], &[1, 2, 3], 1)),
+				"Precommit from an unknown authority"
+			);
+			assert_ok!(Bridge::finalize_header(
+				Origin::signed(1),
+				headers[2].hash(),
+				justification(&headers[2], &[5, 6, 7], 1),
+			));
+			assert_eq!(Bridge::finalized_header(), Some(headers[2].clone()));
+		});
+	}
+
+	#[test]
+	fn forced_authority_set_changes_are_followed_once_finalized() {
+		with_externalities(&mut new_test_ext(), || {
+			let change = ScheduledChange { next_authorities: authority_set(&[5, 6, 7]), delay: 1 };
+			// the change isn't the first GRANDPA log of the header.
+			let signal = with_logs(
+				header(1, &genesis()),
+				vec![ConsensusLog::OnDisabled(0), ConsensusLog::ForcedChange(0, change)],
+			);
+			assert_ok!(Bridge::import_header(Origin::signed(1), signal.clone()));
+			let headers = import_chain(&signal, 2);
+
+			assert_ok!(Bridge::finalize_header(
+				Origin::signed(1),
+				headers[0].hash(),
+				justification(&headers[0], &[1, 2, 3], 0),
+			));
+			assert_eq!(Bridge::next_change(), None);
+			assert_eq!(Bridge::set_id(), 1);
+			assert_eq!(Bridge::authorities(), authority_set(&[5, 6, 7]));
+
+			assert_ok!(Bridge::finalize_header(
+				Origin::signed(1),
+				headers[1].hash(),
+				justification(&headers[1], &[5, 6, 7], 1),
+			));
+			assert_eq!(Bridge::finalized_header(), Some(headers[1].clone()));
+		});
+	}
+}
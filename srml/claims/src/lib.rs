@@ -0,0 +1,648 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Claims Module
+//!
+//! A module for claiming balances allocated to Ethereum addresses.
+//!
+//! - [`claims::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//! - [`Module`](./struct.Module.html)
+//!
+//! ## Overview
+//!
+//! Balances are allocated to Ethereum addresses at genesis, or later by root. The holder of an
+//! address claims its balance into an account by signing the account with `eth_sign`, i.e. by
+//! signing the message
+//!
+//! `"\x19Ethereum Signed Message:\n" + length + prefix + hex(account) + statement`
+//!
+//! where `prefix` is given by the runtime and `statement` is empty unless the claim requires
+//! one. A claim may require the claimant to attest to a statement, in which case the statement
+//! whose hash is stored with the claim must be included in the signed message. A claim may also
+//! come with a vesting schedule, which is attached to the account the balance is claimed into.
+//!
+//! Claims are unsigned transactions: the signature proves the claimant's right to the balance,
+//! so the account it's claimed into doesn't need to pay any fees.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `claim` - Claim the balance of the signing Ethereum address into an account.
+//! - `claim_attest` - Claim the balance of the signing Ethereum address into an account,
+//!   attesting to the statement the claim requires.
+//! - `mint_claim` - Allocate a new claim to an Ethereum address. Root only.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use parity_codec::Encode;
+use runtime_io::{keccak_256, secp256k1_ecdsa_recover};
+use primitives::{
+	ApplyError,
+	traits::{CheckedSub, Hash, Saturating, Zero},
+	transaction_validity::{TransactionValidity, TransactionLongevity, ValidTransaction},
+};
+use srml_support::{
+	StorageValue, StorageMap, decl_module, decl_storage, decl_event, ensure,
+	traits::{Currency, VestingCurrency, Get},
+};
+use system::{ensure_none, ensure_root};
+
+/// An Ethereum address.
+pub type EthereumAddress = [u8; 20];
+
+/// A recoverable secp256k1 signature, as produced by `eth_sign`.
+pub type EcdsaSignature = substrate_primitives::ecdsa::Signature;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// The module's configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency claims are paid out in.
+	type Currency: VestingCurrency<Self::AccountId, Moment = Self::BlockNumber>;
+
+	/// The prefix of the signed message, which should make it specific to this chain.
+	type Prefix: Get<&'static [u8]>;
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+	{
+		/// An Ethereum address claimed its balance into an account.
+		Claimed(AccountId, EthereumAddress, Balance),
+	}
+);
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Claims {
+		/// The balance claimable by each Ethereum address.
+		Claims get(claims) build(|config: &GenesisConfig<T>| {
+			config.claims.iter().map(|(address, balance, _)| (*address, *balance)).collect::<Vec<_>>()
+		}): map EthereumAddress => Option<BalanceOf<T>>;
+		/// The total balance of all claims.
+		Total get(total) build(|config: &GenesisConfig<T>| {
+			config.claims.iter().fold(Zero::zero(), |total: BalanceOf<T>, (_, balance, _)| {
+				total.saturating_add(*balance)
+			})
+		}): BalanceOf<T>;
+		/// The vesting schedule of a claim: the locked balance, the balance unlocked per block and
+		/// the block from which it starts unlocking.
+		Vesting get(vesting) config(): map EthereumAddress => Option<(BalanceOf<T>, BalanceOf<T>, T::BlockNumber)>;
+		/// The hash of the statement a claim requires its claimant to attest to.
+		Signing get(signing) build(|config: &GenesisConfig<T>| {
+			config.claims.iter()
+				.filter_map(|(address, _, statement)| statement.map(|statement| (*address, statement)))
+				.collect::<Vec<_>>()
+		}): map EthereumAddress => Option<T::Hash>;
+	}
+	add_extra_genesis {
+		config(claims): Vec<(EthereumAddress, BalanceOf<T>, Option<T::Hash>)>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event<T>() = default;
+
+		/// Claim the balance of the Ethereum address which signed `dest` into `dest`.
+		///
+		/// Fails if the claim requires a statement; use `claim_attest` for those.
+		fn claim(origin, dest: T::AccountId, ethereum_signature: EcdsaSignature) {
+			ensure_none(origin)?;
+
+			let data = dest.using_encoded(to_ascii_hex);
+			let signer = Self::eth_recover(&ethereum_signature, &data, &[][..])
+				.ok_or("Invalid Ethereum signature")?;
+			ensure!(!<Signing<T>>::exists(&signer), "The claim requires attesting to a statement");
+
+			Self::process_claim(signer, dest)?;
+		}
+
+		/// Claim the balance of the Ethereum address which signed `dest` and `statement` into
+		/// `dest`.
+		///
+		/// `statement` must be the statement the claim requires, if it requires one.
+		fn claim_attest(
+			origin,
+			dest: T::AccountId,
+			ethereum_signature: EcdsaSignature,
+			statement: Vec<u8>
+		) {
+			ensure_none(origin)?;
+
+			let data = dest.using_encoded(to_ascii_hex);
+			let signer = Self::eth_recover(&ethereum_signature, &data, &statement)
+				.ok_or("Invalid Ethereum signature")?;
+			if let Some(required) = <Signing<T>>::get(&signer) {
+				ensure!(T::Hashing::hash(&statement) == required, "Invalid statement");
+			}
+
+			Self::process_claim(signer, dest)?;
+		}
+
+		/// Allocate `value` to the Ethereum address `who`, optionally with a vesting schedule and
+		/// the hash of a statement the claimant must attest to.
+		///
+		/// Replaces any claim `who` already has.
+		fn mint_claim(
+			origin,
+			who: EthereumAddress,
+			value: BalanceOf<T>,
+			vesting_schedule: Option<(BalanceOf<T>, BalanceOf<T>, T::BlockNumber)>,
+			statement: Option<T::Hash>
+		) {
+			ensure_root(origin)?;
+
+			let previous = <Claims<T>>::get(&who).unwrap_or_else(Zero::zero);
+			<Total<T>>::mutate(|total| *total = total.saturating_sub(previous).saturating_add(value));
+			<Claims<T>>::insert(&who, value);
+			match vesting_schedule {
+				Some(vesting_schedule) => <Vesting<T>>::insert(&who, vesting_schedule),
+				None => <Vesting<T>>::remove(&who),
+			}
+			match statement {
+				Some(statement) => <Signing<T>>::insert(&who, statement),
+				None => <Signing<T>>::remove(&who),
+			}
+		}
+	}
+}
+
+/// The lowercase ASCII hex encoding of `data`.
+fn to_ascii_hex(data: &[u8]) -> Vec<u8> {
+	let mut hex = Vec::with_capacity(data.len() * 2);
+	let mut push_nibble = |nibble: u8| hex.push(if nibble < 10 { b'0' + nibble } else { b'a' - 10 + nibble });
+	for byte in data {
+		push_nibble(byte >> 4);
+		push_nibble(byte & 0x0f);
+	}
+	hex
+}
+
+impl<T: Trait> Module<T> {
+	/// The message `eth_sign` signs for `what` and `extra`: the Ethereum prefix and length,
+	/// followed by the chain's prefix, `what` and `extra`.
+	pub fn ethereum_signable_message(what: &[u8], extra: &[u8]) -> Vec<u8> {
+		let prefix = T::Prefix::get();
+		let mut length = prefix.len() + what.len() + extra.len();
+		let mut length_digits = Vec::new();
+		while length > 0 {
+			length_digits.push(b'0' + (length % 10) as u8);
+			length /= 10;
+		}
+
+		let mut message = b"\x19Ethereum Signed Message:\n".to_vec();
+		message.extend(length_digits.into_iter().rev());
+		message.extend_from_slice(prefix);
+		message.extend_from_slice(what);
+		message.extend_from_slice(extra);
+		message
+	}
+
+	/// The Ethereum address which signed `what` and `extra` with `signature`, if it's valid.
+	pub fn eth_recover(signature: &EcdsaSignature, what: &[u8], extra: &[u8]) -> Option<EthereumAddress> {
+		let message_hash = keccak_256(&Self::ethereum_signable_message(what, extra));
+		let public = secp256k1_ecdsa_recover(&signature.0, &message_hash).ok()?;
+		let mut address = EthereumAddress::default();
+		address.copy_from_slice(&keccak_256(&public[..])[12..]);
+		Some(address)
+	}
+
+	/// Pay out the claim of `signer` into `dest`, attaching its vesting schedule.
+	fn process_claim(signer: EthereumAddress, dest: T::AccountId) -> srml_support::dispatch::Result {
+		let balance_due = <Claims<T>>::get(&signer).ok_or("Ethereum address has no claim")?;
+		let new_total = Self::total().checked_sub(&balance_due).ok_or("Claim exceeds the total")?;
+
+		// attach the vesting schedule first, so that nothing is paid out if `dest` already has one.
+		if let Some((locked, per_block, starting_block)) = <Vesting<T>>::get(&signer) {
+			T::Currency::add_vesting_schedule(&dest, locked, per_block, starting_block)?;
+		}
+		T::Currency::deposit_creating(&dest, balance_due);
+
+		<Total<T>>::put(new_total);
+		<Claims<T>>::remove(&signer);
+		<Vesting<T>>::remove(&signer);
+		<Signing<T>>::remove(&signer);
+
+		Self::deposit_event(RawEvent::Claimed(dest, signer, balance_due));
+		Ok(())
+	}
+}
+
+impl<T: Trait> srml_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		const PRIORITY: u64 = 100;
+
+		let (dest, signer, statement) = match call {
+			Call::claim(dest, ethereum_signature) => {
+				let data = dest.using_encoded(to_ascii_hex);
+				(dest, Self::eth_recover(ethereum_signature, &data, &[][..]), None)
+			}
+			Call::claim_attest(dest, ethereum_signature, statement) => {
+				let data = dest.using_encoded(to_ascii_hex);
+				(dest, Self::eth_recover(ethereum_signature, &data, statement), Some(statement))
+			}
+			_ => return TransactionValidity::Invalid(0),
+		};
+
+		let signer = match signer {
+			Some(signer) => signer,
+			None => return TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+		};
+		// mirror the checks of `process_claim`, so that the call can't fail once it's dispatched.
+		let balance_due = match <Claims<T>>::get(&signer) {
+			Some(balance_due) => balance_due,
+			None => return TransactionValidity::Invalid(ApplyError::CantPay as i8),
+		};
+		if Self::total() < balance_due {
+			return TransactionValidity::Invalid(ApplyError::CantPay as i8);
+		}
+		if <Vesting<T>>::exists(&signer) && T::Currency::has_vesting_schedule(dest) {
+			return TransactionValidity::Invalid(ApplyError::CantPay as i8);
+		}
+		match (<Signing<T>>::get(&signer), statement) {
+			(None, _) => {}
+			(Some(required), Some(statement)) if T::Hashing::hash(statement) == required => {}
+			_ => return TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+		}
+
+		TransactionValidity::Valid(ValidTransaction {
+			priority: PRIORITY,
+			requires: vec![],
+			provides: vec![("claims", signer).encode()],
+			longevity: TransactionLongevity::max_value(),
+			propagate: true,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use srml_support::{assert_noop, assert_ok, assert_err, impl_outer_origin, parameter_types};
+	use srml_support::unsigned::ValidateUnsigned;
+	use substrate_primitives::{H256, Blake2Hasher};
+	use primitives::{traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+		pub const TransactionBaseFee: u64 = 0;
+		pub const TransactionByteFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnNewAccount = ();
+		type OnFreeBalanceZero = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TipPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+		type TransactionBaseFee = TransactionBaseFee;
+		type TransactionByteFee = TransactionByteFee;
+	}
+	parameter_types! {
+		pub const Prefix: &'static [u8] = b"Pay RUSTs to the TEST account:";
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type Prefix = Prefix;
+	}
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type Claims = Module<Test>;
+
+	const STATEMENT: &[u8] = b"I agree to the terms.";
+
+	fn secret(seed: &[u8]) -> secp256k1::SecretKey {
+		secp256k1::SecretKey::parse(&keccak_256(seed)).unwrap()
+	}
+	fn alice() -> secp256k1::SecretKey {
+		secret(b"Alice")
+	}
+	fn bob() -> secp256k1::SecretKey {
+		secret(b"Bob")
+	}
+	fn dave() -> secp256k1::SecretKey {
+		secret(b"Dave")
+	}
+	fn eve() -> secp256k1::SecretKey {
+		secret(b"Eve")
+	}
+
+	fn eth(secret: &secp256k1::SecretKey) -> EthereumAddress {
+		let public = secp256k1::PublicKey::from_secret_key(secret).serialize();
+		let mut address = EthereumAddress::default();
+		address.copy_from_slice(&keccak_256(&public[1..65])[12..]);
+		address
+	}
+
+	fn sig(secret: &secp256k1::SecretKey, dest: u64, extra: &[u8]) -> EcdsaSignature {
+		let message = Claims::ethereum_signable_message(&dest.using_encoded(to_ascii_hex), extra);
+		let message = secp256k1::Message::parse(&keccak_256(&message));
+		let (signature, recovery_id) = secp256k1::sign(&message, secret).unwrap();
+		let mut raw = [0u8; 65];
+		raw[0..64].copy_from_slice(&signature.serialize()[..]);
+		raw[64] = recovery_id.serialize();
+		EcdsaSignature::from_raw(raw)
+	}
+
+	fn statement_hash() -> H256 {
+		BlakeTwo256::hash(STATEMENT)
+	}
+
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+		t.extend(balances::GenesisConfig::<Test> {
+			balances: vec![],
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		t.extend(GenesisConfig::<Test> {
+			claims: vec![
+				(eth(&alice()), 100, None),
+				(eth(&dave()), 200, Some(statement_hash())),
+				(eth(&eve()), 300, None),
+			],
+			vesting: vec![(eth(&eve()), (300, 30, 10))],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	#[test]
+	fn basic_setup_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(Claims::total(), 600);
+			assert_eq!(Claims::claims(&eth(&alice())), Some(100));
+			assert_eq!(Claims::claims(&eth(&dave())), Some(200));
+			assert_eq!(Claims::claims(&eth(&bob())), None);
+			assert_eq!(Claims::signing(&eth(&dave())), Some(statement_hash()));
+			assert_eq!(Claims::signing(&eth(&alice())), None);
+			assert_eq!(Claims::vesting(&eth(&eve())), Some((300, 30, 10)));
+			assert_eq!(Claims::vesting(&eth(&alice())), None);
+		});
+	}
+
+	#[test]
+	fn signable_message_works() {
+		with_externalities(&mut new_test_ext(), || {
+			let mut expected = b"\x19Ethereum Signed Message:\n49Pay RUSTs to the TEST account:".to_vec();
+			expected.extend_from_slice(b"2a00000000000000");
+			expected.extend_from_slice(b"xyz");
+			assert_eq!(Claims::ethereum_signable_message(&42u64.using_encoded(to_ascii_hex), b"xyz"), expected);
+			assert_eq!(to_ascii_hex(&[0x01, 0xab, 0xf0]), b"01abf0".to_vec());
+		});
+	}
+
+	#[test]
+	fn claiming_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(Balances::free_balance(&42), 0);
+			assert_ok!(Claims::claim(Origin::NONE, 42, sig(&alice(), 42, &[][..])));
+			assert_eq!(Balances::free_balance(&42), 100);
+			assert_eq!(Balances::vesting_balance(&42), 0);
+			assert_eq!(Claims::total(), 500);
+			assert_eq!(Claims::claims(&eth(&alice())), None);
+		});
+	}
+
+	#[test]
+	fn claiming_with_vesting_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Claims::claim(Origin::NONE, 42, sig(&eve(), 42, &[][..])));
+			assert_eq!(Balances::free_balance(&42), 300);
+			assert_eq!(Balances::vesting_balance(&42), 300);
+			assert_eq!(Claims::vesting(&eth(&eve())), None);
+
+			System::set_block_number(13);
+			assert_eq!(Balances::vesting_balance(&42), 300 - 3 * 30);
+		});
+	}
+
+	#[test]
+	fn claiming_into_vesting_account_does_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Claims::mint_claim(Origin::ROOT, eth(&bob()), 50, Some((50, 10, 1)), None));
+			assert_ok!(Claims::claim(Origin::NONE, 42, sig(&bob(), 42, &[][..])));
+
+			assert_noop!(
+				Claims::claim(Origin::NONE, 42, sig(&eve(), 42, &[][..])),
+				"a vesting schedule already exists for this account"
+			);
+			assert_eq!(Claims::claims(&eth(&eve())), Some(300));
+		});
+	}
+
+	#[test]
+	fn claiming_twice_does_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Claims::claim(Origin::NONE, 42, sig(&alice(), 42, &[][..])));
+			assert_noop!(
+				Claims::claim(Origin::NONE, 42, sig(&alice(), 42, &[][..])),
+				"Ethereum address has no claim"
+			);
+		});
+	}
+
+	#[test]
+	fn signature_for_another_account_does_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			// recovers some unrelated address.
+			assert_noop!(
+				Claims::claim(Origin::NONE, 69, sig(&alice(), 42, &[][..])),
+				"Ethereum address has no claim"
+			);
+		});
+	}
+
+	#[test]
+	fn claiming_requires_none_origin() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_err!(
+				Claims::claim(Origin::signed(42), 42, sig(&alice(), 42, &[][..])),
+				"bad origin: expected to be no origin"
+			);
+		});
+	}
+
+	#[test]
+	fn claiming_does_not_bypass_statement() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(
+				Claims::claim(Origin::NONE, 42, sig(&dave(), 42, &[][..])),
+				"The claim requires attesting to a statement"
+			);
+			assert_noop!(
+				Claims::claim_attest(Origin::NONE, 42, sig(&dave(), 42, b"Something else."), b"Something else.".to_vec()),
+				"Invalid statement"
+			);
+			assert_ok!(Claims::claim_attest(Origin::NONE, 42, sig(&dave(), 42, STATEMENT), STATEMENT.to_vec()));
+			assert_eq!(Balances::free_balance(&42), 200);
+			assert_eq!(Claims::signing(&eth(&dave())), None);
+			assert_eq!(Claims::total(), 400);
+		});
+	}
+
+	#[test]
+	fn attesting_without_required_statement_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Claims::claim_attest(Origin::NONE, 42, sig(&alice(), 42, STATEMENT), STATEMENT.to_vec()));
+			assert_eq!(Balances::free_balance(&42), 100);
+		});
+	}
+
+	#[test]
+	fn mint_claim_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(
+				Claims::mint_claim(Origin::signed(42), eth(&bob()), 200, None, None),
+				"bad origin: expected to be a root origin"
+			);
+			assert_noop!(
+				Claims::claim(Origin::NONE, 69, sig(&bob(), 69, &[][..])),
+				"Ethereum address has no claim"
+			);
+			assert_ok!(Claims::mint_claim(Origin::ROOT, eth(&bob()), 200, None, None));
+			assert_eq!(Claims::total(), 800);
+
+			assert_ok!(Claims::claim(Origin::NONE, 69, sig(&bob(), 69, &[][..])));
+			assert_eq!(Balances::free_balance(&69), 200);
+			assert_eq!(Claims::total(), 600);
+		});
+	}
+
+	#[test]
+	fn mint_claim_replaces_existing_claim() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Claims::mint_claim(Origin::ROOT, eth(&dave()), 50, None, None));
+			assert_eq!(Claims::total(), 450);
+			assert_eq!(Claims::signing(&eth(&dave())), None);
+			assert_ok!(Claims::claim(Origin::NONE, 42, sig(&dave(), 42, &[][..])));
+			assert_eq!(Balances::free_balance(&42), 50);
+		});
+	}
+
+	#[test]
+	fn validate_unsigned_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(&Call::claim(42, sig(&alice(), 42, &[][..]))),
+				TransactionValidity::Valid(ValidTransaction {
+					priority: 100,
+					requires: vec![],
+					provides: vec![("claims", eth(&alice())).encode()],
+					longevity: TransactionLongevity::max_value(),
+					propagate: true,
+				})
+			);
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(&Call::claim(42, EcdsaSignature::from_raw([0; 65]))),
+				TransactionValidity::Invalid(ApplyError::BadSignature as i8)
+			);
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(&Call::claim(42, sig(&bob(), 42, &[][..]))),
+				TransactionValidity::Invalid(ApplyError::CantPay as i8)
+			);
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(&Call::claim(42, sig(&dave(), 42, &[][..]))),
+				TransactionValidity::Invalid(ApplyError::BadSignature as i8)
+			);
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(
+					&Call::claim_attest(42, sig(&dave(), 42, STATEMENT), STATEMENT.to_vec())
+				),
+				TransactionValidity::Valid(ValidTransaction {
+					priority: 100,
+					requires: vec![],
+					provides: vec![("claims", eth(&dave())).encode()],
+					longevity: TransactionLongevity::max_value(),
+					propagate: true,
+				})
+			);
+		});
+	}
+
+	#[test]
+	fn validate_unsigned_rejects_claims_into_vesting_account() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Claims::mint_claim(Origin::ROOT, eth(&bob()), 50, Some((50, 10, 1)), None));
+			assert_ok!(Claims::claim(Origin::NONE, 42, sig(&bob(), 42, &[][..])));
+
+			// the schedule of 42 has fully vested, but it still can't be replaced.
+			System::set_block_number(10);
+			assert_eq!(Balances::vesting_balance(&42), 0);
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(&Call::claim(42, sig(&eve(), 42, &[][..]))),
+				TransactionValidity::Invalid(ApplyError::CantPay as i8)
+			);
+			// claims without a vesting schedule can still go to 42.
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(&Call::claim(42, sig(&alice(), 42, &[][..]))),
+				TransactionValidity::Valid(ValidTransaction {
+					priority: 100,
+					requires: vec![],
+					provides: vec![("claims", eth(&alice())).encode()],
+					longevity: TransactionLongevity::max_value(),
+					propagate: true,
+				})
+			);
+		});
+	}
+}
@@ -24,7 +24,7 @@ use rstd::{prelude::*, result};
 use substrate_primitives::u32_trait::Value as U32;
 use primitives::traits::{Hash, EnsureOrigin};
 use srml_support::{
-	dispatch::{Dispatchable, Parameter}, codec::{Encode, Decode}, traits::ChangeMembers,
+	dispatch::{Dispatchable, Parameter}, codec::{Encode, Decode}, traits::{ChangeMembers, InitializeMembers},
 	StorageValue, StorageMap, decl_module, decl_event, decl_storage, ensure
 };
 use system::{self, ensure_signed, ensure_root};
@@ -134,35 +134,7 @@ decl_module! {
 			old_members.sort();
 			let mut new_members = new_members;
 			new_members.sort();
-			let mut old_iter = old_members.iter();
-			let mut new_iter = new_members.iter();
-			let mut incoming = vec![];
-			let mut outgoing = vec![];
-			let mut old_i = old_iter.next();
-			let mut new_i = new_iter.next();
-			loop {
-				match (old_i, new_i) {
-					(None, None) => break,
-					(Some(old), Some(new)) if old == new => {
-						old_i = old_iter.next();
-						new_i = new_iter.next();
-					}
-					(Some(old), Some(new)) if old < new => {
-						outgoing.push(old.clone());
-						old_i = old_iter.next();
-					}
-					(Some(old), None) => {
-						outgoing.push(old.clone());
-						old_i = old_iter.next();
-					}
-					(_, Some(new)) => {
-						incoming.push(new.clone());
-						new_i = new_iter.next();
-					}
-				}
-			}
-
-			Self::change_members(&incoming, &outgoing, &new_members);
+			Self::set_members_sorted(&new_members, &old_members);
 		}
 
 		/// Dispatch a proposal from a member using the `Member` origin.
@@ -302,6 +274,15 @@ impl<T: Trait<I>, I: Instance> ChangeMembers<T::AccountId> for Module<T, I> {
 	}
 }
 
+impl<T: Trait<I>, I: Instance> InitializeMembers<T::AccountId> for Module<T, I> {
+	fn initialize_members(members: &[T::AccountId]) {
+		if !members.is_empty() {
+			assert!(<Members<T, I>>::get().is_empty(), "Members are already initialized!");
+			<Members<T, I>>::put_ref(members);
+		}
+	}
+}
+
 /// Ensure that the origin `o` represents at least `n` members. Returns `Ok` or an `Err`
 /// otherwise.
 pub fn ensure_members<OuterOrigin, AccountId, I>(o: OuterOrigin, n: MemberCount)
@@ -404,6 +385,8 @@ mod tests {
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -534,6 +517,35 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn set_members_sorted_works() {
+		with_externalities(&mut make_ext(), || {
+			System::set_block_number(1);
+			let proposal = make_proposal(42);
+			let hash = BlakeTwo256::hash_of(&proposal);
+			assert_ok!(Collective::propose(Origin::signed(1), 3, Box::new(proposal.clone())));
+			assert_ok!(Collective::vote(Origin::signed(3), hash.clone(), 0, false));
+
+			Collective::set_members_sorted(&[2, 3, 5], &[1, 2, 3]);
+			assert_eq!(Collective::members(), vec![2, 3, 5]);
+			assert_eq!(
+				Collective::voting(&hash),
+				Some(Votes { index: 0, threshold: 3, ayes: vec![], nays: vec![3] })
+			);
+		});
+	}
+
+	#[test]
+	fn initialize_members_works() {
+		with_externalities(&mut make_ext(), || {
+			Collective::initialize_members(&[]);
+			assert_eq!(Collective::members(), vec![1, 2, 3]);
+			<Members<Test, Instance1>>::kill();
+			Collective::initialize_members(&[4, 5]);
+			assert_eq!(Collective::members(), vec![4, 5]);
+		});
+	}
+
 	#[test]
 	fn propose_works() {
 		with_externalities(&mut make_ext(), || {
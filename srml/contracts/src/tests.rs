@@ -103,6 +103,8 @@ parameter_types! {
 }
 impl system::Trait for Test {
 	type Origin = Origin;
+	type Call = ();
+	type BaseCallFilter = ();
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -122,6 +124,7 @@ impl balances::Trait for Test {
 	type OnNewAccount = ();
 	type Event = MetaEvent;
 	type TransactionPayment = ();
+	type TipPayment = ();
 	type DustRemoval = ();
 	type TransferPayment = ();
 	type ExistentialDeposit = ExistentialDeposit;
@@ -132,11 +135,13 @@ impl balances::Trait for Test {
 }
 parameter_types! {
 	pub const MinimumPeriod: u64 = 1;
+	pub const MaxDrift: u64 = 60;
 }
 impl timestamp::Trait for Test {
 	type Moment = u64;
 	type OnTimestampSet = ();
 	type MinimumPeriod = MinimumPeriod;
+	type MaxDrift = MaxDrift;
 }
 parameter_types! {
 	pub const SignedClaimHandicap: u64 = 2;
@@ -103,6 +103,8 @@ mod tests {
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -129,6 +131,7 @@ mod tests {
 		type OnFreeBalanceZero = ();
 		type Event = Event;
 		type TransactionPayment = ();
+		type TipPayment = ();
 		type TransferPayment = ();
 		type DustRemoval = ();
 		type ExistentialDeposit = ExistentialDeposit;
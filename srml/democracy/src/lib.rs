@@ -1003,6 +1003,8 @@ mod tests {
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -1029,6 +1031,7 @@ mod tests {
 		type OnNewAccount = ();
 		type Event = ();
 		type TransactionPayment = ();
+		type TipPayment = ();
 		type TransferPayment = ();
 		type DustRemoval = ();
 		type ExistentialDeposit = ExistentialDeposit;
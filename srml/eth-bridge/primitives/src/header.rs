@@ -0,0 +1,173 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ethereum block headers.
+
+use rstd::prelude::*;
+use parity_codec::{Encode, Decode};
+use substrate_primitives::{H160, H256, U256};
+use crate::keccak_256;
+use crate::rlp::{self, Rlp};
+
+/// An Ethereum block header, along with its hashes.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EthHeader {
+	/// The hash of the parent block.
+	pub parent_hash: H256,
+	/// The hash of the list of ommers of the block.
+	pub ommers_hash: H256,
+	/// The account receiving the rewards of the block.
+	pub beneficiary: H160,
+	/// The state root after the block.
+	pub state_root: H256,
+	/// The root of the transactions trie of the block.
+	pub transactions_root: H256,
+	/// The root of the receipts trie of the block.
+	pub receipts_root: H256,
+	/// The bloom filter of the logs of the block.
+	pub logs_bloom: Vec<u8>,
+	/// The difficulty of the block.
+	pub difficulty: U256,
+	/// The number of the block.
+	pub number: u64,
+	/// The gas limit of the block.
+	pub gas_limit: U256,
+	/// The gas used by the transactions of the block.
+	pub gas_used: U256,
+	/// The timestamp of the block, in seconds.
+	pub timestamp: u64,
+	/// Arbitrary data, up to 32 bytes.
+	pub extra_data: Vec<u8>,
+	/// The mix hash of the proof of work.
+	pub mix_hash: H256,
+	/// The nonce of the proof of work.
+	pub nonce: [u8; 8],
+	/// The hash of the header.
+	pub hash: H256,
+	/// The hash of the header without its seal, which the proof of work is computed over.
+	pub seal_hash: H256,
+}
+
+/// The number of fields of a header without its seal.
+const BARE_FIELDS: usize = 13;
+
+impl EthHeader {
+	/// Decode a header from its RLP encoding.
+	pub fn decode_rlp(bytes: &[u8]) -> rlp::Result<Self> {
+		let items = Rlp::new(bytes)?.items()?;
+		if items.len() != BARE_FIELDS + 2 {
+			return Err("Header has an invalid number of fields");
+		}
+
+		let logs_bloom = items[6].data()?;
+		if logs_bloom.len() != 256 {
+			return Err("Header logs bloom is expected to be 256 bytes long");
+		}
+		let nonce = items[14].data()?;
+		if nonce.len() != 8 {
+			return Err("Header nonce is expected to be 8 bytes long");
+		}
+		let mut nonce_bytes = [0u8; 8];
+		nonce_bytes.copy_from_slice(nonce);
+
+		let bare_items: Vec<&[u8]> = items[..BARE_FIELDS].iter().map(|item| item.as_raw()).collect();
+
+		Ok(EthHeader {
+			parent_hash: items[0].as_h256()?,
+			ommers_hash: items[1].as_h256()?,
+			beneficiary: items[2].as_h160()?,
+			state_root: items[3].as_h256()?,
+			transactions_root: items[4].as_h256()?,
+			receipts_root: items[5].as_h256()?,
+			logs_bloom: logs_bloom.to_vec(),
+			difficulty: items[7].as_u256()?,
+			number: items[8].as_u64()?,
+			gas_limit: items[9].as_u256()?,
+			gas_used: items[10].as_u256()?,
+			timestamp: items[11].as_u64()?,
+			extra_data: items[12].data()?.to_vec(),
+			mix_hash: items[13].as_h256()?,
+			nonce: nonce_bytes,
+			hash: keccak_256(bytes).into(),
+			seal_hash: keccak_256(&rlp::encode_list(&bare_items)).into(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rlp::{encode_bytes, encode_u64, encode_list};
+
+	fn bare_fields() -> Vec<Vec<u8>> {
+		vec![
+			encode_bytes(&[1u8; 32]),
+			encode_bytes(&[2u8; 32]),
+			encode_bytes(&[3u8; 20]),
+			encode_bytes(&[4u8; 32]),
+			encode_bytes(&[5u8; 32]),
+			encode_bytes(&[6u8; 32]),
+			encode_bytes(&[0u8; 256]),
+			encode_u64(131_072),
+			encode_u64(42),
+			encode_u64(8_000_000),
+			encode_u64(21_000),
+			encode_u64(1_500_000_000),
+			encode_bytes(b"extra"),
+		]
+	}
+
+	#[test]
+	fn decode_header_works() {
+		let mut fields = bare_fields();
+		let seal_hash = keccak_256(&encode_list(&fields));
+		fields.push(encode_bytes(&[7u8; 32]));
+		fields.push(encode_bytes(&[8u8; 8]));
+		let encoded = encode_list(&fields);
+
+		let header = EthHeader::decode_rlp(&encoded).unwrap();
+		assert_eq!(header.parent_hash, H256::repeat_byte(1));
+		assert_eq!(header.beneficiary, H160::repeat_byte(3));
+		assert_eq!(header.receipts_root, H256::repeat_byte(6));
+		assert_eq!(header.difficulty, U256::from(131_072));
+		assert_eq!(header.number, 42);
+		assert_eq!(header.gas_used, U256::from(21_000));
+		assert_eq!(header.timestamp, 1_500_000_000);
+		assert_eq!(header.extra_data, b"extra".to_vec());
+		assert_eq!(header.mix_hash, H256::repeat_byte(7));
+		assert_eq!(header.nonce, [8u8; 8]);
+		assert_eq!(header.hash, H256::from(keccak_256(&encoded)));
+		assert_eq!(header.seal_hash, H256::from(seal_hash));
+	}
+
+	#[test]
+	fn decode_header_rejects_malformed_headers() {
+		let fields = bare_fields();
+		assert_eq!(
+			EthHeader::decode_rlp(&encode_list(&fields)),
+			Err("Header has an invalid number of fields"),
+		);
+
+		let mut fields = bare_fields();
+		fields.push(encode_bytes(&[7u8; 32]));
+		fields.push(encode_bytes(&[8u8; 4]));
+		assert_eq!(
+			EthHeader::decode_rlp(&encode_list(&fields)),
+			Err("Header nonce is expected to be 8 bytes long"),
+		);
+	}
+}
@@ -0,0 +1,32 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Primitives for verifying Ethereum blocks and the events they contain in the runtime.
+//!
+//! This provides decoding of Ethereum's RLP format, block headers and transaction receipts, as
+//! well as the verification of inclusion proofs in the Merkle-Patricia tries whose roots are in
+//! the headers. Hashing uses the `keccak_256` host function.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod rlp;
+pub mod trie;
+mod header;
+mod receipt;
+
+pub use header::EthHeader;
+pub use receipt::{Log, Receipt, TransactionOutcome};
+pub use runtime_io::keccak_256;
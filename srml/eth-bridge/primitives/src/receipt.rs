@@ -0,0 +1,157 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ethereum transaction receipts and their logs.
+
+use rstd::prelude::*;
+use parity_codec::{Encode, Decode};
+use substrate_primitives::{H160, H256, U256};
+use crate::rlp::{self, Rlp};
+use crate::trie;
+
+/// A log emitted by a contract.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Log {
+	/// The contract which emitted the log.
+	pub address: H160,
+	/// The topics of the log, the first one usually being the hash of the event signature.
+	pub topics: Vec<H256>,
+	/// The data of the log.
+	pub data: Vec<u8>,
+}
+
+/// The outcome of a transaction.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum TransactionOutcome {
+	/// The state root after the transaction, before Byzantium.
+	StateRoot(H256),
+	/// Whether the transaction succeeded, since Byzantium.
+	Status(bool),
+}
+
+/// The receipt of a transaction.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Receipt {
+	/// The outcome of the transaction.
+	pub outcome: TransactionOutcome,
+	/// The gas used in the block up to and including the transaction.
+	pub cumulative_gas_used: U256,
+	/// The logs emitted by the transaction.
+	pub logs: Vec<Log>,
+}
+
+impl Log {
+	fn decode_rlp(rlp: &Rlp) -> rlp::Result<Self> {
+		let items = rlp.items()?;
+		if items.len() != 3 {
+			return Err("Log has an invalid number of fields");
+		}
+		Ok(Log {
+			address: items[0].as_h160()?,
+			topics: items[1].items()?.iter().map(Rlp::as_h256).collect::<rlp::Result<_>>()?,
+			data: items[2].data()?.to_vec(),
+		})
+	}
+}
+
+impl Receipt {
+	/// Decode a receipt from its RLP encoding.
+	pub fn decode_rlp(bytes: &[u8]) -> rlp::Result<Self> {
+		let items = Rlp::new(bytes)?.items()?;
+		if items.len() != 4 {
+			return Err("Receipt has an invalid number of fields");
+		}
+		let outcome = match items[0].data()?.len() {
+			32 => TransactionOutcome::StateRoot(items[0].as_h256()?),
+			_ => match items[0].as_u64()? {
+				0 => TransactionOutcome::Status(false),
+				1 => TransactionOutcome::Status(true),
+				_ => return Err("Receipt has an invalid status"),
+			},
+		};
+		Ok(Receipt {
+			outcome,
+			cumulative_gas_used: items[1].as_u256()?,
+			logs: items[3].items()?.iter().map(Log::decode_rlp).collect::<rlp::Result<_>>()?,
+		})
+	}
+
+	/// Verify a proof of the receipt of the transaction at `index` in the receipts trie with
+	/// the given root, and decode the receipt.
+	pub fn verify_proof(receipts_root: &H256, index: u64, proof: &[Vec<u8>]) -> rlp::Result<Self> {
+		let encoded = trie::verify_proof(receipts_root, &rlp::encode_u64(index), proof)?
+			.ok_or("Receipt is not in the receipts trie")?;
+		Self::decode_rlp(&encoded)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::keccak_256;
+	use crate::rlp::{encode_bytes, encode_u64, encode_list};
+
+	fn encoded_receipt() -> Vec<u8> {
+		let log = encode_list(&[
+			encode_bytes(&[1u8; 20]),
+			encode_list(&[encode_bytes(&[2u8; 32]), encode_bytes(&[3u8; 32])]),
+			encode_bytes(b"data"),
+		]);
+		encode_list(&[encode_u64(1), encode_u64(21_000), encode_bytes(&[0u8; 256]), encode_list(&[log])])
+	}
+
+	fn expected_receipt() -> Receipt {
+		Receipt {
+			outcome: TransactionOutcome::Status(true),
+			cumulative_gas_used: U256::from(21_000),
+			logs: vec![Log {
+				address: H160::repeat_byte(1),
+				topics: vec![H256::repeat_byte(2), H256::repeat_byte(3)],
+				data: b"data".to_vec(),
+			}],
+		}
+	}
+
+	#[test]
+	fn decode_receipt_works() {
+		assert_eq!(Receipt::decode_rlp(&encoded_receipt()), Ok(expected_receipt()));
+
+		let pre_byzantium = encode_list(&[
+			encode_bytes(&[4u8; 32]),
+			encode_u64(21_000),
+			encode_bytes(&[0u8; 256]),
+			encode_list::<Vec<u8>>(&[]),
+		]);
+		assert_eq!(
+			Receipt::decode_rlp(&pre_byzantium).map(|receipt| receipt.outcome),
+			Ok(TransactionOutcome::StateRoot(H256::repeat_byte(4))),
+		);
+	}
+
+	#[test]
+	fn verify_receipt_proof_works() {
+		// the trie of a block with a single transaction: a leaf under the key `rlp(0)`.
+		let leaf = encode_list(&[encode_bytes(&[0x20, 0x80]), encode_bytes(&encoded_receipt())]);
+		let root = H256::from(keccak_256(&leaf));
+		let proof = vec![leaf];
+
+		assert_eq!(Receipt::verify_proof(&root, 0, &proof), Ok(expected_receipt()));
+		assert_eq!(Receipt::verify_proof(&root, 1, &proof), Err("Receipt is not in the receipts trie"));
+	}
+}
@@ -0,0 +1,300 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Minimal decoding and encoding of Ethereum's Recursive Length Prefix (RLP) format.
+
+use rstd::prelude::*;
+use substrate_primitives::{H160, H256, U256};
+
+/// The result of RLP decoding.
+pub type Result<T> = rstd::result::Result<T, &'static str>;
+
+/// The encoding of an item: the length of its prefix and of its payload.
+struct PayloadInfo {
+	header_len: usize,
+	value_len: usize,
+	is_list: bool,
+}
+
+impl PayloadInfo {
+	/// The length of the whole item. The lengths come from untrusted input, so the sum may
+	/// overflow on 32-bit targets.
+	fn total(&self) -> Result<usize> {
+		self.header_len.checked_add(self.value_len).ok_or("RLP length overflows")
+	}
+}
+
+/// Decode the big-endian length of `len_of_len` bytes at the start of `bytes`.
+fn decode_length(bytes: &[u8], len_of_len: usize) -> Result<usize> {
+	if bytes.len() < len_of_len {
+		return Err("RLP is too short");
+	}
+	if len_of_len > rstd::mem::size_of::<u32>() {
+		return Err("RLP length is too big");
+	}
+	if bytes[0] == 0 {
+		return Err("RLP length has leading zeros");
+	}
+	let len = bytes[..len_of_len].iter().fold(0usize, |len, byte| (len << 8) | *byte as usize);
+	if len <= 55 {
+		return Err("RLP length should use the short form");
+	}
+	Ok(len)
+}
+
+fn payload_info(bytes: &[u8]) -> Result<PayloadInfo> {
+	let first = *bytes.first().ok_or("RLP is too short")?;
+	let info = match first {
+		0x00..=0x7f => PayloadInfo { header_len: 0, value_len: 1, is_list: false },
+		0x80..=0xb7 => {
+			let value_len = (first - 0x80) as usize;
+			if value_len == 1 && bytes.get(1).map_or(false, |byte| *byte < 0x80) {
+				return Err("RLP single byte should be encoded as itself");
+			}
+			PayloadInfo { header_len: 1, value_len, is_list: false }
+		}
+		0xb8..=0xbf => {
+			let len_of_len = (first - 0xb7) as usize;
+			let value_len = decode_length(&bytes[1..], len_of_len)?;
+			PayloadInfo { header_len: 1 + len_of_len, value_len, is_list: false }
+		}
+		0xc0..=0xf7 => PayloadInfo { header_len: 1, value_len: (first - 0xc0) as usize, is_list: true },
+		0xf8..=0xff => {
+			let len_of_len = (first - 0xf7) as usize;
+			let value_len = decode_length(&bytes[1..], len_of_len)?;
+			PayloadInfo { header_len: 1 + len_of_len, value_len, is_list: true }
+		}
+	};
+	if info.value_len > bytes.len() || bytes.len() < info.total()? {
+		return Err("RLP is too short");
+	}
+	Ok(info)
+}
+
+/// A view of a single RLP-encoded item.
+#[derive(Clone, Copy)]
+pub struct Rlp<'a> {
+	bytes: &'a [u8],
+	is_list: bool,
+	header_len: usize,
+}
+
+impl<'a> Rlp<'a> {
+	/// Create a view of the item encoded in `bytes`, which must contain exactly one item.
+	pub fn new(bytes: &'a [u8]) -> Result<Self> {
+		let item = Self::first(bytes)?;
+		if item.bytes.len() != bytes.len() {
+			return Err("RLP has trailing bytes");
+		}
+		Ok(item)
+	}
+
+	/// The first item encoded in `bytes`.
+	fn first(bytes: &'a [u8]) -> Result<Self> {
+		let info = payload_info(bytes)?;
+		let bytes = bytes.get(..info.total()?).ok_or("RLP is too short")?;
+		Ok(Rlp { bytes, is_list: info.is_list, header_len: info.header_len })
+	}
+
+	/// The encoding of the item.
+	pub fn as_raw(&self) -> &'a [u8] {
+		self.bytes
+	}
+
+	/// Whether the item is a list.
+	pub fn is_list(&self) -> bool {
+		self.is_list
+	}
+
+	/// The payload of the item, which must not be a list.
+	pub fn data(&self) -> Result<&'a [u8]> {
+		if self.is_list {
+			return Err("RLP is expected to be data");
+		}
+		self.payload()
+	}
+
+	/// The bytes following the prefix of the item.
+	fn payload(&self) -> Result<&'a [u8]> {
+		self.bytes.get(self.header_len..).ok_or("RLP is too short")
+	}
+
+	/// The items of the list, which this must be.
+	pub fn items(&self) -> Result<Vec<Rlp<'a>>> {
+		if !self.is_list {
+			return Err("RLP is expected to be a list");
+		}
+		let mut payload = self.payload()?;
+		let mut items = Vec::new();
+		while !payload.is_empty() {
+			let item = Self::first(payload)?;
+			payload = payload.get(item.bytes.len()..).ok_or("RLP is too short")?;
+			items.push(item);
+		}
+		Ok(items)
+	}
+
+	/// The payload as an unsigned integer that fits in `max_len` bytes.
+	fn as_integer(&self, max_len: usize) -> Result<&'a [u8]> {
+		let data = self.data()?;
+		if data.len() > max_len {
+			return Err("RLP integer is too big");
+		}
+		if data.first() == Some(&0) {
+			return Err("RLP integer has leading zeros");
+		}
+		Ok(data)
+	}
+
+	/// The payload as a `u64`.
+	pub fn as_u64(&self) -> Result<u64> {
+		Ok(self.as_integer(8)?.iter().fold(0u64, |value, byte| (value << 8) | *byte as u64))
+	}
+
+	/// The payload as a `U256`.
+	pub fn as_u256(&self) -> Result<U256> {
+		Ok(U256::from_big_endian(self.as_integer(32)?))
+	}
+
+	/// The payload as a `H256`.
+	pub fn as_h256(&self) -> Result<H256> {
+		let data = self.data()?;
+		if data.len() != 32 {
+			return Err("RLP is expected to be 32 bytes long");
+		}
+		Ok(H256::from_slice(data))
+	}
+
+	/// The payload as a `H160`.
+	pub fn as_h160(&self) -> Result<H160> {
+		let data = self.data()?;
+		if data.len() != 20 {
+			return Err("RLP is expected to be 20 bytes long");
+		}
+		Ok(H160::from_slice(data))
+	}
+}
+
+/// Encode the length prefix of an item whose payload is `len` bytes long.
+fn encode_prefix(len: usize, short_offset: u8, out: &mut Vec<u8>) {
+	if len <= 55 {
+		out.push(short_offset + len as u8);
+	} else {
+		let len_bytes = (len as u64).to_be_bytes();
+		let skip = len_bytes.iter().take_while(|byte| **byte == 0).count();
+		out.push(short_offset + 55 + (len_bytes.len() - skip) as u8);
+		out.extend_from_slice(&len_bytes[skip..]);
+	}
+}
+
+/// Encode `data` as an RLP string.
+pub fn encode_bytes(data: &[u8]) -> Vec<u8> {
+	let mut out = Vec::with_capacity(data.len() + 9);
+	if data.len() != 1 || data[0] >= 0x80 {
+		encode_prefix(data.len(), 0x80, &mut out);
+	}
+	out.extend_from_slice(data);
+	out
+}
+
+/// Encode `value` as an RLP integer.
+pub fn encode_u64(value: u64) -> Vec<u8> {
+	let bytes = value.to_be_bytes();
+	let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+	encode_bytes(&bytes[skip..])
+}
+
+/// Encode a list of already encoded items.
+pub fn encode_list<I: AsRef<[u8]>>(items: &[I]) -> Vec<u8> {
+	let len = items.iter().map(|item| item.as_ref().len()).sum();
+	let mut out = Vec::with_capacity(len + 9);
+	encode_prefix(len, 0xc0, &mut out);
+	for item in items {
+		out.extend_from_slice(item.as_ref());
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_data_works() {
+		assert_eq!(Rlp::new(&[0x83, b'd', b'o', b'g']).unwrap().data(), Ok(&b"dog"[..]));
+		assert_eq!(Rlp::new(&[0x80]).unwrap().data(), Ok(&[][..]));
+		assert_eq!(Rlp::new(&[0x0f]).unwrap().as_u64(), Ok(15));
+		assert_eq!(Rlp::new(&[0x82, 0x04, 0x00]).unwrap().as_u64(), Ok(1024));
+		assert_eq!(Rlp::new(&[0x80]).unwrap().as_u64(), Ok(0));
+
+		let long = vec![b'a'; 56];
+		let mut encoded = vec![0xb8, 56];
+		encoded.extend_from_slice(&long);
+		assert_eq!(Rlp::new(&encoded).unwrap().data(), Ok(&long[..]));
+		assert_eq!(encode_bytes(&long), encoded);
+	}
+
+	#[test]
+	fn decode_list_works() {
+		let encoded = [0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g'];
+		let rlp = Rlp::new(&encoded).unwrap();
+		assert!(rlp.is_list());
+		let items = rlp.items().unwrap();
+		assert_eq!(items.len(), 2);
+		assert_eq!(items[0].data(), Ok(&b"cat"[..]));
+		assert_eq!(items[1].as_raw(), &[0x83, b'd', b'o', b'g']);
+		assert_eq!(rlp.data(), Err("RLP is expected to be data"));
+
+		assert_eq!(encode_list(&[encode_bytes(b"cat"), encode_bytes(b"dog")]), encoded.to_vec());
+		assert_eq!(Rlp::new(&[0xc0]).unwrap().items().unwrap().len(), 0);
+	}
+
+	#[test]
+	fn decode_rejects_malformed_items() {
+		assert_eq!(Rlp::new(&[]).err(), Some("RLP is too short"));
+		assert_eq!(Rlp::new(&[0x83, b'd', b'o']).err(), Some("RLP is too short"));
+		assert_eq!(Rlp::new(&[0x83, b'd', b'o', b'g', 0x00]).err(), Some("RLP has trailing bytes"));
+		assert_eq!(Rlp::new(&[0x81, 0x05]).err(), Some("RLP single byte should be encoded as itself"));
+		assert_eq!(Rlp::new(&[0xb8, 0x05, 1, 2, 3, 4, 5]).err(), Some("RLP length should use the short form"));
+		assert_eq!(Rlp::new(&[0x82, 0x00, 0x01]).unwrap().as_u64(), Err("RLP integer has leading zeros"));
+		assert_eq!(Rlp::new(&[0xc2, 0x83, 0x00]).unwrap().items().err(), Some("RLP is too short"));
+	}
+
+	#[test]
+	fn decode_rejects_truncated_lengths() {
+		assert_eq!(Rlp::new(&[0xb9, 0x01]).err(), Some("RLP is too short"));
+		assert_eq!(Rlp::new(&[0xbb, 0xff, 0xff, 0xff, 0xff]).err(), Some("RLP is too short"));
+		assert_eq!(Rlp::new(&[0xfb, 0xff, 0xff, 0xff, 0xff, 0xc0]).err(), Some("RLP is too short"));
+		assert_eq!(Rlp::new(&[0xbc, 0x01, 0x00, 0x00, 0x00, 0x00]).err(), Some("RLP length is too big"));
+		assert_eq!(Rlp::new(&[0xc2, 0xb8, 0xff]).unwrap().items().err(), Some("RLP is too short"));
+	}
+
+	#[test]
+	fn payload_length_overflow_is_an_error() {
+		let info = PayloadInfo { header_len: 5, value_len: usize::max_value() - 1, is_list: false };
+		assert_eq!(info.total(), Err("RLP length overflows"));
+		let info = PayloadInfo { header_len: 5, value_len: 56, is_list: true };
+		assert_eq!(info.total(), Ok(61));
+	}
+
+	#[test]
+	fn encode_integers_works() {
+		assert_eq!(encode_u64(0), vec![0x80]);
+		assert_eq!(encode_u64(15), vec![0x0f]);
+		assert_eq!(encode_u64(0x80), vec![0x81, 0x80]);
+		assert_eq!(encode_u64(1024), vec![0x82, 0x04, 0x00]);
+	}
+}
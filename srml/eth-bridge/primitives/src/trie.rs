@@ -0,0 +1,215 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of inclusion proofs in Ethereum's Merkle-Patricia tries.
+
+use rstd::prelude::*;
+use substrate_primitives::H256;
+use crate::keccak_256;
+use crate::rlp::{self, Rlp};
+
+/// The nibbles of `bytes`, high nibble first.
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+	bytes.iter().flat_map(|byte| vec![byte >> 4, byte & 0x0f]).collect()
+}
+
+/// Decode the hex-prefix encoded path of a leaf or extension node into its nibbles and whether
+/// the node is a leaf.
+fn decode_path(encoded: &[u8]) -> rlp::Result<(Vec<u8>, bool)> {
+	let first = *encoded.first().ok_or("Trie node path is empty")?;
+	let flag = first >> 4;
+	if flag > 3 {
+		return Err("Trie node path has an invalid prefix");
+	}
+	let is_leaf = flag & 2 != 0;
+	let mut path = Vec::with_capacity(encoded.len() * 2);
+	if flag & 1 != 0 {
+		path.push(first & 0x0f);
+	} else if first & 0x0f != 0 {
+		return Err("Trie node path has an invalid prefix");
+	}
+	path.extend(nibbles(&encoded[1..]));
+	Ok((path, is_leaf))
+}
+
+/// Look up the node with the given hash in `proof`.
+fn proof_node<'a>(proof: &'a [Vec<u8>], hash: &[u8]) -> rlp::Result<&'a [u8]> {
+	proof.iter()
+		.find(|node| &keccak_256(node)[..] == hash)
+		.map(|node| &node[..])
+		.ok_or("Trie proof is missing a node")
+}
+
+/// Resolve a reference to a child node: either its hash or, if it's shorter than a hash, the
+/// node itself. Returns `None` for an empty reference.
+fn child_node<'a>(reference: Rlp<'a>, proof: &'a [Vec<u8>]) -> rlp::Result<Option<&'a [u8]>> {
+	if reference.is_list() {
+		return Ok(Some(reference.as_raw()));
+	}
+	match reference.data()? {
+		[] => Ok(None),
+		hash if hash.len() == 32 => proof_node(proof, hash).map(Some),
+		_ => Err("Trie node has an invalid child reference"),
+	}
+}
+
+/// Verify a proof of the value stored under `key` in the trie with the given root.
+///
+/// The proof is the list of the encoded nodes on the path from the root to the value, in any
+/// order. Returns the value, or `None` if the proof shows that there is no value under `key`.
+pub fn verify_proof(root: &H256, key: &[u8], proof: &[Vec<u8>]) -> rlp::Result<Option<Vec<u8>>> {
+	let key = nibbles(key);
+	let mut remaining = &key[..];
+	let mut node = proof_node(proof, root.as_bytes())?;
+
+	loop {
+		let rlp = Rlp::new(node)?;
+		if !rlp.is_list() {
+			// only the empty trie has an empty root node.
+			return match rlp.data()? {
+				[] => Ok(None),
+				_ => Err("Trie node is not a list"),
+			};
+		}
+
+		let items = rlp.items()?;
+		let child = match items.len() {
+			17 => match remaining.split_first() {
+				None => {
+					let value = items[16].data()?;
+					return Ok(if value.is_empty() { None } else { Some(value.to_vec()) });
+				}
+				Some((nibble, rest)) => {
+					remaining = rest;
+					items[*nibble as usize]
+				}
+			},
+			2 => {
+				let (path, is_leaf) = decode_path(items[0].data()?)?;
+				if is_leaf {
+					return Ok(if remaining == &path[..] {
+						Some(items[1].data()?.to_vec())
+					} else {
+						None
+					});
+				}
+				if !remaining.starts_with(&path) {
+					return Ok(None);
+				}
+				remaining = &remaining[path.len()..];
+				items[1]
+			}
+			_ => return Err("Trie node has an invalid number of items"),
+		};
+
+		node = match child_node(child, proof)? {
+			Some(node) => node,
+			None => return Ok(None),
+		};
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rlp::{encode_bytes, encode_list};
+
+	/// Encode the path of a leaf or extension node given as nibbles.
+	fn encode_path(path: &[u8], is_leaf: bool) -> Vec<u8> {
+		let flag = (if is_leaf { 2 } else { 0 }) + (path.len() % 2) as u8;
+		let mut encoded = Vec::new();
+		let rest = if path.len() % 2 == 1 {
+			encoded.push(flag << 4 | path[0]);
+			&path[1..]
+		} else {
+			encoded.push(flag << 4);
+			path
+		};
+		encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+		encoded
+	}
+
+	fn leaf(path: &[u8], value: &[u8]) -> Vec<u8> {
+		encode_list(&[encode_bytes(&encode_path(path, true)), encode_bytes(value)])
+	}
+
+	fn branch(children: &[(u8, Vec<u8>)]) -> Vec<u8> {
+		let mut items = vec![encode_bytes(&[]); 17];
+		for (nibble, child) in children {
+			items[*nibble as usize] = child.clone();
+		}
+		encode_list(&items)
+	}
+
+	fn hash_reference(node: &[u8]) -> Vec<u8> {
+		encode_bytes(&keccak_256(node))
+	}
+
+	#[test]
+	fn empty_trie_proves_absence() {
+		let empty = vec![0x80];
+		let root = H256::from(keccak_256(&empty));
+		assert_eq!(
+			root,
+			H256::from_slice(&[
+				0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+				0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+			]),
+		);
+		assert_eq!(verify_proof(&root, &[0x80], &[empty]), Ok(None));
+	}
+
+	#[test]
+	fn single_leaf_proof_works() {
+		let value = vec![7u8; 40];
+		let node = leaf(&nibbles(&[0x12, 0x34]), &value);
+		let root = H256::from(keccak_256(&node));
+		let proof = vec![node];
+
+		assert_eq!(verify_proof(&root, &[0x12, 0x34], &proof), Ok(Some(value)));
+		assert_eq!(verify_proof(&root, &[0x12, 0x35], &proof), Ok(None));
+		assert_eq!(verify_proof(&H256::repeat_byte(1), &[0x12, 0x34], &proof), Err("Trie proof is missing a node"));
+	}
+
+	#[test]
+	fn branch_proof_works() {
+		// a long value, referenced by hash, and a short one, embedded in the branch.
+		let long_value = vec![1u8; 40];
+		let long_leaf = leaf(&[0x0, 0x2], &long_value);
+		let short_leaf = leaf(&[0x0, 0x0], &[2u8]);
+		let branch = branch(&[(0x0, hash_reference(&long_leaf)), (0x8, short_leaf)]);
+		// an extension over the first nibble shared by both keys.
+		let extension = encode_list(&[encode_bytes(&encode_path(&[0x1], false)), hash_reference(&branch)]);
+		let root = H256::from(keccak_256(&extension));
+		let proof = vec![long_leaf, branch, extension];
+
+		assert_eq!(verify_proof(&root, &[0x10, 0x02], &proof), Ok(Some(long_value)));
+		assert_eq!(verify_proof(&root, &[0x18, 0x00], &proof), Ok(Some(vec![2u8])));
+		assert_eq!(verify_proof(&root, &[0x14, 0x00], &proof), Ok(None));
+		assert_eq!(verify_proof(&root, &[0x20, 0x00], &proof), Ok(None));
+		assert_eq!(verify_proof(&root, &[0x10, 0x02], &proof[1..]), Err("Trie proof is missing a node"));
+	}
+
+	#[test]
+	fn decode_path_works() {
+		assert_eq!(decode_path(&[0x20, 0x12]), Ok((vec![0x1, 0x2], true)));
+		assert_eq!(decode_path(&[0x31, 0x23]), Ok((vec![0x1, 0x2, 0x3], true)));
+		assert_eq!(decode_path(&[0x00, 0x12]), Ok((vec![0x1, 0x2], false)));
+		assert_eq!(decode_path(&[0x11]), Ok((vec![0x1], false)));
+		assert_eq!(decode_path(&[0x40]), Err("Trie node path has an invalid prefix"));
+		assert_eq!(encode_path(&[0x1, 0x2, 0x3], true), vec![0x31, 0x23]);
+	}
+}
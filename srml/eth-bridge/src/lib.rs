@@ -0,0 +1,512 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Ethereum Bridge Module
+//!
+//! An on-chain light client of Ethereum, letting the runtime react to events emitted there.
+//!
+//! - [`eth_bridge::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//! - [`Module`](./struct.Module.html)
+//!
+//! ## Overview
+//!
+//! The module tracks Ethereum headers from a trusted header given at initialization. Anyone can
+//! relay headers, which are only accepted on top of already imported ones and once their proof
+//! of work is checked by the [`SealVerifier`](./trait.SealVerifier.html) of the runtime. The
+//! canonical chain is the one with the most total difficulty, and its blocks are considered
+//! final once they have `Confirmations` descendants.
+//!
+//! Anyone can then submit the receipt of a transaction in a final block, with a proof of its
+//! inclusion in the receipts trie of the block. The logs of the receipt are handed to the
+//! [`OnLog`](./trait.OnLog.html) handler of the runtime, once per receipt.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `initialize` - Set the trusted header the chain is tracked from. Root only.
+//! - `import_header` - Import an RLP-encoded header on top of an imported one.
+//! - `submit_receipt` - Process the logs of a receipt of a final block, given its proof.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use parity_codec::{Encode, Decode};
+use substrate_primitives::{H256, U256};
+use srml_support::{StorageValue, StorageMap, decl_module, decl_storage, decl_event, ensure, traits::Get};
+use system::{ensure_root, ensure_signed};
+pub use eth_primitives::{EthHeader, Log, Receipt, TransactionOutcome};
+
+/// Verifies the seal of Ethereum headers, i.e. their proof of work.
+pub trait SealVerifier {
+	/// Check the seal of `header`, whose parent is `parent`.
+	fn verify_seal(header: &EthHeader, parent: &EthHeader) -> Result<(), &'static str>;
+}
+
+/// Handler for the logs of the processed receipts.
+pub trait OnLog {
+	/// Handle a log emitted in the given block.
+	fn on_log(block_hash: &H256, log: &Log);
+}
+
+impl OnLog for () {
+	fn on_log(_: &H256, _: &Log) {}
+}
+
+/// An imported header, along with the total difficulty of the chain ending with it.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct StoredHeader {
+	/// The header.
+	pub header: EthHeader,
+	/// The sum of the difficulties of the header and its ancestors.
+	pub total_difficulty: U256,
+}
+
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+
+	/// Verifies the proof of work of the imported headers.
+	type Verifier: SealVerifier;
+
+	/// Handler for the logs of the processed receipts.
+	type OnLog: OnLog;
+
+	/// The number of descendants a block of the canonical chain needs for its receipts to be
+	/// processed.
+	type Confirmations: Get<u64>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as EthBridge {
+		/// The imported headers, by hash.
+		pub Headers get(header): map H256 => Option<StoredHeader>;
+
+		/// The hash of the best header, ending the chain with the most total difficulty.
+		pub BestHeader get(best_header): Option<H256>;
+
+		/// The hashes of the headers of the canonical chain, by number.
+		pub CanonicalHashes get(canonical_hash): map u64 => Option<H256>;
+
+		/// Whether the receipt of the given block and transaction index was processed.
+		pub ProcessedReceipts get(is_receipt_processed): map (H256, u64) => bool;
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// A header was imported.
+		HeaderImported(H256, u64),
+		/// The best header changed.
+		BestHeaderChanged(H256, u64),
+		/// The receipt of the given block and transaction index was processed.
+		ReceiptProcessed(H256, u64),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Set the trusted header the chain is tracked from, with the total difficulty of its
+		/// chain.
+		///
+		/// The dispatch origin for this call must be _Root_, and the bridge must not be
+		/// initialized yet.
+		fn initialize(origin, header: Vec<u8>, total_difficulty: U256) {
+			ensure_root(origin)?;
+			ensure!(Self::best_header().is_none(), "Bridge already initialized");
+			let header = EthHeader::decode_rlp(&header)?;
+
+			let hash = header.hash;
+			let number = header.number;
+			<Headers<T>>::insert(hash, StoredHeader { header, total_difficulty });
+			<CanonicalHashes<T>>::insert(number, hash);
+			<BestHeader<T>>::put(hash);
+
+			Self::deposit_event(Event::BestHeaderChanged(hash, number));
+		}
+
+		/// Import an RLP-encoded header. Its parent must be imported already.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		fn import_header(origin, header: Vec<u8>) {
+			ensure_signed(origin)?;
+			let best = Self::best_header().and_then(Self::header).ok_or("Bridge not initialized")?;
+			let header = EthHeader::decode_rlp(&header)?;
+			ensure!(!<Headers<T>>::exists(&header.hash), "Header already imported");
+			let parent = Self::header(&header.parent_hash).ok_or("Parent header not imported")?;
+			Self::check_header(&header, &parent.header)?;
+			T::Verifier::verify_seal(&header, &parent.header)?;
+
+			let total_difficulty = parent.total_difficulty.checked_add(header.difficulty)
+				.ok_or("Total difficulty overflow")?;
+			let hash = header.hash;
+			let number = header.number;
+			<Headers<T>>::insert(hash, StoredHeader { header, total_difficulty });
+			Self::deposit_event(Event::HeaderImported(hash, number));
+
+			if total_difficulty > best.total_difficulty {
+				Self::set_best_header(hash, number, best.header.number);
+			}
+		}
+
+		/// Process the receipt of the transaction at `index` in the given block, with a proof of
+		/// its inclusion in the receipts trie of the block. The block must be in the canonical
+		/// chain and have at least `Confirmations` descendants.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		fn submit_receipt(origin, block_hash: H256, index: u64, proof: Vec<Vec<u8>>) {
+			ensure_signed(origin)?;
+			ensure!(!Self::is_receipt_processed((block_hash, index)), "Receipt already processed");
+			let receipt = Self::verify_receipt(&block_hash, index, &proof)?;
+
+			<ProcessedReceipts<T>>::insert((block_hash, index), true);
+			for log in &receipt.logs {
+				T::OnLog::on_log(&block_hash, log);
+			}
+
+			Self::deposit_event(Event::ReceiptProcessed(block_hash, index));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Whether the block with the given hash is in the canonical chain and has at least
+	/// `Confirmations` descendants.
+	pub fn is_confirmed(block_hash: &H256) -> bool {
+		let best_number = match Self::best_header().and_then(Self::header) {
+			Some(best) => best.header.number,
+			None => return false,
+		};
+		Self::header(block_hash).map_or(false, |stored| {
+			let number = stored.header.number;
+			Self::canonical_hash(number) == Some(*block_hash)
+				&& number.saturating_add(T::Confirmations::get()) <= best_number
+		})
+	}
+
+	/// Verify the proof of the receipt of the transaction at `index` in the given block, which
+	/// must be confirmed, and decode it.
+	pub fn verify_receipt(block_hash: &H256, index: u64, proof: &[Vec<u8>]) -> Result<Receipt, &'static str> {
+		let stored = Self::header(block_hash).ok_or("Block not imported")?;
+		ensure!(Self::is_confirmed(block_hash), "Block not confirmed");
+		Receipt::verify_proof(&stored.header.receipts_root, index, proof)
+	}
+
+	/// Check the fields of `header` against its parent.
+	fn check_header(header: &EthHeader, parent: &EthHeader) -> Result<(), &'static str> {
+		let number = parent.number.checked_add(1).ok_or("Parent header number is too large")?;
+		ensure!(header.number == number, "Header number does not follow its parent");
+		ensure!(header.timestamp > parent.timestamp, "Header timestamp is not after its parent");
+		ensure!(!header.difficulty.is_zero(), "Header difficulty is zero");
+		ensure!(header.gas_used <= header.gas_limit, "Header gas used exceeds its gas limit");
+		ensure!(header.extra_data.len() <= 32, "Header extra data is too long");
+		Ok(())
+	}
+
+	/// Make the header with the given hash and number the best one, updating the canonical chain.
+	fn set_best_header(hash: H256, number: u64, previous_best_number: u64) {
+		for stale in number.saturating_add(1)..=previous_best_number {
+			<CanonicalHashes<T>>::remove(stale);
+		}
+
+		let mut current = (hash, number);
+		while Self::canonical_hash(current.1) != Some(current.0) {
+			<CanonicalHashes<T>>::insert(current.1, current.0);
+			match Self::header(&current.0) {
+				// the header the chain is tracked from is always canonical, so we stop before
+				// missing a parent.
+				Some(stored) if current.1 > 0 => current = (stored.header.parent_hash, current.1 - 1),
+				_ => break,
+			}
+		}
+
+		<BestHeader<T>>::put(hash);
+		Self::deposit_event(Event::BestHeaderChanged(hash, number));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::cell::RefCell;
+	use runtime_io::with_externalities;
+	use srml_support::{assert_noop, assert_ok, impl_outer_origin, parameter_types};
+	use substrate_primitives::{H160, Blake2Hasher};
+	use primitives::{traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+	use eth_primitives::{keccak_256, rlp::{encode_bytes, encode_list, encode_u64}};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+	}
+
+	/// Accepts the seal of headers with a non-zero nonce.
+	pub struct TestVerifier;
+	impl SealVerifier for TestVerifier {
+		fn verify_seal(header: &EthHeader, _: &EthHeader) -> Result<(), &'static str> {
+			ensure!(header.nonce != [0u8; 8], "Invalid seal");
+			Ok(())
+		}
+	}
+
+	thread_local! {
+		static LOGS: RefCell<Vec<(H256, Log)>> = RefCell::new(Vec::new());
+	}
+
+	pub struct RecordLogs;
+	impl OnLog for RecordLogs {
+		fn on_log(block_hash: &H256, log: &Log) {
+			LOGS.with(|logs| logs.borrow_mut().push((*block_hash, log.clone())));
+		}
+	}
+
+	parameter_types! {
+		pub const Confirmations: u64 = 2;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Verifier = TestVerifier;
+		type OnLog = RecordLogs;
+		type Confirmations = Confirmations;
+	}
+	type EthBridge = Module<Test>;
+
+	/// An RLP-encoded header with the given parent, difficulty and receipts root.
+	fn encode_header(number: u64, parent_hash: H256, difficulty: u64, receipts_root: H256, nonce: u8) -> Vec<u8> {
+		encode_list(&[
+			encode_bytes(parent_hash.as_bytes()),
+			encode_bytes(&[0u8; 32]),
+			encode_bytes(&[0u8; 20]),
+			encode_bytes(&[0u8; 32]),
+			encode_bytes(&[0u8; 32]),
+			encode_bytes(receipts_root.as_bytes()),
+			encode_bytes(&[0u8; 256]),
+			encode_u64(difficulty),
+			encode_u64(number),
+			encode_u64(8_000_000),
+			encode_u64(0),
+			encode_u64(number.saturating_add(1_000)),
+			encode_bytes(&[]),
+			encode_bytes(&[0u8; 32]),
+			encode_bytes(&[nonce; 8]),
+		])
+	}
+
+	fn hash_of(header: &[u8]) -> H256 {
+		H256::from(keccak_256(header))
+	}
+
+	fn genesis() -> Vec<u8> {
+		encode_header(0, H256::zero(), 1, H256::zero(), 1)
+	}
+
+	/// Import `count` headers of the given difficulty on top of `parent`, returning their hashes.
+	fn import_chain(parent: H256, difficulty: u64, receipts_root: H256, count: u64) -> Vec<H256> {
+		let mut hashes: Vec<H256> = Vec::new();
+		for _ in 0..count {
+			let parent = hashes.last().cloned().unwrap_or(parent);
+			let number = EthBridge::header(&parent).unwrap().header.number + 1;
+			let header = encode_header(number, parent, difficulty, receipts_root, 1);
+			assert_ok!(EthBridge::import_header(Origin::signed(1), header.clone()));
+			hashes.push(hash_of(&header));
+		}
+		hashes
+	}
+
+	fn encoded_receipt() -> Vec<u8> {
+		let log = encode_list(&[
+			encode_bytes(&[1u8; 20]),
+			encode_list(&[encode_bytes(&[2u8; 32])]),
+			encode_bytes(b"data"),
+		]);
+		encode_list(&[encode_u64(1), encode_u64(21_000), encode_bytes(&[0u8; 256]), encode_list(&[log])])
+	}
+
+	/// The receipts trie of a block with a single transaction, and the proof of its receipt.
+	fn receipts_trie() -> (H256, Vec<Vec<u8>>) {
+		// a single leaf under the key `rlp(0)`, whose nibbles are `8, 0`.
+		let leaf = encode_list(&[encode_bytes(&[0x20, 0x80]), encode_bytes(&encoded_receipt())]);
+		(hash_of(&leaf), vec![leaf])
+	}
+
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+		let mut ext: runtime_io::TestExternalities<Blake2Hasher> = t.into();
+		with_externalities(&mut ext, || {
+			assert_ok!(EthBridge::initialize(Origin::ROOT, genesis(), U256::from(1)));
+		});
+		ext
+	}
+
+	#[test]
+	fn initialize_works() {
+		with_externalities(&mut new_test_ext(), || {
+			let hash = hash_of(&genesis());
+			assert_eq!(EthBridge::best_header(), Some(hash));
+			assert_eq!(EthBridge::canonical_hash(0), Some(hash));
+			assert_noop!(
+				EthBridge::initialize(Origin::ROOT, genesis(), U256::from(1)),
+				"Bridge already initialized"
+			);
+		});
+	}
+
+	#[test]
+	fn import_header_checks_header() {
+		with_externalities(&mut new_test_ext(), || {
+			let genesis = hash_of(&genesis());
+			assert_noop!(
+				EthBridge::import_header(Origin::signed(1), vec![0xc0]),
+				"Header has an invalid number of fields"
+			);
+			assert_noop!(
+				EthBridge::import_header(Origin::signed(1), encode_header(1, H256::repeat_byte(1), 1, H256::zero(), 1)),
+				"Parent header not imported"
+			);
+			assert_noop!(
+				EthBridge::import_header(Origin::signed(1), encode_header(2, genesis, 1, H256::zero(), 1)),
+				"Header number does not follow its parent"
+			);
+			assert_noop!(
+				EthBridge::import_header(Origin::signed(1), encode_header(1, genesis, 0, H256::zero(), 1)),
+				"Header difficulty is zero"
+			);
+			assert_noop!(
+				EthBridge::import_header(Origin::signed(1), encode_header(1, genesis, 1, H256::zero(), 0)),
+				"Invalid seal"
+			);
+
+			let header = encode_header(1, genesis, 1, H256::zero(), 1);
+			assert_ok!(EthBridge::import_header(Origin::signed(1), header.clone()));
+			assert_noop!(EthBridge::import_header(Origin::signed(1), header), "Header already imported");
+		});
+	}
+
+	#[test]
+	fn import_header_rejects_child_of_last_number() {
+		let t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+		let mut ext: runtime_io::TestExternalities<Blake2Hasher> = t.into();
+		with_externalities(&mut ext, || {
+			let last = encode_header(u64::max_value(), H256::zero(), 1, H256::zero(), 1);
+			assert_ok!(EthBridge::initialize(Origin::ROOT, last.clone(), U256::from(1)));
+			assert_noop!(
+				EthBridge::import_header(Origin::signed(1), encode_header(0, hash_of(&last), 1, H256::zero(), 1)),
+				"Parent header number is too large"
+			);
+		});
+	}
+
+	#[test]
+	fn canonical_chain_follows_total_difficulty() {
+		with_externalities(&mut new_test_ext(), || {
+			let genesis = hash_of(&genesis());
+			let long_chain = import_chain(genesis, 1, H256::zero(), 3);
+			assert_eq!(EthBridge::best_header(), Some(long_chain[2]));
+
+			// a shorter chain with more work takes over.
+			let heavy_chain = import_chain(genesis, 2, H256::repeat_byte(1), 2);
+			assert_eq!(EthBridge::best_header(), Some(heavy_chain[1]));
+			assert_eq!(EthBridge::canonical_hash(1), Some(heavy_chain[0]));
+			assert_eq!(EthBridge::canonical_hash(2), Some(heavy_chain[1]));
+			assert_eq!(EthBridge::canonical_hash(3), None);
+			assert_eq!(EthBridge::header(&heavy_chain[1]).unwrap().total_difficulty, U256::from(5));
+
+			// and the first chain takes over again once heavier.
+			let extension = import_chain(long_chain[2], 1, H256::zero(), 2);
+			assert_eq!(EthBridge::best_header(), Some(extension[1]));
+			assert_eq!(EthBridge::canonical_hash(1), Some(long_chain[0]));
+			assert_eq!(EthBridge::canonical_hash(5), Some(extension[1]));
+		});
+	}
+
+	#[test]
+	fn submit_receipt_works() {
+		with_externalities(&mut new_test_ext(), || {
+			let (receipts_root, proof) = receipts_trie();
+			let block = import_chain(hash_of(&genesis()), 1, receipts_root, 1)[0];
+
+			assert_noop!(
+				EthBridge::submit_receipt(Origin::signed(1), block, 0, proof.clone()),
+				"Block not confirmed"
+			);
+			import_chain(block, 1, H256::zero(), 2);
+
+			assert_noop!(
+				EthBridge::submit_receipt(Origin::signed(1), block, 1, proof.clone()),
+				"Receipt is not in the receipts trie"
+			);
+			assert_ok!(EthBridge::submit_receipt(Origin::signed(1), block, 0, proof.clone()));
+			assert!(EthBridge::is_receipt_processed((block, 0)));
+			LOGS.with(|logs| assert_eq!(*logs.borrow(), vec![(block, Log {
+				address: H160::repeat_byte(1),
+				topics: vec![H256::repeat_byte(2)],
+				data: b"data".to_vec(),
+			})]));
+
+			assert_noop!(
+				EthBridge::submit_receipt(Origin::signed(1), block, 0, proof),
+				"Receipt already processed"
+			);
+		});
+	}
+
+	#[test]
+	fn submit_receipt_requires_canonical_block() {
+		with_externalities(&mut new_test_ext(), || {
+			let (receipts_root, proof) = receipts_trie();
+			let genesis = hash_of(&genesis());
+			let block = import_chain(genesis, 1, receipts_root, 1)[0];
+			import_chain(block, 1, H256::zero(), 2);
+			// a heavier fork makes the block stale.
+			import_chain(genesis, 3, H256::zero(), 3);
+
+			assert!(!EthBridge::is_confirmed(&block));
+			assert_noop!(
+				EthBridge::submit_receipt(Origin::signed(1), block, 0, proof),
+				"Block not confirmed"
+			);
+		});
+	}
+}
@@ -0,0 +1,351 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Offchain Worker Example Module
+//!
+//! The Offchain Worker Example: A simple module demonstrating the offchain worker APIs, and how
+//! their results make it back on chain.
+//!
+//! Run `cargo doc --package srml-example-offchain-worker --open` to view this module's
+//! documentation.
+//!
+//! - [`example_offchain_worker::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//! - [`Module`](./struct.Module.html)
+//!
+//! ## Overview
+//!
+//! After every block, the offchain worker of this module fetches the current BTC price in USD
+//! over HTTP and submits it back to the chain, where the most recent prices are kept:
+//!
+//! - On even blocks, the price is submitted in a signed transaction, paid for by the account of
+//!   the module's key.
+//! - On odd blocks, the price is submitted in an unsigned transaction, which carries a payload
+//!   signed by the module's key. The module validates the payload in `ValidateUnsigned`, and
+//!   accepts at most one such transaction every `UnsignedInterval` blocks, since nobody pays for
+//!   it.
+//!
+//! The module's key is an sr25519 key which is created through the offchain API on first use,
+//! and remembered in the persistent local storage of the node. The account of that key must be
+//! funded for the signed transactions to be accepted.
+//!
+//! This module is for demonstration only: prices reported by a single node shouldn't be trusted.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `submit_price` - Submit a new price, from a signed origin.
+//! - `submit_price_unsigned` - Submit a new price with a signed payload, from no origin.
+//!
+//! ### Public Functions
+//!
+//! - `average_price` - The average of the recent prices.
+//!
+//! ## Usage
+//!
+//! The runtime needs to implement `system::offchain::CreateTransaction`, and to forward unsigned
+//! transactions to this module's `ValidateUnsigned`:
+//!
+//! ```nocompile
+//! impl example_offchain_worker::Trait for Runtime {
+//!     type Event = Event;
+//!     type Call = Call;
+//!     type SubmitTransaction = system::offchain::TransactionSubmitter<Runtime, UncheckedExtrinsic>;
+//!     type UnsignedInterval = UnsignedInterval;
+//! }
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use rstd::convert::TryFrom;
+use parity_codec::{Encode, Decode};
+use primitives::ApplyError;
+use primitives::traits::{Zero, SaturatedConversion};
+use primitives::transaction_validity::{TransactionValidity, ValidTransaction};
+use srml_support::{StorageValue, decl_module, decl_storage, decl_event, print, traits::Get};
+use substrate_primitives::offchain::{CryptoKey, CryptoKind, Duration, StorageKind};
+use sr_io::offchain::http;
+use system::{ensure_signed, ensure_none};
+use system::offchain::{SubmitSignedTransaction, SubmitUnsignedTransaction};
+
+mod tests;
+
+/// The URL the price is fetched from.
+pub const PRICE_URL: &str = "https://min-api.cryptocompare.com/data/price?fsym=BTC&tsyms=USD";
+
+/// How long to wait for the price, in milliseconds.
+pub const FETCH_TIMEOUT: u64 = 2_000;
+
+/// The local storage key under which the module's key is remembered.
+pub const KEY_DB_KEY: &[u8] = b"srml/example-offchain-worker/key";
+
+/// The number of recent prices which are kept.
+pub const MAX_PRICES: usize = 64;
+
+/// A price signed by the module's key, for an unsigned transaction.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PricePayload<AccountId, BlockNumber> {
+	/// The block at which the price was fetched.
+	pub block_number: BlockNumber,
+	/// The price, in cents.
+	pub price: u32,
+	/// The account of the key which signed the payload.
+	pub public: AccountId,
+}
+
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The overarching call type.
+	type Call: From<Call<Self>>;
+
+	/// Submits the transactions of the offchain worker.
+	type SubmitTransaction:
+		SubmitSignedTransaction<Self, <Self as Trait>::Call> +
+		SubmitUnsignedTransaction<Self, <Self as Trait>::Call>;
+
+	/// The minimum number of blocks between two unsigned price submissions.
+	type UnsignedInterval: Get<Self::BlockNumber>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as ExampleOffchainWorker {
+		/// The most recent prices, in cents, oldest first.
+		Prices get(prices): Vec<u32>;
+
+		/// The first block at which a new unsigned price submission is accepted.
+		NextUnsignedAt get(next_unsigned_at): T::BlockNumber;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where <T as system::Trait>::AccountId {
+		/// A new price, in cents, was submitted by the given account.
+		NewPrice(u32, AccountId),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The minimum number of blocks between two unsigned price submissions.
+		const UnsignedInterval: T::BlockNumber = T::UnsignedInterval::get();
+
+		fn deposit_event<T>() = default;
+
+		/// Submit a new price, in cents.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Emits `NewPrice`.
+		fn submit_price(origin, price: u32) {
+			let who = ensure_signed(origin)?;
+			Self::add_price(who, price);
+		}
+
+		/// Submit a new price, in cents, in a payload signed by the submitter.
+		///
+		/// The dispatch origin for this call must be _None_. Accepted at most once every
+		/// `UnsignedInterval` blocks.
+		///
+		/// Emits `NewPrice`.
+		fn submit_price_unsigned(
+			origin,
+			payload: PricePayload<T::AccountId, T::BlockNumber>,
+			signature: Vec<u8>
+		) {
+			ensure_none(origin)?;
+			// unsigned transactions aren't validated again when they are dispatched.
+			Self::check_payload(&payload, &signature).map_err(|_| "invalid price payload")?;
+
+			let now = <system::Module<T>>::block_number();
+			<NextUnsignedAt<T>>::put(now + T::UnsignedInterval::get());
+			Self::add_price(payload.public, payload.price);
+		}
+
+		// Runs after every block.
+		fn offchain_worker(block_number: T::BlockNumber) {
+			// alternate between both ways of submitting a price.
+			let result = if (block_number % T::BlockNumber::from(2u32)).is_zero() {
+				Self::fetch_price_and_send_signed()
+			} else {
+				Self::fetch_price_and_send_unsigned(block_number)
+			};
+
+			if let Err(e) = result {
+				print(e);
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The average of the recent prices, in cents, if any.
+	pub fn average_price() -> Option<u32> {
+		let prices = Self::prices();
+		if prices.is_empty() {
+			return None;
+		}
+		let sum = prices.iter().map(|p| *p as u64).sum::<u64>();
+		Some((sum / prices.len() as u64) as u32)
+	}
+
+	fn add_price(who: T::AccountId, price: u32) {
+		Prices::mutate(|prices| {
+			if prices.len() >= MAX_PRICES {
+				prices.remove(0);
+			}
+			prices.push(price);
+		});
+
+		Self::deposit_event(RawEvent::NewPrice(price, who));
+	}
+
+	/// Check that `payload` is signed by its account, and that it isn't too early for it.
+	fn check_payload(
+		payload: &PricePayload<T::AccountId, T::BlockNumber>,
+		signature: &[u8],
+	) -> Result<(), ApplyError> {
+		if signature.len() != 64 {
+			return Err(ApplyError::BadSignature);
+		}
+		let mut sig = [0u8; 64];
+		sig.copy_from_slice(signature);
+
+		// the account is an sr25519 public key, which encodes as its raw bytes.
+		if !sr_io::sr25519_verify(&sig, &payload.encode(), payload.public.encode()) {
+			return Err(ApplyError::BadSignature);
+		}
+
+		// a price from before the last unsigned submission could be a replay.
+		if payload.block_number < Self::next_unsigned_at() {
+			return Err(ApplyError::Stale);
+		}
+
+		Ok(())
+	}
+
+	fn fetch_price_and_send_signed() -> Result<(), &'static str> {
+		let (key, account) = Self::local_key()?;
+		let price = Self::fetch_price()?;
+
+		T::SubmitTransaction::sign_and_submit(Call::submit_price(price), key, account)
+			.map_err(|_| "Offchain error: submitting signed transaction failed!")
+	}
+
+	fn fetch_price_and_send_unsigned(block_number: T::BlockNumber) -> Result<(), &'static str> {
+		// don't bother if the transaction would be rejected anyway.
+		if block_number < Self::next_unsigned_at() {
+			return Ok(());
+		}
+
+		let (key, public) = Self::local_key()?;
+		let price = Self::fetch_price()?;
+
+		let payload = PricePayload { block_number, price, public };
+		let signature = sr_io::sign(key, &payload.encode())
+			.map_err(|_| "Offchain error: signing failed!")?;
+
+		T::SubmitTransaction::submit_unsigned(Call::submit_price_unsigned(payload, signature))
+			.map_err(|_| "Offchain error: submitting unsigned transaction failed!")
+	}
+
+	/// The module's key and its account, creating the key if there is none yet.
+	fn local_key() -> Result<(CryptoKey, T::AccountId), &'static str> {
+		let key = match sr_io::local_storage_get(StorageKind::PERSISTENT, KEY_DB_KEY) {
+			Some(stored) => u64::decode(&mut &stored[..])
+				.and_then(|key| CryptoKey::try_from(key).ok())
+				.ok_or("Offchain error: decoding stored key failed!")?,
+			None => {
+				let key = sr_io::new_crypto_key(CryptoKind::Sr25519)
+					.map_err(|_| "Offchain error: creating key failed!")?;
+				sr_io::local_storage_set(StorageKind::PERSISTENT, KEY_DB_KEY, &u64::from(key).encode());
+				key
+			}
+		};
+
+		let public = sr_io::pubkey(key).map_err(|_| "Offchain error: key not available!")?;
+		let account = T::AccountId::decode(&mut &public[..])
+			.ok_or("Offchain error: decoding account failed!")?;
+		Ok((key, account))
+	}
+
+	/// Fetch the current price, in cents.
+	fn fetch_price() -> Result<u32, &'static str> {
+		let deadline = sr_io::timestamp().add(Duration::from_millis(FETCH_TIMEOUT));
+		let pending = http::Request::get(PRICE_URL)
+			.deadline(deadline)
+			.send()
+			.map_err(|_| "Offchain error: sending price request failed!")?;
+		let response = pending.try_wait(deadline)
+			.map_err(|_| "Offchain error: price request timed out!")?
+			.map_err(|_| "Offchain error: price request failed!")?;
+		if response.code != 200 {
+			return Err("Offchain error: unexpected price response status!");
+		}
+
+		let body = response.body().collect::<Vec<u8>>();
+		parse_price(&body).ok_or("Offchain error: parsing price failed!")
+	}
+}
+
+impl<T: Trait> srml_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> srml_support::unsigned::TransactionValidity {
+		if let Call::submit_price_unsigned(payload, signature) = call {
+			if let Err(e) = Self::check_payload(payload, signature) {
+				return TransactionValidity::Invalid(e as i8);
+			}
+
+			TransactionValidity::Valid(ValidTransaction {
+				priority: 0,
+				requires: vec![],
+				// only one unsigned price per interval makes it into the pool.
+				provides: vec![(KEY_DB_KEY, Self::next_unsigned_at()).encode()],
+				longevity: T::UnsignedInterval::get().saturated_into::<u64>(),
+				propagate: true,
+			})
+		} else {
+			TransactionValidity::Invalid(0)
+		}
+	}
+}
+
+/// Parse the price, in cents, out of a response like `{"USD":10234.56}`.
+///
+/// Decimals beyond the second are ignored.
+fn parse_price(body: &[u8]) -> Option<u32> {
+	const FIELD: &str = "\"USD\":";
+
+	let body = rstd::str::from_utf8(body).ok()?;
+	let number = body[body.find(FIELD)? + FIELD.len()..].trim_start();
+	let end = number.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(number.len());
+
+	let mut parts = number[..end].splitn(2, '.');
+	let whole = parts.next()?.parse::<u32>().ok()?;
+	let fraction = parts.next().unwrap_or("");
+	let cents = match fraction.len() {
+		0 => 0,
+		1 => fraction.parse::<u32>().ok()? * 10,
+		_ => fraction[..2].parse::<u32>().ok()?,
+	};
+
+	whole.checked_mul(100)?.checked_add(cents)
+}
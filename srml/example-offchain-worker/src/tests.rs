@@ -0,0 +1,236 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the offchain worker example module.
+
+#![cfg(test)]
+
+use super::*;
+
+use std::sync::Arc;
+use sr_io::{with_externalities, TestExternalities};
+use substrate_primitives::{H256, Blake2Hasher, sr25519, crypto::Pair};
+use substrate_offchain::testing::{self, TestOffchainExt};
+use srml_support::{assert_ok, impl_outer_origin, parameter_types};
+use srml_support::dispatch::Dispatchable;
+use srml_support::unsigned::ValidateUnsigned;
+use primitives::testing::Header;
+use primitives::traits::{BlakeTwo256, IdentityLookup, OffchainWorker, Extrinsic as ExtrinsicT};
+use system::offchain::{CreateTransaction, TransactionSubmitter};
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: u32 = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+}
+impl system::Trait for Test {
+	type Origin = Origin;
+	type Call = ();
+	type BaseCallFilter = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = sr25519::Public;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type WeightMultiplierUpdate = ();
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+}
+
+/// An extrinsic carrying its sender and the signature of its call and nonce, if signed.
+#[derive(Encode, Decode)]
+pub struct Extrinsic(Option<(sr25519::Public, Vec<u8>)>, Call<Test>);
+
+impl ExtrinsicT for Extrinsic {
+	type Call = Call<Test>;
+
+	fn new_unsigned(call: Self::Call) -> Option<Self> {
+		Some(Extrinsic(None, call))
+	}
+}
+
+impl CreateTransaction<Test, Extrinsic> for Test {
+	fn create_transaction<F: FnOnce(&[u8]) -> Option<Vec<u8>>>(
+		call: Call<Test>,
+		account: sr25519::Public,
+		nonce: u64,
+		sign: F,
+	) -> Option<Extrinsic> {
+		let signature = sign(&(&call, nonce).encode())?;
+		Some(Extrinsic(Some((account, signature)), call))
+	}
+}
+
+parameter_types! {
+	pub const UnsignedInterval: u64 = 4;
+}
+impl Trait for Test {
+	type Event = ();
+	type Call = Call<Test>;
+	type SubmitTransaction = TransactionSubmitter<Test, Extrinsic>;
+	type UnsignedInterval = UnsignedInterval;
+}
+type System = system::Module<Test>;
+type ExampleOffchainWorker = Module<Test>;
+
+/// The key the module creates on first use.
+const KEY: CryptoKey = CryptoKey::LocalKey { id: 0, kind: CryptoKind::Sr25519 };
+
+fn new_test_ext() -> (TestExternalities<Blake2Hasher>, Arc<parking_lot::RwLock<testing::State>>) {
+	let (offchain, state) = TestOffchainExt::new();
+	let mut t: TestExternalities<Blake2Hasher> =
+		system::GenesisConfig::default().build_storage::<Test>().unwrap().0.into();
+	t.set_offchain_externalities(offchain);
+	(t, state)
+}
+
+fn expect_price_request(state: &mut testing::State) {
+	state.expect_request(0, testing::PendingRequest {
+		method: "GET".into(),
+		uri: PRICE_URL.into(),
+		response: br#"{"USD":155.23}"#.to_vec(),
+		sent: true,
+		..Default::default()
+	});
+}
+
+fn submitted_transactions(state: &testing::State) -> Vec<Extrinsic> {
+	state.transactions.iter().map(|xt| Extrinsic::decode(&mut &xt[..]).unwrap()).collect()
+}
+
+fn key_account(state: &testing::State) -> sr25519::Public {
+	sr25519::Public::decode(&mut &state.public_key(KEY).unwrap()[..]).unwrap()
+}
+
+#[test]
+fn parse_price_works() {
+	assert_eq!(parse_price(br#"{"USD":155.23}"#), Some(15523));
+	assert_eq!(parse_price(br#"{"USD": 155}"#), Some(15500));
+	assert_eq!(parse_price(br#"{"USD":155.2}"#), Some(15520));
+	assert_eq!(parse_price(br#"{"USD":0.987}"#), Some(98));
+	assert_eq!(parse_price(br#"{"EUR":155.23}"#), None);
+	assert_eq!(parse_price(br#"{"USD":}"#), None);
+	assert_eq!(parse_price(br#"{"USD":99999999999}"#), None);
+}
+
+#[test]
+fn fetch_price_works() {
+	let (mut t, state) = new_test_ext();
+	expect_price_request(&mut state.write());
+
+	with_externalities(&mut t, || {
+		assert_eq!(ExampleOffchainWorker::fetch_price(), Ok(15523));
+	});
+}
+
+#[test]
+fn should_submit_signed_transaction_on_even_blocks() {
+	let (mut t, state) = new_test_ext();
+	expect_price_request(&mut state.write());
+
+	with_externalities(&mut t, || {
+		ExampleOffchainWorker::generate_extrinsics(2);
+	});
+
+	let (account, call) = {
+		let state = state.read();
+		let account = key_account(&state);
+		let mut transactions = submitted_transactions(&state);
+		assert_eq!(transactions.len(), 1);
+
+		let Extrinsic(signed, call) = transactions.remove(0);
+		let (sender, signature) = signed.expect("the price is submitted in a signed transaction");
+		assert_eq!(sender, account);
+		assert!(sr25519::Pair::verify_weak(&signature, (&call, 0u64).encode(), &account));
+		assert_eq!(call, Call::submit_price(15523));
+		(account, call)
+	};
+
+	with_externalities(&mut t, || {
+		assert_ok!(call.dispatch(Origin::signed(account)));
+		assert_eq!(ExampleOffchainWorker::prices(), vec![15523]);
+		assert_eq!(ExampleOffchainWorker::average_price(), Some(15523));
+	});
+}
+
+#[test]
+fn should_submit_unsigned_transaction_on_odd_blocks() {
+	let (mut t, state) = new_test_ext();
+	expect_price_request(&mut state.write());
+
+	with_externalities(&mut t, || {
+		System::set_block_number(1);
+		ExampleOffchainWorker::generate_extrinsics(1);
+	});
+
+	let (account, call) = {
+		let state = state.read();
+		let mut transactions = submitted_transactions(&state);
+		assert_eq!(transactions.len(), 1);
+
+		let Extrinsic(signed, call) = transactions.remove(0);
+		assert!(signed.is_none());
+		(key_account(&state), call)
+	};
+
+	with_externalities(&mut t, || {
+		match call {
+			Call::submit_price_unsigned(ref payload, _) => {
+				assert_eq!(payload, &PricePayload { block_number: 1, price: 15523, public: account });
+			}
+			_ => panic!("unexpected call"),
+		}
+		match ExampleOffchainWorker::validate_unsigned(&call) {
+			TransactionValidity::Valid(v) => assert_eq!(v.longevity, 4),
+			other => panic!("unexpected validity: {:?}", other),
+		}
+
+		assert_ok!(call.clone().dispatch(Origin::NONE));
+		assert_eq!(ExampleOffchainWorker::prices(), vec![15523]);
+		assert_eq!(ExampleOffchainWorker::next_unsigned_at(), 5);
+
+		// the same payload can't be submitted again.
+		assert_eq!(
+			ExampleOffchainWorker::validate_unsigned(&call),
+			TransactionValidity::Invalid(ApplyError::Stale as i8),
+		);
+
+		// nor can it be tampered with.
+		if let Call::submit_price_unsigned(mut payload, signature) = call {
+			payload.block_number = 5;
+			assert_eq!(
+				ExampleOffchainWorker::validate_unsigned(&Call::submit_price_unsigned(payload, signature)),
+				TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+			);
+		}
+
+		// and the worker doesn't submit another unsigned price before `NextUnsignedAt`.
+		System::set_block_number(3);
+		ExampleOffchainWorker::generate_extrinsics(3);
+	});
+
+	assert_eq!(state.read().transactions.len(), 1);
+}
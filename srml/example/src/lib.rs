@@ -22,6 +22,9 @@
 //!
 //! Run `cargo doc --package srml-example --open` to view this module's documentation.
 //!
+//! For a small but complete module, which reserves deposits through `ReservableCurrency` and
+//! gates privileged calls behind a configurable `ForceOrigin`, see the Nicks module in `srml/nicks`.
+//!
 //! ### Documentation Guidelines:
 //!
 //! <!-- Original author of paragraph: Various. Based on collation of review comments to PRs addressing issues with -->
@@ -529,6 +532,8 @@ mod tests {
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -555,6 +560,7 @@ mod tests {
 		type OnNewAccount = ();
 		type Event = ();
 		type TransactionPayment = ();
+		type TipPayment = ();
 		type TransferPayment = ();
 		type DustRemoval = ();
 		type ExistentialDeposit = ExistentialDeposit;
@@ -0,0 +1,236 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fuzz harness for the executive dispatch path.
+//!
+//! The harness generates inputs (blocks or extrinsics), usually by mutating the encoding of a
+//! valid sample, and feeds them into a closure such as `Executive::execute_block` or
+//! `Executive::apply_extrinsic`, each time inside fresh `TestExternalities`. Runtime code is
+//! allowed to panic only through its defined error paths (e.g. the executive's block checks);
+//! any other panic, including one raised while decoding, fails the run and reports the
+//! offending input.
+//!
+//! ```ignore
+//! use srml_executive_fuzz::{Fuzzer, EXECUTE_BLOCK_PANICS, mutated};
+//!
+//! Fuzzer::new(42)
+//! 	.expected_panics(EXECUTE_BLOCK_PANICS)
+//! 	.run(
+//! 		|rng| Some(Block { header: header.clone(), extrinsics: vec![mutated(rng, &xt)?] }),
+//! 		new_test_ext,
+//! 		|block| Executive::execute_block(block),
+//! 	);
+//! ```
+//!
+//! Runs are deterministic for a given seed, so a failure can be replayed by re-running the
+//! test it was found by.
+//!
+//! Note that mutating the length prefix of a collection can make its decoder try to allocate
+//! an arbitrary amount of memory, which aborts the process instead of panicking. Samples with
+//! collections are best mutated piecewise, as above.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use parity_codec::{Decode, Encode};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use runtime_io::{TestExternalities, with_externalities};
+use substrate_primitives::Blake2Hasher;
+
+/// Number of inputs tried by default.
+pub const DEFAULT_ITERATIONS: usize = 500;
+
+/// The panics `Executive::execute_block` raises for blocks which are invalid.
+pub const EXECUTE_BLOCK_PANICS: &[&str] = &[
+	"Parent hash should be valid.",
+	"Transaction trie root must be valid.",
+	"Storage root must match that calculated.",
+	"Number of digest items must match that calculated.",
+	"Digest item must match that calculated.",
+	"All extrinsics should have sender able to pay their fees",
+	"All extrinsics should be properly signed",
+	"All extrinsics should have the correct nonce",
+	"Extrinsics should not exceed block limit",
+];
+
+/// Deterministic fuzzer feeding generated inputs into runtime code.
+pub struct Fuzzer {
+	seed: u64,
+	rng: StdRng,
+	iterations: usize,
+	expected_panics: Vec<&'static str>,
+}
+
+impl Fuzzer {
+	/// Create a fuzzer seeded with `seed`, which allows no panics at all.
+	pub fn new(seed: u64) -> Self {
+		Fuzzer {
+			seed,
+			rng: StdRng::seed_from_u64(seed),
+			iterations: DEFAULT_ITERATIONS,
+			expected_panics: Vec::new(),
+		}
+	}
+
+	/// Set the number of inputs to try.
+	pub fn iterations(mut self, iterations: usize) -> Self {
+		self.iterations = iterations;
+		self
+	}
+
+	/// Allow panics whose message contains any of `messages`.
+	pub fn expected_panics(mut self, messages: &[&'static str]) -> Self {
+		self.expected_panics.extend_from_slice(messages);
+		self
+	}
+
+	/// Run `f` on the inputs returned by `generate`, each time in fresh externalities built
+	/// by `new_ext`. Iterations for which `generate` returns `None` are skipped.
+	///
+	/// Returns the number of inputs `f` was run on. Panics if `generate` panics, or if `f`
+	/// panics with a message which isn't expected.
+	pub fn run<T, G, E, F>(mut self, mut generate: G, new_ext: E, f: F) -> usize where
+		T: Encode,
+		G: FnMut(&mut StdRng) -> Option<T>,
+		E: Fn() -> TestExternalities<Blake2Hasher>,
+		F: Fn(T),
+	{
+		let mut executed = 0;
+
+		for iteration in 0..self.iterations {
+			let rng = &mut self.rng;
+			let input = match panic::catch_unwind(AssertUnwindSafe(|| generate(rng))) {
+				Ok(Some(input)) => input,
+				Ok(None) => continue,
+				Err(e) => self.report(iteration, None, "generation", e),
+			};
+			executed += 1;
+
+			let encoded = input.encode();
+			let mut ext = new_ext();
+			// the panic is caught inside the externalities so that they are always reset.
+			let result = with_externalities(&mut ext, || {
+				panic::catch_unwind(AssertUnwindSafe(|| f(input)))
+			});
+
+			if let Err(e) = result {
+				let message = panic_message(&*e);
+				if !self.expected_panics.iter().any(|expected| message.contains(expected)) {
+					self.report(iteration, Some(&encoded), "execution", e);
+				}
+			}
+		}
+
+		executed
+	}
+
+	fn report(&self, iteration: usize, input: Option<&[u8]>, stage: &str, e: Box<dyn Any + Send>) -> ! {
+		panic!(
+			"Unexpected panic during {} (seed {}, iteration {}): {}\ninput: {}",
+			stage,
+			self.seed,
+			iteration,
+			panic_message(&*e),
+			input.map_or_else(
+				|| "<none>".into(),
+				|input| input.iter().map(|b| format!("{:02x}", b)).collect::<String>(),
+			),
+		)
+	}
+}
+
+/// Mutate the encoding of `sample` and decode it back.
+///
+/// Returns `None` if the mutated encoding doesn't decode.
+pub fn mutated<T: Encode + Decode, R: Rng>(rng: &mut R, sample: &T) -> Option<T> {
+	let mut encoded = sample.encode();
+	mutate(rng, &mut encoded);
+	T::decode(&mut &encoded[..])
+}
+
+/// Apply between one and four random mutations to `input`.
+///
+/// Besides random noise, bytes are set to the boundary values which tend to hit edge cases of
+/// compact integer and length-prefix decoding.
+pub fn mutate<R: Rng>(rng: &mut R, input: &mut Vec<u8>) {
+	const BOUNDARIES: [u8; 6] = [0x00, 0x01, 0x7f, 0x80, 0xfe, 0xff];
+
+	for _ in 0..rng.gen_range(1, 5) {
+		if input.is_empty() {
+			input.push(rng.gen());
+			continue;
+		}
+
+		let at = rng.gen_range(0, input.len());
+		match rng.gen_range(0, 6) {
+			0 => input[at] ^= 1 << rng.gen_range(0, 8),
+			1 => input[at] = BOUNDARIES[rng.gen_range(0, BOUNDARIES.len())],
+			2 => input.insert(at, rng.gen()),
+			3 => { input.remove(at); },
+			4 => input.truncate(at),
+			_ => {
+				let end = rng.gen_range(at, input.len()) + 1;
+				let chunk = input[at..end].to_vec();
+				input.splice(at..at, chunk);
+			}
+		}
+	}
+}
+
+fn panic_message(e: &(dyn Any + Send)) -> String {
+	if let Some(message) = e.downcast_ref::<&str>() {
+		message.to_string()
+	} else if let Some(message) = e.downcast_ref::<String>() {
+		message.clone()
+	} else {
+		"<non-string panic>".into()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn new_ext() -> TestExternalities<Blake2Hasher> {
+		Default::default()
+	}
+
+	#[test]
+	fn mutations_are_deterministic() {
+		let mutations = |seed| {
+			let mut rng = StdRng::seed_from_u64(seed);
+			(0..20).map(|_| mutated(&mut rng, &(1u32, 2u64, 3u8))).collect::<Vec<_>>()
+		};
+
+		assert_eq!(mutations(7), mutations(7));
+	}
+
+	#[test]
+	fn expected_panics_are_tolerated() {
+		let executed = Fuzzer::new(1)
+			.expected_panics(&["too large"])
+			.run(|rng| mutated(rng, &(1u32, 2u32)), new_ext, |(a, b)| {
+				assert!(a.checked_add(b).is_some(), "too large")
+			});
+
+		assert!(executed > 0);
+	}
+
+	#[test]
+	#[should_panic(expected = "Unexpected panic during execution")]
+	fn unexpected_panics_are_reported() {
+		Fuzzer::new(1).run(|rng| mutated(rng, &(1u32, 2u32)), new_ext, |_| panic!("always"));
+	}
+}
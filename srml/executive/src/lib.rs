@@ -71,6 +71,10 @@
 //! /// Executive: handles dispatch to the various modules.
 //! pub type Executive = executive::Executive<Runtime, Block, Context, Runtime, AllModules>;
 //! ```
+//!
+//! `AllModules` is generated by `construct_runtime!`. The module hooks are called in the order of
+//! this tuple (`on_finalize` in reverse order), so a runtime can pass `AllModulesReversed` or its own
+//! tuple of modules to reorder the hooks or to leave some modules out.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -79,9 +83,9 @@ use rstd::marker::PhantomData;
 use rstd::result;
 use primitives::{generic::Digest, traits::{
 	self, Header, Zero, One, Checkable, Applyable, CheckEqual, OnFinalize,
-	OnInitialize, NumberFor, Block as BlockT, OffchainWorker, ValidateUnsigned
+	OnInitialize, OnIdle, OnRuntimeUpgrade, NumberFor, Block as BlockT, OffchainWorker, ValidateUnsigned
 }};
-use srml_support::Dispatchable;
+use srml_support::{Dispatchable, traits::{Get, Filter}};
 use parity_codec::{Codec, Encode};
 use system::{extrinsics_root, DigestOf};
 use primitives::{ApplyOutcome, ApplyError};
@@ -96,7 +100,7 @@ mod internal {
 		Stale,
 		Future,
 		CantPay,
-		FullBlock,
+		ExhaustsResources,
 	}
 
 	pub enum ApplyOutcome {
@@ -113,6 +117,7 @@ mod internal {
 				DispatchError::Stale => ApplyError::Stale,
 				DispatchError::Future => ApplyError::Future,
 				DispatchError::BadProof => ApplyError::BadSignature(""),
+				DispatchError::ExhaustsResources => ApplyError::ExhaustsResources,
 			}
 		}
 	}
@@ -124,6 +129,9 @@ pub trait ExecuteBlock<Block: BlockT> {
 	fn execute_block(block: Block);
 }
 
+/// The error of an extrinsic whose call is rejected by `system::Trait::BaseCallFilter`.
+pub use primitives::CALL_FILTERED;
+
 pub type CheckedOf<E, C> = <E as Checkable<C>>::Checked;
 pub type CallOf<E, C> = <CheckedOf<E, C> as Applyable>::Call;
 pub type OriginOf<E, C> = <CallOf<E, C> as Dispatchable>::Origin;
@@ -137,11 +145,13 @@ impl<
 	Block: traits::Block<Header=System::Header, Hash=System::Hash>,
 	Context: Default,
 	UnsignedValidator,
-	AllModules: OnInitialize<System::BlockNumber> + OnFinalize<System::BlockNumber> + OffchainWorker<System::BlockNumber>,
+	AllModules: OnInitialize<System::BlockNumber> + OnIdle<System::BlockNumber> + OnRuntimeUpgrade +
+		OnFinalize<System::BlockNumber> + OffchainWorker<System::BlockNumber>,
 > ExecuteBlock<Block> for Executive<System, Block, Context, UnsignedValidator, AllModules>
 where
 	Block::Extrinsic: Checkable<Context> + Codec,
-	CheckedOf<Block::Extrinsic, Context>: Applyable<AccountId=System::AccountId> + GetDispatchInfo,
+	CheckedOf<Block::Extrinsic, Context>:
+		Applyable<AccountId=System::AccountId, Call=System::Call> + GetDispatchInfo,
 	CallOf<Block::Extrinsic, Context>: Dispatchable,
 	OriginOf<Block::Extrinsic, Context>: From<Option<System::AccountId>>,
 	UnsignedValidator: ValidateUnsigned<Call=CallOf<Block::Extrinsic, Context>>,
@@ -156,11 +166,13 @@ impl<
 	Block: traits::Block<Header=System::Header, Hash=System::Hash>,
 	Context: Default,
 	UnsignedValidator,
-	AllModules: OnInitialize<System::BlockNumber> + OnFinalize<System::BlockNumber> + OffchainWorker<System::BlockNumber>,
+	AllModules: OnInitialize<System::BlockNumber> + OnIdle<System::BlockNumber> + OnRuntimeUpgrade +
+		OnFinalize<System::BlockNumber> + OffchainWorker<System::BlockNumber>,
 > Executive<System, Block, Context, UnsignedValidator, AllModules>
 where
 	Block::Extrinsic: Checkable<Context> + Codec,
-	CheckedOf<Block::Extrinsic, Context>: Applyable<AccountId=System::AccountId> + GetDispatchInfo,
+	CheckedOf<Block::Extrinsic, Context>:
+		Applyable<AccountId=System::AccountId, Call=System::Call> + GetDispatchInfo,
 	CallOf<Block::Extrinsic, Context>: Dispatchable,
 	OriginOf<Block::Extrinsic, Context>: From<Option<System::AccountId>>,
 	UnsignedValidator: ValidateUnsigned<Call=CallOf<Block::Extrinsic, Context>>,
@@ -179,7 +191,12 @@ where
 		digest: &Digest<System::Hash>,
 	) {
 		<system::Module<System>>::initialize(block_number, parent_hash, extrinsics_root, digest);
-		<AllModules as OnInitialize<System::BlockNumber>>::on_initialize(*block_number);
+		if <system::Module<System>>::take_runtime_upgraded() {
+			let weight = <AllModules as OnRuntimeUpgrade>::on_runtime_upgrade();
+			<system::Module<System>>::register_extra_weight_unchecked(weight);
+		}
+		let weight = <AllModules as OnInitialize<System::BlockNumber>>::on_initialize(*block_number);
+		<system::Module<System>>::register_extra_weight_unchecked(weight);
 	}
 
 	fn initial_checks(block: &Block) {
@@ -201,7 +218,9 @@ where
 
 	/// Actually execute all transitions for `block`.
 	pub fn execute_block(block: Block) {
+		let span = runtime_io::wasm_tracing_enter_span(b"executive", b"initialize_block");
 		Self::initialize_block(block.header());
+		runtime_io::wasm_tracing_exit_span(span);
 
 		// any initial checks
 		Self::initial_checks(&block);
@@ -215,12 +234,38 @@ where
 	}
 
 	/// Execute given extrinsics and take care of post-extrinsics book-keeping.
+	///
+	/// Signatures are verified as one batch after all extrinsics are applied.
 	fn execute_extrinsics_with_book_keeping(extrinsics: Vec<Block::Extrinsic>, block_number: NumberFor<Block>) {
-
-		extrinsics.into_iter().for_each(Self::apply_extrinsic_no_note);
+		runtime_io::start_batch_verify();
+		extrinsics.into_iter().for_each(|uxt| {
+			let span = runtime_io::wasm_tracing_enter_span(b"executive", b"apply_extrinsic");
+			Self::apply_extrinsic_no_note(uxt);
+			runtime_io::wasm_tracing_exit_span(span);
+		});
+		assert!(runtime_io::finish_batch_verify(), "All extrinsics should be properly signed");
 
 		// post-extrinsics book-keeping
 		<system::Module<System>>::note_finished_extrinsics();
+		let span = runtime_io::wasm_tracing_enter_span(b"executive", b"finalize_block");
+		Self::idle_and_finalize_hook(block_number);
+		runtime_io::wasm_tracing_exit_span(span);
+	}
+
+	/// Give the modules the block weight left over through `on_idle`, then run `on_finalize`.
+	fn idle_and_finalize_hook(block_number: System::BlockNumber) {
+		let weight = <system::Module<System>>::all_extrinsics_weight();
+		let max_weight = <System as system::Trait>::MaximumBlockWeight::get();
+		let remaining_weight = max_weight.saturating_sub(weight);
+
+		if remaining_weight > 0 {
+			let used_weight = <AllModules as OnIdle<System::BlockNumber>>::on_idle(
+				block_number,
+				remaining_weight,
+			);
+			<system::Module<System>>::register_extra_weight_unchecked(used_weight);
+		}
+
 		<AllModules as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
 	}
 
@@ -228,7 +273,7 @@ where
 	/// except state-root.
 	pub fn finalize_block() -> System::Header {
 		<system::Module<System>>::note_finished_extrinsics();
-		<AllModules as OnFinalize<System::BlockNumber>>::on_finalize(<system::Module<System>>::block_number());
+		Self::idle_and_finalize_hook(<system::Module<System>>::block_number());
 
 		// set up extrinsics
 		<system::Module<System>>::derive_extrinsics();
@@ -243,12 +288,12 @@ where
 		let encoded_len = encoded.len();
 		match Self::apply_extrinsic_with_len(uxt, encoded_len, Some(encoded)) {
 			Ok(internal::ApplyOutcome::Success) => Ok(ApplyOutcome::Success),
-			Ok(internal::ApplyOutcome::Fail(_)) => Ok(ApplyOutcome::Fail),
+			Ok(internal::ApplyOutcome::Fail(e)) => Ok(ApplyOutcome::Fail(e.into())),
 			Err(internal::ApplyError::CantPay) => Err(ApplyError::CantPay),
 			Err(internal::ApplyError::BadSignature(_)) => Err(ApplyError::BadSignature),
 			Err(internal::ApplyError::Stale) => Err(ApplyError::Stale),
 			Err(internal::ApplyError::Future) => Err(ApplyError::Future),
-			Err(internal::ApplyError::FullBlock) => Err(ApplyError::FullBlock),
+			Err(internal::ApplyError::ExhaustsResources) => Err(ApplyError::ExhaustsResources),
 		}
 	}
 
@@ -261,7 +306,7 @@ where
 			Err(internal::ApplyError::CantPay) => panic!("All extrinsics should have sender able to pay their fees"),
 			Err(internal::ApplyError::BadSignature(_)) => panic!("All extrinsics should be properly signed"),
 			Err(internal::ApplyError::Stale) | Err(internal::ApplyError::Future) => panic!("All extrinsics should have the correct nonce"),
-			Err(internal::ApplyError::FullBlock) => panic!("Extrinsics should not exceed block limit"),
+			Err(internal::ApplyError::ExhaustsResources) => panic!("Extrinsics should not exceed block limit"),
 		}
 	}
 
@@ -283,15 +328,16 @@ where
 
 		// AUDIT: Under no circumstances may this function panic from here onwards.
 
-		// Decode parameters and dispatch
+		// Decode parameters and dispatch. Calls rejected by the base call filter fail without
+		// being dispatched, but only after the fees were paid.
 		let dispatch_info = xt.get_dispatch_info();
-		let r = Applyable::dispatch(xt, dispatch_info, encoded_len)
+		let r = Applyable::dispatch(xt, dispatch_info, encoded_len, System::BaseCallFilter::filter)
 			.map_err(internal::ApplyError::from)?;
 
 		<system::Module<System>>::note_applied_extrinsic(&r, encoded_len as u32);
 
 		r.map(|_| internal::ApplyOutcome::Success).or_else(|e| match e {
-			primitives::BLOCK_FULL => Err(internal::ApplyError::FullBlock),
+			primitives::BLOCK_FULL => Err(internal::ApplyError::ExhaustsResources),
 			e => Ok(internal::ApplyOutcome::Fail(e))
 		})
 	}
@@ -326,6 +372,7 @@ where
 		// Note errors > 0 are from ApplyError
 		const UNKNOWN_ERROR: i8 = -127;
 		const INVALID_INDEX: i8 = -10;
+		const FILTERED_CALL: i8 = -11;
 
 		let encoded_len = uxt.using_encoded(|d| d.len());
 		let xt = match uxt.check(&Default::default()) {
@@ -339,6 +386,10 @@ where
 			Err(_) => return TransactionValidity::Invalid(UNKNOWN_ERROR),
 		};
 
+		if !System::BaseCallFilter::filter(xt.call()) {
+			return TransactionValidity::Invalid(FILTERED_CALL);
+		}
+
 		let dispatch_info = xt.get_dispatch_info();
 		xt.validate::<UnsignedValidator>(dispatch_info, encoded_len)
 	}
@@ -363,6 +414,40 @@ mod tests {
 	use srml_support::traits::{Currency, LockIdentifier, LockableCurrency, WithdrawReasons, WithdrawReason, Get};
 	use system;
 	use hex_literal::hex;
+	use rand::Rng;
+	use srml_executive_fuzz::{Fuzzer, EXECUTE_BLOCK_PANICS, mutated};
+
+	mod custom {
+		use primitives::weights::Weight;
+		use srml_support::StorageValue;
+
+		pub trait Trait: system::Trait {}
+
+		srml_support::decl_module! {
+			pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+				fn on_initialize(_n: T::BlockNumber) -> Weight {
+					175
+				}
+
+				fn on_idle(_n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+					IdleWeight::put(remaining_weight);
+					remaining_weight / 2
+				}
+
+				fn on_runtime_upgrade() -> Weight {
+					Upgrades::mutate(|upgrades| *upgrades += 1);
+					25
+				}
+			}
+		}
+
+		srml_support::decl_storage! {
+			trait Store for Module<T: Trait> as Custom {
+				pub IdleWeight get(idle_weight): Weight;
+				pub Upgrades get(upgrades): u32;
+			}
+		}
+	}
 
 	impl_outer_origin! {
 		pub enum Origin for Runtime {
@@ -385,6 +470,8 @@ mod tests {
 	}
 	impl system::Trait for Runtime {
 		type Origin = Origin;
+		type Call = Call<Runtime>;
+		type BaseCallFilter = TestCallFilter;
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = substrate_primitives::H256;
@@ -411,6 +498,7 @@ mod tests {
 		type OnNewAccount = ();
 		type Event = MetaEvent;
 		type TransactionPayment = ();
+		type TipPayment = ();
 		type DustRemoval = ();
 		type TransferPayment = ();
 		type ExistentialDeposit = ExistentialDeposit;
@@ -420,6 +508,19 @@ mod tests {
 		type TransactionByteFee = TransactionByteFee;
 	}
 
+	impl custom::Trait for Runtime {}
+
+	/// Rejects all transfers to account `666`.
+	pub struct TestCallFilter;
+	impl Filter<Call<Runtime>> for TestCallFilter {
+		fn filter(call: &Call<Runtime>) -> bool {
+			match call {
+				Call::transfer(dest, _) => *dest != 666,
+				_ => true,
+			}
+		}
+	}
+
 	impl ValidateUnsigned for Runtime {
 		type Call = Call<Runtime>;
 
@@ -439,6 +540,13 @@ mod tests {
 	);
 	type TestXt = primitives::testing::TestXt<Call<Runtime>, SignedExtra>;
 	type Executive = super::Executive<Runtime, Block<TestXt>, system::ChainContext<Runtime>, Runtime, ()>;
+	type CustomExecutive = super::Executive<
+		Runtime,
+		Block<TestXt>,
+		system::ChainContext<Runtime>,
+		Runtime,
+		custom::Module<Runtime>,
+	>;
 
 	fn extra(nonce: u64, fee: u64) -> SignedExtra {
 		(
@@ -582,7 +690,7 @@ mod tests {
 					assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight(), encoded_len * (nonce + 1));
 					assert_eq!(<system::Module<Runtime>>::extrinsic_index(), Some(nonce + 1));
 				} else {
-					assert_eq!(res, Err(ApplyError::CantPay));
+					assert_eq!(res, Err(ApplyError::ExhaustsResources));
 				}
 			}
 		});
@@ -622,7 +730,43 @@ mod tests {
 
 		with_externalities(&mut t, || {
 			assert_eq!(Executive::validate_transaction(xt.clone()), valid);
-			assert_eq!(Executive::apply_extrinsic(xt), Ok(ApplyOutcome::Fail));
+			assert_eq!(
+				Executive::apply_extrinsic(xt),
+				Ok(ApplyOutcome::Fail("bad origin: expected to be a root origin".into())),
+			);
+		});
+	}
+
+	#[test]
+	fn filtered_calls_are_not_dispatched() {
+		let xt = primitives::testing::TestXt(sign_extra(1, 0, 0), Call::transfer(666, 69));
+		let weight = xt.get_dispatch_info().weight as u64;
+		let mut t = new_test_ext(1);
+
+		with_externalities(&mut t, || {
+			assert_eq!(Executive::validate_transaction(xt.clone()), TransactionValidity::Invalid(-11));
+
+			Executive::initialize_block(&Header::new(
+				1,
+				H256::default(),
+				H256::default(),
+				[69u8; 32].into(),
+				Digest::default(),
+			));
+			assert_eq!(Executive::apply_extrinsic(xt), Ok(ApplyOutcome::Fail(CALL_FILTERED.into())));
+			// the fees are paid, even though the call isn't dispatched.
+			assert_eq!(<balances::Module<Runtime>>::total_balance(&1), 111 - 10 - weight);
+			assert_eq!(<balances::Module<Runtime>>::total_balance(&666), 0);
+			assert_eq!(<system::Module<Runtime>>::account_nonce(&1), 1);
+		});
+	}
+
+	#[test]
+	fn calls_dispatched_by_modules_are_not_filtered() {
+		with_externalities(&mut new_test_ext(1), || {
+			// modules dispatching calls themselves, e.g. `sudo`, bypass the base call filter.
+			assert_eq!(Call::<Runtime>::transfer(666, 69).dispatch(Origin::signed(1)), Ok(()));
+			assert_eq!(<balances::Module<Runtime>>::total_balance(&666), 69);
 		});
 	}
 
@@ -650,7 +794,10 @@ mod tests {
 				));
 
 				if lock == WithdrawReasons::except(WithdrawReason::TransactionPayment) {
-					assert_eq!(Executive::apply_extrinsic(xt).unwrap(), ApplyOutcome::Fail);
+					assert!(match Executive::apply_extrinsic(xt).unwrap() {
+						ApplyOutcome::Fail(_) => true,
+						_ => false,
+					});
 					// but tx fee has been deducted. the transaction failed on transfer, not on fee.
 					assert_eq!(<balances::Module<Runtime>>::total_balance(&1), 111 - 10 - weight);
 				} else {
@@ -663,4 +810,104 @@ mod tests {
 		execute_with_lock(WithdrawReasons::all());
 		execute_with_lock(WithdrawReasons::except(WithdrawReason::TransactionPayment));
 	}
+
+	#[test]
+	fn hook_weights_are_accounted() {
+		with_externalities(&mut new_test_ext(1), || {
+			CustomExecutive::initialize_block(&Header::new(
+				1,
+				H256::default(),
+				H256::default(),
+				[69u8; 32].into(),
+				Digest::default(),
+			));
+			// `on_initialize` weight is registered right away.
+			assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight(), 175);
+
+			CustomExecutive::idle_and_finalize_hook(1);
+			let remaining_weight = <MaximumBlockWeight as Get<u32>>::get() - 175;
+			assert_eq!(custom::Module::<Runtime>::idle_weight(), remaining_weight);
+			assert_eq!(
+				<system::Module<Runtime>>::all_extrinsics_weight(),
+				175 + remaining_weight / 2,
+			);
+		});
+	}
+
+	#[test]
+	fn runtime_upgrade_hook_runs_once_after_the_code_is_updated() {
+		with_externalities(&mut new_test_ext(1), || {
+			let header = |n| Header::new(n, H256::default(), H256::default(), [69u8; 32].into(), Digest::default());
+
+			CustomExecutive::initialize_block(&header(1));
+			assert_eq!(custom::Module::<Runtime>::upgrades(), 0);
+
+			assert_eq!(
+				<system::Module<Runtime>>::set_code_without_checks(system::RawOrigin::Root.into(), vec![1, 2, 3]),
+				Ok(()),
+			);
+			<system::Module<Runtime>>::finalize();
+
+			// The new runtime migrates before initializing the next block.
+			CustomExecutive::initialize_block(&header(2));
+			assert_eq!(custom::Module::<Runtime>::upgrades(), 1);
+			assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight(), 25 + 175);
+			<system::Module<Runtime>>::finalize();
+
+			CustomExecutive::initialize_block(&header(3));
+			assert_eq!(custom::Module::<Runtime>::upgrades(), 1);
+			assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight(), 175);
+		});
+	}
+
+	#[test]
+	fn mutated_extrinsics_do_not_panic() {
+		let xt = primitives::testing::TestXt(sign_extra(1, 0, 0), Call::transfer(2, 69));
+
+		let executed = Fuzzer::new(42).run(|rng| mutated(rng, &xt), || new_test_ext(10), |xt| {
+			Executive::initialize_block(&Header::new(
+				1,
+				H256::default(),
+				H256::default(),
+				[69u8; 32].into(),
+				Digest::default(),
+			));
+			let _ = Executive::validate_transaction(xt.clone());
+			let _ = Executive::apply_extrinsic(xt);
+		});
+		assert!(executed > 0);
+	}
+
+	#[test]
+	fn mutated_blocks_only_panic_on_block_checks() {
+		let xts = vec![
+			primitives::testing::TestXt(sign_extra(1, 0, 0), Call::transfer(2, 69)),
+			primitives::testing::TestXt(sign_extra(1, 1, 0), Call::transfer(3, 10)),
+		];
+
+		// the extrinsics root is recomputed so that mutated extrinsics reach the dispatch path,
+		// while the state root is left invalid.
+		let executed = Fuzzer::new(42)
+			.expected_panics(EXECUTE_BLOCK_PANICS)
+			.run(
+				|rng| {
+					let extrinsics = xts.iter()
+						.map(|xt| if rng.gen() { mutated(rng, xt) } else { Some(xt.clone()) })
+						.collect::<Option<Vec<_>>>()?;
+					Some(Block {
+						header: Header {
+							parent_hash: [69u8; 32].into(),
+							number: 1,
+							state_root: Default::default(),
+							extrinsics_root: extrinsics_root::<BlakeTwo256, _>(&extrinsics),
+							digest: Digest { logs: vec![], },
+						},
+						extrinsics,
+					})
+				},
+				|| new_test_ext(10),
+				|block| Executive::execute_block(block),
+			);
+		assert!(executed > 0);
+	}
 }
@@ -40,7 +40,7 @@ use primitives::{
 };
 use fg_primitives::{ScheduledChange, ConsensusLog, GRANDPA_ENGINE_ID};
 pub use fg_primitives::{AuthorityId, AuthorityWeight};
-use system::{ensure_signed, DigestOf};
+use system::{ensure_signed, ensure_root, DigestOf};
 
 mod mock;
 mod tests;
@@ -159,6 +159,24 @@ decl_module! {
 			// FIXME: https://github.com/paritytech/substrate/issues/1112
 		}
 
+		/// Pause the current authority set `delay` blocks after the current block. The
+		/// authorities stop voting once that block is finalized.
+		///
+		/// May only be called from root, e.g. when the chain is bridged or has to be halted.
+		fn pause(origin, delay: T::BlockNumber) {
+			ensure_root(origin)?;
+			Self::schedule_pause(delay)?;
+		}
+
+		/// Resume the paused authority set `delay` blocks after the current block. The
+		/// authorities start voting again once that block is imported.
+		///
+		/// May only be called from root.
+		fn resume(origin, delay: T::BlockNumber) {
+			ensure_root(origin)?;
+			Self::schedule_resume(delay)?;
+		}
+
 		fn on_finalize(block_number: T::BlockNumber) {
 			// check for scheduled pending authority set changes
 			if let Some(pending_change) = <PendingChange<T>>::get() {
@@ -230,6 +248,10 @@ impl<T: Trait> Module<T> {
 		Authorities::get()
 	}
 
+	/// Schedule a pause of the current authority set.
+	///
+	/// The pause is signaled in the current block and enacted at the end of the block
+	/// `in_blocks` after it. Returns an error if the set isn't live.
 	pub fn schedule_pause(in_blocks: T::BlockNumber) -> Result {
 		if let StoredState::Live = <State<T>>::get() {
 			let scheduled_at = system::ChainContext::<T>::default().current_height();
@@ -245,6 +267,10 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Schedule the resumption of the paused authority set.
+	///
+	/// The resumption is signaled in the current block and enacted at the end of the block
+	/// `in_blocks` after it. Returns an error if the set isn't paused.
 	pub fn schedule_resume(in_blocks: T::BlockNumber) -> Result {
 		if let StoredState::Paused = <State<T>>::get() {
 			let scheduled_at = system::ChainContext::<T>::default().current_height();
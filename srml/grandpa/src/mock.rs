@@ -48,6 +48,8 @@ parameter_types! {
 }
 impl system::Trait for Test {
 	type Origin = Origin;
+	type Call = ();
+	type BaseCallFilter = ();
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -77,10 +79,10 @@ pub fn to_authorities(vec: Vec<(u64, u64)>) -> Vec<(AuthorityId, u64)> {
 }
 
 pub fn new_test_ext(authorities: Vec<(u64, u64)>) -> runtime_io::TestExternalities<Blake2Hasher> {
-	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
-	t.extend(GenesisConfig {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	GenesisConfig {
 		authorities: to_authorities(authorities),
-	}.build_storage().unwrap().0);
+	}.assimilate_storage(&mut t.0, &mut t.1).unwrap();
 	t.into()
 }
 
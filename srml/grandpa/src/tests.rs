@@ -27,6 +27,10 @@ use codec::{Decode, Encode};
 use fg_primitives::ScheduledChange;
 use super::*;
 
+fn run_to_block(n: u64) {
+	srml_test_helpers::run_to_block::<Test, Grandpa>(n);
+}
+
 #[test]
 fn authorities_change_logged() {
 	with_externalities(&mut new_test_ext(vec![(1, 1), (2, 1), (3, 1)]), || {
@@ -91,27 +95,18 @@ fn authorities_change_logged_after_delay() {
 #[test]
 fn cannot_schedule_change_when_one_pending() {
 	with_externalities(&mut new_test_ext(vec![(1, 1), (2, 1), (3, 1)]), || {
-		System::initialize(&1, &Default::default(), &Default::default(), &Default::default());
+		run_to_block(1);
 		Grandpa::schedule_change(to_authorities(vec![(4, 1), (5, 1), (6, 1)]), 1, None).unwrap();
 		assert!(<PendingChange<Test>>::exists());
 		assert!(Grandpa::schedule_change(to_authorities(vec![(5, 1)]), 1, None).is_err());
 
-		Grandpa::on_finalize(1);
-		let header = System::finalize();
-
-		System::initialize(&2, &header.hash(), &Default::default(), &Default::default());
+		run_to_block(2);
 		assert!(<PendingChange<Test>>::exists());
 		assert!(Grandpa::schedule_change(to_authorities(vec![(5, 1)]), 1, None).is_err());
 
-		Grandpa::on_finalize(2);
-		let header = System::finalize();
-
-		System::initialize(&3, &header.hash(), &Default::default(), &Default::default());
+		run_to_block(3);
 		assert!(!<PendingChange<Test>>::exists());
 		assert!(Grandpa::schedule_change(to_authorities(vec![(5, 1)]), 1, None).is_ok());
-
-		Grandpa::on_finalize(3);
-		let _header = System::finalize();
 	});
 }
 
@@ -241,7 +236,7 @@ fn schedule_pause_only_when_live() {
 #[test]
 fn schedule_resume_only_when_paused() {
 	with_externalities(&mut new_test_ext(vec![(1, 1), (2, 1), (3, 1)]), || {
-		System::initialize(&1, &Default::default(), &Default::default(), &Default::default());
+		run_to_block(1);
 
 		// the set is currently live, resuming it is an error
 		assert!(Grandpa::schedule_resume(1).is_err());
@@ -253,8 +248,7 @@ fn schedule_resume_only_when_paused() {
 
 		// we schedule a pause to be applied instantly
 		Grandpa::schedule_pause(0).unwrap();
-		Grandpa::on_finalize(1);
-		let _ = System::finalize();
+		run_to_block(2);
 
 		assert_eq!(
 			Grandpa::state(),
@@ -262,23 +256,42 @@ fn schedule_resume_only_when_paused() {
 		);
 
 		// we schedule the set to go back live in 2 blocks
-		System::initialize(&2, &Default::default(), &Default::default(), &Default::default());
 		Grandpa::schedule_resume(2).unwrap();
-		Grandpa::on_finalize(2);
-		let _ = System::finalize();
+		run_to_block(5);
 
-		System::initialize(&3, &Default::default(), &Default::default(), &Default::default());
-		Grandpa::on_finalize(3);
-		let _ = System::finalize();
-
-		System::initialize(&4, &Default::default(), &Default::default(), &Default::default());
-		Grandpa::on_finalize(4);
-		let _ = System::finalize();
-
-		// it should be live at block 4
+		// it should be live after finalizing block 4
 		assert_eq!(
 			Grandpa::state(),
 			StoredState::Live,
 		);
 	});
 }
+
+#[test]
+fn pause_and_resume_are_root_only_and_logged() {
+	with_externalities(&mut new_test_ext(vec![(1, 1), (2, 1), (3, 1)]), || {
+		let finalize_block = |number| {
+			Grandpa::on_finalize(number);
+			System::finalize().digest
+		};
+
+		System::initialize(&1, &Default::default(), &Default::default(), &Default::default());
+		assert!(Grandpa::pause(Origin::signed(1), 1).is_err());
+		assert!(Grandpa::resume(Origin::ROOT, 1).is_err());
+		assert_eq!(Grandpa::pause(Origin::ROOT, 1), Ok(()));
+		assert_eq!(finalize_block(1), Digest {
+			logs: vec![grandpa_log(ConsensusLog::Pause(1))],
+		});
+
+		System::initialize(&2, &Default::default(), &Default::default(), &Default::default());
+		assert_eq!(finalize_block(2), Digest::default());
+		assert_eq!(Grandpa::state(), StoredState::Paused);
+
+		System::initialize(&3, &Default::default(), &Default::default(), &Default::default());
+		assert!(Grandpa::resume(Origin::signed(1), 2).is_err());
+		assert_eq!(Grandpa::resume(Origin::ROOT, 2), Ok(()));
+		assert_eq!(finalize_block(3), Digest {
+			logs: vec![grandpa_log(ConsensusLog::Resume(2))],
+		});
+	});
+}
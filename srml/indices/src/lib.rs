@@ -14,16 +14,28 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-//! An index is a short form of an address. This module handles allocation
-//! of indices for a newly created accounts.
+//! An index is a short form of an address. This module handles the allocation of indices to
+//! accounts.
+//!
+//! Any account can claim a free index by reserving a `Deposit`. The owner of an index can
+//! transfer it, along with its deposit, to another account, free it to get the deposit back, or
+//! freeze it, which makes it permanently theirs in exchange for the deposit.
+//!
+//! An index which isn't frozen and whose owner is a dead account can be reclaimed by anyone.
+//! Root may also reassign any index with `force_transfer`.
+//!
+//! Indices allocated by the previous version of the module, which assigned them to new accounts
+//! in enumeration sets, are moved to `Accounts` without deposit on runtime upgrade.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use rstd::{prelude::*, result, marker::PhantomData, convert::TryInto};
+use rstd::{prelude::*, result};
 use parity_codec::{Encode, Decode, Codec, Input, Output};
-use srml_support::{StorageValue, StorageMap, Parameter, decl_module, decl_event, decl_storage};
-use primitives::traits::{One, SimpleArithmetic, StaticLookup, Member};
-use system::{IsDeadAccount, OnNewAccount};
+use srml_support::{StorageMap, Parameter, decl_module, decl_event, decl_storage, ensure};
+use srml_support::traits::{Currency, ReservableCurrency, Get};
+use primitives::traits::{SimpleArithmetic, StaticLookup, Member, Zero, One};
+use primitives::weights::Weight;
+use system::{IsDeadAccount, ensure_signed, ensure_root};
 
 use self::address::Address as RawAddress;
 
@@ -32,28 +44,12 @@ mod mock;
 pub mod address;
 mod tests;
 
-/// Number of account IDs stored per enum set.
+/// Number of account IDs stored per enum set by the previous version of the module.
 const ENUM_SET_SIZE: u32 = 64;
 
 pub type Address<T> = RawAddress<<T as system::Trait>::AccountId, <T as Trait>::AccountIndex>;
 
-/// Turn an Id into an Index, or None for the purpose of getting
-/// a hint at a possibly desired index.
-pub trait ResolveHint<AccountId, AccountIndex> {
-	/// Turn an Id into an Index, or None for the purpose of getting
-	/// a hint at a possibly desired index.
-	fn resolve_hint(who: &AccountId) -> Option<AccountIndex>;
-}
-
-/// Simple encode-based resolve hint implementation.
-pub struct SimpleResolveHint<AccountId, AccountIndex>(PhantomData<(AccountId, AccountIndex)>);
-impl<AccountId: Encode, AccountIndex: From<u32>>
-	ResolveHint<AccountId, AccountIndex> for SimpleResolveHint<AccountId, AccountIndex>
-{
-	fn resolve_hint(who: &AccountId) -> Option<AccountIndex> {
-		Some(AccountIndex::from(who.using_encoded(|e| e[0] as u32 + e[1] as u32 * 256)))
-	}
-}
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
 /// The module's config trait.
 pub trait Trait: system::Trait {
@@ -61,11 +57,14 @@ pub trait Trait: system::Trait {
 	/// can hold.
 	type AccountIndex: Parameter + Member + Codec + Default + SimpleArithmetic + Copy;
 
-	/// Whether an account is dead or not.
-	type IsDeadAccount: IsDeadAccount<Self::AccountId>;
+	/// The currency in which index deposits are held.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The deposit reserved for claiming an index.
+	type Deposit: Get<BalanceOf<Self>>;
 
-	/// How to turn an id into an index.
-	type ResolveHint: ResolveHint<Self::AccountId, Self::AccountIndex>;
+	/// Whether an account is dead or not. Unfrozen indices of dead accounts can be reclaimed.
+	type IsDeadAccount: IsDeadAccount<Self::AccountId>;
 
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
@@ -74,6 +73,94 @@ pub trait Trait: system::Trait {
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event<T>() = default;
+
+		fn on_runtime_upgrade() -> Weight {
+			Self::migrate_enum_sets()
+		}
+
+		/// Claim `index`, reserving `Deposit` from the sender.
+		///
+		/// The index must either be unclaimed, or be held by a dead account and not be frozen.
+		///
+		/// Emits `IndexAssigned` if successful.
+		fn claim(origin, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+
+			if let Some((owner, deposit, frozen)) = Self::accounts(index) {
+				ensure!(!frozen && T::IsDeadAccount::is_dead_account(&owner), "index already taken");
+				// defensive only: an account holding a deposit can't be dead.
+				T::Currency::unreserve(&owner, deposit);
+			}
+
+			let deposit = T::Deposit::get();
+			T::Currency::reserve(&who, deposit)?;
+			<Accounts<T>>::insert(index, (who.clone(), deposit, false));
+
+			Self::deposit_event(RawEvent::IndexAssigned(who, index));
+		}
+
+		/// Transfer `index`, which the sender must own, to `new`, moving the deposit along.
+		///
+		/// `new` must be an existing account.
+		///
+		/// Emits `IndexAssigned` if successful.
+		fn transfer(origin, new: T::AccountId, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+			ensure!(who != new, "index already owned by the recipient");
+			let deposit = Self::owned_deposit(&who, index)?;
+
+			let moved = deposit - T::Currency::repatriate_reserved(&who, &new, deposit)?;
+			// `new` just received `moved` as free balance, so this only fails if it is locked.
+			let deposit = if T::Currency::reserve(&new, moved).is_ok() { moved } else { Zero::zero() };
+			<Accounts<T>>::insert(index, (new.clone(), deposit, false));
+
+			Self::deposit_event(RawEvent::IndexAssigned(new, index));
+		}
+
+		/// Free `index`, which the sender must own, returning the deposit.
+		///
+		/// Emits `IndexFreed` if successful.
+		fn free(origin, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+			let deposit = Self::owned_deposit(&who, index)?;
+
+			T::Currency::unreserve(&who, deposit);
+			<Accounts<T>>::remove(index);
+
+			Self::deposit_event(RawEvent::IndexFreed(index));
+		}
+
+		/// Permanently freeze `index`, which the sender must own. The deposit is burned.
+		///
+		/// A frozen index can't be transferred, freed or reclaimed anymore.
+		///
+		/// Emits `IndexFrozen` if successful.
+		fn freeze(origin, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+			let deposit = Self::owned_deposit(&who, index)?;
+
+			let _ = T::Currency::slash_reserved(&who, deposit);
+			<Accounts<T>>::insert(index, (who.clone(), Zero::zero(), true));
+
+			Self::deposit_event(RawEvent::IndexFrozen(index, who));
+		}
+
+		/// Assign `index` to `new`, regardless of who holds it. Any deposit held for it is
+		/// returned, and no deposit is taken from `new`.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// Emits `IndexAssigned` if successful.
+		fn force_transfer(origin, new: T::AccountId, index: T::AccountIndex) {
+			ensure_root(origin)?;
+
+			if let Some((owner, deposit, _)) = Self::accounts(index) {
+				T::Currency::unreserve(&owner, deposit);
+			}
+			<Accounts<T>>::insert(index, (new.clone(), Zero::zero(), false));
+
+			Self::deposit_event(RawEvent::IndexAssigned(new, index));
+		}
 	}
 }
 
@@ -82,35 +169,35 @@ decl_event!(
 		<T as system::Trait>::AccountId,
 		<T as Trait>::AccountIndex
 	{
-		/// A new account index was assigned.
-		///
-		/// This event is not triggered when an existing index is reassigned
-		/// to another `AccountId`.
-		NewAccountIndex(AccountId, AccountIndex),
+		/// An account index was assigned to an account.
+		IndexAssigned(AccountId, AccountIndex),
+		/// An account index was freed up.
+		IndexFreed(AccountIndex),
+		/// An account index was frozen to the given account.
+		IndexFrozen(AccountIndex, AccountId),
 	}
 );
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Indices {
-		/// The next free enumeration set.
-		pub NextEnumSet get(next_enum_set) build(|config: &GenesisConfig<T>| {
-			(config.ids.len() as u32 / ENUM_SET_SIZE).into()
-		}): T::AccountIndex;
-
-		/// The enumeration sets.
-		pub EnumSet get(enum_set) build(|config: &GenesisConfig<T>| {
-			(0..((config.ids.len() as u32) + ENUM_SET_SIZE - 1) / ENUM_SET_SIZE)
-				.map(|i| (
-					i.into(),
-					config.ids[
-						(i * ENUM_SET_SIZE) as usize..
-						config.ids.len().min(((i + 1) * ENUM_SET_SIZE) as usize)
-					].to_owned(),
-				))
+		/// The owner of each claimed index, the deposit held for it, and whether it is frozen.
+		pub Accounts get(accounts) build(|config: &GenesisConfig<T>| {
+			config.ids.iter()
+				.enumerate()
+				.map(|(i, who)| ((i as u32).into(), (who.clone(), Zero::zero(), false)))
 				.collect::<Vec<_>>()
-		}): map T::AccountIndex => Vec<T::AccountId>;
+		}): map T::AccountIndex => Option<(T::AccountId, BalanceOf<T>, bool)>;
+
+		/// The next free enumeration set of the previous version of the module. Only read by the
+		/// migration to `Accounts`.
+		NextEnumSet: T::AccountIndex;
+
+		/// The enumeration sets of the previous version of the module. Only read by the migration
+		/// to `Accounts`.
+		EnumSet: map T::AccountIndex => Vec<T::AccountId>;
 	}
 	add_extra_genesis {
+		/// Accounts which are given the indices `0..ids.len()`, without deposit.
 		config(ids): Vec<T::AccountId>;
 	}
 }
@@ -120,22 +207,7 @@ impl<T: Trait> Module<T> {
 
 	/// Lookup an T::AccountIndex to get an Id, if there's one there.
 	pub fn lookup_index(index: T::AccountIndex) -> Option<T::AccountId> {
-		let enum_set_size = Self::enum_set_size();
-		let set = Self::enum_set(index / enum_set_size);
-		let i: usize = (index % enum_set_size).try_into().ok()?;
-		set.get(i).cloned()
-	}
-
-	/// `true` if the account `index` is ready for reclaim.
-	pub fn can_reclaim(try_index: T::AccountIndex) -> bool {
-		let enum_set_size = Self::enum_set_size();
-		let try_set = Self::enum_set(try_index / enum_set_size);
-		let maybe_usize: Result<usize, _> = (try_index % enum_set_size).try_into();
-		if let Ok(i) = maybe_usize {
-			i < try_set.len() && T::IsDeadAccount::is_dead_account(&try_set[i])
-		} else {
-			false
-		}
+		Self::accounts(index).map(|(who, _, _)| who)
 	}
 
 	/// Lookup an address to get an Id, if there's one there.
@@ -148,76 +220,45 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
-	// PUBLIC MUTABLES (DANGEROUS)
-
-	fn enum_set_size() -> T::AccountIndex {
-		ENUM_SET_SIZE.into()
-	}
-}
+	// PRIVATE MUTABLES
 
-impl<T: Trait> OnNewAccount<T::AccountId> for Module<T> {
-	// Implementation of the config type managing the creation of new accounts.
-	// See Balances module for a concrete example.
-	//
-	// # <weight>
-	// - Independent of the arguments.
-	// - Given the correct value of `Self::next_enum_set`, it always has a limited
-	//   number of reads and writes and no complex computation.
-	//
-	// As for storage, calling this function with _non-dead-indices_ will linearly grow the length of
-	// of `Self::enum_set`. Appropriate economic incentives should exist to make callers of this
-	// function provide a `who` argument that reclaims a dead account.
-	//
-	// At the time of this writing, only the Balances module calls this function upon creation
-	// of new accounts.
-	// # </weight>
-	fn on_new_account(who: &T::AccountId) {
-		let enum_set_size = Self::enum_set_size();
-		let next_set_index = Self::next_enum_set();
-
-		if let Some(try_index) = T::ResolveHint::resolve_hint(who) {
-			// then check to see if this account id identifies a dead account index.
-			let set_index = try_index / enum_set_size;
-			let mut try_set = Self::enum_set(set_index);
-			if let Ok(item_index) = (try_index % enum_set_size).try_into() {
-				if item_index < try_set.len() {
-					if T::IsDeadAccount::is_dead_account(&try_set[item_index]) {
-						// yup - this index refers to a dead account. can be reused.
-						try_set[item_index] = who.clone();
-						<EnumSet<T>>::insert(set_index, try_set);
-
-						return
-					}
-				}
-			}
+	/// Move the indices allocated in the enumeration sets of the previous version of the module
+	/// to `Accounts`, without deposit, and remove the sets. Does nothing once they are removed.
+	///
+	/// Returns the weight consumed, which is the number of storage items touched.
+	fn migrate_enum_sets() -> Weight {
+		// `NextEnumSet` was only written once the first set was full.
+		if !<NextEnumSet<T>>::exists() && !<EnumSet<T>>::exists(T::AccountIndex::zero()) {
+			return 1;
 		}
 
-		// insert normally as a back up
-		let mut set_index = next_set_index;
-		// defensive only: this loop should never iterate since we keep NextEnumSet up to date
-		// later.
-		let mut set = loop {
-			let set = Self::enum_set(set_index);
-			if set.len() < ENUM_SET_SIZE as usize {
-				break set;
+		let enum_set_size = T::AccountIndex::from(ENUM_SET_SIZE);
+		let next_enum_set = <NextEnumSet<T>>::take();
+		let mut weight: Weight = 2;
+		let mut set_index = Zero::zero();
+		while set_index <= next_enum_set {
+			let set = <EnumSet<T>>::take(set_index);
+			weight = weight.saturating_add(1 + set.len() as Weight);
+			for (i, who) in set.into_iter().enumerate() {
+				let index = set_index * enum_set_size + T::AccountIndex::from(i as u32);
+				<Accounts<T>>::insert(index, (who, BalanceOf::<T>::zero(), false));
 			}
 			set_index += One::one();
-		};
-
-		let index = set_index * enum_set_size + T::AccountIndex::from(set.len() as u32);
-
-		// update set.
-		set.push(who.clone());
-
-		// keep NextEnumSet up to date
-		if set.len() == ENUM_SET_SIZE as usize {
-			<NextEnumSet<T>>::put(set_index + One::one());
 		}
+		weight
+	}
 
-		// write set.
-		<EnumSet<T>>::insert(set_index, set);
-
-		Self::deposit_event(RawEvent::NewAccountIndex(who.clone(), index));
+	// PRIVATE IMMUTABLES
+
+	/// The deposit held for `index`, if `who` owns it and it isn't frozen.
+	fn owned_deposit(
+		who: &T::AccountId,
+		index: T::AccountIndex,
+	) -> result::Result<BalanceOf<T>, &'static str> {
+		let (owner, deposit, frozen) = Self::accounts(index).ok_or("index not claimed")?;
+		ensure!(&owner == who, "not the owner of the index");
+		ensure!(!frozen, "index is frozen");
+		Ok(deposit)
 	}
 }
 
@@ -18,49 +18,16 @@
 
 #![cfg(test)]
 
-use std::collections::HashSet;
-use ref_thread_local::{ref_thread_local, RefThreadLocal};
 use primitives::testing::Header;
 use substrate_primitives::{H256, Blake2Hasher};
 use srml_support::{impl_outer_origin, parameter_types};
 use {runtime_io, system};
-use crate::{GenesisConfig, Module, Trait, IsDeadAccount, OnNewAccount, ResolveHint};
+use crate::{GenesisConfig, Module, Trait};
 
 impl_outer_origin!{
 	pub enum Origin for Runtime {}
 }
 
-ref_thread_local! {
-	static managed ALIVE: HashSet<u64> = HashSet::new();
-}
-
-pub fn make_account(who: u64) {
-	ALIVE.borrow_mut().insert(who);
-	Indices::on_new_account(&who);
-}
-
-pub fn kill_account(who: u64) {
-	ALIVE.borrow_mut().remove(&who);
-}
-
-pub struct TestIsDeadAccount {}
-impl IsDeadAccount<u64> for TestIsDeadAccount {
-	fn is_dead_account(who: &u64) -> bool {
-		!ALIVE.borrow_mut().contains(who)
-	}
-}
-
-pub struct TestResolveHint;
-impl ResolveHint<u64, u64> for TestResolveHint {
-	fn resolve_hint(who: &u64) -> Option<u64> {
-		if *who < 256 {
-			None
-		} else {
-			Some(*who - 256)
-		}
-	}
-}
-
 // Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Runtime;
@@ -71,6 +38,8 @@ parameter_types! {
 }
 impl system::Trait for Runtime {
 	type Origin = Origin;
+	type Call = ();
+	type BaseCallFilter = ();
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -84,25 +53,50 @@ impl system::Trait for Runtime {
 	type MaximumBlockWeight = MaximumBlockWeight;
 	type MaximumBlockLength = MaximumBlockLength;
 }
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 0;
+	pub const TransferFee: u64 = 0;
+	pub const CreationFee: u64 = 0;
+	pub const TransactionBaseFee: u64 = 0;
+	pub const TransactionByteFee: u64 = 0;
+}
+impl balances::Trait for Runtime {
+	type Balance = u64;
+	type OnNewAccount = ();
+	type OnFreeBalanceZero = ();
+	type Event = ();
+	type TransactionPayment = ();
+	type TipPayment = ();
+	type TransferPayment = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type TransferFee = TransferFee;
+	type CreationFee = CreationFee;
+	type TransactionBaseFee = TransactionBaseFee;
+	type TransactionByteFee = TransactionByteFee;
+}
+parameter_types! {
+	pub const Deposit: u64 = 1;
+}
 impl Trait for Runtime {
 	type AccountIndex = u64;
-	type IsDeadAccount = TestIsDeadAccount;
-	type ResolveHint = TestResolveHint;
+	type Currency = Balances;
+	type Deposit = Deposit;
+	type IsDeadAccount = Balances;
 	type Event = ();
 }
 
 pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
-	{
-		let mut h = ALIVE.borrow_mut();
-		h.clear();
-		for i in 1..5 { h.insert(i); }
-	}
-
 	let mut t = system::GenesisConfig::default().build_storage::<Runtime>().unwrap().0;
+	t.extend(balances::GenesisConfig::<Runtime> {
+		balances: vec![(1, 10), (2, 20), (3, 30), (4, 40), (5, 50), (6, 60)],
+		vesting: vec![],
+	}.build_storage().unwrap().0);
 	t.extend(GenesisConfig::<Runtime> {
 		ids: vec![1, 2, 3, 4]
 	}.build_storage().unwrap().0);
 	t.into()
 }
 
+pub type Balances = balances::Module<Runtime>;
 pub type Indices = Module<Runtime>;
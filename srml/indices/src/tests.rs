@@ -19,8 +19,10 @@
 #![cfg(test)]
 
 use super::*;
-use crate::mock::{Indices, new_test_ext, make_account, kill_account, TestIsDeadAccount};
+use crate::mock::{Indices, Balances, Origin, Runtime, new_test_ext};
+use primitives::traits::OnRuntimeUpgrade;
 use runtime_io::with_externalities;
+use srml_support::{assert_ok, assert_noop};
 
 #[test]
 fn indexing_lookup_should_work() {
@@ -37,44 +39,140 @@ fn indexing_lookup_should_work() {
 }
 
 #[test]
-fn default_indexing_on_new_accounts_should_work() {
+fn address_lookup_should_work() {
 	with_externalities(
 		&mut new_test_ext(),
 		|| {
-			assert_eq!(Indices::lookup_index(4), None);
-			make_account(5);
+			assert_eq!(Indices::lookup(RawAddress::Index(1)), Ok(2));
+			assert_eq!(Indices::lookup(RawAddress::Id(42)), Ok(42));
+			assert_eq!(Indices::lookup(RawAddress::Index(4)), Err("invalid account index"));
+			assert_eq!(Indices::unlookup(2), RawAddress::Id(2));
+		},
+	);
+}
+
+#[test]
+fn claiming_should_work() {
+	with_externalities(
+		&mut new_test_ext(),
+		|| {
+			assert_noop!(Indices::claim(Origin::signed(5), 0), "index already taken");
+			assert_ok!(Indices::claim(Origin::signed(5), 4));
 			assert_eq!(Indices::lookup_index(4), Some(5));
+			assert_eq!(Balances::reserved_balance(&5), 1);
+			assert_noop!(Indices::claim(Origin::signed(6), 4), "index already taken");
 		},
 	);
 }
 
 #[test]
-fn reclaim_indexing_on_new_accounts_should_work() {
+fn freeing_should_work() {
 	with_externalities(
 		&mut new_test_ext(),
 		|| {
-			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert_ok!(Indices::claim(Origin::signed(5), 4));
+			assert_noop!(Indices::free(Origin::signed(6), 4), "not the owner of the index");
+			assert_noop!(Indices::free(Origin::signed(5), 5), "index not claimed");
+			assert_ok!(Indices::free(Origin::signed(5), 4));
 			assert_eq!(Indices::lookup_index(4), None);
+			assert_eq!(Balances::reserved_balance(&5), 0);
+			assert_ok!(Indices::claim(Origin::signed(6), 4));
+		},
+	);
+}
+
+#[test]
+fn transferring_should_work() {
+	with_externalities(
+		&mut new_test_ext(),
+		|| {
+			assert_ok!(Indices::claim(Origin::signed(5), 4));
+			assert_noop!(Indices::transfer(Origin::signed(6), 6, 4), "index already owned by the recipient");
+			assert_noop!(Indices::transfer(Origin::signed(6), 5, 4), "not the owner of the index");
+			assert_ok!(Indices::transfer(Origin::signed(5), 6, 4));
+			assert_eq!(Indices::lookup_index(4), Some(6));
+			assert_eq!(Balances::reserved_balance(&5), 0);
+			assert_eq!(Balances::free_balance(&5), 49);
+			assert_eq!(Balances::reserved_balance(&6), 1);
+			assert_eq!(Balances::free_balance(&6), 60);
+		},
+	);
+}
+
+#[test]
+fn freezing_should_work() {
+	with_externalities(
+		&mut new_test_ext(),
+		|| {
+			assert_ok!(Indices::claim(Origin::signed(5), 4));
+			assert_ok!(Indices::freeze(Origin::signed(5), 4));
+			assert_eq!(Balances::reserved_balance(&5), 0);
+			assert_eq!(Balances::free_balance(&5), 49);
+			assert_eq!(Indices::accounts(4), Some((5, 0, true)));
+
+			assert_noop!(Indices::freeze(Origin::signed(5), 4), "index is frozen");
+			assert_noop!(Indices::free(Origin::signed(5), 4), "index is frozen");
+			assert_noop!(Indices::transfer(Origin::signed(5), 6, 4), "index is frozen");
+		},
+	);
+}
+
+#[test]
+fn dead_accounts_indices_can_be_reclaimed() {
+	with_externalities(
+		&mut new_test_ext(),
+		|| {
+			assert_noop!(Indices::claim(Origin::signed(5), 1), "index already taken");
+			assert_ok!(Indices::freeze(Origin::signed(3), 2));
 
-			kill_account(2);					// index 1 no longer locked to id 2
+			let _ = Balances::make_free_balance_be(&2, 0);
+			let _ = Balances::make_free_balance_be(&3, 0);
 
-			make_account(1 + 256);				// id 257 takes index 1.
-			assert_eq!(Indices::lookup_index(1), Some(257));
+			assert_ok!(Indices::claim(Origin::signed(5), 1));
+			assert_eq!(Indices::lookup_index(1), Some(5));
+			assert_noop!(Indices::claim(Origin::signed(5), 2), "index already taken");
 		},
 	);
 }
 
 #[test]
-fn alive_account_should_prevent_reclaim() {
+fn force_transfer_should_work() {
 	with_externalities(
 		&mut new_test_ext(),
 		|| {
-			assert!(!TestIsDeadAccount::is_dead_account(&2));
-			assert_eq!(Indices::lookup_index(1), Some(2));
-			assert_eq!(Indices::lookup_index(4), None);
+			assert_ok!(Indices::claim(Origin::signed(5), 4));
+			assert_noop!(Indices::force_transfer(Origin::signed(5), 6, 4), "bad origin: expected to be a root origin");
+			assert_ok!(Indices::force_transfer(Origin::ROOT, 6, 4));
+			assert_eq!(Indices::lookup_index(4), Some(6));
+			assert_eq!(Balances::reserved_balance(&5), 0);
+			assert_eq!(Balances::reserved_balance(&6), 0);
+		},
+	);
+}
+
+#[test]
+fn enum_sets_are_migrated_on_runtime_upgrade() {
+	with_externalities(
+		&mut new_test_ext(),
+		|| {
+			<EnumSet<Runtime>>::insert(0, (100..164).collect::<Vec<u64>>());
+			<EnumSet<Runtime>>::insert(1, vec![200, 201]);
+			<NextEnumSet<Runtime>>::put(1);
+
+			assert_eq!(<Indices as OnRuntimeUpgrade>::on_runtime_upgrade(), 70);
+			assert_eq!(Indices::lookup_index(0), Some(100));
+			assert_eq!(Indices::lookup_index(63), Some(163));
+			assert_eq!(Indices::lookup_index(64), Some(200));
+			assert_eq!(Indices::lookup_index(65), Some(201));
+			assert_eq!(Indices::lookup_index(66), None);
+			assert_eq!(Indices::accounts(64), Some((200, 0, false)));
+			assert!(!<EnumSet<Runtime>>::exists(0));
+			assert!(!<EnumSet<Runtime>>::exists(1));
+			assert!(!<NextEnumSet<Runtime>>::exists());
 
-			make_account(1 + 256);				// id 257 takes index 1.
-			assert_eq!(Indices::lookup_index(4), Some(257));
+			// nothing is left to migrate.
+			assert_eq!(<Indices as OnRuntimeUpgrade>::on_runtime_upgrade(), 1);
+			assert_eq!(Indices::lookup_index(64), Some(200));
 		},
 	);
 }
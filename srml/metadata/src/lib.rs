@@ -256,6 +256,7 @@ pub enum StorageHasher {
 	Twox128,
 	Twox256,
 	Twox64Concat,
+	Blake2_128Concat,
 }
 
 /// A storage entry type.
@@ -0,0 +1,345 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Nicks Module
+//!
+//! The Nicks module lets accounts set a nickname for themselves, in exchange for a deposit.
+//!
+//! - [`nicks::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! Setting a name reserves `ReservationFee` from the account, which it gets back when it clears
+//! the name. Names are limited to `MinLength..=MaxLength` bytes and are not unique: this is a
+//! toy for demonstration purposes rather than an identity system.
+//!
+//! The `ForceOrigin` may name any account without a deposit, or remove the name of any account,
+//! in which case the deposit is slashed and handed to `Slashed`.
+//!
+//! Beyond the [example module](../srml_example/index.html), this module shows how a module
+//! reserves, returns and slashes funds through `ReservableCurrency`, and how privileged calls
+//! are gated by a configurable origin.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `set_name` - Set the name of the sender, reserving the deposit if it had none.
+//! - `clear_name` - Clear the name of the sender and return the deposit.
+//! - `kill_name` - Clear the name of an account and slash its deposit. Callable by
+//!   `ForceOrigin`.
+//! - `force_name` - Set the name of an account without taking a deposit. Callable by
+//!   `ForceOrigin`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use primitives::traits::{StaticLookup, EnsureOrigin, Zero};
+use srml_support::{StorageMap, decl_module, decl_storage, decl_event, ensure};
+use srml_support::traits::{Currency, ReservableCurrency, OnUnbalanced, Get};
+use system::ensure_signed;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency in which the deposits are held.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The deposit reserved for setting a name.
+	type ReservationFee: Get<BalanceOf<Self>>;
+
+	/// Handler for the deposits slashed when a name is killed.
+	type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// The origin which may set or remove any name.
+	type ForceOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The minimum length of a name, in bytes.
+	type MinLength: Get<u32>;
+
+	/// The maximum length of a name, in bytes.
+	type MaxLength: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Nicks {
+		/// The name of an account and the deposit held for it.
+		NameOf get(name_of): map T::AccountId => Option<(Vec<u8>, BalanceOf<T>)>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		Balance = BalanceOf<T>
+	{
+		/// A name was set.
+		NameSet(AccountId),
+		/// A name was forced.
+		NameForced(AccountId),
+		/// A name was changed.
+		NameChanged(AccountId),
+		/// A name was cleared, and the given balance returned.
+		NameCleared(AccountId, Balance),
+		/// A name was removed, and the given balance slashed.
+		NameKilled(AccountId, Balance),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The deposit reserved for setting a name.
+		const ReservationFee: BalanceOf<T> = T::ReservationFee::get();
+
+		/// The minimum length of a name, in bytes.
+		const MinLength: u32 = T::MinLength::get();
+
+		/// The maximum length of a name, in bytes.
+		const MaxLength: u32 = T::MaxLength::get();
+
+		fn deposit_event<T>() = default;
+
+		/// Set the name of the sender.
+		///
+		/// If the sender has no name yet, `ReservationFee` is reserved from it. Otherwise the
+		/// name is changed and the deposit stays as it is.
+		///
+		/// Emits `NameSet` or `NameChanged`.
+		fn set_name(origin, name: Vec<u8>) {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_valid_length(&name)?;
+
+			let deposit = if let Some((_, deposit)) = Self::name_of(&sender) {
+				Self::deposit_event(RawEvent::NameChanged(sender.clone()));
+				deposit
+			} else {
+				let deposit = T::ReservationFee::get();
+				T::Currency::reserve(&sender, deposit)?;
+				Self::deposit_event(RawEvent::NameSet(sender.clone()));
+				deposit
+			};
+
+			<NameOf<T>>::insert(&sender, (name, deposit));
+		}
+
+		/// Clear the name of the sender, returning its deposit.
+		///
+		/// Emits `NameCleared`.
+		fn clear_name(origin) {
+			let sender = ensure_signed(origin)?;
+
+			let deposit = <NameOf<T>>::take(&sender).ok_or("Not named")?.1;
+			let _ = T::Currency::unreserve(&sender, deposit);
+
+			Self::deposit_event(RawEvent::NameCleared(sender, deposit));
+		}
+
+		/// Remove the name of `target` and slash its deposit.
+		///
+		/// The dispatch origin for this call must be `ForceOrigin`.
+		///
+		/// Emits `NameKilled`.
+		fn kill_name(origin, target: <T::Lookup as StaticLookup>::Source) {
+			T::ForceOrigin::ensure_origin(origin)?;
+			let target = T::Lookup::lookup(target)?;
+
+			let deposit = <NameOf<T>>::take(&target).ok_or("Not named")?.1;
+			T::Slashed::on_unbalanced(T::Currency::slash_reserved(&target, deposit).0);
+
+			Self::deposit_event(RawEvent::NameKilled(target, deposit));
+		}
+
+		/// Set the name of `target`, keeping any deposit it already holds but taking none.
+		///
+		/// The dispatch origin for this call must be `ForceOrigin`.
+		///
+		/// Emits `NameForced`.
+		fn force_name(origin, target: <T::Lookup as StaticLookup>::Source, name: Vec<u8>) {
+			T::ForceOrigin::ensure_origin(origin)?;
+			let target = T::Lookup::lookup(target)?;
+			Self::ensure_valid_length(&name)?;
+
+			let deposit = Self::name_of(&target).map(|(_, deposit)| deposit).unwrap_or_else(Zero::zero);
+			<NameOf<T>>::insert(&target, (name, deposit));
+
+			Self::deposit_event(RawEvent::NameForced(target));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	fn ensure_valid_length(name: &[u8]) -> Result<(), &'static str> {
+		ensure!(name.len() >= T::MinLength::get() as usize, "Name too short");
+		ensure!(name.len() <= T::MaxLength::get() as usize, "Name too long");
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use srml_support::{assert_noop, assert_ok, impl_outer_origin, parameter_types};
+	use substrate_primitives::{H256, Blake2Hasher};
+	use primitives::{traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+		pub const TransactionBaseFee: u64 = 0;
+		pub const TransactionByteFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnNewAccount = ();
+		type OnFreeBalanceZero = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TipPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+		type TransactionBaseFee = TransactionBaseFee;
+		type TransactionByteFee = TransactionByteFee;
+	}
+	parameter_types! {
+		pub const ReservationFee: u64 = 2;
+		pub const MinLength: u32 = 3;
+		pub const MaxLength: u32 = 16;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type ReservationFee = ReservationFee;
+		type Slashed = ();
+		type ForceOrigin = system::EnsureRoot<u64>;
+		type MinLength = MinLength;
+		type MaxLength = MaxLength;
+	}
+	type Balances = balances::Module<Test>;
+	type Nicks = Module<Test>;
+
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+		t.extend(balances::GenesisConfig::<Test> {
+			balances: vec![(1, 10), (2, 10)],
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	#[test]
+	fn set_and_clear_name_work() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Nicks::set_name(Origin::signed(1), b"Gav".to_vec()));
+			assert_eq!(Nicks::name_of(1), Some((b"Gav".to_vec(), 2)));
+			assert_eq!(Balances::free_balance(&1), 8);
+			assert_eq!(Balances::reserved_balance(&1), 2);
+
+			// changing the name takes no further deposit.
+			assert_ok!(Nicks::set_name(Origin::signed(1), b"Gavin".to_vec()));
+			assert_eq!(Nicks::name_of(1), Some((b"Gavin".to_vec(), 2)));
+			assert_eq!(Balances::reserved_balance(&1), 2);
+
+			assert_ok!(Nicks::clear_name(Origin::signed(1)));
+			assert_eq!(Nicks::name_of(1), None);
+			assert_eq!(Balances::free_balance(&1), 10);
+		});
+	}
+
+	#[test]
+	fn kill_name_slashes_deposit() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Nicks::set_name(Origin::signed(2), b"Dave".to_vec()));
+			assert_eq!(Balances::total_issuance(), 20);
+
+			assert_ok!(Nicks::kill_name(Origin::ROOT, 2));
+			assert_eq!(Nicks::name_of(2), None);
+			assert_eq!(Balances::total_balance(&2), 8);
+			assert_eq!(Balances::total_issuance(), 18);
+		});
+	}
+
+	#[test]
+	fn force_name_takes_no_deposit() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Nicks::force_name(Origin::ROOT, 1, b"Gav".to_vec()));
+			assert_eq!(Nicks::name_of(1), Some((b"Gav".to_vec(), 0)));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+
+			// a forced name keeps the deposit already held.
+			assert_ok!(Nicks::set_name(Origin::signed(2), b"Dave".to_vec()));
+			assert_ok!(Nicks::force_name(Origin::ROOT, 2, b"Dr. David".to_vec()));
+			assert_eq!(Nicks::name_of(2), Some((b"Dr. David".to_vec(), 2)));
+		});
+	}
+
+	#[test]
+	fn error_catching_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(Nicks::clear_name(Origin::signed(1)), "Not named");
+
+			assert_noop!(Nicks::set_name(Origin::signed(3), b"Dave".to_vec()), "not enough free funds");
+			assert_noop!(Nicks::set_name(Origin::signed(1), b"Ga".to_vec()), "Name too short");
+			assert_noop!(
+				Nicks::set_name(Origin::signed(1), b"Gavin James Wood, Esquire".to_vec()),
+				"Name too long"
+			);
+
+			assert_ok!(Nicks::set_name(Origin::signed(1), b"Dave".to_vec()));
+			assert_noop!(Nicks::kill_name(Origin::signed(2), 1), "Invalid origin");
+			assert_noop!(Nicks::force_name(Origin::signed(2), 1, b"Whatever".to_vec()), "Invalid origin");
+			assert_noop!(Nicks::kill_name(Origin::ROOT, 2), "Not named");
+		});
+	}
+}
@@ -32,6 +32,9 @@
 //! or exit the validator set at a session change. It is measured in block numbers. The block where a session is
 //! ended is determined by the `ShouldSessionEnd` trait. When the session is ending, a new validator set
 //! can be chosen by `OnSessionEnding` implementations.
+//! Any module which maintains a set of validators, such as staking or a simple proof-of-authority
+//! validator set, can provide it by implementing `SessionManager` and being plugged in through
+//! `ManagedBy`.
 //! - **Session key:** A session key is actually several keys kept together that provide the various signing
 //! functions required by network authorities/validators in pursuit of their duties.
 //! - **Validator ID:** Every account has an associated validator ID. For some simple staking systems, this
@@ -246,6 +249,44 @@ impl<V> SelectInitialValidators<V> for () {
 	}
 }
 
+/// A source of validator sets for the session module, e.g. a staking, elections or simple
+/// validator-set module.
+///
+/// Use [`ManagedBy`](./struct.ManagedBy.html) to plug a manager in as both the `OnSessionEnding`
+/// and the `SelectInitialValidators` of the session module.
+pub trait SessionManager<ValidatorId> {
+	/// Plan the session `new_index`, returning its validator set if it changes.
+	///
+	/// The returned validator set, if any, is queued and will be applied when the session
+	/// `new_index` begins.
+	fn new_session(new_index: SessionIndex) -> Option<Vec<ValidatorId>>;
+
+	/// The validator set of the genesis session. If `None` is returned all accounts that have
+	/// session keys set in the genesis block will be validators.
+	fn initial_validators() -> Option<Vec<ValidatorId>> {
+		None
+	}
+}
+
+impl<V> SessionManager<V> for () {
+	fn new_session(_: SessionIndex) -> Option<Vec<V>> { None }
+}
+
+/// Adapter which makes a `SessionManager` the source of the session module's validator sets.
+pub struct ManagedBy<M>(PhantomData<M>);
+
+impl<V, M: SessionManager<V>> OnSessionEnding<V> for ManagedBy<M> {
+	fn on_session_ending(_ending_index: SessionIndex, next_index: SessionIndex) -> Option<Vec<V>> {
+		M::new_session(next_index)
+	}
+}
+
+impl<V, M: SessionManager<V>> SelectInitialValidators<V> for ManagedBy<M> {
+	fn select_initial_validators() -> Option<Vec<V>> {
+		M::initial_validators()
+	}
+}
+
 pub trait Trait: system::Trait {
 	/// The overarching event type.
 	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
@@ -790,4 +831,25 @@ mod tests {
 
 		assert!(P::should_end_session(13));
 	}
+
+	#[test]
+	fn managed_by_forwards_to_session_manager() {
+		struct Manager;
+		impl SessionManager<u64> for Manager {
+			fn new_session(new_index: SessionIndex) -> Option<Vec<u64>> {
+				if new_index % 2 == 0 { Some(vec![new_index as u64]) } else { None }
+			}
+			fn initial_validators() -> Option<Vec<u64>> {
+				Some(vec![1, 2])
+			}
+		}
+
+		assert_eq!(<ManagedBy<Manager> as OnSessionEnding<u64>>::on_session_ending(1, 2), Some(vec![2]));
+		assert_eq!(<ManagedBy<Manager> as OnSessionEnding<u64>>::on_session_ending(2, 3), None);
+		assert_eq!(
+			<ManagedBy<Manager> as SelectInitialValidators<u64>>::select_initial_validators(),
+			Some(vec![1, 2])
+		);
+		assert_eq!(<ManagedBy<()> as SelectInitialValidators<u64>>::select_initial_validators(), None);
+	}
 }
@@ -0,0 +1,206 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compact representation of a Phragmén election solution.
+//!
+//! The assignments returned by the election refer to voters and targets by account id and carry
+//! one ratio per edge, which is far too large to be submitted in a single extrinsic once the
+//! staker set grows. [`CompactAssignments`] instead refers to voters and targets by their index
+//! in lists which both the submitter and the chain know, encodes all numbers compactly, and
+//! omits the ratio of the last edge of each voter: it is whatever is left of `ACCURACY`.
+
+use rstd::{prelude::*, result, collections::btree_set::BTreeSet};
+use parity_codec::{Encode, Decode};
+use crate::MAX_NOMINATIONS;
+use crate::phragmen::{ACCURACY, ExtendedBalance};
+
+/// Index of a voter in the list of voters the solution was computed from.
+pub type VoterIndex = u32;
+
+/// Index of a target in the list of candidates the solution was computed from.
+pub type TargetIndex = u16;
+
+/// An assignment of a voter's stake to targets, with each ratio out of `ACCURACY`.
+pub type Assignment<AccountId> = (AccountId, Vec<(AccountId, ExtendedBalance)>);
+
+/// A voter backing a single target with all of its stake.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SingleVote {
+	/// The voter.
+	#[codec(compact)]
+	pub voter: VoterIndex,
+	/// The only target of the voter.
+	#[codec(compact)]
+	pub target: TargetIndex,
+}
+
+/// A target and the part of `ACCURACY` of the voter's stake it receives.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct CompactEdge {
+	/// The target.
+	#[codec(compact)]
+	pub target: TargetIndex,
+	/// The ratio of the voter's stake going to the target.
+	#[codec(compact)]
+	pub ratio: ExtendedBalance,
+}
+
+/// A voter splitting its stake between several targets.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SplitVote {
+	/// The voter.
+	#[codec(compact)]
+	pub voter: VoterIndex,
+	/// All targets but the last one, with their ratio.
+	pub distribution: Vec<CompactEdge>,
+	/// The last target, which receives what `distribution` leaves of `ACCURACY`.
+	#[codec(compact)]
+	pub last: TargetIndex,
+}
+
+/// Compact representation of the assignments of an election solution.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct CompactAssignments {
+	/// Voters with a single target.
+	pub single: Vec<SingleVote>,
+	/// Voters with more than one target.
+	pub split: Vec<SplitVote>,
+}
+
+impl CompactAssignments {
+	/// Compact `assignments`, using `voter_index` and `target_index` to look up the indices of
+	/// accounts.
+	///
+	/// The ratio of the last edge of each voter is dropped, and implied to be the remainder of
+	/// `ACCURACY` when converting back. Fails if an account has no index, a voter has no or more
+	/// than `MAX_NOMINATIONS` targets, or the other ratios of a voter exceed `ACCURACY`.
+	pub fn from_assignment<A, FV, FT>(
+		assignments: &[Assignment<A>],
+		voter_index: FV,
+		target_index: FT,
+	) -> result::Result<Self, &'static str> where
+		FV: Fn(&A) -> Option<VoterIndex>,
+		FT: Fn(&A) -> Option<TargetIndex>,
+	{
+		let mut compact = Self::default();
+
+		for (voter, edges) in assignments {
+			let voter = voter_index(voter).ok_or("unknown voter")?;
+			let (last, rest) = edges.split_last().ok_or("voter without targets")?;
+			if edges.len() > MAX_NOMINATIONS {
+				return Err("too many targets for a voter");
+			}
+
+			let last = target_index(&last.0).ok_or("unknown target")?;
+			if rest.is_empty() {
+				compact.single.push(SingleVote { voter, target: last });
+				continue;
+			}
+
+			let distribution = rest.iter()
+				.map(|(target, ratio)| target_index(target)
+					.map(|target| CompactEdge { target, ratio: *ratio })
+					.ok_or("unknown target")
+				)
+				.collect::<result::Result<Vec<_>, _>>()?;
+			ensure_within_accuracy(&distribution)?;
+
+			compact.split.push(SplitVote { voter, distribution, last });
+		}
+
+		Ok(compact)
+	}
+
+	/// Expand into the full assignments, using `voter_at` and `target_at` to look up the accounts
+	/// behind indices.
+	///
+	/// Fails if an index is unknown, a voter appears more than once, a voter has more than
+	/// `MAX_NOMINATIONS` targets or the same target twice, or the ratios of a voter exceed
+	/// `ACCURACY`. This makes it safe to call on solutions submitted by anyone.
+	pub fn into_assignment<A, FV, FT>(
+		self,
+		voter_at: FV,
+		target_at: FT,
+	) -> result::Result<Vec<Assignment<A>>, &'static str> where
+		FV: Fn(VoterIndex) -> Option<A>,
+		FT: Fn(TargetIndex) -> Option<A>,
+	{
+		let mut seen_voters = BTreeSet::new();
+		let mut assignments = Vec::with_capacity(self.voter_count());
+		let mut lookup_voter = |index| {
+			if !seen_voters.insert(index) {
+				return Err("duplicate voter");
+			}
+			voter_at(index).ok_or("unknown voter")
+		};
+
+		for SingleVote { voter, target } in self.single {
+			let target = target_at(target).ok_or("unknown target")?;
+			assignments.push((lookup_voter(voter)?, vec![(target, ACCURACY)]));
+		}
+
+		for SplitVote { voter, distribution, last } in self.split {
+			if distribution.len() + 1 > MAX_NOMINATIONS {
+				return Err("too many targets for a voter");
+			}
+			let mut targets = distribution.iter().map(|e| e.target).collect::<BTreeSet<_>>();
+			if targets.len() != distribution.len() || !targets.insert(last) {
+				return Err("duplicate target");
+			}
+			let remainder = ensure_within_accuracy(&distribution)?;
+
+			let edges = distribution.into_iter()
+				.map(|CompactEdge { target, ratio }| (target, ratio))
+				.chain(Some((last, remainder)))
+				.map(|(target, ratio)| target_at(target).map(|t| (t, ratio)).ok_or("unknown target"))
+				.collect::<result::Result<Vec<_>, _>>()?;
+			assignments.push((lookup_voter(voter)?, edges));
+		}
+
+		Ok(assignments)
+	}
+
+	/// The number of voters in the solution.
+	pub fn voter_count(&self) -> usize {
+		self.single.len() + self.split.len()
+	}
+
+	/// The number of edges in the solution.
+	pub fn edge_count(&self) -> usize {
+		self.single.len() + self.split.iter().map(|v| v.distribution.len() + 1).sum::<usize>()
+	}
+
+	/// Ensure the encoded solution is no longer than `max_len` bytes, e.g. to fit it into a
+	/// single extrinsic.
+	pub fn ensure_encoded_len(&self, max_len: usize) -> result::Result<(), &'static str> {
+		if self.encode().len() > max_len {
+			Err("solution too large")
+		} else {
+			Ok(())
+		}
+	}
+}
+
+/// Returns what `distribution` leaves of `ACCURACY`, or an error if it exceeds it.
+fn ensure_within_accuracy(distribution: &[CompactEdge]) -> result::Result<ExtendedBalance, &'static str> {
+	distribution.iter()
+		.try_fold(ACCURACY, |left, e| left.checked_sub(e.ratio))
+		.ok_or("ratios exceed accuracy")
+}
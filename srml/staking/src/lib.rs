@@ -242,6 +242,9 @@
 //! values until the total difference among votes of a particular nominator are less than a
 //! threshold.
 //!
+//! The [`compact`](./compact/index.html) module provides a compact encoding of the election
+//! result, small enough to be submitted in a single extrinsic for large staker sets.
+//!
 //! ## GenesisConfig
 //!
 //! The Staking module depends on the [`GenesisConfig`](./struct.GenesisConfig.html).
@@ -266,6 +269,7 @@ mod mock;
 mod tests;
 
 mod phragmen;
+pub mod compact;
 
 #[cfg(all(feature = "bench", test))]
 mod benches;
@@ -302,6 +306,29 @@ const STAKING_ID: LockIdentifier = *b"staking ";
 /// Counter for the number of eras that have passed.
 pub type EraIndex = u32;
 
+/// Reward points of an era. Used to split era total payout between validators.
+#[derive(Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, PartialEq))]
+pub struct EraPoints {
+	/// Total number of points. Equals the sum of reward points for each validator.
+	pub total: u32,
+	/// The reward points earned by a given validator. The index of this vec corresponds to the
+	/// index into the current validator set.
+	pub individual: Vec<u32>,
+}
+
+impl EraPoints {
+	/// Add the reward to the validator at the given index. Index must be valid
+	/// (i.e. `index < current_elected.len()`).
+	fn add_points_to_index(&mut self, index: u32, points: u32) {
+		if let Some(new_total) = self.total.checked_add(points) {
+			self.total = new_total;
+			self.individual.resize((index as usize + 1).max(self.individual.len()), 0);
+			self.individual[index as usize] += points; // Addition is less than total
+		}
+	}
+}
+
 /// Indicates the initial status of the staker.
 #[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
 pub enum StakerStatus<AccountId> {
@@ -568,6 +595,9 @@ decl_storage! {
 		/// and increased for every successfully finished session.
 		pub CurrentEraReward get(current_era_reward): BalanceOf<T>;
 
+		/// Rewards for the current era. Using indices of current elected set.
+		pub CurrentEraPointsEarned get(current_era_reward_points): EraPoints;
+
 		/// The amount of balance actively at stake for each validator slot, currently.
 		///
 		/// This is used to derive rewards and punishments.
@@ -996,13 +1026,13 @@ impl<T: Trait> Module<T> {
 		// The amount we'll slash from the validator's stash directly.
 		let own_slash = exposure.own.min(slash);
 		let (mut imbalance, missing) = T::Currency::slash(stash, own_slash);
-		let own_slash = own_slash - missing;
+		let own_slash = own_slash.saturating_sub(missing);
 		// The amount remaining that we can't slash from the validator, that must be taken from the
 		// nominators.
-		let rest_slash = slash - own_slash;
+		let rest_slash = slash.saturating_sub(own_slash);
 		if !rest_slash.is_zero() {
 			// The total to be slashed from the nominators.
-			let total = exposure.total - exposure.own;
+			let total = exposure.total.saturating_sub(exposure.own);
 			if !total.is_zero() {
 				for i in exposure.others.iter() {
 					let per_u64 = Perbill::from_rational_approximation(i.value, total);
@@ -1028,8 +1058,8 @@ impl<T: Trait> Module<T> {
 			RewardDestination::Staked => Self::bonded(stash)
 				.and_then(|c| Self::ledger(&c).map(|l| (c, l)))
 				.and_then(|(controller, mut l)| {
-					l.active += amount;
-					l.total += amount;
+					l.active = l.active.saturating_add(amount);
+					l.total = l.total.saturating_add(amount);
 					let r = T::Currency::deposit_into_existing(stash, amount).ok();
 					Self::update_ledger(&controller, &l);
 					r
@@ -1042,7 +1072,7 @@ impl<T: Trait> Module<T> {
 	/// pre-payout cut.
 	fn reward_validator(stash: &T::AccountId, reward: BalanceOf<T>) {
 		let off_the_table = reward.min(Self::validators(stash).validator_payment);
-		let reward = reward - off_the_table;
+		let reward = reward.saturating_sub(off_the_table);
 		let mut imbalance = <PositiveImbalanceOf<T>>::zero();
 		let validator_cut = if reward.is_zero() {
 			Zero::zero()
@@ -1058,7 +1088,7 @@ impl<T: Trait> Module<T> {
 			let per_u64 = Perbill::from_rational_approximation(exposure.own, total);
 			per_u64 * reward
 		};
-		imbalance.maybe_subsume(Self::make_payout(stash, validator_cut + off_the_table));
+		imbalance.maybe_subsume(Self::make_payout(stash, validator_cut.saturating_add(off_the_table)));
 		T::Reward::on_unbalanced(imbalance);
 	}
 
@@ -1069,7 +1099,7 @@ impl<T: Trait> Module<T> {
 	{
 		// accumulate good session reward
 		let reward = Self::current_session_reward();
-		<CurrentEraReward<T>>::mutate(|r| *r += reward);
+		<CurrentEraReward<T>>::mutate(|r| *r = r.saturating_add(reward));
 
 		if ForceNewEra::take() || session_index % T::SessionsPerEra::get() == 0 {
 			let validators = T::SessionInterface::validators();
@@ -1090,16 +1120,30 @@ impl<T: Trait> Module<T> {
 	fn new_era(start_session_index: SessionIndex) -> Option<Vec<T::AccountId>> {
 		// Payout
 		let reward = <CurrentEraReward<T>>::take();
+		let points = CurrentEraPointsEarned::take();
 		if !reward.is_zero() {
 			let validators = Self::current_elected();
-			for v in validators.iter() {
-				Self::reward_validator(v, reward);
-			}
-			Self::deposit_event(RawEvent::Reward(reward));
 			let len = validators.len() as u32; // validators length can never overflow u64
 			let len: BalanceOf<T> = len.into();
-			let total_minted = reward * len;
-			let total_rewarded_stake = Self::slot_stake() * len;
+			let total_minted = reward.saturating_mul(len);
+
+			if points.total == 0 {
+				// Nobody earned points, fall back to an equal split.
+				for v in validators.iter() {
+					Self::reward_validator(v, reward);
+				}
+			} else {
+				// Split the total payout pro-rata to the points earned.
+				for (v, p) in validators.iter().zip(points.individual.into_iter()) {
+					if p != 0 {
+						let share = Perbill::from_rational_approximation(p, points.total);
+						Self::reward_validator(v, share * total_minted);
+					}
+				}
+			}
+
+			Self::deposit_event(RawEvent::Reward(reward));
+			let total_rewarded_stake = Self::slot_stake().saturating_mul(len);
 			T::OnRewardMinted::on_dilution(total_minted, total_rewarded_stake);
 		}
 
@@ -1134,6 +1178,43 @@ impl<T: Trait> Module<T> {
 		maybe_new_validators
 	}
 
+	/// Add reward points to validators using their stash account ID.
+	///
+	/// Validators are keyed by stash account ID and must be in the current elected set.
+	///
+	/// For each element in the iterator the given number of points in u32 is added to the
+	/// validator, thus duplicates are handled.
+	///
+	/// At the end of the era each the total payout will be distributed among validator
+	/// relatively to their points.
+	pub fn reward_by_ids(validators_points: impl IntoIterator<Item = (T::AccountId, u32)>) {
+		let current_elected = <Module<T>>::current_elected();
+		CurrentEraPointsEarned::mutate(|rewards| {
+			for (validator, points) in validators_points.into_iter() {
+				if let Some(index) = current_elected.iter()
+					.position(|elected| *elected == validator)
+				{
+					rewards.add_points_to_index(index as u32, points);
+				}
+			}
+		});
+	}
+
+	/// Add reward points to validators using their validator index.
+	///
+	/// For each element in the iterator the given number of points in u32 is added to the
+	/// validator, thus duplicates are handled.
+	pub fn reward_by_indices(validators_points: impl IntoIterator<Item = (u32, u32)>) {
+		let current_elected_len = <Module<T>>::current_elected().len() as u32;
+		CurrentEraPointsEarned::mutate(|rewards| {
+			for (validator_index, points) in validators_points.into_iter() {
+				if validator_index < current_elected_len {
+					rewards.add_points_to_index(validator_index, points);
+				}
+			}
+		});
+	}
+
 	fn slashable_balance_of(stash: &T::AccountId) -> BalanceOf<T> {
 		Self::bonded(stash).and_then(Self::ledger).map(|l| l.total).unwrap_or_default()
 	}
@@ -1375,6 +1456,22 @@ impl<T: Trait> Convert<T::AccountId, Option<Exposure<T::AccountId, BalanceOf<T>>
 	}
 }
 
+/// Add reward points to block authors:
+/// * 20 points to the block producer for producing a (non-uncle) block in the relay chain,
+/// * 2 points to the block producer for each reference to a previously unreferenced uncle, and
+/// * 1 point to the producer of each referenced uncle block.
+impl<T: Trait + authorship::Trait> authorship::EventHandler<T::AccountId, T::BlockNumber> for Module<T> {
+	fn note_author(author: T::AccountId) {
+		Self::reward_by_ids(vec![(author, 20)]);
+	}
+	fn note_uncle(author: T::AccountId, _age: T::BlockNumber) {
+		Self::reward_by_ids(vec![
+			(<authorship::Module<T>>::author(), 2),
+			(author, 1)
+		])
+	}
+}
+
 impl<T: Trait> SelectInitialValidators<T::AccountId> for Module<T> {
 	fn select_initial_validators() -> Option<Vec<T::AccountId>> {
 		<Module<T>>::select_validators().1
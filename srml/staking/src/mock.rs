@@ -92,6 +92,8 @@ parameter_types! {
 }
 impl system::Trait for Test {
 	type Origin = Origin;
+	type Call = ();
+	type BaseCallFilter = ();
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -117,6 +119,7 @@ impl balances::Trait for Test {
 	type OnNewAccount = ();
 	type Event = ();
 	type TransactionPayment = ();
+	type TipPayment = ();
 	type TransferPayment = ();
 	type DustRemoval = ();
 	type ExistentialDeposit = ExistentialDeposit;
@@ -147,11 +150,13 @@ impl session::historical::Trait for Test {
 
 parameter_types! {
 	pub const MinimumPeriod: u64 = 5;
+	pub const MaxDrift: u64 = 60;
 }
 impl timestamp::Trait for Test {
 	type Moment = u64;
 	type OnTimestampSet = ();
 	type MinimumPeriod = MinimumPeriod;
+	type MaxDrift = MaxDrift;
 }
 parameter_types! {
 	pub const SessionsPerEra: session::SessionIndex = 3;
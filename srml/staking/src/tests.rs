@@ -2076,3 +2076,103 @@ fn reward_validator_slashing_validator_doesnt_overflow() {
 		assert_eq!(Balances::total_balance(&2), 1);
 	})
 }
+
+#[test]
+fn era_payout_is_split_by_reward_points() {
+	with_externalities(&mut ExtBuilder::default()
+		.nominate(false)
+		.build(),
+	|| {
+		assert_eq_uvec!(Staking::current_elected(), vec![11, 21]);
+		assert_ok!(Staking::set_payee(Origin::signed(10), RewardDestination::Controller));
+		assert_ok!(Staking::set_payee(Origin::signed(20), RewardDestination::Controller));
+
+		// points for unknown validators are ignored.
+		Staking::reward_by_ids(vec![(11, 2), (21, 1), (11, 1), (31, 10)]);
+		Staking::reward_by_indices(vec![(42, 10)]);
+		assert_eq!(Staking::current_era_reward_points().total, 4);
+
+		start_era(1);
+
+		// 3 sessions of 10 for each of the 2 validators, split 3:1.
+		assert_eq!(Balances::total_balance(&10), 1 + 45);
+		assert_eq!(Balances::total_balance(&20), 1 + 15);
+		assert_eq!(Staking::current_era_reward_points(), EraPoints::default());
+	})
+}
+
+#[test]
+fn compact_assignments_round_trip() {
+	use compact::{CompactAssignments, SingleVote, SplitVote, CompactEdge};
+
+	let voters = vec![1u64, 3];
+	let targets = vec![11u64, 31];
+	let index_of = |list: &Vec<u64>, who: &u64| list.iter().position(|x| x == who);
+	let assignments = vec![
+		(3, vec![(11, 2816371998), (31, 1478595298)]),
+		(1, vec![(11, ACCURACY)]),
+	];
+
+	let compact = CompactAssignments::from_assignment(
+		&assignments,
+		|v| index_of(&voters, v).map(|i| i as u32),
+		|t| index_of(&targets, t).map(|i| i as u16),
+	).unwrap();
+	assert_eq!(compact, CompactAssignments {
+		single: vec![SingleVote { voter: 0, target: 0 }],
+		split: vec![SplitVote {
+			voter: 1,
+			distribution: vec![CompactEdge { target: 0, ratio: 2816371998 }],
+			last: 1,
+		}],
+	});
+	assert_eq!(compact.voter_count(), 2);
+	assert_eq!(compact.edge_count(), 3);
+	assert!(compact.encode().len() < assignments.encode().len());
+	assert_ok!(compact.ensure_encoded_len(compact.encode().len()));
+	assert!(compact.ensure_encoded_len(compact.encode().len() - 1).is_err());
+
+	let expanded = compact.into_assignment(
+		|v| voters.get(v as usize).cloned(),
+		|t| targets.get(t as usize).cloned(),
+	).unwrap();
+	assert_eq_uvec!(expanded, assignments);
+}
+
+#[test]
+fn compact_assignments_reject_invalid_solutions() {
+	use compact::{CompactAssignments, SingleVote, SplitVote, CompactEdge};
+
+	let split = |voter, distribution: Vec<(u16, ExtendedBalance)>, last| SplitVote {
+		voter,
+		distribution: distribution.into_iter()
+			.map(|(target, ratio)| CompactEdge { target, ratio })
+			.collect(),
+		last,
+	};
+	let expand = |split: Vec<SplitVote>| CompactAssignments {
+		single: vec![SingleVote { voter: 0, target: 0 }],
+		split,
+	}.into_assignment(|v| Some(v as u64), |t| if t < 20 { Some(t as u64) } else { None });
+
+	assert!(expand(vec![split(1, vec![(0, ACCURACY / 2)], 1)]).is_ok());
+	assert_eq!(expand(vec![split(0, vec![(0, 1)], 1)]), Err("duplicate voter"));
+	assert_eq!(expand(vec![split(1, vec![(0, 1)], 0)]), Err("duplicate target"));
+	assert_eq!(expand(vec![split(1, vec![(0, 1), (0, 1)], 1)]), Err("duplicate target"));
+	assert_eq!(expand(vec![split(1, vec![(0, 1)], 20)]), Err("unknown target"));
+	assert_eq!(expand(vec![split(1, vec![(0, ACCURACY), (1, 1)], 2)]), Err("ratios exceed accuracy"));
+	assert_eq!(
+		expand(vec![split(1, (0..MAX_NOMINATIONS as u16).map(|t| (t, 1)).collect(), 16)]),
+		Err("too many targets for a voter"),
+	);
+
+	let too_many = (0..=MAX_NOMINATIONS as u64).map(|t| (t, 1)).collect::<Vec<_>>();
+	assert_eq!(
+		CompactAssignments::from_assignment(&[(1u64, too_many)], |v| Some(*v as u32), |t| Some(*t as u16)),
+		Err("too many targets for a voter"),
+	);
+	assert_eq!(
+		CompactAssignments::from_assignment(&[(1u64, vec![])], |v| Some(*v as u32), |t| Some(*t as u16)),
+		Err("voter without targets"),
+	);
+}
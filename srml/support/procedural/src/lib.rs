@@ -60,6 +60,12 @@ use proc_macro::TokenStream;
 ///   If the keys are not trusted (e.g. can be set by a user), a cryptographic `hasher` such as
 ///   `blake2_256` must be used. Otherwise, other values in storage can be compromised.
 ///
+///   Maps using a concat hasher (`twox_64_concat` or `blake2_128_concat`) store their entries
+///   under a common prefix instead, with the final key
+///   `twox_128(module_name ++ " " ++ storage_name) ++ $hash(encoding(key))`. They also implement
+///   the [`IterableStorageMap`](../srml_support/storage/trait.IterableStorageMap.html) trait,
+///   providing `iter`, `iter_values` and `drain`.
+///
 /// * Linked map: `Foo: linked_map hasher($hash) type => type`: Same as `Map` but also implements
 ///   the [`EnumerableStorageMap`](../srml_support/storage/trait.EnumerableStorageMap.html) trait.
 ///
@@ -86,6 +92,7 @@ use proc_macro::TokenStream;
 /// Supported hashers (ordered from least to best security):
 ///
 /// * `twox_64_concat` - TwoX with 64bit + key concatenated.
+/// * `blake2_128_concat` - Blake2 with 128bit + key concatenated.
 /// * `twox_128` - TwoX with 128bit.
 /// * `twox_256` - TwoX with with 256bit.
 /// * `blake2_128` - Blake2 with 128bit.
@@ -97,7 +104,9 @@ use proc_macro::TokenStream;
 ///
 /// * `#vis`: Set the visibility of the structure. `pub` or nothing.
 /// * `#name`: Name of the storage item, used as a prefix in storage.
-/// * [optional] `get(#getter)`: Implements the function #getter to `Module`.
+/// * [optional] `get(#getter)` or `get(fn #getter)`: Implements the function #getter to `Module`.
+/// The getter returns `Option<T>` when the storage type is declared as `Option<T>`, otherwise it
+/// returns the value or `#default` when none.
 /// * [optional] `config(#field_name)`: `field_name` is optional if get is set.
 /// Will include the item in `GenesisConfig`.
 /// * [optional] `build(#closure)`: Closure called with storage overlays.
@@ -110,6 +119,9 @@ use proc_macro::TokenStream;
 /// * The `Store` trait structure: `<Module<T> as Store>::Foo`
 /// * The getter on the module that calls get on the structure: `Module::<T>::foo()`
 ///
+/// Besides `get`, the structure provides `exists`, `take` and `try_get`. `try_get` returns `Err`
+/// if the value is not in storage, instead of the default value.
+///
 /// ## GenesisConfig
 ///
 /// An optional `GenesisConfig` struct for storage initialization can be defined, either
@@ -135,6 +147,11 @@ use proc_macro::TokenStream;
 ///
 /// This struct can be exposed as `Config` by the `decl_runtime!` macro.
 ///
+/// The `GenesisConfig` always implements `BuildModuleGenesisStorage<T, I>`. It also implements
+/// `BuildStorage` if it is generic over the trait, or if building its storage doesn't involve the
+/// trait (e.g. no `build` closure referring to `T`). This allows assembling the genesis storage
+/// of a subset of modules, e.g. in tests, without the runtime's `GenesisConfig`.
+///
 /// ### Module with Instances
 ///
 /// The `decl_storage!` macro supports building modules with instances with the following syntax
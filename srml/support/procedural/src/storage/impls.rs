@@ -160,6 +160,17 @@ impl<'a, I: Iterator<Item=syn::Meta>> Impls<'a, I> {
 	}
 
 	pub fn map(self, hasher: TokenStream2, kty: &syn::Type) -> TokenStream2 {
+		self.map_with_hasher(hasher, kty, false)
+	}
+
+	/// A map whose entries are stored under a common prefix and can thus be iterated.
+	///
+	/// `hasher` must be a concat hasher, so that the keys can be recovered from storage.
+	pub fn iterable_map(self, hasher: TokenStream2, kty: &syn::Type) -> TokenStream2 {
+		self.map_with_hasher(hasher, kty, true)
+	}
+
+	fn map_with_hasher(self, hasher: TokenStream2, kty: &syn::Type, iterable: bool) -> TokenStream2 {
 		let Self {
 			scrate,
 			visibility,
@@ -225,6 +236,41 @@ impl<'a, I: Iterator<Item=syn::Meta>> Impls<'a, I> {
 			)
 		};
 
+		// Iterable maps compute the final storage key themselves: the common prefix followed by
+		// the hashed key.
+		let (storage_hasher, key_for, iterable_impl) = if iterable {
+			(
+				quote!( Identity ),
+				quote!{
+					let mut key = <
+						Self as #scrate::storage::hashed::generator::IterableStorageMap<#kty, #typ>
+					>::final_prefix().to_vec();
+					#scrate::codec::Encode::using_encoded(x, |encoded| key.extend(
+						<#scrate::#hasher as #scrate::storage::hashed::generator::StorageHasher>::hash(encoded)
+							.as_ref()
+					));
+					key
+				},
+				quote!{
+					impl<#impl_trait> #scrate::storage::hashed::generator::IterableStorageMap<#kty, #typ>
+						for #name<#trait_and_instance> #where_clause
+					{
+						type KeyHasher = #scrate::#hasher;
+					}
+				},
+			)
+		} else {
+			(
+				hasher,
+				quote!{
+					let mut key = #as_map::prefix().to_vec();
+					#scrate::codec::Encode::encode_to(x, &mut key);
+					key
+				},
+				quote!(),
+			)
+		};
+
 		// generator for map
 		quote!{
 			#( #[ #attrs ] )*
@@ -237,7 +283,7 @@ impl<'a, I: Iterator<Item=syn::Meta>> Impls<'a, I> {
 			{
 				type Query = #value_type;
 
-				type Hasher = #scrate::#hasher;
+				type Hasher = #scrate::#storage_hasher;
 
 				/// Get the prefix key in storage.
 				fn prefix() -> &'static [u8] {
@@ -246,19 +292,17 @@ impl<'a, I: Iterator<Item=syn::Meta>> Impls<'a, I> {
 
 				/// Get the storage key used to fetch a value corresponding to a specific key.
 				fn key_for(x: &#kty) -> #scrate::rstd::vec::Vec<u8> {
-					let mut key = #as_map::prefix().to_vec();
-					#scrate::codec::Encode::encode_to(x, &mut key);
-					key
+					#key_for
 				}
 
 				/// Load the value associated with the given key from the map.
-				fn get<S: #scrate::HashedStorage<#scrate::#hasher>>(key: &#kty, storage: &S) -> Self::Query {
+				fn get<S: #scrate::HashedStorage<#scrate::#storage_hasher>>(key: &#kty, storage: &S) -> Self::Query {
 					let key = #as_map::key_for(key);
 					storage.get(&key[..]).#option_simple_1(|| #fielddefault)
 				}
 
 				/// Take the value, reading and removing it.
-				fn take<S: #scrate::HashedStorage<#scrate::#hasher>>(key: &#kty, storage: &mut S) -> Self::Query {
+				fn take<S: #scrate::HashedStorage<#scrate::#storage_hasher>>(key: &#kty, storage: &mut S) -> Self::Query {
 					let key = #as_map::key_for(key);
 					storage.take(&key[..]).#option_simple_1(|| #fielddefault)
 				}
@@ -267,7 +311,7 @@ impl<'a, I: Iterator<Item=syn::Meta>> Impls<'a, I> {
 				fn mutate<R, F, S>(key: &#kty, f: F, storage: &mut S) -> R
 				where
 					F: FnOnce(&mut Self::Query) -> R,
-					S: #scrate::HashedStorage<#scrate::#hasher>,
+					S: #scrate::HashedStorage<#scrate::#storage_hasher>,
 				{
 					let mut val = #as_map::get(key, storage);
 
@@ -280,6 +324,8 @@ impl<'a, I: Iterator<Item=syn::Meta>> Impls<'a, I> {
 			impl<#impl_trait> #scrate::storage::hashed::generator::AppendableStorageMap<#kty, #typ>
 				for #name<#trait_and_instance> #where_clause
 			{}
+
+			#iterable_impl
 		}
 	}
 
@@ -42,6 +42,7 @@ mod keyword {
 	syn::custom_keyword!(twox_256);
 	syn::custom_keyword!(twox_128);
 	syn::custom_keyword!(twox_64_concat);
+	syn::custom_keyword!(blake2_128_concat);
 	syn::custom_keyword!(hasher);
 }
 
@@ -123,7 +124,14 @@ struct DeclStorageLine {
 #[derive(Parse, ToTokens, Debug)]
 struct DeclStorageGetter {
 	pub getter_keyword: keyword::get,
-	pub getfn: ext::Parens<Ident>,
+	pub getfn: ext::Parens<DeclStorageGetterBody>,
+}
+
+/// The content of `get(..)`, either `get(name)` or `get(fn name)`.
+#[derive(Parse, ToTokens, Debug)]
+struct DeclStorageGetterBody {
+	fn_keyword: Option<Token![fn]>,
+	ident: Ident,
 }
 
 #[derive(Parse, ToTokens, Debug)]
@@ -183,6 +191,7 @@ enum Hasher {
 	Twox256(keyword::twox_256),
 	Twox128(keyword::twox_128),
 	Twox64Concat(keyword::twox_64_concat),
+	Blake2_128Concat(keyword::blake2_128_concat),
 }
 
 #[derive(Parse, ToTokens, Debug)]
@@ -204,6 +213,7 @@ enum HasherKind {
 	Twox256,
 	Twox128,
 	Twox64Concat,
+	Blake2_128Concat,
 }
 
 impl From<&SetHasher> for HasherKind {
@@ -220,6 +230,7 @@ impl From<&Hasher> for HasherKind {
 			Hasher::Twox256(_) => HasherKind::Twox256,
 			Hasher::Twox128(_) => HasherKind::Twox128,
 			Hasher::Twox64Concat(_) => HasherKind::Twox64Concat,
+			Hasher::Blake2_128Concat(_) => HasherKind::Blake2_128Concat,
 		}
 	}
 }
@@ -232,6 +243,15 @@ impl HasherKind {
 			HasherKind::Twox256 => quote!( Twox256 ),
 			HasherKind::Twox128 => quote!( Twox128 ),
 			HasherKind::Twox64Concat => quote!( Twox64Concat ),
+			HasherKind::Blake2_128Concat => quote!( Blake2_128Concat ),
+		}
+	}
+
+	/// Whether the hash output contains the hashed data, i.e. whether the keys can be recovered.
+	fn is_concat(&self) -> bool {
+		match self {
+			HasherKind::Twox64Concat | HasherKind::Blake2_128Concat => true,
+			_ => false,
 		}
 	}
 
@@ -242,6 +262,7 @@ impl HasherKind {
 			HasherKind::Twox256 => quote!( StorageHasher::Twox256 ),
 			HasherKind::Twox128 => quote!( StorageHasher::Twox128 ),
 			HasherKind::Twox64Concat => quote!( StorageHasher::Twox64Concat ),
+			HasherKind::Blake2_128Concat => quote!( StorageHasher::Blake2_128Concat ),
 		}
 	}
 }
@@ -239,7 +239,7 @@ fn decl_store_extra_genesis(
 			let ident = if let Some(ident) = config.expr.content.as_ref() {
 				quote!( #ident )
 			} else if let Some(ref getter) = getter.inner {
-				let ident = &getter.getfn.content;
+				let ident = &getter.getfn.content.ident;
 				quote!( #ident )
 			} else {
 				return Err(
@@ -510,6 +510,27 @@ fn decl_store_extra_genesis(
 			extend_where_clause(&mut fn_where_clause);
 		}
 
+		// `BuildStorage` can only be implemented if building the storage does not require the
+		// trait instance, or if the config itself is generic over it.
+		let impl_build_storage = if is_trait_needed || !assimilate_require_generic {
+			quote! {
+				#[cfg(feature = "std")]
+				impl#fparam_impl #scrate::runtime_primitives::BuildStorage
+					for GenesisConfig#sparam #genesis_where_clause
+				{
+					fn assimilate_storage(
+						self,
+						r: &mut #scrate::runtime_primitives::StorageOverlay,
+						c: &mut #scrate::runtime_primitives::ChildrenStorageOverlay,
+					) -> std::result::Result<(), String> {
+						<GenesisConfig#sparam>::assimilate_storage(self, r, c)
+					}
+				}
+			}
+		} else {
+			quote!()
+		};
+
 		let res = quote!{
 			#[derive(#scrate::Serialize, #scrate::Deserialize)]
 			#[cfg(feature = "std")]
@@ -574,6 +595,8 @@ fn decl_store_extra_genesis(
 					self.assimilate_storage::<#fn_traitinstance> (r, c)
 				}
 			}
+
+			#impl_build_storage
 		};
 
 		Ok(res)
@@ -766,7 +789,11 @@ fn decl_storage_items(
 				i.simple_value()
 			},
 			DeclStorageTypeInfosKind::Map { key_type, is_linked: false, hasher } => {
-				i.map(hasher.into_storage_hasher_struct(), key_type)
+				if hasher.is_concat() {
+					i.iterable_map(hasher.into_storage_hasher_struct(), key_type)
+				} else {
+					i.map(hasher.into_storage_hasher_struct(), key_type)
+				}
 			},
 			DeclStorageTypeInfosKind::Map { key_type, is_linked: true, hasher } => {
 				i.linked_map(hasher.into_storage_hasher_struct(), key_type)
@@ -848,7 +875,7 @@ fn impl_store_fns(
 		} = sline;
 
 		if let Some(getter) = getter.inner.as_ref() {
-			let get_fn = &getter.getfn.content;
+			let get_fn = &getter.getfn.content.ident;
 
 			let type_infos = get_type_infos(storage_type);
 			let value_type = type_infos.value_type;
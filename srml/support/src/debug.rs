@@ -0,0 +1,200 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Runtime logging.
+//!
+//! The macros of this module send log messages to the node's logger through
+//! `runtime_io::log`. The messages are logged under the target `runtime::<target>`, where the
+//! target defaults to the module path of the caller:
+//!
+//! ```nocompile
+//! use srml_support::debug;
+//!
+//! debug::info!("new session {} started", index);
+//! debug::warn!(target: "staking", "no validators elected, keeping the old set");
+//! ```
+//!
+//! In the native runtime all levels are logged, the node's log filter decides what is shown
+//! (e.g. `-l runtime=debug`). In a wasm runtime only errors and warnings are kept, all more
+//! verbose messages are compiled out. Enable the `runtime-log` feature of this crate to keep
+//! them as well, e.g. to debug a live chain.
+
+pub use runtime_io::LogLevel;
+
+use crate::rstd::vec::Vec;
+use core::fmt;
+
+/// The most verbose level that is logged. More verbose messages are compiled out.
+#[cfg(any(feature = "std", feature = "runtime-log"))]
+pub const STATIC_MAX_LEVEL: LogLevel = LogLevel::Trace;
+
+/// The most verbose level that is logged. More verbose messages are compiled out.
+#[cfg(not(any(feature = "std", feature = "runtime-log")))]
+pub const STATIC_MAX_LEVEL: LogLevel = LogLevel::Warn;
+
+/// Format `args` and send the message to the node's logger.
+///
+/// Prefer the macros of this module, which skip the formatting for levels that are not logged.
+pub fn log(level: LogLevel, target: &str, args: fmt::Arguments) {
+	let mut writer = Writer::default();
+	let _ = fmt::Write::write_fmt(&mut writer, args);
+	runtime_io::log(level, target.as_bytes(), &writer.0);
+}
+
+/// A `fmt::Write` implementation that collects the formatted message.
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl fmt::Write for Writer {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.0.extend(s.as_bytes());
+		Ok(())
+	}
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __runtime_log {
+	(target: $target:expr, $level:expr, $( $arg:tt )+) => {{
+		let level = $level;
+		if level <= $crate::debug::STATIC_MAX_LEVEL {
+			$crate::debug::log(level, $target, format_args!($( $arg )+));
+		}
+	}};
+	($level:expr, $( $arg:tt )+) => {
+		$crate::__runtime_log!(target: module_path!(), $level, $( $arg )+)
+	};
+}
+
+/// Log a message at the error level.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __runtime_error {
+	(target: $target:expr, $( $arg:tt )+) => {
+		$crate::__runtime_log!(target: $target, $crate::debug::LogLevel::Error, $( $arg )+)
+	};
+	($( $arg:tt )+) => {
+		$crate::__runtime_log!($crate::debug::LogLevel::Error, $( $arg )+)
+	};
+}
+
+/// Log a message at the warn level.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __runtime_warn {
+	(target: $target:expr, $( $arg:tt )+) => {
+		$crate::__runtime_log!(target: $target, $crate::debug::LogLevel::Warn, $( $arg )+)
+	};
+	($( $arg:tt )+) => {
+		$crate::__runtime_log!($crate::debug::LogLevel::Warn, $( $arg )+)
+	};
+}
+
+/// Log a message at the info level.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __runtime_info {
+	(target: $target:expr, $( $arg:tt )+) => {
+		$crate::__runtime_log!(target: $target, $crate::debug::LogLevel::Info, $( $arg )+)
+	};
+	($( $arg:tt )+) => {
+		$crate::__runtime_log!($crate::debug::LogLevel::Info, $( $arg )+)
+	};
+}
+
+/// Log a message at the debug level.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __runtime_debug {
+	(target: $target:expr, $( $arg:tt )+) => {
+		$crate::__runtime_log!(target: $target, $crate::debug::LogLevel::Debug, $( $arg )+)
+	};
+	($( $arg:tt )+) => {
+		$crate::__runtime_log!($crate::debug::LogLevel::Debug, $( $arg )+)
+	};
+}
+
+/// Log a message at the trace level.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __runtime_trace {
+	(target: $target:expr, $( $arg:tt )+) => {
+		$crate::__runtime_log!(target: $target, $crate::debug::LogLevel::Trace, $( $arg )+)
+	};
+	($( $arg:tt )+) => {
+		$crate::__runtime_log!($crate::debug::LogLevel::Trace, $( $arg )+)
+	};
+}
+
+pub use crate::__runtime_error as error;
+pub use crate::__runtime_warn as warn;
+pub use crate::__runtime_info as info;
+pub use crate::__runtime_debug as debug;
+pub use crate::__runtime_trace as trace;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::RefCell;
+
+	thread_local! {
+		/// Messages logged on this thread, as `(level, target, message)`.
+		static LOGGED: RefCell<Vec<(log::Level, String, String)>> = RefCell::new(Vec::new());
+	}
+
+	/// Records the messages in `LOGGED`, so tests running in parallel don't see each other's.
+	struct TestLogger;
+
+	impl log::Log for TestLogger {
+		fn enabled(&self, _: &log::Metadata) -> bool {
+			true
+		}
+
+		fn log(&self, record: &log::Record) {
+			LOGGED.with(|logged| logged.borrow_mut().push(
+				(record.level(), record.target().to_owned(), record.args().to_string())
+			));
+		}
+
+		fn flush(&self) {}
+	}
+
+	static LOGGER: TestLogger = TestLogger;
+
+	#[test]
+	fn max_level_keeps_everything_in_native() {
+		assert_eq!(STATIC_MAX_LEVEL, LogLevel::Trace);
+		assert!(LogLevel::Error < LogLevel::Warn);
+		assert!(LogLevel::Debug < LogLevel::Trace);
+	}
+
+	#[test]
+	fn macros_log_to_the_node() {
+		let _ = log::set_logger(&LOGGER);
+		log::set_max_level(log::LevelFilter::Trace);
+
+		crate::debug::info!("a number: {}", 42);
+		crate::debug::error!(target: "test", "an error: {:?}", "oops");
+		crate::debug::trace!(target: "test", "tracing");
+
+		LOGGED.with(|logged| assert_eq!(*logged.borrow(), vec![
+			(log::Level::Info, "runtime::srml_support::debug::tests".to_owned(), "a number: 42".to_owned()),
+			(log::Level::Error, "runtime::test".to_owned(), "an error: \"oops\"".to_owned()),
+			(log::Level::Trace, "runtime::test".to_owned(), "tracing".to_owned()),
+		]));
+	}
+}
@@ -27,7 +27,7 @@ pub use srml_metadata::{
 };
 pub use sr_primitives::weights::{SimpleDispatchInfo, GetDispatchInfo, DispatchInfo, WeighData,
 	ClassifyDispatch,
-	TransactionPriority
+	TransactionPriority, Weight,
 };
 pub use sr_primitives::traits::{Dispatchable, DispatchResult};
 
@@ -44,6 +44,28 @@ pub trait Callable<T> {
 	type Call: Dispatchable + Codec + Clone + PartialEq + Eq;
 }
 
+/// A call that knows the name of the function it dispatches to.
+pub trait GetCallName {
+	/// Return the name of the function this call dispatches to.
+	fn get_call_name(&self) -> &'static str;
+}
+
+/// The names of the module and of the function that a call dispatches to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct CallMetadata {
+	/// The name of the module, as declared in the runtime.
+	pub module_name: &'static str,
+	/// The name of the function.
+	pub function_name: &'static str,
+}
+
+/// An aggregated call that knows the module and the function it dispatches to.
+pub trait GetCallMetadata {
+	/// Return the names of the module and of the function this call dispatches to.
+	fn get_call_metadata(&self) -> CallMetadata;
+}
+
 // dirty hack to work around serde_derive issue
 // https://github.com/rust-lang/rust/issues/51331
 pub type CallableCallFor<A, T> = <A as Callable<T>>::Call;
@@ -193,6 +215,30 @@ impl<T> Parameter for T where T: Codec + Clone + Eq {}
 /// # fn main() {}
 /// ```
 ///
+/// ## Transactional Functions
+///
+/// A dispatchable function annotated with `#[transactional]` runs inside a storage transaction:
+/// if it returns an `Err`, every storage change it made is reverted. The function must return
+/// a `Result`.
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate srml_support;
+/// # use srml_support::dispatch::Result;
+/// # use srml_system::{self as system, Trait, ensure_signed};
+/// decl_module! {
+/// 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+/// 		#[transactional]
+/// 		fn my_function(origin) -> Result {
+/// 			let _sender = ensure_signed(origin)?;
+/// 			// Writes done before an error is returned are discarded.
+/// 			Ok(())
+/// 		}
+/// 	}
+/// }
+/// # fn main() {}
+/// ```
+///
 /// ## Reserved Functions
 ///
 /// The following are reserved function signatures:
@@ -206,11 +252,23 @@ impl<T> Parameter for T where T: Codec + Clone + Eq {}
 ///
 /// * `on_initialize`: Executes at the beginning of a block. Using this function will
 /// implement the [`OnInitialize`](../sr_primitives/traits/trait.OnInitialize.html) trait.
+/// It can optionally return the `Weight` it consumed, e.g. `fn on_initialize(n: T::BlockNumber) -> Weight`,
+/// which is accounted in the block weight.
 /// * `on_finalize`: Executes at the end of a block. Using this function will
 /// implement the [`OnFinalize`](../sr_primitives/traits/trait.OnFinalize.html) trait.
 /// * `offchain_worker`: Executes at the beginning of a block and produces extrinsics for a future block
 /// upon completion. Using this function will implement the
 /// [`OffchainWorker`](../sr_primitives/traits/trait.OffchainWorker.html) trait.
+///
+/// * `on_idle`: Executes at the end of a block, before `on_finalize`, if there is block weight left
+/// over. It must take both the block number and the remaining `Weight` and return the `Weight` it
+/// consumed: `fn on_idle(n: T::BlockNumber, remaining_weight: Weight) -> Weight`. Using this function
+/// will implement the [`OnIdle`](../sr_primitives/traits/trait.OnIdle.html) trait.
+///
+/// * `on_runtime_upgrade`: Executes when a new runtime is enacted, before `on_initialize` of the
+/// first block it executes. Meant for storage migrations. It must return the `Weight` it consumed:
+/// `fn on_runtime_upgrade() -> Weight`. Using this function will implement the
+/// [`OnRuntimeUpgrade`](../sr_primitives/traits/trait.OnRuntimeUpgrade.html) trait.
 #[macro_export]
 macro_rules! decl_module {
 	// Entry point #1.
@@ -236,6 +294,8 @@ macro_rules! decl_module {
 			{}
 			{}
 			{}
+			{}
+			{}
 			[]
 			$($t)*
 		);
@@ -267,6 +327,8 @@ macro_rules! decl_module {
 			{}
 			{}
 			{}
+			{}
+			{}
 			[]
 			$($t)*
 		);
@@ -281,6 +343,8 @@ macro_rules! decl_module {
 		{}
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -296,6 +360,8 @@ macro_rules! decl_module {
 			{ $vis fn deposit_event $(<$dpeg $(, $dpeg_instance)?>)* () = default; }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			[ $( $dispatchables )* ]
@@ -310,6 +376,8 @@ macro_rules! decl_module {
 		{}
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -327,6 +395,8 @@ macro_rules! decl_module {
 			{ $vis fn deposit_event $(<$dpeg $(, $dpeg_instance)?>)* ($( $param_name: $param ),* ) { $( $impl )* } }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			[ $( $dispatchables )* ]
@@ -341,6 +411,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{}
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -356,6 +428,8 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ fn on_finalize( $( $param_name : $param ),* ) { $( $impl )* } }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			[ $( $dispatchables )* ]
@@ -370,6 +444,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{}
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -387,13 +463,48 @@ macro_rules! decl_module {
 		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
 		{ $( $other_where_bounds:tt )* }
 		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{ $( $on_finalize:tt )* }
 		{}
+		{ $( $on_runtime_upgrade:tt )* }
+		{ $( $offchain:tt )* }
+		{ $( $constants:tt )* }
+		[ $( $dispatchables:tt )* ]
+		$(#[doc = $doc_attr:tt])*
+		fn on_idle($($param_name:ident : $param:ty),* ) -> $return:ty { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name$(<I>, I: $instantiable $(= $module_default_instance)?)?>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $other_where_bounds )* }
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{ $( $on_finalize )* }
+			{ fn on_idle( $( $param_name : $param ),* ) -> $return { $( $impl )* } }
+			{ $( $on_runtime_upgrade )* }
+			{ $( $offchain )* }
+			{ $( $constants )* }
+			[ $( $dispatchables )* ]
+			$($rest)*
+		);
+	};
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, I: $instantiable:path $(= $module_default_instance:path)?)?>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $other_where_bounds:tt )* }
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{}
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
 		$(#[doc = $doc_attr:tt])*
-		fn on_initialize($($param_name:ident : $param:ty),* ) { $( $impl:tt )* }
+		fn on_runtime_upgrade() -> $return:ty { $( $impl:tt )* }
 		$($rest:tt)*
 	) => {
 		$crate::decl_module!(@normalize
@@ -402,8 +513,10 @@ macro_rules! decl_module {
 			for enum $call_type where origin: $origin_type, system = $system
 			{ $( $other_where_bounds )* }
 			{ $( $deposit_event )* }
-			{ fn on_initialize( $( $param_name : $param ),* ) { $( $impl )* } }
+			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ fn on_runtime_upgrade() -> $return { $( $impl )* } }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			[ $( $dispatchables )* ]
@@ -418,6 +531,41 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{}
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
+		{ $( $offchain:tt )* }
+		{ $( $constants:tt )* }
+		[ $( $dispatchables:tt )* ]
+		$(#[doc = $doc_attr:tt])*
+		fn on_initialize($($param_name:ident : $param:ty),* ) $( -> $return:ty )? { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name$(<I>, I: $instantiable $(= $module_default_instance)?)?>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $other_where_bounds )* }
+			{ $( $deposit_event )* }
+			{ fn on_initialize( $( $param_name : $param ),* ) $( -> $return )? { $( $impl )* } }
+			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
+			{ $( $offchain )* }
+			{ $( $constants )* }
+			[ $( $dispatchables )* ]
+			$($rest)*
+		);
+	};
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, I: $instantiable:path $(= $module_default_instance:path)?)?>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $other_where_bounds:tt )* }
+		{ $( $deposit_event:tt )* }
+		{}
+		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -440,6 +588,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -457,6 +607,8 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
 			{ fn offchain_worker( $( $param_name : $param ),* ) { $( $impl )* } }
 			{ $( $constants )* }
 			[ $( $dispatchables )* ]
@@ -476,6 +628,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -494,6 +648,8 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{
 				$( $constants )*
@@ -505,6 +661,99 @@ macro_rules! decl_module {
 		);
 	};
 
+	// Wrap the body of a `#[transactional]` function into a storage transaction.
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<
+			$trait_instance:ident: $trait_name:ident
+			$(<I>, $instance:ident: $instantiable:path $(= $module_default_instance:path)?)?
+			>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $other_where_bounds:tt )* }
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
+		{ $( $offchain:tt )* }
+		{ $( $constants:tt )* }
+		[ $( $dispatchables:tt )* ]
+		$(#[doc = $doc_attr:tt])*
+		#[transactional]
+		$(#[weight = $weight:expr])?
+		$fn_vis:vis fn $fn_name:ident(
+			$origin:ident $(, $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
+		) $( -> $result:ty )* { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<
+				$trait_instance: $trait_name$(<I>, $instance: $instantiable $(= $module_default_instance)?)?
+			>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $other_where_bounds )* }
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
+			{ $( $offchain )* }
+			{ $( $constants )* }
+			[ $( $dispatchables )* ]
+			$(#[doc = $doc_attr])*
+			$(#[weight = $weight])?
+			$fn_vis fn $fn_name(
+				$origin $(, $(#[$codec_attr])* $param_name : $param )*
+			) $( -> $result )* {
+				$crate::storage::with_transaction_result(|| { $( $impl )* })
+			}
+			$($rest)*
+		);
+	};
+	// Same as above, with `#[transactional]` given after `#[weight]`.
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<
+			$trait_instance:ident: $trait_name:ident
+			$(<I>, $instance:ident: $instantiable:path $(= $module_default_instance:path)?)?
+			>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $other_where_bounds:tt )* }
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
+		{ $( $offchain:tt )* }
+		{ $( $constants:tt )* }
+		[ $( $dispatchables:tt )* ]
+		$(#[doc = $doc_attr:tt])*
+		#[weight = $weight:expr]
+		#[transactional]
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<
+				$trait_instance: $trait_name$(<I>, $instance: $instantiable $(= $module_default_instance)?)?
+			>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $other_where_bounds )* }
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
+			{ $( $offchain )* }
+			{ $( $constants )* }
+			[ $( $dispatchables )* ]
+			$(#[doc = $doc_attr])*
+			#[transactional]
+			#[weight = $weight]
+			$($rest)*
+		);
+	};
 	// This puts the function statement into the [], decreasing `$rest` and moving toward finishing the parse.
 	(@normalize
 		$(#[$attr:meta])*
@@ -517,6 +766,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -537,6 +788,8 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			[
@@ -563,6 +816,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -582,6 +837,8 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			[ $( $dispatchables )* ]
@@ -602,6 +859,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -626,6 +885,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -650,6 +911,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -675,6 +938,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		[ $( $dispatchables:tt )* ]
@@ -689,6 +954,8 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_idle )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 		);
@@ -752,7 +1019,27 @@ macro_rules! decl_module {
 			$crate::runtime_primitives::traits::OnInitialize<$trait_instance::BlockNumber>
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
-			fn on_initialize(_block_number_not_used: $trait_instance::BlockNumber) { $( $impl )* }
+			fn on_initialize(
+				_block_number_not_used: $trait_instance::BlockNumber
+			) -> $crate::dispatch::Weight {
+				{ $( $impl )* }
+				0
+			}
+		}
+	};
+
+	(@impl_on_initialize
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+		fn on_initialize() -> $return:ty { $( $impl:tt )* }
+	) => {
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::runtime_primitives::traits::OnInitialize<$trait_instance::BlockNumber>
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{
+			fn on_initialize(_block_number_not_used: $trait_instance::BlockNumber) -> $return {
+				$( $impl )*
+			}
 		}
 	};
 
@@ -765,7 +1052,23 @@ macro_rules! decl_module {
 			$crate::runtime_primitives::traits::OnInitialize<$trait_instance::BlockNumber>
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
-			fn on_initialize($param: $param_ty) { $( $impl )* }
+			fn on_initialize($param: $param_ty) -> $crate::dispatch::Weight {
+				{ $( $impl )* }
+				0
+			}
+		}
+	};
+
+	(@impl_on_initialize
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+		fn on_initialize($param:ident : $param_ty:ty) -> $return:ty { $( $impl:tt )* }
+	) => {
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::runtime_primitives::traits::OnInitialize<$trait_instance::BlockNumber>
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{
+			fn on_initialize($param: $param_ty) -> $return { $( $impl )* }
 		}
 	};
 
@@ -816,6 +1119,54 @@ macro_rules! decl_module {
 		}
 	};
 
+	(@impl_on_idle
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+		fn on_idle(
+			$param:ident : $param_ty:ty, $weight:ident : $weight_ty:ty
+		) -> $return:ty { $( $impl:tt )* }
+	) => {
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::runtime_primitives::traits::OnIdle<$trait_instance::BlockNumber>
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{
+			fn on_idle($param: $param_ty, $weight: $weight_ty) -> $return { $( $impl )* }
+		}
+	};
+
+	(@impl_on_idle
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+	) => {
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::runtime_primitives::traits::OnIdle<$trait_instance::BlockNumber>
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{}
+	};
+
+	(@impl_on_runtime_upgrade
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+		fn on_runtime_upgrade() -> $return:ty { $( $impl:tt )* }
+	) => {
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::runtime_primitives::traits::OnRuntimeUpgrade
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{
+			fn on_runtime_upgrade() -> $return { $( $impl )* }
+		}
+	};
+
+	(@impl_on_runtime_upgrade
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+	) => {
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::runtime_primitives::traits::OnRuntimeUpgrade
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{}
+	};
+
 	(@impl_offchain
 		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
 		{ $( $other_where_bounds:tt )* }
@@ -1029,6 +1380,8 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_idle:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 	) => {
@@ -1057,6 +1410,20 @@ macro_rules! decl_module {
 			$( $on_finalize )*
 		}
 
+		$crate::decl_module! {
+			@impl_on_idle
+			$mod_type<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?>;
+			{ $( $other_where_bounds )* }
+			$( $on_idle )*
+		}
+
+		$crate::decl_module! {
+			@impl_on_runtime_upgrade
+			$mod_type<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?>;
+			{ $( $other_where_bounds )* }
+			$( $on_runtime_upgrade )*
+		}
+
 		$crate::decl_module! {
 			@impl_offchain
 			$mod_type<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?>;
@@ -1186,6 +1553,19 @@ macro_rules! decl_module {
 			for $call_type<$trait_instance $(, $instance)?> where $( $other_where_bounds )*
 		{}
 
+		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $crate::dispatch::GetCallName
+			for $call_type<$trait_instance $(, $instance)?> where $( $other_where_bounds )*
+		{
+			fn get_call_name(&self) -> &'static str {
+				match *self {
+					$(
+						$call_type::$fn_name( .. ) => stringify!($fn_name),
+					)*
+					_ => unreachable!(),
+				}
+			}
+		}
+
 		#[cfg(feature = "std")]
 		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $crate::dispatch::fmt::Debug
 			for $call_type<$trait_instance $(, $instance)?> where $( $other_where_bounds )*
@@ -1293,6 +1673,19 @@ macro_rules! impl_outer_dispatch {
 				}
 			}
 		}
+		impl $crate::dispatch::GetCallMetadata for $call_type {
+			fn get_call_metadata(&self) -> $crate::dispatch::CallMetadata {
+				use $crate::dispatch::GetCallName;
+				match self {
+					$(
+						$call_type::$camelcase(call) => $crate::dispatch::CallMetadata {
+							module_name: stringify!($camelcase),
+							function_name: call.get_call_name(),
+						},
+					)*
+				}
+			}
+		}
 		impl $crate::dispatch::Dispatchable for $call_type {
 			type Origin = $origin;
 			type Trait = $call_type;
@@ -1576,6 +1969,12 @@ macro_rules! __check_reserved_fn_name {
 	(on_finalise $( $rest:ident )*) => {
 		$crate::__check_reserved_fn_name!(@compile_error on_finalise);
 	};
+	(on_idle $( $rest:ident )*) => {
+		$crate::__check_reserved_fn_name!(@compile_error on_idle);
+	};
+	(on_runtime_upgrade $( $rest:ident )*) => {
+		$crate::__check_reserved_fn_name!(@compile_error on_runtime_upgrade);
+	};
 	(offchain_worker $( $rest:ident )*) => {
 		$crate::__check_reserved_fn_name!(@compile_error offchain_worker);
 	};
@@ -1595,7 +1994,7 @@ macro_rules! __check_reserved_fn_name {
 #[allow(dead_code)]
 mod tests {
 	use super::*;
-	use crate::runtime_primitives::traits::{OnInitialize, OnFinalize};
+	use crate::runtime_primitives::traits::{OnInitialize, OnIdle, OnRuntimeUpgrade, OnFinalize};
 	use sr_primitives::weights::{DispatchInfo, DispatchClass};
 
 	pub trait Trait: system::Trait + Sized where Self::AccountId: From<u32> {
@@ -1627,7 +2026,15 @@ mod tests {
 			fn aux_4(_origin, _data: i32) -> Result { unreachable!() }
 			fn aux_5(_origin, _data: i32, #[compact] _data2: u32) -> Result { unreachable!() }
 
-			fn on_initialize(n: T::BlockNumber) { if n.into() == 42 { panic!("on_initialize") } }
+			fn on_initialize(n: T::BlockNumber) -> Weight {
+				if n.into() == 42 { panic!("on_initialize") }
+				7
+			}
+			fn on_idle(n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+				if n.into() == 42 { panic!("on_idle") }
+				remaining_weight / 2
+			}
+			fn on_runtime_upgrade() -> Weight { 3 }
 			fn on_finalize(n: T::BlockNumber) { if n.into() == 42 { panic!("on_finalize") } }
 			fn offchain_worker() {}
 
@@ -1764,6 +2171,27 @@ mod tests {
 		<Module<TraitImpl> as OnInitialize<u32>>::on_initialize(42);
 	}
 
+	#[test]
+	fn on_initialize_should_return_weight() {
+		assert_eq!(<Module<TraitImpl> as OnInitialize<u32>>::on_initialize(1), 7);
+	}
+
+	#[test]
+	#[should_panic(expected = "on_idle")]
+	fn on_idle_should_work() {
+		<Module<TraitImpl> as OnIdle<u32>>::on_idle(42, 10);
+	}
+
+	#[test]
+	fn on_idle_should_return_weight() {
+		assert_eq!(<Module<TraitImpl> as OnIdle<u32>>::on_idle(1, 10), 5);
+	}
+
+	#[test]
+	fn on_runtime_upgrade_should_return_weight() {
+		assert_eq!(<Module<TraitImpl> as OnRuntimeUpgrade>::on_runtime_upgrade(), 3);
+	}
+
 	#[test]
 	#[should_panic(expected = "on_finalize")]
 	fn on_finalize_should_work() {
@@ -1788,4 +2216,13 @@ mod tests {
 			DispatchInfo { weight: 10, class: DispatchClass::Normal },
 		);
 	}
+
+	#[test]
+	fn call_names_are_known() {
+		assert_eq!(Call::<TraitImpl>::aux_3().get_call_name(), "aux_3");
+		assert_eq!(
+			OuterCall::Test(Call::<TraitImpl>::operational()).get_call_metadata(),
+			CallMetadata { module_name: "Test", function_name: "operational" },
+		);
+	}
 }
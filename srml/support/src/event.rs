@@ -76,6 +76,28 @@ pub use srml_metadata::{EventMetadata, DecodeDifferent, OuterEventMetadata, FnEn
 ///
 /// The syntax for generic events requires the `where`.
 ///
+/// Event arguments can be any type, including tuples, arrays and types that use the generic
+/// parameters:
+///
+/// ```rust
+/// trait Trait {
+///     type AccountId;
+///     type Balance;
+/// }
+///
+/// srml_support::decl_event!(
+///    pub enum Event<T> where
+///       <T as Trait>::AccountId,
+///       Balance = <T as Trait>::Balance,
+///    {
+///       Transfer(AccountId, AccountId, Balance),
+///       Batch(Vec<(AccountId, Balance)>, [u8; 4]),
+///       Noted(Option<Balance>,),
+///    }
+/// );
+///# fn main() {}
+/// ```
+///
 /// # Generic Event with Instance Example:
 ///
 /// ```rust
@@ -102,7 +124,7 @@ pub use srml_metadata::{EventMetadata, DecodeDifferent, OuterEventMetadata, FnEn
 macro_rules! decl_event {
 	(
 		$(#[$attr:meta])*
-		pub enum Event<$evt_generic_param:ident $(, $instance:ident $(: $instantiable:ident)? $( = $event_default_instance:path)? )?> where
+		pub enum Event<$evt_generic_param:ident $(, $instance:ident $(: $instantiable:path)? $( = $event_default_instance:path)? )?> where
 			$( $tt:tt )*
 	) => {
 		$crate::__decl_generic_event!(
@@ -297,7 +319,7 @@ macro_rules! __events_to_metadata {
 	(
 		$( $metadata:expr ),*;
 		$( #[doc = $doc_attr:tt] )*
-		$event:ident $( ( $( $param:path ),* ) )*,
+		$event:ident $( ( $( $param:ty ),* $(,)? ) )*,
 		$( $rest:tt )*
 	) => {
 		$crate::__events_to_metadata!(
@@ -676,6 +698,28 @@ mod tests {
 		);
 	}
 
+	mod event_module6 {
+		pub trait Instance {}
+		pub struct DefaultInstance;
+		impl Instance for DefaultInstance {}
+
+		pub trait Trait<I: Instance = DefaultInstance> {
+			type Balance;
+			type AccountId;
+		}
+
+		decl_event!(
+			/// Event with complex argument types and an instance.
+			pub enum Event<T, I: self::Instance = DefaultInstance> where
+				<T as Trait<I>>::AccountId,
+				Balance = <T as Trait<I>>::Balance,
+			{
+				Transfers(Vec<(AccountId, Balance)>, [u8; 4]),
+				Noted(Option<Balance>, (u32, u64),),
+			}
+		);
+	}
+
 	#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize)]
 	pub struct TestRuntime;
 
@@ -788,6 +832,25 @@ mod tests {
 		])
 	};
 
+	#[test]
+	fn event_metadata_supports_complex_types() {
+		assert_eq!(
+			event_module6::RawEvent::<u32, u64, event_module6::DefaultInstance>::metadata(),
+			&[
+				EventMetadata {
+					name: DecodeDifferent::Encode("Transfers"),
+					arguments: DecodeDifferent::Encode(&[ "Vec<(AccountId, Balance)>", "[u8; 4]" ]),
+					documentation: DecodeDifferent::Encode(&[]),
+				},
+				EventMetadata {
+					name: DecodeDifferent::Encode("Noted"),
+					arguments: DecodeDifferent::Encode(&[ "Option<Balance>", "(u32, u64)" ]),
+					documentation: DecodeDifferent::Encode(&[]),
+				},
+			][..],
+		);
+	}
+
 	#[test]
 	fn outer_event_metadata() {
 		assert_eq!(EXPECTED_METADATA, TestRuntime::outer_event_metadata());
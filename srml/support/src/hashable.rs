@@ -17,9 +17,9 @@
 //! Hashable trait.
 
 use crate::codec::Codec;
-use runtime_io::{blake2_128, blake2_256, twox_128, twox_256};
+use runtime_io::{blake2_128, blake2_256, twox_128, twox_256, keccak_256, sha2_256};
 use crate::storage::hashed::generator::StorageHasher;
-use crate::Twox64Concat;
+use crate::{Twox64Concat, Blake2_128Concat};
 use crate::rstd::prelude::Vec;
 
 // This trait must be kept coherent with srml-support-procedural HasherKind usage
@@ -29,6 +29,9 @@ pub trait Hashable: Sized {
 	fn twox_128(&self) -> [u8; 16];
 	fn twox_256(&self) -> [u8; 32];
 	fn twox_64_concat(&self) -> Vec<u8>;
+	fn blake2_128_concat(&self) -> Vec<u8>;
+	fn keccak_256(&self) -> [u8; 32];
+	fn sha2_256(&self) -> [u8; 32];
 }
 
 impl<T: Codec> Hashable for T {
@@ -47,4 +50,13 @@ impl<T: Codec> Hashable for T {
 	fn twox_64_concat(&self) -> Vec<u8> {
 		self.using_encoded(Twox64Concat::hash)
 	}
+	fn blake2_128_concat(&self) -> Vec<u8> {
+		self.using_encoded(Blake2_128Concat::hash)
+	}
+	fn keccak_256(&self) -> [u8; 32] {
+		self.using_encoded(keccak_256)
+	}
+	fn sha2_256(&self) -> [u8; 32] {
+		self.using_encoded(sha2_256)
+	}
 }
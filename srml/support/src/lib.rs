@@ -32,10 +32,12 @@ pub use codec;
 pub use once_cell;
 #[doc(hidden)]
 pub use paste;
+#[doc(hidden)]
+pub use runtime_io;
 pub use sr_primitives as runtime_primitives;
 
 pub use self::storage::hashed::generator::{
-	HashedStorage, Twox256, Twox128, Blake2_256, Blake2_128, Twox64Concat
+	HashedStorage, Twox256, Twox128, Blake2_256, Blake2_128, Twox64Concat, Blake2_128Concat, Identity
 };
 pub use self::storage::unhashed::generator::UnhashedStorage;
 
@@ -58,9 +60,12 @@ pub mod inherent;
 pub mod unsigned;
 mod double_map;
 pub mod traits;
+#[macro_use]
+pub mod debug;
 
 pub use self::storage::{
-	StorageValue, StorageMap, EnumerableStorageMap, StorageDoubleMap, AppendableStorageMap
+	StorageValue, StorageMap, EnumerableStorageMap, StorageDoubleMap, AppendableStorageMap,
+	IterableStorageMap,
 };
 pub use self::hashable::Hashable;
 pub use self::dispatch::{Parameter, Dispatchable, Callable, IsSubType};
@@ -83,6 +88,18 @@ pub use runtime_primitives::ConsensusEngineId;
 ///   type Parameter = Argument;
 /// }
 /// ```
+///
+/// A parameter can also be backed by storage, so that it can be changed at runtime (e.g. by
+/// governance) without a runtime upgrade. The given value is used as long as none was set:
+///
+/// ```no_compile
+/// parameter_types! {
+///   pub storage Argument: u64 = 42;
+/// }
+/// Argument::set(&43);
+/// ```
+///
+/// The value is stored under `twox_128(":Argument:")`.
 #[macro_export]
 macro_rules! parameter_types {
 	(
@@ -95,7 +112,40 @@ macro_rules! parameter_types {
 		$crate::parameter_types!{IMPL $name , $type , $value}
 		$crate::parameter_types!{ $( $rest )* }
 	);
+	(
+		$( #[ $attr:meta ] )*
+		$vis:vis storage $name:ident: $type:ty = $value:expr;
+		$( $rest:tt )*
+	) => (
+		$( #[ $attr ] )*
+		$vis struct $name;
+		$crate::parameter_types!{IMPL_STORAGE $name , $type , $value}
+		$crate::parameter_types!{ $( $rest )* }
+	);
 	() => ();
+	(IMPL_STORAGE $name:ident , $type:ty , $value:expr) => {
+		impl $name {
+			/// Returns the storage key of this parameter.
+			pub fn key() -> [u8; 16] {
+				$crate::runtime_io::twox_128(concat!(":", stringify!($name), ":").as_bytes())
+			}
+
+			/// Store a new value for this parameter.
+			pub fn set(value: &$type) {
+				$crate::storage::unhashed::put(&Self::key(), value);
+			}
+
+			/// Returns the stored value, or the default one if none was set.
+			pub fn get() -> $type {
+				$crate::storage::unhashed::get_or(&Self::key(), $value)
+			}
+		}
+		impl<I: From<$type>> $crate::traits::Get<I> for $name {
+			fn get() -> I {
+				I::from(Self::get())
+			}
+		}
+	};
 	(IMPL $name:ident , $type:ty , $value:expr) => {
 		impl $name {
 			fn get() -> $type {
@@ -277,7 +327,7 @@ mod tests {
 		trait Store for Module<T: Trait> as Example {
 			pub Data get(data) build(|_| vec![(15u32, 42u64)]): linked_map hasher(twox_64_concat) u32 => u64;
 			pub GenericData get(generic_data): linked_map hasher(twox_128) T::BlockNumber => T::BlockNumber;
-			pub GenericData2 get(generic_data2): linked_map T::BlockNumber => Option<T::BlockNumber>;
+			pub GenericData2 get(fn generic_data2): linked_map T::BlockNumber => Option<T::BlockNumber>;
 
 			pub DataDM config(test_config) build(|_| vec![(15u32, 16u32, 42u64)]):
 				double_map hasher(twox_64_concat) u32, blake2_256(u32) => u64;
@@ -377,6 +427,26 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn try_get_distinguishes_missing_from_default() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(Map::try_get(&15u32), Ok(42u64));
+			assert_eq!(Map::try_get(&17u32), Err(()));
+			Map::insert(17u32, 0u64);
+			assert!(Map::exists(&17u32));
+			assert_eq!(Map::try_get(&17u32), Ok(0u64));
+
+			assert_eq!(DataDM::try_get(&15u32, &16u32), Ok(42u64));
+			assert_eq!(DataDM::try_get(&15u32, &17u32), Err(()));
+
+			// getters declared with `get(fn ..)` return the `Option` of the storage type.
+			assert_eq!(Module::<Test>::generic_data2(5), None);
+			GenericData2::<Test>::insert(5, 6);
+			assert_eq!(Module::<Test>::generic_data2(5), Some(6));
+			assert_eq!(GenericData2::<Test>::try_get(5), Ok(6));
+		});
+	}
+
 	#[test]
 	fn double_map_basic_insert_remove_remove_prefix_should_work() {
 		with_externalities(&mut new_test_ext(), || {
@@ -539,4 +609,27 @@ mod tests {
 		let metadata = Module::<Test>::store_metadata_functions();
 		assert_eq!(EXPECTED_METADATA, metadata);
 	}
+
+	parameter_types! {
+		pub const ConstParameter: u32 = 10;
+		pub storage StorageParameter: u64 = 42;
+	}
+
+	#[test]
+	fn parameter_types_work() {
+		use crate::traits::Get;
+
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(ConstParameter::get(), 10u32);
+			assert_eq!(<ConstParameter as Get<u64>>::get(), 10);
+
+			assert_eq!(<StorageParameter as Get<u64>>::get(), 42);
+			StorageParameter::set(&43);
+			assert_eq!(<StorageParameter as Get<u128>>::get(), 43);
+			assert_eq!(
+				storage::unhashed::get::<u64>(&runtime_io::twox_128(b":StorageParameter:")),
+				Some(43),
+			);
+		});
+	}
 }
@@ -19,6 +19,13 @@
 
 /// Constructs an Origin type for a runtime. This is usually called automatically by the
 /// construct_runtime macro. See also __create_decl_macro.
+///
+/// Each listed module must expose an `Origin` type (optionally generic over the runtime and/or
+/// an instance). The outer origin gets a variant per module, and can be converted:
+///
+/// - from the system and module origins with `From`;
+/// - back into them with `Into<Result<ModuleOrigin, Outer>>` or `TryInto<ModuleOrigin>`, which
+///   return the outer origin unchanged if it is of another variant.
 #[macro_export]
 macro_rules! impl_outer_origin {
 
@@ -189,6 +196,12 @@ macro_rules! impl_outer_origin {
 				}
 			}
 		}
+		impl $crate::rstd::convert::TryFrom<$name> for $system::Origin<$runtime> {
+			type Error = $name;
+			fn try_from(x: $name) -> $crate::rstd::result::Result<Self, $name> {
+				x.into()
+			}
+		}
 		impl From<Option<<$runtime as $system::Trait>::AccountId>> for $name {
 			fn from(x: Option<<$runtime as $system::Trait>::AccountId>) -> Self {
 				<$system::Origin<$runtime>>::from(x).into()
@@ -196,19 +209,19 @@ macro_rules! impl_outer_origin {
 		}
 		$(
 			$crate::paste::item! {
-				impl From<$module::Origin < $( $generic )? $(, $module::$generic_instance )? > > for $name {
-					fn from(x: $module::Origin < $( $generic )? $(, $module::$generic_instance )? >) -> Self {
+				impl From<$module::Origin < $( $generic, )? $( $module::$generic_instance )? > > for $name {
+					fn from(x: $module::Origin < $( $generic, )? $( $module::$generic_instance )? >) -> Self {
 						$name::[< $module $( _ $generic_instance )? >](x)
 					}
 				}
 				impl Into<
 					$crate::rstd::result::Result<
-						$module::Origin < $( $generic )? $(, $module::$generic_instance )? >,
+						$module::Origin < $( $generic, )? $( $module::$generic_instance )? >,
 						$name,
 					>>
 				for $name {
 					fn into(self) -> $crate::rstd::result::Result<
-						$module::Origin < $( $generic )? $(, $module::$generic_instance )? >,
+						$module::Origin < $( $generic, )? $( $module::$generic_instance )? >,
 						Self,
 					> {
 						if let $name::[< $module $( _ $generic_instance )? >](l) = self {
@@ -218,6 +231,14 @@ macro_rules! impl_outer_origin {
 						}
 					}
 				}
+				impl $crate::rstd::convert::TryFrom<$name>
+					for $module::Origin < $( $generic, )? $( $module::$generic_instance )? >
+				{
+					type Error = $name;
+					fn try_from(x: $name) -> $crate::rstd::result::Result<Self, $name> {
+						x.into()
+					}
+				}
 			}
 		)*
 	}
@@ -257,10 +278,26 @@ mod tests {
 	mod origin_with_generic {
 		#[derive(Clone, PartialEq, Eq, Debug)]
 		pub struct Origin<T> {
-			t: T
+			pub t: T
 		}
 	}
 
+	mod origin_with_instance {
+		pub trait Instance {}
+
+		#[derive(Clone, PartialEq, Eq, Debug)]
+		pub struct Instance1;
+		impl Instance for Instance1 {}
+
+		#[derive(Clone, PartialEq, Eq, Debug)]
+		pub enum RawOrigin<I> {
+			Members(u32, u32),
+			_Phantom(std::marker::PhantomData<I>),
+		}
+
+		pub type Origin<I> = RawOrigin<I>;
+	}
+
 	#[derive(Clone, PartialEq, Eq, Debug)]
 	pub struct TestRuntime;
 
@@ -299,4 +336,36 @@ mod tests {
 	impl_outer_origin!(
 		pub enum OriginEmpty for TestRuntime where system = system {}
 	);
+
+	impl_outer_origin!(
+		pub enum OriginWithInstance for TestRuntime where system = system {
+			origin_with_generic<T>,
+			origin_with_instance Instance1
+		}
+	);
+
+	#[test]
+	fn origin_conversions_work() {
+		use std::convert::TryInto;
+
+		let members = origin_with_instance::RawOrigin::<origin_with_instance::Instance1>::Members(2, 3);
+		let outer: OriginWithInstance = members.clone().into();
+		assert_eq!(outer.clone().try_into(), Ok(members));
+		assert_eq!(
+			TryInto::<system::Origin<TestRuntime>>::try_into(outer.clone()),
+			Err(outer),
+		);
+
+		let outer: OriginWithInstance = origin_with_generic::Origin { t: TestRuntime }.into();
+		assert_eq!(
+			outer.clone().try_into(),
+			Ok(origin_with_generic::Origin { t: TestRuntime }),
+		);
+
+		assert_eq!(OriginWithInstance::ROOT.try_into(), Ok(system::RawOrigin::<u32>::Root));
+		assert_eq!(
+			TryInto::<system::Origin<TestRuntime>>::try_into(OriginWithInstance::signed(1)),
+			Ok(system::RawOrigin::Signed(1)),
+		);
+	}
 }
@@ -69,6 +69,23 @@
 ///                             inherent.
 /// - `ValidateUnsigned`      - If the module validates unsigned extrinsics.
 ///
+/// # Hooks
+///
+/// Two tuples of all modules with a `Module` part are generated: `AllModules` lists them in
+/// declaration order and `AllModulesReversed` in reverse declaration order. `System` is not part
+/// of them, since its hooks are called by `Executive` directly. These tuples are meant to be
+/// given to `Executive`, which dispatches `on_initialize`, `on_idle` and `offchain_worker` to the
+/// modules in tuple order and `on_finalize` in reverse tuple order.
+///
+/// To exclude some modules from the hooks or to call them in a different order, give `Executive`
+/// a tuple of the wanted modules instead:
+///
+/// ```nocompile
+/// pub type Executive = executive::Executive<
+///     Runtime, Block, Context, Runtime, (Timestamp, Balances, Staking)
+/// >;
+/// ```
+///
 /// # Note
 ///
 /// The population of the genesis storage depends on the order of modules. So, if one of your
@@ -477,10 +494,34 @@ macro_rules! __decl_all_modules {
 		$(
 			pub type $parsed_name = $parsed_module::Module<$runtime $(, $parsed_module::$instance )?>;
 		)*
-		type AllModules = ( $( $parsed_name, )* );
+		/// All modules included in the runtime (except `System`), in declaration order.
+		pub type AllModules = ( $( $parsed_name, )* );
+		$crate::__decl_all_modules_reversed!({ $( $parsed_name, )* } {});
 	}
 }
 
+/// A macro that defines `AllModulesReversed`, the tuple of all modules in reverse order.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __decl_all_modules_reversed {
+	(
+		{ $first:ident, $( $rest:ident, )* }
+		{ $( $reversed:ident, )* }
+	) => {
+		$crate::__decl_all_modules_reversed!(
+			{ $( $rest, )* }
+			{ $first, $( $reversed, )* }
+		);
+	};
+	(
+		{}
+		{ $( $reversed:ident, )* }
+	) => {
+		/// All modules included in the runtime (except `System`), in reverse declaration order.
+		pub type AllModulesReversed = ( $( $reversed, )* );
+	};
+}
+
 /// A macro that defines the Call enum to represent calls to functions in the modules included
 /// in the runtime (by wrapping the values of all FooModule::Call enums).
 #[macro_export]
@@ -0,0 +1,324 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Typed aliases for arbitrary storage items.
+
+/// Declare typed accessors for storage items that are not (or no longer) declared by the current
+/// module.
+///
+/// This is primarily meant for storage migrations and for reading another module's storage
+/// without depending on it. The alias only needs the full prefix of the item
+/// (`"$module_prefix $item_name"`) and its types, and derives the storage keys as `decl_storage!`
+/// does for values and (non-linked) maps:
+///
+/// - values are stored at `twox_128(prefix)`;
+/// - maps with a `twox_64_concat` or `blake2_128_concat` hasher are stored at
+///   `twox_128(prefix) ++ hasher(encoded_key)`, and the alias implements `IterableStorageMap`
+///   for them;
+/// - maps with any other hasher are stored at `hasher(prefix ++ encoded_key)`.
+///
+/// As in `decl_storage!`, the hasher of a map defaults to `blake2_256` and an `Option<_>` value
+/// type makes the query return `None` for missing entries, while any other value type returns
+/// its default.
+///
+/// ```nocompile
+/// storage_alias! {
+/// 	/// The value `Foo` of the `Example` module.
+/// 	pub OldFoo: "Example Foo" => Option<u32>;
+/// 	/// The map `Balances` of the `Example` module, keyed by account. It is iterable, as it uses
+/// 	/// a concat hasher.
+/// 	pub OldBalances<T: Trait>: "Example Balances" => map hasher(blake2_128_concat) T::AccountId => u64;
+/// }
+///
+/// fn migrate<T: Trait>() {
+/// 	if let Some(foo) = OldFoo::take() {
+/// 		Foo::put(foo);
+/// 	}
+/// }
+/// ```
+///
+/// The generated types implement the `StorageValue` and `StorageMap` traits, so the usual
+/// `get`, `insert`, `take`, `mutate`, ... functions are available on them.
+#[macro_export]
+macro_rules! storage_alias {
+	() => {};
+	(
+		$(#[$attr:meta])*
+		$vis:vis $name:ident $( < $t:ident : $bound:path > )? : $prefix:expr =>
+			map hasher($hasher:ident) $key:ty => Option<$value:ty>;
+		$( $rest:tt )*
+	) => {
+		$crate::storage_alias! {
+			@map
+			$(#[$attr])* $vis $name { $( $t : $bound )? } $prefix,
+			hasher($hasher) $key => $value, option
+		}
+		$crate::storage_alias! { $( $rest )* }
+	};
+	(
+		$(#[$attr:meta])*
+		$vis:vis $name:ident $( < $t:ident : $bound:path > )? : $prefix:expr =>
+			map hasher($hasher:ident) $key:ty => $value:ty;
+		$( $rest:tt )*
+	) => {
+		$crate::storage_alias! {
+			@map
+			$(#[$attr])* $vis $name { $( $t : $bound )? } $prefix,
+			hasher($hasher) $key => $value, default
+		}
+		$crate::storage_alias! { $( $rest )* }
+	};
+	(
+		$(#[$attr:meta])*
+		$vis:vis $name:ident $( < $t:ident : $bound:path > )? : $prefix:expr =>
+			map $key:ty => Option<$value:ty>;
+		$( $rest:tt )*
+	) => {
+		$crate::storage_alias! {
+			@map
+			$(#[$attr])* $vis $name { $( $t : $bound )? } $prefix,
+			hasher(blake2_256) $key => $value, option
+		}
+		$crate::storage_alias! { $( $rest )* }
+	};
+	(
+		$(#[$attr:meta])*
+		$vis:vis $name:ident $( < $t:ident : $bound:path > )? : $prefix:expr =>
+			map $key:ty => $value:ty;
+		$( $rest:tt )*
+	) => {
+		$crate::storage_alias! {
+			@map
+			$(#[$attr])* $vis $name { $( $t : $bound )? } $prefix,
+			hasher(blake2_256) $key => $value, default
+		}
+		$crate::storage_alias! { $( $rest )* }
+	};
+	(
+		$(#[$attr:meta])*
+		$vis:vis $name:ident $( < $t:ident : $bound:path > )? : $prefix:expr => Option<$value:ty>;
+		$( $rest:tt )*
+	) => {
+		$crate::storage_alias! {
+			@value
+			$(#[$attr])* $vis $name { $( $t : $bound )? } $prefix => $value, option
+		}
+		$crate::storage_alias! { $( $rest )* }
+	};
+	(
+		$(#[$attr:meta])*
+		$vis:vis $name:ident $( < $t:ident : $bound:path > )? : $prefix:expr => $value:ty;
+		$( $rest:tt )*
+	) => {
+		$crate::storage_alias! {
+			@value
+			$(#[$attr])* $vis $name { $( $t : $bound )? } $prefix => $value, default
+		}
+		$crate::storage_alias! { $( $rest )* }
+	};
+	(
+		@value
+		$(#[$attr:meta])* $vis:vis $name:ident { $( $t:ident : $bound:path )? } $prefix:expr =>
+			$value:ty, $query:ident
+	) => {
+		$(#[$attr])*
+		$vis struct $name<$( $t: $bound )?>($crate::rstd::marker::PhantomData<($( $t, )?)>);
+
+		impl<$( $t: $bound )?> $crate::storage::hashed::generator::StorageValue<$value>
+			for $name<$( $t )?>
+		{
+			type Query = $crate::storage_alias!(@query $query $value);
+
+			fn key() -> &'static [u8] {
+				$prefix.as_bytes()
+			}
+
+			fn get<S: $crate::HashedStorage<$crate::Twox128>>(storage: &S) -> Self::Query {
+				let value = storage.get(
+					<Self as $crate::storage::hashed::generator::StorageValue<$value>>::key()
+				);
+				$crate::storage_alias!(@from_storage $query value)
+			}
+
+			fn take<S: $crate::HashedStorage<$crate::Twox128>>(storage: &mut S) -> Self::Query {
+				let value = storage.take(
+					<Self as $crate::storage::hashed::generator::StorageValue<$value>>::key()
+				);
+				$crate::storage_alias!(@from_storage $query value)
+			}
+
+			fn mutate<R, F, S>(f: F, storage: &mut S) -> R
+			where
+				F: FnOnce(&mut Self::Query) -> R,
+				S: $crate::HashedStorage<$crate::Twox128>,
+			{
+				let mut val = <Self as $crate::storage::hashed::generator::StorageValue<$value>>::get(storage);
+
+				let ret = f(&mut val);
+				$crate::storage_alias!(
+					@put $query val,
+					|v| <Self as $crate::storage::hashed::generator::StorageValue<$value>>::put(v, storage),
+					|| <Self as $crate::storage::hashed::generator::StorageValue<$value>>::kill(storage)
+				);
+				ret
+			}
+		}
+	};
+	(
+		@map
+		$(#[$attr:meta])* $vis:vis $name:ident { $( $t:ident : $bound:path )? } $prefix:expr,
+			hasher(twox_64_concat) $key:ty => $value:ty, $query:ident
+	) => {
+		$crate::storage_alias! {
+			@map_impl
+			$(#[$attr])* $vis $name { $( $t : $bound )? } $prefix,
+			[ $crate::Identity ] iterable [ $crate::Twox64Concat ] $key => $value, $query
+		}
+	};
+	(
+		@map
+		$(#[$attr:meta])* $vis:vis $name:ident { $( $t:ident : $bound:path )? } $prefix:expr,
+			hasher(blake2_128_concat) $key:ty => $value:ty, $query:ident
+	) => {
+		$crate::storage_alias! {
+			@map_impl
+			$(#[$attr])* $vis $name { $( $t : $bound )? } $prefix,
+			[ $crate::Identity ] iterable [ $crate::Blake2_128Concat ] $key => $value, $query
+		}
+	};
+	(
+		@map
+		$(#[$attr:meta])* $vis:vis $name:ident { $( $t:ident : $bound:path )? } $prefix:expr,
+			hasher($hasher:ident) $key:ty => $value:ty, $query:ident
+	) => {
+		$crate::storage_alias! {
+			@map_impl
+			$(#[$attr])* $vis $name { $( $t : $bound )? } $prefix,
+			[ $crate::storage_alias!(@hasher $hasher) ] plain [] $key => $value, $query
+		}
+	};
+	(
+		@map_impl
+		$(#[$attr:meta])* $vis:vis $name:ident { $( $t:ident : $bound:path )? } $prefix:expr,
+			[ $hasher:ty ] $layout:ident [ $( $key_hasher:ty )? ] $key:ty => $value:ty, $query:ident
+	) => {
+		$(#[$attr])*
+		$vis struct $name<$( $t: $bound )?>($crate::rstd::marker::PhantomData<($( $t, )?)>);
+
+		impl<$( $t: $bound )?> $crate::storage::hashed::generator::StorageMap<$key, $value>
+			for $name<$( $t )?>
+		{
+			type Query = $crate::storage_alias!(@query $query $value);
+
+			type Hasher = $hasher;
+
+			fn prefix() -> &'static [u8] {
+				$prefix.as_bytes()
+			}
+
+			fn key_for(x: &$key) -> $crate::rstd::vec::Vec<u8> {
+				$crate::storage_alias!(@key_for $layout [ $( $key_hasher )? ] x: $key => $value)
+			}
+
+			fn get<S: $crate::HashedStorage<$hasher>>(key: &$key, storage: &S) -> Self::Query {
+				let key = <
+					Self as $crate::storage::hashed::generator::StorageMap<$key, $value>
+				>::key_for(key);
+				let value = storage.get(&key[..]);
+				$crate::storage_alias!(@from_storage $query value)
+			}
+
+			fn take<S: $crate::HashedStorage<$hasher>>(key: &$key, storage: &mut S) -> Self::Query {
+				let key = <
+					Self as $crate::storage::hashed::generator::StorageMap<$key, $value>
+				>::key_for(key);
+				let value = storage.take(&key[..]);
+				$crate::storage_alias!(@from_storage $query value)
+			}
+
+			fn mutate<R, F, S>(key: &$key, f: F, storage: &mut S) -> R
+			where
+				F: FnOnce(&mut Self::Query) -> R,
+				S: $crate::HashedStorage<$hasher>,
+			{
+				let mut val = <
+					Self as $crate::storage::hashed::generator::StorageMap<$key, $value>
+				>::get(key, storage);
+
+				let ret = f(&mut val);
+				$crate::storage_alias!(
+					@put $query val,
+					|v| <Self as $crate::storage::hashed::generator::StorageMap<$key, $value>>::insert(
+						key, v, storage
+					),
+					|| <Self as $crate::storage::hashed::generator::StorageMap<$key, $value>>::remove(
+						key, storage
+					)
+				);
+				ret
+			}
+		}
+
+		$crate::storage_alias! {
+			@iterable_impl $layout [ $( $key_hasher )? ] $name { $( $t : $bound )? } $key => $value
+		}
+	};
+	(@key_for plain [] $x:ident : $key:ty => $value:ty) => {{
+		let mut key = <
+			Self as $crate::storage::hashed::generator::StorageMap<$key, $value>
+		>::prefix().to_vec();
+		$crate::codec::Encode::encode_to($x, &mut key);
+		key
+	}};
+	(@key_for iterable [ $key_hasher:ty ] $x:ident : $key:ty => $value:ty) => {{
+		let mut key = <
+			Self as $crate::storage::hashed::generator::IterableStorageMap<$key, $value>
+		>::final_prefix().to_vec();
+		$crate::codec::Encode::using_encoded($x, |encoded| key.extend(
+			<$key_hasher as $crate::storage::hashed::generator::StorageHasher>::hash(encoded).as_ref()
+		));
+		key
+	}};
+	(@iterable_impl plain [] $name:ident { $( $t:ident : $bound:path )? } $key:ty => $value:ty) => {};
+	(
+		@iterable_impl iterable [ $key_hasher:ty ] $name:ident { $( $t:ident : $bound:path )? }
+			$key:ty => $value:ty
+	) => {
+		impl<$( $t: $bound )?> $crate::storage::hashed::generator::IterableStorageMap<$key, $value>
+			for $name<$( $t )?>
+		{
+			type KeyHasher = $key_hasher;
+		}
+	};
+	(@hasher blake2_256) => { $crate::Blake2_256 };
+	(@hasher blake2_128) => { $crate::Blake2_128 };
+	(@hasher twox_256) => { $crate::Twox256 };
+	(@hasher twox_128) => { $crate::Twox128 };
+	(@query option $value:ty) => { Option<$value> };
+	(@query default $value:ty) => { $value };
+	(@from_storage option $value:ident) => { $value };
+	(@from_storage default $value:ident) => { $value.unwrap_or_default() };
+	(@put option $val:ident, $put:expr, $kill:expr) => {
+		match $val {
+			Some(ref val) => ($put)(val),
+			None => ($kill)(),
+		}
+	};
+	(@put default $val:ident, $put:expr, $kill:expr) => {
+		($put)(&$val)
+	};
+}
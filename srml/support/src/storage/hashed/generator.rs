@@ -46,6 +46,54 @@ fn test_twox_64_concat() {
 	assert_eq!(r.split_at(8), (&twox_128(b"foo")[..8], &b"foo"[..]))
 }
 
+/// Hash storage keys with `concat(blake2_128(key), key)`
+pub struct Blake2_128Concat;
+impl StorageHasher for Blake2_128Concat {
+	type Output = Vec<u8>;
+	fn hash(x: &[u8]) -> Vec<u8> {
+		blake2_128(x)
+			.iter()
+			.chain(x.into_iter())
+			.cloned()
+			.collect::<Vec<_>>()
+	}
+}
+
+#[test]
+fn test_blake2_128_concat() {
+	let r = Blake2_128Concat::hash(b"foo");
+	assert_eq!(r.split_at(16), (&blake2_128(b"foo")[..], &b"foo"[..]))
+}
+
+/// A storage hasher whose output contains the hashed data, so that it can be recovered.
+pub trait ReversibleStorageHasher: StorageHasher {
+	/// Get back the data that was hashed from the hash output.
+	fn reverse(x: &[u8]) -> &[u8];
+}
+
+impl ReversibleStorageHasher for Twox64Concat {
+	fn reverse(x: &[u8]) -> &[u8] {
+		if x.len() < 8 { &[] } else { &x[8..] }
+	}
+}
+
+impl ReversibleStorageHasher for Blake2_128Concat {
+	fn reverse(x: &[u8]) -> &[u8] {
+		if x.len() < 16 { &[] } else { &x[16..] }
+	}
+}
+
+/// Don't hash storage keys at all.
+///
+/// Used by storage items which compute their final storage key themselves.
+pub struct Identity;
+impl StorageHasher for Identity {
+	type Output = Vec<u8>;
+	fn hash(x: &[u8]) -> Vec<u8> {
+		x.to_vec()
+	}
+}
+
 /// Hash storage keys with blake2 128
 pub struct Blake2_128;
 impl StorageHasher for Blake2_128 {
@@ -246,7 +294,7 @@ pub trait StorageMap<K: codec::Codec, V: codec::Codec> {
 	/// Store a value under this key into the provided storage instance; this can take any reference
 	/// type that derefs to `T` (and has `Encode` implemented).
 	/// Store a value under this key into the provided storage instance.
-	fn insert_ref<Arg: ?Sized + Encode, S: HashedStorage<Twox128>>(
+	fn insert_ref<Arg: ?Sized + Encode, S: HashedStorage<Self::Hasher>>(
 		key: &K,
 		val: &Arg,
 		storage: &mut S
@@ -274,6 +322,19 @@ pub trait EnumerableStorageMap<K: codec::Codec, V: codec::Codec>: StorageMap<K,
 	) -> Box<dyn Iterator<Item = (K, V)> + 'a> where K: 'a, V: 'a;
 }
 
+/// A `StorageMap` whose entries are stored under a common prefix, so they can be iterated.
+///
+/// The storage key of an entry is `concat(twox_128(prefix), KeyHasher::hash(encoded_key))`.
+pub trait IterableStorageMap<K: codec::Codec, V: codec::Codec>: StorageMap<K, V> {
+	/// The hasher used on the encoded map keys; it must allow getting the key back.
+	type KeyHasher: ReversibleStorageHasher;
+
+	/// The storage prefix shared by all entries of the map.
+	fn final_prefix() -> [u8; 16] {
+		twox_128(Self::prefix())
+	}
+}
+
 /// A `StorageMap` with appendable entries.
 pub trait AppendableStorageMap<K: codec::Codec, V: codec::Codec>: StorageMap<K, V> {
 	/// Append the given items to the value in the storage.
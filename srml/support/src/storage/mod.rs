@@ -19,11 +19,13 @@
 use crate::rstd::prelude::*;
 use crate::rstd::borrow::Borrow;
 use codec::{Codec, Encode, Decode, KeyedVec, Input, EncodeAppend};
-use hashed::generator::{HashedStorage, StorageHasher};
+use hashed::generator::{HashedStorage, StorageHasher, Twox128};
 use unhashed::generator::UnhashedStorage;
 
 #[macro_use]
 pub mod storage_items;
+#[macro_use]
+mod alias;
 pub mod unhashed;
 pub mod hashed;
 
@@ -146,6 +148,10 @@ pub trait StorageValue<T: Codec> {
 	/// Load the value from the provided storage instance.
 	fn get() -> Self::Query;
 
+	/// Load the value from the provided storage instance, returning `Err` if it doesn't exist
+	/// instead of falling back to the default.
+	fn try_get() -> Result<T, ()>;
+
 	/// Store a value under this key into the provided storage instance.
 	fn put<Arg: Borrow<T>>(val: Arg);
 
@@ -181,6 +187,12 @@ impl<T: Codec, U> StorageValue<T> for U where U: hashed::generator::StorageValue
 	fn get() -> Self::Query {
 		U::get(&RuntimeStorage)
 	}
+	fn try_get() -> Result<T, ()> {
+		HashedStorage::<Twox128>::get(
+			&RuntimeStorage,
+			<U as hashed::generator::StorageValue<T>>::key(),
+		).ok_or(())
+	}
 	fn put<Arg: Borrow<T>>(val: Arg) {
 		U::put(val.borrow(), &mut RuntimeStorage)
 	}
@@ -220,6 +232,10 @@ pub trait StorageMap<K: Codec, V: Codec> {
 	/// Load the value associated with the given key from the map.
 	fn get<KeyArg: Borrow<K>>(key: KeyArg) -> Self::Query;
 
+	/// Load the value associated with the given key from the map, returning `Err` if it doesn't
+	/// exist instead of falling back to the default.
+	fn try_get<KeyArg: Borrow<K>>(key: KeyArg) -> Result<V, ()>;
+
 	/// Store a value to be associated with the given key from the map.
 	fn insert<KeyArg: Borrow<K>, ValArg: Borrow<V>>(key: KeyArg, val: ValArg);
 
@@ -256,6 +272,13 @@ impl<K: Codec, V: Codec, U> StorageMap<K, V> for U where U: hashed::generator::S
 		U::get(key.borrow(), &RuntimeStorage)
 	}
 
+	fn try_get<KeyArg: Borrow<K>>(key: KeyArg) -> Result<V, ()> {
+		// Linked maps store the value followed by its linkage, so decoding just the value
+		// from the front of the entry is valid for both kinds of maps.
+		let key = <U as hashed::generator::StorageMap<K, V>>::key_for(key.borrow());
+		HashedStorage::<U::Hasher>::get(&RuntimeStorage, &key).ok_or(())
+	}
+
 	fn insert<KeyArg: Borrow<K>, ValArg: Borrow<V>>(key: KeyArg, val: ValArg) {
 		U::insert(key.borrow(), val.borrow(), &mut RuntimeStorage)
 	}
@@ -320,6 +343,89 @@ impl<K: Codec, V: Codec, U> EnumerableStorageMap<K, V> for U
 	}
 }
 
+/// A strongly-typed map in storage whose entries can be iterated without any linkage overhead.
+///
+/// This is implemented by maps using a concat hasher (`twox_64_concat` or `blake2_128_concat`):
+/// all entries are stored under a common prefix and the keys can be recovered from the storage
+/// keys. The order of iteration is the order of the hashed keys.
+pub trait IterableStorageMap<K: Codec, V: Codec>: StorageMap<K, V> {
+	/// Enumerate all elements in the map.
+	fn iter() -> Box<dyn Iterator<Item = (K, V)>> where K: 'static, V: 'static;
+
+	/// Enumerate all values in the map.
+	fn iter_values() -> Box<dyn Iterator<Item = V>> where K: 'static, V: 'static;
+
+	/// Remove all elements from the map and iterate through them.
+	///
+	/// If the iterator isn't fully consumed, only the elements that were iterated are removed.
+	fn drain() -> Box<dyn Iterator<Item = (K, V)>> where K: 'static, V: 'static;
+}
+
+impl<K: Codec, V: Codec, U> IterableStorageMap<K, V> for U
+	where U: hashed::generator::IterableStorageMap<K, V>
+{
+	fn iter() -> Box<dyn Iterator<Item = (K, V)>> where K: 'static, V: 'static {
+		Box::new(PrefixIterator::<K, V, U::KeyHasher>::new(U::final_prefix().to_vec(), false))
+	}
+
+	fn iter_values() -> Box<dyn Iterator<Item = V>> where K: 'static, V: 'static {
+		Box::new(Self::iter().map(|(_, v)| v))
+	}
+
+	fn drain() -> Box<dyn Iterator<Item = (K, V)>> where K: 'static, V: 'static {
+		Box::new(PrefixIterator::<K, V, U::KeyHasher>::new(U::final_prefix().to_vec(), true))
+	}
+}
+
+/// Iterate over the entries stored under a prefix, decoding keys and values.
+///
+/// Entries that fail to decode are skipped.
+struct PrefixIterator<K, V, H> {
+	prefix: Vec<u8>,
+	previous_key: Vec<u8>,
+	drain: bool,
+	_phantom: crate::rstd::marker::PhantomData<(K, V, H)>,
+}
+
+impl<K, V, H> PrefixIterator<K, V, H> {
+	fn new(prefix: Vec<u8>, drain: bool) -> Self {
+		PrefixIterator {
+			previous_key: prefix.clone(),
+			prefix,
+			drain,
+			_phantom: Default::default(),
+		}
+	}
+}
+
+impl<K: Decode, V: Decode, H: hashed::generator::ReversibleStorageHasher> Iterator
+	for PrefixIterator<K, V, H>
+{
+	type Item = (K, V);
+
+	fn next(&mut self) -> Option<(K, V)> {
+		loop {
+			let next_key = runtime_io::next_key(&self.previous_key)
+				.filter(|key| key.starts_with(&self.prefix))?;
+			self.previous_key = next_key;
+
+			let raw_value = match unhashed::get_raw(&self.previous_key) {
+				Some(raw_value) => raw_value,
+				None => continue,
+			};
+			if self.drain {
+				unhashed::kill(&self.previous_key);
+			}
+
+			let mut key_material = H::reverse(&self.previous_key[self.prefix.len()..]);
+			match (K::decode(&mut key_material), V::decode(&mut &raw_value[..])) {
+				(Some(key), Some(value)) => return Some((key, value)),
+				_ => continue,
+			}
+		}
+	}
+}
+
 /// An implementation of a map with a two keys.
 ///
 /// It provides an important ability to efficiently remove all entries
@@ -351,6 +457,10 @@ pub trait StorageDoubleMap<K1: Codec, K2: Codec, V: Codec> {
 	/// Load the value associated with the given key from the map.
 	fn get<KArg1: Borrow<K1>, KArg2: Borrow<K2>>(k1: KArg1, k2: KArg2) -> Self::Query;
 
+	/// Load the value associated with the given key from the map, returning `Err` if it doesn't
+	/// exist instead of falling back to the default.
+	fn try_get<KArg1: Borrow<K1>, KArg2: Borrow<K2>>(k1: KArg1, k2: KArg2) -> Result<V, ()>;
+
 	/// Take the value under a key.
 	fn take<KArg1: Borrow<K1>, KArg2: Borrow<K2>>(k1: KArg1, k2: KArg2) -> Self::Query;
 
@@ -411,6 +521,11 @@ where
 		U::get(k1.borrow(), k2.borrow(), &RuntimeStorage)
 	}
 
+	fn try_get<KArg1: Borrow<K1>, KArg2: Borrow<K2>>(k1: KArg1, k2: KArg2) -> Result<V, ()> {
+		let key = <U as unhashed::generator::StorageDoubleMap<K1, K2, V>>::key_for(k1.borrow(), k2.borrow());
+		UnhashedStorage::get(&RuntimeStorage, &key).ok_or(())
+	}
+
 	fn take<KArg1: Borrow<K1>, KArg2: Borrow<K2>>(k1: KArg1, k2: KArg2) -> Self::Query {
 		U::take(k1.borrow(), k2.borrow(), &mut RuntimeStorage)
 	}
@@ -451,6 +566,47 @@ where
 	}
 }
 
+/// The outcome of a closure run by [`with_transaction`].
+pub enum TransactionOutcome<R> {
+	/// Keep the storage changes made by the closure.
+	Commit(R),
+	/// Revert the storage changes made by the closure.
+	Rollback(R),
+}
+
+/// Execute `f` in a new storage transaction, keeping or reverting its storage changes
+/// depending on the returned [`TransactionOutcome`].
+///
+/// Transactions can be nested: rolling back an inner transaction only reverts the changes
+/// made since it was started, and the changes of a committed inner transaction are still
+/// reverted if an outer transaction is rolled back.
+pub fn with_transaction<R>(f: impl FnOnce() -> TransactionOutcome<R>) -> R {
+	runtime_io::storage_start_transaction();
+	match f() {
+		TransactionOutcome::Commit(result) => {
+			runtime_io::storage_commit_transaction();
+			result
+		},
+		TransactionOutcome::Rollback(result) => {
+			runtime_io::storage_rollback_transaction();
+			result
+		},
+	}
+}
+
+/// Execute `f` in a new storage transaction which is committed if `f` returns `Ok` and
+/// rolled back if it returns `Err`.
+pub fn with_transaction_result<T, E>(f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+	with_transaction(|| {
+		let result = f();
+		if result.is_ok() {
+			TransactionOutcome::Commit(result)
+		} else {
+			TransactionOutcome::Rollback(result)
+		}
+	})
+}
+
 /// child storage NOTE could replace unhashed by having only one kind of storage (root being null storage
 /// key (storage_key can become Option<&[u8]>).
 /// This module is a currently only a variant of unhashed with additional `storage_key`.
@@ -18,7 +18,7 @@
 //!
 //! NOTE: If you're looking for `parameter_types`, it has moved in to the top-level module.
 
-use crate::rstd::{result, marker::PhantomData, ops::Div};
+use crate::rstd::{prelude::*, result, marker::PhantomData, ops::Div};
 use crate::codec::{Codec, Encode, Decode};
 use substrate_primitives::u32_trait::Value as U32;
 use crate::runtime_primitives::traits::{MaybeSerializeDebug, SimpleArithmetic, Saturating};
@@ -46,6 +46,46 @@ impl<V: PartialEq, T: Get<V>> Contains<V> for T {
 	}
 }
 
+/// A trait for deciding whether a value, typically a call, is allowed.
+///
+/// `()` allows everything and a tuple of filters only allows what all of its members allow.
+pub trait Filter<T> {
+	/// Return `true` if `t` is allowed.
+	fn filter(t: &T) -> bool;
+}
+
+macro_rules! impl_filter {
+	() => (
+		impl<T> Filter<T> for () {
+			fn filter(_: &T) -> bool { true }
+		}
+	);
+
+	( $($t:ident)* ) => {
+		impl<T, $($t: Filter<T>),*> Filter<T> for ($($t,)*) {
+			fn filter(t: &T) -> bool {
+				$( $t::filter(t) )&&*
+			}
+		}
+	}
+}
+
+for_each_tuple!(impl_filter);
+
+/// A `Filter` that denies everything.
+pub struct DenyAll;
+
+impl<T> Filter<T> for DenyAll {
+	fn filter(_: &T) -> bool { false }
+}
+
+/// A `Filter` that allows exactly what `F` denies.
+pub struct Not<F>(PhantomData<F>);
+
+impl<T, F: Filter<T>> Filter<T> for Not<F> {
+	fn filter(t: &T) -> bool { !F::filter(t) }
+}
+
 /// The account with the given id was killed.
 pub trait OnFreeBalanceZero<AccountId> {
 	/// The account was the given id was killed.
@@ -596,6 +636,33 @@ pub trait LockableCurrency<AccountId>: Currency<AccountId> {
 	);
 }
 
+/// A currency whose accounts can have liquidity restrictions which decrease linearly over time.
+pub trait VestingCurrency<AccountId>: Currency<AccountId> {
+	/// The quantity used to denote time; usually just a `BlockNumber`.
+	type Moment;
+
+	/// Get the amount that is currently being vested and cannot be transferred out of this account.
+	fn vesting_balance(who: &AccountId) -> Self::Balance;
+
+	/// Whether account `who` has a vesting schedule, even one which has fully vested.
+	fn has_vesting_schedule(who: &AccountId) -> bool;
+
+	/// Add a vesting schedule to account `who`: `locked` funds, of which `per_block` are unlocked
+	/// every block after `starting_block`.
+	///
+	/// If there already exists a vesting schedule for the given account, an `Err` is returned
+	/// and nothing is updated.
+	fn add_vesting_schedule(
+		who: &AccountId,
+		locked: Self::Balance,
+		per_block: Self::Balance,
+		starting_block: Self::Moment,
+	) -> result::Result<(), &'static str>;
+
+	/// Remove the vesting schedule of account `who`, if any.
+	fn remove_vesting_schedule(who: &AccountId);
+}
+
 bitmask! {
 	/// Reasons for moving funds out of an account.
 	#[derive(Encode, Decode)]
@@ -625,12 +692,47 @@ impl WithdrawReasons {
 }
 
 /// Trait for type that can handle incremental changes to a set of account IDs.
-pub trait ChangeMembers<AccountId> {
+pub trait ChangeMembers<AccountId: Clone + Ord> {
 	/// A number of members `_incoming` just joined the set and replaced some `_outgoing` ones. The
 	/// new set is thus given by `_new`.
 	fn change_members(_incoming: &[AccountId], _outgoing: &[AccountId], _new: &[AccountId]);
+
+	/// Set the new members; they, and `old_members`, must be sorted. The members which joined and
+	/// left the set are worked out from the two and passed on to `change_members`.
+	fn set_members_sorted(new_members: &[AccountId], old_members: &[AccountId]) {
+		let (mut incoming, mut outgoing) = (Vec::new(), Vec::new());
+		let mut old_iter = old_members.iter().peekable();
+		for new in new_members {
+			while let Some(old) = old_iter.peek() {
+				if *old < new {
+					outgoing.push((*old).clone());
+					old_iter.next();
+				} else {
+					break;
+				}
+			}
+			if old_iter.peek() == Some(&new) {
+				old_iter.next();
+			} else {
+				incoming.push(new.clone());
+			}
+		}
+		outgoing.extend(old_iter.cloned());
+		Self::change_members(&incoming[..], &outgoing[..], new_members);
+	}
 }
 
-impl<T> ChangeMembers<T> for () {
+impl<T: Clone + Ord> ChangeMembers<T> for () {
 	fn change_members(_incoming: &[T], _outgoing: &[T], _new_set: &[T]) {}
+	fn set_members_sorted(_new: &[T], _old: &[T]) {}
+}
+
+/// Trait for type that can set up the initial set of account IDs.
+pub trait InitializeMembers<AccountId> {
+	/// Initialize the members to the given `members`.
+	fn initialize_members(members: &[AccountId]);
+}
+
+impl<T> InitializeMembers<T> for () {
+	fn initialize_members(_: &[T]) {}
 }
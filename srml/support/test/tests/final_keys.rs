@@ -15,7 +15,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use runtime_io::{with_externalities, Blake2Hasher};
-use srml_support::{StorageValue, StorageMap, StorageDoubleMap};
+use srml_support::{StorageValue, StorageMap, StorageDoubleMap, IterableStorageMap};
 use srml_support::storage::unhashed;
 use parity_codec::{Encode, Decode};
 
@@ -34,6 +34,8 @@ srml_support::decl_storage!{
 
 		pub Map: map u32 => u32;
 		pub Map2: map hasher(twox_128) u32 => u32;
+		pub Map3: map hasher(twox_64_concat) u32 => u32;
+		pub Map4: map hasher(blake2_128_concat) u32 => Option<u64>;
 
 		pub LinkedMap: linked_map u32 => u32;
 		pub LinkedMap2: linked_map hasher(twox_128) u32 => u32;
@@ -97,3 +99,61 @@ fn final_keys() {
 		assert_eq!(unhashed::get::<u32>(&k), Some(3u32));
 	});
 }
+
+#[test]
+fn try_get_value() {
+	with_externalities(&mut new_test_ext(), || {
+		Value::kill();
+		assert_eq!(Value::try_get(), Err(()));
+		assert_eq!(Value::get(), 0);
+
+		Value::put(3);
+		assert_eq!(Value::try_get(), Ok(3));
+		assert_eq!(Value::take(), 3);
+		assert!(!Value::exists());
+	});
+}
+
+#[test]
+fn iterable_map_final_keys() {
+	with_externalities(&mut new_test_ext(), || {
+		Map3::insert(1, 2);
+		let mut k = runtime_io::twox_128(b"Module Map3").to_vec();
+		k.extend(&runtime_io::twox_128(&1u32.encode())[..8]);
+		k.extend(1u32.encode());
+		assert_eq!(unhashed::get::<u32>(&k), Some(2u32));
+
+		Map4::insert(1, 2);
+		let mut k = runtime_io::twox_128(b"Module Map4").to_vec();
+		k.extend(&runtime_io::blake2_128(&1u32.encode()));
+		k.extend(1u32.encode());
+		assert_eq!(unhashed::get::<u64>(&k), Some(2u64));
+	});
+}
+
+#[test]
+fn iterable_map_iter_and_drain() {
+	with_externalities(&mut new_test_ext(), || {
+		for i in 0..4u32 {
+			Map4::insert(i, i as u64 * 10);
+		}
+		// Entries of other maps are not part of the iteration.
+		Map3::insert(5, 5);
+		Map4::remove(2);
+
+		let mut entries = Map4::iter().collect::<Vec<_>>();
+		entries.sort();
+		assert_eq!(entries, vec![(0, 0), (1, 10), (3, 30)]);
+
+		let mut values = Map4::iter_values().collect::<Vec<_>>();
+		values.sort();
+		assert_eq!(values, vec![0, 10, 30]);
+
+		let mut drained = Map4::drain().collect::<Vec<_>>();
+		drained.sort();
+		assert_eq!(drained, vec![(0, 0), (1, 10), (3, 30)]);
+		assert_eq!(Map4::iter().count(), 0);
+		assert_eq!(Map4::get(1), None);
+		assert_eq!(Map3::get(5), 5);
+	});
+}
@@ -41,4 +41,17 @@ fn init_genesis_config() {
 	GenesisConfig::<Test> {
 		t: Default::default(),
 	};
-}
\ No newline at end of file
+}
+
+#[test]
+fn genesis_config_implements_build_storage() {
+	use srml_support::runtime_primitives::BuildStorage;
+
+	let config = GenesisConfig::<Test> {
+		t: vec![(1, 2, vec![3])],
+	};
+	let (top, children) = BuildStorage::build_storage(config).unwrap();
+
+	assert_eq!(top.len(), 1);
+	assert!(children.is_empty());
+}
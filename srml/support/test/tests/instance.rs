@@ -408,3 +408,21 @@ fn storage_with_instance_basic_operation() {
 		assert_eq!(DoubleMap::get(key1, key2), 0);
 	});
 }
+
+#[test]
+fn all_modules_are_in_declaration_order() {
+	fn assert_same_type<T>(_: std::marker::PhantomData<T>, _: std::marker::PhantomData<T>) {}
+
+	assert_same_type(
+		std::marker::PhantomData::<AllModules>,
+		std::marker::PhantomData::<
+			(Module1_1, Module1_2, Module2, Module2_1, Module2_2, Module2_3, Module3)
+		>,
+	);
+	assert_same_type(
+		std::marker::PhantomData::<AllModulesReversed>,
+		std::marker::PhantomData::<
+			(Module3, Module2_3, Module2_2, Module2_1, Module2, Module1_2, Module1_1)
+		>,
+	);
+}
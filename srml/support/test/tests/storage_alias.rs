@@ -0,0 +1,109 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use runtime_io::{with_externalities, Blake2Hasher};
+use srml_support::{StorageValue, StorageMap, IterableStorageMap, storage_alias};
+
+pub trait Trait {
+	type Origin;
+	type BlockNumber: parity_codec::Codec + Default;
+}
+
+srml_support::decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+}
+
+srml_support::decl_storage!{
+	trait Store for Module<T: Trait> as Example {
+		pub Value: u32;
+		pub OptionValue: Option<u64>;
+		pub Map: map u32 => u64;
+		pub GenericMap: map hasher(twox_64_concat) T::BlockNumber => Option<T::BlockNumber>;
+	}
+}
+
+struct Test;
+impl Trait for Test {
+	type Origin = u32;
+	type BlockNumber = u32;
+}
+
+storage_alias! {
+	/// Alias of `Example Value`.
+	ValueAlias: "Example Value" => u32;
+	/// Alias of `Example OptionValue`.
+	OptionValueAlias: "Example OptionValue" => Option<u64>;
+	/// Alias of `Example Map`.
+	MapAlias: "Example Map" => map u32 => u64;
+	/// Alias of `Example GenericMap`.
+	GenericMapAlias<T: Trait>: "Example GenericMap" =>
+		map hasher(twox_64_concat) T::BlockNumber => Option<T::BlockNumber>;
+}
+
+fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+	runtime_io::TestExternalities::default()
+}
+
+#[test]
+fn storage_alias_uses_same_keys_as_decl_storage() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(<ValueAlias as StorageValue<u32>>::key(), <Value as StorageValue<u32>>::key());
+		assert_eq!(MapAlias::key_for(&3), Map::key_for(&3));
+		assert_eq!(
+			GenericMapAlias::<Test>::key_for(&3),
+			GenericMap::<Test>::key_for(&3),
+		);
+	});
+}
+
+#[test]
+fn storage_alias_values_work() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(ValueAlias::get(), 0);
+		Value::put(5);
+		assert_eq!(ValueAlias::get(), 5);
+		ValueAlias::mutate(|v| *v += 1);
+		assert_eq!(Value::get(), 6);
+		assert_eq!(ValueAlias::take(), 6);
+		assert!(!Value::exists());
+
+		assert_eq!(OptionValueAlias::get(), None);
+		OptionValue::put(7);
+		assert_eq!(OptionValueAlias::get(), Some(7));
+		OptionValueAlias::mutate(|v| *v = None);
+		assert_eq!(OptionValue::get(), None);
+	});
+}
+
+#[test]
+fn storage_alias_maps_work() {
+	with_externalities(&mut new_test_ext(), || {
+		Map::insert(1, 10);
+		assert_eq!(MapAlias::get(1), 10);
+		assert_eq!(MapAlias::get(2), 0);
+		MapAlias::insert(2, 20);
+		assert_eq!(Map::get(2), 20);
+		assert_eq!(MapAlias::take(1), 10);
+		assert!(!Map::exists(1));
+
+		GenericMap::<Test>::insert(3, 30);
+		assert_eq!(GenericMapAlias::<Test>::get(3), Some(30));
+		assert_eq!(GenericMapAlias::<Test>::iter().collect::<Vec<_>>(), vec![(3, 30)]);
+		GenericMapAlias::<Test>::mutate(3, |v| *v = None);
+		assert_eq!(GenericMap::<Test>::get(3), None);
+	});
+}
@@ -0,0 +1,162 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use runtime_io::{with_externalities, Blake2Hasher};
+use srml_support::{StorageValue, StorageMap, dispatch::Result};
+use srml_support::storage::{with_transaction, TransactionOutcome};
+
+pub trait Trait {
+	type Origin;
+	type BlockNumber;
+}
+
+srml_support::decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		#[transactional]
+		fn value_commits(_origin, v: u32) -> Result {
+			Value::put(v);
+			Ok(())
+		}
+
+		#[transactional]
+		fn value_rollbacks(_origin, v: u32) -> Result {
+			Value::put(v);
+			Map::insert(v, v);
+			Err("Revert!")
+		}
+
+		#[weight = srml_support::dispatch::SimpleDispatchInfo::FixedNormal(10)]
+		#[transactional]
+		fn value_rollbacks_on_question_mark(_origin, v: u32) -> Result {
+			Value::put(v);
+			Self::fail()?;
+			Value::put(v + 1);
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	fn fail() -> Result {
+		Err("Failed")
+	}
+}
+
+srml_support::decl_storage!{
+	trait Store for Module<T: Trait> as StorageTransactions {
+		pub Value: u32;
+		pub Map: map u32 => u32;
+	}
+}
+
+struct Test;
+impl Trait for Test {
+	type Origin = u32;
+	type BlockNumber = u32;
+}
+
+fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+	runtime_io::TestExternalities::default()
+}
+
+#[test]
+fn storage_transaction_basic_commit() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(Value::get(), 0);
+
+		let result = with_transaction(|| {
+			Value::put(99);
+			Map::insert(0, 99);
+			TransactionOutcome::Commit(Value::get())
+		});
+
+		assert_eq!(result, 99);
+		assert_eq!(Value::get(), 99);
+		assert_eq!(Map::get(0), 99);
+	});
+}
+
+#[test]
+fn storage_transaction_basic_rollback() {
+	with_externalities(&mut new_test_ext(), || {
+		Value::put(1);
+
+		let result = with_transaction(|| {
+			Value::put(99);
+			Map::insert(0, 99);
+			TransactionOutcome::Rollback(Value::get())
+		});
+
+		assert_eq!(result, 99);
+		assert_eq!(Value::get(), 1);
+		assert!(!Map::exists(0));
+	});
+}
+
+#[test]
+fn storage_transaction_nested() {
+	with_externalities(&mut new_test_ext(), || {
+		with_transaction(|| {
+			Value::put(1);
+
+			// The inner rollback only reverts the inner changes.
+			with_transaction(|| {
+				Value::put(2);
+				Map::insert(2, 2);
+				TransactionOutcome::Rollback(())
+			});
+			assert_eq!(Value::get(), 1);
+			assert!(!Map::exists(2));
+
+			with_transaction(|| {
+				Map::insert(3, 3);
+				TransactionOutcome::Commit(())
+			});
+			assert_eq!(Map::get(3), 3);
+
+			TransactionOutcome::Commit(())
+		});
+
+		assert_eq!(Value::get(), 1);
+		assert_eq!(Map::get(3), 3);
+
+		// A committed inner transaction is still reverted by its outer transaction.
+		with_transaction(|| {
+			with_transaction(|| {
+				Map::insert(4, 4);
+				TransactionOutcome::Commit(())
+			});
+			TransactionOutcome::Rollback(())
+		});
+
+		assert!(!Map::exists(4));
+	});
+}
+
+#[test]
+fn transactional_annotation() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_eq!(Module::<Test>::value_commits(0, 2), Ok(()));
+		assert_eq!(Value::get(), 2);
+
+		assert_eq!(Module::<Test>::value_rollbacks(0, 3), Err("Revert!"));
+		assert_eq!(Value::get(), 2);
+		assert!(!Map::exists(3));
+
+		assert_eq!(Module::<Test>::value_rollbacks_on_question_mark(0, 4), Err("Failed"));
+		assert_eq!(Value::get(), 2);
+	});
+}
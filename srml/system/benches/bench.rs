@@ -58,6 +58,8 @@ impl_outer_event! {
 pub struct Runtime;
 impl system::Trait for Runtime {
 	type Origin = Origin;
+	type Call = ();
+	type BaseCallFilter = ();
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
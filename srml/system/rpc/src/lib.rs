@@ -0,0 +1,303 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! System module specific RPC methods.
+
+use std::{collections::HashSet, sync::Arc};
+
+use client::{blockchain::HeaderBackend, block_builder::api::BlockBuilder};
+use jsonrpc_core::{Result, Error, ErrorCode};
+use jsonrpc_derive::rpc;
+use log::debug;
+use parity_codec::{Encode, Decode, Codec};
+use primitives::Bytes;
+use sr_primitives::{
+	generic::BlockId,
+	traits::{self, ProvideRuntimeApi, SimpleArithmetic},
+};
+use transaction_pool::txpool::{self, Pool};
+
+pub use srml_system_rpc_runtime_api::AccountNonceApi;
+pub use self::gen_client::Client as SystemClient;
+
+/// System RPC methods.
+#[rpc]
+pub trait SystemApi<BlockHash, AccountId, Index> {
+	/// Returns the next valid index (aka nonce) for given account.
+	///
+	/// This method takes into consideration all pending transactions
+	/// currently in the pool and if no transactions are found in the pool
+	/// it fallbacks to query the index from the runtime (aka. state nonce).
+	#[rpc(name = "system_accountNextIndex")]
+	fn nonce(&self, account: AccountId) -> Result<Index>;
+
+	/// Applies the given encoded extrinsic on top of the given block or the best block, without
+	/// persisting any changes or broadcasting it, and returns the encoded `ApplyResult`.
+	#[rpc(name = "system_dryRun")]
+	fn dry_run(&self, extrinsic: Bytes, at: Option<BlockHash>) -> Result<Bytes>;
+}
+
+const RUNTIME_ERROR: i64 = 1;
+const DECODE_ERROR: i64 = 2;
+
+/// An implementation of System-specific RPC methods.
+pub struct System<P: txpool::ChainApi, C, B> {
+	client: Arc<C>,
+	pool: Arc<Pool<P>>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<P: txpool::ChainApi, C, B> System<P, C, B> {
+	/// Create new `System` given client and transaction pool.
+	pub fn new(client: Arc<C>, pool: Arc<Pool<P>>) -> Self {
+		System {
+			client,
+			pool,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<P, C, Block, AccountId, Index> SystemApi<<Block as traits::Block>::Hash, AccountId, Index>
+	for System<P, C, Block>
+where
+	C: ProvideRuntimeApi,
+	C: HeaderBackend<Block>,
+	C: Send + Sync + 'static,
+	C::Api: AccountNonceApi<Block, AccountId, Index>,
+	C::Api: BlockBuilder<Block>,
+	P: txpool::ChainApi + Sync + Send + 'static,
+	Block: traits::Block,
+	AccountId: Clone + std::fmt::Display + Codec,
+	Index: Clone + std::fmt::Display + Codec + Send + traits::SimpleArithmetic + 'static,
+{
+	fn nonce(&self, account: AccountId) -> Result<Index> {
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+
+		let nonce = api.account_nonce(&at, account.clone()).map_err(|e| Error {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to query nonce.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+
+		Ok(adjust_nonce(&*self.pool, account, nonce))
+	}
+
+	fn dry_run(&self, extrinsic: Bytes, at: Option<<Block as traits::Block>::Hash>) -> Result<Bytes> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let uxt: Block::Extrinsic = Decode::decode(&mut &*extrinsic).ok_or_else(|| Error {
+			code: ErrorCode::ServerError(DECODE_ERROR),
+			message: "Unable to decode extrinsic.".into(),
+			data: None,
+		})?;
+
+		// runtime calls are executed on a temporary overlay, the changes are dropped afterwards.
+		let result = api.apply_extrinsic(&at, uxt).map_err(|e| Error {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to dry run extrinsic.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+
+		Ok(result.encode().into())
+	}
+}
+
+/// Adjust account nonce from state, so that tx with the nonce will be
+/// placed after all ready and future txpool transactions of the account.
+///
+/// Only the nonces following the state nonce without gaps are taken into account,
+/// so a transaction using the returned nonce fills the first gap, if there is one.
+fn adjust_nonce<P, AccountId, Index>(
+	pool: &Pool<P>,
+	account: AccountId,
+	nonce: Index,
+) -> Index where
+	P: txpool::ChainApi,
+	AccountId: Clone + std::fmt::Display + Encode,
+	Index: Clone + std::fmt::Display + Encode + SimpleArithmetic + 'static,
+{
+	debug!(target: "rpc", "State nonce for {}: {}", account, nonce);
+	// Now we need to query the transaction pool
+	// and find transactions originating from the same sender.
+	//
+	// Since extrinsics are opaque to us, we look for them using
+	// `provides` tag. Neither ready nor future transactions are ordered
+	// by nonce, so we collect all the tags first and then increment
+	// the nonce as long as some transaction provides the current one.
+	let provides = pool.ready()
+		.chain(pool.futures())
+		.flat_map(|tx| tx.provides.clone())
+		.collect::<HashSet<_>>();
+
+	let mut current_nonce = nonce;
+	while provides.contains(&(account.clone(), current_nonce.clone()).encode()) {
+		current_nonce = current_nonce + traits::One::one();
+	}
+
+	current_nonce
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use sr_primitives::{
+		ApplyError, ApplyOutcome, ApplyResult,
+		transaction_validity::{TransactionValidity, ValidTransaction},
+	};
+	use test_client::AccountKeyring;
+	use test_runtime::{Block, Extrinsic, Transfer, AccountId, H256};
+	use txpool::{ChainApi, ExtrinsicFor, NumberFor, BlockHash};
+
+	struct TestApi;
+
+	impl ChainApi for TestApi {
+		type Block = Block;
+		type Hash = u64;
+		type Error = txpool::error::Error;
+
+		fn validate_transaction(
+			&self,
+			_at: &BlockId<Self::Block>,
+			uxt: ExtrinsicFor<Self>,
+		) -> std::result::Result<TransactionValidity, Self::Error> {
+			let transfer = uxt.transfer();
+			Ok(TransactionValidity::Valid(ValidTransaction {
+				priority: 4,
+				requires: if transfer.nonce > 0 {
+					vec![(transfer.from.clone(), transfer.nonce - 1).encode()]
+				} else {
+					vec![]
+				},
+				provides: vec![(transfer.from.clone(), transfer.nonce).encode()],
+				longevity: 3,
+				propagate: true,
+			}))
+		}
+
+		fn block_id_to_number(
+			&self,
+			at: &BlockId<Self::Block>,
+		) -> std::result::Result<Option<NumberFor<Self>>, Self::Error> {
+			Ok(match at {
+				BlockId::Number(num) => Some(*num),
+				BlockId::Hash(_) => None,
+			})
+		}
+
+		fn block_id_to_hash(
+			&self,
+			at: &BlockId<Self::Block>,
+		) -> std::result::Result<Option<BlockHash<Self>>, Self::Error> {
+			Ok(match at {
+				BlockId::Number(num) => Some(H256::from_low_u64_be(*num)),
+				BlockId::Hash(_) => None,
+			})
+		}
+
+		fn hash_and_length(&self, uxt: &ExtrinsicFor<Self>) -> (Self::Hash, usize) {
+			let len = uxt.encode().len();
+			(
+				(H256::from(uxt.transfer().from.clone()).to_low_u64_be() << 5) + uxt.transfer().nonce,
+				len
+			)
+		}
+	}
+
+	fn account(n: u64) -> AccountId {
+		AccountId::from_h256(H256::from_low_u64_be(n))
+	}
+
+	fn submit(pool: &Pool<TestApi>, from: u64, nonce: u64) {
+		let uxt = Extrinsic::Transfer(Transfer {
+			from: account(from),
+			to: account(100),
+			amount: 5,
+			nonce,
+		}, Default::default());
+		pool.submit_one(&BlockId::Number(0), uxt).unwrap();
+	}
+
+	#[test]
+	fn should_return_next_nonce_after_ready_transactions() {
+		let pool = Pool::new(Default::default(), TestApi);
+		submit(&pool, 1, 0);
+		submit(&pool, 1, 1);
+		submit(&pool, 2, 0);
+
+		assert_eq!(adjust_nonce(&pool, account(1), 0u64), 2);
+		assert_eq!(adjust_nonce(&pool, account(2), 0u64), 1);
+		assert_eq!(adjust_nonce(&pool, account(3), 0u64), 0);
+	}
+
+	#[test]
+	fn should_take_future_transactions_into_account() {
+		let pool = Pool::new(Default::default(), TestApi);
+		submit(&pool, 1, 0);
+		submit(&pool, 1, 1);
+		submit(&pool, 1, 3);
+		submit(&pool, 2, 1);
+
+		// the gap at nonce 2 is filled first.
+		assert_eq!(adjust_nonce(&pool, account(1), 0u64), 2);
+		// once it's filled, the future transaction becomes ready.
+		submit(&pool, 1, 2);
+		assert_eq!(adjust_nonce(&pool, account(1), 0u64), 4);
+		// a future transaction right after the state nonce is skipped as well.
+		assert_eq!(adjust_nonce(&pool, account(2), 1u64), 2);
+	}
+
+	fn dry_run(extrinsic: Bytes) -> Result<Bytes> {
+		let client = Arc::new(test_client::new());
+		let pool = Arc::new(Pool::new(Default::default(), transaction_pool::ChainApi::new(client.clone())));
+		let system = System::<_, _, Block>::new(client, pool);
+		SystemApi::<_, AccountId, u64>::dry_run(&system, extrinsic, None)
+	}
+
+	fn transfer(amount: u64, nonce: u64) -> Extrinsic {
+		Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount,
+			nonce,
+		}.into_signed_tx()
+	}
+
+	#[test]
+	fn dry_run_should_return_the_result_of_applying_the_extrinsic() {
+		let result = dry_run(transfer(5, 0).encode().into()).unwrap();
+		assert_eq!(ApplyResult::decode(&mut &*result), Some(Ok(ApplyOutcome::Success)));
+
+		// the transfer hasn't been persisted, so it can be dry run again.
+		let result = dry_run(transfer(5, 0).encode().into()).unwrap();
+		assert_eq!(ApplyResult::decode(&mut &*result), Some(Ok(ApplyOutcome::Success)));
+
+		let result = dry_run(transfer(5, 1).encode().into()).unwrap();
+		assert_eq!(ApplyResult::decode(&mut &*result), Some(Err(ApplyError::Stale)));
+		let result = dry_run(transfer(10_000, 0).encode().into()).unwrap();
+		assert_eq!(ApplyResult::decode(&mut &*result), Some(Err(ApplyError::CantPay)));
+	}
+
+	#[test]
+	fn dry_run_should_reject_invalid_extrinsics() {
+		let error = dry_run(vec![0xff, 0xff].into()).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(DECODE_ERROR));
+	}
+}
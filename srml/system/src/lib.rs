@@ -88,7 +88,7 @@ use primitives::traits::{self, CheckEqual, SimpleArithmetic, Zero, SignedExtensi
 use substrate_primitives::storage::well_known_keys;
 use srml_support::{
 	storage, decl_module, decl_event, decl_storage, StorageDoubleMap, StorageValue, StorageMap,
-	Parameter, for_each_tuple, traits::{Contains, Get}
+	Parameter, for_each_tuple, traits::{Contains, Get, Filter}
 };
 use safe_mix::TripletMix;
 use parity_codec::{Encode, Decode};
@@ -99,6 +99,8 @@ use runtime_io::{twox_128, TestExternalities, Blake2Hasher};
 #[cfg(any(feature = "std", test))]
 use substrate_primitives::ChangesTrieConfiguration;
 
+pub mod offchain;
+
 /// Handler for when a new account has been created.
 pub trait OnNewAccount<AccountId> {
 	/// A new account `who` has been registered.
@@ -150,6 +152,18 @@ pub trait Trait: 'static + Eq + Clone {
 	/// The aggregated `Origin` type used by dispatchable calls.
 	type Origin: Into<Result<RawOrigin<Self::AccountId>, Self::Origin>> + From<RawOrigin<Self::AccountId>>;
 
+	/// The aggregated `Call` type of the runtime.
+	type Call;
+
+	/// The filter that every call must pass before it is dispatched by `Executive`.
+	///
+	/// Calls that are not allowed fail without being dispatched, after the fees of their
+	/// extrinsic were paid. Use `()` to allow all calls.
+	///
+	/// Only the calls of extrinsics are filtered: calls which a module dispatches itself, e.g.
+	/// through `sudo` or a democracy proposal, are not.
+	type BaseCallFilter: Filter<Self::Call>;
+
 	/// Account index (aka nonce) type. This stores the number of previous transactions associated with a sender
 	/// account.
 	type Index:
@@ -229,10 +243,28 @@ decl_module! {
 			storage::unhashed::put_raw(well_known_keys::HEAP_PAGES, &pages.encode());
 		}
 
-		/// Set the new code.
-		pub fn set_code(origin, new: Vec<u8>) {
+		/// Set the new runtime code.
+		///
+		/// The new code must report the same `spec_name` and a higher `spec_version` than the
+		/// current runtime code.
+		pub fn set_code(origin, code: Vec<u8>) {
+			ensure_root(origin)?;
+			Self::can_set_code(&code)?;
+
+			storage::unhashed::put_raw(well_known_keys::CODE, &code);
+			RuntimeUpgraded::put(true);
+			Self::deposit_event(Event::CodeUpdated.into());
+		}
+
+		/// Set the new runtime code without doing any checks of the given `code`.
+		///
+		/// Only meant for emergencies, e.g. when the current runtime can no longer report its
+		/// version.
+		pub fn set_code_without_checks(origin, code: Vec<u8>) {
 			ensure_root(origin)?;
-			storage::unhashed::put_raw(well_known_keys::CODE, &new);
+			storage::unhashed::put_raw(well_known_keys::CODE, &code);
+			RuntimeUpgraded::put(true);
+			Self::deposit_event(Event::CodeUpdated.into());
 		}
 
 		/// Set some items of storage.
@@ -282,9 +314,29 @@ decl_event!(
 		ExtrinsicSuccess,
 		/// An extrinsic failed.
 		ExtrinsicFailed,
+		/// The runtime code was updated.
+		CodeUpdated,
 	}
 );
 
+/// The leading fields of an encoded `RuntimeVersion`, as needed to check a runtime upgrade.
+///
+/// `RuntimeVersion` itself can't be decoded without `std`; the remaining fields are ignored.
+#[derive(Decode)]
+struct SpecVersion {
+	spec_name: Vec<u8>,
+	_impl_name: Vec<u8>,
+	_authoring_version: u32,
+	spec_version: u32,
+}
+
+impl SpecVersion {
+	/// Reads the version reported by the given runtime code.
+	fn of_code(code: &[u8]) -> Option<Self> {
+		runtime_io::runtime_version(code).and_then(|version| Self::decode(&mut &version[..]))
+	}
+}
+
 /// Origin for the System module.
 #[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -332,6 +384,9 @@ decl_storage! {
 		pub AccountNonce get(account_nonce): map T::AccountId => T::Index;
 		/// Total extrinsics count for the current block.
 		ExtrinsicCount: Option<u32>;
+		/// True if the runtime code was updated and the new runtime didn't run its
+		/// `on_runtime_upgrade` hooks yet.
+		pub RuntimeUpgraded get(runtime_upgraded): bool;
 		/// Total weight for all extrinsics put together, for the current block.
 		AllExtrinsicsWeight: Option<Weight>;
 		/// Total length (in bytes) for all extrinsics put together, for the current block.
@@ -564,6 +619,21 @@ impl<T: Trait> Module<T> {
 		AllExtrinsicsLen::get().unwrap_or_default()
 	}
 
+	/// Returns whether the runtime code was updated since the last call, and resets the flag.
+	///
+	/// Used by the executive to run `on_runtime_upgrade` once in the new runtime.
+	pub fn take_runtime_upgraded() -> bool {
+		RuntimeUpgraded::take()
+	}
+
+	/// Inform the system module of some additional weight that should be accounted for in the
+	/// current block, such as the weight consumed by block hooks.
+	///
+	/// NOTE: this does not check the weight against `MaximumBlockWeight`, it only saturates.
+	pub fn register_extra_weight_unchecked(weight: Weight) {
+		AllExtrinsicsWeight::put(Self::all_extrinsics_weight().saturating_add(weight));
+	}
+
 	/// Update the next weight multiplier.
 	///
 	/// This should be called at then end of each block, before `all_extrinsics_weight` is cleared.
@@ -759,6 +829,27 @@ impl<T: Trait> Module<T> {
 		ExtrinsicData::insert(Self::extrinsic_index().unwrap_or_default(), encoded_xt);
 	}
 
+	/// Checks that `code` can replace the current runtime code.
+	///
+	/// Both versions must be readable, the spec names must match and the spec version must
+	/// increase.
+	fn can_set_code(code: &[u8]) -> Result<(), &'static str> {
+		let current_code = storage::unhashed::get_raw(well_known_keys::CODE)
+			.ok_or("Current runtime code is missing")?;
+		let current = SpecVersion::of_code(&current_code)
+			.ok_or("Failed to read the version of the current runtime")?;
+		let new = SpecVersion::of_code(code)
+			.ok_or("Failed to read the version of the new runtime")?;
+
+		if new.spec_name != current.spec_name {
+			return Err("Spec name of the new runtime does not match");
+		}
+		if new.spec_version <= current.spec_version {
+			return Err("Spec version of the new runtime must be higher");
+		}
+		Ok(())
+	}
+
 	/// To be called immediately after an extrinsic has been applied.
 	pub fn note_applied_extrinsic(r: &Result<(), &'static str>, _encoded_len: u32) {
 		Self::deposit_event(match r {
@@ -811,7 +902,7 @@ impl<T: Trait + Send + Sync> CheckWeight<T> {
 		let added_weight = info.weight.min(limit);
 		let next_weight = current_weight.saturating_add(added_weight);
 		if next_weight > limit {
-			return Err(DispatchError::BadState)
+			return Err(DispatchError::ExhaustsResources)
 		}
 		Ok(next_weight)
 	}
@@ -826,7 +917,7 @@ impl<T: Trait + Send + Sync> CheckWeight<T> {
 		let added_len = len as u32;
 		let next_len = current_len.saturating_add(added_len);
 		if next_len > limit {
-			return Err(DispatchError::BadState)
+			return Err(DispatchError::ExhaustsResources)
 		}
 		Ok(next_len)
 	}
@@ -1040,6 +1131,8 @@ mod tests {
 
 	impl Trait for Test {
 		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -1059,6 +1152,7 @@ mod tests {
 			match e {
 				Event::ExtrinsicSuccess => 100,
 				Event::ExtrinsicFailed => 101,
+				Event::CodeUpdated => 102,
 			}
 		}
 	}
@@ -1345,4 +1439,40 @@ mod tests {
 			assert!(CheckEra::<Test>::from(Era::mortal(4, 12)).additional_signed().is_ok());
 		})
 	}
+
+	#[test]
+	fn set_code_checks_the_new_runtime_version() {
+		// The "code" of these tests is the encoded version it reports.
+		let code = |spec_name: &[u8], spec_version: u32| (spec_name.to_vec(), b"impl".to_vec(), 1u32, spec_version).encode();
+
+		let mut ext = new_test_ext();
+		ext.insert(well_known_keys::CODE.to_vec(), code(b"test", 1));
+		with_externalities(&mut ext, || runtime_io::with_runtime_version_provider(|code| Some(code.to_vec()), || {
+			System::initialize(&1, &[0u8; 32].into(), &[0u8; 32].into(), &Default::default());
+
+			assert_eq!(
+				System::set_code(RawOrigin::Root.into(), code(b"other", 2)),
+				Err("Spec name of the new runtime does not match"),
+			);
+			assert_eq!(
+				System::set_code(RawOrigin::Root.into(), code(b"test", 1)),
+				Err("Spec version of the new runtime must be higher"),
+			);
+			assert_eq!(
+				System::set_code(RawOrigin::Root.into(), vec![]),
+				Err("Failed to read the version of the new runtime"),
+			);
+			assert!(System::set_code(RawOrigin::Signed(1).into(), code(b"test", 2)).is_err());
+
+			assert!(!System::runtime_upgraded());
+			assert_eq!(System::set_code(RawOrigin::Root.into(), code(b"test", 2)), Ok(()));
+			assert_eq!(storage::unhashed::get_raw(well_known_keys::CODE), Some(code(b"test", 2)));
+			assert_eq!(System::events().last().map(|e| e.event), Some(102));
+			assert!(System::take_runtime_upgraded());
+			assert!(!System::runtime_upgraded());
+
+			assert_eq!(System::set_code_without_checks(RawOrigin::Root.into(), vec![1, 2, 3]), Ok(()));
+			assert_eq!(storage::unhashed::get_raw(well_known_keys::CODE), Some(vec![1, 2, 3]));
+		}));
+	}
 }
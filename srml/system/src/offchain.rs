@@ -0,0 +1,91 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers for submitting transactions from offchain workers.
+//!
+//! Modules are generic over the runtime's extrinsic format, so they can't build signed
+//! transactions by themselves. Instead, the runtime implements [`CreateTransaction`], and modules
+//! take a [`SubmitSignedTransaction`] and/or [`SubmitUnsignedTransaction`] in their `Trait`,
+//! which the runtime usually sets to [`TransactionSubmitter`].
+
+use rstd::prelude::*;
+use rstd::marker::PhantomData;
+use parity_codec::Encode;
+use primitives::traits::Extrinsic as ExtrinsicT;
+use substrate_primitives::offchain::CryptoKey;
+
+/// Creates a runtime specific signed transaction.
+pub trait CreateTransaction<T: crate::Trait, Extrinsic: ExtrinsicT> {
+	/// Create a transaction of `call`, sent by `account` with the given `nonce`.
+	///
+	/// `sign` signs the given payload with the key of `account`, or returns `None` if it can't.
+	/// Returns `None` if the transaction can't be created.
+	fn create_transaction<F: FnOnce(&[u8]) -> Option<Vec<u8>>>(
+		call: Extrinsic::Call,
+		account: T::AccountId,
+		nonce: T::Index,
+		sign: F,
+	) -> Option<Extrinsic>;
+}
+
+/// Signs and submits transactions to the pool.
+pub trait SubmitSignedTransaction<T: crate::Trait, Call> {
+	/// Submit `call` as a transaction of `account`, signed with `key` through the offchain API.
+	///
+	/// `key` must be the key of `account`. Returns an error if the transaction can't be created,
+	/// signed or submitted.
+	fn sign_and_submit(call: impl Into<Call>, key: CryptoKey, account: T::AccountId) -> Result<(), ()>;
+}
+
+/// Submits unsigned transactions to the pool.
+pub trait SubmitUnsignedTransaction<T: crate::Trait, Call> {
+	/// Submit `call` as an unsigned transaction.
+	///
+	/// The transaction is only accepted by the pool if the module validates it through
+	/// `ValidateUnsigned`.
+	fn submit_unsigned(call: impl Into<Call>) -> Result<(), ()>;
+}
+
+/// The default way to submit transactions, for a runtime implementing `CreateTransaction` with
+/// `C`, and using `Extrinsic` as its extrinsic format.
+pub struct TransactionSubmitter<C, Extrinsic>(PhantomData<(C, Extrinsic)>);
+
+impl<T, C, E, Call> SubmitSignedTransaction<T, Call> for TransactionSubmitter<C, E> where
+	T: crate::Trait,
+	C: CreateTransaction<T, E>,
+	E: ExtrinsicT<Call=Call> + Encode,
+{
+	fn sign_and_submit(call: impl Into<Call>, key: CryptoKey, account: T::AccountId) -> Result<(), ()> {
+		let nonce = <crate::Module<T>>::account_nonce(&account);
+		let xt = C::create_transaction(
+			call.into(),
+			account,
+			nonce,
+			|payload| runtime_io::sign(key, payload).ok(),
+		).ok_or(())?;
+		runtime_io::submit_transaction(&xt)
+	}
+}
+
+impl<T, C, E, Call> SubmitUnsignedTransaction<T, Call> for TransactionSubmitter<C, E> where
+	T: crate::Trait,
+	E: ExtrinsicT<Call=Call> + Encode,
+{
+	fn submit_unsigned(call: impl Into<Call>) -> Result<(), ()> {
+		let xt = E::new_unsigned(call.into()).ok_or(())?;
+		runtime_io::submit_transaction(&xt)
+	}
+}
@@ -0,0 +1,203 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Helpers for writing SRML module tests.
+//!
+//! Module test suites frequently need to move the chain forward by a number of blocks while
+//! running the `on_initialize`/`on_finalize` hooks of the modules under test. This crate
+//! provides functions that do so the same way the executive does, so that storage, digests
+//! and events behave as they would on a real chain.
+//!
+//! ```ignore
+//! // Run the hooks of `Grandpa` and `Session` up to and including the initialization of
+//! // block 10.
+//! srml_test_helpers::run_to_block::<Test, (Grandpa, Session)>(10);
+//! ```
+
+use primitives::traits::{Header, OnInitialize, OnIdle, OnFinalize, One, Zero};
+use srml_support::traits::Get;
+use system::DigestOf;
+
+type System<T> = system::Module<T>;
+
+/// Initialize block `number` and run the `on_initialize` hooks of `M`.
+pub fn initialize_block<T, M>(number: T::BlockNumber, parent_hash: T::Hash, digest: &DigestOf<T>)
+where
+	T: system::Trait,
+	M: OnInitialize<T::BlockNumber>,
+{
+	<System<T>>::initialize(&number, &parent_hash, &Default::default(), digest);
+	let weight = M::on_initialize(number);
+	<System<T>>::register_extra_weight_unchecked(weight);
+}
+
+/// Run the `on_idle` and `on_finalize` hooks of `M` and finalize the current block.
+///
+/// Returns the header of the finalized block.
+pub fn finalize_block<T, M>() -> T::Header
+where
+	T: system::Trait,
+	M: OnIdle<T::BlockNumber> + OnFinalize<T::BlockNumber>,
+{
+	let number = <System<T>>::block_number();
+	<System<T>>::note_finished_extrinsics();
+
+	let remaining_weight = T::MaximumBlockWeight::get()
+		.saturating_sub(<System<T>>::all_extrinsics_weight());
+	if remaining_weight > 0 {
+		let weight = M::on_idle(number, remaining_weight);
+		<System<T>>::register_extra_weight_unchecked(weight);
+	}
+	M::on_finalize(number);
+
+	<System<T>>::derive_extrinsics();
+	<System<T>>::finalize()
+}
+
+/// Finalize the current block and initialize all blocks up to and including `n`, running the
+/// hooks of `M` for each of them.
+///
+/// If no block has been initialized yet, the chain starts at block `1`. Block `n` is left
+/// initialized, so its state can be inspected and extrinsics can be dispatched in it.
+pub fn run_to_block<T, M>(n: T::BlockNumber)
+where
+	T: system::Trait,
+	M: OnInitialize<T::BlockNumber> + OnIdle<T::BlockNumber> + OnFinalize<T::BlockNumber>,
+{
+	run_to_block_with_digest::<T, M, _>(n, |_| Default::default())
+}
+
+/// Same as [`run_to_block`], but every new block is initialized with the digest returned by
+/// `digest`, e.g. to provide pre-runtime digests of the block author.
+pub fn run_to_block_with_digest<T, M, F>(n: T::BlockNumber, digest: F)
+where
+	T: system::Trait,
+	M: OnInitialize<T::BlockNumber> + OnIdle<T::BlockNumber> + OnFinalize<T::BlockNumber>,
+	F: Fn(T::BlockNumber) -> DigestOf<T>,
+{
+	let mut number = <System<T>>::block_number();
+	assert!(n > number, "can only run to a block after the current one");
+
+	let mut parent_hash = if number.is_zero() {
+		Default::default()
+	} else {
+		finalize_block::<T, M>().hash()
+	};
+
+	loop {
+		number += One::one();
+		initialize_block::<T, M>(number, parent_hash, &digest(number));
+		if number == n {
+			break
+		}
+		parent_hash = finalize_block::<T, M>().hash();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::RefCell;
+	use primitives::{traits::IdentityLookup, testing::Header, weights::Weight};
+	use runtime_io::with_externalities;
+	use srml_support::{impl_outer_origin, parameter_types};
+	use substrate_primitives::{H256, Blake2Hasher};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = ::primitives::traits::BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+	}
+
+	type System = system::Module<Test>;
+
+	thread_local! {
+		static HOOKS: RefCell<Vec<(&'static str, u64)>> = RefCell::new(Vec::new());
+	}
+
+	struct Recorder;
+	impl OnInitialize<u64> for Recorder {
+		fn on_initialize(n: u64) -> Weight {
+			HOOKS.with(|h| h.borrow_mut().push(("initialize", n)));
+			0
+		}
+	}
+	impl OnIdle<u64> for Recorder {}
+	impl OnFinalize<u64> for Recorder {
+		fn on_finalize(n: u64) {
+			HOOKS.with(|h| h.borrow_mut().push(("finalize", n)));
+		}
+	}
+
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		system::GenesisConfig::default().build_storage::<Test>().unwrap().0.into()
+	}
+
+	#[test]
+	fn run_to_block_drives_hooks() {
+		with_externalities(&mut new_test_ext(), || {
+			run_to_block::<Test, Recorder>(2);
+			assert_eq!(System::block_number(), 2);
+
+			run_to_block::<Test, Recorder>(3);
+			assert_eq!(System::block_number(), 3);
+
+			HOOKS.with(|h| assert_eq!(*h.borrow(), vec![
+				("initialize", 1),
+				("finalize", 1),
+				("initialize", 2),
+				("finalize", 2),
+				("initialize", 3),
+			]));
+		});
+	}
+
+	#[test]
+	fn blocks_are_chained() {
+		with_externalities(&mut new_test_ext(), || {
+			run_to_block::<Test, ()>(1);
+			let header = finalize_block::<Test, ()>();
+
+			initialize_block::<Test, ()>(2, header.hash(), &Default::default());
+			assert_eq!(System::parent_hash(), header.hash());
+			assert_eq!(System::block_hash(1), header.hash());
+		});
+	}
+}
@@ -48,6 +48,7 @@
 //! ### Trait Getters
 //!
 //! * `MinimumPeriod` - Gets the minimum (and advised) period between blocks for the chain.
+//! * `MaxDrift` - Gets how far ahead of the local time of a node a block timestamp may be.
 //!
 //! ## Usage
 //!
@@ -95,7 +96,7 @@ use parity_codec::Encode;
 #[cfg(feature = "std")]
 use parity_codec::Decode;
 #[cfg(feature = "std")]
-use inherents::ProvideInherentData;
+use inherents::{ProvideInherentData, RetryHint};
 use srml_support::{StorageValue, Parameter, decl_storage, decl_module, for_each_tuple, traits::Get};
 use runtime_primitives::traits::{SimpleArithmetic, Zero, SaturatedConversion};
 use system::ensure_none;
@@ -176,6 +177,13 @@ impl ProvideInherentData for InherentDataProvider {
 	fn error_to_string(&self, error: &[u8]) -> Option<String> {
 		InherentError::try_from(&INHERENT_IDENTIFIER, error).map(|e| format!("{:?}", e))
 	}
+
+	fn retry_hint(&self, error: &[u8]) -> Option<RetryHint> {
+		match InherentError::try_from(&INHERENT_IDENTIFIER, error)? {
+			InherentError::ValidAtTimestamp(timestamp) => Some(RetryHint::AtTimestamp(timestamp)),
+			InherentError::Other(_) => None,
+		}
+	}
 }
 
 /// A trait which is called when the timestamp is set.
@@ -216,6 +224,11 @@ pub trait Trait: system::Trait {
 	/// work with this to determine a sensible block time. e.g. For Aura, it will be double this
 	/// period on default settings.
 	type MinimumPeriod: Get<Self::Moment>;
+
+	/// The maximum amount by which the timestamp of a block may be ahead of the local time of the
+	/// node checking its inherents. Blocks with a timestamp further in the future are rejected, to
+	/// tolerate small clock differences between the author and the checking node.
+	type MaxDrift: Get<Self::Moment>;
 }
 
 decl_module! {
@@ -226,6 +239,10 @@ decl_module! {
 		/// period on default settings.
 		const MinimumPeriod: T::Moment = T::MinimumPeriod::get();
 
+		/// The maximum amount by which the timestamp of a block may be ahead of the local time of
+		/// the node checking its inherents.
+		const MaxDrift: T::Moment = T::MaxDrift::get();
+
 		/// Set the current time.
 		///
 		/// This call should be invoked exactly once per block. It will panic at the finalization
@@ -301,8 +318,6 @@ impl<T: Trait> ProvideInherent for Module<T> {
 	}
 
 	fn check_inherent(call: &Self::Call, data: &InherentData) -> result::Result<(), Self::Error> {
-		const MAX_TIMESTAMP_DRIFT: u64 = 60;
-
 		let t: u64 = match call {
 			Call::set(ref t) => t.clone().saturated_into::<u64>(),
 			_ => return Ok(()),
@@ -311,7 +326,8 @@ impl<T: Trait> ProvideInherent for Module<T> {
 		let data = extract_inherent_data(data).map_err(|e| InherentError::Other(e))?;
 
 		let minimum = (Self::now() + T::MinimumPeriod::get()).saturated_into::<u64>();
-		if t > data + MAX_TIMESTAMP_DRIFT {
+		let max_drift = T::MaxDrift::get().saturated_into::<u64>();
+		if t > data.saturating_add(max_drift) {
 			Err(InherentError::Other("Timestamp too far in future to accept".into()))
 		} else if t < minimum {
 			Err(InherentError::ValidAtTimestamp(minimum))
@@ -343,6 +359,8 @@ mod tests {
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -358,11 +376,13 @@ mod tests {
 	}
 	parameter_types! {
 		pub const MinimumPeriod: u64 = 5;
+		pub const MaxDrift: u64 = 60;
 	}
 	impl Trait for Test {
 		type Moment = u64;
 		type OnTimestampSet = ();
 		type MinimumPeriod = MinimumPeriod;
+		type MaxDrift = MaxDrift;
 	}
 	type Timestamp = Module<Test>;
 
@@ -387,6 +407,32 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn check_inherent_tolerates_drift() {
+		let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		with_externalities(&mut TestExternalities::new_with_children(t), || {
+			Timestamp::set_timestamp(42);
+
+			let mut data = InherentData::new();
+			data.put_data(INHERENT_IDENTIFIER, &100u64).unwrap();
+
+			// within the drift of the local time.
+			assert!(Timestamp::check_inherent(&Call::set(160), &data).is_ok());
+
+			// too far ahead of the local time.
+			match Timestamp::check_inherent(&Call::set(161), &data) {
+				Err(InherentError::Other(_)) => {},
+				_ => panic!("timestamp beyond the drift should be rejected"),
+			}
+
+			// before the minimum period, but valid later.
+			match Timestamp::check_inherent(&Call::set(46), &data) {
+				Err(InherentError::ValidAtTimestamp(47)) => {},
+				_ => panic!("timestamp before the minimum period should be valid later"),
+			}
+		});
+	}
+
 	#[test]
 	#[should_panic(expected = "Timestamp must increment by at least <MinimumPeriod> between sequential blocks")]
 	fn block_period_minimum_enforced() {
@@ -41,6 +41,14 @@
 //! respectively.
 //! - **Pot:** Unspent funds accumulated by the treasury module.
 //!
+//! ### Spend Periods
+//!
+//! Every `SpendPeriod`, approved proposals are paid out of the pot for as long as it allows, after
+//! which the [`SpendFunds`](./trait.SpendFunds.html) hook lets other modules (e.g. bounties or
+//! tips) spend from what is left. If nothing had to be postponed, a `Burn` portion of the
+//! remainder is then taken out of the pot and handed to `BurnDestination`, which destroys it
+//! unless the runtime routes it elsewhere.
+//!
 //! ### Implementations
 //!
 //! The treasury module provides an implementation for the following trait:
@@ -124,6 +132,41 @@ pub trait Trait: system::Trait {
 
 	/// Percentage of spare funds (if any) that are burnt per spend period.
 	type Burn: Get<Permill>;
+
+	/// Handler for the funds burnt at the end of a spend period.
+	type BurnDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Hook spending funds from the pot after the approved proposals of a spend period.
+	type SpendFunds: SpendFunds<Self>;
+}
+
+/// A hook into the periodic spend of the treasury, for modules which pay out of the pot.
+pub trait SpendFunds<T: Trait> {
+	/// Spend funds out of the pot.
+	///
+	/// Every payout must be deducted from `budget_remaining` and subsumed into `imbalance`, which
+	/// is settled against the pot afterwards. `missed_any` should be set if a payout had to be
+	/// postponed for lack of funds, in which case nothing is burnt this period.
+	fn spend_funds(
+		budget_remaining: &mut BalanceOf<T>,
+		imbalance: &mut PositiveImbalanceOf<T>,
+		missed_any: &mut bool,
+	);
+}
+
+impl<T: Trait> SpendFunds<T> for () {
+	fn spend_funds(_: &mut BalanceOf<T>, _: &mut PositiveImbalanceOf<T>, _: &mut bool) {}
+}
+
+impl<T: Trait, A: SpendFunds<T>, B: SpendFunds<T>> SpendFunds<T> for (A, B) {
+	fn spend_funds(
+		budget_remaining: &mut BalanceOf<T>,
+		imbalance: &mut PositiveImbalanceOf<T>,
+		missed_any: &mut bool,
+	) {
+		A::spend_funds(budget_remaining, imbalance, missed_any);
+		B::spend_funds(budget_remaining, imbalance, missed_any);
+	}
 }
 
 type ProposalIndex = u32;
@@ -304,11 +347,17 @@ impl<T: Trait> Module<T> {
 			});
 		});
 
+		// let other modules spend from what is left.
+		T::SpendFunds::spend_funds(&mut budget_remaining, &mut imbalance, &mut missed_any);
+
 		if !missed_any {
 			// burn some proportion of the remaining budget if we run a surplus.
 			let burn = (T::Burn::get() * budget_remaining).min(budget_remaining);
 			budget_remaining -= burn;
+			// take the burn out of the pot along with the spends, and let the runtime decide
+			// what becomes of it.
 			imbalance.subsume(T::Currency::burn(burn));
+			T::BurnDestination::on_unbalanced(T::Currency::issue(burn));
 			Self::deposit_event(RawEvent::Burnt(burn))
 		}
 
@@ -361,6 +410,7 @@ mod tests {
 	use srml_support::{assert_noop, assert_ok, impl_outer_origin, parameter_types};
 	use substrate_primitives::{H256, Blake2Hasher};
 	use runtime_primitives::{traits::{BlakeTwo256, OnFinalize, IdentityLookup}, testing::Header};
+	use std::cell::RefCell;
 
 	impl_outer_origin! {
 		pub enum Origin for Test {}
@@ -375,6 +425,8 @@ mod tests {
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -401,6 +453,7 @@ mod tests {
 		type OnFreeBalanceZero = ();
 		type Event = ();
 		type TransactionPayment = ();
+		type TipPayment = ();
 		type TransferPayment = ();
 		type DustRemoval = ();
 		type ExistentialDeposit = ExistentialDeposit;
@@ -409,6 +462,34 @@ mod tests {
 		type TransactionBaseFee = TransactionBaseFee;
 		type TransactionByteFee = TransactionByteFee;
 	}
+	thread_local! {
+		static HOOK_SPEND: RefCell<u64> = RefCell::new(0);
+	}
+
+	/// The account paid by `PayHookBeneficiary`.
+	const HOOK_BENEFICIARY: u64 = 6;
+
+	/// Pays `HOOK_SPEND` to `HOOK_BENEFICIARY` every spend period, if the budget allows.
+	pub struct PayHookBeneficiary;
+	impl SpendFunds<Test> for PayHookBeneficiary {
+		fn spend_funds(
+			budget_remaining: &mut u64,
+			imbalance: &mut PositiveImbalanceOf<Test>,
+			missed_any: &mut bool,
+		) {
+			let value = HOOK_SPEND.with(|v| *v.borrow());
+			if value.is_zero() {
+				return;
+			}
+			if value <= *budget_remaining {
+				*budget_remaining -= value;
+				imbalance.subsume(Balances::deposit_creating(&HOOK_BENEFICIARY, value));
+			} else {
+				*missed_any = true;
+			}
+		}
+	}
+
 	parameter_types! {
 		pub const ProposalBond: Permill = Permill::from_percent(5);
 		pub const ProposalBondMinimum: u64 = 1;
@@ -426,6 +507,8 @@ mod tests {
 		type ProposalBondMinimum = ProposalBondMinimum;
 		type SpendPeriod = SpendPeriod;
 		type Burn = Burn;
+		type BurnDestination = ();
+		type SpendFunds = PayHookBeneficiary;
 	}
 	type Balances = balances::Module<Test>;
 	type Treasury = Module<Test>;
@@ -618,4 +701,45 @@ mod tests {
 			assert_eq!(Treasury::pot(), 75);
 		});
 	}
+
+	#[test]
+	fn unused_pot_burn_reduces_total_issuance() {
+		with_externalities(&mut new_test_ext(), || {
+			Treasury::on_dilution(100, 100);
+			assert_eq!(Balances::total_issuance(), 300);
+
+			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_eq!(Treasury::pot(), 50);
+			assert_eq!(Balances::total_issuance(), 250);
+		});
+	}
+
+	#[test]
+	fn spend_funds_hook_spends_after_proposals() {
+		with_externalities(&mut new_test_ext(), || {
+			HOOK_SPEND.with(|v| *v.borrow_mut() = 20);
+			Treasury::on_dilution(100, 100);
+
+			assert_ok!(Treasury::propose_spend(Origin::signed(0), 60, 3));
+			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 0));
+
+			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_eq!(Balances::free_balance(&3), 60);
+			assert_eq!(Balances::free_balance(&HOOK_BENEFICIARY), 20);
+			// half of the 20 left is burnt.
+			assert_eq!(Treasury::pot(), 10);
+		});
+	}
+
+	#[test]
+	fn spend_funds_hook_missing_funds_prevents_burn() {
+		with_externalities(&mut new_test_ext(), || {
+			HOOK_SPEND.with(|v| *v.borrow_mut() = 150);
+			Treasury::on_dilution(100, 100);
+
+			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_eq!(Balances::free_balance(&HOOK_BENEFICIARY), 0);
+			assert_eq!(Treasury::pot(), 100);
+		});
+	}
 }
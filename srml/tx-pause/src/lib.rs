@@ -0,0 +1,256 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! # Transaction Pause Module
+//!
+//! The Transaction Pause module allows a configured origin to pause and unpause individual
+//! dispatchable functions of the runtime, without the need for a runtime upgrade.
+//!
+//! - [`tx_pause::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! A call is identified by the name of its module, as declared in `construct_runtime!`, and the
+//! name of its function, e.g. `(b"Balances", b"transfer")`. The set of paused calls is kept in
+//! storage and can be populated at genesis.
+//!
+//! The module enforces nothing by itself: it implements `Filter` for the runtime's `Call`, so it
+//! must be used as (part of) the `BaseCallFilter` of the system module. The calls of this module
+//! are never paused, so that a pause can always be lifted.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `pause_call` - Pause a call. Callable by `PauseOrigin`.
+//! - `unpause_call` - Unpause a call. Callable by `UnpauseOrigin`.
+//!
+//! ## Usage
+//!
+//! ```nocompile
+//! impl system::Trait for Runtime {
+//!     type Call = Call;
+//!     type BaseCallFilter = TxPause;
+//!     // ...
+//! }
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use primitives::traits::EnsureOrigin;
+use srml_support::{StorageMap, decl_module, decl_storage, decl_event, ensure};
+use srml_support::dispatch::{CallMetadata, GetCallMetadata, IsSubType};
+use srml_support::traits::Filter;
+
+/// The name of a module and of one of its functions.
+pub type CallName = (Vec<u8>, Vec<u8>);
+
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+
+	/// The origin which may pause calls.
+	type PauseOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The origin which may unpause calls.
+	type UnpauseOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as TxPause {
+		/// The calls that are currently paused.
+		PausedCalls get(is_paused) build(|config: &GenesisConfig| {
+			config.paused.iter().cloned().map(|call| (call, true)).collect::<Vec<_>>()
+		}): map CallName => bool;
+	}
+	add_extra_genesis {
+		config(paused): Vec<CallName>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Pause the function `function_name` of the module `module_name`.
+		///
+		/// The origin must be `PauseOrigin`.
+		fn pause_call(origin, module_name: Vec<u8>, function_name: Vec<u8>) {
+			T::PauseOrigin::ensure_origin(origin)?;
+
+			let call = (module_name, function_name);
+			ensure!(!Self::is_paused(&call), "call is already paused");
+			PausedCalls::insert(&call, true);
+			Self::deposit_event(Event::CallPaused(call.0, call.1));
+		}
+
+		/// Unpause the function `function_name` of the module `module_name`.
+		///
+		/// The origin must be `UnpauseOrigin`.
+		fn unpause_call(origin, module_name: Vec<u8>, function_name: Vec<u8>) {
+			T::UnpauseOrigin::ensure_origin(origin)?;
+
+			let call = (module_name, function_name);
+			ensure!(Self::is_paused(&call), "call is not paused");
+			PausedCalls::remove(&call);
+			Self::deposit_event(Event::CallUnpaused(call.0, call.1));
+		}
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// A call has been paused: (module name, function name).
+		CallPaused(Vec<u8>, Vec<u8>),
+		/// A call has been unpaused: (module name, function name).
+		CallUnpaused(Vec<u8>, Vec<u8>),
+	}
+);
+
+impl<T: Trait> Filter<<T as system::Trait>::Call> for Module<T> where
+	<T as system::Trait>::Call: GetCallMetadata + IsSubType<Module<T>, T>,
+{
+	fn filter(call: &<T as system::Trait>::Call) -> bool {
+		// Never pause our own calls, otherwise a pause could not be lifted anymore.
+		if call.is_aux_sub_type().is_some() {
+			return true;
+		}
+
+		let CallMetadata { module_name, function_name } = call.get_call_metadata();
+		!Self::is_paused(&(module_name.as_bytes().to_vec(), function_name.as_bytes().to_vec()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use srml_support::{assert_noop, assert_ok, impl_outer_origin, impl_outer_dispatch, parameter_types};
+	use substrate_primitives::{H256, Blake2Hasher};
+	use primitives::{traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum OuterCall for Test where origin: Origin {
+			system::System,
+			self::TxPause,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = OuterCall;
+		type BaseCallFilter = TxPause;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type PauseOrigin = system::EnsureRoot<u64>;
+		type UnpauseOrigin = system::EnsureRoot<u64>;
+	}
+	type System = system::Module<Test>;
+	type TxPause = Module<Test>;
+
+	fn new_test_ext(paused: Vec<CallName>) -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+		t.extend(GenesisConfig { paused }.build_storage::<Test>().unwrap().0);
+		t.into()
+	}
+
+	fn remark() -> OuterCall {
+		OuterCall::System(system::Call::remark(vec![]))
+	}
+
+	fn is_allowed(call: &OuterCall) -> bool {
+		<Test as system::Trait>::BaseCallFilter::filter(call)
+	}
+
+	#[test]
+	fn pause_and_unpause_work() {
+		with_externalities(&mut new_test_ext(vec![]), || {
+			assert!(is_allowed(&remark()));
+
+			assert_ok!(TxPause::pause_call(Origin::ROOT, b"System".to_vec(), b"remark".to_vec()));
+			assert!(TxPause::is_paused(&(b"System".to_vec(), b"remark".to_vec())));
+			assert!(!is_allowed(&remark()));
+
+			assert_ok!(TxPause::unpause_call(Origin::ROOT, b"System".to_vec(), b"remark".to_vec()));
+			assert!(is_allowed(&remark()));
+		});
+	}
+
+	#[test]
+	fn only_configured_origins_can_pause_and_unpause() {
+		with_externalities(&mut new_test_ext(vec![(b"System".to_vec(), b"remark".to_vec())]), || {
+			assert_noop!(
+				TxPause::pause_call(Origin::signed(1), b"System".to_vec(), b"set_code".to_vec()),
+				"Invalid origin"
+			);
+			assert_noop!(
+				TxPause::unpause_call(Origin::signed(1), b"System".to_vec(), b"remark".to_vec()),
+				"Invalid origin"
+			);
+		});
+	}
+
+	#[test]
+	fn cannot_pause_twice_or_unpause_unpaused_call() {
+		with_externalities(&mut new_test_ext(vec![(b"System".to_vec(), b"remark".to_vec())]), || {
+			assert!(!is_allowed(&remark()));
+			assert_noop!(
+				TxPause::pause_call(Origin::ROOT, b"System".to_vec(), b"remark".to_vec()),
+				"call is already paused"
+			);
+			assert_noop!(
+				TxPause::unpause_call(Origin::ROOT, b"System".to_vec(), b"set_code".to_vec()),
+				"call is not paused"
+			);
+		});
+	}
+
+	#[test]
+	fn own_calls_are_never_paused() {
+		with_externalities(&mut new_test_ext(vec![(b"TxPause".to_vec(), b"unpause_call".to_vec())]), || {
+			let unpause = OuterCall::TxPause(Call::unpause_call(b"System".to_vec(), b"remark".to_vec()));
+			assert!(is_allowed(&unpause));
+		});
+	}
+}
@@ -0,0 +1,286 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Validator Set Module
+//!
+//! A module maintaining the validator set of a proof-of-authority chain.
+//!
+//! - [`validator_set::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//! - [`Module`](./struct.Module.html)
+//!
+//! ## Overview
+//!
+//! Validators are added and removed by configurable origins, e.g. root or a collective. The
+//! module is the [`SessionManager`](../srml_session/trait.SessionManager.html) of the session
+//! module: changes to the set take effect at the next session rotation, when the session module
+//! hands the new validators and their session keys to the consensus modules (Aura, BABE,
+//! GRANDPA) through its session handler.
+//!
+//! To plug it into the session module, use `ManagedBy<ValidatorSet>` as both its
+//! `OnSessionEnding` and its `SelectInitialValidators`, with the validator ID being the account
+//! ID.
+//!
+//! The set can also be driven by another module, such as elections, through its
+//! `ChangeMembers` and `InitializeMembers` implementations.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `add_validator` - Add a validator to the set.
+//! - `remove_validator` - Remove a validator from the set.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use primitives::traits::EnsureOrigin;
+use srml_support::{
+	StorageValue, decl_module, decl_storage, decl_event, ensure,
+	traits::{ChangeMembers, InitializeMembers},
+};
+use session::{SessionIndex, SessionManager};
+
+/// The module's configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The origin which may add validators.
+	type AddOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The origin which may remove validators.
+	type RemoveOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_event!(
+	pub enum Event<T> where <T as system::Trait>::AccountId {
+		/// A validator was added; it takes effect at the next session.
+		ValidatorAdded(AccountId),
+		/// A validator was removed; it takes effect at the next session.
+		ValidatorRemoved(AccountId),
+	}
+);
+
+decl_storage! {
+	trait Store for Module<T: Trait> as ValidatorSet {
+		/// The current set of validators, sorted.
+		Validators get(validators) build(|config: &GenesisConfig<T>| {
+			let mut validators = config.validators.clone();
+			validators.sort();
+			validators.dedup();
+			validators
+		}): Vec<T::AccountId>;
+
+		/// True if the set has changed since it was last handed to the session module.
+		Changed get(changed): bool;
+	}
+	add_extra_genesis {
+		config(validators): Vec<T::AccountId>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event<T>() = default;
+
+		/// Add `who` to the validator set, from the next session on.
+		///
+		/// May only be called from `T::AddOrigin`.
+		fn add_validator(origin, who: T::AccountId) {
+			T::AddOrigin::ensure_origin(origin)?;
+
+			let mut validators = Self::validators();
+			let index = validators.binary_search(&who).err().ok_or("Already a validator")?;
+			validators.insert(index, who.clone());
+			Self::set_validators(&validators);
+
+			Self::deposit_event(RawEvent::ValidatorAdded(who));
+		}
+
+		/// Remove `who` from the validator set, from the next session on.
+		///
+		/// May only be called from `T::RemoveOrigin`.
+		fn remove_validator(origin, who: T::AccountId) {
+			T::RemoveOrigin::ensure_origin(origin)?;
+
+			let mut validators = Self::validators();
+			let index = validators.binary_search(&who).ok().ok_or("Not a validator")?;
+			ensure!(validators.len() > 1, "Cannot remove the last validator");
+			validators.remove(index);
+			Self::set_validators(&validators);
+
+			Self::deposit_event(RawEvent::ValidatorRemoved(who));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Replace the validator set with `validators`, which must be sorted.
+	fn set_validators(validators: &[T::AccountId]) {
+		<Validators<T>>::put_ref(validators);
+		<Changed<T>>::put(true);
+	}
+}
+
+impl<T: Trait> SessionManager<T::AccountId> for Module<T> {
+	fn new_session(_new_index: SessionIndex) -> Option<Vec<T::AccountId>> {
+		if <Changed<T>>::take() {
+			Some(Self::validators())
+		} else {
+			None
+		}
+	}
+
+	fn initial_validators() -> Option<Vec<T::AccountId>> {
+		let validators = Self::validators();
+		if validators.is_empty() {
+			None
+		} else {
+			Some(validators)
+		}
+	}
+}
+
+impl<T: Trait> ChangeMembers<T::AccountId> for Module<T> {
+	fn change_members(_incoming: &[T::AccountId], _outgoing: &[T::AccountId], new: &[T::AccountId]) {
+		let mut validators = new.to_vec();
+		validators.sort();
+		Self::set_validators(&validators);
+	}
+}
+
+impl<T: Trait> InitializeMembers<T::AccountId> for Module<T> {
+	fn initialize_members(members: &[T::AccountId]) {
+		if !members.is_empty() {
+			assert!(Self::validators().is_empty(), "Validators are already initialized!");
+			let mut validators = members.to_vec();
+			validators.sort();
+			<Validators<T>>::put(validators);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use srml_support::{assert_noop, assert_ok, impl_outer_origin, parameter_types, traits::Get};
+	use substrate_primitives::{H256, Blake2Hasher};
+	use primitives::{traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+	use system::{EnsureRoot, EnsureSignedBy};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type BaseCallFilter = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+	}
+	parameter_types! {
+		pub const Admin: u64 = 10;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type AddOrigin = EnsureSignedBy<Admin, u64>;
+		type RemoveOrigin = EnsureRoot<u64>;
+	}
+	type ValidatorSet = Module<Test>;
+
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+		t.extend(GenesisConfig::<Test> {
+			validators: vec![3, 1, 2],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	#[test]
+	fn genesis_validators_are_initial_validators() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(ValidatorSet::validators(), vec![1, 2, 3]);
+			assert_eq!(ValidatorSet::initial_validators(), Some(vec![1, 2, 3]));
+			assert_eq!(ValidatorSet::new_session(1), None);
+		});
+	}
+
+	#[test]
+	fn add_validator_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(ValidatorSet::add_validator(Origin::signed(1), 4), "Invalid origin");
+			assert_noop!(ValidatorSet::add_validator(Origin::signed(Admin::get()), 2), "Already a validator");
+
+			assert_ok!(ValidatorSet::add_validator(Origin::signed(Admin::get()), 0));
+			assert_eq!(ValidatorSet::validators(), vec![0, 1, 2, 3]);
+			assert_eq!(ValidatorSet::new_session(1), Some(vec![0, 1, 2, 3]));
+			// the change is only handed over once.
+			assert_eq!(ValidatorSet::new_session(2), None);
+		});
+	}
+
+	#[test]
+	fn remove_validator_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(ValidatorSet::remove_validator(Origin::signed(Admin::get()), 1), "Invalid origin");
+			assert_noop!(ValidatorSet::remove_validator(Origin::ROOT, 4), "Not a validator");
+
+			assert_ok!(ValidatorSet::remove_validator(Origin::ROOT, 2));
+			assert_ok!(ValidatorSet::remove_validator(Origin::ROOT, 1));
+			assert_noop!(ValidatorSet::remove_validator(Origin::ROOT, 3), "Cannot remove the last validator");
+			assert_eq!(ValidatorSet::new_session(1), Some(vec![3]));
+		});
+	}
+
+	#[test]
+	fn change_members_updates_validators() {
+		with_externalities(&mut new_test_ext(), || {
+			ValidatorSet::set_members_sorted(&[2, 5], &ValidatorSet::validators());
+			assert_eq!(ValidatorSet::validators(), vec![2, 5]);
+			assert_eq!(ValidatorSet::new_session(1), Some(vec![2, 5]));
+		});
+	}
+
+	#[test]
+	fn initialize_members_works() {
+		with_externalities(&mut runtime_io::TestExternalities::<Blake2Hasher>::default(), || {
+			assert_eq!(ValidatorSet::initial_validators(), None);
+			ValidatorSet::initialize_members(&[7, 4]);
+			assert_eq!(ValidatorSet::initial_validators(), Some(vec![4, 7]));
+		});
+	}
+}
@@ -23,13 +23,13 @@ use hex_literal::hex;
 use clap::load_yaml;
 use bip39::{Mnemonic, Language, MnemonicType};
 use substrate_primitives::{
-	ed25519, sr25519, hexdisplay::HexDisplay, Pair, Public, blake2_256,
+	ed25519, sr25519, ecdsa, hexdisplay::HexDisplay, Pair, Public, blake2_256,
 	crypto::{Ss58Codec, set_default_ss58_version, Ss58AddressFormat}
 };
-use parity_codec::{Encode, Decode, Compact};
+use parity_codec::{Encode, Decode};
 use sr_primitives::generic::Era;
 use node_primitives::{Balance, Index, Hash};
-use node_runtime::{Call, UncheckedExtrinsic, /*CheckNonce, TakeFees, */BalancesCall};
+use node_runtime::{Call, Runtime, SignedExtra, UncheckedExtrinsic, BalancesCall};
 
 mod vanity;
 
@@ -86,23 +86,68 @@ impl Crypto for Sr25519 {
 	type Public = sr25519::Public;
 }
 
+struct Ecdsa;
+
+impl Crypto for Ecdsa {
+	type Pair = ecdsa::Pair;
+	type Public = ecdsa::Public;
+}
+
+/// The signed extensions of an immortal transaction with the given index and tip.
+fn extra(index: Index, tip: Balance) -> SignedExtra {
+	(
+		system::CheckEra::<Runtime>::from(Era::Immortal),
+		system::CheckNonce::<Runtime>::from(index),
+		system::CheckWeight::<Runtime>::from(),
+		balances::TakeFees::<Runtime>::from(tip),
+	)
+}
+
+/// Signs `function` as an immortal transaction of `signer` on the chain with the given genesis
+/// hash.
+fn sign_extrinsic(
+	function: Call,
+	signer: &sr25519::Pair,
+	index: Index,
+	genesis_hash: Hash,
+) -> UncheckedExtrinsic {
+	// Immortal transactions are checked against the hash of the genesis block.
+	let raw_payload = (function, extra(index, 0), (genesis_hash, (), (), ()));
+	let signature = raw_payload.using_encoded(|payload| if payload.len() > 256 {
+		signer.sign(&blake2_256(payload)[..])
+	} else {
+		signer.sign(payload)
+	});
+
+	UncheckedExtrinsic::new_signed(
+		raw_payload.0,
+		signer.public().into(),
+		signature.into(),
+		raw_payload.1,
+	)
+}
+
+/// Parses a hex-encoded genesis hash, or the identifier of a known chain.
+fn genesis_hash(value: &str) -> Hash {
+	match value {
+		"elm" => hex!["10c08714a10c7da78f40a60f6f732cf0dba97acfb5e2035445b032386157d5c3"].into(),
+		"alex" => hex!["dcd1346701ca8396496e52aa2785b1748deb6db09551b72159dcb3e08991025b"].into(),
+		h => hex::decode(h.trim_start_matches("0x")).ok().and_then(|x| Decode::decode(&mut &x[..]))
+			.expect("Invalid genesis hash or unrecognised chain identifier"),
+	}
+}
+
 fn execute<C: Crypto>(matches: clap::ArgMatches) where
 	<<C as Crypto>::Pair as Pair>::Signature: AsRef<[u8]> + AsMut<[u8]> + Default,
 	<<C as Crypto>::Pair as Pair>::Public: Sized + AsRef<[u8]> + Ss58Codec + AsRef<<<C as Crypto>::Pair as Pair>::Public>,
 {
-	// let extra = |i: Index, f: Balance| {
-	// 	(
-	// 		system::CheckEra::<Runtime>::from(Era::Immortal),
-	// 		system::CheckNonce::<Runtime>::from(i),
-	// 		system::CheckWeight::<Runtime>::from(),
-	// 		balances::TakeFees::<Runtime>::from(f),
-	// 	)
-	// };
 	let password = matches.value_of("password");
 	let maybe_network = matches.value_of("network");
 	if let Some(network) = maybe_network {
-		let v = network.try_into()
-			.expect("Invalid network name: must be polkadot/substrate/kusama");
+		let v = network.try_into().unwrap_or_else(|_| panic!(
+			"Invalid network name: must be one of {} or a prefix below 64",
+			Ss58AddressFormat::all_names().join("/"),
+		));
 		set_default_ss58_version(v);
 	}
 	match matches.subcommand() {
@@ -138,7 +183,7 @@ fn execute<C: Crypto>(matches: clap::ArgMatches) where
 			let sig = pair.sign(&message);
 			println!("{}", hex::encode(&sig));
 		}
-		/*("transfer", Some(matches)) => {
+		("transfer", Some(matches)) => {
 			let signer = matches.value_of("from")
 				.expect("parameter is required; thus it can't be None; qed");
 			let signer = Sr25519::pair_from_suri(signer, password);
@@ -157,32 +202,14 @@ fn execute<C: Crypto>(matches: clap::ArgMatches) where
 			let index = matches.value_of("index")
 				.expect("parameter is required; thus it can't be None; qed");
 			let index = str::parse::<Index>(index)
-				.expect("Invalid 'amount' parameter; expecting an integer.");
+				.expect("Invalid 'index' parameter; expecting an integer.");
 
 			let function = Call::Balances(BalancesCall::transfer(to.into(), amount));
 
-			let genesis_hash: Hash = match matches.value_of("genesis").unwrap_or("alex") {
-				"elm" => hex!["10c08714a10c7da78f40a60f6f732cf0dba97acfb5e2035445b032386157d5c3"].into(),
-				"alex" => hex!["dcd1346701ca8396496e52aa2785b1748deb6db09551b72159dcb3e08991025b"].into(),
-				h => hex::decode(h).ok().and_then(|x| Decode::decode(&mut &x[..]))
-					.expect("Invalid genesis hash or unrecognised chain identifier"),
-			};
-
+			let genesis_hash = genesis_hash(matches.value_of("genesis").unwrap_or("alex"));
 			println!("Using a genesis hash of {}", HexDisplay::from(&genesis_hash.as_ref()));
 
-			let raw_payload = (function, extra(index, 0), genesis_hash);
-			let signature = raw_payload.using_encoded(|payload| if payload.len() > 256 {
-				signer.sign(&blake2_256(payload)[..])
-			} else {
-				println!("Signing {}", HexDisplay::from(&payload));
-				signer.sign(payload)
-			});
-			let extrinsic = UncheckedExtrinsic::new_signed(
-				raw_payload.0,
-				signer.public().into(),
-				signature.into(),
-				extra(index, 0),
-			);
+			let extrinsic = sign_extrinsic(function, &signer, index, genesis_hash);
 			println!("0x{}", hex::encode(&extrinsic.encode()));
 		}
 		("sign-transaction", Some(matches)) => {
@@ -193,39 +220,20 @@ fn execute<C: Crypto>(matches: clap::ArgMatches) where
 			let index = matches.value_of("nonce")
 				.expect("nonce is required; thus it can't be None; qed");
 			let index = str::parse::<Index>(index)
-				.expect("Invalid 'index' parameter; expecting an integer.");
+				.expect("Invalid 'nonce' parameter; expecting an integer.");
 
 			let call = matches.value_of("call")
 				.expect("call is required; thus it can't be None; qed");
-			let function: Call = hex::decode(&call).ok()
-				.and_then(|x| Decode::decode(&mut &x[..])).unwrap();
-
-			let h = matches.value_of("prior-block-hash")
-				.expect("prior-block-hash is required; thus it can't be None; qed");
-			let prior_block_hash: Hash = hex::decode(h).ok()
+			let function: Call = hex::decode(call.trim_start_matches("0x")).ok()
 				.and_then(|x| Decode::decode(&mut &x[..]))
-				.expect("Invalid prior block hash");
+				.expect("Invalid call; expecting a hex-encoded call of the node runtime.");
 
-			let era = Era::immortal();
-
-			let raw_payload = (function, era, prior_block_hash, extra(index, 0));
-			let signature = raw_payload.using_encoded(|payload|
-				if payload.len() > 256 {
-					signer.sign(&blake2_256(payload)[..])
-				} else {
-					signer.sign(payload)
-				}
-			);
-
-			let extrinsic = UncheckedExtrinsic::new_signed(
-				raw_payload.0,
-				signer.public().into(),
-				signature.into(),
-				extra(index, 0),
-			);
+			let genesis_hash = genesis_hash(matches.value_of("genesis")
+				.expect("genesis is required; thus it can't be None; qed"));
 
+			let extrinsic = sign_extrinsic(function, &signer, index, genesis_hash);
 			println!("0x{}", hex::encode(&extrinsic.encode()));
-		}*/
+		}
 		("verify", Some(matches)) => {
 			let sig_data = matches.value_of("sig")
 				.expect("signature parameter is required; thus it can't be None; qed");
@@ -263,6 +271,8 @@ fn main() {
 
 	if matches.is_present("ed25519") {
 		execute::<Ed25519>(matches)
+	} else if matches.is_present("ecdsa") {
+		execute::<Ecdsa>(matches)
 	} else {
 		execute::<Sr25519>(matches)
 	}
@@ -274,7 +284,23 @@ fn print_usage(matches: &clap::ArgMatches) {
 
 #[cfg(test)]
 mod tests {
-	use super::{Hash, Decode};
+	use super::*;
+
+	#[test]
+	fn signed_transaction_carries_a_valid_signature() {
+		let signer = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let genesis_hash = genesis_hash(&format!("0x{}", "12".repeat(32)));
+		let function = Call::Balances(BalancesCall::transfer(signer.public().into(), 42));
+
+		let extrinsic = sign_extrinsic(function.clone(), &signer, 7, genesis_hash);
+		let (address, signature, extra) = extrinsic.signature.expect("extrinsic is signed; qed");
+
+		assert_eq!(address, signer.public().into());
+		assert_eq!(extra.1, system::CheckNonce::<Runtime>::from(7));
+		let payload = (function, extra, (genesis_hash, (), (), ())).encode();
+		assert!(sr_primitives::traits::Verify::verify(&signature, &payload[..], &signer.public()));
+	}
+
 	#[test]
 	fn should_work() {
 		let s = "0123456789012345678901234567890123456789012345678901234567890123";
@@ -175,6 +175,7 @@ fn import_block<F>(
 		justification: None,
 		auxiliary: Vec::new(),
 		fork_choice: ForkChoiceStrategy::LongestChain,
+		storage_changes: None,
 	};
 	(&**client).import_block(import, HashMap::new()).expect("Failed to import block");
 }